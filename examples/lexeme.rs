@@ -1,55 +1,26 @@
-// Lexeme parsing
+// Token printing
+//
+// Usage: cargo run --example lexeme [FILE.rs]
 
-use compiler;
-
-enum RustChunk {
-    SingleLineComment,
-    MultiLineComment,
-    String,
-    Character,
-    RawString(usize),
-}
+use compiler::rust::TokenIterator;
 
 fn main() {
-    let file = std::fs::read_to_string("src/lexeme.rs").unwrap();
+    let path = std::env::args().nth(1);
+    let path = path.as_deref().unwrap_or("src/lexeme.rs");
+    let file = std::fs::read_to_string(path).unwrap();
 
-    fn rs_open(input: &str) -> (Option<RustChunk>, Option<char>) {
-        if input.starts_with("//") {
-            (Some(RustChunk::SingleLineComment), Some('\\'))
-        } else if input.starts_with("/*") {
-            (Some(RustChunk::MultiLineComment), None)
-        } else if input.starts_with("\"") {
-            (Some(RustChunk::String), Some('\\'))
-        } else if input.starts_with("'") {
-            (Some(RustChunk::Character), Some('\\'))
-        } else if input.starts_with("r#") {
-            if let Some(index) = input[2..].find("\"") {
-                (Some(RustChunk::RawString(index)), None)
-            } else {
-                (None, None)
-            }
-        } else {
-            (None, None)
-        }
-    }
+    let mut tokens = TokenIterator::new(&file);
 
-    fn rs_close(input: &str, chunk: &RustChunk) -> (bool, usize) {
-        match chunk {
-            RustChunk::SingleLineComment => (input.starts_with("\n"), 1),
-            RustChunk::MultiLineComment => (input.starts_with("*/"), 2),
-            RustChunk::String => (input.starts_with("\""), 1),
-            RustChunk::Character => (input.starts_with("'"), 1),
-            RustChunk::RawString(extra) => (if input.starts_with("\"#") {
-                Some(*extra) == input[2..].find(|c| c != '#')
-            } else {
-                false
-            }, 2 + extra)
+    while let Some(token) = tokens.next() {
+        match token {
+            Ok(token) => {
+                let (line, col) = tokens.span().line_col(&file);
+                println!("{}:{}: {:?}", line, col, token);
+            }
+            Err(e) => {
+                eprint!("{}", e.render(path, &file));
+                std::process::exit(1);
+            }
         }
     }
-
-    let it = compiler::LexemeIterator::new(&file, rs_open, rs_close);
-
-    for lexeme in it {
-        println!("{}", lexeme);
-    }
 }
@@ -1,3 +1,659 @@
 // Aratar
 //
 //! Aratar Programming Language official compiler.
+//!
+//! # Lexical structure
+//!
+//! Aratar source is ASCII text made of lines.  A line holds a statement, or
+//! several separated by `;`, and lines inside parentheses are joined.
+//! Blocks are marked by indenting with spaces (tabs aren't allowed): a line
+//! indented more than the one before it starts a block, and a line indented
+//! less ends the blocks it is outside of.  As in Python, this gives the
+//! tokens `NEWLINE` at the end of a line, and `INDENT` and `DEDENT` around
+//! a block.  Blank lines and lines with only comments are ignored.
+//!
+//! ```text
+//! token    = keyword | name | input | number | operator | bracket | comment
+//! keyword  = "def" | "let" | "var" | "out"
+//! name     = (letter | "_") (letter | digit | "_")*
+//! input    = "$" name
+//! number   = digit+ ("." digit+)?
+//! operator = "+" | "-" | "*" | "/" | "%" | "." | "," | ":" | ";"
+//! bracket  = "(" | ")"
+//! comment  = "#" (any character except "#" and newline)* "#"?
+//! ```
+//!
+//! A comment ends at the next `#`, so code can follow it on the same line,
+//! or else at the end of the line.  Keywords can't be used as names.  An
+//! input, `$Type`, is a value of type `Type` that the program is given
+//! rather than computes, such as the attributes of a vertex.
+//!
+//! ```text
+//! def main:
+//!     let pos: $Vec4  # where the vertex is #
+//!     let view: $View # the camera
+//!     out position: view * pos
+//! ```
+//!
+//! # Syntax
+//!
+//! A program is a sequence of statements.  `def name:` defines a function
+//! whose body is the indented block after it.  A binding gives a name to a
+//! value: `let` for a variable that can't change, `var` for one that can,
+//! and `out` for an output of the function.  A line starting with one of
+//! these keywords may be followed by an indented block of more bindings
+//! using the keyword of the line's last binding, without repeating it.
+//!
+//! ```text
+//! statement = "def" name ":" NEWLINE INDENT statement+ DEDENT
+//!           | binding (";" binding)* ";"? NEWLINE block?
+//! binding   = ("let" | "var" | "out") item
+//! block     = INDENT (item (";" item)* ";"? NEWLINE)+ DEDENT
+//! item      = name ":" (input | expr)
+//! expr      = term (("+" | "-") term)*
+//! term      = unary (("*" | "/" | "%") unary)*
+//! unary     = "-" unary | apply
+//! apply     = primary primary*
+//! primary   = name | number | "(" expr ")"
+//! ```
+//!
+//! An input can only be the value of a `let` or `var`.  `f x` applies the
+//! operator function `f` to `x`; applying a `View` to a vector transforms
+//! the vector by it.
+
+#[cfg(feature = "shader")]
+pub mod yote;
+
+mod ops;
+mod parser;
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::{Diagnostic, Lexeme, LexemeIterator, Span};
+use parser::Parser;
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+/// An Aratar keyword
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyword {
+    /// Define a function or type.
+    Def,
+    /// Declare an immutable variable.
+    Let,
+    /// Declare a mutable variable.
+    Var,
+    /// Set an output of the function.
+    Out,
+}
+
+const KEYWORDS: &[(&str, Keyword)] = &[
+    ("def", Keyword::Def),
+    ("let", Keyword::Let),
+    ("var", Keyword::Var),
+    ("out", Keyword::Out),
+];
+
+impl Keyword {
+    fn new(word: &str) -> Option<Keyword> {
+        KEYWORDS.iter().find(|(text, _)| *text == word).map(|(_, kw)| *kw)
+    }
+
+    /// Get the source text of the keyword.
+    pub fn as_str(self) -> &'static str {
+        KEYWORDS.iter().find(|(_, kw)| *kw == self).unwrap().0
+    }
+}
+
+/// Aratar punctuation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Dot,
+    Comma,
+    Colon,
+    Semi,
+}
+
+const OPERATORS: &[(&str, Operator)] = &[
+    ("+", Operator::Plus),
+    ("-", Operator::Minus),
+    ("*", Operator::Star),
+    ("/", Operator::Slash),
+    ("%", Operator::Percent),
+    (".", Operator::Dot),
+    (",", Operator::Comma),
+    (":", Operator::Colon),
+    (";", Operator::Semi),
+];
+
+impl Operator {
+    /// Get the source text of the operator.
+    pub fn as_str(self) -> &'static str {
+        OPERATORS.iter().find(|(_, op)| *op == self).unwrap().0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bracket {
+    ParensL,
+    ParensR,
+}
+
+impl Bracket {
+    /// Get the source text of the bracket.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Bracket::ParensL => "(",
+            Bracket::ParensR => ")",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token<'a> {
+    Keyword(Keyword),
+    Name(&'a str),
+    /// `$Type`, with the name of the type
+    Input(&'a str),
+    Int(u64),
+    Float(f64),
+    /// Text between the `#`s
+    Comment(&'a str),
+    Operator(Operator),
+    Bracket(Bracket),
+    /// End of a line
+    Newline,
+    Indent,
+    Dedent,
+}
+
+impl std::fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Keyword(keyword) => write!(f, "{}", keyword.as_str()),
+            Token::Name(name) => write!(f, "{}", name),
+            Token::Input(ty) => write!(f, "${}", ty),
+            Token::Int(value) => write!(f, "{}", value),
+            Token::Float(value) => write!(f, "{:?}", value),
+            Token::Comment(_) => write!(f, "comment"),
+            Token::Operator(op) => write!(f, "{}", op.as_str()),
+            Token::Bracket(bracket) => write!(f, "{}", bracket.as_str()),
+            Token::Newline => write!(f, "newline"),
+            Token::Indent => write!(f, "indent"),
+            Token::Dedent => write!(f, "dedent"),
+        }
+    }
+}
+
+enum AratarChunk {
+    Comment,
+    /// A non-ASCII character, which is reported by the token iterator
+    Unicode,
+}
+
+fn begin_text(input: &str) -> (Option<AratarChunk>, Option<char>) {
+    if input.starts_with('#') {
+        (Some(AratarChunk::Comment), None)
+    } else if input.starts_with(|c: char| !c.is_ascii()) {
+        (Some(AratarChunk::Unicode), None)
+    } else {
+        (None, None)
+    }
+}
+
+fn end_text(input: &str, chunk: &mut AratarChunk) -> (bool, usize) {
+    match chunk {
+        AratarChunk::Comment => {
+            (input.starts_with('#') || input.starts_with('\n'), 1)
+        }
+        AratarChunk::Unicode => (true, 0),
+    }
+}
+
+/// An iterator over Aratar tokens.  `NEWLINE`, `INDENT` and `DEDENT` are
+/// found from the whitespace between tokens.
+pub struct TokenIterator<'a> {
+    lexemes: LexemeIterator<'a, AratarChunk>,
+    span: Span,
+    // Tokens found but not returned yet, such as the `DEDENT`s before a
+    // token.
+    queue: VecDeque<(Result<Token<'a>>, Span)>,
+    // End of the previous token, where the whitespace before the next one
+    // starts.
+    last: usize,
+    // Whether a token of the current line was returned.
+    in_line: bool,
+    // Indentation of the enclosing blocks, in spaces.
+    indents: Vec<usize>,
+    // Open parentheses.
+    brackets: Vec<Span>,
+    done: bool,
+}
+
+impl<'a> TokenIterator<'a> {
+    /// Create a new Aratar token iterator.
+    pub fn new(text: &'a str) -> Self {
+        TokenIterator {
+            lexemes: LexemeIterator::new(text, begin_text, end_text),
+            span: Span::default(),
+            queue: VecDeque::new(),
+            last: 0,
+            in_line: false,
+            indents: vec![0],
+            brackets: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Get the span of the most recently returned token.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Get the source text being tokenized.
+    pub fn text(&self) -> &'a str {
+        self.lexemes.text()
+    }
+
+    fn push(&mut self, token: Token<'a>, span: Span) {
+        self.queue.push_back((Ok(token), span));
+        self.last = span.end;
+    }
+
+    fn error<T: Into<String>>(&mut self, span: Span, message: T) {
+        self.queue.push_back((Err(Diagnostic::new(span, message)), span));
+    }
+
+    // Continue lexing from byte index `index`.
+    fn seek(&mut self, index: usize) {
+        self.last = self.last.max(index);
+        self.lexemes.seek(index);
+    }
+
+    // Find the end of the line and the indentation of the next one in the
+    // whitespace before a token at `start`.
+    fn whitespace(&mut self, start: usize, comment: bool) {
+        if !self.brackets.is_empty() {
+            return;
+        }
+        let text = self.lexemes.text();
+        if let Some(i) = text[self.last..start].find('\n') {
+            if self.in_line {
+                let at = self.last + i;
+                self.queue.push_back((Ok(Token::Newline), Span::new(at, at + 1)));
+                self.in_line = false;
+            }
+        }
+        if self.in_line || comment {
+            return;
+        }
+
+        // Only the spaces before a comment at the start of the line count.
+        let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line = &text[line_start..start];
+        let width = line.find(|c| c != ' ').unwrap_or(line.len());
+        let span = Span::new(line_start, line_start + width);
+        if line[width..].starts_with('\t') {
+            let at = line_start + width;
+            return self.error(Span::new(at, at + 1),
+                "tabs can't be used for indentation");
+        }
+        if width > *self.indents.last().unwrap() {
+            self.indents.push(width);
+            self.queue.push_back((Ok(Token::Indent), span));
+            return;
+        }
+        while width < *self.indents.last().unwrap() {
+            self.indents.pop();
+            let at = Span::new(start, start);
+            self.queue.push_back((Ok(Token::Dedent), at));
+        }
+        if width != *self.indents.last().unwrap() {
+            self.error(span,
+                "unindent does not match any outer indentation level");
+        }
+    }
+
+    // Report what is left open at the end of the input.
+    fn end(&mut self) {
+        self.done = true;
+        let len = self.lexemes.text().len();
+        let eof = Span::new(len, len);
+        if let Some(&span) = self.brackets.first() {
+            self.error(span, "'(' was never closed");
+            return;
+        }
+        if self.in_line {
+            self.queue.push_back((Ok(Token::Newline), eof));
+        }
+        for _ in 1..self.indents.len() {
+            self.queue.push_back((Ok(Token::Dedent), eof));
+        }
+    }
+
+    // Scan a comment starting at byte index `start`, which ends after the
+    // next `#` or before the end of the line.
+    fn comment(&mut self, start: usize) {
+        let text = self.lexemes.text();
+        let len = text[start + 1..]
+            .find(['#', '\n'])
+            .unwrap_or(text.len() - start - 1);
+        let end = start + 1 + len;
+        let comment = text[start + 1..end].trim_end_matches('\r');
+        let end = match text[end..].starts_with('#') {
+            true => end + 1,
+            false => end,
+        };
+        self.seek(end);
+        let span = Span::new(start, end);
+        self.queue.push_back((Ok(Token::Comment(comment)), span));
+    }
+
+    // Scan a name or keyword starting at byte index `start`.
+    fn name(&mut self, start: usize) {
+        let text = self.lexemes.text();
+        let len = text[start..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(text.len() - start);
+        let name = &text[start..start + len];
+        let span = Span::new(start, start + len);
+        self.seek(span.end);
+        let token = match Keyword::new(name) {
+            Some(keyword) => Token::Keyword(keyword),
+            None => Token::Name(name),
+        };
+        self.push(token, span);
+    }
+
+    // Scan an input, `$` followed by the name of a type, starting at byte
+    // index `start`.
+    fn input(&mut self, start: usize) {
+        let text = self.lexemes.text();
+        let name = start + 1;
+        let len = match text[name..].starts_with(|c: char| {
+            c.is_ascii_alphabetic() || c == '_'
+        }) {
+            true => text[name..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(text.len() - name),
+            false => 0,
+        };
+        let span = Span::new(start, name + len);
+        self.seek(span.end);
+        match len {
+            0 => self.error(span, "expected the name of a type after `$`"),
+            _ => self.push(Token::Input(&text[name..span.end]), span),
+        }
+    }
+
+    // Scan a number starting at byte index `start`.
+    fn number(&mut self, start: usize) {
+        let text = self.lexemes.text();
+        let digits = |from: usize| {
+            text[from..]
+                .find(|c: char| !c.is_ascii_digit())
+                .map_or(text.len(), |len| from + len)
+        };
+        let mut end = digits(start);
+        let float = text[end..].starts_with('.')
+            && text[end + 1..].starts_with(|c: char| c.is_ascii_digit());
+        if float {
+            end = digits(end + 1);
+        }
+        let suffix = text[end..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .map_or(text.len(), |len| end + len);
+        let span = Span::new(start, suffix);
+        self.seek(span.end);
+        if suffix != end {
+            return self.error(span, "invalid number literal");
+        }
+        let literal = &text[start..end];
+        match float {
+            true => match literal.parse() {
+                Ok(value) => self.push(Token::Float(value), span),
+                Err(_) => self.error(span, "invalid number literal"),
+            },
+            false => match literal.parse() {
+                Ok(value) => self.push(Token::Int(value), span),
+                Err(_) => self.error(span, "integer literal is too large"),
+            },
+        }
+    }
+
+    // Split a run of punctuation starting at `span` into its first
+    // operator.
+    fn operator(&mut self, text: &str, span: Span) {
+        if text.starts_with('$') {
+            return self.input(span.start);
+        }
+        let ch = text.chars().next().unwrap();
+        let first = Span::new(span.start, span.start + ch.len_utf8());
+        self.seek(first.end);
+        match OPERATORS.iter().find(|(op, _)| text.starts_with(op)) {
+            Some(&(_, op)) => self.push(Token::Operator(op), first),
+            None => self.error(first, format!("unexpected character `{}`", ch)),
+        }
+    }
+
+    fn bracket(&mut self, text: &str, span: Span) {
+        let bracket = match text {
+            "(" => {
+                self.brackets.push(span);
+                Bracket::ParensL
+            }
+            ")" => {
+                if self.brackets.pop().is_none() {
+                    self.error(span, "unmatched ')'");
+                }
+                Bracket::ParensR
+            }
+            _ => {
+                return self.error(span,
+                    format!("unexpected character `{}`", text));
+            }
+        };
+        self.push(Token::Bracket(bracket), span);
+    }
+}
+
+impl<'a> Iterator for TokenIterator<'a> {
+    type Item = Result<Token<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((token, span)) = self.queue.pop_front() {
+                self.span = span;
+                if matches!(token, Ok(ref token) if !matches!(token,
+                    Token::Comment(_) | Token::Newline | Token::Indent
+                    | Token::Dedent))
+                {
+                    self.in_line = true;
+                }
+                return Some(token);
+            }
+            if self.done {
+                return None;
+            }
+            let lexeme = match self.lexemes.next() {
+                Some(lexeme) => lexeme,
+                None => {
+                    self.whitespace(self.lexemes.text().len(), true);
+                    self.end();
+                    continue;
+                }
+            };
+            let span = self.lexemes.span();
+            let comment = matches!(lexeme, Lexeme::Text(text)
+                if text.starts_with('#'));
+            self.whitespace(span.start, comment);
+
+            match lexeme {
+                Lexeme::Word(_) => self.name(span.start),
+                Lexeme::Text(_) if comment => self.comment(span.start),
+                Lexeme::Text(_) => {
+                    let text = self.lexemes.text();
+                    let ch = text[span.start..].chars().next().unwrap();
+                    let span = Span::new(span.start, span.start + ch.len_utf8());
+                    self.error(span, format!("invalid character '{}' (U+{:04X})",
+                        ch, ch as u32));
+                    self.seek(span.end);
+                }
+                Lexeme::Number(_) => self.number(span.start),
+                Lexeme::Operator(text) => self.operator(text, span),
+                Lexeme::Bracket(text) => self.bracket(text, span),
+            }
+        }
+    }
+}
+
+/// Parse an Aratar program.
+pub fn parse_module(text: &str) -> Result<Module<'_>> {
+    Parser::new(text).module()
+}
+
+/// A parsed source file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module<'a> {
+    pub body: Vec<Stmt<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stmt<'a> {
+    pub kind: StmtKind<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StmtKind<'a> {
+    /// `def name:` and its body
+    Def(&'a str, Vec<Stmt<'a>>),
+    Let(Binding<'a>),
+    Var(Binding<'a>),
+    Out(Binding<'a>),
+}
+
+/// `name: value`, after `let`, `var` or `out`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binding<'a> {
+    pub name: &'a str,
+    pub value: Expr<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr<'a> {
+    pub kind: ExprKind<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind<'a> {
+    Name(&'a str),
+    /// `$Type`, with the name of the type
+    Input(&'a str),
+    Int(u64),
+    Float(f64),
+    Neg(Box<Expr<'a>>),
+    BinOp(Box<Expr<'a>>, BinaryOp, Box<Expr<'a>>),
+    /// `f x`, applying the operator function `f` to `x`
+    Apply(Box<Expr<'a>>, Box<Expr<'a>>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinaryOp {
+    /// Get the source text of the operator.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+        }
+    }
+}
+
+/// Formats the program as source, with every operation in parentheses and
+/// each binding on its own line.
+impl fmt::Display for Module<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn stmts(f: &mut fmt::Formatter<'_>, body: &[Stmt<'_>], depth: usize)
+            -> fmt::Result
+        {
+            for stmt in body {
+                let indent = "    ".repeat(depth);
+                let (keyword, binding) = match &stmt.kind {
+                    StmtKind::Def(name, body) => {
+                        writeln!(f, "{}def {}:", indent, name)?;
+                        stmts(f, body, depth + 1)?;
+                        continue;
+                    }
+                    StmtKind::Let(binding) => ("let", binding),
+                    StmtKind::Var(binding) => ("var", binding),
+                    StmtKind::Out(binding) => ("out", binding),
+                };
+                writeln!(f, "{}{} {}: {}", indent, keyword, binding.name,
+                    binding.value)?;
+            }
+            Ok(())
+        }
+        stmts(f, &self.body, 0)
+    }
+}
+
+impl fmt::Display for Expr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ExprKind::Name(name) => write!(f, "{}", name),
+            ExprKind::Input(ty) => write!(f, "${}", ty),
+            ExprKind::Int(value) => write!(f, "{}", value),
+            ExprKind::Float(value) => write!(f, "{:?}", value),
+            ExprKind::Neg(operand) => write!(f, "(-{})", operand),
+            ExprKind::BinOp(left, op, right) => {
+                write!(f, "({} {} {})", left, op.as_str(), right)
+            }
+            ExprKind::Apply(func, arg) => write!(f, "({} {})", func, arg),
+        }
+    }
+}
+
+/// An operation of the old line-based compiler, which the shader backend
+/// was written against.  [`Module::to_ops`] derives them from a program.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op {
+    /// Collect input for a function.
+    InLet { name: String, def: String },
+    /// Collect input for a function.
+    InVar { name: String, def: String },
+    /// Function call.
+    Call {
+        name: String,
+        var_a: String,
+        operator: String,
+        var_b: String,
+    },
+    /// Collect output from a function.
+    Out {
+        name: String,
+        def: String,
+        from: String,
+    },
+    /// Start of a function definition.
+    Function { name: String },
+    /// A type definition, which no program produces.
+    Type {},
+}
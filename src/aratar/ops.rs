@@ -0,0 +1,129 @@
+// Aratar operations
+//
+//! Flattens a program into the [`Op`] list of the old line-based compiler.
+//! Each operation becomes an [`Op::Call`] of two variables, named after the
+//! binding it is the value of.  Operations inside another are named `%1`,
+//! `%2` and so on, which can't clash with names in the program, and the one
+//! an `out` outputs is named `""`, as the old compiler named it.
+
+use super::{Binding, Expr, ExprKind, Module, Op, Result, Stmt, StmtKind};
+use crate::{Diagnostic, Span};
+
+impl Module<'_> {
+    /// Convert the program to the operations of the old compiler.  Numbers
+    /// and negation have no operations, so are errors.
+    pub fn to_ops(&self) -> Result<Vec<Op>> {
+        let ops = self.spanned_ops()?;
+        Ok(ops.into_iter().map(|(op, _)| op).collect())
+    }
+
+    /// The operations, each with the span of the code it comes from.
+    pub(super) fn spanned_ops(&self) -> Result<Vec<(Op, Span)>> {
+        let mut lower = Lower { ops: Vec::new(), temps: 0 };
+        lower.stmts(&self.body)?;
+        Ok(lower.ops)
+    }
+}
+
+struct Lower {
+    ops: Vec<(Op, Span)>,
+    // Number of operations named `%n` so far.
+    temps: usize,
+}
+
+impl Lower {
+    fn stmts(&mut self, stmts: &[Stmt<'_>]) -> Result<()> {
+        for stmt in stmts {
+            match &stmt.kind {
+                StmtKind::Def(name, body) => {
+                    let op = Op::Function { name: name.to_string() };
+                    self.ops.push((op, stmt.span));
+                    self.stmts(body)?;
+                }
+                StmtKind::Let(binding) | StmtKind::Var(binding) => {
+                    let mutable = matches!(stmt.kind, StmtKind::Var(_));
+                    self.variable(binding, mutable, stmt.span)?;
+                }
+                StmtKind::Out(Binding { name, value }) => {
+                    let from = match value.kind {
+                        ExprKind::Name(from) => from.to_string(),
+                        _ => self.call(value, String::new())?,
+                    };
+                    let op = Op::Out {
+                        name: name.to_string(),
+                        def: String::new(),
+                        from,
+                    };
+                    self.ops.push((op, stmt.span));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn variable(&mut self, binding: &Binding<'_>, mutable: bool, span: Span)
+        -> Result<()>
+    {
+        let Binding { name, value } = binding;
+        let (name, def) = match value.kind {
+            ExprKind::Input(ty) => (name.to_string(), ty.to_string()),
+            ExprKind::Name(_) => {
+                return Err(Diagnostic::new(value.span,
+                    "a copy of a variable has no operation"));
+            }
+            _ => {
+                self.call(value, name.to_string())?;
+                return Ok(());
+            }
+        };
+        let op = match mutable {
+            true => Op::InVar { name, def },
+            false => Op::InLet { name, def },
+        };
+        self.ops.push((op, span));
+        Ok(())
+    }
+
+    // The name of the variable with the value of `expr`, pushing the
+    // operations computing it.
+    fn operand(&mut self, expr: &Expr<'_>) -> Result<String> {
+        match expr.kind {
+            ExprKind::Name(name) => Ok(name.to_string()),
+            _ => {
+                self.temps += 1;
+                let name = format!("%{}", self.temps);
+                self.call(expr, name)
+            }
+        }
+    }
+
+    // Push the operation computing `expr` into the variable `name`,
+    // returning `name`.
+    fn call(&mut self, expr: &Expr<'_>, name: String) -> Result<String> {
+        let (a, operator, b) = match &expr.kind {
+            ExprKind::BinOp(a, op, b) => (a, op.as_str(), b),
+            ExprKind::Apply(a, b) => (a, "", b),
+            ExprKind::Int(_) | ExprKind::Float(_) => {
+                return Err(Diagnostic::new(expr.span,
+                    "a number has no operation"));
+            }
+            ExprKind::Neg(_) => {
+                return Err(Diagnostic::new(expr.span,
+                    "negation has no operation"));
+            }
+            ExprKind::Name(_) | ExprKind::Input(_) => {
+                unreachable!("variables and inputs aren't operations")
+            }
+        };
+        let var_a = self.operand(a)?;
+        let var_b = self.operand(b)?;
+        let op = Op::Call {
+            name: name.clone(),
+            var_a,
+            operator: operator.to_string(),
+            var_b,
+        };
+        self.ops.push((op, expr.span));
+        Ok(name)
+    }
+}
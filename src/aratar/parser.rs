@@ -0,0 +1,348 @@
+// Aratar parser
+//
+//! Recursive descent parser for Aratar.  Comments are dropped before
+//! parsing.
+
+use super::{
+    BinaryOp, Binding, Bracket, Expr, ExprKind, Keyword, Module, Operator,
+    Result, Stmt, StmtKind, Token, TokenIterator,
+};
+use crate::{Diagnostic, Span};
+
+pub(super) struct Parser<'a> {
+    tokens: Vec<(Token<'a>, Span)>,
+    pos: usize,
+    // Span of the end of the input.
+    end: Span,
+    // Error from the tokenizer, reported when the parser reaches the end.
+    error: Option<Diagnostic>,
+}
+
+impl<'a> Parser<'a> {
+    /// Tokenize source text for parsing, up to the first error.
+    pub(super) fn new(text: &'a str) -> Self {
+        let mut tokens = Vec::new();
+        let mut error = None;
+        let mut iter = TokenIterator::new(text);
+        while let Some(token) = iter.next() {
+            match token {
+                Ok(Token::Comment(_)) => {}
+                Ok(token) => tokens.push((token, iter.span())),
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+        let end = match error {
+            Some(ref e) => Span::new(e.span.start, e.span.start),
+            None => Span::new(text.len(), text.len()),
+        };
+
+        Parser { tokens, pos: 0, end, error }
+    }
+
+    fn is_eof(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    // Span of the next token.
+    fn span(&self) -> Span {
+        self.tokens.get(self.pos).map(|(_, span)| *span).unwrap_or(self.end)
+    }
+
+    // Span from `start` to the end of the previous token, not counting the
+    // `NEWLINE`, `INDENT` and `DEDENT` tokens after a block.
+    fn since(&self, start: Span) -> Span {
+        let end = self.tokens[..self.pos]
+            .iter()
+            .rev()
+            .find(|(token, _)| {
+                !matches!(token, Token::Newline | Token::Indent | Token::Dedent)
+            })
+            .map_or(start.start, |(_, span)| span.end);
+        Span::new(start.start, end.max(start.start))
+    }
+
+    fn bump(&mut self) -> Token<'a> {
+        let token = self.tokens[self.pos].0.clone();
+        self.pos += 1;
+        token
+    }
+
+    fn is(&self, token: &Token<'_>) -> bool {
+        self.peek() == Some(token)
+    }
+
+    fn eat(&mut self, token: &Token<'_>) -> bool {
+        let found = self.is(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat_op(&mut self, op: Operator) -> bool {
+        self.eat(&Token::Operator(op))
+    }
+
+    fn expect_op(&mut self, op: Operator) -> Result<()> {
+        if self.eat_op(op) {
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{}`", op.as_str()))
+        }
+    }
+
+    // Report the next token as unexpected (or the tokenizer error at the
+    // end).
+    fn unexpected<T>(&self, expected: &str) -> Result<T> {
+        match self.peek() {
+            Some(token) => {
+                let found = match token {
+                    Token::Newline | Token::Indent | Token::Dedent => {
+                        token.to_string()
+                    }
+                    _ => format!("`{}`", token),
+                };
+                Err(Diagnostic::new(
+                    self.span(),
+                    format!("expected {}, found {}", expected, found),
+                ))
+            }
+            None => Err(self.error.clone().unwrap_or_else(|| {
+                Diagnostic::new(
+                    self.end,
+                    format!("expected {}, found end of file", expected),
+                )
+            })),
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str> {
+        match self.peek() {
+            Some(&Token::Name(name)) => {
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => self.unexpected("name"),
+        }
+    }
+
+    /// Parse the whole input as a module.
+    pub(super) fn module(&mut self) -> Result<Module<'a>> {
+        let mut body = Vec::new();
+        while !self.is_eof() {
+            body.extend(self.statement()?);
+        }
+        match self.error {
+            Some(ref e) => Err(e.clone()),
+            None => Ok(Module { body }),
+        }
+    }
+
+    // A definition, or a line of bindings.
+    fn statement(&mut self) -> Result<Vec<Stmt<'a>>> {
+        let start = self.span();
+        match self.peek() {
+            Some(Token::Indent) => {
+                Err(Diagnostic::new(start, "unexpected indent"))
+            }
+            Some(Token::Keyword(Keyword::Def)) => {
+                self.bump();
+                let name = self.name()?;
+                self.expect_op(Operator::Colon)?;
+                let body = self.block(start)?;
+                let kind = StmtKind::Def(name, body);
+                Ok(vec![Stmt { kind, span: self.since(start) }])
+            }
+            _ => self.bindings(),
+        }
+    }
+
+    // The body of `def` starting at `start`, after its `:`.
+    fn block(&mut self, start: Span) -> Result<Vec<Stmt<'a>>> {
+        if !self.eat(&Token::Newline) {
+            return self.unexpected("newline");
+        }
+        if !self.eat(&Token::Indent) {
+            if self.is_eof() {
+                return self.unexpected("indented block");
+            }
+            return Err(Diagnostic::new(start,
+                "expected an indented block after `def`"));
+        }
+        let mut body = Vec::new();
+        while !self.eat(&Token::Dedent) {
+            if self.is_eof() {
+                return self.unexpected("statement");
+            }
+            body.extend(self.statement()?);
+        }
+        Ok(body)
+    }
+
+    // Bindings separated by `;` up to the end of the line, and the block of
+    // bindings after it using the line's last keyword.
+    fn bindings(&mut self) -> Result<Vec<Stmt<'a>>> {
+        let mut stmts = Vec::new();
+        let mut keyword;
+        loop {
+            let start = self.span();
+            keyword = match self.peek() {
+                Some(&Token::Keyword(keyword)) if keyword != Keyword::Def => {
+                    self.bump();
+                    keyword
+                }
+                _ => return self.unexpected("`def`, `let`, `var` or `out`"),
+            };
+            stmts.push(self.binding(keyword, start)?);
+            if !self.end_of_binding()? {
+                break;
+            }
+        }
+        if self.eat(&Token::Indent) {
+            while !self.eat(&Token::Dedent) {
+                loop {
+                    let start = self.span();
+                    stmts.push(self.binding(keyword, start)?);
+                    if !self.end_of_binding()? {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(stmts)
+    }
+
+    // Skip the `;` or `NEWLINE` after a binding, returning whether another
+    // binding follows on the same line.
+    fn end_of_binding(&mut self) -> Result<bool> {
+        let semi = self.eat_op(Operator::Semi);
+        if self.eat(&Token::Newline) {
+            return Ok(false);
+        }
+        match semi {
+            true => Ok(true),
+            false => self.unexpected("`;` or newline"),
+        }
+    }
+
+    // `name: value` after `keyword`, for a binding starting at `start`.
+    fn binding(&mut self, keyword: Keyword, start: Span) -> Result<Stmt<'a>> {
+        let name = self.name()?;
+        self.expect_op(Operator::Colon)?;
+        let value = match self.peek() {
+            Some(&Token::Input(ty)) => {
+                let span = self.span();
+                self.bump();
+                if keyword == Keyword::Out {
+                    return Err(Diagnostic::new(span,
+                        "an input can only be the value of `let` or `var`"));
+                }
+                Expr { kind: ExprKind::Input(ty), span }
+            }
+            _ => self.expr()?,
+        };
+        let binding = Binding { name, value };
+        let kind = match keyword {
+            Keyword::Let => StmtKind::Let(binding),
+            Keyword::Var => StmtKind::Var(binding),
+            _ => StmtKind::Out(binding),
+        };
+        Ok(Stmt { kind, span: self.since(start) })
+    }
+
+    // Operations of increasing precedence.
+    fn expr(&mut self) -> Result<Expr<'a>> {
+        self.binary(0)
+    }
+
+    // Binary operators binding at least as tightly as `level`.
+    fn binary(&mut self, level: usize) -> Result<Expr<'a>> {
+        const LEVELS: &[&[(Operator, BinaryOp)]] = &[
+            &[
+                (Operator::Plus, BinaryOp::Add),
+                (Operator::Minus, BinaryOp::Sub),
+            ],
+            &[
+                (Operator::Star, BinaryOp::Mul),
+                (Operator::Slash, BinaryOp::Div),
+                (Operator::Percent, BinaryOp::Rem),
+            ],
+        ];
+        let operators = match LEVELS.get(level) {
+            Some(operators) => operators,
+            None => return self.unary(),
+        };
+        let start = self.span();
+        let mut left = self.binary(level + 1)?;
+        while let Some(&(_, op)) = operators
+            .iter()
+            .find(|(operator, _)| self.is(&Token::Operator(*operator)))
+        {
+            self.bump();
+            let right = self.binary(level + 1)?;
+            left = Expr {
+                kind: ExprKind::BinOp(Box::new(left), op, Box::new(right)),
+                span: self.since(start),
+            };
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        if self.eat_op(Operator::Minus) {
+            let operand = self.unary()?;
+            return Ok(Expr {
+                kind: ExprKind::Neg(Box::new(operand)),
+                span: self.since(start),
+            });
+        }
+        let mut func = self.primary()?;
+        while matches!(self.peek(), Some(Token::Name(_) | Token::Int(_)
+            | Token::Float(_) | Token::Input(_)
+            | Token::Bracket(Bracket::ParensL)))
+        {
+            let arg = self.primary()?;
+            func = Expr {
+                kind: ExprKind::Apply(Box::new(func), Box::new(arg)),
+                span: self.since(start),
+            };
+        }
+        Ok(func)
+    }
+
+    fn primary(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let kind = match self.peek() {
+            Some(&Token::Name(name)) => ExprKind::Name(name),
+            Some(&Token::Int(value)) => ExprKind::Int(value),
+            Some(&Token::Float(value)) => ExprKind::Float(value),
+            Some(Token::Input(_)) => {
+                return Err(Diagnostic::new(start,
+                    "an input must be the whole value of `let` or `var`"));
+            }
+            Some(Token::Bracket(Bracket::ParensL)) => {
+                self.bump();
+                let expr = self.expr()?;
+                if !self.eat(&Token::Bracket(Bracket::ParensR)) {
+                    return self.unexpected("`)`");
+                }
+                return Ok(Expr { kind: expr.kind, span: self.since(start) });
+            }
+            _ => return self.unexpected("expression"),
+        };
+        self.bump();
+        if self.is(&Token::Operator(Operator::Dot)) {
+            return Err(Diagnostic::new(self.span(),
+                "function operators aren't supported yet"));
+        }
+        Ok(Expr { kind, span: start })
+    }
+}
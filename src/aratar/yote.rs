@@ -0,0 +1,606 @@
+// Yote
+//
+//! Yote, the portable shader bytecode Aratar compiles to, and its
+//! disassembler.
+//!
+//! A Yote program computes the outputs of a vertex, its position and
+//! color, from inputs: uniforms, which are the same for every vertex of a
+//! draw, and attributes, which are given for each vertex.  A program is a
+//! list of instructions, each of which but the outputs defines a variable.
+//! Variables are numbered from 0 in the order they are defined, and can
+//! only be used after they are.
+//!
+//! # Binary format
+//!
+//! The bytecode starts with the header `b"Yote"` and a version byte, which
+//! is 1 for the format described here, and is followed by instructions of
+//! one or more bytes:
+//!
+//! | Bytes               | Instruction                                   |
+//! |---------------------|-----------------------------------------------|
+//! | `T`                 | Uniform of type `T`                           |
+//! | `0x40 + T`          | Attribute of type `T`                         |
+//! | `0x80 + T, O, A, B` | Operation `O` on variables `A` and `B`, a `T` |
+//! | `0xFE, A`           | Output variable `A` as the position           |
+//! | `0xFF, A`           | Output variable `A` as the color              |
+//!
+//! Types `T` are:
+//!
+//! | Byte | Type   |                                         |
+//! |------|--------|-----------------------------------------|
+//! | 1    | `Vec1` | a float                                 |
+//! | 2    | `Vec2` | a vector of 2 floats                    |
+//! | 3    | `Vec3` | a vector of 3 floats                    |
+//! | 4    | `Vec4` | a vector of 4 floats                    |
+//! | 5    | `View` | a 4x4 matrix of floats                  |
+//! | 6    | `Int1` | a 32-bit integer                        |
+//! | 7    | `Bmap` | a bitmap, a texture sampled at `Vec2`s  |
+//!
+//! Operations `O` are:
+//!
+//! | Byte | Operation |                                                   |
+//! |------|-----------|---------------------------------------------------|
+//! | 1    | `add`     |                                                   |
+//! | 2    | `sub`     |                                                   |
+//! | 3    | `mul`     | of a `View` and a `Vec4`, transforms the vector   |
+//! | 4    | `div`     |                                                   |
+//! | 5    | `mod`     | the remainder, with the sign of the divisor       |
+//! | 6    | `sample`  | the `Vec4` color of a `Bmap` at a `Vec2`          |
+//!
+//! The arithmetic operations apply to two values of the same type, item by
+//! item (except `mul` of two `View`s, which multiplies the matrices), or to
+//! a `Vec1` and a vector or a `View`, applying the `Vec1` to each item.
+//! Their result has the type of the wider operand.  `Int1`s can only be
+//! used with `Int1`s.  Nothing can be divided by a `View`, and `mod` can't
+//! be used on a `View` or to divide a `Vec1` by a wider vector.
+//!
+//! Both outputs must be a `Vec3` or a `Vec4`; a `Vec3` position has a `w`
+//! of 1 and a `Vec3` color is opaque.  Each output can be given at most
+//! once.
+//!
+//! # From Aratar
+//!
+//! A shader is an Aratar program without definitions.  `let name: $Type`
+//! is a uniform and `var name: $Type` an attribute, and the outputs are
+//! `out position: value` and `out color: value`.  The operators `+`, `-`,
+//! `*`, `/` and `%` are `add`, `sub`, `mul`, `div` and `mod`, applying a
+//! `View` to a vector is `mul`, and applying a `Bmap` to a `Vec2` is
+//! `sample`.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+use super::{parse_module, Module, Op, Result};
+use crate::{Diagnostic, Span};
+
+pub mod glsl;
+pub mod lower;
+pub mod spirv;
+pub mod wgsl;
+
+/// The version of the bytecode format
+pub const VERSION: u8 = 1;
+
+const MAGIC: &[u8] = b"Yote";
+const ATTRIBUTE: u8 = 0x40;
+const CALL: u8 = 0x80;
+const POSITION: u8 = 0xFE;
+const COLOR: u8 = 0xFF;
+
+/// The type of a variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Primitive {
+    Vec1 = 1,
+    Vec2 = 2,
+    Vec3 = 3,
+    Vec4 = 4,
+    View = 5,
+    Int1 = 6,
+    Bmap = 7,
+}
+
+const PRIMITIVES: &[(&str, Primitive)] = &[
+    ("Vec1", Primitive::Vec1),
+    ("Vec2", Primitive::Vec2),
+    ("Vec3", Primitive::Vec3),
+    ("Vec4", Primitive::Vec4),
+    ("View", Primitive::View),
+    ("Int1", Primitive::Int1),
+    ("Bmap", Primitive::Bmap),
+];
+
+impl Primitive {
+    fn new(name: &str) -> Option<Primitive> {
+        PRIMITIVES.iter().find(|(text, _)| *text == name).map(|(_, ty)| *ty)
+    }
+
+    fn from_byte(byte: u8) -> Option<Primitive> {
+        PRIMITIVES.get(usize::from(byte).checked_sub(1)?).map(|(_, ty)| *ty)
+    }
+
+    /// Get the Aratar name of the type.
+    pub fn as_str(self) -> &'static str {
+        PRIMITIVES[self as usize - 1].0
+    }
+
+    /// The number of floats in a vector, for `Vec1` to `Vec4`.
+    pub fn width(self) -> Option<usize> {
+        match self {
+            Primitive::Vec1 => Some(1),
+            Primitive::Vec2 => Some(2),
+            Primitive::Vec3 => Some(3),
+            Primitive::Vec4 => Some(4),
+            _ => None,
+        }
+    }
+}
+
+/// An operation of a call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Add = 1,
+    Sub = 2,
+    Mul = 3,
+    Div = 4,
+    Mod = 5,
+    Sample = 6,
+}
+
+const OPERATIONS: &[(&str, Operation)] = &[
+    ("add", Operation::Add),
+    ("sub", Operation::Sub),
+    ("mul", Operation::Mul),
+    ("div", Operation::Div),
+    ("mod", Operation::Mod),
+    ("sample", Operation::Sample),
+];
+
+impl Operation {
+    fn from_byte(byte: u8) -> Option<Operation> {
+        OPERATIONS.get(usize::from(byte).checked_sub(1)?).map(|(_, op)| *op)
+    }
+
+    /// Get the name of the operation in disassembly.
+    pub fn as_str(self) -> &'static str {
+        OPERATIONS[self as usize - 1].0
+    }
+
+    /// The type of the result of the operation on values of types `a` and
+    /// `b`, if it can be done.
+    pub fn result(self, a: Primitive, b: Primitive) -> Option<Primitive> {
+        use Primitive::*;
+
+        match (self, a, b) {
+            (Operation::Sample, Bmap, Vec2) => Some(Vec4),
+            (Operation::Sample, _, _) | (_, Bmap, _) | (_, _, Bmap) => None,
+            (Operation::Div, _, View)
+            | (Operation::Mod, View, _)
+            | (Operation::Mod, _, View) => None,
+            (_, a, b) if a == b => Some(a),
+            (Operation::Mul, View, Vec4) => Some(Vec4),
+            (_, Int1, _) | (_, _, Int1) => None,
+            (_, a, Vec1) => Some(a),
+            (Operation::Mod, Vec1, _) => None,
+            (_, Vec1, b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+/// An output of a program
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Output {
+    Position,
+    Color,
+}
+
+impl Output {
+    /// Get the Aratar name of the output.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Output::Position => "position",
+            Output::Color => "color",
+        }
+    }
+}
+
+/// A decoded instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Uniform(Primitive),
+    Attribute(Primitive),
+    /// The type of the result, the operation and its operands
+    Call(Primitive, Operation, u8, u8),
+    Output(Output, u8),
+}
+
+/// A decoded and checked program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+    /// The type of each variable
+    pub types: Vec<Primitive>,
+}
+
+impl Program {
+    /// Decode bytecode, checking that it is a valid program.  The spans of
+    /// errors are byte offsets in the bytecode.
+    pub fn decode(bytecode: &[u8]) -> Result<Program> {
+        let error = |start: usize, len: usize, message: String| {
+            Diagnostic::new(Span::new(start, start + len), message)
+        };
+        if !bytecode.starts_with(MAGIC) {
+            return Err(error(0, MAGIC.len(), "not Yote bytecode".to_string()));
+        }
+        match bytecode.get(MAGIC.len()) {
+            Some(&VERSION) => {}
+            Some(version) => {
+                return Err(error(MAGIC.len(), 1,
+                    format!("unsupported Yote version {}", version)));
+            }
+            None => {
+                return Err(error(MAGIC.len(), 0,
+                    "bytecode ends before the version".to_string()));
+            }
+        }
+
+        let mut program = Program {
+            instructions: Vec::new(),
+            types: Vec::new(),
+        };
+        let mut outputs = Vec::new();
+        let mut at = MAGIC.len() + 1;
+        while let Some(&byte) = bytecode.get(at) {
+            let len = match byte {
+                POSITION | COLOR => 2,
+                _ if byte & CALL != 0 => 4,
+                _ => 1,
+            };
+            let bytes = match bytecode.get(at..at + len) {
+                Some(bytes) => bytes,
+                None => {
+                    return Err(error(at, bytecode.len() - at,
+                        "bytecode ends in the middle of an instruction"
+                            .to_string()));
+                }
+            };
+            let types = &program.types;
+            let variable = |i: usize| match types.get(usize::from(bytes[i])) {
+                Some(&ty) => Ok(ty),
+                None => Err(error(at + i, 1,
+                    format!("variable {} isn't defined yet", bytes[i]))),
+            };
+            let instruction = match byte {
+                POSITION | COLOR => {
+                    let output = match byte {
+                        POSITION => Output::Position,
+                        _ => Output::Color,
+                    };
+                    let ty = variable(1)?;
+                    if !matches!(ty, Primitive::Vec3 | Primitive::Vec4) {
+                        return Err(error(at + 1, 1, format!(
+                            "the {} must be a `Vec3` or `Vec4`, not `{}`",
+                            output.as_str(),
+                            ty.as_str(),
+                        )));
+                    }
+                    if outputs.contains(&output) {
+                        return Err(error(at, 2, format!(
+                            "the {} is output twice",
+                            output.as_str(),
+                        )));
+                    }
+                    outputs.push(output);
+                    Instruction::Output(output, bytes[1])
+                }
+                _ => {
+                    let both = CALL | ATTRIBUTE;
+                    let ty = match Primitive::from_byte(byte & !both) {
+                        Some(ty) if byte & both != both => ty,
+                        _ => {
+                            return Err(error(at, 1,
+                                format!("unknown instruction 0x{:02X}", byte)));
+                        }
+                    };
+                    if program.types.len() == 256 {
+                        return Err(error(at, len,
+                            "a program can have at most 256 variables"
+                                .to_string()));
+                    }
+                    let instruction = if byte & CALL != 0 {
+                        let op = match Operation::from_byte(bytes[1]) {
+                            Some(op) => op,
+                            None => {
+                                return Err(error(at + 1, 1, format!(
+                                    "unknown operation {}",
+                                    bytes[1],
+                                )));
+                            }
+                        };
+                        let (a, b) = (variable(2)?, variable(3)?);
+                        if op.result(a, b) != Some(ty) {
+                            return Err(error(at, len, format!(
+                                "`{}` of `{}` and `{}` isn't `{}`",
+                                op.as_str(),
+                                a.as_str(),
+                                b.as_str(),
+                                ty.as_str(),
+                            )));
+                        }
+                        Instruction::Call(ty, op, bytes[2], bytes[3])
+                    } else if byte & ATTRIBUTE != 0 {
+                        Instruction::Attribute(ty)
+                    } else {
+                        Instruction::Uniform(ty)
+                    };
+                    program.types.push(ty);
+                    instruction
+                }
+            };
+            program.instructions.push(instruction);
+            at += len;
+        }
+
+        Ok(program)
+    }
+
+    /// Encode the program as bytecode.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytecode = MAGIC.to_vec();
+        bytecode.push(VERSION);
+        for instruction in &self.instructions {
+            match *instruction {
+                Instruction::Uniform(ty) => bytecode.push(ty as u8),
+                Instruction::Attribute(ty) => {
+                    bytecode.push(ATTRIBUTE | ty as u8);
+                }
+                Instruction::Call(ty, op, a, b) => {
+                    bytecode.extend([CALL | ty as u8, op as u8, a, b]);
+                }
+                Instruction::Output(output, a) => {
+                    let byte = match output {
+                        Output::Position => POSITION,
+                        Output::Color => COLOR,
+                    };
+                    bytecode.extend([byte, a]);
+                }
+            }
+        }
+        bytecode
+    }
+
+    /// The variable output as `output`, if it is.
+    pub fn output(&self, output: Output) -> Option<u8> {
+        self.instructions.iter().find_map(|instruction| match instruction {
+            Instruction::Output(which, a) if *which == output => Some(*a),
+            _ => None,
+        })
+    }
+
+    /// The instructions defining the variables, in the order of their
+    /// numbers.
+    pub fn variables(&self) -> Vec<Instruction> {
+        self.instructions
+            .iter()
+            .filter(|instruction| {
+                !matches!(instruction, Instruction::Output(..))
+            })
+            .copied()
+            .collect()
+    }
+
+    /// The binding of each uniform in group (or descriptor set) 0 of
+    /// WebGPU and Vulkan, indexed by number.  Uniforms are bound in order,
+    /// and a `Bmap` to two bindings, its texture's and then its sampler's.
+    pub fn bindings(&self) -> Vec<Option<u32>> {
+        let mut binding = 0;
+        let mut bindings = Vec::new();
+        for instruction in self.variables() {
+            bindings.push(match instruction {
+                Instruction::Uniform(ty) => {
+                    let this = binding;
+                    binding += if ty == Primitive::Bmap { 2 } else { 1 };
+                    Some(this)
+                }
+                _ => None,
+            });
+        }
+        bindings
+    }
+
+    /// Which variables the value of `output` is computed from, itself
+    /// included, indexed by number.  None are if it isn't output.
+    pub fn dependencies(&self, output: Output) -> Vec<bool> {
+        let mut needed = vec![false; self.types.len()];
+        if let Some(a) = self.output(output) {
+            needed[usize::from(a)] = true;
+        }
+        for (number, instruction) in self.variables().iter().enumerate().rev() {
+            if let (true, Instruction::Call(_, _, a, b)) =
+                (needed[number], instruction)
+            {
+                needed[usize::from(*a)] = true;
+                needed[usize::from(*b)] = true;
+            }
+        }
+        needed
+    }
+}
+
+// Decode bytecode to translate, which needs both outputs.
+fn decode_outputs(bytecode: &[u8]) -> Result<Program> {
+    let program = Program::decode(bytecode)?;
+    for &output in &[Output::Position, Output::Color] {
+        if program.output(output).is_none() {
+            let end = Span::new(bytecode.len(), bytecode.len());
+            return Err(Diagnostic::new(end,
+                format!("the program doesn't output a {}", output.as_str())));
+        }
+    }
+    Ok(program)
+}
+
+/// Compile the source of an Aratar shader to Yote bytecode.
+pub fn compile_shader(text: &str) -> Result<Vec<u8>> {
+    compile(&parse_module(text)?)
+}
+
+/// Compile an Aratar shader to Yote bytecode.
+pub fn compile(module: &Module<'_>) -> Result<Vec<u8>> {
+    let mut program = Program { instructions: Vec::new(), types: Vec::new() };
+    // The variable numbers and types of the names
+    let mut variables: HashMap<String, (u8, Primitive)> = HashMap::new();
+    let mut outputs = Vec::new();
+
+    for (op, span) in module.spanned_ops()? {
+        let error = |message: String| Diagnostic::new(span, message);
+        let variable = |name: &str| match variables.get(name) {
+            Some(&variable) => Ok(variable),
+            None => Err(error(format!("no variable named `{}`", name))),
+        };
+        let attribute = matches!(op, Op::InVar { .. });
+        let (name, instruction, ty) = match op {
+            Op::InLet { name, def } | Op::InVar { name, def } => {
+                let ty = match Primitive::new(&def) {
+                    Some(ty) => ty,
+                    None => {
+                        return Err(error(format!("unknown type `{}`", def)));
+                    }
+                };
+                let instruction = match attribute {
+                    true if ty == Primitive::Bmap => {
+                        return Err(error(
+                            "a `Bmap` can't be given for each vertex"
+                                .to_string(),
+                        ));
+                    }
+                    true => Instruction::Attribute(ty),
+                    false => Instruction::Uniform(ty),
+                };
+                (name, instruction, ty)
+            }
+            Op::Call { name, var_a, operator, var_b } => {
+                let (a, a_ty) = variable(&var_a)?;
+                let (b, b_ty) = variable(&var_b)?;
+                let operation = match (operator.as_str(), a_ty) {
+                    ("+", _) => Operation::Add,
+                    ("-", _) => Operation::Sub,
+                    ("*", _) | ("", Primitive::View) => Operation::Mul,
+                    ("/", _) => Operation::Div,
+                    ("%", _) => Operation::Mod,
+                    ("", Primitive::Bmap) => Operation::Sample,
+                    _ => {
+                        return Err(error(format!(
+                            "`{}` can't be applied, only `View` and `Bmap` can",
+                            a_ty.as_str(),
+                        )));
+                    }
+                };
+                let ty = match operation.result(a_ty, b_ty) {
+                    Some(ty) => ty,
+                    None if operator.is_empty() => {
+                        return Err(error(format!(
+                            "`{}` can't be applied to `{}`",
+                            a_ty.as_str(),
+                            b_ty.as_str(),
+                        )));
+                    }
+                    None => {
+                        return Err(error(format!(
+                            "`{}` can't be used on `{}` and `{}`",
+                            operator,
+                            a_ty.as_str(),
+                            b_ty.as_str(),
+                        )));
+                    }
+                };
+                (name, Instruction::Call(ty, operation, a, b), ty)
+            }
+            Op::Out { name, from, .. } => {
+                let output = match name.as_str() {
+                    "position" => Output::Position,
+                    "color" => Output::Color,
+                    _ => {
+                        return Err(error(
+                            "the outputs of a shader are `position` and \
+                                `color`".to_string(),
+                        ));
+                    }
+                };
+                let (a, ty) = variable(&from)?;
+                if !matches!(ty, Primitive::Vec3 | Primitive::Vec4) {
+                    return Err(error(format!(
+                        "the {} must be a `Vec3` or `Vec4`, not `{}`",
+                        output.as_str(),
+                        ty.as_str(),
+                    )));
+                }
+                if outputs.contains(&output) {
+                    return Err(error(format!(
+                        "the {} is output twice",
+                        output.as_str(),
+                    )));
+                }
+                outputs.push(output);
+                program.instructions.push(Instruction::Output(output, a));
+                continue;
+            }
+            Op::Function { .. } | Op::Type {} => {
+                let message = "a shader can't have definitions";
+                return Err(error(message.to_string()));
+            }
+        };
+        let number = match u8::try_from(program.types.len()) {
+            Ok(number) => number,
+            Err(_) => {
+                return Err(error(
+                    "a shader can have at most 256 variables".to_string(),
+                ));
+            }
+        };
+        if variables.insert(name.clone(), (number, ty)).is_some()
+            && !name.starts_with('%')
+            && !name.is_empty()
+        {
+            return Err(error(format!("`{}` is defined twice", name)));
+        }
+        program.instructions.push(instruction);
+        program.types.push(ty);
+    }
+
+    Ok(program.encode())
+}
+
+/// Disassemble bytecode to text, one instruction per line.
+pub fn disassemble(bytecode: &[u8]) -> Result<String> {
+    let program = Program::decode(bytecode)?;
+    let mut text = format!("yote {}\n", VERSION);
+    let mut number = 0;
+    for instruction in &program.instructions {
+        let _ = match *instruction {
+            Instruction::Uniform(ty) => {
+                writeln!(text, "%{} = uniform {}", number, ty.as_str())
+            }
+            Instruction::Attribute(ty) => {
+                writeln!(text, "%{} = attribute {}", number, ty.as_str())
+            }
+            Instruction::Call(ty, op, a, b) => writeln!(
+                text,
+                "%{} = {} {} %{}, %{}",
+                number,
+                op.as_str(),
+                ty.as_str(),
+                a,
+                b,
+            ),
+            Instruction::Output(output, a) => {
+                writeln!(text, "{} %{}", output.as_str(), a)
+            }
+        };
+        if !matches!(instruction, Instruction::Output(..)) {
+            number += 1;
+        }
+    }
+    Ok(text)
+}
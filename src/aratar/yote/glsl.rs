@@ -0,0 +1,273 @@
+// GLSL
+//
+//! Translates Yote bytecode to GLSL ES 1.00 or 3.00 vertex and fragment
+//! shaders.
+//!
+//! The vertex shader computes the position and the fragment shader the
+//! color, each computing only the variables its output is computed from.
+//! Variables are named after their numbers: uniform 0 is `u0`, attribute 1
+//! is `a1` and call 2 is `t2`.  Attributes the color is computed from are
+//! passed to the fragment shader as varyings named `v1`, which are
+//! interpolated between the vertices.
+//!
+//! The vertex shader declares every attribute, so that in GLSL ES 3.00 the
+//! location of an attribute is its position among the attributes of the
+//! program.  Outputs can't be computed from `Int1`s, so they are only
+//! declared, as floats in GLSL ES 1.00, which has no integer attributes.
+//!
+//! Uniforms used by both shaders must have the same precision in each,
+//! which is `mediump`, the default of the fragment shader.
+
+use std::fmt::Write;
+
+use super::{
+    decode_outputs, Instruction, Operation, Output, Primitive, Program,
+};
+use crate::aratar::Result;
+
+/// A version of GLSL ES
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    /// GLSL ES 1.00, of OpenGL ES 2 and WebGL
+    Es100,
+    /// GLSL ES 3.00, of OpenGL ES 3 and WebGL 2
+    Es300,
+}
+
+impl Version {
+    /// Get the version as written after `#version`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Version::Es100 => "100",
+            Version::Es300 => "300 es",
+        }
+    }
+}
+
+/// The source of the shaders of a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shaders {
+    pub vertex: String,
+    pub fragment: String,
+}
+
+/// Translate Yote bytecode to GLSL ES shaders.  The program must output
+/// both a position and a color.
+pub fn translate(bytecode: &[u8], version: Version) -> Result<Shaders> {
+    let program = decode_outputs(bytecode)?;
+    let shader = Shader {
+        program: &program,
+        variables: program.variables(),
+        version,
+        vertex: program.dependencies(Output::Position),
+        fragment: program.dependencies(Output::Color),
+    };
+    Ok(Shaders {
+        vertex: shader.vertex(),
+        fragment: shader.fragment(),
+    })
+}
+
+// A program being translated
+struct Shader<'a> {
+    program: &'a Program,
+    variables: Vec<Instruction>,
+    version: Version,
+    // The variables the position and the color are computed from
+    vertex: Vec<bool>,
+    fragment: Vec<bool>,
+}
+
+impl Shader<'_> {
+    fn vertex(&self) -> String {
+        let mut declarations = Vec::new();
+        let mut varyings = Vec::new();
+        let mut location = 0;
+        for (n, instruction) in self.variables.iter().enumerate() {
+            let ty = self.program.types[n];
+            match instruction {
+                Instruction::Uniform(_) if self.vertex[n] => {
+                    let precision = match self.fragment[n] {
+                        true if ty != Primitive::Bmap => "mediump ",
+                        _ => "",
+                    };
+                    declarations.push(format!("uniform {}{} u{};",
+                        precision, self.ty(ty), n));
+                }
+                Instruction::Attribute(_) => {
+                    declarations.push(match self.version {
+                        Version::Es100 => {
+                            format!("attribute {} a{};", self.input(ty), n)
+                        }
+                        Version::Es300 => format!(
+                            "layout(location = {}) in {} a{};",
+                            location,
+                            self.ty(ty),
+                            n,
+                        ),
+                    });
+                    location += 1;
+                    if self.fragment[n] {
+                        varyings.push(n);
+                    }
+                }
+                _ => {}
+            }
+        }
+        for &n in &varyings {
+            declarations.push(self.varying(n, "out"));
+        }
+
+        let mut body = self.calls(&self.vertex, false);
+        let position = self.program.output(Output::Position).unwrap();
+        body.push(format!("gl_Position = {};",
+            self.output(position, false)));
+        for &n in &varyings {
+            body.push(format!("v{} = a{};", n, n));
+        }
+        self.source(None, &declarations, &body)
+    }
+
+    fn fragment(&self) -> String {
+        let mut declarations = Vec::new();
+        for (n, instruction) in self.variables.iter().enumerate() {
+            match instruction {
+                Instruction::Uniform(ty) if self.fragment[n] => {
+                    declarations.push(format!("uniform {} u{};",
+                        self.ty(*ty), n));
+                }
+                Instruction::Attribute(_) if self.fragment[n] => {
+                    declarations.push(self.varying(n, "in"));
+                }
+                _ => {}
+            }
+        }
+
+        let mut body = self.calls(&self.fragment, true);
+        let color = self.program.output(Output::Color).unwrap();
+        let color = self.output(color, true);
+        match self.version {
+            Version::Es100 => body.push(format!("gl_FragColor = {};", color)),
+            Version::Es300 => {
+                declarations.push("out vec4 color;".to_string());
+                body.push(format!("color = {};", color));
+            }
+        }
+        let precision = "precision mediump float;";
+        self.source(Some(precision), &declarations, &body)
+    }
+
+    // The whole source of a shader.
+    fn source(&self, precision: Option<&str>, declarations: &[String],
+        body: &[String]) -> String
+    {
+        let mut text = format!("#version {}\n", self.version.as_str());
+        if let Some(precision) = precision {
+            let _ = writeln!(text, "{}", precision);
+        }
+        text.push('\n');
+        for declaration in declarations {
+            let _ = writeln!(text, "{}", declaration);
+        }
+        if !declarations.is_empty() {
+            text.push('\n');
+        }
+        text.push_str("void main() {\n");
+        for line in body {
+            let _ = writeln!(text, "    {}", line);
+        }
+        text.push_str("}\n");
+        text
+    }
+
+    // The declaration of the varying passing attribute `n`, with `qualifier`
+    // `in` or `out` in GLSL ES 3.00.
+    fn varying(&self, n: usize, qualifier: &str) -> String {
+        let ty = self.program.types[n];
+        match self.version {
+            Version::Es100 => format!("varying {} v{};", self.ty(ty), n),
+            Version::Es300 => {
+                format!("{} {} v{};", qualifier, self.ty(ty), n)
+            }
+        }
+    }
+
+    // The statements computing the calls in `needed`.
+    fn calls(&self, needed: &[bool], fragment: bool) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (n, instruction) in self.variables.iter().enumerate() {
+            if let (true, &Instruction::Call(ty, op, a, b)) =
+                (needed[n], instruction)
+            {
+                let a = self.operand(a, fragment);
+                let b = self.operand(b, fragment);
+                let value = match op {
+                    Operation::Add => format!("{} + {}", a, b),
+                    Operation::Sub => format!("{} - {}", a, b),
+                    Operation::Mul => format!("{} * {}", a, b),
+                    Operation::Div => format!("{} / {}", a, b),
+                    Operation::Mod => format!("mod({}, {})", a, b),
+                    // Vertex shaders have no derivatives to choose a level
+                    // of detail with.
+                    Operation::Sample => match (self.version, fragment) {
+                        (Version::Es100, true) => {
+                            format!("texture2D({}, {})", a, b)
+                        }
+                        (Version::Es100, false) => {
+                            format!("texture2DLod({}, {}, 0.0)", a, b)
+                        }
+                        (Version::Es300, true) => {
+                            format!("texture({}, {})", a, b)
+                        }
+                        (Version::Es300, false) => {
+                            format!("textureLod({}, {}, 0.0)", a, b)
+                        }
+                    },
+                };
+                lines.push(format!("{} t{} = {};", self.ty(ty), n, value));
+            }
+        }
+        lines
+    }
+
+    // The expression for the value of variable `n`.
+    fn operand(&self, n: u8, fragment: bool) -> String {
+        let n = usize::from(n);
+        match self.variables[n] {
+            Instruction::Uniform(_) => format!("u{}", n),
+            Instruction::Attribute(_) if fragment => format!("v{}", n),
+            Instruction::Attribute(_) => format!("a{}", n),
+            _ => format!("t{}", n),
+        }
+    }
+
+    // The `vec4` value of output variable `n`.
+    fn output(&self, n: u8, fragment: bool) -> String {
+        let value = self.operand(n, fragment);
+        match self.program.types[usize::from(n)] {
+            Primitive::Vec3 => format!("vec4({}, 1.0)", value),
+            _ => value,
+        }
+    }
+
+    // The GLSL type of an attribute of type `ty`.
+    fn input(&self, ty: Primitive) -> &'static str {
+        match (self.version, ty) {
+            (Version::Es100, Primitive::Int1) => "float",
+            _ => self.ty(ty),
+        }
+    }
+
+    // The GLSL type of `ty`.
+    fn ty(&self, ty: Primitive) -> &'static str {
+        match ty {
+            Primitive::Vec1 => "float",
+            Primitive::Vec2 => "vec2",
+            Primitive::Vec3 => "vec3",
+            Primitive::Vec4 => "vec4",
+            Primitive::View => "mat4",
+            Primitive::Int1 => "int",
+            Primitive::Bmap => "sampler2D",
+        }
+    }
+}
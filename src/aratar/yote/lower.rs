@@ -0,0 +1,225 @@
+// Yote lowering
+//
+//! Lowers Yote bytecode to the [IR](crate::ir), as one function `@shader`
+//! computing the outputs of a vertex from its inputs.
+//!
+//! The parameters of `@shader` are the uniforms and attributes, in the
+//! order of their numbers.  A `Vec1` is an `f32`, the other vectors are
+//! arrays of `f32`s, a `View` is a `[16 x f32]` in column-major order, an
+//! `Int1` is an `i32` and a `Bmap` is a `ptr` to a texture.  It returns the
+//! position and the color as a `{[4 x f32], [4 x f32]}`, with a `w` of 1
+//! added to a `Vec3`, and only the variables they depend on are computed.
+//!
+//! Operations are done item by item with `extractvalue` and
+//! `insertvalue`.  `mod` calls the C library's `floorf`, and `sample` calls
+//! `@sample(ptr, [2 x f32])`, returning the `[4 x f32]` color of a texture
+//! at a point; both are declared.
+
+use super::{decode_outputs, Instruction, Operation, Output, Primitive};
+use crate::aratar::Result;
+use crate::ir::{self, BinaryOp, Builder, InstKind, Signature, Type, Value};
+
+/// Lower Yote bytecode to an IR module.  The program must output both a
+/// position and a color.
+pub fn lower(bytecode: &[u8]) -> Result<ir::Module> {
+    let program = decode_outputs(bytecode)?;
+    let variables = program.variables();
+    let params: Vec<_> = variables.iter()
+        .filter_map(|instruction| match *instruction {
+            Instruction::Uniform(ty) | Instruction::Attribute(ty) => {
+                Some(ir_type(ty))
+            }
+            _ => None,
+        })
+        .collect();
+    let ret = Type::Struct(vec![vec4(), vec4()]);
+    let mut function = ir::Function::new("shader",
+        Signature::new(ret.clone(), params));
+
+    let mut builder = Builder::new(&mut function);
+    let needed: Vec<_> = program.dependencies(Output::Position).iter()
+        .zip(program.dependencies(Output::Color))
+        .map(|(position, color)| *position || color)
+        .collect();
+    let mut values: Vec<Value> = Vec::new();
+    let mut params = 0;
+    for (number, instruction) in variables.iter().enumerate() {
+        let value = match *instruction {
+            Instruction::Uniform(_) | Instruction::Attribute(_) => {
+                params += 1;
+                Value::Param(params - 1)
+            }
+            Instruction::Call(..) if !needed[number] => {
+                Value::Undef(ir_type(program.types[number]))
+            }
+            Instruction::Call(ty, op, a, b) => {
+                let (a, b) = (usize::from(a), usize::from(b));
+                let a = (values[a].clone(), program.types[a]);
+                let b = (values[b].clone(), program.types[b]);
+                call(&mut builder, ty, op, a, b)
+            }
+            Instruction::Output(..) => unreachable!(),
+        };
+        values.push(value);
+    }
+    let mut result = Value::Undef(ret);
+    for (index, &output) in [Output::Position, Output::Color].iter()
+        .enumerate()
+    {
+        let a = usize::from(program.output(output).unwrap());
+        let value = match program.types[a] {
+            Primitive::Vec3 => {
+                let mut vector = Value::Undef(vec4());
+                for i in 0..3 {
+                    let item = builder.extract_value(values[a].clone(), i);
+                    vector = builder.insert_value(vector, item, i);
+                }
+                let one = Value::float(Type::F32, 1.0);
+                builder.insert_value(vector, one, 3)
+            }
+            _ => values[a].clone(),
+        };
+        result = builder.insert_value(result, value, index as u32);
+    }
+    builder.ret(Some(result));
+
+    let mut module = ir::Module::new();
+    if calls(&function, "floorf") {
+        module.declare("floorf", Signature::new(Type::F32, vec![Type::F32]));
+    }
+    if calls(&function, "sample") {
+        let params = vec![Type::Ptr, ir_type(Primitive::Vec2)];
+        module.declare("sample", Signature::new(vec4(), params));
+    }
+    module.functions.push(function);
+    Ok(module)
+}
+
+fn vec4() -> Type {
+    ir_type(Primitive::Vec4)
+}
+
+fn ir_type(ty: Primitive) -> Type {
+    match ty {
+        Primitive::Vec1 => Type::F32,
+        Primitive::View => Type::Array(Box::new(Type::F32), 16),
+        Primitive::Int1 => Type::I32,
+        Primitive::Bmap => Type::Ptr,
+        ty => Type::Array(Box::new(Type::F32), ty.width().unwrap() as u64),
+    }
+}
+
+// The number of items of a value of a type, 1 for a scalar.
+fn items(ty: Primitive) -> u32 {
+    match ty {
+        Primitive::View => 16,
+        ty => ty.width().unwrap_or(1) as u32,
+    }
+}
+
+// Whether a function calls a function by name.
+fn calls(function: &ir::Function, name: &str) -> bool {
+    function.insts.iter().any(|inst| match inst.kind {
+        InstKind::Call(_, Value::Global(ref callee), _) => callee == name,
+        _ => false,
+    })
+}
+
+// Item `i` of a value, a scalar being the same for every item.
+fn item(builder: &mut Builder, (value, ty): &(Value, Primitive), i: u32)
+    -> Value
+{
+    match items(*ty) {
+        1 => value.clone(),
+        _ => builder.extract_value(value.clone(), i),
+    }
+}
+
+// A value of type `ty` from its items.
+fn aggregate(builder: &mut Builder, ty: Primitive, items: Vec<Value>)
+    -> Value
+{
+    if let [item] = items.as_slice() {
+        return item.clone();
+    }
+    let mut value = Value::Undef(ir_type(ty));
+    for (i, item) in items.into_iter().enumerate() {
+        value = builder.insert_value(value, item, i as u32);
+    }
+    value
+}
+
+// The sum of the products of items `a(k)` and `b(k)` for `k` in 0..4.
+fn dot(builder: &mut Builder, a: &(Value, Primitive), b: &(Value, Primitive),
+    index_a: impl Fn(u32) -> u32, index_b: impl Fn(u32) -> u32) -> Value
+{
+    let mut sum = None;
+    for k in 0..4 {
+        let x = item(builder, a, index_a(k));
+        let y = item(builder, b, index_b(k));
+        let product = builder.binary(BinaryOp::FMul, x, y);
+        sum = Some(match sum {
+            None => product,
+            Some(sum) => builder.binary(BinaryOp::FAdd, sum, product),
+        });
+    }
+    sum.unwrap()
+}
+
+// Lower a call of an operation on `a` and `b`, with a result of type `ty`.
+fn call(builder: &mut Builder, ty: Primitive, op: Operation,
+    a: (Value, Primitive), b: (Value, Primitive)) -> Value
+{
+    use Primitive::{View, Vec4};
+
+    match (op, a.1, b.1) {
+        (Operation::Sample, _, _) => {
+            let params = vec![Type::Ptr, ir_type(Primitive::Vec2)];
+            let sig = Signature::new(vec4(), params);
+            return builder.call(sig, Value::global("sample"), vec![a.0, b.0]);
+        }
+        // Matrices are column-major: item `row` of column `col` is at
+        // `col * 4 + row`.
+        (Operation::Mul, View, View) => {
+            let mut result = Vec::new();
+            for i in 0..16 {
+                let (col, row) = (i / 4, i % 4);
+                result.push(dot(builder, &a, &b, |k| k * 4 + row,
+                    |k| col * 4 + k));
+            }
+            return aggregate(builder, ty, result);
+        }
+        (Operation::Mul, View, Vec4) => {
+            let mut result = Vec::new();
+            for row in 0..4 {
+                result.push(dot(builder, &a, &b, |k| k * 4 + row, |k| k));
+            }
+            return aggregate(builder, ty, result);
+        }
+        _ => {}
+    }
+    // Outputs can't be computed from `Int1`s, so only floats are.
+    let mut result = Vec::new();
+    for i in 0..items(ty) {
+        let x = item(builder, &a, i);
+        let y = item(builder, &b, i);
+        let value = match op {
+            Operation::Add => builder.binary(BinaryOp::FAdd, x, y),
+            Operation::Sub => builder.binary(BinaryOp::FSub, x, y),
+            Operation::Mul => builder.binary(BinaryOp::FMul, x, y),
+            Operation::Div => builder.binary(BinaryOp::FDiv, x, y),
+            _ => {
+                // `x - y * floor(x / y)`
+                let quotient = builder.binary(BinaryOp::FDiv, x.clone(),
+                    y.clone());
+                let sig = Signature::new(Type::F32, vec![Type::F32]);
+                let floor = builder.call(sig, Value::global("floorf"),
+                    vec![quotient]);
+                let product = builder.binary(BinaryOp::FMul, y, floor);
+                builder.binary(BinaryOp::FSub, x, product)
+            }
+        };
+        result.push(value);
+    }
+    aggregate(builder, ty, result)
+}
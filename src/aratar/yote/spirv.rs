@@ -0,0 +1,890 @@
+// SPIR-V
+//
+//! Translates Yote bytecode to a SPIR-V 1.0 module for Vulkan, and
+//! disassembles such modules to text.
+//!
+//! The module has two entry points, the vertex shader `vs_main` computing
+//! the position and the fragment shader `fs_main` the color.  Each uniform
+//! is a variable of its own in descriptor set 0, in a block of one member
+//! but for a `Bmap`, which is an image and a separate sampler.  Uniforms
+//! are bound as given by [`Program::bindings`], and attributes and
+//! varyings have the same locations as in WGSL, so both translations of a
+//! program can be used with the same pipeline layout.  Variables are named
+//! in the debug information as in WGSL.
+//!
+//! The disassembly has the layout of `spirv-dis`, naming types, constants
+//! and named variables rather than numbering them.
+
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+use super::{
+    decode_outputs, Instruction, Operation, Output, Primitive, Program,
+};
+use crate::aratar::Result;
+use crate::{Diagnostic, Span};
+
+/// The first word of a SPIR-V module
+pub const MAGIC: u32 = 0x0723_0203;
+
+// SPIR-V 1.0
+const VERSION: u32 = 0x0001_0000;
+
+const OP_NAME: u16 = 5;
+const OP_MEMORY_MODEL: u16 = 14;
+const OP_ENTRY_POINT: u16 = 15;
+const OP_EXECUTION_MODE: u16 = 16;
+const OP_CAPABILITY: u16 = 17;
+const OP_TYPE_VOID: u16 = 19;
+const OP_TYPE_INT: u16 = 21;
+const OP_TYPE_FLOAT: u16 = 22;
+const OP_TYPE_VECTOR: u16 = 23;
+const OP_TYPE_MATRIX: u16 = 24;
+const OP_TYPE_IMAGE: u16 = 25;
+const OP_TYPE_SAMPLER: u16 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u16 = 27;
+const OP_TYPE_STRUCT: u16 = 30;
+const OP_TYPE_POINTER: u16 = 32;
+const OP_TYPE_FUNCTION: u16 = 33;
+const OP_CONSTANT: u16 = 43;
+const OP_FUNCTION: u16 = 54;
+const OP_FUNCTION_END: u16 = 56;
+const OP_VARIABLE: u16 = 59;
+const OP_LOAD: u16 = 61;
+const OP_STORE: u16 = 62;
+const OP_ACCESS_CHAIN: u16 = 65;
+const OP_DECORATE: u16 = 71;
+const OP_MEMBER_DECORATE: u16 = 72;
+const OP_COMPOSITE_CONSTRUCT: u16 = 80;
+const OP_COMPOSITE_EXTRACT: u16 = 81;
+const OP_SAMPLED_IMAGE: u16 = 86;
+const OP_IMAGE_SAMPLE_IMPLICIT_LOD: u16 = 87;
+const OP_IMAGE_SAMPLE_EXPLICIT_LOD: u16 = 88;
+const OP_F_ADD: u16 = 129;
+const OP_F_SUB: u16 = 131;
+const OP_F_MUL: u16 = 133;
+const OP_F_DIV: u16 = 136;
+const OP_F_MOD: u16 = 141;
+const OP_VECTOR_TIMES_SCALAR: u16 = 142;
+const OP_MATRIX_TIMES_SCALAR: u16 = 143;
+const OP_MATRIX_TIMES_VECTOR: u16 = 145;
+const OP_MATRIX_TIMES_MATRIX: u16 = 146;
+const OP_LABEL: u16 = 248;
+const OP_RETURN: u16 = 253;
+
+const CAPABILITIES: &[(u32, &str)] = &[(1, "Shader")];
+const ADDRESSING_MODELS: &[(u32, &str)] = &[(0, "Logical")];
+const MEMORY_MODELS: &[(u32, &str)] = &[(1, "GLSL450")];
+const EXECUTION_MODELS: &[(u32, &str)] = &[(0, "Vertex"), (4, "Fragment")];
+const EXECUTION_MODES: &[(u32, &str)] = &[(7, "OriginUpperLeft")];
+const STORAGE_CLASSES: &[(u32, &str)] = &[
+    (0, "UniformConstant"),
+    (1, "Input"),
+    (2, "Uniform"),
+    (3, "Output"),
+    (7, "Function"),
+];
+const DIMS: &[(u32, &str)] = &[(1, "2D")];
+const IMAGE_FORMATS: &[(u32, &str)] = &[(0, "Unknown")];
+const FUNCTION_CONTROLS: &[(u32, &str)] = &[(0, "None")];
+const IMAGE_OPERANDS: &[(u32, &str)] = &[(2, "Lod")];
+const DECORATIONS: &[(u32, &str)] = &[
+    (2, "Block"),
+    (5, "ColMajor"),
+    (7, "MatrixStride"),
+    (11, "BuiltIn"),
+    (30, "Location"),
+    (33, "Binding"),
+    (34, "DescriptorSet"),
+    (35, "Offset"),
+];
+const BUILT_INS: &[(u32, &str)] = &[(0, "Position")];
+
+const SHADER: u32 = 1;
+const LOGICAL: u32 = 0;
+const GLSL450: u32 = 1;
+const VERTEX: u32 = 0;
+const FRAGMENT: u32 = 4;
+const ORIGIN_UPPER_LEFT: u32 = 7;
+const LOD: u32 = 2;
+const POSITION: u32 = 0;
+
+const UNIFORM_CONSTANT: u32 = 0;
+const INPUT: u32 = 1;
+const UNIFORM: u32 = 2;
+const OUTPUT: u32 = 3;
+
+const BLOCK: u32 = 2;
+const COL_MAJOR: u32 = 5;
+const MATRIX_STRIDE: u32 = 7;
+const BUILT_IN: u32 = 11;
+const LOCATION: u32 = 30;
+const BINDING: u32 = 33;
+const DESCRIPTOR_SET: u32 = 34;
+const OFFSET: u32 = 35;
+
+// The operands of an instruction, as laid out in the disassembler
+#[derive(Debug, Clone, Copy)]
+enum Operand {
+    ResultType,
+    Result,
+    Id,
+    Literal,
+    String,
+    Enum(&'static [(u32, &'static str)]),
+    // A decoration and its literals
+    Decoration,
+    // The rest of the operands
+    Ids,
+    Literals,
+}
+
+const INSTRUCTIONS: &[(u16, &str, &[Operand])] = {
+    use Operand::*;
+
+    &[
+        (OP_NAME, "OpName", &[Id, String]),
+        (OP_MEMORY_MODEL, "OpMemoryModel",
+            &[Enum(ADDRESSING_MODELS), Enum(MEMORY_MODELS)]),
+        (OP_ENTRY_POINT, "OpEntryPoint",
+            &[Enum(EXECUTION_MODELS), Id, String, Ids]),
+        (OP_EXECUTION_MODE, "OpExecutionMode",
+            &[Id, Enum(EXECUTION_MODES), Literals]),
+        (OP_CAPABILITY, "OpCapability", &[Enum(CAPABILITIES)]),
+        (OP_TYPE_VOID, "OpTypeVoid", &[Result]),
+        (OP_TYPE_INT, "OpTypeInt", &[Result, Literal, Literal]),
+        (OP_TYPE_FLOAT, "OpTypeFloat", &[Result, Literal]),
+        (OP_TYPE_VECTOR, "OpTypeVector", &[Result, Id, Literal]),
+        (OP_TYPE_MATRIX, "OpTypeMatrix", &[Result, Id, Literal]),
+        (OP_TYPE_IMAGE, "OpTypeImage", &[
+            Result, Id, Enum(DIMS), Literal, Literal, Literal, Literal,
+            Enum(IMAGE_FORMATS),
+        ]),
+        (OP_TYPE_SAMPLER, "OpTypeSampler", &[Result]),
+        (OP_TYPE_SAMPLED_IMAGE, "OpTypeSampledImage", &[Result, Id]),
+        (OP_TYPE_STRUCT, "OpTypeStruct", &[Result, Ids]),
+        (OP_TYPE_POINTER, "OpTypePointer",
+            &[Result, Enum(STORAGE_CLASSES), Id]),
+        (OP_TYPE_FUNCTION, "OpTypeFunction", &[Result, Id, Ids]),
+        (OP_CONSTANT, "OpConstant", &[ResultType, Result, Literal]),
+        (OP_FUNCTION, "OpFunction",
+            &[ResultType, Result, Enum(FUNCTION_CONTROLS), Id]),
+        (OP_FUNCTION_END, "OpFunctionEnd", &[]),
+        (OP_VARIABLE, "OpVariable",
+            &[ResultType, Result, Enum(STORAGE_CLASSES)]),
+        (OP_LOAD, "OpLoad", &[ResultType, Result, Id]),
+        (OP_STORE, "OpStore", &[Id, Id]),
+        (OP_ACCESS_CHAIN, "OpAccessChain", &[ResultType, Result, Id, Ids]),
+        (OP_DECORATE, "OpDecorate", &[Id, Decoration]),
+        (OP_MEMBER_DECORATE, "OpMemberDecorate", &[Id, Literal, Decoration]),
+        (OP_COMPOSITE_CONSTRUCT, "OpCompositeConstruct",
+            &[ResultType, Result, Ids]),
+        (OP_COMPOSITE_EXTRACT, "OpCompositeExtract",
+            &[ResultType, Result, Id, Literals]),
+        (OP_SAMPLED_IMAGE, "OpSampledImage", &[ResultType, Result, Id, Id]),
+        (OP_IMAGE_SAMPLE_IMPLICIT_LOD, "OpImageSampleImplicitLod",
+            &[ResultType, Result, Id, Id]),
+        (OP_IMAGE_SAMPLE_EXPLICIT_LOD, "OpImageSampleExplicitLod",
+            &[ResultType, Result, Id, Id, Enum(IMAGE_OPERANDS), Ids]),
+        (OP_F_ADD, "OpFAdd", &[ResultType, Result, Id, Id]),
+        (OP_F_SUB, "OpFSub", &[ResultType, Result, Id, Id]),
+        (OP_F_MUL, "OpFMul", &[ResultType, Result, Id, Id]),
+        (OP_F_DIV, "OpFDiv", &[ResultType, Result, Id, Id]),
+        (OP_F_MOD, "OpFMod", &[ResultType, Result, Id, Id]),
+        (OP_VECTOR_TIMES_SCALAR, "OpVectorTimesScalar",
+            &[ResultType, Result, Id, Id]),
+        (OP_MATRIX_TIMES_SCALAR, "OpMatrixTimesScalar",
+            &[ResultType, Result, Id, Id]),
+        (OP_MATRIX_TIMES_VECTOR, "OpMatrixTimesVector",
+            &[ResultType, Result, Id, Id]),
+        (OP_MATRIX_TIMES_MATRIX, "OpMatrixTimesMatrix",
+            &[ResultType, Result, Id, Id]),
+        (OP_LABEL, "OpLabel", &[Result]),
+        (OP_RETURN, "OpReturn", &[]),
+    ]
+};
+
+// A type of the module
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Type {
+    Void,
+    Float,
+    Int,
+    Vector(u32),
+    Matrix,
+    Image,
+    Sampler,
+    SampledImage,
+    // A block holding a uniform of the type
+    Block(Primitive),
+    Pointer(u32, Box<Type>),
+    Function,
+}
+
+impl Type {
+    fn new(ty: Primitive) -> Type {
+        match ty {
+            Primitive::Vec1 => Type::Float,
+            Primitive::Vec2 => Type::Vector(2),
+            Primitive::Vec3 => Type::Vector(3),
+            Primitive::Vec4 => Type::Vector(4),
+            Primitive::View => Type::Matrix,
+            Primitive::Int1 => Type::Int,
+            Primitive::Bmap => Type::Image,
+        }
+    }
+}
+
+// Append an instruction to `section`.
+fn push(section: &mut Vec<u32>, opcode: u16, operands: &[u32]) {
+    let len = u32::try_from(operands.len() + 1).unwrap();
+    section.push(len << 16 | u32::from(opcode));
+    section.extend_from_slice(operands);
+}
+
+// A literal string, as words.
+fn string(text: &str) -> Vec<u32> {
+    let mut bytes = text.as_bytes().to_vec();
+    bytes.resize(bytes.len() / 4 * 4 + 4, 0);
+    bytes
+        .chunks(4)
+        .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+        .collect()
+}
+
+// A module being written, by section
+#[derive(Default)]
+struct Module {
+    bound: u32,
+    entry_points: Vec<u32>,
+    names: Vec<u32>,
+    annotations: Vec<u32>,
+    // Types, constants and variables
+    globals: Vec<u32>,
+    functions: Vec<u32>,
+    types: HashMap<Type, u32>,
+    // Constants by type and value
+    constants: HashMap<(u32, u32), u32>,
+}
+
+impl Module {
+    fn id(&mut self) -> u32 {
+        self.bound += 1;
+        self.bound
+    }
+
+    fn ty(&mut self, ty: Type) -> u32 {
+        if let Some(&id) = self.types.get(&ty) {
+            return id;
+        }
+        let operands = match &ty {
+            Type::Void | Type::Sampler => vec![],
+            Type::Float => vec![32],
+            Type::Int => vec![32, 1],
+            Type::Vector(width) => vec![self.ty(Type::Float), *width],
+            Type::Matrix => vec![self.ty(Type::Vector(4)), 4],
+            Type::Image => vec![self.ty(Type::Float), 1, 0, 0, 0, 1, 0],
+            Type::SampledImage => vec![self.ty(Type::Image)],
+            Type::Block(member) => vec![self.ty(Type::new(*member))],
+            Type::Pointer(storage, pointee) => {
+                vec![*storage, self.ty((**pointee).clone())]
+            }
+            Type::Function => vec![self.ty(Type::Void)],
+        };
+        let id = self.id();
+        let opcode = match ty {
+            Type::Void => OP_TYPE_VOID,
+            Type::Float => OP_TYPE_FLOAT,
+            Type::Int => OP_TYPE_INT,
+            Type::Vector(_) => OP_TYPE_VECTOR,
+            Type::Matrix => OP_TYPE_MATRIX,
+            Type::Image => OP_TYPE_IMAGE,
+            Type::Sampler => OP_TYPE_SAMPLER,
+            Type::SampledImage => OP_TYPE_SAMPLED_IMAGE,
+            Type::Block(_) => OP_TYPE_STRUCT,
+            Type::Pointer(..) => OP_TYPE_POINTER,
+            Type::Function => OP_TYPE_FUNCTION,
+        };
+        push(&mut self.globals, opcode, &[&[id], &operands[..]].concat());
+        if let Type::Block(member) = ty {
+            push(&mut self.annotations, OP_DECORATE, &[id, BLOCK]);
+            let decorate = OP_MEMBER_DECORATE;
+            push(&mut self.annotations, decorate, &[id, 0, OFFSET, 0]);
+            if member == Primitive::View {
+                push(&mut self.annotations, decorate, &[id, 0, COL_MAJOR]);
+                push(&mut self.annotations, decorate,
+                    &[id, 0, MATRIX_STRIDE, 16]);
+            }
+        }
+        self.types.insert(ty, id);
+        id
+    }
+
+    fn constant(&mut self, ty: Type, bits: u32) -> u32 {
+        let ty = self.ty(ty);
+        if let Some(&id) = self.constants.get(&(ty, bits)) {
+            return id;
+        }
+        let id = self.id();
+        push(&mut self.globals, OP_CONSTANT, &[ty, id, bits]);
+        self.constants.insert((ty, bits), id);
+        id
+    }
+
+    // A variable of type `ty` in `storage`, named `name`.
+    fn variable(&mut self, ty: Type, storage: u32, name: &str) -> u32 {
+        let pointer = self.ty(Type::Pointer(storage, Box::new(ty)));
+        let id = self.id();
+        push(&mut self.globals, OP_VARIABLE, &[pointer, id, storage]);
+        self.name(id, name);
+        id
+    }
+
+    fn name(&mut self, id: u32, name: &str) {
+        push(&mut self.names, OP_NAME, &[&[id], &string(name)[..]].concat());
+    }
+
+    fn decorate(&mut self, id: u32, decoration: &[u32]) {
+        push(&mut self.annotations, OP_DECORATE,
+            &[&[id], decoration].concat());
+    }
+
+    // Append an instruction with a result of type `ty` to the functions,
+    // returning the result.
+    fn op(&mut self, opcode: u16, ty: Type, operands: &[u32]) -> u32 {
+        let ty = self.ty(ty);
+        let id = self.id();
+        push(&mut self.functions, opcode, &[&[ty, id], operands].concat());
+        id
+    }
+
+    fn words(self) -> Vec<u32> {
+        let mut words = vec![MAGIC, VERSION, 0, self.bound + 1, 0];
+        push(&mut words, OP_CAPABILITY, &[SHADER]);
+        push(&mut words, OP_MEMORY_MODEL, &[LOGICAL, GLSL450]);
+        words.extend(self.entry_points);
+        words.extend(self.names);
+        words.extend(self.annotations);
+        words.extend(self.globals);
+        words.extend(self.functions);
+        words
+    }
+}
+
+// The variables of the module for a Yote variable
+#[derive(Debug, Clone, Copy)]
+enum Input {
+    // A uniform in a block
+    Block(u32),
+    // The image and sampler of a `Bmap`
+    Bmap(u32, u32),
+    // An attribute, and the output and input of its varying
+    Attribute(u32, Option<(u32, u32)>),
+    Call,
+}
+
+/// Translate Yote bytecode to a SPIR-V module, as words.  The program must
+/// output both a position and a color.
+pub fn translate(bytecode: &[u8]) -> Result<Vec<u32>> {
+    let program = decode_outputs(bytecode)?;
+    let mut shader = Shader {
+        program: &program,
+        variables: program.variables(),
+        module: Module::default(),
+        inputs: Vec::new(),
+        values: Vec::new(),
+        fragment: false,
+    };
+    Ok(shader.module())
+}
+
+// A program being translated
+struct Shader<'a> {
+    program: &'a Program,
+    variables: Vec<Instruction>,
+    module: Module,
+    inputs: Vec<Input>,
+    // The values of the variables computed by the function being written
+    values: Vec<Option<u32>>,
+    fragment: bool,
+}
+
+impl Shader<'_> {
+    fn module(&mut self) -> Vec<u32> {
+        let vertex_main = self.module.id();
+        let fragment_main = self.module.id();
+        self.module.name(vertex_main, "vs_main");
+        self.module.name(fragment_main, "fs_main");
+
+        // The interface variables of each entry point
+        let mut vertex = Vec::new();
+        let mut fragment = Vec::new();
+        let needed = self.program.dependencies(Output::Color);
+        let bindings = self.program.bindings();
+        let mut attributes = 0;
+        let mut varyings = 0;
+        for n in 0..self.variables.len() {
+            let module = &mut self.module;
+            let input = match self.variables[n] {
+                Instruction::Uniform(Primitive::Bmap) => {
+                    let binding = bindings[n].unwrap();
+                    let image = module.variable(Type::Image, UNIFORM_CONSTANT,
+                        &format!("u{}", n));
+                    let sampler = module.variable(Type::Sampler,
+                        UNIFORM_CONSTANT, &format!("s{}", n));
+                    module.decorate(image, &[DESCRIPTOR_SET, 0]);
+                    module.decorate(image, &[BINDING, binding]);
+                    module.decorate(sampler, &[DESCRIPTOR_SET, 0]);
+                    module.decorate(sampler, &[BINDING, binding + 1]);
+                    Input::Bmap(image, sampler)
+                }
+                Instruction::Uniform(ty) => {
+                    let block = module.variable(Type::Block(ty), UNIFORM,
+                        &format!("u{}", n));
+                    module.decorate(block, &[DESCRIPTOR_SET, 0]);
+                    module.decorate(block, &[BINDING, bindings[n].unwrap()]);
+                    Input::Block(block)
+                }
+                Instruction::Attribute(ty) => {
+                    let attribute = module.variable(Type::new(ty), INPUT,
+                        &format!("a{}", n));
+                    module.decorate(attribute, &[LOCATION, attributes]);
+                    attributes += 1;
+                    vertex.push(attribute);
+                    let varying = match needed[n] {
+                        true => {
+                            let name = format!("v{}", n);
+                            let output = module.variable(Type::new(ty),
+                                OUTPUT, &name);
+                            let input = module.variable(Type::new(ty), INPUT,
+                                &name);
+                            module.decorate(output, &[LOCATION, varyings]);
+                            module.decorate(input, &[LOCATION, varyings]);
+                            varyings += 1;
+                            vertex.push(output);
+                            fragment.push(input);
+                            Some((output, input))
+                        }
+                        false => None,
+                    };
+                    Input::Attribute(attribute, varying)
+                }
+                _ => Input::Call,
+            };
+            self.inputs.push(input);
+        }
+        let position = self.module.variable(Type::Vector(4), OUTPUT,
+            "position");
+        self.module.decorate(position, &[BUILT_IN, POSITION]);
+        vertex.push(position);
+        let color = self.module.variable(Type::Vector(4), OUTPUT, "color");
+        self.module.decorate(color, &[LOCATION, 0]);
+        fragment.push(color);
+
+        self.function(vertex_main, false, position);
+        self.function(fragment_main, true, color);
+
+        let module = &mut self.module;
+        for &(model, main, name, interface) in &[
+            (VERTEX, vertex_main, "vs_main", &vertex),
+            (FRAGMENT, fragment_main, "fs_main", &fragment),
+        ] {
+            let operands = [&[model, main], &string(name)[..], interface];
+            push(&mut module.entry_points, OP_ENTRY_POINT,
+                &operands.concat());
+        }
+        push(&mut module.entry_points, OP_EXECUTION_MODE,
+            &[fragment_main, ORIGIN_UPPER_LEFT]);
+        std::mem::take(module).words()
+    }
+
+    // Write the function `id` of the vertex or fragment shader, storing its
+    // output in the variable `output`.
+    fn function(&mut self, id: u32, fragment: bool, output: u32) {
+        self.fragment = fragment;
+        self.values = vec![None; self.variables.len()];
+        let module = &mut self.module;
+        let void = module.ty(Type::Void);
+        let ty = module.ty(Type::Function);
+        push(&mut module.functions, OP_FUNCTION, &[void, id, 0, ty]);
+        let label = module.id();
+        push(&mut module.functions, OP_LABEL, &[label]);
+
+        let output_of = match fragment {
+            true => Output::Color,
+            false => Output::Position,
+        };
+        let needed = self.program.dependencies(output_of);
+        for (n, needed) in needed.into_iter().enumerate() {
+            if let (true, Instruction::Call(ty, op, a, b)) =
+                (needed, self.variables[n])
+            {
+                let value = self.call(ty, op, a, b);
+                self.values[n] = Some(value);
+            }
+        }
+        let n = usize::from(self.program.output(output_of).unwrap());
+        let mut value = self.value(n);
+        if self.program.types[n] == Primitive::Vec3 {
+            let one = self.module.constant(Type::Float, 1f32.to_bits());
+            value = self.module.op(OP_COMPOSITE_CONSTRUCT, Type::Vector(4),
+                &[value, one]);
+        }
+        push(&mut self.module.functions, OP_STORE, &[output, value]);
+        if !fragment {
+            for n in 0..self.variables.len() {
+                if let Input::Attribute(_, Some((varying, _))) = self.inputs[n]
+                {
+                    let value = self.value(n);
+                    push(&mut self.module.functions, OP_STORE,
+                        &[varying, value]);
+                }
+            }
+        }
+        push(&mut self.module.functions, OP_RETURN, &[]);
+        push(&mut self.module.functions, OP_FUNCTION_END, &[]);
+    }
+
+    // The value of variable `n`, loading it if it's an input.
+    fn value(&mut self, n: usize) -> u32 {
+        if let Some(value) = self.values[n] {
+            return value;
+        }
+        let ty = self.program.types[n];
+        let module = &mut self.module;
+        let value = match self.inputs[n] {
+            Input::Block(block) => {
+                let pointer = Type::Pointer(UNIFORM, Box::new(Type::new(ty)));
+                let zero = module.constant(Type::Int, 0);
+                let member = module.op(OP_ACCESS_CHAIN, pointer,
+                    &[block, zero]);
+                module.op(OP_LOAD, Type::new(ty), &[member])
+            }
+            Input::Bmap(image, sampler) => {
+                let image = module.op(OP_LOAD, Type::Image, &[image]);
+                let sampler = module.op(OP_LOAD, Type::Sampler, &[sampler]);
+                module.op(OP_SAMPLED_IMAGE, Type::SampledImage,
+                    &[image, sampler])
+            }
+            Input::Attribute(_, Some((_, varying))) if self.fragment => {
+                module.op(OP_LOAD, Type::new(ty), &[varying])
+            }
+            Input::Attribute(attribute, _) => {
+                module.op(OP_LOAD, Type::new(ty), &[attribute])
+            }
+            Input::Call => unreachable!("calls are computed in order"),
+        };
+        self.values[n] = Some(value);
+        value
+    }
+
+    // The result of operation `op` of variables `a` and `b`, a `ty`.
+    fn call(&mut self, ty: Primitive, op: Operation, a: u8, b: u8) -> u32 {
+        use Primitive::*;
+
+        let (a, b) = (usize::from(a), usize::from(b));
+        let (a_ty, b_ty) = (self.program.types[a], self.program.types[b]);
+        let (x, y) = (self.value(a), self.value(b));
+        let module = &mut self.module;
+        let result = Type::new(ty);
+        let opcode = match op {
+            Operation::Add => OP_F_ADD,
+            Operation::Sub => OP_F_SUB,
+            Operation::Mul => OP_F_MUL,
+            Operation::Div => OP_F_DIV,
+            Operation::Mod => OP_F_MOD,
+            Operation::Sample if self.fragment => {
+                return module.op(OP_IMAGE_SAMPLE_IMPLICIT_LOD, result,
+                    &[x, y]);
+            }
+            // Vertex shaders have no derivatives to choose a level of
+            // detail with.
+            Operation::Sample => {
+                let zero = module.constant(Type::Float, 0f32.to_bits());
+                return module.op(OP_IMAGE_SAMPLE_EXPLICIT_LOD, result,
+                    &[x, y, LOD, zero]);
+            }
+        };
+        let (opcode, operands) = match (op, a_ty, b_ty) {
+            (Operation::Mul, View, Vec4) => (OP_MATRIX_TIMES_VECTOR, [x, y]),
+            (Operation::Mul, View, View) => (OP_MATRIX_TIMES_MATRIX, [x, y]),
+            (Operation::Mul, View, Vec1) => (OP_MATRIX_TIMES_SCALAR, [x, y]),
+            (Operation::Mul, Vec1, View) => (OP_MATRIX_TIMES_SCALAR, [y, x]),
+            (Operation::Mul, _, Vec1) if ty != Vec1 => {
+                (OP_VECTOR_TIMES_SCALAR, [x, y])
+            }
+            (Operation::Mul, Vec1, _) if ty != Vec1 => {
+                (OP_VECTOR_TIMES_SCALAR, [y, x])
+            }
+            // Matrices are added, subtracted and divided column by column.
+            _ if ty == View => {
+                let x = module.columns(x, a_ty);
+                let y = module.columns(y, b_ty);
+                let columns: Vec<_> = (0..4)
+                    .map(|i| module.op(opcode, Type::Vector(4), &[x[i], y[i]]))
+                    .collect();
+                return module.op(OP_COMPOSITE_CONSTRUCT, result, &columns);
+            }
+            _ => {
+                let x = module.splat(x, a_ty, ty);
+                let y = module.splat(y, b_ty, ty);
+                (opcode, [x, y])
+            }
+        };
+        module.op(opcode, result, &operands)
+    }
+}
+
+impl Module {
+    // The columns of `value`, a `View` or a `Vec1` for each column.
+    fn columns(&mut self, value: u32, ty: Primitive) -> Vec<u32> {
+        match ty {
+            Primitive::View => (0..4)
+                .map(|i| self.op(OP_COMPOSITE_EXTRACT, Type::Vector(4),
+                    &[value, i]))
+                .collect(),
+            _ => vec![self.splat(value, ty, Primitive::Vec4); 4],
+        }
+    }
+
+    // `value` of type `ty` as a `to`, a vector of it if it's a `Vec1`.
+    fn splat(&mut self, value: u32, ty: Primitive, to: Primitive) -> u32 {
+        match (ty, to.width()) {
+            (Primitive::Vec1, Some(width)) if width > 1 => {
+                self.op(OP_COMPOSITE_CONSTRUCT, Type::new(to),
+                    &vec![value; width])
+            }
+            _ => value,
+        }
+    }
+}
+
+// A decoded operand
+#[derive(Debug, Clone)]
+enum Part {
+    Id(u32),
+    Literal(u32),
+    String(String),
+    Enum(&'static [(u32, &'static str)], u32),
+}
+
+// A decoded instruction
+struct Parsed {
+    opcode: u16,
+    name: &'static str,
+    result: Option<u32>,
+    parts: Vec<Part>,
+}
+
+// Decode the operands of an instruction laid out as `layout`, if they are.
+fn parse(layout: &[Operand], operands: &[u32])
+    -> Option<(Option<u32>, Vec<Part>)>
+{
+    let mut result = None;
+    let mut parts = Vec::new();
+    let mut at = 0;
+    for kind in layout {
+        match *kind {
+            Operand::Result => result = Some(*operands.get(at)?),
+            Operand::ResultType | Operand::Id => {
+                parts.push(Part::Id(*operands.get(at)?));
+            }
+            Operand::Literal => parts.push(Part::Literal(*operands.get(at)?)),
+            Operand::String => {
+                let len = operands[at..]
+                    .iter()
+                    .position(|word| word.to_le_bytes().contains(&0))?;
+                let bytes: Vec<u8> = operands[at..=at + len]
+                    .iter()
+                    .flat_map(|word| word.to_le_bytes())
+                    .take_while(|&byte| byte != 0)
+                    .collect();
+                parts.push(Part::String(String::from_utf8(bytes).ok()?));
+                at += len;
+            }
+            Operand::Enum(names) => {
+                parts.push(Part::Enum(names, *operands.get(at)?));
+            }
+            Operand::Decoration => {
+                let decoration = *operands.get(at)?;
+                parts.push(Part::Enum(DECORATIONS, decoration));
+                if decoration == BUILT_IN {
+                    at += 1;
+                    parts.push(Part::Enum(BUILT_INS, *operands.get(at)?));
+                } else {
+                    parts.extend(operands[at + 1..].iter()
+                        .map(|&word| Part::Literal(word)));
+                    at = operands.len() - 1;
+                }
+            }
+            Operand::Ids | Operand::Literals => {
+                parts.extend(operands[at..].iter().map(|&word| match kind {
+                    Operand::Ids => Part::Id(word),
+                    _ => Part::Literal(word),
+                }));
+                at = operands.len();
+                continue;
+            }
+        }
+        at += 1;
+    }
+    match at == operands.len() {
+        true => Some((result, parts)),
+        false => None,
+    }
+}
+
+// The name of `value` in `names`, or its number.
+fn enum_name(names: &[(u32, &str)], value: u32) -> String {
+    match names.iter().find(|(number, _)| *number == value) {
+        Some((_, name)) => name.to_string(),
+        None => value.to_string(),
+    }
+}
+
+/// Disassemble a SPIR-V module to text.  Only the instructions written by
+/// [`translate`] are understood.  The spans of errors are word offsets in
+/// the module.
+pub fn disassemble(words: &[u32]) -> Result<String> {
+    let error = |at: usize, message: String| {
+        Diagnostic::new(Span::new(at, at + 1), message)
+    };
+    if words.len() < 5 || words[0] != MAGIC {
+        return Err(Diagnostic::new(Span::new(0, words.len().min(5)),
+            "not a SPIR-V module".to_string()));
+    }
+    let mut instructions = Vec::new();
+    let mut at = 5;
+    while at < words.len() {
+        let len = (words[at] >> 16) as usize;
+        let opcode = (words[at] & 0xFFFF) as u16;
+        if len == 0 || at + len > words.len() {
+            return Err(error(at,
+                "instruction ends past the end of the module".to_string()));
+        }
+        let (name, layout) = match INSTRUCTIONS
+            .iter()
+            .find(|(number, ..)| *number == opcode)
+        {
+            Some(&(_, name, layout)) => (name, layout),
+            None => return Err(error(at, format!("unknown opcode {}", opcode))),
+        };
+        let (result, parts) = match parse(layout, &words[at + 1..at + len]) {
+            Some(parsed) => parsed,
+            None => return Err(error(at, format!("malformed `{}`", name))),
+        };
+        instructions.push(Parsed { opcode, name, result, parts });
+        at += len;
+    }
+
+    // Names from `OpName`, and of types and constants after what they are,
+    // made unique with a suffix.
+    let mut names: HashMap<u32, String> = HashMap::new();
+    let mut taken = HashSet::new();
+    let mut floats = HashSet::new();
+    for instruction in &instructions {
+        let name = |id: &u32| match names.get(id) {
+            Some(name) => name.clone(),
+            None => id.to_string(),
+        };
+        let (id, base) = match (instruction.opcode, &instruction.parts[..]) {
+            (OP_NAME, [Part::Id(id), Part::String(text)]) => {
+                (*id, text.clone())
+            }
+            (OP_CONSTANT, [Part::Id(ty), Part::Literal(value)]) => {
+                let value = match floats.contains(ty) {
+                    true => f32::from_bits(*value).to_string(),
+                    false => (*value as i32).to_string(),
+                };
+                let value = value.replace('.', "_").replace('-', "n");
+                (instruction.result.unwrap(), format!("{}_{}", name(ty), value))
+            }
+            (_, parts) => {
+                let id = match instruction.result {
+                    Some(id) => id,
+                    None => continue,
+                };
+                let base = match (instruction.opcode, parts) {
+                    (OP_TYPE_VOID, _) => "void".to_string(),
+                    (OP_TYPE_FLOAT, _) => {
+                        floats.insert(id);
+                        "float".to_string()
+                    }
+                    (OP_TYPE_INT, _) => "int".to_string(),
+                    (OP_TYPE_VECTOR, [Part::Id(item), Part::Literal(len)]) => {
+                        format!("v{}{}", len, name(item))
+                    }
+                    (OP_TYPE_MATRIX, [Part::Id(item), Part::Literal(len)]) => {
+                        format!("mat{}{}", len, name(item))
+                    }
+                    (OP_TYPE_IMAGE, _) => "type_2d_image".to_string(),
+                    (OP_TYPE_SAMPLER, _) => "type_sampler".to_string(),
+                    (OP_TYPE_SAMPLED_IMAGE, _) => {
+                        "type_sampled_image".to_string()
+                    }
+                    (OP_TYPE_STRUCT, _) => format!("_struct_{}", id),
+                    (OP_TYPE_POINTER, [Part::Enum(_, class), Part::Id(ty)]) => {
+                        let class = enum_name(STORAGE_CLASSES, *class);
+                        format!("_ptr_{}_{}", class, name(ty))
+                    }
+                    _ => continue,
+                };
+                (id, base)
+            }
+        };
+        if names.contains_key(&id) {
+            continue;
+        }
+        let mut unique = base.clone();
+        let mut suffix = 0;
+        while !taken.insert(unique.clone()) {
+            unique = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        names.insert(id, unique);
+    }
+
+    let id = |id: u32| match names.get(&id) {
+        Some(name) => format!("%{}", name),
+        None => format!("%{}", id),
+    };
+    let mut text = format!(
+        "; SPIR-V\n; Version: {}.{}\n; Generator: {}; {}\n; Bound: {}\n\
+            ; Schema: {}\n",
+        words[1] >> 16 & 0xFF,
+        words[1] >> 8 & 0xFF,
+        words[2] >> 16,
+        words[2] & 0xFFFF,
+        words[3],
+        words[4],
+    );
+    for instruction in &instructions {
+        let mut line = instruction.name.to_string();
+        let float = match (instruction.opcode, instruction.parts.first()) {
+            (OP_CONSTANT, Some(Part::Id(ty))) => floats.contains(ty),
+            _ => false,
+        };
+        for part in &instruction.parts {
+            line.push(' ');
+            match part {
+                Part::Id(number) => line.push_str(&id(*number)),
+                Part::Literal(value) if float => {
+                    line.push_str(&f32::from_bits(*value).to_string());
+                }
+                Part::Literal(value) => line.push_str(&value.to_string()),
+                Part::String(string) => {
+                    let _ = write!(line, "{:?}", string);
+                }
+                Part::Enum(names, value) => {
+                    line.push_str(&enum_name(names, *value));
+                }
+            }
+        }
+        let _ = match instruction.result {
+            Some(result) => writeln!(text, "{:>12} = {}", id(result), line),
+            None => writeln!(text, "{:15}{}", "", line),
+        };
+    }
+    Ok(text)
+}
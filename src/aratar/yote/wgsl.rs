@@ -0,0 +1,199 @@
+// WGSL
+//
+//! Translates Yote bytecode to a WGSL module, with the vertex shader
+//! `vs_main` computing the position and the fragment shader `fs_main` the
+//! color.
+//!
+//! Variables are named as in GLSL: uniform 0 is `u0`, attribute 1 is `a1`,
+//! call 2 is `t2`, and the varying passing attribute 1 to the fragment
+//! shader is `v1`, a member of the `VertexOutput` struct.  A `Bmap` 3 is
+//! the texture `u3` and the sampler `s3`, bound as given by
+//! [`Program::bindings`].  Attribute locations and varying locations are
+//! their positions among the attributes and varyings.
+//!
+//! WGSL can't add a float to a matrix or divide a matrix by one, so these
+//! are done column by column, and its `%` has the sign of the dividend, so
+//! `mod` is written out with `floor`.
+
+use std::fmt::Write;
+
+use super::{
+    decode_outputs, Instruction, Operation, Output, Primitive, Program,
+};
+use crate::aratar::Result;
+
+/// Translate Yote bytecode to a WGSL module.  The program must output both
+/// a position and a color.
+pub fn translate(bytecode: &[u8]) -> Result<String> {
+    let program = decode_outputs(bytecode)?;
+    let shader = Shader {
+        program: &program,
+        variables: program.variables(),
+        vertex: program.dependencies(Output::Position),
+        fragment: program.dependencies(Output::Color),
+    };
+    Ok(shader.module())
+}
+
+// A program being translated
+struct Shader<'a> {
+    program: &'a Program,
+    variables: Vec<Instruction>,
+    // The variables the position and the color are computed from
+    vertex: Vec<bool>,
+    fragment: Vec<bool>,
+}
+
+impl Shader<'_> {
+    fn module(&self) -> String {
+        let mut text = String::new();
+        let mut attributes = Vec::new();
+        let mut varyings = Vec::new();
+        text.push_str("struct VertexOutput {\n");
+        text.push_str("    @builtin(position) position: vec4<f32>,\n");
+        for (n, instruction) in self.variables.iter().enumerate() {
+            if let Instruction::Attribute(ty) = instruction {
+                attributes.push(format!("@location({}) a{}: {}",
+                    attributes.len(), n, ty_name(*ty)));
+                if self.fragment[n] {
+                    let _ = writeln!(text, "    @location({}) v{}: {},",
+                        varyings.len(), n, ty_name(*ty));
+                    varyings.push(n);
+                }
+            }
+        }
+        text.push_str("}\n\n");
+
+        let bindings = self.program.bindings();
+        for (n, instruction) in self.variables.iter().enumerate() {
+            let (ty, binding) = match (instruction, bindings[n]) {
+                (Instruction::Uniform(ty), Some(binding)) => (*ty, binding),
+                _ => continue,
+            };
+            let _ = match ty {
+                Primitive::Bmap => writeln!(text,
+                    "@group(0) @binding({}) var u{}: texture_2d<f32>;\n\
+                        @group(0) @binding({}) var s{}: sampler;",
+                    binding, n, binding + 1, n),
+                _ => writeln!(text,
+                    "@group(0) @binding({}) var<uniform> u{}: {};",
+                    binding, n, ty_name(ty)),
+            };
+        }
+        if bindings.iter().any(Option::is_some) {
+            text.push('\n');
+        }
+
+        text.push_str("@vertex\nfn vs_main(");
+        if !attributes.is_empty() {
+            text.push('\n');
+            for attribute in &attributes {
+                let _ = writeln!(text, "    {},", attribute);
+            }
+        }
+        text.push_str(") -> VertexOutput {\n");
+        self.calls(&mut text, &self.vertex, false);
+        let position = self.program.output(Output::Position).unwrap();
+        text.push_str("    var output: VertexOutput;\n");
+        let _ = writeln!(text, "    output.position = {};",
+            self.output(position, false));
+        for &n in &varyings {
+            let _ = writeln!(text, "    output.v{} = a{};", n, n);
+        }
+        text.push_str("    return output;\n}\n\n");
+
+        text.push_str("@fragment\n\
+            fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {\n");
+        self.calls(&mut text, &self.fragment, true);
+        let color = self.program.output(Output::Color).unwrap();
+        let _ = writeln!(text, "    return {};", self.output(color, true));
+        text.push_str("}\n");
+        text
+    }
+
+    // Write the statements computing the calls in `needed`.
+    fn calls(&self, text: &mut String, needed: &[bool], fragment: bool) {
+        for (n, instruction) in self.variables.iter().enumerate() {
+            let (ty, op, a, b) = match (needed[n], instruction) {
+                (true, &Instruction::Call(ty, op, a, b)) => (ty, op, a, b),
+                _ => continue,
+            };
+            let a_ty = self.program.types[usize::from(a)];
+            let b_ty = self.program.types[usize::from(b)];
+            let (x, y) = (self.operand(a, fragment), self.operand(b, fragment));
+            let value = match op {
+                Operation::Sample if fragment => {
+                    format!("textureSample({}, s{}, {})", x, a, y)
+                }
+                // Vertex shaders have no derivatives to choose a level of
+                // detail with.
+                Operation::Sample => {
+                    format!("textureSampleLevel({}, s{}, {}, 0.0)", x, a, y)
+                }
+                Operation::Mod => {
+                    format!("{} - {} * floor({} / {})", x, y, x, y)
+                }
+                _ => {
+                    let operator = match op {
+                        Operation::Add => "+",
+                        Operation::Sub => "-",
+                        Operation::Mul => "*",
+                        _ => "/",
+                    };
+                    let column = |x: &str, ty, i| match ty {
+                        Primitive::View => format!("{}[{}]", x, i),
+                        _ => x.to_string(),
+                    };
+                    match ty {
+                        Primitive::View
+                            if op != Operation::Mul && a_ty != b_ty =>
+                        {
+                            let columns: Vec<_> = (0..4)
+                                .map(|i| format!("{} {} {}",
+                                    column(&x, a_ty, i),
+                                    operator,
+                                    column(&y, b_ty, i)))
+                                .collect();
+                            format!("mat4x4<f32>({})", columns.join(", "))
+                        }
+                        _ => format!("{} {} {}", x, operator, y),
+                    }
+                }
+            };
+            let _ = writeln!(text, "    let t{} = {};", n, value);
+        }
+    }
+
+    // The expression for the value of variable `n`.
+    fn operand(&self, n: u8, fragment: bool) -> String {
+        let n = usize::from(n);
+        match self.variables[n] {
+            Instruction::Uniform(_) => format!("u{}", n),
+            Instruction::Attribute(_) if fragment => format!("input.v{}", n),
+            Instruction::Attribute(_) => format!("a{}", n),
+            _ => format!("t{}", n),
+        }
+    }
+
+    // The `vec4<f32>` value of output variable `n`.
+    fn output(&self, n: u8, fragment: bool) -> String {
+        let value = self.operand(n, fragment);
+        match self.program.types[usize::from(n)] {
+            Primitive::Vec3 => format!("vec4<f32>({}, 1.0)", value),
+            _ => value,
+        }
+    }
+}
+
+// The WGSL type of `ty`.
+fn ty_name(ty: Primitive) -> &'static str {
+    match ty {
+        Primitive::Vec1 => "f32",
+        Primitive::Vec2 => "vec2<f32>",
+        Primitive::Vec3 => "vec3<f32>",
+        Primitive::Vec4 => "vec4<f32>",
+        Primitive::View => "mat4x4<f32>",
+        Primitive::Int1 => "i32",
+        Primitive::Bmap => "texture_2d<f32>",
+    }
+}
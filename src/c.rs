@@ -3,9 +3,16 @@
 //! C Programming Language version [C2x](https://en.wikipedia.org/wiki/C2x)
 //! without platform-dependant sizes (int is typedef for int32_t, etc).
 
-use crate::{LexemeIterator, Lexeme};
+pub mod interpreter;
+pub mod lower;
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+
+use crate::{Diagnostic, Lexeme, LexemeIterator, Span};
 
 /// A C Keyword
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Keyword {
     Auto,
     Double,
@@ -39,9 +46,20 @@ pub enum Keyword {
     If,
     Static,
     While,
+    // C2x additions
+    Alignof,
+    Bool,
+    Constexpr,
+    False,
+    Inline,
+    Nullptr,
+    Restrict,
+    True,
 }
 
 /// A C Built-in type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(non_camel_case_types)]
 pub enum BuiltInType {
     /// No-size type
     Void,
@@ -101,7 +119,182 @@ pub enum BuiltInType {
     Char8T, // u8 (ascii character, or part of unicode codepoint)
 }
 
+impl BuiltInType {
+    /// Size in bytes, `None` for `void` and unsupported types.
+    pub fn size(self) -> Option<usize> {
+        use BuiltInType::*;
+
+        Some(match self {
+            Void | _Complex | _Imaginary | Complex | Imaginary => return None,
+            Char | SignedChar | UnsignedChar | _Bool | Bool | Char8T
+            | Int8T | Uint8T => 1,
+            SignedShort | UnsignedShort | Int16T | Uint16T | Float16T => 2,
+            SignedInt | UnsignedInt | Int32T | Uint32T | Float | Float32T => 4,
+            SignedLongInt | UnsignedLongInt | SignedLongLongInt
+            | UnsignedLongLongInt | Int64T | Uint64T | SsizeT | SizeT
+            | Double | Float64T => 8,
+            Int128T | Uint128T | Float80T => 16,
+        })
+    }
+
+    /// Returns true for integer types (including `bool` and characters).
+    pub fn is_integer(self) -> bool {
+        !self.is_float() && self.size().is_some()
+    }
+
+    /// Returns true for floating point types.
+    pub fn is_float(self) -> bool {
+        use BuiltInType::*;
+
+        matches!(self, Float | Double | Float16T | Float32T | Float64T
+            | Float80T)
+    }
+
+    /// Returns true for signed integer types.
+    pub fn is_signed(self) -> bool {
+        use BuiltInType::*;
+
+        matches!(self, SignedChar | SignedShort | SignedInt | SignedLongInt
+            | SignedLongLongInt | Int8T | Int16T | Int32T | Int64T | Int128T
+            | SsizeT)
+    }
+
+    /// Returns true for `bool` and `_Bool`.
+    pub fn is_bool(self) -> bool {
+        matches!(self, BuiltInType::Bool | BuiltInType::_Bool)
+    }
+
+    /// Wrap an integer to the range of this type (two's complement).
+    pub fn wrap(self, value: i128) -> i128 {
+        if self.is_bool() {
+            return (value != 0) as i128;
+        }
+        let bits = self.size().unwrap_or(16) * 8;
+        if bits >= 128 {
+            return value;
+        }
+        let mask = (1i128 << bits) - 1;
+        let value = value & mask;
+        if self.is_signed() && value >> (bits - 1) != 0 {
+            value - (1i128 << bits)
+        } else {
+            value
+        }
+    }
+
+    /// Smallest and largest value representable by this integer type.  The
+    /// largest `unsigned __int128` doesn't fit, so it saturates.
+    pub fn range(self) -> (i128, i128) {
+        let bits = self.size().unwrap_or(16) * 8;
+        if self.is_bool() {
+            (0, 1)
+        } else if self.is_signed() {
+            if bits >= 128 {
+                (i128::MIN, i128::MAX)
+            } else {
+                (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+            }
+        } else if bits >= 128 {
+            (0, i128::MAX)
+        } else {
+            (0, (1i128 << bits) - 1)
+        }
+    }
+
+    /// The signed or unsigned integer type of a size, as picked by the usual
+    /// arithmetic conversions.
+    fn integer(size: usize, signed: bool) -> BuiltInType {
+        use BuiltInType::*;
+
+        match (size, signed) {
+            (1, true) => SignedChar,
+            (1, false) => UnsignedChar,
+            (2, true) => SignedShort,
+            (2, false) => UnsignedShort,
+            (4, true) => SignedInt,
+            (4, false) => UnsignedInt,
+            (8, true) => SignedLongLongInt,
+            (8, false) => UnsignedLongLongInt,
+            (_, true) => Int128T,
+            (_, false) => Uint128T,
+        }
+    }
+
+    /// Look up a Yeet extension type name (`int32_t`, `size_t`, ...).
+    fn from_name(name: &str) -> Option<BuiltInType> {
+        use BuiltInType::*;
+
+        Some(match name {
+            "float16_t" => Float16T,
+            "float32_t" => Float32T,
+            "float64_t" => Float64T,
+            "float80_t" => Float80T,
+            "int8_t" => Int8T,
+            "int16_t" => Int16T,
+            "int32_t" => Int32T,
+            "int64_t" => Int64T,
+            "int128_t" => Int128T,
+            "uint8_t" => Uint8T,
+            "uint16_t" => Uint16T,
+            "uint32_t" => Uint32T,
+            "uint64_t" => Uint64T,
+            "uint128_t" => Uint128T,
+            "ssize_t" | "ptrdiff_t" | "intptr_t" => SsizeT,
+            "size_t" | "uintptr_t" => SizeT,
+            "char8_t" => Char8T,
+            _ => return None,
+        })
+    }
+}
+
+impl std::fmt::Display for BuiltInType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use BuiltInType::*;
+
+        f.write_str(match self {
+            Void => "void",
+            Char => "char",
+            Float => "float",
+            Double => "double",
+            SignedChar => "signed char",
+            SignedShort => "short",
+            SignedInt => "int",
+            SignedLongInt => "long",
+            SignedLongLongInt => "long long",
+            UnsignedChar => "unsigned char",
+            UnsignedShort => "unsigned short",
+            UnsignedInt => "unsigned int",
+            UnsignedLongInt => "unsigned long",
+            UnsignedLongLongInt => "unsigned long long",
+            _Bool => "_Bool",
+            _Complex => "_Complex",
+            _Imaginary => "_Imaginary",
+            Float16T => "float16_t",
+            Float32T => "float32_t",
+            Float64T => "float64_t",
+            Float80T => "float80_t",
+            Int8T => "int8_t",
+            Int16T => "int16_t",
+            Int32T => "int32_t",
+            Int64T => "int64_t",
+            Int128T => "int128_t",
+            Uint8T => "uint8_t",
+            Uint16T => "uint16_t",
+            Uint32T => "uint32_t",
+            Uint64T => "uint64_t",
+            Uint128T => "uint128_t",
+            SsizeT => "ssize_t",
+            SizeT => "size_t",
+            Bool => "bool",
+            Complex => "complex",
+            Imaginary => "imaginary",
+            Char8T => "char8_t",
+        })
+    }
+}
+
 /// A C Type
+#[derive(Debug, Clone, PartialEq)]
 pub enum Type<'a> {
     /// A built-in type
     BuiltIn(BuiltInType),
@@ -110,15 +303,108 @@ pub enum Type<'a> {
     /// A typedef for either a built-in type, struct or enum.  Needs to be
     /// resolved.
     Typedef(&'a str),
+    /// A pointer to a type.
+    Pointer(Box<Type<'a>>),
+    /// An array with a number of elements.
+    Array(Box<Type<'a>>, usize),
+    /// A function with return type, parameter types and if it's variadic.
+    Function(Box<Type<'a>>, Vec<Type<'a>>, bool),
+}
+
+impl<'a> Type<'a> {
+    /// Size in bytes, `None` for incomplete types.
+    pub fn size(&self, structs: &Structs<'a>) -> Option<usize> {
+        match self {
+            Type::BuiltIn(ty) => ty.size(),
+            Type::Defined(name) => structs.get(name)?.size,
+            Type::Typedef(_) | Type::Function(..) => None,
+            Type::Pointer(_) => Some(8),
+            Type::Array(ty, len) => Some(ty.size(structs)? * len),
+        }
+    }
+
+    /// Alignment in bytes, `None` for incomplete types.
+    pub fn align(&self, structs: &Structs<'a>) -> Option<usize> {
+        match self {
+            Type::BuiltIn(ty) => ty.size(),
+            Type::Defined(name) => Some(structs.get(name)?.align),
+            Type::Typedef(_) | Type::Function(..) => None,
+            Type::Pointer(_) => Some(8),
+            Type::Array(ty, _) => ty.align(structs),
+        }
+    }
+
+    /// Get the built-in type, if it is one.
+    pub fn builtin(&self) -> Option<BuiltInType> {
+        match self {
+            Type::BuiltIn(ty) => Some(*ty),
+            _ => None,
+        }
+    }
+
+    /// Returns true for integer and floating point types.
+    pub fn is_arithmetic(&self) -> bool {
+        self.builtin().is_some_and(|ty| ty.size().is_some())
+    }
+
+    /// Returns true for integer types.
+    pub fn is_integer(&self) -> bool {
+        self.builtin().is_some_and(BuiltInType::is_integer)
+    }
+
+    /// Returns true for arithmetic and pointer types.
+    pub fn is_scalar(&self) -> bool {
+        self.is_arithmetic() || matches!(self, Type::Pointer(_))
+    }
+
+    /// Returns true for `void`.
+    pub fn is_void(&self) -> bool {
+        *self == Type::BuiltIn(BuiltInType::Void)
+    }
+
+    /// Get the type pointed to, if this is a pointer.
+    pub fn pointee(&self) -> Option<&Type<'a>> {
+        match self {
+            Type::Pointer(ty) => Some(ty),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Type<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Type::BuiltIn(ty) => write!(f, "{}", ty),
+            Type::Defined(name) | Type::Typedef(name) => write!(f, "{}", name),
+            Type::Pointer(ty) => write!(f, "{}*", ty),
+            Type::Array(ty, len) => write!(f, "{}[{}]", ty, len),
+            Type::Function(ret, params, variadic) => {
+                write!(f, "{}(", ret)?;
+                for (i, param) in params.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", param)?;
+                }
+                if *variadic {
+                    write!(f, ", ...")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
 }
 
 /// Variable definition.
+#[derive(Debug, Clone)]
 pub struct Variable<'a> {
     pub ty: Type<'a>,
     pub name: &'a str,
+    pub span: Span,
 }
 
 /// A prototype for a function.  May have a block ('{') or a `;`.
+#[derive(Debug)]
 pub struct Prototype<'a> {
     // The first component of a prototype.
     pub return_type: Type<'a>,
@@ -126,8 +412,199 @@ pub struct Prototype<'a> {
     pub name: &'a str,
     // Formal parameters to the function
     pub params: Vec<Variable<'a>>,
+    // Whether the parameter list ends with `...`
+    pub variadic: bool,
     // A code block to define what the function does.
     pub block: Option<Block<'a>>,
+    // Every local variable, starting with the parameters.  Indexed by
+    // `ExprKind::Local` and `Stmt::Declare`.
+    pub locals: Vec<Variable<'a>>,
+    // Location of the function name
+    pub span: Span,
+}
+
+impl<'a> Prototype<'a> {
+    /// The function type of this prototype.
+    pub fn ty(&self) -> Type<'a> {
+        Type::Function(
+            Box::new(self.return_type.clone()),
+            self.params.iter().map(|p| p.ty.clone()).collect(),
+            self.variadic,
+        )
+    }
+}
+
+/// A member of a struct or union.
+#[derive(Debug, Clone)]
+pub struct Field<'a> {
+    pub name: &'a str,
+    pub ty: Type<'a>,
+    pub offset: usize,
+}
+
+/// A struct or union definition with its layout.
+#[derive(Debug, Clone)]
+pub struct StructDef<'a> {
+    /// The tag, or the source text of the body for anonymous structs.
+    pub name: &'a str,
+    pub union: bool,
+    pub fields: Vec<Field<'a>>,
+    /// `None` until the definition is complete.
+    pub size: Option<usize>,
+    pub align: usize,
+    pub span: Span,
+}
+
+/// Struct and union definitions by name.
+pub type Structs<'a> = HashMap<&'a str, StructDef<'a>>;
+
+/// A global variable.
+#[derive(Debug)]
+pub struct Global<'a> {
+    pub var: Variable<'a>,
+    pub init: Option<Initializer<'a>>,
+    /// Declared with `extern` and no initializer.
+    pub external: bool,
+    /// Declared with `constexpr`.
+    pub constexpr: bool,
+}
+
+/// How a variable is initialized.
+#[derive(Debug, Clone)]
+pub enum Initializer<'a> {
+    /// A single expression of the variable's type.
+    Expr(Expr<'a>),
+    /// Scalar expressions stored at byte offsets, everything else is zero.
+    List(Vec<(usize, Expr<'a>)>),
+}
+
+/// A unary operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// `-`
+    Neg,
+    /// `~`
+    BitNot,
+    /// `!`
+    Not,
+    /// `*`
+    Deref,
+    /// `&`
+    AddrOf,
+}
+
+/// A binary operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Shl,
+    Shr,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Eq,
+    Ne,
+    /// `&&`
+    And,
+    /// `||`
+    Or,
+}
+
+impl BinaryOp {
+    /// Returns true for operators that produce an `int` truth value.
+    pub fn is_comparison(self) -> bool {
+        use BinaryOp::*;
+
+        matches!(self, Lt | Gt | Le | Ge | Eq | Ne | And | Or)
+    }
+}
+
+/// A typed expression.
+#[derive(Debug, Clone)]
+pub struct Expr<'a> {
+    pub kind: ExprKind<'a>,
+    pub ty: Type<'a>,
+    pub span: Span,
+}
+
+/// Kind of expression.  Implicit conversions are explicit `Cast`s and
+/// `Decay`s, so every operator's operands already have the right type.
+#[derive(Debug, Clone)]
+pub enum ExprKind<'a> {
+    /// Integer constant
+    Int(i128),
+    /// Floating point constant
+    Float(f64),
+    /// String literal, including the terminating zero byte
+    String(Vec<u8>),
+    /// Local variable or parameter
+    Local(usize),
+    /// Global variable
+    Global(&'a str),
+    /// Function designator
+    Function(&'a str),
+    Unary(UnaryOp, Box<Expr<'a>>),
+    /// Arithmetic, comparison or logical operation.  For pointer arithmetic,
+    /// the pointer is always the left operand.
+    Binary(BinaryOp, Box<Expr<'a>>, Box<Expr<'a>>),
+    Assign(Box<Expr<'a>>, Box<Expr<'a>>),
+    /// `a op= b`, calculated in `Type` then converted back.
+    CompoundAssign(BinaryOp, Box<Expr<'a>>, Box<Expr<'a>>, Type<'a>),
+    /// `++` and `--`
+    IncDec {
+        target: Box<Expr<'a>>,
+        increment: bool,
+        prefix: bool,
+    },
+    Conditional(Box<Expr<'a>>, Box<Expr<'a>>, Box<Expr<'a>>),
+    Comma(Box<Expr<'a>>, Box<Expr<'a>>),
+    Call(Box<Expr<'a>>, Vec<Expr<'a>>),
+    /// Member at a byte offset
+    Member(Box<Expr<'a>>, usize),
+    /// Conversion to the expression's type
+    Cast(Box<Expr<'a>>),
+    /// `va_start` of the `va_list` at an address
+    VaStart(Box<Expr<'a>>),
+    /// `va_arg` of the expression's type from the `va_list` at an address
+    VaArg(Box<Expr<'a>>),
+    /// Array to pointer or function to pointer conversion
+    Decay(Box<Expr<'a>>),
+}
+
+/// A statement label.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Label<'a> {
+    Named(&'a str),
+    Case(i128),
+    Default,
+}
+
+/// A statement.
+#[derive(Debug, Clone)]
+pub enum Stmt<'a> {
+    Empty,
+    Expr(Expr<'a>),
+    /// Local variable comes into scope.
+    Declare(usize, Option<Initializer<'a>>),
+    Block(Block<'a>),
+    If(Expr<'a>, Box<Stmt<'a>>, Option<Box<Stmt<'a>>>),
+    While(Expr<'a>, Box<Stmt<'a>>),
+    DoWhile(Box<Stmt<'a>>, Expr<'a>),
+    For(Vec<Stmt<'a>>, Option<Expr<'a>>, Option<Expr<'a>>, Box<Stmt<'a>>),
+    Switch(Expr<'a>, Box<Stmt<'a>>),
+    Labeled(Label<'a>, Span, Box<Stmt<'a>>),
+    Goto(&'a str, Span),
+    Break(Span),
+    Continue(Span),
+    Return(Option<Expr<'a>>, Span),
 }
 
 enum CChunk {
@@ -140,6 +617,7 @@ enum CChunk {
 /// An iterator over C tokens.
 pub struct TokenIterator<'a> {
     lexemes: LexemeIterator<'a, CChunk>,
+    span: Span,
 }
 
 impl<'a> TokenIterator<'a> {
@@ -148,16 +626,176 @@ impl<'a> TokenIterator<'a> {
         let lexemes = LexemeIterator::new(text, begin_text, end_text);
 
         TokenIterator {
-            lexemes
+            lexemes,
+            span: Span::default(),
+        }
+    }
+
+    /// Get the span of the most recently returned token.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    // Scan a number literal starting at byte index `start`.
+    fn number(&mut self, start: usize) -> Result<Token<'a>> {
+        let text = self.lexemes.text();
+        let bytes = text.as_bytes();
+        let mut end = start;
+        let radix = if text[start..].starts_with("0x")
+            || text[start..].starts_with("0X")
+        {
+            end += 2;
+            16
+        } else if text[start..].starts_with("0b")
+            || text[start..].starts_with("0B")
+        {
+            end += 2;
+            2
+        } else {
+            10
+        };
+        let mut float = false;
+        while end < bytes.len() {
+            let ch = bytes[end];
+            if ch.is_ascii_hexdigit() && (radix == 16 || ch.is_ascii_digit()) {
+                end += 1;
+            } else if ch == b'.' && radix == 10 && !float {
+                float = true;
+                end += 1;
+            } else if (ch == b'e' || ch == b'E') && radix == 10 {
+                float = true;
+                end += 1;
+                if end < bytes.len() && (bytes[end] == b'+' || bytes[end] == b'-')
+                {
+                    end += 1;
+                }
+            } else {
+                break;
+            }
+        }
+        let digits_end = end;
+        while end < bytes.len() && bytes[end].is_ascii_alphanumeric() {
+            end += 1;
         }
+        self.span = Span::new(start, end);
+        self.lexemes.seek(end);
+
+        let digits = &text[start..digits_end];
+        let suffix = text[digits_end..end].to_ascii_lowercase();
+        let invalid = || {
+            Diagnostic::new(Span::new(start, end), "invalid number literal")
+        };
+
+        if float {
+            let ty = match suffix.as_str() {
+                "" => BuiltInType::Double,
+                "f" => BuiltInType::Float,
+                "l" => BuiltInType::Float80T,
+                _ => return Err(invalid()),
+            };
+            let value = digits.parse::<f64>().map_err(|_| invalid())?;
+            return Ok(Token::Float(value, ty));
+        }
+
+        let (radix, digits) = if radix != 10 {
+            (radix, &digits[2..])
+        } else if digits.len() > 1 && digits.starts_with('0') {
+            (8, &digits[1..])
+        } else {
+            (10, digits)
+        };
+        let value = u128::from_str_radix(digits, radix)
+            .ok()
+            .filter(|v| *v <= i128::MAX as u128)
+            .ok_or_else(invalid)? as i128;
+
+        use BuiltInType::*;
+        let candidates: &[BuiltInType] = match (suffix.as_str(), radix) {
+            ("", 10) => &[SignedInt, SignedLongInt],
+            ("", _) => &[SignedInt, UnsignedInt, SignedLongInt,
+                UnsignedLongInt],
+            ("u", _) => &[UnsignedInt, UnsignedLongInt],
+            ("l", 10) => &[SignedLongInt],
+            ("l", _) => &[SignedLongInt, UnsignedLongInt],
+            ("ul", _) | ("lu", _) => &[UnsignedLongInt],
+            ("ll", 10) => &[SignedLongLongInt],
+            ("ll", _) => &[SignedLongLongInt, UnsignedLongLongInt],
+            ("ull", _) | ("llu", _) => &[UnsignedLongLongInt],
+            _ => return Err(invalid()),
+        };
+        let ty = candidates
+            .iter()
+            .find(|ty| value <= ty.range().1)
+            .copied()
+            .unwrap_or(Uint128T);
+
+        Ok(Token::Int(value, ty))
     }
 }
 
+// The type and the builtins `<stdarg.h>` is made of, and the struct a
+// `va_list` is an array of one of, as in the System V ABI.
+const VA_LIST: &str = "__builtin_va_list";
+const VA_BUILTINS: &[&str] = &[
+    "__builtin_va_start", "__builtin_va_arg", "__builtin_va_end",
+    "__builtin_va_copy",
+];
+const VA_LIST_TAG: &str = "__va_list_tag";
+
+const OPERATORS: &[(&str, Operator)] = &[
+    ("...", Operator::Ellipsis),
+    ("<<=", Operator::ShlAssign),
+    (">>=", Operator::ShrAssign),
+    ("->", Operator::Arrow),
+    ("++", Operator::Increment),
+    ("--", Operator::Decrement),
+    ("<<", Operator::Shl),
+    (">>", Operator::Shr),
+    ("<=", Operator::Le),
+    (">=", Operator::Ge),
+    ("==", Operator::EqEq),
+    ("!=", Operator::Ne),
+    ("&&", Operator::AndAnd),
+    ("||", Operator::OrOr),
+    ("+=", Operator::AddAssign),
+    ("-=", Operator::SubAssign),
+    ("*=", Operator::MulAssign),
+    ("/=", Operator::DivAssign),
+    ("%=", Operator::RemAssign),
+    ("&=", Operator::AndAssign),
+    ("|=", Operator::OrAssign),
+    ("^=", Operator::XorAssign),
+    ("##", Operator::HashHash),
+    (",", Operator::Separator),
+    (";", Operator::Semicolon),
+    (":", Operator::Colon),
+    ("?", Operator::Question),
+    (".", Operator::Dot),
+    ("+", Operator::Plus),
+    ("-", Operator::Minus),
+    ("*", Operator::Star),
+    ("/", Operator::Slash),
+    ("%", Operator::Percent),
+    ("&", Operator::Ampersand),
+    ("|", Operator::Pipe),
+    ("^", Operator::Caret),
+    ("~", Operator::Tilde),
+    ("!", Operator::Bang),
+    ("<", Operator::Lt),
+    (">", Operator::Gt),
+    ("=", Operator::Assign),
+    ("#", Operator::Hash),
+];
+
 impl<'a> Iterator for TokenIterator<'a> {
-    type Item = Token<'a>;
+    type Item = Result<Token<'a>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        Some(match self.lexemes.next()? {
+        let lexeme = self.lexemes.next()?;
+        let span = self.lexemes.span();
+        self.span = span;
+
+        Some(Ok(match lexeme {
             Lexeme::Word(string) => match string {
                 "auto" => Token::Keyword(Keyword::Auto),
                 "double" => Token::Keyword(Keyword::Double),
@@ -191,56 +829,105 @@ impl<'a> Iterator for TokenIterator<'a> {
                 "if" => Token::Keyword(Keyword::If),
                 "static" => Token::Keyword(Keyword::Static),
                 "while" => Token::Keyword(Keyword::While),
+                "alignof" | "_Alignof" => Token::Keyword(Keyword::Alignof),
+                "bool" | "_Bool" => Token::Keyword(Keyword::Bool),
+                "constexpr" => Token::Keyword(Keyword::Constexpr),
+                "false" => Token::Keyword(Keyword::False),
+                "inline" => Token::Keyword(Keyword::Inline),
+                "nullptr" => Token::Keyword(Keyword::Nullptr),
+                "restrict" => Token::Keyword(Keyword::Restrict),
+                "true" => Token::Keyword(Keyword::True),
                 id => Token::Identifier(id),
             },
             Lexeme::Text(string) => {
-                if string.starts_with("//") {
-                    Token::SingleLineComment(&string[2..])
-                } else if string.starts_with("/*") {
-                    Token::MultiLineComment(&string[2..])
-                } else if string.starts_with("'") {
-                    if string.len() != 2 {
-                        if string.len() != 3 || string.chars().nth(1).unwrap() != '\\' {
-                            panic!("Error: 1 unicode character expected in single quotes");
-                        }
-                        // Escaped characters
-                        let ch = string.chars().nth(2).unwrap();
-                        if ch != 'n' && ch != '0' && ch != 'r'
-                            && ch != 'v' /* FIXME: there's more */
-                        {
-                            panic!("Invalid escape sequence");
+                let text = self.lexemes.text();
+                let closed = |close: &str| {
+                    span.end - span.start > close.len()
+                        && text[..span.end].ends_with(close)
+                };
+                if let Some(comment) = string.strip_prefix("//") {
+                    Token::SingleLineComment(comment)
+                } else if let Some(comment) = string.strip_prefix("/*") {
+                    if !closed("*/") {
+                        return Some(Err(Diagnostic::new(
+                            span,
+                            "unterminated comment",
+                        )));
+                    }
+                    Token::MultiLineComment(comment)
+                } else if let Some(contents) = string.strip_prefix('\'') {
+                    if !closed("'") {
+                        return Some(Err(Diagnostic::new(
+                            span,
+                            "unterminated character constant",
+                        )));
+                    }
+                    let ch = if contents.starts_with('\\') {
+                        match unescape(contents, span) {
+                            Ok(bytes) if bytes.len() == 1 => bytes[0] as char,
+                            Ok(_) => {
+                                return Some(Err(Diagnostic::new(
+                                    span,
+                                    "1 character expected in single quotes",
+                                )))
+                            }
+                            Err(e) => return Some(Err(e)),
                         }
-                        Token::Character(ch)
                     } else {
-                        // Non-escaped characters
-                        Token::Character(string.chars().nth(1).unwrap())
+                        let mut chars = contents.chars();
+                        match (chars.next(), chars.next()) {
+                            (Some(ch), None) => ch,
+                            _ => {
+                                return Some(Err(Diagnostic::new(
+                                    span,
+                                    "1 character expected in single quotes",
+                                )))
+                            }
+                        }
+                    };
+                    Token::Character(ch)
+                } else if let Some(contents) = string.strip_prefix('"') {
+                    if !closed("\"") {
+                        return Some(Err(Diagnostic::new(
+                            span,
+                            "unterminated string literal",
+                        )));
                     }
-                } else if string.starts_with("\"") {
-                    Token::String(&string[1..])
+                    Token::String(contents)
                 } else {
                     panic!("Compiler Bug: Invalid text")
                 }
             },
-            Lexeme::Number(text) => {
-                let mut iter = text.split('.');
-
-                let integer = iter.next().unwrap(); // FIXME.
-                let integer = integer.parse::<i128>().unwrap(); // FIXME
-
-                if let Some(mantissa) = iter.next() {
-                    let mantissa = mantissa.parse::<u128>().unwrap(); // FIXME
-
-                    Token::Float(integer, mantissa)
-                } else {
-                    Token::Int(integer)
-                }
-            }
+            Lexeme::Number(_) => match self.number(span.start) {
+                Ok(token) => token,
+                Err(e) => return Some(Err(e)),
+            },
             Lexeme::Operator(text) => {
-                Token::Operator(match text {
-                    "," => Operator::Separator,
-                    ";" => Operator::Semicolon,
-                    _ => panic!("Unknown operator"), // FIXME
-                })
+                let source = self.lexemes.text();
+                if text.starts_with('.')
+                    && source[span.start + 1..]
+                        .starts_with(|c: char| c.is_ascii_digit())
+                {
+                    return Some(self.number(span.start));
+                }
+                let (op_text, op) = match OPERATORS
+                    .iter()
+                    .find(|(op_text, _)| text.starts_with(op_text))
+                {
+                    Some(found) => *found,
+                    None => {
+                        let ch = text.chars().next().unwrap();
+                        return Some(Err(Diagnostic::new(
+                            Span::new(span.start, span.start + ch.len_utf8()),
+                            format!("unexpected character `{}`", text),
+                        )))
+                    }
+                };
+                if op_text.len() != text.len() {
+                    self.lexemes.seek(span.start + op_text.len());
+                }
+                self.span = Span::new(span.start, span.start + op_text.len());
+                Token::Operator(op)
             }
             Lexeme::Bracket(text) => {
                 Token::Bracket(match text {
@@ -253,15 +940,163 @@ impl<'a> Iterator for TokenIterator<'a> {
                     _ => panic!("COMPILER BUG: Invalid bracket"),
                 })
             }
-        })
+        }))
     }
 }
 
+/// Decode the escape sequences of a string or character literal.
+fn unescape(text: &str, span: Span) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut bytes = text.bytes().peekable();
+
+    while let Some(byte) = bytes.next() {
+        if byte != b'\\' {
+            out.push(byte);
+            continue;
+        }
+        let escape = bytes.next().unwrap_or(b'\\');
+        out.push(match escape {
+            b'n' => b'\n',
+            b't' => b'\t',
+            b'r' => b'\r',
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b'f' => 0x0C,
+            b'v' => 0x0B,
+            b'e' => 0x1B,
+            b'\\' | b'\'' | b'"' | b'?' => escape,
+            b'x' => {
+                let mut value = 0u32;
+                let mut digits = 0;
+                while let Some(d) =
+                    bytes.peek().and_then(|b| (*b as char).to_digit(16))
+                {
+                    value = value * 16 + d;
+                    digits += 1;
+                    bytes.next();
+                }
+                if digits == 0 || value > 0xFF {
+                    return Err(Diagnostic::new(span, "invalid hex escape"));
+                }
+                value as u8
+            }
+            b'0'..=b'7' => {
+                let mut value = u32::from(escape - b'0');
+                for _ in 0..2 {
+                    match bytes.peek() {
+                        Some(d @ b'0'..=b'7') => {
+                            value = value * 8 + u32::from(d - b'0');
+                            bytes.next();
+                        }
+                        _ => break,
+                    }
+                }
+                if value > 0xFF {
+                    return Err(Diagnostic::new(span, "invalid octal escape"));
+                }
+                value as u8
+            }
+            _ => {
+                return Err(Diagnostic::new(span, "invalid escape sequence"))
+            }
+        });
+    }
+
+    Ok(out)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Operator {
     Separator,
     Semicolon,
+    Colon,
+    Question,
+    Dot,
+    Arrow,
+    Ellipsis,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
+    Bang,
+    Shl,
+    Shr,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    EqEq,
+    Ne,
+    AndAnd,
+    OrOr,
+    Assign,
+    AddAssign,
+    SubAssign,
+    MulAssign,
+    DivAssign,
+    RemAssign,
+    AndAssign,
+    OrAssign,
+    XorAssign,
+    ShlAssign,
+    ShrAssign,
+    Increment,
+    Decrement,
+    Hash,
+    HashHash,
+}
+
+impl Operator {
+    // The binary operator and its precedence (higher binds tighter).
+    fn binary(self) -> Option<(BinaryOp, u8)> {
+        Some(match self {
+            Operator::OrOr => (BinaryOp::Or, 1),
+            Operator::AndAnd => (BinaryOp::And, 2),
+            Operator::Pipe => (BinaryOp::BitOr, 3),
+            Operator::Caret => (BinaryOp::BitXor, 4),
+            Operator::Ampersand => (BinaryOp::BitAnd, 5),
+            Operator::EqEq => (BinaryOp::Eq, 6),
+            Operator::Ne => (BinaryOp::Ne, 6),
+            Operator::Lt => (BinaryOp::Lt, 7),
+            Operator::Gt => (BinaryOp::Gt, 7),
+            Operator::Le => (BinaryOp::Le, 7),
+            Operator::Ge => (BinaryOp::Ge, 7),
+            Operator::Shl => (BinaryOp::Shl, 8),
+            Operator::Shr => (BinaryOp::Shr, 8),
+            Operator::Plus => (BinaryOp::Add, 9),
+            Operator::Minus => (BinaryOp::Sub, 9),
+            Operator::Star => (BinaryOp::Mul, 10),
+            Operator::Slash => (BinaryOp::Div, 10),
+            Operator::Percent => (BinaryOp::Rem, 10),
+            _ => return None,
+        })
+    }
+
+    // The operator of a compound assignment (`None` for plain `=`).
+    fn assignment(self) -> Option<Option<BinaryOp>> {
+        Some(Some(match self {
+            Operator::Assign => return Some(None),
+            Operator::AddAssign => BinaryOp::Add,
+            Operator::SubAssign => BinaryOp::Sub,
+            Operator::MulAssign => BinaryOp::Mul,
+            Operator::DivAssign => BinaryOp::Div,
+            Operator::RemAssign => BinaryOp::Rem,
+            Operator::AndAssign => BinaryOp::BitAnd,
+            Operator::OrAssign => BinaryOp::BitOr,
+            Operator::XorAssign => BinaryOp::BitXor,
+            Operator::ShlAssign => BinaryOp::Shl,
+            Operator::ShrAssign => BinaryOp::Shr,
+            _ => return None,
+        }))
+    }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Bracket {
     ParensL,
     ParensR,
@@ -271,146 +1106,2204 @@ pub enum Bracket {
     SquareR,
 }
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum Token<'a> {
     Keyword(Keyword),
     MultiLineComment(&'a str),
     SingleLineComment(&'a str),
     Character(char),
+    /// String contents with escape sequences not yet decoded
     String(&'a str),
     Identifier(&'a str),
-    Float(i128, u128),
-    Int(i128),
+    Float(f64, BuiltInType),
+    Int(i128, BuiltInType),
     Operator(Operator),
     Bracket(Bracket),
 }
 
+#[derive(Debug)]
 pub enum Item<'a> {
     Prototype(Prototype<'a>),
-    Block(Block<'a>),
+    Global(Global<'a>),
+    Struct(StructDef<'a>),
+    Typedef(&'a str, Type<'a>),
+}
+
+// Storage class of a declaration.
+#[derive(Clone, Copy, PartialEq)]
+enum Storage {
+    None,
+    Typedef,
+    Extern,
+    Static,
+    Constexpr,
+}
+
+// A parsed (possibly abstract) declarator, applied to a type later.
+struct Declarator<'a> {
+    name: Option<(&'a str, Span)>,
+    pointers: usize,
+    inner: Option<Box<Declarator<'a>>>,
+    suffixes: Vec<Suffix<'a>>,
+}
+
+enum Suffix<'a> {
+    Array(Option<usize>),
+    Function(Vec<Variable<'a>>, bool),
 }
 
+struct SwitchContext {
+    ty: Type<'static>,
+    cases: Vec<i128>,
+    default: bool,
+}
+
+struct FunctionContext<'a> {
+    return_type: Type<'a>,
+    locals: Vec<Variable<'a>>,
+    scopes: Vec<HashMap<&'a str, usize>>,
+    labels: HashMap<&'a str, Span>,
+    gotos: Vec<(&'a str, Span)>,
+    loops: usize,
+    switches: Vec<SwitchContext>,
+    // Whether the parameters end with `...`
+    variadic: bool,
+}
+
+/// An iterator over the top-level items of a C translation unit.  This is a
+/// single-pass parser and type checker: implicit conversions are inserted,
+/// typedefs are resolved, and `sizeof` and member offsets are calculated as
+/// items are parsed.
 pub struct ItemIterator<'a> {
     tokens: TokenIterator<'a>,
+    peeked: VecDeque<(Token<'a>, Span)>,
+    last: Span,
+    pending: VecDeque<Item<'a>>,
+    failed: bool,
+    typedefs: HashMap<&'a str, Type<'a>>,
+    structs: Structs<'a>,
+    enums: HashMap<&'a str, i128>,
+    globals: HashMap<&'a str, (Type<'a>, Option<i128>)>,
+    functions: HashMap<&'a str, Type<'a>>,
+    function: Option<FunctionContext<'a>>,
+}
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+fn int<'a>(value: i128, ty: BuiltInType, span: Span) -> Expr<'a> {
+    Expr {
+        kind: ExprKind::Int(value),
+        ty: Type::BuiltIn(ty),
+        span,
+    }
+}
+
+fn boxed<'a>(kind: fn(Box<Expr<'a>>) -> ExprKind<'a>, e: Expr<'a>)
+    -> ExprKind<'a>
+{
+    kind(Box::new(e))
 }
 
 impl<'a> ItemIterator<'a> {
     pub fn new(text: &'a str) -> Self {
         ItemIterator {
             tokens: TokenIterator::new(text),
+            peeked: VecDeque::new(),
+            last: Span::default(),
+            pending: VecDeque::new(),
+            failed: false,
+            typedefs: HashMap::new(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            globals: HashMap::new(),
+            functions: HashMap::new(),
+            function: None,
         }
     }
-}
 
-impl<'a> Iterator for ItemIterator<'a> {
-    type Item = Result<Item<'a>, ()>;
+    /// Struct and union definitions parsed so far.
+    pub fn structs(&self) -> &Structs<'a> {
+        &self.structs
+    }
 
-    #[allow(unused)] // FIXME
-    fn next(&mut self) -> Option<Self::Item> {
-        let mut ty = None;
-//        let var_definition = None;
-//        let prototype_definition = None;
-
-        match self.tokens.next()? {
-            Token::Keyword(keyword) => match keyword {
-                Keyword::Auto => todo!(),
-                Keyword::Double => {
-                    ty = Some(Type::BuiltIn(BuiltInType::Double));
-                    let name = if let Some(token) = self.tokens.next() {
-                        match token {
-                            Token::Identifier(ident) => {
-                                ident
-                            }
-                            _ => return Some(Err(()))
-                        }
-                    } else {
-                        return Some(Err(()));
-                    };
-                    if let Some(token) = self.tokens.next() {
-                        match token {
-                            Token::Bracket(Bracket::ParensL) => { /* open */ }
-                            _ => return Some(Err(()))
-                        }
+    fn fill(&mut self, n: usize) -> Result<()> {
+        while self.peeked.len() <= n {
+            match self.tokens.next() {
+                None => break,
+                Some(Err(e)) => return Err(e),
+                Some(Ok(Token::SingleLineComment(_)))
+                | Some(Ok(Token::MultiLineComment(_))) => {}
+                Some(Ok(token)) => {
+                    self.peeked.push_back((token, self.tokens.span()))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn peek_nth(&mut self, n: usize) -> Result<Option<Token<'a>>> {
+        self.fill(n)?;
+        Ok(self.peeked.get(n).map(|(token, _)| token.clone()))
+    }
+
+    fn peek(&mut self) -> Result<Option<Token<'a>>> {
+        self.peek_nth(0)
+    }
+
+    // Span of the next token, or the end of the text.
+    fn peek_span(&mut self) -> Result<Span> {
+        self.fill(0)?;
+        Ok(match self.peeked.front() {
+            Some((_, span)) => *span,
+            None => {
+                let end = self.tokens.lexemes.text().len();
+                Span::new(end, end)
+            }
+        })
+    }
+
+    fn bump(&mut self) -> Result<(Token<'a>, Span)> {
+        self.fill(0)?;
+        match self.peeked.pop_front() {
+            Some((token, span)) => {
+                self.last = span;
+                Ok((token, span))
+            }
+            None => {
+                let span = self.peek_span()?;
+                Err(Diagnostic::new(span, "unexpected end of file"))
+            }
+        }
+    }
+
+    fn eat(&mut self, token: Token<'a>) -> Result<bool> {
+        if self.peek()? == Some(token) {
+            self.bump()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn eat_op(&mut self, op: Operator) -> Result<bool> {
+        self.eat(Token::Operator(op))
+    }
+
+    fn expect(&mut self, token: Token<'a>, what: &str) -> Result<Span> {
+        if self.eat(token)? {
+            Ok(self.last)
+        } else {
+            let span = self.peek_span()?;
+            Err(Diagnostic::new(span, format!("expected {}", what)))
+        }
+    }
+
+    fn expect_op(&mut self, op: Operator, what: &str) -> Result<Span> {
+        self.expect(Token::Operator(op), what)
+    }
+
+    fn expect_identifier(&mut self) -> Result<(&'a str, Span)> {
+        match self.peek()? {
+            Some(Token::Identifier(name)) => {
+                let (_, span) = self.bump()?;
+                Ok((name, span))
+            }
+            _ => {
+                let span = self.peek_span()?;
+                Err(Diagnostic::new(span, "expected identifier"))
+            }
+        }
+    }
+
+    fn error<T>(&mut self, span: Span, message: impl Into<String>)
+        -> Result<T>
+    {
+        Err(Diagnostic::new(span, message))
+    }
+
+    // Look up a local variable in the current function.
+    fn local(&self, name: &str) -> Option<usize> {
+        let function = self.function.as_ref()?;
+        function.scopes.iter().rev().find_map(|s| s.get(name).copied())
+    }
+
+    fn is_typedef_name(&self, name: &str) -> bool {
+        self.local(name).is_none()
+            && (self.typedefs.contains_key(name)
+                || BuiltInType::from_name(name).is_some()
+                || name == VA_LIST)
+    }
+
+    // Returns true if a token can start a declaration.
+    fn starts_declaration(&self, token: &Option<Token<'a>>) -> bool {
+        use Keyword::*;
+
+        match token {
+            Some(Token::Keyword(keyword)) => matches!(keyword, Auto | Double
+                | Int | Struct | Long | Enum | Register | Typedef | Char
+                | Extern | Union | Const | Float | Short | Unsigned | Signed
+                | Void | Volatile | Static | Bool | Constexpr | Inline
+                | Restrict),
+            Some(Token::Identifier(name)) => self.is_typedef_name(name),
+            _ => false,
+        }
+    }
+
+    // Parse declaration specifiers.
+    fn specifiers(&mut self) -> Result<(Storage, Type<'a>, Span)> {
+        let start = self.peek_span()?;
+        let mut storage = Storage::None;
+        let mut named: Option<Type<'a>> = None;
+        let (mut void, mut char, mut short, mut int, mut long) = (0, 0, 0, 0, 0);
+        let (mut float, mut double, mut signed, mut unsigned, mut bool) =
+            (0, 0, 0, 0, 0);
+        let mut any = false;
+
+        loop {
+            // A typedef name may follow storage classes and qualifiers, but
+            // not another type.
+            let typed = named.is_some() || void + char + short + int + long
+                + float + double + signed + unsigned + bool > 0;
+            let token = self.peek()?;
+            let keyword = match token {
+                Some(Token::Keyword(keyword)) => keyword,
+                Some(Token::Identifier(name))
+                    if !typed && self.is_typedef_name(name) =>
+                {
+                    self.bump()?;
+                    named = Some(match self.typedefs.get(name) {
+                        Some(ty) => ty.clone(),
+                        None if name == VA_LIST => self.va_list(),
+                        None => Type::BuiltIn(
+                            BuiltInType::from_name(name).unwrap(),
+                        ),
+                    });
+                    any = true;
+                    continue;
+                }
+                _ => break,
+            };
+            let set_storage = |storage: &mut Storage, new| {
+                if *storage != Storage::None {
+                    Err(())
+                } else {
+                    *storage = new;
+                    Ok(())
+                }
+            };
+            let result = match keyword {
+                Keyword::Const | Keyword::Volatile | Keyword::Restrict
+                | Keyword::Inline | Keyword::Register | Keyword::Auto => Ok(()),
+                Keyword::Typedef => set_storage(&mut storage, Storage::Typedef),
+                Keyword::Extern => set_storage(&mut storage, Storage::Extern),
+                Keyword::Static => set_storage(&mut storage, Storage::Static),
+                Keyword::Constexpr => {
+                    set_storage(&mut storage, Storage::Constexpr)
+                }
+                Keyword::Void => { void += 1; Ok(()) }
+                Keyword::Char => { char += 1; Ok(()) }
+                Keyword::Short => { short += 1; Ok(()) }
+                Keyword::Int => { int += 1; Ok(()) }
+                Keyword::Long => { long += 1; Ok(()) }
+                Keyword::Float => { float += 1; Ok(()) }
+                Keyword::Double => { double += 1; Ok(()) }
+                Keyword::Signed => { signed += 1; Ok(()) }
+                Keyword::Unsigned => { unsigned += 1; Ok(()) }
+                Keyword::Bool => { bool += 1; Ok(()) }
+                Keyword::Struct | Keyword::Union => {
+                    if named.is_some() {
+                        Err(())
                     } else {
-                        return Some(Err(()));
-                    };
-                },
-                Keyword::Int => {
-                },
-                Keyword::Struct => {
-                },
-                Keyword::Break => {
-                },
-                Keyword::Else => {
-                },
-                Keyword::Long => {
-                },
-                Keyword::Switch => {
-                },
-                Keyword::Case => {
-                },
+                        named = Some(self.struct_specifier()?);
+                        any = true;
+                        continue;
+                    }
+                }
                 Keyword::Enum => {
-                },
-                Keyword::Register => {
-                },
-                Keyword::Typedef => {
-                },
-                Keyword::Char => {
-                },
-                Keyword::Extern => {
-                },
-                Keyword::Return => {
-                },
-                Keyword::Union => {
-                },
-                Keyword::Const => {
-                },
-                Keyword::Float => {
-                },
-                Keyword::Short => {
-                },
-                Keyword::Unsigned => {
-                },
-                Keyword::Continue => {
-                },
-                Keyword::For => {
-                },
-                Keyword::Signed => {
-                },
-                Keyword::Void => {
-                },
-                Keyword::Default => {
-                },
-                Keyword::Goto => {
-                },
-                Keyword::Sizeof => {
-                },
-                Keyword::Volatile => {
-                },
-                Keyword::Do => {
-                },
-                Keyword::If => {
-                },
-                Keyword::Static => {
-                },
-                Keyword::While => {
-                },
+                    if named.is_some() {
+                        Err(())
+                    } else {
+                        named = Some(self.enum_specifier()?);
+                        any = true;
+                        continue;
+                    }
+                }
+                _ => break,
+            };
+            let (_, span) = self.bump()?;
+            if result.is_err() {
+                return self.error(span, "invalid declaration specifiers");
+            }
+            any = true;
+        }
+
+        let span = start.to(self.last);
+        if !any {
+            return self.error(start, "expected declaration specifiers");
+        }
+
+        use BuiltInType::*;
+        let builtin = match (void, char, short, int, long, float, double,
+            signed, unsigned, bool)
+        {
+            (0, 0, 0, 0, 0, 0, 0, 0, 0, 0) => None,
+            (1, 0, 0, 0, 0, 0, 0, 0, 0, 0) => Some(Void),
+            (0, 0, 0, 0, 0, 0, 0, 0, 0, 1) => Some(BuiltInType::Bool),
+            (0, 1, 0, 0, 0, 0, 0, 0, 0, 0) => Some(Char),
+            (0, 1, 0, 0, 0, 0, 0, 1, 0, 0) => Some(SignedChar),
+            (0, 1, 0, 0, 0, 0, 0, 0, 1, 0) => Some(UnsignedChar),
+            (0, 0, 1, 0 | 1, 0, 0, 0, 0 | 1, 0, 0) => Some(SignedShort),
+            (0, 0, 1, 0 | 1, 0, 0, 0, 0, 1, 0) => Some(UnsignedShort),
+            (0, 0, 0, 1, 0, 0, 0, 0 | 1, 0, 0)
+            | (0, 0, 0, 0, 0, 0, 0, 1, 0, 0) => Some(SignedInt),
+            (0, 0, 0, 0 | 1, 0, 0, 0, 0, 1, 0) => Some(UnsignedInt),
+            (0, 0, 0, 0 | 1, 1, 0, 0, 0 | 1, 0, 0) => Some(SignedLongInt),
+            (0, 0, 0, 0 | 1, 1, 0, 0, 0, 1, 0) => Some(UnsignedLongInt),
+            (0, 0, 0, 0 | 1, 2, 0, 0, 0 | 1, 0, 0) => Some(SignedLongLongInt),
+            (0, 0, 0, 0 | 1, 2, 0, 0, 0, 1, 0) => Some(UnsignedLongLongInt),
+            (0, 0, 0, 0, 0, 1, 0, 0, 0, 0) => Some(Float),
+            (0, 0, 0, 0, 0, 0, 1, 0, 0, 0) => Some(Double),
+            (0, 0, 0, 0, 1, 0, 1, 0, 0, 0) => Some(Float80T),
+            _ => return self.error(span, "invalid combination of types"),
+        };
+        let ty = match (builtin, named) {
+            (Some(builtin), None) => Type::BuiltIn(builtin),
+            (None, Some(named)) => named,
+            (None, None) if storage != Storage::None => {
+                return self.error(span, "type inference is not supported");
+            }
+            (None, None) => return self.error(span, "expected type"),
+            (Some(_), Some(_)) => {
+                return self.error(span, "invalid combination of types")
+            }
+        };
+
+        Ok((storage, ty, span))
+    }
+
+    fn struct_specifier(&mut self) -> Result<Type<'a>> {
+        let (keyword, start) = self.bump()?;
+        let union = keyword == Token::Keyword(Keyword::Union);
+        let tag = match self.peek()? {
+            Some(Token::Identifier(name)) => {
+                self.bump()?;
+                Some(name)
+            }
+            _ => None,
+        };
+        if self.peek()? != Some(Token::Bracket(Bracket::BraceL)) {
+            let tag = match tag {
+                Some(tag) => tag,
+                None => return self.error(start, "expected struct name"),
+            };
+            if !self.structs.contains_key(tag) {
+                self.structs.insert(tag, StructDef {
+                    name: tag,
+                    union,
+                    fields: Vec::new(),
+                    size: None,
+                    align: 1,
+                    span: start,
+                });
+            }
+            return Ok(Type::Defined(tag));
+        }
+
+        let (_, brace) = self.bump()?;
+        let mut fields: Vec<Field<'a>> = Vec::new();
+        let (mut size, mut align) = (0, 1);
+        let name = match tag {
+            Some(tag) => {
+                // Allow self-referential pointers while parsing the body.
+                if self.structs.get(tag).is_some_and(|s| s.size.is_some()) {
+                    return self.error(start, format!("redefinition of `{}`",
+                        tag));
+                }
+                self.structs.insert(tag, StructDef {
+                    name: tag,
+                    union,
+                    fields: Vec::new(),
+                    size: None,
+                    align: 1,
+                    span: start,
+                });
+                tag
+            }
+            None => "",
+        };
+
+        while !self.eat(Token::Bracket(Bracket::BraceR))? {
+            let (storage, base, span) = self.specifiers()?;
+            if storage != Storage::None {
+                return self.error(span, "storage class in struct member");
+            }
+            loop {
+                let declarator = self.declarator()?;
+                let (field, span) = match declarator.name {
+                    Some(name) => name,
+                    None => return self.error(self.last, "expected member name"),
+                };
+                let ty = self.apply(declarator, base.clone());
+                if self.peek()? == Some(Token::Operator(Operator::Colon)) {
+                    return self.error(self.last, "bit-fields are not supported");
+                }
+                let (field_size, field_align) = match (ty.size(&self.structs),
+                    ty.align(&self.structs))
+                {
+                    (Some(size), Some(align)) => (size, align),
+                    _ => return self.error(span, format!(
+                        "member `{}` has incomplete type", field)),
+                };
+                if fields.iter().any(|f| f.name == field) {
+                    return self.error(span, format!("duplicate member `{}`",
+                        field));
+                }
+                let offset = if union {
+                    size = size.max(field_size);
+                    0
+                } else {
+                    let offset = size.div_ceil(field_align) * field_align;
+                    size = offset + field_size;
+                    offset
+                };
+                align = align.max(field_align);
+                fields.push(Field { name: field, ty, offset });
+                if !self.eat_op(Operator::Separator)? {
+                    break;
+                }
+            }
+            self.expect_op(Operator::Semicolon, "`;`")?;
+        }
+
+        let size = size.div_ceil(align) * align;
+        let text = self.tokens.lexemes.text();
+        let name = if name.is_empty() {
+            &text[brace.start..self.last.end]
+        } else {
+            name
+        };
+        let def = StructDef {
+            name,
+            union,
+            fields,
+            size: Some(size),
+            align,
+            span: start,
+        };
+        self.structs.insert(name, def.clone());
+        self.pending.push_back(Item::Struct(def));
+
+        Ok(Type::Defined(name))
+    }
+
+    // The type `__builtin_va_list` stands for, defining the struct it is an
+    // array of when first used.
+    fn va_list(&mut self) -> Type<'a> {
+        if !self.structs.contains_key(VA_LIST_TAG) {
+            let unsigned = Type::BuiltIn(BuiltInType::UnsignedInt);
+            let pointer = Type::Pointer(Box::new(Type::BuiltIn(
+                BuiltInType::Void)));
+            let field = |name, ty, offset| Field { name, ty, offset };
+            let def = StructDef {
+                name: VA_LIST_TAG,
+                union: false,
+                fields: vec![
+                    field("gp_offset", unsigned.clone(), 0),
+                    field("fp_offset", unsigned, 4),
+                    field("overflow_arg_area", pointer.clone(), 8),
+                    field("reg_save_area", pointer, 16),
+                ],
+                size: Some(24),
+                align: 8,
+                span: self.last,
+            };
+            self.structs.insert(VA_LIST_TAG, def.clone());
+            self.pending.push_back(Item::Struct(def));
+        }
+        Type::Array(Box::new(Type::Defined(VA_LIST_TAG)), 1)
+    }
+
+    fn enum_specifier(&mut self) -> Result<Type<'a>> {
+        self.bump()?;
+        if let Some(Token::Identifier(_)) = self.peek()? {
+            self.bump()?;
+        }
+        if self.eat(Token::Bracket(Bracket::BraceL))? {
+            let mut value = 0;
+            while !self.eat(Token::Bracket(Bracket::BraceR))? {
+                let (name, span) = self.expect_identifier()?;
+                if self.eat_op(Operator::Assign)? {
+                    value = self.constant_expression()?.0;
+                }
+                if self.enums.contains_key(name) {
+                    return self.error(span, format!("redefinition of `{}`",
+                        name));
+                }
+                self.enums.insert(name, value);
+                value += 1;
+                if !self.eat_op(Operator::Separator)? {
+                    self.expect(Token::Bracket(Bracket::BraceR), "`}`")?;
+                    break;
+                }
+            }
+        }
+        Ok(Type::BuiltIn(BuiltInType::SignedInt))
+    }
+
+    fn declarator(&mut self) -> Result<Declarator<'a>> {
+        let mut pointers = 0;
+        while self.eat_op(Operator::Star)? {
+            pointers += 1;
+            while let Some(Token::Keyword(Keyword::Const | Keyword::Volatile
+                | Keyword::Restrict)) = self.peek()?
+            {
+                self.bump()?;
+            }
+        }
+
+        let mut name = None;
+        let mut inner = None;
+        match self.peek()? {
+            Some(Token::Identifier(id)) if !self.is_typedef_name(id) => {
+                let (_, span) = self.bump()?;
+                name = Some((id, span));
+            }
+            Some(Token::Bracket(Bracket::ParensL)) => {
+                // Nested declarator or (abstract) function parameters?
+                let next = self.peek_nth(1)?;
+                let nested = match &next {
+                    Some(Token::Operator(Operator::Star))
+                    | Some(Token::Bracket(Bracket::ParensL))
+                    | Some(Token::Bracket(Bracket::SquareL)) => true,
+                    Some(Token::Identifier(id)) => !self.is_typedef_name(id),
+                    _ => false,
+                };
+                if nested {
+                    self.bump()?;
+                    let declarator = self.declarator()?;
+                    self.expect(Token::Bracket(Bracket::ParensR), "`)`")?;
+                    name = declarator.name;
+                    inner = Some(Box::new(declarator));
+                }
+            }
+            _ => {}
+        }
+
+        let mut suffixes = Vec::new();
+        loop {
+            if self.eat(Token::Bracket(Bracket::SquareL))? {
+                if self.eat(Token::Bracket(Bracket::SquareR))? {
+                    suffixes.push(Suffix::Array(None));
+                } else {
+                    let (len, span) = self.constant_expression()?;
+                    if len < 0 {
+                        return self.error(span, "negative array size");
+                    }
+                    self.expect(Token::Bracket(Bracket::SquareR), "`]`")?;
+                    suffixes.push(Suffix::Array(Some(len as usize)));
+                }
+            } else if self.eat(Token::Bracket(Bracket::ParensL))? {
+                let (params, variadic) = self.parameters()?;
+                suffixes.push(Suffix::Function(params, variadic));
+            } else {
+                break;
+            }
+        }
+
+        Ok(Declarator { name, pointers, inner, suffixes })
+    }
+
+    // Apply a declarator to the type from the declaration specifiers.
+    fn apply(&self, declarator: Declarator<'a>, base: Type<'a>) -> Type<'a> {
+        let mut ty = base;
+        for _ in 0..declarator.pointers {
+            ty = Type::Pointer(Box::new(ty));
+        }
+        for suffix in declarator.suffixes.into_iter().rev() {
+            ty = match suffix {
+                Suffix::Array(len) => Type::Array(Box::new(ty), len.unwrap_or(0)),
+                Suffix::Function(params, variadic) => Type::Function(
+                    Box::new(ty),
+                    params.into_iter().map(|p| p.ty).collect(),
+                    variadic,
+                ),
+            };
+        }
+        match declarator.inner {
+            Some(inner) => self.apply(*inner, ty),
+            None => ty,
+        }
+    }
+
+    // Parse parameters after the `(`.
+    fn parameters(&mut self) -> Result<(Vec<Variable<'a>>, bool)> {
+        let mut params = Vec::new();
+        if self.eat(Token::Bracket(Bracket::ParensR))? {
+            return Ok((params, false));
+        }
+        if self.peek()? == Some(Token::Keyword(Keyword::Void))
+            && self.peek_nth(1)? == Some(Token::Bracket(Bracket::ParensR))
+        {
+            self.bump()?;
+            self.bump()?;
+            return Ok((params, false));
+        }
+        loop {
+            if self.eat_op(Operator::Ellipsis)? {
+                self.expect(Token::Bracket(Bracket::ParensR), "`)`")?;
+                return Ok((params, true));
+            }
+            let (storage, base, span) = self.specifiers()?;
+            if storage != Storage::None {
+                return self.error(span, "storage class in parameter");
+            }
+            let declarator = self.declarator()?;
+            let (name, span) = declarator.name.unwrap_or(("", span));
+            let ty = match self.apply(declarator, base) {
+                Type::Array(ty, _) => Type::Pointer(ty),
+                ty @ Type::Function(..) => Type::Pointer(Box::new(ty)),
+                ty => ty,
+            };
+            if ty.is_void() {
+                return self.error(span, "parameter has type void");
+            }
+            params.push(Variable { ty, name, span });
+            if !self.eat_op(Operator::Separator)? {
+                self.expect(Token::Bracket(Bracket::ParensR), "`)`")?;
+                return Ok((params, false));
+            }
+        }
+    }
+
+    // Parse a type name, as used in casts and `sizeof`.
+    fn type_name(&mut self) -> Result<Type<'a>> {
+        let (storage, base, span) = self.specifiers()?;
+        if storage != Storage::None {
+            return self.error(span, "storage class in type name");
+        }
+        let declarator = self.declarator()?;
+        if let Some((_, span)) = declarator.name {
+            return self.error(span, "unexpected name in type");
+        }
+        Ok(self.apply(declarator, base))
+    }
+
+    // Parse a top-level declaration or function definition.
+    fn external_declaration(&mut self) -> Result<()> {
+        if let Some(Token::Operator(Operator::Hash)) = self.peek()? {
+            let span = self.peek_span()?;
+            return self.error(span, "preprocessor directives are not supported");
+        }
+        let (storage, base, _) = self.specifiers()?;
+        if self.eat_op(Operator::Semicolon)? {
+            return Ok(());
+        }
+        let mut first = true;
+        loop {
+            let declarator = self.declarator()?;
+            let direct_function = declarator.inner.is_none()
+                && matches!(declarator.suffixes.first(),
+                    Some(Suffix::Function(..)));
+            let (name, span) = match declarator.name {
+                Some(name) => name,
+                None => return self.error(self.last, "expected identifier"),
+            };
+            let params = match declarator.suffixes.first() {
+                Some(Suffix::Function(params, _)) if direct_function => {
+                    params.clone()
+                }
+                _ => Vec::new(),
+            };
+            let ty = self.apply(declarator, base.clone());
+
+            match ty {
+                _ if storage == Storage::Typedef => {
+                    self.typedefs.insert(name, ty.clone());
+                    self.pending.push_back(Item::Typedef(name, ty));
+                }
+                Type::Function(return_type, _, variadic) => {
+                    if !direct_function {
+                        return self.error(span, "invalid function declarator");
+                    }
+                    if let Type::Array(..) | Type::Function(..) = *return_type {
+                        return self.error(span,
+                            "function can't return an array or function");
+                    }
+                    let fn_ty = Type::Function(return_type.clone(),
+                        params.iter().map(|p| p.ty.clone()).collect(),
+                        variadic);
+                    if let Some(old) = self.functions.get(name) {
+                        if *old != fn_ty {
+                            return self.error(span, format!(
+                                "conflicting types for `{}`", name));
+                        }
+                    }
+                    self.functions.insert(name, fn_ty);
+                    let mut prototype = Prototype {
+                        return_type: *return_type,
+                        name,
+                        params,
+                        variadic,
+                        block: None,
+                        locals: Vec::new(),
+                        span,
+                    };
+                    if first
+                        && self.peek()? == Some(Token::Bracket(Bracket::BraceL))
+                    {
+                        self.function_body(&mut prototype)?;
+                        self.pending.push_back(Item::Prototype(prototype));
+                        return Ok(());
+                    }
+                    self.pending.push_back(Item::Prototype(prototype));
+                }
+                ty => {
+                    let var = Variable { ty, name, span };
+                    let global = self.global(var, storage)?;
+                    self.pending.push_back(Item::Global(global));
+                }
+            }
+
+            first = false;
+            if !self.eat_op(Operator::Separator)? {
+                self.expect_op(Operator::Semicolon, "`;`")?;
+                return Ok(());
+            }
+        }
+    }
+
+    fn global(&mut self, mut var: Variable<'a>, storage: Storage)
+        -> Result<Global<'a>>
+    {
+        let init = if self.eat_op(Operator::Assign)? {
+            let (init, ty) = self.initializer(&var.ty)?;
+            var.ty = ty;
+            Some(init)
+        } else {
+            None
+        };
+        let constexpr = storage == Storage::Constexpr;
+        let value = match &init {
+            Some(Initializer::Expr(e)) if constexpr => {
+                if e.ty.is_integer() {
+                    match self.constant(e) {
+                        Some(value) => Some(value),
+                        None => return self.error(e.span,
+                            "constexpr initializer is not constant"),
+                    }
+                } else {
+                    None
+                }
+            }
+            None if constexpr => {
+                return self.error(var.span, "constexpr requires initializer")
+            }
+            _ => None,
+        };
+        if var.ty.size(&self.structs).is_none()
+            && !(storage == Storage::Extern && init.is_none())
+        {
+            return self.error(var.span, format!(
+                "variable `{}` has incomplete type", var.name));
+        }
+        if self.functions.contains_key(var.name) {
+            return self.error(var.span, format!(
+                "`{}` redeclared as different kind of symbol", var.name));
+        }
+        self.globals.insert(var.name, (var.ty.clone(), value));
+        Ok(Global {
+            var,
+            init,
+            external: storage == Storage::Extern,
+            constexpr,
+        })
+    }
+
+    fn function_body(&mut self, prototype: &mut Prototype<'a>) -> Result<()> {
+        let mut scope = HashMap::new();
+        for (i, param) in prototype.params.iter().enumerate() {
+            if param.name.is_empty() {
+                continue;
+            }
+            if scope.insert(param.name, i).is_some() {
+                return self.error(param.span, format!(
+                    "duplicate parameter `{}`", param.name));
+            }
+        }
+        self.function = Some(FunctionContext {
+            return_type: prototype.return_type.clone(),
+            locals: prototype.params.clone(),
+            scopes: vec![scope],
+            labels: HashMap::new(),
+            gotos: Vec::new(),
+            loops: 0,
+            switches: Vec::new(),
+            variadic: prototype.variadic,
+        });
+        let block = self.block();
+        let function = self.function.take().unwrap();
+        let block = block?;
+        for (label, span) in function.gotos {
+            if !function.labels.contains_key(label) {
+                return self.error(span, format!("use of undeclared label `{}`",
+                    label));
+            }
+        }
+        prototype.block = Some(block);
+        prototype.locals = function.locals;
+
+        Ok(())
+    }
+
+    fn context(&mut self) -> &mut FunctionContext<'a> {
+        self.function.as_mut().unwrap()
+    }
+
+    // Parse a `{ ... }` block.
+    fn block(&mut self) -> Result<Block<'a>> {
+        self.expect(Token::Bracket(Bracket::BraceL), "`{`")?;
+        self.context().scopes.push(HashMap::new());
+        let mut stmts = Vec::new();
+        let result = loop {
+            match self.peek()? {
+                Some(Token::Bracket(Bracket::BraceR)) => {
+                    self.bump()?;
+                    break Ok(());
+                }
+                None => {
+                    let span = self.peek_span()?;
+                    break self.error(span, "expected `}`");
+                }
+                _ => {}
+            }
+            if let Err(e) = self.block_item(&mut stmts) {
+                break Err(e);
+            }
+        };
+        self.context().scopes.pop();
+        result?;
+
+        Ok(Block { stmts })
+    }
+
+    // Parse a declaration or statement within a block.
+    fn block_item(&mut self, stmts: &mut Vec<Stmt<'a>>) -> Result<()> {
+        let token = self.peek()?;
+        if self.starts_declaration(&token) {
+            self.local_declaration(stmts)
+        } else {
+            stmts.push(self.statement()?);
+            Ok(())
+        }
+    }
+
+    fn local_declaration(&mut self, stmts: &mut Vec<Stmt<'a>>) -> Result<()> {
+        let (storage, base, span) = self.specifiers()?;
+        if storage == Storage::Static {
+            return self.error(span, "static local variables are not supported");
+        }
+        if self.eat_op(Operator::Semicolon)? {
+            return Ok(());
+        }
+        loop {
+            let declarator = self.declarator()?;
+            let (name, span) = match declarator.name {
+                Some(name) => name,
+                None => return self.error(self.last, "expected identifier"),
+            };
+            let ty = self.apply(declarator, base.clone());
+            if storage == Storage::Typedef {
+                self.typedefs.insert(name, ty);
+            } else if let Type::Function(..) = ty {
+                self.functions.insert(name, ty);
+            } else if storage == Storage::Extern {
+                return self.error(span, "block-scope extern is not supported");
+            } else {
+                let id = self.context().locals.len();
+                let scope = self.context().scopes.last_mut().unwrap();
+                if scope.insert(name, id).is_some() {
+                    return self.error(span, format!("redefinition of `{}`",
+                        name));
+                }
+                self.context().locals.push(Variable {
+                    ty: ty.clone(),
+                    name,
+                    span,
+                });
+                let init = if self.eat_op(Operator::Assign)? {
+                    let (init, ty) = self.initializer(&ty)?;
+                    self.context().locals[id].ty = ty;
+                    Some(init)
+                } else {
+                    if storage == Storage::Constexpr {
+                        return self.error(span, "constexpr requires initializer");
+                    }
+                    None
+                };
+                let ty = &self.function.as_ref().unwrap().locals[id].ty;
+                if ty.size(&self.structs).is_none() {
+                    return self.error(span, format!(
+                        "variable `{}` has incomplete type", name));
+                }
+                stmts.push(Stmt::Declare(id, init));
+            }
+            if !self.eat_op(Operator::Separator)? {
+                self.expect_op(Operator::Semicolon, "`;`")?;
+                return Ok(());
+            }
+        }
+    }
+
+    // Parse an initializer for a variable of type `ty`, returning the
+    // completed type (for arrays of unknown size).
+    fn initializer(&mut self, ty: &Type<'a>)
+        -> Result<(Initializer<'a>, Type<'a>)>
+    {
+        let is_aggregate = matches!(ty, Type::Array(..) | Type::Defined(_));
+        let string_init = matches!(self.peek()?, Some(Token::String(_)))
+            && matches!(ty, Type::Array(..));
+        if self.peek()? == Some(Token::Bracket(Bracket::BraceL))
+            || string_init
+        {
+            let mut list = Vec::new();
+            let len = self.initializer_list(ty, 0, &mut list)?;
+            let ty = match ty {
+                Type::Array(elem, 0) => Type::Array(elem.clone(), len),
+                ty => ty.clone(),
+            };
+            return Ok((Initializer::List(list), ty));
+        }
+        let e = self.assignment()?;
+        if is_aggregate && !matches!(ty, Type::Defined(_)) {
+            return self.error(e.span, "array initializer must be a list");
+        }
+        let e = self.convert(e, ty, "initialization")?;
+        Ok((Initializer::Expr(e), ty.clone()))
+    }
+
+    // Parse an initializer for an aggregate or scalar at `offset`, with or
+    // without braces.  Returns the number of array elements initialized.
+    fn initializer_list(&mut self, ty: &Type<'a>, offset: usize,
+        out: &mut Vec<(usize, Expr<'a>)>) -> Result<usize>
+    {
+        // String literal for a character array.
+        if let (Type::Array(elem, len), Some(Token::String(_))) =
+            (ty, self.peek()?)
+        {
+            if elem.size(&self.structs) == Some(1) {
+                let (bytes, span) = self.string_literal()?;
+                if *len != 0 && bytes.len() > *len + 1 {
+                    return self.error(span, "initializer string is too long");
+                }
+                let count = if *len == 0 { bytes.len() } else { *len };
+                for (i, byte) in bytes.into_iter().take(count).enumerate() {
+                    out.push((offset + i, int(byte as i128, elem.builtin()
+                        .unwrap_or(BuiltInType::Char), span)));
+                }
+                return Ok(count);
+            }
+        }
+
+        let braced = self.eat(Token::Bracket(Bracket::BraceL))?;
+        if !braced {
+            if let Type::Array(..) | Type::Defined(_) = ty {
+                // Brace elision: fall through with members consumed below.
+            } else {
+                let e = self.assignment()?;
+                let e = self.convert(e, ty, "initialization")?;
+                out.push((offset, e));
+                return Ok(1);
+            }
+        }
+
+        let mut count = 0;
+        match ty {
+            Type::Array(elem, len) => {
+                let elem_size = elem.size(&self.structs).unwrap_or(0);
+                let mut index = 0;
+                loop {
+                    if braced && self.eat(Token::Bracket(Bracket::BraceR))? {
+                        return Ok(count);
+                    }
+                    if braced && self.eat(Token::Bracket(Bracket::SquareL))? {
+                        let (i, span) = self.constant_expression()?;
+                        if i < 0 || (*len != 0 && i as usize >= *len) {
+                            return self.error(span, "array index out of bounds");
+                        }
+                        self.expect(Token::Bracket(Bracket::SquareR), "`]`")?;
+                        self.expect_op(Operator::Assign, "`=`")?;
+                        index = i as usize;
+                    } else if *len != 0 && index >= *len {
+                        if braced {
+                            let span = self.peek_span()?;
+                            return self.error(span,
+                                "excess elements in array initializer");
+                        }
+                        return Ok(count);
+                    }
+                    self.initializer_list(elem, offset + index * elem_size,
+                        out)?;
+                    index += 1;
+                    count = count.max(index);
+                    if !self.eat_op(Operator::Separator)? {
+                        if braced {
+                            self.expect(Token::Bracket(Bracket::BraceR), "`}`")?;
+                        }
+                        return Ok(count);
+                    }
+                    if !braced && self.peek()?
+                        == Some(Token::Bracket(Bracket::BraceR))
+                    {
+                        return Ok(count);
+                    }
+                }
+            }
+            Type::Defined(name) => {
+                let def = match self.structs.get(name) {
+                    Some(def) if def.size.is_some() => def.clone(),
+                    _ => return self.error(self.last,
+                        "initializing incomplete type"),
+                };
+                let mut index = 0;
+                loop {
+                    if braced && self.eat(Token::Bracket(Bracket::BraceR))? {
+                        return Ok(1);
+                    }
+                    if braced && self.eat_op(Operator::Dot)? {
+                        let (field, span) = self.expect_identifier()?;
+                        index = match def.fields.iter()
+                            .position(|f| f.name == field)
+                        {
+                            Some(index) => index,
+                            None => return self.error(span, format!(
+                                "no member named `{}`", field)),
+                        };
+                        self.expect_op(Operator::Assign, "`=`")?;
+                    } else if index >= def.fields.len()
+                        || (def.union && index > 0)
+                    {
+                        if braced {
+                            let span = self.peek_span()?;
+                            return self.error(span,
+                                "excess elements in struct initializer");
+                        }
+                        return Ok(1);
+                    }
+                    let field = &def.fields[index];
+                    self.initializer_list(&field.ty, offset + field.offset,
+                        out)?;
+                    index += 1;
+                    if !self.eat_op(Operator::Separator)? {
+                        if braced {
+                            self.expect(Token::Bracket(Bracket::BraceR), "`}`")?;
+                        }
+                        return Ok(1);
+                    }
+                    if !braced && self.peek()?
+                        == Some(Token::Bracket(Bracket::BraceR))
+                    {
+                        return Ok(1);
+                    }
+                }
+            }
+            ty => {
+                // Scalar in braces.
+                let e = self.assignment()?;
+                let e = self.convert(e, ty, "initialization")?;
+                out.push((offset, e));
+                self.eat_op(Operator::Separator)?;
+                self.expect(Token::Bracket(Bracket::BraceR), "`}`")?;
+                Ok(1)
+            }
+        }
+    }
+
+    fn statement(&mut self) -> Result<Stmt<'a>> {
+        let (token, span) = match self.peek()? {
+            Some(token) => (token, self.peek_span()?),
+            None => {
+                let span = self.peek_span()?;
+                return self.error(span, "expected statement");
+            }
+        };
+        match token {
+            Token::Bracket(Bracket::BraceL) => Ok(Stmt::Block(self.block()?)),
+            Token::Operator(Operator::Semicolon) => {
+                self.bump()?;
+                Ok(Stmt::Empty)
+            }
+            Token::Identifier(name)
+                if self.peek_nth(1)?
+                    == Some(Token::Operator(Operator::Colon)) =>
+            {
+                self.bump()?;
+                self.bump()?;
+                if let Some(old) = self.context().labels.insert(name, span) {
+                    let _ = old;
+                    return self.error(span, format!("redefinition of label `{}`",
+                        name));
+                }
+                let stmt = self.labeled_statement()?;
+                Ok(Stmt::Labeled(Label::Named(name), span, Box::new(stmt)))
+            }
+            Token::Keyword(Keyword::Case) => {
+                self.bump()?;
+                let (value, value_span) = self.constant_expression()?;
+                self.expect_op(Operator::Colon, "`:`")?;
+                let switch = match self.context().switches.last_mut() {
+                    Some(switch) => switch,
+                    None => return self.error(span,
+                        "`case` statement not in switch statement"),
+                };
+                let value = switch.ty.builtin().unwrap().wrap(value);
+                if switch.cases.contains(&value) {
+                    return self.error(value_span, "duplicate case value");
+                }
+                switch.cases.push(value);
+                let stmt = self.labeled_statement()?;
+                Ok(Stmt::Labeled(Label::Case(value), span, Box::new(stmt)))
+            }
+            Token::Keyword(Keyword::Default) => {
+                self.bump()?;
+                self.expect_op(Operator::Colon, "`:`")?;
+                let switch = match self.context().switches.last_mut() {
+                    Some(switch) => switch,
+                    None => return self.error(span,
+                        "`default` statement not in switch statement"),
+                };
+                if switch.default {
+                    return self.error(span, "multiple default labels");
+                }
+                switch.default = true;
+                let stmt = self.labeled_statement()?;
+                Ok(Stmt::Labeled(Label::Default, span, Box::new(stmt)))
+            }
+            Token::Keyword(Keyword::If) => {
+                self.bump()?;
+                let cond = self.condition()?;
+                let then = self.statement()?;
+                let otherwise = if self.eat(Token::Keyword(Keyword::Else))? {
+                    Some(Box::new(self.statement()?))
+                } else {
+                    None
+                };
+                Ok(Stmt::If(cond, Box::new(then), otherwise))
+            }
+            Token::Keyword(Keyword::While) => {
+                self.bump()?;
+                let cond = self.condition()?;
+                let body = self.loop_body()?;
+                Ok(Stmt::While(cond, Box::new(body)))
+            }
+            Token::Keyword(Keyword::Do) => {
+                self.bump()?;
+                let body = self.loop_body()?;
+                self.expect(Token::Keyword(Keyword::While), "`while`")?;
+                let cond = self.condition()?;
+                self.expect_op(Operator::Semicolon, "`;`")?;
+                Ok(Stmt::DoWhile(Box::new(body), cond))
+            }
+            Token::Keyword(Keyword::For) => {
+                self.bump()?;
+                self.expect(Token::Bracket(Bracket::ParensL), "`(`")?;
+                self.context().scopes.push(HashMap::new());
+                let result = self.for_statement();
+                self.context().scopes.pop();
+                result
+            }
+            Token::Keyword(Keyword::Switch) => {
+                self.bump()?;
+                self.expect(Token::Bracket(Bracket::ParensL), "`(`")?;
+                let e = self.expression()?;
+                self.expect(Token::Bracket(Bracket::ParensR), "`)`")?;
+                let e = self.rvalue(e);
+                if !e.ty.is_integer() {
+                    return self.error(e.span,
+                        "switch quantity is not an integer");
+                }
+                let e = self.promote(e);
+                self.context().switches.push(SwitchContext {
+                    ty: Type::BuiltIn(e.ty.builtin().unwrap()),
+                    cases: Vec::new(),
+                    default: false,
+                });
+                self.context().loops += 1;
+                let body = self.statement();
+                self.context().loops -= 1;
+                self.context().switches.pop();
+                Ok(Stmt::Switch(e, Box::new(body?)))
+            }
+            Token::Keyword(Keyword::Break) => {
+                self.bump()?;
+                self.expect_op(Operator::Semicolon, "`;`")?;
+                if self.context().loops == 0 {
+                    return self.error(span,
+                        "`break` statement not in loop or switch statement");
+                }
+                Ok(Stmt::Break(span))
+            }
+            Token::Keyword(Keyword::Continue) => {
+                self.bump()?;
+                self.expect_op(Operator::Semicolon, "`;`")?;
+                let context = self.context();
+                if context.loops == context.switches.len() {
+                    return self.error(span,
+                        "`continue` statement not in loop statement");
+                }
+                Ok(Stmt::Continue(span))
+            }
+            Token::Keyword(Keyword::Goto) => {
+                self.bump()?;
+                let (label, label_span) = self.expect_identifier()?;
+                self.expect_op(Operator::Semicolon, "`;`")?;
+                self.context().gotos.push((label, label_span));
+                Ok(Stmt::Goto(label, span))
+            }
+            Token::Keyword(Keyword::Return) => {
+                self.bump()?;
+                let return_type = self.context().return_type.clone();
+                if self.eat_op(Operator::Semicolon)? {
+                    if !return_type.is_void() {
+                        return self.error(span,
+                            "non-void function should return a value");
+                    }
+                    return Ok(Stmt::Return(None, span));
+                }
+                let e = self.expression()?;
+                self.expect_op(Operator::Semicolon, "`;`")?;
+                if return_type.is_void() {
+                    return self.error(e.span,
+                        "void function should not return a value");
+                }
+                let e = self.convert(e, &return_type, "return")?;
+                Ok(Stmt::Return(Some(e), span))
+            }
+            _ => {
+                let e = self.expression()?;
+                self.expect_op(Operator::Semicolon, "`;`")?;
+                Ok(Stmt::Expr(e))
+            }
+        }
+    }
+
+    // The statement after a label, which may be missing at a block's end.
+    fn labeled_statement(&mut self) -> Result<Stmt<'a>> {
+        match self.peek()? {
+            Some(Token::Bracket(Bracket::BraceR)) => Ok(Stmt::Empty),
+            _ => self.statement(),
+        }
+    }
+
+    fn loop_body(&mut self) -> Result<Stmt<'a>> {
+        self.context().loops += 1;
+        let body = self.statement();
+        self.context().loops -= 1;
+        body
+    }
+
+    fn for_statement(&mut self) -> Result<Stmt<'a>> {
+        let mut init = Vec::new();
+        let token = self.peek()?;
+        if self.starts_declaration(&token) {
+            self.local_declaration(&mut init)?;
+        } else if !self.eat_op(Operator::Semicolon)? {
+            init.push(Stmt::Expr(self.expression()?));
+            self.expect_op(Operator::Semicolon, "`;`")?;
+        }
+        let cond = if self.eat_op(Operator::Semicolon)? {
+            None
+        } else {
+            let e = self.expression()?;
+            self.expect_op(Operator::Semicolon, "`;`")?;
+            Some(self.scalar(e)?)
+        };
+        let step = if self.eat(Token::Bracket(Bracket::ParensR))? {
+            None
+        } else {
+            let e = self.expression()?;
+            self.expect(Token::Bracket(Bracket::ParensR), "`)`")?;
+            Some(e)
+        };
+        let body = self.loop_body()?;
+
+        Ok(Stmt::For(init, cond, step, Box::new(body)))
+    }
+
+    // Parse a parenthesized condition.
+    fn condition(&mut self) -> Result<Expr<'a>> {
+        self.expect(Token::Bracket(Bracket::ParensL), "`(`")?;
+        let e = self.expression()?;
+        self.expect(Token::Bracket(Bracket::ParensR), "`)`")?;
+        self.scalar(e)
+    }
+
+    // Check that an expression can be used as a condition.
+    fn scalar(&mut self, e: Expr<'a>) -> Result<Expr<'a>> {
+        let e = self.rvalue(e);
+        if !e.ty.is_scalar() {
+            return self.error(e.span, format!(
+                "expected scalar type, found `{}`", e.ty));
+        }
+        Ok(e)
+    }
+
+    fn constant_expression(&mut self) -> Result<(i128, Span)> {
+        let e = self.conditional()?;
+        if !e.ty.is_integer() {
+            return self.error(e.span, "expected integer constant expression");
+        }
+        match self.constant(&e) {
+            Some(value) => Ok((value, e.span)),
+            None => self.error(e.span, "expected integer constant expression"),
+        }
+    }
+
+    /// Fold an integer constant expression.
+    fn constant(&self, e: &Expr<'a>) -> Option<i128> {
+        let ty = e.ty.builtin();
+        let value = match &e.kind {
+            ExprKind::Int(value) => *value,
+            ExprKind::Float(value) => *value as i128,
+            ExprKind::Global(name) => self.globals.get(name)?.1?,
+            ExprKind::Cast(inner) => {
+                if !e.ty.is_integer() {
+                    return None;
+                }
+                self.constant(inner)?
+            }
+            ExprKind::Unary(op, inner) => {
+                let value = self.constant(inner)?;
+                match op {
+                    UnaryOp::Neg => value.checked_neg()?,
+                    UnaryOp::BitNot => !value,
+                    UnaryOp::Not => (value == 0) as i128,
+                    _ => return None,
+                }
+            }
+            ExprKind::Binary(op, a, b) => {
+                let signed = a.ty.builtin().is_none_or(|t| t.is_signed());
+                let a = self.constant(a)?;
+                match op {
+                    BinaryOp::And if a == 0 => return Some(0),
+                    BinaryOp::Or if a != 0 => return Some(1),
+                    _ => {}
+                }
+                let b = self.constant(b)?;
+                let cmp = |ordering: std::cmp::Ordering| {
+                    let ord = if signed {
+                        a.cmp(&b)
+                    } else {
+                        (a as u128).cmp(&(b as u128))
+                    };
+                    (ord == ordering) as i128
+                };
+                match op {
+                    BinaryOp::Add => a.checked_add(b)?,
+                    BinaryOp::Sub => a.checked_sub(b)?,
+                    BinaryOp::Mul => a.checked_mul(b)?,
+                    BinaryOp::Div => a.checked_div(b)?,
+                    BinaryOp::Rem => a.checked_rem(b)?,
+                    BinaryOp::Shl => a.checked_shl(u32::try_from(b).ok()?)?,
+                    BinaryOp::Shr => a.checked_shr(u32::try_from(b).ok()?)?,
+                    BinaryOp::BitAnd => a & b,
+                    BinaryOp::BitOr => a | b,
+                    BinaryOp::BitXor => a ^ b,
+                    BinaryOp::Lt => cmp(std::cmp::Ordering::Less),
+                    BinaryOp::Gt => cmp(std::cmp::Ordering::Greater),
+                    BinaryOp::Le => 1 - cmp(std::cmp::Ordering::Greater),
+                    BinaryOp::Ge => 1 - cmp(std::cmp::Ordering::Less),
+                    BinaryOp::Eq => (a == b) as i128,
+                    BinaryOp::Ne => (a != b) as i128,
+                    BinaryOp::And | BinaryOp::Or => (b != 0) as i128,
+                }
+            }
+            ExprKind::Conditional(cond, a, b) => {
+                if self.constant(cond)? != 0 {
+                    self.constant(a)?
+                } else {
+                    self.constant(b)?
+                }
+            }
+            _ => return None,
+        };
+        Some(match ty {
+            Some(ty) if ty.is_integer() => ty.wrap(value),
+            _ => value,
+        })
+    }
+
+    // Null pointer constants convert to any pointer type.
+    fn is_null_constant(&self, e: &Expr<'a>) -> bool {
+        (e.ty.is_integer() && self.constant(e) == Some(0))
+            || (matches!(&e.kind, ExprKind::Cast(inner)
+                if inner.ty.is_integer() && self.constant(inner) == Some(0))
+                && e.ty == Type::Pointer(Box::new(Type::BuiltIn(
+                    BuiltInType::Void))))
+    }
+
+    fn cast(&self, e: Expr<'a>, ty: &Type<'a>) -> Expr<'a> {
+        if e.ty == *ty {
+            return e;
+        }
+        let span = e.span;
+        Expr {
+            kind: boxed(ExprKind::Cast, e),
+            ty: ty.clone(),
+            span,
+        }
+    }
+
+    // Convert arrays and functions to pointers.
+    fn rvalue(&self, e: Expr<'a>) -> Expr<'a> {
+        let ty = match &e.ty {
+            Type::Array(elem, _) => Type::Pointer(elem.clone()),
+            ty @ Type::Function(..) => Type::Pointer(Box::new(ty.clone())),
+            _ => return e,
+        };
+        let span = e.span;
+        Expr { kind: boxed(ExprKind::Decay, e), ty, span }
+    }
+
+    // Integer promotions.
+    fn promote(&self, e: Expr<'a>) -> Expr<'a> {
+        match e.ty.builtin() {
+            Some(ty) if ty.is_integer() && ty.size().unwrap() < 4 => {
+                self.cast(e, &Type::BuiltIn(BuiltInType::SignedInt))
+            }
+            _ => e,
+        }
+    }
+
+    // Usual arithmetic conversions.
+    fn common_type(&self, a: BuiltInType, b: BuiltInType) -> BuiltInType {
+        if a.is_float() || b.is_float() {
+            let float = |t: BuiltInType| if t.is_float() { t.size().unwrap() }
+                else { 0 };
+            return if float(a) >= float(b) { a } else { b };
+        }
+        let promote = |t: BuiltInType| if t.size().unwrap() < 4 {
+            BuiltInType::SignedInt
+        } else {
+            t
+        };
+        let (a, b) = (promote(a), promote(b));
+        if a == b {
+            return a;
+        }
+        let (sa, sb) = (a.size().unwrap(), b.size().unwrap());
+        match (a.is_signed(), b.is_signed()) {
+            (x, y) if x == y => if sa >= sb { a } else { b },
+            (true, false) if sa > sb => a,
+            (false, true) if sb > sa => b,
+            _ => BuiltInType::integer(sa.max(sb), false),
+        }
+    }
+
+    // Implicit conversion as if by assignment.
+    fn convert(&self, e: Expr<'a>, ty: &Type<'a>, what: &str)
+        -> Result<Expr<'a>>
+    {
+        let e = self.rvalue(e);
+        if e.ty == *ty {
+            return Ok(e);
+        }
+        let ok = match (&e.ty, ty) {
+            (a, b) if a.is_arithmetic() && b.is_arithmetic() => true,
+            (Type::Pointer(_), Type::Pointer(_)) => true,
+            (Type::Pointer(_), Type::BuiltIn(b)) if b.is_bool() => true,
+            (_, Type::Pointer(_)) => self.is_null_constant(&e),
+            _ => false,
+        };
+        if !ok {
+            return Err(Diagnostic::new(e.span, format!(
+                "incompatible types in {}: expected `{}`, found `{}`",
+                what, ty, e.ty)));
+        }
+        Ok(self.cast(e, ty))
+    }
+
+    fn is_lvalue(e: &Expr<'a>) -> bool {
+        match &e.kind {
+            ExprKind::Local(_) | ExprKind::Global(_) | ExprKind::String(_) => {
+                true
+            }
+            ExprKind::Unary(UnaryOp::Deref, _) => true,
+            ExprKind::Member(base, _) => Self::is_lvalue(base),
+            _ => false,
+        }
+    }
+
+    // Check for an lvalue that can be assigned to.
+    fn modifiable(&mut self, e: &Expr<'a>) -> Result<()> {
+        if !Self::is_lvalue(e) || matches!(e.kind, ExprKind::String(_)) {
+            return self.error(e.span, "expression is not assignable");
+        }
+        if let Type::Array(..) | Type::Function(..) = e.ty {
+            return self.error(e.span, format!("cannot assign to `{}`", e.ty));
+        }
+        Ok(())
+    }
+
+    fn expression(&mut self) -> Result<Expr<'a>> {
+        let mut e = self.assignment()?;
+        while self.eat_op(Operator::Separator)? {
+            let b = self.assignment()?;
+            let b = self.rvalue(b);
+            let span = e.span.to(b.span);
+            let ty = b.ty.clone();
+            e = Expr {
+                kind: ExprKind::Comma(Box::new(e), Box::new(b)),
+                ty,
+                span,
+            };
+        }
+        Ok(e)
+    }
+
+    fn assignment(&mut self) -> Result<Expr<'a>> {
+        let lhs = self.conditional()?;
+        let op = match self.peek()? {
+            Some(Token::Operator(op)) => match op.assignment() {
+                Some(op) => op,
+                None => return Ok(lhs),
             },
-            Token::MultiLineComment(comment) => {},
-            Token::SingleLineComment(comment) => {},
-            Token::Character(ch) => {},
-            Token::String(text) => {},
-            Token::Identifier(ident) => {},
-            Token::Float(int, mantissa) => {},
-            Token::Int(int) => {},
-            Token::Operator(op) => {},
-            Token::Bracket(br) => {},
+            _ => return Ok(lhs),
         };
-        todo!() // FIXME
+        let (_, op_span) = self.bump()?;
+        let rhs = self.assignment()?;
+        self.modifiable(&lhs)?;
+        let span = lhs.span.to(rhs.span);
+        let ty = lhs.ty.clone();
+
+        let kind = match op {
+            None => {
+                let rhs = self.convert(rhs, &ty, "assignment")?;
+                ExprKind::Assign(Box::new(lhs), Box::new(rhs))
+            }
+            Some(op) => {
+                // Type check `lhs op rhs` to find the calculation type.
+                let probe = self.binary(op, lhs.clone(), rhs, op_span)?;
+                let (lhs_probe, rhs) = match probe.kind {
+                    ExprKind::Binary(_, a, b) => (a, b),
+                    _ => unreachable!(),
+                };
+                let calc_ty = if matches!(ty, Type::Pointer(_)) {
+                    ty.clone()
+                } else {
+                    lhs_probe.ty.clone()
+                };
+                ExprKind::CompoundAssign(op, Box::new(lhs), rhs, calc_ty)
+            }
+        };
+
+        Ok(Expr { kind, ty, span })
+    }
+
+    fn conditional(&mut self) -> Result<Expr<'a>> {
+        let cond = self.binary_expression(1)?;
+        if !self.eat_op(Operator::Question)? {
+            return Ok(cond);
+        }
+        let cond = self.scalar(cond)?;
+        let a = self.expression()?;
+        self.expect_op(Operator::Colon, "`:`")?;
+        let b = self.conditional()?;
+        let (a, b) = (self.rvalue(a), self.rvalue(b));
+        let span = cond.span.to(b.span);
+
+        let ty = match (&a.ty, &b.ty) {
+            (x, y) if x.is_arithmetic() && y.is_arithmetic() => Type::BuiltIn(
+                self.common_type(x.builtin().unwrap(), y.builtin().unwrap())),
+            (Type::Pointer(_), _) if self.is_null_constant(&b) => a.ty.clone(),
+            (_, Type::Pointer(_)) if self.is_null_constant(&a) => b.ty.clone(),
+            (Type::Pointer(x), Type::Pointer(_)) if x.is_void() => a.ty.clone(),
+            (Type::Pointer(_), Type::Pointer(y)) if y.is_void() => b.ty.clone(),
+            (x, y) if x == y => x.clone(),
+            (x, y) => return self.error(span, format!(
+                "incompatible operand types `{}` and `{}`", x, y)),
+        };
+        let (a, b) = if ty.is_void() {
+            (a, b)
+        } else {
+            (self.convert(a, &ty, "conditional")?,
+                self.convert(b, &ty, "conditional")?)
+        };
+
+        Ok(Expr {
+            kind: ExprKind::Conditional(Box::new(cond), Box::new(a),
+                Box::new(b)),
+            ty,
+            span,
+        })
+    }
+
+    fn binary_expression(&mut self, min: u8) -> Result<Expr<'a>> {
+        let mut lhs = self.unary()?;
+        loop {
+            let (op, prec) = match self.peek()? {
+                Some(Token::Operator(op)) => match op.binary() {
+                    Some((op, prec)) if prec >= min => (op, prec),
+                    _ => return Ok(lhs),
+                },
+                _ => return Ok(lhs),
+            };
+            let (_, span) = self.bump()?;
+            let rhs = self.binary_expression(prec + 1)?;
+            lhs = self.binary(op, lhs, rhs, span)?;
+        }
+    }
+
+    // Type check a binary operation.
+    fn binary(&mut self, op: BinaryOp, a: Expr<'a>, b: Expr<'a>, op_span: Span)
+        -> Result<Expr<'a>>
+    {
+        use BinaryOp::*;
+
+        let (mut a, mut b) = (self.rvalue(a), self.rvalue(b));
+        let span = a.span.to(b.span);
+        let int_ty = Type::BuiltIn(BuiltInType::SignedInt);
+        let invalid = |a: &Expr<'a>, b: &Expr<'a>| Diagnostic::new(op_span,
+            format!("invalid operands to binary operator (`{}` and `{}`)",
+                a.ty, b.ty));
+        let complete = |this: &Self, ptr: &Type<'a>| -> Result<()> {
+            match ptr.pointee().and_then(|t| t.size(&this.structs)) {
+                Some(_) => Ok(()),
+                None => Err(Diagnostic::new(op_span,
+                    "arithmetic on a pointer to an incomplete type")),
+            }
+        };
+
+        let ty = match op {
+            And | Or => {
+                if !a.ty.is_scalar() || !b.ty.is_scalar() {
+                    return Err(invalid(&a, &b));
+                }
+                int_ty
+            }
+            Add | Sub if matches!(a.ty, Type::Pointer(_))
+                || matches!(b.ty, Type::Pointer(_)) =>
+            {
+                if op == Add && b.ty.is_scalar() && !b.ty.is_integer()
+                    && a.ty.is_integer()
+                {
+                    std::mem::swap(&mut a, &mut b);
+                }
+                match (&a.ty, &b.ty) {
+                    (Type::Pointer(_), t) if t.is_integer() => {
+                        complete(self, &a.ty)?;
+                        a.ty.clone()
+                    }
+                    (Type::Pointer(_), Type::Pointer(_)) if op == Sub => {
+                        complete(self, &a.ty)?;
+                        Type::BuiltIn(BuiltInType::SsizeT)
+                    }
+                    _ => return Err(invalid(&a, &b)),
+                }
+            }
+            Lt | Gt | Le | Ge | Eq | Ne if matches!(a.ty, Type::Pointer(_))
+                || matches!(b.ty, Type::Pointer(_)) =>
+            {
+                let null_a = self.is_null_constant(&a);
+                let null_b = self.is_null_constant(&b);
+                let eq = op == Eq || op == Ne;
+                match (&a.ty, &b.ty) {
+                    (Type::Pointer(_), Type::Pointer(_)) => {}
+                    (Type::Pointer(_), _) if null_b && eq => {
+                        b = self.cast(b, &a.ty);
+                    }
+                    (_, Type::Pointer(_)) if null_a && eq => {
+                        a = self.cast(a, &b.ty);
+                    }
+                    _ => return Err(invalid(&a, &b)),
+                }
+                int_ty
+            }
+            Shl | Shr => {
+                if !a.ty.is_integer() || !b.ty.is_integer() {
+                    return Err(invalid(&a, &b));
+                }
+                a = self.promote(a);
+                b = self.promote(b);
+                a.ty.clone()
+            }
+            _ => {
+                if !a.ty.is_arithmetic() || !b.ty.is_arithmetic() {
+                    return Err(invalid(&a, &b));
+                }
+                let integer_only = matches!(op, Rem | BitAnd | BitOr | BitXor);
+                if integer_only && (!a.ty.is_integer() || !b.ty.is_integer()) {
+                    return Err(invalid(&a, &b));
+                }
+                let common = Type::BuiltIn(self.common_type(
+                    a.ty.builtin().unwrap(), b.ty.builtin().unwrap()));
+                a = self.cast(a, &common);
+                b = self.cast(b, &common);
+                if op.is_comparison() {
+                    int_ty
+                } else {
+                    common
+                }
+            }
+        };
+
+        Ok(Expr {
+            kind: ExprKind::Binary(op, Box::new(a), Box::new(b)),
+            ty,
+            span,
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expr<'a>> {
+        let (token, span) = match self.peek()? {
+            Some(token) => (token, self.peek_span()?),
+            None => return self.primary(),
+        };
+        let op = match token {
+            Token::Operator(op) => op,
+            Token::Keyword(Keyword::Sizeof) | Token::Keyword(Keyword::Alignof) => {
+                self.bump()?;
+                let align = token == Token::Keyword(Keyword::Alignof);
+                let ty = if self.peek()? == Some(Token::Bracket(Bracket::ParensL))
+                    && { let next = self.peek_nth(1)?;
+                        self.starts_declaration(&next) }
+                {
+                    self.bump()?;
+                    let ty = self.type_name()?;
+                    self.expect(Token::Bracket(Bracket::ParensR), "`)`")?;
+                    ty
+                } else {
+                    self.unary()?.ty
+                };
+                let size = if align {
+                    ty.align(&self.structs)
+                } else {
+                    ty.size(&self.structs)
+                };
+                let span = span.to(self.last);
+                return match size {
+                    Some(size) => Ok(int(size as i128, BuiltInType::SizeT, span)),
+                    None => self.error(span, format!(
+                        "invalid application of `sizeof` to `{}`", ty)),
+                };
+            }
+            Token::Bracket(Bracket::ParensL) => {
+                let next = self.peek_nth(1)?;
+                if !self.starts_declaration(&next) {
+                    return self.postfix();
+                }
+                self.bump()?;
+                let ty = self.type_name()?;
+                self.expect(Token::Bracket(Bracket::ParensR), "`)`")?;
+                if self.peek()? == Some(Token::Bracket(Bracket::BraceL)) {
+                    return self.error(span, "compound literals are not supported");
+                }
+                let e = self.unary()?;
+                let e = self.rvalue(e);
+                let span = span.to(e.span);
+                let ok = ty.is_void() || (ty.is_scalar() && e.ty.is_scalar()
+                    && !(matches!(ty, Type::Pointer(_)) && e.ty.is_arithmetic()
+                        && !e.ty.is_integer())
+                    && !(matches!(e.ty, Type::Pointer(_)) && ty.is_arithmetic()
+                        && !ty.is_integer()));
+                if !ok {
+                    return self.error(span, format!(
+                        "invalid cast from `{}` to `{}`", e.ty, ty));
+                }
+                return Ok(Expr { kind: boxed(ExprKind::Cast, e), ty, span });
+            }
+            _ => return self.postfix(),
+        };
+
+        match op {
+            Operator::Increment | Operator::Decrement => {
+                self.bump()?;
+                let e = self.unary()?;
+                self.inc_dec(e, op == Operator::Increment, true, span)
+            }
+            Operator::Ampersand => {
+                self.bump()?;
+                let e = self.unary()?;
+                let span = span.to(e.span);
+                if !Self::is_lvalue(&e) && !matches!(e.ty, Type::Function(..)) {
+                    return self.error(span,
+                        "cannot take the address of an rvalue");
+                }
+                let ty = Type::Pointer(Box::new(e.ty.clone()));
+                Ok(Expr { kind: ExprKind::Unary(UnaryOp::AddrOf, Box::new(e)),
+                    ty, span })
+            }
+            Operator::Star => {
+                self.bump()?;
+                let e = self.unary()?;
+                let e = self.rvalue(e);
+                let span = span.to(e.span);
+                let ty = match &e.ty {
+                    Type::Pointer(ty) if !ty.is_void() => (**ty).clone(),
+                    ty => return self.error(span, format!(
+                        "cannot dereference `{}`", ty)),
+                };
+                Ok(Expr { kind: ExprKind::Unary(UnaryOp::Deref, Box::new(e)),
+                    ty, span })
+            }
+            Operator::Plus | Operator::Minus | Operator::Tilde => {
+                self.bump()?;
+                let e = self.unary()?;
+                let e = self.rvalue(e);
+                let span = span.to(e.span);
+                let ok = if op == Operator::Tilde {
+                    e.ty.is_integer()
+                } else {
+                    e.ty.is_arithmetic()
+                };
+                if !ok {
+                    return self.error(span, format!(
+                        "invalid argument type `{}` to unary expression", e.ty));
+                }
+                let e = self.promote(e);
+                if op == Operator::Plus {
+                    return Ok(e);
+                }
+                let ty = e.ty.clone();
+                let op = if op == Operator::Minus {
+                    UnaryOp::Neg
+                } else {
+                    UnaryOp::BitNot
+                };
+                Ok(Expr { kind: ExprKind::Unary(op, Box::new(e)), ty, span })
+            }
+            Operator::Bang => {
+                self.bump()?;
+                let e = self.unary()?;
+                let e = self.scalar(e)?;
+                let span = span.to(e.span);
+                Ok(Expr {
+                    kind: ExprKind::Unary(UnaryOp::Not, Box::new(e)),
+                    ty: Type::BuiltIn(BuiltInType::SignedInt),
+                    span,
+                })
+            }
+            _ => self.postfix(),
+        }
+    }
+
+    fn inc_dec(&mut self, e: Expr<'a>, increment: bool, prefix: bool,
+        span: Span) -> Result<Expr<'a>>
+    {
+        self.modifiable(&e)?;
+        let span = span.to(e.span);
+        if !e.ty.is_scalar() {
+            return self.error(span, format!(
+                "cannot increment value of type `{}`", e.ty));
+        }
+        if let Type::Pointer(ty) = &e.ty {
+            if ty.size(&self.structs).is_none() {
+                return self.error(span,
+                    "arithmetic on a pointer to an incomplete type");
+            }
+        }
+        let ty = e.ty.clone();
+        Ok(Expr {
+            kind: ExprKind::IncDec { target: Box::new(e), increment, prefix },
+            ty,
+            span,
+        })
+    }
+
+    fn postfix(&mut self) -> Result<Expr<'a>> {
+        let mut e = self.primary()?;
+        loop {
+            let (token, span) = match self.peek()? {
+                Some(token) => (token, self.peek_span()?),
+                None => return Ok(e),
+            };
+            e = match token {
+                Token::Bracket(Bracket::SquareL) => {
+                    self.bump()?;
+                    let index = self.expression()?;
+                    self.expect(Token::Bracket(Bracket::SquareR), "`]`")?;
+                    let sum = self.binary(BinaryOp::Add, e, index, span)?;
+                    let span = sum.span.to(self.last);
+                    let ty = match &sum.ty {
+                        Type::Pointer(ty) => (**ty).clone(),
+                        _ => return self.error(span,
+                            "subscripted value is not an array or pointer"),
+                    };
+                    Expr { kind: ExprKind::Unary(UnaryOp::Deref,
+                        Box::new(sum)), ty, span }
+                }
+                Token::Bracket(Bracket::ParensL) => {
+                    self.bump()?;
+                    self.call(e)?
+                }
+                Token::Operator(Operator::Dot)
+                | Token::Operator(Operator::Arrow) => {
+                    self.bump()?;
+                    let (field, field_span) = self.expect_identifier()?;
+                    let span = e.span.to(field_span);
+                    let base = if token == Token::Operator(Operator::Arrow) {
+                        let e = self.rvalue(e);
+                        let ty = match &e.ty {
+                            Type::Pointer(ty) => (**ty).clone(),
+                            ty => return self.error(span, format!(
+                                "member reference type `{}` is not a pointer",
+                                ty)),
+                        };
+                        let span = e.span;
+                        Expr { kind: ExprKind::Unary(UnaryOp::Deref,
+                            Box::new(e)), ty, span }
+                    } else {
+                        e
+                    };
+                    let name = match base.ty {
+                        Type::Defined(name) => name,
+                        ref ty => return self.error(span, format!(
+                            "member reference base type `{}` is not a struct",
+                            ty)),
+                    };
+                    let found = self.structs.get(name).and_then(|def| {
+                        def.fields.iter().find(|f| f.name == field)
+                    });
+                    let (ty, offset) = match found {
+                        Some(f) => (f.ty.clone(), f.offset),
+                        None => return self.error(field_span, format!(
+                            "no member named `{}` in `{}`", field, name)),
+                    };
+                    Expr { kind: ExprKind::Member(Box::new(base), offset), ty,
+                        span }
+                }
+                Token::Operator(op @ Operator::Increment)
+                | Token::Operator(op @ Operator::Decrement) => {
+                    self.bump()?;
+                    let span = e.span.to(span);
+                    self.inc_dec(e, op == Operator::Increment, false, span)?
+                }
+                _ => return Ok(e),
+            };
+        }
+    }
+
+    fn call(&mut self, callee: Expr<'a>) -> Result<Expr<'a>> {
+        let callee = self.rvalue(callee);
+        let (ret, params, variadic) = match callee.ty.pointee() {
+            Some(Type::Function(ret, params, variadic)) => {
+                ((**ret).clone(), params.clone(), *variadic)
+            }
+            _ => return self.error(callee.span, format!(
+                "called object type `{}` is not a function", callee.ty)),
+        };
+        let mut args = Vec::new();
+        if !self.eat(Token::Bracket(Bracket::ParensR))? {
+            loop {
+                let arg = self.assignment()?;
+                let arg = if let Some(param) = params.get(args.len()) {
+                    self.convert(arg, param, "argument")?
+                } else {
+                    // Default argument promotions.
+                    let arg = self.promote(self.rvalue(arg));
+                    match arg.ty.builtin() {
+                        Some(ty) if ty.is_float() && ty.size() < Some(8) => {
+                            self.cast(arg, &Type::BuiltIn(BuiltInType::Double))
+                        }
+                        _ => arg,
+                    }
+                };
+                args.push(arg);
+                if !self.eat_op(Operator::Separator)? {
+                    self.expect(Token::Bracket(Bracket::ParensR), "`)`")?;
+                    break;
+                }
+            }
+        }
+        let span = callee.span.to(self.last);
+        if args.len() < params.len() || (args.len() > params.len() && !variadic)
+        {
+            return self.error(span, format!(
+                "expected {} arguments, found {}", params.len(), args.len()));
+        }
+        Ok(Expr { kind: ExprKind::Call(Box::new(callee), args), ty: ret, span })
+    }
+
+    // Parse adjacent string literals.
+    fn string_literal(&mut self) -> Result<(Vec<u8>, Span)> {
+        let mut bytes = Vec::new();
+        let mut span = self.peek_span()?;
+        while let Some(Token::String(text)) = self.peek()? {
+            let (_, s) = self.bump()?;
+            bytes.extend(unescape(text, s)?);
+            span = span.to(s);
+        }
+        bytes.push(0);
+        Ok((bytes, span))
+    }
+
+    // Parse the arguments of a builtin of `<stdarg.h>`.
+    fn va_builtin(&mut self, name: &str, span: Span) -> Result<Expr<'a>> {
+        self.expect(Token::Bracket(Bracket::ParensL), "`(`")?;
+        let list = self.va_list_operand()?;
+        let void = Type::BuiltIn(BuiltInType::Void);
+        let kind = match name {
+            "__builtin_va_start" => {
+                // The name of the last parameter may follow.
+                if self.eat_op(Operator::Separator)? {
+                    self.assignment()?;
+                }
+                if !self.function.as_ref().is_some_and(|f| f.variadic) {
+                    return self.error(span, "`va_start` used in a function \
+                        with fixed arguments");
+                }
+                ExprKind::VaStart(Box::new(list))
+            }
+            "__builtin_va_arg" => {
+                self.expect_op(Operator::Separator, "`,`")?;
+                let start = self.peek_span()?;
+                let ty = self.type_name()?;
+                let type_span = start.to(self.last);
+                self.expect(Token::Bracket(Bracket::ParensR), "`)`")?;
+                let promoted = match ty.builtin() {
+                    Some(t) if t.is_float() && t.size() < Some(8) => {
+                        Some(BuiltInType::Double)
+                    }
+                    Some(t) if t.is_integer() && t.size() < Some(4) => {
+                        Some(BuiltInType::SignedInt)
+                    }
+                    _ => None,
+                };
+                if let Some(promoted) = promoted {
+                    return self.error(type_span, format!("arguments of type \
+                        `{}` are promoted to `{}` when passed through `...`",
+                        ty, promoted));
+                }
+                if ty.is_void() || matches!(ty, Type::Array(..)
+                    | Type::Function(..)) || ty.size(&self.structs).is_none()
+                {
+                    return self.error(type_span, format!(
+                        "no argument of type `{}` can be passed through \
+                        `...`", ty));
+                }
+                let span = span.to(self.last);
+                let kind = boxed(ExprKind::VaArg, list);
+                return Ok(Expr { kind, ty, span });
+            }
+            "__builtin_va_copy" => {
+                self.expect_op(Operator::Separator, "`,`")?;
+                let src = self.va_list_operand()?;
+                let deref = |e: Expr<'a>| Expr {
+                    span: e.span,
+                    kind: ExprKind::Unary(UnaryOp::Deref, Box::new(e)),
+                    ty: Type::Defined(VA_LIST_TAG),
+                };
+                let (dst, src) = (deref(list), deref(src));
+                let assign = Expr {
+                    span: dst.span.to(src.span),
+                    kind: ExprKind::Assign(Box::new(dst), Box::new(src)),
+                    ty: Type::Defined(VA_LIST_TAG),
+                };
+                boxed(ExprKind::Cast, assign)
+            }
+            _ => boxed(ExprKind::Cast, list),
+        };
+        self.expect(Token::Bracket(Bracket::ParensR), "`)`")?;
+        Ok(Expr { kind, ty: void, span: span.to(self.last) })
+    }
+
+    // Parse a `va_list` as an argument of a builtin: the address of its
+    // `__va_list_tag`.
+    fn va_list_operand(&mut self) -> Result<Expr<'a>> {
+        let e = self.assignment()?;
+        let e = self.rvalue(e);
+        if e.ty != Type::Pointer(Box::new(Type::Defined(VA_LIST_TAG))) {
+            return self.error(e.span, format!("expected a `va_list`, found \
+                `{}`", e.ty));
+        }
+        Ok(e)
+    }
+
+    fn primary(&mut self) -> Result<Expr<'a>> {
+        let span = self.peek_span()?;
+        let token = match self.peek()? {
+            Some(token) => token,
+            None => return self.error(span, "expected expression"),
+        };
+        if let Token::String(_) = token {
+            let (bytes, span) = self.string_literal()?;
+            let ty = Type::Array(Box::new(Type::BuiltIn(BuiltInType::Char)),
+                bytes.len());
+            return Ok(Expr { kind: ExprKind::String(bytes), ty, span });
+        }
+        self.bump()?;
+        Ok(match token {
+            Token::Int(value, ty) => int(value, ty, span),
+            Token::Float(value, ty) => Expr {
+                kind: ExprKind::Float(value),
+                ty: Type::BuiltIn(ty),
+                span,
+            },
+            Token::Character(ch) => int(ch as i128, BuiltInType::SignedInt, span),
+            Token::Keyword(Keyword::True) => int(1, BuiltInType::Bool, span),
+            Token::Keyword(Keyword::False) => int(0, BuiltInType::Bool, span),
+            Token::Keyword(Keyword::Nullptr) => {
+                let void_ptr = Type::Pointer(Box::new(Type::BuiltIn(
+                    BuiltInType::Void)));
+                self.cast(int(0, BuiltInType::SignedInt, span), &void_ptr)
+            }
+            Token::Bracket(Bracket::ParensL) => {
+                let e = self.expression()?;
+                self.expect(Token::Bracket(Bracket::ParensR), "`)`")?;
+                Expr { span: span.to(self.last), ..e }
+            }
+            Token::Identifier(name) => {
+                if let Some(id) = self.local(name) {
+                    let ty = self.function.as_ref().unwrap().locals[id].ty.clone();
+                    Expr { kind: ExprKind::Local(id), ty, span }
+                } else if let Some(value) = self.enums.get(name) {
+                    int(*value, BuiltInType::SignedInt, span)
+                } else if let Some((ty, _)) = self.globals.get(name) {
+                    Expr { kind: ExprKind::Global(name), ty: ty.clone(), span }
+                } else if let Some(ty) = self.functions.get(name) {
+                    Expr { kind: ExprKind::Function(name), ty: ty.clone(), span }
+                } else if VA_BUILTINS.contains(&name) {
+                    return self.va_builtin(name, span);
+                } else {
+                    return self.error(span, format!(
+                        "use of undeclared identifier `{}`", name));
+                }
+            }
+            _ => return self.error(span, "expected expression"),
+        })
+    }
+}
+
+impl<'a> Iterator for ItemIterator<'a> {
+    type Item = Result<Item<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(Ok(item));
+            }
+            if self.failed {
+                return None;
+            }
+            match self.peek() {
+                Ok(None) => return None,
+                Ok(Some(_)) => {}
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            }
+            if let Err(e) = self.external_declaration() {
+                self.failed = true;
+                self.pending.clear();
+                return Some(Err(e));
+            }
+        }
     }
 }
 
+#[derive(Debug, Clone)]
 pub struct Block<'a> {
-    _items: Vec<Item<'a>>,
+    pub stmts: Vec<Stmt<'a>>,
 }
 
 fn begin_text(input: &str) -> (Option<CChunk>, Option<char>) {
@@ -418,20 +3311,20 @@ fn begin_text(input: &str) -> (Option<CChunk>, Option<char>) {
         (Some(CChunk::SingleLineComment), Some('\\'))
     } else if input.starts_with("/*") {
         (Some(CChunk::MultiLineComment), None)
-    } else if input.starts_with("\"") {
+    } else if input.starts_with('"') {
         (Some(CChunk::String), Some('\\'))
-    } else if input.starts_with("'") {
+    } else if input.starts_with('\'') {
         (Some(CChunk::Character), Some('\\'))
     } else {
         (None, None)
     }
 }
 
-fn end_text(input: &str, chunk: &CChunk) -> (bool, usize) {
+fn end_text(input: &str, chunk: &mut CChunk) -> (bool, usize) {
     match chunk {
-        CChunk::SingleLineComment => (input.starts_with("\n"), 1),
+        CChunk::SingleLineComment => (input.starts_with('\n'), 1),
         CChunk::MultiLineComment => (input.starts_with("*/"), 2),
-        CChunk::String => (input.starts_with("\""), 1),
-        CChunk::Character => (input.starts_with("'"), 1),
+        CChunk::String => (input.starts_with('"'), 1),
+        CChunk::Character => (input.starts_with('\''), 1),
     }
 }
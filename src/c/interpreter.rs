@@ -0,0 +1,1743 @@
+// C interpreter
+//
+//! Interpreter for the typed C AST, used to evaluate `constexpr` objects and
+//! to test the front end without a back end.
+//!
+//! Integers have the fixed sizes of [`BuiltInType`].
+//! Memory is a set of byte-addressed allocations, and every pointer remembers
+//! the allocation it was derived from (its provenance), so out-of-bounds
+//! accesses, use-after-free and dereferencing integers cast to pointers are
+//! caught.  Undefined behavior is reported as a [`Diagnostic`] pointing at the
+//! expression that caused it.
+//!
+//! The extra arguments of a call to a variadic function are laid out in an
+//! allocation of their own, which a `va_list` points into, so reading past
+//! the last of them with `va_arg` is caught too.
+
+use std::collections::{BTreeMap, HashMap};
+
+use super::{
+    BinaryOp, Block, BuiltInType, Expr, ExprKind, Initializer, Item,
+    ItemIterator, Label, Prototype, Stmt, Structs, Type, UnaryOp,
+};
+use crate::{Diagnostic, Span};
+
+// Bytes of host stack to allow for each call the interpreted program makes,
+// enough for an unoptimized build.
+const STACK_PER_CALL: usize = 128 << 10;
+
+/// A pointer into an allocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pointer {
+    /// The allocation this pointer is derived from, `None` for null and
+    /// pointers made from integers.
+    pub alloc: Option<usize>,
+    /// Byte offset into the allocation (the address if there isn't one).
+    pub offset: i128,
+}
+
+impl Pointer {
+    /// The null pointer.
+    pub const NULL: Pointer = Pointer { alloc: None, offset: 0 };
+}
+
+/// Bytes of memory, with which bytes are initialized and where pointers are.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Bytes {
+    data: Vec<u8>,
+    init: Vec<bool>,
+    // Offset of a stored pointer => its allocation.
+    provenance: BTreeMap<usize, usize>,
+}
+
+impl Bytes {
+    fn new(data: Vec<u8>) -> Self {
+        let init = vec![true; data.len()];
+        Bytes { data, init, provenance: BTreeMap::new() }
+    }
+
+    fn uninit(len: usize) -> Self {
+        Bytes {
+            data: vec![0; len],
+            init: vec![false; len],
+            provenance: BTreeMap::new(),
+        }
+    }
+
+    /// The raw bytes.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    fn slice(&self, start: usize, len: usize) -> Bytes {
+        Bytes {
+            data: self.data[start..start + len].to_vec(),
+            init: self.init[start..start + len].to_vec(),
+            provenance: self
+                .provenance
+                .range(start..start + len)
+                .map(|(k, v)| (k - start, *v))
+                .collect(),
+        }
+    }
+
+    fn write(&mut self, start: usize, bytes: &Bytes) {
+        let len = bytes.data.len();
+        self.data[start..start + len].copy_from_slice(&bytes.data);
+        self.init[start..start + len].copy_from_slice(&bytes.init);
+        // Overwriting any byte of a pointer loses its provenance.
+        let stale: Vec<usize> = self
+            .provenance
+            .range(start.saturating_sub(7)..start + len)
+            .map(|(k, _)| *k)
+            .collect();
+        for k in stale {
+            self.provenance.remove(&k);
+        }
+        for (k, v) in &bytes.provenance {
+            self.provenance.insert(start + k, *v);
+        }
+    }
+}
+
+/// A value produced by evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Result of a `void` expression
+    Void,
+    /// Any integer type, wrapped to its range (`unsigned __int128` values
+    /// above `i128::MAX` are stored as their bit pattern)
+    Int(i128),
+    /// Any floating point type, rounded to its precision
+    Float(f64),
+    Pointer(Pointer),
+    /// A struct or array
+    Aggregate(Bytes),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Value::Void => write!(f, "void"),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Pointer(Pointer { alloc: Some(alloc), offset }) => {
+                write!(f, "alloc{}+{}", alloc, offset)
+            }
+            Value::Pointer(Pointer { alloc: None, offset }) => {
+                write!(f, "{:#x}", offset)
+            }
+            Value::Aggregate(bytes) => write!(f, "{:02x?}", bytes.data),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AllocKind<'a> {
+    Stack,
+    Heap,
+    Global,
+    Literal,
+    Function(&'a str),
+}
+
+struct Allocation<'a> {
+    bytes: Bytes,
+    kind: AllocKind<'a>,
+    live: bool,
+    base: i128,
+}
+
+// Why evaluation stopped early.
+enum Stop {
+    Error(Diagnostic),
+    Exit(i32),
+}
+
+impl From<Diagnostic> for Stop {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Stop::Error(diagnostic)
+    }
+}
+
+type Eval<T> = Result<T, Stop>;
+
+fn ub<T>(span: Span, message: impl std::fmt::Display) -> Eval<T> {
+    Err(Stop::Error(Diagnostic::new(
+        span,
+        format!("undefined behavior: {}", message),
+    )))
+}
+
+fn error<T>(span: Span, message: impl Into<String>) -> Eval<T> {
+    Err(Stop::Error(Diagnostic::new(span, message)))
+}
+
+// How a statement finished.
+enum Flow<'a> {
+    Normal,
+    Break,
+    Continue,
+    Return(Option<Value>),
+    Goto(&'a str),
+}
+
+struct Frame<'a, 'b> {
+    function: &'b Prototype<'a>,
+    locals: Vec<Option<usize>>,
+    // The extra arguments of a variadic function, for `va_arg`.
+    varargs: Option<usize>,
+}
+
+/// An interpreter for a parsed C translation unit.
+pub struct Interpreter<'a, 'b> {
+    functions: HashMap<&'a str, &'b Prototype<'a>>,
+    function_allocs: HashMap<&'a str, usize>,
+    structs: Structs<'a>,
+    globals: HashMap<&'a str, (usize, Type<'a>)>,
+    literals: HashMap<usize, usize>,
+    allocs: Vec<Allocation<'a>>,
+    next_base: i128,
+    frames: Vec<Frame<'a, 'b>>,
+    output: Vec<u8>,
+    steps: u64,
+    step_limit: u64,
+    depth_limit: usize,
+}
+
+impl<'a, 'b> Interpreter<'a, 'b> {
+    /// Create an interpreter, allocating globals and evaluating their
+    /// initializers in order.
+    pub fn new(items: &'b [Item<'a>]) -> Result<Self, Diagnostic> {
+        let mut interpreter = Interpreter {
+            functions: HashMap::new(),
+            function_allocs: HashMap::new(),
+            structs: HashMap::new(),
+            globals: HashMap::new(),
+            literals: HashMap::new(),
+            allocs: Vec::new(),
+            next_base: 0x1000,
+            frames: Vec::new(),
+            output: Vec::new(),
+            steps: 0,
+            step_limit: 10_000_000,
+            depth_limit: 200,
+        };
+
+        for item in items {
+            match item {
+                Item::Struct(def) => {
+                    interpreter.structs.insert(def.name, def.clone());
+                }
+                Item::Prototype(prototype) => {
+                    let defined = interpreter
+                        .functions
+                        .get(prototype.name)
+                        .is_some_and(|p| p.block.is_some());
+                    if !defined {
+                        interpreter.functions.insert(prototype.name, prototype);
+                    }
+                    if !interpreter.function_allocs.contains_key(prototype.name)
+                    {
+                        let alloc = interpreter.allocate(
+                            Bytes::new(Vec::new()),
+                            AllocKind::Function(prototype.name),
+                        );
+                        interpreter.function_allocs.insert(prototype.name, alloc);
+                    }
+                }
+                Item::Global(_) | Item::Typedef(..) => {}
+            }
+        }
+
+        // Static storage is zero-initialized.
+        for item in items {
+            if let Item::Global(global) = item {
+                let var = &global.var;
+                let size = var.ty.size(&interpreter.structs).unwrap_or(0);
+                match interpreter.globals.get(var.name) {
+                    Some((alloc, _))
+                        if interpreter.allocs[*alloc].bytes.data.len()
+                            >= size => {}
+                    _ => {
+                        let alloc = interpreter.allocate(
+                            Bytes::new(vec![0; size]),
+                            AllocKind::Global,
+                        );
+                        interpreter
+                            .globals
+                            .insert(var.name, (alloc, var.ty.clone()));
+                    }
+                }
+            }
+        }
+
+        for item in items {
+            if let Item::Global(global) = item {
+                if let Some(init) = &global.init {
+                    let (alloc, _) = interpreter.globals[global.var.name];
+                    let ptr = Pointer { alloc: Some(alloc), offset: 0 };
+                    interpreter
+                        .initialize(ptr, &global.var.ty, init, global.var.span)
+                        .map_err(Self::diagnostic)?;
+                }
+            }
+        }
+
+        Ok(interpreter)
+    }
+
+    /// Limit the number of evaluation steps, to stop infinite loops.
+    pub fn set_step_limit(&mut self, limit: u64) {
+        self.step_limit = limit;
+    }
+
+    /// Limit the depth of function calls.
+    pub fn set_depth_limit(&mut self, limit: usize) {
+        self.depth_limit = limit;
+    }
+
+    /// Everything written by `printf`, `puts` and `putchar`.
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    // Evaluate on a thread with a stack deep enough for the depth limit, so
+    // that the limit is reached before the stack of the host runs out.
+    fn deep<T: Send>(&mut self, f: impl FnOnce(&mut Self) -> T + Send) -> T
+    where
+        Self: Send,
+    {
+        let size = (1 << 20) + self.depth_limit * STACK_PER_CALL;
+        std::thread::scope(|scope| {
+            std::thread::Builder::new()
+                .stack_size(size)
+                .spawn_scoped(scope, || f(self))
+                .expect("failed to start the interpreter thread")
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        })
+    }
+
+    /// Run `main()`, returning the exit code.
+    pub fn run_main(&mut self) -> Result<i32, Diagnostic> {
+        let result = self.deep(|interpreter| {
+            interpreter.call_named("main", Vec::new(), None, Span::default())
+        });
+        match result {
+            Ok(value) => Ok(match value {
+                Some(Value::Int(code)) => code as i32,
+                _ => 0,
+            }),
+            Err(Stop::Exit(code)) => Ok(code),
+            Err(Stop::Error(e)) => Err(e),
+        }
+    }
+
+    /// Call a function by name.
+    pub fn call(&mut self, name: &str, args: Vec<Value>)
+        -> Result<Value, Diagnostic>
+    {
+        let name = match self.functions.keys().find(|n| **n == name) {
+            Some(name) => *name,
+            None => {
+                return Err(Diagnostic::new(
+                    Span::default(),
+                    format!("no function named `{}`", name),
+                ))
+            }
+        };
+        self.deep(|interpreter| {
+            interpreter.call_named(name, args, None, Span::default())
+        })
+            .map(|value| value.unwrap_or(Value::Void))
+            .map_err(Self::diagnostic)
+    }
+
+    /// Read the value of a global variable, such as a `constexpr` object.
+    pub fn global(&mut self, name: &str) -> Result<Value, Diagnostic> {
+        let (alloc, ty) = match self.globals.iter().find(|(n, _)| **n == name)
+        {
+            Some((_, global)) => global.clone(),
+            None => {
+                return Err(Diagnostic::new(
+                    Span::default(),
+                    format!("no global named `{}`", name),
+                ))
+            }
+        };
+        let ptr = Pointer { alloc: Some(alloc), offset: 0 };
+        self.load(ptr, &ty, Span::default()).map_err(Self::diagnostic)
+    }
+
+    /// Evaluate an expression outside of any function.
+    pub fn eval(&mut self, e: &Expr<'a>) -> Result<Value, Diagnostic> {
+        self.expr(e).map_err(Self::diagnostic)
+    }
+
+    /// Read a zero-terminated string from memory.
+    pub fn read_string(&mut self, ptr: Pointer) -> Result<Vec<u8>, Diagnostic> {
+        self.c_string(ptr, Span::default()).map_err(Self::diagnostic)
+    }
+
+    fn diagnostic(stop: Stop) -> Diagnostic {
+        match stop {
+            Stop::Error(e) => e,
+            Stop::Exit(code) => Diagnostic::new(
+                Span::default(),
+                format!("program exited with code {}", code),
+            ),
+        }
+    }
+
+    fn allocate(&mut self, bytes: Bytes, kind: AllocKind<'a>) -> usize {
+        let base = (self.next_base + 15) / 16 * 16;
+        // Leave a gap so one-past-the-end never aliases the next allocation.
+        self.next_base = base + bytes.data.len() as i128 + 16;
+        self.allocs.push(Allocation { bytes, kind, live: true, base });
+        self.allocs.len() - 1
+    }
+
+    fn size(&self, ty: &Type<'a>, span: Span) -> Eval<usize> {
+        match ty.size(&self.structs) {
+            Some(size) => Ok(size),
+            None => error(span, format!("type `{}` has no size", ty)),
+        }
+    }
+
+    fn step(&mut self, span: Span) -> Eval<()> {
+        self.steps += 1;
+        if self.steps > self.step_limit {
+            return error(span, "evaluation step limit exceeded");
+        }
+        Ok(())
+    }
+
+    // Check that `size` bytes at `ptr` can be accessed.
+    fn check(&self, ptr: Pointer, size: usize, write: bool, span: Span)
+        -> Eval<(usize, usize)>
+    {
+        let alloc_id = match ptr.alloc {
+            Some(alloc) => alloc,
+            None if ptr.offset == 0 => return ub(span, "null pointer dereference"),
+            None => {
+                return ub(span, "dereferencing a pointer without provenance \
+                    (made from an integer)")
+            }
+        };
+        let alloc = &self.allocs[alloc_id];
+        if !alloc.live {
+            return match alloc.kind {
+                AllocKind::Heap => ub(span, "use after free"),
+                _ => ub(span, "use of a local variable after its lifetime \
+                    ended"),
+            };
+        }
+        if let AllocKind::Function(name) = alloc.kind {
+            return ub(span, format!("accessing function `{}` as an object",
+                name));
+        }
+        let len = alloc.bytes.data.len();
+        if ptr.offset < 0 || ptr.offset as usize + size > len {
+            return ub(span, format!(
+                "out-of-bounds access of {} bytes at offset {} of a {}-byte \
+                allocation", size, ptr.offset, len));
+        }
+        if write && alloc.kind == AllocKind::Literal {
+            return ub(span, "modifying a string literal");
+        }
+        Ok((alloc_id, ptr.offset as usize))
+    }
+
+    fn read(&self, ptr: Pointer, size: usize, span: Span) -> Eval<Bytes> {
+        let (alloc, offset) = self.check(ptr, size, false, span)?;
+        Ok(self.allocs[alloc].bytes.slice(offset, size))
+    }
+
+    fn write(&mut self, ptr: Pointer, bytes: &Bytes, span: Span) -> Eval<()> {
+        let (alloc, offset) = self.check(ptr, bytes.data.len(), true, span)?;
+        self.allocs[alloc].bytes.write(offset, bytes);
+        Ok(())
+    }
+
+    fn address(&self, ptr: Pointer) -> i128 {
+        match ptr.alloc {
+            Some(alloc) => self.allocs[alloc].base + ptr.offset,
+            None => ptr.offset,
+        }
+    }
+
+    fn load(&self, ptr: Pointer, ty: &Type<'a>, span: Span) -> Eval<Value> {
+        let size = self.size(ty, span)?;
+        let bytes = self.read(ptr, size, span)?;
+        if ty.is_scalar() && bytes.init.iter().any(|init| !init) {
+            return ub(span, "read of uninitialized memory");
+        }
+        Ok(self.decode(bytes, ty))
+    }
+
+    fn store(&mut self, ptr: Pointer, ty: &Type<'a>, value: &Value,
+        span: Span) -> Eval<()>
+    {
+        let bytes = self.encode(value, ty, span)?;
+        self.write(ptr, &bytes, span)
+    }
+
+    fn decode(&self, bytes: Bytes, ty: &Type<'a>) -> Value {
+        let mut raw = [0u8; 16];
+        let len = bytes.data.len().min(16);
+        raw[..len].copy_from_slice(&bytes.data[..len]);
+        match ty {
+            Type::BuiltIn(builtin) if builtin.is_float() => {
+                Value::Float(match builtin.size() {
+                    Some(2) => f16_to_f32(u16::from_le_bytes([raw[0], raw[1]]))
+                        as f64,
+                    Some(4) => f32::from_le_bytes([raw[0], raw[1], raw[2],
+                        raw[3]]) as f64,
+                    _ => f64::from_le_bytes([raw[0], raw[1], raw[2], raw[3],
+                        raw[4], raw[5], raw[6], raw[7]]),
+                })
+            }
+            Type::BuiltIn(builtin) => {
+                Value::Int(builtin.wrap(i128::from_le_bytes(raw)))
+            }
+            Type::Pointer(_) => {
+                let address = i128::from_le_bytes(raw);
+                match bytes.provenance.get(&0) {
+                    Some(alloc) => Value::Pointer(Pointer {
+                        alloc: Some(*alloc),
+                        offset: address - self.allocs[*alloc].base,
+                    }),
+                    None => Value::Pointer(Pointer {
+                        alloc: None,
+                        offset: address,
+                    }),
+                }
+            }
+            _ => Value::Aggregate(bytes),
+        }
+    }
+
+    fn encode(&self, value: &Value, ty: &Type<'a>, span: Span)
+        -> Eval<Bytes>
+    {
+        let size = self.size(ty, span)?;
+        Ok(match (value, ty) {
+            (Value::Float(value), Type::BuiltIn(builtin)) => {
+                Bytes::new(match builtin.size() {
+                    Some(2) => f32_to_f16(*value as f32).to_le_bytes().to_vec(),
+                    Some(4) => (*value as f32).to_le_bytes().to_vec(),
+                    _ => {
+                        let mut bytes = value.to_le_bytes().to_vec();
+                        bytes.resize(size, 0);
+                        bytes
+                    }
+                })
+            }
+            (Value::Int(value), Type::BuiltIn(_)) => {
+                Bytes::new(value.to_le_bytes()[..size].to_vec())
+            }
+            (Value::Pointer(ptr), Type::Pointer(_)) => {
+                let address = self.address(*ptr) as u64;
+                let mut bytes = Bytes::new(address.to_le_bytes().to_vec());
+                if let Some(alloc) = ptr.alloc {
+                    bytes.provenance.insert(0, alloc);
+                }
+                bytes
+            }
+            (Value::Aggregate(bytes), _) if bytes.data.len() == size => {
+                bytes.clone()
+            }
+            (value, ty) => {
+                return error(span, format!(
+                    "interpreter bug: can't store {} as `{}`", value, ty))
+            }
+        })
+    }
+
+    fn truth(&self, value: &Value) -> bool {
+        match value {
+            Value::Int(value) => *value != 0,
+            Value::Float(value) => *value != 0.0,
+            Value::Pointer(ptr) => self.address(*ptr) != 0,
+            Value::Void | Value::Aggregate(_) => false,
+        }
+    }
+
+    // Convert a value between types.
+    fn convert(&self, value: Value, from: &Type<'a>, to: &Type<'a>,
+        span: Span) -> Eval<Value>
+    {
+        if to.is_void() {
+            return Ok(Value::Void);
+        }
+        if let Type::BuiltIn(to) = to {
+            if to.is_bool() {
+                return Ok(Value::Int(self.truth(&value) as i128));
+            }
+        }
+        Ok(match (value, to) {
+            (Value::Int(value), Type::BuiltIn(to)) if to.is_float() => {
+                let unsigned = from.builtin().is_some_and(|f| !f.is_signed());
+                let value = if unsigned {
+                    value as u128 as f64
+                } else {
+                    value as f64
+                };
+                Value::Float(round(value, *to))
+            }
+            (Value::Int(value), Type::BuiltIn(to)) => {
+                Value::Int(to.wrap(value))
+            }
+            (Value::Float(value), Type::BuiltIn(to)) if to.is_float() => {
+                Value::Float(round(value, *to))
+            }
+            (Value::Float(value), Type::BuiltIn(to)) => {
+                let (min, max) = to.range();
+                let value = value.trunc();
+                if !value.is_finite() || value < min as f64
+                    || value > max as f64
+                {
+                    return ub(span, format!(
+                        "floating point value {} doesn't fit in `{}`",
+                        value, to));
+                }
+                Value::Int(value as i128)
+            }
+            (Value::Pointer(ptr), Type::BuiltIn(to)) => {
+                Value::Int(to.wrap(self.address(ptr)))
+            }
+            (Value::Int(value), Type::Pointer(_)) => {
+                Value::Pointer(Pointer { alloc: None, offset: value })
+            }
+            (value @ Value::Pointer(_), Type::Pointer(_)) => value,
+            (value @ Value::Aggregate(_), _) => value,
+            (value, to) => {
+                return error(span, format!(
+                    "interpreter bug: can't convert {} to `{}`", value, to))
+            }
+        })
+    }
+
+    // Get the address of an lvalue.
+    fn place(&mut self, e: &Expr<'a>) -> Eval<Pointer> {
+        Ok(match &e.kind {
+            ExprKind::Local(id) => {
+                let frame = match self.frames.last() {
+                    Some(frame) => frame,
+                    None => return error(e.span,
+                        "local variable outside of a function"),
+                };
+                let alloc = match frame.locals[*id] {
+                    Some(alloc) => alloc,
+                    // Jumped past the declaration with `goto` or `switch`.
+                    None => self.declare(*id, e.span)?,
+                };
+                Pointer { alloc: Some(alloc), offset: 0 }
+            }
+            ExprKind::Global(name) => {
+                let (alloc, _) = self.globals[name];
+                Pointer { alloc: Some(alloc), offset: 0 }
+            }
+            ExprKind::String(bytes) => {
+                let alloc = match self.literals.get(&e.span.start) {
+                    Some(alloc) => *alloc,
+                    None => {
+                        let alloc = self.allocate(
+                            Bytes::new(bytes.clone()),
+                            AllocKind::Literal,
+                        );
+                        self.literals.insert(e.span.start, alloc);
+                        alloc
+                    }
+                };
+                Pointer { alloc: Some(alloc), offset: 0 }
+            }
+            ExprKind::Function(name) => Pointer {
+                alloc: Some(self.function_allocs[name]),
+                offset: 0,
+            },
+            ExprKind::Unary(UnaryOp::Deref, inner) => match self.expr(inner)? {
+                Value::Pointer(ptr) => ptr,
+                value => return error(e.span, format!(
+                    "interpreter bug: dereferencing {}", value)),
+            },
+            ExprKind::Member(base, offset) => {
+                let ptr = self.place(base)?;
+                Pointer { offset: ptr.offset + *offset as i128, ..ptr }
+            }
+            _ => return error(e.span, "interpreter bug: not an lvalue"),
+        })
+    }
+
+    // Allocate a local variable in the current frame.
+    fn declare(&mut self, id: usize, span: Span) -> Eval<usize> {
+        let ty = self.frames.last().unwrap().function.locals[id].ty.clone();
+        let size = self.size(&ty, span)?;
+        if let Some(old) = self.frames.last_mut().unwrap().locals[id].take() {
+            self.allocs[old].live = false;
+        }
+        let alloc = self.allocate(Bytes::uninit(size), AllocKind::Stack);
+        self.frames.last_mut().unwrap().locals[id] = Some(alloc);
+        Ok(alloc)
+    }
+
+    fn kill(&mut self, id: usize) {
+        if let Some(alloc) = self.frames.last_mut().unwrap().locals[id].take() {
+            self.allocs[alloc].live = false;
+        }
+    }
+
+    fn initialize(&mut self, ptr: Pointer, ty: &Type<'a>,
+        init: &Initializer<'a>, span: Span) -> Eval<()>
+    {
+        match init {
+            Initializer::Expr(e) => {
+                let value = self.expr(e)?;
+                self.store(ptr, ty, &value, e.span)
+            }
+            Initializer::List(list) => {
+                let size = self.size(ty, span)?;
+                self.write(ptr, &Bytes::new(vec![0; size]), span)?;
+                for (offset, e) in list {
+                    let value = self.expr(e)?;
+                    let ptr = Pointer {
+                        offset: ptr.offset + *offset as i128,
+                        ..ptr
+                    };
+                    self.store(ptr, &e.ty, &value, e.span)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn int(&mut self, e: &Expr<'a>) -> Eval<i128> {
+        match self.expr(e)? {
+            Value::Int(value) => Ok(value),
+            value => error(e.span, format!(
+                "interpreter bug: expected integer, found {}", value)),
+        }
+    }
+
+    fn expr(&mut self, e: &Expr<'a>) -> Eval<Value> {
+        self.step(e.span)?;
+        Ok(match &e.kind {
+            ExprKind::Int(value) => Value::Int(*value),
+            ExprKind::Float(value) => match e.ty {
+                Type::BuiltIn(ty) => Value::Float(round(*value, ty)),
+                _ => Value::Float(*value),
+            },
+            ExprKind::Member(base, offset) if !ItemIterator::is_lvalue(base) => {
+                // Member of an rvalue, such as a returned struct.
+                let bytes = match self.expr(base)? {
+                    Value::Aggregate(bytes) => bytes,
+                    value => return error(e.span, format!(
+                        "interpreter bug: member of {}", value)),
+                };
+                let size = self.size(&e.ty, e.span)?;
+                self.decode(bytes.slice(*offset, size), &e.ty)
+            }
+            ExprKind::Local(_) | ExprKind::Global(_) | ExprKind::Member(..)
+            | ExprKind::Unary(UnaryOp::Deref, _) | ExprKind::String(_) => {
+                let ptr = self.place(e)?;
+                self.load(ptr, &e.ty, e.span)?
+            }
+            ExprKind::Function(_) => Value::Pointer(self.place(e)?),
+            ExprKind::Decay(inner) | ExprKind::Unary(UnaryOp::AddrOf, inner) => {
+                Value::Pointer(self.place(inner)?)
+            }
+            ExprKind::Unary(op, inner) => {
+                let value = self.expr(inner)?;
+                let ty = match e.ty {
+                    Type::BuiltIn(ty) => ty,
+                    _ => return error(e.span, "interpreter bug: unary type"),
+                };
+                match (op, value) {
+                    (UnaryOp::Not, value) => {
+                        Value::Int(!self.truth(&value) as i128)
+                    }
+                    (UnaryOp::Neg, Value::Float(value)) => Value::Float(-value),
+                    (UnaryOp::Neg, Value::Int(value)) => Value::Int(
+                        int_binary(BinaryOp::Sub, 0, value, ty, e.span)?),
+                    (UnaryOp::BitNot, Value::Int(value)) => {
+                        Value::Int(ty.wrap(!value))
+                    }
+                    (_, value) => return error(e.span, format!(
+                        "interpreter bug: unary operator on {}", value)),
+                }
+            }
+            ExprKind::Binary(op, a, b) => self.binary(*op, a, b, e)?,
+            ExprKind::Assign(lhs, rhs) => {
+                let ptr = self.place(lhs)?;
+                let value = self.expr(rhs)?;
+                self.store(ptr, &lhs.ty, &value, e.span)?;
+                value
+            }
+            ExprKind::CompoundAssign(op, lhs, rhs, calc_ty) => {
+                let ptr = self.place(lhs)?;
+                let old = self.load(ptr, &lhs.ty, lhs.span)?;
+                let rhs_value = self.expr(rhs)?;
+                let value = if let Type::Pointer(pointee) = &lhs.ty {
+                    let delta = match rhs_value {
+                        Value::Int(delta) => delta,
+                        _ => return error(e.span, "interpreter bug: pointer op"),
+                    };
+                    let delta = if *op == BinaryOp::Sub { -delta } else { delta };
+                    self.offset(old, pointee, delta, e.span)?
+                } else {
+                    let old = self.convert(old, &lhs.ty, calc_ty, e.span)?;
+                    let value = self.arithmetic(*op, old, rhs_value, calc_ty,
+                        e.span)?;
+                    self.convert(value, calc_ty, &lhs.ty, e.span)?
+                };
+                self.store(ptr, &lhs.ty, &value, e.span)?;
+                value
+            }
+            ExprKind::IncDec { target, increment, prefix } => {
+                let ptr = self.place(target)?;
+                let old = self.load(ptr, &target.ty, target.span)?;
+                let delta = if *increment { 1 } else { -1 };
+                let new = match (&old, &target.ty) {
+                    (_, Type::Pointer(pointee)) => {
+                        self.offset(old.clone(), pointee, delta, e.span)?
+                    }
+                    (Value::Float(value), Type::BuiltIn(ty)) => {
+                        Value::Float(round(value + delta as f64, *ty))
+                    }
+                    (Value::Int(value), Type::BuiltIn(ty)) => {
+                        // Small types are promoted to `int`, so only
+                        // wrap when converting back.
+                        if ty.size().unwrap() < 4 || ty.is_bool() {
+                            let value = if ty.is_bool() && !*increment {
+                                1 - *value
+                            } else {
+                                value + delta
+                            };
+                            Value::Int(ty.wrap(value))
+                        } else {
+                            let op = if *increment {
+                                BinaryOp::Add
+                            } else {
+                                BinaryOp::Sub
+                            };
+                            Value::Int(int_binary(op, *value, 1, *ty, e.span)?)
+                        }
+                    }
+                    _ => return error(e.span, "interpreter bug: increment"),
+                };
+                self.store(ptr, &target.ty, &new, e.span)?;
+                if *prefix { new } else { old }
+            }
+            ExprKind::Conditional(cond, a, b) => {
+                let cond = self.expr(cond)?;
+                if self.truth(&cond) {
+                    self.expr(a)?
+                } else {
+                    self.expr(b)?
+                }
+            }
+            ExprKind::Comma(a, b) => {
+                self.expr(a)?;
+                self.expr(b)?
+            }
+            ExprKind::Call(..) => match self.call_expr(e)? {
+                Some(value) => value,
+                None if e.ty.is_void() => Value::Void,
+                None => return ub(e.span, "using the result of a function \
+                    that ended without `return`"),
+            },
+            ExprKind::Cast(inner) => {
+                let value = self.expr(inner)?;
+                self.convert(value, &inner.ty, &e.ty, e.span)?
+            }
+            ExprKind::VaStart(list) => {
+                let buffer = self.frames.last().and_then(|f| f.varargs);
+                let next = Pointer { alloc: buffer, offset: 0 };
+                let ptr = self.va_next(list)?;
+                self.store(ptr, &void_pointer(), &Value::Pointer(next),
+                    e.span)?;
+                Value::Void
+            }
+            ExprKind::VaArg(list) => {
+                let ptr = self.va_next(list)?;
+                let next = match self.load(ptr, &void_pointer(), e.span)? {
+                    Value::Pointer(next) => next,
+                    value => return error(e.span, format!(
+                        "interpreter bug: `va_list` holding {}", value)),
+                };
+                let size = self.size(&e.ty, e.span)?;
+                let align = e.ty.align(&self.structs).unwrap_or(1) as i128;
+                let arg = Pointer {
+                    offset: (next.offset + align - 1) / align * align,
+                    ..next
+                };
+                let len = match arg.alloc {
+                    Some(alloc) => self.allocs[alloc].bytes.data.len() as i128,
+                    None => 0,
+                };
+                if arg.offset + size as i128 > len {
+                    return ub(e.span, format!(
+                        "`va_arg` of type `{}` after the last argument",
+                        e.ty));
+                }
+                let value = self.load(arg, &e.ty, e.span)?;
+                let next = Pointer { offset: arg.offset + size as i128, ..arg };
+                self.store(ptr, &void_pointer(), &Value::Pointer(next),
+                    e.span)?;
+                value
+            }
+        })
+    }
+
+    // Get the address of the next extra argument in a `va_list`.
+    fn va_next(&mut self, list: &Expr<'a>) -> Eval<Pointer> {
+        match self.expr(list)? {
+            Value::Pointer(ptr) => {
+                Ok(Pointer { offset: ptr.offset + 8, ..ptr })
+            }
+            value => error(list.span, format!(
+                "interpreter bug: `va_list` at {}", value)),
+        }
+    }
+
+    // Lay out the extra arguments of a variadic function in memory, each
+    // aligned to its type.
+    fn varargs(&mut self, args: &[Expr<'a>], values: &[Value])
+        -> Eval<usize>
+    {
+        let mut bytes = Bytes::new(Vec::new());
+        for (arg, value) in args.iter().zip(values) {
+            let value = self.encode(value, &arg.ty, arg.span)?;
+            let align = arg.ty.align(&self.structs).unwrap_or(1);
+            let start = bytes.data.len().div_ceil(align) * align;
+            let mut grown = Bytes::uninit(start + value.data.len());
+            grown.write(0, &bytes);
+            grown.write(start, &value);
+            bytes = grown;
+        }
+        Ok(self.allocate(bytes, AllocKind::Stack))
+    }
+
+    // Move a pointer by a number of elements.
+    fn offset(&self, value: Value, pointee: &Type<'a>, delta: i128,
+        span: Span) -> Eval<Value>
+    {
+        let ptr = match value {
+            Value::Pointer(ptr) => ptr,
+            value => return error(span, format!(
+                "interpreter bug: pointer arithmetic on {}", value)),
+        };
+        let size = self.size(pointee, span)? as i128;
+        let offset = ptr.offset + delta * size;
+        match ptr.alloc {
+            Some(alloc) => {
+                let len = self.allocs[alloc].bytes.data.len() as i128;
+                if offset < 0 || offset > len {
+                    return ub(span, format!(
+                        "pointer arithmetic out of bounds (offset {} of a \
+                        {}-byte allocation)", offset, len));
+                }
+            }
+            None if delta != 0 => {
+                return ub(span, "arithmetic on a null pointer or a pointer \
+                    without provenance");
+            }
+            None => {}
+        }
+        Ok(Value::Pointer(Pointer { offset, ..ptr }))
+    }
+
+    fn binary(&mut self, op: BinaryOp, a: &Expr<'a>, b: &Expr<'a>,
+        e: &Expr<'a>) -> Eval<Value>
+    {
+        if op == BinaryOp::And || op == BinaryOp::Or {
+            let lhs = self.expr(a)?;
+            let lhs = self.truth(&lhs);
+            if lhs == (op == BinaryOp::Or) {
+                return Ok(Value::Int(lhs as i128));
+            }
+            let rhs = self.expr(b)?;
+            return Ok(Value::Int(self.truth(&rhs) as i128));
+        }
+        let lhs = self.expr(a)?;
+        let rhs = self.expr(b)?;
+
+        if let Type::Pointer(pointee) = &a.ty {
+            return match (op, rhs) {
+                (BinaryOp::Add, Value::Int(delta)) => {
+                    self.offset(lhs, pointee, delta, e.span)
+                }
+                (BinaryOp::Sub, Value::Int(delta)) => {
+                    self.offset(lhs, pointee, -delta, e.span)
+                }
+                (op, Value::Pointer(q)) => {
+                    let p = match lhs {
+                        Value::Pointer(p) => p,
+                        _ => return error(e.span, "interpreter bug: pointer"),
+                    };
+                    self.pointer_binary(op, p, q, pointee, e.span)
+                }
+                _ => error(e.span, "interpreter bug: pointer operation"),
+            };
+        }
+
+        self.arithmetic(op, lhs, rhs, &a.ty, e.span)
+    }
+
+    fn pointer_binary(&self, op: BinaryOp, p: Pointer, q: Pointer,
+        pointee: &Type<'a>, span: Span) -> Eval<Value>
+    {
+        let same = p.alloc.is_some() && p.alloc == q.alloc;
+        let (pa, qa) = (self.address(p), self.address(q));
+        Ok(Value::Int(match op {
+            BinaryOp::Eq => (pa == qa) as i128,
+            BinaryOp::Ne => (pa != qa) as i128,
+            BinaryOp::Sub => {
+                if !same {
+                    return ub(span, "subtracting pointers to different \
+                        objects");
+                }
+                let size = self.size(pointee, span)? as i128;
+                (p.offset - q.offset) / size.max(1)
+            }
+            BinaryOp::Lt | BinaryOp::Gt | BinaryOp::Le | BinaryOp::Ge => {
+                if !same {
+                    return ub(span, "comparing pointers to different objects");
+                }
+                let ordering = p.offset.cmp(&q.offset);
+                compare(op, ordering) as i128
+            }
+            _ => return error(span, "interpreter bug: pointer operation"),
+        }))
+    }
+
+    // Arithmetic or comparison on operands already converted to `ty`.
+    fn arithmetic(&self, op: BinaryOp, lhs: Value, rhs: Value, ty: &Type<'a>,
+        span: Span) -> Eval<Value>
+    {
+        let builtin = match ty {
+            Type::BuiltIn(builtin) => *builtin,
+            _ => return error(span, "interpreter bug: arithmetic type"),
+        };
+        Ok(match (lhs, rhs) {
+            (Value::Int(a), Value::Int(b)) => {
+                if op.is_comparison() {
+                    let ordering = if builtin.is_signed() {
+                        a.cmp(&b)
+                    } else {
+                        (a as u128).cmp(&(b as u128))
+                    };
+                    Value::Int(compare(op, ordering) as i128)
+                } else {
+                    Value::Int(int_binary(op, a, b, builtin, span)?)
+                }
+            }
+            (Value::Float(a), Value::Float(b)) => {
+                let result = match op {
+                    BinaryOp::Add => a + b,
+                    BinaryOp::Sub => a - b,
+                    BinaryOp::Mul => a * b,
+                    BinaryOp::Div => a / b,
+                    _ => {
+                        let ordering = match a.partial_cmp(&b) {
+                            Some(ordering) => ordering,
+                            // NaN compares unequal to everything.
+                            None => return Ok(Value::Int(
+                                (op == BinaryOp::Ne) as i128)),
+                        };
+                        return Ok(Value::Int(compare(op, ordering) as i128));
+                    }
+                };
+                Value::Float(round(result, builtin))
+            }
+            (a, b) => return error(span, format!(
+                "interpreter bug: arithmetic on {} and {}", a, b)),
+        })
+    }
+
+    fn call_expr(&mut self, e: &Expr<'a>) -> Eval<Option<Value>> {
+        let (callee, args) = match &e.kind {
+            ExprKind::Call(callee, args) => (callee, args),
+            _ => return error(e.span, "interpreter bug: not a call"),
+        };
+        let ptr = match self.expr(callee)? {
+            Value::Pointer(ptr) => ptr,
+            value => return error(e.span, format!(
+                "interpreter bug: calling {}", value)),
+        };
+        let name = match ptr.alloc.map(|alloc| &self.allocs[alloc]) {
+            Some(Allocation { kind: AllocKind::Function(name), .. })
+                if ptr.offset == 0 => *name,
+            Some(_) => return ub(callee.span, "calling a pointer that doesn't \
+                point to a function"),
+            None => return ub(callee.span, "calling a null pointer or a \
+                pointer without provenance"),
+        };
+        let mut values = Vec::new();
+        for arg in args {
+            values.push(self.expr(arg)?);
+        }
+        let varargs = match self.functions.get(name) {
+            Some(function) if function.variadic && function.block.is_some()
+                && args.len() >= function.params.len() =>
+            {
+                let fixed = function.params.len();
+                Some(self.varargs(&args[fixed..], &values[fixed..])?)
+            }
+            _ => None,
+        };
+        self.call_named(name, values, varargs, e.span)
+    }
+
+    fn call_named(&mut self, name: &'a str, args: Vec<Value>,
+        varargs: Option<usize>, span: Span) -> Eval<Option<Value>>
+    {
+        let function = match self.functions.get(name) {
+            Some(function) => *function,
+            None => return error(span, format!(
+                "call to undeclared function `{}`", name)),
+        };
+        let block = match &function.block {
+            Some(block) => block,
+            None => return self.builtin(name, &args, span),
+        };
+        if self.frames.len() >= self.depth_limit {
+            return error(span, "stack overflow (call depth limit exceeded)");
+        }
+        if args.len() < function.params.len() {
+            return error(span, format!("`{}` expects {} arguments", name,
+                function.params.len()));
+        }
+
+        self.frames.push(Frame {
+            function,
+            locals: vec![None; function.locals.len()],
+            varargs,
+        });
+        let result = self.run_function(function, block, args, span);
+        let frame = self.frames.pop().unwrap();
+        for alloc in frame.locals.into_iter().chain([frame.varargs]).flatten()
+        {
+            self.allocs[alloc].live = false;
+        }
+        let value = result?;
+
+        Ok(match value {
+            None if name == "main" => Some(Value::Int(0)),
+            value => value,
+        })
+    }
+
+    fn run_function(&mut self, function: &'b Prototype<'a>,
+        block: &'b Block<'a>, args: Vec<Value>, span: Span)
+        -> Eval<Option<Value>>
+    {
+        for (id, (param, value)) in function.params.iter().zip(args).enumerate()
+        {
+            let alloc = self.declare(id, span)?;
+            let ptr = Pointer { alloc: Some(alloc), offset: 0 };
+            self.store(ptr, &param.ty, &value, param.span)?;
+        }
+        let mut seek = None;
+        loop {
+            match self.block(block, &mut seek)? {
+                Flow::Goto(label) => seek = Some(Label::Named(label)),
+                Flow::Return(value) => return Ok(value),
+                _ => return Ok(None),
+            }
+        }
+    }
+
+    fn block(&mut self, block: &'b Block<'a>, seek: &mut Option<Label<'a>>)
+        -> Eval<Flow<'a>>
+    {
+        let result = self.stmts(&block.stmts, seek);
+        // Locals stay alive on `goto`, which may jump back into this block.
+        if let Ok(Flow::Goto(_)) = result {
+            return result;
+        }
+        for stmt in &block.stmts {
+            if let Stmt::Declare(id, _) = stmt {
+                self.kill(*id);
+            }
+        }
+        result
+    }
+
+    fn stmts(&mut self, stmts: &'b [Stmt<'a>], seek: &mut Option<Label<'a>>)
+        -> Eval<Flow<'a>>
+    {
+        for stmt in stmts {
+            let flow = self.stmt(stmt, seek)?;
+            if seek.is_none() {
+                if let Flow::Normal = flow {
+                    continue;
+                }
+                return Ok(flow);
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    // Execute a statement.  While `seek` is set, nothing is executed until
+    // the statement with that label is found (for `goto` and `switch`).
+    fn stmt(&mut self, stmt: &'b Stmt<'a>, seek: &mut Option<Label<'a>>)
+        -> Eval<Flow<'a>>
+    {
+        if seek.is_some() {
+            return self.seek(stmt, seek);
+        }
+
+        Ok(match stmt {
+            Stmt::Empty => Flow::Normal,
+            Stmt::Expr(e) => {
+                self.step(e.span)?;
+                if let ExprKind::Call(..) = e.kind {
+                    self.call_expr(e)?;
+                } else {
+                    self.expr(e)?;
+                }
+                Flow::Normal
+            }
+            Stmt::Declare(id, init) => {
+                let alloc = self.declare(*id, Span::default())?;
+                if let Some(init) = init {
+                    let var = &self.frames.last().unwrap().function.locals[*id];
+                    let (ty, span) = (var.ty.clone(), var.span);
+                    let ptr = Pointer { alloc: Some(alloc), offset: 0 };
+                    self.initialize(ptr, &ty, init, span)?;
+                }
+                Flow::Normal
+            }
+            Stmt::Block(block) => self.block(block, seek)?,
+            Stmt::If(cond, then, otherwise) => {
+                let cond = self.expr(cond)?;
+                if self.truth(&cond) {
+                    self.stmt(then, seek)?
+                } else if let Some(otherwise) = otherwise {
+                    self.stmt(otherwise, seek)?
+                } else {
+                    Flow::Normal
+                }
+            }
+            Stmt::While(cond, body) => {
+                self.while_loop(Some(cond), body, None, true, seek)?
+            }
+            Stmt::DoWhile(body, cond) => {
+                self.while_loop(Some(cond), body, None, false, seek)?
+            }
+            Stmt::For(init, cond, step, body) => {
+                let flow = match self.stmts(init, seek)? {
+                    Flow::Normal => self.while_loop(cond.as_ref(), body,
+                        step.as_ref(), true, seek)?,
+                    flow => flow,
+                };
+                for stmt in init {
+                    if let Stmt::Declare(id, _) = stmt {
+                        self.kill(*id);
+                    }
+                }
+                flow
+            }
+            Stmt::Switch(e, body) => {
+                let value = self.int(e)?;
+                *seek = Some(Label::Case(value));
+                let mut flow = self.stmt(body, seek)?;
+                if seek.is_some() {
+                    *seek = Some(Label::Default);
+                    flow = self.stmt(body, seek)?;
+                    if seek.is_some() {
+                        *seek = None;
+                    }
+                }
+                match flow {
+                    Flow::Break => Flow::Normal,
+                    flow => flow,
+                }
+            }
+            Stmt::Labeled(_, _, stmt) => self.stmt(stmt, seek)?,
+            Stmt::Goto(label, _) => Flow::Goto(label),
+            Stmt::Break(_) => Flow::Break,
+            Stmt::Continue(_) => Flow::Continue,
+            Stmt::Return(e, span) => {
+                self.step(*span)?;
+                match e {
+                    Some(e) => Flow::Return(Some(self.expr(e)?)),
+                    None => Flow::Return(None),
+                }
+            }
+        })
+    }
+
+    // Search a statement for the label in `seek`, then resume from there.
+    fn seek(&mut self, stmt: &'b Stmt<'a>, seek: &mut Option<Label<'a>>)
+        -> Eval<Flow<'a>>
+    {
+        let named = matches!(seek, Some(Label::Named(_)));
+        Ok(match stmt {
+            Stmt::Labeled(label, _, stmt) => {
+                if Some(*label) == *seek {
+                    *seek = None;
+                }
+                self.stmt(stmt, seek)?
+            }
+            Stmt::Block(block) => self.block(block, seek)?,
+            Stmt::If(_, then, otherwise) => {
+                let flow = self.stmt(then, seek)?;
+                match otherwise {
+                    Some(otherwise) if seek.is_some() => {
+                        self.stmt(otherwise, seek)?
+                    }
+                    _ => flow,
+                }
+            }
+            Stmt::While(cond, body) => {
+                self.while_loop(Some(cond), body, None, true, seek)?
+            }
+            Stmt::DoWhile(body, cond) => {
+                self.while_loop(Some(cond), body, None, false, seek)?
+            }
+            Stmt::For(init, cond, step, body) => {
+                let flow = self.while_loop(cond.as_ref(), body, step.as_ref(),
+                    true, seek)?;
+                for stmt in init {
+                    if let Stmt::Declare(id, _) = stmt {
+                        self.kill(*id);
+                    }
+                }
+                flow
+            }
+            // Case labels of a nested switch belong to that switch.
+            Stmt::Switch(_, body) if named => match self.stmt(body, seek)? {
+                Flow::Break => Flow::Normal,
+                flow => flow,
+            },
+            _ => Flow::Normal,
+        })
+    }
+
+    fn while_loop(&mut self, cond: Option<&'b Expr<'a>>, body: &'b Stmt<'a>,
+        step: Option<&'b Expr<'a>>, check_first: bool,
+        seek: &mut Option<Label<'a>>) -> Eval<Flow<'a>>
+    {
+        let mut seeking = seek.is_some();
+        let mut first = true;
+        loop {
+            // A loop without a condition is charged to its function.
+            let span = match cond {
+                Some(cond) => cond.span,
+                None => self.frames.last().map_or(Span::default(),
+                    |frame| frame.function.span),
+            };
+            self.step(span)?;
+            if !seeking && (check_first || !first) {
+                if let Some(cond) = cond {
+                    let value = self.expr(cond)?;
+                    if !self.truth(&value) {
+                        return Ok(Flow::Normal);
+                    }
+                }
+            }
+            first = false;
+            let flow = self.stmt(body, seek)?;
+            if seeking {
+                if seek.is_some() {
+                    // The label isn't in this loop.
+                    return Ok(Flow::Normal);
+                }
+                seeking = false;
+            }
+            match flow {
+                Flow::Break => return Ok(Flow::Normal),
+                Flow::Normal | Flow::Continue => {}
+                flow => return Ok(flow),
+            }
+            if let Some(step) = step {
+                self.expr(step)?;
+            }
+        }
+    }
+
+    // Read a zero-terminated string.
+    fn c_string(&self, mut ptr: Pointer, span: Span) -> Eval<Vec<u8>> {
+        let mut out = Vec::new();
+        loop {
+            let byte = self.read(ptr, 1, span)?;
+            if !byte.init[0] {
+                return ub(span, "read of uninitialized memory");
+            }
+            if byte.data[0] == 0 {
+                return Ok(out);
+            }
+            out.push(byte.data[0]);
+            ptr.offset += 1;
+        }
+    }
+
+    // Functions that are declared but not defined.
+    fn builtin(&mut self, name: &str, args: &[Value], span: Span)
+        -> Eval<Option<Value>>
+    {
+        let ptr = |i: usize| match args.get(i) {
+            Some(Value::Pointer(ptr)) => Ok(*ptr),
+            _ => error(span, format!("`{}` expects a pointer argument", name)),
+        };
+        let int = |i: usize| match args.get(i) {
+            Some(Value::Int(value)) => Ok(*value),
+            _ => error(span, format!("`{}` expects an integer argument", name)),
+        };
+
+        Ok(Some(match name {
+            "malloc" => {
+                let alloc = self.allocate(Bytes::uninit(int(0)? as usize),
+                    AllocKind::Heap);
+                Value::Pointer(Pointer { alloc: Some(alloc), offset: 0 })
+            }
+            "calloc" => {
+                let size = int(0)?.saturating_mul(int(1)?) as usize;
+                let alloc = self.allocate(Bytes::new(vec![0; size]),
+                    AllocKind::Heap);
+                Value::Pointer(Pointer { alloc: Some(alloc), offset: 0 })
+            }
+            "realloc" => {
+                let old = ptr(0)?;
+                let size = int(1)? as usize;
+                let mut bytes = Bytes::uninit(size);
+                if old != Pointer::NULL {
+                    self.free(old, span)?;
+                    let old = &self.allocs[old.alloc.unwrap()].bytes;
+                    let len = old.data.len().min(size);
+                    bytes.write(0, &old.slice(0, len));
+                }
+                let alloc = self.allocate(bytes, AllocKind::Heap);
+                Value::Pointer(Pointer { alloc: Some(alloc), offset: 0 })
+            }
+            "free" => {
+                self.free(ptr(0)?, span)?;
+                return Ok(None);
+            }
+            "putchar" => {
+                let ch = int(0)?;
+                self.output.push(ch as u8);
+                Value::Int(ch)
+            }
+            "puts" => {
+                let string = self.c_string(ptr(0)?, span)?;
+                self.output.extend(string);
+                self.output.push(b'\n');
+                Value::Int(0)
+            }
+            "printf" => {
+                let format = self.c_string(ptr(0)?, span)?;
+                let text = self.format(&format, &args[1..], span)?;
+                self.output.extend(&text);
+                Value::Int(text.len() as i128)
+            }
+            "strlen" => Value::Int(self.c_string(ptr(0)?, span)?.len() as i128),
+            "strcmp" => {
+                let a = self.c_string(ptr(0)?, span)?;
+                let b = self.c_string(ptr(1)?, span)?;
+                Value::Int(a.cmp(&b) as i128)
+            }
+            "memcmp" => {
+                let len = int(2)? as usize;
+                let a = self.read(ptr(0)?, len, span)?;
+                let b = self.read(ptr(1)?, len, span)?;
+                Value::Int(a.data.cmp(&b.data) as i128)
+            }
+            "memcpy" | "memmove" => {
+                let (dst, src, len) = (ptr(0)?, ptr(1)?, int(2)?);
+                let overlap = dst.alloc == src.alloc
+                    && (dst.offset - src.offset).abs() < len;
+                if name == "memcpy" && overlap {
+                    return ub(span, "memcpy of overlapping memory");
+                }
+                let bytes = self.read(src, len as usize, span)?;
+                self.write(dst, &bytes, span)?;
+                Value::Pointer(dst)
+            }
+            "memset" => {
+                let (dst, byte, len) = (ptr(0)?, int(1)?, int(2)?);
+                self.write(dst, &Bytes::new(vec![byte as u8; len as usize]),
+                    span)?;
+                Value::Pointer(dst)
+            }
+            "abs" => {
+                let value = int(0)?;
+                if value == i32::MIN as i128 {
+                    return ub(span, "abs(INT_MIN) overflows");
+                }
+                Value::Int(value.abs())
+            }
+            "exit" => return Err(Stop::Exit(int(0)? as i32)),
+            "abort" => return error(span, "program aborted"),
+            _ => return error(span, format!(
+                "call to undefined function `{}`", name)),
+        }))
+    }
+
+    fn free(&mut self, ptr: Pointer, span: Span) -> Eval<()> {
+        if ptr == Pointer::NULL {
+            return Ok(());
+        }
+        let alloc = match ptr.alloc {
+            Some(alloc) => alloc,
+            None => return ub(span, "freeing a pointer without provenance"),
+        };
+        if self.allocs[alloc].kind != AllocKind::Heap {
+            return ub(span, "freeing memory that wasn't allocated with \
+                malloc");
+        }
+        if !self.allocs[alloc].live {
+            return ub(span, "double free");
+        }
+        if ptr.offset != 0 {
+            return ub(span, "freeing a pointer to the middle of an \
+                allocation");
+        }
+        self.allocs[alloc].live = false;
+        Ok(())
+    }
+
+    // Implement the `printf` format string.
+    fn format(&self, format: &[u8], args: &[Value], span: Span)
+        -> Eval<Vec<u8>>
+    {
+        let mut out = Vec::new();
+        let mut args = args.iter();
+        let mut i = 0;
+        while i < format.len() {
+            if format[i] != b'%' {
+                out.push(format[i]);
+                i += 1;
+                continue;
+            }
+            i += 1;
+            let start = i;
+            while i < format.len() && b"-+ #0".contains(&format[i]) {
+                i += 1;
+            }
+            let flags = &format[start..i];
+            let mut width = 0;
+            while i < format.len() && format[i].is_ascii_digit() {
+                width = width * 10 + (format[i] - b'0') as usize;
+                i += 1;
+            }
+            let mut precision = None;
+            if i < format.len() && format[i] == b'.' {
+                i += 1;
+                let mut p = 0;
+                while i < format.len() && format[i].is_ascii_digit() {
+                    p = p * 10 + (format[i] - b'0') as usize;
+                    i += 1;
+                }
+                precision = Some(p);
+            }
+            while i < format.len() && b"hlzjt".contains(&format[i]) {
+                i += 1;
+            }
+            let conversion = match format.get(i) {
+                Some(c) => *c,
+                None => return ub(span, "printf: incomplete format"),
+            };
+            i += 1;
+            if conversion == b'%' {
+                out.push(b'%');
+                continue;
+            }
+            let arg = match args.next() {
+                Some(arg) => arg,
+                None => return ub(span, "printf: too few arguments"),
+            };
+            let text = match (conversion, arg) {
+                (b'd' | b'i', Value::Int(v)) => {
+                    if flags.contains(&b'+') && *v >= 0 {
+                        format!("+{}", v)
+                    } else {
+                        v.to_string()
+                    }
+                }
+                (b'u', Value::Int(v)) => (*v as u128).to_string(),
+                (b'x', Value::Int(v)) => format!("{:x}", v),
+                (b'X', Value::Int(v)) => format!("{:X}", v),
+                (b'o', Value::Int(v)) => format!("{:o}", v),
+                (b'c', Value::Int(v)) => (*v as u8 as char).to_string(),
+                (b'f' | b'F', Value::Float(v)) => {
+                    format!("{:.*}", precision.unwrap_or(6), v)
+                }
+                (b'e' | b'E', Value::Float(v)) => {
+                    format!("{:.*e}", precision.unwrap_or(6), v)
+                }
+                (b'g' | b'G', Value::Float(v)) => format!("{}", v),
+                (b's', Value::Pointer(ptr)) => {
+                    let mut s = self.c_string(*ptr, span)?;
+                    if let Some(p) = precision {
+                        s.truncate(p);
+                    }
+                    String::from_utf8_lossy(&s).into_owned()
+                }
+                (b'p', Value::Pointer(ptr)) => {
+                    format!("{:#x}", self.address(*ptr))
+                }
+                (c, arg) => return ub(span, format!(
+                    "printf: `%{}` doesn't match argument {}", c as char, arg)),
+            };
+            let pad = width.saturating_sub(text.len());
+            if flags.contains(&b'-') {
+                out.extend(text.bytes());
+                out.extend(std::iter::repeat_n(b' ', pad));
+            } else {
+                let fill = if flags.contains(&b'0') { b'0' } else { b' ' };
+                out.extend(std::iter::repeat_n(fill, pad));
+                out.extend(text.bytes());
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Parse and run a C program, returning the exit code and output.
+pub fn run(text: &str) -> Result<(i32, Vec<u8>), Diagnostic> {
+    let items = ItemIterator::new(text).collect::<Result<Vec<_>, _>>()?;
+    let mut interpreter = Interpreter::new(&items)?;
+    let code = interpreter.run_main()?;
+    Ok((code, interpreter.output))
+}
+
+// The type of the pointers in a `va_list`.
+fn void_pointer<'a>() -> Type<'a> {
+    Type::Pointer(Box::new(Type::BuiltIn(BuiltInType::Void)))
+}
+
+fn compare(op: BinaryOp, ordering: std::cmp::Ordering) -> bool {
+    use std::cmp::Ordering::*;
+
+    match op {
+        BinaryOp::Lt => ordering == Less,
+        BinaryOp::Gt => ordering == Greater,
+        BinaryOp::Le => ordering != Greater,
+        BinaryOp::Ge => ordering != Less,
+        BinaryOp::Eq => ordering == Equal,
+        _ => ordering != Equal,
+    }
+}
+
+// Integer arithmetic in type `ty`, with signed overflow as undefined behavior.
+fn int_binary(op: BinaryOp, a: i128, b: i128, ty: BuiltInType, span: Span)
+    -> Eval<i128>
+{
+    let bits = ty.size().unwrap_or(16) as i128 * 8;
+    let overflow = || ub(span, format!("signed integer overflow in `{}`",
+        ty));
+    if ty.is_signed() {
+        let (min, max) = ty.range();
+        let result = match op {
+            BinaryOp::Add => a.checked_add(b),
+            BinaryOp::Sub => a.checked_sub(b),
+            BinaryOp::Mul => a.checked_mul(b),
+            BinaryOp::Div | BinaryOp::Rem if b == 0 => {
+                return ub(span, "division by zero")
+            }
+            BinaryOp::Div => a.checked_div(b),
+            BinaryOp::Rem => a.checked_div(b).map(|_| a % b),
+            BinaryOp::Shl | BinaryOp::Shr if b < 0 || b >= bits => {
+                return ub(span, format!("shift by {} bits in `{}`", b, ty))
+            }
+            BinaryOp::Shl if a < 0 => {
+                return ub(span, "left shift of a negative value")
+            }
+            BinaryOp::Shl => {
+                if a != 0 && (a.leading_zeros() as i128) <= b {
+                    None
+                } else {
+                    Some(a << b)
+                }
+            }
+            BinaryOp::Shr => Some(a >> b),
+            BinaryOp::BitAnd => Some(a & b),
+            BinaryOp::BitOr => Some(a | b),
+            BinaryOp::BitXor => Some(a ^ b),
+            _ => return error(span, "interpreter bug: integer operator"),
+        };
+        match result {
+            Some(result) if result >= min && result <= max => Ok(result),
+            _ => overflow(),
+        }
+    } else {
+        let (a, b) = (a as u128, b as u128);
+        let result = match op {
+            BinaryOp::Add => a.wrapping_add(b),
+            BinaryOp::Sub => a.wrapping_sub(b),
+            BinaryOp::Mul => a.wrapping_mul(b),
+            BinaryOp::Div | BinaryOp::Rem if b == 0 => {
+                return ub(span, "division by zero")
+            }
+            BinaryOp::Div => a / b,
+            BinaryOp::Rem => a % b,
+            BinaryOp::Shl | BinaryOp::Shr if b >= bits as u128 => {
+                return ub(span, format!("shift by {} bits in `{}`", b, ty))
+            }
+            BinaryOp::Shl => a << b,
+            BinaryOp::Shr => a >> b,
+            BinaryOp::BitAnd => a & b,
+            BinaryOp::BitOr => a | b,
+            BinaryOp::BitXor => a ^ b,
+            _ => return error(span, "interpreter bug: integer operator"),
+        };
+        Ok(ty.wrap(result as i128))
+    }
+}
+
+// Round a float to the precision of a type.
+fn round(value: f64, ty: BuiltInType) -> f64 {
+    match ty.size() {
+        Some(2) => f16_to_f32(f32_to_f16(value as f32)) as f64,
+        Some(4) => value as f32 as f64,
+        _ => value,
+    }
+}
+
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x7F_FFFF;
+    if exponent == 0xFF {
+        // Infinity or NaN
+        return sign | 0x7C00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1F {
+        sign | 0x7C00
+    } else if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let half = ((mantissa >> shift) + ((mantissa >> (shift - 1)) & 1)) as u16;
+        sign | half
+    } else {
+        let half = sign | ((exponent as u16) << 10) | (mantissa >> 13) as u16;
+        // Round to nearest, carrying into the exponent if needed.
+        half + ((mantissa >> 12) & 1) as u16
+    }
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half & 0x8000) as u32) << 16;
+    let exponent = ((half >> 10) & 0x1F) as u32;
+    let mantissa = (half & 0x3FF) as u32;
+    let bits = match (exponent, mantissa) {
+        (0, 0) => sign,
+        (0, _) => {
+            // Subnormal
+            let value = mantissa as f32 / 1024.0 / 16384.0;
+            return if sign != 0 { -value } else { value };
+        }
+        (0x1F, _) => sign | 0x7F80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
+}
@@ -0,0 +1,1035 @@
+// C lowering
+//
+//! Lowering of the typed C AST to the [IR](crate::ir).
+//!
+//! Every local variable and parameter gets a stack slot at the start of the
+//! function, and is loaded and stored wherever it is used, leaving it to
+//! later passes to promote them to registers.  Integers become IR integers
+//! of their size, with `bool` a byte that is 0 or 1, and the operations
+//! that depend on signedness pick their signed or unsigned form from the C
+//! types of their operands.  Structs become IR structs with the same
+//! layout, and unions arrays of integers of their alignment.  Pointer
+//! arithmetic is done in bytes with `ptradd`.
+//!
+//! Structs and unions are copied by loading and storing them whole, and one
+//! that isn't an lvalue, like the result of a call, is stored in a stack
+//! slot of its own to access its members.  `&&`, `||` and `?:` branch to
+//! evaluate only one side, and pick the result with a `phi`; in conditions,
+//! `&&`, `||` and `!` branch straight to where control goes.  A `switch`
+//! becomes a `switch` terminator, a jump table over its cases, and labels
+//! get blocks of their own that `goto` branches to.
+//!
+//! Calls to variadic functions pass the extra arguments after their default
+//! argument promotions, and `va_start` and `va_arg` become the IR's: a
+//! `va_list` is an array of one `__va_list_tag`, which has the layout of
+//! the IR's `va_list`.  Every distinct string literal becomes a constant
+//! global `@str.N` of bytes.
+
+use std::collections::HashMap;
+
+use super::{
+    BinaryOp, Block, BuiltInType, Expr, ExprKind, Initializer, Item, Label,
+    Prototype, Stmt, Structs, Type, UnaryOp,
+};
+use crate::ir::{
+    self, BlockId, Builder, CastOp, FloatPredicate, IntPredicate, Signature,
+    Value,
+};
+use crate::{Diagnostic, Span};
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+/// Lower the items of a translation unit to an IR module.
+pub fn lower(items: &[Item]) -> Result<ir::Module> {
+    let mut structs = Structs::new();
+    for item in items {
+        if let Item::Struct(def) = item {
+            structs.insert(def.name, def.clone());
+        }
+    }
+    let mut cx = Context { structs, strings: Vec::new() };
+    let mut module = ir::Module::new();
+    for item in items {
+        match item {
+            Item::Prototype(prototype) => {
+                let function = cx.function(prototype)?;
+                let existing = module.functions.iter()
+                    .position(|f| f.name == function.name);
+                // Definitions replace declarations.
+                match existing {
+                    Some(_) if function.is_declaration() => {}
+                    Some(index) => module.functions[index] = function,
+                    None => module.functions.push(function),
+                }
+            }
+            Item::Global(global) => {
+                let explicit = global.init.is_some();
+                let ty = cx.ty(&global.var.ty, global.var.span)?;
+                let init = match (&global.init, global.external) {
+                    (_, true) => None,
+                    (Some(init), false) => {
+                        Some(cx.initializer(init, &global.var.ty, &ty)?)
+                    }
+                    (None, false) => Some(Value::Zero(ty.clone())),
+                };
+                let global = ir::Global {
+                    name: global.var.name.to_string(),
+                    ty,
+                    init,
+                    constant: global.constexpr,
+                };
+                // An initialized definition replaces tentative definitions
+                // and `extern` declarations, and a tentative definition
+                // replaces `extern` declarations.
+                let existing = module.globals.iter()
+                    .position(|g| g.name == global.name);
+                match existing {
+                    Some(index) if explicit
+                        || module.globals[index].init.is_none() =>
+                    {
+                        module.globals[index] = global;
+                    }
+                    Some(_) => {}
+                    None => module.globals.push(global),
+                }
+            }
+            Item::Struct(_) | Item::Typedef(..) => {}
+        }
+    }
+    for (i, bytes) in cx.strings.into_iter().enumerate() {
+        module.globals.push(ir::Global {
+            name: format!("str.{}", i),
+            ty: ir::Type::Array(Box::new(ir::Type::I8), bytes.len() as u64),
+            init: Some(Value::Bytes(bytes)),
+            constant: true,
+        });
+    }
+    Ok(module)
+}
+
+fn unsupported<T>(span: Span, what: &str) -> Result<T> {
+    Err(Diagnostic::new(span,
+        format!("{} can't be lowered to the IR yet", what)))
+}
+
+// What the items of the translation unit share.
+struct Context<'a> {
+    structs: Structs<'a>,
+    // The bytes of each string literal, including the terminating zero.
+    strings: Vec<Vec<u8>>,
+}
+
+impl<'a> Context<'a> {
+    // The IR type of a C type.
+    fn ty(&self, ty: &Type<'a>, span: Span) -> Result<ir::Type> {
+        Ok(match ty {
+            Type::BuiltIn(builtin) => match builtin {
+                BuiltInType::Void => ir::Type::Void,
+                ty if ty.is_float() => match ty.size() {
+                    Some(4) => ir::Type::F32,
+                    Some(8) => ir::Type::F64,
+                    _ => return unsupported(span, &format!("`{}`", ty)),
+                },
+                ty => match ty.size() {
+                    Some(size) => ir::Type::Int(size as u32 * 8),
+                    None => return unsupported(span, &format!("`{}`", ty)),
+                },
+            },
+            Type::Pointer(_) | Type::Function(..) => ir::Type::Ptr,
+            Type::Array(elem, len) => {
+                ir::Type::Array(Box::new(self.ty(elem, span)?), *len as u64)
+            }
+            Type::Defined(name) => {
+                let def = match self.structs.get(name) {
+                    Some(def) if def.size.is_some() => def,
+                    _ => {
+                        return Err(Diagnostic::new(span,
+                            format!("`{}` is incomplete", name)));
+                    }
+                };
+                let size = def.size.unwrap_or(0) as u64;
+                let align = def.align as u64;
+                if !def.union {
+                    let fields = def.fields.iter()
+                        .map(|field| self.ty(&field.ty, span))
+                        .collect::<Result<Vec<_>>>()?;
+                    let ty = ir::Type::Struct(fields);
+                    let same = def.fields.iter().enumerate()
+                        .all(|(i, field)| ty.offset(i) == field.offset as u64);
+                    if same && ty.size() == size {
+                        return Ok(ty);
+                    }
+                }
+                ir::Type::Array(Box::new(ir::Type::Int(align as u32 * 8)),
+                    size / align)
+            }
+            Type::Typedef(name) => {
+                return Err(Diagnostic::new(span,
+                    format!("typedef `{}` isn't resolved", name)));
+            }
+        })
+    }
+
+    fn signature(&self, ty: &Type<'a>, span: Span) -> Result<Signature> {
+        match ty {
+            Type::Function(ret, params, variadic) => Ok(Signature {
+                ret: self.ty(ret, span)?,
+                params: params.iter()
+                    .map(|param| self.ty(param, span))
+                    .collect::<Result<_>>()?,
+                variadic: *variadic,
+            }),
+            _ => Err(Diagnostic::new(span,
+                format!("`{}` isn't a function type", ty))),
+        }
+    }
+
+    fn function(&mut self, prototype: &Prototype<'a>)
+        -> Result<ir::Function>
+    {
+        let signature = self.signature(&prototype.ty(), prototype.span)?;
+        let mut function = ir::Function::new(prototype.name, signature);
+        let block = match prototype.block {
+            Some(ref block) => block,
+            None => return Ok(function),
+        };
+        let mut lowering = Lowering {
+            cx: self,
+            prototype,
+            builder: Builder::new(&mut function),
+            locals: Vec::new(),
+            loops: Vec::new(),
+            switches: Vec::new(),
+            labels: HashMap::new(),
+        };
+        for local in &prototype.locals {
+            let ty = lowering.cx.ty(&local.ty, local.span)?;
+            let slot = lowering.builder.alloca(ty);
+            lowering.locals.push(slot);
+        }
+        for i in 0..prototype.params.len() {
+            let slot = lowering.locals[i].clone();
+            lowering.builder.store(Value::Param(i), slot);
+        }
+        lowering.block(block)?;
+        if !lowering.builder.is_terminated() {
+            let ret = lowering.builder.function.signature.ret.clone();
+            let value = match ret {
+                ir::Type::Void => None,
+                // Reaching the end of `main` returns 0.
+                _ if prototype.name == "main" => Some(Value::int(ret, 0)),
+                _ => Some(Value::Undef(ret)),
+            };
+            lowering.builder.ret(value);
+        }
+        function.remove_unreachable_blocks();
+        Ok(function)
+    }
+
+    // The constant value of an initializer of a global of type `ty`.
+    fn initializer(&mut self, init: &Initializer<'a>, c_ty: &Type<'a>,
+        ty: &ir::Type) -> Result<Value>
+    {
+        match init {
+            Initializer::Expr(e) => self.constant(e),
+            Initializer::List(list) => self.aggregate(list, c_ty, ty, 0),
+        }
+    }
+
+    // The constant of type `ty` at byte `offset` of the object initialized
+    // by `list`.
+    fn aggregate(&mut self, list: &[(usize, Expr<'a>)], c_ty: &Type<'a>,
+        ty: &ir::Type, offset: u64) -> Result<Value>
+    {
+        let end = offset + ty.size();
+        let inside: Vec<_> = list.iter()
+            .filter(|(at, _)| (offset..end).contains(&(*at as u64)))
+            .collect();
+        if inside.is_empty() {
+            return Ok(Value::Zero(ty.clone()));
+        }
+        let members = match ty {
+            ir::Type::Array(member, len) => vec![(**member).clone(); *len as usize],
+            ir::Type::Struct(fields) => fields.clone(),
+            _ => {
+                let (at, e) = inside[0];
+                if *at as u64 != offset || self.ty(&e.ty, e.span)? != *ty {
+                    return unsupported(e.span,
+                        &format!("initializing a `{}` partly", c_ty));
+                }
+                return self.constant(e);
+            }
+        };
+        let mut values = Vec::new();
+        for (i, member) in members.iter().enumerate() {
+            values.push(self.aggregate(list, c_ty, member,
+                offset + ty.offset(i))?);
+        }
+        Ok(Value::Aggregate(ty.clone(), values))
+    }
+
+    // The value of a constant expression.
+    fn constant(&mut self, e: &Expr<'a>) -> Result<Value> {
+        let ty = self.ty(&e.ty, e.span)?;
+        Ok(match e.kind {
+            ExprKind::Int(value) if ty.is_int() => Value::int(ty, value),
+            ExprKind::Int(0) if ty == ir::Type::Ptr => Value::Null,
+            ExprKind::Float(value) if ty.is_float() => Value::float(ty, value),
+            ExprKind::Decay(ref inner) | ExprKind::Unary(UnaryOp::AddrOf,
+                ref inner) => match inner.kind {
+                ExprKind::Global(name) | ExprKind::Function(name) => {
+                    Value::global(name)
+                }
+                ExprKind::String(ref bytes) => self.string(bytes),
+                _ => return unsupported(e.span, "this initializer"),
+            },
+            ExprKind::Cast(ref inner) => {
+                let value = self.constant(inner)?;
+                match fold(value, &inner.ty, &e.ty, ty) {
+                    Some(value) => value,
+                    None => return unsupported(e.span, "this initializer"),
+                }
+            }
+            _ => return unsupported(e.span, "this initializer"),
+        })
+    }
+
+    // The global holding a string literal.
+    fn string(&mut self, bytes: &[u8]) -> Value {
+        let index = match self.strings.iter().position(|s| s == bytes) {
+            Some(index) => index,
+            None => {
+                self.strings.push(bytes.to_vec());
+                self.strings.len() - 1
+            }
+        };
+        Value::global(format!("str.{}", index))
+    }
+}
+
+// Convert a constant of C type `from` to C type `to`, whose IR type is `ty`,
+// `None` if the conversion happens at run time.
+fn fold(value: Value, from: &Type, to: &Type, ty: ir::Type) -> Option<Value> {
+    if to.builtin().is_some_and(BuiltInType::is_bool) {
+        let nonzero = match value {
+            Value::Int(_, value) => value != 0,
+            value => value.as_float()? != 0.0,
+        };
+        return Some(Value::int(ty, nonzero as i128));
+    }
+    Some(match (value, &ty) {
+        (Value::Int(_, value), ir::Type::Int(_)) => {
+            Value::int(ty, unsigned(value, from))
+        }
+        (Value::Int(_, value), ir::Type::F32 | ir::Type::F64) => {
+            Value::float(ty, unsigned(value, from) as f64)
+        }
+        (value @ Value::Float(..), ir::Type::F32 | ir::Type::F64) => {
+            Value::float(ty, value.as_float()?)
+        }
+        (value @ Value::Float(..), ir::Type::Int(_)) => {
+            Value::int(ty, value.as_float()? as i128)
+        }
+        (Value::Int(_, 0), ir::Type::Ptr) => Value::Null,
+        (value @ Value::Null, ir::Type::Ptr)
+        | (value @ Value::Global(_), ir::Type::Ptr) => value,
+        _ => return None,
+    })
+}
+
+// Whether a C type is a signed integer.
+fn is_signed(ty: &Type) -> bool {
+    ty.builtin().is_some_and(BuiltInType::is_signed)
+}
+
+// Reinterpret an integer constant of type `ty` as unsigned, unless `ty` is
+// signed.
+fn unsigned(value: i128, ty: &Type) -> i128 {
+    match ty.builtin() {
+        Some(builtin) if !builtin.is_signed() && builtin.is_integer() => {
+            let bits = builtin.size().unwrap_or(16) * 8;
+            match bits {
+                128 => value,
+                _ => value & ((1 << bits) - 1),
+            }
+        }
+        _ => value,
+    }
+}
+
+// Collect the `case` and `default` labels of a `switch` body, leaving out
+// those of `switch`es inside it.
+fn case_labels<'a>(stmt: &Stmt<'a>, labels: &mut Vec<Label<'a>>) {
+    match stmt {
+        Stmt::Labeled(label, _, stmt) => {
+            if !matches!(label, Label::Named(_)) {
+                labels.push(*label);
+            }
+            case_labels(stmt, labels);
+        }
+        Stmt::Block(block) => {
+            for stmt in &block.stmts {
+                case_labels(stmt, labels);
+            }
+        }
+        Stmt::If(_, then, else_) => {
+            case_labels(then, labels);
+            if let Some(else_) = else_ {
+                case_labels(else_, labels);
+            }
+        }
+        Stmt::While(_, body)
+        | Stmt::DoWhile(body, _)
+        | Stmt::For(_, _, _, body) => case_labels(body, labels),
+        _ => {}
+    }
+}
+
+// A loop or `switch` being lowered, which `break` leaves.  `continue`
+// goes to the innermost loop, which a `switch` isn't.
+struct Loop {
+    continue_: Option<BlockId>,
+    break_: BlockId,
+}
+
+// A function body being lowered.
+struct Lowering<'l, 'f, 'a> {
+    cx: &'l mut Context<'a>,
+    prototype: &'l Prototype<'a>,
+    builder: Builder<'f>,
+    // Stack slot of each local variable.
+    locals: Vec<Value>,
+    loops: Vec<Loop>,
+    // The block of each `case` and `default` of the `switch`es being lowered.
+    switches: Vec<Vec<(Label<'a>, BlockId)>>,
+    // The block of each named label, made when first seen.
+    labels: HashMap<&'a str, BlockId>,
+}
+
+impl<'a> Lowering<'_, '_, 'a> {
+    fn ty(&self, ty: &Type<'a>, span: Span) -> Result<ir::Type> {
+        self.cx.ty(ty, span)
+    }
+
+    fn block(&mut self, block: &Block<'a>) -> Result<()> {
+        for stmt in &block.stmts {
+            self.stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: &Stmt<'a>) -> Result<()> {
+        match stmt {
+            Stmt::Empty => {}
+            Stmt::Expr(e) => {
+                self.expr(e)?;
+            }
+            Stmt::Declare(local, init) => {
+                let slot = self.locals[*local].clone();
+                let var = &self.prototype.locals[*local];
+                match init {
+                    Some(Initializer::Expr(e)) => {
+                        let value = self.expr(e)?;
+                        self.builder.store(value, slot);
+                    }
+                    Some(Initializer::List(list)) => {
+                        let ty = self.ty(&var.ty, var.span)?;
+                        self.builder.store(Value::Zero(ty), slot.clone());
+                        for (offset, e) in list {
+                            let value = self.expr(e)?;
+                            let offset = Value::int(ir::Type::I64,
+                                *offset as i128);
+                            let ptr = self.builder.ptradd(slot.clone(), offset);
+                            self.builder.store(value, ptr);
+                        }
+                    }
+                    None => {}
+                }
+            }
+            Stmt::Block(block) => self.block(block)?,
+            Stmt::If(cond, then, else_) => {
+                let then_block = self.builder.new_block();
+                let end = self.builder.new_block();
+                let else_block = match else_ {
+                    Some(_) => self.builder.new_block(),
+                    None => end,
+                };
+                self.branch(cond, then_block, else_block)?;
+                self.builder.switch_to(then_block);
+                self.stmt(then)?;
+                self.builder.br(end);
+                if let Some(else_) = else_ {
+                    self.builder.switch_to(else_block);
+                    self.stmt(else_)?;
+                    self.builder.br(end);
+                }
+                self.builder.switch_to(end);
+            }
+            Stmt::While(cond, body) => {
+                let head = self.builder.new_block();
+                let body_block = self.builder.new_block();
+                let end = self.builder.new_block();
+                self.builder.br(head);
+                self.builder.switch_to(head);
+                self.branch(cond, body_block, end)?;
+                self.builder.switch_to(body_block);
+                self.loop_body(body, Some(head), end)?;
+                self.builder.br(head);
+                self.builder.switch_to(end);
+            }
+            Stmt::DoWhile(body, cond) => {
+                let body_block = self.builder.new_block();
+                let test = self.builder.new_block();
+                let end = self.builder.new_block();
+                self.builder.br(body_block);
+                self.builder.switch_to(body_block);
+                self.loop_body(body, Some(test), end)?;
+                self.builder.br(test);
+                self.builder.switch_to(test);
+                self.branch(cond, body_block, end)?;
+                self.builder.switch_to(end);
+            }
+            Stmt::For(init, cond, step, body) => {
+                for stmt in init {
+                    self.stmt(stmt)?;
+                }
+                let head = self.builder.new_block();
+                let body_block = self.builder.new_block();
+                let next = self.builder.new_block();
+                let end = self.builder.new_block();
+                self.builder.br(head);
+                self.builder.switch_to(head);
+                match cond {
+                    Some(cond) => self.branch(cond, body_block, end)?,
+                    None => self.builder.br(body_block),
+                }
+                self.builder.switch_to(body_block);
+                self.loop_body(body, Some(next), end)?;
+                self.builder.br(next);
+                self.builder.switch_to(next);
+                if let Some(step) = step {
+                    self.expr(step)?;
+                }
+                self.builder.br(head);
+                self.builder.switch_to(end);
+            }
+            Stmt::Switch(e, body) => {
+                let value = self.expr(e)?;
+                let end = self.builder.new_block();
+                let mut labels = Vec::new();
+                case_labels(body, &mut labels);
+                let labels: Vec<_> = labels.into_iter()
+                    .map(|label| (label, self.builder.new_block()))
+                    .collect();
+                let mut default = end;
+                let mut cases = Vec::new();
+                for &(label, block) in &labels {
+                    match label {
+                        Label::Case(value) => cases.push((value, block)),
+                        _ => default = block,
+                    }
+                }
+                self.builder.switch(value, default, cases);
+                self.switches.push(labels);
+                self.loops.push(Loop { continue_: None, break_: end });
+                let result = self.stmt(body);
+                self.loops.pop();
+                self.switches.pop();
+                result?;
+                self.builder.br(end);
+                self.builder.switch_to(end);
+            }
+            Stmt::Labeled(label, _, stmt) => {
+                let block = match label {
+                    Label::Named(name) => self.label(name),
+                    _ => {
+                        let labels = self.switches.last().unwrap();
+                        labels.iter().find(|(l, _)| l == label).unwrap().1
+                    }
+                };
+                self.builder.br(block);
+                self.builder.switch_to(block);
+                self.stmt(stmt)?;
+            }
+            Stmt::Goto(name, _) => {
+                let block = self.label(name);
+                self.builder.br(block);
+            }
+            Stmt::Break(_) => {
+                let target = self.loops.last().unwrap().break_;
+                self.builder.br(target);
+            }
+            Stmt::Continue(_) => {
+                let target = self.loops.iter().rev()
+                    .find_map(|l| l.continue_)
+                    .unwrap();
+                self.builder.br(target);
+            }
+            Stmt::Return(e, _) => {
+                let value = match e {
+                    Some(e) => Some(self.expr(e)?),
+                    None => None,
+                };
+                self.builder.ret(value);
+            }
+        }
+        Ok(())
+    }
+
+    fn loop_body(&mut self, body: &Stmt<'a>, continue_: Option<BlockId>,
+        break_: BlockId) -> Result<()>
+    {
+        self.loops.push(Loop { continue_, break_ });
+        let result = self.stmt(body);
+        self.loops.pop();
+        result
+    }
+
+    // The block of a named label.
+    fn label(&mut self, name: &'a str) -> BlockId {
+        match self.labels.get(name) {
+            Some(&block) => block,
+            None => {
+                let block = self.builder.new_block();
+                self.labels.insert(name, block);
+                block
+            }
+        }
+    }
+
+    // Branch to `then` if a condition is true and to `else_` if not,
+    // short-circuiting `&&` and `||`.
+    fn branch(&mut self, e: &Expr<'a>, then: BlockId, else_: BlockId)
+        -> Result<()>
+    {
+        match e.kind {
+            ExprKind::Binary(op @ BinaryOp::And, ref a, ref b)
+            | ExprKind::Binary(op @ BinaryOp::Or, ref a, ref b) => {
+                let rhs = self.builder.new_block();
+                match op {
+                    BinaryOp::And => self.branch(a, rhs, else_)?,
+                    _ => self.branch(a, then, rhs)?,
+                }
+                self.builder.switch_to(rhs);
+                self.branch(b, then, else_)
+            }
+            ExprKind::Unary(UnaryOp::Not, ref operand) => {
+                self.branch(operand, else_, then)
+            }
+            _ => {
+                let cond = self.cond(e)?;
+                self.builder.cond_br(cond, then, else_);
+                Ok(())
+            }
+        }
+    }
+
+    // Lower an expression used as a condition to an `i1`.
+    fn cond(&mut self, e: &Expr<'a>) -> Result<Value> {
+        match e.kind {
+            ExprKind::Binary(op, ref a, ref b)
+                if op.is_comparison() && !matches!(op, BinaryOp::And
+                    | BinaryOp::Or) =>
+            {
+                let x = self.expr(a)?;
+                let y = self.expr(b)?;
+                return Ok(self.compare(op, x, y, a));
+            }
+            _ => {}
+        }
+        let value = self.expr(e)?;
+        self.is_nonzero(value, &e.ty, e.span)
+    }
+
+    // Compare a scalar of type `ty` with zero.
+    fn is_nonzero(&mut self, value: Value, ty: &Type<'a>, span: Span)
+        -> Result<Value>
+    {
+        let ir_ty = self.ty(ty, span)?;
+        Ok(match ir_ty {
+            ir::Type::F32 | ir::Type::F64 => {
+                let zero = Value::float(ir_ty, 0.0);
+                self.builder.fcmp(FloatPredicate::Une, value, zero)
+            }
+            ir::Type::Ptr => {
+                self.builder.icmp(IntPredicate::Ne, value, Value::Null)
+            }
+            _ => {
+                let zero = Value::int(ir_ty, 0);
+                self.builder.icmp(IntPredicate::Ne, value, zero)
+            }
+        })
+    }
+
+    // The address of an lvalue.
+    fn place(&mut self, e: &Expr<'a>) -> Result<Value> {
+        match e.kind {
+            ExprKind::Local(local) => Ok(self.locals[local].clone()),
+            ExprKind::Global(name) => Ok(Value::global(name)),
+            ExprKind::Unary(UnaryOp::Deref, ref ptr) => self.expr(ptr),
+            ExprKind::Member(ref base, offset) => {
+                let base = self.place(base)?;
+                let offset = Value::int(ir::Type::I64, offset as i128);
+                Ok(self.builder.ptradd(base, offset))
+            }
+            ExprKind::String(ref bytes) => Ok(self.cx.string(bytes)),
+            // A struct that isn't an lvalue, like one returned by a call.
+            _ if matches!(e.ty, Type::Defined(_)) => {
+                let value = self.expr(e)?;
+                let ty = self.ty(&e.ty, e.span)?;
+                let slot = self.builder.alloca(ty);
+                self.builder.store(value, slot.clone());
+                Ok(slot)
+            }
+            _ => unsupported(e.span, "this lvalue"),
+        }
+    }
+
+    fn expr(&mut self, e: &Expr<'a>) -> Result<Value> {
+        let ty = self.ty(&e.ty, e.span)?;
+        Ok(match e.kind {
+            ExprKind::Int(value) => match ty {
+                ir::Type::Ptr if value == 0 => Value::Null,
+                _ => Value::int(ty, value),
+            },
+            ExprKind::Float(value) => Value::float(ty, value),
+            ExprKind::String(_) => {
+                let ptr = self.place(e)?;
+                self.builder.load(ty, ptr)
+            }
+            ExprKind::Local(_)
+            | ExprKind::Global(_)
+            | ExprKind::Member(..)
+            | ExprKind::Unary(UnaryOp::Deref, _) => {
+                let ptr = self.place(e)?;
+                self.builder.load(ty, ptr)
+            }
+            ExprKind::Function(name) => Value::global(name),
+            ExprKind::Unary(UnaryOp::AddrOf, ref operand) => {
+                match operand.kind {
+                    ExprKind::Function(name) => Value::global(name),
+                    _ => self.place(operand)?,
+                }
+            }
+            ExprKind::Unary(op, ref operand) => {
+                let value = self.expr(operand)?;
+                match op {
+                    UnaryOp::Neg if ty.is_float() => self.builder.fneg(value),
+                    UnaryOp::Neg => {
+                        let zero = Value::int(ty, 0);
+                        self.builder.binary(ir::BinaryOp::Sub, zero, value)
+                    }
+                    UnaryOp::BitNot => {
+                        let ones = Value::int(ty, -1);
+                        self.builder.binary(ir::BinaryOp::Xor, value, ones)
+                    }
+                    _ => {
+                        let nonzero = self.is_nonzero(value, &operand.ty,
+                            operand.span)?;
+                        let zero = self.builder.icmp(IntPredicate::Eq, nonzero,
+                            Value::bool(false));
+                        self.builder.cast(CastOp::ZExt, zero, ty)
+                    }
+                }
+            }
+            ExprKind::Binary(op @ BinaryOp::And, ref a, ref b)
+            | ExprKind::Binary(op @ BinaryOp::Or, ref a, ref b) => {
+                let rhs = self.builder.new_block();
+                let end = self.builder.new_block();
+                let x = self.cond(a)?;
+                let lhs_end = self.builder.block();
+                match op {
+                    BinaryOp::And => self.builder.cond_br(x, rhs, end),
+                    _ => self.builder.cond_br(x, end, rhs),
+                }
+                self.builder.switch_to(rhs);
+                let y = self.cond(b)?;
+                let rhs_end = self.builder.block();
+                self.builder.br(end);
+                self.builder.switch_to(end);
+                let short = Value::bool(op == BinaryOp::Or);
+                let value = self.builder.phi(ir::Type::I1,
+                    vec![(lhs_end, short), (rhs_end, y)]);
+                self.builder.cast(CastOp::ZExt, value, ty)
+            }
+            ExprKind::Binary(op, ref a, ref b) => {
+                let x = self.expr(a)?;
+                let y = self.expr(b)?;
+                self.binary(op, x, y, a, b, &e.ty)?
+            }
+            ExprKind::Assign(ref target, ref value) => {
+                let value = self.expr(value)?;
+                let ptr = self.place(target)?;
+                self.builder.store(value.clone(), ptr);
+                value
+            }
+            ExprKind::CompoundAssign(op, ref target, ref value, ref calc_ty) => {
+                let ptr = self.place(target)?;
+                let old = self.builder.load(ty.clone(), ptr.clone());
+                let old = self.convert(old, &target.ty, calc_ty, e.span)?;
+                let y = self.expr(value)?;
+                let new = self.binary(op, old, y, &Expr {
+                    kind: ExprKind::Int(0),
+                    ty: calc_ty.clone(),
+                    span: target.span,
+                }, value, calc_ty)?;
+                let new = self.convert(new, calc_ty, &target.ty, e.span)?;
+                self.builder.store(new.clone(), ptr);
+                new
+            }
+            ExprKind::IncDec { ref target, increment, prefix } => {
+                let ptr = self.place(target)?;
+                let old = self.builder.load(ty.clone(), ptr.clone());
+                let new = match target.ty {
+                    Type::Pointer(ref pointee) => {
+                        let size = self.size(pointee, e.span)?;
+                        let step = if increment { size } else { -size };
+                        let step = Value::int(ir::Type::I64, step);
+                        self.builder.ptradd(old.clone(), step)
+                    }
+                    _ if ty.is_float() => {
+                        let op = match increment {
+                            true => ir::BinaryOp::FAdd,
+                            false => ir::BinaryOp::FSub,
+                        };
+                        let one = Value::float(ty.clone(), 1.0);
+                        self.builder.binary(op, old.clone(), one)
+                    }
+                    _ => {
+                        let op = match increment {
+                            true => ir::BinaryOp::Add,
+                            false => ir::BinaryOp::Sub,
+                        };
+                        let one = Value::int(ty.clone(), 1);
+                        let new = self.builder.binary(op, old.clone(), one);
+                        match e.ty.builtin() {
+                            Some(builtin) if builtin.is_bool() => {
+                                let zero = Value::int(ty.clone(), 0);
+                                let nonzero = self.builder.icmp(
+                                    IntPredicate::Ne, new, zero);
+                                self.builder.cast(CastOp::ZExt, nonzero, ty)
+                            }
+                            _ => new,
+                        }
+                    }
+                };
+                self.builder.store(new.clone(), ptr);
+                match prefix {
+                    true => new,
+                    false => old,
+                }
+            }
+            ExprKind::Conditional(ref cond, ref a, ref b) => {
+                let then = self.builder.new_block();
+                let else_ = self.builder.new_block();
+                let end = self.builder.new_block();
+                self.branch(cond, then, else_)?;
+                let mut incoming = Vec::new();
+                for (block, e) in [(then, a), (else_, b)] {
+                    self.builder.switch_to(block);
+                    let value = self.expr(e)?;
+                    incoming.push((self.builder.block(), value));
+                    self.builder.br(end);
+                }
+                self.builder.switch_to(end);
+                match ty {
+                    ir::Type::Void => Value::Undef(ty),
+                    _ => self.builder.phi(ty, incoming),
+                }
+            }
+            ExprKind::Comma(ref a, ref b) => {
+                self.expr(a)?;
+                self.expr(b)?
+            }
+            ExprKind::Call(ref callee, ref args) => {
+                let signature = match callee.ty.pointee() {
+                    Some(ty) => self.cx.signature(ty, callee.span)?,
+                    None => {
+                        return Err(Diagnostic::new(callee.span,
+                            "called object isn't a function"));
+                    }
+                };
+                let callee = self.expr(callee)?;
+                let args = args.iter()
+                    .map(|arg| self.expr(arg))
+                    .collect::<Result<_>>()?;
+                self.builder.call(signature, callee, args)
+            }
+            ExprKind::Cast(ref inner) => {
+                let value = self.expr(inner)?;
+                self.convert(value, &inner.ty, &e.ty, e.span)?
+            }
+            ExprKind::Decay(ref inner) => match inner.kind {
+                ExprKind::Function(name) => Value::global(name),
+                _ => self.place(inner)?,
+            },
+            ExprKind::VaStart(ref list) => {
+                let list = self.expr(list)?;
+                self.builder.va_start(list);
+                Value::Undef(ty)
+            }
+            ExprKind::VaArg(ref list) => {
+                let list = self.expr(list)?;
+                self.builder.va_arg(ty, list)
+            }
+        })
+    }
+
+    // The size of a C type in bytes.
+    fn size(&self, ty: &Type<'a>, span: Span) -> Result<i128> {
+        match ty.size(&self.cx.structs) {
+            Some(size) => Ok(size as i128),
+            None => Err(Diagnostic::new(span,
+                format!("`{}` has no size", ty))),
+        }
+    }
+
+    // A binary operation on `x` and `y`, the values of `a` and `b`, with a
+    // result of type `ty`.
+    fn binary(&mut self, op: BinaryOp, x: Value, y: Value, a: &Expr<'a>,
+        b: &Expr<'a>, ty: &Type<'a>) -> Result<Value>
+    {
+        let span = a.span.to(b.span);
+        if let Type::Pointer(ref pointee) = a.ty {
+            if let (BinaryOp::Add, _) | (BinaryOp::Sub, false) =
+                (op, matches!(b.ty, Type::Pointer(_)))
+            {
+                let size = self.size(pointee, span)?;
+                let index = self.convert(y, &b.ty,
+                    &Type::BuiltIn(BuiltInType::SignedLongLongInt), span)?;
+                let scale = Value::int(ir::Type::I64, match op {
+                    BinaryOp::Add => size,
+                    _ => -size,
+                });
+                let offset = self.builder.binary(ir::BinaryOp::Mul, index,
+                    scale);
+                return Ok(self.builder.ptradd(x, offset));
+            }
+            if op == BinaryOp::Sub {
+                let size = self.size(pointee, span)?;
+                let x = self.builder.cast(CastOp::PtrToInt, x, ir::Type::I64);
+                let y = self.builder.cast(CastOp::PtrToInt, y, ir::Type::I64);
+                let bytes = self.builder.binary(ir::BinaryOp::Sub, x, y);
+                let size = Value::int(ir::Type::I64, size);
+                let diff = self.builder.binary(ir::BinaryOp::SDiv, bytes, size);
+                return self.convert(diff,
+                    &Type::BuiltIn(BuiltInType::SignedLongLongInt), ty, span);
+            }
+        }
+
+        let operand = self.ty(&a.ty, a.span)?;
+        let signed = is_signed(&a.ty);
+        if op.is_comparison() {
+            let cmp = self.compare(op, x, y, a);
+            let ty = self.ty(ty, span)?;
+            return Ok(self.builder.cast(CastOp::ZExt, cmp, ty));
+        }
+
+        let op = match (op, operand.is_float(), signed) {
+            (BinaryOp::Add, true, _) => ir::BinaryOp::FAdd,
+            (BinaryOp::Sub, true, _) => ir::BinaryOp::FSub,
+            (BinaryOp::Mul, true, _) => ir::BinaryOp::FMul,
+            (BinaryOp::Div, true, _) => ir::BinaryOp::FDiv,
+            (BinaryOp::Add, false, _) => ir::BinaryOp::Add,
+            (BinaryOp::Sub, false, _) => ir::BinaryOp::Sub,
+            (BinaryOp::Mul, false, _) => ir::BinaryOp::Mul,
+            (BinaryOp::Div, false, true) => ir::BinaryOp::SDiv,
+            (BinaryOp::Div, false, false) => ir::BinaryOp::UDiv,
+            (BinaryOp::Rem, false, true) => ir::BinaryOp::SRem,
+            (BinaryOp::Rem, false, false) => ir::BinaryOp::URem,
+            (BinaryOp::Shl, false, _) => ir::BinaryOp::Shl,
+            (BinaryOp::Shr, false, true) => ir::BinaryOp::AShr,
+            (BinaryOp::Shr, false, false) => ir::BinaryOp::LShr,
+            (BinaryOp::BitAnd, false, _) => ir::BinaryOp::And,
+            (BinaryOp::BitOr, false, _) => ir::BinaryOp::Or,
+            (BinaryOp::BitXor, false, _) => ir::BinaryOp::Xor,
+            _ => return unsupported(span, &format!("`{:?}` of `{}`", op,
+                a.ty)),
+        };
+        // The operands of shifts are promoted separately.
+        let y = match op {
+            ir::BinaryOp::Shl | ir::BinaryOp::AShr | ir::BinaryOp::LShr => {
+                self.convert(y, &b.ty, &a.ty, span)?
+            }
+            _ => y,
+        };
+        Ok(self.builder.binary(op, x, y))
+    }
+
+    // A comparison of `x` and `y`, the values of `a` and the other operand,
+    // as an `i1`.
+    fn compare(&mut self, op: BinaryOp, x: Value, y: Value, a: &Expr<'a>)
+        -> Value
+    {
+        let signed = is_signed(&a.ty);
+        match a.ty.builtin().is_some_and(BuiltInType::is_float) {
+            true => {
+                let pred = match op {
+                    BinaryOp::Lt => FloatPredicate::Olt,
+                    BinaryOp::Gt => FloatPredicate::Ogt,
+                    BinaryOp::Le => FloatPredicate::Ole,
+                    BinaryOp::Ge => FloatPredicate::Oge,
+                    BinaryOp::Eq => FloatPredicate::Oeq,
+                    _ => FloatPredicate::Une,
+                };
+                self.builder.fcmp(pred, x, y)
+            }
+            false => {
+                let pred = match (op, signed) {
+                    (BinaryOp::Lt, true) => IntPredicate::Slt,
+                    (BinaryOp::Gt, true) => IntPredicate::Sgt,
+                    (BinaryOp::Le, true) => IntPredicate::Sle,
+                    (BinaryOp::Ge, true) => IntPredicate::Sge,
+                    (BinaryOp::Lt, false) => IntPredicate::Ult,
+                    (BinaryOp::Gt, false) => IntPredicate::Ugt,
+                    (BinaryOp::Le, false) => IntPredicate::Ule,
+                    (BinaryOp::Ge, false) => IntPredicate::Uge,
+                    (BinaryOp::Eq, _) => IntPredicate::Eq,
+                    _ => IntPredicate::Ne,
+                };
+                self.builder.icmp(pred, x, y)
+            }
+        }
+    }
+
+    // Convert a value of C type `from` to C type `to`.
+    fn convert(&mut self, value: Value, from: &Type<'a>, to: &Type<'a>,
+        span: Span) -> Result<Value>
+    {
+        let from_ir = self.ty(from, span)?;
+        let to_ir = self.ty(to, span)?;
+        if let Value::Int(..) | Value::Float(..) = value {
+            if let Some(value) = fold(value.clone(), from, to, to_ir.clone()) {
+                return Ok(value);
+            }
+        }
+        if to.builtin().is_some_and(BuiltInType::is_bool)
+            && !from.builtin().is_some_and(BuiltInType::is_bool)
+        {
+            let nonzero = self.is_nonzero(value, from, span)?;
+            return Ok(self.builder.cast(CastOp::ZExt, nonzero, to_ir));
+        }
+        if from_ir == to_ir || to_ir == ir::Type::Void {
+            return Ok(value);
+        }
+        let signed = is_signed(from);
+        let op = match (&from_ir, &to_ir) {
+            (ir::Type::Int(a), ir::Type::Int(b)) if a > b => CastOp::Trunc,
+            (ir::Type::Int(_), ir::Type::Int(_)) if signed => CastOp::SExt,
+            (ir::Type::Int(_), ir::Type::Int(_)) => CastOp::ZExt,
+            (ir::Type::Int(_), ir::Type::Ptr) => CastOp::IntToPtr,
+            (ir::Type::Ptr, ir::Type::Int(_)) => CastOp::PtrToInt,
+            (ir::Type::Int(_), _) if signed => CastOp::SiToFp,
+            (ir::Type::Int(_), _) => CastOp::UiToFp,
+            (_, ir::Type::Int(_)) if is_signed(to) => CastOp::FpToSi,
+            (_, ir::Type::Int(_)) => CastOp::FpToUi,
+            (ir::Type::F64, ir::Type::F32) => CastOp::FpTrunc,
+            (ir::Type::F32, ir::Type::F64) => CastOp::FpExt,
+            _ => {
+                return unsupported(span,
+                    &format!("converting `{}` to `{}`", from, to));
+            }
+        };
+        Ok(self.builder.cast(op, value, to_ir))
+    }
+}
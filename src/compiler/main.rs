@@ -1,2 +1,215 @@
+// main.rs
+//
+//! The compiler command: lowers a source file to IR, optimizes it and
+//! prints it, or generates x86-64 or WebAssembly code from it.
+//!
+//! ```text
+//! compiler [-O0|-O1|-O2|-O3] [-f<pass>|-fno-<pass>]... [--verify-each]
+//!     [--emit ir|asm|obj|wasm|wat] [-o <output>] <input>
+//! ```
+//!
+//! The front end is picked by the extension of the input: `.c`, `.rs`,
+//! `.py`, `.aratar` (a shader), or `.ir` for IR text.  The level is `-O0`
+//! if not given; `-f<pass>` runs a pass on top of it and `-fno-<pass>`
+//! skips it.  `--emit` picks the output: IR text, the default, x86-64
+//! assembly, an ELF object, a WebAssembly module or its text.  Binary
+//! output needs `-o`.
+
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use compiler::ir::passes::{Pass, PassManager};
+use compiler::ir::{self, Module};
+use compiler::{wasm, x86_64};
+
+const USAGE: &str = "\
+usage: compiler [-O0|-O1|-O2|-O3] [-f<pass>|-fno-<pass>]... [--verify-each]
+                [--emit ir|asm|obj|wasm|wat] [-o <output>] <input>";
+
+// What the command outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Emit {
+    Ir,
+    Asm,
+    Obj,
+    Wasm,
+    Wat,
+}
+
+// What the command line asks for.
+struct Options {
+    level: u32,
+    // Passes to enable (`true`) or disable, in order.
+    passes: Vec<(Pass, bool)>,
+    verify_each: bool,
+    emit: Emit,
+    output: Option<String>,
+    input: String,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>)
+    -> Result<Options, String>
+{
+    let mut level = 0;
+    let mut passes = Vec::new();
+    let mut verify_each = false;
+    let mut emit = Emit::Ir;
+    let mut output = None;
+    let mut input = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-O0" => level = 0,
+            "-O1" => level = 1,
+            "-O2" => level = 2,
+            "-O3" => level = 3,
+            "--verify-each" => verify_each = true,
+            "--emit" => {
+                emit = match args.next().as_deref() {
+                    Some("ir") => Emit::Ir,
+                    Some("asm") => Emit::Asm,
+                    Some("obj") => Emit::Obj,
+                    Some("wasm") => Emit::Wasm,
+                    Some("wat") => Emit::Wat,
+                    _ => {
+                        return Err("`--emit` needs `ir`, `asm`, `obj`, \
+                            `wasm` or `wat`".to_string());
+                    }
+                };
+            }
+            "-o" => match args.next() {
+                Some(path) => output = Some(path),
+                None => return Err("`-o` needs a file".to_string()),
+            },
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("-f") => {
+                let (name, enable) = match arg.strip_prefix("-fno-") {
+                    Some(name) => (name, false),
+                    None => (&arg[2..], true),
+                };
+                let pass = Pass::named(name).ok_or_else(|| {
+                    let names: Vec<_> = Pass::all().map(Pass::as_str)
+                        .collect();
+                    format!("no pass `{}`; the passes are {}", name,
+                        names.join(", "))
+                })?;
+                passes.push((pass, enable));
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option `{}`\n{}", arg, USAGE));
+            }
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(format!("more than one input\n{}", USAGE)),
+        }
+    }
+    let input = input.ok_or_else(|| USAGE.to_string())?;
+    match emit {
+        Emit::Obj if output.is_none() => {
+            return Err("`--emit obj` needs `-o`".to_string());
+        }
+        Emit::Wasm if output.is_none() => {
+            return Err("`--emit wasm` needs `-o`".to_string());
+        }
+        _ => {}
+    }
+    Ok(Options { level, passes, verify_each, emit, output, input })
+}
+
+// Lower a file to IR, with the front end for its extension.
+fn lower(path: &Path) -> Result<Module, String> {
+    let read = || fs::read_to_string(path).map_err(|error| {
+        format!("couldn't read `{}`: {}", path.display(), error)
+    });
+    let name = path.display().to_string();
+    let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    match ext {
+        "ir" => {
+            let text = read()?;
+            ir::parse(&text).map_err(|error| error.render(&name, &text))
+        }
+        #[cfg(feature = "c")]
+        "c" => {
+            use compiler::c::{lower, ItemIterator};
+            let text = read()?;
+            let items: Result<Vec<_>, _> = ItemIterator::new(&text).collect();
+            items.and_then(|items| lower::lower(&items))
+                .map_err(|error| error.render(&name, &text))
+        }
+        #[cfg(feature = "rust")]
+        "rs" => {
+            use compiler::rust::resolve::{Crate, Sources};
+            use compiler::rust::{lower, typeck};
+            let sources = Sources::load(path)
+                .map_err(|error| error.diagnostic.message)?;
+            let krate = Crate::new(&sources)
+                .map_err(|errors| sources.render(&errors[0]))?;
+            let types = typeck::check(&krate)
+                .map_err(|errors| sources.render(&errors[0]))?;
+            lower::lower(&krate, &types)
+                .map_err(|errors| sources.render(&errors[0]))
+        }
+        #[cfg(feature = "python")]
+        "py" => {
+            use compiler::python::{lower, parse_module};
+            let text = read()?;
+            parse_module(&text).and_then(|module| lower::lower(&module))
+                .map_err(|error| error.render(&name, &text))
+        }
+        #[cfg(feature = "shader")]
+        "aratar" => {
+            use compiler::aratar::yote::{compile_shader, lower};
+            let text = read()?;
+            compile_shader(&text).and_then(|bytecode| lower::lower(&bytecode))
+                .map_err(|error| error.render(&name, &text))
+        }
+        _ => Err(format!("`{}`: no front end for `.{}` files", name, ext)),
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let mut module = lower(Path::new(&options.input))?;
+    ir::verify(&module).map_err(|error| error.message)?;
+
+    let mut manager = PassManager::for_level(options.level);
+    manager.verify_each = options.verify_each;
+    for &(pass, enable) in &options.passes {
+        match enable {
+            true => manager.enable(pass),
+            false => manager.disable(pass),
+        }
+    }
+    manager.run(&mut module).map_err(|error| error.message)?;
+
+    let bytes = match options.emit {
+        Emit::Ir => module.to_string().into_bytes(),
+        Emit::Asm => x86_64::assembly(&module)
+            .map_err(|error| error.message)?
+            .into_bytes(),
+        Emit::Obj => x86_64::object(&module).map_err(|error| error.message)?,
+        Emit::Wasm => wasm::translate(&module)
+            .map_err(|error| error.message)?
+            .encode(),
+        Emit::Wat => wasm::translate(&module)
+            .map_err(|error| error.message)?
+            .to_string()
+            .into_bytes(),
+    };
+    match options.output {
+        Some(ref path) => fs::write(path, bytes).map_err(|error| {
+            format!("couldn't write `{}`: {}", path, error)
+        }),
+        None => {
+            print!("{}", String::from_utf8_lossy(&bytes));
+            Ok(())
+        }
+    }
+}
+
 fn main() {
+    let result = parse_args(std::env::args().skip(1))
+        .and_then(|options| run(&options));
+    if let Err(message) = result {
+        eprintln!("{}", message);
+        process::exit(1);
+    }
 }
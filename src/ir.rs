@@ -0,0 +1,1300 @@
+// Intermediate representation
+//
+//! The language-independent intermediate representation that the front ends
+//! lower to and the back ends generate code from.
+//!
+//! A [`Module`] is a list of global variables and functions.  A function
+//! with a body is a list of basic blocks in static single assignment (SSA)
+//! form: every instruction that produces a value defines it exactly once,
+//! and the definition dominates every use.  Where values from different
+//! paths meet, a `phi` at the start of a block picks the one of the edge
+//! control came in by.  Each block ends with exactly one terminator, which
+//! branches to other blocks or leaves the function; the first block is the
+//! entry, which nothing branches to.
+//!
+//! Values are typed.  Integers are `i1`, `i8`, `i16`, `i32`, `i64` and
+//! `i128`, with no signedness: the operations that care, like `sdiv` and
+//! `udiv`, say how they treat their operands.  Floats are `f32` and `f64`,
+//! and all pointers are `ptr`, whatever they point to.  Aggregates are
+//! arrays `[4 x i32]` and structs `{i32, ptr}`, laid out as in C with every
+//! member at a multiple of its alignment.  Memory is only accessed through
+//! explicit `alloca`s, `load`s and `store`s, and addresses are computed
+//! with `ptradd`, which adds a byte offset to a pointer.
+//!
+//! A variadic function reads the arguments after its parameters through a
+//! `va_list`: 24 bytes aligned to 8, the `{i32, i32, ptr, ptr}` of the
+//! System V ABI, which each back end fills in its own way.  `va_start`
+//! sets one up in the function, and `va_arg` takes the next argument of
+//! its type from one, which may have been passed to another function.
+//!
+//! # Text format
+//!
+//! Modules print as text and parse back from it:
+//!
+//! ```text
+//! @message = constant [6 x i8] c"hello\00"
+//! @count = global i32 0
+//!
+//! declare i32 @puts(ptr)
+//!
+//! define i32 @main(i32 %0) {
+//! b0:
+//!   %1 = call i32 @puts(ptr @message)
+//!   %2 = icmp sgt i32 %0, 1
+//!   condbr %2, b1, b2
+//! b1:
+//!   %3 = load i32, @count
+//!   %4 = add i32 %3, 1
+//!   store i32 %4, @count
+//!   br b2
+//! b2:
+//!   %5 = phi i32 [0, b0], [%4, b1]
+//!   ret i32 %5
+//! }
+//! ```
+//!
+//! Printing numbers the parameters and then the instructions producing
+//! values `%0`, `%1`, ... in order, and the blocks `b0`, `b1`, ...; parsed
+//! text may use any names, and values may be used before the line defining
+//! them.  Constants are written `1`, `-2.5`, `true`, `null`, `undef`,
+//! `zeroinitializer`, `{i32 1, ptr null}`, `[i8 1, i8 2]` and `c"bytes"`,
+//! with other bytes of strings and symbol names written as `\` and two hex
+//! digits.  A `;` starts a comment.
+
+pub mod dominators;
+mod mem2reg;
+mod parser;
+pub mod passes;
+mod verify;
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::Diagnostic;
+
+pub use self::mem2reg::mem2reg;
+pub use self::parser::parse;
+pub use self::verify::verify;
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+/// Index of a block in [`Function::blocks`]
+pub type BlockId = usize;
+/// Index of an instruction in [`Function::insts`]
+pub type InstId = usize;
+
+/// The type of a value
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Type {
+    /// No value, only returned by functions and instructions
+    Void,
+    /// Integer of a number of bits: 1, 8, 16, 32, 64 or 128
+    Int(u32),
+    F32,
+    F64,
+    Ptr,
+    /// Array of a number of elements
+    Array(Box<Type>, u64),
+    Struct(Vec<Type>),
+}
+
+impl Type {
+    pub const I1: Type = Type::Int(1);
+    pub const I8: Type = Type::Int(8);
+    pub const I16: Type = Type::Int(16);
+    pub const I32: Type = Type::Int(32);
+    pub const I64: Type = Type::Int(64);
+
+    /// Returns true for integer types.
+    pub fn is_int(&self) -> bool {
+        matches!(self, Type::Int(_))
+    }
+
+    /// Returns true for `f32` and `f64`.
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::F32 | Type::F64)
+    }
+
+    /// Returns true for arrays and structs.
+    pub fn is_aggregate(&self) -> bool {
+        matches!(self, Type::Array(..) | Type::Struct(_))
+    }
+
+    /// Size in bytes, a multiple of the alignment.
+    pub fn size(&self) -> u64 {
+        match self {
+            Type::Void => 0,
+            Type::Int(bits) => u64::from(*bits).div_ceil(8).next_power_of_two(),
+            Type::F32 => 4,
+            Type::F64 | Type::Ptr => 8,
+            Type::Array(ty, len) => ty.size() * len,
+            Type::Struct(fields) => {
+                let end = match fields.last() {
+                    Some(last) => self.offset(fields.len() - 1) + last.size(),
+                    None => 0,
+                };
+                align_to(end, self.align())
+            }
+        }
+    }
+
+    /// Alignment in bytes.
+    pub fn align(&self) -> u64 {
+        match self {
+            Type::Array(ty, _) => ty.align(),
+            Type::Struct(fields) => {
+                fields.iter().map(Type::align).max().unwrap_or(1)
+            }
+            ty => ty.size().max(1),
+        }
+    }
+
+    /// Byte offset of member `index` of an aggregate.
+    pub fn offset(&self, index: usize) -> u64 {
+        match self {
+            Type::Array(ty, _) => ty.size() * index as u64,
+            Type::Struct(fields) => {
+                let mut offset = 0;
+                for field in &fields[..index] {
+                    offset = align_to(offset, field.align()) + field.size();
+                }
+                align_to(offset, fields[index].align())
+            }
+            _ => 0,
+        }
+    }
+
+    /// The type of member `index` of an aggregate, if it has one.
+    pub fn member(&self, index: u32) -> Option<&Type> {
+        match self {
+            Type::Array(ty, len) if u64::from(index) < *len => Some(ty),
+            Type::Struct(fields) => fields.get(index as usize),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Void => write!(f, "void"),
+            Type::Int(bits) => write!(f, "i{}", bits),
+            Type::F32 => write!(f, "f32"),
+            Type::F64 => write!(f, "f64"),
+            Type::Ptr => write!(f, "ptr"),
+            Type::Array(ty, len) => write!(f, "[{} x {}]", len, ty),
+            Type::Struct(fields) => {
+                write!(f, "{{")?;
+                for (i, field) in fields.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", field)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+// Round `offset` up to a multiple of `align`.
+fn align_to(offset: u64, align: u64) -> u64 {
+    offset.div_ceil(align) * align
+}
+
+/// Wrap an integer to `bits` bits, sign extended (except `i1`, which is 0
+/// or 1).
+pub fn wrap(bits: u32, value: i128) -> i128 {
+    match bits {
+        1 => value & 1,
+        128 => value,
+        _ => value << (128 - bits) >> (128 - bits),
+    }
+}
+
+/// The return and parameter types of a function
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Signature {
+    pub ret: Type,
+    pub params: Vec<Type>,
+    /// Whether more arguments may follow the parameters
+    pub variadic: bool,
+}
+
+impl Signature {
+    /// Create a signature that isn't variadic.
+    pub fn new(ret: Type, params: Vec<Type>) -> Self {
+        Signature { ret, params, variadic: false }
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (", self.ret)?;
+        for (i, param) in self.params.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", param)?;
+        }
+        if self.variadic {
+            match self.params.is_empty() {
+                true => write!(f, "...")?,
+                false => write!(f, ", ...")?,
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+/// An operand: the result of an instruction, a parameter or a constant
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Value {
+    Inst(InstId),
+    Param(usize),
+    /// Integer of an integer type, as given by [`wrap`]
+    Int(Type, i128),
+    /// Float of a float type, as the bits of an `f64`
+    Float(Type, u64),
+    /// The null pointer
+    Null,
+    /// Any value of a type
+    Undef(Type),
+    /// The value of a type with every byte zero
+    Zero(Type),
+    /// Array or struct of constants
+    Aggregate(Type, Vec<Value>),
+    /// `[N x i8]` of bytes
+    Bytes(Vec<u8>),
+    /// Address of a global variable or function
+    Global(String),
+}
+
+impl Value {
+    /// An integer constant of type `ty`, wrapped to its size.
+    pub fn int(ty: Type, value: i128) -> Value {
+        match ty {
+            Type::Int(bits) => Value::Int(ty, wrap(bits, value)),
+            _ => Value::Int(ty, value),
+        }
+    }
+
+    /// An `i1` constant.
+    pub fn bool(value: bool) -> Value {
+        Value::Int(Type::I1, value as i128)
+    }
+
+    /// A float constant of type `ty`, rounded to its precision.
+    pub fn float(ty: Type, value: f64) -> Value {
+        let value = match ty {
+            Type::F32 => value as f32 as f64,
+            _ => value,
+        };
+        Value::Float(ty, value.to_bits())
+    }
+
+    /// A reference to a global variable or function.
+    pub fn global<T: Into<String>>(name: T) -> Value {
+        Value::Global(name.into())
+    }
+
+    /// Returns true for values that don't depend on the function they are
+    /// used in.
+    pub fn is_const(&self) -> bool {
+        !matches!(self, Value::Inst(_) | Value::Param(_))
+    }
+
+    /// Get the value of an integer constant.
+    pub fn as_int(&self) -> Option<i128> {
+        match self {
+            Value::Int(_, value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a float constant.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(_, bits) => Some(f64::from_bits(*bits)),
+            _ => None,
+        }
+    }
+}
+
+/// An arithmetic or bitwise operation on two operands of the same type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    SDiv,
+    UDiv,
+    SRem,
+    URem,
+    Shl,
+    /// Logical (zero filling) shift right
+    LShr,
+    /// Arithmetic (sign filling) shift right
+    AShr,
+    And,
+    Or,
+    Xor,
+    FAdd,
+    FSub,
+    FMul,
+    FDiv,
+    FRem,
+}
+
+const BINARY_OPS: &[(&str, BinaryOp)] = &[
+    ("add", BinaryOp::Add),
+    ("sub", BinaryOp::Sub),
+    ("mul", BinaryOp::Mul),
+    ("sdiv", BinaryOp::SDiv),
+    ("udiv", BinaryOp::UDiv),
+    ("srem", BinaryOp::SRem),
+    ("urem", BinaryOp::URem),
+    ("shl", BinaryOp::Shl),
+    ("lshr", BinaryOp::LShr),
+    ("ashr", BinaryOp::AShr),
+    ("and", BinaryOp::And),
+    ("or", BinaryOp::Or),
+    ("xor", BinaryOp::Xor),
+    ("fadd", BinaryOp::FAdd),
+    ("fsub", BinaryOp::FSub),
+    ("fmul", BinaryOp::FMul),
+    ("fdiv", BinaryOp::FDiv),
+    ("frem", BinaryOp::FRem),
+];
+
+impl BinaryOp {
+    /// Get the name of the operation in the text format.
+    pub fn as_str(self) -> &'static str {
+        BINARY_OPS[self as usize].0
+    }
+
+    /// Returns true for the operations on floats.
+    pub fn is_float(self) -> bool {
+        self as usize >= BinaryOp::FAdd as usize
+    }
+}
+
+/// A comparison of integers or pointers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntPredicate {
+    Eq,
+    Ne,
+    Slt,
+    Sle,
+    Sgt,
+    Sge,
+    Ult,
+    Ule,
+    Ugt,
+    Uge,
+}
+
+const INT_PREDICATES: &[(&str, IntPredicate)] = &[
+    ("eq", IntPredicate::Eq),
+    ("ne", IntPredicate::Ne),
+    ("slt", IntPredicate::Slt),
+    ("sle", IntPredicate::Sle),
+    ("sgt", IntPredicate::Sgt),
+    ("sge", IntPredicate::Sge),
+    ("ult", IntPredicate::Ult),
+    ("ule", IntPredicate::Ule),
+    ("ugt", IntPredicate::Ugt),
+    ("uge", IntPredicate::Uge),
+];
+
+impl IntPredicate {
+    /// Get the name of the predicate in the text format.
+    pub fn as_str(self) -> &'static str {
+        INT_PREDICATES[self as usize].0
+    }
+}
+
+/// A comparison of floats: ordered ones are false if either operand is NaN,
+/// unordered ones true.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FloatPredicate {
+    Oeq,
+    One,
+    Olt,
+    Ole,
+    Ogt,
+    Oge,
+    Ueq,
+    Une,
+}
+
+const FLOAT_PREDICATES: &[(&str, FloatPredicate)] = &[
+    ("oeq", FloatPredicate::Oeq),
+    ("one", FloatPredicate::One),
+    ("olt", FloatPredicate::Olt),
+    ("ole", FloatPredicate::Ole),
+    ("ogt", FloatPredicate::Ogt),
+    ("oge", FloatPredicate::Oge),
+    ("ueq", FloatPredicate::Ueq),
+    ("une", FloatPredicate::Une),
+];
+
+impl FloatPredicate {
+    /// Get the name of the predicate in the text format.
+    pub fn as_str(self) -> &'static str {
+        FLOAT_PREDICATES[self as usize].0
+    }
+}
+
+/// A conversion of a value to another type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CastOp {
+    /// Integer to a narrower integer
+    Trunc,
+    /// Integer to a wider integer, zero filling
+    ZExt,
+    /// Integer to a wider integer, sign filling
+    SExt,
+    /// `f64` to `f32`
+    FpTrunc,
+    /// `f32` to `f64`
+    FpExt,
+    /// Float to signed integer, rounding towards zero
+    FpToSi,
+    /// Float to unsigned integer, rounding towards zero
+    FpToUi,
+    SiToFp,
+    UiToFp,
+    PtrToInt,
+    IntToPtr,
+    /// Reinterpretation of the bits of a value as another type of the same
+    /// size
+    Bitcast,
+}
+
+const CAST_OPS: &[(&str, CastOp)] = &[
+    ("trunc", CastOp::Trunc),
+    ("zext", CastOp::ZExt),
+    ("sext", CastOp::SExt),
+    ("fptrunc", CastOp::FpTrunc),
+    ("fpext", CastOp::FpExt),
+    ("fptosi", CastOp::FpToSi),
+    ("fptoui", CastOp::FpToUi),
+    ("sitofp", CastOp::SiToFp),
+    ("uitofp", CastOp::UiToFp),
+    ("ptrtoint", CastOp::PtrToInt),
+    ("inttoptr", CastOp::IntToPtr),
+    ("bitcast", CastOp::Bitcast),
+];
+
+impl CastOp {
+    /// Get the name of the conversion in the text format.
+    pub fn as_str(self) -> &'static str {
+        CAST_OPS[self as usize].0
+    }
+}
+
+/// An instruction, with the type of the value it produces
+#[derive(Debug, Clone, PartialEq)]
+pub struct Inst {
+    pub ty: Type,
+    pub kind: InstKind,
+}
+
+/// The operation of an instruction
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InstKind {
+    Binary(BinaryOp, Value, Value),
+    /// Negation of a float
+    FNeg(Value),
+    /// Comparison producing an `i1`
+    Icmp(IntPredicate, Value, Value),
+    Fcmp(FloatPredicate, Value, Value),
+    /// Conversion to the type of the instruction
+    Cast(CastOp, Value),
+    /// Condition, value if true and value if false
+    Select(Value, Value, Value),
+    /// Stack slot for a value of a type, freed when the function returns
+    Alloca(Type),
+    /// Load of the instruction's type from an address
+    Load(Value),
+    /// Value and address
+    Store(Value, Value),
+    /// Address and byte offset (an `i64`)
+    PtrAdd(Value, Value),
+    /// Call of a function of a signature with arguments
+    Call(Signature, Value, Vec<Value>),
+    /// Value of each predecessor block
+    Phi(Vec<(BlockId, Value)>),
+    /// Member of an aggregate
+    ExtractValue(Value, u32),
+    /// Aggregate with a member replaced
+    InsertValue(Value, Value, u32),
+    /// Start of the arguments after the parameters, in the `va_list` at an
+    /// address
+    VaStart(Value),
+    /// Next argument of the instruction's type, from the `va_list` at an
+    /// address
+    VaArg(Value),
+}
+
+impl InstKind {
+    /// The operands of the instruction.
+    pub fn operands(&self) -> Vec<&Value> {
+        match self {
+            InstKind::Binary(_, a, b)
+            | InstKind::Icmp(_, a, b)
+            | InstKind::Fcmp(_, a, b)
+            | InstKind::Store(a, b)
+            | InstKind::PtrAdd(a, b)
+            | InstKind::InsertValue(a, b, _) => vec![a, b],
+            InstKind::FNeg(a)
+            | InstKind::Cast(_, a)
+            | InstKind::Load(a)
+            | InstKind::ExtractValue(a, _)
+            | InstKind::VaStart(a)
+            | InstKind::VaArg(a) => vec![a],
+            InstKind::Select(a, b, c) => vec![a, b, c],
+            InstKind::Alloca(_) => Vec::new(),
+            InstKind::Call(_, callee, args) => {
+                std::iter::once(callee).chain(args).collect()
+            }
+            InstKind::Phi(incoming) => {
+                incoming.iter().map(|(_, value)| value).collect()
+            }
+        }
+    }
+
+    /// The operands of the instruction, to replace them.
+    pub fn operands_mut(&mut self) -> Vec<&mut Value> {
+        match self {
+            InstKind::Binary(_, a, b)
+            | InstKind::Icmp(_, a, b)
+            | InstKind::Fcmp(_, a, b)
+            | InstKind::Store(a, b)
+            | InstKind::PtrAdd(a, b)
+            | InstKind::InsertValue(a, b, _) => vec![a, b],
+            InstKind::FNeg(a)
+            | InstKind::Cast(_, a)
+            | InstKind::Load(a)
+            | InstKind::ExtractValue(a, _)
+            | InstKind::VaStart(a)
+            | InstKind::VaArg(a) => vec![a],
+            InstKind::Select(a, b, c) => vec![a, b, c],
+            InstKind::Alloca(_) => Vec::new(),
+            InstKind::Call(_, callee, args) => {
+                std::iter::once(callee).chain(args).collect()
+            }
+            InstKind::Phi(incoming) => {
+                incoming.iter_mut().map(|(_, value)| value).collect()
+            }
+        }
+    }
+
+    /// Returns true for instructions that do more than produce a value, so
+    /// they can't be removed when it isn't used.
+    pub fn has_side_effects(&self) -> bool {
+        matches!(self, InstKind::Store(..) | InstKind::Call(..)
+            | InstKind::VaStart(_) | InstKind::VaArg(_))
+    }
+}
+
+/// The instruction ending a block
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Br(BlockId),
+    /// Condition (an `i1`), block if true and block if false
+    CondBr(Value, BlockId, BlockId),
+    /// Integer, default block and the block of each case
+    Switch(Value, BlockId, Vec<(i128, BlockId)>),
+    Ret(Option<Value>),
+    Unreachable,
+}
+
+impl Terminator {
+    /// The blocks branched to, in order, with repeats.
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Br(block) => vec![*block],
+            Terminator::CondBr(_, then, else_) => vec![*then, *else_],
+            Terminator::Switch(_, default, cases) => {
+                std::iter::once(*default)
+                    .chain(cases.iter().map(|(_, block)| *block))
+                    .collect()
+            }
+            Terminator::Ret(_) | Terminator::Unreachable => Vec::new(),
+        }
+    }
+
+    /// The blocks branched to, to replace them.
+    pub fn successors_mut(&mut self) -> Vec<&mut BlockId> {
+        match self {
+            Terminator::Br(block) => vec![block],
+            Terminator::CondBr(_, then, else_) => vec![then, else_],
+            Terminator::Switch(_, default, cases) => {
+                std::iter::once(default)
+                    .chain(cases.iter_mut().map(|(_, block)| block))
+                    .collect()
+            }
+            Terminator::Ret(_) | Terminator::Unreachable => Vec::new(),
+        }
+    }
+
+    /// The operand of the terminator, if it has one.
+    pub fn operand_mut(&mut self) -> Option<&mut Value> {
+        match self {
+            Terminator::CondBr(value, ..)
+            | Terminator::Switch(value, ..)
+            | Terminator::Ret(Some(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The operand of the terminator, if it has one.
+    pub fn operand(&self) -> Option<&Value> {
+        match self {
+            Terminator::CondBr(value, ..)
+            | Terminator::Switch(value, ..)
+            | Terminator::Ret(Some(value)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// A basic block: instructions, then a terminator
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub insts: Vec<InstId>,
+    pub term: Terminator,
+}
+
+/// A function, or the declaration of one if it has no blocks
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub signature: Signature,
+    /// Every instruction, including those removed from the blocks
+    pub insts: Vec<Inst>,
+    pub blocks: Vec<Block>,
+}
+
+impl Function {
+    /// Create a function without blocks.
+    pub fn new<T: Into<String>>(name: T, signature: Signature) -> Self {
+        Function {
+            name: name.into(),
+            signature,
+            insts: Vec::new(),
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Returns true if the function has no body.
+    pub fn is_declaration(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Add an empty block ending with `unreachable`.
+    pub fn add_block(&mut self) -> BlockId {
+        self.blocks.push(Block {
+            insts: Vec::new(),
+            term: Terminator::Unreachable,
+        });
+        self.blocks.len() - 1
+    }
+
+    /// The type of a value used in the function.
+    pub fn value_type(&self, value: &Value) -> Type {
+        match value {
+            Value::Inst(inst) => self.insts[*inst].ty.clone(),
+            Value::Param(index) => self.signature.params[*index].clone(),
+            Value::Int(ty, _)
+            | Value::Float(ty, _)
+            | Value::Undef(ty)
+            | Value::Zero(ty)
+            | Value::Aggregate(ty, _) => ty.clone(),
+            Value::Null | Value::Global(_) => Type::Ptr,
+            Value::Bytes(bytes) => {
+                Type::Array(Box::new(Type::I8), bytes.len() as u64)
+            }
+        }
+    }
+
+    /// The distinct predecessors of each block, in order.
+    pub fn predecessors(&self) -> Vec<Vec<BlockId>> {
+        let mut preds = vec![Vec::new(); self.blocks.len()];
+        for (id, block) in self.blocks.iter().enumerate() {
+            for succ in block.term.successors() {
+                if !preds[succ].contains(&id) {
+                    preds[succ].push(id);
+                }
+            }
+        }
+        preds
+    }
+
+    /// Replace every use of the value of an instruction in the blocks.
+    pub fn replace_uses(&mut self, inst: InstId, value: &Value) {
+        let old = Value::Inst(inst);
+        for block in &mut self.blocks {
+            for &id in &block.insts {
+                for operand in self.insts[id].kind.operands_mut() {
+                    if *operand == old {
+                        *operand = value.clone();
+                    }
+                }
+            }
+            if let Some(operand) = block.term.operand_mut() {
+                if *operand == old {
+                    *operand = value.clone();
+                }
+            }
+        }
+    }
+
+    /// Make the phis of a block forget a predecessor it no longer has.
+    pub fn remove_incoming(&mut self, block: BlockId, pred: BlockId) {
+        for &inst in &self.blocks[block].insts {
+            if let InstKind::Phi(ref mut incoming) = self.insts[inst].kind {
+                incoming.retain(|(from, _)| *from != pred);
+            }
+        }
+    }
+
+    /// Keep only the blocks in `order`, in that order, which must start
+    /// with the entry block.  Phis forget the blocks that are removed.
+    pub fn reorder_blocks(&mut self, order: &[BlockId]) {
+        let mut new_ids = vec![None; self.blocks.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_ids[old] = Some(new);
+        }
+        let mut blocks: Vec<_> = self.blocks.drain(..).map(Some).collect();
+        self.blocks = order.iter().map(|&old| blocks[old].take().unwrap())
+            .collect();
+        for block in &mut self.blocks {
+            for succ in block.term.successors_mut() {
+                *succ = new_ids[*succ].expect("branch to a removed block");
+            }
+            for &inst in &block.insts {
+                if let InstKind::Phi(ref mut incoming) =
+                    self.insts[inst].kind
+                {
+                    incoming.retain(|(pred, _)| new_ids[*pred].is_some());
+                    for (pred, _) in incoming.iter_mut() {
+                        *pred = new_ids[*pred].unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Remove the blocks that can't be reached from the entry block.
+    pub fn remove_unreachable_blocks(&mut self) {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut stack = vec![0];
+        while let Some(block) = stack.pop() {
+            if !std::mem::replace(&mut reachable[block], true) {
+                stack.extend(self.blocks[block].term.successors());
+            }
+        }
+        let order: Vec<_> = (0..self.blocks.len())
+            .filter(|&block| reachable[block])
+            .collect();
+        if order.len() != self.blocks.len() {
+            self.reorder_blocks(&order);
+        }
+    }
+}
+
+/// A global variable, or the declaration of one defined elsewhere
+#[derive(Debug, Clone, PartialEq)]
+pub struct Global {
+    pub name: String,
+    pub ty: Type,
+    /// The constant initial value, `None` for a declaration
+    pub init: Option<Value>,
+    /// Whether the variable is never written to
+    pub constant: bool,
+}
+
+/// A compilation unit: global variables and functions
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Module {
+    pub globals: Vec<Global>,
+    pub functions: Vec<Function>,
+}
+
+impl Module {
+    /// Create an empty module.
+    pub fn new() -> Self {
+        Module::default()
+    }
+
+    /// Get a function by name.
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    /// Get a global variable by name.
+    pub fn global(&self, name: &str) -> Option<&Global> {
+        self.globals.iter().find(|global| global.name == name)
+    }
+
+    /// Declare a function, unless the module already has one of its name.
+    pub fn declare<T: Into<String>>(&mut self, name: T, signature: Signature) {
+        let name = name.into();
+        if self.function(&name).is_none() {
+            self.functions.push(Function::new(name, signature));
+        }
+    }
+}
+
+/// Appends instructions to the blocks of a function.
+///
+/// Instructions are added to the current block until it is terminated.
+/// Anything added after that, before switching to another block, goes to a
+/// new block that can't be reached, for the front ends to lower code after
+/// a `return` without checking;
+/// [`Function::remove_unreachable_blocks`] cleans them up.
+pub struct Builder<'f> {
+    pub function: &'f mut Function,
+    block: BlockId,
+    terminated: bool,
+    // Number of `alloca`s at the start of the entry block.
+    allocas: usize,
+}
+
+impl<'f> Builder<'f> {
+    /// Start adding to the entry block of a function without a body.
+    pub fn new(function: &'f mut Function) -> Self {
+        let block = function.add_block();
+        Builder { function, block, terminated: false, allocas: 0 }
+    }
+
+    /// The block instructions are added to.
+    pub fn block(&self) -> BlockId {
+        self.block
+    }
+
+    /// Add a new empty block.
+    pub fn new_block(&mut self) -> BlockId {
+        self.function.add_block()
+    }
+
+    /// Add instructions to the end of a block that isn't terminated yet.
+    pub fn switch_to(&mut self, block: BlockId) {
+        self.block = block;
+        self.terminated = false;
+    }
+
+    /// Whether the current block is terminated.
+    pub fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+
+    /// Add an instruction producing a value of type `ty`.
+    pub fn push(&mut self, ty: Type, kind: InstKind) -> Value {
+        if self.terminated {
+            let block = self.new_block();
+            self.switch_to(block);
+        }
+        self.function.insts.push(Inst { ty, kind });
+        let inst = self.function.insts.len() - 1;
+        self.function.blocks[self.block].insts.push(inst);
+        Value::Inst(inst)
+    }
+
+    /// End the current block with a terminator, unless it already ends.
+    pub fn terminate(&mut self, term: Terminator) {
+        if !self.terminated {
+            self.function.blocks[self.block].term = term;
+            self.terminated = true;
+        }
+    }
+
+    pub fn binary(&mut self, op: BinaryOp, a: Value, b: Value) -> Value {
+        let ty = self.function.value_type(&a);
+        self.push(ty, InstKind::Binary(op, a, b))
+    }
+
+    pub fn fneg(&mut self, a: Value) -> Value {
+        let ty = self.function.value_type(&a);
+        self.push(ty, InstKind::FNeg(a))
+    }
+
+    pub fn icmp(&mut self, pred: IntPredicate, a: Value, b: Value) -> Value {
+        self.push(Type::I1, InstKind::Icmp(pred, a, b))
+    }
+
+    pub fn fcmp(&mut self, pred: FloatPredicate, a: Value, b: Value)
+        -> Value
+    {
+        self.push(Type::I1, InstKind::Fcmp(pred, a, b))
+    }
+
+    pub fn cast(&mut self, op: CastOp, a: Value, ty: Type) -> Value {
+        self.push(ty, InstKind::Cast(op, a))
+    }
+
+    pub fn select(&mut self, cond: Value, a: Value, b: Value) -> Value {
+        let ty = self.function.value_type(&a);
+        self.push(ty, InstKind::Select(cond, a, b))
+    }
+
+    /// Add a stack slot for a value of type `ty` to the start of the entry
+    /// block, where every `alloca` goes.
+    pub fn alloca(&mut self, ty: Type) -> Value {
+        self.function.insts.push(Inst {
+            ty: Type::Ptr,
+            kind: InstKind::Alloca(ty),
+        });
+        let inst = self.function.insts.len() - 1;
+        self.function.blocks[0].insts.insert(self.allocas, inst);
+        self.allocas += 1;
+        Value::Inst(inst)
+    }
+
+    pub fn load(&mut self, ty: Type, ptr: Value) -> Value {
+        self.push(ty, InstKind::Load(ptr))
+    }
+
+    pub fn store(&mut self, value: Value, ptr: Value) {
+        self.push(Type::Void, InstKind::Store(value, ptr));
+    }
+
+    /// Add a byte offset to a pointer, unless it is a constant 0.
+    pub fn ptradd(&mut self, ptr: Value, offset: Value) -> Value {
+        match offset {
+            Value::Int(_, 0) => ptr,
+            _ => self.push(Type::Ptr, InstKind::PtrAdd(ptr, offset)),
+        }
+    }
+
+    pub fn call(&mut self, signature: Signature, callee: Value,
+        args: Vec<Value>) -> Value
+    {
+        let ty = signature.ret.clone();
+        self.push(ty, InstKind::Call(signature, callee, args))
+    }
+
+    pub fn phi(&mut self, ty: Type, incoming: Vec<(BlockId, Value)>)
+        -> Value
+    {
+        self.push(ty, InstKind::Phi(incoming))
+    }
+
+    pub fn extract_value(&mut self, aggregate: Value, index: u32) -> Value {
+        let ty = self.function.value_type(&aggregate);
+        let member = ty.member(index).cloned().unwrap_or(Type::Void);
+        self.push(member, InstKind::ExtractValue(aggregate, index))
+    }
+
+    pub fn insert_value(&mut self, aggregate: Value, value: Value,
+        index: u32) -> Value
+    {
+        let ty = self.function.value_type(&aggregate);
+        self.push(ty, InstKind::InsertValue(aggregate, value, index))
+    }
+
+    pub fn va_start(&mut self, va_list: Value) {
+        self.push(Type::Void, InstKind::VaStart(va_list));
+    }
+
+    pub fn va_arg(&mut self, ty: Type, va_list: Value) -> Value {
+        self.push(ty, InstKind::VaArg(va_list))
+    }
+
+    pub fn br(&mut self, block: BlockId) {
+        self.terminate(Terminator::Br(block));
+    }
+
+    pub fn cond_br(&mut self, cond: Value, then: BlockId, else_: BlockId) {
+        self.terminate(Terminator::CondBr(cond, then, else_));
+    }
+
+    pub fn switch(&mut self, value: Value, default: BlockId,
+        cases: Vec<(i128, BlockId)>)
+    {
+        self.terminate(Terminator::Switch(value, default, cases));
+    }
+
+    pub fn ret(&mut self, value: Option<Value>) {
+        self.terminate(Terminator::Ret(value));
+    }
+}
+
+// The names values and blocks are printed with.
+struct Names {
+    // Number of each instruction producing a value that is in a block.
+    insts: HashMap<InstId, usize>,
+}
+
+impl Names {
+    fn new(function: &Function) -> Self {
+        let mut insts = HashMap::new();
+        let mut next = function.signature.params.len();
+        for block in &function.blocks {
+            for &inst in &block.insts {
+                if function.insts[inst].ty != Type::Void {
+                    insts.insert(inst, next);
+                    next += 1;
+                }
+            }
+        }
+        Names { insts }
+    }
+
+    fn value(&self, value: &Value) -> String {
+        match value {
+            Value::Inst(inst) => match self.insts.get(inst) {
+                Some(number) => format!("%{}", number),
+                None => format!("%<removed {}>", inst),
+            },
+            Value::Param(index) => format!("%{}", index),
+            Value::Int(Type::Int(1), value) => match value {
+                0 => "false".to_string(),
+                _ => "true".to_string(),
+            },
+            Value::Int(_, value) => value.to_string(),
+            Value::Float(_, bits) => format!("{:?}", f64::from_bits(*bits)),
+            Value::Null => "null".to_string(),
+            Value::Undef(_) => "undef".to_string(),
+            Value::Zero(_) => "zeroinitializer".to_string(),
+            Value::Aggregate(ty, values) => {
+                let values: Vec<_> = values.iter()
+                    .map(|value| self.typed(ty_of_const(value), value))
+                    .collect();
+                match ty {
+                    Type::Array(..) => format!("[{}]", values.join(", ")),
+                    _ => format!("{{{}}}", values.join(", ")),
+                }
+            }
+            Value::Bytes(bytes) => format!("c\"{}\"", escape(bytes)),
+            Value::Global(name) => symbol(name),
+        }
+    }
+
+    fn typed(&self, ty: Type, value: &Value) -> String {
+        format!("{} {}", ty, self.value(value))
+    }
+}
+
+// The type of a constant.
+fn ty_of_const(value: &Value) -> Type {
+    match value {
+        Value::Int(ty, _)
+        | Value::Float(ty, _)
+        | Value::Undef(ty)
+        | Value::Zero(ty)
+        | Value::Aggregate(ty, _) => ty.clone(),
+        Value::Bytes(bytes) => {
+            Type::Array(Box::new(Type::I8), bytes.len() as u64)
+        }
+        _ => Type::Ptr,
+    }
+}
+
+// Write bytes as the contents of a quoted string.
+fn escape(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for &byte in bytes {
+        match byte {
+            b' '..=b'~' if byte != b'"' && byte != b'\\' => {
+                text.push(char::from(byte));
+            }
+            _ => text.push_str(&format!("\\{:02X}", byte)),
+        }
+    }
+    text
+}
+
+// A symbol name with its `@`, quoted unless it is a plain name.
+pub(crate) fn symbol(name: &str) -> String {
+    let plain = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(is_name_char);
+    match plain {
+        true => format!("@{}", name),
+        false => format!("@\"{}\"", escape(name.as_bytes())),
+    }
+}
+
+// Whether a character can be part of a name without quotes.
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '$'
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let signature = &self.signature;
+        let names = Names::new(self);
+        let keyword = match self.is_declaration() {
+            true => "declare",
+            false => "define",
+        };
+        write!(f, "{} {} {}(", keyword, signature.ret, symbol(&self.name))?;
+        for (i, param) in signature.params.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            match self.is_declaration() {
+                true => write!(f, "{}", param)?,
+                false => write!(f, "{} %{}", param, i)?,
+            }
+        }
+        if signature.variadic {
+            match signature.params.is_empty() {
+                true => write!(f, "...")?,
+                false => write!(f, ", ...")?,
+            }
+        }
+        if self.is_declaration() {
+            return writeln!(f, ")");
+        }
+        writeln!(f, ") {{")?;
+        for (id, block) in self.blocks.iter().enumerate() {
+            writeln!(f, "b{}:", id)?;
+            for &inst in &block.insts {
+                write!(f, "  ")?;
+                if let Some(number) = names.insts.get(&inst) {
+                    write!(f, "%{} = ", number)?;
+                }
+                self.fmt_inst(f, &names, &self.insts[inst])?;
+                writeln!(f)?;
+            }
+            write!(f, "  ")?;
+            self.fmt_term(f, &names, &block.term)?;
+            writeln!(f)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+impl Function {
+    fn fmt_inst(&self, f: &mut fmt::Formatter, names: &Names, inst: &Inst)
+        -> fmt::Result
+    {
+        let typed = |value: &Value| {
+            names.typed(self.value_type(value), value)
+        };
+        let value = |value: &Value| names.value(value);
+        match inst.kind {
+            InstKind::Binary(op, ref a, ref b) => write!(f, "{} {} {}, {}",
+                op.as_str(), inst.ty, value(a), value(b)),
+            InstKind::FNeg(ref a) => write!(f, "fneg {}", typed(a)),
+            InstKind::Icmp(pred, ref a, ref b) => write!(f, "icmp {} {}, {}",
+                pred.as_str(), typed(a), value(b)),
+            InstKind::Fcmp(pred, ref a, ref b) => write!(f, "fcmp {} {}, {}",
+                pred.as_str(), typed(a), value(b)),
+            InstKind::Cast(op, ref a) => write!(f, "{} {} to {}",
+                op.as_str(), typed(a), inst.ty),
+            InstKind::Select(ref cond, ref a, ref b) => write!(f,
+                "select {} {}, {}, {}", inst.ty, value(cond), value(a),
+                value(b)),
+            InstKind::Alloca(ref ty) => write!(f, "alloca {}", ty),
+            InstKind::Load(ref ptr) => {
+                write!(f, "load {}, {}", inst.ty, value(ptr))
+            }
+            InstKind::Store(ref a, ref ptr) => {
+                write!(f, "store {}, {}", typed(a), value(ptr))
+            }
+            InstKind::PtrAdd(ref ptr, ref offset) => {
+                write!(f, "ptradd {}, {}", value(ptr), value(offset))
+            }
+            InstKind::Call(ref signature, ref callee, ref args) => {
+                match signature.variadic {
+                    true => write!(f, "call {}", signature)?,
+                    false => write!(f, "call {}", signature.ret)?,
+                }
+                write!(f, " {}(", value(callee))?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", typed(arg))?;
+                }
+                write!(f, ")")
+            }
+            InstKind::Phi(ref incoming) => {
+                write!(f, "phi {}", inst.ty)?;
+                for (i, (block, a)) in incoming.iter().enumerate() {
+                    match i {
+                        0 => write!(f, " ")?,
+                        _ => write!(f, ", ")?,
+                    }
+                    write!(f, "[{}, b{}]", value(a), block)?;
+                }
+                Ok(())
+            }
+            InstKind::ExtractValue(ref aggregate, index) => {
+                write!(f, "extractvalue {}, {}", typed(aggregate), index)
+            }
+            InstKind::InsertValue(ref aggregate, ref a, index) => {
+                write!(f, "insertvalue {}, {}, {}", typed(aggregate),
+                    typed(a), index)
+            }
+            InstKind::VaStart(ref va_list) => {
+                write!(f, "va_start {}", value(va_list))
+            }
+            InstKind::VaArg(ref va_list) => {
+                write!(f, "va_arg {}, {}", inst.ty, value(va_list))
+            }
+        }
+    }
+
+    fn fmt_term(&self, f: &mut fmt::Formatter, names: &Names,
+        term: &Terminator) -> fmt::Result
+    {
+        match term {
+            Terminator::Br(block) => write!(f, "br b{}", block),
+            Terminator::CondBr(cond, then, else_) => write!(f,
+                "condbr {}, b{}, b{}", names.value(cond), then, else_),
+            Terminator::Switch(value, default, cases) => {
+                write!(f, "switch {}, b{} [",
+                    names.typed(self.value_type(value), value), default)?;
+                for (i, (case, block)) in cases.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: b{}", case, block)?;
+                }
+                write!(f, "]")
+            }
+            Terminator::Ret(Some(value)) => {
+                write!(f, "ret {}", names.typed(self.value_type(value), value))
+            }
+            Terminator::Ret(None) => write!(f, "ret void"),
+            Terminator::Unreachable => write!(f, "unreachable"),
+        }
+    }
+}
+
+impl fmt::Display for Global {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let keyword = match self.constant {
+            true => "constant",
+            false => "global",
+        };
+        match self.init {
+            Some(ref init) => write!(f, "{} = {} {} {}", symbol(&self.name),
+                keyword, self.ty, Names { insts: HashMap::new() }.value(init)),
+            None => write!(f, "{} = external {} {}", symbol(&self.name),
+                keyword, self.ty),
+        }
+    }
+}
+
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for global in &self.globals {
+            writeln!(f, "{}", global)?;
+        }
+        for (i, function) in self.functions.iter().enumerate() {
+            if i != 0 || !self.globals.is_empty() {
+                writeln!(f)?;
+            }
+            write!(f, "{}", function)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+// IR dominators
+//
+//! Dominator trees of the control-flow graphs of functions.
+//!
+//! Block `a` dominates block `b` if every path from the entry block to `b`
+//! goes through `a`.  The immediate dominator of a block is the one of its
+//! dominators that every other one dominates, found with the iterative
+//! algorithm of Cooper, Harvey and Kennedy ("A Simple, Fast Dominance
+//! Algorithm") over the blocks in reverse postorder.  Blocks that can't be
+//! reached from the entry block have no dominators.
+
+use super::{BlockId, Function};
+
+/// The dominator tree of a function
+#[derive(Debug, Clone)]
+pub struct Dominators {
+    // Immediate dominator of each reachable block, the entry being its own.
+    idom: Vec<Option<BlockId>>,
+    // The reachable blocks in reverse postorder.
+    order: Vec<BlockId>,
+    // Index of each reachable block in `order`.
+    index: Vec<Option<usize>>,
+    preds: Vec<Vec<BlockId>>,
+}
+
+impl Dominators {
+    /// Find the dominators of the blocks of a function with a body.
+    pub fn new(function: &Function) -> Self {
+        let count = function.blocks.len();
+        let preds = function.predecessors();
+
+        // Depth-first search, keeping the successors left to visit.
+        let mut postorder = Vec::new();
+        let mut visited = vec![false; count];
+        let mut stack = vec![(0, function.blocks[0].term.successors())];
+        visited[0] = true;
+        while let Some((block, succs)) = stack.last_mut() {
+            match succs.pop() {
+                Some(succ) if !visited[succ] => {
+                    visited[succ] = true;
+                    let succs = function.blocks[succ].term.successors();
+                    stack.push((succ, succs));
+                }
+                Some(_) => {}
+                None => {
+                    postorder.push(*block);
+                    stack.pop();
+                }
+            }
+        }
+        let order: Vec<_> = postorder.into_iter().rev().collect();
+        let mut index = vec![None; count];
+        for (i, &block) in order.iter().enumerate() {
+            index[block] = Some(i);
+        }
+
+        let mut idom = vec![None; count];
+        idom[0] = Some(0);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in &order[1..] {
+                let mut new = None;
+                for &pred in &preds[block] {
+                    if idom[pred].is_none() {
+                        continue;
+                    }
+                    new = Some(match new {
+                        None => pred,
+                        Some(other) => intersect(&idom, &index, pred, other),
+                    });
+                }
+                if new.is_some() && idom[block] != new {
+                    idom[block] = new;
+                    changed = true;
+                }
+            }
+        }
+
+        Dominators { idom, order, index, preds }
+    }
+
+    /// Whether a block can be reached from the entry block.
+    pub fn is_reachable(&self, block: BlockId) -> bool {
+        self.index[block].is_some()
+    }
+
+    /// The immediate dominator of a block, `None` for the entry block and
+    /// blocks that can't be reached.
+    pub fn idom(&self, block: BlockId) -> Option<BlockId> {
+        match block {
+            0 => None,
+            _ => self.idom[block],
+        }
+    }
+
+    /// Whether `a` dominates `b`, which every block reachable does itself.
+    pub fn dominates(&self, a: BlockId, mut b: BlockId) -> bool {
+        if !self.is_reachable(a) || !self.is_reachable(b) {
+            return false;
+        }
+        while a != b {
+            match self.idom(b) {
+                Some(idom) => b = idom,
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// The blocks that can be reached, in reverse postorder: each block
+    /// comes before the blocks it branches to, except along back edges.
+    pub fn reverse_postorder(&self) -> &[BlockId] {
+        &self.order
+    }
+
+    /// The distinct predecessors of each block.
+    pub fn predecessors(&self) -> &[Vec<BlockId>] {
+        &self.preds
+    }
+
+    /// The blocks each block immediately dominates, in reverse postorder.
+    pub fn children(&self) -> Vec<Vec<BlockId>> {
+        let mut children = vec![Vec::new(); self.idom.len()];
+        for &block in &self.order[1..] {
+            children[self.idom[block].unwrap()].push(block);
+        }
+        children
+    }
+
+    /// The dominance frontier of each block: the blocks it doesn't strictly
+    /// dominate but dominates a predecessor of, where paths from it meet
+    /// paths that avoid it.
+    pub fn frontiers(&self) -> Vec<Vec<BlockId>> {
+        let mut frontiers = vec![Vec::new(); self.idom.len()];
+        for &block in &self.order {
+            let preds: Vec<_> = self.preds[block].iter()
+                .filter(|&&pred| self.is_reachable(pred))
+                .collect();
+            if preds.len() < 2 {
+                continue;
+            }
+            let idom = self.idom[block].unwrap();
+            for &pred in preds {
+                // Walk up from the predecessor to the immediate dominator.
+                let mut runner = pred;
+                while runner != idom {
+                    if !frontiers[runner].contains(&block) {
+                        frontiers[runner].push(block);
+                    }
+                    match self.idom(runner) {
+                        Some(up) => runner = up,
+                        None => break,
+                    }
+                }
+            }
+        }
+        frontiers
+    }
+}
+
+// The nearest common dominator of two blocks.
+fn intersect(idom: &[Option<BlockId>], index: &[Option<usize>],
+    mut a: BlockId, mut b: BlockId) -> BlockId
+{
+    while a != b {
+        while index[a] > index[b] {
+            a = idom[a].unwrap();
+        }
+        while index[b] > index[a] {
+            b = idom[b].unwrap();
+        }
+    }
+    a
+}
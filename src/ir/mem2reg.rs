@@ -0,0 +1,286 @@
+// Promotion of memory to registers
+//
+//! The `mem2reg` pass: stack slots that are only loaded from and stored to
+//! become SSA values.
+//!
+//! An `alloca` can be promoted if every use of its address is the address
+//! of a `load` or `store` of its type, so nothing else can see the memory.
+//! A `phi` for it goes at the start of every block in the iterated
+//! dominance frontier of the blocks storing to it, where values stored on
+//! different paths meet (Cytron et al., "Efficiently Computing Static
+//! Single Assignment Form and the Control Dependence Graph").  Walking the
+//! dominator tree from the entry block, each `load` is then replaced by the
+//! value last stored on the way, or `undef` before the first `store`, and
+//! the `phi`s of each successor get the values at the end of the block.
+//! Loads, stores and the `alloca`s themselves are removed, and so are the
+//! `phi`s nothing but dead `phi`s uses.
+
+use std::collections::HashMap;
+
+use super::dominators::Dominators;
+use super::{Function, Inst, InstId, InstKind, Type, Value};
+
+/// Promote the stack slots of a function with a body that can be promoted
+/// to SSA values.  Blocks that can't be reached are removed first.  Returns
+/// whether anything changed.
+pub fn mem2reg(function: &mut Function) -> bool {
+    if function.is_declaration() {
+        return false;
+    }
+    function.remove_unreachable_blocks();
+    let slots = promotable(function);
+    if slots.is_empty() {
+        return false;
+    }
+    let index: HashMap<InstId, usize> = slots.iter().enumerate()
+        .map(|(i, &slot)| (slot, i))
+        .collect();
+    let dominators = Dominators::new(function);
+    let phis = insert_phis(function, &dominators, &slots, &index);
+    rename(function, &dominators, &slots, &index, &phis);
+    remove_dead_phis(function, &phis);
+    true
+}
+
+// The `alloca`s whose addresses are only used to load and store values of
+// their type.
+fn promotable(function: &Function) -> Vec<InstId> {
+    let mut slots: Vec<InstId> = Vec::new();
+    for block in &function.blocks {
+        for &inst in &block.insts {
+            match function.insts[inst].kind {
+                InstKind::Alloca(ref ty) if *ty != Type::Void => {
+                    slots.push(inst);
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut escapes = vec![false; function.insts.len()];
+    let mut escape = |value: &Value| {
+        if let Value::Inst(inst) = *value {
+            escapes[inst] = true;
+        }
+    };
+    for block in &function.blocks {
+        for &inst in &block.insts {
+            let Inst { ref ty, ref kind } = function.insts[inst];
+            match kind {
+                InstKind::Load(Value::Inst(slot))
+                    if function.insts[*slot].kind == InstKind::Alloca(
+                        ty.clone()) => {}
+                InstKind::Store(value, Value::Inst(slot))
+                    if function.insts[*slot].kind == InstKind::Alloca(
+                        function.value_type(value)) =>
+                {
+                    escape(value);
+                }
+                kind => kind.operands().into_iter().for_each(&mut escape),
+            }
+        }
+        if let Some(value) = block.term.operand() {
+            escape(value);
+        }
+    }
+    slots.retain(|&slot| !escapes[slot]);
+    slots
+}
+
+// Insert empty `phi`s for the slots, returning the slot of each `phi`.
+fn insert_phis(function: &mut Function, dominators: &Dominators,
+    slots: &[InstId], index: &HashMap<InstId, usize>)
+    -> HashMap<InstId, usize>
+{
+    // The blocks storing to each slot.
+    let mut stores = vec![Vec::new(); slots.len()];
+    for (id, block) in function.blocks.iter().enumerate() {
+        for &inst in &block.insts {
+            if let InstKind::Store(_, Value::Inst(ref slot)) =
+                function.insts[inst].kind
+            {
+                if let Some(&i) = index.get(slot) {
+                    if !stores[i].contains(&id) {
+                        stores[i].push(id);
+                    }
+                }
+            }
+        }
+    }
+
+    let frontiers = dominators.frontiers();
+    let mut phis = HashMap::new();
+    for (i, &slot) in slots.iter().enumerate() {
+        let ty = match function.insts[slot].kind {
+            InstKind::Alloca(ref ty) => ty.clone(),
+            _ => unreachable!(),
+        };
+        let mut has_phi = vec![false; function.blocks.len()];
+        let mut work = stores[i].clone();
+        while let Some(block) = work.pop() {
+            for &frontier in &frontiers[block] {
+                if has_phi[frontier] {
+                    continue;
+                }
+                has_phi[frontier] = true;
+                function.insts.push(Inst {
+                    ty: ty.clone(),
+                    kind: InstKind::Phi(Vec::new()),
+                });
+                let phi = function.insts.len() - 1;
+                function.blocks[frontier].insts.insert(0, phi);
+                phis.insert(phi, i);
+                if !stores[i].contains(&frontier) {
+                    work.push(frontier);
+                }
+            }
+        }
+    }
+    phis
+}
+
+// Replace the loads of the slots with the values stored, filling in the
+// `phi`s, and remove the slots with their loads and stores.
+fn rename(function: &mut Function, dominators: &Dominators, slots: &[InstId],
+    index: &HashMap<InstId, usize>, phis: &HashMap<InstId, usize>)
+{
+    let children = dominators.children();
+    let undef: Vec<_> = slots.iter()
+        .map(|&slot| match function.insts[slot].kind {
+            InstKind::Alloca(ref ty) => Value::Undef(ty.clone()),
+            _ => unreachable!(),
+        })
+        .collect();
+    // The values stored to each slot on the way to the current block, and
+    // the values of the removed loads.
+    let mut values: Vec<Vec<Value>> = vec![Vec::new(); slots.len()];
+    let mut replaced: HashMap<InstId, Value> = HashMap::new();
+    let resolve = |replaced: &HashMap<InstId, Value>, value: &mut Value| {
+        if let Value::Inst(inst) = *value {
+            if let Some(new) = replaced.get(&inst) {
+                *value = new.clone();
+            }
+        }
+    };
+
+    // Blocks to enter, and `None` to leave the block entered last, popping
+    // the values it pushed.
+    let mut stack = vec![Some(0)];
+    let mut pushed: Vec<Vec<usize>> = Vec::new();
+    while let Some(entry) = stack.pop() {
+        let block = match entry {
+            Some(block) => block,
+            None => {
+                for i in pushed.pop().unwrap() {
+                    values[i].pop();
+                }
+                continue;
+            }
+        };
+        let mut mine = Vec::new();
+        let mut kept = Vec::new();
+        for inst in std::mem::take(&mut function.blocks[block].insts) {
+            if let Some(&i) = phis.get(&inst) {
+                values[i].push(Value::Inst(inst));
+                mine.push(i);
+                kept.push(inst);
+                continue;
+            }
+            let kind = &mut function.insts[inst].kind;
+            for operand in kind.operands_mut() {
+                resolve(&replaced, operand);
+            }
+            match *kind {
+                InstKind::Alloca(_) if index.contains_key(&inst) => {}
+                InstKind::Load(Value::Inst(slot))
+                    if index.contains_key(&slot) =>
+                {
+                    let i = index[&slot];
+                    let value = values[i].last().unwrap_or(&undef[i]).clone();
+                    replaced.insert(inst, value);
+                }
+                InstKind::Store(ref value, Value::Inst(slot))
+                    if index.contains_key(&slot) =>
+                {
+                    let i = index[&slot];
+                    values[i].push(value.clone());
+                    mine.push(i);
+                }
+                _ => kept.push(inst),
+            }
+        }
+        function.blocks[block].insts = kept;
+        if let Some(operand) = function.blocks[block].term.operand_mut() {
+            resolve(&replaced, operand);
+        }
+
+        let mut succs = function.blocks[block].term.successors();
+        succs.sort_unstable();
+        succs.dedup();
+        for succ in succs {
+            for &inst in &function.blocks[succ].insts {
+                let i = match phis.get(&inst) {
+                    Some(&i) => i,
+                    None => continue,
+                };
+                let value = values[i].last().unwrap_or(&undef[i]).clone();
+                if let InstKind::Phi(ref mut incoming) =
+                    function.insts[inst].kind
+                {
+                    incoming.push((block, value));
+                }
+            }
+        }
+
+        pushed.push(mine);
+        stack.push(None);
+        stack.extend(children[block].iter().rev().map(|&child| Some(child)));
+    }
+
+    // The `phi`s there were already can have values from blocks entered
+    // after theirs, along back edges.
+    for block in &function.blocks {
+        for &inst in &block.insts {
+            if let InstKind::Phi(ref mut incoming) = function.insts[inst].kind {
+                for (_, value) in incoming {
+                    resolve(&replaced, value);
+                }
+            }
+        }
+    }
+}
+
+// Remove the inserted `phi`s that only dead `phi`s use.
+fn remove_dead_phis(function: &mut Function, phis: &HashMap<InstId, usize>) {
+    let mut live: Vec<bool> = vec![false; function.insts.len()];
+    let mut work = Vec::new();
+    let mut mark = |value: &Value, work: &mut Vec<InstId>| {
+        if let Value::Inst(inst) = *value {
+            if phis.contains_key(&inst) && !live[inst] {
+                live[inst] = true;
+                work.push(inst);
+            }
+        }
+    };
+    for block in &function.blocks {
+        for &inst in &block.insts {
+            if phis.contains_key(&inst) {
+                continue;
+            }
+            for operand in function.insts[inst].kind.operands() {
+                mark(operand, &mut work);
+            }
+        }
+        if let Some(value) = block.term.operand() {
+            mark(value, &mut work);
+        }
+    }
+    while let Some(phi) = work.pop() {
+        for operand in function.insts[phi].kind.operands() {
+            mark(operand, &mut work);
+        }
+    }
+    for block in &mut function.blocks {
+        block.insts.retain(|inst| !phis.contains_key(inst) || live[*inst]);
+    }
+}
+
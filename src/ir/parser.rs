@@ -0,0 +1,818 @@
+// IR parser
+//
+//! Parsing of the text format of the IR back into a [`Module`].
+//!
+//! Values and blocks may be used before they are defined, so instructions
+//! and blocks are numbered as they are first seen, and the blocks are put in
+//! the order they are written once the whole function is read.  The parser
+//! only checks what it needs to build the module; [`verify`](super::verify)
+//! checks the rest.
+
+use std::collections::HashMap;
+
+use super::{
+    Block, BlockId, FloatPredicate, Function, Global, Inst, InstId,
+    InstKind, IntPredicate, Module, Result, Signature, Terminator, Type,
+    Value, BINARY_OPS, CAST_OPS, FLOAT_PREDICATES, INT_PREDICATES,
+};
+use crate::{Diagnostic, Span};
+
+/// Parse a module from its text format.
+pub fn parse(text: &str) -> Result<Module> {
+    let mut parser = Parser {
+        text,
+        tokens: tokenize(text)?,
+        at: 0,
+        body: None,
+    };
+    let mut module = Module::new();
+    while let Some((token, span)) = parser.peek() {
+        match token {
+            Token::Global(_) => module.globals.push(parser.global()?),
+            Token::Word("define") | Token::Word("declare") => {
+                module.functions.push(parser.function()?);
+            }
+            _ => {
+                return Err(parser.unexpected(span,
+                    "`define`, `declare` or a global variable"));
+            }
+        }
+    }
+    Ok(module)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'a> {
+    /// `%name`
+    Local(String),
+    /// `@name`
+    Global(String),
+    /// Keyword, type, label or other name
+    Word(&'a str),
+    Number(&'a str),
+    /// `c"..."`
+    Bytes(Vec<u8>),
+    /// `=`, `,`, `:`, or a bracket
+    Punct(char),
+    /// `...`
+    Ellipsis,
+}
+
+// Split text into tokens.
+fn tokenize(text: &str) -> Result<Vec<(Token<'_>, Span)>> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut at = 0;
+    let name_end = |mut at: usize| {
+        while at < bytes.len() && super::is_name_char(char::from(bytes[at])) {
+            at += 1;
+        }
+        at
+    };
+    while at < bytes.len() {
+        let start = at;
+        let byte = bytes[at];
+        let token = match byte {
+            b' ' | b'\t' | b'\r' | b'\n' => {
+                at += 1;
+                continue;
+            }
+            b';' => {
+                while at < bytes.len() && bytes[at] != b'\n' {
+                    at += 1;
+                }
+                continue;
+            }
+            b'%' | b'@' => {
+                let name = match bytes.get(at + 1) {
+                    Some(b'"') => {
+                        let (name, end) = string(text, at + 1)?;
+                        at = end;
+                        String::from_utf8(name).map_err(|_| {
+                            Diagnostic::new(Span::new(start, at),
+                                "names must be UTF-8")
+                        })?
+                    }
+                    _ => {
+                        at = name_end(at + 1);
+                        if at == start + 1 {
+                            return Err(Diagnostic::new(
+                                Span::new(start, at), "expected a name"));
+                        }
+                        text[start + 1..at].to_string()
+                    }
+                };
+                match byte {
+                    b'%' => Token::Local(name),
+                    _ => Token::Global(name),
+                }
+            }
+            b'c' if bytes.get(at + 1) == Some(&b'"') => {
+                let (string, end) = string(text, at + 1)?;
+                at = end;
+                Token::Bytes(string)
+            }
+            b'.' if text[at..].starts_with("...") => {
+                at += 3;
+                Token::Ellipsis
+            }
+            b'-' | b'0'..=b'9' => {
+                at += 1;
+                while at < bytes.len() {
+                    let exponent = matches!(bytes[at - 1], b'e' | b'E')
+                        && matches!(bytes[at], b'+' | b'-');
+                    if !exponent
+                        && !bytes[at].is_ascii_alphanumeric()
+                        && bytes[at] != b'.'
+                    {
+                        break;
+                    }
+                    at += 1;
+                }
+                Token::Number(&text[start..at])
+            }
+            b'=' | b',' | b':' | b'(' | b')' | b'[' | b']' | b'{' | b'}' => {
+                at += 1;
+                Token::Punct(char::from(byte))
+            }
+            _ if super::is_name_char(char::from(byte)) => {
+                at = name_end(at);
+                Token::Word(&text[start..at])
+            }
+            _ => {
+                let len = text[at..].chars().next().map_or(1, char::len_utf8);
+                return Err(Diagnostic::new(Span::new(at, at + len),
+                    "unexpected character"));
+            }
+        };
+        tokens.push((token, Span::new(start, at)));
+    }
+    Ok(tokens)
+}
+
+// Read the quoted string starting at `start`, returning its bytes and where
+// it ends.
+fn string(text: &str, start: usize) -> Result<(Vec<u8>, usize)> {
+    let bytes = text.as_bytes();
+    let mut string = Vec::new();
+    let mut at = start + 1;
+    loop {
+        match bytes.get(at) {
+            None | Some(b'\n') => {
+                return Err(Diagnostic::new(Span::new(start, at),
+                    "unterminated string"));
+            }
+            Some(b'"') => return Ok((string, at + 1)),
+            Some(b'\\') => {
+                let byte = text.get(at + 1..at + 3)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match byte {
+                    Some(byte) => string.push(byte),
+                    None => {
+                        return Err(Diagnostic::new(Span::new(at, at + 1),
+                            "expected two hex digits after `\\`"));
+                    }
+                }
+                at += 3;
+            }
+            Some(&byte) => {
+                string.push(byte);
+                at += 1;
+            }
+        }
+    }
+}
+
+// Look up the operation named `name` in a table of names.
+fn find<T: Copy>(table: &[(&str, T)], name: &str) -> Option<T> {
+    table.iter().find(|(text, _)| *text == name).map(|(_, op)| *op)
+}
+
+// The names of the function being parsed.
+#[derive(Default)]
+struct Body {
+    // Parameters and instructions by name, with where instructions are
+    // first used and whether they are defined yet.
+    values: HashMap<String, (Value, Span, bool)>,
+    insts: Vec<Option<Inst>>,
+    // Blocks by name, with where they are first used and whether they are
+    // defined yet.
+    blocks: HashMap<String, (BlockId, Span, bool)>,
+    // The blocks by number, once they are defined.
+    defined: Vec<Option<Block>>,
+    // Block numbers in the order the blocks are written.
+    order: Vec<BlockId>,
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<(Token<'a>, Span)>,
+    at: usize,
+    // The function whose body is being parsed.
+    body: Option<Body>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<(Token<'a>, Span)> {
+        self.tokens.get(self.at).cloned()
+    }
+
+    // The span of the next token, or the end of the text.
+    fn span(&self) -> Span {
+        match self.tokens.get(self.at) {
+            Some((_, span)) => *span,
+            None => Span::new(self.text.len(), self.text.len()),
+        }
+    }
+
+    fn next(&mut self, what: &str) -> Result<(Token<'a>, Span)> {
+        match self.peek() {
+            Some(token) => {
+                self.at += 1;
+                Ok(token)
+            }
+            None => Err(self.unexpected(self.span(), what)),
+        }
+    }
+
+    // An error for finding the token at `span` instead of `what`.
+    fn unexpected(&self, span: Span, what: &str) -> Diagnostic {
+        let found = match span.start == self.text.len() {
+            true => "the end of the text".to_string(),
+            false => format!("`{}`", &self.text[span.start..span.end]),
+        };
+        Diagnostic::new(span, format!("expected {}, found {}", what, found))
+    }
+
+    // Skip the next token if it is `token`.
+    fn eat(&mut self, token: Token) -> bool {
+        match self.tokens.get(self.at) {
+            Some((next, _)) if *next == token => {
+                self.at += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<()> {
+        let what = match token {
+            Token::Punct(c) => format!("`{}`", c),
+            Token::Word(word) => format!("`{}`", word),
+            _ => "`...`".to_string(),
+        };
+        match self.eat(token) {
+            true => Ok(()),
+            false => Err(self.unexpected(self.span(), &what)),
+        }
+    }
+
+    fn word(&mut self, what: &str) -> Result<(&'a str, Span)> {
+        match self.next(what)? {
+            (Token::Word(word), span) => Ok((word, span)),
+            (_, span) => Err(self.unexpected(span, what)),
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&mut self, what: &str) -> Result<T> {
+        match self.next(what)? {
+            (Token::Number(number), span) => number.parse()
+                .map_err(|_| self.unexpected(span, what)),
+            (_, span) => Err(self.unexpected(span, what)),
+        }
+    }
+
+    fn ty(&mut self) -> Result<Type> {
+        let (token, span) = self.next("a type")?;
+        Ok(match token {
+            Token::Word("void") => Type::Void,
+            Token::Word("f32") => Type::F32,
+            Token::Word("f64") => Type::F64,
+            Token::Word("ptr") => Type::Ptr,
+            Token::Word(word) if word.starts_with('i') => {
+                match word[1..].parse::<u32>() {
+                    Ok(bits) if [1, 8, 16, 32, 64, 128].contains(&bits) => {
+                        Type::Int(bits)
+                    }
+                    _ => return Err(self.unexpected(span, "a type")),
+                }
+            }
+            Token::Punct('[') => {
+                let len = self.number("an array length")?;
+                self.expect(Token::Word("x"))?;
+                let ty = self.ty()?;
+                self.expect(Token::Punct(']'))?;
+                Type::Array(Box::new(ty), len)
+            }
+            Token::Punct('{') => {
+                let mut fields = Vec::new();
+                if !self.eat(Token::Punct('}')) {
+                    loop {
+                        fields.push(self.ty()?);
+                        if self.eat(Token::Punct('}')) {
+                            break;
+                        }
+                        self.expect(Token::Punct(','))?;
+                    }
+                }
+                Type::Struct(fields)
+            }
+            _ => return Err(self.unexpected(span, "a type")),
+        })
+    }
+
+    // A value of type `ty`.
+    fn value(&mut self, ty: &Type) -> Result<Value> {
+        let (token, span) = self.next("a value")?;
+        let text = self.text;
+        let mismatch = || Diagnostic::new(span,
+            format!("`{}` isn't a value of type `{}`",
+                &text[span.start..span.end], ty));
+        Ok(match token {
+            Token::Local(name) => self.local(name, span)?,
+            Token::Global(name) => Value::Global(name),
+            Token::Number(number) => match ty {
+                Type::Int(_) => {
+                    let value = number.parse::<i128>().ok()
+                        .or_else(|| number.parse::<u128>().ok()
+                            .map(|value| value as i128));
+                    match value {
+                        Some(value) => Value::int(ty.clone(), value),
+                        None => return Err(mismatch()),
+                    }
+                }
+                Type::F32 | Type::F64 => match number.parse() {
+                    Ok(value) => Value::float(ty.clone(), value),
+                    Err(_) => return Err(mismatch()),
+                },
+                _ => return Err(mismatch()),
+            },
+            Token::Word(word @ "inf") | Token::Word(word @ "NaN")
+                if ty.is_float() =>
+            {
+                Value::float(ty.clone(), word.parse().unwrap())
+            }
+            Token::Word(word @ "true") | Token::Word(word @ "false")
+                if *ty == Type::I1 =>
+            {
+                Value::bool(word == "true")
+            }
+            Token::Word("null") if *ty == Type::Ptr => Value::Null,
+            Token::Word("undef") => Value::Undef(ty.clone()),
+            Token::Word("zeroinitializer") => Value::Zero(ty.clone()),
+            Token::Punct(open @ '{') | Token::Punct(open @ '[') => {
+                let close = match open {
+                    '{' => '}',
+                    _ => ']',
+                };
+                let mut values = Vec::new();
+                if !self.eat(Token::Punct(close)) {
+                    loop {
+                        let member = self.ty()?;
+                        values.push(self.value(&member)?);
+                        if self.eat(Token::Punct(close)) {
+                            break;
+                        }
+                        self.expect(Token::Punct(','))?;
+                    }
+                }
+                Value::Aggregate(ty.clone(), values)
+            }
+            Token::Bytes(bytes) => Value::Bytes(bytes),
+            _ => return Err(self.unexpected(span, "a value")),
+        })
+    }
+
+    fn typed_value(&mut self) -> Result<Value> {
+        let ty = self.ty()?;
+        self.value(&ty)
+    }
+
+    // The value named `%name` in the function being parsed.
+    fn local(&mut self, name: String, span: Span) -> Result<Value> {
+        let body = match self.body {
+            Some(ref mut body) => body,
+            None => {
+                return Err(Diagnostic::new(span,
+                    "global variables can only be initialized with \
+                        constants"));
+            }
+        };
+        let insts = &mut body.insts;
+        let (value, _, _) = body.values.entry(name).or_insert_with(|| {
+            insts.push(None);
+            (Value::Inst(insts.len() - 1), span, false)
+        });
+        Ok(value.clone())
+    }
+
+    // The block named `name` in the function being parsed.
+    fn block(&mut self) -> Result<BlockId> {
+        let (name, span) = self.word("a block")?;
+        let body = self.body.as_mut().unwrap();
+        let defined = &mut body.defined;
+        let (block, _, _) = body.blocks.entry(name.to_string())
+            .or_insert_with(|| {
+                defined.push(None);
+                (defined.len() - 1, span, false)
+            });
+        Ok(*block)
+    }
+
+    fn global(&mut self) -> Result<Global> {
+        let name = match self.next("a global variable")? {
+            (Token::Global(name), _) => name,
+            (_, span) => return Err(self.unexpected(span, "a global variable")),
+        };
+        self.expect(Token::Punct('='))?;
+        let external = self.eat(Token::Word("external"));
+        let constant = match self.word("`global` or `constant`")? {
+            ("global", _) => false,
+            ("constant", _) => true,
+            (_, span) => {
+                return Err(self.unexpected(span, "`global` or `constant`"));
+            }
+        };
+        let ty = self.ty()?;
+        let init = match external {
+            true => None,
+            false => Some(self.value(&ty)?),
+        };
+        Ok(Global { name, ty, init, constant })
+    }
+
+    fn function(&mut self) -> Result<Function> {
+        let (keyword, _) = self.word("`define` or `declare`")?;
+        let ret = self.ty()?;
+        let name = match self.next("a function name")? {
+            (Token::Global(name), _) => name,
+            (_, span) => return Err(self.unexpected(span, "a function name")),
+        };
+        let mut body = Body::default();
+        let mut signature = Signature::new(ret, Vec::new());
+        self.expect(Token::Punct('('))?;
+        if !self.eat(Token::Punct(')')) {
+            loop {
+                if self.eat(Token::Ellipsis) {
+                    signature.variadic = true;
+                    self.expect(Token::Punct(')'))?;
+                    break;
+                }
+                signature.params.push(self.ty()?);
+                let index = signature.params.len() - 1;
+                match self.peek() {
+                    Some((Token::Local(name), span)) => {
+                        self.at += 1;
+                        let param = (Value::Param(index), span, true);
+                        if body.values.insert(name.clone(), param).is_some() {
+                            return Err(Diagnostic::new(span, format!(
+                                "`%{}` is defined twice", name)));
+                        }
+                    }
+                    _ if keyword == "define" => {
+                        return Err(self.unexpected(self.span(),
+                            "a parameter name"));
+                    }
+                    _ => {}
+                }
+                if self.eat(Token::Punct(')')) {
+                    break;
+                }
+                self.expect(Token::Punct(','))?;
+            }
+        }
+        let mut function = Function::new(name, signature);
+        if keyword == "declare" {
+            return Ok(function);
+        }
+
+        self.expect(Token::Punct('{'))?;
+        self.body = Some(body);
+        while !self.eat(Token::Punct('}')) {
+            self.basic_block(&function.signature)?;
+        }
+        let body = self.body.take().unwrap();
+        if body.order.is_empty() {
+            return Err(Diagnostic::new(self.tokens[self.at - 1].1,
+                "a function definition needs a block"));
+        }
+        let undefined = body.values.iter()
+            .filter(|(_, (_, _, defined))| !defined)
+            .min_by_key(|(_, (_, span, _))| span.start);
+        if let Some((name, (_, span, _))) = undefined {
+            return Err(Diagnostic::new(*span,
+                format!("`%{}` isn't defined", name)));
+        }
+        let undefined = body.blocks.iter()
+            .filter(|(_, (_, _, defined))| !defined)
+            .min_by_key(|(_, (_, span, _))| span.start);
+        if let Some((name, (_, span, _))) = undefined {
+            return Err(Diagnostic::new(*span,
+                format!("block `{}` isn't defined", name)));
+        }
+        function.insts = body.insts.into_iter().map(Option::unwrap).collect();
+        function.blocks = body.defined.into_iter().map(Option::unwrap)
+            .collect();
+        function.reorder_blocks(&body.order);
+        Ok(function)
+    }
+
+    // A label and the instructions of its block up to the terminator.
+    fn basic_block(&mut self, signature: &Signature) -> Result<()> {
+        let (_, label_span) = match self.peek() {
+            Some((Token::Word(word), span)) => (word, span),
+            _ => return Err(self.unexpected(self.span(), "a block label")),
+        };
+        let block = self.block()?;
+        self.expect(Token::Punct(':'))?;
+        let mut insts = Vec::new();
+        let term = loop {
+            if let Some(term) = self.terminator(signature)? {
+                break term;
+            }
+            let (name, span) = match self.peek() {
+                Some((Token::Local(name), span)) => {
+                    self.at += 1;
+                    self.expect(Token::Punct('='))?;
+                    (Some(name), span)
+                }
+                _ => (None, self.span()),
+            };
+            let inst = self.inst()?;
+            let id = match name {
+                Some(name) => {
+                    if inst.ty == Type::Void {
+                        return Err(Diagnostic::new(span, format!(
+                            "`%{}` is given an instruction without a value",
+                            name)));
+                    }
+                    let id = match self.local(name.clone(), span)? {
+                        Value::Inst(id) => id,
+                        _ => InstId::MAX,
+                    };
+                    let body = self.body.as_mut().unwrap();
+                    let entry = body.values.get_mut(&name).unwrap();
+                    if entry.2 {
+                        return Err(Diagnostic::new(span,
+                            format!("`%{}` is defined twice", name)));
+                    }
+                    entry.2 = true;
+                    body.insts[id] = Some(inst);
+                    id
+                }
+                None => {
+                    let body = self.body.as_mut().unwrap();
+                    body.insts.push(Some(inst));
+                    body.insts.len() - 1
+                }
+            };
+            insts.push(id);
+        };
+        let body = self.body.as_mut().unwrap();
+        let name = &self.text[label_span.start..label_span.end];
+        let entry = body.blocks.get_mut(name).unwrap();
+        if entry.2 {
+            return Err(Diagnostic::new(label_span,
+                format!("block `{}` is defined twice", name)));
+        }
+        entry.2 = true;
+        body.defined[block] = Some(Block { insts, term });
+        body.order.push(block);
+        Ok(())
+    }
+
+    // A terminator, if one is next.
+    fn terminator(&mut self, signature: &Signature)
+        -> Result<Option<Terminator>>
+    {
+        let word = match self.peek() {
+            Some((Token::Word(word), _)) => word,
+            _ => return Ok(None),
+        };
+        if !["br", "condbr", "switch", "ret", "unreachable"].contains(&word) {
+            return Ok(None);
+        }
+        self.at += 1;
+        Ok(Some(match word {
+            "br" => Terminator::Br(self.block()?),
+            "condbr" => {
+                let cond = self.value(&Type::I1)?;
+                self.expect(Token::Punct(','))?;
+                let then = self.block()?;
+                self.expect(Token::Punct(','))?;
+                Terminator::CondBr(cond, then, self.block()?)
+            }
+            "switch" => {
+                let value = self.typed_value()?;
+                self.expect(Token::Punct(','))?;
+                let default = self.block()?;
+                self.expect(Token::Punct('['))?;
+                let mut cases = Vec::new();
+                if !self.eat(Token::Punct(']')) {
+                    loop {
+                        let case = self.number("a case value")?;
+                        self.expect(Token::Punct(':'))?;
+                        cases.push((case, self.block()?));
+                        if self.eat(Token::Punct(']')) {
+                            break;
+                        }
+                        self.expect(Token::Punct(','))?;
+                    }
+                }
+                Terminator::Switch(value, default, cases)
+            }
+            "ret" => match self.eat(Token::Word("void")) {
+                true => Terminator::Ret(None),
+                false => {
+                    let span = self.span();
+                    let ty = self.ty()?;
+                    if ty != signature.ret {
+                        return Err(Diagnostic::new(span, format!(
+                            "the function returns `{}`", signature.ret)));
+                    }
+                    Terminator::Ret(Some(self.value(&ty)?))
+                }
+            },
+            _ => Terminator::Unreachable,
+        }))
+    }
+
+    fn inst(&mut self) -> Result<Inst> {
+        let (op, span) = self.word("an instruction")?;
+        if let Some(op) = find(BINARY_OPS, op) {
+            let ty = self.ty()?;
+            let a = self.value(&ty)?;
+            self.expect(Token::Punct(','))?;
+            let b = self.value(&ty)?;
+            return Ok(Inst { ty, kind: InstKind::Binary(op, a, b) });
+        }
+        if let Some(op) = find(CAST_OPS, op) {
+            let a = self.typed_value()?;
+            self.expect(Token::Word("to"))?;
+            let ty = self.ty()?;
+            return Ok(Inst { ty, kind: InstKind::Cast(op, a) });
+        }
+        let kind = match op {
+            "fneg" => {
+                let ty = self.ty()?;
+                let a = self.value(&ty)?;
+                return Ok(Inst { ty, kind: InstKind::FNeg(a) });
+            }
+            "icmp" => {
+                let (pred, span) = self.word("a comparison")?;
+                let pred: IntPredicate = find(INT_PREDICATES, pred)
+                    .ok_or_else(|| self.unexpected(span, "a comparison"))?;
+                let ty = self.ty()?;
+                let a = self.value(&ty)?;
+                self.expect(Token::Punct(','))?;
+                InstKind::Icmp(pred, a, self.value(&ty)?)
+            }
+            "fcmp" => {
+                let (pred, span) = self.word("a comparison")?;
+                let pred: FloatPredicate = find(FLOAT_PREDICATES, pred)
+                    .ok_or_else(|| self.unexpected(span, "a comparison"))?;
+                let ty = self.ty()?;
+                let a = self.value(&ty)?;
+                self.expect(Token::Punct(','))?;
+                InstKind::Fcmp(pred, a, self.value(&ty)?)
+            }
+            "select" => {
+                let ty = self.ty()?;
+                let cond = self.value(&Type::I1)?;
+                self.expect(Token::Punct(','))?;
+                let a = self.value(&ty)?;
+                self.expect(Token::Punct(','))?;
+                let b = self.value(&ty)?;
+                return Ok(Inst { ty, kind: InstKind::Select(cond, a, b) });
+            }
+            "alloca" => {
+                let ty = self.ty()?;
+                return Ok(Inst { ty: Type::Ptr, kind: InstKind::Alloca(ty) });
+            }
+            "load" => {
+                let ty = self.ty()?;
+                self.expect(Token::Punct(','))?;
+                let ptr = self.value(&Type::Ptr)?;
+                return Ok(Inst { ty, kind: InstKind::Load(ptr) });
+            }
+            "store" => {
+                let a = self.typed_value()?;
+                self.expect(Token::Punct(','))?;
+                let ptr = self.value(&Type::Ptr)?;
+                return Ok(Inst { ty: Type::Void, kind: InstKind::Store(a, ptr) });
+            }
+            "ptradd" => {
+                let ptr = self.value(&Type::Ptr)?;
+                self.expect(Token::Punct(','))?;
+                let offset = self.value(&Type::I64)?;
+                InstKind::PtrAdd(ptr, offset)
+            }
+            "call" => return self.call(),
+            "va_start" => {
+                let va_list = self.value(&Type::Ptr)?;
+                return Ok(Inst {
+                    ty: Type::Void,
+                    kind: InstKind::VaStart(va_list),
+                });
+            }
+            "va_arg" => {
+                let ty = self.ty()?;
+                self.expect(Token::Punct(','))?;
+                let va_list = self.value(&Type::Ptr)?;
+                return Ok(Inst { ty, kind: InstKind::VaArg(va_list) });
+            }
+            "phi" => {
+                let ty = self.ty()?;
+                let mut incoming = Vec::new();
+                loop {
+                    self.expect(Token::Punct('['))?;
+                    let value = self.value(&ty)?;
+                    self.expect(Token::Punct(','))?;
+                    incoming.push((self.block()?, value));
+                    self.expect(Token::Punct(']'))?;
+                    if !self.eat(Token::Punct(',')) {
+                        break;
+                    }
+                }
+                return Ok(Inst { ty, kind: InstKind::Phi(incoming) });
+            }
+            "extractvalue" => {
+                let ty = self.ty()?;
+                let aggregate = self.value(&ty)?;
+                self.expect(Token::Punct(','))?;
+                let index = self.number("an index")?;
+                let member = ty.member(index).cloned().unwrap_or(Type::Void);
+                return Ok(Inst {
+                    ty: member,
+                    kind: InstKind::ExtractValue(aggregate, index),
+                });
+            }
+            "insertvalue" => {
+                let ty = self.ty()?;
+                let aggregate = self.value(&ty)?;
+                self.expect(Token::Punct(','))?;
+                let a = self.typed_value()?;
+                self.expect(Token::Punct(','))?;
+                let index = self.number("an index")?;
+                return Ok(Inst {
+                    ty,
+                    kind: InstKind::InsertValue(aggregate, a, index),
+                });
+            }
+            _ => return Err(self.unexpected(span, "an instruction")),
+        };
+        let ty = match kind {
+            InstKind::PtrAdd(..) => Type::Ptr,
+            _ => Type::I1,
+        };
+        Ok(Inst { ty, kind })
+    }
+
+    // The rest of a `call`: the return type or signature, the callee and
+    // the arguments.
+    fn call(&mut self) -> Result<Inst> {
+        let ret = self.ty()?;
+        let signature = match self.eat(Token::Punct('(')) {
+            true => {
+                let mut signature = Signature::new(ret.clone(), Vec::new());
+                if !self.eat(Token::Punct(')')) {
+                    loop {
+                        if self.eat(Token::Ellipsis) {
+                            signature.variadic = true;
+                            self.expect(Token::Punct(')'))?;
+                            break;
+                        }
+                        signature.params.push(self.ty()?);
+                        if self.eat(Token::Punct(')')) {
+                            break;
+                        }
+                        self.expect(Token::Punct(','))?;
+                    }
+                }
+                Some(signature)
+            }
+            false => None,
+        };
+        let callee = self.value(&Type::Ptr)?;
+        self.expect(Token::Punct('('))?;
+        let mut args = Vec::new();
+        let mut types = Vec::new();
+        if !self.eat(Token::Punct(')')) {
+            loop {
+                let ty = self.ty()?;
+                args.push(self.value(&ty)?);
+                types.push(ty);
+                if self.eat(Token::Punct(')')) {
+                    break;
+                }
+                self.expect(Token::Punct(','))?;
+            }
+        }
+        let signature = signature
+            .unwrap_or_else(|| Signature::new(ret, types));
+        Ok(Inst {
+            ty: signature.ret.clone(),
+            kind: InstKind::Call(signature, callee, args),
+        })
+    }
+}
@@ -0,0 +1,181 @@
+// IR optimization passes
+//
+//! Passes transforming IR modules, and the pipelines of them run at each
+//! optimization level.
+//!
+//! | Name          | Pass                                                |
+//! |---------------|-----------------------------------------------------|
+//! | `mem2reg`     | Promote stack slots to SSA values, see [`mem2reg`]  |
+//! | `sccp`        | Sparse conditional constant propagation             |
+//! | `dce`         | Dead code elimination                               |
+//! | `gvn`         | Global value numbering                              |
+//! | `simplifycfg` | Control-flow graph simplification                   |
+//! | `licm`        | Loop-invariant code motion                          |
+//! | `inline`      | Inlining of calls to small functions                |
+//!
+//! `-O0` runs nothing and `-O1` only the cheap cleanups.  `-O2` also
+//! inlines and removes redundancy, and `-O3` inlines bigger functions and
+//! runs the cleanups again afterwards.  Any pass can be enabled or disabled
+//! on top of a level, a pass enabled that isn't in the level's pipeline
+//! running at the end.
+//!
+//! [`mem2reg`]: super::mem2reg
+
+mod dce;
+mod gvn;
+mod inline;
+mod licm;
+mod sccp;
+mod simplifycfg;
+
+use super::{mem2reg, verify, Module, Result};
+use crate::Diagnostic;
+
+/// A pass over a module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    Mem2Reg,
+    Sccp,
+    Dce,
+    Gvn,
+    SimplifyCfg,
+    Licm,
+    Inline,
+}
+
+const PASSES: &[(&str, Pass)] = &[
+    ("mem2reg", Pass::Mem2Reg),
+    ("sccp", Pass::Sccp),
+    ("dce", Pass::Dce),
+    ("gvn", Pass::Gvn),
+    ("simplifycfg", Pass::SimplifyCfg),
+    ("licm", Pass::Licm),
+    ("inline", Pass::Inline),
+];
+
+/// The cost of the biggest function `-O2` inlines.
+pub const INLINE_THRESHOLD: usize = 40;
+
+impl Pass {
+    /// Every pass, in the order of the table above.
+    pub fn all() -> impl Iterator<Item = Pass> {
+        PASSES.iter().map(|&(_, pass)| pass)
+    }
+
+    /// Get the pass with a name.
+    pub fn named(name: &str) -> Option<Pass> {
+        PASSES.iter().find(|(n, _)| *n == name).map(|&(_, pass)| pass)
+    }
+
+    /// Get the name of the pass.
+    pub fn as_str(self) -> &'static str {
+        PASSES[self as usize].0
+    }
+
+    /// Run the pass over a module, inlining with [`INLINE_THRESHOLD`].
+    /// Returns whether anything changed.
+    pub fn run(self, module: &mut Module) -> bool {
+        self.run_with(module, INLINE_THRESHOLD)
+    }
+
+    fn run_with(self, module: &mut Module, inline_threshold: usize) -> bool {
+        let function_pass = match self {
+            Pass::Mem2Reg => mem2reg,
+            Pass::Sccp => sccp::sccp,
+            Pass::Dce => dce::dce,
+            Pass::Gvn => gvn::gvn,
+            Pass::SimplifyCfg => simplifycfg::simplify_cfg,
+            Pass::Licm => licm::licm,
+            Pass::Inline => return inline::inline(module, inline_threshold),
+        };
+        let mut changed = false;
+        for function in &mut module.functions {
+            if !function.is_declaration() {
+                changed |= function_pass(function);
+            }
+        }
+        changed
+    }
+}
+
+/// A pipeline of passes
+#[derive(Debug, Clone, Default)]
+pub struct PassManager {
+    passes: Vec<Pass>,
+    disabled: Vec<Pass>,
+    /// The cost of the biggest function inlined
+    pub inline_threshold: usize,
+    /// Whether to verify the module after every pass
+    pub verify_each: bool,
+}
+
+impl PassManager {
+    /// Create an empty pipeline.
+    pub fn new() -> Self {
+        PassManager { inline_threshold: INLINE_THRESHOLD, ..Self::default() }
+    }
+
+    /// Create the pipeline of an optimization level from 0 to 3.
+    pub fn for_level(level: u32) -> Self {
+        use Pass::*;
+
+        let mut manager = PassManager::new();
+        manager.passes = match level {
+            0 => Vec::new(),
+            1 => vec![Mem2Reg, SimplifyCfg, Sccp, Dce, SimplifyCfg],
+            2 => vec![
+                Mem2Reg, SimplifyCfg, Inline, Sccp, SimplifyCfg, Gvn, Licm,
+                Dce, SimplifyCfg,
+            ],
+            _ => vec![
+                Mem2Reg, SimplifyCfg, Sccp, Inline, Sccp, SimplifyCfg, Gvn,
+                Licm, Sccp, Gvn, Dce, SimplifyCfg,
+            ],
+        };
+        if level >= 3 {
+            manager.inline_threshold = INLINE_THRESHOLD * 3;
+        }
+        manager
+    }
+
+    /// Add a pass to the end of the pipeline.
+    pub fn add(&mut self, pass: Pass) {
+        self.passes.push(pass);
+    }
+
+    /// Run a pass, adding it to the end of the pipeline if it isn't in it.
+    pub fn enable(&mut self, pass: Pass) {
+        self.disabled.retain(|&p| p != pass);
+        if !self.passes.contains(&pass) {
+            self.passes.push(pass);
+        }
+    }
+
+    /// Skip every run of a pass.
+    pub fn disable(&mut self, pass: Pass) {
+        if !self.disabled.contains(&pass) {
+            self.disabled.push(pass);
+        }
+    }
+
+    /// The passes that run, in order.
+    pub fn passes(&self) -> impl Iterator<Item = Pass> + '_ {
+        self.passes.iter().copied()
+            .filter(move |pass| !self.disabled.contains(pass))
+    }
+
+    /// Run the pipeline over a module, returning whether anything changed.
+    /// The module must verify; with `verify_each`, an error verifying it
+    /// after a pass is returned.
+    pub fn run(&self, module: &mut Module) -> Result<bool> {
+        let mut changed = false;
+        for pass in self.passes() {
+            changed |= pass.run_with(module, self.inline_threshold);
+            if self.verify_each {
+                verify(module).map_err(|error| Diagnostic::new(error.span,
+                    format!("after `{}`: {}", pass.as_str(), error.message)))?;
+            }
+        }
+        Ok(changed)
+    }
+}
@@ -0,0 +1,47 @@
+// Dead code elimination
+//
+//! The `dce` pass: instructions whose values are never used, and that have
+//! no side effects, are removed.
+//!
+//! Stores, calls, `va_start`s, `va_arg`s and the operands of terminators
+//! are live, and so is every operand of a live instruction; the rest is
+//! dead, including cycles of `phi`s only using each other.
+
+use crate::ir::{Function, InstId, Value};
+
+/// Remove the dead instructions of a function.  Returns whether any were.
+pub fn dce(function: &mut Function) -> bool {
+    let mut live = vec![false; function.insts.len()];
+    let mut work: Vec<InstId> = Vec::new();
+    let mut mark = |value: &Value, work: &mut Vec<InstId>| {
+        if let Value::Inst(inst) = *value {
+            if !live[inst] {
+                live[inst] = true;
+                work.push(inst);
+            }
+        }
+    };
+    for block in &function.blocks {
+        for &inst in &block.insts {
+            if function.insts[inst].kind.has_side_effects() {
+                mark(&Value::Inst(inst), &mut work);
+            }
+        }
+        if let Some(value) = block.term.operand() {
+            mark(value, &mut work);
+        }
+    }
+    while let Some(inst) = work.pop() {
+        for operand in function.insts[inst].kind.operands() {
+            mark(operand, &mut work);
+        }
+    }
+
+    let mut changed = false;
+    for block in &mut function.blocks {
+        let before = block.insts.len();
+        block.insts.retain(|&inst| live[inst]);
+        changed |= block.insts.len() != before;
+    }
+    changed
+}
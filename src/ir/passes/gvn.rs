@@ -0,0 +1,161 @@
+// Global value numbering
+//
+//! The `gvn` pass: an instruction computing the same value as one that
+//! dominates it is replaced by that one.
+//!
+//! Walking the dominator tree from the entry block, every instruction
+//! without side effects that doesn't read memory is looked up in a table
+//! of the instructions of the blocks on the way, keyed by its operation
+//! and operands, and added to it if it isn't there.  The operands of
+//! commutative operations are put in a fixed order first, with constants
+//! last, so `a + b` and `b + a` are found to be the same.  Instructions of
+//! constants are folded, and a `phi` whose values are all the same (or the
+//! `phi` itself) is replaced by that value.
+
+use std::collections::HashMap;
+
+use super::sccp::fold;
+use crate::ir::dominators::Dominators;
+use crate::ir::{
+    BinaryOp, Function, InstId, InstKind, IntPredicate, Type, Value,
+};
+
+/// Replace the redundant instructions of a function.  Returns whether any
+/// were.
+pub fn gvn(function: &mut Function) -> bool {
+    let dominators = Dominators::new(function);
+    let children = dominators.children();
+    let mut replaced: HashMap<InstId, Value> = HashMap::new();
+    let mut table: HashMap<(InstKind, Type), InstId> = HashMap::new();
+
+    // Blocks to enter, and `None` to leave the block entered last, taking
+    // out what it added to the table.
+    let mut stack = vec![Some(0)];
+    let mut added: Vec<Vec<(InstKind, Type)>> = Vec::new();
+    while let Some(entry) = stack.pop() {
+        let block = match entry {
+            Some(block) => block,
+            None => {
+                for key in added.pop().unwrap() {
+                    table.remove(&key);
+                }
+                continue;
+            }
+        };
+        let mut mine = Vec::new();
+        let mut kept = Vec::new();
+        for inst in std::mem::take(&mut function.blocks[block].insts) {
+            let kind = &mut function.insts[inst].kind;
+            for operand in kind.operands_mut() {
+                resolve(&replaced, operand);
+            }
+            canonicalize(kind);
+            let ty = function.insts[inst].ty.clone();
+            let kind = &function.insts[inst].kind;
+            if let Some(value) = simplify(inst, kind, &ty) {
+                replaced.insert(inst, value);
+                continue;
+            }
+            if !is_pure(kind) {
+                kept.push(inst);
+                continue;
+            }
+            let key = (kind.clone(), ty);
+            match table.get(&key) {
+                Some(&existing) => {
+                    replaced.insert(inst, Value::Inst(existing));
+                }
+                None => {
+                    table.insert(key.clone(), inst);
+                    mine.push(key);
+                    kept.push(inst);
+                }
+            }
+        }
+        function.blocks[block].insts = kept;
+        added.push(mine);
+        stack.push(None);
+        stack.extend(children[block].iter().rev().map(|&child| Some(child)));
+    }
+
+    // Uses along back edges, and in terminators, come after.
+    for block in &mut function.blocks {
+        for &inst in &block.insts {
+            for operand in function.insts[inst].kind.operands_mut() {
+                resolve(&replaced, operand);
+            }
+        }
+        if let Some(operand) = block.term.operand_mut() {
+            resolve(&replaced, operand);
+        }
+    }
+    !replaced.is_empty()
+}
+
+fn resolve(replaced: &HashMap<InstId, Value>, value: &mut Value) {
+    if let Value::Inst(inst) = *value {
+        if let Some(new) = replaced.get(&inst) {
+            *value = new.clone();
+        }
+    }
+}
+
+// Whether an instruction only computes a value from its operands.
+fn is_pure(kind: &InstKind) -> bool {
+    !matches!(kind, InstKind::Alloca(_)
+        | InstKind::Load(_)
+        | InstKind::Store(..)
+        | InstKind::Call(..)
+        | InstKind::VaStart(_)
+        | InstKind::VaArg(_))
+}
+
+// The value an instruction is the same as, if it isn't a new one.
+fn simplify(inst: InstId, kind: &InstKind, ty: &Type) -> Option<Value> {
+    if let InstKind::Phi(ref incoming) = *kind {
+        let mut values = incoming.iter()
+            .map(|(_, value)| value)
+            .filter(|&value| *value != Value::Inst(inst));
+        let first = values.next()?;
+        return match values.all(|value| value == first) {
+            true => Some(first.clone()),
+            false => None,
+        };
+    }
+    if is_pure(kind) && kind.operands().iter().all(|value| value.is_const()) {
+        return fold(kind, ty);
+    }
+    None
+}
+
+// Put the operands of a commutative operation in order: instructions by
+// number, then parameters, then constants.
+fn canonicalize(kind: &mut InstKind) {
+    let rank = |value: &Value| match *value {
+        Value::Inst(inst) => (0, inst),
+        Value::Param(index) => (1, index),
+        _ => (2, 0),
+    };
+    match kind {
+        InstKind::Binary(op, a, b) if is_commutative(*op)
+            && rank(a) > rank(b) =>
+        {
+            std::mem::swap(a, b);
+        }
+        InstKind::Icmp(IntPredicate::Eq, a, b)
+        | InstKind::Icmp(IntPredicate::Ne, a, b) if rank(a) > rank(b) => {
+            std::mem::swap(a, b);
+        }
+        _ => {}
+    }
+}
+
+fn is_commutative(op: BinaryOp) -> bool {
+    matches!(op, BinaryOp::Add
+        | BinaryOp::Mul
+        | BinaryOp::And
+        | BinaryOp::Or
+        | BinaryOp::Xor
+        | BinaryOp::FAdd
+        | BinaryOp::FMul)
+}
@@ -0,0 +1,241 @@
+// Inlining
+//
+//! The `inline` pass: calls to functions of the module are replaced by the
+//! bodies of the functions, when they are small enough.
+//!
+//! The cost of a function is the number of its instructions and blocks.
+//! A call is inlined if the cost of the function, less what inlining saves
+//! at the call, is at most the threshold: the call itself saves
+//! [`CALL_COST`], and each constant argument [`CONSTANT_ARGUMENT`], which
+//! later passes can fold into the body.  Calls to the function making
+//! them, to variadic functions, and with another signature than the
+//! function's aren't inlined, and neither are the calls that inlining
+//! brings in, so recursion stops.  Callees are done before their callers,
+//! so what they inlined is counted in their cost.
+//!
+//! The block of the call is split after it, the blocks of the function
+//! are copied in between with their parameters replaced by the arguments,
+//! and its returns branch to the rest of the block, a `phi` there picking
+//! the value returned.  Its `alloca`s move to the caller's entry block.
+
+use crate::ir::{
+    BlockId, Function, Inst, InstId, InstKind, Module, Terminator, Type,
+    Value,
+};
+
+/// What not making a call saves.
+pub const CALL_COST: usize = 5;
+/// What an argument being a constant saves.
+pub const CONSTANT_ARGUMENT: usize = 3;
+
+/// Inline the calls of a module whose callees cost at most `threshold`.
+/// Returns whether any were.
+pub fn inline(module: &mut Module, threshold: usize) -> bool {
+    let mut changed = false;
+    for caller in bottom_up(module) {
+        let calls: Vec<InstId> = module.functions[caller].blocks.iter()
+            .flat_map(|block| block.insts.iter().copied())
+            .collect();
+        let mut inlined = false;
+        for call in calls {
+            let callee = match callee(module, caller, call, threshold) {
+                Some(callee) => module.functions[callee].clone(),
+                None => continue,
+            };
+            inline_call(&mut module.functions[caller], call, &callee);
+            inlined = true;
+        }
+        if inlined {
+            module.functions[caller].remove_unreachable_blocks();
+            changed = true;
+        }
+    }
+    changed
+}
+
+// The indices of the functions with bodies, callees before their callers
+// where there is no recursion.
+fn bottom_up(module: &Module) -> Vec<usize> {
+    fn visit(module: &Module, index: usize, visited: &mut [bool],
+        order: &mut Vec<usize>)
+    {
+        visited[index] = true;
+        let function = &module.functions[index];
+        for block in &function.blocks {
+            for &inst in &block.insts {
+                if let InstKind::Call(_, Value::Global(ref name), _) =
+                    function.insts[inst].kind
+                {
+                    let callee = module.functions.iter()
+                        .position(|f| f.name == *name);
+                    if let Some(callee) = callee {
+                        if !visited[callee] {
+                            visit(module, callee, visited, order);
+                        }
+                    }
+                }
+            }
+        }
+        if !function.is_declaration() {
+            order.push(index);
+        }
+    }
+
+    let mut visited = vec![false; module.functions.len()];
+    let mut order = Vec::new();
+    for index in 0..module.functions.len() {
+        if !visited[index] {
+            visit(module, index, &mut visited, &mut order);
+        }
+    }
+    order
+}
+
+// The cost of a function.
+fn cost(function: &Function) -> usize {
+    function.blocks.iter().map(|block| block.insts.len() + 1).sum()
+}
+
+// The index of the function an instruction calls, if the call is inlined.
+fn callee(module: &Module, caller: usize, call: InstId, threshold: usize)
+    -> Option<usize>
+{
+    let function = &module.functions[caller];
+    let (signature, name, args) = match function.insts[call].kind {
+        InstKind::Call(ref signature, Value::Global(ref name), ref args) => {
+            (signature, name, args)
+        }
+        _ => return None,
+    };
+    let index = module.functions.iter().position(|f| f.name == *name)?;
+    let callee = &module.functions[index];
+    if index == caller
+        || callee.is_declaration()
+        || callee.signature.variadic
+        || callee.signature != *signature
+    {
+        return None;
+    }
+    let constants = args.iter().filter(|arg| arg.is_const()).count();
+    let saved = CALL_COST + constants * CONSTANT_ARGUMENT;
+    match cost(callee) <= threshold + saved {
+        true => Some(index),
+        false => None,
+    }
+}
+
+// Replace a call in `function` with the body of `callee`.
+fn inline_call(function: &mut Function, call: InstId, callee: &Function) {
+    let block = function.blocks.iter()
+        .position(|block| block.insts.contains(&call))
+        .unwrap();
+    let args = match function.insts[call].kind {
+        InstKind::Call(_, _, ref args) => args.clone(),
+        _ => unreachable!(),
+    };
+
+    // Split the block after the call.
+    let rest = function.add_block();
+    let position = function.blocks[block].insts.iter()
+        .position(|&inst| inst == call)
+        .unwrap();
+    let after = function.blocks[block].insts.split_off(position + 1);
+    function.blocks[block].insts.pop();
+    function.blocks[rest].insts = after;
+    let term = std::mem::replace(&mut function.blocks[block].term,
+        Terminator::Unreachable);
+    for succ in term.successors() {
+        for &inst in &function.blocks[succ].insts {
+            if let InstKind::Phi(ref mut incoming) = function.insts[inst].kind {
+                for (pred, _) in incoming.iter_mut() {
+                    if *pred == block {
+                        *pred = rest;
+                    }
+                }
+            }
+        }
+    }
+    function.blocks[rest].term = term;
+
+    // Copy the blocks and instructions.
+    let blocks: Vec<BlockId> = callee.blocks.iter()
+        .map(|_| function.add_block())
+        .collect();
+    let mut insts: Vec<Option<InstId>> = vec![None; callee.insts.len()];
+    for callee_block in &callee.blocks {
+        for &inst in &callee_block.insts {
+            function.insts.push(callee.insts[inst].clone());
+            insts[inst] = Some(function.insts.len() - 1);
+        }
+    }
+    let map = |value: &mut Value| match *value {
+        Value::Inst(inst) => *value = Value::Inst(insts[inst].unwrap()),
+        Value::Param(index) => *value = args[index].clone(),
+        _ => {}
+    };
+    let mut allocas = Vec::new();
+    let mut returns = Vec::new();
+    for (callee_block, &new) in callee.blocks.iter().zip(&blocks) {
+        for &inst in &callee_block.insts {
+            let inst = insts[inst].unwrap();
+            let Inst { ref mut kind, .. } = function.insts[inst];
+            kind.operands_mut().into_iter().for_each(map);
+            if let InstKind::Phi(ref mut incoming) = *kind {
+                for (pred, _) in incoming.iter_mut() {
+                    *pred = blocks[*pred];
+                }
+            }
+            match *kind {
+                InstKind::Alloca(_) => allocas.push(inst),
+                _ => function.blocks[new].insts.push(inst),
+            }
+        }
+        let mut term = callee_block.term.clone();
+        if let Some(operand) = term.operand_mut() {
+            map(operand);
+        }
+        for succ in term.successors_mut() {
+            *succ = blocks[*succ];
+        }
+        if let Terminator::Ret(value) = term {
+            returns.push((new, value));
+            term = Terminator::Br(rest);
+        }
+        function.blocks[new].term = term;
+    }
+    let entry = &mut function.blocks[0].insts;
+    entry.splice(0..0, allocas);
+    function.blocks[block].term = Terminator::Br(blocks[0]);
+
+    // The value returned.
+    let ty = function.insts[call].ty.clone();
+    let value = match returns.as_slice() {
+        _ if ty == Type::Void => None,
+        [] => Some(Value::Undef(ty)),
+        [(_, Some(value))] => Some(value.clone()),
+        _ => {
+            let incoming = returns.into_iter()
+                .map(|(block, value)| (block, value.unwrap()))
+                .collect();
+            function.insts.push(Inst { ty, kind: InstKind::Phi(incoming) });
+            let phi = function.insts.len() - 1;
+            function.blocks[rest].insts.insert(0, phi);
+            Some(Value::Inst(phi))
+        }
+    };
+    if let Some(value) = value {
+        function.replace_uses(call, &value);
+    }
+    place_blocks(function, block, rest);
+}
+
+// Move the blocks copied from the callee, then the rest of the block of
+// the call, right after that block.
+fn place_blocks(function: &mut Function, block: BlockId, rest: BlockId) {
+    let order: Vec<BlockId> = (0..=block)
+        .chain(rest + 1..function.blocks.len())
+        .chain(rest..=rest)
+        .chain(block + 1..rest)
+        .collect();
+    function.reorder_blocks(&order);
+}
@@ -0,0 +1,220 @@
+// Loop-invariant code motion
+//
+//! The `licm` pass: instructions in loops that compute the same value on
+//! every iteration are moved out in front of the loop.
+//!
+//! A loop is found from each block (its header) that a branch from inside
+//! it goes back to: the header dominates the blocks it goes back from, and
+//! the loop is the blocks that can reach them without going through the
+//! header.  Instructions are moved to the loop's preheader, a block
+//! branching only to the header that every edge into the loop comes from,
+//! which is added when there isn't one.  Inner loops are done first, so
+//! what is moved out of one can be moved out of the loop around it too.
+//!
+//! An instruction is moved if its operands are defined outside the loop,
+//! it doesn't access memory or have side effects, and computing it when the
+//! loop wouldn't have can't trap, which rules out divisions by anything
+//! that may be zero or -1.
+
+use crate::ir::dominators::Dominators;
+use crate::ir::{
+    BinaryOp, BlockId, Function, Inst, InstKind, Terminator, Value,
+};
+
+/// Move the loop-invariant instructions of a function out of their loops.
+/// Returns whether any were.
+pub fn licm(function: &mut Function) -> bool {
+    let dominators = Dominators::new(function);
+    let mut headers: Vec<(usize, BlockId)> = loop_headers(function,
+        &dominators)
+        .into_iter()
+        .map(|header| {
+            let body = body(function, &dominators, header);
+            (body.iter().filter(|&&inside| inside).count(), header)
+        })
+        .collect();
+    headers.sort_unstable();
+
+    let mut changed = false;
+    let mut added = Vec::new();
+    for (_, header) in headers {
+        let dominators = Dominators::new(function);
+        let body = body(function, &dominators, header);
+        let (preheader, new) = match preheader(function, header, &body) {
+            Some(preheader) => preheader,
+            None => continue,
+        };
+        if new {
+            added.push((header, preheader));
+            changed = true;
+        }
+        changed |= hoist(function, &dominators, &body, preheader);
+    }
+
+    // The preheaders added go right in front of their headers.
+    let mut order = Vec::new();
+    for block in 0..function.blocks.len() {
+        if added.iter().any(|&(_, preheader)| preheader == block) {
+            continue;
+        }
+        order.extend(added.iter()
+            .filter(|&&(header, _)| header == block)
+            .map(|&(_, preheader)| preheader));
+        order.push(block);
+    }
+    function.reorder_blocks(&order);
+    changed
+}
+
+// The blocks that branches go back to.
+fn loop_headers(function: &Function, dominators: &Dominators)
+    -> Vec<BlockId>
+{
+    let mut headers = Vec::new();
+    for &block in dominators.reverse_postorder() {
+        for succ in function.blocks[block].term.successors() {
+            if dominators.dominates(succ, block) && !headers.contains(&succ) {
+                headers.push(succ);
+            }
+        }
+    }
+    headers
+}
+
+// Whether each block is in the loop of a header.
+fn body(function: &Function, dominators: &Dominators, header: BlockId)
+    -> Vec<bool>
+{
+    let preds = dominators.predecessors();
+    let mut body = vec![false; function.blocks.len()];
+    body[header] = true;
+    let mut work: Vec<_> = preds[header].iter().copied()
+        .filter(|&pred| dominators.dominates(header, pred))
+        .collect();
+    while let Some(block) = work.pop() {
+        if !body[block] {
+            body[block] = true;
+            work.extend(preds[block].iter().copied()
+                .filter(|&pred| dominators.is_reachable(pred)));
+        }
+    }
+    body
+}
+
+// The preheader of a loop, and whether it had to be added, or `None` if
+// it can't be entered.
+fn preheader(function: &mut Function, header: BlockId, body: &[bool])
+    -> Option<(BlockId, bool)>
+{
+    let outside: Vec<_> = function.predecessors()[header].iter().copied()
+        .filter(|&pred| !body[pred])
+        .collect();
+    match *outside.as_slice() {
+        [] => return None,
+        [pred] if function.blocks[pred].term == Terminator::Br(header) => {
+            return Some((pred, false));
+        }
+        _ => {}
+    }
+
+    let preheader = function.add_block();
+    function.blocks[preheader].term = Terminator::Br(header);
+    for &pred in &outside {
+        for succ in function.blocks[pred].term.successors_mut() {
+            if *succ == header {
+                *succ = preheader;
+            }
+        }
+    }
+    // The values of the edges coming in move to `phi`s in the preheader.
+    for inst in function.blocks[header].insts.clone() {
+        let ty = function.insts[inst].ty.clone();
+        let incoming = match function.insts[inst].kind {
+            InstKind::Phi(ref mut incoming) => incoming,
+            _ => continue,
+        };
+        let (entering, staying): (Vec<_>, Vec<_>) = incoming.drain(..)
+            .partition(|(pred, _)| outside.contains(pred));
+        *incoming = staying;
+        let value = match entering.as_slice() {
+            [(_, value)] => value.clone(),
+            _ => {
+                function.insts.push(Inst {
+                    ty,
+                    kind: InstKind::Phi(entering),
+                });
+                let phi = function.insts.len() - 1;
+                function.blocks[preheader].insts.push(phi);
+                Value::Inst(phi)
+            }
+        };
+        if let InstKind::Phi(ref mut incoming) = function.insts[inst].kind {
+            incoming.push((preheader, value));
+        }
+    }
+    Some((preheader, true))
+}
+
+// Move the invariant instructions of a loop to the end of its preheader.
+fn hoist(function: &mut Function, dominators: &Dominators, body: &[bool],
+    preheader: BlockId) -> bool
+{
+    let mut inside = vec![false; function.insts.len()];
+    for (block, _) in body.iter().enumerate().filter(|(_, &b)| b) {
+        for &inst in &function.blocks[block].insts {
+            inside[inst] = true;
+        }
+    }
+    let mut changed = false;
+    let mut again = true;
+    while again {
+        again = false;
+        for &block in dominators.reverse_postorder() {
+            if !body[block] {
+                continue;
+            }
+            let mut kept = Vec::new();
+            for inst in std::mem::take(&mut function.blocks[block].insts) {
+                let kind = &function.insts[inst].kind;
+                let invariant = kind.operands().iter().all(|value| {
+                    !matches!(value, Value::Inst(i) if inside[*i])
+                });
+                match invariant && can_hoist(kind) {
+                    true => {
+                        function.blocks[preheader].insts.push(inst);
+                        inside[inst] = false;
+                        again = true;
+                        changed = true;
+                    }
+                    false => kept.push(inst),
+                }
+            }
+            function.blocks[block].insts = kept;
+        }
+    }
+    changed
+}
+
+// Whether an instruction can be computed in front of a loop, even if the
+// loop wouldn't have computed it.
+fn can_hoist(kind: &InstKind) -> bool {
+    match kind {
+        InstKind::Binary(BinaryOp::SDiv, _, divisor)
+        | InstKind::Binary(BinaryOp::SRem, _, divisor) => {
+            matches!(divisor, Value::Int(_, value) if *value != 0
+                && *value != -1)
+        }
+        InstKind::Binary(BinaryOp::UDiv, _, divisor)
+        | InstKind::Binary(BinaryOp::URem, _, divisor) => {
+            matches!(divisor, Value::Int(_, value) if *value != 0)
+        }
+        InstKind::Phi(_)
+        | InstKind::Alloca(_)
+        | InstKind::Load(_)
+        | InstKind::Store(..)
+        | InstKind::Call(..)
+        | InstKind::VaStart(_)
+        | InstKind::VaArg(_) => false,
+        _ => true,
+    }
+}
@@ -0,0 +1,518 @@
+// Sparse conditional constant propagation
+//
+//! The `sccp` pass: instructions that always produce the same constant are
+//! replaced by it, and branches that always go the same way by a `br`.
+//!
+//! This is the algorithm of Wegman and Zadeck ("Constant Propagation with
+//! Conditional Branches").  Every value starts unknown, and only goes down
+//! to a constant and then to varying.  Blocks are only evaluated once an
+//! edge to them can be taken, so a `phi` ignores the values of edges that
+//! can't be, and the values of a block that can't be reached don't count.
+//! Parameters, memory and calls are varying, and so is `undef`, except
+//! that `phi`s ignore it.
+//!
+//! Constants are folded as the target would compute them; divisions by
+//! zero, overflowing signed divisions, shifts by the size of their type or
+//! more and conversions of floats out of range are left for run time.
+
+use std::collections::HashSet;
+
+use crate::ir::{
+    wrap, BinaryOp, BlockId, CastOp, FloatPredicate, Function, InstId,
+    InstKind, IntPredicate, Terminator, Type, Value,
+};
+
+// What is known about a value.
+#[derive(Debug, Clone, PartialEq)]
+enum State {
+    Unknown,
+    Const(Value),
+    Varying,
+}
+
+impl State {
+    fn meet(&self, other: &State) -> State {
+        match (self, other) {
+            (State::Unknown, state) | (state, State::Unknown) => state.clone(),
+            (State::Const(a), State::Const(b)) if a == b => self.clone(),
+            _ => State::Varying,
+        }
+    }
+}
+
+/// Propagate the constants of a function.  Returns whether anything
+/// changed.
+pub fn sccp(function: &mut Function) -> bool {
+    let mut solver = Solver::new(function);
+    solver.solve();
+    let Solver { states, executable, edges, forced, .. } = solver;
+
+    let reached: Vec<BlockId> = executable.iter().enumerate()
+        .filter(|&(_, &executable)| executable)
+        .map(|(block, _)| block)
+        .collect();
+    let mut changed = false;
+    for &block in &reached {
+        let succs = function.blocks[block].term.successors();
+        let target = match function.blocks[block].term {
+            Terminator::CondBr(..) | Terminator::Switch(..) => {
+                let mut taken = succs.iter().copied()
+                    .filter(|&succ| edges.contains(&(block, succ)));
+                match (taken.next(), forced.get(block)) {
+                    (Some(first), _) if taken.all(|succ| succ == first) => {
+                        Some(first)
+                    }
+                    (None, Some(&Some(succ))) => Some(succ),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        if let Some(target) = target {
+            for succ in succs {
+                if succ != target {
+                    function.remove_incoming(succ, block);
+                }
+            }
+            function.blocks[block].term = Terminator::Br(target);
+            changed = true;
+        }
+    }
+    for &block in &reached {
+        for inst in function.blocks[block].insts.clone() {
+            if let State::Const(ref value) = states[inst] {
+                function.replace_uses(inst, value);
+                function.blocks[block].insts.retain(|&i| i != inst);
+                changed = true;
+            }
+        }
+    }
+    let before = function.blocks.len();
+    function.remove_unreachable_blocks();
+    changed || function.blocks.len() != before
+}
+
+struct Solver<'f> {
+    function: &'f Function,
+    states: Vec<State>,
+    // The block of each instruction in a block.
+    block_of: Vec<Option<BlockId>>,
+    // The instructions using each instruction, and the blocks whose
+    // terminators use it.
+    users: Vec<Vec<InstId>>,
+    term_users: Vec<Vec<BlockId>>,
+    executable: Vec<bool>,
+    edges: HashSet<(BlockId, BlockId)>,
+    // The successor chosen for a block branching on a value that stayed
+    // unknown.
+    forced: Vec<Option<BlockId>>,
+    edge_work: Vec<(BlockId, BlockId)>,
+    inst_work: Vec<InstId>,
+}
+
+impl<'f> Solver<'f> {
+    fn new(function: &'f Function) -> Self {
+        let count = function.insts.len();
+        let mut block_of = vec![None; count];
+        let mut users = vec![Vec::new(); count];
+        let mut term_users = vec![Vec::new(); count];
+        for (id, block) in function.blocks.iter().enumerate() {
+            for &inst in &block.insts {
+                block_of[inst] = Some(id);
+                for operand in function.insts[inst].kind.operands() {
+                    if let Value::Inst(used) = *operand {
+                        users[used].push(inst);
+                    }
+                }
+            }
+            if let Some(&Value::Inst(used)) = block.term.operand() {
+                term_users[used].push(id);
+            }
+        }
+        Solver {
+            function,
+            states: vec![State::Unknown; count],
+            block_of,
+            users,
+            term_users,
+            executable: vec![false; function.blocks.len()],
+            edges: HashSet::new(),
+            forced: vec![None; function.blocks.len()],
+            edge_work: Vec::new(),
+            inst_work: Vec::new(),
+        }
+    }
+
+    fn solve(&mut self) {
+        self.enter(0);
+        loop {
+            while let Some((_, to)) = self.edge_work.pop() {
+                match self.executable[to] {
+                    true => {
+                        for &inst in &self.function.blocks[to].insts {
+                            if let InstKind::Phi(_) =
+                                self.function.insts[inst].kind
+                            {
+                                self.evaluate(inst);
+                            }
+                        }
+                    }
+                    false => self.enter(to),
+                }
+            }
+            if let Some(inst) = self.inst_work.pop() {
+                if self.block_of[inst].is_some_and(|b| self.executable[b]) {
+                    self.evaluate(inst);
+                }
+                for block in self.term_users[inst].clone() {
+                    if self.executable[block] {
+                        self.terminator(block);
+                    }
+                }
+                continue;
+            }
+            // Branches on values that stayed unknown can go anywhere, so
+            // take their first edge.
+            let stuck = (0..self.function.blocks.len()).find(|&block| {
+                self.executable[block]
+                    && self.forced[block].is_none()
+                    && !self.function.blocks[block].term.successors().iter()
+                        .any(|&succ| self.edges.contains(&(block, succ)))
+                    && !self.function.blocks[block].term.successors()
+                        .is_empty()
+            });
+            match stuck {
+                Some(block) => {
+                    let succ = self.function.blocks[block].term.successors()[0];
+                    self.forced[block] = Some(succ);
+                    self.edge(block, succ);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn enter(&mut self, block: BlockId) {
+        self.executable[block] = true;
+        for &inst in &self.function.blocks[block].insts {
+            self.evaluate(inst);
+        }
+        self.terminator(block);
+    }
+
+    fn edge(&mut self, from: BlockId, to: BlockId) {
+        if self.edges.insert((from, to)) {
+            self.edge_work.push((from, to));
+        }
+    }
+
+    fn state(&self, value: &Value) -> State {
+        match value {
+            Value::Inst(inst) => self.states[*inst].clone(),
+            Value::Param(_) | Value::Undef(_) => State::Varying,
+            value => State::Const(value.clone()),
+        }
+    }
+
+    fn evaluate(&mut self, inst: InstId) {
+        let new = self.compute(inst);
+        if new != self.states[inst] {
+            self.states[inst] = new;
+            self.inst_work.extend(self.users[inst].iter().copied());
+            if !self.term_users[inst].is_empty() {
+                self.inst_work.push(inst);
+            }
+        }
+    }
+
+    fn compute(&self, inst: InstId) -> State {
+        let inst_ = &self.function.insts[inst];
+        match inst_.kind {
+            InstKind::Phi(ref incoming) => {
+                let block = self.block_of[inst].unwrap();
+                let mut state = State::Unknown;
+                for (pred, value) in incoming {
+                    if !self.edges.contains(&(*pred, block))
+                        || matches!(value, Value::Undef(_))
+                    {
+                        continue;
+                    }
+                    state = state.meet(&self.state(value));
+                }
+                state
+            }
+            InstKind::Select(ref cond, ref a, ref b) => {
+                match self.state(cond) {
+                    State::Unknown => State::Unknown,
+                    State::Const(Value::Int(_, 1)) => self.state(a),
+                    State::Const(Value::Int(_, 0)) => self.state(b),
+                    _ => self.state(a).meet(&self.state(b)),
+                }
+            }
+            InstKind::Alloca(_)
+            | InstKind::Load(_)
+            | InstKind::Store(..)
+            | InstKind::PtrAdd(..)
+            | InstKind::Call(..)
+            | InstKind::VaStart(_)
+            | InstKind::VaArg(_) => State::Varying,
+            ref kind => {
+                let mut kind = kind.clone();
+                for operand in kind.operands_mut() {
+                    match self.state(operand) {
+                        State::Const(value) => *operand = value,
+                        state => return state,
+                    }
+                }
+                match fold(&kind, &inst_.ty) {
+                    Some(value) => State::Const(value),
+                    None => State::Varying,
+                }
+            }
+        }
+    }
+
+    fn terminator(&mut self, block: BlockId) {
+        let term = &self.function.blocks[block].term;
+        let succs = term.successors();
+        let taken = match term {
+            Terminator::Br(target) => vec![*target],
+            Terminator::CondBr(cond, then, else_) => match self.state(cond) {
+                State::Unknown => Vec::new(),
+                State::Const(Value::Int(_, 1)) => vec![*then],
+                State::Const(Value::Int(_, 0)) => vec![*else_],
+                _ => succs,
+            },
+            Terminator::Switch(value, default, cases) => {
+                match self.state(value) {
+                    State::Unknown => Vec::new(),
+                    State::Const(Value::Int(_, value)) => {
+                        let case = cases.iter().find(|(case, _)| *case == value);
+                        vec![case.map_or(*default, |&(_, block)| block)]
+                    }
+                    _ => succs,
+                }
+            }
+            Terminator::Ret(_) | Terminator::Unreachable => Vec::new(),
+        };
+        for succ in taken {
+            self.edge(block, succ);
+        }
+    }
+}
+
+// The constant of type `ty` with every byte zero.
+fn zero(ty: &Type) -> Value {
+    match ty {
+        Type::Int(_) => Value::int(ty.clone(), 0),
+        Type::F32 | Type::F64 => Value::float(ty.clone(), 0.0),
+        Type::Ptr => Value::Null,
+        ty => Value::Zero(ty.clone()),
+    }
+}
+
+// The members of a constant aggregate of type `ty`.
+fn members(value: &Value, ty: &Type) -> Option<Vec<Value>> {
+    Some(match value {
+        Value::Aggregate(_, members) => members.clone(),
+        Value::Zero(_) => match ty {
+            Type::Array(member, len) => vec![zero(member); *len as usize],
+            Type::Struct(fields) => fields.iter().map(zero).collect(),
+            _ => return None,
+        },
+        Value::Bytes(bytes) => bytes.iter()
+            .map(|&byte| Value::int(Type::I8, i128::from(byte)))
+            .collect(),
+        _ => return None,
+    })
+}
+
+// Reinterpret an integer of `bits` bits as unsigned.
+fn unsigned(bits: u32, value: i128) -> u128 {
+    match bits {
+        128 => value as u128,
+        _ => (value as u128) & ((1 << bits) - 1),
+    }
+}
+
+// Reinterpret an integer of `bits` bits as signed, which only changes an
+// `i1` of 1 to -1.
+fn signed(bits: u32, value: i128) -> i128 {
+    match bits {
+        1 => -value,
+        _ => value,
+    }
+}
+
+/// The constant an instruction of type `ty` produces, if its operands are
+/// constants and it can be computed now.
+pub fn fold(kind: &InstKind, ty: &Type) -> Option<Value> {
+    match *kind {
+        InstKind::Binary(op, Value::Int(_, a), Value::Int(_, b)) => {
+            let bits = match *ty {
+                Type::Int(bits) => bits,
+                _ => return None,
+            };
+            let (ua, ub) = (unsigned(bits, a), unsigned(bits, b));
+            let (sa, sb) = (signed(bits, a), signed(bits, b));
+            let value = match op {
+                BinaryOp::Add => a.wrapping_add(b),
+                BinaryOp::Sub => a.wrapping_sub(b),
+                BinaryOp::Mul => a.wrapping_mul(b),
+                // The most negative value divided by -1 overflows, and is
+                // the only value its negation wraps to besides 0.
+                BinaryOp::SDiv | BinaryOp::SRem
+                    if sb == 0 || (sb == -1 && sa != 0
+                        && wrap(bits, sa.wrapping_neg()) == a) => return None,
+                BinaryOp::SDiv => sa / sb,
+                BinaryOp::SRem => sa % sb,
+                BinaryOp::UDiv | BinaryOp::URem if ub == 0 => return None,
+                BinaryOp::UDiv => (ua / ub) as i128,
+                BinaryOp::URem => (ua % ub) as i128,
+                BinaryOp::Shl | BinaryOp::LShr | BinaryOp::AShr
+                    if ub >= u128::from(bits) => return None,
+                BinaryOp::Shl => a << ub,
+                BinaryOp::LShr => (ua >> ub) as i128,
+                BinaryOp::AShr => sa >> ub,
+                BinaryOp::And => a & b,
+                BinaryOp::Or => a | b,
+                BinaryOp::Xor => a ^ b,
+                _ => return None,
+            };
+            Some(Value::int(ty.clone(), value))
+        }
+        InstKind::Binary(op, ref a @ Value::Float(..), ref b @ Value::Float(..)) => {
+            let (a, b) = (a.as_float()?, b.as_float()?);
+            let value = match op {
+                BinaryOp::FAdd => a + b,
+                BinaryOp::FSub => a - b,
+                BinaryOp::FMul => a * b,
+                BinaryOp::FDiv => a / b,
+                BinaryOp::FRem => a % b,
+                _ => return None,
+            };
+            Some(Value::float(ty.clone(), value))
+        }
+        InstKind::FNeg(ref a @ Value::Float(..)) => {
+            Some(Value::float(ty.clone(), -a.as_float()?))
+        }
+        InstKind::Icmp(pred, ref a, ref b) => {
+            let (a, b, bits) = match (a, b) {
+                (Value::Int(Type::Int(bits), a), Value::Int(_, b)) => {
+                    (*a, *b, *bits)
+                }
+                (Value::Null, Value::Null) => (0, 0, 64),
+                (Value::Global(x), Value::Global(y)) => {
+                    return match pred {
+                        IntPredicate::Eq => Some(Value::bool(x == y)),
+                        IntPredicate::Ne => Some(Value::bool(x != y)),
+                        _ => None,
+                    };
+                }
+                (Value::Global(_), Value::Null)
+                | (Value::Null, Value::Global(_)) => {
+                    return match pred {
+                        IntPredicate::Eq => Some(Value::bool(false)),
+                        IntPredicate::Ne => Some(Value::bool(true)),
+                        _ => None,
+                    };
+                }
+                _ => return None,
+            };
+            let (ua, ub) = (unsigned(bits, a), unsigned(bits, b));
+            let (sa, sb) = (signed(bits, a), signed(bits, b));
+            Some(Value::bool(match pred {
+                IntPredicate::Eq => a == b,
+                IntPredicate::Ne => a != b,
+                IntPredicate::Slt => sa < sb,
+                IntPredicate::Sle => sa <= sb,
+                IntPredicate::Sgt => sa > sb,
+                IntPredicate::Sge => sa >= sb,
+                IntPredicate::Ult => ua < ub,
+                IntPredicate::Ule => ua <= ub,
+                IntPredicate::Ugt => ua > ub,
+                IntPredicate::Uge => ua >= ub,
+            }))
+        }
+        InstKind::Fcmp(pred, ref a, ref b) => {
+            let (a, b) = (a.as_float()?, b.as_float()?);
+            let unordered = a.is_nan() || b.is_nan();
+            Some(Value::bool(match pred {
+                FloatPredicate::Oeq => !unordered && a == b,
+                FloatPredicate::One => !unordered && a != b,
+                FloatPredicate::Olt => a < b,
+                FloatPredicate::Ole => a <= b,
+                FloatPredicate::Ogt => a > b,
+                FloatPredicate::Oge => a >= b,
+                FloatPredicate::Ueq => unordered || a == b,
+                FloatPredicate::Une => unordered || a != b,
+            }))
+        }
+        InstKind::Cast(op, ref value) => cast(op, value, ty),
+        InstKind::ExtractValue(ref aggregate, index) => {
+            let aggregate_ty = match aggregate {
+                Value::Aggregate(ty, _) | Value::Zero(ty) => ty.clone(),
+                Value::Bytes(bytes) => {
+                    Type::Array(Box::new(Type::I8), bytes.len() as u64)
+                }
+                _ => return None,
+            };
+            members(aggregate, &aggregate_ty)?.get(index as usize).cloned()
+        }
+        InstKind::InsertValue(ref aggregate, ref value, index)
+            if value.is_const() && !matches!(value, Value::Undef(_)) =>
+        {
+            let mut members = members(aggregate, ty)?;
+            *members.get_mut(index as usize)? = value.clone();
+            Some(Value::Aggregate(ty.clone(), members))
+        }
+        _ => None,
+    }
+}
+
+// Fold a conversion of a constant to type `ty`.
+fn cast(op: CastOp, value: &Value, ty: &Type) -> Option<Value> {
+    let from_bits = match *value {
+        Value::Int(Type::Int(bits), _) => bits,
+        _ => 0,
+    };
+    let to_bits = match *ty {
+        Type::Int(bits) => bits,
+        _ => 0,
+    };
+    Some(match (op, value) {
+        (CastOp::Trunc, Value::Int(_, value)) => Value::int(ty.clone(), *value),
+        (CastOp::SExt, Value::Int(_, value)) => {
+            Value::int(ty.clone(), signed(from_bits, *value))
+        }
+        (CastOp::ZExt, Value::Int(_, value)) => {
+            Value::int(ty.clone(), unsigned(from_bits, *value) as i128)
+        }
+        (CastOp::SiToFp, Value::Int(_, value)) => {
+            Value::float(ty.clone(), signed(from_bits, *value) as f64)
+        }
+        (CastOp::UiToFp, Value::Int(_, value)) => {
+            Value::float(ty.clone(), unsigned(from_bits, *value) as f64)
+        }
+        (CastOp::FpTrunc, value) | (CastOp::FpExt, value) => {
+            Value::float(ty.clone(), value.as_float()?)
+        }
+        (CastOp::FpToSi, value) | (CastOp::FpToUi, value) => {
+            let value = value.as_float()?.trunc();
+            let (min, max) = match op {
+                CastOp::FpToSi => {
+                    let half = 2f64.powi(to_bits as i32 - 1);
+                    (-half, half)
+                }
+                _ => (0.0, 2f64.powi(to_bits as i32)),
+            };
+            if !(min..max).contains(&value) {
+                return None;
+            }
+            Value::int(ty.clone(), value as i128)
+        }
+        (CastOp::PtrToInt, Value::Null) => Value::int(ty.clone(), 0),
+        (CastOp::IntToPtr, Value::Int(_, 0)) => Value::Null,
+        _ => return None,
+    })
+}
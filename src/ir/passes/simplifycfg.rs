@@ -0,0 +1,190 @@
+// Control-flow graph simplification
+//
+//! The `simplifycfg` pass: branches and blocks that don't need to be there
+//! are removed, until there are none.
+//!
+//! - A `condbr` on a constant, or to the same block either way, and a
+//!   `switch` on a constant or with every case going to the default
+//!   become a `br`.
+//! - Blocks that can't be reached are removed.
+//! - A block branched to by a `br` from its only predecessor is merged
+//!   into it, its `phi`s replaced by their one value.
+//! - An empty block that only branches on is skipped by its predecessors,
+//!   unless one of them also branches to where it goes and the `phi`s
+//!   there tell the two edges apart.
+
+use crate::ir::{BlockId, Function, InstKind, Terminator, Value};
+
+/// Simplify the control-flow graph of a function.  Returns whether anything
+/// changed.
+pub fn simplify_cfg(function: &mut Function) -> bool {
+    let mut changed = false;
+    loop {
+        let mut again = fold_branches(function);
+        let before = function.blocks.len();
+        function.remove_unreachable_blocks();
+        again |= function.blocks.len() != before;
+        again |= merge_blocks(function);
+        again |= skip_empty_blocks(function);
+        if !again {
+            return changed;
+        }
+        changed = true;
+    }
+}
+
+// Turn branches that always go to the same block into `br`s.
+fn fold_branches(function: &mut Function) -> bool {
+    let mut changed = false;
+    for block in 0..function.blocks.len() {
+        let term = &function.blocks[block].term;
+        let target = match *term {
+            Terminator::CondBr(Value::Int(_, cond), then, else_) => {
+                match cond {
+                    0 => else_,
+                    _ => then,
+                }
+            }
+            Terminator::CondBr(_, then, else_) if then == else_ => then,
+            Terminator::Switch(Value::Int(_, value), default, ref cases) => {
+                cases.iter().find(|(case, _)| *case == value)
+                    .map_or(default, |&(_, block)| block)
+            }
+            Terminator::Switch(_, default, ref cases)
+                if cases.iter().all(|&(_, block)| block == default) =>
+            {
+                default
+            }
+            _ => continue,
+        };
+        for succ in term.successors() {
+            if succ != target {
+                function.remove_incoming(succ, block);
+            }
+        }
+        function.blocks[block].term = Terminator::Br(target);
+        changed = true;
+    }
+    changed
+}
+
+// Merge blocks into their only predecessors branching only to them.
+fn merge_blocks(function: &mut Function) -> bool {
+    let mut changed = false;
+    let mut block = 1;
+    while block < function.blocks.len() {
+        let preds = function.predecessors();
+        let pred = match preds[block].as_slice() {
+            [pred] if *pred != block
+                && function.blocks[*pred].term == Terminator::Br(block) =>
+            {
+                *pred
+            }
+            _ => {
+                block += 1;
+                continue;
+            }
+        };
+        // The `phi`s have one value, from the predecessor.
+        let insts = std::mem::take(&mut function.blocks[block].insts);
+        let mut moved = Vec::new();
+        for inst in insts {
+            match function.insts[inst].kind {
+                InstKind::Phi(ref incoming) => {
+                    let value = incoming[0].1.clone();
+                    function.replace_uses(inst, &value);
+                }
+                _ => moved.push(inst),
+            }
+        }
+        let term = std::mem::replace(&mut function.blocks[block].term,
+            Terminator::Unreachable);
+        for succ in term.successors() {
+            rename_incoming(function, succ, block, pred);
+        }
+        function.blocks[pred].insts.extend(moved);
+        function.blocks[pred].term = term;
+        changed = true;
+        function.remove_unreachable_blocks();
+        block = 1;
+    }
+    changed
+}
+
+// Make the predecessors of empty blocks ending with a `br` branch past
+// them.
+fn skip_empty_blocks(function: &mut Function) -> bool {
+    let mut changed = false;
+    for block in 1..function.blocks.len() {
+        let target = match function.blocks[block].term {
+            Terminator::Br(target) if target != block
+                && function.blocks[block].insts.is_empty() => target,
+            _ => continue,
+        };
+        let preds = function.predecessors();
+        let target_preds = &preds[target];
+        let phis: Vec<_> = function.blocks[target].insts.iter().copied()
+            .filter(|&inst| {
+                matches!(function.insts[inst].kind, InstKind::Phi(_))
+            })
+            .collect();
+        // A predecessor already branching to the target can only skip the
+        // block if every `phi` has the same value for both edges.
+        let value = |pred: BlockId, phi: usize| match function.insts[phi].kind {
+            InstKind::Phi(ref incoming) => incoming.iter()
+                .find(|(from, _)| *from == pred)
+                .map(|(_, value)| value.clone()),
+            _ => None,
+        };
+        let skipping: Vec<_> = preds[block].iter().copied()
+            .filter(|&pred| {
+                !target_preds.contains(&pred) || phis.iter()
+                    .all(|&phi| value(pred, phi) == value(block, phi))
+            })
+            .collect();
+        if skipping.is_empty() || preds[block].is_empty() {
+            continue;
+        }
+        let values: Vec<_> = phis.iter()
+            .map(|&phi| value(block, phi).unwrap())
+            .collect();
+        for &pred in &skipping {
+            for succ in function.blocks[pred].term.successors_mut() {
+                if *succ == block {
+                    *succ = target;
+                }
+            }
+            if !target_preds.contains(&pred) {
+                for (&phi, value) in phis.iter().zip(&values) {
+                    if let InstKind::Phi(ref mut incoming) =
+                        function.insts[phi].kind
+                    {
+                        incoming.push((pred, value.clone()));
+                    }
+                }
+            }
+        }
+        // The target still has the block as a predecessor if some of its
+        // predecessors couldn't skip it.
+        if skipping.len() == preds[block].len() {
+            function.remove_incoming(target, block);
+        }
+        changed = true;
+    }
+    changed
+}
+
+// Make the `phi`s of a block take the value of edges from `old` from `new`.
+fn rename_incoming(function: &mut Function, block: BlockId, old: BlockId,
+    new: BlockId)
+{
+    for &inst in &function.blocks[block].insts {
+        if let InstKind::Phi(ref mut incoming) = function.insts[inst].kind {
+            for (from, _) in incoming.iter_mut() {
+                if *from == old {
+                    *from = new;
+                }
+            }
+        }
+    }
+}
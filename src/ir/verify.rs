@@ -0,0 +1,552 @@
+// IR verifier
+//
+//! Checking that a module is well formed.
+//!
+//! Every symbol must be defined once, and global variables initialized with
+//! constants of their types.  In a function, every block ends with a
+//! terminator branching to blocks of the function, the entry block has no
+//! predecessors, and phis come first in their blocks with one value for
+//! each predecessor.  Every operand must have the type its instruction
+//! expects, and the definition of every value must dominate its uses: come
+//! before them in the same block, or be in a block that dominates theirs.
+//! A phi uses its values at the end of the predecessors they come from.
+//! Code that can't be reached has no dominators, so its uses aren't
+//! checked.  Only a variadic function may have a `va_start`.
+//!
+//! Errors are reported with the function they are in and the names values
+//! and blocks print with, and have no span.
+
+use std::collections::HashMap;
+
+use super::dominators::Dominators;
+use super::{
+    symbol, ty_of_const, wrap, BlockId, CastOp, Function, InstId, InstKind,
+    Module, Names, Result, Terminator, Type, Value,
+};
+use crate::{Diagnostic, Span};
+
+/// Check that a module is well formed.
+pub fn verify(module: &Module) -> Result<()> {
+    let mut symbols = HashMap::new();
+    let names = module.globals.iter().map(|global| &global.name)
+        .chain(module.functions.iter().map(|function| &function.name));
+    for (index, name) in names.enumerate() {
+        if symbols.insert(name.as_str(), index).is_some() {
+            return Err(error(name, "is defined twice".to_string()));
+        }
+    }
+    let checker = Checker { module, symbols };
+    for global in &module.globals {
+        if let Some(ref init) = global.init {
+            checker.constant(init, &global.ty)
+                .map_err(|message| error(&global.name, message))?;
+        }
+        if global.ty == Type::Void {
+            return Err(error(&global.name, "has type `void`".to_string()));
+        }
+    }
+    for function in &module.functions {
+        if let Some(index) = function.signature.params.iter()
+            .position(|ty| *ty == Type::Void)
+        {
+            return Err(error(&function.name,
+                format!("parameter {} has type `void`", index)));
+        }
+        if !function.is_declaration() {
+            checker.function(function)
+                .map_err(|message| error(&function.name, message))?;
+        }
+    }
+    Ok(())
+}
+
+fn error(name: &str, message: String) -> Diagnostic {
+    Diagnostic::new(Span::default(),
+        format!("in `{}`: {}", symbol(name), message))
+}
+
+// What the symbols of a module refer to.
+enum Symbol<'m> {
+    Global,
+    Function(&'m Function),
+}
+
+struct Checker<'m> {
+    module: &'m Module,
+    // Index of each symbol, globals then functions.
+    symbols: HashMap<&'m str, usize>,
+}
+
+type Check<T> = std::result::Result<T, String>;
+
+impl<'m> Checker<'m> {
+    fn symbol(&self, name: &str) -> Option<Symbol<'m>> {
+        let index = *self.symbols.get(name)?;
+        let globals = self.module.globals.len();
+        Some(match index.checked_sub(globals) {
+            Some(index) => Symbol::Function(&self.module.functions[index]),
+            None => Symbol::Global,
+        })
+    }
+
+    // Check that `value` is a constant of type `ty`.
+    fn constant(&self, value: &Value, ty: &Type) -> Check<()> {
+        let names = Names { insts: HashMap::new() };
+        let mismatch = || Err(format!("`{}` isn't a `{}`",
+            names.value(value), ty));
+        match value {
+            Value::Inst(_) | Value::Param(_) => {
+                return Err("a constant uses a value of a function"
+                    .to_string());
+            }
+            Value::Int(Type::Int(bits), int) if ty.is_int() => {
+                if wrap(*bits, *int) != *int {
+                    return Err(format!("`{}` doesn't fit in `{}`", int, ty));
+                }
+            }
+            Value::Float(_, _) if ty.is_float() => {}
+            Value::Null if *ty == Type::Ptr => {}
+            Value::Undef(_) | Value::Zero(_) if *ty != Type::Void => {}
+            Value::Aggregate(_, values) => match ty {
+                Type::Array(member, len) if values.len() as u64 == *len => {
+                    for value in values {
+                        self.constant(value, member)?;
+                    }
+                }
+                Type::Struct(fields) if values.len() == fields.len() => {
+                    for (value, field) in values.iter().zip(fields) {
+                        self.constant(value, field)?;
+                    }
+                }
+                _ => return mismatch(),
+            },
+            Value::Bytes(bytes) => match ty {
+                Type::Array(member, len)
+                    if **member == Type::I8 && bytes.len() as u64 == *len => {}
+                _ => return mismatch(),
+            },
+            Value::Global(name) if *ty == Type::Ptr => {
+                if self.symbol(name).is_none() {
+                    return Err(format!("`{}` isn't defined", symbol(name)));
+                }
+            }
+            _ => return mismatch(),
+        }
+        if ty_of_const(value) != *ty {
+            return mismatch();
+        }
+        Ok(())
+    }
+
+    fn function(&self, function: &Function) -> Check<()> {
+        let count = function.blocks.len();
+        for (id, block) in function.blocks.iter().enumerate() {
+            if let Some(succ) = block.term.successors().into_iter()
+                .find(|&succ| succ >= count)
+            {
+                return Err(format!("b{} branches to b{}, which doesn't exist",
+                    id, succ));
+            }
+        }
+        let mut places = vec![None; function.insts.len()];
+        for (id, block) in function.blocks.iter().enumerate() {
+            for (position, &inst) in block.insts.iter().enumerate() {
+                match places.get_mut(inst) {
+                    Some(place @ None) => *place = Some((id, position)),
+                    Some(Some(_)) => {
+                        return Err(format!(
+                            "instruction {} is in more than one place", inst));
+                    }
+                    None => {
+                        return Err(format!("b{} has instruction {}, which \
+                            doesn't exist", id, inst));
+                    }
+                }
+            }
+        }
+        let doms = Dominators::new(function);
+        if !doms.predecessors()[0].is_empty() {
+            return Err("the entry block b0 has predecessors".to_string());
+        }
+        let body = Body {
+            checker: self,
+            function,
+            names: Names::new(function),
+            places,
+            doms,
+        };
+        for id in 0..count {
+            body.block(id)?;
+        }
+        Ok(())
+    }
+}
+
+// A function being checked.
+struct Body<'c, 'm> {
+    checker: &'c Checker<'m>,
+    function: &'c Function,
+    names: Names,
+    // Block of each instruction in a block, and its position there.
+    places: Vec<Option<(BlockId, usize)>>,
+    doms: Dominators,
+}
+
+impl Body<'_, '_> {
+    fn block(&self, id: BlockId) -> Check<()> {
+        let block = &self.function.blocks[id];
+        let mut phis = true;
+        for (position, &inst) in block.insts.iter().enumerate() {
+            let kind = &self.function.insts[inst].kind;
+            match kind {
+                InstKind::Phi(ref incoming) => {
+                    if !phis {
+                        return Err(format!("{} in b{} comes after \
+                            instructions that aren't phis", self.name(inst),
+                            id));
+                    }
+                    self.phi(inst, id, incoming)?;
+                }
+                _ => {
+                    phis = false;
+                    for value in kind.operands() {
+                        self.dominates(value, id, position)?;
+                    }
+                }
+            }
+            self.inst(inst).map_err(|message| {
+                format!("{} in b{}: {}", self.name(inst), id, message)
+            })?;
+        }
+        if let Some(value) = block.term.operand() {
+            self.dominates(value, id, block.insts.len())?;
+        }
+        self.terminator(&block.term)
+            .map_err(|message| format!("terminator of b{}: {}", id, message))
+    }
+
+    // How an instruction is named in errors.
+    fn name(&self, inst: InstId) -> String {
+        match self.names.insts.get(&inst) {
+            Some(number) => format!("`%{}`", number),
+            None => format!("instruction {}", inst),
+        }
+    }
+
+    fn phi(&self, inst: InstId, id: BlockId,
+        incoming: &[(BlockId, Value)]) -> Check<()>
+    {
+        let preds = &self.doms.predecessors()[id];
+        for (i, (pred, value)) in incoming.iter().enumerate() {
+            if !preds.contains(pred) {
+                return Err(format!("{} has a value for b{}, which isn't a \
+                    predecessor of b{}", self.name(inst), pred, id));
+            }
+            if incoming[..i].iter().any(|(other, _)| other == pred) {
+                return Err(format!("{} has two values for b{}",
+                    self.name(inst), pred));
+            }
+            self.dominates(value, *pred, usize::MAX)?;
+        }
+        if let Some(pred) = preds.iter()
+            .find(|pred| incoming.iter().all(|(other, _)| other != *pred))
+        {
+            return Err(format!("{} has no value for b{}", self.name(inst),
+                pred));
+        }
+        Ok(())
+    }
+
+    // Check that the definition of `value` dominates its use at `position`
+    // in block `id`.
+    fn dominates(&self, value: &Value, id: BlockId, position: usize)
+        -> Check<()>
+    {
+        let def = match *value {
+            Value::Inst(def) => def,
+            _ => return Ok(()),
+        };
+        let (block, def_position) = match self.places.get(def) {
+            Some(Some(place)) => *place,
+            _ => {
+                return Err(format!("b{} uses instruction {}, which isn't in \
+                    a block", id, def));
+            }
+        };
+        if !self.doms.is_reachable(id) {
+            return Ok(());
+        }
+        let dominates = match block == id {
+            true => def_position < position,
+            false => self.doms.dominates(block, id),
+        };
+        match dominates {
+            true => Ok(()),
+            false => Err(format!("{} doesn't dominate its use in b{}",
+                self.name(def), id)),
+        }
+    }
+
+    // The type of an operand.
+    fn operand(&self, value: &Value) -> Check<Type> {
+        match *value {
+            Value::Inst(inst) => {
+                if self.places.get(inst).is_none_or(Option::is_none) {
+                    return Err(format!("uses instruction {}, which isn't in \
+                        a block", inst));
+                }
+                match self.function.insts[inst].ty {
+                    Type::Void => Err(format!("uses {}, which has no value",
+                        self.name(inst))),
+                    ref ty => Ok(ty.clone()),
+                }
+            }
+            Value::Param(index) => {
+                match self.function.signature.params.get(index) {
+                    Some(ty) => Ok(ty.clone()),
+                    None => Err(format!("uses parameter {}, which doesn't \
+                        exist", index)),
+                }
+            }
+            _ => {
+                let ty = ty_of_const(value);
+                self.checker.constant(value, &ty)?;
+                Ok(ty)
+            }
+        }
+    }
+
+    // Check that an operand has type `ty`.
+    fn expect(&self, value: &Value, ty: &Type) -> Check<()> {
+        let actual = self.operand(value)?;
+        match actual == *ty {
+            true => Ok(()),
+            false => Err(format!("`{}` is a `{}`, not a `{}`",
+                self.names.value(value), actual, ty)),
+        }
+    }
+
+    fn inst(&self, inst: InstId) -> Check<()> {
+        let inst = &self.function.insts[inst];
+        let ty = &inst.ty;
+        let value_ty = || match *ty {
+            Type::Void => Err("has type `void`".to_string()),
+            _ => Ok(()),
+        };
+        let result = |expected: &Type| match ty == expected {
+            true => Ok(()),
+            false => Err(format!("has type `{}`, not `{}`", ty, expected)),
+        };
+        match inst.kind {
+            InstKind::Binary(op, ref a, ref b) => {
+                if op.is_float() && !ty.is_float()
+                    || !op.is_float() && !ty.is_int()
+                {
+                    return Err(format!("`{}` can't be done on `{}`",
+                        op.as_str(), ty));
+                }
+                self.expect(a, ty)?;
+                self.expect(b, ty)
+            }
+            InstKind::FNeg(ref a) => {
+                if !ty.is_float() {
+                    return Err(format!("`fneg` can't be done on `{}`", ty));
+                }
+                self.expect(a, ty)
+            }
+            InstKind::Icmp(_, ref a, ref b) => {
+                result(&Type::I1)?;
+                let operands = self.operand(a)?;
+                if !operands.is_int() && operands != Type::Ptr {
+                    return Err(format!("`icmp` can't compare `{}`",
+                        operands));
+                }
+                self.expect(b, &operands)
+            }
+            InstKind::Fcmp(_, ref a, ref b) => {
+                result(&Type::I1)?;
+                let operands = self.operand(a)?;
+                if !operands.is_float() {
+                    return Err(format!("`fcmp` can't compare `{}`",
+                        operands));
+                }
+                self.expect(b, &operands)
+            }
+            InstKind::Cast(op, ref a) => {
+                let from = self.operand(a)?;
+                if !can_cast(op, &from, ty) {
+                    return Err(format!("`{}` can't convert `{}` to `{}`",
+                        op.as_str(), from, ty));
+                }
+                Ok(())
+            }
+            InstKind::Select(ref cond, ref a, ref b) => {
+                value_ty()?;
+                self.expect(cond, &Type::I1)?;
+                self.expect(a, ty)?;
+                self.expect(b, ty)
+            }
+            InstKind::Alloca(ref slot) => {
+                if *slot == Type::Void {
+                    return Err("allocates `void`".to_string());
+                }
+                result(&Type::Ptr)
+            }
+            InstKind::Load(ref ptr) => {
+                value_ty()?;
+                self.expect(ptr, &Type::Ptr)
+            }
+            InstKind::Store(ref a, ref ptr) => {
+                self.operand(a)?;
+                self.expect(ptr, &Type::Ptr)?;
+                match *ty {
+                    Type::Void => Ok(()),
+                    _ => Err("`store` has a value".to_string()),
+                }
+            }
+            InstKind::PtrAdd(ref ptr, ref offset) => {
+                result(&Type::Ptr)?;
+                self.expect(ptr, &Type::Ptr)?;
+                self.expect(offset, &Type::I64)
+            }
+            InstKind::Call(ref signature, ref callee, ref args) => {
+                if signature.ret != *ty {
+                    return Err(format!("returns `{}`, not `{}`",
+                        signature.ret, ty));
+                }
+                self.expect(callee, &Type::Ptr)?;
+                if let Value::Global(ref name) = *callee {
+                    match self.checker.symbol(name) {
+                        Some(Symbol::Function(function))
+                            if function.signature != *signature =>
+                        {
+                            return Err(format!("calls `{}` as `{}`, but it \
+                                is `{}`", symbol(name), signature,
+                                function.signature));
+                        }
+                        Some(Symbol::Global) => {
+                            return Err(format!("calls `{}`, which is a \
+                                global variable", symbol(name)));
+                        }
+                        _ => {}
+                    }
+                }
+                let params = &signature.params;
+                if args.len() < params.len()
+                    || args.len() > params.len() && !signature.variadic
+                {
+                    return Err(format!("has {} arguments for {} parameters",
+                        args.len(), params.len()));
+                }
+                for (i, arg) in args.iter().enumerate() {
+                    match params.get(i) {
+                        Some(param) => self.expect(arg, param)?,
+                        None => {
+                            self.operand(arg)?;
+                        }
+                    }
+                }
+                Ok(())
+            }
+            InstKind::Phi(ref incoming) => {
+                value_ty()?;
+                for (_, value) in incoming {
+                    self.expect(value, ty)?;
+                }
+                Ok(())
+            }
+            InstKind::ExtractValue(ref aggregate, index) => {
+                let aggregate = self.operand(aggregate)?;
+                match aggregate.member(index) {
+                    Some(member) if member == ty => Ok(()),
+                    Some(member) => Err(format!("member {} of `{}` is a \
+                        `{}`, not a `{}`", index, aggregate, member, ty)),
+                    None => Err(format!("`{}` has no member {}", aggregate,
+                        index)),
+                }
+            }
+            InstKind::InsertValue(ref aggregate, ref a, index) => {
+                self.expect(aggregate, ty)?;
+                match ty.member(index) {
+                    Some(member) => self.expect(a, member),
+                    None => Err(format!("`{}` has no member {}", ty, index)),
+                }
+            }
+            InstKind::VaStart(ref va_list) => {
+                if !self.function.signature.variadic {
+                    return Err("`va_start` in a function that isn't \
+                        variadic".to_string());
+                }
+                result(&Type::Void)?;
+                self.expect(va_list, &Type::Ptr)
+            }
+            InstKind::VaArg(ref va_list) => {
+                value_ty()?;
+                self.expect(va_list, &Type::Ptr)
+            }
+        }
+    }
+
+    fn terminator(&self, term: &Terminator) -> Check<()> {
+        let ret = &self.function.signature.ret;
+        match *term {
+            Terminator::Br(_) | Terminator::Unreachable => Ok(()),
+            Terminator::CondBr(ref cond, ..) => self.expect(cond, &Type::I1),
+            Terminator::Switch(ref value, _, ref cases) => {
+                let ty = self.operand(value)?;
+                let bits = match ty {
+                    Type::Int(bits) => bits,
+                    _ => return Err(format!("can't switch on `{}`", ty)),
+                };
+                for (i, (case, _)) in cases.iter().enumerate() {
+                    if wrap(bits, *case) != *case {
+                        return Err(format!("case {} doesn't fit in `{}`",
+                            case, ty));
+                    }
+                    if cases[..i].iter().any(|(other, _)| other == case) {
+                        return Err(format!("case {} is there twice", case));
+                    }
+                }
+                Ok(())
+            }
+            Terminator::Ret(Some(ref value)) => {
+                if *ret == Type::Void {
+                    return Err("returns a value from a `void` function"
+                        .to_string());
+                }
+                self.expect(value, ret)
+            }
+            Terminator::Ret(None) => match *ret {
+                Type::Void => Ok(()),
+                _ => Err(format!("returns nothing instead of a `{}`", ret)),
+            },
+        }
+    }
+}
+
+// Whether `op` can convert a `from` to a `to`.
+fn can_cast(op: CastOp, from: &Type, to: &Type) -> bool {
+    let bits = |ty: &Type| match *ty {
+        Type::Int(bits) => bits,
+        _ => 0,
+    };
+    match op {
+        CastOp::Trunc => from.is_int() && to.is_int() && bits(to) < bits(from),
+        CastOp::ZExt | CastOp::SExt => {
+            from.is_int() && to.is_int() && bits(to) > bits(from)
+        }
+        CastOp::FpTrunc => *from == Type::F64 && *to == Type::F32,
+        CastOp::FpExt => *from == Type::F32 && *to == Type::F64,
+        CastOp::FpToSi | CastOp::FpToUi => from.is_float() && to.is_int(),
+        CastOp::SiToFp | CastOp::UiToFp => from.is_int() && to.is_float(),
+        CastOp::PtrToInt => *from == Type::Ptr && to.is_int(),
+        CastOp::IntToPtr => from.is_int() && *to == Type::Ptr,
+        CastOp::Bitcast => {
+            let scalar = |ty: &Type| {
+                ty.is_int() && *ty != Type::I1 || ty.is_float()
+                    || *ty == Type::Ptr
+            };
+            scalar(from) && scalar(to) && from.size() == to.size()
+        }
+    }
+}
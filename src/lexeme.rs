@@ -2,6 +2,83 @@
 
 use std::str::CharIndices;
 
+/// A range of bytes in the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub struct Span {
+    /// Byte index of the first character.
+    pub start: usize,
+    /// Byte index one past the last character.
+    pub end: usize,
+}
+
+impl Span {
+    /// Create a new span from byte indices.
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// Create a span that covers both `self` and `other`.
+    pub fn to(self, other: Span) -> Self {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    /// Get the 1-based line and column of the start of the span.
+    pub fn line_col(&self, text: &str) -> (usize, usize) {
+        let before = &text[..self.start.min(text.len())];
+        let line = before.matches('\n').count() + 1;
+        let col = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+
+        (line, col)
+    }
+}
+
+/// An error or warning pointing at a location in the source text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Where in the source the problem is.
+    pub span: Span,
+    /// Description of the problem.
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Create a new diagnostic.
+    pub fn new<T: Into<String>>(span: Span, message: T) -> Self {
+        Diagnostic {
+            span,
+            message: message.into(),
+        }
+    }
+
+    /// Render the diagnostic with the offending source line underlined.
+    pub fn render(&self, filename: &str, text: &str) -> String {
+        let (line, col) = self.span.line_col(text);
+        let source = text.lines().nth(line - 1).unwrap_or("");
+        let width = self.span.end.saturating_sub(self.span.start).max(1);
+        let width = width.min(source.len().saturating_sub(col - 1)).max(1);
+
+        format!(
+            "error: {}\n --> {}:{}:{}\n  |\n  | {}\n  | {}{}\n",
+            self.message,
+            filename,
+            line,
+            col,
+            source,
+            " ".repeat(col - 1),
+            "^".repeat(width),
+        )
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}..{}: {}", self.span.start, self.span.end, self.message)
+    }
+}
+
 enum CharType {
     Letter,
     Number,
@@ -19,13 +96,13 @@ impl CharType {
             || ch == '{' || ch == '}'
         {
             CharType::Bracket
-        } else if ch.is_digit(10) {
+        } else if ch.is_ascii_digit() {
             CharType::Number
         } else if ch.is_ascii_punctuation() {
             CharType::Operator
         } else if ch.is_ascii_whitespace() {
             CharType::Whitespace
-        }else {
+        } else {
             CharType::NonAscii
         }
     }
@@ -57,6 +134,9 @@ pub enum Lexeme<'a> {
     ///
     /// Example: `a::<B::<C>>::new() >> d`; `>>` will always count as 1 lexeme
     /// even though it's first occurance it should be interpreted as 2.
+    ///
+    /// A character outside of ASCII that doesn't start a text chunk is an
+    /// operator on its own.
     Operator(&'a str),
     /// One of: `()[]{}`
     Bracket(&'a str),
@@ -80,17 +160,43 @@ impl<'a> std::fmt::Display for Lexeme<'a> {
 pub struct LexemeIterator<'a, C> {
     text: &'a str,
     chars: CharIndices<'a>,
+    offset: usize,
     nextc: Option<(usize, char)>,
+    span: Span,
     begin_text: fn(&str) -> (Option<C>, Option<char>),
-    end_text: fn(&str, &C) -> (bool, usize),
+    end_text: fn(&str, &mut C) -> (bool, usize),
 }
 
 impl<'a, C> LexemeIterator<'a, C> {
     /// Create a new lexeme iterator from a string.
-    pub fn new(text: &'a str, begin_text: fn(&str) -> (Option<C>, Option<char>), end_text: fn(&str, &C) -> (bool, usize)) -> Self {
+    pub fn new(text: &'a str, begin_text: fn(&str) -> (Option<C>, Option<char>), end_text: fn(&str, &mut C) -> (bool, usize)) -> Self {
         let mut chars = text.char_indices();
         let nextc = chars.next();
-        LexemeIterator { text, chars, nextc, begin_text, end_text }
+        let span = Span::default();
+        LexemeIterator { text, chars, offset: 0, nextc, span, begin_text, end_text }
+    }
+
+    /// Get the source text being iterated over.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Get the span of the most recently returned lexeme.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Continue lexing from byte index `index` of the source text.  Used by
+    /// language token iterators to split or join lexemes.
+    pub fn seek(&mut self, index: usize) {
+        self.offset = index;
+        self.chars = self.text[index..].char_indices();
+        self.nextc = self.next_char();
+    }
+
+    fn next_char(&mut self) -> Option<(usize, char)> {
+        let offset = self.offset;
+        self.chars.next().map(|(i, ch)| (i + offset, ch))
     }
 }
 
@@ -100,19 +206,29 @@ impl<'a, C> Iterator for LexemeIterator<'a, C> {
     fn next(&mut self) -> Option<Lexeme<'a>> {
         let (start_index, ch) = self.nextc?;
         let mut end_index = None;
-        let (chunk_kind, _escape) = (self.begin_text)(&self.text[start_index..]);
+        let (chunk_kind, escape) = (self.begin_text)(&self.text[start_index..]);
+        let is_text = chunk_kind.is_some();
 
         self.nextc = None;
 
-        let kind = if let Some(ref chunk) = chunk_kind {
+        let kind = if let Some(mut chunk) = chunk_kind {
             let kind = CharType::NonAscii;
+            let mut escaped = false;
+
+            while let Some((i, ch)) = self.next_char() {
+                if escaped {
+                    escaped = false;
+                    continue;
+                } else if Some(ch) == escape {
+                    escaped = true;
+                    continue;
+                }
 
-            while let Some((i, ch)) = self.chars.next() {
-                let (end, end_size) = (self.end_text)(&self.text[i..], chunk);
+                let (end, end_size) = (self.end_text)(&self.text[i..], &mut chunk);
 
                 let quit = if end {
                     for _ in 0..end_size {
-                        self.nextc = self.chars.next();
+                        self.nextc = self.next_char();
                     }
                     true
                 } else if !kind.can_append(ch) {
@@ -131,10 +247,12 @@ impl<'a, C> Iterator for LexemeIterator<'a, C> {
         } else {
             let kind = CharType::new(ch);
 
-            while let Some((i, ch)) = self.chars.next() {
+            while let Some((i, ch)) = self.next_char() {
                 let (chunk, _esc) = (self.begin_text)(&self.text[i..]);
 
-                if !kind.can_append(ch) || chunk.is_some() {
+                if matches!(kind, CharType::NonAscii) || !kind.can_append(ch)
+                    || chunk.is_some()
+                {
                     self.nextc = Some((i, ch));
                     end_index = Some(i);
                     break;
@@ -144,11 +262,15 @@ impl<'a, C> Iterator for LexemeIterator<'a, C> {
             kind
         };
 
-        let slice = if let Some(end_index) = end_index {
-            &self.text[start_index .. end_index]
+        let end_index = end_index.unwrap_or(self.text.len());
+        let slice = &self.text[start_index .. end_index];
+        // Text spans include the closing delimiter, which isn't in the slice.
+        let span_end = if is_text {
+            self.nextc.map(|(i, _)| i).unwrap_or(self.text.len())
         } else {
-            &self.text[start_index ..]
+            end_index
         };
+        self.span = Span::new(start_index, span_end);
 
         match kind {
             CharType::Letter => Some(Lexeme::Word(slice)),
@@ -156,10 +278,11 @@ impl<'a, C> Iterator for LexemeIterator<'a, C> {
             CharType::Operator => Some(Lexeme::Operator(slice)),
             CharType::Bracket => Some(Lexeme::Bracket(slice)),
             CharType::Whitespace => Self::next(self),
-            CharType::NonAscii => if chunk_kind.is_some() {
+            CharType::NonAscii => if is_text {
                 Some(Lexeme::Text(slice))
             } else {
-                panic!("Failed to compile, invalid ascii!")                
+                // Left to the language to reject, with its span.
+                Some(Lexeme::Operator(slice))
             }
         }
     }
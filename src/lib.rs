@@ -11,6 +11,9 @@ pub mod c;
 pub mod python;
 #[cfg(feature = "aratar")]
 pub mod aratar;
+pub mod ir;
+pub mod wasm;
+pub mod x86_64;
 
 mod lexeme;
 
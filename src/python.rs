@@ -1,3 +1,1425 @@
 // Python
 //
 //! Python Programming Language (but compiled).
+
+pub mod compile;
+pub mod lower;
+pub mod runtime;
+
+mod dump;
+mod parser;
+
+use std::collections::VecDeque;
+
+use crate::{Diagnostic, Lexeme, LexemeIterator, Span};
+use parser::Parser;
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+/// A Python keyword (soft keywords like `match`, `case` and `type` are names)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyword {
+    False,
+    None,
+    True,
+    And,
+    As,
+    Assert,
+    Async,
+    Await,
+    Break,
+    Class,
+    Continue,
+    Def,
+    Del,
+    Elif,
+    Else,
+    Except,
+    Finally,
+    For,
+    From,
+    Global,
+    If,
+    Import,
+    In,
+    Is,
+    Lambda,
+    Nonlocal,
+    Not,
+    Or,
+    Pass,
+    Raise,
+    Return,
+    Try,
+    While,
+    With,
+    Yield,
+}
+
+const KEYWORDS: &[(&str, Keyword)] = &[
+    ("False", Keyword::False),
+    ("None", Keyword::None),
+    ("True", Keyword::True),
+    ("and", Keyword::And),
+    ("as", Keyword::As),
+    ("assert", Keyword::Assert),
+    ("async", Keyword::Async),
+    ("await", Keyword::Await),
+    ("break", Keyword::Break),
+    ("class", Keyword::Class),
+    ("continue", Keyword::Continue),
+    ("def", Keyword::Def),
+    ("del", Keyword::Del),
+    ("elif", Keyword::Elif),
+    ("else", Keyword::Else),
+    ("except", Keyword::Except),
+    ("finally", Keyword::Finally),
+    ("for", Keyword::For),
+    ("from", Keyword::From),
+    ("global", Keyword::Global),
+    ("if", Keyword::If),
+    ("import", Keyword::Import),
+    ("in", Keyword::In),
+    ("is", Keyword::Is),
+    ("lambda", Keyword::Lambda),
+    ("nonlocal", Keyword::Nonlocal),
+    ("not", Keyword::Not),
+    ("or", Keyword::Or),
+    ("pass", Keyword::Pass),
+    ("raise", Keyword::Raise),
+    ("return", Keyword::Return),
+    ("try", Keyword::Try),
+    ("while", Keyword::While),
+    ("with", Keyword::With),
+    ("yield", Keyword::Yield),
+];
+
+impl Keyword {
+    fn new(word: &str) -> Option<Keyword> {
+        KEYWORDS.iter().find(|(text, _)| *text == word).map(|(_, kw)| *kw)
+    }
+
+    /// Get the source text of the keyword.
+    pub fn as_str(self) -> &'static str {
+        KEYWORDS.iter().find(|(_, kw)| *kw == self).unwrap().0
+    }
+}
+
+/// Python punctuation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Plus,
+    Minus,
+    Star,
+    DoubleStar,
+    Slash,
+    DoubleSlash,
+    Percent,
+    At,
+    Shl,
+    Shr,
+    And,
+    Or,
+    Caret,
+    Tilde,
+    /// `:=`
+    ColonEq,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    EqEq,
+    Ne,
+    PlusEq,
+    MinusEq,
+    StarEq,
+    DoubleStarEq,
+    SlashEq,
+    DoubleSlashEq,
+    PercentEq,
+    AtEq,
+    ShlEq,
+    ShrEq,
+    AndEq,
+    OrEq,
+    CaretEq,
+    Eq,
+    Dot,
+    Comma,
+    Colon,
+    Semi,
+    RArrow,
+    Ellipsis,
+    /// `!`, the conversion in an f-string replacement field
+    Not,
+}
+
+impl Operator {
+    /// Get the source text of the operator.
+    pub fn as_str(self) -> &'static str {
+        OPERATORS.iter().find(|(_, op)| *op == self).unwrap().0
+    }
+}
+
+const OPERATORS: &[(&str, Operator)] = &[
+    ("**=", Operator::DoubleStarEq),
+    ("//=", Operator::DoubleSlashEq),
+    ("<<=", Operator::ShlEq),
+    (">>=", Operator::ShrEq),
+    ("...", Operator::Ellipsis),
+    ("->", Operator::RArrow),
+    (":=", Operator::ColonEq),
+    ("**", Operator::DoubleStar),
+    ("//", Operator::DoubleSlash),
+    ("<<", Operator::Shl),
+    (">>", Operator::Shr),
+    ("<=", Operator::Le),
+    (">=", Operator::Ge),
+    ("==", Operator::EqEq),
+    ("!=", Operator::Ne),
+    ("+=", Operator::PlusEq),
+    ("-=", Operator::MinusEq),
+    ("*=", Operator::StarEq),
+    ("/=", Operator::SlashEq),
+    ("%=", Operator::PercentEq),
+    ("@=", Operator::AtEq),
+    ("&=", Operator::AndEq),
+    ("|=", Operator::OrEq),
+    ("^=", Operator::CaretEq),
+    ("+", Operator::Plus),
+    ("-", Operator::Minus),
+    ("*", Operator::Star),
+    ("/", Operator::Slash),
+    ("%", Operator::Percent),
+    ("@", Operator::At),
+    ("&", Operator::And),
+    ("|", Operator::Or),
+    ("^", Operator::Caret),
+    ("~", Operator::Tilde),
+    ("<", Operator::Lt),
+    (">", Operator::Gt),
+    ("=", Operator::Eq),
+    (".", Operator::Dot),
+    (",", Operator::Comma),
+    (":", Operator::Colon),
+    (";", Operator::Semi),
+    ("!", Operator::Not),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bracket {
+    ParensL,
+    ParensR,
+    BraceL,
+    BraceR,
+    SquareL,
+    SquareR,
+}
+
+impl Bracket {
+    /// Get the source text of the bracket.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Bracket::ParensL => "(",
+            Bracket::ParensR => ")",
+            Bracket::BraceL => "{",
+            Bracket::BraceR => "}",
+            Bracket::SquareL => "[",
+            Bracket::SquareR => "]",
+        }
+    }
+
+    // The closing bracket for an opening one.
+    fn close(self) -> Bracket {
+        match self {
+            Bracket::ParensL => Bracket::ParensR,
+            Bracket::BraceL => Bracket::BraceR,
+            _ => Bracket::SquareR,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token<'a> {
+    Keyword(Keyword),
+    Name(&'a str),
+    /// Text after the `#`
+    Comment(&'a str),
+    /// Integer literal (Python's are unbounded, these are limited to 128 bits)
+    Int(u128),
+    Float(f64),
+    /// Imaginary literal such as `2j`
+    Imaginary(f64),
+    /// String literal, with any `r` or `u` prefix
+    String(String),
+    Bytes(Vec<u8>),
+    /// Start of an f-string, with its prefix and quote
+    FStringStart(&'a str),
+    /// Literal text of an f-string between replacement fields, including the
+    /// text of a format spec
+    FStringMiddle(String),
+    FStringEnd,
+    Operator(Operator),
+    Bracket(Bracket),
+    /// End of a logical line
+    Newline,
+    Indent,
+    Dedent,
+}
+
+impl std::fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Keyword(keyword) => write!(f, "{}", keyword.as_str()),
+            Token::Name(name) => write!(f, "{}", name),
+            Token::Comment(_) => write!(f, "comment"),
+            Token::Int(value) => write!(f, "{}", value),
+            Token::Float(value) => write!(f, "{:?}", value),
+            Token::Imaginary(value) => write!(f, "{:?}j", value),
+            Token::String(string) => write!(f, "{:?}", string),
+            Token::Bytes(bytes) => {
+                write!(f, "b{:?}", String::from_utf8_lossy(bytes))
+            }
+            Token::FStringStart(start) => write!(f, "{}", start),
+            Token::FStringMiddle(text) => write!(f, "{}", text),
+            Token::FStringEnd => write!(f, "end of f-string"),
+            Token::Operator(op) => write!(f, "{}", op.as_str()),
+            Token::Bracket(bracket) => write!(f, "{}", bracket.as_str()),
+            Token::Newline => write!(f, "newline"),
+            Token::Indent => write!(f, "indent"),
+            Token::Dedent => write!(f, "dedent"),
+        }
+    }
+}
+
+enum PythonChunk {
+    Comment,
+    /// Quote that closes the string and characters of the opening still to
+    /// skip
+    String(&'static str, usize),
+    /// An identifier starting with a non-ASCII character, which is scanned
+    /// by the token iterator
+    Unicode,
+}
+
+// The length of the prefix of a string literal, and its quote.
+fn string_start(input: &str) -> Option<(usize, &'static str)> {
+    let prefix = input
+        .find(|c: char| !"rRbBuUfF".contains(c))
+        .unwrap_or(input.len());
+    let valid = matches!(input[..prefix].to_ascii_lowercase().as_str(),
+        "" | "r" | "u" | "b" | "br" | "rb" | "f" | "fr" | "rf");
+    let rest = &input[prefix..];
+    let quote = ["\"\"\"", "'''", "\"", "'"]
+        .iter()
+        .find(|quote| rest.starts_with(**quote))?;
+    valid.then_some((prefix, quote))
+}
+
+fn begin_text(input: &str) -> (Option<PythonChunk>, Option<char>) {
+    if input.starts_with('#') {
+        return (Some(PythonChunk::Comment), None);
+    }
+    if input.starts_with(|c: char| !c.is_ascii()) {
+        return (Some(PythonChunk::Unicode), None);
+    }
+    match string_start(input) {
+        // f-strings are scanned by the token iterator.
+        Some((prefix, _)) if input[..prefix].contains(['f', 'F']) => {
+            (None, None)
+        }
+        Some((prefix, quote)) => {
+            let chunk = PythonChunk::String(quote, prefix + quote.len() - 1);
+            (Some(chunk), Some('\\'))
+        }
+        None => (None, None),
+    }
+}
+
+fn end_text(input: &str, chunk: &mut PythonChunk) -> (bool, usize) {
+    match chunk {
+        PythonChunk::Comment => (input.starts_with('\n'), 1),
+        PythonChunk::String(_, skip) if *skip > 0 => {
+            *skip -= 1;
+            (false, 0)
+        }
+        PythonChunk::String(quote, _) => (input.starts_with(*quote), quote.len()),
+        PythonChunk::Unicode => (true, 0),
+    }
+}
+
+// An f-string being scanned.
+struct FString {
+    quote: &'static str,
+    raw: bool,
+    // Span of the start, for errors.
+    start: Span,
+    // Open replacement fields, innermost last.
+    fields: Vec<Field>,
+}
+
+// A replacement field of an f-string.
+struct Field {
+    // Number of open brackets outside the field.
+    depth: usize,
+    // Whether the format spec after the `:` is being scanned.
+    spec: bool,
+}
+
+/// An iterator over Python tokens.  `NEWLINE`, `INDENT` and `DEDENT` are
+/// found from the whitespace between tokens: lines inside brackets or ending
+/// with a backslash are joined, and blank or comment-only lines are ignored.
+pub struct TokenIterator<'a> {
+    lexemes: LexemeIterator<'a, PythonChunk>,
+    span: Span,
+    // Tokens found but not returned yet, such as the `DEDENT`s before a
+    // token.
+    queue: VecDeque<(Result<Token<'a>>, Span)>,
+    // End of the previous token, where the whitespace before the next one
+    // starts.
+    last: usize,
+    // Whether a token of the current logical line was returned.
+    in_line: bool,
+    // Indentation of the enclosing blocks, in columns with tabs to multiples
+    // of 8 and with tabs as 1 column.
+    indents: Vec<(usize, usize)>,
+    // Open brackets.
+    brackets: Vec<(Bracket, Span)>,
+    // Open f-strings, innermost last.
+    fstrings: Vec<FString>,
+    done: bool,
+}
+
+impl<'a> TokenIterator<'a> {
+    /// Create a new Python token iterator.
+    pub fn new(text: &'a str) -> Self {
+        TokenIterator {
+            lexemes: LexemeIterator::new(text, begin_text, end_text),
+            span: Span::default(),
+            queue: VecDeque::new(),
+            last: 0,
+            in_line: false,
+            indents: vec![(0, 0)],
+            brackets: Vec::new(),
+            fstrings: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Get the span of the most recently returned token.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Get the source text being tokenized.
+    pub fn text(&self) -> &'a str {
+        self.lexemes.text()
+    }
+
+    fn push(&mut self, token: Token<'a>, span: Span) {
+        self.queue.push_back((Ok(token), span));
+        self.last = span.end;
+    }
+
+    fn error<T: Into<String>>(&mut self, span: Span, message: T) {
+        self.queue.push_back((Err(Diagnostic::new(span, message)), span));
+    }
+
+    // Continue lexing from byte index `index`.
+    fn seek(&mut self, index: usize) {
+        self.last = self.last.max(index);
+        self.lexemes.seek(index);
+    }
+
+    // Whether the next token is text of an f-string rather than code.
+    fn in_fstring_text(&self) -> bool {
+        self.fstrings.last().is_some_and(|fstring| {
+            fstring.fields.last().is_none_or(|field| field.spec)
+        })
+    }
+
+    // Find the end of the logical line and the indentation of the next one
+    // in the whitespace before a token at `start`.
+    fn whitespace(&mut self, start: usize, comment: bool) {
+        if !self.brackets.is_empty() {
+            return;
+        }
+        let text = self.lexemes.text();
+        let gap = &text[self.last..start];
+        let newline = gap.match_indices('\n').map(|(i, _)| i).find(|&i| {
+            !gap[..i].trim_end_matches('\r').ends_with('\\')
+        });
+        if let Some(i) = newline.filter(|_| self.in_line) {
+            let at = self.last + i;
+            self.queue.push_back((Ok(Token::Newline), Span::new(at, at + 1)));
+            self.in_line = false;
+        }
+        if self.in_line || comment {
+            return;
+        }
+
+        let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+        let indent = &text[line_start..start];
+        let (mut wide, mut narrow) = (0, 0);
+        for ch in indent.chars() {
+            match ch {
+                ' ' => {
+                    wide += 1;
+                    narrow += 1;
+                }
+                '\t' => {
+                    wide = (wide / 8 + 1) * 8;
+                    narrow += 1;
+                }
+                '\x0c' => {
+                    wide = 0;
+                    narrow = 0;
+                }
+                // After a line continuation
+                _ => return,
+            }
+        }
+        let span = Span::new(line_start, start);
+        let inconsistent = "inconsistent use of tabs and spaces in indentation";
+        let &(top_wide, top_narrow) = self.indents.last().unwrap();
+        if wide > top_wide {
+            if narrow <= top_narrow {
+                return self.error(span, inconsistent);
+            }
+            self.indents.push((wide, narrow));
+            self.queue.push_back((Ok(Token::Indent), span));
+            return;
+        }
+        while wide < self.indents.last().unwrap().0 {
+            self.indents.pop();
+            let at = Span::new(start, start);
+            self.queue.push_back((Ok(Token::Dedent), at));
+        }
+        let &(top_wide, top_narrow) = self.indents.last().unwrap();
+        if wide != top_wide {
+            self.error(span,
+                "unindent does not match any outer indentation level");
+        } else if narrow != top_narrow {
+            self.error(span, inconsistent);
+        }
+    }
+
+    // Report what is left open at the end of the input.
+    fn end(&mut self) {
+        self.done = true;
+        let len = self.lexemes.text().len();
+        let eof = Span::new(len, len);
+        if let Some(&(bracket, span)) = self.brackets.first() {
+            self.error(span, format!("'{}' was never closed",
+                bracket.as_str()));
+            return;
+        }
+        if self.lexemes.text()[self.last..].trim_end().ends_with('\\') {
+            self.error(eof, "unexpected EOF while parsing");
+            return;
+        }
+        if self.in_line {
+            self.queue.push_back((Ok(Token::Newline), eof));
+        }
+        for _ in 1..self.indents.len() {
+            self.queue.push_back((Ok(Token::Dedent), eof));
+        }
+    }
+
+    // Scan a name starting at byte index `start`, which may be the prefix of
+    // an f-string.
+    fn name(&mut self, start: usize) {
+        let text = self.lexemes.text();
+        let len = text[start..]
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(text.len() - start);
+        let name = &text[start..start + len];
+        let span = Span::new(start, start + len);
+        if len == 0 {
+            let ch = text[start..].chars().next().unwrap();
+            let span = Span::new(start, start + ch.len_utf8());
+            self.error(span, format!("invalid character '{}' (U+{:04X})", ch,
+                ch as u32));
+            self.seek(span.end);
+            return;
+        }
+        if let Some((prefix, quote)) = string_start(&text[start..]) {
+            if prefix == len && name.contains(['f', 'F']) {
+                let end = start + prefix + quote.len();
+                self.fstrings.push(FString {
+                    quote,
+                    raw: name.contains(['r', 'R']),
+                    start: Span::new(start, end),
+                    fields: Vec::new(),
+                });
+                self.push(Token::FStringStart(&text[start..end]),
+                    Span::new(start, end));
+                self.seek(end);
+                return;
+            }
+        }
+        self.seek(span.end);
+        let token = match Keyword::new(name) {
+            Some(keyword) => Token::Keyword(keyword),
+            None => Token::Name(name),
+        };
+        self.push(token, span);
+    }
+
+    // Scan the text of an f-string up to a replacement field or its end.
+    fn fstring_text(&mut self) {
+        let text = self.lexemes.text();
+        let fstring = self.fstrings.last().unwrap();
+        let (quote, raw, spec) = (
+            fstring.quote,
+            fstring.raw,
+            fstring.fields.last().is_some_and(|field| field.spec),
+        );
+        let start = self.last;
+        let mut i = start;
+        let mut literal = String::new();
+        let middle = |this: &mut Self, literal: &mut String, i: usize| {
+            if i > start {
+                let span = Span::new(start, i);
+                match if raw {
+                    Ok(std::mem::take(literal).into_bytes())
+                } else {
+                    unescape(literal, span, false)
+                } {
+                    Ok(bytes) => {
+                        let string = String::from_utf8(bytes).unwrap();
+                        this.push(Token::FStringMiddle(string), span);
+                    }
+                    Err(e) => this.queue.push_back((Err(e), span)),
+                }
+                literal.clear();
+            }
+        };
+        loop {
+            let rest = &text[i..];
+            if rest.is_empty() || (quote.len() == 1 && rest.starts_with('\n'))
+            {
+                let span = self.fstrings[0].start;
+                self.error(span, "unterminated f-string literal");
+                self.fstrings.clear();
+                self.brackets.clear();
+                self.seek(i);
+                return;
+            }
+            if rest.starts_with(quote) {
+                if spec {
+                    self.error(Span::new(i, i + quote.len()),
+                        "f-string: expecting '}'");
+                }
+                middle(self, &mut literal, i);
+                self.push(Token::FStringEnd, Span::new(i, i + quote.len()));
+                self.fstrings.pop();
+                self.seek(i + quote.len());
+                return;
+            }
+            if !spec && (rest.starts_with("{{") || rest.starts_with("}}")) {
+                literal.push_str(&rest[..1]);
+                i += 2;
+            } else if rest.starts_with('{') {
+                middle(self, &mut literal, i);
+                let span = Span::new(i, i + 1);
+                self.fstrings.last_mut().unwrap().fields.push(Field {
+                    depth: self.brackets.len(),
+                    spec: false,
+                });
+                self.brackets.push((Bracket::BraceL, span));
+                self.push(Token::Bracket(Bracket::BraceL), span);
+                self.seek(i + 1);
+                return;
+            } else if rest.starts_with('}') {
+                let span = Span::new(i, i + 1);
+                if spec {
+                    middle(self, &mut literal, i);
+                    self.brackets.pop();
+                    self.fstrings.last_mut().unwrap().fields.pop();
+                    self.push(Token::Bracket(Bracket::BraceR), span);
+                    self.seek(i + 1);
+                    return;
+                }
+                self.error(span, "f-string: single '}' is not allowed");
+                i += 1;
+            } else if let Some(escaped) = rest.strip_prefix('\\') {
+                // Keep escapes for `unescape`, except before a brace.  A
+                // backslash still quotes the next character in raw f-strings.
+                let next = escaped.chars().next().unwrap_or(' ');
+                let len = if next == '{' || next == '}' {
+                    1
+                } else if !raw && escaped.starts_with("N{") {
+                    rest.find('}').map_or(rest.len(), |end| end + 1)
+                } else {
+                    1 + next.len_utf8()
+                };
+                let len = len.min(rest.len());
+                literal.push_str(&rest[..len]);
+                i += len;
+            } else {
+                let ch = rest.chars().next().unwrap();
+                literal.push(ch);
+                i += ch.len_utf8();
+            }
+        }
+    }
+
+    // Scan a number literal starting at byte index `start`.
+    fn number(&mut self, start: usize) -> Result<Token<'a>> {
+        let text = self.lexemes.text();
+        let bytes = text.as_bytes();
+        // Digits of `radix` with single underscores between them.
+        let digits = |mut i: usize, radix: u32, leading: bool| {
+            let begin = i;
+            loop {
+                match bytes.get(i) {
+                    Some(b'_') if (i > begin || leading)
+                        && bytes.get(i + 1)
+                            .is_some_and(|b| (*b as char).is_digit(radix)) =>
+                    {
+                        i += 2;
+                    }
+                    Some(b) if (*b as char).is_digit(radix) => i += 1,
+                    _ => return i,
+                }
+            }
+        };
+        let radix = match bytes.get(start + 1) {
+            _ if bytes[start] != b'0' => 10,
+            Some(b'x' | b'X') => 16,
+            Some(b'o' | b'O') => 8,
+            Some(b'b' | b'B') => 2,
+            _ => 10,
+        };
+
+        let mut float = false;
+        let mut end;
+        if radix != 10 {
+            end = digits(start + 2, radix, true);
+            if end == start + 2 {
+                self.span = Span::new(start, end);
+                let name = match radix {
+                    16 => "hexadecimal",
+                    8 => "octal",
+                    _ => "binary",
+                };
+                return Err(Diagnostic::new(self.span,
+                    format!("invalid {} literal", name)));
+            }
+        } else {
+            end = digits(start, 10, false);
+            if bytes.get(end) == Some(&b'.') {
+                float = true;
+                end = digits(end + 1, 10, false);
+            }
+            if matches!(bytes.get(end), Some(b'e' | b'E')) {
+                let mut exp = end + 1;
+                if matches!(bytes.get(exp), Some(b'+' | b'-')) {
+                    exp += 1;
+                }
+                if bytes.get(exp).is_some_and(u8::is_ascii_digit) {
+                    float = true;
+                    end = digits(exp, 10, false);
+                }
+            }
+        }
+        let imaginary = radix == 10 && matches!(bytes.get(end), Some(b'j' | b'J'));
+        let digits_end = end;
+        if imaginary {
+            end += 1;
+        }
+        self.span = Span::new(start, end);
+        self.seek(end);
+
+        // `1if x else 2` is allowed, but not `1abc`.
+        let after = &text[end..];
+        let word = after
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .map_or(after, |len| &after[..len]);
+        if !word.is_empty() && Keyword::new(word).is_none() {
+            let kind = match radix {
+                16 => "hexadecimal",
+                8 => "octal",
+                2 => "binary",
+                _ if imaginary => "imaginary",
+                _ => "decimal",
+            };
+            let span = Span::new(start, end + word.len());
+            self.seek(span.end);
+            return Err(Diagnostic::new(span,
+                format!("invalid {} literal", kind)));
+        }
+
+        let literal = text[start..digits_end].replace('_', "");
+        if float || imaginary {
+            let value = literal.parse::<f64>().map_err(|_| {
+                Diagnostic::new(self.span, "invalid float literal")
+            })?;
+            return Ok(if imaginary {
+                Token::Imaginary(value)
+            } else {
+                Token::Float(value)
+            });
+        }
+        let digits = if radix == 10 { &literal[..] } else { &literal[2..] };
+        if radix == 10 && digits.len() > 1 && digits.starts_with('0')
+            && digits.bytes().any(|b| b != b'0')
+        {
+            return Err(Diagnostic::new(self.span,
+                "leading zeros in decimal integer literals are not permitted; \
+                    use an 0o prefix for octal integers"));
+        }
+        u128::from_str_radix(digits, radix).map(Token::Int).map_err(|_| {
+            Diagnostic::new(self.span, "integer literal is too large")
+        })
+    }
+
+    // Decode a string or bytes literal, where `string` is its text without
+    // the closing quote.
+    fn string(&mut self, string: &'a str, span: Span) -> Result<Token<'a>> {
+        let (prefix, quote) = string_start(string).unwrap();
+        let contents = &string[prefix + quote.len()..];
+        let text = self.lexemes.text();
+        let closed = span.end - span.start == string.len() + quote.len()
+            && text[..span.end].ends_with(quote);
+
+        // Single-quoted strings end at the end of the line.
+        if quote.len() == 1 {
+            let mut chars = contents.char_indices();
+            while let Some((i, ch)) = chars.next() {
+                if ch == '\\' {
+                    chars.next();
+                } else if ch == '\n' {
+                    let end = span.start + prefix + 1 + i;
+                    self.span = Span::new(span.start, end);
+                    self.seek(end);
+                    return Err(Diagnostic::new(self.span,
+                        "unterminated string literal"));
+                }
+            }
+        }
+        if !closed {
+            let message = match quote.len() {
+                1 => "unterminated string literal",
+                _ => "unterminated triple-quoted string literal",
+            };
+            return Err(Diagnostic::new(span, message));
+        }
+
+        let prefix = string[..prefix].to_ascii_lowercase();
+        let raw = prefix.contains('r');
+        let bytes = prefix.contains('b');
+        if bytes && !contents.is_ascii() {
+            return Err(Diagnostic::new(span,
+                "bytes can only contain ASCII literal characters"));
+        }
+        let value = if raw {
+            contents.as_bytes().to_vec()
+        } else {
+            unescape(contents, span, bytes)?
+        };
+        Ok(if bytes {
+            Token::Bytes(value)
+        } else {
+            Token::String(String::from_utf8(value).unwrap())
+        })
+    }
+
+    fn operator(&mut self, text: &'a str, span: Span) {
+        let source = self.lexemes.text();
+        if text.starts_with('\\') {
+            let rest = &source[span.start + 1..];
+            if !(rest.starts_with('\n') || rest.starts_with("\r\n")) {
+                let span = Span::new(span.start, span.start + 1);
+                self.error(span,
+                    "unexpected character after line continuation character");
+                self.last = span.end;
+            }
+            // The line is joined with the next one by `whitespace`.
+            self.lexemes.seek(span.start + 1);
+            return;
+        }
+        if text.starts_with('.')
+            && source[span.start + 1..].starts_with(|c: char| c.is_ascii_digit())
+        {
+            let token = self.number(span.start);
+            self.queue.push_back((token, self.span));
+            return;
+        }
+        let (op_text, mut op) = match OPERATORS.iter()
+            .find(|(op_text, _)| text.starts_with(op_text))
+        {
+            Some(&found) => found,
+            None => {
+                let ch = text.chars().next().unwrap();
+                let span = Span::new(span.start, span.start + ch.len_utf8());
+                self.error(span, format!("invalid character '{}' (U+{:04X})",
+                    ch, ch as u32));
+                self.seek(span.end);
+                return;
+            }
+        };
+        let mut len = op_text.len();
+        // A `:` at the top level of a replacement field starts its format
+        // spec, even in `{x:=5}`.
+        let depth = self.brackets.len();
+        let field = self.fstrings.last_mut()
+            .and_then(|fstring| fstring.fields.last_mut())
+            .filter(|field| field.depth + 1 == depth);
+        if let Some(field) = field {
+            if matches!(op, Operator::Colon | Operator::ColonEq) {
+                op = Operator::Colon;
+                len = 1;
+                field.spec = true;
+            }
+        }
+        let span = Span::new(span.start, span.start + len);
+        self.seek(span.end);
+        self.push(Token::Operator(op), span);
+    }
+
+    fn bracket(&mut self, text: &str, span: Span) {
+        let bracket = match text {
+            "(" => Bracket::ParensL,
+            ")" => Bracket::ParensR,
+            "{" => Bracket::BraceL,
+            "}" => Bracket::BraceR,
+            "[" => Bracket::SquareL,
+            "]" => Bracket::SquareR,
+            _ => panic!("COMPILER BUG: Invalid bracket"),
+        };
+        if matches!(bracket, Bracket::ParensL | Bracket::BraceL
+            | Bracket::SquareL)
+        {
+            self.brackets.push((bracket, span));
+            self.push(Token::Bracket(bracket), span);
+            return;
+        }
+        match self.brackets.pop() {
+            Some((open, _)) if open.close() == bracket => {}
+            Some((open, _)) => {
+                self.error(span, format!("closing parenthesis '{}' does not \
+                    match opening parenthesis '{}'", text, open.as_str()));
+            }
+            None => self.error(span, format!("unmatched '{}'", text)),
+        }
+        // Closing a replacement field returns to the text of the f-string.
+        let depth = self.brackets.len();
+        if let Some(fstring) = self.fstrings.last_mut() {
+            if fstring.fields.last()
+                .is_some_and(|field| field.depth == depth)
+            {
+                fstring.fields.pop();
+            }
+        }
+        self.push(Token::Bracket(bracket), span);
+    }
+}
+
+impl<'a> Iterator for TokenIterator<'a> {
+    type Item = Result<Token<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((token, span)) = self.queue.pop_front() {
+                self.span = span;
+                if matches!(token, Ok(ref token) if !matches!(token,
+                    Token::Comment(_) | Token::Newline | Token::Indent
+                    | Token::Dedent))
+                {
+                    self.in_line = true;
+                }
+                return Some(token);
+            }
+            if self.done {
+                return None;
+            }
+            if self.in_fstring_text() {
+                self.fstring_text();
+                continue;
+            }
+            let lexeme = match self.lexemes.next() {
+                Some(lexeme) => lexeme,
+                None => {
+                    if !self.fstrings.is_empty() {
+                        self.fstring_text();
+                    } else {
+                        self.whitespace(self.lexemes.text().len(), true);
+                        self.end();
+                    }
+                    continue;
+                }
+            };
+            let span = self.lexemes.span();
+            let comment = matches!(lexeme, Lexeme::Text(text)
+                if text.starts_with('#'));
+            let continuation = matches!(lexeme, Lexeme::Operator(text)
+                if text.starts_with('\\'));
+            if !continuation {
+                self.whitespace(span.start, comment);
+            }
+
+            match lexeme {
+                Lexeme::Word(_) => self.name(span.start),
+                Lexeme::Text(text) if comment => {
+                    let comment = text[1..].trim_end_matches('\r');
+                    let span = Span::new(span.start, span.start + text.len());
+                    self.push(Token::Comment(comment), span);
+                }
+                Lexeme::Text(text) if !text.is_ascii()
+                    && text.starts_with(|c: char| !c.is_ascii()) =>
+                {
+                    self.name(span.start);
+                }
+                Lexeme::Text(text) => {
+                    let token = self.string(text, span);
+                    let span = match token {
+                        Ok(_) => span,
+                        Err(_) => self.span,
+                    };
+                    self.queue.push_back((token, span));
+                    self.last = self.last.max(span.end);
+                }
+                Lexeme::Number(_) => {
+                    let token = self.number(span.start);
+                    self.queue.push_back((token, self.span));
+                }
+                Lexeme::Operator(text) => self.operator(text, span),
+                Lexeme::Bracket(text) => self.bracket(text, span),
+            }
+        }
+    }
+}
+
+/// Decode the escape sequences of a string or bytes literal, returning UTF-8
+/// (or raw bytes for bytes literals).  Unknown escapes are kept as written.
+fn unescape(text: &str, span: Span, bytes: bool) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut chars = text.chars().peekable();
+    let invalid = |message: &str| Err(Diagnostic::new(span, message));
+    let push = |out: &mut Vec<u8>, ch: char| {
+        let mut buf = [0; 4];
+        out.extend(ch.encode_utf8(&mut buf).bytes());
+    };
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            push(&mut out, ch);
+            continue;
+        }
+        let ch = match chars.next() {
+            Some('\n') => continue,
+            Some('\\') => '\\',
+            Some('\'') => '\'',
+            Some('"') => '"',
+            Some('a') => '\x07',
+            Some('b') => '\x08',
+            Some('f') => '\x0c',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('v') => '\x0b',
+            Some(first @ '0'..='7') => {
+                let mut value = first.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            value = value * 8 + digit;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                if bytes {
+                    if value > 0o377 {
+                        return invalid("octal escape out of range");
+                    }
+                    out.push(value as u8);
+                    continue;
+                }
+                char::from_u32(value).unwrap()
+            }
+            Some(kind @ ('x' | 'u' | 'U')) if !bytes || kind == 'x' => {
+                let len = match kind {
+                    'x' => 2,
+                    'u' => 4,
+                    _ => 8,
+                };
+                let digits: String = chars.by_ref().take(len).collect();
+                let value = match u32::from_str_radix(&digits, 16) {
+                    Ok(value) if digits.len() == len
+                        && digits.chars().all(|c| c.is_ascii_hexdigit()) =>
+                    {
+                        value
+                    }
+                    _ => {
+                        return Err(Diagnostic::new(span, format!(
+                            "truncated \\{}{} escape", kind,
+                            "X".repeat(len))));
+                    }
+                };
+                if bytes {
+                    out.push(value as u8);
+                    continue;
+                }
+                match char::from_u32(value) {
+                    Some(ch) => ch,
+                    None => return invalid("illegal Unicode character"),
+                }
+            }
+            Some('N') if !bytes => {
+                return invalid("`\\N{...}` escapes are not supported");
+            }
+            Some(ch) => {
+                // Invalid escapes are a warning in Python.
+                push(&mut out, '\\');
+                ch
+            }
+            None => '\\',
+        };
+        push(&mut out, ch);
+    }
+
+    Ok(out)
+}
+
+/// Parse a Python module.  The tree has the shape of the one Python's `ast`
+/// module builds, with spans in place of line and column numbers.
+pub fn parse_module(text: &str) -> Result<Module<'_>> {
+    Parser::new(text).module()
+}
+
+/// A parsed source file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Module<'a> {
+    pub body: Vec<Stmt<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stmt<'a> {
+    pub kind: StmtKind<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StmtKind<'a> {
+    FunctionDef(FunctionDef<'a>),
+    AsyncFunctionDef(FunctionDef<'a>),
+    ClassDef(ClassDef<'a>),
+    Return(Option<Expr<'a>>),
+    /// Targets of `del`
+    Delete(Vec<Expr<'a>>),
+    /// Targets (several in `a = b = value`) and value
+    Assign(Vec<Expr<'a>>, Expr<'a>),
+    /// `type Name[params] = value`
+    TypeAlias(Expr<'a>, Vec<TypeParam<'a>>, Expr<'a>),
+    /// `target op= value`
+    AugAssign(Expr<'a>, BinaryOp, Expr<'a>),
+    /// Target, annotation, value, and whether the target is a name outside
+    /// parentheses
+    AnnAssign(Expr<'a>, Expr<'a>, Option<Expr<'a>>, bool),
+    For(For<'a>),
+    AsyncFor(For<'a>),
+    /// Condition, body and `else` body
+    While(Expr<'a>, Vec<Stmt<'a>>, Vec<Stmt<'a>>),
+    /// Condition, body and `else` body (an `elif` is an `If` alone in it)
+    If(Expr<'a>, Vec<Stmt<'a>>, Vec<Stmt<'a>>),
+    With(Vec<WithItem<'a>>, Vec<Stmt<'a>>),
+    AsyncWith(Vec<WithItem<'a>>, Vec<Stmt<'a>>),
+    /// Subject and cases
+    Match(Expr<'a>, Vec<MatchCase<'a>>),
+    /// Exception and cause
+    Raise(Option<Expr<'a>>, Option<Expr<'a>>),
+    Try(Try<'a>),
+    /// `try` with `except*` handlers
+    TryStar(Try<'a>),
+    /// Condition and message
+    Assert(Expr<'a>, Option<Expr<'a>>),
+    Import(Vec<Alias<'a>>),
+    /// Module (none in `from . import name`), names and number of leading
+    /// dots
+    ImportFrom(Option<String>, Vec<Alias<'a>>, usize),
+    Global(Vec<&'a str>),
+    Nonlocal(Vec<&'a str>),
+    Expr(Expr<'a>),
+    Pass,
+    Break,
+    Continue,
+}
+
+/// A `def` statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDef<'a> {
+    pub name: &'a str,
+    pub type_params: Vec<TypeParam<'a>>,
+    pub args: Arguments<'a>,
+    pub body: Vec<Stmt<'a>>,
+    pub decorator_list: Vec<Expr<'a>>,
+    pub returns: Option<Expr<'a>>,
+}
+
+/// A `class` statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassDef<'a> {
+    pub name: &'a str,
+    pub type_params: Vec<TypeParam<'a>>,
+    pub bases: Vec<Expr<'a>>,
+    pub keywords: Vec<KeywordArg<'a>>,
+    pub body: Vec<Stmt<'a>>,
+    pub decorator_list: Vec<Expr<'a>>,
+}
+
+/// A `for` loop
+#[derive(Debug, Clone, PartialEq)]
+pub struct For<'a> {
+    pub target: Expr<'a>,
+    pub iter: Expr<'a>,
+    pub body: Vec<Stmt<'a>>,
+    pub orelse: Vec<Stmt<'a>>,
+}
+
+/// A `try` statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct Try<'a> {
+    pub body: Vec<Stmt<'a>>,
+    pub handlers: Vec<ExceptHandler<'a>>,
+    pub orelse: Vec<Stmt<'a>>,
+    pub finalbody: Vec<Stmt<'a>>,
+}
+
+/// `except ty as name:`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExceptHandler<'a> {
+    pub ty: Option<Expr<'a>>,
+    pub name: Option<&'a str>,
+    pub body: Vec<Stmt<'a>>,
+    pub span: Span,
+}
+
+/// `context_expr as optional_vars` in a `with` statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithItem<'a> {
+    pub context_expr: Expr<'a>,
+    pub optional_vars: Option<Expr<'a>>,
+}
+
+/// A name in an `import` statement (`*` in `from module import *`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alias<'a> {
+    /// Dotted name
+    pub name: String,
+    pub asname: Option<&'a str>,
+    pub span: Span,
+}
+
+/// A parameter of a generic function, class or type alias
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeParam<'a> {
+    /// `T` or `T: bound`
+    TypeVar(&'a str, Option<Expr<'a>>),
+    /// `**P`
+    ParamSpec(&'a str),
+    /// `*Ts`
+    TypeVarTuple(&'a str),
+}
+
+/// Parameters of a function or lambda
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Arguments<'a> {
+    /// Parameters before `/`
+    pub posonlyargs: Vec<Arg<'a>>,
+    pub args: Vec<Arg<'a>>,
+    /// `*args`
+    pub vararg: Option<Arg<'a>>,
+    /// Parameters after `*` or `*args`
+    pub kwonlyargs: Vec<Arg<'a>>,
+    /// Default of each keyword-only parameter
+    pub kw_defaults: Vec<Option<Expr<'a>>>,
+    /// `**kwargs`
+    pub kwarg: Option<Arg<'a>>,
+    /// Defaults of the last positional parameters
+    pub defaults: Vec<Expr<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arg<'a> {
+    pub arg: &'a str,
+    pub annotation: Option<Expr<'a>>,
+    pub span: Span,
+}
+
+/// `name=value` in a call or class bases (`**value` has no name)
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeywordArg<'a> {
+    pub arg: Option<&'a str>,
+    pub value: Expr<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr<'a> {
+    pub kind: ExprKind<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind<'a> {
+    /// `and` or `or` of two or more values
+    BoolOp(BoolOp, Vec<Expr<'a>>),
+    /// `target := value`
+    NamedExpr(Box<Expr<'a>>, Box<Expr<'a>>),
+    BinOp(Box<Expr<'a>>, BinaryOp, Box<Expr<'a>>),
+    UnaryOp(UnaryOp, Box<Expr<'a>>),
+    Lambda(Box<Arguments<'a>>, Box<Expr<'a>>),
+    /// `body if test else orelse`, as test, body and orelse
+    IfExp(Box<Expr<'a>>, Box<Expr<'a>>, Box<Expr<'a>>),
+    /// Keys (none for `**mapping`) and values
+    Dict(Vec<Option<Expr<'a>>>, Vec<Expr<'a>>),
+    Set(Vec<Expr<'a>>),
+    ListComp(Box<Expr<'a>>, Vec<Comprehension<'a>>),
+    SetComp(Box<Expr<'a>>, Vec<Comprehension<'a>>),
+    DictComp(Box<Expr<'a>>, Box<Expr<'a>>, Vec<Comprehension<'a>>),
+    GeneratorExp(Box<Expr<'a>>, Vec<Comprehension<'a>>),
+    Await(Box<Expr<'a>>),
+    Yield(Option<Box<Expr<'a>>>),
+    YieldFrom(Box<Expr<'a>>),
+    /// `a < b <= c`, as the first operand, operators and the other operands
+    Compare(Box<Expr<'a>>, Vec<CmpOp>, Vec<Expr<'a>>),
+    /// Function, positional arguments and keyword arguments
+    Call(Box<Expr<'a>>, Vec<Expr<'a>>, Vec<KeywordArg<'a>>),
+    /// Replacement field of an f-string: value, conversion (`s`, `r` or
+    /// `a`) and format spec
+    FormattedValue(Box<Expr<'a>>, Option<char>, Option<Box<Expr<'a>>>),
+    /// An f-string, as constant strings and replacement fields
+    JoinedStr(Vec<Expr<'a>>),
+    Constant(Constant),
+    Attribute(Box<Expr<'a>>, &'a str, ExprContext),
+    /// Value and index (a `Slice`, or a `Tuple` for `a[i, j]`)
+    Subscript(Box<Expr<'a>>, Box<Expr<'a>>, ExprContext),
+    /// `*value`
+    Starred(Box<Expr<'a>>, ExprContext),
+    Name(&'a str, ExprContext),
+    List(Vec<Expr<'a>>, ExprContext),
+    Tuple(Vec<Expr<'a>>, ExprContext),
+    /// `lower:upper:step` in a subscript
+    Slice(
+        Option<Box<Expr<'a>>>,
+        Option<Box<Expr<'a>>>,
+        Option<Box<Expr<'a>>>,
+    ),
+}
+
+/// Whether an expression is read, assigned or deleted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExprContext {
+    Load,
+    Store,
+    Del,
+}
+
+/// A literal
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    None,
+    Bool(bool),
+    Int(u128),
+    Float(f64),
+    /// Imaginary number
+    Complex(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+    /// `...`
+    Ellipsis,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoolOp {
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mult,
+    /// `@`
+    MatMult,
+    Div,
+    Mod,
+    Pow,
+    LShift,
+    RShift,
+    BitOr,
+    BitXor,
+    BitAnd,
+    FloorDiv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// `~`
+    Invert,
+    Not,
+    /// `+`
+    UAdd,
+    /// `-`
+    USub,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtE,
+    Gt,
+    GtE,
+    Is,
+    IsNot,
+    In,
+    NotIn,
+}
+
+/// `for target in iter if ifs` in a comprehension
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comprehension<'a> {
+    pub target: Expr<'a>,
+    pub iter: Expr<'a>,
+    pub ifs: Vec<Expr<'a>>,
+    pub is_async: bool,
+}
+
+/// `case pattern if guard:` in a `match` statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchCase<'a> {
+    pub pattern: Pattern<'a>,
+    pub guard: Option<Expr<'a>>,
+    pub body: Vec<Stmt<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern<'a> {
+    pub kind: PatternKind<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternKind<'a> {
+    /// A literal or a dotted name, compared with `==`
+    MatchValue(Expr<'a>),
+    /// `None`, `True` or `False`, compared with `is`
+    MatchSingleton(Constant),
+    /// `[a, *rest]` or `(a, b)`
+    MatchSequence(Vec<Pattern<'a>>),
+    /// Keys, their patterns and the name of `**rest`
+    MatchMapping(Vec<Expr<'a>>, Vec<Pattern<'a>>, Option<&'a str>),
+    /// Class, positional patterns, keyword names and keyword patterns
+    MatchClass(Expr<'a>, Vec<Pattern<'a>>, Vec<&'a str>, Vec<Pattern<'a>>),
+    /// `*name` in a sequence pattern (no name for `*_`)
+    MatchStar(Option<&'a str>),
+    /// `pattern as name`, a capture (no pattern) or `_` (neither)
+    MatchAs(Option<Box<Pattern<'a>>>, Option<&'a str>),
+    MatchOr(Vec<Pattern<'a>>),
+}
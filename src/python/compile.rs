@@ -0,0 +1,1898 @@
+// Python compilation
+//
+//! Ahead-of-time compilation of Python modules.
+//!
+//! Types are inferred for the whole module at once.  Every function is
+//! compiled once for each combination of argument types it is called with
+//! (monomorphization), and a variable's type is the union of the types of
+//! everything assigned to it in its function.  Types come from literals,
+//! from the operations and built-in functions that produce values, from what
+//! functions return, and from PEP 484 annotations of `int`, `float`, `bool`,
+//! `str` and `None`, which are trusted: a value of another type is an error
+//! when it is known statically, and raises `TypeError` at run time when it
+//! isn't (as with mypyc, an `int` may be passed where a `float` is expected).
+//! Inference repeats until nothing changes, since a function's return type
+//! may depend on itself through recursion.
+//!
+//! Anything whose type can't be resolved to one of these falls back to a
+//! boxed [`Value`](super::runtime::Value) of type `object`, whose operations
+//! dispatch at run time.  Lists and dictionaries that may be the same object
+//! share one element type, and one whose contents could be changed through a
+//! boxed reference has `object` elements, so element types hold however the
+//! container is reached.
+//!
+//! Each function is compiled to a tree of Rust closures specialized for the
+//! types of its operands: arithmetic on `int`s, `float`s and `bool`s is done
+//! on unboxed values held in typed local slots (an `int` is a machine integer
+//! until it outgrows one), with no interpreter loop or type dispatch between
+//! them.  Sets, and the exceptions `except` clauses catch, are `object`s.
+//!
+//! Generator functions and `async` functions are compiled to resumable state
+//! machines: their frames outlive the calls that make them, and a statement
+//! containing a `yield` or `await` records where it stopped, so resuming runs
+//! the statements around it again from there.  Coroutines are run by the
+//! event loop of the runtime's `asyncio` module, the one module that can be
+//! imported.
+//!
+//! Variables that nested functions use are kept in cells shared with them,
+//! and hold `object`s unless they are annotated.  A function using
+//! variables of the functions it is in is a closure: a boxed function
+//! holding their cells, called through its instance taking boxed arguments.
+//!
+//! Classes are made at run time, and their instances are `object`s: a
+//! method is compiled once, taking boxed arguments, and attributes are
+//! looked up by name.  A class may have one base class defined in Python,
+//! and its methods may call those of the base through `super()`.
+//!
+//! Not compiled yet: special methods other than `__init__`, decorators,
+//! subclasses of built-in classes, `*args` and `**kwargs` parameters,
+//! `with` statements, imports of modules other than `asyncio`, slice
+//! assignment, and `yield` or `await` anywhere but as a statement, the value
+//! of an assignment or a returned value.
+
+mod builtins;
+mod call;
+mod expr;
+mod generator;
+mod stmt;
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::rc::Rc;
+
+use super::runtime::{self, Eval, Exception, Int, Kind, Resume, Step, Value};
+use super::{Arguments, Expr, ExprKind, Module, Stmt, StmtKind};
+use crate::{Diagnostic, Span};
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+/// How many instances a function may be compiled to before further calls
+/// use the one taking boxed arguments
+const MAX_INSTANCES: usize = 8;
+
+/// How deeply tuples may be nested in the argument types of an instance
+const MAX_DEPTH: usize = 3;
+
+/// How deeply compiled functions may call each other
+const RECURSION_LIMIT: usize = 1000;
+
+// A static type
+#[derive(Debug, Clone, PartialEq)]
+enum Type {
+    /// The type of an expression that doesn't produce a value (it raises an
+    /// exception or never returns), and of variables nothing is assigned to
+    Never,
+    None,
+    Bool,
+    Int,
+    Float,
+    Str,
+    Range,
+    /// List, by container (lists that may be the same object share one)
+    List(usize),
+    /// Dictionary, by container
+    Dict(usize),
+    Tuple(Vec<Type>),
+    /// A function, by definition
+    Function(usize),
+    /// A boxed value of any type
+    Dynamic,
+}
+
+impl Type {
+    fn repr(&self) -> Repr {
+        match self {
+            Type::Int => Repr::Int,
+            Type::Float => Repr::Float,
+            Type::Bool => Repr::Bool,
+            _ => Repr::Value,
+        }
+    }
+
+    fn is_number(&self) -> bool {
+        matches!(self, Type::Int | Type::Float | Type::Bool)
+    }
+}
+
+// How values of a type are represented
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Repr {
+    Int,
+    Float,
+    Bool,
+    Value,
+}
+
+// The element types of lists and dictionaries that may be the same object,
+// merged with union-find
+#[derive(Debug, Clone)]
+struct Container {
+    parent: usize,
+    /// Key type of a dictionary
+    key: Type,
+    /// Item type of a list, value type of a dictionary
+    value: Type,
+}
+
+// A function (or lambda, or the module itself)
+struct Def<'a> {
+    name: &'a str,
+    span: Span,
+    /// The parameter list (the module has none)
+    args: Option<&'a Arguments<'a>>,
+    params: Vec<Param<'a>>,
+    /// Number of positional-only parameters
+    posonly: usize,
+    /// Number of parameters that may be positional
+    positional: usize,
+    body: Body<'a>,
+    /// Annotated return type
+    returns: Option<Type>,
+    /// Names bound in the function
+    locals: HashSet<&'a str>,
+    /// Names declared `global`
+    globals: HashSet<&'a str>,
+    /// Types of the variables annotated in the function
+    annotations: HashMap<&'a str, Type>,
+    /// The function this one is defined in
+    parent: Option<usize>,
+    /// Whether it is a generator function or an `async` function
+    kind: Option<Kind>,
+    /// Its variables that functions nested in it use, kept in cells
+    cells: Vec<&'a str>,
+    /// The variables of enclosing functions it or functions nested in it
+    /// use, whose cells follow its own in a frame
+    free: Vec<&'a str>,
+    /// Whether it is defined in a class body
+    method: bool,
+    /// Whether it is a method using `super()`, which has a cell holding its
+    /// class after the others
+    uses_class: bool,
+}
+
+struct Param<'a> {
+    name: &'a str,
+    annotation: Option<Type>,
+    has_default: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Body<'a> {
+    Stmts(&'a [Stmt<'a>]),
+    Lambda(&'a Expr<'a>),
+}
+
+// What a variable is named by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum VarKey<'a> {
+    Name(&'a str),
+    /// Variable of the comprehension at a span
+    Comprehension(&'a str, Span),
+    /// Default value of a parameter of a function (a global)
+    Default(usize, usize),
+    /// Hidden variable of the construct at a span
+    Temp(Span, u8),
+}
+
+struct Var<'a> {
+    key: VarKey<'a>,
+    ty: Type,
+    /// Whether the type is given by an annotation
+    pinned: bool,
+    /// Index in the slots of its representation
+    slot: usize,
+}
+
+// A function compiled for some argument types
+struct Instance<'a> {
+    def: usize,
+    params: Vec<Type>,
+    ret: Type,
+    vars: Vec<Var<'a>>,
+    var_ids: HashMap<VarKey<'a>, usize>,
+    /// The code from the last time the instance was compiled
+    code: Option<Compiled>,
+    /// The instances its code uses
+    uses: Vec<usize>,
+}
+
+/// A compiled Python module
+pub struct Program {
+    instances: Rc<Vec<Compiled>>,
+    globals: Layout,
+    signatures: Vec<String>,
+}
+
+// A compiled function instance
+struct Compiled {
+    name: String,
+    /// Where the function is defined
+    span: Span,
+    layout: Layout,
+    /// Where each parameter is stored, and its type
+    params: Vec<(Slot, Type)>,
+    param_names: Vec<String>,
+    posonly: usize,
+    positional: usize,
+    /// The global each parameter's default value is in
+    defaults: Vec<Option<Slot>>,
+    ret: Slot,
+    /// Whether calling it makes a generator or coroutine running the body
+    kind: Option<Kind>,
+    /// The cell each parameter nested functions use is copied to
+    captured: Vec<(usize, Slot)>,
+    body: Exec,
+}
+
+// The number of slots of each representation in a frame
+#[derive(Debug, Clone, Copy, Default)]
+struct Layout {
+    ints: usize,
+    floats: usize,
+    bools: usize,
+    values: usize,
+    /// Number of variables, each with a flag for whether it is bound
+    vars: usize,
+    /// Number of cells of its own (a closure's cells are added to them)
+    cells: usize,
+}
+
+// A typed slot of a frame
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    repr: Repr,
+    index: usize,
+}
+
+// The variables of a frame, by representation
+#[derive(Default)]
+struct Slots {
+    ints: Vec<Int>,
+    floats: Vec<f64>,
+    bools: Vec<bool>,
+    values: Vec<Value>,
+    bound: Vec<bool>,
+    cells: Vec<runtime::Cell>,
+}
+
+impl Slots {
+    fn new(layout: &Layout) -> Self {
+        Slots {
+            ints: vec![Int::Small(0); layout.ints],
+            floats: vec![0.0; layout.floats],
+            bools: vec![false; layout.bools],
+            values: vec![Value::None; layout.values],
+            bound: vec![false; layout.vars],
+            cells: (0..layout.cells).map(|_| Rc::default()).collect(),
+        }
+    }
+}
+
+// The state of a running program
+struct Runtime {
+    instances: Rc<Vec<Compiled>>,
+    globals: RefCell<Slots>,
+    output: RefCell<String>,
+    depth: Cell<usize>,
+    /// The exceptions being handled by `except` clauses, innermost last
+    handling: RefCell<Vec<Exception>>,
+}
+
+// The frame of a running function
+struct Frame<'r> {
+    rt: &'r Rc<Runtime>,
+    slots: Slots,
+    /// Where a generator stopped, innermost first: the state of each
+    /// statement the `yield` is in
+    path: Vec<usize>,
+    /// What a generator was resumed with
+    sent: Option<Resume>,
+    /// What a generator yields when it stops
+    yielded: Value,
+}
+
+impl<'r> Frame<'r> {
+    fn new(rt: &'r Rc<Runtime>, slots: Slots) -> Self {
+        Frame { rt, slots, path: Vec::new(), sent: None, yielded: Value::None }
+    }
+}
+
+type Fun<T> = Box<dyn Fn(&mut Frame<'_>) -> Eval<T>>;
+
+// Compiled code producing a value, by representation
+enum Code {
+    Int(Fun<Int>),
+    Float(Fun<f64>),
+    Bool(Fun<bool>),
+    Value(Fun<Value>),
+}
+
+// Compiled code and the type of its value
+struct Typed {
+    ty: Type,
+    code: Code,
+}
+
+// How control leaves a statement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return,
+    /// A generator stops until it is resumed
+    Yield,
+}
+
+type Exec = Box<dyn Fn(&mut Frame<'_>) -> Eval<Flow>>;
+
+// Code storing a boxed value in a variable
+type Store = Box<dyn Fn(&mut Frame<'_>, Value) -> Eval<()>>;
+
+// An unboxed representation held in slots
+trait Unboxed: Clone + 'static {
+    fn slots(slots: &Slots) -> &Vec<Self>;
+    fn slots_mut(slots: &mut Slots) -> &mut Vec<Self>;
+    fn code(fun: Fun<Self>) -> Code;
+    fn fun(code: Code) -> Fun<Self>;
+}
+
+impl Unboxed for Int {
+    fn slots(slots: &Slots) -> &Vec<Self> {
+        &slots.ints
+    }
+
+    fn slots_mut(slots: &mut Slots) -> &mut Vec<Self> {
+        &mut slots.ints
+    }
+
+    fn code(fun: Fun<Self>) -> Code {
+        Code::Int(fun)
+    }
+
+    fn fun(code: Code) -> Fun<Self> {
+        match code {
+            Code::Int(fun) => fun,
+            _ => unreachable!("code is converted before it is unwrapped"),
+        }
+    }
+}
+
+impl Unboxed for f64 {
+    fn slots(slots: &Slots) -> &Vec<Self> {
+        &slots.floats
+    }
+
+    fn slots_mut(slots: &mut Slots) -> &mut Vec<Self> {
+        &mut slots.floats
+    }
+
+    fn code(fun: Fun<Self>) -> Code {
+        Code::Float(fun)
+    }
+
+    fn fun(code: Code) -> Fun<Self> {
+        match code {
+            Code::Float(fun) => fun,
+            _ => unreachable!("code is converted before it is unwrapped"),
+        }
+    }
+}
+
+impl Unboxed for bool {
+    fn slots(slots: &Slots) -> &Vec<Self> {
+        &slots.bools
+    }
+
+    fn slots_mut(slots: &mut Slots) -> &mut Vec<Self> {
+        &mut slots.bools
+    }
+
+    fn code(fun: Fun<Self>) -> Code {
+        Code::Bool(fun)
+    }
+
+    fn fun(code: Code) -> Fun<Self> {
+        match code {
+            Code::Bool(fun) => fun,
+            _ => unreachable!("code is converted before it is unwrapped"),
+        }
+    }
+}
+
+impl Unboxed for Value {
+    fn slots(slots: &Slots) -> &Vec<Self> {
+        &slots.values
+    }
+
+    fn slots_mut(slots: &mut Slots) -> &mut Vec<Self> {
+        &mut slots.values
+    }
+
+    fn code(fun: Fun<Self>) -> Code {
+        Code::Value(fun)
+    }
+
+    fn fun(code: Code) -> Fun<Self> {
+        match code {
+            Code::Value(fun) => fun,
+            _ => unreachable!("code is converted before it is unwrapped"),
+        }
+    }
+}
+
+impl Code {
+    fn repr(&self) -> Repr {
+        match self {
+            Code::Int(_) => Repr::Int,
+            Code::Float(_) => Repr::Float,
+            Code::Bool(_) => Repr::Bool,
+            Code::Value(_) => Repr::Value,
+        }
+    }
+
+    // Run the code and store its value in a slot of `slots`.
+    fn store(&self, frame: &mut Frame<'_>, slots: &mut Slots, index: usize)
+        -> Eval<()>
+    {
+        match self {
+            Code::Int(code) => slots.ints[index] = code(frame)?,
+            Code::Float(code) => slots.floats[index] = code(frame)?,
+            Code::Bool(code) => slots.bools[index] = code(frame)?,
+            Code::Value(code) => slots.values[index] = code(frame)?,
+        }
+        Ok(())
+    }
+
+    // Run the code for its effects.
+    fn run(&self, frame: &mut Frame<'_>) -> Eval<()> {
+        match self {
+            Code::Int(code) => code(frame).map(drop),
+            Code::Float(code) => code(frame).map(drop),
+            Code::Bool(code) => code(frame).map(drop),
+            Code::Value(code) => code(frame).map(drop),
+        }
+    }
+}
+
+/// Compile a module.
+pub fn compile(module: &Module<'_>) -> Result<Program> {
+    let mut compiler = Compiler::new(module)?;
+    compiler.instance(0, Vec::new());
+    let mut rounds = 0;
+    loop {
+        compiler.changed = false;
+        let mut id = 0;
+        while id < compiler.instances.len() {
+            compiler.compile_instance(id)?;
+            id += 1;
+        }
+        if !compiler.changed {
+            break;
+        }
+        rounds += 1;
+        if rounds > 200 {
+            let span = Span::new(0, 0);
+            return Err(Diagnostic::new(span, "type inference didn't converge"));
+        }
+    }
+    Ok(compiler.finish())
+}
+
+// Inference and code generation state for a module
+struct Compiler<'a> {
+    defs: Vec<Def<'a>>,
+    /// The function defined by the statement or lambda at a span
+    def_ids: HashMap<Span, usize>,
+    /// The name of the class defined by the statement at a span, leaked to
+    /// name the class at run time
+    classes: HashMap<Span, &'static str>,
+    containers: Vec<Container>,
+    /// The container made by the construct at a span
+    sites: HashMap<(Span, u8), usize>,
+    instances: Vec<Instance<'a>>,
+    /// The instance of each function that takes boxed arguments
+    generic: HashMap<usize, usize>,
+    /// Whether anything inferred changed in this round
+    changed: bool,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(module: &'a Module<'a>) -> Result<Self> {
+        let mut compiler = Compiler {
+            defs: Vec::new(),
+            def_ids: HashMap::new(),
+            classes: HashMap::new(),
+            containers: Vec::new(),
+            sites: HashMap::new(),
+            instances: Vec::new(),
+            generic: HashMap::new(),
+            changed: false,
+        };
+        let (locals, globals) = bound_names(&module.body);
+        compiler.defs.push(Def {
+            name: "<module>",
+            span: Span::new(0, 0),
+            args: None,
+            params: Vec::new(),
+            posonly: 0,
+            positional: 0,
+            body: Body::Stmts(&module.body),
+            returns: None,
+            locals,
+            globals,
+            annotations: annotations(&module.body),
+            parent: None,
+            kind: None,
+            cells: Vec::new(),
+            free: Vec::new(),
+            method: false,
+            uses_class: false,
+        });
+        compiler.collect_stmts(&module.body, 0)?;
+        // Names functions declare global are module variables.
+        let declared: Vec<_> = compiler.defs[1..]
+            .iter()
+            .flat_map(|def| def.globals.iter().copied())
+            .collect();
+        compiler.defs[0].locals.extend(declared);
+        Ok(compiler)
+    }
+
+    // Find the functions and lambdas defined in statements of `parent`.
+    fn collect_stmts(&mut self, stmts: &'a [Stmt<'a>], parent: usize)
+        -> Result<()>
+    {
+        for stmt in stmts {
+            self.collect_stmt(stmt, parent)?;
+        }
+        Ok(())
+    }
+
+    fn collect_stmt(&mut self, stmt: &'a Stmt<'a>, parent: usize)
+        -> Result<()>
+    {
+        let mut exprs: Vec<&'a Expr<'a>> = Vec::new();
+        match &stmt.kind {
+            StmtKind::FunctionDef(def) | StmtKind::AsyncFunctionDef(def) => {
+                for default in def.args.defaults.iter()
+                    .chain(def.args.kw_defaults.iter().flatten())
+                {
+                    self.collect_expr(default, parent)?;
+                }
+                let (locals, globals) = bound_names(&def.body);
+                let id = self.def(
+                    def.name,
+                    &def.args,
+                    Body::Stmts(&def.body),
+                    def.returns.as_ref(),
+                    locals,
+                    globals,
+                    parent,
+                    stmt.span,
+                )?;
+                if let StmtKind::AsyncFunctionDef(_) = stmt.kind {
+                    self.defs[id].kind = Some(Kind::Coroutine);
+                }
+                return self.collect_stmts(&def.body, id);
+            }
+            StmtKind::ClassDef(class) => {
+                exprs.extend(&class.bases);
+                // Its body runs in the function it is in, and its methods
+                // are nested in that function, not in the class.
+                self.collect_stmts(&class.body, parent)?;
+                for stmt in &class.body {
+                    if let StmtKind::FunctionDef(_)
+                    | StmtKind::AsyncFunctionDef(_) = stmt.kind
+                    {
+                        let id = self.def_ids[&stmt.span];
+                        self.defs[id].method = true;
+                    }
+                }
+                let name = Box::leak(class.name.to_string().into_boxed_str());
+                self.classes.insert(stmt.span, name);
+            }
+            StmtKind::If(test, body, orelse)
+            | StmtKind::While(test, body, orelse) => {
+                self.collect_expr(test, parent)?;
+                self.collect_stmts(body, parent)?;
+                return self.collect_stmts(orelse, parent);
+            }
+            StmtKind::For(each) => {
+                self.collect_expr(&each.iter, parent)?;
+                self.collect_stmts(&each.body, parent)?;
+                return self.collect_stmts(&each.orelse, parent);
+            }
+            StmtKind::Try(body) | StmtKind::TryStar(body) => {
+                self.collect_stmts(&body.body, parent)?;
+                for handler in &body.handlers {
+                    if let Some(ty) = &handler.ty {
+                        self.collect_expr(ty, parent)?;
+                    }
+                    self.collect_stmts(&handler.body, parent)?;
+                }
+                self.collect_stmts(&body.orelse, parent)?;
+                return self.collect_stmts(&body.finalbody, parent);
+            }
+            StmtKind::Raise(exc, cause) => {
+                exprs.extend(exc);
+                exprs.extend(cause);
+            }
+            StmtKind::Return(value) => exprs.extend(value),
+            StmtKind::Assign(targets, value) => {
+                exprs.extend(targets);
+                exprs.push(value);
+            }
+            StmtKind::AugAssign(target, _, value) => {
+                exprs.push(target);
+                exprs.push(value);
+            }
+            StmtKind::AnnAssign(target, _, value, _) => {
+                exprs.push(target);
+                exprs.extend(value);
+            }
+            StmtKind::Delete(targets) => exprs.extend(targets),
+            StmtKind::Assert(test, message) => {
+                exprs.push(test);
+                exprs.extend(message);
+            }
+            StmtKind::Expr(value) => exprs.push(value),
+            _ => {}
+        }
+        for expr in exprs {
+            self.collect_expr(expr, parent)?;
+        }
+        Ok(())
+    }
+
+    fn collect_expr(&mut self, expr: &'a Expr<'a>, parent: usize)
+        -> Result<()>
+    {
+        match &expr.kind {
+            ExprKind::Lambda(args, body) => {
+                for default in children(expr) {
+                    self.collect_expr(default, parent)?;
+                }
+                let mut locals = HashSet::new();
+                expr_bound_names(body, &mut locals);
+                let id = self.def(
+                    "<lambda>",
+                    args,
+                    Body::Lambda(body),
+                    None,
+                    locals,
+                    HashSet::new(),
+                    parent,
+                    expr.span,
+                )?;
+                return self.collect_expr(body, id);
+            }
+            ExprKind::Await(_) => self.awaits(parent, expr.span)?,
+            ExprKind::YieldFrom(_) | ExprKind::Yield(_) => {
+                self.yields(parent, expr.span)?
+            }
+            _ => {}
+        }
+        for child in children(expr) {
+            self.collect_expr(child, parent)?;
+        }
+        Ok(())
+    }
+
+    // Note that a function has a `yield` in it, which makes it a generator
+    // function.
+    fn yields(&mut self, def: usize, span: Span) -> Result<()> {
+        let def = match def {
+            0 => return Err(Diagnostic::new(span, "'yield' outside function")),
+            def => &mut self.defs[def],
+        };
+        match (def.body, def.kind) {
+            (Body::Lambda(_), _) => Err(Diagnostic::new(
+                span,
+                "`yield` in a lambda can't be compiled yet",
+            )),
+            (_, Some(Kind::Coroutine)) => Err(Diagnostic::new(
+                span,
+                "asynchronous generators can't be compiled yet",
+            )),
+            _ => {
+                def.kind = Some(Kind::Generator);
+                Ok(())
+            }
+        }
+    }
+
+    // Check that an `await` is in an `async` function.
+    fn awaits(&self, def: usize, span: Span) -> Result<()> {
+        match (def, self.defs[def].kind) {
+            (_, Some(Kind::Coroutine)) => Ok(()),
+            (0, _) => Err(Diagnostic::new(span, "'await' outside function")),
+            _ => Err(Diagnostic::new(span, "'await' outside async function")),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn def(
+        &mut self,
+        name: &'a str,
+        args: &'a Arguments<'a>,
+        body: Body<'a>,
+        returns: Option<&'a Expr<'a>>,
+        mut locals: HashSet<&'a str>,
+        globals: HashSet<&'a str>,
+        parent: usize,
+        span: Span,
+    ) -> Result<usize> {
+        if let Some(arg) = args.vararg.as_ref().or(args.kwarg.as_ref()) {
+            return Err(Diagnostic::new(
+                arg.span,
+                "`*args` and `**kwargs` parameters can't be compiled yet",
+            ));
+        }
+        let defaults = args.posonlyargs.len() + args.args.len()
+            - args.defaults.len();
+        let mut params = Vec::new();
+        let positional = args.posonlyargs.iter().chain(&args.args);
+        for (i, arg) in positional.enumerate() {
+            params.push(Param {
+                name: arg.arg,
+                annotation: arg.annotation.as_ref().and_then(annotation),
+                has_default: i >= defaults,
+            });
+        }
+        for (arg, default) in args.kwonlyargs.iter().zip(&args.kw_defaults) {
+            params.push(Param {
+                name: arg.arg,
+                annotation: arg.annotation.as_ref().and_then(annotation),
+                has_default: default.is_some(),
+            });
+        }
+        for param in &params {
+            locals.insert(param.name);
+        }
+        for name in &globals {
+            locals.remove(name);
+        }
+        let id = self.defs.len();
+        self.defs.push(Def {
+            name,
+            span,
+            args: Some(args),
+            params,
+            posonly: args.posonlyargs.len(),
+            positional: args.posonlyargs.len() + args.args.len(),
+            body,
+            returns: returns.and_then(annotation),
+            locals,
+            globals,
+            annotations: match body {
+                Body::Stmts(stmts) => annotations(stmts),
+                Body::Lambda(_) => HashMap::new(),
+            },
+            parent: Some(parent),
+            kind: None,
+            cells: Vec::new(),
+            free: Vec::new(),
+            method: false,
+            uses_class: false,
+        });
+        self.def_ids.insert(span, id);
+        Ok(id)
+    }
+
+    // The instance of a function for argument types, made if it doesn't
+    // exist yet.
+    fn instance(&mut self, def: usize, params: Vec<Type>) -> usize {
+        let params: Vec<_> = params.iter().map(|ty| self.resolve(ty)).collect();
+        let found = self.instances.iter().position(|instance| {
+            instance.def == def
+                && instance.params.len() == params.len()
+                && instance.params.iter().zip(&params)
+                    .all(|(a, b)| self.resolve(a) == *b)
+        });
+        if let Some(id) = found {
+            return id;
+        }
+        // Calling a generator function returns the generator.
+        let ret = match self.defs[def].kind {
+            Some(_) => Type::Dynamic,
+            None => self.defs[def].returns.clone().unwrap_or(Type::Never),
+        };
+        let mut instance = Instance {
+            def,
+            params: params.clone(),
+            ret,
+            vars: Vec::new(),
+            var_ids: HashMap::new(),
+            code: None,
+            uses: Vec::new(),
+        };
+        for (param, ty) in self.defs[def].params.iter().zip(params) {
+            let key = VarKey::Name(param.name);
+            let pinned = param.annotation.is_some();
+            instance.var_ids.insert(key, instance.vars.len());
+            instance.vars.push(Var { key, ty, pinned, slot: 0 });
+        }
+        self.instances.push(instance);
+        self.changed = true;
+        self.instances.len() - 1
+    }
+
+    // The instance of a function that takes boxed arguments.
+    fn generic(&mut self, def: usize) -> usize {
+        if let Some(&id) = self.generic.get(&def) {
+            return id;
+        }
+        let params = self.defs[def]
+            .params
+            .iter()
+            .map(|param| param.annotation.clone().unwrap_or(Type::Dynamic))
+            .collect();
+        let id = self.instance(def, params);
+        self.generic.insert(def, id);
+        id
+    }
+
+    // The instance of a function for a call with arguments of some types.
+    // Instances are told apart by the shapes of their argument types: lists
+    // and dictionaries passed to one share its parameter's container.
+    fn callee(&mut self, def: usize, args: &[Type]) -> usize {
+        let annotations: Vec<_> = self.defs[def]
+            .params
+            .iter()
+            .map(|param| param.annotation.clone())
+            .collect();
+        let params: Vec<_> = annotations.into_iter()
+            .zip(args)
+            .map(|(annotation, arg)| match annotation {
+                Some(annotation) => annotation,
+                None => self.limit(arg, 0),
+            })
+            .collect();
+        let found = self.instances.iter().position(|instance| {
+            instance.def == def
+                && instance.params.len() == params.len()
+                && instance.params.iter().zip(&params)
+                    .all(|(a, b)| self.same_shape(a, b))
+        });
+        if let Some(id) = found {
+            return id;
+        }
+        let count = self.instances.iter()
+            .filter(|instance| instance.def == def)
+            .count();
+        match count >= MAX_INSTANCES {
+            true => self.generic(def),
+            false => self.instance(def, params),
+        }
+    }
+
+    // Whether two types are the same but for which containers they are.
+    fn same_shape(&self, a: &Type, b: &Type) -> bool {
+        match (a, b) {
+            (Type::List(_), Type::List(_)) | (Type::Dict(_), Type::Dict(_)) => {
+                true
+            }
+            (Type::Tuple(a), Type::Tuple(b)) => {
+                a.len() == b.len()
+                    && a.iter().zip(b).all(|(a, b)| self.same_shape(a, b))
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    // A type with tuples nested too deeply replaced by `object`.
+    fn limit(&mut self, ty: &Type, depth: usize) -> Type {
+        match self.resolve(ty) {
+            Type::Tuple(items) if depth >= MAX_DEPTH => {
+                self.escape(&Type::Tuple(items));
+                Type::Dynamic
+            }
+            Type::Tuple(items) => Type::Tuple(
+                items.iter().map(|item| self.limit(item, depth + 1)).collect(),
+            ),
+            ty => ty,
+        }
+    }
+
+    // The container made by a construct.
+    fn site(&mut self, span: Span, tag: u8) -> usize {
+        if let Some(&id) = self.sites.get(&(span, tag)) {
+            return self.find(id);
+        }
+        let id = self.containers.len();
+        self.containers.push(Container {
+            parent: id,
+            key: Type::Never,
+            value: Type::Never,
+        });
+        self.sites.insert((span, tag), id);
+        id
+    }
+
+    fn find(&self, mut id: usize) -> usize {
+        while self.containers[id].parent != id {
+            id = self.containers[id].parent;
+        }
+        id
+    }
+
+    // A type with its containers replaced by their representatives.
+    fn resolve(&self, ty: &Type) -> Type {
+        match ty {
+            Type::List(id) => Type::List(self.find(*id)),
+            Type::Dict(id) => Type::Dict(self.find(*id)),
+            Type::Tuple(items) => {
+                Type::Tuple(items.iter().map(|ty| self.resolve(ty)).collect())
+            }
+            ty => ty.clone(),
+        }
+    }
+
+    // The item type of a list or value type of a dictionary.
+    fn value(&self, id: usize) -> Type {
+        let value = self.containers[self.find(id)].value.clone();
+        self.resolve(&value)
+    }
+
+    // The key type of a dictionary.
+    fn key(&self, id: usize) -> Type {
+        let key = self.containers[self.find(id)].key.clone();
+        self.resolve(&key)
+    }
+
+    // Add a type to what a container's items (or values) may be.
+    fn store_value(&mut self, id: usize, ty: &Type) {
+        let id = self.find(id);
+        let old = self.containers[id].value.clone();
+        let new = self.join(&old, ty);
+        self.set_value(id, new);
+    }
+
+    // Add a type to what a dictionary's keys may be.
+    fn store_key(&mut self, id: usize, ty: &Type) {
+        let id = self.find(id);
+        let old = self.containers[id].key.clone();
+        let new = self.join(&old, ty);
+        let id = self.find(id);
+        if self.resolve(&self.containers[id].key) != new {
+            self.containers[id].key = new;
+            self.changed = true;
+        }
+    }
+
+    fn set_value(&mut self, id: usize, ty: Type) {
+        let id = self.find(id);
+        if self.resolve(&self.containers[id].value) != ty {
+            self.containers[id].value = ty;
+            self.changed = true;
+        }
+    }
+
+    // The smallest type that includes both types.
+    fn join(&mut self, a: &Type, b: &Type) -> Type {
+        match (self.resolve(a), self.resolve(b)) {
+            (Type::Never, ty) | (ty, Type::Never) => ty,
+            (a, b) if a == b => a,
+            (Type::List(a), Type::List(b)) => Type::List(self.union(a, b)),
+            (Type::Dict(a), Type::Dict(b)) => Type::Dict(self.union(a, b)),
+            (Type::Tuple(a), Type::Tuple(b)) if a.len() == b.len() => {
+                Type::Tuple(
+                    a.iter().zip(&b).map(|(a, b)| self.join(a, b)).collect(),
+                )
+            }
+            (a, b) => {
+                self.escape(&a);
+                self.escape(&b);
+                Type::Dynamic
+            }
+        }
+    }
+
+    // Merge two containers that may be the same object.
+    fn union(&mut self, a: usize, b: usize) -> usize {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return a;
+        }
+        self.containers[b].parent = a;
+        self.changed = true;
+        let (key, value) = {
+            let b = &self.containers[b];
+            (b.key.clone(), b.value.clone())
+        };
+        self.store_key(a, &key);
+        self.store_value(a, &value);
+        self.find(a)
+    }
+
+    // Note that a value of a type may be reached through a boxed reference:
+    // the items of its containers may then be anything, and its functions
+    // may be called with boxed arguments.
+    fn escape(&mut self, ty: &Type) {
+        match self.resolve(ty) {
+            Type::List(id) | Type::Dict(id) => {
+                let container = &mut self.containers[id];
+                let key = std::mem::replace(&mut container.key, Type::Dynamic);
+                let value =
+                    std::mem::replace(&mut container.value, Type::Dynamic);
+                if key != Type::Dynamic || value != Type::Dynamic {
+                    self.changed = true;
+                    self.escape(&key);
+                    self.escape(&value);
+                }
+            }
+            Type::Tuple(items) => {
+                for item in &items {
+                    self.escape(item);
+                }
+            }
+            Type::Function(def) => {
+                self.generic(def);
+            }
+            _ => {}
+        }
+    }
+
+    // The name of a type, like `list[int]`.
+    fn type_name(&self, ty: &Type) -> String {
+        self.type_name_at(ty, 0)
+    }
+
+    fn type_name_at(&self, ty: &Type, depth: usize) -> String {
+        if depth > 4 {
+            return "...".to_string();
+        }
+        let name = |ty: &Type| self.type_name_at(ty, depth + 1);
+        match self.resolve(ty) {
+            Type::Never => "NoReturn".to_string(),
+            Type::None => "None".to_string(),
+            Type::Bool => "bool".to_string(),
+            Type::Int => "int".to_string(),
+            Type::Float => "float".to_string(),
+            Type::Str => "str".to_string(),
+            Type::Range => "range".to_string(),
+            Type::List(id) => format!("list[{}]", name(&self.value(id))),
+            Type::Dict(id) => format!(
+                "dict[{}, {}]",
+                name(&self.key(id)),
+                name(&self.value(id)),
+            ),
+            Type::Tuple(items) if items.is_empty() => "tuple[()]".to_string(),
+            Type::Tuple(items) => {
+                let items: Vec<_> = items.iter().map(name).collect();
+                format!("tuple[{}]", items.join(", "))
+            }
+            Type::Function(def) => format!("<function {}>", self.defs[def].name),
+            Type::Dynamic => "object".to_string(),
+        }
+    }
+
+    // Infer types in an instance and compile it with what is known so far.
+    fn compile_instance(&mut self, id: usize) -> Result<()> {
+        let mut walker = Walker::new(self, id);
+        let body = walker.function_body()?;
+        let code = walker.finish(body);
+        self.instances[id].code = Some(code);
+        Ok(())
+    }
+
+    fn finish(mut self) -> Program {
+        // Only list the instances the module uses.
+        let mut used = vec![false; self.instances.len()];
+        let mut stack = vec![0];
+        while let Some(id) = stack.pop() {
+            if !std::mem::replace(&mut used[id], true) {
+                stack.extend(&self.instances[id].uses);
+            }
+        }
+        let mut signatures = Vec::new();
+        for (id, instance) in self.instances.iter().enumerate().skip(1) {
+            if !used[id] {
+                continue;
+            }
+            let def = &self.defs[instance.def];
+            let mut signature = format!("{}(", def.name);
+            let params = def.params.iter()
+                .zip(instance.vars.iter().map(|var| &var.ty));
+            for (i, (param, ty)) in params.enumerate() {
+                if i > 0 {
+                    signature.push_str(", ");
+                }
+                let _ = write!(signature, "{}: {}", param.name, self.type_name(ty));
+            }
+            let _ = write!(signature, ") -> {}", self.type_name(&instance.ret));
+            signatures.push(signature);
+        }
+        let globals = self.instances[0].code.as_ref().unwrap().layout;
+        let instances = self.instances
+            .iter_mut()
+            .map(|instance| instance.code.take().unwrap())
+            .collect();
+        Program { instances: Rc::new(instances), globals, signatures }
+    }
+}
+
+// The names assigned in a function body (not counting nested functions and
+// comprehensions, and names declared `nonlocal`), and the names it declares
+// global.
+fn bound_names<'a>(body: &'a [Stmt<'a>])
+    -> (HashSet<&'a str>, HashSet<&'a str>)
+{
+    let mut locals = HashSet::new();
+    let mut declared = (HashSet::new(), HashSet::new());
+    stmts_bound_names(body, &mut locals, &mut declared);
+    let (globals, nonlocals) = declared;
+    for name in globals.iter().chain(&nonlocals) {
+        locals.remove(name);
+    }
+    (locals, globals)
+}
+
+// The names declared `global` and `nonlocal` in a function body
+type Declared<'a> = (HashSet<&'a str>, HashSet<&'a str>);
+
+fn stmts_bound_names<'a>(
+    stmts: &'a [Stmt<'a>],
+    locals: &mut HashSet<&'a str>,
+    declared: &mut Declared<'a>,
+) {
+    for stmt in stmts {
+        match &stmt.kind {
+            StmtKind::FunctionDef(def) | StmtKind::AsyncFunctionDef(def) => {
+                locals.insert(def.name);
+            }
+            StmtKind::ClassDef(def) => {
+                locals.insert(def.name);
+            }
+            StmtKind::Assign(targets, value) => {
+                for target in targets {
+                    expr_bound_names(target, locals);
+                }
+                expr_bound_names(value, locals);
+            }
+            StmtKind::AugAssign(target, _, value)
+            | StmtKind::AnnAssign(target, _, Some(value), _) => {
+                expr_bound_names(target, locals);
+                expr_bound_names(value, locals);
+            }
+            StmtKind::AnnAssign(target, ..) | StmtKind::Expr(target) => {
+                expr_bound_names(target, locals);
+            }
+            StmtKind::Delete(targets) => {
+                for target in targets {
+                    expr_bound_names(target, locals);
+                }
+            }
+            StmtKind::For(each) | StmtKind::AsyncFor(each) => {
+                expr_bound_names(&each.target, locals);
+                expr_bound_names(&each.iter, locals);
+                stmts_bound_names(&each.body, locals, declared);
+                stmts_bound_names(&each.orelse, locals, declared);
+            }
+            StmtKind::While(test, body, orelse)
+            | StmtKind::If(test, body, orelse) => {
+                expr_bound_names(test, locals);
+                stmts_bound_names(body, locals, declared);
+                stmts_bound_names(orelse, locals, declared);
+            }
+            StmtKind::With(items, body) | StmtKind::AsyncWith(items, body) => {
+                for item in items {
+                    if let Some(vars) = &item.optional_vars {
+                        expr_bound_names(vars, locals);
+                    }
+                }
+                stmts_bound_names(body, locals, declared);
+            }
+            StmtKind::Try(body) | StmtKind::TryStar(body) => {
+                stmts_bound_names(&body.body, locals, declared);
+                for handler in &body.handlers {
+                    locals.extend(handler.name);
+                    stmts_bound_names(&handler.body, locals, declared);
+                }
+                stmts_bound_names(&body.orelse, locals, declared);
+                stmts_bound_names(&body.finalbody, locals, declared);
+            }
+            StmtKind::Return(Some(value)) => expr_bound_names(value, locals),
+            StmtKind::Import(aliases) | StmtKind::ImportFrom(_, aliases, _) => {
+                for alias in aliases {
+                    let name = alias.asname.unwrap_or_else(|| {
+                        // `import a.b` binds `a`; leaked from the alias.
+                        let name = alias.name.split('.').next().unwrap();
+                        Box::leak(name.to_string().into_boxed_str())
+                    });
+                    locals.insert(name);
+                }
+            }
+            StmtKind::Global(names) => declared.0.extend(names),
+            StmtKind::Nonlocal(names) => declared.1.extend(names),
+            _ => {}
+        }
+    }
+}
+
+// The names an expression assigns, as a target or with `:=`.
+fn expr_bound_names<'a>(expr: &'a Expr<'a>, locals: &mut HashSet<&'a str>) {
+    match &expr.kind {
+        ExprKind::Name(name, ctx) if *ctx != super::ExprContext::Load => {
+            locals.insert(name);
+        }
+        ExprKind::NamedExpr(target, value) => {
+            expr_bound_names(target, locals);
+            expr_bound_names(value, locals);
+        }
+        ExprKind::Tuple(items, _) | ExprKind::List(items, _) => {
+            for item in items {
+                expr_bound_names(item, locals);
+            }
+        }
+        ExprKind::Starred(value, _) => expr_bound_names(value, locals),
+        _ => {}
+    }
+}
+
+// The expressions directly in an expression, which for a lambda are the
+// default values of its parameters.
+fn children<'a>(expr: &'a Expr<'a>) -> Vec<&'a Expr<'a>> {
+    let mut children: Vec<&'a Expr<'a>> = Vec::new();
+    match &expr.kind {
+        ExprKind::Lambda(args, _) => {
+            children.extend(&args.defaults);
+            children.extend(args.kw_defaults.iter().flatten());
+        }
+        ExprKind::BoolOp(_, values) => children.extend(values),
+        ExprKind::NamedExpr(target, value) => {
+            children.push(target);
+            children.push(value);
+        }
+        ExprKind::BinOp(left, _, right) => {
+            children.push(left);
+            children.push(right);
+        }
+        ExprKind::Yield(value) => children.extend(value.as_deref()),
+        ExprKind::UnaryOp(_, value)
+        | ExprKind::Await(value)
+        | ExprKind::YieldFrom(value)
+        | ExprKind::Starred(value, _)
+        | ExprKind::Attribute(value, _, _) => children.push(value),
+        ExprKind::IfExp(test, body, orelse) => {
+            children.extend([&**test, &**body, &**orelse]);
+        }
+        ExprKind::Dict(keys, values) => {
+            children.extend(keys.iter().flatten());
+            children.extend(values);
+        }
+        ExprKind::Set(items)
+        | ExprKind::List(items, _)
+        | ExprKind::Tuple(items, _)
+        | ExprKind::JoinedStr(items) => children.extend(items),
+        ExprKind::ListComp(elt, generators)
+        | ExprKind::SetComp(elt, generators)
+        | ExprKind::GeneratorExp(elt, generators) => {
+            children.push(elt);
+            for generator in generators {
+                children.push(&generator.target);
+                children.push(&generator.iter);
+                children.extend(&generator.ifs);
+            }
+        }
+        ExprKind::DictComp(key, value, generators) => {
+            children.push(key);
+            children.push(value);
+            for generator in generators {
+                children.push(&generator.target);
+                children.push(&generator.iter);
+                children.extend(&generator.ifs);
+            }
+        }
+        ExprKind::Compare(left, _, rights) => {
+            children.push(left);
+            children.extend(rights);
+        }
+        ExprKind::Call(func, args, keywords) => {
+            children.push(func);
+            children.extend(args);
+            children.extend(keywords.iter().map(|keyword| &keyword.value));
+        }
+        ExprKind::FormattedValue(value, _, spec) => {
+            children.push(value);
+            children.extend(spec.as_deref());
+        }
+        ExprKind::Subscript(value, index, _) => {
+            children.push(value);
+            children.push(index);
+        }
+        ExprKind::Slice(lower, upper, step) => {
+            children.extend(lower.as_deref());
+            children.extend(upper.as_deref());
+            children.extend(step.as_deref());
+        }
+        ExprKind::Constant(_) | ExprKind::Name(..) => {}
+    }
+    children
+}
+
+// The type an annotation pins a value to, if it is one the compiler trusts.
+fn annotation(expr: &Expr<'_>) -> Option<Type> {
+    match &expr.kind {
+        ExprKind::Name("int", _) => Some(Type::Int),
+        ExprKind::Name("float", _) => Some(Type::Float),
+        ExprKind::Name("bool", _) => Some(Type::Bool),
+        ExprKind::Name("str", _) => Some(Type::Str),
+        ExprKind::Name("object", _) => Some(Type::Dynamic),
+        ExprKind::Constant(super::Constant::None) => Some(Type::None),
+        _ => None,
+    }
+}
+
+// The trusted annotations of names in `annotated: type` statements of a
+// function body (not counting nested functions).
+fn annotations<'a>(stmts: &'a [Stmt<'a>]) -> HashMap<&'a str, Type> {
+    let mut annotations = HashMap::new();
+    for stmt in stmts {
+        match &stmt.kind {
+            StmtKind::AnnAssign(target, ty, _, _) => {
+                if let (ExprKind::Name(name, _), Some(ty)) =
+                    (&target.kind, annotation(ty))
+                {
+                    annotations.insert(*name, ty);
+                }
+            }
+            StmtKind::If(_, body, orelse) | StmtKind::While(_, body, orelse) => {
+                annotations.extend(self::annotations(body));
+                annotations.extend(self::annotations(orelse));
+            }
+            StmtKind::For(each) => {
+                annotations.extend(self::annotations(&each.body));
+                annotations.extend(self::annotations(&each.orelse));
+            }
+            _ => {}
+        }
+    }
+    annotations
+}
+
+// Infers types in an instance and generates its code
+struct Walker<'c, 'a> {
+    c: &'c mut Compiler<'a>,
+    id: usize,
+    def: usize,
+    /// Variables of the comprehensions being compiled, innermost last
+    scopes: Vec<Vec<(&'a str, usize)>>,
+    /// The variables of the instance that are definitely bound here
+    bound: HashSet<usize>,
+    /// Whether the statement being compiled can be reached
+    reachable: bool,
+    /// For each loop being compiled, whether it may be broken out of
+    breaks: Vec<bool>,
+    layout: Layout,
+    ret: Slot,
+    uses: Vec<usize>,
+}
+
+impl<'c, 'a> Walker<'c, 'a> {
+    fn new(c: &'c mut Compiler<'a>, id: usize) -> Self {
+        let def = c.instances[id].def;
+        let mut layout = Layout::default();
+        // The return value has the first slot of its representation.
+        let ret_repr = c.instances[id].ret.repr();
+        let ret = Slot { repr: ret_repr, index: 0 };
+        if id != 0 {
+            *count(&mut layout, ret_repr) += 1;
+        }
+        let mut bound = HashSet::new();
+        let instance = &mut c.instances[id];
+        for (i, var) in instance.vars.iter_mut().enumerate() {
+            var.slot = *count(&mut layout, var.ty.repr());
+            *count(&mut layout, var.ty.repr()) += 1;
+            if i < c.defs[def].params.len() {
+                bound.insert(i);
+            }
+        }
+        layout.vars = instance.vars.len();
+        Walker {
+            c,
+            id,
+            def,
+            scopes: Vec::new(),
+            bound,
+            reachable: true,
+            breaks: Vec::new(),
+            layout,
+            ret,
+            uses: Vec::new(),
+        }
+    }
+
+    fn finish(self, body: Exec) -> Compiled {
+        let def = &self.c.defs[self.def];
+        let instance = &self.c.instances[self.id];
+        let params: Vec<_> = instance.vars[..def.params.len()]
+            .iter()
+            .map(|var| {
+                let slot = Slot { repr: var.ty.repr(), index: var.slot };
+                (slot, var.ty.clone())
+            })
+            .collect();
+        let globals = &self.c.instances[0];
+        let defaults = (0..def.params.len())
+            .map(|i| {
+                let id = globals.var_ids.get(&VarKey::Default(self.def, i))?;
+                let var = &globals.vars[*id];
+                Some(Slot { repr: var.ty.repr(), index: var.slot })
+            })
+            .collect();
+        let mut layout = self.layout;
+        layout.vars = instance.vars.len();
+        layout.cells = def.cells.len();
+        let captured = params.iter()
+            .zip(&def.params)
+            .filter_map(|((slot, _), param)| {
+                let cell = def.cells.iter().position(|name| *name == param.name)?;
+                Some((cell, *slot))
+            })
+            .collect();
+        let code = Compiled {
+            name: def.name.to_string(),
+            span: def.span,
+            layout,
+            params,
+            param_names: def.params.iter().map(|p| p.name.to_string()).collect(),
+            posonly: def.posonly,
+            positional: def.positional,
+            defaults,
+            ret: self.ret,
+            kind: def.kind,
+            captured,
+            body,
+        };
+        self.c.instances[self.id].uses = self.uses;
+        code
+    }
+
+    // The variable of an instance named by a key, made if it doesn't exist.
+    fn var(&mut self, owner: usize, key: VarKey<'a>) -> usize {
+        if let Some(&id) = self.c.instances[owner].var_ids.get(&key) {
+            return id;
+        }
+        let instance = &mut self.c.instances[owner];
+        let id = instance.vars.len();
+        // Give it a slot, so code using it can be generated this round.
+        let slot = match owner == self.id {
+            true => {
+                let slot = *count(&mut self.layout, Repr::Value);
+                *count(&mut self.layout, Repr::Value) += 1;
+                slot
+            }
+            false => 0,
+        };
+        // A name annotated in its function has the annotated type.
+        let annotated = match key {
+            VarKey::Name(name) => {
+                let def = instance.def;
+                self.c.defs[def].annotations.get(name).cloned()
+            }
+            _ => None,
+        };
+        let instance = &mut self.c.instances[owner];
+        let var = match annotated {
+            Some(ty) => Var { key, ty, pinned: true, slot },
+            None => Var { key, ty: Type::Never, pinned: false, slot },
+        };
+        instance.vars.push(var);
+        instance.var_ids.insert(key, id);
+        if owner == self.id {
+            self.layout.vars = id + 1;
+        }
+        self.c.changed = true;
+        id
+    }
+}
+
+fn count(layout: &mut Layout, repr: Repr) -> &mut usize {
+    match repr {
+        Repr::Int => &mut layout.ints,
+        Repr::Float => &mut layout.floats,
+        Repr::Bool => &mut layout.bools,
+        Repr::Value => &mut layout.values,
+    }
+}
+
+impl Program {
+    /// The signature of each compiled function instance, with the types
+    /// inferred for its parameters and result, like `f(x: int) -> int`.
+    pub fn signatures(&self) -> &[String] {
+        &self.signatures
+    }
+
+    /// Run the module, returning what it prints, or the exception it raises
+    /// (with its traceback) with what it printed before.
+    ///
+    /// Each level of Python calls takes several frames of the native stack,
+    /// so deeply recursive programs need a thread with a large stack.
+    #[allow(clippy::result_large_err)]
+    pub fn run(&self) -> std::result::Result<String, (Exception, String)> {
+        let rt = Rc::new(Runtime {
+            instances: self.instances.clone(),
+            globals: RefCell::new(Slots::new(&self.globals)),
+            output: RefCell::new(String::new()),
+            depth: Cell::new(0),
+            handling: RefCell::new(Vec::new()),
+        });
+        let module = &self.instances[0];
+        let mut frame = Frame::new(&rt, Slots::new(&module.layout));
+        let result = (module.body)(&mut frame);
+        // Generators in globals refer to the runtime.
+        let globals = rt.globals.take();
+        drop(globals);
+        match result {
+            Ok(_) => Ok(rt.output.take()),
+            Err(exception) => {
+                let exception = exception.leave(&module.name, module.span);
+                Err((exception, rt.output.take()))
+            }
+        }
+    }
+}
+
+impl Runtime {
+    // Run a compiled instance with its arguments stored in `slots`, or make
+    // the generator that runs it.
+    fn enter(self: &Rc<Self>, id: usize, mut slots: Slots) -> Eval<Slots> {
+        let compiled = &self.instances[id];
+        for bound in &mut slots.bound[..compiled.params.len()] {
+            *bound = true;
+        }
+        for &(cell, slot) in &compiled.captured {
+            *slots.cells[cell].borrow_mut() = Some(read_boxed(&slots, slot));
+        }
+        if let Some(kind) = compiled.kind {
+            let body = Suspended { rt: self.clone(), id, slots, path: Vec::new() };
+            let generator =
+                runtime::Generator::new(&compiled.name, kind, Box::new(body));
+            let mut slots = Slots::new(&Layout { values: 1, ..Layout::default() });
+            slots.values[compiled.ret.index] = Value::generator(generator);
+            return Ok(slots);
+        }
+        let depth = self.enter_call()?;
+        let mut frame = Frame::new(self, slots);
+        let result = (compiled.body)(&mut frame);
+        self.depth.set(depth);
+        match result {
+            Ok(_) => Ok(frame.slots),
+            Err(exception) => Err(exception.leave(&compiled.name, compiled.span)),
+        }
+    }
+
+    // Count a call made, returning the depth before it, or raise
+    // `RecursionError` if there are too many.
+    fn enter_call(&self) -> Eval<usize> {
+        let depth = self.depth.get();
+        if depth >= RECURSION_LIMIT {
+            return Err(Exception::new(
+                "RecursionError",
+                "maximum recursion depth exceeded",
+            ));
+        }
+        self.depth.set(depth + 1);
+        Ok(depth)
+    }
+
+    // Call a boxed value.
+    fn call(
+        self: &Rc<Self>,
+        function: &Value,
+        args: Vec<Value>,
+        keywords: Vec<(Rc<str>, Value)>,
+    ) -> Eval<Value> {
+        let (id, closure) = match function {
+            Value::Function(function) => (function.id, &function.closure),
+            Value::Builtin(name) if keywords.is_empty() => {
+                return runtime::call_builtin(name, args)
+            }
+            Value::Class(class) if keywords.is_empty() => {
+                return Ok(runtime::call_class(class, args))
+            }
+            Value::Builtin(name) | Value::Class(name) => {
+                return Err(Exception::new("TypeError", format!(
+                    "{}() takes no keyword arguments",
+                    name,
+                )))
+            }
+            Value::Type(class) => return self.construct(class, args, keywords),
+            Value::Method(method) => {
+                let mut args = args;
+                args.insert(0, method.receiver.clone());
+                return self.call(&method.function, args, keywords);
+            }
+            _ => {
+                return Err(Exception::new("TypeError", format!(
+                    "'{}' object is not callable",
+                    function.type_name(),
+                )))
+            }
+        };
+        let compiled = &self.instances[id];
+        let args = bind(
+            &compiled.name,
+            &compiled.param_names,
+            compiled.posonly,
+            compiled.positional,
+            &compiled.defaults.iter().map(Option::is_some).collect::<Vec<_>>(),
+            args,
+            keywords,
+        )
+        .map_err(|message| Exception::new("TypeError", message))?;
+        let mut slots = Slots::new(&compiled.layout);
+        slots.cells.extend(closure.iter().cloned());
+        for (i, arg) in args.into_iter().enumerate() {
+            let arg = match arg {
+                Some(arg) => arg,
+                None => {
+                    let slot = compiled.defaults[i].unwrap();
+                    read_boxed(&self.globals.borrow(), slot)
+                }
+            };
+            let (slot, ty) = &compiled.params[i];
+            unbox_into(arg, ty, &mut slots, *slot)?;
+        }
+        let slots = self.enter(id, slots)?;
+        Ok(read_boxed(&slots, compiled.ret))
+    }
+
+    // Call a class, making an instance and initializing it with its
+    // `__init__` method.
+    fn construct(
+        self: &Rc<Self>,
+        class: &Rc<runtime::Class>,
+        mut args: Vec<Value>,
+        keywords: Vec<(Rc<str>, Value)>,
+    ) -> Eval<Value> {
+        let object = Value::object(class.clone());
+        match class.lookup("__init__") {
+            Some(init) => {
+                args.insert(0, object.clone());
+                let result = self.call(&init, args, keywords)?;
+                if !matches!(result, Value::None) {
+                    return Err(Exception::new("TypeError", format!(
+                        "__init__() should return None, not '{}'",
+                        result.type_name(),
+                    )));
+                }
+            }
+            None if !args.is_empty() || !keywords.is_empty() => {
+                return Err(Exception::new("TypeError", format!(
+                    "{}() takes no arguments",
+                    class.name,
+                )))
+            }
+            None => {}
+        }
+        Ok(object)
+    }
+}
+
+// The frame of a generator or coroutine, and where it stopped
+struct Suspended {
+    rt: Rc<Runtime>,
+    id: usize,
+    slots: Slots,
+    path: Vec<usize>,
+}
+
+impl runtime::Body for Suspended {
+    fn resume(&mut self, resume: Resume) -> Eval<Step> {
+        let rt = self.rt.clone();
+        let compiled = &rt.instances[self.id];
+        if self.path.is_empty() {
+            // It hasn't started, so there is no `yield` to raise it at.
+            if let Resume::Throw(exception) = resume {
+                return Err(exception);
+            }
+        }
+        let depth = rt.enter_call()?;
+        let mut frame = Frame::new(&rt, std::mem::take(&mut self.slots));
+        frame.path = std::mem::take(&mut self.path);
+        frame.sent = Some(resume);
+        let result = (compiled.body)(&mut frame);
+        rt.depth.set(depth);
+        match result {
+            Ok(Flow::Yield) => {
+                self.slots = frame.slots;
+                self.path = frame.path;
+                Ok(Step::Yield(frame.yielded))
+            }
+            Ok(_) => Ok(Step::Return(read_boxed(&frame.slots, compiled.ret))),
+            Err(exception) => Err(exception.leave(&compiled.name, compiled.span)),
+        }
+    }
+
+    fn visit(&self, f: &mut dyn FnMut(&Value)) {
+        self.slots.values.iter().for_each(f);
+    }
+
+    fn clear(&mut self) -> Vec<Value> {
+        std::mem::take(&mut self.slots.values)
+    }
+}
+
+// Read a slot as a boxed value.
+fn read_boxed(slots: &Slots, slot: Slot) -> Value {
+    match slot.repr {
+        Repr::Int => Value::Int(slots.ints[slot.index].clone()),
+        Repr::Float => Value::Float(slots.floats[slot.index]),
+        Repr::Bool => Value::Bool(slots.bools[slot.index]),
+        Repr::Value => slots.values[slot.index].clone(),
+    }
+}
+
+// Store a boxed value in a slot for a value of type `ty`, raising
+// `TypeError` if it isn't of that type.
+fn unbox_into(value: Value, ty: &Type, slots: &mut Slots, slot: Slot)
+    -> Eval<()>
+{
+    match slot.repr {
+        Repr::Int => slots.ints[slot.index] = unbox_int(&value)?,
+        Repr::Float => slots.floats[slot.index] = unbox_float(&value)?,
+        Repr::Bool => slots.bools[slot.index] = unbox_bool(&value)?,
+        Repr::Value => slots.values[slot.index] = check(value, ty)?,
+    }
+    Ok(())
+}
+
+fn expected<T>(what: &str, value: &Value) -> Eval<T> {
+    Err(Exception::new("TypeError", format!(
+        "{} object expected; got {}",
+        what,
+        value.type_name(),
+    )))
+}
+
+fn unbox_int(value: &Value) -> Eval<Int> {
+    match value {
+        Value::Int(value) => Ok(value.clone()),
+        Value::Bool(value) => Ok(Int::Small(*value as i64)),
+        _ => expected("int", value),
+    }
+}
+
+fn unbox_float(value: &Value) -> Eval<f64> {
+    match value {
+        Value::Float(value) => Ok(*value),
+        Value::Int(value) => value.to_f64(),
+        Value::Bool(value) => Ok(*value as i64 as f64),
+        _ => expected("float", value),
+    }
+}
+
+fn unbox_bool(value: &Value) -> Eval<bool> {
+    match *value {
+        Value::Bool(value) => Ok(value),
+        _ => expected("bool", value),
+    }
+}
+
+// Check that a boxed value is of a type represented by boxed values.
+fn check(value: Value, ty: &Type) -> Eval<Value> {
+    let ok = match (ty, &value) {
+        (Type::Str, Value::Str(_)) | (Type::None, Value::None) => true,
+        (Type::Str, _) | (Type::None, _) => false,
+        _ => true,
+    };
+    match ok {
+        true => Ok(value),
+        false => expected(if *ty == Type::Str { "str" } else { "None" }, &value),
+    }
+}
+
+// Match the arguments of a call to the parameters of a function, the way
+// Python does.  Parameters that weren't given an argument and have a default
+// are `None`.
+fn bind<T>(
+    name: &str,
+    params: &[String],
+    posonly: usize,
+    positional: usize,
+    has_default: &[bool],
+    args: Vec<T>,
+    keywords: Vec<(Rc<str>, T)>,
+) -> std::result::Result<Vec<Option<T>>, String> {
+    let mut bound: Vec<Option<T>> = params.iter().map(|_| None).collect();
+    let given = args.len();
+    if given > positional {
+        let defaults = has_default[..positional].iter().filter(|d| **d).count();
+        let takes = match defaults {
+            0 => format!("{}", positional),
+            _ => format!("from {} to {}", positional - defaults, positional),
+        };
+        return Err(format!(
+            "{}() takes {} positional argument{} but {} {} given",
+            name,
+            takes,
+            if positional == 1 && defaults == 0 { "" } else { "s" },
+            given,
+            if given == 1 { "was" } else { "were" },
+        ));
+    }
+    for (slot, arg) in bound.iter_mut().zip(args) {
+        *slot = Some(arg);
+    }
+    let mut posonly_names = Vec::new();
+    for (keyword, arg) in keywords {
+        let i = params.iter().position(|param| **param == *keyword);
+        match i {
+            Some(i) if i < posonly => posonly_names.push(keyword.to_string()),
+            Some(i) if bound[i].is_some() => {
+                return Err(format!(
+                    "{}() got multiple values for argument '{}'",
+                    name, keyword,
+                ))
+            }
+            Some(i) => bound[i] = Some(arg),
+            None => {
+                return Err(format!(
+                    "{}() got an unexpected keyword argument '{}'",
+                    name, keyword,
+                ))
+            }
+        }
+    }
+    if !posonly_names.is_empty() {
+        return Err(format!(
+            "{}() got some positional-only arguments passed as keyword \
+                arguments: '{}'",
+            name,
+            posonly_names.join(", "),
+        ));
+    }
+    let missing = |range: std::ops::Range<usize>| {
+        range
+            .filter(|&i| bound[i].is_none() && !has_default[i])
+            .map(|i| format!("'{}'", params[i]))
+            .collect::<Vec<_>>()
+    };
+    for (range, kind) in [
+        (0..positional, "positional"),
+        (positional..params.len(), "keyword-only"),
+    ] {
+        let missing = missing(range);
+        if missing.is_empty() {
+            continue;
+        }
+        let list = match missing.len() {
+            1 => missing[0].clone(),
+            2 => format!("{} and {}", missing[0], missing[1]),
+            n => format!("{}, and {}", missing[..n - 1].join(", "), missing[n - 1]),
+        };
+        return Err(format!(
+            "{}() missing {} required {} argument{}: {}",
+            name,
+            missing.len(),
+            kind,
+            if missing.len() == 1 { "" } else { "s" },
+            list,
+        ));
+    }
+    Ok(bound)
+}
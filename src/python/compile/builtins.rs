@@ -0,0 +1,1007 @@
+// Python built-in compilation
+//
+//! Calls of built-in functions and of methods of built-in types.  Their
+//! result types follow from the types of their arguments, and methods that
+//! store their arguments in a list or dictionary add their types to its
+//! container's.
+
+use std::collections::HashMap;
+
+use super::super::runtime::{self, index_arg, Eval, Exception, Int, Value};
+use super::super::{BinaryOp, Constant, Expr, ExprKind, KeywordArg};
+use super::expr::{collect, constant, map, unbox, Item, Lookup};
+use super::{Code, Frame, Fun, Result, Type, Typed, Unboxed, Walker};
+use crate::{Diagnostic, Span};
+
+// The built-in functions that can be compiled, with the least and most
+// positional arguments they take and their keyword arguments
+const BUILTINS: &[(&str, usize, usize, &[&str])] = &[
+    ("abs", 1, 1, &[]),
+    ("all", 1, 1, &[]),
+    ("any", 1, 1, &[]),
+    ("bin", 1, 1, &[]),
+    ("bool", 0, 1, &[]),
+    ("chr", 1, 1, &[]),
+    ("dict", 0, 1, &[]),
+    ("divmod", 2, 2, &[]),
+    ("enumerate", 1, 2, &["start"]),
+    ("filter", 2, 2, &[]),
+    ("float", 0, 1, &[]),
+    ("format", 1, 2, &[]),
+    ("hash", 1, 1, &[]),
+    ("hex", 1, 1, &[]),
+    ("int", 0, 2, &[]),
+    ("isinstance", 2, 2, &[]),
+    ("iter", 1, 1, &[]),
+    ("len", 1, 1, &[]),
+    ("list", 0, 1, &[]),
+    ("map", 2, 2, &[]),
+    ("max", 1, usize::MAX, &["key", "default"]),
+    ("min", 1, usize::MAX, &["key", "default"]),
+    ("next", 1, 2, &[]),
+    ("oct", 1, 1, &[]),
+    ("ord", 1, 1, &[]),
+    ("pow", 2, 3, &[]),
+    ("print", 0, usize::MAX, &["sep", "end"]),
+    ("range", 1, 3, &[]),
+    ("repr", 1, 1, &[]),
+    ("reversed", 1, 1, &[]),
+    ("round", 1, 2, &["ndigits"]),
+    ("set", 0, 1, &[]),
+    ("sorted", 1, 1, &["key", "reverse"]),
+    ("str", 0, 1, &[]),
+    ("sum", 1, 2, &["start"]),
+    ("tuple", 0, 1, &[]),
+    ("zip", 0, usize::MAX, &[]),
+];
+
+// The built-in classes `isinstance` can check for, besides exceptions
+const CLASSES: &[&str] = &[
+    "bool", "dict", "float", "int", "list", "object", "range", "set", "str",
+    "tuple",
+];
+
+/// Whether a name is a built-in function or exception class that can be
+/// compiled.
+pub(super) fn is_builtin(name: &str) -> bool {
+    BUILTINS.iter().any(|builtin| builtin.0 == name)
+        || runtime::exception_class(name).is_some()
+}
+
+// The result type of a method of `str`.
+fn str_method(name: &str) -> Option<Type> {
+    Some(match name {
+        "capitalize" | "casefold" | "center" | "expandtabs" | "format"
+        | "join" | "ljust" | "lower" | "lstrip" | "removeprefix"
+        | "removesuffix" | "replace" | "rjust" | "rstrip" | "strip"
+        | "swapcase" | "title" | "upper" | "zfill" => Type::Str,
+        "endswith" | "isalnum" | "isalpha" | "isascii" | "isdecimal"
+        | "isdigit" | "isidentifier" | "islower" | "isnumeric"
+        | "isprintable" | "isspace" | "istitle" | "isupper"
+        | "startswith" => Type::Bool,
+        "count" | "find" | "index" | "rfind" | "rindex" => Type::Int,
+        "partition" | "rpartition" => {
+            Type::Tuple(vec![Type::Str, Type::Str, Type::Str])
+        }
+        _ => return None,
+    })
+}
+
+impl<'c, 'a> Walker<'c, 'a> {
+    // Code calling a runtime function with the boxed values of arguments,
+    // and unboxing its result to `ty`.
+    fn runtime_call(
+        &mut self,
+        args: Vec<Typed>,
+        ty: Type,
+        run: impl Fn(&mut Frame<'_>, Vec<Value>) -> Eval<Value> + 'static,
+    ) -> Typed {
+        let codes: Vec<_> = args.into_iter().map(|arg| self.boxed(arg)).collect();
+        let code: Fun<Value> = Box::new(move |f| {
+            let mut values = Vec::with_capacity(codes.len());
+            for code in &codes {
+                values.push(code(f)?);
+            }
+            run(f, values)
+        });
+        Typed { code: unbox(code, &ty), ty }
+    }
+
+    // A function passed to a built-in, which calls it with items of type
+    // `element`.
+    fn callback(&mut self, function: Typed, element: &Type) -> Fun<Value> {
+        self.c.escape(element);
+        self.dynamic(function)
+    }
+
+    /// Compile a call of a built-in function.
+    pub(super) fn builtin(
+        &mut self,
+        name: &str,
+        args: &'a [Expr<'a>],
+        keywords: &'a [KeywordArg<'a>],
+        span: Span,
+    ) -> Result<Typed> {
+        let &(name, min, max, allowed) = match BUILTINS.iter()
+            .find(|builtin| builtin.0 == name)
+        {
+            Some(builtin) => builtin,
+            None => return self.exception(name, args, keywords),
+        };
+        if args.len() < min || args.len() > max {
+            let takes = match (min, max) {
+                (min, max) if min == max => format!("exactly {}", min),
+                (min, usize::MAX) => format!("at least {}", min),
+                (0, max) => format!("at most {}", max),
+                (min, max) => format!("from {} to {}", min, max),
+            };
+            return Err(Diagnostic::new(span, format!(
+                "`{}()` takes {} argument{} ({} given)",
+                name,
+                takes,
+                if max == 1 { "" } else { "s" },
+                args.len(),
+            )));
+        }
+        let mut named = HashMap::new();
+        for keyword in keywords {
+            match keyword.arg {
+                Some(arg) if allowed.contains(&arg) || name == "dict" => {
+                    named.insert(arg, &keyword.value);
+                }
+                _ => {
+                    return Err(Diagnostic::new(keyword.span, format!(
+                        "`{}()` doesn't take this keyword argument",
+                        name,
+                    )))
+                }
+            }
+        }
+        if name == "print" {
+            return self.print(args, &named);
+        }
+        if let Some(arg) = args.iter()
+            .find(|arg| matches!(arg.kind, ExprKind::Starred(..)))
+        {
+            return Err(Diagnostic::new(
+                arg.span,
+                "`*` arguments to built-in functions can't be compiled yet",
+            ));
+        }
+        if name == "isinstance" {
+            return self.isinstance(&args[0], &args[1]);
+        }
+        let mut values = Vec::new();
+        for arg in args {
+            values.push(self.expr(arg)?);
+        }
+        let keyword = |walker: &mut Self, name: &str| match named.get(name) {
+            Some(value) => walker.expr(value).map(Some),
+            None => Ok(None),
+        };
+        let types: Vec<_> = values.iter().map(|v| self.c.resolve(&v.ty)).collect();
+        let int_like = |ty: &Type| matches!(ty, Type::Int | Type::Bool);
+        Ok(match name {
+            "len" => self.runtime_call(values, Type::Int, |_, v| {
+                runtime::len(&v[0]).map(Value::int)
+            }),
+            "abs" => match types[0] {
+                Type::Int | Type::Bool => {
+                    let value = self.coerce(values.remove(0), &Type::Int);
+                    let code = map(Int::fun(value), |v: Int| Ok(v.abs()));
+                    Typed { ty: Type::Int, code }
+                }
+                Type::Float => {
+                    let code = map(f64::fun(values.remove(0).code), |v: f64| {
+                        Ok(v.abs())
+                    });
+                    Typed { ty: Type::Float, code }
+                }
+                _ => self.runtime_call(values, Type::Dynamic, |_, v| {
+                    runtime::abs(&v[0])
+                }),
+            },
+            "int" => match types.first() {
+                None => Typed { ty: Type::Int, code: constant(Int::Small(0)) },
+                Some(ty) if int_like(ty) => Typed {
+                    ty: Type::Int,
+                    code: self.coerce(values.remove(0), &Type::Int),
+                },
+                Some(_) if values.len() == 1 => {
+                    self.runtime_call(values, Type::Int, |_, v| {
+                        runtime::to_int(&v[0]).map(Value::Int)
+                    })
+                }
+                _ => self.runtime_call(values, Type::Int, |_, v| {
+                    runtime::call_builtin("int", v)
+                }),
+            },
+            "hash" => self.runtime_call(values, Type::Int, |_, v| {
+                runtime::hash(&v[0]).map(Value::int)
+            }),
+            "bin" | "hex" | "oct" => {
+                self.runtime_call(values, Type::Str, move |_, v| {
+                    runtime::call_builtin(name, v)
+                })
+            }
+            "iter" | "next" | "set" => {
+                // Their items may be used in any way.
+                for ty in &types {
+                    let element = self.element(ty);
+                    self.c.escape(&element);
+                    self.c.escape(ty);
+                }
+                match name {
+                    "iter" => self.runtime_call(values, Type::Dynamic, |_, v| {
+                        runtime::iter_object(&v[0])
+                    }),
+                    "next" => self.runtime_call(values, Type::Dynamic, |_, v| {
+                        runtime::next(&v[0], v.get(1).cloned())
+                    }),
+                    _ => self.runtime_call(values, Type::Dynamic, |_, v| {
+                        match v.first() {
+                            Some(iterable) => Ok(Value::set(runtime::to_set(iterable)?)),
+                            None => Ok(Value::set(runtime::Set::default())),
+                        }
+                    }),
+                }
+            }
+            "float" => match types.first() {
+                None => Typed { ty: Type::Float, code: constant(0.0) },
+                Some(ty) if ty.is_number() => Typed {
+                    ty: Type::Float,
+                    code: self.coerce(values.remove(0), &Type::Float),
+                },
+                _ => self.runtime_call(values, Type::Float, |_, v| {
+                    runtime::to_float(&v[0]).map(Value::Float)
+                }),
+            },
+            "str" => match types.first() {
+                None => Typed { ty: Type::Str, code: constant(Value::str("")) },
+                Some(Type::Str) => values.remove(0),
+                _ => self.runtime_call(values, Type::Str, |_, v| {
+                    Ok(Value::str(&v[0].to_string()))
+                }),
+            },
+            "repr" => self.runtime_call(values, Type::Str, |_, v| {
+                Ok(Value::str(&v[0].repr()))
+            }),
+            "bool" => match values.pop() {
+                None => Typed { ty: Type::Bool, code: constant(false) },
+                Some(value) => Typed {
+                    ty: Type::Bool,
+                    code: Code::Bool(self.truth(value)),
+                },
+            },
+            "format" => self.runtime_call(values, Type::Str, |_, v| {
+                let spec = match v.get(1) {
+                    Some(Value::Str(spec)) => spec.to_string(),
+                    Some(other) => {
+                        return Err(Exception::new("TypeError", format!(
+                            "format() argument 2 must be str, not {}",
+                            other.type_name(),
+                        )))
+                    }
+                    None => String::new(),
+                };
+                runtime::format(&v[0], &spec).map(|s| Value::str(&s))
+            }),
+            "chr" => self.runtime_call(values, Type::Str, |_, v| {
+                runtime::chr(index_arg(&v[0])?)
+            }),
+            "ord" => self.runtime_call(values, Type::Int, |_, v| {
+                runtime::ord(&v[0]).map(Value::int)
+            }),
+            "range" => {
+                let mut codes = Vec::new();
+                for (value, ty) in values.into_iter().zip(&types) {
+                    codes.push(match int_like(ty) {
+                        true => {
+                            let value = Int::fun(self.coerce(value, &Type::Int));
+                            Box::new(move |f: &mut Frame<'_>| {
+                                index_arg(&Value::Int(value(f)?))
+                            }) as Fun<i64>
+                        }
+                        false => {
+                            let value = self.boxed(value);
+                            Box::new(move |f: &mut Frame<'_>| index_arg(&value(f)?))
+                        }
+                    });
+                }
+                let code = Code::Value(Box::new(move |f| {
+                    let mut bounds = Vec::new();
+                    for code in &codes {
+                        bounds.push(code(f)?);
+                    }
+                    let (start, stop, step) = match bounds[..] {
+                        [stop] => (0, stop, 1),
+                        [start, stop] => (start, stop, 1),
+                        [start, stop, step] => (start, stop, step),
+                        _ => unreachable!(),
+                    };
+                    if step == 0 {
+                        return Err(Exception::new(
+                            "ValueError",
+                            "range() arg 3 must not be zero",
+                        ));
+                    }
+                    Ok(Value::Range(start, stop, step))
+                }));
+                Typed { ty: Type::Range, code }
+            }
+            "list" | "reversed" | "sorted" => {
+                let site = self.c.site(span, 0);
+                let element = match types.first() {
+                    Some(ty) => self.element(ty),
+                    None => Type::Never,
+                };
+                self.c.store_value(site, &element);
+                let key = keyword(self, "key")?
+                    .map(|key| self.callback(key, &element));
+                let reverse = keyword(self, "reverse")?
+                    .map(|reverse| self.truth(reverse));
+                let iterable = match values.pop() {
+                    Some(value) => self.boxed(value),
+                    None => Box::new(|_: &mut Frame<'_>| Ok(Value::list(Vec::new()))),
+                };
+                let sorted = name == "sorted";
+                let reversed = name == "reversed";
+                let code = Code::Value(Box::new(move |f| {
+                    let mut items = runtime::items(&iterable(f)?)?;
+                    if reversed {
+                        items.reverse();
+                    }
+                    if sorted {
+                        let reverse = match &reverse {
+                            Some(reverse) => reverse(f)?,
+                            None => false,
+                        };
+                        let keys = keys(f, key.as_ref(), &items)?;
+                        runtime::sort(&mut items, keys, reverse)?;
+                    }
+                    Ok(Value::list(items))
+                }));
+                Typed { ty: Type::List(site), code }
+            }
+            "tuple" => {
+                let element = match types.first() {
+                    Some(ty) => self.element(ty),
+                    None => Type::Never,
+                };
+                self.c.escape(&element);
+                let ty = match values.is_empty() {
+                    true => Type::Tuple(Vec::new()),
+                    false => Type::Dynamic,
+                };
+                self.runtime_call(values, ty, |_, v| match v.first() {
+                    Some(iterable) => Ok(Value::tuple(runtime::items(iterable)?)),
+                    None => Ok(Value::tuple(Vec::new())),
+                })
+            }
+            "dict" => {
+                let site = self.c.site(span, 0);
+                let (key, value) = match types.first() {
+                    None => (Type::Never, Type::Never),
+                    Some(Type::Dict(id)) => (self.c.key(*id), self.c.value(*id)),
+                    Some(ty) => match self.element(ty) {
+                        Type::Tuple(pair) if pair.len() == 2 => {
+                            (pair[0].clone(), pair[1].clone())
+                        }
+                        _ => (Type::Dynamic, Type::Dynamic),
+                    },
+                };
+                self.c.store_key(site, &key);
+                self.c.store_value(site, &value);
+                let mut entries = Vec::new();
+                for keyword in keywords {
+                    let value = self.expr(&keyword.value)?;
+                    self.c.store_key(site, &Type::Str);
+                    self.c.store_value(site, &value.ty);
+                    let name = Value::str(keyword.arg.unwrap());
+                    entries.push((name, self.boxed(value)));
+                }
+                let ty = Type::Dict(site);
+                self.runtime_call(values, ty, move |f, v| {
+                    let dict = Value::dict(Default::default());
+                    if let Some(other) = v.first() {
+                        runtime::call_method(&dict, "update", vec![other.clone()])?;
+                    }
+                    for (key, value) in &entries {
+                        let value = value(f)?;
+                        runtime::set_item(&dict, key, value)?;
+                    }
+                    Ok(dict)
+                })
+            }
+            "enumerate" | "zip" => {
+                let site = self.c.site(span, 0);
+                let mut items: Vec<_> =
+                    types.iter().map(|ty| self.element(ty)).collect();
+                let start = match keyword(self, "start")? {
+                    Some(start) => Some(start),
+                    None if name == "enumerate" && values.len() == 2 => values.pop(),
+                    None => None,
+                };
+                if name == "enumerate" {
+                    items = vec![Type::Int, items[0].clone()];
+                }
+                self.c.store_value(site, &Type::Tuple(items));
+                let start = match start {
+                    Some(start) => {
+                        let start = self.boxed(start);
+                        Box::new(move |f: &mut Frame<'_>| index_arg(&start(f)?))
+                    }
+                    None => Box::new(|_: &mut Frame<'_>| Ok(0_i64)) as Fun<i64>,
+                };
+                let enumerate = name == "enumerate";
+                self.runtime_call(values, Type::List(site), move |f, v| {
+                    let start = start(f)?;
+                    let mut iterables = Vec::new();
+                    for iterable in &v {
+                        iterables.push(runtime::items(iterable)?);
+                    }
+                    if enumerate {
+                        let items = iterables.pop().unwrap();
+                        return Ok(Value::list(items.into_iter()
+                            .enumerate()
+                            .map(|(i, item)| {
+                                Value::tuple(vec![Value::int(start + i as i64), item])
+                            })
+                            .collect()));
+                    }
+                    let len = iterables.iter().map(Vec::len).min().unwrap_or(0);
+                    Ok(Value::list((0..len)
+                        .map(|i| {
+                            Value::tuple(iterables.iter().map(|items| items[i].clone()).collect())
+                        })
+                        .collect()))
+                })
+            }
+            "map" | "filter" => {
+                let site = self.c.site(span, 0);
+                let element = self.element(&types[1]);
+                let function = values.remove(0);
+                let none = types[0] == Type::None && name == "filter";
+                let function = match none {
+                    true => None,
+                    false => Some(self.callback(function, &element)),
+                };
+                let item = match name {
+                    "map" => Type::Dynamic,
+                    _ => element,
+                };
+                self.c.store_value(site, &item);
+                let filter = name == "filter";
+                self.runtime_call(values, Type::List(site), move |f, v| {
+                    let mut results = Vec::new();
+                    for item in runtime::iter(&v[0])? {
+                        let item = item?;
+                        let result = match &function {
+                            Some(function) => {
+                                let function = function(f)?;
+                                f.rt.call(&function, vec![item.clone()], Vec::new())?
+                            }
+                            None => item.clone(),
+                        };
+                        match filter {
+                            true if result.truthy() => results.push(item),
+                            true => {}
+                            false => results.push(result),
+                        }
+                    }
+                    Ok(Value::list(results))
+                })
+            }
+            "sum" => {
+                let element = self.element(&types[0]);
+                let start = match keyword(self, "start")? {
+                    Some(start) => Some(start),
+                    None if values.len() == 2 => values.pop(),
+                    None => None,
+                };
+                let start_ty = start.as_ref()
+                    .map_or(Type::Int, |start| self.c.resolve(&start.ty));
+                let ty = match (&element, &start_ty) {
+                    (element, start) if int_like(element) && int_like(start) => {
+                        Type::Int
+                    }
+                    (element, Type::Float) if element.is_number() => Type::Float,
+                    (Type::Never, start) if start.is_number() => start.clone(),
+                    _ => {
+                        self.c.escape(&element);
+                        self.c.escape(&start_ty);
+                        Type::Dynamic
+                    }
+                };
+                let ty = match ty {
+                    Type::Bool => Type::Int,
+                    ty => ty,
+                };
+                if let Some(start) = start {
+                    values.push(start);
+                }
+                self.runtime_call(values, ty, |_, mut v| {
+                    let start = match v.len() {
+                        2 => v.pop().unwrap(),
+                        _ => Value::int(0),
+                    };
+                    runtime::sum(&v[0], start)
+                })
+            }
+            "any" | "all" => {
+                let all = name == "all";
+                self.runtime_call(values, Type::Bool, move |_, v| {
+                    for item in runtime::iter(&v[0])? {
+                        if item?.truthy() != all {
+                            return Ok(Value::Bool(!all));
+                        }
+                    }
+                    Ok(Value::Bool(all))
+                })
+            }
+            "min" | "max" => {
+                let mut ty = match values.len() {
+                    1 => self.element(&types[0]),
+                    _ => {
+                        let mut ty = Type::Never;
+                        for item in &types {
+                            ty = self.c.join(&ty, item);
+                        }
+                        ty
+                    }
+                };
+                let key = keyword(self, "key")?
+                    .map(|key| self.callback(key, &ty));
+                let default = match keyword(self, "default")? {
+                    Some(default) => {
+                        ty = self.c.join(&ty, &default.ty);
+                        Some(self.boxed(default))
+                    }
+                    None => None,
+                };
+                let name = name.to_string();
+                let several = values.len() > 1;
+                self.runtime_call(values, ty, move |f, v| {
+                    let items = match several {
+                        true => v,
+                        false => runtime::items(&v[0])?,
+                    };
+                    if items.is_empty() {
+                        if let Some(default) = &default {
+                            return default(f);
+                        }
+                    }
+                    match keys(f, key.as_ref(), &items)? {
+                        None => runtime::min_max(&name, items),
+                        Some(keys) => {
+                            let best = runtime::min_max(&name, keys.clone())?;
+                            let i = keys.iter()
+                                .position(|key| runtime::equal(key, &best))
+                                .unwrap();
+                            Ok(items[i].clone())
+                        }
+                    }
+                })
+            }
+            "round" => {
+                let digits = match keyword(self, "ndigits")? {
+                    Some(digits) => Some(digits),
+                    None if values.len() == 2 => values.pop(),
+                    None => None,
+                };
+                let no_digits = digits.as_ref()
+                    .is_none_or(|digits| self.c.resolve(&digits.ty) == Type::None);
+                let ty = match (&types[0], no_digits) {
+                    (Type::Float, true) => Type::Int,
+                    (Type::Float, false) => Type::Float,
+                    (ty, _) if int_like(ty) => Type::Int,
+                    _ => Type::Dynamic,
+                };
+                if let Some(digits) = digits {
+                    values.push(digits);
+                }
+                self.runtime_call(values, ty, |_, v| runtime::round(&v[0], v.get(1)))
+            }
+            "divmod" => {
+                let ty = match (&types[0], &types[1]) {
+                    (a, b) if int_like(a) && int_like(b) => {
+                        Type::Tuple(vec![Type::Int, Type::Int])
+                    }
+                    (a, b) if a.is_number() && b.is_number() => {
+                        Type::Tuple(vec![Type::Float, Type::Float])
+                    }
+                    _ => Type::Dynamic,
+                };
+                self.runtime_call(values, ty, |_, v| {
+                    Ok(Value::tuple(vec![
+                        runtime::binary(BinaryOp::FloorDiv, &v[0], &v[1])?,
+                        runtime::binary(BinaryOp::Mod, &v[0], &v[1])?,
+                    ]))
+                })
+            }
+            "pow" if values.len() == 2 => {
+                let exponent = match &args[1].kind {
+                    ExprKind::Constant(Constant::Int(value)) => Some(*value),
+                    _ => None,
+                };
+                let exponent_value = values.pop().unwrap();
+                let base = values.pop().unwrap();
+                self.binary(base, BinaryOp::Pow, exponent_value, exponent, span)
+            }
+            "pow" => {
+                let ty = match types.iter().all(int_like) {
+                    true => Type::Int,
+                    false => Type::Dynamic,
+                };
+                self.runtime_call(values, ty, |_, v| {
+                    runtime::pow(&v[0], &v[1], v.get(2))
+                })
+            }
+            _ => unreachable!("`{}` is in the list of built-in functions", name),
+        })
+    }
+
+    fn print(
+        &mut self,
+        args: &'a [Expr<'a>],
+        named: &HashMap<&str, &'a Expr<'a>>,
+    ) -> Result<Typed> {
+        let mut items = Vec::new();
+        for arg in args {
+            items.push(match &arg.kind {
+                ExprKind::Starred(value, _) => {
+                    let value = self.expr(value)?;
+                    Item::Starred(self.boxed(value))
+                }
+                _ => {
+                    let value = self.expr(arg)?;
+                    Item::Single(self.boxed(value))
+                }
+            });
+        }
+        let mut separator = |name: &str, default: &'static str| -> Result<Fun<String>> {
+            Ok(match named.get(name) {
+                Some(value) => {
+                    let value = self.expr(value)?;
+                    let value = self.boxed(value);
+                    let name = name.to_string();
+                    Box::new(move |f| match value(f)? {
+                        Value::None => Ok(default.to_string()),
+                        Value::Str(string) => Ok(string.to_string()),
+                        other => Err(Exception::new("TypeError", format!(
+                            "{} must be None or a string, not {}",
+                            name,
+                            other.type_name(),
+                        ))),
+                    })
+                }
+                None => Box::new(move |_| Ok(default.to_string())),
+            })
+        };
+        let sep = separator("sep", " ")?;
+        let end = separator("end", "\n")?;
+        let code = Code::Value(Box::new(move |f| {
+            let values = collect(&items, f)?;
+            let sep = sep(f)?;
+            let end = end(f)?;
+            let mut output = f.rt.output.borrow_mut();
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    output.push_str(&sep);
+                }
+                output.push_str(&value.to_string());
+            }
+            output.push_str(&end);
+            Ok(Value::None)
+        }));
+        Ok(Typed { ty: Type::None, code })
+    }
+
+    // A call of a built-in exception class, making an exception.
+    fn exception(
+        &mut self,
+        name: &str,
+        args: &'a [Expr<'a>],
+        keywords: &'a [KeywordArg<'a>],
+    ) -> Result<Typed> {
+        if let Some(keyword) = keywords.first() {
+            return Err(Diagnostic::new(keyword.span, format!(
+                "`{}()` doesn't take keyword arguments",
+                name,
+            )));
+        }
+        let class = runtime::exception_class(name).unwrap();
+        let mut values = Vec::new();
+        for arg in args {
+            if let ExprKind::Starred(..) = arg.kind {
+                return Err(Diagnostic::new(
+                    arg.span,
+                    "`*` arguments to built-in functions can't be compiled yet",
+                ));
+            }
+            let value = self.expr(arg)?;
+            self.c.escape(&value.ty);
+            values.push(value);
+        }
+        Ok(self.runtime_call(values, Type::Dynamic, move |_, v| {
+            Ok(runtime::call_class(class, v))
+        }))
+    }
+
+    // `isinstance(value, class)`, where the built-in classes a tuple of
+    // classes names are checked for by name and other classes are values.
+    fn isinstance(&mut self, value: &'a Expr<'a>, class: &'a Expr<'a>)
+        -> Result<Typed>
+    {
+        let items: Vec<_> = match &class.kind {
+            ExprKind::Tuple(items, _) => items.iter().collect(),
+            _ => vec![class],
+        };
+        let mut args = vec![self.expr(value)?];
+        let mut builtins = Vec::new();
+        for item in items {
+            match item.kind {
+                ExprKind::Name(name, _)
+                    if (CLASSES.contains(&name)
+                        || runtime::exception_class(name).is_some())
+                        && !matches!(
+                            self.lookup(name),
+                            Lookup::Var(..) | Lookup::Free(_),
+                        ) =>
+                {
+                    builtins.push(name.to_string());
+                }
+                _ => {
+                    let class = self.expr(item)?;
+                    self.c.escape(&class.ty);
+                    args.push(class);
+                }
+            }
+        }
+        Ok(self.runtime_call(args, Type::Bool, move |_, v| {
+            let mut found = builtins.iter()
+                .any(|class| runtime::isinstance(&v[0], class));
+            for class in &v[1..] {
+                found = found || runtime::instance_of(&v[0], class)?;
+            }
+            Ok(Value::Bool(found))
+        }))
+    }
+
+    /// Compile a method call.
+    pub(super) fn method(
+        &mut self,
+        receiver: &'a Expr<'a>,
+        name: &'a str,
+        args: &'a [Expr<'a>],
+        keywords: &'a [KeywordArg<'a>],
+        span: Span,
+    ) -> Result<Typed> {
+        if let ExprKind::Call(callee, super_args, super_keywords) = &receiver.kind {
+            if let (ExprKind::Name("super", _), [], [], Lookup::Undefined) = (
+                &callee.kind,
+                super_args.as_slice(),
+                super_keywords.as_slice(),
+                self.lookup("super"),
+            ) {
+                return self.super_method(name, args, keywords, receiver.span);
+            }
+        }
+        let receiver = self.expr(receiver)?;
+        if let Type::Dynamic = self.c.resolve(&receiver.ty) {
+            return self.call_attribute(receiver, name, args, keywords);
+        }
+        if let Some(arg) = args.iter()
+            .find(|arg| matches!(arg.kind, ExprKind::Starred(..)))
+        {
+            return Err(Diagnostic::new(
+                arg.span,
+                "`*` arguments to methods can't be compiled yet",
+            ));
+        }
+        let mut values = Vec::new();
+        for arg in args {
+            values.push(self.expr(arg)?);
+        }
+        let ty = self.c.resolve(&receiver.ty);
+        if let (Type::List(id), "sort") = (&ty, name) {
+            return self.sort(receiver, *id, values, keywords, span);
+        }
+        // `split(sep=None, maxsplit=-1)` also takes keyword arguments.
+        let splits = matches!((&ty, name), (Type::Str, "split") | (Type::Str, "rsplit"));
+        for keyword in keywords {
+            let position = match keyword.arg {
+                Some("sep") if splits => 0,
+                Some("maxsplit") if splits => 1,
+                _ => {
+                    return Err(Diagnostic::new(keyword.span, format!(
+                        "keyword arguments to `{}()` can't be compiled yet",
+                        name,
+                    )))
+                }
+            };
+            while values.len() < position {
+                let none = Typed { ty: Type::None, code: constant(Value::None) };
+                values.push(none);
+            }
+            if position < values.len() {
+                return Err(Diagnostic::new(keyword.span, format!(
+                    "`{}()` got multiple values for `{}`",
+                    name,
+                    keyword.arg.unwrap(),
+                )));
+            }
+            values.push(self.expr(&keyword.value)?);
+        }
+        let types: Vec<_> = values.iter().map(|v| self.c.resolve(&v.ty)).collect();
+        let result = match ty {
+            Type::List(id) => match name {
+                "append" | "insert" => {
+                    if let Some(item) = types.last() {
+                        self.c.store_value(id, item);
+                    }
+                    Type::None
+                }
+                "extend" => {
+                    if let Some(items) = types.first() {
+                        let element = self.element(items);
+                        self.c.store_value(id, &element);
+                    }
+                    Type::None
+                }
+                "pop" => self.c.value(id),
+                "index" | "count" => Type::Int,
+                "remove" | "reverse" | "clear" => Type::None,
+                "copy" => Type::List(id),
+                _ => Type::Dynamic,
+            },
+            Type::Dict(id) => match name {
+                "get" | "pop" => {
+                    let default = match types.get(1) {
+                        Some(default) => default.clone(),
+                        None if name == "get" => Type::None,
+                        None => Type::Never,
+                    };
+                    let value = self.c.value(id);
+                    self.c.join(&value, &default)
+                }
+                "setdefault" => {
+                    if let Some(key) = types.first() {
+                        self.c.store_key(id, key);
+                    }
+                    let default = types.get(1).cloned().unwrap_or(Type::None);
+                    self.c.store_value(id, &default);
+                    self.c.value(id)
+                }
+                "keys" | "values" | "items" => {
+                    let site = self.c.site(span, 0);
+                    let (key, value) = (self.c.key(id), self.c.value(id));
+                    let item = match name {
+                        "keys" => key,
+                        "values" => value,
+                        _ => Type::Tuple(vec![key, value]),
+                    };
+                    self.c.store_value(site, &item);
+                    Type::List(site)
+                }
+                "update" => {
+                    let (key, value) = match types.first() {
+                        Some(Type::Dict(other)) => {
+                            (self.c.key(*other), self.c.value(*other))
+                        }
+                        Some(ty) => match self.element(ty) {
+                            Type::Tuple(pair) if pair.len() == 2 => {
+                                (pair[0].clone(), pair[1].clone())
+                            }
+                            _ => (Type::Dynamic, Type::Dynamic),
+                        },
+                        None => (Type::Never, Type::Never),
+                    };
+                    self.c.store_key(id, &key);
+                    self.c.store_value(id, &value);
+                    Type::None
+                }
+                "copy" => Type::Dict(id),
+                "clear" => Type::None,
+                _ => Type::Dynamic,
+            },
+            Type::Str => match name {
+                "split" | "rsplit" | "splitlines" => {
+                    let site = self.c.site(span, 0);
+                    self.c.store_value(site, &Type::Str);
+                    Type::List(site)
+                }
+                _ => str_method(name).unwrap_or(Type::Dynamic),
+            },
+            Type::Tuple(_) if name == "index" || name == "count" => Type::Int,
+            _ => {
+                // Anything might be stored in an `object`.
+                for ty in &types {
+                    self.c.escape(ty);
+                }
+                Type::Dynamic
+            }
+        };
+        values.insert(0, receiver);
+        let name = name.to_string();
+        Ok(self.runtime_call(values, result, move |_, mut v| {
+            let receiver = v.remove(0);
+            runtime::call_method(&receiver, &name, v)
+        }))
+    }
+
+    // `list.sort(key=None, reverse=False)`
+    fn sort(
+        &mut self,
+        list: Typed,
+        id: usize,
+        values: Vec<Typed>,
+        keywords: &'a [KeywordArg<'a>],
+        span: Span,
+    ) -> Result<Typed> {
+        if !values.is_empty() {
+            return Err(Diagnostic::new(
+                span,
+                "`sort()` takes no positional arguments",
+            ));
+        }
+        let element = self.c.value(id);
+        let mut key = None;
+        let mut reverse = None;
+        for keyword in keywords {
+            let value = self.expr(&keyword.value)?;
+            match keyword.arg {
+                Some("key") => key = Some(self.callback(value, &element)),
+                Some("reverse") => reverse = Some(self.truth(value)),
+                _ => {
+                    return Err(Diagnostic::new(
+                        keyword.span,
+                        "`sort()` doesn't take this keyword argument",
+                    ))
+                }
+            }
+        }
+        let list = self.boxed(list);
+        let code = Code::Value(Box::new(move |f| {
+            let list = match list(f)? {
+                Value::List(list) => list,
+                _ => unreachable!("the receiver is a list"),
+            };
+            let key = key.as_ref();
+            let reverse = match &reverse {
+                Some(reverse) => reverse(f)?,
+                None => false,
+            };
+            let mut items = list.borrow().clone();
+            let keys = keys(f, key, &items)?;
+            runtime::sort(&mut items, keys, reverse)?;
+            *list.borrow_mut() = items;
+            Ok(Value::None)
+        }));
+        Ok(Typed { ty: Type::None, code })
+    }
+}
+
+// The results of a key function for some items.
+fn keys(f: &mut Frame<'_>, key: Option<&Fun<Value>>, items: &[Value])
+    -> Eval<Option<Vec<Value>>>
+{
+    let key = match key {
+        Some(key) => key(f)?,
+        None => return Ok(None),
+    };
+    if let Value::None = key {
+        return Ok(None);
+    }
+    let mut keys = Vec::with_capacity(items.len());
+    for item in items {
+        keys.push(f.rt.call(&key, vec![item.clone()], Vec::new())?);
+    }
+    Ok(Some(keys))
+}
+
+// Only `Constant` is needed for `None` checks in keyword arguments.
+#[allow(dead_code)]
+fn is_none(expr: &Expr<'_>) -> bool {
+    matches!(expr.kind, ExprKind::Constant(Constant::None))
+}
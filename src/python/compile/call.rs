@@ -0,0 +1,399 @@
+// Python call compilation
+//
+//! Calls of functions: direct calls of the instance for the argument types
+//! when the function is known statically, and calls through a boxed function
+//! value, bound at run time, otherwise.
+
+use std::rc::Rc;
+
+use super::super::runtime::{self, Exception, Int, Value};
+use super::super::{Expr, ExprKind, KeywordArg};
+use super::expr::{collect, compatible, raising, Item, Lookup};
+use super::{bind, Code, Frame, Fun, Repr, Result, Slots, Type, Typed, Unboxed, VarKey, Walker};
+use crate::{Diagnostic, Span};
+
+impl<'c, 'a> Walker<'c, 'a> {
+    /// Compile a call.
+    pub(super) fn call(
+        &mut self,
+        func: &'a Expr<'a>,
+        args: &'a [Expr<'a>],
+        keywords: &'a [KeywordArg<'a>],
+        span: Span,
+    ) -> Result<Typed> {
+        let callee = match &func.kind {
+            ExprKind::Name(name, _) => match self.lookup(name) {
+                Lookup::Builtin => {
+                    return self.builtin(name, args, keywords, span)
+                }
+                Lookup::Var(owner, id) => self.read_var(owner, id),
+                Lookup::Free(cell) => self.read_free(cell, name),
+                Lookup::Undefined if *name == "super" => {
+                    return Err(Diagnostic::new(
+                        span,
+                        "`super()` other than to call a method can't be \
+                            compiled yet",
+                    ))
+                }
+                lookup => return self.name_error(name, &lookup, func.span),
+            },
+            ExprKind::Attribute(receiver, name, _) => {
+                return self.method(receiver, name, args, keywords, span)
+            }
+            _ => self.expr(func)?,
+        };
+        let starred = args.iter()
+            .any(|arg| matches!(arg.kind, ExprKind::Starred(..)))
+            || keywords.iter().any(|keyword| keyword.arg.is_none());
+        match self.c.resolve(&callee.ty) {
+            Type::Function(def) if !starred => {
+                self.call_def(callee.code, def, args, keywords)
+            }
+            _ => self.call_dynamic(callee, args, keywords),
+        }
+    }
+
+    // A direct call of a function, binding the arguments statically.
+    fn call_def(
+        &mut self,
+        callee: Code,
+        def: usize,
+        args: &'a [Expr<'a>],
+        keywords: &'a [KeywordArg<'a>],
+    ) -> Result<Typed> {
+        let mut values = Vec::new();
+        for arg in args.iter().chain(keywords.iter().map(|keyword| &keyword.value)) {
+            values.push(self.expr(arg)?);
+        }
+        let d = &self.c.defs[def];
+        let names: Vec<_> = d.params.iter().map(|p| p.name.to_string()).collect();
+        let has_default: Vec<_> = d.params.iter().map(|p| p.has_default).collect();
+        let bound = bind(
+            d.name,
+            &names,
+            d.posonly,
+            d.positional,
+            &has_default,
+            (0..args.len()).collect(),
+            keywords.iter()
+                .enumerate()
+                .map(|(i, keyword)| (Rc::from(keyword.arg.unwrap()), args.len() + i))
+                .collect(),
+        );
+        let bound = match bound {
+            Ok(bound) => bound,
+            Err(message) => {
+                let error = raising(Exception::new("TypeError", message));
+                return Ok(self.after(callee, values, error));
+            }
+        };
+        // The type of each parameter's argument or default value.
+        let mut types = Vec::new();
+        let mut defaults = Vec::new();
+        for (i, arg) in bound.iter().enumerate() {
+            match arg {
+                Some(j) => types.push(self.c.resolve(&values[*j].ty)),
+                None => {
+                    let id = self.var(0, VarKey::Default(def, i));
+                    let default = self.read_var(0, id);
+                    types.push(self.c.resolve(&default.ty));
+                    defaults.push((i, default));
+                }
+            }
+        }
+        if types.contains(&Type::Never) {
+            let never = raising(Exception::new(
+                "SystemError",
+                "unreachable code was reached",
+            ));
+            return Ok(self.after(callee, values, never));
+        }
+        let id = self.c.callee(def, &types);
+        self.uses.push(id);
+        // Widen the parameters to what they are given.
+        let mut params = Vec::new();
+        for (i, ty) in types.iter().enumerate() {
+            let var = &self.c.instances[id].vars[i];
+            let param = self.c.resolve(&var.ty);
+            if var.pinned {
+                if !compatible(ty, &param) {
+                    let span = match bound[i] {
+                        Some(j) if j < args.len() => args[j].span,
+                        Some(j) => keywords[j - args.len()].span,
+                        None => self.c.defs[def].span,
+                    };
+                    return Err(Diagnostic::new(span, format!(
+                        "mismatched types: expected `{}`, found `{}`",
+                        self.c.type_name(&param),
+                        self.c.type_name(ty),
+                    )));
+                }
+                params.push(param);
+            } else {
+                let joined = self.c.join(&param, ty);
+                if joined != param {
+                    self.c.instances[id].vars[i].ty = joined.clone();
+                    self.c.changed = true;
+                }
+                params.push(joined);
+            }
+        }
+        // The arguments in the order they are evaluated, then the defaults.
+        let mut codes = Vec::new();
+        for (j, value) in values.into_iter().enumerate() {
+            let i = bound.iter().position(|arg| *arg == Some(j)).unwrap();
+            codes.push((i, self.coerce(value, &params[i])));
+        }
+        for (i, default) in defaults {
+            codes.push((i, self.coerce(default, &params[i])));
+        }
+        let ty = self.c.resolve(&self.c.instances[id].ret);
+        fn call<T: Unboxed>(callee: Code, codes: Vec<(usize, Code)>, id: usize)
+            -> Code
+        {
+            T::code(Box::new(move |f| {
+                callee.run(f)?;
+                let compiled = &f.rt.instances[id];
+                let mut slots = Slots::new(&compiled.layout);
+                for (i, code) in &codes {
+                    code.store(f, &mut slots, compiled.params[*i].0.index)?;
+                }
+                let slots = f.rt.enter(id, slots)?;
+                Ok(T::slots(&slots)[compiled.ret.index].clone())
+            }))
+        }
+        let code = match ty.repr() {
+            Repr::Int => call::<Int>(callee, codes, id),
+            Repr::Float => call::<f64>(callee, codes, id),
+            Repr::Bool => call::<bool>(callee, codes, id),
+            Repr::Value => call::<Value>(callee, codes, id),
+        };
+        Ok(Typed { ty, code })
+    }
+
+    // Code evaluating the callee and arguments of a call, and then `result`.
+    fn after(&mut self, callee: Code, values: Vec<Typed>, result: Typed)
+        -> Typed
+    {
+        let values: Vec<_> = values.into_iter().map(|v| v.code).collect();
+        let ty = result.ty;
+        let result = result.code;
+        Typed {
+            ty,
+            code: super::expr::then(
+                Box::new(move |f| {
+                    callee.run(f)?;
+                    for value in &values {
+                        value.run(f)?;
+                    }
+                    Ok(())
+                }),
+                result,
+            ),
+        }
+    }
+
+    // A call of a boxed value.
+    fn call_dynamic(
+        &mut self,
+        callee: Typed,
+        args: &'a [Expr<'a>],
+        keywords: &'a [KeywordArg<'a>],
+    ) -> Result<Typed> {
+        let callee = self.dynamic(callee);
+        let arguments = self.arguments(args, keywords)?;
+        let code = Code::Value(Box::new(move |f: &mut Frame<'_>| {
+            let callee = callee(f)?;
+            let (args, keywords) = arguments(f, &callee)?;
+            f.rt.call(&callee, args, keywords)
+        }));
+        Ok(Typed { ty: Type::Dynamic, code })
+    }
+
+    /// A method call on an `object`: a call of an attribute of an instance
+    /// or class, or of a method of a built-in type.
+    pub(super) fn call_attribute(
+        &mut self,
+        receiver: Typed,
+        name: &'a str,
+        args: &'a [Expr<'a>],
+        keywords: &'a [KeywordArg<'a>],
+    ) -> Result<Typed> {
+        let keyword = keywords.first();
+        if let (Some(keyword), "sort" | "split" | "rsplit") = (keyword, name) {
+            return Err(Diagnostic::new(keyword.span, format!(
+                "keyword arguments to `{}()` of an `object` can't be compiled \
+                    yet",
+                name,
+            )));
+        }
+        let receiver = self.boxed(receiver);
+        let arguments = self.arguments(args, keywords)?;
+        let name = name.to_string();
+        let code = Code::Value(Box::new(move |f: &mut Frame<'_>| {
+            let receiver = receiver(f)?;
+            if let Value::Object(_) | Value::Type(_) = receiver {
+                let method = runtime::get_attribute(&receiver, &name)?;
+                let (args, keywords) = arguments(f, &method)?;
+                return f.rt.call(&method, args, keywords);
+            }
+            let (args, keywords) = arguments(f, &receiver)?;
+            if !keywords.is_empty() {
+                return Err(Exception::new("TypeError", format!(
+                    "{}.{}() takes no keyword arguments",
+                    receiver.type_name(),
+                    name,
+                )));
+            }
+            runtime::call_method(&receiver, &name, args)
+        }));
+        Ok(Typed { ty: Type::Dynamic, code })
+    }
+
+    /// `super().name(...)` in a method: a call of the attribute of the
+    /// nearest base class of the method's class that has it, bound to the
+    /// method's first argument.
+    pub(super) fn super_method(
+        &mut self,
+        name: &'a str,
+        args: &'a [Expr<'a>],
+        keywords: &'a [KeywordArg<'a>],
+        span: Span,
+    ) -> Result<Typed> {
+        let def = &self.c.defs[self.def];
+        let receiver = match def.params.first() {
+            Some(param) if def.method => param.name,
+            _ => {
+                return Err(Diagnostic::new(
+                    span,
+                    "`super()` outside a method can't be compiled yet",
+                ))
+            }
+        };
+        if !def.uses_class {
+            self.c.defs[self.def].uses_class = true;
+            self.c.changed = true;
+        }
+        // The class's cell follows the others.
+        let def = &self.c.defs[self.def];
+        let cell = def.cells.len() + def.free.len();
+        let receiver = match self.lookup(receiver) {
+            Lookup::Var(owner, id) => self.read_var(owner, id),
+            _ => unreachable!("parameters are variables"),
+        };
+        let receiver = self.boxed(receiver);
+        let arguments = self.arguments(args, keywords)?;
+        let name = name.to_string();
+        let code = Code::Value(Box::new(move |f: &mut Frame<'_>| {
+            let class = f.slots.cells[cell].borrow().clone().unwrap();
+            let receiver = receiver(f)?;
+            let method = match runtime::super_attribute(&class, &receiver, &name)? {
+                Some(method) => method,
+                None if name == "__init__" => {
+                    // `object.__init__`, which takes no other arguments
+                    let (args, keywords) = arguments(f, &Value::None)?;
+                    if args.is_empty() && keywords.is_empty() {
+                        return Ok(Value::None);
+                    }
+                    return Err(Exception::new(
+                        "TypeError",
+                        "object.__init__() takes exactly one argument (the \
+                            instance to initialize)",
+                    ));
+                }
+                None => {
+                    return Err(Exception::new("AttributeError", format!(
+                        "'super' object has no attribute '{}'",
+                        name,
+                    )))
+                }
+            };
+            let (args, keywords) = arguments(f, &method)?;
+            f.rt.call(&method, args, keywords)
+        }));
+        Ok(Typed { ty: Type::Dynamic, code })
+    }
+
+    /// Code evaluating the arguments of a call of a boxed value, given the
+    /// value it calls.
+    pub(super) fn arguments(
+        &mut self,
+        args: &'a [Expr<'a>],
+        keywords: &'a [KeywordArg<'a>],
+    ) -> Result<Args> {
+        let mut items = Vec::new();
+        for arg in args {
+            items.push(match &arg.kind {
+                ExprKind::Starred(value, _) => {
+                    let value = self.expr(value)?;
+                    let element = self.element(&value.ty);
+                    self.c.escape(&element);
+                    Item::Starred(self.boxed(value))
+                }
+                _ => {
+                    let value = self.expr(arg)?;
+                    Item::Single(self.dynamic(value))
+                }
+            });
+        }
+        let mut named: Vec<(Option<Rc<str>>, Fun<Value>)> = Vec::new();
+        for keyword in keywords {
+            let value = self.expr(&keyword.value)?;
+            if keyword.arg.is_none() {
+                if let Type::Dict(id) = self.c.resolve(&value.ty) {
+                    let value = self.c.value(id);
+                    self.c.escape(&value);
+                }
+            }
+            named.push((keyword.arg.map(Rc::from), self.dynamic(value)));
+        }
+        Ok(Box::new(move |f, callee| {
+            let args = collect(&items, f)?;
+            let mut keywords = Vec::new();
+            for (name, value) in &named {
+                let value = value(f)?;
+                match name {
+                    Some(name) => keywords.push((name.clone(), value)),
+                    None => keywords.extend(mapping(callee, value)?),
+                }
+            }
+            Ok((args, keywords))
+        }))
+    }
+}
+
+/// Code evaluating the positional and keyword arguments of a call
+pub(super) type Args = Box<
+    dyn Fn(&mut Frame<'_>, &Value) -> runtime::Eval<(Vec<Value>, Keywords)>,
+>;
+
+type Keywords = Vec<(Rc<str>, Value)>;
+
+// The keyword arguments of `**value` in a call.
+fn mapping(callee: &Value, value: Value) -> runtime::Eval<Keywords> {
+    let name = match callee {
+        Value::Function(function) => function.name.clone(),
+        _ => "function".to_string(),
+    };
+    let dict = match value {
+        Value::Dict(dict) => dict,
+        other => {
+            return Err(Exception::new("TypeError", format!(
+                "{}() argument after ** must be a mapping, not {}",
+                name,
+                other.type_name(),
+            )))
+        }
+    };
+    let items = dict.borrow().items();
+    items
+        .into_iter()
+        .map(|item| match item {
+            Value::Tuple(pair) => match &pair[0] {
+                Value::Str(key) => Ok((key.clone(), pair[1].clone())),
+                _ => Err(Exception::new("TypeError", "keywords must be strings")),
+            },
+            _ => unreachable!("dictionary items are pairs"),
+        })
+        .collect()
+}
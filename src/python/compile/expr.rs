@@ -0,0 +1,1469 @@
+// Python expression compilation
+//
+//! Type inference and code generation for expressions, and the conversions
+//! between representations that code of different types is joined with.
+
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use super::super::runtime::{self, Eval, Exception, Function, Int, Value};
+use super::super::{BinaryOp, BoolOp, CmpOp, Constant, Expr, ExprKind, UnaryOp};
+use super::{
+    check, unbox_bool, unbox_float, unbox_int, Code, Flow, Frame, Fun, Repr,
+    Result, Store, Type, Typed, Unboxed, VarKey, Walker,
+};
+use crate::{Diagnostic, Span};
+
+// How a name used in a function resolves
+pub(super) enum Lookup {
+    /// A variable of an instance
+    Var(usize, usize),
+    Builtin,
+    /// A variable of an enclosing function, by the index of its cell in the
+    /// frame
+    Free(usize),
+    Undefined,
+}
+
+// A constant value.
+pub(super) fn constant<T: Unboxed>(value: T) -> Code {
+    T::code(Box::new(move |_| Ok(value.clone())))
+}
+
+// Code that raises an exception.
+pub(super) fn raising(exception: Exception) -> Typed {
+    Typed {
+        ty: Type::Never,
+        code: Code::Value(Box::new(move |_| Err(exception.clone()))),
+    }
+}
+
+// Code of a representation that runs code of type `Never`.
+fn never(code: Code, repr: Repr) -> Code {
+    fn diverge<T: Unboxed>(code: Code) -> Code {
+        T::code(Box::new(move |f| {
+            code.run(f)?;
+            Err(Exception::new("SystemError", "unreachable code was reached"))
+        }))
+    }
+    match repr {
+        Repr::Int => diverge::<Int>(code),
+        Repr::Float => diverge::<f64>(code),
+        Repr::Bool => diverge::<bool>(code),
+        Repr::Value => diverge::<Value>(code),
+    }
+}
+
+// Code applying a function to the value of other code.
+pub(super) fn map<A: Unboxed, B: Unboxed>(
+    code: Fun<A>,
+    function: impl Fn(A) -> Eval<B> + 'static,
+) -> Code {
+    B::code(Box::new(move |f| function(code(f)?)))
+}
+
+// Code of a boxed value of a type that is unboxed or checked.
+pub(super) fn unbox(code: Fun<Value>, ty: &Type) -> Code {
+    match ty {
+        Type::Int => Code::Int(Box::new(move |f| unbox_int(&code(f)?))),
+        Type::Float => Code::Float(Box::new(move |f| unbox_float(&code(f)?))),
+        Type::Bool => Code::Bool(Box::new(move |f| unbox_bool(&code(f)?))),
+        Type::Str | Type::None => {
+            let ty = ty.clone();
+            Code::Value(Box::new(move |f| check(code(f)?, &ty)))
+        }
+        _ => Code::Value(code),
+    }
+}
+
+// Whether a value of one type may be given where another is declared.
+pub(super) fn compatible(from: &Type, to: &Type) -> bool {
+    match (from, to) {
+        (Type::Never, _) | (Type::Dynamic, _) | (_, Type::Dynamic) => true,
+        (Type::Int, Type::Float)
+        | (Type::Bool, Type::Float)
+        | (Type::Bool, Type::Int) => true,
+        (from, to) => from == to,
+    }
+}
+
+// `a op b` for `int`s or `bool`s.
+fn compare_ints(op: CmpOp, a: &Int, b: &Int) -> bool {
+    match op {
+        CmpOp::Eq => a == b,
+        CmpOp::NotEq => a != b,
+        CmpOp::Lt => a < b,
+        CmpOp::LtE => a <= b,
+        CmpOp::Gt => a > b,
+        _ => a >= b,
+    }
+}
+
+fn compare_floats(op: CmpOp, a: f64, b: f64) -> bool {
+    match op {
+        CmpOp::Eq => a == b,
+        CmpOp::NotEq => a != b,
+        CmpOp::Lt => a < b,
+        CmpOp::LtE => a <= b,
+        CmpOp::Gt => a > b,
+        _ => a >= b,
+    }
+}
+
+impl<'c, 'a> Walker<'c, 'a> {
+    /// Convert code to the representation of another type, which its type
+    /// must be included in (or statically compatible with).
+    pub(super) fn coerce(&mut self, typed: Typed, to: &Type) -> Code {
+        let from = self.c.resolve(&typed.ty);
+        let to = self.c.resolve(to);
+        if from == to {
+            return typed.code;
+        }
+        if to == Type::Dynamic {
+            self.c.escape(&from);
+            return Code::Value(self.boxed(Typed { ty: from, code: typed.code }));
+        }
+        if from == Type::Never {
+            return never(typed.code, to.repr());
+        }
+        match (typed.code, to.repr()) {
+            (Code::Int(code), Repr::Float) => map(code, |v: Int| v.to_f64()),
+            (Code::Bool(code), Repr::Int) => map(code, |v| Ok(Int::Small(v as i64))),
+            (Code::Bool(code), Repr::Float) => map(code, |v| Ok(v as i64 as f64)),
+            (Code::Value(code), Repr::Value) if from != Type::Dynamic => {
+                Code::Value(code)
+            }
+            (code, _) => {
+                let boxed = self.boxed(Typed { ty: from, code });
+                unbox(boxed, &to)
+            }
+        }
+    }
+
+    /// Box the value of code (without changing its type).
+    pub(super) fn boxed(&mut self, typed: Typed) -> Fun<Value> {
+        match typed.code {
+            Code::Int(code) => Box::new(move |f| code(f).map(Value::Int)),
+            Code::Float(code) => Box::new(move |f| code(f).map(Value::Float)),
+            Code::Bool(code) => Box::new(move |f| code(f).map(Value::Bool)),
+            Code::Value(code) => match self.c.resolve(&typed.ty) {
+                Type::Function(def) => {
+                    let id = self.c.generic(def);
+                    self.uses.push(id);
+                    let name = self.c.defs[def].name.to_string();
+                    let value = Value::Function(Rc::new(Function {
+                        name,
+                        id,
+                        closure: Vec::new(),
+                    }));
+                    Box::new(move |f| {
+                        code(f)?;
+                        Ok(value.clone())
+                    })
+                }
+                _ => code,
+            },
+        }
+    }
+
+    /// Box the value of code as an `object`, which may be used in any way.
+    pub(super) fn dynamic(&mut self, typed: Typed) -> Fun<Value> {
+        self.c.escape(&typed.ty);
+        self.boxed(typed)
+    }
+
+    /// The truth value of the value of code.
+    pub(super) fn truth(&mut self, typed: Typed) -> Fun<bool> {
+        match typed.code {
+            Code::Int(code) => Box::new(move |f| Ok(!code(f)?.is_zero())),
+            Code::Float(code) => Box::new(move |f| Ok(code(f)? != 0.0)),
+            Code::Bool(code) => code,
+            Code::Value(code) => Box::new(move |f| Ok(code(f)?.truthy())),
+        }
+    }
+
+    // --- Variables
+
+    /// Find the variable a name refers to.
+    pub(super) fn lookup(&mut self, name: &'a str) -> Lookup {
+        for scope in self.scopes.iter().rev() {
+            if let Some(&(_, id)) = scope.iter().rev().find(|(n, _)| *n == name) {
+                return Lookup::Var(self.id, id);
+            }
+        }
+        let def = &self.c.defs[self.def];
+        if self.def != 0 && def.locals.contains(name) {
+            let id = self.var(self.id, VarKey::Name(name));
+            return Lookup::Var(self.id, id);
+        }
+        if !def.globals.contains(name) {
+            let mut parent = def.parent;
+            while let Some(def) = parent.filter(|&def| def != 0) {
+                if self.c.defs[def].locals.contains(name) {
+                    return Lookup::Free(self.capture(def, name));
+                }
+                parent = self.c.defs[def].parent;
+            }
+        }
+        if self.c.defs[0].locals.contains(name) {
+            let id = self.var(0, VarKey::Name(name));
+            return Lookup::Var(0, id);
+        }
+        match super::builtins::is_builtin(name) {
+            true => Lookup::Builtin,
+            false => Lookup::Undefined,
+        }
+    }
+
+    // Note that the function being compiled uses a variable of an enclosing
+    // function `owner`, which the functions between them pass on, returning
+    // the index of its cell in the frame.
+    fn capture(&mut self, owner: usize, name: &'a str) -> usize {
+        if !self.c.defs[owner].cells.contains(&name) {
+            self.c.defs[owner].cells.push(name);
+            self.c.changed = true;
+        }
+        let mut def = self.def;
+        while def != owner {
+            if !self.c.defs[def].free.contains(&name) {
+                self.c.defs[def].free.push(name);
+                self.c.changed = true;
+            }
+            def = self.c.defs[def].parent.unwrap();
+        }
+        self.cell_index(name).unwrap()
+    }
+
+    /// The index in the frame of the cell holding a variable of the
+    /// function being compiled or of an enclosing function.
+    pub(super) fn cell_index(&self, name: &str) -> Option<usize> {
+        let def = &self.c.defs[self.def];
+        match def.cells.iter().position(|cell| *cell == name) {
+            Some(index) => Some(index),
+            None => {
+                let index = def.free.iter().position(|free| *free == name)?;
+                Some(def.cells.len() + index)
+            }
+        }
+    }
+
+    // The cell of a variable of the instance, if nested functions use it.
+    // Its type is widened to `object` unless it is annotated, since they
+    // may assign anything to it.
+    fn cell(&mut self, owner: usize, id: usize) -> Option<usize> {
+        if owner != self.id || self.def == 0 {
+            return None;
+        }
+        let var = &self.c.instances[owner].vars[id];
+        let cell = match var.key {
+            VarKey::Name(name) => self.c.defs[self.def].cells.iter()
+                .position(|cell| *cell == name)?,
+            _ => return None,
+        };
+        if !var.pinned && self.c.resolve(&var.ty) != Type::Dynamic {
+            let ty = var.ty.clone();
+            self.c.escape(&ty);
+            self.c.instances[owner].vars[id].ty = Type::Dynamic;
+            self.c.changed = true;
+        }
+        Some(cell)
+    }
+
+    /// Whether a variable of the instance is kept in a cell.
+    pub(super) fn is_cell(&mut self, owner: usize, id: usize) -> bool {
+        self.cell(owner, id).is_some()
+    }
+
+    // The error for using a name that isn't a variable.
+    pub(super) fn name_error(&self, name: &str, lookup: &Lookup, span: Span)
+        -> Result<Typed>
+    {
+        match lookup {
+            Lookup::Builtin => match runtime::builtin(name) {
+                Some(value) => Ok(Typed {
+                    ty: Type::Dynamic,
+                    code: constant(value),
+                }),
+                None => Err(Diagnostic::new(span, format!(
+                    "built-in function `{}` can only be called directly",
+                    name,
+                ))),
+            },
+            _ => Ok(raising(Exception::new(
+                "NameError",
+                format!("name '{}' is not defined", name),
+            ))),
+        }
+    }
+
+    /// Read a variable.
+    pub(super) fn read_var(&mut self, owner: usize, id: usize) -> Typed {
+        let cell = self.cell(owner, id);
+        let var = &self.c.instances[owner].vars[id];
+        let ty = self.c.resolve(&var.ty);
+        let slot = var.slot;
+        let name = match var.key {
+            VarKey::Name(name) | VarKey::Comprehension(name, _) => name,
+            _ => "",
+        };
+        let error = match owner == self.id && self.bound.contains(&id) {
+            true => None,
+            false if owner == 0 => Some(Exception::new(
+                "NameError",
+                format!("name '{}' is not defined", name),
+            )),
+            false => Some(Exception::new(
+                "UnboundLocalError",
+                format!(
+                    "cannot access local variable '{}' where it is not \
+                        associated with a value",
+                    name,
+                ),
+            )),
+        };
+        if let Some(cell) = cell {
+            let error = error.unwrap_or_else(|| Exception::new(
+                "UnboundLocalError",
+                format!(
+                    "cannot access local variable '{}' where it is not \
+                        associated with a value",
+                    name,
+                ),
+            ));
+            return read_cell(cell, error, ty);
+        }
+        if ty == Type::Never {
+            return raising(error.unwrap_or_else(|| {
+                Exception::new("SystemError", "unreachable code was reached")
+            }));
+        }
+        let global = owner == 0;
+        let code = match ty.repr() {
+            Repr::Int => read::<Int>(global, slot, id, error),
+            Repr::Float => read::<f64>(global, slot, id, error),
+            Repr::Bool => read::<bool>(global, slot, id, error),
+            Repr::Value => read::<Value>(global, slot, id, error),
+        };
+        Typed { ty, code }
+    }
+
+    /// Assign a value to a variable.
+    pub(super) fn assign_var(
+        &mut self,
+        owner: usize,
+        id: usize,
+        value: Typed,
+        span: Span,
+    ) -> Result<Fun<()>> {
+        let cell = self.cell(owner, id);
+        let from = self.c.resolve(&value.ty);
+        let var = &self.c.instances[owner].vars[id];
+        let ty = self.c.resolve(&var.ty);
+        if var.pinned {
+            if !compatible(&from, &ty) {
+                return Err(Diagnostic::new(span, format!(
+                    "mismatched types: expected `{}`, found `{}`",
+                    self.c.type_name(&ty),
+                    self.c.type_name(&from),
+                )));
+            }
+        } else {
+            let joined = self.c.join(&ty, &from);
+            if joined != ty {
+                self.c.instances[owner].vars[id].ty = joined;
+                self.c.changed = true;
+            }
+        }
+        let var = &self.c.instances[owner].vars[id];
+        let ty = self.c.resolve(&var.ty);
+        let slot = var.slot;
+        if owner == self.id {
+            self.bound.insert(id);
+        }
+        if let Some(cell) = cell {
+            let code = self.coerce(value, &ty);
+            return Ok(write_cell(self.boxed(Typed { ty, code }), cell));
+        }
+        let global = owner == 0;
+        Ok(match self.coerce(value, &ty) {
+            Code::Int(code) => write::<Int>(code, global, slot, id),
+            Code::Float(code) => write::<f64>(code, global, slot, id),
+            Code::Bool(code) => write::<bool>(code, global, slot, id),
+            Code::Value(code) => write::<Value>(code, global, slot, id),
+        })
+    }
+
+    /// Read a variable of an enclosing function.
+    pub(super) fn read_free(&mut self, cell: usize, name: &str) -> Typed {
+        let error = Exception::new("NameError", format!(
+            "cannot access free variable '{}' where it is not associated \
+                with a value in enclosing scope",
+            name,
+        ));
+        read_cell(cell, error, Type::Dynamic)
+    }
+
+    /// Assign a value to a name, a variable of the function or (declared
+    /// `nonlocal`) of an enclosing function.
+    pub(super) fn assign_name(&mut self, name: &'a str, value: Typed, span: Span)
+        -> Result<Fun<()>>
+    {
+        match self.lookup(name) {
+            Lookup::Var(owner, id) => self.assign_var(owner, id, value, span),
+            Lookup::Free(cell) => {
+                let value = self.dynamic(value);
+                Ok(write_cell(value, cell))
+            }
+            _ => unreachable!("assigned names are variables"),
+        }
+    }
+
+    /// A hidden variable of the instance for a construct.
+    pub(super) fn temp(&mut self, span: Span, tag: u8) -> usize {
+        self.var(self.id, VarKey::Temp(span, tag))
+    }
+
+    /// Store a value in a hidden variable, returning the code storing it and
+    /// code reading it.
+    pub(super) fn stash(&mut self, value: Typed, span: Span, tag: u8)
+        -> Result<(Fun<()>, Typed)>
+    {
+        let id = self.temp(span, tag);
+        let store = self.assign_var(self.id, id, value, span)?;
+        Ok((store, self.read_var(self.id, id)))
+    }
+
+    /// Code storing a boxed value in a hidden variable, unboxed to its type,
+    /// and code reading it.
+    pub(super) fn stash_boxed(&mut self, ty: Type, span: Span, tag: u8)
+        -> Result<(Store, Typed)>
+    {
+        let id = self.temp(span, tag);
+        let unknown = Typed {
+            ty: ty.clone(),
+            code: Code::Value(Box::new(|_| Ok(Value::None))),
+        };
+        // Only the type assigning it gives the variable is needed.
+        let _ = self.assign_var(self.id, id, unknown, span)?;
+        let var = &self.c.instances[self.id].vars[id];
+        let (slot, ty) = (var.slot, self.c.resolve(&var.ty));
+        let global = self.id == 0;
+        let store: Store =
+            Box::new(move |f, value| {
+                let mut globals;
+                let slots = match global {
+                    true => {
+                        globals = f.rt.globals.borrow_mut();
+                        &mut *globals
+                    }
+                    false => &mut f.slots,
+                };
+                slots.bound[id] = true;
+                let slot = super::Slot { repr: ty.repr(), index: slot };
+                super::unbox_into(value, &ty, slots, slot)
+            });
+        Ok((store, self.read_var(self.id, id)))
+    }
+
+    // --- Expressions
+
+    /// Compile an expression.
+    pub(super) fn expr(&mut self, expr: &'a Expr<'a>) -> Result<Typed> {
+        let span = expr.span;
+        match &expr.kind {
+            ExprKind::Constant(value) => self.constant(value, span),
+            ExprKind::Name(name, _) => match self.lookup(name) {
+                Lookup::Var(owner, id) => Ok(self.read_var(owner, id)),
+                Lookup::Free(cell) => Ok(self.read_free(cell, name)),
+                lookup => self.name_error(name, &lookup, span),
+            },
+            ExprKind::BinOp(left, op, right) => {
+                let left = self.expr(left)?;
+                let exponent = match &right.kind {
+                    ExprKind::Constant(Constant::Int(value)) => Some(*value),
+                    _ => None,
+                };
+                let right = self.expr(right)?;
+                Ok(self.binary(left, *op, right, exponent, span))
+            }
+            ExprKind::UnaryOp(UnaryOp::Not, _)
+            | ExprKind::Compare(..) => {
+                let code = self.cond(expr)?;
+                Ok(Typed { ty: Type::Bool, code: Code::Bool(code) })
+            }
+            ExprKind::UnaryOp(op, value) => {
+                let value = self.expr(value)?;
+                Ok(self.unary(*op, value))
+            }
+            ExprKind::BoolOp(op, values) => self.bool_op(*op, values),
+            ExprKind::IfExp(test, body, orelse) => {
+                let test = self.cond(test)?;
+                let saved = self.bound.clone();
+                let body = self.expr(body)?;
+                let orelse = self.expr(orelse)?;
+                self.bound = saved;
+                let ty = self.c.join(&body.ty, &orelse.ty);
+                let body = self.coerce(body, &ty);
+                let orelse = self.coerce(orelse, &ty);
+                Ok(Typed { ty, code: choose(test, body, orelse) })
+            }
+            ExprKind::NamedExpr(target, value) => {
+                let name = match target.kind {
+                    ExprKind::Name(name, _) => name,
+                    _ => unreachable!("`:=` assigns a name"),
+                };
+                let value = self.expr(value)?;
+                let (owner, id) = match self.lookup(name) {
+                    Lookup::Var(owner, id) => (owner, id),
+                    Lookup::Free(cell) => {
+                        let store = self.assign_name(name, value, span)?;
+                        let read = self.read_free(cell, name);
+                        return Ok(Typed {
+                            ty: read.ty,
+                            code: then(store, read.code),
+                        });
+                    }
+                    _ => {
+                        let id = self.var(self.id, VarKey::Name(name));
+                        (self.id, id)
+                    }
+                };
+                let store = self.assign_var(owner, id, value, span)?;
+                let read = self.read_var(owner, id);
+                Ok(Typed { ty: read.ty, code: then(store, read.code) })
+            }
+            ExprKind::Call(func, args, keywords) => {
+                self.call(func, args, keywords, span)
+            }
+            ExprKind::Lambda(..) => {
+                let def = self.c.def_ids[&span];
+                self.define(def, None)
+            }
+            ExprKind::Subscript(value, index, _) => {
+                let value = self.expr(value)?;
+                if let ExprKind::Slice(lower, upper, step) = &index.kind {
+                    return self.slice(value, [lower, upper, step], span);
+                }
+                let constant = constant_index(index);
+                let index = self.expr(index)?;
+                Ok(self.subscript(value, index, constant))
+            }
+            ExprKind::List(items, _) => {
+                let site = self.c.site(span, 0);
+                let (items, _) = self.items(items, Some(site))?;
+                let code = Code::Value(Box::new(move |f| {
+                    Ok(Value::list(collect(&items, f)?))
+                }));
+                Ok(Typed { ty: Type::List(site), code })
+            }
+            ExprKind::Tuple(items, _) => {
+                let (items, types) = self.items(items, None)?;
+                let starred = items.iter()
+                    .any(|item| matches!(item, Item::Starred(_)));
+                let code = Code::Value(Box::new(move |f| {
+                    Ok(Value::tuple(collect(&items, f)?))
+                }));
+                let ty = match starred {
+                    true => {
+                        for ty in &types {
+                            self.c.escape(ty);
+                        }
+                        Type::Dynamic
+                    }
+                    false => Type::Tuple(types),
+                };
+                Ok(Typed { ty, code })
+            }
+            ExprKind::Dict(keys, values) => self.dict(keys, values, span),
+            ExprKind::ListComp(elt, generators)
+            | ExprKind::GeneratorExp(elt, generators) => {
+                let site = self.c.site(span, 0);
+                let (init, list) = self.stash(
+                    Typed {
+                        ty: Type::List(site),
+                        code: Code::Value(Box::new(|_| Ok(Value::list(Vec::new())))),
+                    },
+                    span,
+                    0,
+                )?;
+                let append = match list.code {
+                    Code::Value(list) => list,
+                    _ => unreachable!(),
+                };
+                let run = self.comprehension(generators, span, |walker| {
+                    let item = walker.expr(elt)?;
+                    walker.c.store_value(site, &item.ty);
+                    let item = walker.boxed(item);
+                    Ok(Box::new(move |f| {
+                        let item = item(f)?;
+                        if let Value::List(items) = append(f)? {
+                            items.borrow_mut().push(item);
+                        }
+                        Ok(Flow::Next)
+                    }))
+                })?;
+                let temp = self.temp(span, 0);
+                let list = self.read_var(self.id, temp);
+                Ok(Typed {
+                    ty: Type::List(site),
+                    code: then(Box::new(move |f| {
+                        init(f)?;
+                        run(f).map(drop)
+                    }), list.code),
+                })
+            }
+            ExprKind::DictComp(key, value, generators) => {
+                let site = self.c.site(span, 0);
+                let (init, dict) = self.stash(
+                    Typed {
+                        ty: Type::Dict(site),
+                        code: Code::Value(Box::new(|_| {
+                            Ok(Value::dict(Default::default()))
+                        })),
+                    },
+                    span,
+                    0,
+                )?;
+                let insert = match dict.code {
+                    Code::Value(dict) => dict,
+                    _ => unreachable!(),
+                };
+                let run = self.comprehension(generators, span, |walker| {
+                    let key = walker.expr(key)?;
+                    let value = walker.expr(value)?;
+                    walker.c.store_key(site, &key.ty);
+                    walker.c.store_value(site, &value.ty);
+                    let key = walker.boxed(key);
+                    let value = walker.boxed(value);
+                    Ok(Box::new(move |f| {
+                        let key = key(f)?;
+                        let value = value(f)?;
+                        if let Value::Dict(dict) = insert(f)? {
+                            dict.borrow_mut().insert(key, value)?;
+                        }
+                        Ok(Flow::Next)
+                    }))
+                })?;
+                let temp = self.temp(span, 0);
+                let dict = self.read_var(self.id, temp);
+                Ok(Typed {
+                    ty: Type::Dict(site),
+                    code: then(Box::new(move |f| {
+                        init(f)?;
+                        run(f).map(drop)
+                    }), dict.code),
+                })
+            }
+            ExprKind::JoinedStr(parts) => {
+                let mut codes = Vec::new();
+                for part in parts {
+                    codes.push(self.fstring_part(part)?);
+                }
+                let code = Code::Value(Box::new(move |f| {
+                    let mut string = String::new();
+                    for code in &codes {
+                        string.push_str(&code(f)?);
+                    }
+                    Ok(Value::str(&string))
+                }));
+                Ok(Typed { ty: Type::Str, code })
+            }
+            ExprKind::FormattedValue(..) => {
+                let code = self.fstring_part(expr)?;
+                let code = Code::Value(Box::new(move |f| {
+                    Ok(Value::str(&code(f)?))
+                }));
+                Ok(Typed { ty: Type::Str, code })
+            }
+            ExprKind::Attribute(value, name, _) => {
+                let value = self.expr(value)?;
+                self.attribute(value, name, span)
+            }
+            // Sets are `object`s, so their items may be used in any way.
+            ExprKind::Set(items) => {
+                let (items, types) = self.items(items, None)?;
+                for ty in &types {
+                    self.c.escape(ty);
+                }
+                let code = Code::Value(Box::new(move |f| {
+                    let mut set = runtime::Set::default();
+                    for item in collect(&items, f)? {
+                        set.insert(item)?;
+                    }
+                    Ok(Value::set(set))
+                }));
+                Ok(Typed { ty: Type::Dynamic, code })
+            }
+            ExprKind::SetComp(elt, generators) => {
+                let (init, set) = self.stash(
+                    Typed {
+                        ty: Type::Dynamic,
+                        code: Code::Value(Box::new(|_| {
+                            Ok(Value::set(Default::default()))
+                        })),
+                    },
+                    span,
+                    0,
+                )?;
+                let insert = match set.code {
+                    Code::Value(set) => set,
+                    _ => unreachable!(),
+                };
+                let run = self.comprehension(generators, span, |walker| {
+                    let item = walker.expr(elt)?;
+                    let item = walker.dynamic(item);
+                    Ok(Box::new(move |f| {
+                        let item = item(f)?;
+                        if let Value::Set(set) = insert(f)? {
+                            set.borrow_mut().insert(item)?;
+                        }
+                        Ok(Flow::Next)
+                    }))
+                })?;
+                let temp = self.temp(span, 0);
+                let set = self.read_var(self.id, temp);
+                Ok(Typed {
+                    ty: Type::Dynamic,
+                    code: then(Box::new(move |f| {
+                        init(f)?;
+                        run(f).map(drop)
+                    }), set.code),
+                })
+            }
+            ExprKind::Await(_) | ExprKind::Yield(_) | ExprKind::YieldFrom(_) => {
+                Err(super::generator::misplaced(expr))
+            }
+            ExprKind::Starred(..) => Err(Diagnostic::new(
+                span,
+                "can't use starred expression here",
+            )),
+            ExprKind::Slice(..) => Err(Diagnostic::new(
+                span,
+                "slices can only be used in subscripts",
+            )),
+        }
+    }
+
+    fn constant(&mut self, value: &Constant, span: Span) -> Result<Typed> {
+        Ok(match value {
+            Constant::None => Typed {
+                ty: Type::None,
+                code: constant(Value::None),
+            },
+            Constant::Bool(value) => Typed {
+                ty: Type::Bool,
+                code: constant(*value),
+            },
+            Constant::Int(value) => Typed {
+                ty: Type::Int,
+                code: constant(Int::from(*value)),
+            },
+            Constant::Float(value) => Typed {
+                ty: Type::Float,
+                code: constant(*value),
+            },
+            Constant::Str(value) => Typed {
+                ty: Type::Str,
+                code: constant(Value::str(value)),
+            },
+            _ => {
+                return Err(Diagnostic::new(
+                    span,
+                    "complex, bytes and `...` constants can't be compiled yet",
+                ))
+            }
+        })
+    }
+
+    // The elements of a list or tuple display, boxed, and the types of those
+    // that aren't starred.  Their types are added to a list's container.
+    fn items(&mut self, items: &'a [Expr<'a>], site: Option<usize>)
+        -> Result<(Vec<Item>, Vec<Type>)>
+    {
+        let mut codes = Vec::new();
+        let mut types = Vec::new();
+        for item in items {
+            match &item.kind {
+                ExprKind::Starred(value, _) => {
+                    let value = self.expr(value)?;
+                    let element = self.element(&value.ty);
+                    match site {
+                        Some(site) => self.c.store_value(site, &element),
+                        None => self.c.escape(&element),
+                    }
+                    codes.push(Item::Starred(self.boxed(value)));
+                }
+                _ => {
+                    let value = self.expr(item)?;
+                    if let Some(site) = site {
+                        self.c.store_value(site, &value.ty);
+                    }
+                    types.push(self.c.resolve(&value.ty));
+                    codes.push(Item::Single(self.boxed(value)));
+                }
+            }
+        }
+        Ok((codes, types))
+    }
+
+    fn dict(
+        &mut self,
+        keys: &'a [Option<Expr<'a>>],
+        values: &'a [Expr<'a>],
+        span: Span,
+    ) -> Result<Typed> {
+        let site = self.c.site(span, 0);
+        let mut entries = Vec::new();
+        for (key, value) in keys.iter().zip(values) {
+            match key {
+                Some(key) => {
+                    let key = self.expr(key)?;
+                    let value = self.expr(value)?;
+                    self.c.store_key(site, &key.ty);
+                    self.c.store_value(site, &value.ty);
+                    let key = self.boxed(key);
+                    let value = self.boxed(value);
+                    entries.push((Some(key), value));
+                }
+                None => {
+                    let other = self.expr(value)?;
+                    let (key, value) = match self.c.resolve(&other.ty) {
+                        Type::Dict(id) => (self.c.key(id), self.c.value(id)),
+                        _ => (Type::Dynamic, Type::Dynamic),
+                    };
+                    self.c.store_key(site, &key);
+                    self.c.store_value(site, &value);
+                    entries.push((None, self.boxed(other)));
+                }
+            }
+        }
+        let code = Code::Value(Box::new(move |f| {
+            let mut dict = runtime::Dict::default();
+            for (key, value) in &entries {
+                match key {
+                    Some(key) => {
+                        let key = key(f)?;
+                        dict.insert(key, value(f)?)?;
+                    }
+                    None => match value(f)? {
+                        Value::Dict(other) => {
+                            for item in other.borrow().items() {
+                                if let Value::Tuple(pair) = item {
+                                    dict.insert(pair[0].clone(), pair[1].clone())?;
+                                }
+                            }
+                        }
+                        other => {
+                            return Err(Exception::new("TypeError", format!(
+                                "'{}' object is not a mapping",
+                                other.type_name(),
+                            )))
+                        }
+                    },
+                }
+            }
+            Ok(Value::dict(dict))
+        }));
+        Ok(Typed { ty: Type::Dict(site), code })
+    }
+
+    /// The type of the items of an iterable of a type.
+    pub(super) fn element(&mut self, ty: &Type) -> Type {
+        match self.c.resolve(ty) {
+            Type::List(id) => self.c.value(id),
+            Type::Dict(id) => self.c.key(id),
+            Type::Str => Type::Str,
+            Type::Range => Type::Int,
+            Type::Tuple(items) => {
+                let mut element = Type::Never;
+                for item in &items {
+                    element = self.c.join(&element, item);
+                }
+                element
+            }
+            Type::Never => Type::Never,
+            _ => Type::Dynamic,
+        }
+    }
+
+    // One part of an f-string, as a string.
+    fn fstring_part(&mut self, part: &'a Expr<'a>) -> Result<Fun<String>> {
+        let (value, conversion, spec) = match &part.kind {
+            ExprKind::Constant(Constant::Str(text)) => {
+                let text = text.clone();
+                return Ok(Box::new(move |_| Ok(text.clone())));
+            }
+            ExprKind::FormattedValue(value, conversion, spec) => {
+                (value, *conversion, spec)
+            }
+            _ => {
+                let value = self.expr(part)?;
+                let value = self.boxed(value);
+                return Ok(Box::new(move |f| Ok(value(f)?.to_string())));
+            }
+        };
+        let value = self.expr(value)?;
+        let value = self.boxed(value);
+        let spec = match spec {
+            Some(spec) => {
+                let spec = self.expr(spec)?;
+                Some(self.boxed(spec))
+            }
+            None => None,
+        };
+        Ok(Box::new(move |f| {
+            let value = value(f)?;
+            let value = match conversion {
+                Some('r') => Value::str(&value.repr()),
+                Some('a') => Value::str(&runtime::ascii(&value.repr())),
+                Some('s') => Value::str(&value.to_string()),
+                _ => value,
+            };
+            match &spec {
+                Some(spec) => runtime::format(&value, &spec(f)?.to_string()),
+                None => Ok(value.to_string()),
+            }
+        }))
+    }
+
+    // --- Operators
+
+    /// `left op right`, given the value of `right` if it is an integer
+    /// literal.
+    pub(super) fn binary(
+        &mut self,
+        left: Typed,
+        op: BinaryOp,
+        right: Typed,
+        exponent: Option<u128>,
+        span: Span,
+    ) -> Typed {
+        use Type::*;
+        let lt = self.c.resolve(&left.ty);
+        let rt = self.c.resolve(&right.ty);
+        let int_like = |ty: &Type| matches!(ty, Int | Bool);
+        let bits = matches!(op, BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor);
+        match (&lt, &rt) {
+            (Never, _) | (_, Never) => {
+                let left = self.boxed(left);
+                let right = self.boxed(right);
+                return Typed {
+                    ty: Never,
+                    code: Code::Value(Box::new(move |f| {
+                        let left = left(f)?;
+                        runtime::binary(op, &left, &right(f)?)
+                    })),
+                };
+            }
+            (Bool, Bool) if bits => {
+                let (left, right) = (self.coerce(left, &Bool), self.coerce(right, &Bool));
+                let (left, right) = (bool::fun(left), bool::fun(right));
+                let code = Box::new(move |f: &mut Frame<'_>| {
+                    let (a, b) = (left(f)?, right(f)?);
+                    Ok(match op {
+                        BinaryOp::BitAnd => a & b,
+                        BinaryOp::BitOr => a | b,
+                        _ => a ^ b,
+                    })
+                });
+                return Typed { ty: Bool, code: Code::Bool(code) };
+            }
+            (a, b) if int_like(a) && int_like(b) && op == BinaryOp::Div => {
+                let (left, right) = (self.coerce(left, &Int), self.coerce(right, &Int));
+                let (left, right) = (runtime::Int::fun(left), runtime::Int::fun(right));
+                let code = Box::new(move |f: &mut Frame<'_>| {
+                    let (a, b) = (left(f)?, right(f)?);
+                    match b.is_zero() {
+                        true => Err(Exception::new("ZeroDivisionError", "division by zero")),
+                        false => Ok(a.to_f64()? / b.to_f64()?),
+                    }
+                });
+                return Typed { ty: Float, code: Code::Float(code) };
+            }
+            (a, b) if int_like(a) && int_like(b) && op != BinaryOp::MatMult
+                && (op != BinaryOp::Pow || exponent.is_some()) =>
+            {
+                let (left, right) = (self.coerce(left, &Int), self.coerce(right, &Int));
+                let (left, right) = (runtime::Int::fun(left), runtime::Int::fun(right));
+                let code: Fun<runtime::Int> = match op {
+                    BinaryOp::Add => Box::new(move |f| Ok(&left(f)? + &right(f)?)),
+                    BinaryOp::Sub => Box::new(move |f| Ok(&left(f)? - &right(f)?)),
+                    BinaryOp::Mult => Box::new(move |f| Ok(&left(f)? * &right(f)?)),
+                    _ => Box::new(move |f| {
+                        let a = left(f)?;
+                        runtime::int_binary(op, &a, &right(f)?)
+                    }),
+                };
+                return Typed { ty: Int, code: Code::Int(code) };
+            }
+            (a, b) if a.is_number() && b.is_number()
+                && (*a == Float || *b == Float)
+                && matches!(op, BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mult
+                    | BinaryOp::Div | BinaryOp::FloorDiv | BinaryOp::Mod
+                    | BinaryOp::Pow) =>
+            {
+                let (left, right) = (self.coerce(left, &Float), self.coerce(right, &Float));
+                let (left, right) = (f64::fun(left), f64::fun(right));
+                let code: Fun<f64> = match op {
+                    BinaryOp::Add => Box::new(move |f| Ok(left(f)? + right(f)?)),
+                    BinaryOp::Sub => Box::new(move |f| Ok(left(f)? - right(f)?)),
+                    BinaryOp::Mult => Box::new(move |f| Ok(left(f)? * right(f)?)),
+                    _ => Box::new(move |f| {
+                        let a = left(f)?;
+                        runtime::float_binary(op, a, right(f)?)
+                    }),
+                };
+                return Typed { ty: Float, code: Code::Float(code) };
+            }
+            _ => {}
+        }
+        let ty = match (&lt, op, &rt) {
+            (Str, BinaryOp::Add, Str) => Str,
+            (Str, BinaryOp::Mult, b) | (b, BinaryOp::Mult, Str) if int_like(b) => Str,
+            (List(a), BinaryOp::Add, List(b)) => {
+                let site = self.c.site(span, 0);
+                let (a, b) = (self.c.value(*a), self.c.value(*b));
+                self.c.store_value(site, &a);
+                self.c.store_value(site, &b);
+                List(site)
+            }
+            (List(a), BinaryOp::Mult, b) | (b, BinaryOp::Mult, List(a))
+                if int_like(b) =>
+            {
+                let site = self.c.site(span, 0);
+                let a = self.c.value(*a);
+                self.c.store_value(site, &a);
+                List(site)
+            }
+            (Tuple(a), BinaryOp::Add, Tuple(b)) => {
+                Tuple(a.iter().chain(b).cloned().collect())
+            }
+            (Str, BinaryOp::Mod, _) => {
+                self.c.escape(&rt);
+                Str
+            }
+            _ => {
+                self.c.escape(&lt);
+                self.c.escape(&rt);
+                Dynamic
+            }
+        };
+        let left = self.boxed(left);
+        let right = self.boxed(right);
+        let code: Fun<Value> = Box::new(move |f| {
+            let left = left(f)?;
+            runtime::binary(op, &left, &right(f)?)
+        });
+        Typed { code: unbox(code, &ty), ty }
+    }
+
+    fn unary(&mut self, op: UnaryOp, value: Typed) -> Typed {
+        match (self.c.resolve(&value.ty), op) {
+            (Type::Int, _) | (Type::Bool, _) => {
+                let value = Int::fun(self.coerce(value, &Type::Int));
+                let code = match op {
+                    UnaryOp::USub => map(value, |v: Int| Ok(v.neg())),
+                    UnaryOp::Invert => map(value, |v: Int| Ok(v.invert())),
+                    _ => Code::Int(value),
+                };
+                Typed { ty: Type::Int, code }
+            }
+            (Type::Float, UnaryOp::USub) | (Type::Float, UnaryOp::UAdd) => {
+                let value = f64::fun(value.code);
+                let code = match op {
+                    UnaryOp::USub => map(value, |v: f64| Ok(-v)),
+                    _ => Code::Float(value),
+                };
+                Typed { ty: Type::Float, code }
+            }
+            (ty, _) => {
+                let ty = match ty {
+                    Type::Float => Type::Never,
+                    _ => Type::Dynamic,
+                };
+                let value = self.boxed(value);
+                let code: Fun<Value> = Box::new(move |f| {
+                    runtime::unary(op, &value(f)?)
+                });
+                Typed { ty, code: Code::Value(code) }
+            }
+        }
+    }
+
+    fn bool_op(&mut self, op: BoolOp, values: &'a [Expr<'a>]) -> Result<Typed> {
+        let mut typed = Vec::new();
+        let mut first = None;
+        for value in values {
+            typed.push(self.expr(value)?);
+            // Later operands might not be evaluated.
+            first.get_or_insert_with(|| self.bound.clone());
+        }
+        self.bound = first.unwrap_or_default();
+        let mut ty = Type::Never;
+        for value in &typed {
+            ty = self.c.join(&ty, &value.ty);
+        }
+        let codes: Vec<_> = typed
+            .into_iter()
+            .map(|value| self.coerce(value, &ty))
+            .collect();
+        let and = op == BoolOp::And;
+        let code = match ty.repr() {
+            Repr::Int => bool_op::<Int>(codes, and, |v| !v.is_zero()),
+            Repr::Float => bool_op::<f64>(codes, and, |v| *v != 0.0),
+            Repr::Bool => bool_op::<bool>(codes, and, |v| *v),
+            Repr::Value => bool_op::<Value>(codes, and, Value::truthy),
+        };
+        Ok(Typed { ty, code })
+    }
+
+    /// The truth value of an expression, short-circuiting `and`, `or` and
+    /// comparisons.
+    pub(super) fn cond(&mut self, expr: &'a Expr<'a>) -> Result<Fun<bool>> {
+        match &expr.kind {
+            ExprKind::UnaryOp(UnaryOp::Not, value) => {
+                let value = self.cond(value)?;
+                Ok(Box::new(move |f| Ok(!value(f)?)))
+            }
+            ExprKind::BoolOp(op, values) => {
+                let mut conds = Vec::new();
+                let mut first = None;
+                for value in values {
+                    conds.push(self.cond(value)?);
+                    first.get_or_insert_with(|| self.bound.clone());
+                }
+                self.bound = first.unwrap_or_default();
+                let and = *op == BoolOp::And;
+                Ok(Box::new(move |f| {
+                    for cond in &conds {
+                        if cond(f)? != and {
+                            return Ok(!and);
+                        }
+                    }
+                    Ok(and)
+                }))
+            }
+            ExprKind::Compare(left, ops, rights) => {
+                let mut operands = vec![self.expr(left)?];
+                for right in rights {
+                    operands.push(self.expr(right)?);
+                }
+                Ok(self.compare(operands, ops.clone()))
+            }
+            _ => {
+                let value = self.expr(expr)?;
+                Ok(self.truth(value))
+            }
+        }
+    }
+
+    // A chain of comparisons.
+    fn compare(&mut self, operands: Vec<Typed>, ops: Vec<CmpOp>) -> Fun<bool> {
+        let types: Vec<_> = operands.iter()
+            .map(|operand| self.c.resolve(&operand.ty))
+            .collect();
+        let ordering = ops.iter().all(|op| {
+            !matches!(op, CmpOp::Is | CmpOp::IsNot | CmpOp::In | CmpOp::NotIn)
+        });
+        if ordering && types.iter().all(|ty| matches!(ty, Type::Int | Type::Bool)) {
+            let codes: Vec<_> = operands.into_iter()
+                .map(|operand| Int::fun(self.coerce(operand, &Type::Int)))
+                .collect();
+            return chain(codes, ops, |op, a, b| Ok(compare_ints(op, a, b)));
+        }
+        // An `int` compared to a `float` is compared exactly, boxed.
+        if ordering && types.iter().all(|ty| matches!(ty, Type::Float | Type::Bool)) {
+            let codes: Vec<_> = operands.into_iter()
+                .map(|operand| f64::fun(self.coerce(operand, &Type::Float)))
+                .collect();
+            return chain(codes, ops, |op, a, b| Ok(compare_floats(op, *a, *b)));
+        }
+        let codes: Vec<_> = operands.into_iter()
+            .map(|operand| self.boxed(operand))
+            .collect();
+        chain(codes, ops, runtime::compare)
+    }
+
+    // --- Attributes and subscripts
+
+    /// `value.name`, for an instance or class.
+    pub(super) fn attribute(&mut self, value: Typed, name: &str, span: Span)
+        -> Result<Typed>
+    {
+        match self.c.resolve(&value.ty) {
+            Type::Dynamic | Type::Never => {}
+            _ => {
+                return Err(Diagnostic::new(
+                    span,
+                    "attributes of built-in types can't be compiled yet",
+                ))
+            }
+        }
+        let value = self.boxed(value);
+        let name = name.to_string();
+        let code = Code::Value(Box::new(move |f| {
+            runtime::get_attribute(&value(f)?, &name)
+        }));
+        Ok(Typed { ty: Type::Dynamic, code })
+    }
+
+    /// `value[index]`, given the index if it is an integer literal.
+    pub(super) fn subscript(
+        &mut self,
+        value: Typed,
+        index: Typed,
+        constant: Option<i64>,
+    ) -> Typed {
+        let ty = match (self.c.resolve(&value.ty), self.c.resolve(&index.ty)) {
+            (Type::List(id), Type::Int) | (Type::List(id), Type::Bool) => {
+                self.c.value(id)
+            }
+            (Type::Dict(id), _) => self.c.value(id),
+            (Type::Str, Type::Int) | (Type::Str, Type::Bool) => Type::Str,
+            (Type::Range, Type::Int) | (Type::Range, Type::Bool) => Type::Int,
+            (Type::Tuple(items), _) => {
+                let len = items.len() as i64;
+                match constant {
+                    Some(i) if i >= -len && i < len => {
+                        items[(if i < 0 { i + len } else { i }) as usize].clone()
+                    }
+                    _ => self.element(&Type::Tuple(items)),
+                }
+            }
+            (ty, _) => {
+                self.c.escape(&ty);
+                Type::Dynamic
+            }
+        };
+        let value = self.boxed(value);
+        let index = self.boxed(index);
+        let code: Fun<Value> = Box::new(move |f| {
+            let value = value(f)?;
+            runtime::get_item(&value, &index(f)?)
+        });
+        Typed { code: unbox(code, &ty), ty }
+    }
+
+    fn slice(
+        &mut self,
+        value: Typed,
+        bounds: [&'a Option<Box<Expr<'a>>>; 3],
+        span: Span,
+    ) -> Result<Typed> {
+        let ty = match self.c.resolve(&value.ty) {
+            Type::List(id) => {
+                let site = self.c.site(span, 0);
+                let element = self.c.value(id);
+                self.c.store_value(site, &element);
+                Type::List(site)
+            }
+            Type::Str => Type::Str,
+            ty => {
+                self.c.escape(&ty);
+                Type::Dynamic
+            }
+        };
+        let mut codes = Vec::new();
+        for bound in bounds.iter() {
+            codes.push(match bound {
+                Some(bound) => {
+                    let bound = self.expr(bound)?;
+                    Some(self.boxed(bound))
+                }
+                None => None,
+            });
+        }
+        let value = self.boxed(value);
+        let code = Code::Value(Box::new(move |f| {
+            let value = value(f)?;
+            let mut bounds = Vec::new();
+            for code in &codes {
+                bounds.push(match code {
+                    Some(code) => Some(code(f)?),
+                    None => None,
+                });
+            }
+            runtime::get_slice(
+                &value,
+                bounds[0].as_ref(),
+                bounds[1].as_ref(),
+                bounds[2].as_ref(),
+            )
+        }));
+        Ok(Typed { ty, code })
+    }
+}
+
+// An item of a list or tuple display
+pub(super) enum Item {
+    Single(Fun<Value>),
+    /// `*iterable`
+    Starred(Fun<Value>),
+}
+
+// The items of a display.
+pub(super) fn collect(items: &[Item], f: &mut Frame<'_>) -> Eval<Vec<Value>> {
+    let mut values = Vec::new();
+    for item in items {
+        match item {
+            Item::Single(code) => values.push(code(f)?),
+            Item::Starred(code) => {
+                for value in runtime::iter(&code(f)?)? {
+                    values.push(value?);
+                }
+            }
+        }
+    }
+    Ok(values)
+}
+
+// The value of an integer literal index, like `-1`.
+pub(super) fn constant_index(expr: &Expr<'_>) -> Option<i64> {
+    match &expr.kind {
+        ExprKind::Constant(Constant::Int(value)) => i64::try_from(*value).ok(),
+        ExprKind::UnaryOp(UnaryOp::USub, value) => {
+            constant_index(value).map(|value| -value)
+        }
+        _ => None,
+    }
+}
+
+// Code reading the cell at an index of the frame, raising `error` if the
+// variable isn't bound.
+fn read_cell(cell: usize, error: Exception, ty: Type) -> Typed {
+    let code: Fun<Value> = Box::new(move |f| {
+        match &*f.slots.cells[cell].borrow() {
+            Some(value) => Ok(value.clone()),
+            None => Err(error.clone()),
+        }
+    });
+    Typed { code: unbox(code, &ty), ty }
+}
+
+// Code storing a value in the cell at an index of the frame.
+fn write_cell(value: Fun<Value>, cell: usize) -> Fun<()> {
+    Box::new(move |f| {
+        let value = value(f)?;
+        *f.slots.cells[cell].borrow_mut() = Some(value);
+        Ok(())
+    })
+}
+
+// Read a slot, raising `error` if the variable isn't bound.
+fn read<T: Unboxed>(
+    global: bool,
+    slot: usize,
+    id: usize,
+    error: Option<Exception>,
+) -> Code {
+    T::code(match (global, error) {
+        (false, None) => Box::new(move |f| Ok(T::slots(&f.slots)[slot].clone())),
+        (false, Some(error)) => Box::new(move |f| match f.slots.bound[id] {
+            true => Ok(T::slots(&f.slots)[slot].clone()),
+            false => Err(error.clone()),
+        }),
+        (true, None) => Box::new(move |f| {
+            Ok(T::slots(&f.rt.globals.borrow())[slot].clone())
+        }),
+        (true, Some(error)) => Box::new(move |f| {
+            let globals = f.rt.globals.borrow();
+            match globals.bound[id] {
+                true => Ok(T::slots(&globals)[slot].clone()),
+                false => Err(error.clone()),
+            }
+        }),
+    })
+}
+
+// Write the value of code to a slot.
+fn write<T: Unboxed>(code: Fun<T>, global: bool, slot: usize, id: usize)
+    -> Fun<()>
+{
+    match global {
+        false => Box::new(move |f| {
+            let value = code(f)?;
+            T::slots_mut(&mut f.slots)[slot] = value;
+            f.slots.bound[id] = true;
+            Ok(())
+        }),
+        true => Box::new(move |f| {
+            let value = code(f)?;
+            let mut globals = f.rt.globals.borrow_mut();
+            let old = std::mem::replace(&mut T::slots_mut(&mut globals)[slot], value);
+            globals.bound[id] = true;
+            drop(globals);
+            drop(old);
+            Ok(())
+        }),
+    }
+}
+
+// Code running `effect` and then producing the value of `code`.
+pub(super) fn then(effect: Fun<()>, code: Code) -> Code {
+    fn then<T: Unboxed>(effect: Fun<()>, code: Fun<T>) -> Code {
+        T::code(Box::new(move |f| {
+            effect(f)?;
+            code(f)
+        }))
+    }
+    match code {
+        Code::Int(code) => then(effect, code),
+        Code::Float(code) => then(effect, code),
+        Code::Bool(code) => then(effect, code),
+        Code::Value(code) => then(effect, code),
+    }
+}
+
+// `body if test else orelse` for code of the same representation.
+fn choose(test: Fun<bool>, body: Code, orelse: Code) -> Code {
+    fn choose<T: Unboxed>(test: Fun<bool>, body: Code, orelse: Code) -> Code {
+        let (body, orelse) = (T::fun(body), T::fun(orelse));
+        T::code(Box::new(move |f| match test(f)? {
+            true => body(f),
+            false => orelse(f),
+        }))
+    }
+    match body.repr() {
+        Repr::Int => choose::<Int>(test, body, orelse),
+        Repr::Float => choose::<f64>(test, body, orelse),
+        Repr::Bool => choose::<bool>(test, body, orelse),
+        Repr::Value => choose::<Value>(test, body, orelse),
+    }
+}
+
+// `a and b and ...` or `a or b or ...`, which is the first operand whose
+// truth value decides the result.
+fn bool_op<T: Unboxed>(codes: Vec<Code>, and: bool, truthy: fn(&T) -> bool)
+    -> Code
+{
+    let codes: Vec<Fun<T>> = codes.into_iter().map(T::fun).collect();
+    T::code(Box::new(move |f| {
+        let (last, rest) = codes.split_last().unwrap();
+        for code in rest {
+            let value = code(f)?;
+            if truthy(&value) != and {
+                return Ok(value);
+            }
+        }
+        last(f)
+    }))
+}
+
+// `a op b op c ...`, evaluating each operand at most once.
+fn chain<T: 'static>(
+    codes: Vec<Fun<T>>,
+    ops: Vec<CmpOp>,
+    compare: impl Fn(CmpOp, &T, &T) -> Eval<bool> + 'static,
+) -> Fun<bool> {
+    Box::new(move |f| {
+        let mut left = codes[0](f)?;
+        for (op, code) in ops.iter().zip(&codes[1..]) {
+            let right = code(f)?;
+            if !compare(*op, &left, &right)? {
+                return Ok(false);
+            }
+            left = right;
+        }
+        Ok(true)
+    })
+}
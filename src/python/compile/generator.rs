@@ -0,0 +1,351 @@
+// Python generator compilation
+//
+//! Code generation for the statements of generator functions and `async`
+//! functions that can stop at a `yield` or `await`, and be resumed there.
+//!
+//! A statement with a suspension point in it runs in one of two ways.  Run
+//! normally, when the frame's path is empty, it runs like any other
+//! statement, except that when something in it yields, it pushes its state
+//! (which of its blocks was running) onto the path and returns
+//! [`Flow::Yield`].  Run when the generator is resumed, it pops its state
+//! and goes straight back into that block, so only the statements around
+//! the suspension point run again, up to the point itself, which takes what
+//! the generator was resumed with.  What a statement needs across a
+//! suspension, like the iterator of a `for` loop, is kept in hidden
+//! variables of the frame.
+
+use std::rc::Rc;
+
+use super::super::runtime::{self, Eval, Exception, Resume, Step, Value};
+use super::super::{Expr, ExprKind, Stmt, StmtKind};
+use super::expr::constant;
+use super::stmt::Handler;
+use super::{Exec, Flow, Frame, Fun, Result, Store, Type, Typed, Walker};
+use crate::Diagnostic;
+
+/// Whether a statement has a `yield` or `await` in it that it can stop at.
+pub(super) fn suspends(stmt: &Stmt<'_>) -> bool {
+    let any = |stmts: &[Stmt<'_>]| stmts.iter().any(suspends);
+    match &stmt.kind {
+        StmtKind::Expr(value)
+        | StmtKind::Assign(_, value)
+        | StmtKind::AnnAssign(_, _, Some(value), _)
+        | StmtKind::Return(Some(value)) => is_suspension(value),
+        StmtKind::If(_, body, orelse) | StmtKind::While(_, body, orelse) => {
+            any(body) || any(orelse)
+        }
+        StmtKind::For(each) => any(&each.body) || any(&each.orelse),
+        StmtKind::Try(stmt) => {
+            any(&stmt.body)
+                || stmt.handlers.iter().any(|handler| any(&handler.body))
+                || any(&stmt.orelse)
+                || any(&stmt.finalbody)
+        }
+        _ => false,
+    }
+}
+
+fn is_suspension(expr: &Expr<'_>) -> bool {
+    matches!(
+        expr.kind,
+        ExprKind::Yield(_) | ExprKind::YieldFrom(_) | ExprKind::Await(_),
+    )
+}
+
+/// The error for a `yield` or `await` where it can't be compiled.
+pub(super) fn misplaced(expr: &Expr<'_>) -> Diagnostic {
+    let keyword = match expr.kind {
+        ExprKind::Await(_) => "await",
+        _ => "yield",
+    };
+    Diagnostic::new(expr.span, format!(
+        "`{}` can only be compiled as a statement, an assignment's value or \
+            a returned value",
+        keyword,
+    ))
+}
+
+/// Code running a statement's suspension point, if it has one, and then
+/// the rest of the statement.
+pub(super) fn resumed(point: Option<Exec>, rest: Exec) -> Exec {
+    match point {
+        Some(point) => Box::new(move |f| match point(f)? {
+            Flow::Next => rest(f),
+            flow => Ok(flow),
+        }),
+        None => rest,
+    }
+}
+
+// Run a block that is resumed at state `state` if it yields, recording
+// that state if it does.
+fn run(f: &mut Frame<'_>, block: &Exec, state: usize) -> Eval<Flow> {
+    let flow = block(f)?;
+    if flow == Flow::Yield {
+        f.path.push(state);
+    }
+    Ok(flow)
+}
+
+/// Code running statements in order, resuming at the one that yielded.
+pub(super) fn sequence(stmts: Vec<Exec>) -> Exec {
+    Box::new(move |f| {
+        let start = f.path.pop().unwrap_or(0);
+        for (i, stmt) in stmts.iter().enumerate().skip(start) {
+            match run(f, stmt, i)? {
+                Flow::Next => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Next)
+    })
+}
+
+/// `if test: body else: orelse`, resumable in either branch.
+pub(super) fn if_stmt(test: Fun<bool>, body: Exec, orelse: Exec) -> Exec {
+    Box::new(move |f| {
+        let branch = match f.path.pop() {
+            Some(branch) => branch,
+            None if test(f)? => 0,
+            None => 1,
+        };
+        match branch {
+            0 => run(f, &body, 0),
+            _ => run(f, &orelse, 1),
+        }
+    })
+}
+
+/// `while test: body else: orelse`, resumable in the body or the `else`
+/// clause.
+pub(super) fn while_stmt(test: Fun<bool>, body: Exec, orelse: Exec) -> Exec {
+    Box::new(move |f| {
+        let mut state = f.path.pop();
+        if state != Some(1) {
+            // Resuming in the body doesn't test the condition first.
+            while state.take().is_some() || test(f)? {
+                match run(f, &body, 0)? {
+                    Flow::Break => return Ok(Flow::Next),
+                    flow @ (Flow::Return | Flow::Yield) => return Ok(flow),
+                    Flow::Next | Flow::Continue => {}
+                }
+            }
+        }
+        run(f, &orelse, 1)
+    })
+}
+
+// The parts of a resumable `for` loop
+pub(super) struct ForLoop {
+    /// The iterable
+    pub(super) source: Fun<Value>,
+    /// The hidden variable keeping the iterator, and code reading it
+    pub(super) iterator: (Store, Fun<Value>),
+    /// Code storing an item in the hidden variable the target is assigned
+    /// from
+    pub(super) store: Store,
+    pub(super) target: Fun<()>,
+    pub(super) body: Exec,
+    pub(super) orelse: Exec,
+}
+
+/// `for target in source: body else: orelse`, resumable in the body or the
+/// `else` clause.
+pub(super) fn for_stmt(each: ForLoop) -> Exec {
+    let ForLoop { source, iterator: (keep, iterator), store, target, body, orelse } =
+        each;
+    Box::new(move |f| {
+        let mut state = f.path.pop();
+        if state != Some(1) {
+            if state.is_none() {
+                let items = runtime::iter_object(&source(f)?)?;
+                keep(f, items)?;
+            }
+            loop {
+                // Resuming in the body continues with the same item.
+                if state.take().is_none() {
+                    match runtime::next_item(&iterator(f)?)? {
+                        Some(item) => store(f, item)?,
+                        None => break,
+                    }
+                    target(f)?;
+                }
+                match run(f, &body, 0)? {
+                    Flow::Break => return Ok(Flow::Next),
+                    flow @ (Flow::Return | Flow::Yield) => return Ok(flow),
+                    Flow::Next | Flow::Continue => {}
+                }
+            }
+        }
+        run(f, &orelse, 1)
+    })
+}
+
+// The parts of a resumable `try` statement
+pub(super) struct TryStmt {
+    pub(super) body: Exec,
+    pub(super) orelse: Exec,
+    pub(super) handlers: Vec<Handler>,
+    pub(super) finally: Option<Exec>,
+    /// The hidden variable keeping the exception a handler that yielded is
+    /// handling, and code reading it
+    pub(super) handling: (Store, Fun<Value>),
+}
+
+/// A `try` statement, resumable in its body, `else` clause or handlers.
+/// The `finally` clause runs when the statement finishes, not when it
+/// yields.
+pub(super) fn try_stmt(stmt: TryStmt) -> Exec {
+    let TryStmt { body, orelse, handlers, finally, handling } = stmt;
+    Box::new(move |f| {
+        let result = match f.path.pop() {
+            None | Some(0) => match body(f) {
+                Ok(Flow::Yield) => {
+                    f.path.push(0);
+                    return Ok(Flow::Yield);
+                }
+                Ok(Flow::Next) => run(f, &orelse, 1),
+                Ok(flow) => Ok(flow),
+                Err(exception) => {
+                    let mut caught = None;
+                    for (i, handler) in handlers.iter().enumerate() {
+                        let catches = match &handler.class {
+                            Some(class) => runtime::catches(&exception, &class(f)?)?,
+                            None => true,
+                        };
+                        if catches {
+                            caught = Some(i);
+                            break;
+                        }
+                    }
+                    match caught {
+                        Some(i) => {
+                            handle(f, &handlers[i], exception, i, &handling, false)
+                        }
+                        None => Err(exception),
+                    }
+                }
+            },
+            Some(1) => run(f, &orelse, 1),
+            Some(state) => {
+                let exception = match (handling.1)(f)? {
+                    Value::Exception(exception) => (*exception).clone(),
+                    _ => unreachable!("a handler's exception is kept"),
+                };
+                let i = state - 2;
+                handle(f, &handlers[i], exception, i, &handling, true)
+            }
+        };
+        if let Ok(Flow::Yield) = result {
+            return result;
+        }
+        let mut result = result;
+        if let Some(finally) = &finally {
+            match finally(f)? {
+                Flow::Next => {}
+                flow => result = Ok(flow),
+            }
+        }
+        result
+    })
+}
+
+// Run handler `i` of a `try` statement, or resume it, keeping the
+// exception it handles if it yields.
+fn handle(
+    f: &mut Frame<'_>,
+    handler: &Handler,
+    exception: Exception,
+    i: usize,
+    (keep, _): &(Store, Fun<Value>),
+    resuming: bool,
+) -> Eval<Flow> {
+    f.rt.handling.borrow_mut().push(exception);
+    let result = match (&handler.name, resuming) {
+        (Some((store, _, _)), false) => store(f).and_then(|()| (handler.body)(f)),
+        _ => (handler.body)(f),
+    };
+    let exception = f.rt.handling.borrow_mut().pop().unwrap();
+    if let Ok(Flow::Yield) = result {
+        keep(f, Value::Exception(Rc::new(exception)))?;
+        f.path.push(2 + i);
+        return result;
+    }
+    if let Some((_, global, id)) = handler.name {
+        match global {
+            true => f.rt.globals.borrow_mut().bound[id] = false,
+            false => f.slots.bound[id] = false,
+        }
+    }
+    result
+}
+
+impl<'c, 'a> Walker<'c, 'a> {
+    /// The value of a statement: code stopping at it and then reading what
+    /// the generator is resumed with if it is a `yield` or `await`, or else
+    /// the code of the expression.
+    pub(super) fn operand(&mut self, expr: &'a Expr<'a>)
+        -> Result<(Option<Exec>, Typed)>
+    {
+        let span = expr.span;
+        let (value, delegates) = match &expr.kind {
+            ExprKind::Yield(value) => (value.as_deref(), false),
+            ExprKind::YieldFrom(value) | ExprKind::Await(value) => {
+                (Some(&**value), true)
+            }
+            _ => return Ok((None, self.expr(expr)?)),
+        };
+        let value = match value {
+            Some(value) => self.expr(value)?,
+            None => Typed { ty: Type::None, code: constant(Value::None) },
+        };
+        let value = self.dynamic(value);
+        let (received, result) = self.stash_boxed(Type::Dynamic, span, 80)?;
+        if !delegates {
+            let point: Exec = Box::new(move |f| {
+                if f.path.pop().is_none() {
+                    f.yielded = value(f)?;
+                    f.path.push(0);
+                    return Ok(Flow::Yield);
+                }
+                match f.sent.take() {
+                    Some(Resume::Throw(exception)) => Err(exception),
+                    Some(Resume::Send(sent)) => received(f, sent).map(|()| Flow::Next),
+                    None => received(f, Value::None).map(|()| Flow::Next),
+                }
+            });
+            return Ok((Some(point), result));
+        }
+        // `yield from` and `await` pass what the generator is resumed with
+        // to what they delegate to until it returns.
+        let awaits = matches!(expr.kind, ExprKind::Await(_));
+        let (keep, delegate) = self.stash_boxed(Type::Dynamic, span, 81)?;
+        let delegate = self.boxed(delegate);
+        let point: Exec = Box::new(move |f| {
+            let resume = match f.path.pop() {
+                None => {
+                    let value = value(f)?;
+                    let value = match awaits {
+                        true => runtime::awaitable(&value)?,
+                        false => runtime::yield_from(&value)?,
+                    };
+                    keep(f, value)?;
+                    Resume::Send(Value::None)
+                }
+                Some(_) => f.sent.take().unwrap_or(Resume::Send(Value::None)),
+            };
+            let step = runtime::delegate(&delegate(f)?, resume);
+            if let Ok(Step::Yield(value)) = step {
+                f.yielded = value;
+                f.path.push(0);
+                return Ok(Flow::Yield);
+            }
+            keep(f, Value::None)?;
+            match step? {
+                Step::Return(value) => received(f, value).map(|()| Flow::Next),
+                Step::Yield(_) => unreachable!("a yielded value is passed on"),
+            }
+        });
+        Ok((Some(point), result))
+    }
+}
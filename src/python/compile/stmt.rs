@@ -0,0 +1,1156 @@
+// Python statement compilation
+//
+//! Type inference and code generation for statements, assignment targets,
+//! loops and comprehensions.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::super::runtime::{
+    self, Class, Eval, Exception, Function, Int, Value,
+};
+use super::super::{
+    ClassDef, Comprehension, Constant, Expr, ExprKind, Stmt, StmtKind, Try,
+};
+use super::expr::{compatible, constant, constant_index, Lookup};
+use super::generator::{self, resumed, suspends, ForLoop, TryStmt};
+use super::{
+    children, expr_bound_names, Body, Code, Exec, Flow, Frame, Fun, Result,
+    Store, Type, Typed, Unboxed, VarKey, Walker,
+};
+use crate::{Diagnostic, Span};
+
+// A loop over the items of an iterable, storing each in a hidden variable
+pub(super) struct Items {
+    pub(super) source: Fun<Value>,
+    pub(super) store: Store,
+}
+
+impl Items {
+    // Run `body` for each item, until it breaks out of the loop or returns.
+    fn run(
+        &self,
+        f: &mut Frame<'_>,
+        body: &dyn Fn(&mut Frame<'_>) -> Eval<Flow>,
+    ) -> Eval<Flow> {
+        let items = runtime::iter(&(self.source)(f)?)?;
+        for item in items {
+            (self.store)(f, item?)?;
+            match body(f)? {
+                Flow::Break => return Ok(Flow::Break),
+                flow @ (Flow::Return | Flow::Yield) => return Ok(flow),
+                Flow::Next | Flow::Continue => {}
+            }
+        }
+        Ok(Flow::Next)
+    }
+}
+
+// An `except` clause: the class it catches, the code binding the name it
+// gives the exception (with where the variable is), and its body
+pub(super) struct Handler {
+    pub(super) class: Option<Fun<Value>>,
+    pub(super) name: Option<(Fun<()>, bool, usize)>,
+    pub(super) body: Exec,
+}
+
+// Code running statements in order.
+fn sequence(stmts: Vec<Exec>) -> Exec {
+    Box::new(move |f| {
+        for stmt in &stmts {
+            match stmt(f)? {
+                Flow::Next => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Next)
+    })
+}
+
+fn effect(code: Fun<()>) -> Exec {
+    Box::new(move |f| code(f).map(|()| Flow::Next))
+}
+
+// Run the first handler that catches an exception, or raise it again if
+// none does.
+fn handle(f: &mut Frame<'_>, handlers: &[Handler], exception: Exception)
+    -> Eval<Flow>
+{
+    for handler in handlers {
+        if let Some(class) = &handler.class {
+            if !runtime::catches(&exception, &class(f)?)? {
+                continue;
+            }
+        }
+        f.rt.handling.borrow_mut().push(exception);
+        let result = match &handler.name {
+            Some((store, _, _)) => store(f).and_then(|()| (handler.body)(f)),
+            None => (handler.body)(f),
+        };
+        f.rt.handling.borrow_mut().pop();
+        if let Some((_, global, id)) = handler.name {
+            match global {
+                true => f.rt.globals.borrow_mut().bound[id] = false,
+                false => f.slots.bound[id] = false,
+            }
+        }
+        return result;
+    }
+    Err(exception)
+}
+
+fn unsupported<T>(span: Span, what: &str) -> Result<T> {
+    Err(Diagnostic::new(span, format!("{} can't be compiled yet", what)))
+}
+
+impl<'c, 'a> Walker<'c, 'a> {
+    /// The code of the instance's function.
+    pub(super) fn function_body(&mut self) -> Result<Exec> {
+        let def = &self.c.defs[self.def];
+        let span = def.span;
+        match def.body {
+            Body::Stmts(stmts) => {
+                let body = self.block(stmts)?;
+                if self.id == 0 || !self.reachable {
+                    return Ok(body);
+                }
+                let none = Typed { ty: Type::None, code: constant(Value::None) };
+                let none = self.ret(none, span)?;
+                Ok(Box::new(move |f| match body(f)? {
+                    Flow::Next => none(f),
+                    flow => Ok(flow),
+                }))
+            }
+            Body::Lambda(expr) => {
+                let value = self.expr(expr)?;
+                self.ret(value, expr.span)
+            }
+        }
+    }
+
+    /// Compile a block of statements.
+    pub(super) fn block(&mut self, stmts: &'a [Stmt<'a>]) -> Result<Exec> {
+        let mut codes: Vec<Exec> = Vec::new();
+        for stmt in stmts {
+            let code = self.stmt(stmt)?;
+            let span = stmt.span;
+            codes.push(Box::new(move |f| code(f).map_err(|e| e.at(span))));
+        }
+        match stmts.iter().any(suspends) {
+            true => Ok(generator::sequence(codes)),
+            false => Ok(sequence(codes)),
+        }
+    }
+
+    fn stmt(&mut self, stmt: &'a Stmt<'a>) -> Result<Exec> {
+        let span = stmt.span;
+        let resumable = suspends(stmt);
+        match &stmt.kind {
+            StmtKind::Expr(value) => {
+                let (point, value) = self.operand(value)?;
+                let code: Exec =
+                    Box::new(move |f| value.code.run(f).map(|()| Flow::Next));
+                Ok(resumed(point, code))
+            }
+            StmtKind::Assign(targets, value) => {
+                let (point, value) = self.operand(value)?;
+                if let [target] = targets.as_slice() {
+                    let code = self.assign(target, value).map(effect)?;
+                    return Ok(resumed(point, code));
+                }
+                let (store, _) = self.stash(value, span, 50)?;
+                let mut codes = vec![store];
+                for target in targets {
+                    let value = self.reread(span, 50);
+                    codes.push(self.assign(target, value)?);
+                }
+                Ok(resumed(point, Box::new(move |f| {
+                    for code in &codes {
+                        code(f)?;
+                    }
+                    Ok(Flow::Next)
+                })))
+            }
+            StmtKind::AnnAssign(target, _, value, _) => match value {
+                Some(value) => {
+                    let (point, value) = self.operand(value)?;
+                    let code = self.assign(target, value).map(effect)?;
+                    Ok(resumed(point, code))
+                }
+                None => Ok(Box::new(|_| Ok(Flow::Next))),
+            },
+            StmtKind::AugAssign(target, op, value) => {
+                self.aug_assign(target, *op, value, span).map(effect)
+            }
+            StmtKind::Return(value) => {
+                let (point, value) = match value {
+                    Some(value) => self.operand(value)?,
+                    None => {
+                        let none = constant(Value::None);
+                        (None, Typed { ty: Type::None, code: none })
+                    }
+                };
+                let code = self.ret(value, span)?;
+                self.reachable = false;
+                Ok(resumed(point, code))
+            }
+            StmtKind::If(test, body, orelse) => {
+                let test = self.cond(test)?;
+                let saved = self.bound.clone();
+                let body = self.block(body)?;
+                let after_body = (self.reachable, std::mem::replace(&mut self.bound, saved));
+                self.reachable = true;
+                let orelse = self.block(orelse)?;
+                self.merge(after_body);
+                if resumable {
+                    return Ok(generator::if_stmt(test, body, orelse));
+                }
+                Ok(Box::new(move |f| match test(f)? {
+                    true => body(f),
+                    false => orelse(f),
+                }))
+            }
+            StmtKind::While(test, body, orelse) => {
+                let forever = matches!(
+                    test.kind,
+                    ExprKind::Constant(Constant::Bool(true)),
+                );
+                let test = self.cond(test)?;
+                let saved = self.bound.clone();
+                self.breaks.push(false);
+                let body = self.block(body)?;
+                let broke = self.breaks.pop().unwrap();
+                self.bound = saved.clone();
+                self.reachable = true;
+                let orelse = self.block(orelse)?;
+                self.bound = saved;
+                self.reachable = broke || (!forever && self.reachable);
+                if resumable {
+                    return Ok(generator::while_stmt(test, body, orelse));
+                }
+                Ok(Box::new(move |f| {
+                    while test(f)? {
+                        match body(f)? {
+                            Flow::Break => return Ok(Flow::Next),
+                            flow @ (Flow::Return | Flow::Yield) => return Ok(flow),
+                            Flow::Next | Flow::Continue => {}
+                        }
+                    }
+                    orelse(f)
+                }))
+            }
+            StmtKind::For(each) => {
+                let iterable = self.expr(&each.iter)?;
+                let (items, item) = self.iterate(iterable, span, 40)?;
+                let saved = self.bound.clone();
+                let target = self.assign(&each.target, item)?;
+                self.breaks.push(false);
+                let body = self.block(&each.body)?;
+                let broke = self.breaks.pop().unwrap();
+                self.bound = saved.clone();
+                self.reachable = true;
+                let orelse = self.block(&each.orelse)?;
+                self.bound = saved;
+                self.reachable = self.reachable || broke;
+                if resumable {
+                    let (keep, iterator) =
+                        self.stash_boxed(Type::Dynamic, span, 41)?;
+                    let iterator = (keep, self.boxed(iterator));
+                    let Items { source, store } = items;
+                    return Ok(generator::for_stmt(ForLoop {
+                        source,
+                        iterator,
+                        store,
+                        target,
+                        body,
+                        orelse,
+                    }));
+                }
+                Ok(Box::new(move |f| {
+                    let flow = items.run(f, &|f| {
+                        target(f)?;
+                        body(f)
+                    })?;
+                    match flow {
+                        Flow::Break => Ok(Flow::Next),
+                        Flow::Return | Flow::Yield => Ok(flow),
+                        _ => orelse(f),
+                    }
+                }))
+            }
+            StmtKind::Break | StmtKind::Continue => {
+                let flow = match stmt.kind {
+                    StmtKind::Break => {
+                        if let Some(broke) = self.breaks.last_mut() {
+                            *broke = true;
+                        }
+                        Flow::Break
+                    }
+                    _ => Flow::Continue,
+                };
+                self.reachable = false;
+                Ok(Box::new(move |_| Ok(flow)))
+            }
+            StmtKind::Pass | StmtKind::Global(_) => {
+                Ok(Box::new(|_| Ok(Flow::Next)))
+            }
+            StmtKind::Delete(targets) => {
+                let mut codes = Vec::new();
+                for target in targets {
+                    codes.push(self.delete(target)?);
+                }
+                Ok(Box::new(move |f| {
+                    for code in &codes {
+                        code(f)?;
+                    }
+                    Ok(Flow::Next)
+                }))
+            }
+            StmtKind::Assert(test, message) => {
+                let test = self.cond(test)?;
+                let message = match message {
+                    Some(message) => {
+                        let message = self.expr(message)?;
+                        Some(self.boxed(message))
+                    }
+                    None => None,
+                };
+                Ok(Box::new(move |f| {
+                    if test(f)? {
+                        return Ok(Flow::Next);
+                    }
+                    let message = match &message {
+                        Some(message) => message(f)?.to_string(),
+                        None => String::new(),
+                    };
+                    Err(Exception::new("AssertionError", message))
+                }))
+            }
+            StmtKind::FunctionDef(def) | StmtKind::AsyncFunctionDef(def) => {
+                if let Some(decorator) = def.decorator_list.first() {
+                    return unsupported(decorator.span, "decorators");
+                }
+                let id = self.c.def_ids[&span];
+                let function = self.define(id, None)?;
+                self.assign_name(def.name, function, span).map(effect)
+            }
+            StmtKind::Nonlocal(names) => {
+                if self.def == 0 {
+                    return Err(Diagnostic::new(
+                        span,
+                        "nonlocal declaration not allowed at module level",
+                    ));
+                }
+                for name in names {
+                    if let Lookup::Free(_) = self.lookup(name) {
+                        continue;
+                    }
+                    return Err(Diagnostic::new(span, format!(
+                        "no binding for nonlocal '{}' found",
+                        name,
+                    )));
+                }
+                Ok(Box::new(|_| Ok(Flow::Next)))
+            }
+            StmtKind::ClassDef(class) => self.class_def(class, span),
+            StmtKind::Raise(exc, cause) => {
+                let exc = match exc {
+                    Some(exc) => {
+                        let exc = self.expr(exc)?;
+                        Some(self.boxed(exc))
+                    }
+                    None => None,
+                };
+                let cause = match cause {
+                    Some(cause) => {
+                        let cause = self.expr(cause)?;
+                        Some(self.boxed(cause))
+                    }
+                    None => None,
+                };
+                self.reachable = false;
+                Ok(Box::new(move |f| {
+                    let exception = match &exc {
+                        Some(exc) => {
+                            // It is raised again from here.
+                            let mut exception = runtime::to_exception(&exc(f)?);
+                            exception.span = None;
+                            exception
+                        }
+                        None => match f.rt.handling.borrow().last() {
+                            Some(exception) => exception.clone(),
+                            None => Exception::new(
+                                "RuntimeError",
+                                "No active exception to reraise",
+                            ),
+                        },
+                    };
+                    if let Some(cause) = &cause {
+                        match cause(f)? {
+                            Value::None | Value::Class(_) | Value::Exception(_) => {}
+                            _ => {
+                                return Err(Exception::new(
+                                    "TypeError",
+                                    "exception causes must derive from \
+                                        BaseException",
+                                ))
+                            }
+                        }
+                    }
+                    Err(exception)
+                }))
+            }
+            StmtKind::Try(stmt) => self.try_stmt(stmt, span, resumable),
+            StmtKind::TryStar(_) => unsupported(span, "`except*` clauses"),
+            StmtKind::AsyncFor(_) => unsupported(span, "`async for` loops"),
+            StmtKind::AsyncWith(..) => unsupported(span, "`async with` statements"),
+            StmtKind::Import(aliases) => {
+                let mut codes = Vec::new();
+                for alias in aliases {
+                    if alias.name != "asyncio" {
+                        return unsupported(
+                            alias.span,
+                            "imports of modules other than `asyncio`",
+                        );
+                    }
+                    let name = alias.asname.unwrap_or(&alias.name);
+                    let module = Typed {
+                        ty: Type::Dynamic,
+                        code: constant(Value::Module("asyncio")),
+                    };
+                    codes.push(self.assign_name(name, module, alias.span)?);
+                }
+                Ok(Box::new(move |f| {
+                    for code in &codes {
+                        code(f)?;
+                    }
+                    Ok(Flow::Next)
+                }))
+            }
+            StmtKind::ImportFrom(..) => unsupported(span, "`from` imports"),
+            StmtKind::With(..) => unsupported(span, "`with` statements"),
+            StmtKind::Match(..) => unsupported(span, "`match` statements"),
+            StmtKind::TypeAlias(..) => unsupported(span, "type aliases"),
+        }
+    }
+
+    // A `try` statement.  Names bound in the body are only known to be bound
+    // in the handlers and the `finally` clause if they were before it.
+    fn try_stmt(&mut self, stmt: &'a Try<'a>, span: Span, resumable: bool)
+        -> Result<Exec>
+    {
+        if let Some(stmt) = stmt.finalbody.iter().find(|stmt| suspends(stmt)) {
+            return unsupported(stmt.span, "`yield` in a `finally` clause");
+        }
+        let saved = self.bound.clone();
+        let body = self.block(&stmt.body)?;
+        let orelse = self.block(&stmt.orelse)?;
+        let mut handlers = Vec::new();
+        for handler in &stmt.handlers {
+            let after = (self.reachable, std::mem::replace(&mut self.bound, saved.clone()));
+            self.reachable = true;
+            let class = match &handler.ty {
+                Some(class) => {
+                    let class = self.expr(class)?;
+                    Some(self.boxed(class))
+                }
+                None => None,
+            };
+            let name = match handler.name {
+                Some(name) => {
+                    let (owner, id) = match self.lookup(name) {
+                        Lookup::Var(owner, id) if !self.is_cell(owner, id) => {
+                            (owner, id)
+                        }
+                        _ => return unsupported(
+                            handler.span,
+                            "binding an exception to a variable nested \
+                                functions use",
+                        ),
+                    };
+                    let exception = Typed {
+                        ty: Type::Dynamic,
+                        code: Code::Value(Box::new(|f| {
+                            let handling = f.rt.handling.borrow();
+                            let exception = handling.last().unwrap().clone();
+                            Ok(Value::Exception(Rc::new(exception)))
+                        })),
+                    };
+                    let store = self.assign_var(owner, id, exception, handler.span)?;
+                    Some((store, owner == 0, id))
+                }
+                None => None,
+            };
+            let body = self.block(&handler.body)?;
+            // The name is deleted when the handler finishes.
+            if let Some((_, _, id)) = name {
+                self.bound.remove(&id);
+            }
+            handlers.push(Handler { class, name, body });
+            self.merge(after);
+        }
+        let finally = match stmt.finalbody.is_empty() {
+            true => None,
+            false => {
+                let (reachable, bound) =
+                    (self.reachable, std::mem::replace(&mut self.bound, saved));
+                self.reachable = true;
+                let finally = self.block(&stmt.finalbody)?;
+                self.bound.extend(bound);
+                self.reachable &= reachable;
+                Some(finally)
+            }
+        };
+        if resumable {
+            let (keep, exception) = self.stash_boxed(Type::Dynamic, span, 42)?;
+            let handling = (keep, self.boxed(exception));
+            return Ok(generator::try_stmt(TryStmt {
+                body,
+                orelse,
+                handlers,
+                finally,
+                handling,
+            }));
+        }
+        Ok(Box::new(move |f| {
+            let mut result = match body(f) {
+                Ok(Flow::Next) => orelse(f),
+                Ok(flow) => Ok(flow),
+                Err(exception) => handle(f, &handlers, exception),
+            };
+            if let Some(finally) = &finally {
+                match finally(f)? {
+                    Flow::Next => {}
+                    flow => result = Ok(flow),
+                }
+            }
+            result
+        }))
+    }
+
+    // Continue after two branches, given whether the first can finish and
+    // the variables it definitely binds.
+    fn merge(&mut self, (reachable, bound): (bool, std::collections::HashSet<usize>)) {
+        match (reachable, self.reachable) {
+            (true, true) => {
+                self.bound = self.bound.intersection(&bound).copied().collect();
+            }
+            (true, false) => self.bound = bound,
+            _ => {}
+        }
+        self.reachable |= reachable;
+    }
+
+    /// Return a value from the function.
+    pub(super) fn ret(&mut self, value: Typed, span: Span) -> Result<Exec> {
+        if self.id == 0 {
+            return Err(Diagnostic::new(span, "'return' outside function"));
+        }
+        let from = self.c.resolve(&value.ty);
+        let ret = self.c.resolve(&self.c.instances[self.id].ret);
+        if self.c.defs[self.def].returns.is_some() {
+            if !compatible(&from, &ret) {
+                return Err(Diagnostic::new(span, format!(
+                    "mismatched types: expected `{}`, found `{}`",
+                    self.c.type_name(&ret),
+                    self.c.type_name(&from),
+                )));
+            }
+        } else {
+            let joined = self.c.join(&ret, &from);
+            if joined != ret {
+                self.c.instances[self.id].ret = joined;
+                self.c.changed = true;
+            }
+        }
+        let ret = self.c.resolve(&self.c.instances[self.id].ret);
+        fn store<T: Unboxed>(code: Fun<T>) -> Exec {
+            Box::new(move |f| {
+                let value = code(f)?;
+                T::slots_mut(&mut f.slots)[0] = value;
+                Ok(Flow::Return)
+            })
+        }
+        Ok(match self.coerce(value, &ret) {
+            Code::Int(code) => store(code),
+            Code::Float(code) => store(code),
+            Code::Bool(code) => store(code),
+            Code::Value(code) => store(code),
+        })
+    }
+
+    /// The value of a function definition or lambda: code evaluating the
+    /// default values of its parameters, which is a closure holding the
+    /// cells it uses if it uses variables of enclosing functions.  A method
+    /// using `super()` also holds a cell with the class, which `class`
+    /// reads.
+    pub(super) fn define(&mut self, def: usize, class: Option<Fun<Value>>)
+        -> Result<Typed>
+    {
+        let args = self.c.defs[def].args.unwrap();
+        let positional = self.c.defs[def].positional;
+        let first = positional - args.defaults.len();
+        let defaults = args.defaults.iter()
+            .enumerate()
+            .map(|(i, default)| (first + i, default))
+            .chain(args.kw_defaults.iter()
+                .enumerate()
+                .filter_map(|(i, default)| Some((positional + i, default.as_ref()?))));
+        let mut stores = Vec::new();
+        for (i, default) in defaults {
+            let value = self.expr(default)?;
+            let id = self.var(0, VarKey::Default(def, i));
+            stores.push(self.assign_var(0, id, value, default.span)?);
+        }
+        let define = move |f: &mut Frame<'_>| {
+            for store in &stores {
+                store(f)?;
+            }
+            Ok(())
+        };
+        let class = class.filter(|_| self.c.defs[def].uses_class);
+        if self.c.defs[def].free.is_empty() && class.is_none() {
+            let code = Code::Value(Box::new(move |f| {
+                define(f)?;
+                Ok(Value::None)
+            }));
+            return Ok(Typed { ty: Type::Function(def), code });
+        }
+        // A closure is a boxed function holding the cells it uses.
+        let id = self.c.generic(def);
+        self.uses.push(id);
+        let name = self.c.defs[def].name.to_string();
+        let free = self.c.defs[def].free.clone();
+        let cells: Vec<_> = free.iter()
+            .filter_map(|name| self.cell_index(name))
+            .collect();
+        let code = Code::Value(Box::new(move |f| {
+            define(f)?;
+            let mut closure: Vec<_> = cells.iter()
+                .map(|&cell| f.slots.cells[cell].clone())
+                .collect();
+            if let Some(class) = &class {
+                closure.push(Rc::new(RefCell::new(Some(class(f)?))));
+            }
+            Ok(Value::Function(Rc::new(Function {
+                name: name.clone(),
+                id,
+                closure,
+            })))
+        }));
+        Ok(Typed { ty: Type::Dynamic, code })
+    }
+
+    // A `class` statement: code making the class, setting the attributes
+    // its body defines, and binding its name.  The body may only define
+    // methods and assign to names.
+    fn class_def(&mut self, class: &'a ClassDef<'a>, span: Span)
+        -> Result<Exec>
+    {
+        if let Some(decorator) = class.decorator_list.first() {
+            return unsupported(decorator.span, "decorators");
+        }
+        if let Some(keyword) = class.keywords.first() {
+            return unsupported(keyword.span, "class keywords");
+        }
+        let base = match class.bases.as_slice() {
+            [] => None,
+            [base] => {
+                let lookup = match base.kind {
+                    ExprKind::Name(name, _) => Some(self.lookup(name)),
+                    _ => None,
+                };
+                match (&base.kind, lookup) {
+                    (_, Some(Lookup::Builtin)) => {
+                        return unsupported(
+                            base.span,
+                            "subclasses of built-in classes",
+                        )
+                    }
+                    (ExprKind::Name("object", _), Some(Lookup::Undefined)) => None,
+                    _ => {
+                        let base = self.expr(base)?;
+                        Some(self.dynamic(base))
+                    }
+                }
+            }
+            [_, base, ..] => return unsupported(base.span, "multiple inheritance"),
+        };
+        let name = self.c.classes[&span];
+        let make = Code::Value(Box::new(move |f| {
+            let base = match &base {
+                Some(base) => match base(f)? {
+                    Value::Type(base) => Some(base),
+                    other => {
+                        return Err(Exception::new("TypeError", format!(
+                            "class bases must be classes, not '{}'",
+                            other.type_name(),
+                        )))
+                    }
+                },
+                None => None,
+            };
+            Ok(Value::Type(Rc::new(Class::new(name, base))))
+        }));
+        let made = Typed { ty: Type::Dynamic, code: make };
+        let (store, _) = self.stash(made, span, 80)?;
+        let mut names = Vec::new();
+        let mut attributes = Vec::new();
+        for stmt in &class.body {
+            let (name, value) = match &stmt.kind {
+                StmtKind::Pass | StmtKind::AnnAssign(_, _, None, _) => continue,
+                // A docstring
+                StmtKind::Expr(Expr {
+                    kind: ExprKind::Constant(Constant::Str(_)),
+                    ..
+                }) => continue,
+                StmtKind::FunctionDef(def) | StmtKind::AsyncFunctionDef(def) => {
+                    if let Some(decorator) = def.decorator_list.first() {
+                        return unsupported(decorator.span, "decorators");
+                    }
+                    let special = def.name.starts_with("__")
+                        && def.name.ends_with("__");
+                    if special && def.name != "__init__" {
+                        return unsupported(
+                            stmt.span,
+                            "special methods other than `__init__`",
+                        );
+                    }
+                    let id = self.c.def_ids[&stmt.span];
+                    let class = self.reread(span, 80);
+                    let class = self.boxed(class);
+                    let function = self.define(id, Some(class))?;
+                    (def.name, self.boxed(function))
+                }
+                _ => match assigned_name(stmt) {
+                    Some((name, value)) => {
+                        // The class body's names aren't variables of the
+                        // function it is in.
+                        if let Some(used) = mentions(value, &names) {
+                            return unsupported(
+                                used.span,
+                                "using a class attribute by name in the class \
+                                    body",
+                            );
+                        }
+                        let value = self.expr(value)?;
+                        (name, self.dynamic(value))
+                    }
+                    None => {
+                        return unsupported(
+                            stmt.span,
+                            "this statement in a class body",
+                        )
+                    }
+                },
+            };
+            names.push(name);
+            attributes.push((name.to_string(), value));
+        }
+        let made = self.reread(span, 80);
+        let made = self.boxed(made);
+        let value = self.reread(span, 80);
+        let bind = self.assign_name(class.name, value, span)?;
+        Ok(Box::new(move |f| {
+            store(f)?;
+            let class = match made(f)? {
+                Value::Type(class) => class,
+                _ => unreachable!("the class was just made"),
+            };
+            for (name, value) in &attributes {
+                class.set(name, value(f)?);
+            }
+            bind(f)?;
+            Ok(Flow::Next)
+        }))
+    }
+
+    // Code reading a value stored with `stash` again.
+    fn reread(&mut self, span: Span, tag: u8) -> Typed {
+        let id = self.temp(span, tag);
+        self.read_var(self.id, id)
+    }
+
+    /// Assign a value to a target.
+    pub(super) fn assign(&mut self, target: &'a Expr<'a>, value: Typed)
+        -> Result<Fun<()>>
+    {
+        let span = target.span;
+        match &target.kind {
+            ExprKind::Name(name, _) => self.assign_name(name, value, span),
+            ExprKind::Tuple(targets, _) | ExprKind::List(targets, _) => {
+                self.unpack(targets, value, span)
+            }
+            ExprKind::Subscript(container, index, _) => {
+                if let ExprKind::Slice(..) = index.kind {
+                    return unsupported(span, "slice assignments");
+                }
+                let container = self.expr(container)?;
+                let index = self.expr(index)?;
+                Ok(self.set_item(container, index, value))
+            }
+            ExprKind::Attribute(receiver, name, _) => {
+                let receiver = self.expr(receiver)?;
+                Ok(self.set_attribute(receiver, name, value))
+            }
+            _ => Err(Diagnostic::new(span, "can't assign to this expression")),
+        }
+    }
+
+    // `container[index] = item`, evaluating `item` first.
+    fn set_item(&mut self, container: Typed, index: Typed, item: Typed)
+        -> Fun<()>
+    {
+        match self.c.resolve(&container.ty) {
+            Type::List(id) => self.c.store_value(id, &item.ty),
+            Type::Dict(id) => {
+                self.c.store_key(id, &index.ty);
+                self.c.store_value(id, &item.ty);
+            }
+            _ => {
+                self.c.escape(&index.ty);
+                self.c.escape(&item.ty);
+            }
+        }
+        let container = self.boxed(container);
+        let index = self.boxed(index);
+        let item = self.boxed(item);
+        Box::new(move |f| {
+            let item = item(f)?;
+            let container = container(f)?;
+            runtime::set_item(&container, &index(f)?, item)
+        })
+    }
+
+    // `receiver.name = value`, evaluating `value` first.
+    fn set_attribute(&mut self, receiver: Typed, name: &str, value: Typed)
+        -> Fun<()>
+    {
+        let receiver = self.boxed(receiver);
+        let value = self.dynamic(value);
+        let name = name.to_string();
+        Box::new(move |f| {
+            let value = value(f)?;
+            runtime::set_attribute(&receiver(f)?, &name, value)
+        })
+    }
+
+    // `a, b, *c = value`
+    fn unpack(&mut self, targets: &'a [Expr<'a>], value: Typed, span: Span)
+        -> Result<Fun<()>>
+    {
+        let count = targets.len();
+        let star = targets.iter()
+            .position(|target| matches!(target.kind, ExprKind::Starred(..)));
+        let value = match (self.c.resolve(&value.ty), star) {
+            (Type::Tuple(items), None) if items.len() == count => value,
+            (ty, _) => {
+                let element = self.element(&ty);
+                let mut types = vec![element.clone(); count];
+                if let Some(star) = star {
+                    let site = self.c.site(span, 0);
+                    self.c.store_value(site, &element);
+                    types[star] = Type::List(site);
+                }
+                let source = self.boxed(value);
+                let code = Code::Value(Box::new(move |f| {
+                    let items = runtime::items(&source(f)?)?;
+                    unpacked(items, count, star)
+                }));
+                Typed { ty: Type::Tuple(types), code }
+            }
+        };
+        let (store, _) = self.stash(value, span, 60)?;
+        let mut codes = vec![store];
+        for (i, target) in targets.iter().enumerate() {
+            let tuple = self.reread(span, 60);
+            let index = Typed { ty: Type::Int, code: constant(Int::Small(i as i64)) };
+            let item = self.subscript(tuple, index, Some(i as i64));
+            let target = match &target.kind {
+                ExprKind::Starred(target, _) => target,
+                _ => target,
+            };
+            codes.push(self.assign(target, item)?);
+        }
+        Ok(Box::new(move |f| {
+            for code in &codes {
+                code(f)?;
+            }
+            Ok(())
+        }))
+    }
+
+    fn aug_assign(
+        &mut self,
+        target: &'a Expr<'a>,
+        op: super::super::BinaryOp,
+        value: &'a Expr<'a>,
+        span: Span,
+    ) -> Result<Fun<()>> {
+        let exponent = match &value.kind {
+            ExprKind::Constant(Constant::Int(value)) => Some(*value),
+            _ => None,
+        };
+        match &target.kind {
+            ExprKind::Name(..) => {
+                let current = self.expr(target)?;
+                let value = self.expr(value)?;
+                let result = self.in_place(current, op, value, exponent, span);
+                self.assign(target, result)
+            }
+            ExprKind::Subscript(container, index, _) => {
+                if let ExprKind::Slice(..) = index.kind {
+                    return unsupported(span, "slice assignments");
+                }
+                let container = self.expr(container)?;
+                let constant = constant_index(index);
+                let index = self.expr(index)?;
+                let (store_container, container) = self.stash(container, span, 70)?;
+                let (store_index, index) = self.stash(index, span, 71)?;
+                let current = self.subscript(container, index, constant);
+                let value = self.expr(value)?;
+                let result = self.in_place(current, op, value, exponent, span);
+                let container = self.reread(span, 70);
+                let index = self.reread(span, 71);
+                let store = self.set_item(container, index, result);
+                Ok(Box::new(move |f| {
+                    store_container(f)?;
+                    store_index(f)?;
+                    store(f)
+                }))
+            }
+            ExprKind::Attribute(receiver, name, _) => {
+                let receiver = self.expr(receiver)?;
+                let (store_receiver, receiver) = self.stash(receiver, span, 72)?;
+                let current = self.attribute(receiver, name, span)?;
+                let value = self.expr(value)?;
+                let result = self.in_place(current, op, value, exponent, span);
+                let receiver = self.reread(span, 72);
+                let store = self.set_attribute(receiver, name, result);
+                Ok(Box::new(move |f| {
+                    store_receiver(f)?;
+                    store(f)
+                }))
+            }
+            _ => Err(Diagnostic::new(span, "can't assign to this expression")),
+        }
+    }
+
+    // `current op= value`, which extends lists in place.
+    fn in_place(
+        &mut self,
+        current: Typed,
+        op: super::super::BinaryOp,
+        value: Typed,
+        exponent: Option<u128>,
+        span: Span,
+    ) -> Typed {
+        if let (Type::List(id), super::super::BinaryOp::Add) =
+            (self.c.resolve(&current.ty), op)
+        {
+            let element = self.element(&value.ty);
+            self.c.store_value(id, &element);
+            let current = self.boxed(current);
+            let value = self.boxed(value);
+            let code = Code::Value(Box::new(move |f| {
+                let list = current(f)?;
+                runtime::call_method(&list, "extend", vec![value(f)?])?;
+                Ok(list)
+            }));
+            return Typed { ty: Type::List(id), code };
+        }
+        self.binary(current, op, value, exponent, span)
+    }
+
+    fn delete(&mut self, target: &'a Expr<'a>) -> Result<Fun<()>> {
+        let span = target.span;
+        match &target.kind {
+            ExprKind::Name(name, _) => {
+                let (owner, id) = match self.lookup(name) {
+                    Lookup::Var(owner, id) if !self.is_cell(owner, id) => {
+                        (owner, id)
+                    }
+                    _ => return unsupported(
+                        span,
+                        "deleting a variable nested functions use",
+                    ),
+                };
+                // Check that it is bound.
+                let read = self.read_var(owner, id);
+                self.bound.remove(&id);
+                let global = owner == 0;
+                Ok(Box::new(move |f| {
+                    read.code.run(f)?;
+                    match global {
+                        true => f.rt.globals.borrow_mut().bound[id] = false,
+                        false => f.slots.bound[id] = false,
+                    }
+                    Ok(())
+                }))
+            }
+            ExprKind::Subscript(container, index, _) => {
+                if let ExprKind::Slice(..) = index.kind {
+                    return unsupported(span, "slice deletions");
+                }
+                let container = self.expr(container)?;
+                let container = self.boxed(container);
+                let index = self.expr(index)?;
+                let index = self.boxed(index);
+                Ok(Box::new(move |f| {
+                    let container = container(f)?;
+                    runtime::del_item(&container, &index(f)?)
+                }))
+            }
+            ExprKind::Attribute(receiver, name, _) => {
+                let receiver = self.expr(receiver)?;
+                let receiver = self.boxed(receiver);
+                let name = name.to_string();
+                Ok(Box::new(move |f| {
+                    runtime::delete_attribute(&receiver(f)?, &name)
+                }))
+            }
+            ExprKind::Tuple(targets, _) | ExprKind::List(targets, _) => {
+                let mut codes = Vec::new();
+                for target in targets {
+                    codes.push(self.delete(target)?);
+                }
+                Ok(Box::new(move |f| {
+                    for code in &codes {
+                        code(f)?;
+                    }
+                    Ok(())
+                }))
+            }
+            _ => Err(Diagnostic::new(span, "can't delete this expression")),
+        }
+    }
+
+    /// A loop over the items of an iterable, and code reading the current
+    /// item.
+    pub(super) fn iterate(&mut self, iterable: Typed, span: Span, tag: u8)
+        -> Result<(Items, Typed)>
+    {
+        let element = self.element(&iterable.ty);
+        let source = self.boxed(iterable);
+        let (store, item) = self.stash_boxed(element, span, tag)?;
+        Ok((Items { source, store }, item))
+    }
+
+    /// The loops of a comprehension, running the code `produce` compiles
+    /// for each item.
+    pub(super) fn comprehension(
+        &mut self,
+        generators: &'a [Comprehension<'a>],
+        span: Span,
+        produce: impl FnOnce(&mut Self) -> Result<Exec>,
+    ) -> Result<Exec> {
+        // The first iterable is evaluated outside the comprehension's scope.
+        let mut first = Some(self.expr(&generators[0].iter)?);
+        let saved = self.bound.clone();
+        let mut names = std::collections::HashSet::new();
+        let mut scope = Vec::new();
+        for generator in generators {
+            let mut bound = std::collections::HashSet::new();
+            expr_bound_names(&generator.target, &mut bound);
+            let mut bound: Vec<_> = bound.into_iter().collect();
+            bound.sort_unstable();
+            for name in bound {
+                if names.insert(name) {
+                    let id = self.var(self.id, VarKey::Comprehension(name, span));
+                    scope.push((name, id));
+                }
+            }
+        }
+        self.scopes.push(scope);
+        let mut loops = Vec::new();
+        for (i, generator) in generators.iter().enumerate() {
+            if generator.is_async {
+                return unsupported(generator.iter.span, "`async` code");
+            }
+            let iterable = match first.take() {
+                Some(iterable) => iterable,
+                None => self.expr(&generator.iter)?,
+            };
+            let (items, item) = self.iterate(iterable, span, 1 + i as u8)?;
+            let target = self.assign(&generator.target, item)?;
+            let mut conds = Vec::new();
+            for test in &generator.ifs {
+                conds.push(self.cond(test)?);
+            }
+            loops.push((items, target, conds));
+        }
+        let mut body = produce(self)?;
+        self.scopes.pop();
+        self.bound = saved;
+        for (items, target, conds) in loops.into_iter().rev() {
+            let inner = body;
+            body = Box::new(move |f| {
+                items.run(f, &|f| {
+                    target(f)?;
+                    for cond in &conds {
+                        if !cond(f)? {
+                            return Ok(Flow::Next);
+                        }
+                    }
+                    inner(f)
+                })?;
+                Ok(Flow::Next)
+            });
+        }
+        Ok(body)
+    }
+}
+
+// The items being unpacked into `count` targets, with those for the starred
+// target at `star` collected in a list.
+fn unpacked(mut items: Vec<Value>, count: usize, star: Option<usize>)
+    -> Eval<Value>
+{
+    let len = items.len();
+    match star {
+        None if len > count => Err(Exception::new("ValueError", format!(
+            "too many values to unpack (expected {})",
+            count,
+        ))),
+        None if len < count => Err(Exception::new("ValueError", format!(
+            "not enough values to unpack (expected {}, got {})",
+            count, len,
+        ))),
+        None => Ok(Value::tuple(items)),
+        Some(_) if len < count - 1 => Err(Exception::new("ValueError", format!(
+            "not enough values to unpack (expected at least {}, got {})",
+            count - 1,
+            len,
+        ))),
+        Some(star) => {
+            let after = count - 1 - star;
+            let rest: Vec<_> = items.drain(star..len - after).collect();
+            items.insert(star, Value::list(rest));
+            Ok(Value::tuple(items))
+        }
+    }
+}
+
+// The name and value of an assignment to a name.
+fn assigned_name<'a>(stmt: &'a Stmt<'a>) -> Option<(&'a str, &'a Expr<'a>)> {
+    let (target, value) = match &stmt.kind {
+        StmtKind::Assign(targets, value) if targets.len() == 1 => {
+            (&targets[0], value)
+        }
+        StmtKind::AnnAssign(target, _, Some(value), _) => (target, value),
+        _ => return None,
+    };
+    match target.kind {
+        ExprKind::Name(name, _) => Some((name, value)),
+        _ => None,
+    }
+}
+
+// A name among `names` an expression uses, not counting the bodies of the
+// lambdas in it.
+fn mentions<'a>(expr: &'a Expr<'a>, names: &[&str]) -> Option<&'a Expr<'a>> {
+    match expr.kind {
+        ExprKind::Name(name, _) if names.contains(&name) => Some(expr),
+        _ => children(expr).into_iter().find_map(|child| mentions(child, names)),
+    }
+}
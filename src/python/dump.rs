@@ -0,0 +1,491 @@
+// Python AST dump
+//
+//! Formats a module like Python 3.12's `ast.dump`, so a tree can be compared
+//! with the one CPython builds.  Optional fields that are `None` are left
+//! out, as `ast.dump` does.
+
+use super::{
+    Alias, Arg, Arguments, BinaryOp, CmpOp, Comprehension, Constant, Expr,
+    ExprContext, ExprKind, For, FunctionDef, KeywordArg, Module, Pattern,
+    PatternKind, Stmt, StmtKind, Try, TypeParam, WithItem,
+};
+use super::runtime::{repr_bytes, repr_float, repr_str};
+
+impl Module<'_> {
+    /// Format the module like Python's `ast.dump(module)`.
+    pub fn dump(&self) -> String {
+        node("Module", &[
+            ("body", body(&self.body)),
+            ("type_ignores", Some("[]".to_string())),
+        ])
+    }
+}
+
+// `Name(field=value, ...)`, leaving out fields without a value.
+fn node(name: &str, fields: &[(&str, Option<String>)]) -> String {
+    let fields: Vec<_> = fields
+        .iter()
+        .filter_map(|(field, value)| {
+            value.as_ref().map(|value| format!("{}={}", field, value))
+        })
+        .collect();
+    format!("{}({})", name, fields.join(", "))
+}
+
+fn list<T>(items: &[T], f: impl Fn(&T) -> String) -> String {
+    let items: Vec<_> = items.iter().map(f).collect();
+    format!("[{}]", items.join(", "))
+}
+
+fn body(stmts: &[Stmt<'_>]) -> Option<String> {
+    Some(list(stmts, stmt))
+}
+
+fn exprs(exprs: &[Expr<'_>]) -> Option<String> {
+    Some(list(exprs, expr))
+}
+
+fn optional(value: &Option<Expr<'_>>) -> Option<String> {
+    value.as_ref().map(expr)
+}
+
+fn boxed(value: &Option<Box<Expr<'_>>>) -> Option<String> {
+    value.as_deref().map(expr)
+}
+
+fn name(name: &Option<&str>) -> Option<String> {
+    name.map(repr_str)
+}
+
+fn names(names: &[&str]) -> Option<String> {
+    Some(list(names, |name| repr_str(name)))
+}
+
+fn stmt(stmt: &Stmt<'_>) -> String {
+    match stmt.kind {
+        StmtKind::FunctionDef(ref def) => function_def("FunctionDef", def),
+        StmtKind::AsyncFunctionDef(ref def) => {
+            function_def("AsyncFunctionDef", def)
+        }
+        StmtKind::ClassDef(ref def) => node("ClassDef", &[
+            ("name", Some(repr_str(def.name))),
+            ("bases", exprs(&def.bases)),
+            ("keywords", Some(list(&def.keywords, keyword))),
+            ("body", body(&def.body)),
+            ("decorator_list", exprs(&def.decorator_list)),
+            ("type_params", Some(list(&def.type_params, type_param))),
+        ]),
+        StmtKind::Return(ref value) => {
+            node("Return", &[("value", optional(value))])
+        }
+        StmtKind::Delete(ref targets) => {
+            node("Delete", &[("targets", exprs(targets))])
+        }
+        StmtKind::Assign(ref targets, ref value) => node("Assign", &[
+            ("targets", exprs(targets)),
+            ("value", Some(expr(value))),
+        ]),
+        StmtKind::TypeAlias(ref name, ref params, ref value) => {
+            node("TypeAlias", &[
+                ("name", Some(expr(name))),
+                ("type_params", Some(list(params, type_param))),
+                ("value", Some(expr(value))),
+            ])
+        }
+        StmtKind::AugAssign(ref target, op, ref value) => node("AugAssign", &[
+            ("target", Some(expr(target))),
+            ("op", Some(binary_op(op).to_string())),
+            ("value", Some(expr(value))),
+        ]),
+        StmtKind::AnnAssign(ref target, ref annotation, ref value, simple) => {
+            node("AnnAssign", &[
+                ("target", Some(expr(target))),
+                ("annotation", Some(expr(annotation))),
+                ("value", optional(value)),
+                ("simple", Some((simple as u8).to_string())),
+            ])
+        }
+        StmtKind::For(ref stmt) => for_loop("For", stmt),
+        StmtKind::AsyncFor(ref stmt) => for_loop("AsyncFor", stmt),
+        StmtKind::While(ref test, ref stmts, ref orelse) => node("While", &[
+            ("test", Some(expr(test))),
+            ("body", body(stmts)),
+            ("orelse", body(orelse)),
+        ]),
+        StmtKind::If(ref test, ref stmts, ref orelse) => node("If", &[
+            ("test", Some(expr(test))),
+            ("body", body(stmts)),
+            ("orelse", body(orelse)),
+        ]),
+        StmtKind::With(ref items, ref stmts) => node("With", &[
+            ("items", Some(list(items, with_item))),
+            ("body", body(stmts)),
+        ]),
+        StmtKind::AsyncWith(ref items, ref stmts) => node("AsyncWith", &[
+            ("items", Some(list(items, with_item))),
+            ("body", body(stmts)),
+        ]),
+        StmtKind::Match(ref subject, ref cases) => node("Match", &[
+            ("subject", Some(expr(subject))),
+            ("cases", Some(list(cases, |case| node("match_case", &[
+                ("pattern", Some(pattern(&case.pattern))),
+                ("guard", optional(&case.guard)),
+                ("body", body(&case.body)),
+            ])))),
+        ]),
+        StmtKind::Raise(ref exc, ref cause) => node("Raise", &[
+            ("exc", optional(exc)),
+            ("cause", optional(cause)),
+        ]),
+        StmtKind::Try(ref stmt) => try_stmt("Try", stmt),
+        StmtKind::TryStar(ref stmt) => try_stmt("TryStar", stmt),
+        StmtKind::Assert(ref test, ref msg) => node("Assert", &[
+            ("test", Some(expr(test))),
+            ("msg", optional(msg)),
+        ]),
+        StmtKind::Import(ref aliases) => {
+            node("Import", &[("names", Some(list(aliases, alias)))])
+        }
+        StmtKind::ImportFrom(ref module, ref aliases, level) => {
+            node("ImportFrom", &[
+                ("module", module.as_deref().map(repr_str)),
+                ("names", Some(list(aliases, alias))),
+                ("level", Some(level.to_string())),
+            ])
+        }
+        StmtKind::Global(ref list) => node("Global", &[("names", names(list))]),
+        StmtKind::Nonlocal(ref list) => {
+            node("Nonlocal", &[("names", names(list))])
+        }
+        StmtKind::Expr(ref value) => {
+            node("Expr", &[("value", Some(expr(value)))])
+        }
+        StmtKind::Pass => "Pass()".to_string(),
+        StmtKind::Break => "Break()".to_string(),
+        StmtKind::Continue => "Continue()".to_string(),
+    }
+}
+
+fn function_def(kind: &str, def: &FunctionDef<'_>) -> String {
+    node(kind, &[
+        ("name", Some(repr_str(def.name))),
+        ("args", Some(arguments(&def.args))),
+        ("body", body(&def.body)),
+        ("decorator_list", exprs(&def.decorator_list)),
+        ("returns", optional(&def.returns)),
+        ("type_params", Some(list(&def.type_params, type_param))),
+    ])
+}
+
+fn for_loop(kind: &str, stmt: &For<'_>) -> String {
+    node(kind, &[
+        ("target", Some(expr(&stmt.target))),
+        ("iter", Some(expr(&stmt.iter))),
+        ("body", body(&stmt.body)),
+        ("orelse", body(&stmt.orelse)),
+    ])
+}
+
+fn try_stmt(kind: &str, stmt: &Try<'_>) -> String {
+    node(kind, &[
+        ("body", body(&stmt.body)),
+        ("handlers", Some(list(&stmt.handlers, |handler| {
+            node("ExceptHandler", &[
+                ("type", optional(&handler.ty)),
+                ("name", name(&handler.name)),
+                ("body", body(&handler.body)),
+            ])
+        }))),
+        ("orelse", body(&stmt.orelse)),
+        ("finalbody", body(&stmt.finalbody)),
+    ])
+}
+
+fn with_item(item: &WithItem<'_>) -> String {
+    node("withitem", &[
+        ("context_expr", Some(expr(&item.context_expr))),
+        ("optional_vars", optional(&item.optional_vars)),
+    ])
+}
+
+fn alias(alias: &Alias<'_>) -> String {
+    node("alias", &[
+        ("name", Some(repr_str(&alias.name))),
+        ("asname", name(&alias.asname)),
+    ])
+}
+
+fn type_param(param: &TypeParam<'_>) -> String {
+    match param {
+        TypeParam::TypeVar(name, bound) => node("TypeVar", &[
+            ("name", Some(repr_str(name))),
+            ("bound", optional(bound)),
+        ]),
+        TypeParam::ParamSpec(name) => {
+            node("ParamSpec", &[("name", Some(repr_str(name)))])
+        }
+        TypeParam::TypeVarTuple(name) => {
+            node("TypeVarTuple", &[("name", Some(repr_str(name)))])
+        }
+    }
+}
+
+fn arguments(args: &Arguments<'_>) -> String {
+    let arg = |arg: &Arg<'_>| {
+        node("arg", &[
+            ("arg", Some(repr_str(arg.arg))),
+            ("annotation", optional(&arg.annotation)),
+        ])
+    };
+    let kw_defaults = list(&args.kw_defaults, |default| {
+        default.as_ref().map_or("None".to_string(), expr)
+    });
+    node("arguments", &[
+        ("posonlyargs", Some(list(&args.posonlyargs, arg))),
+        ("args", Some(list(&args.args, arg))),
+        ("vararg", args.vararg.as_ref().map(arg)),
+        ("kwonlyargs", Some(list(&args.kwonlyargs, arg))),
+        ("kw_defaults", Some(kw_defaults)),
+        ("kwarg", args.kwarg.as_ref().map(arg)),
+        ("defaults", exprs(&args.defaults)),
+    ])
+}
+
+fn keyword(keyword: &KeywordArg<'_>) -> String {
+    node("keyword", &[
+        ("arg", name(&keyword.arg)),
+        ("value", Some(expr(&keyword.value))),
+    ])
+}
+
+fn comprehension(generator: &Comprehension<'_>) -> String {
+    node("comprehension", &[
+        ("target", Some(expr(&generator.target))),
+        ("iter", Some(expr(&generator.iter))),
+        ("ifs", exprs(&generator.ifs)),
+        ("is_async", Some((generator.is_async as u8).to_string())),
+    ])
+}
+
+fn context(ctx: ExprContext) -> Option<String> {
+    Some(format!("{:?}()", ctx))
+}
+
+fn expr(expr: &Expr<'_>) -> String {
+    let generators = |generators: &[Comprehension<'_>]| {
+        Some(list(generators, comprehension))
+    };
+    match expr.kind {
+        ExprKind::BoolOp(op, ref values) => node("BoolOp", &[
+            ("op", Some(format!("{:?}()", op))),
+            ("values", exprs(values)),
+        ]),
+        ExprKind::NamedExpr(ref target, ref value) => node("NamedExpr", &[
+            ("target", Some(self::expr(target))),
+            ("value", Some(self::expr(value))),
+        ]),
+        ExprKind::BinOp(ref left, op, ref right) => node("BinOp", &[
+            ("left", Some(self::expr(left))),
+            ("op", Some(binary_op(op).to_string())),
+            ("right", Some(self::expr(right))),
+        ]),
+        ExprKind::UnaryOp(op, ref operand) => node("UnaryOp", &[
+            ("op", Some(format!("{:?}()", op))),
+            ("operand", Some(self::expr(operand))),
+        ]),
+        ExprKind::Lambda(ref args, ref body) => node("Lambda", &[
+            ("args", Some(arguments(args))),
+            ("body", Some(self::expr(body))),
+        ]),
+        ExprKind::IfExp(ref test, ref body, ref orelse) => node("IfExp", &[
+            ("test", Some(self::expr(test))),
+            ("body", Some(self::expr(body))),
+            ("orelse", Some(self::expr(orelse))),
+        ]),
+        ExprKind::Dict(ref keys, ref values) => node("Dict", &[
+            ("keys", Some(list(keys, |key| {
+                key.as_ref().map_or("None".to_string(), self::expr)
+            }))),
+            ("values", exprs(values)),
+        ]),
+        ExprKind::Set(ref elts) => node("Set", &[("elts", exprs(elts))]),
+        ExprKind::ListComp(ref elt, ref gens) => node("ListComp", &[
+            ("elt", Some(self::expr(elt))),
+            ("generators", generators(gens)),
+        ]),
+        ExprKind::SetComp(ref elt, ref gens) => node("SetComp", &[
+            ("elt", Some(self::expr(elt))),
+            ("generators", generators(gens)),
+        ]),
+        ExprKind::DictComp(ref key, ref value, ref gens) => {
+            node("DictComp", &[
+                ("key", Some(self::expr(key))),
+                ("value", Some(self::expr(value))),
+                ("generators", generators(gens)),
+            ])
+        }
+        ExprKind::GeneratorExp(ref elt, ref gens) => node("GeneratorExp", &[
+            ("elt", Some(self::expr(elt))),
+            ("generators", generators(gens)),
+        ]),
+        ExprKind::Await(ref value) => {
+            node("Await", &[("value", Some(self::expr(value)))])
+        }
+        ExprKind::Yield(ref value) => node("Yield", &[("value", boxed(value))]),
+        ExprKind::YieldFrom(ref value) => {
+            node("YieldFrom", &[("value", Some(self::expr(value)))])
+        }
+        ExprKind::Compare(ref left, ref ops, ref comparators) => {
+            node("Compare", &[
+                ("left", Some(self::expr(left))),
+                ("ops", Some(list(ops, |op| cmp_op(*op).to_string()))),
+                ("comparators", exprs(comparators)),
+            ])
+        }
+        ExprKind::Call(ref func, ref args, ref keywords) => node("Call", &[
+            ("func", Some(self::expr(func))),
+            ("args", exprs(args)),
+            ("keywords", Some(list(keywords, keyword))),
+        ]),
+        ExprKind::FormattedValue(ref value, conversion, ref spec) => {
+            let conversion = conversion.map_or(-1, |c| c as i32);
+            node("FormattedValue", &[
+                ("value", Some(self::expr(value))),
+                ("conversion", Some(conversion.to_string())),
+                ("format_spec", boxed(spec)),
+            ])
+        }
+        ExprKind::JoinedStr(ref values) => {
+            node("JoinedStr", &[("values", exprs(values))])
+        }
+        ExprKind::Constant(ref value) => {
+            node("Constant", &[("value", Some(constant(value)))])
+        }
+        ExprKind::Attribute(ref value, attr, ctx) => node("Attribute", &[
+            ("value", Some(self::expr(value))),
+            ("attr", Some(repr_str(attr))),
+            ("ctx", context(ctx)),
+        ]),
+        ExprKind::Subscript(ref value, ref slice, ctx) => node("Subscript", &[
+            ("value", Some(self::expr(value))),
+            ("slice", Some(self::expr(slice))),
+            ("ctx", context(ctx)),
+        ]),
+        ExprKind::Starred(ref value, ctx) => node("Starred", &[
+            ("value", Some(self::expr(value))),
+            ("ctx", context(ctx)),
+        ]),
+        ExprKind::Name(id, ctx) => node("Name", &[
+            ("id", Some(repr_str(id))),
+            ("ctx", context(ctx)),
+        ]),
+        ExprKind::List(ref elts, ctx) => node("List", &[
+            ("elts", exprs(elts)),
+            ("ctx", context(ctx)),
+        ]),
+        ExprKind::Tuple(ref elts, ctx) => node("Tuple", &[
+            ("elts", exprs(elts)),
+            ("ctx", context(ctx)),
+        ]),
+        ExprKind::Slice(ref lower, ref upper, ref step) => node("Slice", &[
+            ("lower", boxed(lower)),
+            ("upper", boxed(upper)),
+            ("step", boxed(step)),
+        ]),
+    }
+}
+
+fn pattern(pattern: &Pattern<'_>) -> String {
+    let patterns = |patterns: &[Pattern<'_>]| {
+        Some(list(patterns, self::pattern))
+    };
+    match pattern.kind {
+        PatternKind::MatchValue(ref value) => {
+            node("MatchValue", &[("value", Some(expr(value)))])
+        }
+        PatternKind::MatchSingleton(ref value) => {
+            node("MatchSingleton", &[("value", Some(constant(value)))])
+        }
+        PatternKind::MatchSequence(ref items) => {
+            node("MatchSequence", &[("patterns", patterns(items))])
+        }
+        PatternKind::MatchMapping(ref keys, ref items, rest) => {
+            node("MatchMapping", &[
+                ("keys", exprs(keys)),
+                ("patterns", patterns(items)),
+                ("rest", name(&rest)),
+            ])
+        }
+        PatternKind::MatchClass(ref cls, ref items, ref attrs, ref kwds) => {
+            node("MatchClass", &[
+                ("cls", Some(expr(cls))),
+                ("patterns", patterns(items)),
+                ("kwd_attrs", names(attrs)),
+                ("kwd_patterns", patterns(kwds)),
+            ])
+        }
+        PatternKind::MatchStar(star) => {
+            node("MatchStar", &[("name", name(&star))])
+        }
+        PatternKind::MatchAs(ref inner, id) => node("MatchAs", &[
+            ("pattern", inner.as_deref().map(self::pattern)),
+            ("name", name(&id)),
+        ]),
+        PatternKind::MatchOr(ref items) => {
+            node("MatchOr", &[("patterns", patterns(items))])
+        }
+    }
+}
+
+fn binary_op(op: BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "Add()",
+        BinaryOp::Sub => "Sub()",
+        BinaryOp::Mult => "Mult()",
+        BinaryOp::MatMult => "MatMult()",
+        BinaryOp::Div => "Div()",
+        BinaryOp::Mod => "Mod()",
+        BinaryOp::Pow => "Pow()",
+        BinaryOp::LShift => "LShift()",
+        BinaryOp::RShift => "RShift()",
+        BinaryOp::BitOr => "BitOr()",
+        BinaryOp::BitXor => "BitXor()",
+        BinaryOp::BitAnd => "BitAnd()",
+        BinaryOp::FloorDiv => "FloorDiv()",
+    }
+}
+
+fn cmp_op(op: CmpOp) -> &'static str {
+    match op {
+        CmpOp::Eq => "Eq()",
+        CmpOp::NotEq => "NotEq()",
+        CmpOp::Lt => "Lt()",
+        CmpOp::LtE => "LtE()",
+        CmpOp::Gt => "Gt()",
+        CmpOp::GtE => "GtE()",
+        CmpOp::Is => "Is()",
+        CmpOp::IsNot => "IsNot()",
+        CmpOp::In => "In()",
+        CmpOp::NotIn => "NotIn()",
+    }
+}
+
+fn constant(value: &Constant) -> String {
+    match value {
+        Constant::None => "None".to_string(),
+        Constant::Bool(true) => "True".to_string(),
+        Constant::Bool(false) => "False".to_string(),
+        Constant::Int(value) => value.to_string(),
+        Constant::Float(value) => repr_float(*value),
+        Constant::Complex(value) => {
+            let repr = repr_float(*value);
+            match repr.strip_suffix(".0") {
+                Some(whole) => format!("{}j", whole),
+                None => format!("{}j", repr),
+            }
+        }
+        Constant::Str(string) => repr_str(string),
+        Constant::Bytes(bytes) => repr_bytes(bytes),
+        Constant::Ellipsis => "Ellipsis".to_string(),
+    }
+}
@@ -0,0 +1,858 @@
+// Python lowering
+//
+//! Lowering of statically typed Python to the [IR](crate::ir).
+//!
+//! A module of `def` statements is lowered, whose parameters are annotated
+//! `int`, `float` or `bool` and whose returns are annotated with one of
+//! those or `None`.  An `int` is an `i64`, a `float` an `f64` and a `bool`
+//! an `i1`.  Each variable has one type, from its annotation or the first
+//! value assigned to it, and a stack slot loaded and stored where it is
+//! used.  Operators mix `bool`s, `int`s and `float`s as Python does, with
+//! `//` and `%` rounding towards negative infinity; unlike in Python, `int`
+//! arithmetic wraps at 64 bits.  A function that falls off its end without
+//! returning the value it is annotated with returns an undefined one.
+//!
+//! `range` loops and the built-in functions `int`, `float` and `bool` are
+//! understood; floor and `**` of `float`s call the C library's `floor` and
+//! `pow`, which are declared when used.
+
+use std::collections::HashMap;
+
+use super::{
+    BinaryOp, BoolOp, CmpOp, Constant, Expr, ExprKind, FunctionDef, Module,
+    Stmt, StmtKind, UnaryOp,
+};
+use crate::ir::{
+    self, BlockId, Builder, CastOp, FloatPredicate, IntPredicate, Signature,
+    Value,
+};
+use crate::{Diagnostic, Span};
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+/// Lower a Python module of typed functions to an IR module.
+pub fn lower(module: &Module) -> Result<ir::Module> {
+    let mut defs = HashMap::new();
+    let mut functions = Vec::new();
+    for stmt in &module.body {
+        match stmt.kind {
+            StmtKind::FunctionDef(ref def) => {
+                let sig = signature(def, stmt.span)?;
+                defs.insert(def.name, sig);
+                functions.push(def);
+            }
+            // Docstrings
+            StmtKind::Expr(Expr { kind: ExprKind::Constant(_), .. })
+            | StmtKind::Pass => {}
+            _ => {
+                return Err(Diagnostic::new(stmt.span, "only `def` statements \
+                    can be lowered to the IR yet"));
+            }
+        }
+    }
+    let mut ir_module = ir::Module::new();
+    let mut libm = Vec::new();
+    for def in functions {
+        let sig = defs[def.name].clone();
+        let mut function = ir::Function::new(def.name, sig.ir());
+        let mut lowering = Lowering {
+            defs: &defs,
+            builder: Builder::new(&mut function),
+            ret: sig.ret,
+            vars: HashMap::new(),
+            loops: Vec::new(),
+            libm: &mut libm,
+        };
+        for (i, arg) in def.args.args.iter().enumerate() {
+            let slot = lowering.builder.alloca(sig.params[i].ir());
+            lowering.builder.store(Value::Param(i), slot.clone());
+            lowering.vars.insert(arg.arg, (slot, sig.params[i]));
+        }
+        lowering.body(&def.body)?;
+        let value = sig.ret.map(|ty| Value::Undef(ty.ir()));
+        lowering.builder.ret(value);
+        function.remove_unreachable_blocks();
+        ir_module.functions.push(function);
+    }
+    for name in libm {
+        let ty = match name {
+            "pow" => vec![ir::Type::F64, ir::Type::F64],
+            _ => vec![ir::Type::F64],
+        };
+        ir_module.declare(name, Signature::new(ir::Type::F64, ty));
+    }
+    Ok(ir_module)
+}
+
+// A type of value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Ty {
+    Bool,
+    Int,
+    Float,
+}
+
+impl Ty {
+    fn ir(self) -> ir::Type {
+        match self {
+            Ty::Bool => ir::Type::I1,
+            Ty::Int => ir::Type::I64,
+            Ty::Float => ir::Type::F64,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Ty::Bool => "bool",
+            Ty::Int => "int",
+            Ty::Float => "float",
+        }
+    }
+}
+
+// The parameter types and return type (`None` for `None`) of a function.
+#[derive(Debug, Clone)]
+struct Sig {
+    params: Vec<Ty>,
+    ret: Option<Ty>,
+}
+
+impl Sig {
+    fn ir(&self) -> Signature {
+        let ret = self.ret.map_or(ir::Type::Void, Ty::ir);
+        Signature::new(ret, self.params.iter().map(|ty| ty.ir()).collect())
+    }
+}
+
+// The type an annotation names, `None` for `None`.
+fn annotation(e: &Expr) -> Result<Option<Ty>> {
+    match e.kind {
+        ExprKind::Name("int", _) => Ok(Some(Ty::Int)),
+        ExprKind::Name("float", _) => Ok(Some(Ty::Float)),
+        ExprKind::Name("bool", _) => Ok(Some(Ty::Bool)),
+        ExprKind::Constant(Constant::None) => Ok(None),
+        _ => Err(Diagnostic::new(e.span,
+            "only `int`, `float`, `bool` and `None` can be lowered to the IR \
+            yet")),
+    }
+}
+
+fn signature(def: &FunctionDef, span: Span) -> Result<Sig> {
+    let args = &def.args;
+    if !args.posonlyargs.is_empty() || args.vararg.is_some()
+        || !args.kwonlyargs.is_empty() || args.kwarg.is_some()
+        || !args.defaults.is_empty() || !def.decorator_list.is_empty()
+    {
+        return Err(Diagnostic::new(span, format!("`{}` can't be lowered to \
+            the IR yet", def.name)));
+    }
+    let mut params = Vec::new();
+    for arg in &args.args {
+        let ty = match arg.annotation {
+            Some(ref e) => annotation(e)?,
+            None => None,
+        };
+        match ty {
+            Some(ty) => params.push(ty),
+            None => {
+                return Err(Diagnostic::new(arg.span, format!(
+                    "`{}` needs a type to be lowered to the IR", arg.arg)));
+            }
+        }
+    }
+    let ret = match def.returns {
+        Some(ref e) => annotation(e)?,
+        None => None,
+    };
+    Ok(Sig { params, ret })
+}
+
+// A loop being lowered.
+struct Loop {
+    continue_: BlockId,
+    break_: BlockId,
+}
+
+// A function being lowered.
+struct Lowering<'l, 'f, 'a> {
+    defs: &'l HashMap<&'a str, Sig>,
+    builder: Builder<'f>,
+    ret: Option<Ty>,
+    // Stack slot and type of each variable assigned so far.
+    vars: HashMap<&'a str, (Value, Ty)>,
+    loops: Vec<Loop>,
+    // C library functions called.
+    libm: &'l mut Vec<&'static str>,
+}
+
+impl<'a> Lowering<'_, '_, 'a> {
+    fn body(&mut self, body: &[Stmt<'a>]) -> Result<()> {
+        for stmt in body {
+            self.stmt(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn stmt(&mut self, stmt: &Stmt<'a>) -> Result<()> {
+        match stmt.kind {
+            StmtKind::Pass => {}
+            StmtKind::Expr(ref e) => {
+                if let ExprKind::Call(..) = e.kind {
+                    self.call(e)?;
+                } else {
+                    self.expr(e)?;
+                }
+            }
+            StmtKind::Return(ref e) => {
+                let value = match (e, self.ret) {
+                    (Some(e), Some(ty)) => {
+                        let value = self.expr(e)?;
+                        Some(self.coerce(value, ty, e.span)?)
+                    }
+                    (None, None) => None,
+                    (Some(Expr { kind: ExprKind::Constant(Constant::None),
+                        .. }), None) => None,
+                    _ => {
+                        return Err(Diagnostic::new(stmt.span,
+                            "the value returned doesn't match the annotation"));
+                    }
+                };
+                self.builder.ret(value);
+            }
+            StmtKind::Assign(ref targets, ref value) => {
+                let value = self.expr(value)?;
+                for target in targets {
+                    self.assign(target, value.clone(), None)?;
+                }
+            }
+            StmtKind::AnnAssign(ref target, ref ann, ref value, _) => {
+                let ty = match annotation(ann)? {
+                    Some(ty) => ty,
+                    None => {
+                        return Err(Diagnostic::new(ann.span,
+                            "variables can't be `None` in the IR"));
+                    }
+                };
+                match value {
+                    Some(value) => {
+                        let value = self.expr(value)?;
+                        self.assign(target, value, Some(ty))?;
+                    }
+                    None => {
+                        self.variable(target, ty)?;
+                    }
+                }
+            }
+            StmtKind::AugAssign(ref target, op, ref value) => {
+                let old = self.expr(target)?;
+                let y = self.expr(value)?;
+                let new = self.binary(op, old, y, stmt.span)?;
+                self.assign(target, new, None)?;
+            }
+            StmtKind::If(ref cond, ref then, ref else_) => {
+                let then_block = self.builder.new_block();
+                let else_block = self.builder.new_block();
+                let end = self.builder.new_block();
+                let cond = self.truth(cond)?;
+                self.builder.cond_br(cond, then_block, else_block);
+                self.builder.switch_to(then_block);
+                self.body(then)?;
+                self.builder.br(end);
+                self.builder.switch_to(else_block);
+                self.body(else_)?;
+                self.builder.br(end);
+                self.builder.switch_to(end);
+            }
+            StmtKind::While(ref cond, ref body, ref else_) => {
+                let head = self.builder.new_block();
+                let body_block = self.builder.new_block();
+                let else_block = self.builder.new_block();
+                let end = self.builder.new_block();
+                self.builder.br(head);
+                self.builder.switch_to(head);
+                let cond = self.truth(cond)?;
+                self.builder.cond_br(cond, body_block, else_block);
+                self.builder.switch_to(body_block);
+                self.loop_body(body, head, end)?;
+                self.builder.switch_to(else_block);
+                self.body(else_)?;
+                self.builder.br(end);
+                self.builder.switch_to(end);
+            }
+            StmtKind::For(ref for_) => self.range_loop(for_, stmt.span)?,
+            StmtKind::Break => {
+                let target = self.innermost(stmt.span)?.break_;
+                self.builder.br(target);
+            }
+            StmtKind::Continue => {
+                let target = self.innermost(stmt.span)?.continue_;
+                self.builder.br(target);
+            }
+            _ => {
+                return Err(Diagnostic::new(stmt.span,
+                    "this statement can't be lowered to the IR yet"));
+            }
+        }
+        Ok(())
+    }
+
+    fn innermost(&self, span: Span) -> Result<&Loop> {
+        self.loops.last()
+            .ok_or_else(|| Diagnostic::new(span, "not in a loop"))
+    }
+
+    // Lower a loop body in the current block, which continues at
+    // `continue_` after it.
+    fn loop_body(&mut self, body: &[Stmt<'a>], continue_: BlockId,
+        break_: BlockId) -> Result<()>
+    {
+        self.loops.push(Loop { continue_, break_ });
+        let result = self.body(body);
+        self.loops.pop();
+        result?;
+        self.builder.br(continue_);
+        Ok(())
+    }
+
+    // Lower `for name in range(...)`.
+    fn range_loop(&mut self, for_: &super::For<'a>, span: Span) -> Result<()> {
+        let args = match for_.iter.kind {
+            ExprKind::Call(ref f, ref args, ref kwargs)
+                if matches!(f.kind, ExprKind::Name("range", _))
+                    && (1..=3).contains(&args.len()) && kwargs.is_empty() =>
+            {
+                args
+            }
+            _ => {
+                return Err(Diagnostic::new(for_.iter.span,
+                    "only `for` loops over a `range` can be lowered to the IR \
+                    yet"));
+            }
+        };
+        let mut values = Vec::new();
+        for arg in args {
+            let value = self.expr(arg)?;
+            values.push(self.coerce(value, Ty::Int, arg.span)?);
+        }
+        let (start, stop, step) = match values.len() {
+            1 => (Value::int(ir::Type::I64, 0), values[0].clone(),
+                Value::int(ir::Type::I64, 1)),
+            2 => (values[0].clone(), values[1].clone(),
+                Value::int(ir::Type::I64, 1)),
+            _ => (values[0].clone(), values[1].clone(), values[2].clone()),
+        };
+        // The counter is separate from the variable, which the body may
+        // assign.
+        let counter = self.builder.alloca(ir::Type::I64);
+        self.builder.store(start, counter.clone());
+        let head = self.builder.new_block();
+        let body = self.builder.new_block();
+        let next = self.builder.new_block();
+        let else_block = self.builder.new_block();
+        let end = self.builder.new_block();
+        self.builder.br(head);
+        self.builder.switch_to(head);
+        let i = self.builder.load(ir::Type::I64, counter.clone());
+        let cond = match step.as_int() {
+            Some(step) if step > 0 => {
+                self.builder.icmp(IntPredicate::Slt, i.clone(), stop)
+            }
+            Some(step) if step < 0 => {
+                self.builder.icmp(IntPredicate::Sgt, i.clone(), stop)
+            }
+            Some(_) => {
+                return Err(Diagnostic::new(span,
+                    "range() arg 3 must not be zero"));
+            }
+            None => {
+                let zero = Value::int(ir::Type::I64, 0);
+                let up = self.builder.icmp(IntPredicate::Sgt, step.clone(),
+                    zero);
+                let below = self.builder.icmp(IntPredicate::Slt, i.clone(),
+                    stop.clone());
+                let above = self.builder.icmp(IntPredicate::Sgt, i.clone(),
+                    stop);
+                self.builder.select(up, below, above)
+            }
+        };
+        self.builder.cond_br(cond, body, else_block);
+        self.builder.switch_to(body);
+        self.assign(&for_.target, (i, Ty::Int), None)?;
+        self.loop_body(&for_.body, next, end)?;
+        self.builder.switch_to(next);
+        let i = self.builder.load(ir::Type::I64, counter.clone());
+        let i = self.builder.binary(ir::BinaryOp::Add, i, step);
+        self.builder.store(i, counter);
+        self.builder.br(head);
+        self.builder.switch_to(else_block);
+        self.body(&for_.orelse)?;
+        self.builder.br(end);
+        self.builder.switch_to(end);
+        Ok(())
+    }
+
+    // The stack slot of a variable, made with type `ty` if it is new.
+    fn variable(&mut self, target: &Expr<'a>, ty: Ty) -> Result<Value> {
+        let name = match target.kind {
+            ExprKind::Name(name, _) => name,
+            _ => {
+                return Err(Diagnostic::new(target.span,
+                    "only variables can be assigned in the IR yet"));
+            }
+        };
+        match self.vars.get(name) {
+            Some(&(ref slot, old)) if old == ty => Ok(slot.clone()),
+            Some(&(_, old)) => Err(Diagnostic::new(target.span, format!(
+                "`{}` is a `{}`, and can't be a `{}` too in the IR",
+                name, old.name(), ty.name()))),
+            None => {
+                let slot = self.builder.alloca(ty.ir());
+                self.vars.insert(name, (slot.clone(), ty));
+                Ok(slot)
+            }
+        }
+    }
+
+    // Assign a value to a variable, of type `ty` if it is annotated.
+    fn assign(&mut self, target: &Expr<'a>, (value, from): (Value, Ty),
+        ty: Option<Ty>) -> Result<()>
+    {
+        let ty = match (ty, target.kind.clone()) {
+            (Some(ty), _) => ty,
+            (None, ExprKind::Name(name, _)) => match self.vars.get(name) {
+                Some(&(_, ty)) => ty,
+                None => from,
+            },
+            (None, _) => from,
+        };
+        let value = self.coerce((value, from), ty, target.span)?;
+        let slot = self.variable(target, ty)?;
+        self.builder.store(value, slot);
+        Ok(())
+    }
+
+    // Convert a value to a type it may be used as: a `bool` as an `int` or
+    // `float`, and an `int` as a `float`.
+    fn coerce(&mut self, (value, from): (Value, Ty), to: Ty, span: Span)
+        -> Result<Value>
+    {
+        if let (Value::Int(_, int), Ty::Float) = (&value, to) {
+            return Ok(Value::float(ir::Type::F64, *int as f64));
+        }
+        if let (Value::Int(_, int), Ty::Bool, Ty::Int) = (&value, from, to) {
+            return Ok(Value::int(ir::Type::I64, *int));
+        }
+        Ok(match (from, to) {
+            _ if from == to => value,
+            (Ty::Bool, Ty::Int) => {
+                self.builder.cast(CastOp::ZExt, value, ir::Type::I64)
+            }
+            (Ty::Bool, Ty::Float) => {
+                self.builder.cast(CastOp::UiToFp, value, ir::Type::F64)
+            }
+            (Ty::Int, Ty::Float) => {
+                self.builder.cast(CastOp::SiToFp, value, ir::Type::F64)
+            }
+            _ => {
+                return Err(Diagnostic::new(span, format!(
+                    "expected `{}`, found `{}`", to.name(), from.name())));
+            }
+        })
+    }
+
+    // The truth of a value as an `i1`.
+    fn truth_of(&mut self, (value, ty): (Value, Ty)) -> Value {
+        match ty {
+            Ty::Bool => value,
+            Ty::Int => {
+                let zero = Value::int(ir::Type::I64, 0);
+                self.builder.icmp(IntPredicate::Ne, value, zero)
+            }
+            Ty::Float => {
+                let zero = Value::float(ir::Type::F64, 0.0);
+                self.builder.fcmp(FloatPredicate::Une, value, zero)
+            }
+        }
+    }
+
+    fn truth(&mut self, e: &Expr<'a>) -> Result<Value> {
+        let value = self.expr(e)?;
+        Ok(self.truth_of(value))
+    }
+
+    // Lower a call, which may return `None`.
+    fn call(&mut self, e: &Expr<'a>) -> Result<Option<(Value, Ty)>> {
+        let (f, args) = match e.kind {
+            ExprKind::Call(ref f, ref args, ref kwargs) if kwargs.is_empty() => {
+                (f, args)
+            }
+            _ => {
+                return Err(Diagnostic::new(e.span,
+                    "this call can't be lowered to the IR yet"));
+            }
+        };
+        let name = match f.kind {
+            ExprKind::Name(name, _) => name,
+            _ => "",
+        };
+        if let Some(sig) = self.defs.get(name) {
+            if sig.params.len() != args.len() {
+                return Err(Diagnostic::new(e.span, format!(
+                    "`{}` takes {} arguments", name, sig.params.len())));
+            }
+            let sig = sig.clone();
+            let mut values = Vec::new();
+            for (arg, &ty) in args.iter().zip(&sig.params) {
+                let value = self.expr(arg)?;
+                values.push(self.coerce(value, ty, arg.span)?);
+            }
+            let value = self.builder.call(sig.ir(), Value::global(name),
+                values);
+            return Ok(sig.ret.map(|ty| (value, ty)));
+        }
+        let to = match name {
+            "int" => Ty::Int,
+            "float" => Ty::Float,
+            "bool" => Ty::Bool,
+            _ => {
+                return Err(Diagnostic::new(f.span,
+                    "only functions of the module and `int`, `float` and \
+                    `bool` can be called in the IR yet"));
+            }
+        };
+        if args.len() != 1 {
+            return Err(Diagnostic::new(e.span, format!(
+                "`{}` takes 1 argument in the IR", name)));
+        }
+        let (value, from) = self.expr(&args[0])?;
+        let value = match (from, to) {
+            (_, Ty::Bool) => self.truth_of((value, from)),
+            // `int` rounds towards zero.
+            (Ty::Float, Ty::Int) => {
+                self.builder.cast(CastOp::FpToSi, value, ir::Type::I64)
+            }
+            _ => self.coerce((value, from), to, e.span)?,
+        };
+        Ok(Some((value, to)))
+    }
+
+    fn expr(&mut self, e: &Expr<'a>) -> Result<(Value, Ty)> {
+        Ok(match e.kind {
+            ExprKind::Constant(Constant::Bool(value)) => {
+                (Value::bool(value), Ty::Bool)
+            }
+            ExprKind::Constant(Constant::Int(value)) => {
+                if value > i64::MAX as u128 {
+                    return Err(Diagnostic::new(e.span,
+                        "integers are 64 bits in the IR"));
+                }
+                (Value::int(ir::Type::I64, value as i128), Ty::Int)
+            }
+            ExprKind::Constant(Constant::Float(value)) => {
+                (Value::float(ir::Type::F64, value), Ty::Float)
+            }
+            ExprKind::Name(name, _) => match self.vars.get(name) {
+                Some(&(ref slot, ty)) => {
+                    let slot = slot.clone();
+                    (self.builder.load(ty.ir(), slot), ty)
+                }
+                None => {
+                    return Err(Diagnostic::new(e.span, format!(
+                        "`{}` isn't assigned before here", name)));
+                }
+            },
+            ExprKind::UnaryOp(op, ref operand) => {
+                let (value, ty) = self.expr(operand)?;
+                match (op, ty) {
+                    (UnaryOp::Not, _) => {
+                        let truth = self.truth_of((value, ty));
+                        let value = self.builder.binary(ir::BinaryOp::Xor,
+                            truth, Value::bool(true));
+                        (value, Ty::Bool)
+                    }
+                    (UnaryOp::USub, Ty::Float) => {
+                        (self.builder.fneg(value), Ty::Float)
+                    }
+                    (UnaryOp::UAdd, Ty::Float) => (value, Ty::Float),
+                    (UnaryOp::Invert, Ty::Float) => {
+                        return Err(Diagnostic::new(e.span,
+                            "bad operand type for unary ~: 'float'"));
+                    }
+                    (op, _) => {
+                        let value = self.coerce((value, ty), Ty::Int,
+                            e.span)?;
+                        let value = match op {
+                            UnaryOp::USub => {
+                                let zero = Value::int(ir::Type::I64, 0);
+                                self.builder.binary(ir::BinaryOp::Sub, zero,
+                                    value)
+                            }
+                            UnaryOp::Invert => {
+                                let ones = Value::int(ir::Type::I64, -1);
+                                self.builder.binary(ir::BinaryOp::Xor, value,
+                                    ones)
+                            }
+                            _ => value,
+                        };
+                        (value, Ty::Int)
+                    }
+                }
+            }
+            ExprKind::BinOp(ref a, op, ref b) => {
+                let x = self.expr(a)?;
+                let y = self.expr(b)?;
+                self.binary(op, x, y, e.span)?
+            }
+            ExprKind::BoolOp(op, ref values) => {
+                // Each operand but the last decides whether to go on.
+                let end = self.builder.new_block();
+                let mut incoming = Vec::new();
+                let mut result_ty = None;
+                for (i, value) in values.iter().enumerate() {
+                    let (value, ty) = self.expr(value)?;
+                    match result_ty {
+                        None => result_ty = Some(ty),
+                        Some(result_ty) if result_ty == ty => {}
+                        Some(result_ty) => {
+                            return Err(Diagnostic::new(e.span, format!(
+                                "`{}` and `{}` can't be mixed in `{}` in the \
+                                IR", result_ty.name(), ty.name(),
+                                match op {
+                                    BoolOp::And => "and",
+                                    BoolOp::Or => "or",
+                                })));
+                        }
+                    }
+                    if i + 1 == values.len() {
+                        incoming.push((self.builder.block(), value));
+                        self.builder.br(end);
+                        break;
+                    }
+                    let truth = self.truth_of((value.clone(), ty));
+                    incoming.push((self.builder.block(), value));
+                    let next = self.builder.new_block();
+                    match op {
+                        BoolOp::And => self.builder.cond_br(truth, next, end),
+                        BoolOp::Or => self.builder.cond_br(truth, end, next),
+                    }
+                    self.builder.switch_to(next);
+                }
+                self.builder.switch_to(end);
+                let ty = result_ty.unwrap_or(Ty::Bool);
+                (self.builder.phi(ty.ir(), incoming), ty)
+            }
+            ExprKind::Compare(ref first, ref ops, ref others) => {
+                let mut x = self.expr(first)?;
+                if ops.len() == 1 {
+                    let y = self.expr(&others[0])?;
+                    return Ok((self.compare(ops[0], x, y, e.span)?, Ty::Bool));
+                }
+                // `a < b < c` is `a < b and b < c`, with `b` evaluated once.
+                let end = self.builder.new_block();
+                let mut incoming = Vec::new();
+                for (i, (&op, other)) in ops.iter().zip(others).enumerate() {
+                    let y = self.expr(other)?;
+                    let cmp = self.compare(op, x, y.clone(), e.span)?;
+                    incoming.push((self.builder.block(), cmp.clone()));
+                    if i + 1 == ops.len() {
+                        self.builder.br(end);
+                        break;
+                    }
+                    let next = self.builder.new_block();
+                    self.builder.cond_br(cmp, next, end);
+                    self.builder.switch_to(next);
+                    x = y;
+                }
+                self.builder.switch_to(end);
+                (self.builder.phi(ir::Type::I1, incoming), Ty::Bool)
+            }
+            ExprKind::IfExp(ref cond, ref then, ref else_) => {
+                let then_block = self.builder.new_block();
+                let else_block = self.builder.new_block();
+                let end = self.builder.new_block();
+                let cond = self.truth(cond)?;
+                self.builder.cond_br(cond, then_block, else_block);
+                self.builder.switch_to(then_block);
+                let (x, x_ty) = self.expr(then)?;
+                let then_end = self.builder.block();
+                self.builder.switch_to(else_block);
+                let (y, y_ty) = self.expr(else_)?;
+                let ty = match (x_ty, y_ty) {
+                    _ if x_ty == y_ty => x_ty,
+                    (Ty::Float, _) | (_, Ty::Float) => Ty::Float,
+                    _ => Ty::Int,
+                };
+                let y = self.coerce((y, y_ty), ty, else_.span)?;
+                let else_end = self.builder.block();
+                self.builder.br(end);
+                self.builder.switch_to(then_end);
+                let x = self.coerce((x, x_ty), ty, then.span)?;
+                let then_end = self.builder.block();
+                self.builder.br(end);
+                self.builder.switch_to(end);
+                let incoming = vec![(then_end, x), (else_end, y)];
+                (self.builder.phi(ty.ir(), incoming), ty)
+            }
+            ExprKind::Call(..) => match self.call(e)? {
+                Some(value) => value,
+                None => {
+                    return Err(Diagnostic::new(e.span,
+                        "the function returns `None`"));
+                }
+            },
+            _ => {
+                return Err(Diagnostic::new(e.span,
+                    "this expression can't be lowered to the IR yet"));
+            }
+        })
+    }
+
+    // The type both operands of an arithmetic operator are converted to.
+    fn arithmetic(&mut self, x: (Value, Ty), y: (Value, Ty), span: Span)
+        -> Result<(Value, Value, Ty)>
+    {
+        let ty = match (x.1, y.1) {
+            (Ty::Float, _) | (_, Ty::Float) => Ty::Float,
+            _ => Ty::Int,
+        };
+        let x = self.coerce(x, ty, span)?;
+        let y = self.coerce(y, ty, span)?;
+        Ok((x, y, ty))
+    }
+
+    // Call a C library function taking and returning `f64`s.
+    fn libm(&mut self, name: &'static str, args: Vec<Value>) -> Value {
+        if !self.libm.contains(&name) {
+            self.libm.push(name);
+        }
+        let params = vec![ir::Type::F64; args.len()];
+        let sig = Signature::new(ir::Type::F64, params);
+        self.builder.call(sig, Value::global(name), args)
+    }
+
+    fn binary(&mut self, op: BinaryOp, x: (Value, Ty), y: (Value, Ty),
+        span: Span) -> Result<(Value, Ty)>
+    {
+        use ir::BinaryOp as Op;
+
+        if let (BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor,
+            Ty::Bool, Ty::Bool) = (op, x.1, y.1)
+        {
+            let op = match op {
+                BinaryOp::BitAnd => Op::And,
+                BinaryOp::BitOr => Op::Or,
+                _ => Op::Xor,
+            };
+            return Ok((self.builder.binary(op, x.0, y.0), Ty::Bool));
+        }
+        if op == BinaryOp::Div {
+            let x = self.coerce(x, Ty::Float, span)?;
+            let y = self.coerce(y, Ty::Float, span)?;
+            return Ok((self.builder.binary(Op::FDiv, x, y), Ty::Float));
+        }
+        let (x, y, ty) = self.arithmetic(x, y, span)?;
+        let value = match (op, ty) {
+            (BinaryOp::Add, Ty::Float) => self.builder.binary(Op::FAdd, x, y),
+            (BinaryOp::Sub, Ty::Float) => self.builder.binary(Op::FSub, x, y),
+            (BinaryOp::Mult, Ty::Float) => self.builder.binary(Op::FMul, x, y),
+            (BinaryOp::Pow, Ty::Float) => self.libm("pow", vec![x, y]),
+            (BinaryOp::FloorDiv, Ty::Float) => {
+                let quotient = self.builder.binary(Op::FDiv, x, y);
+                self.libm("floor", vec![quotient])
+            }
+            (BinaryOp::Mod, Ty::Float) => {
+                // `x - y * floor(x / y)`
+                let quotient = self.builder.binary(Op::FDiv, x.clone(),
+                    y.clone());
+                let floor = self.libm("floor", vec![quotient]);
+                let product = self.builder.binary(Op::FMul, y, floor);
+                self.builder.binary(Op::FSub, x, product)
+            }
+            (BinaryOp::Add, _) => self.builder.binary(Op::Add, x, y),
+            (BinaryOp::Sub, _) => self.builder.binary(Op::Sub, x, y),
+            (BinaryOp::Mult, _) => self.builder.binary(Op::Mul, x, y),
+            (BinaryOp::LShift, _) => self.builder.binary(Op::Shl, x, y),
+            (BinaryOp::RShift, _) => self.builder.binary(Op::AShr, x, y),
+            (BinaryOp::BitAnd, _) => self.builder.binary(Op::And, x, y),
+            (BinaryOp::BitOr, _) => self.builder.binary(Op::Or, x, y),
+            (BinaryOp::BitXor, _) => self.builder.binary(Op::Xor, x, y),
+            (BinaryOp::FloorDiv, _) | (BinaryOp::Mod, _) => {
+                // Truncating division, corrected when the remainder is
+                // nonzero and its sign differs from the divisor's.
+                let zero = Value::int(ir::Type::I64, 0);
+                let rem = self.builder.binary(Op::SRem, x.clone(), y.clone());
+                let nonzero = self.builder.icmp(IntPredicate::Ne, rem.clone(),
+                    zero.clone());
+                let signs = self.builder.binary(Op::Xor, rem.clone(),
+                    y.clone());
+                let differ = self.builder.icmp(IntPredicate::Slt, signs, zero);
+                let adjust = self.builder.binary(Op::And, nonzero, differ);
+                match op {
+                    BinaryOp::FloorDiv => {
+                        let quotient = self.builder.binary(Op::SDiv, x, y);
+                        let adjust = self.builder.cast(CastOp::ZExt, adjust,
+                            ir::Type::I64);
+                        self.builder.binary(Op::Sub, quotient, adjust)
+                    }
+                    _ => {
+                        let zero = Value::int(ir::Type::I64, 0);
+                        let add = self.builder.select(adjust, y, zero);
+                        self.builder.binary(Op::Add, rem, add)
+                    }
+                }
+            }
+            _ => {
+                return Err(Diagnostic::new(span,
+                    "this operator can't be lowered to the IR yet"));
+            }
+        };
+        Ok((value, ty))
+    }
+
+    fn compare(&mut self, op: CmpOp, x: (Value, Ty), y: (Value, Ty),
+        span: Span) -> Result<Value>
+    {
+        if (x.1 == Ty::Bool) && (y.1 == Ty::Bool)
+            && matches!(op, CmpOp::Eq | CmpOp::NotEq)
+        {
+            let pred = match op {
+                CmpOp::Eq => IntPredicate::Eq,
+                _ => IntPredicate::Ne,
+            };
+            return Ok(self.builder.icmp(pred, x.0, y.0));
+        }
+        let (x, y, ty) = self.arithmetic(x, y, span)?;
+        Ok(match ty {
+            Ty::Float => {
+                let pred = match op {
+                    CmpOp::Eq => FloatPredicate::Oeq,
+                    CmpOp::NotEq => FloatPredicate::Une,
+                    CmpOp::Lt => FloatPredicate::Olt,
+                    CmpOp::LtE => FloatPredicate::Ole,
+                    CmpOp::Gt => FloatPredicate::Ogt,
+                    CmpOp::GtE => FloatPredicate::Oge,
+                    _ => return unsupported_cmp(span),
+                };
+                self.builder.fcmp(pred, x, y)
+            }
+            _ => {
+                let pred = match op {
+                    CmpOp::Eq => IntPredicate::Eq,
+                    CmpOp::NotEq => IntPredicate::Ne,
+                    CmpOp::Lt => IntPredicate::Slt,
+                    CmpOp::LtE => IntPredicate::Sle,
+                    CmpOp::Gt => IntPredicate::Sgt,
+                    CmpOp::GtE => IntPredicate::Sge,
+                    _ => return unsupported_cmp(span),
+                };
+                self.builder.icmp(pred, x, y)
+            }
+        })
+    }
+}
+
+fn unsupported_cmp<T>(span: Span) -> Result<T> {
+    Err(Diagnostic::new(span,
+        "only `==`, `!=`, `<`, `<=`, `>` and `>=` can be lowered to the IR \
+        yet"))
+}
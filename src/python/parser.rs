@@ -0,0 +1,1287 @@
+// Python parser
+//
+//! Recursive descent parser for the PEG grammar of Python 3.12.  Where
+//! alternatives share a prefix (the soft keyword `match` and parenthesized
+//! `with` items) the parser saves its position and backtracks.  Comments are
+//! dropped before parsing.
+
+use super::{
+    Alias, Arg, Arguments, BinaryOp, Bracket, ClassDef, Constant,
+    ExceptHandler, Expr, ExprContext, ExprKind, For, FunctionDef, Keyword,
+    MatchCase, Module, Operator, Pattern, PatternKind, Result, Stmt,
+    StmtKind, Token, TokenIterator, Try, TypeParam, UnaryOp, WithItem,
+};
+use crate::{Diagnostic, Span};
+
+mod expr;
+
+pub(super) struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<(Token<'a>, Span)>,
+    pos: usize,
+    // Span of the end of the input.
+    end: Span,
+    // Error from the tokenizer, reported when the parser reaches the end.
+    error: Option<Diagnostic>,
+}
+
+impl<'a> Parser<'a> {
+    /// Tokenize source text for parsing, up to the first error.
+    pub(super) fn new(text: &'a str) -> Self {
+        let mut tokens = Vec::new();
+        let mut error = None;
+        let mut iter = TokenIterator::new(text);
+        while let Some(token) = iter.next() {
+            match token {
+                Ok(Token::Comment(_)) => {}
+                Ok(token) => tokens.push((token, iter.span())),
+                Err(e) => {
+                    error = Some(e);
+                    break;
+                }
+            }
+        }
+        let end = match error {
+            Some(ref e) => Span::new(e.span.start, e.span.start),
+            None => Span::new(text.len(), text.len()),
+        };
+
+        Parser { text, tokens, pos: 0, end, error }
+    }
+
+    pub(super) fn is_eof(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    pub(super) fn peek(&self) -> Option<&Token<'a>> {
+        self.peek_at(0)
+    }
+
+    pub(super) fn peek_at(&self, n: usize) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos + n).map(|(token, _)| token)
+    }
+
+    /// Span of the next token.
+    pub(super) fn span(&self) -> Span {
+        self.tokens.get(self.pos).map(|(_, span)| *span).unwrap_or(self.end)
+    }
+
+    /// Span from `start` to the end of the previous token, not counting the
+    /// `NEWLINE`, `INDENT` and `DEDENT` tokens after a block.
+    pub(super) fn since(&self, start: Span) -> Span {
+        let end = self.tokens[..self.pos]
+            .iter()
+            .rev()
+            .find(|(token, _)| {
+                !matches!(token, Token::Newline | Token::Indent | Token::Dedent)
+            })
+            .map_or(start.start, |(_, span)| span.end);
+        Span::new(start.start, end.max(start.start))
+    }
+
+    pub(super) fn bump(&mut self) -> Token<'a> {
+        let token = self.tokens[self.pos].0.clone();
+        self.pos += 1;
+        token
+    }
+
+    pub(super) fn is(&self, token: &Token<'_>) -> bool {
+        self.peek() == Some(token)
+    }
+
+    pub(super) fn eat(&mut self, token: &Token<'_>) -> bool {
+        let found = self.is(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    pub(super) fn is_op(&self, op: Operator) -> bool {
+        self.is(&Token::Operator(op))
+    }
+
+    pub(super) fn is_keyword(&self, keyword: Keyword) -> bool {
+        self.is(&Token::Keyword(keyword))
+    }
+
+    pub(super) fn is_bracket(&self, bracket: Bracket) -> bool {
+        self.is(&Token::Bracket(bracket))
+    }
+
+    /// Whether the next token is the soft keyword `name`.
+    pub(super) fn is_word(&self, name: &str) -> bool {
+        self.is(&Token::Name(name))
+    }
+
+    pub(super) fn eat_op(&mut self, op: Operator) -> bool {
+        self.eat(&Token::Operator(op))
+    }
+
+    pub(super) fn eat_keyword(&mut self, keyword: Keyword) -> bool {
+        self.eat(&Token::Keyword(keyword))
+    }
+
+    pub(super) fn eat_bracket(&mut self, bracket: Bracket) -> bool {
+        self.eat(&Token::Bracket(bracket))
+    }
+
+    pub(super) fn expect_op(&mut self, op: Operator) -> Result<()> {
+        if self.eat_op(op) {
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{}`", op.as_str()))
+        }
+    }
+
+    pub(super) fn expect_keyword(&mut self, keyword: Keyword) -> Result<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{}`", keyword.as_str()))
+        }
+    }
+
+    pub(super) fn expect_bracket(&mut self, bracket: Bracket) -> Result<()> {
+        if self.eat_bracket(bracket) {
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{}`", bracket.as_str()))
+        }
+    }
+
+    /// Report the next token as unexpected (or the tokenizer error at the
+    /// end).
+    pub(super) fn unexpected<T>(&self, expected: &str) -> Result<T> {
+        match self.peek() {
+            Some(token) => {
+                let found = match token {
+                    Token::Newline
+                    | Token::Indent
+                    | Token::Dedent
+                    | Token::FStringEnd => token.to_string(),
+                    _ => format!("`{}`", token),
+                };
+                Err(Diagnostic::new(
+                    self.span(),
+                    format!("expected {}, found {}", expected, found),
+                ))
+            }
+            None => Err(self.error.clone().unwrap_or_else(|| {
+                Diagnostic::new(
+                    self.end,
+                    format!("expected {}, found end of file", expected),
+                )
+            })),
+        }
+    }
+
+    pub(super) fn name(&mut self) -> Result<&'a str> {
+        match self.peek() {
+            Some(&Token::Name(name)) => {
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => self.unexpected("name"),
+        }
+    }
+
+    /// Parse the whole input as a module.
+    pub(super) fn module(&mut self) -> Result<Module<'a>> {
+        let mut body = Vec::new();
+        while !self.is_eof() {
+            body.extend(self.statement()?);
+        }
+        match self.error {
+            Some(ref e) => Err(e.clone()),
+            None => Ok(Module { body }),
+        }
+    }
+
+    // A compound statement, or a line of simple statements.
+    fn statement(&mut self) -> Result<Vec<Stmt<'a>>> {
+        let start = self.span();
+        let kind = match self.peek() {
+            Some(Token::Indent) => {
+                return Err(Diagnostic::new(start, "unexpected indent"));
+            }
+            Some(Token::Keyword(Keyword::If)) => {
+                self.if_stmt("'if' statement")?
+            }
+            Some(Token::Keyword(Keyword::While)) => self.while_stmt()?,
+            Some(Token::Keyword(Keyword::For)) => {
+                StmtKind::For(self.for_stmt(start)?)
+            }
+            Some(Token::Keyword(Keyword::Try)) => self.try_stmt()?,
+            Some(Token::Keyword(Keyword::With)) => {
+                let (items, body) = self.with_stmt(start)?;
+                StmtKind::With(items, body)
+            }
+            Some(Token::Keyword(Keyword::Def)) => {
+                StmtKind::FunctionDef(self.function_def(Vec::new(), start)?)
+            }
+            Some(Token::Keyword(Keyword::Class)) => {
+                StmtKind::ClassDef(self.class_def(Vec::new(), start)?)
+            }
+            Some(Token::Keyword(Keyword::Async)) => {
+                self.async_stmt(Vec::new())?
+            }
+            Some(Token::Operator(Operator::At)) => self.decorated()?,
+            Some(Token::Name("match")) => match self.match_stmt()? {
+                Some(kind) => kind,
+                None => return self.simple_stmts(),
+            },
+            _ => return self.simple_stmts(),
+        };
+        let start = match kind {
+            // Decorated definitions start at `def` or `class`.
+            StmtKind::FunctionDef(_)
+            | StmtKind::AsyncFunctionDef(_)
+            | StmtKind::ClassDef(_) => self.definition_start(start),
+            _ => start,
+        };
+
+        Ok(vec![Stmt { kind, span: self.since(start) }])
+    }
+
+    // Span of the `def`, `async` or `class` after the decorators starting at
+    // `start`.
+    fn definition_start(&self, start: Span) -> Span {
+        self.tokens[..self.pos]
+            .iter()
+            .find(|(token, span)| {
+                span.start >= start.start
+                    && matches!(token, Token::Keyword(Keyword::Def
+                        | Keyword::Async | Keyword::Class))
+            })
+            .map_or(start, |(_, span)| *span)
+    }
+
+    // The body of a compound statement, from its `:`.  `what` names the
+    // statement starting at `start` for errors.
+    fn block(&mut self, what: &str, start: Span) -> Result<Vec<Stmt<'a>>> {
+        self.expect_op(Operator::Colon)?;
+        if !self.eat(&Token::Newline) {
+            return self.simple_stmts();
+        }
+        if !self.eat(&Token::Indent) {
+            if self.is_eof() {
+                self.unexpected::<()>("indented block")?;
+            }
+            let (line, _) = start.line_col(self.text);
+            return Err(Diagnostic::new(self.span(), format!(
+                "expected an indented block after {} on line {}", what, line
+            )));
+        }
+        let mut body = Vec::new();
+        while !self.eat(&Token::Dedent) {
+            if self.is_eof() {
+                return self.unexpected("statement");
+            }
+            body.extend(self.statement()?);
+        }
+        Ok(body)
+    }
+
+    // Simple statements separated by `;`, up to the end of the line.
+    fn simple_stmts(&mut self) -> Result<Vec<Stmt<'a>>> {
+        let mut stmts = vec![self.simple_stmt()?];
+        while self.eat_op(Operator::Semi) {
+            if self.is(&Token::Newline) {
+                break;
+            }
+            stmts.push(self.simple_stmt()?);
+        }
+        if !self.eat(&Token::Newline) {
+            return self.unexpected("newline");
+        }
+        Ok(stmts)
+    }
+
+    // Whether the next token ends a simple statement.
+    fn at_stmt_end(&self) -> bool {
+        matches!(self.peek(),
+            None | Some(Token::Newline | Token::Operator(Operator::Semi)))
+    }
+
+    fn simple_stmt(&mut self) -> Result<Stmt<'a>> {
+        let start = self.span();
+        let keyword = match self.peek() {
+            Some(&Token::Keyword(keyword)) => Some(keyword),
+            _ => None,
+        };
+        let kind = match keyword {
+            Some(Keyword::Pass) => {
+                self.bump();
+                StmtKind::Pass
+            }
+            Some(Keyword::Break) => {
+                self.bump();
+                StmtKind::Break
+            }
+            Some(Keyword::Continue) => {
+                self.bump();
+                StmtKind::Continue
+            }
+            Some(Keyword::Return) => {
+                self.bump();
+                let value = match self.at_stmt_end() {
+                    true => None,
+                    false => Some(self.star_expressions()?),
+                };
+                StmtKind::Return(value)
+            }
+            Some(Keyword::Raise) => {
+                self.bump();
+                if self.at_stmt_end() {
+                    StmtKind::Raise(None, None)
+                } else {
+                    let exc = self.expression()?;
+                    let cause = match self.eat_keyword(Keyword::From) {
+                        true => Some(self.expression()?),
+                        false => None,
+                    };
+                    StmtKind::Raise(Some(exc), cause)
+                }
+            }
+            Some(Keyword::Global) => {
+                self.bump();
+                StmtKind::Global(self.names()?)
+            }
+            Some(Keyword::Nonlocal) => {
+                self.bump();
+                StmtKind::Nonlocal(self.names()?)
+            }
+            Some(Keyword::Del) => {
+                self.bump();
+                StmtKind::Delete(self.del_targets()?)
+            }
+            Some(Keyword::Assert) => {
+                self.bump();
+                let test = self.expression()?;
+                let msg = match self.eat_op(Operator::Comma) {
+                    true => Some(self.expression()?),
+                    false => None,
+                };
+                StmtKind::Assert(test, msg)
+            }
+            Some(Keyword::Import) => self.import()?,
+            Some(Keyword::From) => self.import_from()?,
+            _ if self.is_word("type")
+                && matches!(self.peek_at(1), Some(Token::Name(_)))
+                && matches!(self.peek_at(2),
+                    Some(Token::Operator(Operator::Eq)
+                        | Token::Bracket(Bracket::SquareL))) =>
+            {
+                self.type_alias()?
+            }
+            _ => self.assignment()?,
+        };
+
+        Ok(Stmt { kind, span: self.since(start) })
+    }
+
+    // Names separated by commas, for `global` and `nonlocal`.
+    fn names(&mut self) -> Result<Vec<&'a str>> {
+        let mut names = vec![self.name()?];
+        while self.eat_op(Operator::Comma) {
+            names.push(self.name()?);
+        }
+        Ok(names)
+    }
+
+    // `a.b.c` in an import.
+    fn dotted_name(&mut self) -> Result<String> {
+        let mut name = self.name()?.to_string();
+        while self.eat_op(Operator::Dot) {
+            name.push('.');
+            name.push_str(self.name()?);
+        }
+        Ok(name)
+    }
+
+    fn import(&mut self) -> Result<StmtKind<'a>> {
+        self.bump();
+        let mut names = Vec::new();
+        loop {
+            let start = self.span();
+            let name = self.dotted_name()?;
+            let asname = match self.eat_keyword(Keyword::As) {
+                true => Some(self.name()?),
+                false => None,
+            };
+            names.push(Alias { name, asname, span: self.since(start) });
+            if !self.eat_op(Operator::Comma) {
+                break;
+            }
+        }
+        Ok(StmtKind::Import(names))
+    }
+
+    fn import_from(&mut self) -> Result<StmtKind<'a>> {
+        self.bump();
+        let mut level = 0;
+        loop {
+            if self.eat_op(Operator::Dot) {
+                level += 1;
+            } else if self.eat_op(Operator::Ellipsis) {
+                level += 3;
+            } else {
+                break;
+            }
+        }
+        let module = match level > 0 && self.is_keyword(Keyword::Import) {
+            true => None,
+            false => Some(self.dotted_name()?),
+        };
+        self.expect_keyword(Keyword::Import)?;
+
+        let start = self.span();
+        if self.eat_op(Operator::Star) {
+            let alias = Alias {
+                name: "*".to_string(),
+                asname: None,
+                span: start,
+            };
+            return Ok(StmtKind::ImportFrom(module, vec![alias], level));
+        }
+        let parens = self.eat_bracket(Bracket::ParensL);
+        let mut names = Vec::new();
+        loop {
+            let start = self.span();
+            let name = self.name()?.to_string();
+            let asname = match self.eat_keyword(Keyword::As) {
+                true => Some(self.name()?),
+                false => None,
+            };
+            names.push(Alias { name, asname, span: self.since(start) });
+            if !self.eat_op(Operator::Comma) {
+                break;
+            }
+            if parens && self.is_bracket(Bracket::ParensR) {
+                break;
+            }
+            if !parens && self.at_stmt_end() {
+                return Err(Diagnostic::new(self.since(start),
+                    "trailing comma not allowed without surrounding \
+                        parentheses"));
+            }
+        }
+        if parens {
+            self.expect_bracket(Bracket::ParensR)?;
+        }
+        Ok(StmtKind::ImportFrom(module, names, level))
+    }
+
+    // `type Name[params] = value`
+    fn type_alias(&mut self) -> Result<StmtKind<'a>> {
+        self.bump();
+        let start = self.span();
+        let name = self.name()?;
+        let name = Expr {
+            kind: ExprKind::Name(name, ExprContext::Store),
+            span: self.since(start),
+        };
+        let type_params = self.type_params()?;
+        self.expect_op(Operator::Eq)?;
+        let value = self.expression()?;
+        Ok(StmtKind::TypeAlias(name, type_params, value))
+    }
+
+    // `[T, *Ts, **P]` after the name of a generic definition.
+    fn type_params(&mut self) -> Result<Vec<TypeParam<'a>>> {
+        let mut params = Vec::new();
+        if !self.eat_bracket(Bracket::SquareL) {
+            return Ok(params);
+        }
+        loop {
+            let param = if self.eat_op(Operator::Star) {
+                TypeParam::TypeVarTuple(self.name()?)
+            } else if self.eat_op(Operator::DoubleStar) {
+                TypeParam::ParamSpec(self.name()?)
+            } else {
+                let name = self.name()?;
+                let bound = match self.eat_op(Operator::Colon) {
+                    true => Some(self.expression()?),
+                    false => None,
+                };
+                TypeParam::TypeVar(name, bound)
+            };
+            params.push(param);
+            if !self.eat_op(Operator::Comma)
+                || self.is_bracket(Bracket::SquareR)
+            {
+                break;
+            }
+        }
+        self.expect_bracket(Bracket::SquareR)?;
+        Ok(params)
+    }
+
+    // An expression statement, or an assignment to targets parsed as
+    // expressions.
+    fn assignment(&mut self) -> Result<StmtKind<'a>> {
+        let parenthesized = self.is_bracket(Bracket::ParensL);
+        let first = self.assigned_value()?;
+
+        if self.eat_op(Operator::Colon) {
+            let mut target = first;
+            match target.kind {
+                ExprKind::Name(..)
+                | ExprKind::Attribute(..)
+                | ExprKind::Subscript(..) => {}
+                ExprKind::Tuple(..) => {
+                    return Err(Diagnostic::new(target.span, "only single \
+                        target (not tuple) can be annotated"));
+                }
+                ExprKind::List(..) => {
+                    return Err(Diagnostic::new(target.span, "only single \
+                        target (not list) can be annotated"));
+                }
+                _ => {
+                    return Err(Diagnostic::new(target.span,
+                        "illegal target for annotation"));
+                }
+            }
+            expr::set_context(&mut target, ExprContext::Store)?;
+            let simple = !parenthesized
+                && matches!(target.kind, ExprKind::Name(..));
+            let annotation = self.expression()?;
+            let value = match self.eat_op(Operator::Eq) {
+                true => Some(self.assigned_value()?),
+                false => None,
+            };
+            return Ok(StmtKind::AnnAssign(target, annotation, value, simple));
+        }
+
+        if let Some(&Token::Operator(op)) = self.peek() {
+            if let Some(op) = expr::aug_op(op) {
+                self.bump();
+                let mut target = first;
+                if !matches!(target.kind, ExprKind::Name(..)
+                    | ExprKind::Attribute(..) | ExprKind::Subscript(..))
+                {
+                    return Err(Diagnostic::new(target.span, format!(
+                        "'{}' is an illegal expression for augmented \
+                            assignment",
+                        expr::describe(&target),
+                    )));
+                }
+                expr::set_context(&mut target, ExprContext::Store)?;
+                let value = self.assigned_value()?;
+                return Ok(StmtKind::AugAssign(target, op, value));
+            }
+        }
+
+        if !self.is_op(Operator::Eq) {
+            return Ok(StmtKind::Expr(first));
+        }
+        let mut targets = vec![first];
+        let value = loop {
+            self.bump();
+            let value = self.assigned_value()?;
+            if !self.is_op(Operator::Eq) {
+                break value;
+            }
+            targets.push(value);
+        };
+        for target in &mut targets {
+            expr::check_starred(target)?;
+            expr::set_context(target, ExprContext::Store)?;
+        }
+        Ok(StmtKind::Assign(targets, value))
+    }
+
+    // The right side of an assignment.
+    fn assigned_value(&mut self) -> Result<Expr<'a>> {
+        match self.is_keyword(Keyword::Yield) {
+            true => self.yield_expr(),
+            false => self.star_expressions(),
+        }
+    }
+
+    // `if` or `elif`, and the rest of the chain.
+    fn if_stmt(&mut self, what: &str) -> Result<StmtKind<'a>> {
+        let start = self.span();
+        self.bump();
+        let test = self.named_expression()?;
+        let body = self.block(what, start)?;
+        let orelse = if self.is_keyword(Keyword::Elif) {
+            let start = self.span();
+            let kind = self.if_stmt("'elif' statement")?;
+            vec![Stmt { kind, span: self.since(start) }]
+        } else {
+            self.else_block()?
+        };
+        Ok(StmtKind::If(test, body, orelse))
+    }
+
+    fn else_block(&mut self) -> Result<Vec<Stmt<'a>>> {
+        let start = self.span();
+        match self.eat_keyword(Keyword::Else) {
+            true => self.block("'else' statement", start),
+            false => Ok(Vec::new()),
+        }
+    }
+
+    fn while_stmt(&mut self) -> Result<StmtKind<'a>> {
+        let start = self.span();
+        self.bump();
+        let test = self.named_expression()?;
+        let body = self.block("'while' statement", start)?;
+        let orelse = self.else_block()?;
+        Ok(StmtKind::While(test, body, orelse))
+    }
+
+    fn for_stmt(&mut self, start: Span) -> Result<For<'a>> {
+        self.expect_keyword(Keyword::For)?;
+        let target = self.star_targets()?;
+        self.expect_keyword(Keyword::In)?;
+        let iter = self.star_expressions()?;
+        let body = self.block("'for' statement", start)?;
+        let orelse = self.else_block()?;
+        Ok(For { target, iter, body, orelse })
+    }
+
+    fn with_stmt(&mut self, start: Span)
+        -> Result<(Vec<WithItem<'a>>, Vec<Stmt<'a>>)>
+    {
+        self.expect_keyword(Keyword::With)?;
+        // `with (a as b, c):` or an expression starting with `(`.
+        let save = self.pos;
+        let parenthesized = match self.is_bracket(Bracket::ParensL) {
+            true => self.with_items(true).ok()
+                .filter(|_| self.is_op(Operator::Colon)),
+            false => None,
+        };
+        let items = match parenthesized {
+            Some(items) => items,
+            None => {
+                self.pos = save;
+                self.with_items(false)?
+            }
+        };
+        let body = self.block("'with' statement", start)?;
+        Ok((items, body))
+    }
+
+    fn with_items(&mut self, parens: bool) -> Result<Vec<WithItem<'a>>> {
+        if parens {
+            self.bump();
+        }
+        let mut items = Vec::new();
+        loop {
+            let context_expr = self.expression()?;
+            let optional_vars = match self.eat_keyword(Keyword::As) {
+                true => Some(self.star_target()?),
+                false => None,
+            };
+            items.push(WithItem { context_expr, optional_vars });
+            if !self.eat_op(Operator::Comma) {
+                break;
+            }
+            if parens && self.is_bracket(Bracket::ParensR) {
+                break;
+            }
+        }
+        if parens {
+            self.expect_bracket(Bracket::ParensR)?;
+        }
+        Ok(items)
+    }
+
+    fn try_stmt(&mut self) -> Result<StmtKind<'a>> {
+        let start = self.span();
+        self.bump();
+        let body = self.block("'try' statement", start)?;
+        let mut handlers: Vec<ExceptHandler<'a>> = Vec::new();
+        let mut star = None;
+        while self.is_keyword(Keyword::Except) {
+            let start = self.span();
+            self.bump();
+            let is_star = self.eat_op(Operator::Star);
+            if star.is_some_and(|star| star != is_star) {
+                return Err(Diagnostic::new(self.since(start),
+                    "cannot have both 'except' and 'except*' on the same \
+                        'try'"));
+            }
+            star = Some(is_star);
+            if let Some(last) = handlers.last().filter(|h| h.ty.is_none()) {
+                return Err(Diagnostic::new(last.span,
+                    "default 'except:' must be last"));
+            }
+            let ty = if self.is_op(Operator::Colon) {
+                if is_star {
+                    return self.unexpected("one or more exception types");
+                }
+                None
+            } else {
+                let ty = self.expression()?;
+                if self.is_op(Operator::Comma) {
+                    return Err(Diagnostic::new(self.since(start),
+                        "multiple exception types must be parenthesized"));
+                }
+                Some(ty)
+            };
+            let name = match self.eat_keyword(Keyword::As) {
+                true => Some(self.name()?),
+                false => None,
+            };
+            let what = match is_star {
+                true => "'except*' statement",
+                false => "'except' statement",
+            };
+            let body = self.block(what, start)?;
+            let span = self.since(start);
+            handlers.push(ExceptHandler { ty, name, body, span });
+        }
+        let orelse = match handlers.is_empty() {
+            true => Vec::new(),
+            false => self.else_block()?,
+        };
+        let finalbody = if self.is_keyword(Keyword::Finally) {
+            let start = self.span();
+            self.bump();
+            self.block("'finally' statement", start)?
+        } else if handlers.is_empty() {
+            return self.unexpected("`except` or `finally` block");
+        } else {
+            Vec::new()
+        };
+        let stmt = Try { body, handlers, orelse, finalbody };
+        Ok(match star {
+            Some(true) => StmtKind::TryStar(stmt),
+            _ => StmtKind::Try(stmt),
+        })
+    }
+
+    // `@decorator` lines before a function or class definition.
+    fn decorated(&mut self) -> Result<StmtKind<'a>> {
+        let mut decorators = Vec::new();
+        while self.eat_op(Operator::At) {
+            decorators.push(self.named_expression()?);
+            if !self.eat(&Token::Newline) {
+                return self.unexpected("newline");
+            }
+        }
+        let start = self.span();
+        match self.peek() {
+            Some(Token::Keyword(Keyword::Def)) => {
+                Ok(StmtKind::FunctionDef(self.function_def(decorators, start)?))
+            }
+            Some(Token::Keyword(Keyword::Class)) => {
+                Ok(StmtKind::ClassDef(self.class_def(decorators, start)?))
+            }
+            Some(Token::Keyword(Keyword::Async)) => self.async_stmt(decorators),
+            _ => self.unexpected("function or class definition"),
+        }
+    }
+
+    // `async def`, `async for` or `async with`.
+    fn async_stmt(&mut self, decorators: Vec<Expr<'a>>)
+        -> Result<StmtKind<'a>>
+    {
+        let start = self.span();
+        self.bump();
+        match self.peek() {
+            Some(Token::Keyword(Keyword::Def)) => {
+                let def = self.function_def(decorators, start)?;
+                Ok(StmtKind::AsyncFunctionDef(def))
+            }
+            Some(Token::Keyword(Keyword::For)) if decorators.is_empty() => {
+                Ok(StmtKind::AsyncFor(self.for_stmt(start)?))
+            }
+            Some(Token::Keyword(Keyword::With)) if decorators.is_empty() => {
+                let (items, body) = self.with_stmt(start)?;
+                Ok(StmtKind::AsyncWith(items, body))
+            }
+            _ if decorators.is_empty() => {
+                self.unexpected("`def`, `for` or `with`")
+            }
+            _ => self.unexpected("`def`"),
+        }
+    }
+
+    fn function_def(&mut self, decorator_list: Vec<Expr<'a>>, start: Span)
+        -> Result<FunctionDef<'a>>
+    {
+        self.expect_keyword(Keyword::Def)?;
+        let name = self.name()?;
+        let type_params = self.type_params()?;
+        self.expect_bracket(Bracket::ParensL)?;
+        let args = self.parameters(true)?;
+        self.expect_bracket(Bracket::ParensR)?;
+        let returns = match self.eat_op(Operator::RArrow) {
+            true => Some(self.expression()?),
+            false => None,
+        };
+        let body = self.block("function definition", start)?;
+        Ok(FunctionDef {
+            name,
+            type_params,
+            args,
+            body,
+            decorator_list,
+            returns,
+        })
+    }
+
+    /// Parameters of a function (`annotated`) up to its `)`, or of a lambda
+    /// up to its `:`.
+    pub(super) fn parameters(&mut self, annotated: bool)
+        -> Result<Arguments<'a>>
+    {
+        let close = match annotated {
+            true => Token::Bracket(Bracket::ParensR),
+            false => Token::Operator(Operator::Colon),
+        };
+        let mut args = Arguments::default();
+        // Whether a `*` was seen, so later parameters are keyword-only.
+        let mut star = None;
+        let mut slash = false;
+        while !self.is(&close) {
+            let start = self.span();
+            if self.eat_op(Operator::Slash) {
+                if slash {
+                    return Err(Diagnostic::new(start,
+                        "/ may appear only once"));
+                }
+                if star.is_some() {
+                    return Err(Diagnostic::new(start,
+                        "/ must be ahead of *"));
+                }
+                if args.args.is_empty() {
+                    return Err(Diagnostic::new(start,
+                        "at least one argument must precede /"));
+                }
+                slash = true;
+                args.posonlyargs.append(&mut args.args);
+            } else if self.eat_op(Operator::Star) {
+                if star.is_some() {
+                    return Err(Diagnostic::new(start,
+                        "* argument may appear only once"));
+                }
+                star = Some(start);
+                if !self.is_op(Operator::Comma) && !self.is(&close) {
+                    args.vararg = Some(self.parameter(annotated, true)?);
+                }
+            } else if self.eat_op(Operator::DoubleStar) {
+                args.kwarg = Some(self.parameter(annotated, false)?);
+                self.eat_op(Operator::Comma);
+                if !self.is(&close) {
+                    return Err(Diagnostic::new(self.span(),
+                        "arguments cannot follow var-keyword argument"));
+                }
+                break;
+            } else {
+                let arg = self.parameter(annotated, false)?;
+                let default = match self.eat_op(Operator::Eq) {
+                    true => Some(self.expression()?),
+                    false => None,
+                };
+                if star.is_some() {
+                    args.kwonlyargs.push(arg);
+                    args.kw_defaults.push(default);
+                } else if let Some(default) = default {
+                    args.args.push(arg);
+                    args.defaults.push(default);
+                } else if !args.defaults.is_empty() {
+                    return Err(Diagnostic::new(arg.span, "parameter without \
+                        a default follows parameter with a default"));
+                } else {
+                    args.args.push(arg);
+                }
+            }
+            if !self.eat_op(Operator::Comma) {
+                break;
+            }
+        }
+        if let Some(span) = star {
+            if args.vararg.is_none() && args.kwonlyargs.is_empty() {
+                return Err(Diagnostic::new(span,
+                    "named arguments must follow bare *"));
+            }
+        }
+        Ok(args)
+    }
+
+    // A parameter name with its annotation (which may be starred after `*`).
+    fn parameter(&mut self, annotated: bool, star: bool) -> Result<Arg<'a>> {
+        let start = self.span();
+        let arg = self.name()?;
+        let annotation = match annotated && self.eat_op(Operator::Colon) {
+            true if star && self.is_op(Operator::Star) => {
+                Some(self.star_expression()?)
+            }
+            true => Some(self.expression()?),
+            false => None,
+        };
+        Ok(Arg { arg, annotation, span: self.since(start) })
+    }
+
+    fn class_def(&mut self, decorator_list: Vec<Expr<'a>>, start: Span)
+        -> Result<ClassDef<'a>>
+    {
+        self.expect_keyword(Keyword::Class)?;
+        let name = self.name()?;
+        let type_params = self.type_params()?;
+        let (bases, keywords) = match self.eat_bracket(Bracket::ParensL) {
+            true => self.arguments()?,
+            false => (Vec::new(), Vec::new()),
+        };
+        let body = self.block("class definition", start)?;
+        Ok(ClassDef {
+            name,
+            type_params,
+            bases,
+            keywords,
+            body,
+            decorator_list,
+        })
+    }
+
+    // A `match` statement, or `None` (without moving) if `match` is a name.
+    fn match_stmt(&mut self) -> Result<Option<StmtKind<'a>>> {
+        let start = self.span();
+        let save = self.pos;
+        self.bump();
+        let subject = match self.match_subject() {
+            Ok(subject) if self.is_op(Operator::Colon)
+                && self.peek_at(1) == Some(&Token::Newline) =>
+            {
+                subject
+            }
+            _ => {
+                self.pos = save;
+                return Ok(None);
+            }
+        };
+        self.bump();
+        self.bump();
+        if !self.eat(&Token::Indent) {
+            let (line, _) = start.line_col(self.text);
+            return Err(Diagnostic::new(self.span(), format!(
+                "expected an indented block after 'match' statement on line \
+                    {}", line
+            )));
+        }
+        let mut cases = Vec::new();
+        while !self.eat(&Token::Dedent) {
+            if !self.is_word("case") {
+                return self.unexpected("`case`");
+            }
+            let start = self.span();
+            self.bump();
+            let pattern = self.patterns()?;
+            let guard = match self.eat_keyword(Keyword::If) {
+                true => Some(self.named_expression()?),
+                false => None,
+            };
+            let body = self.block("'case' statement", start)?;
+            cases.push(MatchCase { pattern, guard, body });
+        }
+        Ok(Some(StmtKind::Match(subject, cases)))
+    }
+
+    // The subject of a `match` statement, a tuple if it has commas.
+    fn match_subject(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let first = self.star_named_expression()?;
+        if !self.is_op(Operator::Comma) {
+            return Ok(first);
+        }
+        let mut elts = vec![first];
+        while self.eat_op(Operator::Comma) {
+            if self.is_op(Operator::Colon) {
+                break;
+            }
+            elts.push(self.star_named_expression()?);
+        }
+        let kind = ExprKind::Tuple(elts, ExprContext::Load);
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    // The pattern of a `case`, a sequence if it has commas.
+    fn patterns(&mut self) -> Result<Pattern<'a>> {
+        let start = self.span();
+        let first = self.maybe_star_pattern()?;
+        if !self.is_op(Operator::Comma) {
+            if let PatternKind::MatchStar(_) = first.kind {
+                return Err(Diagnostic::new(first.span,
+                    "can't use starred name here"));
+            }
+            return Ok(first);
+        }
+        let mut patterns = vec![first];
+        while self.eat_op(Operator::Comma) {
+            if self.is_op(Operator::Colon) || self.is_keyword(Keyword::If) {
+                break;
+            }
+            patterns.push(self.maybe_star_pattern()?);
+        }
+        let kind = PatternKind::MatchSequence(patterns);
+        Ok(Pattern { kind, span: self.since(start) })
+    }
+
+    fn maybe_star_pattern(&mut self) -> Result<Pattern<'a>> {
+        let start = self.span();
+        if !self.eat_op(Operator::Star) {
+            return self.pattern();
+        }
+        let name = match self.name()? {
+            "_" => None,
+            name => Some(name),
+        };
+        let kind = PatternKind::MatchStar(name);
+        Ok(Pattern { kind, span: self.since(start) })
+    }
+
+    // An or-pattern, optionally bound with `as`.
+    fn pattern(&mut self) -> Result<Pattern<'a>> {
+        let start = self.span();
+        let mut patterns = vec![self.closed_pattern()?];
+        while self.eat_op(Operator::Or) {
+            patterns.push(self.closed_pattern()?);
+        }
+        let mut pattern = match patterns.len() {
+            1 => patterns.pop().unwrap(),
+            _ => Pattern {
+                kind: PatternKind::MatchOr(patterns),
+                span: self.since(start),
+            },
+        };
+        while self.eat_keyword(Keyword::As) {
+            let span = self.span();
+            let name = self.name()?;
+            if name == "_" {
+                return Err(Diagnostic::new(span,
+                    "cannot use '_' as a target"));
+            }
+            let kind = PatternKind::MatchAs(Some(Box::new(pattern)), Some(name));
+            pattern = Pattern { kind, span: self.since(start) };
+        }
+        Ok(pattern)
+    }
+
+    fn closed_pattern(&mut self) -> Result<Pattern<'a>> {
+        let start = self.span();
+        let kind = match self.peek() {
+            Some(Token::Keyword(Keyword::None)) => {
+                self.bump();
+                PatternKind::MatchSingleton(Constant::None)
+            }
+            Some(Token::Keyword(Keyword::True)) => {
+                self.bump();
+                PatternKind::MatchSingleton(Constant::Bool(true))
+            }
+            Some(Token::Keyword(Keyword::False)) => {
+                self.bump();
+                PatternKind::MatchSingleton(Constant::Bool(false))
+            }
+            Some(Token::Name(_)) => {
+                let mut value = self.value_pattern()?;
+                if self.eat_bracket(Bracket::ParensL) {
+                    self.class_pattern(value)?
+                } else if let ExprKind::Name(name, _) = value.kind {
+                    match name {
+                        "_" => PatternKind::MatchAs(None, None),
+                        name => PatternKind::MatchAs(None, Some(name)),
+                    }
+                } else {
+                    expr::set_context(&mut value, ExprContext::Load)?;
+                    PatternKind::MatchValue(value)
+                }
+            }
+            Some(Token::Bracket(Bracket::ParensL)) => {
+                self.bump();
+                if self.eat_bracket(Bracket::ParensR) {
+                    PatternKind::MatchSequence(Vec::new())
+                } else {
+                    let first = self.maybe_star_pattern()?;
+                    if self.eat_bracket(Bracket::ParensR) {
+                        if let PatternKind::MatchStar(_) = first.kind {
+                            PatternKind::MatchSequence(vec![first])
+                        } else {
+                            // A group keeps the span inside the parentheses.
+                            return Ok(first);
+                        }
+                    } else {
+                        let mut patterns = vec![first];
+                        self.sequence_patterns(&mut patterns,
+                            Bracket::ParensR)?;
+                        PatternKind::MatchSequence(patterns)
+                    }
+                }
+            }
+            Some(Token::Bracket(Bracket::SquareL)) => {
+                self.bump();
+                let mut patterns = Vec::new();
+                if !self.eat_bracket(Bracket::SquareR) {
+                    patterns.push(self.maybe_star_pattern()?);
+                    self.sequence_patterns(&mut patterns, Bracket::SquareR)?;
+                }
+                PatternKind::MatchSequence(patterns)
+            }
+            Some(Token::Bracket(Bracket::BraceL)) => self.mapping_pattern()?,
+            _ => PatternKind::MatchValue(self.literal_pattern()?),
+        };
+        Ok(Pattern { kind, span: self.since(start) })
+    }
+
+    // The rest of a sequence pattern after its first pattern, up to `close`.
+    fn sequence_patterns(
+        &mut self,
+        patterns: &mut Vec<Pattern<'a>>,
+        close: Bracket,
+    ) -> Result<()> {
+        while self.eat_op(Operator::Comma) {
+            if self.is_bracket(close) {
+                break;
+            }
+            patterns.push(self.maybe_star_pattern()?);
+        }
+        self.expect_bracket(close)
+    }
+
+    // A name or dotted name in a pattern.
+    fn value_pattern(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let name = self.name()?;
+        let mut value = Expr {
+            kind: ExprKind::Name(name, ExprContext::Load),
+            span: self.since(start),
+        };
+        while self.eat_op(Operator::Dot) {
+            let attr = self.name()?;
+            let kind = ExprKind::Attribute(
+                Box::new(value), attr, ExprContext::Load);
+            value = Expr { kind, span: self.since(start) };
+        }
+        Ok(value)
+    }
+
+    // A number (with an optional sign and imaginary part) or strings.
+    fn literal_pattern(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        match self.peek() {
+            Some(Token::String(_) | Token::Bytes(_)) => {
+                return self.strings();
+            }
+            Some(Token::FStringStart(_)) => {
+                return Err(Diagnostic::new(start,
+                    "patterns may only match literals and attribute \
+                        lookups"));
+            }
+            _ => {}
+        }
+        let negative = self.eat_op(Operator::Minus);
+        let number = self.number()?;
+        let mut value = match negative {
+            true => expr::join(start, number.span,
+                ExprKind::UnaryOp(UnaryOp::USub, Box::new(number))),
+            false => number,
+        };
+        let op = match self.peek() {
+            Some(Token::Operator(Operator::Plus)) => BinaryOp::Add,
+            Some(Token::Operator(Operator::Minus)) => BinaryOp::Sub,
+            _ => return Ok(value),
+        };
+        self.bump();
+        let imaginary = self.number()?;
+        if !matches!(imaginary.kind, ExprKind::Constant(Constant::Complex(_)))
+        {
+            return Err(Diagnostic::new(imaginary.span,
+                "imaginary number required in complex literal"));
+        }
+        let span = imaginary.span;
+        let kind = ExprKind::BinOp(Box::new(value), op, Box::new(imaginary));
+        value = expr::join(start, span, kind);
+        Ok(value)
+    }
+
+    // A number literal.
+    fn number(&mut self) -> Result<Expr<'a>> {
+        let value = match self.peek() {
+            Some(&Token::Int(value)) => Constant::Int(value),
+            Some(&Token::Float(value)) => Constant::Float(value),
+            Some(&Token::Imaginary(value)) => Constant::Complex(value),
+            _ => return self.unexpected("pattern"),
+        };
+        let span = self.span();
+        self.bump();
+        Ok(Expr { kind: ExprKind::Constant(value), span })
+    }
+
+    fn mapping_pattern(&mut self) -> Result<PatternKind<'a>> {
+        self.bump();
+        let mut keys = Vec::new();
+        let mut patterns = Vec::new();
+        let mut rest = None;
+        while !self.is_bracket(Bracket::BraceR) {
+            if self.eat_op(Operator::DoubleStar) {
+                rest = Some(self.name()?);
+                self.eat_op(Operator::Comma);
+                break;
+            }
+            let key = match self.peek() {
+                Some(Token::Name(_)) => {
+                    let key = self.value_pattern()?;
+                    if let ExprKind::Name(..) = key.kind {
+                        return Err(Diagnostic::new(key.span, "mapping \
+                            pattern keys may only match literals and \
+                            attribute lookups"));
+                    }
+                    key
+                }
+                Some(&Token::Keyword(keyword @ (Keyword::None
+                    | Keyword::True | Keyword::False))) =>
+                {
+                    let span = self.span();
+                    self.bump();
+                    let value = match keyword {
+                        Keyword::None => Constant::None,
+                        keyword => Constant::Bool(keyword == Keyword::True),
+                    };
+                    Expr { kind: ExprKind::Constant(value), span }
+                }
+                _ => self.literal_pattern()?,
+            };
+            self.expect_op(Operator::Colon)?;
+            keys.push(key);
+            patterns.push(self.pattern()?);
+            if !self.eat_op(Operator::Comma) {
+                break;
+            }
+        }
+        self.expect_bracket(Bracket::BraceR)?;
+        Ok(PatternKind::MatchMapping(keys, patterns, rest))
+    }
+
+    // The arguments of a class pattern, after its `(`.
+    fn class_pattern(&mut self, cls: Expr<'a>) -> Result<PatternKind<'a>> {
+        let mut patterns = Vec::new();
+        let mut kwd_attrs = Vec::new();
+        let mut kwd_patterns = Vec::new();
+        while !self.is_bracket(Bracket::ParensR) {
+            let keyword = matches!(self.peek(), Some(Token::Name(_)))
+                && self.peek_at(1) == Some(&Token::Operator(Operator::Eq));
+            if keyword {
+                kwd_attrs.push(self.name()?);
+                self.bump();
+                kwd_patterns.push(self.pattern()?);
+            } else {
+                let pattern = self.pattern()?;
+                if !kwd_attrs.is_empty() {
+                    return Err(Diagnostic::new(pattern.span,
+                        "positional patterns follow keyword patterns"));
+                }
+                patterns.push(pattern);
+            }
+            if !self.eat_op(Operator::Comma) {
+                break;
+            }
+        }
+        self.expect_bracket(Bracket::ParensR)?;
+        Ok(PatternKind::MatchClass(cls, patterns, kwd_attrs, kwd_patterns))
+    }
+}
@@ -0,0 +1,989 @@
+// Python expression parser
+//
+//! Expressions, assignment targets and f-strings.  Binary operators are
+//! parsed by precedence climbing.  Targets are parsed as expressions, then
+//! checked and given a store or delete context.
+
+use super::Parser;
+use crate::python::{
+    BinaryOp, BoolOp, Bracket, CmpOp, Comprehension, Constant, Expr,
+    ExprContext, ExprKind, Keyword, KeywordArg, Operator, Result, Token,
+    UnaryOp,
+};
+use crate::{Diagnostic, Span};
+
+// Binary operator and precedence (higher binds tighter).
+fn binary_op(op: Operator) -> Option<(BinaryOp, u8)> {
+    Some(match op {
+        Operator::Or => (BinaryOp::BitOr, 1),
+        Operator::Caret => (BinaryOp::BitXor, 2),
+        Operator::And => (BinaryOp::BitAnd, 3),
+        Operator::Shl => (BinaryOp::LShift, 4),
+        Operator::Shr => (BinaryOp::RShift, 4),
+        Operator::Plus => (BinaryOp::Add, 5),
+        Operator::Minus => (BinaryOp::Sub, 5),
+        Operator::Star => (BinaryOp::Mult, 6),
+        Operator::Slash => (BinaryOp::Div, 6),
+        Operator::DoubleSlash => (BinaryOp::FloorDiv, 6),
+        Operator::Percent => (BinaryOp::Mod, 6),
+        Operator::At => (BinaryOp::MatMult, 6),
+        _ => return None,
+    })
+}
+
+/// Operator of an augmented assignment.
+pub(super) fn aug_op(op: Operator) -> Option<BinaryOp> {
+    Some(match op {
+        Operator::PlusEq => BinaryOp::Add,
+        Operator::MinusEq => BinaryOp::Sub,
+        Operator::StarEq => BinaryOp::Mult,
+        Operator::AtEq => BinaryOp::MatMult,
+        Operator::SlashEq => BinaryOp::Div,
+        Operator::PercentEq => BinaryOp::Mod,
+        Operator::DoubleStarEq => BinaryOp::Pow,
+        Operator::ShlEq => BinaryOp::LShift,
+        Operator::ShrEq => BinaryOp::RShift,
+        Operator::OrEq => BinaryOp::BitOr,
+        Operator::CaretEq => BinaryOp::BitXor,
+        Operator::AndEq => BinaryOp::BitAnd,
+        Operator::DoubleSlashEq => BinaryOp::FloorDiv,
+        _ => return None,
+    })
+}
+
+/// An expression from the start of `start` to the end of `end`.
+pub(super) fn join<'a>(start: Span, end: Span, kind: ExprKind<'a>)
+    -> Expr<'a>
+{
+    Expr { kind, span: Span::new(start.start, end.end) }
+}
+
+/// What an expression is, for errors about where it can't be used.
+pub(super) fn describe(expr: &Expr<'_>) -> &'static str {
+    match expr.kind {
+        ExprKind::BoolOp(..) | ExprKind::BinOp(..) | ExprKind::UnaryOp(..) => {
+            "expression"
+        }
+        ExprKind::NamedExpr(..) => "named expression",
+        ExprKind::Lambda(..) => "lambda",
+        ExprKind::IfExp(..) => "conditional expression",
+        ExprKind::Dict(..) => "dict literal",
+        ExprKind::Set(..) => "set display",
+        ExprKind::ListComp(..) => "list comprehension",
+        ExprKind::SetComp(..) => "set comprehension",
+        ExprKind::DictComp(..) => "dict comprehension",
+        ExprKind::GeneratorExp(..) => "generator expression",
+        ExprKind::Await(..) => "await expression",
+        ExprKind::Yield(..) | ExprKind::YieldFrom(..) => "yield expression",
+        ExprKind::Compare(..) => "comparison",
+        ExprKind::Call(..) => "function call",
+        ExprKind::FormattedValue(..) | ExprKind::JoinedStr(..) => {
+            "f-string expression"
+        }
+        ExprKind::Constant(Constant::None) => "None",
+        ExprKind::Constant(Constant::Bool(true)) => "True",
+        ExprKind::Constant(Constant::Bool(false)) => "False",
+        ExprKind::Constant(Constant::Ellipsis) => "ellipsis",
+        ExprKind::Constant(_) => "literal",
+        ExprKind::Attribute(..) => "attribute",
+        ExprKind::Subscript(..) => "subscript",
+        ExprKind::Starred(..) => "starred",
+        ExprKind::Name(..) => "name",
+        ExprKind::List(..) => "list",
+        ExprKind::Tuple(..) => "tuple",
+        ExprKind::Slice(..) => "slice",
+    }
+}
+
+/// Check that an expression can be assigned to or deleted, and set its
+/// context (and the context of the targets inside it).
+pub(super) fn set_context(expr: &mut Expr<'_>, ctx: ExprContext)
+    -> Result<()>
+{
+    let span = expr.span;
+    match expr.kind {
+        ExprKind::Name(_, ref mut c)
+        | ExprKind::Attribute(_, _, ref mut c)
+        | ExprKind::Subscript(_, _, ref mut c) => *c = ctx,
+        ExprKind::Starred(ref mut inner, ref mut c) => {
+            if ctx == ExprContext::Del {
+                return Err(Diagnostic::new(span, "cannot delete starred"));
+            }
+            *c = ctx;
+            set_context(inner, ctx)?;
+        }
+        ExprKind::Tuple(ref mut elts, ref mut c)
+        | ExprKind::List(ref mut elts, ref mut c) => {
+            *c = ctx;
+            if ctx == ExprContext::Store {
+                let starred = elts.iter()
+                    .filter(|elt| matches!(elt.kind, ExprKind::Starred(..)))
+                    .count();
+                if starred > 1 {
+                    return Err(Diagnostic::new(span,
+                        "multiple starred expressions in assignment"));
+                }
+            }
+            for elt in elts {
+                set_context(elt, ctx)?;
+            }
+        }
+        _ if ctx == ExprContext::Load => {}
+        _ => {
+            let verb = match ctx {
+                ExprContext::Del => "delete",
+                _ => "assign to",
+            };
+            return Err(Diagnostic::new(span,
+                format!("cannot {} {}", verb, describe(expr))));
+        }
+    }
+    Ok(())
+}
+
+/// Check that a target isn't a starred expression outside a tuple or list.
+pub(super) fn check_starred(target: &Expr<'_>) -> Result<()> {
+    match target.kind {
+        ExprKind::Starred(..) => Err(Diagnostic::new(target.span,
+            "starred assignment target must be in a list or tuple")),
+        _ => Ok(()),
+    }
+}
+
+impl<'a> Parser<'a> {
+    // Whether the next token can start an expression.
+    fn starts_expr(&self) -> bool {
+        match self.peek() {
+            Some(Token::Name(_))
+            | Some(Token::Int(_))
+            | Some(Token::Float(_))
+            | Some(Token::Imaginary(_))
+            | Some(Token::String(_))
+            | Some(Token::Bytes(_))
+            | Some(Token::FStringStart(_)) => true,
+            Some(Token::Keyword(keyword)) => matches!(keyword,
+                Keyword::None | Keyword::True | Keyword::False
+                    | Keyword::Not | Keyword::Lambda | Keyword::Await),
+            Some(Token::Operator(op)) => matches!(op,
+                Operator::Minus | Operator::Plus | Operator::Tilde
+                    | Operator::Star | Operator::Ellipsis),
+            Some(Token::Bracket(bracket)) => matches!(bracket,
+                Bracket::ParensL | Bracket::SquareL | Bracket::BraceL),
+            _ => false,
+        }
+    }
+
+    // Whether the next tokens start the `for` of a comprehension.
+    fn starts_comprehension(&self) -> bool {
+        self.is_keyword(Keyword::For)
+            || (self.is_keyword(Keyword::Async)
+                && self.peek_at(1) == Some(&Token::Keyword(Keyword::For)))
+    }
+
+    /// Expressions separated by commas, as a tuple if there is a comma.
+    pub(super) fn star_expressions(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let first = self.star_expression()?;
+        if !self.is_op(Operator::Comma) {
+            return Ok(first);
+        }
+        let mut elts = vec![first];
+        while self.eat_op(Operator::Comma) {
+            if !self.starts_expr() {
+                break;
+            }
+            elts.push(self.star_expression()?);
+        }
+        let kind = ExprKind::Tuple(elts, ExprContext::Load);
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    /// An expression, or `*` and an operand.
+    pub(super) fn star_expression(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        if !self.eat_op(Operator::Star) {
+            return self.expression();
+        }
+        let value = self.bitwise_or()?;
+        let kind = ExprKind::Starred(Box::new(value), ExprContext::Load);
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    /// A named expression, or `*` and an operand.
+    pub(super) fn star_named_expression(&mut self) -> Result<Expr<'a>> {
+        match self.is_op(Operator::Star) {
+            true => self.star_expression(),
+            false => self.named_expression(),
+        }
+    }
+
+    /// An expression, or an assignment expression `name := value`.
+    pub(super) fn named_expression(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let target = match self.peek() {
+            Some(&Token::Name(name))
+                if self.peek_at(1) == Some(&Token::Operator(Operator::ColonEq))
+                => name,
+            _ => {
+                let expr = self.expression()?;
+                if self.is_op(Operator::ColonEq) {
+                    return Err(Diagnostic::new(expr.span, format!(
+                        "cannot use assignment expressions with {}",
+                        describe(&expr))));
+                }
+                return Ok(expr);
+            }
+        };
+        self.bump();
+        let target = Expr {
+            kind: ExprKind::Name(target, ExprContext::Store),
+            span: start,
+        };
+        self.bump();
+        let value = self.expression()?;
+        let kind = ExprKind::NamedExpr(Box::new(target), Box::new(value));
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    /// An expression: a conditional expression, lambda or operation.
+    pub(super) fn expression(&mut self) -> Result<Expr<'a>> {
+        if self.is_keyword(Keyword::Lambda) {
+            return self.lambda();
+        }
+        let start = self.span();
+        let body = self.disjunction()?;
+        if !self.eat_keyword(Keyword::If) {
+            return Ok(body);
+        }
+        let test = self.disjunction()?;
+        self.expect_keyword(Keyword::Else)?;
+        let orelse = self.expression()?;
+        let kind = ExprKind::IfExp(
+            Box::new(test), Box::new(body), Box::new(orelse));
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    fn lambda(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        self.bump();
+        let args = self.parameters(false)?;
+        self.expect_op(Operator::Colon)?;
+        let body = self.expression()?;
+        let kind = ExprKind::Lambda(Box::new(args), Box::new(body));
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    // `a or b or c`
+    fn disjunction(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let first = self.conjunction()?;
+        if !self.is_keyword(Keyword::Or) {
+            return Ok(first);
+        }
+        let mut values = vec![first];
+        while self.eat_keyword(Keyword::Or) {
+            values.push(self.conjunction()?);
+        }
+        let kind = ExprKind::BoolOp(BoolOp::Or, values);
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    // `a and b and c`
+    fn conjunction(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let first = self.inversion()?;
+        if !self.is_keyword(Keyword::And) {
+            return Ok(first);
+        }
+        let mut values = vec![first];
+        while self.eat_keyword(Keyword::And) {
+            values.push(self.inversion()?);
+        }
+        let kind = ExprKind::BoolOp(BoolOp::And, values);
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    fn inversion(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        if !self.eat_keyword(Keyword::Not) {
+            return self.comparison();
+        }
+        let operand = self.inversion()?;
+        let kind = ExprKind::UnaryOp(UnaryOp::Not, Box::new(operand));
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    // The comparison operator at the next tokens, consumed.
+    fn cmp_op(&mut self) -> Option<CmpOp> {
+        let op = match self.peek()? {
+            Token::Operator(Operator::EqEq) => CmpOp::Eq,
+            Token::Operator(Operator::Ne) => CmpOp::NotEq,
+            Token::Operator(Operator::Lt) => CmpOp::Lt,
+            Token::Operator(Operator::Le) => CmpOp::LtE,
+            Token::Operator(Operator::Gt) => CmpOp::Gt,
+            Token::Operator(Operator::Ge) => CmpOp::GtE,
+            Token::Keyword(Keyword::In) => CmpOp::In,
+            Token::Keyword(Keyword::Is) => {
+                if self.peek_at(1) == Some(&Token::Keyword(Keyword::Not)) {
+                    self.bump();
+                    CmpOp::IsNot
+                } else {
+                    CmpOp::Is
+                }
+            }
+            Token::Keyword(Keyword::Not)
+                if self.peek_at(1) == Some(&Token::Keyword(Keyword::In)) =>
+            {
+                self.bump();
+                CmpOp::NotIn
+            }
+            _ => return None,
+        };
+        self.bump();
+        Some(op)
+    }
+
+    // `a < b <= c`
+    fn comparison(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let left = self.bitwise_or()?;
+        let mut ops = Vec::new();
+        let mut comparators = Vec::new();
+        while let Some(op) = self.cmp_op() {
+            ops.push(op);
+            comparators.push(self.bitwise_or()?);
+        }
+        if ops.is_empty() {
+            return Ok(left);
+        }
+        let kind = ExprKind::Compare(Box::new(left), ops, comparators);
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    /// An operand of a comparison (binary operators bind tighter).
+    pub(super) fn bitwise_or(&mut self) -> Result<Expr<'a>> {
+        self.binary(0)
+    }
+
+    // Binary operators of at least precedence `min`.
+    fn binary(&mut self, min: u8) -> Result<Expr<'a>> {
+        let start = self.span();
+        let mut left = self.factor()?;
+        while let Some((op, prec)) = match self.peek() {
+            Some(&Token::Operator(op)) => binary_op(op),
+            _ => None,
+        }
+        .filter(|&(_, prec)| prec >= min)
+        {
+            self.bump();
+            let right = self.binary(prec + 1)?;
+            let kind = ExprKind::BinOp(Box::new(left), op, Box::new(right));
+            left = Expr { kind, span: self.since(start) };
+        }
+        Ok(left)
+    }
+
+    // Unary `+`, `-` and `~`.
+    fn factor(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let op = match self.peek() {
+            Some(Token::Operator(Operator::Plus)) => UnaryOp::UAdd,
+            Some(Token::Operator(Operator::Minus)) => UnaryOp::USub,
+            Some(Token::Operator(Operator::Tilde)) => UnaryOp::Invert,
+            _ => return self.power(),
+        };
+        self.bump();
+        let operand = self.factor()?;
+        let kind = ExprKind::UnaryOp(op, Box::new(operand));
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    // `a ** b`, which binds tighter than a unary operator on its left.
+    fn power(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let base = self.await_primary()?;
+        if !self.eat_op(Operator::DoubleStar) {
+            return Ok(base);
+        }
+        let exp = self.factor()?;
+        let kind = ExprKind::BinOp(Box::new(base), BinaryOp::Pow, Box::new(exp));
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    fn await_primary(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        if !self.eat_keyword(Keyword::Await) {
+            return self.primary();
+        }
+        let value = self.primary()?;
+        let kind = ExprKind::Await(Box::new(value));
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    // An atom followed by attributes, calls and subscripts.
+    fn primary(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let mut expr = self.atom()?;
+        loop {
+            let kind = if self.eat_op(Operator::Dot) {
+                let attr = self.name()?;
+                ExprKind::Attribute(Box::new(expr), attr, ExprContext::Load)
+            } else if self.eat_bracket(Bracket::ParensL) {
+                let (args, keywords) = self.arguments()?;
+                ExprKind::Call(Box::new(expr), args, keywords)
+            } else if self.eat_bracket(Bracket::SquareL) {
+                let slice = self.slices()?;
+                self.expect_bracket(Bracket::SquareR)?;
+                ExprKind::Subscript(
+                    Box::new(expr), Box::new(slice), ExprContext::Load)
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr { kind, span: self.since(start) };
+        }
+    }
+
+    /// Arguments of a call or the bases of a class, after the `(` and up to
+    /// the `)`.
+    pub(super) fn arguments(&mut self)
+        -> Result<(Vec<Expr<'a>>, Vec<KeywordArg<'a>>)>
+    {
+        let mut args = Vec::new();
+        let mut keywords: Vec<KeywordArg<'a>> = Vec::new();
+        while !self.is_bracket(Bracket::ParensR) {
+            let start = self.span();
+            if self.eat_op(Operator::DoubleStar) {
+                let value = self.expression()?;
+                let span = self.since(start);
+                keywords.push(KeywordArg { arg: None, value, span });
+            } else if matches!(self.peek(), Some(Token::Name(_)))
+                && self.peek_at(1) == Some(&Token::Operator(Operator::Eq))
+            {
+                let arg = self.name()?;
+                self.bump();
+                let value = self.expression()?;
+                let span = self.since(start);
+                keywords.push(KeywordArg { arg: Some(arg), value, span });
+            } else {
+                let starred = self.is_op(Operator::Star);
+                let arg = self.star_named_expression()?;
+                if starred && keywords.iter().any(|kw| kw.arg.is_none()) {
+                    return Err(Diagnostic::new(arg.span, "iterable argument \
+                        unpacking follows keyword argument unpacking"));
+                }
+                if !starred {
+                    if keywords.iter().any(|kw| kw.arg.is_none()) {
+                        return Err(Diagnostic::new(arg.span, "positional \
+                            argument follows keyword argument unpacking"));
+                    }
+                    if !keywords.is_empty() {
+                        return Err(Diagnostic::new(arg.span,
+                            "positional argument follows keyword argument"));
+                    }
+                }
+                if self.starts_comprehension() {
+                    let generators = self.generators()?;
+                    let kind = ExprKind::GeneratorExp(Box::new(arg),
+                        generators);
+                    let genexp = Expr { kind, span: self.since(start) };
+                    let alone = args.is_empty() && keywords.is_empty()
+                        && self.is_bracket(Bracket::ParensR);
+                    if !alone {
+                        return Err(Diagnostic::new(genexp.span,
+                            "Generator expression must be parenthesized"));
+                    }
+                    args.push(genexp);
+                    break;
+                }
+                args.push(arg);
+            }
+            if !self.eat_op(Operator::Comma) {
+                break;
+            }
+        }
+        self.expect_bracket(Bracket::ParensR)?;
+        Ok((args, keywords))
+    }
+
+    // The index of a subscript, a tuple if it has commas.
+    fn slices(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let first = self.slice()?;
+        // `a[*b]` is `a[(*b,)]`.
+        let starred = matches!(first.kind, ExprKind::Starred(..));
+        if !self.is_op(Operator::Comma) && !starred {
+            return Ok(first);
+        }
+        let mut elts = vec![first];
+        while self.eat_op(Operator::Comma) {
+            if self.is_bracket(Bracket::SquareR) {
+                break;
+            }
+            elts.push(self.slice()?);
+        }
+        let kind = ExprKind::Tuple(elts, ExprContext::Load);
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    // An index, a starred expression or `lower:upper:step`.
+    fn slice(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        if self.is_op(Operator::Star) {
+            return self.star_expression();
+        }
+        let lower = if self.is_op(Operator::Colon) {
+            None
+        } else {
+            let index = self.named_expression()?;
+            if !self.is_op(Operator::Colon) {
+                return Ok(index);
+            }
+            Some(Box::new(index))
+        };
+        self.bump();
+        let ends = |this: &Self| {
+            matches!(this.peek(), Some(Token::Operator(Operator::Comma
+                | Operator::Colon) | Token::Bracket(Bracket::SquareR)))
+        };
+        let upper = match ends(self) {
+            true => None,
+            false => Some(Box::new(self.expression()?)),
+        };
+        let step = match self.eat_op(Operator::Colon) && !ends(self) {
+            true => Some(Box::new(self.expression()?)),
+            false => None,
+        };
+        let kind = ExprKind::Slice(lower, upper, step);
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    fn atom(&mut self) -> Result<Expr<'a>> {
+        let span = self.span();
+        let constant = match self.peek() {
+            Some(&Token::Name(name)) => {
+                self.bump();
+                let kind = ExprKind::Name(name, ExprContext::Load);
+                return Ok(Expr { kind, span });
+            }
+            Some(Token::Keyword(Keyword::None)) => Constant::None,
+            Some(Token::Keyword(Keyword::True)) => Constant::Bool(true),
+            Some(Token::Keyword(Keyword::False)) => Constant::Bool(false),
+            Some(Token::Operator(Operator::Ellipsis)) => Constant::Ellipsis,
+            Some(&Token::Int(value)) => Constant::Int(value),
+            Some(&Token::Float(value)) => Constant::Float(value),
+            Some(&Token::Imaginary(value)) => Constant::Complex(value),
+            Some(Token::String(_)
+                | Token::Bytes(_)
+                | Token::FStringStart(_)) => return self.strings(),
+            Some(Token::Bracket(Bracket::ParensL)) => return self.parens(),
+            Some(Token::Bracket(Bracket::SquareL)) => return self.list(),
+            Some(Token::Bracket(Bracket::BraceL)) => return self.dict_or_set(),
+            _ => return self.unexpected("expression"),
+        };
+        self.bump();
+        Ok(Expr { kind: ExprKind::Constant(constant), span })
+    }
+
+    // A tuple, generator expression, yield expression or parenthesized
+    // expression.
+    fn parens(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        self.bump();
+        if self.eat_bracket(Bracket::ParensR) {
+            let kind = ExprKind::Tuple(Vec::new(), ExprContext::Load);
+            return Ok(Expr { kind, span: self.since(start) });
+        }
+        if self.is_keyword(Keyword::Yield) {
+            let value = self.yield_expr()?;
+            self.expect_bracket(Bracket::ParensR)?;
+            return Ok(value);
+        }
+        let first = self.star_named_expression()?;
+        if self.starts_comprehension() {
+            let generators = self.generators()?;
+            self.expect_bracket(Bracket::ParensR)?;
+            let kind = ExprKind::GeneratorExp(Box::new(first), generators);
+            return Ok(Expr { kind, span: self.since(start) });
+        }
+        if self.eat_bracket(Bracket::ParensR) {
+            if let ExprKind::Starred(..) = first.kind {
+                return Err(Diagnostic::new(first.span,
+                    "cannot use starred expression here"));
+            }
+            return Ok(first);
+        }
+        let mut elts = vec![first];
+        while self.eat_op(Operator::Comma) {
+            if self.is_bracket(Bracket::ParensR) {
+                break;
+            }
+            elts.push(self.star_named_expression()?);
+        }
+        self.expect_bracket(Bracket::ParensR)?;
+        let kind = ExprKind::Tuple(elts, ExprContext::Load);
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    // A list display or list comprehension.
+    fn list(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        self.bump();
+        let mut elts = Vec::new();
+        if !self.is_bracket(Bracket::SquareR) {
+            let first = self.star_named_expression()?;
+            if self.starts_comprehension() {
+                let generators = self.generators()?;
+                self.expect_bracket(Bracket::SquareR)?;
+                let kind = ExprKind::ListComp(Box::new(first), generators);
+                return Ok(Expr { kind, span: self.since(start) });
+            }
+            elts.push(first);
+            while self.eat_op(Operator::Comma) {
+                if self.is_bracket(Bracket::SquareR) {
+                    break;
+                }
+                elts.push(self.star_named_expression()?);
+            }
+        }
+        self.expect_bracket(Bracket::SquareR)?;
+        let kind = ExprKind::List(elts, ExprContext::Load);
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    // A dict or set display, or a dict or set comprehension.
+    fn dict_or_set(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        self.bump();
+        if self.eat_bracket(Bracket::BraceR) {
+            let kind = ExprKind::Dict(Vec::new(), Vec::new());
+            return Ok(Expr { kind, span: self.since(start) });
+        }
+
+        let (key, value) = if self.eat_op(Operator::DoubleStar) {
+            (None, self.bitwise_or()?)
+        } else {
+            let first = self.star_named_expression()?;
+            if !self.eat_op(Operator::Colon) {
+                return self.set(start, first);
+            }
+            (Some(first), self.expression()?)
+        };
+        let key = match key {
+            Some(key) if self.starts_comprehension() => {
+                let generators = self.generators()?;
+                self.expect_bracket(Bracket::BraceR)?;
+                let kind = ExprKind::DictComp(
+                    Box::new(key), Box::new(value), generators);
+                return Ok(Expr { kind, span: self.since(start) });
+            }
+            key => key,
+        };
+        let mut keys = vec![key];
+        let mut values = vec![value];
+        while self.eat_op(Operator::Comma) {
+            if self.is_bracket(Bracket::BraceR) {
+                break;
+            }
+            if self.eat_op(Operator::DoubleStar) {
+                keys.push(None);
+                values.push(self.bitwise_or()?);
+            } else {
+                keys.push(Some(self.expression()?));
+                self.expect_op(Operator::Colon)?;
+                values.push(self.expression()?);
+            }
+        }
+        self.expect_bracket(Bracket::BraceR)?;
+        let kind = ExprKind::Dict(keys, values);
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    // The rest of a set display or comprehension after its first element.
+    fn set(&mut self, start: Span, first: Expr<'a>) -> Result<Expr<'a>> {
+        if self.starts_comprehension() {
+            let generators = self.generators()?;
+            self.expect_bracket(Bracket::BraceR)?;
+            let kind = ExprKind::SetComp(Box::new(first), generators);
+            return Ok(Expr { kind, span: self.since(start) });
+        }
+        let mut elts = vec![first];
+        while self.eat_op(Operator::Comma) {
+            if self.is_bracket(Bracket::BraceR) {
+                break;
+            }
+            elts.push(self.star_named_expression()?);
+        }
+        self.expect_bracket(Bracket::BraceR)?;
+        let kind = ExprKind::Set(elts);
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    // The `for` and `if` clauses of a comprehension.
+    fn generators(&mut self) -> Result<Vec<Comprehension<'a>>> {
+        let mut generators = Vec::new();
+        while self.starts_comprehension() {
+            let is_async = self.eat_keyword(Keyword::Async);
+            self.bump();
+            let target = self.star_targets()?;
+            self.expect_keyword(Keyword::In)?;
+            let iter = self.disjunction()?;
+            let mut ifs = Vec::new();
+            while self.eat_keyword(Keyword::If) {
+                ifs.push(self.disjunction()?);
+            }
+            generators.push(Comprehension { target, iter, ifs, is_async });
+        }
+        Ok(generators)
+    }
+
+    /// `yield`, `yield value` or `yield from value`.
+    pub(super) fn yield_expr(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        self.bump();
+        let kind = if self.eat_keyword(Keyword::From) {
+            ExprKind::YieldFrom(Box::new(self.expression()?))
+        } else if self.starts_expr() {
+            ExprKind::Yield(Some(Box::new(self.star_expressions()?)))
+        } else {
+            ExprKind::Yield(None)
+        };
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    /// Targets of an assignment, `for` loop or comprehension, as a tuple if
+    /// there is a comma.
+    pub(super) fn star_targets(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let first = self.star_target()?;
+        if !self.is_op(Operator::Comma) {
+            check_starred(&first)?;
+            return Ok(first);
+        }
+        let mut elts = vec![first];
+        while self.eat_op(Operator::Comma) {
+            if !self.starts_expr() {
+                break;
+            }
+            elts.push(self.star_target()?);
+        }
+        let mut target = Expr {
+            kind: ExprKind::Tuple(elts, ExprContext::Load),
+            span: self.since(start),
+        };
+        set_context(&mut target, ExprContext::Store)?;
+        Ok(target)
+    }
+
+    /// A single target, which may be starred.
+    pub(super) fn star_target(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let mut target = match self.eat_op(Operator::Star) {
+            true => {
+                let value = self.star_target()?;
+                let kind = ExprKind::Starred(Box::new(value),
+                    ExprContext::Load);
+                Expr { kind, span: self.since(start) }
+            }
+            false => self.primary()?,
+        };
+        set_context(&mut target, ExprContext::Store)?;
+        Ok(target)
+    }
+
+    /// Targets of a `del` statement.
+    pub(super) fn del_targets(&mut self) -> Result<Vec<Expr<'a>>> {
+        let mut targets = Vec::new();
+        loop {
+            let mut target = self.primary()?;
+            set_context(&mut target, ExprContext::Del)?;
+            targets.push(target);
+            if !self.eat_op(Operator::Comma) || !self.starts_expr() {
+                break;
+            }
+        }
+        Ok(targets)
+    }
+
+    /// Adjacent string literals and f-strings, concatenated.
+    pub(super) fn strings(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let mut values = Vec::new();
+        let mut literal = None;
+        let mut bytes = None;
+        let mut fstring = false;
+        let mut strings = false;
+        loop {
+            let span = self.span();
+            match self.peek() {
+                Some(Token::String(string)) => {
+                    let string = string.clone();
+                    self.bump();
+                    push_literal(&mut literal, &string, span);
+                    strings = true;
+                }
+                Some(Token::Bytes(more)) => {
+                    let more = more.clone();
+                    self.bump();
+                    bytes.get_or_insert_with(Vec::new).extend(more);
+                }
+                Some(Token::FStringStart(_)) => {
+                    self.fstring(&mut values, &mut literal)?;
+                    fstring = true;
+                }
+                _ => break,
+            }
+        }
+        let span = self.since(start);
+        let kind = match bytes {
+            Some(_) if strings || fstring => {
+                return Err(Diagnostic::new(span,
+                    "cannot mix bytes and nonbytes literals"));
+            }
+            Some(bytes) => ExprKind::Constant(Constant::Bytes(bytes)),
+            None if fstring => {
+                flush_literal(&mut values, &mut literal);
+                ExprKind::JoinedStr(values)
+            }
+            None => {
+                let string = literal.map_or(String::new(), |(string, _)| string);
+                ExprKind::Constant(Constant::Str(string))
+            }
+        };
+        Ok(Expr { kind, span })
+    }
+
+    // An f-string, adding its parts to `values` and its literal text to the
+    // pending `literal`.
+    fn fstring(
+        &mut self,
+        values: &mut Vec<Expr<'a>>,
+        literal: &mut Option<(String, Span)>,
+    ) -> Result<()> {
+        self.bump();
+        loop {
+            let span = self.span();
+            match self.peek() {
+                Some(Token::FStringMiddle(text)) => {
+                    let text = text.clone();
+                    self.bump();
+                    push_literal(literal, &text, span);
+                }
+                Some(Token::Bracket(Bracket::BraceL)) => {
+                    let (debug, value) = self.replacement_field()?;
+                    if let Some(debug) = debug {
+                        push_literal(literal, debug, value.span);
+                    }
+                    flush_literal(values, literal);
+                    values.push(value);
+                }
+                Some(Token::FStringEnd) => {
+                    self.bump();
+                    return Ok(());
+                }
+                _ => return self.unexpected("end of f-string"),
+            }
+        }
+    }
+
+    // A replacement field `{value=!r:spec}` of an f-string, with the text
+    // of a `=` debug specifier.
+    fn replacement_field(&mut self) -> Result<(Option<&'a str>, Expr<'a>)> {
+        let start = self.span();
+        self.bump();
+        if self.is_bracket(Bracket::BraceR) {
+            return Err(Diagnostic::new(self.since(start),
+                "f-string: valid expression required before '}'"));
+        }
+        let value = match self.is_keyword(Keyword::Yield) {
+            true => self.yield_expr()?,
+            false => self.star_expressions()?,
+        };
+        let debug = match self.eat_op(Operator::Eq) {
+            true => Some(&self.text[start.end..self.span().start]),
+            false => None,
+        };
+        let conversion = if self.eat_op(Operator::Not) {
+            let span = self.span();
+            match self.peek() {
+                Some(&Token::Name(name @ ("s" | "r" | "a")))
+                    if span.start == self.since(start).end =>
+                {
+                    self.bump();
+                    name.chars().next()
+                }
+                _ => {
+                    return Err(Diagnostic::new(span,
+                        "f-string: invalid conversion character"));
+                }
+            }
+        } else {
+            None
+        };
+        let format_spec = if self.is_op(Operator::Colon) {
+            let spec_start = self.span();
+            self.bump();
+            let mut values = Vec::new();
+            let mut literal = None;
+            loop {
+                let span = self.span();
+                match self.peek() {
+                    Some(Token::FStringMiddle(text)) => {
+                        let text = text.clone();
+                        self.bump();
+                        push_literal(&mut literal, &text, span);
+                    }
+                    Some(Token::Bracket(Bracket::BraceL)) => {
+                        let (debug, value) = self.replacement_field()?;
+                        if let Some(debug) = debug {
+                            push_literal(&mut literal, debug, value.span);
+                        }
+                        flush_literal(&mut values, &mut literal);
+                        values.push(value);
+                    }
+                    _ => break,
+                }
+            }
+            flush_literal(&mut values, &mut literal);
+            let kind = ExprKind::JoinedStr(values);
+            let span = Span::new(spec_start.end, self.span().start);
+            Some(Box::new(Expr { kind, span }))
+        } else {
+            None
+        };
+        if !self.eat_bracket(Bracket::BraceR) {
+            return self.unexpected("'}' in f-string");
+        }
+        // `{value=}` shows the repr unless there's a format spec.
+        let conversion = match (conversion, &debug, &format_spec) {
+            (None, Some(_), None) => Some('r'),
+            (conversion, ..) => conversion,
+        };
+        let kind = ExprKind::FormattedValue(
+            Box::new(value), conversion, format_spec);
+        Ok((debug, Expr { kind, span: self.since(start) }))
+    }
+}
+
+// Add literal text to a string being built from parts.
+fn push_literal(literal: &mut Option<(String, Span)>, text: &str, span: Span) {
+    match literal {
+        Some((string, literal_span)) => {
+            string.push_str(text);
+            *literal_span = literal_span.to(span);
+        }
+        None => *literal = Some((text.to_string(), span)),
+    }
+}
+
+// End the literal text before a replacement field of an f-string.
+fn flush_literal<'a>(
+    values: &mut Vec<Expr<'a>>,
+    literal: &mut Option<(String, Span)>,
+) {
+    if let Some((string, span)) = literal.take() {
+        if !string.is_empty() {
+            let kind = ExprKind::Constant(Constant::Str(string));
+            values.push(Expr { kind, span });
+        }
+    }
+}
@@ -0,0 +1,2543 @@
+// Python runtime
+//
+//! The values compiled Python code works on when their types aren't known
+//! statically, and the built-in operations on them.
+//!
+//! A [`Value`] is a boxed Python object.  Compiled code keeps `int`, `float`
+//! and `bool` values unboxed where the compiler could infer their types, and
+//! boxes them into a `Value` where it couldn't; everything else is always a
+//! `Value`.  Operations here dispatch on the type of their operands at run
+//! time, and raise Python's exceptions (with CPython's messages) as
+//! [`Exception`]s, which record the traceback of the code they pass through.
+//!
+//! `int`s have arbitrary precision ([`Int`]), `dict`s and `set`s are hash
+//! tables ([`Dict`] and [`Set`]), and objects are reference counted, with
+//! the cycles containers can form freed by the collector in [`gc`].
+//! Generators and coroutines are [`Generator`]s, which coroutines run as
+//! tasks of the event loop in [`asyncio`].
+
+pub mod asyncio;
+mod dict;
+pub mod gc;
+mod generator;
+mod int;
+mod methods;
+mod object;
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::rc::Rc;
+
+use super::{BinaryOp, CmpOp, UnaryOp};
+use crate::Span;
+
+pub use self::asyncio::Future;
+pub use self::dict::{Dict, Set};
+pub use self::generator::{
+    awaitable, delegate, stop_iteration, yield_from, Body, Generator, Kind,
+    Resume, Step,
+};
+pub use self::int::{hash_float, Int};
+pub use self::methods::call_method;
+pub use self::object::{
+    delete_attribute, get_attribute, instance_of, set_attribute,
+    super_attribute, Class, Method, Object,
+};
+use self::methods::{
+    set_difference, set_intersection, set_symmetric_difference, set_union,
+};
+
+/// A boxed Python object
+#[derive(Debug, Clone)]
+pub enum Value {
+    None,
+    Bool(bool),
+    Int(Int),
+    Float(f64),
+    Str(Rc<str>),
+    List(Rc<RefCell<Vec<Value>>>),
+    Tuple(Rc<[Value]>),
+    Dict(Rc<RefCell<Dict>>),
+    Set(Rc<RefCell<Set>>),
+    /// `range(start, stop, step)`
+    Range(i64, i64, i64),
+    /// An iterator object, like the result of `iter(value)`
+    Iterator(Rc<RefCell<Iter>>),
+    Function(Rc<Function>),
+    /// A built-in function used as a value, by name
+    Builtin(&'static str),
+    /// A built-in exception class, by name
+    Class(&'static str),
+    Exception(Rc<Exception>),
+    /// A generator or coroutine object
+    Generator(Rc<Generator>),
+    /// An `asyncio` future or task
+    Future(Rc<Future>),
+    /// An imported module, by name
+    Module(&'static str),
+    /// A class defined by a `class` statement
+    Type(Rc<Class>),
+    /// An instance of such a class
+    Object(Rc<Object>),
+    /// A method bound to an instance
+    Method(Rc<Method>),
+}
+
+/// A compiled function used as a value
+#[derive(Debug)]
+pub struct Function {
+    pub name: String,
+    /// The compiled instance of the function that takes boxed arguments
+    pub id: usize,
+    /// The variables of enclosing functions it uses
+    pub closure: Vec<Cell>,
+}
+
+/// A variable shared by a function and the functions nested in it, unbound
+/// while it is `None`
+pub type Cell = Rc<RefCell<Option<Value>>>;
+
+/// A Python exception, by the name of its class
+#[derive(Debug, Clone)]
+pub struct Exception {
+    pub kind: String,
+    pub message: String,
+    /// The arguments the exception was made with, `args` in Python
+    pub args: Rc<[Value]>,
+    /// Where the exception was raised in each function it passed through,
+    /// innermost first
+    pub traceback: Vec<Location>,
+    /// Where it was raised in the function it is in, once that is known
+    pub span: Option<Span>,
+}
+
+/// A line of a traceback
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub function: String,
+    pub span: Span,
+}
+
+/// The result of running Python code
+pub type Eval<T> = std::result::Result<T, Exception>;
+
+// The built-in exception classes and their base classes
+const EXCEPTIONS: &[(&str, &str)] = &[
+    ("BaseException", ""),
+    ("BaseExceptionGroup", "BaseException"),
+    ("GeneratorExit", "BaseException"),
+    ("KeyboardInterrupt", "BaseException"),
+    ("SystemExit", "BaseException"),
+    ("Exception", "BaseException"),
+    ("ArithmeticError", "Exception"),
+    ("FloatingPointError", "ArithmeticError"),
+    ("OverflowError", "ArithmeticError"),
+    ("ZeroDivisionError", "ArithmeticError"),
+    ("AssertionError", "Exception"),
+    ("AttributeError", "Exception"),
+    ("EOFError", "Exception"),
+    ("LookupError", "Exception"),
+    ("IndexError", "LookupError"),
+    ("KeyError", "LookupError"),
+    ("MemoryError", "Exception"),
+    ("NameError", "Exception"),
+    ("UnboundLocalError", "NameError"),
+    ("OSError", "Exception"),
+    ("RuntimeError", "Exception"),
+    ("NotImplementedError", "RuntimeError"),
+    ("RecursionError", "RuntimeError"),
+    ("StopAsyncIteration", "Exception"),
+    ("StopIteration", "Exception"),
+    ("SyntaxError", "Exception"),
+    ("SystemError", "Exception"),
+    ("TypeError", "Exception"),
+    ("ValueError", "Exception"),
+    ("UnicodeError", "ValueError"),
+];
+
+impl Exception {
+    /// A new exception of the built-in class `kind`.
+    pub fn new(kind: &str, message: impl Into<String>) -> Self {
+        let message = message.into();
+        let args = match message.is_empty() {
+            true => Rc::from([]),
+            false => Rc::from([Value::str(&message)]),
+        };
+        Exception::with_args(kind, message, args)
+    }
+
+    /// The exception made by calling its class with `args`, printed as
+    /// `message`.
+    pub fn with_args(kind: &str, message: String, args: Rc<[Value]>) -> Self {
+        Exception {
+            kind: kind.to_string(),
+            message,
+            args,
+            traceback: Vec::new(),
+            span: None,
+        }
+    }
+
+    /// The exception, raised at `span` if where it was raised in the
+    /// current function isn't known yet.
+    pub fn at(mut self, span: Span) -> Self {
+        self.span.get_or_insert(span);
+        self
+    }
+
+    /// The exception leaving a function, whose definition is at `span`.
+    pub fn leave(mut self, function: &str, span: Span) -> Self {
+        let span = self.span.take().unwrap_or(span);
+        self.traceback.push(Location { function: function.to_string(), span });
+        self
+    }
+
+    /// Whether the exception is an instance of the built-in class `class`.
+    pub fn is_instance(&self, class: &str) -> bool {
+        is_subclass(&self.kind, class)
+    }
+
+    /// The exception as CPython prints it when it isn't caught: the
+    /// traceback of the lines of `source` it was raised from, most recent
+    /// call last, then the exception.
+    pub fn traceback(&self, source: &str, filename: &str) -> String {
+        let mut out = String::from("Traceback (most recent call last):\n");
+        for location in self.traceback.iter().rev() {
+            let start = location.span.start.min(source.len());
+            let line = source[..start].matches('\n').count() + 1;
+            let text = source.lines().nth(line - 1).unwrap_or("").trim();
+            out.push_str(&format!(
+                "  File \"{}\", line {}, in {}\n",
+                filename,
+                line,
+                location.function,
+            ));
+            if !text.is_empty() {
+                out.push_str(&format!("    {}\n", text));
+            }
+        }
+        out.push_str(&format!("{}\n", self));
+        out
+    }
+}
+
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.message.is_empty() {
+            true => write!(f, "{}", self.kind),
+            false => write!(f, "{}: {}", self.kind, self.message),
+        }
+    }
+}
+
+/// Whether the built-in exception class `class` is `base` or derives from
+/// it.
+pub fn is_subclass(class: &str, base: &str) -> bool {
+    let mut class = class;
+    loop {
+        if class == base {
+            return true;
+        }
+        match EXCEPTIONS.iter().find(|(name, _)| *name == class) {
+            Some((_, parent)) if !parent.is_empty() => class = parent,
+            _ => return false,
+        }
+    }
+}
+
+/// A built-in exception class, by name.
+pub fn exception_class(name: &str) -> Option<&'static str> {
+    EXCEPTIONS.iter().find(|(class, _)| *class == name).map(|(class, _)| *class)
+}
+
+// An exception made by calling its class with arguments.
+fn new_exception(class: &str, args: &[Value]) -> Value {
+    let message = match args {
+        [] => String::new(),
+        [arg] if class == "KeyError" => arg.repr(),
+        [arg] => arg.to_string(),
+        _ => Value::tuple(args.to_vec()).repr(),
+    };
+    Value::Exception(Rc::new(Exception::with_args(class, message,
+        args.into())))
+}
+
+/// The exception `raise value` raises.
+pub fn to_exception(value: &Value) -> Exception {
+    match value {
+        Value::Exception(exception) => (**exception).clone(),
+        Value::Class(class) => Exception::new(class, ""),
+        _ => Exception::new("TypeError", "exceptions must derive from BaseException"),
+    }
+}
+
+/// Whether `except class:` catches an exception, where `class` may be a
+/// tuple of classes.
+pub fn catches(exception: &Exception, class: &Value) -> Eval<bool> {
+    match class {
+        Value::Class(class) => Ok(exception.is_instance(class)),
+        Value::Tuple(classes) => {
+            for class in classes.iter() {
+                if catches(exception, class)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        _ => raise(
+            "TypeError",
+            "catching classes that do not inherit from BaseException is not \
+                allowed",
+        ),
+    }
+}
+
+fn raise<T>(kind: &str, message: impl Into<String>) -> Eval<T> {
+    Err(Exception::new(kind, message))
+}
+
+// Raise `IndexError` for an `int` too large for a machine-sized index.
+fn index_overflow<T>() -> Eval<T> {
+    raise("IndexError", "cannot fit 'int' into an index-sized integer")
+}
+
+/// Python's `hash(value)`, raising `TypeError` for mutable containers.
+/// Numbers that are equal have the same hash, like in CPython.
+pub fn hash(value: &Value) -> Eval<i64> {
+    Ok(match value {
+        Value::None => 0xfca8_6420,
+        Value::Bool(value) => *value as i64,
+        Value::Int(value) => value.hash(),
+        Value::Float(value) => hash_float(*value),
+        Value::Str(string) => {
+            // FNV-1a; CPython's string hashes are randomized anyway.
+            let hash = string.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3)
+            }) as i64;
+            if hash == -1 { -2 } else { hash }
+        }
+        Value::Tuple(items) => hash_tuple(items)?,
+        Value::Range(start, stop, step) => {
+            let len = range_len(*start, *stop, *step);
+            let items = match len {
+                0 => vec![Value::int(0), Value::None, Value::None],
+                1 => vec![Value::int(1), Value::int(*start), Value::None],
+                _ => vec![Value::int(len), Value::int(*start), Value::int(*step)],
+            };
+            hash_tuple(&items)?
+        }
+        Value::Builtin(name) | Value::Class(name) => hash(&Value::str(name))?,
+        Value::Function(function) => Rc::as_ptr(function) as i64 >> 4,
+        Value::Iterator(iter) => Rc::as_ptr(iter) as *const () as i64 >> 4,
+        Value::Exception(exception) => Rc::as_ptr(exception) as i64 >> 4,
+        Value::Generator(generator) => Rc::as_ptr(generator) as i64 >> 4,
+        Value::Future(future) => Rc::as_ptr(future) as i64 >> 4,
+        Value::Module(name) => hash(&Value::str(name))?,
+        Value::Type(class) => Rc::as_ptr(class) as i64 >> 4,
+        Value::Object(object) => Rc::as_ptr(object) as i64 >> 4,
+        Value::Method(method) => {
+            hash_tuple(&[method.receiver.clone(), method.function.clone()])?
+        }
+        Value::List(_) | Value::Dict(_) | Value::Set(_) => {
+            return raise("TypeError", format!(
+                "unhashable type: '{}'",
+                value.type_name(),
+            ))
+        }
+    })
+}
+
+// CPython's hash of a tuple, from the hashes of its items.
+fn hash_tuple(items: &[Value]) -> Eval<i64> {
+    const PRIME_1: u64 = 11400714785074694791;
+    const PRIME_2: u64 = 14029467366897019727;
+    const PRIME_5: u64 = 2870177450012600261;
+    let mut hash = PRIME_5;
+    for item in items {
+        let lane = self::hash(item)? as u64;
+        hash = hash.wrapping_add(lane.wrapping_mul(PRIME_2));
+        hash = hash.rotate_left(31).wrapping_mul(PRIME_1);
+    }
+    hash = hash.wrapping_add(items.len() as u64 ^ (PRIME_5 ^ 3527539));
+    Ok(match hash as i64 {
+        -1 => 1546275796,
+        hash => hash,
+    })
+}
+
+impl Value {
+    pub fn int(value: i64) -> Self {
+        Value::Int(Int::Small(value))
+    }
+
+    pub fn str(string: &str) -> Self {
+        Value::Str(string.into())
+    }
+
+    pub fn list(items: Vec<Value>) -> Self {
+        let list = Value::List(Rc::new(RefCell::new(items)));
+        gc::track(&list);
+        list
+    }
+
+    pub fn tuple(items: Vec<Value>) -> Self {
+        let tuple = Value::Tuple(items.into());
+        gc::track(&tuple);
+        tuple
+    }
+
+    pub fn dict(dict: Dict) -> Self {
+        let dict = Value::Dict(Rc::new(RefCell::new(dict)));
+        gc::track(&dict);
+        dict
+    }
+
+    pub fn set(set: Set) -> Self {
+        let set = Value::Set(Rc::new(RefCell::new(set)));
+        gc::track(&set);
+        set
+    }
+
+    pub fn iterator(iter: Iter) -> Self {
+        let iter = Value::Iterator(Rc::new(RefCell::new(iter)));
+        gc::track(&iter);
+        iter
+    }
+
+    pub fn generator(generator: Generator) -> Self {
+        let generator = Value::Generator(Rc::new(generator));
+        gc::track(&generator);
+        generator
+    }
+
+    /// A new instance of a class, with no attributes.
+    pub fn object(class: Rc<Class>) -> Self {
+        let object = Value::Object(Rc::new(Object::new(class)));
+        gc::track(&object);
+        object
+    }
+
+    /// The name of the value's class.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::None => "NoneType",
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Str(_) => "str",
+            Value::List(_) => "list",
+            Value::Tuple(_) => "tuple",
+            Value::Dict(_) => "dict",
+            Value::Set(_) => "set",
+            Value::Range(..) => "range",
+            Value::Iterator(iter) => iter.borrow().type_name(),
+            Value::Function(_) => "function",
+            Value::Builtin(_) => "builtin_function_or_method",
+            Value::Class(_) => "type",
+            Value::Exception(exception) => exception_class(&exception.kind)
+                .unwrap_or("Exception"),
+            Value::Generator(generator) => generator.type_name(),
+            Value::Future(future) => future.name,
+            Value::Module(_) => "module",
+            Value::Type(_) => "type",
+            Value::Object(object) => object.class.name,
+            Value::Method(_) => "method",
+        }
+    }
+
+    /// Python's truth value of the object.
+    pub fn truthy(&self) -> bool {
+        match self {
+            Value::None => false,
+            Value::Bool(value) => *value,
+            Value::Int(value) => !value.is_zero(),
+            Value::Float(value) => *value != 0.0,
+            Value::Str(string) => !string.is_empty(),
+            Value::List(items) => !items.borrow().is_empty(),
+            Value::Tuple(items) => !items.is_empty(),
+            Value::Dict(dict) => !dict.borrow().is_empty(),
+            Value::Set(set) => !set.borrow().is_empty(),
+            Value::Range(start, stop, step) => range_len(*start, *stop, *step) > 0,
+            Value::Iterator(_) | Value::Function(_) | Value::Builtin(_)
+            | Value::Class(_) | Value::Exception(_) | Value::Generator(_)
+            | Value::Future(_) | Value::Module(_) | Value::Type(_)
+            | Value::Object(_) | Value::Method(_) => true,
+        }
+    }
+
+    /// `repr(value)`
+    pub fn repr(&self) -> String {
+        let mut out = String::new();
+        self.write_repr(&mut out, &mut Vec::new());
+        out
+    }
+
+    // Write the repr, with `[...]` for containers already being written.
+    fn write_repr(&self, out: &mut String, seen: &mut Vec<usize>) {
+        let address = match self {
+            Value::List(items) => Rc::as_ptr(items) as *const () as usize,
+            Value::Dict(dict) => Rc::as_ptr(dict) as *const () as usize,
+            Value::Set(set) => Rc::as_ptr(set) as *const () as usize,
+            _ => 0,
+        };
+        if address != 0 && seen.contains(&address) {
+            out.push_str(match self {
+                Value::List(_) => "[...]",
+                Value::Set(_) => "set(...)",
+                _ => "{...}",
+            });
+            return;
+        }
+        seen.push(address);
+        let mut items = |items: &mut dyn Iterator<Item = &Value>, out: &mut String| {
+            for (i, item) in items.enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                item.write_repr(out, seen);
+            }
+        };
+        match self {
+            Value::Str(string) => out.push_str(&repr_str(string)),
+            Value::List(list) => {
+                out.push('[');
+                items(&mut list.borrow().iter(), out);
+                out.push(']');
+            }
+            Value::Tuple(tuple) => {
+                out.push('(');
+                items(&mut tuple.iter(), out);
+                if tuple.len() == 1 {
+                    out.push(',');
+                }
+                out.push(')');
+            }
+            Value::Dict(dict) => {
+                out.push('{');
+                for (i, (key, value)) in dict.borrow().iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    key.write_repr(out, seen);
+                    out.push_str(": ");
+                    value.write_repr(out, seen);
+                }
+                out.push('}');
+            }
+            Value::Set(set) if set.borrow().is_empty() => out.push_str("set()"),
+            Value::Set(set) => {
+                out.push('{');
+                items(&mut set.borrow().iter(), out);
+                out.push('}');
+            }
+            Value::Exception(exception) => {
+                let message = match exception.kind.as_str() {
+                    _ if exception.message.is_empty() => String::new(),
+                    // The message of a `KeyError` is the repr of the key.
+                    "KeyError" => exception.message.clone(),
+                    _ => repr_str(&exception.message),
+                };
+                out.push_str(&format!("{}({})", exception.kind, message));
+            }
+            _ => out.push_str(&self.to_string()),
+        }
+        seen.pop();
+    }
+}
+
+/// `str(value)`
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::None => write!(f, "None"),
+            Value::Bool(true) => write!(f, "True"),
+            Value::Bool(false) => write!(f, "False"),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", repr_float(*value)),
+            Value::Str(string) => write!(f, "{}", string),
+            Value::Range(start, stop, 1) => {
+                write!(f, "range({}, {})", start, stop)
+            }
+            Value::Range(start, stop, step) => {
+                write!(f, "range({}, {}, {})", start, stop, step)
+            }
+            Value::Iterator(iter) => {
+                let address = Rc::as_ptr(iter) as *const () as usize;
+                write!(f, "<{} object at {:#x}>", self.type_name(), address)
+            }
+            Value::Function(function) => {
+                let address = Rc::as_ptr(function) as usize;
+                write!(f, "<function {} at {:#x}>", function.name, address)
+            }
+            Value::Builtin(name) => write!(f, "<built-in function {}>", name),
+            Value::Class(name) => write!(f, "<class '{}'>", name),
+            Value::Exception(exception) => write!(f, "{}", exception.message),
+            Value::Generator(generator) => {
+                let address = Rc::as_ptr(generator) as usize;
+                write!(f, "<{} object {} at {:#x}>", generator.type_name(),
+                    generator.name, address)
+            }
+            Value::Future(future) => {
+                let state = match future.is_done() {
+                    true => "finished",
+                    false => "pending",
+                };
+                write!(f, "<{} {}>", future.name, state)
+            }
+            Value::Module(name) => write!(f, "<module '{}'>", name),
+            Value::Type(class) => write!(f, "<class '__main__.{}'>", class.name),
+            Value::Object(object) => {
+                let address = Rc::as_ptr(object) as usize;
+                write!(f, "<__main__.{} object at {:#x}>", object.class.name,
+                    address)
+            }
+            Value::Method(method) => {
+                let name = match &method.function {
+                    Value::Function(function) => function.name.as_str(),
+                    _ => "?",
+                };
+                write!(f, "<bound method {}.{} of {}>",
+                    method.receiver.type_name(), name, method.receiver.repr())
+            }
+            Value::List(_) | Value::Tuple(_) | Value::Dict(_) | Value::Set(_) => {
+                write!(f, "{}", self.repr())
+            }
+        }
+    }
+}
+
+/// Python's `==`.
+pub fn equal(a: &Value, b: &Value) -> bool {
+    use Value::*;
+    match (a, b) {
+        (None, None) => true,
+        (Str(a), Str(b)) => a == b,
+        (List(a), List(b)) => {
+            Rc::ptr_eq(a, b) || sequence_equal(&a.borrow(), &b.borrow())
+        }
+        (Tuple(a), Tuple(b)) => sequence_equal(a, b),
+        (Dict(a), Dict(b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            a.len() == b.len()
+                && a.iter().all(|(key, value)| {
+                    matches!(b.get(key), Ok(Some(other)) if equal(value, &other))
+                })
+        }
+        (Set(a), Set(b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            a.len() == b.len() && subset(&a, &b)
+        }
+        (Range(a, b, c), Range(d, e, f)) => {
+            let (len, other) = (range_len(*a, *b, *c), range_len(*d, *e, *f));
+            len == other && (len == 0 || a == d && (len == 1 || c == f))
+        }
+        (Function(a), Function(b)) => Rc::ptr_eq(a, b),
+        (Builtin(a), Builtin(b)) | (Class(a), Class(b)) => a == b,
+        (Method(a), Method(b)) => {
+            identical(&a.receiver, &b.receiver)
+                && identical(&a.function, &b.function)
+        }
+        _ => match (number(a), number(b)) {
+            (Some(a), Some(b)) => a.cmp(&b) == Some(Ordering::Equal),
+            _ => identical(a, b),
+        },
+    }
+}
+
+fn sequence_equal(a: &[Value], b: &[Value]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equal(a, b))
+}
+
+// Whether every item of `a` is in `b`.
+fn subset(a: &Set, b: &Set) -> bool {
+    a.iter().all(|item| matches!(b.contains(item), Ok(true)))
+}
+
+// A number, with `bool`s as `int`s.
+#[derive(Debug, Clone)]
+enum Number {
+    Int(Int),
+    Float(f64),
+}
+
+impl Number {
+    fn float(&self) -> Eval<f64> {
+        match self {
+            Number::Int(value) => value.to_f64(),
+            Number::Float(value) => Ok(*value),
+        }
+    }
+
+    // Compare exactly, even an `int` too large to be a `float`.
+    fn cmp(&self, other: &Number) -> Option<Ordering> {
+        match (self, other) {
+            (Number::Int(a), Number::Int(b)) => Some(a.cmp(b)),
+            (Number::Float(a), Number::Float(b)) => a.partial_cmp(b),
+            (Number::Int(a), Number::Float(b)) => {
+                Number::Float(*b).cmp(&Number::Int(a.clone())).map(Ordering::reverse)
+            }
+            (Number::Float(a), Number::Int(b)) => {
+                if a.is_nan() {
+                    return None;
+                }
+                if a.is_infinite() {
+                    return Some(if *a > 0.0 { Ordering::Greater } else { Ordering::Less });
+                }
+                let whole = Int::from_f64(*a);
+                Some(whole.cmp(b).then(match a.fract() {
+                    fraction if fraction > 0.0 => Ordering::Greater,
+                    fraction if fraction < 0.0 => Ordering::Less,
+                    _ => Ordering::Equal,
+                }))
+            }
+        }
+    }
+}
+
+fn number(value: &Value) -> Option<Number> {
+    match value {
+        Value::Bool(value) => Some(Number::Int(Int::Small(*value as i64))),
+        Value::Int(value) => Some(Number::Int(value.clone())),
+        Value::Float(value) => Some(Number::Float(*value)),
+        _ => None,
+    }
+}
+
+/// Python's ordering of two values, `None` when they are unordered floats
+/// or sets neither of which contains the other.
+pub fn order(op: CmpOp, a: &Value, b: &Value) -> Eval<Option<Ordering>> {
+    use Value::*;
+    match (a, b) {
+        (Str(a), Str(b)) => Ok(Some(a.cmp(b))),
+        (List(a), List(b)) => sequence_order(op, &a.borrow(), &b.borrow()),
+        (Tuple(a), Tuple(b)) => sequence_order(op, a, b),
+        (Set(a), Set(b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            Ok(match (subset(&a, &b), subset(&b, &a)) {
+                (true, true) => Some(Ordering::Equal),
+                (true, false) => Some(Ordering::Less),
+                (false, true) => Some(Ordering::Greater),
+                (false, false) => Option::None,
+            })
+        }
+        _ => match (number(a), number(b)) {
+            (Some(a), Some(b)) => Ok(a.cmp(&b)),
+            _ => raise("TypeError", format!(
+                "'{}' not supported between instances of '{}' and '{}'",
+                cmp_symbol(op), a.type_name(), b.type_name(),
+            )),
+        },
+    }
+}
+
+// Compare sequences by their first unequal items, then by length.
+fn sequence_order(op: CmpOp, a: &[Value], b: &[Value])
+    -> Eval<Option<Ordering>>
+{
+    for (a, b) in a.iter().zip(b) {
+        if !equal(a, b) {
+            return order(op, a, b);
+        }
+    }
+    Ok(Some(a.len().cmp(&b.len())))
+}
+
+fn cmp_symbol(op: CmpOp) -> &'static str {
+    match op {
+        CmpOp::Eq => "==",
+        CmpOp::NotEq => "!=",
+        CmpOp::Lt => "<",
+        CmpOp::LtE => "<=",
+        CmpOp::Gt => ">",
+        CmpOp::GtE => ">=",
+        CmpOp::Is => "is",
+        CmpOp::IsNot => "is not",
+        CmpOp::In => "in",
+        CmpOp::NotIn => "not in",
+    }
+}
+
+/// Whether an ordering satisfies `<`, `<=`, `>` or `>=`.
+pub fn ordered(op: CmpOp, ordering: Option<Ordering>) -> bool {
+    match (op, ordering) {
+        (_, None) => false,
+        (CmpOp::Lt, Some(ordering)) => ordering == Ordering::Less,
+        (CmpOp::LtE, Some(ordering)) => ordering != Ordering::Greater,
+        (CmpOp::Gt, Some(ordering)) => ordering == Ordering::Greater,
+        (_, Some(ordering)) => ordering != Ordering::Less,
+    }
+}
+
+/// A comparison operator applied to two values.
+pub fn compare(op: CmpOp, a: &Value, b: &Value) -> Eval<bool> {
+    match op {
+        CmpOp::Eq => Ok(equal(a, b)),
+        CmpOp::NotEq => Ok(!equal(a, b)),
+        CmpOp::Is => Ok(identical(a, b)),
+        CmpOp::IsNot => Ok(!identical(a, b)),
+        CmpOp::In => contains(b, a),
+        CmpOp::NotIn => Ok(!contains(b, a)?),
+        _ => Ok(ordered(op, order(op, a, b)?)),
+    }
+}
+
+// Python's `is`.  Numbers and strings are compared by value, as if they were
+// always interned.
+fn identical(a: &Value, b: &Value) -> bool {
+    use Value::*;
+    match (a, b) {
+        (None, None) => true,
+        (Bool(a), Bool(b)) => a == b,
+        (Int(a), Int(b)) => a == b,
+        (Float(a), Float(b)) => a.to_bits() == b.to_bits(),
+        (Str(a), Str(b)) => a == b,
+        (List(a), List(b)) => Rc::ptr_eq(a, b),
+        (Tuple(a), Tuple(b)) => Rc::ptr_eq(a, b),
+        (Dict(a), Dict(b)) => Rc::ptr_eq(a, b),
+        (Set(a), Set(b)) => Rc::ptr_eq(a, b),
+        (Iterator(a), Iterator(b)) => Rc::ptr_eq(a, b),
+        (Function(a), Function(b)) => Rc::ptr_eq(a, b),
+        (Builtin(a), Builtin(b)) | (Class(a), Class(b)) => a == b,
+        (Exception(a), Exception(b)) => Rc::ptr_eq(a, b),
+        (Generator(a), Generator(b)) => Rc::ptr_eq(a, b),
+        (Future(a), Future(b)) => Rc::ptr_eq(a, b),
+        (Module(a), Module(b)) => a == b,
+        (Type(a), Type(b)) => Rc::ptr_eq(a, b),
+        (Object(a), Object(b)) => Rc::ptr_eq(a, b),
+        (Method(a), Method(b)) => Rc::ptr_eq(a, b),
+        _ => false,
+    }
+}
+
+/// `item in container`
+pub fn contains(container: &Value, item: &Value) -> Eval<bool> {
+    match (container, item) {
+        (Value::Str(string), Value::Str(part)) => Ok(string.contains(&**part)),
+        (Value::Str(_), _) => raise("TypeError", format!(
+            "'in <string>' requires string as left operand, not {}",
+            item.type_name(),
+        )),
+        (Value::Dict(dict), _) => Ok(dict.borrow().get(item)?.is_some()),
+        (Value::Set(set), _) => set.borrow().contains(item),
+        (Value::Range(start, stop, step), _) => Ok(match number(item) {
+            Some(Number::Int(value)) => match value.to_i64() {
+                Some(value) => {
+                    let (start, stop, step) = (*start, *stop, *step);
+                    let inside = match step > 0 {
+                        true => start <= value && value < stop,
+                        false => stop < value && value <= start,
+                    };
+                    inside && (value as i128 - start as i128) % step as i128 == 0
+                }
+                None => false,
+            },
+            _ => iter_contains(container, item)?,
+        }),
+        _ => iter_contains(container, item),
+    }
+}
+
+fn iter_contains(container: &Value, item: &Value) -> Eval<bool> {
+    for value in iter(container)? {
+        if equal(&value?, item) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// The number of items in a range.
+pub fn range_len(start: i64, stop: i64, step: i64) -> i64 {
+    let (start, stop, step) = (start as i128, stop as i128, step as i128);
+    let len = match step > 0 {
+        true if start < stop => (stop - start - 1) / step + 1,
+        false if start > stop => (start - stop - 1) / -step + 1,
+        _ => 0,
+    };
+    len as i64
+}
+
+/// `len(value)`
+pub fn len(value: &Value) -> Eval<i64> {
+    let len = match value {
+        Value::Str(string) => string.chars().count(),
+        Value::List(items) => items.borrow().len(),
+        Value::Tuple(items) => items.len(),
+        Value::Dict(dict) => dict.borrow().len(),
+        Value::Set(set) => set.borrow().len(),
+        Value::Range(start, stop, step) => {
+            return Ok(range_len(*start, *stop, *step))
+        }
+        _ => {
+            return raise("TypeError", format!(
+                "object of type '{}' has no len()",
+                value.type_name(),
+            ))
+        }
+    };
+    Ok(len as i64)
+}
+
+/// An iterator over the items of a value
+#[derive(Debug)]
+pub enum Iter {
+    /// A list and the index of its next item; items added while iterating
+    /// are seen, like in Python
+    List(Rc<RefCell<Vec<Value>>>, usize),
+    Tuple(Rc<[Value]>, usize),
+    /// A string and the byte index of its next character
+    Str(Rc<str>, usize),
+    /// The next value, the end and the step of a range
+    Range(i64, i64, i64),
+    /// Items collected when iterating started, and the name of the
+    /// iterator's class
+    Items(std::vec::IntoIter<Value>, &'static str),
+    /// An iterator object, which this advances
+    Shared(Rc<RefCell<Iter>>),
+    Generator(Rc<Generator>),
+}
+
+impl Iter {
+    /// The name of the class of the iterator as an object.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Iter::List(..) => "list_iterator",
+            Iter::Tuple(..) => "tuple_iterator",
+            Iter::Str(string, _) if string.is_ascii() => "str_ascii_iterator",
+            Iter::Str(..) => "str_iterator",
+            Iter::Range(..) => "range_iterator",
+            Iter::Items(_, name) => name,
+            Iter::Shared(iter) => iter.borrow().type_name(),
+            Iter::Generator(generator) => generator.type_name(),
+        }
+    }
+
+    /// Call `f` with each value the iterator refers to.
+    pub fn visit(&self, f: &mut dyn FnMut(&Value)) {
+        match self {
+            Iter::List(items, _) => f(&Value::List(items.clone())),
+            Iter::Tuple(items, _) => f(&Value::Tuple(items.clone())),
+            Iter::Items(items, _) => items.as_slice().iter().for_each(f),
+            Iter::Shared(iter) => f(&Value::Iterator(iter.clone())),
+            Iter::Generator(generator) => f(&Value::Generator(generator.clone())),
+            Iter::Str(..) | Iter::Range(..) => {}
+        }
+    }
+}
+
+/// The items, or the exception a generator raised
+impl Iterator for Iter {
+    type Item = Eval<Value>;
+
+    fn next(&mut self) -> Option<Eval<Value>> {
+        let item = match self {
+            Iter::List(items, i) => {
+                let item = items.borrow().get(*i).cloned();
+                *i += 1;
+                item
+            }
+            Iter::Tuple(items, i) => {
+                let item = items.get(*i).cloned();
+                *i += 1;
+                item
+            }
+            Iter::Str(string, i) => {
+                let ch = string[*i..].chars().next()?;
+                let item = Value::str(&string[*i..*i + ch.len_utf8()]);
+                *i += ch.len_utf8();
+                Some(item)
+            }
+            Iter::Range(next, stop, step) => {
+                let more = match *step > 0 {
+                    true => *next < *stop,
+                    false => *next > *stop,
+                };
+                if !more {
+                    return None;
+                }
+                let item = *next;
+                *next = next.checked_add(*step).unwrap_or(*stop);
+                Some(Value::int(item))
+            }
+            Iter::Items(items, _) => items.next(),
+            Iter::Shared(iter) => return iter.borrow_mut().next(),
+            Iter::Generator(generator) => {
+                return match generator.resume(Resume::Send(Value::None)) {
+                    Ok(Step::Yield(item)) => Some(Ok(item)),
+                    Ok(Step::Return(_)) => None,
+                    Err(exception) => Some(Err(exception)),
+                }
+            }
+        };
+        item.map(Ok)
+    }
+}
+
+/// Iterate over a value, the way a `for` loop does.
+pub fn iter(value: &Value) -> Eval<Iter> {
+    Ok(match value {
+        Value::List(items) => Iter::List(items.clone(), 0),
+        Value::Tuple(items) => Iter::Tuple(items.clone(), 0),
+        Value::Str(string) => Iter::Str(string.clone(), 0),
+        Value::Range(start, stop, step) => Iter::Range(*start, *stop, *step),
+        Value::Dict(dict) => {
+            Iter::Items(dict.borrow().keys().into_iter(), "dict_keyiterator")
+        }
+        Value::Set(set) => Iter::Items(set.borrow().items().into_iter(), "set_iterator"),
+        Value::Iterator(iter) => Iter::Shared(iter.clone()),
+        Value::Generator(generator) if generator.kind == Kind::Generator => {
+            Iter::Generator(generator.clone())
+        }
+        _ => {
+            return raise("TypeError", format!(
+                "'{}' object is not iterable",
+                value.type_name(),
+            ))
+        }
+    })
+}
+
+/// `iter(value)`: an iterator object, which is the value itself if it is
+/// one.
+pub fn iter_object(value: &Value) -> Eval<Value> {
+    match value {
+        Value::Iterator(_) => Ok(value.clone()),
+        Value::Generator(generator) if generator.kind == Kind::Generator => {
+            Ok(value.clone())
+        }
+        _ => Ok(Value::iterator(iter(value)?)),
+    }
+}
+
+/// The next item of an iterator object, or `None` at its end.
+pub fn next_item(iterator: &Value) -> Eval<Option<Value>> {
+    match iterator {
+        Value::Iterator(iter) => {
+            let item = iter.borrow_mut().next();
+            item.transpose()
+        }
+        Value::Generator(generator) if generator.kind == Kind::Generator => {
+            match generator.resume(Resume::Send(Value::None))? {
+                Step::Yield(item) => Ok(Some(item)),
+                Step::Return(_) => Ok(None),
+            }
+        }
+        _ => raise("TypeError", format!(
+            "'{}' object is not an iterator",
+            iterator.type_name(),
+        )),
+    }
+}
+
+/// `next(iterator)`, raising `StopIteration` at the end, or `next(iterator,
+/// default)`.
+pub fn next(iterator: &Value, default: Option<Value>) -> Eval<Value> {
+    if let (Value::Generator(generator), None) = (iterator, &default) {
+        if generator.kind == Kind::Generator {
+            return generator.send(Value::None);
+        }
+    }
+    match next_item(iterator)?.or(default) {
+        Some(item) => Ok(item),
+        None => raise("StopIteration", ""),
+    }
+}
+
+/// The items of an iterable, collected in a vector.
+pub fn items(value: &Value) -> Eval<Vec<Value>> {
+    match value {
+        Value::List(items) => Ok(items.borrow().clone()),
+        Value::Tuple(items) => Ok(items.to_vec()),
+        _ => iter(value)?.collect(),
+    }
+}
+
+/// `op value` for `-`, `+` and `~` (`not` is [`Value::truthy`]).
+pub fn unary(op: UnaryOp, value: &Value) -> Eval<Value> {
+    match (op, number(value)) {
+        (UnaryOp::Not, _) => Ok(Value::Bool(!value.truthy())),
+        (UnaryOp::UAdd, Some(Number::Int(value))) => Ok(Value::Int(value)),
+        (UnaryOp::USub, Some(Number::Int(value))) => Ok(Value::Int(value.neg())),
+        (UnaryOp::Invert, Some(Number::Int(value))) => {
+            Ok(Value::Int(value.invert()))
+        }
+        (UnaryOp::UAdd, Some(Number::Float(value))) => Ok(Value::Float(value)),
+        (UnaryOp::USub, Some(Number::Float(value))) => Ok(Value::Float(-value)),
+        _ => {
+            let symbol = match op {
+                UnaryOp::Invert => "~",
+                UnaryOp::UAdd => "+",
+                _ => "-",
+            };
+            raise("TypeError", format!(
+                "bad operand type for unary {}: '{}'",
+                symbol,
+                value.type_name(),
+            ))
+        }
+    }
+}
+
+/// The symbol of a binary operator.
+pub fn binary_symbol(op: BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mult => "*",
+        BinaryOp::MatMult => "@",
+        BinaryOp::Div => "/",
+        BinaryOp::Mod => "%",
+        BinaryOp::Pow => "**",
+        BinaryOp::LShift => "<<",
+        BinaryOp::RShift => ">>",
+        BinaryOp::BitOr => "|",
+        BinaryOp::BitXor => "^",
+        BinaryOp::BitAnd => "&",
+        BinaryOp::FloorDiv => "//",
+    }
+}
+
+fn unsupported<T>(op: BinaryOp, a: &Value, b: &Value) -> Eval<T> {
+    raise("TypeError", format!(
+        "unsupported operand type(s) for {}: '{}' and '{}'",
+        binary_symbol(op),
+        a.type_name(),
+        b.type_name(),
+    ))
+}
+
+/// `a op b` on boxed values.
+pub fn binary(op: BinaryOp, a: &Value, b: &Value) -> Eval<Value> {
+    use Value::*;
+    match (a, b) {
+        (Bool(a), Bool(b)) if matches!(op,
+            BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor) =>
+        {
+            return Ok(Bool(match op {
+                BinaryOp::BitAnd => a & b,
+                BinaryOp::BitOr => a | b,
+                _ => a ^ b,
+            }));
+        }
+        (Str(a), Str(b)) if op == BinaryOp::Add => {
+            return Ok(Value::str(&format!("{}{}", a, b)))
+        }
+        (Str(_), _) if op == BinaryOp::Add => {
+            return raise("TypeError", format!(
+                "can only concatenate str (not \"{}\") to str",
+                b.type_name(),
+            ))
+        }
+        (Str(template), _) if op == BinaryOp::Mod => {
+            return Ok(Value::str(&percent_format(template, b)?))
+        }
+        (List(a), List(b)) if op == BinaryOp::Add => {
+            let mut items = a.borrow().clone();
+            items.extend(b.borrow().iter().cloned());
+            return Ok(Value::list(items));
+        }
+        (Tuple(a), Tuple(b)) if op == BinaryOp::Add => {
+            return Ok(Value::tuple(a.iter().chain(b.iter()).cloned().collect()))
+        }
+        (Str(_), _) | (List(_), _) | (Tuple(_), _) if op == BinaryOp::Mult => {
+            return repeat(a, b)
+        }
+        (_, Str(_)) | (_, List(_)) | (_, Tuple(_)) if op == BinaryOp::Mult => {
+            return repeat(b, a)
+        }
+        (Set(x), Set(y)) => {
+            let set = match op {
+                BinaryOp::BitOr => set_union(&x.borrow(), std::slice::from_ref(b))?,
+                BinaryOp::BitAnd => set_intersection(&x.borrow(), std::slice::from_ref(b))?,
+                BinaryOp::Sub => set_difference(&x.borrow(), std::slice::from_ref(b))?,
+                BinaryOp::BitXor => set_symmetric_difference(&x.borrow(), &y.borrow())?,
+                _ => return unsupported(op, a, b),
+            };
+            return Ok(Value::set(set));
+        }
+        _ => {}
+    }
+    match (number(a), number(b)) {
+        (Some(Number::Int(x)), Some(Number::Int(y))) => match op {
+            BinaryOp::Div => float_div(x.to_f64()?, y.to_f64()?).map(Float),
+            BinaryOp::Pow if y.is_negative() => {
+                float_pow(x.to_f64()?, y.to_f64()?).map(Float)
+            }
+            BinaryOp::MatMult => unsupported(op, a, b),
+            _ => int_binary(op, &x, &y).map(Int),
+        },
+        (Some(x), Some(y)) => float_binary(op, x.float()?, y.float()?)
+            .map(Float)
+            .or_else(|error| match error.kind.as_str() {
+                "TypeError" => unsupported(op, a, b),
+                _ => Err(error),
+            }),
+        _ => unsupported(op, a, b),
+    }
+}
+
+/// `pow(base, exponent)` or `pow(base, exponent, modulus)`
+pub fn pow(base: &Value, exponent: &Value, modulus: Option<&Value>)
+    -> Eval<Value>
+{
+    let modulus = match modulus {
+        None | Some(Value::None) => {
+            return binary(BinaryOp::Pow, base, exponent)
+        }
+        Some(modulus) => modulus,
+    };
+    match (number(base), number(exponent), number(modulus)) {
+        (
+            Some(Number::Int(base)),
+            Some(Number::Int(exponent)),
+            Some(Number::Int(modulus)),
+        ) => base.pow_mod(&exponent, &modulus).map(Value::Int),
+        _ => raise("TypeError", "pow() 3rd argument not allowed unless all \
+            arguments are integers"),
+    }
+}
+
+// `sequence * count`
+fn repeat(sequence: &Value, count: &Value) -> Eval<Value> {
+    let count = match number(count) {
+        Some(Number::Int(count)) => match count.to_i64() {
+            Some(count) => count.max(0) as usize,
+            None if count.is_negative() => 0,
+            None => return raise("OverflowError", "cannot fit 'int' into an \
+                index-sized integer"),
+        },
+        _ => {
+            return raise("TypeError", format!(
+                "can't multiply sequence by non-int of type '{}'",
+                count.type_name(),
+            ))
+        }
+    };
+    let repeat = |items: &[Value]| -> Vec<Value> {
+        (0..count).flat_map(|_| items.iter().cloned()).collect()
+    };
+    Ok(match sequence {
+        Value::Str(string) => Value::str(&string.repeat(count)),
+        Value::List(items) => Value::list(repeat(&items.borrow())),
+        Value::Tuple(items) => Value::tuple(repeat(items)),
+        _ => unreachable!(),
+    })
+}
+
+/// `a op b` on `int`s, except `/`, `@` and `**` with a negative exponent,
+/// which don't have `int` results.
+pub fn int_binary(op: BinaryOp, a: &Int, b: &Int) -> Eval<Int> {
+    match op {
+        BinaryOp::Add => Ok(a + b),
+        BinaryOp::Sub => Ok(a - b),
+        BinaryOp::Mult => Ok(a * b),
+        BinaryOp::FloorDiv => a.floor_div(b),
+        BinaryOp::Mod => a.modulo(b),
+        BinaryOp::Pow => a.pow(b),
+        BinaryOp::LShift => a.shift_left(b),
+        BinaryOp::RShift => a.shift_right(b),
+        BinaryOp::BitOr => Ok(a | b),
+        BinaryOp::BitXor => Ok(a ^ b),
+        BinaryOp::BitAnd => Ok(a & b),
+        BinaryOp::Div | BinaryOp::MatMult => {
+            unreachable!("`{}` doesn't make an `int`", binary_symbol(op))
+        }
+    }
+}
+
+/// `a op b` on `float`s.  Raises `TypeError` for operators that don't take
+/// `float`s.
+pub fn float_binary(op: BinaryOp, a: f64, b: f64) -> Eval<f64> {
+    match op {
+        BinaryOp::Add => Ok(a + b),
+        BinaryOp::Sub => Ok(a - b),
+        BinaryOp::Mult => Ok(a * b),
+        BinaryOp::Div => float_div(a, b),
+        BinaryOp::FloorDiv => float_floor_div(a, b),
+        BinaryOp::Mod => float_mod(a, b),
+        BinaryOp::Pow => float_pow(a, b),
+        _ => raise("TypeError", "unsupported operand type(s)"),
+    }
+}
+
+/// `a / b` on `float`s.
+pub fn float_div(a: f64, b: f64) -> Eval<f64> {
+    match b == 0.0 {
+        true => raise("ZeroDivisionError", "float division by zero"),
+        false => Ok(a / b),
+    }
+}
+
+/// `a // b` on `float`s.
+pub fn float_floor_div(a: f64, b: f64) -> Eval<f64> {
+    match b == 0.0 {
+        true => raise("ZeroDivisionError", "float floor division by zero"),
+        false => Ok(((a - float_mod(a, b)?) / b).round()),
+    }
+}
+
+/// `a % b` on `float`s.
+pub fn float_mod(a: f64, b: f64) -> Eval<f64> {
+    if b == 0.0 {
+        return raise("ZeroDivisionError", "float modulo");
+    }
+    let remainder = a % b;
+    Ok(match remainder != 0.0 && ((remainder < 0.0) != (b < 0.0)) {
+        true => remainder + b,
+        false if remainder == 0.0 => 0.0_f64.copysign(b),
+        false => remainder,
+    })
+}
+
+/// `a ** b` on `float`s.
+pub fn float_pow(a: f64, b: f64) -> Eval<f64> {
+    if a == 0.0 && b < 0.0 {
+        return raise(
+            "ZeroDivisionError",
+            "0.0 cannot be raised to a negative power",
+        );
+    }
+    if a < 0.0 && b.fract() != 0.0 {
+        return raise("ValueError", "complex results are not supported");
+    }
+    let value = a.powf(b);
+    match value.is_infinite() && a.is_finite() && b.is_finite() {
+        true => raise("OverflowError", "(34, 'Numerical result out of range')"),
+        false => Ok(value),
+    }
+}
+
+// An index into a sequence of `len` items, counting from the end if it is
+// negative.
+fn index(value: &Value, len: usize, what: &str) -> Eval<usize> {
+    let i = match number(value) {
+        Some(Number::Int(i)) => match i.to_i64() {
+            Some(i) => i,
+            None => return index_overflow(),
+        },
+        _ => {
+            return raise("TypeError", format!(
+                "{} indices must be integers or slices, not {}",
+                what,
+                value.type_name(),
+            ))
+        }
+    };
+    let i = if i < 0 { i + len as i64 } else { i };
+    match (0..len as i64).contains(&i) {
+        true => Ok(i as usize),
+        false => raise("IndexError", format!("{} index out of range", what)),
+    }
+}
+
+/// `value[key]`
+pub fn get_item(value: &Value, key: &Value) -> Eval<Value> {
+    match value {
+        Value::List(items) => {
+            let items = items.borrow();
+            Ok(items[index(key, items.len(), "list")?].clone())
+        }
+        Value::Tuple(items) => Ok(items[index(key, items.len(), "tuple")?].clone()),
+        Value::Str(string) => {
+            let i = index(key, string.chars().count(), "string")?;
+            let ch = string.chars().nth(i).unwrap();
+            Ok(Value::str(ch.encode_utf8(&mut [0; 4])))
+        }
+        Value::Range(start, stop, step) => {
+            let i = index(key, range_len(*start, *stop, *step) as usize, "range")?;
+            Ok(Value::int(start + i as i64 * step))
+        }
+        Value::Dict(dict) => match dict.borrow().get(key)? {
+            Some(value) => Ok(value),
+            None => raise("KeyError", key.repr()),
+        },
+        _ => raise("TypeError", format!(
+            "'{}' object is not subscriptable",
+            value.type_name(),
+        )),
+    }
+}
+
+/// `value[key] = item`
+pub fn set_item(value: &Value, key: &Value, item: Value) -> Eval<()> {
+    match value {
+        Value::List(items) => {
+            let mut items = items.borrow_mut();
+            let i = index(key, items.len(), "list assignment")?;
+            items[i] = item;
+            Ok(())
+        }
+        Value::Dict(dict) => dict.borrow_mut().insert(key.clone(), item),
+        _ => raise("TypeError", format!(
+            "'{}' object does not support item assignment",
+            value.type_name(),
+        )),
+    }
+}
+
+/// `del value[key]`
+pub fn del_item(value: &Value, key: &Value) -> Eval<()> {
+    match value {
+        Value::List(items) => {
+            let mut items = items.borrow_mut();
+            let i = index(key, items.len(), "list assignment")?;
+            items.remove(i);
+            Ok(())
+        }
+        Value::Dict(dict) => match dict.borrow_mut().remove(key)? {
+            Some(_) => Ok(()),
+            None => raise("KeyError", key.repr()),
+        },
+        _ => raise("TypeError", format!(
+            "'{}' object doesn't support item deletion",
+            value.type_name(),
+        )),
+    }
+}
+
+// The indices `lower:upper:step` selects from `len` items.
+fn slice_indices(
+    len: usize,
+    lower: Option<&Value>,
+    upper: Option<&Value>,
+    step: Option<&Value>,
+) -> Eval<Vec<usize>> {
+    let int = |value: Option<&Value>| match value.map(number) {
+        None | Some(None) if matches!(value, None | Some(Value::None)) => {
+            Ok(None)
+        }
+        // Indices past the end are clamped anyway.
+        Some(Some(Number::Int(value))) => Ok(Some(value.to_i64().unwrap_or(
+            match value.is_negative() {
+                true => i64::MIN / 2,
+                false => i64::MAX / 2,
+            },
+        ))),
+        _ => raise(
+            "TypeError",
+            "slice indices must be integers or None or have an __index__ \
+                method",
+        ),
+    };
+    let len = len as i64;
+    let step = int(step)?.unwrap_or(1);
+    if step == 0 {
+        return raise("ValueError", "slice step cannot be zero");
+    }
+    let clamp = |value: Option<i64>, default: i64| match value {
+        None => default,
+        Some(value) if value < 0 => match step > 0 {
+            true => (value + len).max(0),
+            false => (value + len).max(-1),
+        },
+        Some(value) => match step > 0 {
+            true => value.min(len),
+            false => value.min(len - 1),
+        },
+    };
+    let (lower, upper) = match step > 0 {
+        true => (clamp(int(lower)?, 0), clamp(int(upper)?, len)),
+        false => (clamp(int(lower)?, len - 1), clamp(int(upper)?, -1)),
+    };
+    let count = range_len(lower, upper, step);
+    Ok((0..count).map(|i| (lower + i * step) as usize).collect())
+}
+
+/// `value[lower:upper:step]`
+pub fn get_slice(
+    value: &Value,
+    lower: Option<&Value>,
+    upper: Option<&Value>,
+    step: Option<&Value>,
+) -> Eval<Value> {
+    match value {
+        Value::List(items) => {
+            let items = items.borrow();
+            let indices = slice_indices(items.len(), lower, upper, step)?;
+            Ok(Value::list(indices.iter().map(|&i| items[i].clone()).collect()))
+        }
+        Value::Tuple(items) => {
+            let indices = slice_indices(items.len(), lower, upper, step)?;
+            Ok(Value::tuple(indices.iter().map(|&i| items[i].clone()).collect()))
+        }
+        Value::Str(string) => {
+            let chars: Vec<char> = string.chars().collect();
+            let indices = slice_indices(chars.len(), lower, upper, step)?;
+            Ok(Value::str(&indices.iter().map(|&i| chars[i]).collect::<String>()))
+        }
+        _ => raise("TypeError", format!(
+            "'{}' object is not subscriptable",
+            value.type_name(),
+        )),
+    }
+}
+
+/// `int(value)`
+pub fn to_int(value: &Value) -> Eval<Int> {
+    match value {
+        Value::Bool(value) => Ok(Int::Small(*value as i64)),
+        Value::Int(value) => Ok(value.clone()),
+        Value::Float(value) if value.is_nan() => {
+            raise("ValueError", "cannot convert float NaN to integer")
+        }
+        Value::Float(value) if value.is_infinite() => {
+            raise("OverflowError", "cannot convert float infinity to integer")
+        }
+        Value::Float(value) => Ok(Int::from_f64(*value)),
+        Value::Str(string) => parse_int(string, 10),
+        _ => raise("TypeError", format!(
+            "int() argument must be a string, a bytes-like object or a real \
+                number, not '{}'",
+            value.type_name(),
+        )),
+    }
+}
+
+/// `int(string, base)`, with a base of 0 (which takes it from the prefix)
+/// or from 2 to 36.
+pub fn parse_int(string: &str, base: i64) -> Eval<Int> {
+    if base != 0 && !(2..=36).contains(&base) {
+        return raise("ValueError", "int() base must be >= 2 and <= 36, or 0");
+    }
+    let invalid = || raise("ValueError", format!(
+        "invalid literal for int() with base {}: {}",
+        base,
+        repr_str(string),
+    ));
+    let text = string.trim();
+    let (sign, digits) = match text.strip_prefix(['+', '-']) {
+        Some(digits) => (&text[..1], digits),
+        None => ("", text),
+    };
+    let prefixed = |prefix: &str| {
+        digits.len() > 2 && digits[..2].eq_ignore_ascii_case(prefix)
+    };
+    let (radix, digits) = match base {
+        // An underscore may follow the prefix.
+        0 | 16 if prefixed("0x") => (16, strip_underscore(&digits[2..])),
+        0 | 8 if prefixed("0o") => (8, strip_underscore(&digits[2..])),
+        0 | 2 if prefixed("0b") => (2, strip_underscore(&digits[2..])),
+        // Decimal literals can't have leading zeros.
+        0 if digits.trim_start_matches(['0', '_']).is_empty() => (10, digits),
+        0 if digits.starts_with('0') => return invalid(),
+        0 => (10, digits),
+        _ => (base as u32, digits),
+    };
+    match Int::parse(&format!("{}{}", sign, digits), radix) {
+        Some(value) => Ok(value),
+        None => invalid(),
+    }
+}
+
+// Digits after a base prefix, which may start with an underscore.
+fn strip_underscore(digits: &str) -> &str {
+    digits.strip_prefix('_').unwrap_or(digits)
+}
+
+/// `float(value)`
+pub fn to_float(value: &Value) -> Eval<f64> {
+    match value {
+        Value::Bool(value) => Ok(*value as i64 as f64),
+        Value::Int(value) => value.to_f64(),
+        Value::Float(value) => Ok(*value),
+        Value::Str(string) => {
+            let text = string.trim().to_lowercase();
+            let parsed = match text.trim_start_matches(['+', '-']) {
+                "inf" | "infinity" | "nan" => text.replace("inity", "").parse(),
+                _ if text.contains("in") || text.contains("na") => {
+                    "x".parse::<f64>()
+                }
+                _ => text.replace('_', "").parse(),
+            };
+            parsed.or_else(|_| raise("ValueError", format!(
+                "could not convert string to float: {}",
+                repr_str(string),
+            )))
+        }
+        _ => raise("TypeError", format!(
+            "float() argument must be a string or a real number, not '{}'",
+            value.type_name(),
+        )),
+    }
+}
+
+/// `abs(value)`
+pub fn abs(value: &Value) -> Eval<Value> {
+    match number(value) {
+        Some(Number::Int(value)) => Ok(Value::Int(value.abs())),
+        Some(Number::Float(value)) => Ok(Value::Float(value.abs())),
+        None => raise("TypeError", format!(
+            "bad operand type for abs(): '{}'",
+            value.type_name(),
+        )),
+    }
+}
+
+/// `round(value)` or `round(value, digits)`
+pub fn round(value: &Value, digits: Option<&Value>) -> Eval<Value> {
+    let digits = match digits {
+        None | Some(Value::None) => None,
+        Some(digits) => match to_int(digits)?.to_i64() {
+            Some(digits) => Some(digits),
+            None => return index_overflow(),
+        },
+    };
+    match (number(value), digits) {
+        (Some(Number::Int(value)), None) => Ok(Value::Int(value)),
+        (Some(Number::Int(value)), Some(digits)) if digits >= 0 => {
+            Ok(Value::Int(value))
+        }
+        (Some(Number::Int(value)), Some(digits)) => {
+            // Round half to even at a power of ten.
+            let scale = Int::Small(10).pow(&Int::Small(-digits))?;
+            let (quotient, rest) = value.div_mod(&scale)?;
+            let low = &quotient * &scale;
+            let twice = &rest + &rest;
+            let up = match twice.cmp(&scale) {
+                Ordering::Greater => true,
+                Ordering::Equal => !quotient.modulo(&Int::Small(2))?.is_zero(),
+                Ordering::Less => false,
+            };
+            Ok(Value::Int(if up { &low + &scale } else { low }))
+        }
+        (Some(Number::Float(value)), None) => {
+            if !value.is_finite() {
+                return to_int(&Value::Float(value)).map(Value::Int);
+            }
+            let rounded = round_half_even(value);
+            to_int(&Value::Float(rounded)).map(Value::Int)
+        }
+        (Some(Number::Float(value)), Some(digits)) => {
+            if !value.is_finite() || digits > 300 {
+                return Ok(Value::Float(value));
+            }
+            // Round the decimal expansion, like CPython.
+            let text = match digits >= 0 {
+                true => format!("{:.*}", digits as usize, value),
+                false if digits < -308 => return Ok(Value::Float(0.0 * value)),
+                false => {
+                    let scale = 10_f64.powi(-digits as i32);
+                    format!("{}", round_half_even(value / scale) * scale)
+                }
+            };
+            Ok(Value::Float(text.parse().unwrap()))
+        }
+        (None, _) => raise("TypeError", format!(
+            "type {} doesn't define __round__ method",
+            value.type_name(),
+        )),
+    }
+}
+
+fn round_half_even(value: f64) -> f64 {
+    let rounded = value.round();
+    match (value - value.trunc()).abs() == 0.5 && rounded % 2.0 != 0.0 {
+        true => rounded - value.signum(),
+        false => rounded,
+    }
+}
+
+/// `ord(value)`
+pub fn ord(value: &Value) -> Eval<i64> {
+    match value {
+        Value::Str(string) => {
+            let mut chars = string.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => Ok(ch as i64),
+                _ => raise("TypeError", format!(
+                    "ord() expected a character, but string of length {} \
+                        found",
+                    string.chars().count(),
+                )),
+            }
+        }
+        _ => raise("TypeError", format!(
+            "ord() expected string of length 1, but {} found",
+            value.type_name(),
+        )),
+    }
+}
+
+/// `chr(value)`
+pub fn chr(value: i64) -> Eval<Value> {
+    match u32::try_from(value).ok().and_then(char::from_u32) {
+        Some(ch) => Ok(Value::str(ch.encode_utf8(&mut [0; 4]))),
+        None => raise("ValueError", "chr() arg not in range(0x110000)"),
+    }
+}
+
+/// `sum(items, start)`
+pub fn sum(items: &Value, start: Value) -> Eval<Value> {
+    if let Value::Str(_) = start {
+        return raise(
+            "TypeError",
+            "sum() can't sum strings [use ''.join(seq) instead]",
+        );
+    }
+    iter(items)?.try_fold(start, |total, item| {
+        binary(BinaryOp::Add, &total, &item?)
+    })
+}
+
+/// `min(...)` or `max(...)` of some items, which are compared with `<` or
+/// `>` to the best item so far.
+pub fn min_max(name: &str, items: Vec<Value>) -> Eval<Value> {
+    let op = if name == "min" { CmpOp::Lt } else { CmpOp::Gt };
+    let mut items = items.into_iter();
+    let mut best = match items.next() {
+        Some(item) => item,
+        None => {
+            return raise("ValueError", format!(
+                "{}() arg is an empty sequence",
+                name,
+            ))
+        }
+    };
+    for item in items {
+        if ordered(op, order(op, &item, &best)?) {
+            best = item;
+        }
+    }
+    Ok(best)
+}
+
+/// Sort values in place with `<`, stably.
+pub fn sort(items: &mut [Value], keys: Option<Vec<Value>>, reverse: bool)
+    -> Eval<()>
+{
+    let keys = keys.unwrap_or_else(|| items.to_vec());
+    let mut order: Vec<usize> = (0..items.len()).collect();
+    let mut error = None;
+    // A merge sort, as `sort_by` doesn't stop at the first error.
+    order.sort_by(|&a, &b| {
+        let (a, b) = match reverse {
+            true => (&keys[b], &keys[a]),
+            false => (&keys[a], &keys[b]),
+        };
+        if error.is_some() {
+            return Ordering::Equal;
+        }
+        match self::order(CmpOp::Lt, b, a) {
+            Ok(ordering) if ordered(CmpOp::Lt, ordering) => Ordering::Greater,
+            Ok(_) => match self::order(CmpOp::Lt, a, b) {
+                Ok(ordering) if ordered(CmpOp::Lt, ordering) => Ordering::Less,
+                Ok(_) => Ordering::Equal,
+                Err(e) => {
+                    error = Some(e);
+                    Ordering::Equal
+                }
+            },
+            Err(e) => {
+                error = Some(e);
+                Ordering::Equal
+            }
+        }
+    });
+    if let Some(error) = error {
+        return Err(error);
+    }
+    let sorted: Vec<Value> = order.iter().map(|&i| items[i].clone()).collect();
+    items.clone_from_slice(&sorted);
+    Ok(())
+}
+
+/// `isinstance(value, class)` for a built-in class named `class`.
+pub fn isinstance(value: &Value, class: &str) -> bool {
+    match (value, class) {
+        (Value::Bool(_), "int") => true,
+        (_, "object") => true,
+        (Value::Exception(exception), _) => exception.is_instance(class),
+        (Value::Object(_), _) => false,
+        _ => value.type_name() == class,
+    }
+}
+
+// The built-in functions that can be used as values
+const BUILTINS: &[&str] = &[
+    "abs", "bin", "bool", "chr", "float", "hash", "hex", "int", "iter", "len",
+    "list", "max", "min", "next", "oct", "ord", "pow", "repr", "reversed",
+    "set", "sorted", "str", "sum", "tuple",
+];
+
+/// A built-in function or exception class as a value, if it can be used as
+/// one.
+pub fn builtin(name: &str) -> Option<Value> {
+    if let Some(class) = exception_class(name) {
+        return Some(Value::Class(class));
+    }
+    BUILTINS.iter().find(|builtin| **builtin == name).map(|name| Value::Builtin(name))
+}
+
+/// Call a built-in exception class, making an exception.
+pub fn call_class(class: &str, args: Vec<Value>) -> Value {
+    new_exception(class, &args)
+}
+
+/// Call a built-in function used as a value with positional arguments.
+pub fn call_builtin(name: &str, args: Vec<Value>) -> Eval<Value> {
+    let (min, max) = match name {
+        "bool" | "float" | "list" | "set" | "str" | "tuple" => (0, 1),
+        "int" => (0, 2),
+        "sum" | "next" => (1, 2),
+        "pow" => (2, 3),
+        "max" | "min" => (1, usize::MAX),
+        _ => (1, 1),
+    };
+    arity(name, &args, min, max)?;
+    let arg = args.first();
+    Ok(match (name, arg) {
+        ("bool", None) => Value::Bool(false),
+        ("float", None) => Value::Float(0.0),
+        ("int", None) => Value::int(0),
+        ("list", None) => Value::list(Vec::new()),
+        ("set", None) => Value::set(Set::default()),
+        ("str", None) => Value::str(""),
+        ("tuple", None) => Value::tuple(Vec::new()),
+        (_, None) => unreachable!("the other built-ins take an argument"),
+        ("abs", Some(arg)) => abs(arg)?,
+        ("bin", Some(arg)) | ("hex", Some(arg)) | ("oct", Some(arg)) => {
+            let spec = match name {
+                "bin" => "#b",
+                "hex" => "#x",
+                _ => "#o",
+            };
+            match arg {
+                Value::Int(_) | Value::Bool(_) => Value::str(&format(arg, spec)?),
+                _ => return raise("TypeError", format!(
+                    "'{}' object cannot be interpreted as an integer",
+                    arg.type_name(),
+                )),
+            }
+        }
+        ("bool", Some(arg)) => Value::Bool(arg.truthy()),
+        ("chr", Some(arg)) => chr(index_arg(arg)?)?,
+        ("float", Some(arg)) => Value::Float(to_float(arg)?),
+        ("hash", Some(arg)) => Value::int(hash(arg)?),
+        ("int", Some(arg)) => match (arg, args.get(1)) {
+            (_, None) => Value::Int(to_int(arg)?),
+            (Value::Str(string), Some(base)) => {
+                Value::Int(parse_int(string, index_arg(base)?)?)
+            }
+            _ => return raise("TypeError", "int() can't convert non-string with \
+                explicit base"),
+        },
+        ("iter", Some(arg)) => iter_object(arg)?,
+        ("len", Some(arg)) => Value::int(len(arg)?),
+        ("list", Some(arg)) => Value::list(items(arg)?),
+        ("next", Some(arg)) => next(arg, args.get(1).cloned())?,
+        ("max", Some(_)) | ("min", Some(_)) => match args.len() {
+            1 => min_max(name, items(&args[0])?)?,
+            _ => min_max(name, args)?,
+        },
+        ("ord", Some(arg)) => Value::int(ord(arg)?),
+        ("pow", Some(arg)) => pow(arg, &args[1], args.get(2))?,
+        ("repr", Some(arg)) => Value::str(&arg.repr()),
+        ("reversed", Some(arg)) => {
+            let mut items = items(arg)?;
+            items.reverse();
+            Value::list(items)
+        }
+        ("set", Some(arg)) => Value::set(to_set(arg)?),
+        ("sorted", Some(arg)) => {
+            let mut items = items(arg)?;
+            sort(&mut items, None, false)?;
+            Value::list(items)
+        }
+        ("str", Some(arg)) => Value::str(&arg.to_string()),
+        ("sum", Some(arg)) => {
+            let start = args.get(1).cloned().unwrap_or(Value::int(0));
+            sum(arg, start)?
+        }
+        ("tuple", Some(arg)) => Value::tuple(items(arg)?),
+        _ => unreachable!("`{}` is in the list of built-in values", name),
+    })
+}
+
+fn arity(name: &str, args: &[Value], min: usize, max: usize) -> Eval<()> {
+    let count = args.len();
+    if count >= min && count <= max {
+        return Ok(());
+    }
+    let message = match (min == max, count < min) {
+        (true, _) if min == 0 => {
+            format!("{}() takes no arguments ({} given)", name, count)
+        }
+        (true, _) if min == 1 => {
+            format!("{}() takes exactly one argument ({} given)", name, count)
+        }
+        (true, _) => {
+            format!("{} expected {} arguments, got {}", name, min, count)
+        }
+        (false, true) => {
+            format!("{} expected at least {} argument{}, got {}", name, min,
+                if min == 1 { "" } else { "s" }, count)
+        }
+        (false, false) => {
+            format!("{} expected at most {} argument{}, got {}", name, max,
+                if max == 1 { "" } else { "s" }, count)
+        }
+    };
+    raise("TypeError", message)
+}
+
+/// An `int` argument used as a count or index, like those of `range` and
+/// `chr`.
+pub fn index_arg(value: &Value) -> Eval<i64> {
+    match value {
+        Value::Int(value) => match value.to_i64() {
+            Some(value) => Ok(value),
+            None => raise(
+                "OverflowError",
+                "Python int too large to convert to C ssize_t",
+            ),
+        },
+        Value::Bool(value) => Ok(*value as i64),
+        _ => raise("TypeError", format!(
+            "'{}' object cannot be interpreted as an integer",
+            value.type_name(),
+        )),
+    }
+}
+
+/// `set(items)`
+pub fn to_set(value: &Value) -> Eval<Set> {
+    let mut set = Set::default();
+    for item in iter(value)? {
+        set.insert(item?)?;
+    }
+    Ok(set)
+}
+
+fn arg_str<'v>(name: &str, value: &'v Value) -> Eval<&'v str> {
+    match value {
+        Value::Str(string) => Ok(string),
+        _ => raise("TypeError", format!(
+            "{}() argument must be str, not {}",
+            name,
+            value.type_name(),
+        )),
+    }
+}
+
+// A parsed format spec, `[[fill]align][sign][#][0][width][,][.precision]
+// [type]`.
+struct Spec {
+    fill: char,
+    align: Option<char>,
+    sign: char,
+    alternate: bool,
+    width: usize,
+    grouping: Option<char>,
+    precision: Option<usize>,
+    kind: Option<char>,
+}
+
+fn parse_spec(spec: &str) -> Eval<Spec> {
+    let invalid = || raise("ValueError", "Invalid format specifier");
+    let chars: Vec<char> = spec.chars().collect();
+    let mut i = 0;
+    let mut parsed = Spec {
+        fill: ' ',
+        align: None,
+        sign: '-',
+        alternate: false,
+        width: 0,
+        grouping: None,
+        precision: None,
+        kind: None,
+    };
+    let is_align = |ch: &char| matches!(ch, '<' | '>' | '^' | '=');
+    if chars.len() >= 2 && is_align(&chars[1]) {
+        parsed.fill = chars[0];
+        parsed.align = Some(chars[1]);
+        i = 2;
+    } else if chars.first().is_some_and(is_align) {
+        parsed.align = Some(chars[0]);
+        i = 1;
+    }
+    if let Some(&sign @ ('+' | '-' | ' ')) = chars.get(i) {
+        parsed.sign = sign;
+        i += 1;
+    }
+    if chars.get(i) == Some(&'#') {
+        parsed.alternate = true;
+        i += 1;
+    }
+    if chars.get(i) == Some(&'0') {
+        if parsed.align.is_none() {
+            parsed.fill = '0';
+            parsed.align = Some('=');
+        }
+        i += 1;
+    }
+    let digits = |i: &mut usize| {
+        let start = *i;
+        while chars.get(*i).is_some_and(char::is_ascii_digit) {
+            *i += 1;
+        }
+        chars[start..*i].iter().collect::<String>().parse::<usize>().ok()
+    };
+    parsed.width = digits(&mut i).unwrap_or(0);
+    if let Some(&grouping @ (',' | '_')) = chars.get(i) {
+        parsed.grouping = Some(grouping);
+        i += 1;
+    }
+    if chars.get(i) == Some(&'.') {
+        i += 1;
+        parsed.precision = match digits(&mut i) {
+            Some(precision) => Some(precision),
+            None => return raise("ValueError", "Format specifier missing precision"),
+        };
+    }
+    parsed.kind = chars.get(i).copied();
+    if chars.len() > i + 1 {
+        return invalid();
+    }
+    Ok(parsed)
+}
+
+/// `format(value, spec)`
+pub fn format(value: &Value, spec: &str) -> Eval<String> {
+    if spec.is_empty() {
+        return Ok(value.to_string());
+    }
+    let spec = parse_spec(spec)?;
+    let unknown = |kind: char| {
+        raise("ValueError", format!(
+            "Unknown format code '{}' for object of type '{}'",
+            kind,
+            value.type_name(),
+        ))
+    };
+    match (value, spec.kind) {
+        (Value::Str(string), None | Some('s')) => {
+            let string: String = match spec.precision {
+                Some(precision) => string.chars().take(precision).collect(),
+                None => string.to_string(),
+            };
+            if spec.align == Some('=') {
+                return raise(
+                    "ValueError",
+                    "'=' alignment not allowed in string format specifier",
+                );
+            }
+            Ok(pad(&string, spec.fill, spec.align.unwrap_or('<'), spec.width))
+        }
+        (Value::Int(_) | Value::Bool(_), None | Some('d' | 'n' | 'x' | 'X' | 'o' | 'b' | 'c')) => {
+            let value = to_int(value)?;
+            let (digits, prefix, every) = match spec.kind {
+                Some('x') => (value.to_radix(16), "0x", 4),
+                Some('X') => (value.to_radix(16).to_uppercase(), "0X", 4),
+                Some('o') => (value.to_radix(8), "0o", 4),
+                Some('b') => (value.to_radix(2), "0b", 4),
+                Some('c') => {
+                    let ch = chr(index_arg(&Value::Int(value))?)?.to_string();
+                    return Ok(pad(&ch, spec.fill, spec.align.unwrap_or('<'), spec.width));
+                }
+                _ => (value.to_radix(10), "", 3),
+            };
+            let digits = group(&digits, spec.grouping, every);
+            let prefix = if spec.alternate { prefix } else { "" };
+            let negative = value.is_negative();
+            Ok(number_field(&spec, negative, &format!("{}{}", prefix, digits)))
+        }
+        (Value::Int(_) | Value::Bool(_) | Value::Float(_), None | Some('f' | 'F' | 'e' | 'E' | 'g' | 'G' | '%' | 'n')) => {
+            let float = to_float(value)?;
+            let is_int = !matches!(value, Value::Float(_));
+            let body = float_body(float.abs(), &spec, is_int);
+            Ok(number_field(&spec, float.is_sign_negative() && !float.is_nan(), &body))
+        }
+        (_, Some(kind)) => unknown(kind),
+        (_, None) => {
+            let string = value.to_string();
+            Ok(pad(&string, spec.fill, spec.align.unwrap_or('<'), spec.width))
+        }
+    }
+}
+
+/// `template % args`, printf-style formatting.  `args` is a tuple of
+/// positional values, a dict for `%(key)s` conversions, or a single value.
+pub fn percent_format(template: &str, args: &Value) -> Eval<String> {
+    let positional = match args {
+        Value::Tuple(items) => items.to_vec(),
+        _ => vec![args.clone()],
+    };
+    let mapping = matches!(args, Value::Dict(_));
+    let chars: Vec<char> = template.chars().collect();
+    let mut out = String::new();
+    let mut next = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '%' {
+            out.push(chars[i]);
+            i += 1;
+            continue;
+        }
+        i += 1;
+        let mut value = None;
+        if chars.get(i) == Some(&'(') {
+            if !mapping {
+                return raise("TypeError", "format requires a mapping");
+            }
+            let (start, mut depth) = (i + 1, 1);
+            while depth > 0 {
+                i += 1;
+                match chars.get(i) {
+                    Some('(') => depth += 1,
+                    Some(')') => depth -= 1,
+                    Some(_) => {}
+                    None => {
+                        return raise("ValueError", "incomplete format key")
+                    }
+                }
+            }
+            let key: String = chars[start..i].iter().collect();
+            value = Some(get_item(args, &Value::str(&key))?);
+            i += 1;
+        }
+        let mut flags = Vec::new();
+        while let Some(&flag @ ('-' | '+' | ' ' | '#' | '0')) = chars.get(i) {
+            flags.push(flag);
+            i += 1;
+        }
+        // A width or precision, given in the template or by `*`
+        let size = |i: &mut usize, next: &mut usize| -> Eval<Option<i64>> {
+            if chars.get(*i) == Some(&'*') {
+                *i += 1;
+                return match percent_arg(&positional, next)? {
+                    size @ (Value::Int(_) | Value::Bool(_)) => {
+                        index_arg(&size).map(Some)
+                    }
+                    _ => raise("TypeError", "* wants int"),
+                };
+            }
+            let start = *i;
+            while chars.get(*i).is_some_and(char::is_ascii_digit) {
+                *i += 1;
+            }
+            Ok(chars[start..*i].iter().collect::<String>().parse().ok())
+        };
+        let width = size(&mut i, &mut next)?;
+        let precision = match chars.get(i) {
+            Some('.') => {
+                i += 1;
+                Some(size(&mut i, &mut next)?.unwrap_or(0).max(0) as usize)
+            }
+            _ => None,
+        };
+        if width.is_some_and(|width| width < 0) {
+            flags.push('-');
+        }
+        let width = width.map_or(0, |width| width.unsigned_abs() as usize);
+        while let Some('h' | 'l' | 'L') = chars.get(i) {
+            i += 1;
+        }
+        let kind = match chars.get(i) {
+            Some(&kind) => kind,
+            None => return raise("ValueError", "incomplete format"),
+        };
+        i += 1;
+        if kind == '%' {
+            out.push('%');
+            continue;
+        }
+        let value = match value {
+            Some(value) => value,
+            None => percent_arg(&positional, &mut next)?,
+        };
+        let field = Field { flags: &flags, width, precision };
+        out.push_str(&match kind {
+            's' | 'r' | 'a' => {
+                let text = match kind {
+                    's' => value.to_string(),
+                    'r' => value.repr(),
+                    _ => ascii(&value.repr()),
+                };
+                field.text(&text)
+            }
+            'c' => match &value {
+                Value::Str(string) if string.chars().count() == 1 => {
+                    field.text(string)
+                }
+                Value::Int(_) | Value::Bool(_) => {
+                    field.text(&chr(index_arg(&value)?)?.to_string())
+                }
+                _ => return raise("TypeError", "%c requires an int or a \
+                    unicode character"),
+            },
+            'd' | 'i' | 'u' | 'o' | 'x' | 'X' => {
+                let int = match (number(&value), kind) {
+                    (Some(Number::Int(int)), _) => int,
+                    (Some(Number::Float(_)), 'd' | 'i' | 'u') => to_int(&value)?,
+                    (Some(_), _) => {
+                        return raise("TypeError", format!(
+                            "%{} format: an integer is required, not {}",
+                            kind,
+                            value.type_name(),
+                        ))
+                    }
+                    (None, _) => {
+                        return raise("TypeError", format!(
+                            "%{} format: a real number is required, not {}",
+                            kind,
+                            value.type_name(),
+                        ))
+                    }
+                };
+                let (digits, prefix) = match kind {
+                    'o' => (int.abs().to_radix(8), "0o"),
+                    'x' => (int.abs().to_radix(16), "0x"),
+                    'X' => (int.abs().to_radix(16).to_uppercase(), "0X"),
+                    _ => (int.abs().to_radix(10), ""),
+                };
+                let zeros = precision.unwrap_or(0).saturating_sub(digits.len());
+                let digits = "0".repeat(zeros) + &digits;
+                let prefix = if flags.contains(&'#') { prefix } else { "" };
+                field.number(int.is_negative(), prefix, &digits)
+            }
+            'e' | 'E' | 'f' | 'F' | 'g' | 'G' => {
+                let float = match number(&value) {
+                    Some(number) => number.float()?,
+                    None => {
+                        return raise("TypeError", format!(
+                            "must be real number, not {}",
+                            value.type_name(),
+                        ))
+                    }
+                };
+                let spec = Spec {
+                    fill: ' ',
+                    align: None,
+                    sign: '-',
+                    alternate: flags.contains(&'#'),
+                    width: 0,
+                    grouping: None,
+                    precision: Some(precision.unwrap_or(6)),
+                    kind: Some(kind),
+                };
+                let body = float_body(float.abs(), &spec, false);
+                let negative = float.is_sign_negative() && !float.is_nan();
+                field.number(negative, "", &body)
+            }
+            _ => {
+                return raise("ValueError", format!(
+                    "unsupported format character '{}' ({:#x}) at index {}",
+                    kind,
+                    kind as u32,
+                    i - 1,
+                ))
+            }
+        });
+    }
+    if next < positional.len() && !mapping {
+        return raise(
+            "TypeError",
+            "not all arguments converted during string formatting",
+        );
+    }
+    Ok(out)
+}
+
+// The next positional argument of a `%` format.
+fn percent_arg(positional: &[Value], next: &mut usize) -> Eval<Value> {
+    match positional.get(*next) {
+        Some(value) => {
+            *next += 1;
+            Ok(value.clone())
+        }
+        None => raise("TypeError", "not enough arguments for format string"),
+    }
+}
+
+// The flags, width and precision of a `%` conversion.
+struct Field<'a> {
+    flags: &'a [char],
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Field<'_> {
+    // Pad text, truncated to the precision.
+    fn text(&self, text: &str) -> String {
+        let text: String = match self.precision {
+            Some(precision) => text.chars().take(precision).collect(),
+            None => text.to_string(),
+        };
+        let align = if self.flags.contains(&'-') { '<' } else { '>' };
+        pad(&text, ' ', align, self.width)
+    }
+
+    // Add the sign and prefix to the digits of a number and pad it.
+    fn number(&self, negative: bool, prefix: &str, digits: &str) -> String {
+        let sign = match negative {
+            true => "-",
+            false if self.flags.contains(&'+') => "+",
+            false if self.flags.contains(&' ') => " ",
+            false => "",
+        };
+        let head = format!("{}{}", sign, prefix);
+        if self.flags.contains(&'-') {
+            return pad(&(head + digits), ' ', '<', self.width);
+        }
+        if self.flags.contains(&'0') {
+            let len = head.len() + digits.chars().count();
+            let zeros = "0".repeat(self.width.saturating_sub(len));
+            return format!("{}{}{}", head, zeros, digits);
+        }
+        pad(&(head + digits), ' ', '>', self.width)
+    }
+}
+
+/// A string with non-ASCII characters escaped, as by `ascii()`.
+pub fn ascii(string: &str) -> String {
+    let mut escaped = String::new();
+    for ch in string.chars() {
+        match ch as u32 {
+            0..=0x7f => escaped.push(ch),
+            code @ 0x80..=0xff => escaped.push_str(&format!("\\x{:02x}", code)),
+            code @ 0x100..=0xffff => escaped.push_str(&format!("\\u{:04x}", code)),
+            code => escaped.push_str(&format!("\\U{:08x}", code)),
+        }
+    }
+    escaped
+}
+
+// Format a non-negative float for a spec.
+fn float_body(value: f64, spec: &Spec, is_int: bool) -> String {
+    if value.is_nan() || value.is_infinite() {
+        let text = if value.is_nan() { "nan" } else { "inf" };
+        return match spec.kind {
+            Some('F' | 'E' | 'G') => text.to_uppercase(),
+            _ => text.to_string(),
+        };
+    }
+    let grouped = |text: String| match text.split_once('.') {
+        Some((whole, fraction)) => {
+            format!("{}.{}", group(whole, spec.grouping, 3), fraction)
+        }
+        None => group(&text, spec.grouping, 3),
+    };
+    match spec.kind {
+        Some('f' | 'F') => {
+            let text = format!("{:.*}", spec.precision.unwrap_or(6), value);
+            let text = grouped(text);
+            match spec.alternate && !text.contains('.') {
+                true => text + ".",
+                false => text,
+            }
+        }
+        Some('%') => {
+            let text = format!("{:.*}", spec.precision.unwrap_or(6), value * 100.0);
+            grouped(text) + "%"
+        }
+        Some('e' | 'E') => {
+            let text = exponent(value, spec.precision.unwrap_or(6));
+            match spec.kind {
+                Some('E') => text.to_uppercase(),
+                _ => text,
+            }
+        }
+        Some('g' | 'G' | 'n') => {
+            let text = general(value, spec.precision.unwrap_or(6), spec.alternate);
+            match spec.kind {
+                Some('G') => grouped(text.to_uppercase()),
+                _ => grouped(text),
+            }
+        }
+        _ => match spec.precision {
+            None if is_int => grouped(format!("{}", value as i64)),
+            None => grouped(repr_float(value)),
+            Some(precision) => {
+                let text = general(value, precision, spec.alternate);
+                match text.contains(['.', 'e', 'n', 'i']) {
+                    true => grouped(text),
+                    false => grouped(text + ".0"),
+                }
+            }
+        },
+    }
+}
+
+// `value` in scientific notation with `precision` digits after the point.
+fn exponent(value: f64, precision: usize) -> String {
+    let text = format!("{:.*e}", precision, value);
+    let (mantissa, exp) = text.split_once('e').unwrap();
+    let exp: i32 = exp.parse().unwrap();
+    format!("{}e{}{:02}", mantissa, if exp < 0 { '-' } else { '+' }, exp.abs())
+}
+
+// The `g` presentation type: fixed or scientific notation with `precision`
+// significant digits, and trailing zeros removed unless `alternate`.
+fn general(value: f64, precision: usize, alternate: bool) -> String {
+    let precision = precision.max(1);
+    let sci = format!("{:.*e}", precision - 1, value);
+    let exp: i32 = sci.split_once('e').unwrap().1.parse().unwrap();
+    let text = match -4 <= exp && exp < precision as i32 {
+        true => format!("{:.*}", (precision as i32 - 1 - exp) as usize, value),
+        false => exponent(value, precision - 1),
+    };
+    if alternate {
+        return text;
+    }
+    let (number, exp) = match text.find('e') {
+        Some(i) => (&text[..i], &text[i..]),
+        None => (&text[..], ""),
+    };
+    let number = match number.contains('.') {
+        true => number.trim_end_matches('0').trim_end_matches('.'),
+        false => number,
+    };
+    format!("{}{}", number, exp)
+}
+
+// Insert a separator every `every` digits, counting from the right.
+fn group(digits: &str, separator: Option<char>, every: usize) -> String {
+    let separator = match separator {
+        Some(separator) => separator,
+        None => return digits.to_string(),
+    };
+    let mut out = String::new();
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(every) {
+            out.push(separator);
+        }
+        out.push(ch);
+    }
+    out
+}
+
+// Add the sign to a formatted number and pad it to the width.
+fn number_field(spec: &Spec, negative: bool, body: &str) -> String {
+    let sign = match (negative, spec.sign) {
+        (true, _) => "-",
+        (false, '+') => "+",
+        (false, ' ') => " ",
+        _ => "",
+    };
+    match spec.align.unwrap_or('>') {
+        '=' => {
+            let len = sign.chars().count() + body.chars().count();
+            let fill: String =
+                std::iter::repeat_n(spec.fill, spec.width.saturating_sub(len)).collect();
+            format!("{}{}{}", sign, fill, body)
+        }
+        align => pad(&format!("{}{}", sign, body), spec.fill, align, spec.width),
+    }
+}
+
+// Pad `text` to `width` characters with `fill`, aligned with `<`, `>` or
+// `^`.
+fn pad(text: &str, fill: char, align: char, width: usize) -> String {
+    let len = text.chars().count();
+    if len >= width {
+        return text.to_string();
+    }
+    let padding = width - len;
+    let (left, right) = match align {
+        '<' => (0, padding),
+        '^' => (padding / 2, padding - padding / 2),
+        _ => (padding, 0),
+    };
+    let fill = |count| std::iter::repeat_n(fill, count).collect::<String>();
+    format!("{}{}{}", fill(left), text, fill(right))
+}
+
+/// Python's `repr` of a float: the shortest digits that round trip, in
+/// scientific notation outside 1e-4 to 1e16.
+pub fn repr_float(value: f64) -> String {
+    if value.is_nan() {
+        return "nan".to_string();
+    }
+    if value.is_infinite() {
+        return if value < 0.0 { "-inf" } else { "inf" }.to_string();
+    }
+    let sci = format!("{:e}", value);
+    let (mantissa, exp) = sci.split_once('e').unwrap();
+    let exp: i32 = exp.parse().unwrap();
+    if (-4..16).contains(&exp) {
+        let repr = format!("{}", value);
+        return match repr.contains('.') {
+            true => repr,
+            false => format!("{}.0", repr),
+        };
+    }
+    let sign = if exp < 0 { '-' } else { '+' };
+    format!("{}e{}{:02}", mantissa, sign, exp.abs())
+}
+
+/// Python's `repr` of a string.
+pub fn repr_str(string: &str) -> String {
+    let quote = match string.contains('\'') && !string.contains('"') {
+        true => '"',
+        false => '\'',
+    };
+    let mut repr = String::new();
+    repr.push(quote);
+    for ch in string.chars() {
+        match ch {
+            '\\' => repr.push_str("\\\\"),
+            '\n' => repr.push_str("\\n"),
+            '\r' => repr.push_str("\\r"),
+            '\t' => repr.push_str("\\t"),
+            _ if ch == quote => {
+                repr.push('\\');
+                repr.push(ch);
+            }
+            _ if !is_printable(ch) => {
+                let escape = match ch as u32 {
+                    code @ 0..=0xff => format!("\\x{:02x}", code),
+                    code @ 0..=0xffff => format!("\\u{:04x}", code),
+                    code => format!("\\U{:08x}", code),
+                };
+                repr.push_str(&escape);
+            }
+            _ => repr.push(ch),
+        }
+    }
+    repr.push(quote);
+    repr
+}
+
+// Whether Python's `str.isprintable` is true for a character (roughly: not a
+// control, format or separator character other than space).
+fn is_printable(ch: char) -> bool {
+    !(ch.is_control()
+        || (ch.is_whitespace() && ch != ' ')
+        || matches!(ch, '\u{ad}'
+            | '\u{600}'..='\u{605}'
+            | '\u{200b}'..='\u{200f}'
+            | '\u{202a}'..='\u{202e}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{206f}'
+            | '\u{feff}'
+            | '\u{fff9}'..='\u{fffb}'
+            | '\u{e000}'..='\u{f8ff}'))
+}
+
+/// Python's `repr` of bytes.
+pub fn repr_bytes(bytes: &[u8]) -> String {
+    let quote = match bytes.contains(&b'\'') && !bytes.contains(&b'"') {
+        true => b'"',
+        false => b'\'',
+    };
+    let mut repr = String::from("b");
+    repr.push(quote as char);
+    for &byte in bytes {
+        match byte {
+            b'\\' => repr.push_str("\\\\"),
+            b'\n' => repr.push_str("\\n"),
+            b'\r' => repr.push_str("\\r"),
+            b'\t' => repr.push_str("\\t"),
+            _ if byte == quote => {
+                repr.push('\\');
+                repr.push(byte as char);
+            }
+            0x20..=0x7e => repr.push(byte as char),
+            _ => repr.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    repr.push(quote as char);
+    repr
+}
@@ -0,0 +1,327 @@
+// Python event loop
+//
+//! A minimal single-threaded event loop, and the parts of the `asyncio`
+//! module that use it: `run`, `create_task`, `gather` and `sleep`.
+//!
+//! Coroutines run as tasks, which the loop resumes in turn.  A coroutine
+//! that waits for a [`Future`] that isn't done yields it to the loop, which
+//! resumes the task when the future gets its result.  Time is simulated:
+//! when every task is waiting for a `sleep`, the clock jumps to the end of
+//! the earliest one instead of the loop blocking.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::rc::Rc;
+
+use super::generator::{Body, Generator, Kind, Resume, Step};
+use super::{raise, to_float, Eval, Exception, Value};
+
+/// The eventual result of an operation, which coroutines can await
+pub struct Future {
+    /// `"Task"` for the future of a task
+    pub name: &'static str,
+    result: RefCell<Option<Eval<Value>>>,
+    /// The tasks waiting for the result
+    waiters: RefCell<Vec<Rc<Task>>>,
+}
+
+impl fmt::Debug for Future {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.is_done() {
+            true => "finished",
+            false => "pending",
+        };
+        write!(f, "<{} {}>", self.name, state)
+    }
+}
+
+impl Future {
+    fn new(name: &'static str) -> Rc<Self> {
+        Rc::new(Future {
+            name,
+            result: RefCell::new(None),
+            waiters: RefCell::new(Vec::new()),
+        })
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.result.borrow().is_some()
+    }
+
+    /// The result, or the exception, once the future is done.
+    pub fn result(&self) -> Option<Eval<Value>> {
+        self.result.borrow().clone()
+    }
+
+    // Set the result and wake the tasks waiting for it.
+    fn finish(&self, result: Eval<Value>) {
+        *self.result.borrow_mut() = Some(result);
+        let waiters = std::mem::take(&mut *self.waiters.borrow_mut());
+        for task in waiters {
+            schedule(task, Resume::Send(Value::None));
+        }
+    }
+}
+
+// A coroutine the loop runs, and the future of its result
+struct Task {
+    coroutine: Rc<Generator>,
+    future: Rc<Future>,
+}
+
+// The state of the running loop
+#[derive(Default)]
+struct Loop {
+    /// Tasks ready to be resumed, with what to resume them with
+    ready: VecDeque<(Rc<Task>, Resume)>,
+    /// Sleeps, by when they end, in the order they started
+    timers: Vec<(f64, Rc<Future>, Value)>,
+    time: f64,
+}
+
+thread_local! {
+    static LOOP: RefCell<Option<Loop>> = const { RefCell::new(None) };
+}
+
+fn running<T>(f: impl FnOnce(&mut Loop) -> T) -> Eval<T> {
+    LOOP.with(|running| match &mut *running.borrow_mut() {
+        Some(event_loop) => Ok(f(event_loop)),
+        None => raise("RuntimeError", "no running event loop"),
+    })
+}
+
+fn schedule(task: Rc<Task>, resume: Resume) {
+    let _ = running(|event_loop| event_loop.ready.push_back((task, resume)));
+}
+
+// Start running a coroutine as a task.
+fn spawn(coroutine: &Value) -> Eval<Rc<Future>> {
+    let coroutine = match coroutine {
+        Value::Generator(generator) if generator.kind == Kind::Coroutine => {
+            generator.clone()
+        }
+        _ => {
+            return raise("TypeError", format!(
+                "a coroutine was expected, got {}",
+                coroutine.repr(),
+            ))
+        }
+    };
+    let future = Future::new("Task");
+    let task = Rc::new(Task { coroutine, future: future.clone() });
+    let resume = Resume::Send(Value::None);
+    running(|event_loop| event_loop.ready.push_back((task, resume)))?;
+    Ok(future)
+}
+
+// Resume a task until it waits for something.
+fn step(task: Rc<Task>, resume: Resume) {
+    match task.coroutine.resume(resume) {
+        Ok(Step::Yield(Value::Future(future))) => match future.is_done() {
+            true => schedule(task, Resume::Send(Value::None)),
+            false => future.waiters.borrow_mut().push(task),
+        },
+        // A bare `yield` lets the other tasks run.
+        Ok(Step::Yield(Value::None)) => schedule(task, Resume::Send(Value::None)),
+        Ok(Step::Yield(value)) => {
+            let message = format!("Task got bad yield: {}", value.repr());
+            schedule(task, Resume::Throw(Exception::new("RuntimeError", message)));
+        }
+        Ok(Step::Return(value)) => task.future.finish(Ok(value)),
+        Err(exception) => task.future.finish(Err(exception)),
+    }
+}
+
+/// `asyncio.run(coroutine)`: run the coroutine as a task until it finishes,
+/// along with the tasks it starts, returning its result.
+pub fn run(coroutine: &Value) -> Eval<Value> {
+    let started = LOOP.with(|running| {
+        let mut running = running.borrow_mut();
+        match running.is_some() {
+            true => false,
+            false => {
+                *running = Some(Loop::default());
+                true
+            }
+        }
+    });
+    if !started {
+        return raise(
+            "RuntimeError",
+            "asyncio.run() cannot be called from a running event loop",
+        );
+    }
+    let result = run_until_done(coroutine);
+    // Tasks still pending are abandoned.
+    let event_loop = LOOP.with(|running| running.borrow_mut().take());
+    drop(event_loop);
+    result
+}
+
+fn run_until_done(coroutine: &Value) -> Eval<Value> {
+    let main = spawn(coroutine)?;
+    loop {
+        if let Some(result) = main.result() {
+            return result;
+        }
+        let ready = running(|event_loop| event_loop.ready.pop_front())?;
+        if let Some((task, resume)) = ready {
+            step(task, resume);
+            continue;
+        }
+        // Nothing can run until the earliest sleep ends.
+        let timer = running(|event_loop| {
+            let timers = &event_loop.timers;
+            let earliest = (0..timers.len())
+                .min_by(|&a, &b| timers[a].0.total_cmp(&timers[b].0))?;
+            let (time, future, result) = event_loop.timers.remove(earliest);
+            event_loop.time = event_loop.time.max(time);
+            Some((future, result))
+        })?;
+        match timer {
+            Some((future, result)) => future.finish(Ok(result)),
+            None => {
+                return raise(
+                    "RuntimeError",
+                    "Event loop stopped before Future completed.",
+                )
+            }
+        }
+    }
+}
+
+/// `asyncio.create_task(coroutine)`
+pub fn create_task(coroutine: &Value) -> Eval<Value> {
+    spawn(coroutine).map(Value::Future)
+}
+
+// The coroutine `asyncio.sleep(delay, result)` returns
+struct Sleep {
+    delay: f64,
+    result: Option<Value>,
+    started: bool,
+}
+
+impl Body for Sleep {
+    fn resume(&mut self, resume: Resume) -> Eval<Step> {
+        if let Resume::Throw(exception) = resume {
+            return Err(exception);
+        }
+        if std::mem::replace(&mut self.started, true) {
+            return Ok(Step::Return(self.result.take().unwrap_or(Value::None)));
+        }
+        if self.delay <= 0.0 {
+            return Ok(Step::Yield(Value::None));
+        }
+        let future = Future::new("Future");
+        let result = self.result.take().unwrap_or(Value::None);
+        let delay = self.delay;
+        running(|event_loop| {
+            let time = event_loop.time + delay;
+            event_loop.timers.push((time, future.clone(), result));
+        })?;
+        Ok(Step::Yield(Value::Future(future)))
+    }
+
+    fn visit(&self, f: &mut dyn FnMut(&Value)) {
+        self.result.iter().for_each(f);
+    }
+
+    fn clear(&mut self) -> Vec<Value> {
+        self.result.take().into_iter().collect()
+    }
+}
+
+/// `asyncio.sleep(delay, result=None)`
+pub fn sleep(delay: &Value, result: Value) -> Eval<Value> {
+    let delay = to_float(delay)?;
+    let sleep = Sleep { delay, result: Some(result), started: false };
+    let generator = Generator::new("sleep", Kind::Coroutine, Box::new(sleep));
+    Ok(Value::generator(generator))
+}
+
+// The coroutine `asyncio.gather(*awaitables)` returns
+struct Gather {
+    awaitables: Vec<Value>,
+    futures: Option<Vec<Rc<Future>>>,
+}
+
+impl Body for Gather {
+    fn resume(&mut self, resume: Resume) -> Eval<Step> {
+        if let Resume::Throw(exception) = resume {
+            return Err(exception);
+        }
+        if self.futures.is_none() {
+            let mut futures = Vec::new();
+            for awaitable in &self.awaitables {
+                futures.push(match awaitable {
+                    Value::Future(future) => future.clone(),
+                    Value::Generator(generator)
+                        if generator.kind == Kind::Coroutine =>
+                    {
+                        spawn(awaitable)?
+                    }
+                    _ => {
+                        return raise(
+                            "TypeError",
+                            "An asyncio.Future, a coroutine or an awaitable is \
+                                required",
+                        )
+                    }
+                });
+            }
+            self.futures = Some(futures);
+        }
+        let futures = self.futures.as_ref().unwrap();
+        if let Some(pending) = futures.iter().find(|future| !future.is_done()) {
+            return Ok(Step::Yield(Value::Future(pending.clone())));
+        }
+        let mut results = Vec::new();
+        for future in futures {
+            results.push(future.result().unwrap()?);
+        }
+        Ok(Step::Return(Value::list(results)))
+    }
+
+    fn visit(&self, f: &mut dyn FnMut(&Value)) {
+        self.awaitables.iter().for_each(&mut *f);
+        for future in self.futures.iter().flatten() {
+            f(&Value::Future(future.clone()));
+        }
+    }
+
+    fn clear(&mut self) -> Vec<Value> {
+        let mut held = std::mem::take(&mut self.awaitables);
+        held.extend(self.futures.take().into_iter().flatten().map(Value::Future));
+        held
+    }
+}
+
+/// `asyncio.gather(*awaitables)`
+pub fn gather(awaitables: Vec<Value>) -> Value {
+    let gather = Gather { awaitables, futures: None };
+    Value::generator(Generator::new("gather", Kind::Coroutine, Box::new(gather)))
+}
+
+/// Call a function of the `asyncio` module.
+pub fn call(name: &str, args: Vec<Value>) -> Eval<Value> {
+    let (min, max) = match name {
+        "run" | "create_task" => (1, 1),
+        "sleep" => (1, 2),
+        "gather" => (0, usize::MAX),
+        _ => {
+            return raise("AttributeError", format!(
+                "module 'asyncio' has no attribute '{}'",
+                name,
+            ))
+        }
+    };
+    super::arity(name, &args, min, max)?;
+    match name {
+        "run" => run(&args[0]),
+        "create_task" => create_task(&args[0]),
+        "sleep" => sleep(&args[0], args.get(1).cloned().unwrap_or(Value::None)),
+        _ => Ok(gather(args)),
+    }
+}
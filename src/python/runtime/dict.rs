@@ -0,0 +1,224 @@
+// Python dictionaries and sets
+//
+//! Hash tables of [`Value`]s: `dict`, which keeps its keys in insertion
+//! order, and `set`, which uses the same table with no values.
+//!
+//! Like CPython's `dict`, the table is an array of entries in insertion
+//! order and an open-addressed index of those entries by hash.  Removing a
+//! key leaves a hole in the entries, which is closed when the index is next
+//! rebuilt.
+
+use super::{equal, hash, Eval, Value};
+
+// A slot of the index with no entry
+const EMPTY: usize = usize::MAX;
+
+// A slot of the index whose entry was removed
+const REMOVED: usize = usize::MAX - 1;
+
+/// An insertion-ordered dictionary
+#[derive(Debug, Clone, Default)]
+pub struct Dict {
+    /// Entries in insertion order, `None` where one was removed
+    entries: Vec<Option<Entry>>,
+    /// Indices of entries, by hash; the length is a power of two
+    index: Vec<usize>,
+    /// Number of slots of the index that aren't empty
+    used: usize,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    hash: i64,
+    key: Value,
+    value: Value,
+}
+
+impl Dict {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // The slot of the index holding `key`, or the slot it would be inserted
+    // in.
+    fn probe(&self, hash: i64, key: &Value) -> Result<usize, usize> {
+        let mask = self.index.len() - 1;
+        let mut perturb = hash as u64;
+        let mut slot = perturb as usize & mask;
+        let mut free = None;
+        loop {
+            match self.index[slot] {
+                EMPTY => return Err(free.unwrap_or(slot)),
+                REMOVED => {
+                    free.get_or_insert(slot);
+                }
+                i => {
+                    let entry = self.entries[i].as_ref().unwrap();
+                    if entry.hash == hash && equal(&entry.key, key) {
+                        return Ok(slot);
+                    }
+                }
+            }
+            perturb >>= 5;
+            slot = (slot * 5 + 1 + perturb as usize) & mask;
+        }
+    }
+
+    fn find(&self, key: &Value) -> Eval<Option<usize>> {
+        let hash = hash(key)?;
+        if self.len == 0 {
+            return Ok(None);
+        }
+        Ok(self.probe(hash, key).ok().map(|slot| self.index[slot]))
+    }
+
+    // Rebuild the index for the live entries, closing the holes.
+    fn rebuild(&mut self) {
+        self.entries.retain(Option::is_some);
+        let size = (self.entries.len() * 3 + 1).next_power_of_two().max(8);
+        self.index = vec![EMPTY; size];
+        for i in 0..self.entries.len() {
+            let entry = self.entries[i].as_ref().unwrap();
+            let slot = self.probe(entry.hash, &entry.key).unwrap_err();
+            self.index[slot] = i;
+        }
+        self.used = self.entries.len();
+    }
+
+    /// The value of `key`, if it is in the dictionary.
+    pub fn get(&self, key: &Value) -> Eval<Option<Value>> {
+        Ok(self.find(key)?.map(|i| self.entries[i].as_ref().unwrap().value.clone()))
+    }
+
+    /// Set the value of `key`, keeping its place if it was already there.
+    pub fn insert(&mut self, key: Value, value: Value) -> Eval<()> {
+        let hash = hash(&key)?;
+        if (self.used + 1) * 3 > self.index.len() * 2 {
+            self.rebuild();
+        }
+        match self.probe(hash, &key) {
+            Ok(slot) => {
+                self.entries[self.index[slot]].as_mut().unwrap().value = value;
+            }
+            Err(slot) => {
+                if self.index[slot] == EMPTY {
+                    self.used += 1;
+                }
+                self.index[slot] = self.entries.len();
+                self.entries.push(Some(Entry { hash, key, value }));
+                self.len += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove `key`, returning its value.
+    pub fn remove(&mut self, key: &Value) -> Eval<Option<Value>> {
+        let hash = hash(key)?;
+        if self.len == 0 {
+            return Ok(None);
+        }
+        Ok(match self.probe(hash, key) {
+            Ok(slot) => {
+                let entry = self.entries[self.index[slot]].take().unwrap();
+                self.index[slot] = REMOVED;
+                self.len -= 1;
+                Some(entry.value)
+            }
+            Err(_) => None,
+        })
+    }
+
+    /// Remove the last entry inserted, like `dict.popitem()`.
+    pub fn pop_last(&mut self) -> Option<(Value, Value)> {
+        let last = self.entries.iter().rposition(Option::is_some)?;
+        self.entries.truncate(last + 1);
+        let entry = self.entries[last].as_ref().unwrap();
+        let slot = self.probe(entry.hash, &entry.key).unwrap();
+        self.index[slot] = REMOVED;
+        self.len -= 1;
+        let entry = self.entries.pop().unwrap().unwrap();
+        Some((entry.key, entry.value))
+    }
+
+    pub fn clear(&mut self) {
+        *self = Dict::default();
+    }
+
+    /// The entries in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Value, &Value)> {
+        self.entries
+            .iter()
+            .flatten()
+            .map(|entry| (&entry.key, &entry.value))
+    }
+
+    pub fn keys(&self) -> Vec<Value> {
+        self.iter().map(|(key, _)| key.clone()).collect()
+    }
+
+    pub fn values(&self) -> Vec<Value> {
+        self.iter().map(|(_, value)| value.clone()).collect()
+    }
+
+    /// The entries as `(key, value)` tuples.
+    pub fn items(&self) -> Vec<Value> {
+        self.iter()
+            .map(|(key, value)| Value::tuple(vec![key.clone(), value.clone()]))
+            .collect()
+    }
+}
+
+/// A set, which iterates in insertion order
+#[derive(Debug, Clone, Default)]
+pub struct Set {
+    table: Dict,
+}
+
+impl Set {
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    pub fn contains(&self, item: &Value) -> Eval<bool> {
+        Ok(self.table.find(item)?.is_some())
+    }
+
+    /// Add an item, returning whether it was new.
+    pub fn insert(&mut self, item: Value) -> Eval<bool> {
+        let len = self.table.len();
+        self.table.insert(item, Value::None)?;
+        Ok(self.table.len() > len)
+    }
+
+    /// Remove an item, returning whether it was there.
+    pub fn remove(&mut self, item: &Value) -> Eval<bool> {
+        Ok(self.table.remove(item)?.is_some())
+    }
+
+    /// Remove some item, like `set.pop()`.
+    pub fn pop(&mut self) -> Option<Value> {
+        self.table.pop_last().map(|(item, _)| item)
+    }
+
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Value> {
+        self.table.iter().map(|(item, _)| item)
+    }
+
+    pub fn items(&self) -> Vec<Value> {
+        self.table.keys()
+    }
+}
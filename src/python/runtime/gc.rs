@@ -0,0 +1,237 @@
+// Python cycle collector
+//
+//! Collection of reference cycles.
+//!
+//! Values are reference counted (an [`Rc`] per object), which frees
+//! everything except containers that refer to each other in a cycle.  Every
+//! container that can be part of one is tracked here, and [`collect`] finds
+//! the tracked containers that are only referred to by other tracked
+//! containers the way CPython's collector does: it subtracts the references
+//! containers hold to each other from their reference counts, and any
+//! container left with references from elsewhere is alive, with everything
+//! it refers to.  The others are unreachable, and clearing them breaks their
+//! cycles so they are freed.  Instances of classes are containers of their
+//! attributes.
+//!
+//! A collection runs automatically when the number of containers made since
+//! the last one exceeds the number that survived it (or 700, at first).
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use super::{Dict, Generator, Iter, Object, Set, Value};
+
+// How many containers are made before the first automatic collection
+const THRESHOLD: usize = 700;
+
+// A weak reference to a tracked container
+enum Tracked {
+    List(Weak<RefCell<Vec<Value>>>),
+    Tuple(Weak<[Value]>),
+    Dict(Weak<RefCell<Dict>>),
+    Set(Weak<RefCell<Set>>),
+    Iterator(Weak<RefCell<Iter>>),
+    Generator(Weak<Generator>),
+    Object(Weak<Object>),
+}
+
+thread_local! {
+    static TRACKED: RefCell<Vec<Tracked>> = const { RefCell::new(Vec::new()) };
+    // Containers made since the last collection, and how many trigger one
+    static MADE: Cell<usize> = const { Cell::new(0) };
+    static LIMIT: Cell<usize> = const { Cell::new(THRESHOLD) };
+}
+
+impl Tracked {
+    fn upgrade(&self) -> Option<Value> {
+        match self {
+            Tracked::List(list) => list.upgrade().map(Value::List),
+            Tracked::Tuple(tuple) => tuple.upgrade().map(Value::Tuple),
+            Tracked::Dict(dict) => dict.upgrade().map(Value::Dict),
+            Tracked::Set(set) => set.upgrade().map(Value::Set),
+            Tracked::Iterator(iter) => iter.upgrade().map(Value::Iterator),
+            Tracked::Generator(generator) => generator.upgrade().map(Value::Generator),
+            Tracked::Object(object) => object.upgrade().map(Value::Object),
+        }
+    }
+}
+
+/// Start tracking a new container, collecting cycles if enough were made
+/// since the last collection.
+pub(super) fn track(value: &Value) {
+    let tracked = match value {
+        Value::List(list) => Tracked::List(Rc::downgrade(list)),
+        Value::Tuple(tuple) => {
+            // A tuple of values that aren't containers can't be in a cycle.
+            if !tuple.iter().any(|item| address(item).is_some()) {
+                return;
+            }
+            Tracked::Tuple(Rc::downgrade(tuple))
+        }
+        Value::Dict(dict) => Tracked::Dict(Rc::downgrade(dict)),
+        Value::Set(set) => Tracked::Set(Rc::downgrade(set)),
+        Value::Iterator(iter) => Tracked::Iterator(Rc::downgrade(iter)),
+        Value::Generator(generator) => Tracked::Generator(Rc::downgrade(generator)),
+        Value::Object(object) => Tracked::Object(Rc::downgrade(object)),
+        _ => return,
+    };
+    TRACKED.with(|registry| registry.borrow_mut().push(tracked));
+    let made = MADE.with(|made| {
+        made.set(made.get() + 1);
+        made.get()
+    });
+    if made > LIMIT.with(Cell::get) {
+        collect();
+    }
+}
+
+// The identity of a container that may be tracked.
+fn address(value: &Value) -> Option<usize> {
+    match value {
+        Value::List(list) => Some(Rc::as_ptr(list) as *const () as usize),
+        Value::Tuple(tuple) => Some(Rc::as_ptr(tuple) as *const () as usize),
+        Value::Dict(dict) => Some(Rc::as_ptr(dict) as *const () as usize),
+        Value::Set(set) => Some(Rc::as_ptr(set) as *const () as usize),
+        Value::Iterator(iter) => Some(Rc::as_ptr(iter) as *const () as usize),
+        Value::Generator(generator) => Some(Rc::as_ptr(generator) as usize),
+        Value::Object(object) => Some(Rc::as_ptr(object) as usize),
+        _ => None,
+    }
+}
+
+// The number of strong references to a container.
+fn references(value: &Value) -> usize {
+    match value {
+        Value::List(list) => Rc::strong_count(list),
+        Value::Tuple(tuple) => Rc::strong_count(tuple),
+        Value::Dict(dict) => Rc::strong_count(dict),
+        Value::Set(set) => Rc::strong_count(set),
+        Value::Iterator(iter) => Rc::strong_count(iter),
+        Value::Generator(generator) => Rc::strong_count(generator),
+        Value::Object(object) => Rc::strong_count(object),
+        _ => 0,
+    }
+}
+
+// Call `f` with each value a container refers to, returning false if the
+// container is being changed and can't be looked into.
+fn visit(value: &Value, f: &mut dyn FnMut(&Value)) -> bool {
+    match value {
+        Value::List(list) => match list.try_borrow() {
+            Ok(items) => items.iter().for_each(f),
+            Err(_) => return false,
+        },
+        Value::Tuple(tuple) => tuple.iter().for_each(f),
+        Value::Dict(dict) => match dict.try_borrow() {
+            Ok(dict) => dict.iter().for_each(|(key, value)| {
+                f(key);
+                f(value);
+            }),
+            Err(_) => return false,
+        },
+        Value::Set(set) => match set.try_borrow() {
+            Ok(set) => set.iter().for_each(f),
+            Err(_) => return false,
+        },
+        Value::Iterator(iter) => match iter.try_borrow() {
+            Ok(iter) => iter.visit(f),
+            Err(_) => return false,
+        },
+        Value::Generator(generator) => return generator.visit(f),
+        Value::Object(object) => return object.visit(f),
+        _ => {}
+    }
+    true
+}
+
+// Empty a container, returning what it held so it is dropped after every
+// container is cleared.
+fn clear(value: &Value) -> Vec<Value> {
+    match value {
+        Value::List(list) => std::mem::take(&mut *list.borrow_mut()),
+        Value::Dict(dict) => {
+            let dict = std::mem::take(&mut *dict.borrow_mut());
+            dict.iter().flat_map(|(key, value)| vec![key.clone(), value.clone()]).collect()
+        }
+        Value::Set(set) => std::mem::take(&mut *set.borrow_mut()).items(),
+        Value::Iterator(iter) => {
+            let mut iter = iter.borrow_mut();
+            let mut held = Vec::new();
+            iter.visit(&mut |value| held.push(value.clone()));
+            *iter = Iter::Items(Vec::new().into_iter(), "list_iterator");
+            held
+        }
+        Value::Generator(generator) => generator.clear(),
+        Value::Object(object) => object.clear(),
+        _ => Vec::new(),
+    }
+}
+
+/// Free the tracked containers that are only reachable through cycles,
+/// returning how many there were.
+pub fn collect() -> usize {
+    let objects: Vec<Value> = TRACKED.with(|tracked| {
+        let mut tracked = tracked.borrow_mut();
+        let objects: Vec<Value> = tracked.iter().filter_map(Tracked::upgrade).collect();
+        tracked.retain(|tracked| tracked.upgrade().is_some());
+        objects
+    });
+    let ids: HashMap<usize, usize> = objects
+        .iter()
+        .enumerate()
+        .map(|(i, object)| (address(object).unwrap(), i))
+        .collect();
+    // References from outside the tracked containers (`objects` holds one
+    // of each, and the registry only weak ones).
+    let mut outside: Vec<isize> = objects
+        .iter()
+        .map(|object| references(object) as isize - 1)
+        .collect();
+    let mut roots = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        let looked = visit(object, &mut |child| {
+            if let Some(&j) = address(child).and_then(|address| ids.get(&address)) {
+                outside[j] -= 1;
+            }
+        });
+        if !looked {
+            roots.push(i);
+        }
+    }
+    roots.extend((0..objects.len()).filter(|&i| outside[i] > 0));
+    let mut reachable = vec![false; objects.len()];
+    while let Some(i) = roots.pop() {
+        if reachable[i] {
+            continue;
+        }
+        reachable[i] = true;
+        visit(&objects[i], &mut |child| {
+            if let Some(&j) = address(child).and_then(|address| ids.get(&address)) {
+                if !reachable[j] {
+                    roots.push(j);
+                }
+            }
+        });
+    }
+    let unreachable: Vec<&Value> = objects
+        .iter()
+        .zip(&reachable)
+        .filter(|(_, reachable)| !**reachable)
+        .map(|(object, _)| object)
+        .collect();
+    let count = unreachable.len();
+    let held: Vec<Vec<Value>> = unreachable.into_iter().map(clear).collect();
+    drop(held);
+    let survivors = objects.len() - count;
+    drop(objects);
+    MADE.with(|made| made.set(0));
+    LIMIT.with(|limit| limit.set(THRESHOLD.max(survivors)));
+    count
+}
+
+/// The number of containers being tracked, including ones that were freed
+/// since the last collection.
+pub fn tracked() -> usize {
+    TRACKED.with(|tracked| tracked.borrow().len())
+}
@@ -0,0 +1,257 @@
+// Python generators and coroutines
+//
+//! Generator and coroutine objects.
+//!
+//! The body of a generator function is compiled to a state machine, a
+//! [`Body`], which runs until its next `yield` each time it is resumed and
+//! keeps its variables and where it stopped in between.  A [`Generator`]
+//! adds Python's protocol on top: `send`, `throw` and `close`, the return
+//! value carried by `StopIteration`, and the errors for resuming one that is
+//! running or finished.  Coroutines (the results of calling `async`
+//! functions) are generators of another kind, which are awaited instead of
+//! iterated.
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+
+use super::{raise, Eval, Exception, Value};
+
+/// What a generator is resumed with
+#[derive(Debug)]
+pub enum Resume {
+    /// A value for the `yield` it stopped at, `None` for `next`
+    Send(Value),
+    /// An exception to raise at the `yield` it stopped at
+    Throw(Exception),
+}
+
+/// How a generator stops running
+#[derive(Debug)]
+pub enum Step {
+    Yield(Value),
+    Return(Value),
+}
+
+/// The code of a generator, run from where it last yielded each time it is
+/// resumed.
+pub trait Body {
+    /// Run until the next `yield` or the end.
+    fn resume(&mut self, resume: Resume) -> Eval<Step>;
+
+    /// Call `f` with each value the suspended body refers to.
+    fn visit(&self, f: &mut dyn FnMut(&Value));
+
+    /// Forget the values the body refers to, returning them.
+    fn clear(&mut self) -> Vec<Value>;
+}
+
+/// Whether a generator is iterated or awaited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Generator,
+    Coroutine,
+}
+
+/// A generator or coroutine object
+pub struct Generator {
+    pub name: String,
+    pub kind: Kind,
+    /// The body, taken out while it runs and dropped once it finishes
+    body: RefCell<Option<Box<dyn Body>>>,
+    started: Cell<bool>,
+    running: Cell<bool>,
+}
+
+impl fmt::Debug for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{} object {}>", self.type_name(), self.name)
+    }
+}
+
+impl Generator {
+    pub fn new(name: &str, kind: Kind, body: Box<dyn Body>) -> Self {
+        Generator {
+            name: name.to_string(),
+            kind,
+            body: RefCell::new(Some(body)),
+            started: Cell::new(false),
+            running: Cell::new(false),
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self.kind {
+            Kind::Generator => "generator",
+            Kind::Coroutine => "coroutine",
+        }
+    }
+
+    /// Run the generator until it yields or returns.
+    pub fn resume(&self, resume: Resume) -> Eval<Step> {
+        if self.running.get() {
+            return raise("ValueError", format!(
+                "{} already executing",
+                self.type_name(),
+            ));
+        }
+        let mut body = match self.body.borrow_mut().take() {
+            Some(body) => body,
+            None => {
+                return match (resume, self.kind) {
+                    (Resume::Throw(exception), _) => Err(exception),
+                    (_, Kind::Coroutine) => raise(
+                        "RuntimeError",
+                        "cannot reuse already awaited coroutine",
+                    ),
+                    (_, Kind::Generator) => Ok(Step::Return(Value::None)),
+                }
+            }
+        };
+        if let Resume::Send(ref value) = resume {
+            if !self.started.get() && !matches!(value, Value::None) {
+                *self.body.borrow_mut() = Some(body);
+                return raise("TypeError", format!(
+                    "can't send non-None value to a just-started {}",
+                    self.type_name(),
+                ));
+            }
+        }
+        self.started.set(true);
+        self.running.set(true);
+        let step = body.resume(resume);
+        self.running.set(false);
+        match step {
+            Ok(Step::Yield(value)) => {
+                *self.body.borrow_mut() = Some(body);
+                Ok(Step::Yield(value))
+            }
+            Ok(step) => Ok(step),
+            Err(exception) if exception.kind == "StopIteration" => raise(
+                "RuntimeError",
+                format!("{} raised StopIteration", self.type_name()),
+            ),
+            Err(exception) => Err(exception),
+        }
+    }
+
+    /// `generator.send(value)`: the next value yielded, raising
+    /// `StopIteration` with the value returned at the end.
+    pub fn send(&self, value: Value) -> Eval<Value> {
+        match self.resume(Resume::Send(value))? {
+            Step::Yield(value) => Ok(value),
+            Step::Return(value) => Err(stop_iteration(&value)),
+        }
+    }
+
+    /// `generator.throw(exception)`
+    pub fn throw(&self, exception: Exception) -> Eval<Value> {
+        match self.resume(Resume::Throw(exception))? {
+            Step::Yield(value) => Ok(value),
+            Step::Return(value) => Err(stop_iteration(&value)),
+        }
+    }
+
+    /// `generator.close()`: raise `GeneratorExit` where the generator is
+    /// suspended, so its `finally` clauses run.
+    pub fn close(&self) -> Eval<()> {
+        if !self.started.get() && !self.running.get() {
+            self.body.borrow_mut().take();
+            return Ok(());
+        }
+        match self.resume(Resume::Throw(Exception::new("GeneratorExit", ""))) {
+            Ok(Step::Yield(_)) => raise(
+                "RuntimeError",
+                format!("{} ignored GeneratorExit", self.type_name()),
+            ),
+            Ok(Step::Return(_)) => Ok(()),
+            Err(exception) if exception.kind == "GeneratorExit" => Ok(()),
+            Err(exception) => Err(exception),
+        }
+    }
+
+    pub(super) fn visit(&self, f: &mut dyn FnMut(&Value)) -> bool {
+        match self.body.try_borrow() {
+            Ok(body) => {
+                if let Some(body) = &*body {
+                    body.visit(f);
+                }
+                !self.running.get()
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub(super) fn clear(&self) -> Vec<Value> {
+        match &mut *self.body.borrow_mut() {
+            Some(body) => body.clear(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// The `StopIteration` a generator raises when it returns `value`.
+pub fn stop_iteration(value: &Value) -> Exception {
+    match value {
+        Value::None => Exception::new("StopIteration", ""),
+        value => Exception::with_args("StopIteration", value.to_string(),
+            Rc::from([value.clone()])),
+    }
+}
+
+/// Advance what `yield from` or `await` delegates to: a generator, a
+/// coroutine, an iterator or a future.
+pub fn delegate(delegate: &Value, resume: Resume) -> Eval<Step> {
+    match delegate {
+        Value::Generator(generator) => match resume {
+            Resume::Throw(exception) if exception.kind == "GeneratorExit" => {
+                generator.close()?;
+                Err(exception)
+            }
+            resume => generator.resume(resume),
+        },
+        Value::Future(future) => match resume {
+            Resume::Throw(exception) => Err(exception),
+            Resume::Send(_) => match future.result() {
+                Some(result) => result.map(Step::Return),
+                None => Ok(Step::Yield(delegate.clone())),
+            },
+        },
+        _ => match resume {
+            Resume::Throw(exception) => Err(exception),
+            Resume::Send(Value::None) => match super::next_item(delegate)? {
+                Some(item) => Ok(Step::Yield(item)),
+                None => Ok(Step::Return(Value::None)),
+            },
+            Resume::Send(_) => raise("AttributeError", format!(
+                "'{}' object has no attribute 'send'",
+                delegate.type_name(),
+            )),
+        },
+    }
+}
+
+/// What `await value` waits for: a coroutine or a future.
+pub fn awaitable(value: &Value) -> Eval<Value> {
+    match value {
+        Value::Generator(generator) if generator.kind == Kind::Coroutine => {
+            Ok(value.clone())
+        }
+        Value::Future(_) => Ok(value.clone()),
+        _ => raise("TypeError", format!(
+            "object {} can't be used in 'await' expression",
+            value.type_name(),
+        )),
+    }
+}
+
+/// What `yield from value` delegates to: a generator or an iterator.
+pub fn yield_from(value: &Value) -> Eval<Value> {
+    match value {
+        Value::Generator(generator) if generator.kind == Kind::Coroutine => raise(
+            "TypeError",
+            "cannot 'yield from' a coroutine object in a non-coroutine generator",
+        ),
+        _ => super::iter_object(value),
+    }
+}
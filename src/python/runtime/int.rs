@@ -0,0 +1,785 @@
+// Python integers
+//
+//! Python's arbitrary-precision `int`.
+//!
+//! An [`Int`] is a machine integer while its value fits in 64 bits, and a
+//! sign and magnitude of 32-bit digits when it doesn't, so arithmetic on
+//! small values stays a checked machine operation that only allocates when
+//! it overflows.
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops;
+use std::rc::Rc;
+
+use super::{raise, Eval};
+
+/// A Python `int`
+///
+/// `Big` is only used for values that don't fit in an `i64`, so each value
+/// has one representation.
+#[derive(Debug, Clone)]
+pub enum Int {
+    Small(i64),
+    Big(Rc<Big>),
+}
+
+/// An integer too large for an `i64`
+#[derive(Debug, PartialEq, Eq)]
+pub struct Big {
+    negative: bool,
+    /// Base 2^32 digits, least significant first, without leading zeros
+    digits: Vec<u32>,
+}
+
+// The modulus of Python's hashes of numbers, 2^61 - 1
+const MODULUS: u64 = (1 << 61) - 1;
+
+impl Int {
+    // The value of a sign and magnitude.
+    fn from_parts(negative: bool, mut digits: Vec<u32>) -> Int {
+        trim(&mut digits);
+        if digits.len() <= 2 {
+            let magnitude = digits
+                .iter()
+                .rev()
+                .fold(0_u64, |value, &digit| value << 32 | digit as u64);
+            let value = match negative {
+                true => 0_i64.checked_sub_unsigned(magnitude),
+                false => i64::try_from(magnitude).ok(),
+            };
+            if let Some(value) = value {
+                return Int::Small(value);
+            }
+        }
+        Int::Big(Rc::new(Big { negative, digits }))
+    }
+
+    // The sign and magnitude of the value.
+    fn parts(&self) -> (bool, Vec<u32>) {
+        match self {
+            Int::Small(value) => (*value < 0, magnitude(value.unsigned_abs())),
+            Int::Big(big) => (big.negative, big.digits.clone()),
+        }
+    }
+
+    /// The value as an `i64`, if it fits.
+    pub fn to_i64(&self) -> Option<i64> {
+        match self {
+            Int::Small(value) => Some(*value),
+            Int::Big(_) => None,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        matches!(self, Int::Small(0))
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Int::Small(value) => *value < 0,
+            Int::Big(big) => big.negative,
+        }
+    }
+
+    /// `float(value)`, raising `OverflowError` if it is out of range.
+    pub fn to_f64(&self) -> Eval<f64> {
+        match self {
+            Int::Small(value) => Ok(*value as f64),
+            // Parsing the decimal digits rounds correctly.
+            Int::Big(_) => match self.to_string().parse::<f64>() {
+                Ok(value) if value.is_finite() => Ok(value),
+                _ => raise("OverflowError", "int too large to convert to float"),
+            },
+        }
+    }
+
+    /// The integer value of a finite float, truncated.
+    pub fn from_f64(value: f64) -> Int {
+        let value = value.trunc();
+        if value.abs() < 9.2e18 {
+            return Int::Small(value as i64);
+        }
+        // |value| >= 2^63, so it is a whole number with a positive exponent.
+        let bits = value.to_bits();
+        let mantissa = bits & ((1 << 52) - 1) | 1 << 52;
+        let exponent = ((bits >> 52) & 0x7ff) - 1075;
+        let digits = shl_digits(&magnitude(mantissa), exponent);
+        Int::from_parts(value < 0.0, digits)
+    }
+
+    /// Parse digits in a radix from 2 to 36, with an optional sign, and
+    /// underscores between digits, like `int(text, radix)`.
+    pub fn parse(text: &str, radix: u32) -> Option<Int> {
+        let text = text.trim();
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        if digits.is_empty() || digits.starts_with('_') || digits.ends_with('_')
+            || digits.contains("__")
+        {
+            return None;
+        }
+        let mut magnitude = Vec::new();
+        for ch in digits.chars().filter(|&ch| ch != '_') {
+            mul_add(&mut magnitude, radix, ch.to_digit(radix)?);
+        }
+        Some(Int::from_parts(negative, magnitude))
+    }
+
+    /// The digits of the magnitude in a radix from 2 to 36, in lowercase.
+    pub fn to_radix(&self, radix: u32) -> String {
+        let (_, mut digits) = self.parts();
+        if digits.is_empty() {
+            return "0".to_string();
+        }
+        // Take chunks of as many digits as fit in a u32 at a time.
+        let (mut chunk, mut width) = (radix, 1);
+        while let Some(next) = chunk.checked_mul(radix) {
+            chunk = next;
+            width += 1;
+        }
+        let mut chunks = Vec::new();
+        while !digits.is_empty() {
+            let (quotient, remainder) = divmod_small(&digits, chunk);
+            chunks.push(remainder);
+            digits = quotient;
+        }
+        let mut out = String::new();
+        for (i, mut value) in chunks.into_iter().rev().enumerate() {
+            let mut text = Vec::new();
+            while value > 0 || (i > 0 && text.len() < width) || text.is_empty() {
+                text.push(std::char::from_digit(value % radix, radix).unwrap());
+                value /= radix;
+            }
+            out.extend(text.iter().rev());
+        }
+        out
+    }
+
+    /// `-value`
+    pub fn neg(&self) -> Int {
+        match self {
+            Int::Small(value) => match value.checked_neg() {
+                Some(value) => Int::Small(value),
+                None => Int::from_parts(false, magnitude(value.unsigned_abs())),
+            },
+            Int::Big(big) => Int::from_parts(!big.negative, big.digits.clone()),
+        }
+    }
+
+    /// `abs(value)`
+    pub fn abs(&self) -> Int {
+        match self.is_negative() {
+            true => self.neg(),
+            false => self.clone(),
+        }
+    }
+
+    /// `~value`
+    pub fn invert(&self) -> Int {
+        match self {
+            Int::Small(value) => Int::Small(!value),
+            _ => (self + &Int::Small(1)).neg(),
+        }
+    }
+
+    /// `self // other`, and `self % other`, rounding towards negative
+    /// infinity.
+    pub fn div_mod(&self, other: &Int) -> Eval<(Int, Int)> {
+        if other.is_zero() {
+            return raise("ZeroDivisionError", "integer division or modulo by zero");
+        }
+        if let (Int::Small(a), Int::Small(b)) = (self, other) {
+            if let (Some(quotient), Some(remainder)) =
+                (a.checked_div(*b), a.checked_rem(*b))
+            {
+                return Ok(match remainder != 0 && (remainder < 0) != (*b < 0) {
+                    true => (Int::Small(quotient - 1), Int::Small(remainder + b)),
+                    false => (Int::Small(quotient), Int::Small(remainder)),
+                });
+            }
+        }
+        let (a_negative, a) = self.parts();
+        let (b_negative, b) = other.parts();
+        let (quotient, remainder) = divmod_digits(&a, &b);
+        let quotient = Int::from_parts(a_negative != b_negative, quotient);
+        let remainder = Int::from_parts(a_negative, remainder);
+        Ok(match !remainder.is_zero() && a_negative != b_negative {
+            true => (&quotient - &Int::Small(1), &remainder + other),
+            false => (quotient, remainder),
+        })
+    }
+
+    /// `self // other`
+    pub fn floor_div(&self, other: &Int) -> Eval<Int> {
+        Ok(self.div_mod(other)?.0)
+    }
+
+    /// `self % other`
+    pub fn modulo(&self, other: &Int) -> Eval<Int> {
+        if other.is_zero() {
+            return raise("ZeroDivisionError", "integer modulo by zero");
+        }
+        Ok(self.div_mod(other)?.1)
+    }
+
+    /// `self ** exponent`, with the exponent not negative.
+    pub fn pow(&self, exponent: &Int) -> Eval<Int> {
+        if exponent.is_negative() {
+            return raise("ValueError", "negative exponent");
+        }
+        let mut exponent = match exponent {
+            Int::Small(exponent) => *exponent as u64,
+            Int::Big(_) => {
+                return match self {
+                    Int::Small(0) | Int::Small(1) => Ok(self.clone()),
+                    Int::Small(-1) => Ok(Int::Small(match exponent.is_odd() {
+                        true => -1,
+                        false => 1,
+                    })),
+                    _ => raise("MemoryError", ""),
+                }
+            }
+        };
+        if let Int::Small(base) = self {
+            if let Some(value) = u32::try_from(exponent)
+                .ok()
+                .and_then(|exponent| base.checked_pow(exponent))
+            {
+                return Ok(Int::Small(value));
+            }
+        }
+        let mut result = Int::Small(1);
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+        Ok(result)
+    }
+
+    /// `pow(self, exponent, modulus)`.  A negative exponent raises the
+    /// inverse of `self` modulo `modulus`.
+    pub fn pow_mod(&self, exponent: &Int, modulus: &Int) -> Eval<Int> {
+        if modulus.is_zero() {
+            return raise("ValueError", "pow() 3rd argument cannot be 0");
+        }
+        if exponent.is_negative() {
+            return self.inverse(modulus)?.pow_mod(&exponent.neg(), modulus);
+        }
+        let one = Int::Small(1);
+        let mut result = one.modulo(modulus)?;
+        let mut base = self.modulo(modulus)?;
+        let mut exponent = exponent.clone();
+        while !exponent.is_zero() {
+            if exponent.is_odd() {
+                result = (&result * &base).modulo(modulus)?;
+            }
+            exponent = exponent.shift_right(&one)?;
+            base = (&base * &base).modulo(modulus)?;
+        }
+        Ok(result)
+    }
+
+    // The inverse of `self` modulo `modulus`, by the extended Euclidean
+    // algorithm.
+    fn inverse(&self, modulus: &Int) -> Eval<Int> {
+        let mut a = self.modulo(&modulus.abs())?;
+        let mut b = modulus.abs();
+        // `a` and `b` are `x` and `y` times `self`, modulo `modulus`.
+        let (mut x, mut y) = (Int::Small(1), Int::Small(0));
+        while !b.is_zero() {
+            let (quotient, remainder) = a.div_mod(&b)?;
+            a = std::mem::replace(&mut b, remainder);
+            let next = &x - &(&quotient * &y);
+            x = std::mem::replace(&mut y, next);
+        }
+        if a != Int::Small(1) {
+            return raise(
+                "ValueError",
+                "base is not invertible for the given modulus",
+            );
+        }
+        x.modulo(modulus)
+    }
+
+    fn is_odd(&self) -> bool {
+        match self {
+            Int::Small(value) => value & 1 == 1,
+            Int::Big(big) => big.digits[0] & 1 == 1,
+        }
+    }
+
+    // A shift count, raising if it is negative or too large.
+    fn shift_count(count: &Int) -> Eval<u64> {
+        match count {
+            _ if count.is_negative() => raise("ValueError", "negative shift count"),
+            Int::Small(count) => Ok(*count as u64),
+            Int::Big(_) => raise("OverflowError", "too many digits in integer"),
+        }
+    }
+
+    /// `self << count`
+    pub fn shift_left(&self, count: &Int) -> Eval<Int> {
+        let count = Int::shift_count(count)?;
+        if let Int::Small(value) = self {
+            if *value == 0 {
+                return Ok(Int::Small(0));
+            }
+            if count < 63 && value << count >> count == *value {
+                return Ok(Int::Small(value << count));
+            }
+        }
+        if count > 1 << 32 {
+            return raise("MemoryError", "");
+        }
+        let (negative, digits) = self.parts();
+        Ok(Int::from_parts(negative, shl_digits(&digits, count)))
+    }
+
+    /// `self >> count`, rounding towards negative infinity.
+    pub fn shift_right(&self, count: &Int) -> Eval<Int> {
+        if count.is_negative() {
+            return raise("ValueError", "negative shift count");
+        }
+        let count = match count {
+            Int::Small(count) => *count as u64,
+            Int::Big(_) => u64::MAX,
+        };
+        if let Int::Small(value) = self {
+            return Ok(Int::Small(value >> count.min(63)));
+        }
+        let (negative, digits) = self.parts();
+        Ok(match negative {
+            false => Int::from_parts(false, shr_digits(&digits, count)),
+            // -((|x| - 1) >> count) - 1
+            true => {
+                let less = sub_digits(&digits, &[1]);
+                Int::from_parts(false, shr_digits(&less, count)).invert()
+            }
+        })
+    }
+
+    // The value in two's complement with `len` digits.
+    fn twos_complement(&self, len: usize) -> Vec<u32> {
+        let (negative, mut digits) = self.parts();
+        digits.resize(len, 0);
+        if negative {
+            let mut carry = 1_u64;
+            for digit in &mut digits {
+                let value = (!*digit) as u64 + carry;
+                *digit = value as u32;
+                carry = value >> 32;
+            }
+        }
+        digits
+    }
+
+    // The value of digits in two's complement.
+    fn from_twos_complement(mut digits: Vec<u32>) -> Int {
+        let negative = digits.last().is_some_and(|digit| digit >> 31 == 1);
+        if negative {
+            let mut carry = 1_u64;
+            for digit in &mut digits {
+                let value = (!*digit) as u64 + carry;
+                *digit = value as u32;
+                carry = value >> 32;
+            }
+        }
+        Int::from_parts(negative, digits)
+    }
+
+    // A bitwise operator on the two's complements of the values.
+    fn bitwise(&self, other: &Int, op: impl Fn(u32, u32) -> u32) -> Int {
+        let len = self.parts().1.len().max(other.parts().1.len()) + 1;
+        let (a, b) = (self.twos_complement(len), other.twos_complement(len));
+        Int::from_twos_complement(a.iter().zip(&b).map(|(&a, &b)| op(a, b)).collect())
+    }
+
+    /// Python's `hash` of the value.
+    pub fn hash(&self) -> i64 {
+        let (negative, digits) = self.parts();
+        let hash = digits.iter().rev().fold(0_u64, |hash, &digit| {
+            (((hash as u128) << 32 | digit as u128) % MODULUS as u128) as u64
+        }) as i64;
+        match (negative, hash) {
+            (true, 1) => -2,
+            (true, hash) => -hash,
+            (false, hash) => hash,
+        }
+    }
+}
+
+/// Python's `hash` of a float, which equals the hash of an `int` of the
+/// same value.
+pub fn hash_float(value: f64) -> i64 {
+    if value.is_nan() {
+        return 0;
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { 314159 } else { -314159 };
+    }
+    if value.fract() == 0.0 {
+        return Int::from_f64(value).hash();
+    }
+    // The value modulo 2^61 - 1, from its mantissa and binary exponent.
+    let bits = value.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i64;
+    let (mut mantissa, mut exponent) = match exponent {
+        0 => (bits & ((1 << 52) - 1), -1074),
+        _ => (bits & ((1 << 52) - 1) | 1 << 52, exponent - 1075),
+    };
+    while mantissa & 1 == 0 {
+        mantissa >>= 1;
+        exponent += 1;
+    }
+    let mut hash = mantissa % MODULUS;
+    // Multiply by 2^exponent, where 2^61 is 1 modulo 2^61 - 1.
+    let exponent = exponent.rem_euclid(61) as u32;
+    hash = ((hash << exponent) & MODULUS) | hash >> (61 - exponent);
+    if hash >= MODULUS {
+        hash -= MODULUS;
+    }
+    let hash = hash as i64;
+    match (value < 0.0, hash) {
+        (true, 1) => -2,
+        (true, hash) => -hash,
+        (false, hash) => hash,
+    }
+}
+
+impl From<i64> for Int {
+    fn from(value: i64) -> Int {
+        Int::Small(value)
+    }
+}
+
+impl From<u128> for Int {
+    fn from(value: u128) -> Int {
+        let digits = (0..4).map(|i| (value >> (32 * i)) as u32).collect();
+        Int::from_parts(false, digits)
+    }
+}
+
+impl PartialEq for Int {
+    fn eq(&self, other: &Int) -> bool {
+        match (self, other) {
+            (Int::Small(a), Int::Small(b)) => a == b,
+            (Int::Big(a), Int::Big(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Int {}
+
+impl PartialOrd for Int {
+    fn partial_cmp(&self, other: &Int) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Int {
+    fn cmp(&self, other: &Int) -> Ordering {
+        if let (Int::Small(a), Int::Small(b)) = (self, other) {
+            return a.cmp(b);
+        }
+        let (a_negative, a) = self.parts();
+        let (b_negative, b) = other.parts();
+        match (a_negative, b_negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_digits(&a, &b),
+            (true, true) => cmp_digits(&b, &a),
+        }
+    }
+}
+
+impl ops::Add for &Int {
+    type Output = Int;
+
+    fn add(self, other: &Int) -> Int {
+        if let (Int::Small(a), Int::Small(b)) = (self, other) {
+            if let Some(value) = a.checked_add(*b) {
+                return Int::Small(value);
+            }
+        }
+        let (a_negative, a) = self.parts();
+        let (b_negative, b) = other.parts();
+        if a_negative == b_negative {
+            return Int::from_parts(a_negative, add_digits(&a, &b));
+        }
+        match cmp_digits(&a, &b) {
+            Ordering::Less => Int::from_parts(b_negative, sub_digits(&b, &a)),
+            _ => Int::from_parts(a_negative, sub_digits(&a, &b)),
+        }
+    }
+}
+
+impl ops::Sub for &Int {
+    type Output = Int;
+
+    fn sub(self, other: &Int) -> Int {
+        if let (Int::Small(a), Int::Small(b)) = (self, other) {
+            if let Some(value) = a.checked_sub(*b) {
+                return Int::Small(value);
+            }
+        }
+        ops::Add::add(self, &other.neg())
+    }
+}
+
+impl ops::Mul for &Int {
+    type Output = Int;
+
+    fn mul(self, other: &Int) -> Int {
+        if let (Int::Small(a), Int::Small(b)) = (self, other) {
+            if let Some(value) = a.checked_mul(*b) {
+                return Int::Small(value);
+            }
+        }
+        let (a_negative, a) = self.parts();
+        let (b_negative, b) = other.parts();
+        Int::from_parts(a_negative != b_negative, mul_digits(&a, &b))
+    }
+}
+
+impl ops::BitAnd for &Int {
+    type Output = Int;
+
+    fn bitand(self, other: &Int) -> Int {
+        match (self, other) {
+            (Int::Small(a), Int::Small(b)) => Int::Small(a & b),
+            _ => self.bitwise(other, |a, b| a & b),
+        }
+    }
+}
+
+impl ops::BitOr for &Int {
+    type Output = Int;
+
+    fn bitor(self, other: &Int) -> Int {
+        match (self, other) {
+            (Int::Small(a), Int::Small(b)) => Int::Small(a | b),
+            _ => self.bitwise(other, |a, b| a | b),
+        }
+    }
+}
+
+impl ops::BitXor for &Int {
+    type Output = Int;
+
+    fn bitxor(self, other: &Int) -> Int {
+        match (self, other) {
+            (Int::Small(a), Int::Small(b)) => Int::Small(a ^ b),
+            _ => self.bitwise(other, |a, b| a ^ b),
+        }
+    }
+}
+
+impl fmt::Display for Int {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Int::Small(value) => write!(f, "{}", value),
+            Int::Big(big) => {
+                if big.negative {
+                    write!(f, "-")?;
+                }
+                write!(f, "{}", self.to_radix(10))
+            }
+        }
+    }
+}
+
+// The digits of a magnitude.
+fn magnitude(value: u64) -> Vec<u32> {
+    let mut digits = vec![value as u32, (value >> 32) as u32];
+    trim(&mut digits);
+    digits
+}
+
+// Remove leading zero digits.
+fn trim(digits: &mut Vec<u32>) {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+}
+
+fn cmp_digits(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_digits(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut sum = Vec::with_capacity(a.len() + 1);
+    let mut carry = 0_u64;
+    for (i, &digit) in a.iter().enumerate() {
+        let value = digit as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        sum.push(value as u32);
+        carry = value >> 32;
+    }
+    if carry > 0 {
+        sum.push(carry as u32);
+    }
+    sum
+}
+
+// `a - b`, with `a >= b`.
+fn sub_digits(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(a.len());
+    let mut borrow = 0_i64;
+    for (i, &digit) in a.iter().enumerate() {
+        let value = digit as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        difference.push(value as u32);
+        borrow = (value < 0) as i64;
+    }
+    trim(&mut difference);
+    difference
+}
+
+fn mul_digits(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut product = vec![0_u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0_u64;
+        for (j, &y) in b.iter().enumerate() {
+            let value = x as u64 * y as u64 + product[i + j] as u64 + carry;
+            product[i + j] = value as u32;
+            carry = value >> 32;
+        }
+        product[i + b.len()] = carry as u32;
+    }
+    trim(&mut product);
+    product
+}
+
+// `digits * factor + addend`, in place.
+fn mul_add(digits: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = addend as u64;
+    for digit in digits.iter_mut() {
+        let value = *digit as u64 * factor as u64 + carry;
+        *digit = value as u32;
+        carry = value >> 32;
+    }
+    if carry > 0 {
+        digits.push(carry as u32);
+    }
+}
+
+fn divmod_small(digits: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0; digits.len()];
+    let mut remainder = 0_u64;
+    for (i, &digit) in digits.iter().enumerate().rev() {
+        let value = remainder << 32 | digit as u64;
+        quotient[i] = (value / divisor as u64) as u32;
+        remainder = value % divisor as u64;
+    }
+    trim(&mut quotient);
+    (quotient, remainder as u32)
+}
+
+// The quotient and remainder of magnitudes, by Knuth's algorithm D.
+fn divmod_digits(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if cmp_digits(a, b) == Ordering::Less {
+        return (Vec::new(), a.to_vec());
+    }
+    if b.len() == 1 {
+        let (quotient, remainder) = divmod_small(a, b[0]);
+        let mut remainder = vec![remainder];
+        trim(&mut remainder);
+        return (quotient, remainder);
+    }
+    // Normalize so the divisor's top digit has its high bit set.
+    let shift = b.last().unwrap().leading_zeros() as u64;
+    let v = shl_digits(b, shift);
+    let mut u = shl_digits(a, shift);
+    u.resize(a.len() + 1, 0);
+    let n = v.len();
+    let m = u.len() - n - 1;
+    let base = 1_u64 << 32;
+    let mut quotient = vec![0_u32; m + 1];
+    for j in (0..=m).rev() {
+        let top = (u[j + n] as u64) << 32 | u[j + n - 1] as u64;
+        let mut estimate = top / v[n - 1] as u64;
+        let mut rest = top % v[n - 1] as u64;
+        while estimate >= base
+            || estimate * v[n - 2] as u64 > (rest << 32 | u[j + n - 2] as u64)
+        {
+            estimate -= 1;
+            rest += v[n - 1] as u64;
+            if rest >= base {
+                break;
+            }
+        }
+        // Subtract estimate * v from the current digits of u.
+        let mut borrow = 0_i64;
+        let mut carry = 0_u64;
+        for i in 0..n {
+            let product = estimate * v[i] as u64 + carry;
+            carry = product >> 32;
+            let value = u[i + j] as i64 - borrow - (product & 0xffff_ffff) as i64;
+            u[i + j] = value as u32;
+            borrow = (value < 0) as i64;
+        }
+        let value = u[j + n] as i64 - borrow - carry as i64;
+        u[j + n] = value as u32;
+        if value < 0 {
+            // The estimate was one too large: add v back.
+            estimate -= 1;
+            let mut carry = 0_u64;
+            for i in 0..n {
+                let sum = u[i + j] as u64 + v[i] as u64 + carry;
+                u[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u32);
+        }
+        quotient[j] = estimate as u32;
+    }
+    trim(&mut quotient);
+    u.truncate(n);
+    let remainder = shr_digits(&u, shift);
+    (quotient, remainder)
+}
+
+fn shl_digits(digits: &[u32], count: u64) -> Vec<u32> {
+    let (whole, bits) = ((count / 32) as usize, (count % 32) as u32);
+    let mut shifted = vec![0_u32; whole];
+    let mut carry = 0_u32;
+    for &digit in digits {
+        match bits {
+            0 => shifted.push(digit),
+            _ => {
+                shifted.push(digit << bits | carry);
+                carry = digit >> (32 - bits);
+            }
+        }
+    }
+    shifted.push(carry);
+    trim(&mut shifted);
+    shifted
+}
+
+fn shr_digits(digits: &[u32], count: u64) -> Vec<u32> {
+    let whole = usize::try_from(count / 32).unwrap_or(usize::MAX);
+    let bits = (count % 32) as u32;
+    if whole >= digits.len() {
+        return Vec::new();
+    }
+    let digits = &digits[whole..];
+    let mut shifted: Vec<u32> = (0..digits.len())
+        .map(|i| match bits {
+            0 => digits[i],
+            _ => digits[i] >> bits | digits.get(i + 1).map_or(0, |next| next << (32 - bits)),
+        })
+        .collect();
+    trim(&mut shifted);
+    shifted
+}
@@ -0,0 +1,675 @@
+// Python methods
+//
+//! The methods of the built-in types.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::{
+    arg_str, arity, asyncio, equal, format, index, index_arg, items, iter,
+    pad, raise, repr_str, sort, to_exception, Dict, Eval, Generator, Set,
+    Value,
+};
+
+/// `receiver.name(*args)` for a method of a built-in type.
+pub fn call_method(receiver: &Value, name: &str, args: Vec<Value>)
+    -> Eval<Value>
+{
+    match receiver {
+        Value::List(items) => list_method(items, name, args),
+        Value::Str(string) => str_method(string, name, args),
+        Value::Dict(dict) => dict_method(dict, name, args),
+        Value::Set(set) => set_method(set, name, args),
+        Value::Tuple(items) => match name {
+            "index" | "count" => {
+                let list = Rc::new(RefCell::new(items.to_vec()));
+                list_method(&list, name, args)
+            }
+            _ => no_attribute(receiver, name),
+        },
+        Value::Generator(generator) => generator_method(generator, name, args),
+        Value::Module("asyncio") => asyncio::call(name, args),
+        _ => no_attribute(receiver, name),
+    }
+}
+
+fn no_attribute<T>(receiver: &Value, name: &str) -> Eval<T> {
+    raise("AttributeError", format!(
+        "'{}' object has no attribute '{}'",
+        receiver.type_name(),
+        name,
+    ))
+}
+
+fn list_method(items: &Rc<RefCell<Vec<Value>>>, name: &str, args: Vec<Value>)
+    -> Eval<Value>
+{
+    let receiver = Value::List(items.clone());
+    let mut args = args.into_iter();
+    let count = args.len();
+    let (min, max) = match name {
+        "append" | "remove" | "count" | "extend" => (1, 1),
+        "insert" => (2, 2),
+        "pop" => (0, 1),
+        "index" => (1, 3),
+        "reverse" | "copy" | "clear" | "sort" => (0, 0),
+        _ => return no_attribute(&receiver, name),
+    };
+    arity(name, &vec![Value::None; count], min, max)?;
+    let mut arg = || args.next().unwrap();
+    match name {
+        "append" => items.borrow_mut().push(arg()),
+        "extend" => {
+            let new = self::items(&arg())?;
+            items.borrow_mut().extend(new);
+        }
+        "insert" => {
+            let i = index_arg(&arg())?;
+            let mut items = items.borrow_mut();
+            let len = items.len() as i64;
+            let i = if i < 0 { (i + len).max(0) } else { i.min(len) };
+            items.insert(i as usize, arg());
+        }
+        "pop" => {
+            let mut items = items.borrow_mut();
+            if items.is_empty() {
+                return raise("IndexError", "pop from empty list");
+            }
+            let i = match count {
+                0 => items.len() - 1,
+                _ => index(&arg(), items.len(), "pop")?,
+            };
+            return Ok(items.remove(i));
+        }
+        "remove" => {
+            let item = arg();
+            let i = items.borrow().iter().position(|x| equal(x, &item));
+            match i {
+                Some(i) => drop(items.borrow_mut().remove(i)),
+                None => return raise("ValueError", "list.remove(x): x not in list"),
+            }
+        }
+        "index" => {
+            let item = arg();
+            let items = items.borrow();
+            let len = items.len() as i64;
+            let bound = |value: Option<Value>, default| -> Eval<i64> {
+                Ok(match value {
+                    None => default,
+                    Some(value) => {
+                        let i = index_arg(&value)?;
+                        if i < 0 { (i + len).max(0) } else { i.min(len) }
+                    }
+                })
+            };
+            let start = bound(args.next(), 0)?;
+            let end = bound(args.next(), len)?;
+            let found = (start..end.max(start))
+                .find(|&i| equal(&items[i as usize], &item));
+            return match found {
+                Some(i) => Ok(Value::int(i)),
+                None => raise("ValueError", format!(
+                    "{} is not in list",
+                    item.repr(),
+                )),
+            };
+        }
+        "count" => {
+            let item = arg();
+            let count = items.borrow().iter().filter(|x| equal(x, &item)).count();
+            return Ok(Value::int(count as i64));
+        }
+        "reverse" => items.borrow_mut().reverse(),
+        "copy" => return Ok(Value::list(items.borrow().clone())),
+        "clear" => items.borrow_mut().clear(),
+        _ => {
+            let mut sorted = items.borrow().clone();
+            sort(&mut sorted, None, false)?;
+            *items.borrow_mut() = sorted;
+        }
+    }
+    Ok(Value::None)
+}
+
+fn str_method(string: &Rc<str>, name: &str, args: Vec<Value>) -> Eval<Value> {
+    let receiver = Value::Str(string.clone());
+    let (min, max) = match name {
+        "upper" | "lower" | "isdigit" | "isalpha" | "isalnum" | "isspace"
+        | "isupper" | "islower" | "title" | "capitalize" | "swapcase"
+        | "splitlines" => (0, 0),
+        "strip" | "lstrip" | "rstrip" => (0, 1),
+        "split" | "rsplit" => (0, 2),
+        "join" | "zfill" => (1, 1),
+        "startswith" | "endswith" | "find" | "rfind" | "index" | "count" => {
+            (1, 1)
+        }
+        "replace" => (2, 3),
+        "center" | "ljust" | "rjust" => (1, 2),
+        "format" => (0, usize::MAX),
+        _ => return no_attribute(&receiver, name),
+    };
+    arity(name, &args, min, max)?;
+    let string: &str = string;
+    let text = |value: String| Ok(Value::str(&value));
+    let chars = |args: &[Value]| -> Eval<Option<Vec<char>>> {
+        match args.first() {
+            None | Some(Value::None) => Ok(None),
+            Some(value) => Ok(Some(arg_str(name, value)?.chars().collect())),
+        }
+    };
+    match name {
+        "upper" => text(string.to_uppercase()),
+        "lower" => text(string.to_lowercase()),
+        "swapcase" => text(string.chars().map(|ch| match ch.is_uppercase() {
+            true => ch.to_lowercase().collect::<String>(),
+            false => ch.to_uppercase().collect(),
+        }).collect()),
+        "title" | "capitalize" => {
+            let mut out = String::new();
+            let mut start = true;
+            for ch in string.chars() {
+                match start {
+                    true => out.extend(ch.to_uppercase()),
+                    false => out.extend(ch.to_lowercase()),
+                }
+                start = name == "title" && !ch.is_alphabetic();
+            }
+            text(out)
+        }
+        "isdigit" | "isalpha" | "isalnum" | "isspace" => {
+            let test = |ch: char| match name {
+                "isdigit" => ch.is_ascii_digit(),
+                "isalpha" => ch.is_alphabetic(),
+                "isalnum" => ch.is_alphanumeric(),
+                _ => ch.is_whitespace(),
+            };
+            Ok(Value::Bool(!string.is_empty() && string.chars().all(test)))
+        }
+        "isupper" | "islower" => {
+            let cased = string.chars().any(|ch| ch.is_alphabetic());
+            let wrong = string.chars().any(|ch| match name {
+                "isupper" => ch.is_lowercase(),
+                _ => ch.is_uppercase(),
+            });
+            Ok(Value::Bool(cased && !wrong))
+        }
+        "strip" | "lstrip" | "rstrip" => {
+            let chars = chars(&args)?;
+            let strip = |ch: char| match &chars {
+                Some(chars) => chars.contains(&ch),
+                None => ch.is_whitespace(),
+            };
+            text(match name {
+                "strip" => string.trim_matches(strip),
+                "lstrip" => string.trim_start_matches(strip),
+                _ => string.trim_end_matches(strip),
+            }.to_string())
+        }
+        "split" | "rsplit" => {
+            let max = match args.get(1) {
+                Some(max) => index_arg(max)?,
+                None => -1,
+            };
+            let max = if max < 0 { usize::MAX } else { max as usize };
+            let parts = match args.first() {
+                None | Some(Value::None) => split_whitespace(string, max, name == "rsplit"),
+                Some(sep) => {
+                    let sep = arg_str(name, sep)?;
+                    if sep.is_empty() {
+                        return raise("ValueError", "empty separator");
+                    }
+                    match (name, max) {
+                        (_, usize::MAX) => string.split(sep).collect(),
+                        ("split", _) => string.splitn(max + 1, sep).collect(),
+                        _ => {
+                            let mut parts: Vec<_> =
+                                string.rsplitn(max + 1, sep).collect();
+                            parts.reverse();
+                            parts
+                        }
+                    }
+                }
+            };
+            Ok(Value::list(parts.into_iter().map(Value::str).collect()))
+        }
+        "splitlines" => Ok(Value::list(string.lines().map(Value::str).collect())),
+        "join" => {
+            let mut out = String::new();
+            for (i, item) in iter(&args[0])?.enumerate() {
+                if i > 0 {
+                    out.push_str(string);
+                }
+                match item? {
+                    Value::Str(item) => out.push_str(&item),
+                    item => {
+                        return raise("TypeError", format!(
+                            "sequence item {}: expected str instance, {} found",
+                            i,
+                            item.type_name(),
+                        ))
+                    }
+                }
+            }
+            text(out)
+        }
+        "startswith" | "endswith" => {
+            let prefixes = match &args[0] {
+                Value::Tuple(items) => items.to_vec(),
+                value => vec![value.clone()],
+            };
+            for prefix in &prefixes {
+                let prefix = arg_str(name, prefix)?;
+                let found = match name {
+                    "startswith" => string.starts_with(prefix),
+                    _ => string.ends_with(prefix),
+                };
+                if found {
+                    return Ok(Value::Bool(true));
+                }
+            }
+            Ok(Value::Bool(false))
+        }
+        "find" | "rfind" | "index" => {
+            let part = arg_str(name, &args[0])?;
+            let found = match name {
+                "rfind" => string.rfind(part),
+                _ => string.find(part),
+            };
+            match found {
+                Some(i) => Ok(Value::int(string[..i].chars().count() as i64)),
+                None if name == "index" => raise("ValueError", "substring not found"),
+                None => Ok(Value::int(-1)),
+            }
+        }
+        "count" => {
+            let part = arg_str(name, &args[0])?;
+            let count = match part.is_empty() {
+                true => string.chars().count() + 1,
+                false => string.matches(part).count(),
+            };
+            Ok(Value::int(count as i64))
+        }
+        "replace" => {
+            let old = arg_str(name, &args[0])?;
+            let new = arg_str(name, &args[1])?;
+            match args.get(2).map(index_arg).transpose()? {
+                Some(count) if count >= 0 => {
+                    text(string.replacen(old, new, count as usize))
+                }
+                _ => text(string.replace(old, new)),
+            }
+        }
+        "zfill" => {
+            let width = index_arg(&args[0])?.max(0) as usize;
+            let len = string.chars().count();
+            if len >= width {
+                return text(string.to_string());
+            }
+            let (sign, digits) = match string.strip_prefix(['+', '-']) {
+                Some(digits) => (&string[..1], digits),
+                None => ("", string),
+            };
+            text(format!("{}{}{}", sign, "0".repeat(width - len), digits))
+        }
+        "center" | "ljust" | "rjust" => {
+            let width = index_arg(&args[0])?.max(0) as usize;
+            let fill = match args.get(1) {
+                Some(fill) => {
+                    let fill = arg_str(name, fill)?;
+                    let mut chars = fill.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(ch), None) => ch,
+                        _ => {
+                            return raise(
+                                "TypeError",
+                                "The fill character must be exactly one \
+                                    character long",
+                            )
+                        }
+                    }
+                }
+                None => ' ',
+            };
+            let align = match name {
+                "center" => '^',
+                "ljust" => '<',
+                _ => '>',
+            };
+            text(pad(string, fill, align, width))
+        }
+        _ => text(format_method(string, &args)?),
+    }
+}
+
+// `str.split()` without a separator.
+fn split_whitespace(string: &str, max: usize, reverse: bool) -> Vec<&str> {
+    if !reverse {
+        let mut parts = Vec::new();
+        let mut rest = string.trim_start();
+        while !rest.is_empty() {
+            if parts.len() == max {
+                parts.push(rest);
+                break;
+            }
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            parts.push(&rest[..end]);
+            rest = rest[end..].trim_start();
+        }
+        return parts;
+    }
+    let mut parts = Vec::new();
+    let mut rest = string.trim_end();
+    while !rest.is_empty() {
+        if parts.len() == max {
+            parts.push(rest);
+            break;
+        }
+        let start = rest.rfind(char::is_whitespace).map_or(0, |i| {
+            i + rest[i..].chars().next().unwrap().len_utf8()
+        });
+        parts.push(&rest[start..]);
+        rest = rest[..start].trim_end();
+    }
+    parts.reverse();
+    parts
+}
+
+// `string.format(*args)` with positional fields.
+fn format_method(string: &str, args: &[Value]) -> Eval<String> {
+    let mut out = String::new();
+    let mut next = 0;
+    let mut chars = string.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let mut field = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) => field.push(ch),
+                        None => {
+                            return raise(
+                                "ValueError",
+                                "expected '}' before end of string",
+                            )
+                        }
+                    }
+                }
+                let (name, spec) = match field.split_once(':') {
+                    Some((name, spec)) => (name.to_string(), spec.to_string()),
+                    None => (field, String::new()),
+                };
+                let (name, conversion) = match name.split_once('!') {
+                    Some((name, conversion)) => {
+                        (name.to_string(), conversion.chars().next())
+                    }
+                    None => (name, None),
+                };
+                let i = match name.is_empty() {
+                    true => {
+                        next += 1;
+                        next - 1
+                    }
+                    false => match name.parse::<usize>() {
+                        Ok(i) => i,
+                        Err(_) => return raise("KeyError", repr_str(&name)),
+                    },
+                };
+                let value = match args.get(i) {
+                    Some(value) => value,
+                    None => {
+                        return raise("IndexError", format!(
+                            "Replacement index {} out of range for positional \
+                                args tuple",
+                            i,
+                        ))
+                    }
+                };
+                let value = match conversion {
+                    Some('r') | Some('a') => Value::str(&value.repr()),
+                    Some('s') => Value::str(&value.to_string()),
+                    _ => value.clone(),
+                };
+                out.push_str(&format(&value, &spec)?);
+            }
+            '}' => {
+                return raise(
+                    "ValueError",
+                    "Single '}' encountered in format string",
+                )
+            }
+            _ => out.push(ch),
+        }
+    }
+    Ok(out)
+}
+
+fn generator_method(generator: &Rc<Generator>, name: &str, args: Vec<Value>)
+    -> Eval<Value>
+{
+    let (min, max) = match name {
+        "send" | "throw" => (1, 1),
+        "close" => (0, 0),
+        _ => return no_attribute(&Value::Generator(generator.clone()), name),
+    };
+    arity(name, &args, min, max)?;
+    match name {
+        "send" => generator.send(args[0].clone()),
+        "throw" => match &args[0] {
+            Value::Exception(_) | Value::Class(_) => {
+                generator.throw(to_exception(&args[0]))
+            }
+            arg => raise("TypeError", format!(
+                "exceptions must be classes or instances deriving from \
+                    BaseException, not {}",
+                arg.type_name(),
+            )),
+        },
+        _ => generator.close().map(|()| Value::None),
+    }
+}
+
+fn dict_method(dict: &Rc<RefCell<Dict>>, name: &str, args: Vec<Value>)
+    -> Eval<Value>
+{
+    let receiver = Value::Dict(dict.clone());
+    let (min, max) = match name {
+        "keys" | "values" | "items" | "copy" | "clear" | "popitem" => (0, 0),
+        "get" | "pop" | "setdefault" => (1, 2),
+        "update" => (1, 1),
+        _ => return no_attribute(&receiver, name),
+    };
+    arity(name, &args, min, max)?;
+    let default = || args.get(1).cloned().unwrap_or(Value::None);
+    match name {
+        "keys" => Ok(Value::list(dict.borrow().keys())),
+        "values" => Ok(Value::list(dict.borrow().values())),
+        "items" => Ok(Value::list(dict.borrow().items())),
+        "copy" => Ok(Value::dict(dict.borrow().clone())),
+        "clear" => {
+            dict.borrow_mut().clear();
+            Ok(Value::None)
+        }
+        "popitem" => match dict.borrow_mut().pop_last() {
+            Some((key, value)) => Ok(Value::tuple(vec![key, value])),
+            None => raise("KeyError", "'popitem(): dictionary is empty'"),
+        },
+        "get" => Ok(dict.borrow().get(&args[0])?.unwrap_or_else(default)),
+        "pop" => match dict.borrow_mut().remove(&args[0])? {
+            Some(value) => Ok(value),
+            None if args.len() == 2 => Ok(default()),
+            None => raise("KeyError", args[0].repr()),
+        },
+        "setdefault" => {
+            let found = dict.borrow().get(&args[0])?;
+            match found {
+                Some(value) => Ok(value),
+                None => {
+                    dict.borrow_mut().insert(args[0].clone(), default())?;
+                    Ok(default())
+                }
+            }
+        }
+        _ => {
+            let entries: Vec<(Value, Value)> = match &args[0] {
+                Value::Dict(other) => other
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+                other => items(other)?
+                    .into_iter()
+                    .map(|item| match items(&item)?.as_slice() {
+                        [key, value] => Ok((key.clone(), value.clone())),
+                        _ => raise(
+                            "ValueError",
+                            "dictionary update sequence element has wrong \
+                                length",
+                        ),
+                    })
+                    .collect::<Eval<_>>()?,
+            };
+            let mut dict = dict.borrow_mut();
+            for (key, value) in entries {
+                dict.insert(key, value)?;
+            }
+            Ok(Value::None)
+        }
+    }
+}
+
+fn set_method(set: &Rc<RefCell<Set>>, name: &str, args: Vec<Value>)
+    -> Eval<Value>
+{
+    let receiver = Value::Set(set.clone());
+    let (min, max) = match name {
+        "pop" | "clear" | "copy" => (0, 0),
+        "add" | "discard" | "remove" | "issubset" | "issuperset" | "isdisjoint"
+        | "symmetric_difference" | "symmetric_difference_update" => (1, 1),
+        "update" | "union" | "intersection" | "difference"
+        | "intersection_update" | "difference_update" => (0, usize::MAX),
+        _ => return no_attribute(&receiver, name),
+    };
+    arity(name, &args, min, max)?;
+    let other = || -> Eval<Set> {
+        match &args[0] {
+            Value::Set(other) => Ok(other.borrow().clone()),
+            other => super::to_set(other),
+        }
+    };
+    let result = match name {
+        "add" => {
+            set.borrow_mut().insert(args[0].clone())?;
+            return Ok(Value::None);
+        }
+        "discard" => {
+            set.borrow_mut().remove(&args[0])?;
+            return Ok(Value::None);
+        }
+        "remove" => match set.borrow_mut().remove(&args[0])? {
+            true => return Ok(Value::None),
+            false => return raise("KeyError", args[0].repr()),
+        },
+        "pop" => match set.borrow_mut().pop() {
+            Some(item) => return Ok(item),
+            None => return raise("KeyError", "'pop from an empty set'"),
+        },
+        "clear" => {
+            set.borrow_mut().clear();
+            return Ok(Value::None);
+        }
+        "issubset" | "issuperset" | "isdisjoint" => {
+            let (set, other) = (set.borrow(), other()?);
+            let (a, b) = match name {
+                "issuperset" => (&other, &*set),
+                _ => (&*set, &other),
+            };
+            let mut found = a.iter().map(|item| b.contains(item));
+            return Ok(Value::Bool(match name {
+                "isdisjoint" => !found.any(|found| matches!(found, Ok(true))),
+                _ => found.all(|found| matches!(found, Ok(true))),
+            }));
+        }
+        "copy" => set.borrow().clone(),
+        "union" | "update" => set_union(&set.borrow(), &args)?,
+        "intersection" | "intersection_update" => {
+            set_intersection(&set.borrow(), &args)?
+        }
+        "difference" | "difference_update" => {
+            set_difference(&set.borrow(), &args)?
+        }
+        _ => set_symmetric_difference(&set.borrow(), &other()?)?,
+    };
+    match name.ends_with("update") {
+        true => {
+            *set.borrow_mut() = result;
+            Ok(Value::None)
+        }
+        false => Ok(Value::set(result)),
+    }
+}
+
+/// The items of a set and of each of some iterables.
+pub(super) fn set_union(set: &Set, others: &[Value]) -> Eval<Set> {
+    let mut union = set.clone();
+    for other in others {
+        for item in iter(other)? {
+            union.insert(item?)?;
+        }
+    }
+    Ok(union)
+}
+
+/// The items of a set that are in each of some iterables.
+pub(super) fn set_intersection(set: &Set, others: &[Value]) -> Eval<Set> {
+    let mut intersection = set.clone();
+    for other in others {
+        let other = match other {
+            Value::Set(other) => other.borrow().clone(),
+            other => super::to_set(other)?,
+        };
+        let mut kept = Set::default();
+        for item in intersection.iter() {
+            if other.contains(item)? {
+                kept.insert(item.clone())?;
+            }
+        }
+        intersection = kept;
+    }
+    Ok(intersection)
+}
+
+/// The items of a set that aren't in any of some iterables.
+pub(super) fn set_difference(set: &Set, others: &[Value]) -> Eval<Set> {
+    let mut difference = set.clone();
+    for other in others {
+        for item in iter(other)? {
+            difference.remove(&item?)?;
+        }
+    }
+    Ok(difference)
+}
+
+/// The items in exactly one of two sets.
+pub(super) fn set_symmetric_difference(a: &Set, b: &Set) -> Eval<Set> {
+    let mut difference = Set::default();
+    for item in a.iter() {
+        if !b.contains(item)? {
+            difference.insert(item.clone())?;
+        }
+    }
+    for item in b.iter() {
+        if !a.contains(item)? {
+            difference.insert(item.clone())?;
+        }
+    }
+    Ok(difference)
+}
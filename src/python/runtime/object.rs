@@ -0,0 +1,248 @@
+// Python classes
+//
+//! Classes defined by `class` statements, their instances, and the methods
+//! instances are bound to.
+//!
+//! A class has a name, at most one base class and a table of attributes,
+//! which its `def` statements put its methods in.  Looking an attribute up
+//! on an instance finds it among the instance's own attributes, then in its
+//! class and the class's bases; a function found in a class is bound to the
+//! instance, making a [`Method`] that passes the instance as the first
+//! argument when it is called.  Special methods other than `__init__` are
+//! never looked up: instances compare, hash and print by identity.
+//!
+//! Of the attributes of built-in objects, only those of exceptions can be
+//! used: the `args` they were made with, and the `value` of a
+//! `StopIteration`.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use super::{isinstance, raise, Eval, Exception, Value};
+
+/// A class defined by a `class` statement
+pub struct Class {
+    pub name: &'static str,
+    pub base: Option<Rc<Class>>,
+    attributes: RefCell<HashMap<Rc<str>, Value>>,
+}
+
+impl fmt::Debug for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<class '__main__.{}'>", self.name)
+    }
+}
+
+impl Class {
+    pub fn new(name: &'static str, base: Option<Rc<Class>>) -> Self {
+        Class { name, base, attributes: RefCell::new(HashMap::new()) }
+    }
+
+    /// The attribute of the class, or of the nearest base class that has it.
+    pub fn lookup(&self, name: &str) -> Option<Value> {
+        let mut class = self;
+        loop {
+            if let Some(value) = class.attributes.borrow().get(name) {
+                return Some(value.clone());
+            }
+            class = class.base.as_deref()?;
+        }
+    }
+
+    pub fn set(&self, name: &str, value: Value) {
+        self.attributes.borrow_mut().insert(name.into(), value);
+    }
+
+    /// Whether the class is `base` or derives from it.
+    pub fn is_subclass(&self, base: &Class) -> bool {
+        let mut class = self;
+        loop {
+            if std::ptr::eq(class, base) {
+                return true;
+            }
+            match class.base.as_deref() {
+                Some(parent) => class = parent,
+                None => return false,
+            }
+        }
+    }
+}
+
+/// An instance of a class defined by a `class` statement
+pub struct Object {
+    pub class: Rc<Class>,
+    attributes: RefCell<HashMap<Rc<str>, Value>>,
+}
+
+impl fmt::Debug for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<__main__.{} object>", self.class.name)
+    }
+}
+
+impl Object {
+    pub fn new(class: Rc<Class>) -> Self {
+        Object { class, attributes: RefCell::new(HashMap::new()) }
+    }
+
+    /// Call `f` with each value the instance's attributes hold, returning
+    /// false if they are being changed and can't be looked into.
+    pub(super) fn visit(&self, f: &mut dyn FnMut(&Value)) -> bool {
+        match self.attributes.try_borrow() {
+            Ok(attributes) => attributes.values().for_each(f),
+            Err(_) => return false,
+        }
+        true
+    }
+
+    /// Remove the instance's attributes, returning their values.
+    pub(super) fn clear(&self) -> Vec<Value> {
+        let attributes = std::mem::take(&mut *self.attributes.borrow_mut());
+        attributes.into_values().collect()
+    }
+}
+
+/// A function bound to the instance it was looked up on
+#[derive(Debug)]
+pub struct Method {
+    pub receiver: Value,
+    pub function: Value,
+}
+
+// A function found in a class, bound to the instance it was looked up on.
+fn bind(value: Value, receiver: &Value) -> Value {
+    match value {
+        Value::Function(_) => Value::Method(Rc::new(Method {
+            receiver: receiver.clone(),
+            function: value,
+        })),
+        value => value,
+    }
+}
+
+/// `value.name`
+pub fn get_attribute(value: &Value, name: &str) -> Eval<Value> {
+    match value {
+        Value::Object(object) => {
+            if let Some(found) = object.attributes.borrow().get(name) {
+                return Ok(found.clone());
+            }
+            match (object.class.lookup(name), name) {
+                (Some(found), _) => Ok(bind(found, value)),
+                (None, "__class__") => Ok(Value::Type(object.class.clone())),
+                (None, _) => no_attribute(value, name),
+            }
+        }
+        Value::Type(class) => match (class.lookup(name), name) {
+            (Some(found), _) => Ok(found),
+            (None, "__name__") => Ok(Value::str(class.name)),
+            (None, _) => no_attribute(value, name),
+        },
+        Value::Exception(exception) => match name {
+            "args" => Ok(Value::tuple(exception.args.to_vec())),
+            "value" if exception.is_instance("StopIteration") => {
+                Ok(exception.args.first().cloned().unwrap_or(Value::None))
+            }
+            _ => no_attribute(value, name),
+        },
+        _ => raise("NotImplementedError", format!(
+            "attributes of '{}' objects can't be used yet",
+            value.type_name(),
+        )),
+    }
+}
+
+/// `value.name = attribute`
+pub fn set_attribute(value: &Value, name: &str, attribute: Value)
+    -> Eval<()>
+{
+    match value {
+        Value::Object(object) => {
+            object.attributes.borrow_mut().insert(name.into(), attribute);
+            Ok(())
+        }
+        Value::Type(class) => {
+            class.set(name, attribute);
+            Ok(())
+        }
+        _ => no_attribute(value, name),
+    }
+}
+
+/// `del value.name`
+pub fn delete_attribute(value: &Value, name: &str) -> Eval<()> {
+    let removed = match value {
+        Value::Object(object) => object.attributes.borrow_mut().remove(name),
+        Value::Type(class) => class.attributes.borrow_mut().remove(name),
+        _ => None,
+    };
+    match removed {
+        Some(_) => Ok(()),
+        None => no_attribute(value, name),
+    }
+}
+
+fn no_attribute<T>(value: &Value, name: &str) -> Eval<T> {
+    let message = match value {
+        Value::Type(class) => format!(
+            "type object '{}' has no attribute '{}'",
+            class.name,
+            name,
+        ),
+        _ => format!(
+            "'{}' object has no attribute '{}'",
+            value.type_name(),
+            name,
+        ),
+    };
+    raise("AttributeError", message)
+}
+
+/// `super().name` in a method of `class` called on `receiver`: the
+/// attribute of the nearest base class that has it, bound to the receiver.
+pub fn super_attribute(class: &Value, receiver: &Value, name: &str)
+    -> Eval<Option<Value>>
+{
+    let class = match class {
+        Value::Type(class) => class,
+        _ => unreachable!("methods are defined in classes"),
+    };
+    let instance = matches!(
+        receiver,
+        Value::Object(object) if object.class.is_subclass(class),
+    );
+    if !instance {
+        return raise(
+            "TypeError",
+            "super(type, obj): obj must be an instance or subtype of type",
+        );
+    }
+    let found = class.base.as_ref().and_then(|base| base.lookup(name));
+    Ok(found.map(|found| bind(found, receiver)))
+}
+
+/// `isinstance(value, class)` for a class that isn't known statically,
+/// where `class` may be a tuple of classes.
+pub fn instance_of(value: &Value, class: &Value) -> Eval<bool> {
+    match class {
+        Value::Type(class) => Ok(match value {
+            Value::Object(object) => object.class.is_subclass(class),
+            _ => false,
+        }),
+        Value::Class(name) => Ok(isinstance(value, name)),
+        Value::Tuple(classes) => {
+            for class in classes.iter() {
+                if instance_of(value, class)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        _ => Err(Exception::new(
+            "TypeError",
+            "isinstance() arg 2 must be a type, a tuple of types, or a union",
+        )),
+    }
+}
@@ -3,3 +3,1600 @@
 //! Rust Programming Language stable version with ignored lifetime syntax.  The
 //! borrow checker will at least accept all programs that work in stable, and
 //! possibly more.
+
+pub mod borrowck;
+pub mod cfg;
+pub mod lower;
+pub mod resolve;
+pub mod typeck;
+
+mod expand;
+mod libstd;
+mod parser;
+
+use crate::{Diagnostic, Lexeme, LexemeIterator, Span};
+use cfg::Cfg;
+use parser::Parser;
+use resolve::SourceFile;
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+/// A Rust keyword (strict and reserved, but not weak keywords like `union`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyword {
+    As,
+    Async,
+    Await,
+    Break,
+    Const,
+    Continue,
+    Crate,
+    Dyn,
+    Else,
+    Enum,
+    Extern,
+    False,
+    Fn,
+    For,
+    If,
+    Impl,
+    In,
+    Let,
+    Loop,
+    Match,
+    Mod,
+    Move,
+    Mut,
+    Pub,
+    Ref,
+    Return,
+    SelfValue,
+    SelfType,
+    Static,
+    Struct,
+    Super,
+    Trait,
+    True,
+    Type,
+    Unsafe,
+    Use,
+    Where,
+    While,
+    /// `_`
+    Underscore,
+    // Reserved for future use
+    Abstract,
+    Become,
+    Box,
+    Do,
+    Final,
+    Macro,
+    Override,
+    Priv,
+    Try,
+    Typeof,
+    Unsized,
+    Virtual,
+    Yield,
+}
+
+const KEYWORDS: &[(&str, Keyword)] = &[
+    ("as", Keyword::As),
+    ("async", Keyword::Async),
+    ("await", Keyword::Await),
+    ("break", Keyword::Break),
+    ("const", Keyword::Const),
+    ("continue", Keyword::Continue),
+    ("crate", Keyword::Crate),
+    ("dyn", Keyword::Dyn),
+    ("else", Keyword::Else),
+    ("enum", Keyword::Enum),
+    ("extern", Keyword::Extern),
+    ("false", Keyword::False),
+    ("fn", Keyword::Fn),
+    ("for", Keyword::For),
+    ("if", Keyword::If),
+    ("impl", Keyword::Impl),
+    ("in", Keyword::In),
+    ("let", Keyword::Let),
+    ("loop", Keyword::Loop),
+    ("match", Keyword::Match),
+    ("mod", Keyword::Mod),
+    ("move", Keyword::Move),
+    ("mut", Keyword::Mut),
+    ("pub", Keyword::Pub),
+    ("ref", Keyword::Ref),
+    ("return", Keyword::Return),
+    ("self", Keyword::SelfValue),
+    ("Self", Keyword::SelfType),
+    ("static", Keyword::Static),
+    ("struct", Keyword::Struct),
+    ("super", Keyword::Super),
+    ("trait", Keyword::Trait),
+    ("true", Keyword::True),
+    ("type", Keyword::Type),
+    ("unsafe", Keyword::Unsafe),
+    ("use", Keyword::Use),
+    ("where", Keyword::Where),
+    ("while", Keyword::While),
+    ("_", Keyword::Underscore),
+    ("abstract", Keyword::Abstract),
+    ("become", Keyword::Become),
+    ("box", Keyword::Box),
+    ("do", Keyword::Do),
+    ("final", Keyword::Final),
+    ("macro", Keyword::Macro),
+    ("override", Keyword::Override),
+    ("priv", Keyword::Priv),
+    ("try", Keyword::Try),
+    ("typeof", Keyword::Typeof),
+    ("unsized", Keyword::Unsized),
+    ("virtual", Keyword::Virtual),
+    ("yield", Keyword::Yield),
+];
+
+impl Keyword {
+    fn new(word: &str) -> Option<Keyword> {
+        KEYWORDS.iter().find(|(text, _)| *text == word).map(|(_, kw)| *kw)
+    }
+
+    /// Get the source text of the keyword.
+    pub fn as_str(self) -> &'static str {
+        KEYWORDS.iter().find(|(_, kw)| *kw == self).unwrap().0
+    }
+}
+
+/// Suffix of an integer literal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntType {
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    Isize,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    Usize,
+}
+
+const INT_TYPES: &[(&str, IntType)] = &[
+    ("i8", IntType::I8),
+    ("i16", IntType::I16),
+    ("i32", IntType::I32),
+    ("i64", IntType::I64),
+    ("i128", IntType::I128),
+    ("isize", IntType::Isize),
+    ("u8", IntType::U8),
+    ("u16", IntType::U16),
+    ("u32", IntType::U32),
+    ("u64", IntType::U64),
+    ("u128", IntType::U128),
+    ("usize", IntType::Usize),
+];
+
+impl IntType {
+    /// Get the integer type from its name.
+    pub fn from_name(name: &str) -> Option<IntType> {
+        INT_TYPES.iter().find(|(text, _)| *text == name).map(|(_, ty)| *ty)
+    }
+
+    /// Get the name of the type.
+    pub fn as_str(self) -> &'static str {
+        INT_TYPES.iter().find(|(_, ty)| *ty == self).unwrap().0
+    }
+
+    /// Size in bytes (`usize` is 64 bits).
+    pub fn size(self) -> usize {
+        use IntType::*;
+
+        match self {
+            I8 | U8 => 1,
+            I16 | U16 => 2,
+            I32 | U32 => 4,
+            I64 | U64 | Isize | Usize => 8,
+            I128 | U128 => 16,
+        }
+    }
+
+    /// Whether the type is signed.
+    pub fn is_signed(self) -> bool {
+        use IntType::*;
+
+        matches!(self, I8 | I16 | I32 | I64 | I128 | Isize)
+    }
+
+    /// Largest value of the type.
+    pub fn max(self) -> u128 {
+        let bits = self.size() * 8 - self.is_signed() as usize;
+        if bits == 128 {
+            u128::MAX
+        } else {
+            (1 << bits) - 1
+        }
+    }
+}
+
+/// Suffix of a float literal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FloatType {
+    F32,
+    F64,
+}
+
+impl FloatType {
+    /// Get the name of the type.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FloatType::F32 => "f32",
+            FloatType::F64 => "f64",
+        }
+    }
+}
+
+/// Rust punctuation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Caret,
+    Not,
+    And,
+    Or,
+    AndAnd,
+    OrOr,
+    Shl,
+    Shr,
+    PlusEq,
+    MinusEq,
+    StarEq,
+    SlashEq,
+    PercentEq,
+    CaretEq,
+    AndEq,
+    OrEq,
+    ShlEq,
+    ShrEq,
+    Eq,
+    EqEq,
+    Ne,
+    Gt,
+    Lt,
+    Ge,
+    Le,
+    At,
+    Dot,
+    DotDot,
+    DotDotDot,
+    DotDotEq,
+    Comma,
+    Semi,
+    Colon,
+    PathSep,
+    RArrow,
+    FatArrow,
+    Pound,
+    Dollar,
+    Question,
+    Tilde,
+}
+
+impl Operator {
+    /// Get the source text of the operator.
+    pub fn as_str(self) -> &'static str {
+        OPERATORS.iter().find(|(_, op)| *op == self).unwrap().0
+    }
+
+    /// Split a compound operator into its first character and the rest, for
+    /// example `>>` into `>` and `>` when closing generics.
+    pub fn split(self) -> Option<(Operator, Operator)> {
+        use Operator::*;
+
+        Some(match self {
+            AndAnd => (And, And),
+            OrOr => (Or, Or),
+            Shl => (Lt, Lt),
+            Shr => (Gt, Gt),
+            Ge => (Gt, Eq),
+            Le => (Lt, Eq),
+            ShrEq => (Gt, Ge),
+            ShlEq => (Lt, Le),
+            PathSep => (Colon, Colon),
+            DotDot => (Dot, Dot),
+            EqEq => (Eq, Eq),
+            OrEq => (Or, Eq),
+            AndEq => (And, Eq),
+            _ => return None,
+        })
+    }
+}
+
+const OPERATORS: &[(&str, Operator)] = &[
+    ("<<=", Operator::ShlEq),
+    (">>=", Operator::ShrEq),
+    ("...", Operator::DotDotDot),
+    ("..=", Operator::DotDotEq),
+    ("::", Operator::PathSep),
+    ("->", Operator::RArrow),
+    ("=>", Operator::FatArrow),
+    ("==", Operator::EqEq),
+    ("!=", Operator::Ne),
+    ("<=", Operator::Le),
+    (">=", Operator::Ge),
+    ("&&", Operator::AndAnd),
+    ("||", Operator::OrOr),
+    ("+=", Operator::PlusEq),
+    ("-=", Operator::MinusEq),
+    ("*=", Operator::StarEq),
+    ("/=", Operator::SlashEq),
+    ("%=", Operator::PercentEq),
+    ("^=", Operator::CaretEq),
+    ("&=", Operator::AndEq),
+    ("|=", Operator::OrEq),
+    ("<<", Operator::Shl),
+    (">>", Operator::Shr),
+    ("..", Operator::DotDot),
+    ("+", Operator::Plus),
+    ("-", Operator::Minus),
+    ("*", Operator::Star),
+    ("/", Operator::Slash),
+    ("%", Operator::Percent),
+    ("^", Operator::Caret),
+    ("!", Operator::Not),
+    ("&", Operator::And),
+    ("|", Operator::Or),
+    ("=", Operator::Eq),
+    (">", Operator::Gt),
+    ("<", Operator::Lt),
+    ("@", Operator::At),
+    (".", Operator::Dot),
+    (",", Operator::Comma),
+    (";", Operator::Semi),
+    (":", Operator::Colon),
+    ("#", Operator::Pound),
+    ("$", Operator::Dollar),
+    ("?", Operator::Question),
+    ("~", Operator::Tilde),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bracket {
+    ParensL,
+    ParensR,
+    BraceL,
+    BraceR,
+    SquareL,
+    SquareR,
+}
+
+impl Bracket {
+    /// Get the source text of the bracket.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Bracket::ParensL => "(",
+            Bracket::ParensR => ")",
+            Bracket::BraceL => "{",
+            Bracket::BraceR => "}",
+            Bracket::SquareL => "[",
+            Bracket::SquareR => "]",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token<'a> {
+    Keyword(Keyword),
+    /// An identifier, without the `r#` of raw identifiers
+    Identifier(&'a str),
+    /// A lifetime or loop label, without the `'`
+    Lifetime(&'a str),
+    Comment(&'a str),
+    /// `///` or `/** */` documentation
+    OuterDoc(&'a str),
+    /// `//!` or `/*! */` documentation
+    InnerDoc(&'a str),
+    Char(char),
+    Byte(u8),
+    String(String),
+    ByteString(Vec<u8>),
+    /// Contents of a C string literal, without the terminating NUL
+    CString(Vec<u8>),
+    Int(u128, Option<IntType>),
+    Float(f64, Option<FloatType>),
+    Operator(Operator),
+    Bracket(Bracket),
+}
+
+impl std::fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Keyword(keyword) => write!(f, "{}", keyword.as_str()),
+            Token::Identifier(name) => write!(f, "{}", name),
+            Token::Lifetime(name) => write!(f, "'{}", name),
+            Token::Comment(_) => write!(f, "comment"),
+            Token::OuterDoc(_) | Token::InnerDoc(_) => {
+                write!(f, "doc comment")
+            }
+            Token::Char(ch) => write!(f, "{:?}", ch),
+            Token::Byte(byte) => write!(f, "b{:?}", *byte as char),
+            Token::String(string) => write!(f, "{:?}", string),
+            Token::ByteString(bytes) => {
+                write!(f, "b{:?}", String::from_utf8_lossy(bytes))
+            }
+            Token::CString(bytes) => {
+                write!(f, "c{:?}", String::from_utf8_lossy(bytes))
+            }
+            Token::Int(value, None) => write!(f, "{}", value),
+            Token::Int(value, Some(ty)) => {
+                write!(f, "{}{}", value, ty.as_str())
+            }
+            Token::Float(value, None) => write!(f, "{:?}", value),
+            Token::Float(value, Some(ty)) => {
+                write!(f, "{:?}{}", value, ty.as_str())
+            }
+            Token::Operator(op) => write!(f, "{}", op.as_str()),
+            Token::Bracket(bracket) => write!(f, "{}", bracket.as_str()),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ChunkKind {
+    LineComment,
+    // Nesting depth
+    BlockComment(usize),
+    String,
+    Character,
+    // Number of `#`s
+    RawString(usize),
+    // An identifier starting with a non-ASCII letter, which is scanned by
+    // the token iterator
+    Unicode,
+}
+
+struct RustChunk {
+    kind: ChunkKind,
+    // Characters of the opening delimiter still to skip.
+    skip: usize,
+}
+
+fn begin_text(input: &str) -> (Option<RustChunk>, Option<char>) {
+    let chunk = |kind, skip| Some(RustChunk { kind, skip });
+
+    if input.starts_with("//") {
+        (chunk(ChunkKind::LineComment, 0), None)
+    } else if input.starts_with("/*") {
+        (chunk(ChunkKind::BlockComment(0), 1), None)
+    } else if input.starts_with('"') {
+        (chunk(ChunkKind::String, 0), Some('\\'))
+    } else if input.starts_with("b\"") || input.starts_with("c\"") {
+        (chunk(ChunkKind::String, 1), Some('\\'))
+    } else if input.starts_with("b'") {
+        (chunk(ChunkKind::Character, 1), Some('\\'))
+    } else if input.starts_with(|c: char| !c.is_ascii() && c.is_alphabetic()) {
+        (chunk(ChunkKind::Unicode, 0), None)
+    } else if let Some(rest) = input.strip_prefix('\'') {
+        // `'a'` is a character, but `'a` is a lifetime.
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some('\\'), _) | (Some(_), Some('\'')) => {
+                (chunk(ChunkKind::Character, 0), Some('\\'))
+            }
+            _ => (None, None),
+        }
+    } else {
+        let prefix = if input.starts_with("br") || input.starts_with("cr") {
+            2
+        } else if input.starts_with('r') {
+            1
+        } else {
+            return (None, None);
+        };
+        let hashes = input[prefix..].chars().take_while(|c| *c == '#').count();
+        if input[prefix + hashes..].starts_with('"') {
+            (chunk(ChunkKind::RawString(hashes), prefix + hashes), None)
+        } else {
+            (None, None)
+        }
+    }
+}
+
+fn end_text(input: &str, chunk: &mut RustChunk) -> (bool, usize) {
+    if chunk.skip > 0 {
+        chunk.skip -= 1;
+        return (false, 0);
+    }
+    match chunk.kind {
+        ChunkKind::LineComment => (input.starts_with('\n'), 1),
+        ChunkKind::BlockComment(depth) => {
+            if input.starts_with("/*") {
+                chunk.kind = ChunkKind::BlockComment(depth + 1);
+                chunk.skip = 1;
+                (false, 0)
+            } else if input.starts_with("*/") {
+                if depth == 0 {
+                    return (true, 2);
+                }
+                chunk.kind = ChunkKind::BlockComment(depth - 1);
+                chunk.skip = 1;
+                (false, 0)
+            } else {
+                (false, 0)
+            }
+        }
+        ChunkKind::String => (input.starts_with('"'), 1),
+        ChunkKind::Character => (input.starts_with('\''), 1),
+        ChunkKind::RawString(hashes) => {
+            let end = input.starts_with('"')
+                && input[1..].chars().take(hashes).filter(|c| *c == '#').count()
+                    == hashes;
+            (end, 1 + hashes)
+        }
+        ChunkKind::Unicode => (true, 0),
+    }
+}
+
+/// An iterator over Rust tokens.  A shebang line at the start is skipped.
+pub struct TokenIterator<'a> {
+    lexemes: LexemeIterator<'a, RustChunk>,
+    span: Span,
+    // Whether the previous token was `.`, so `x.0.1` isn't a float.
+    after_dot: bool,
+}
+
+impl<'a> TokenIterator<'a> {
+    /// Create a new Rust token iterator.
+    pub fn new(text: &'a str) -> Self {
+        let mut lexemes = LexemeIterator::new(text, begin_text, end_text);
+
+        if let Some(rest) = text.strip_prefix("#!") {
+            // `#![attribute]` isn't a shebang.
+            if !rest.trim_start().starts_with('[') {
+                lexemes.seek(text.find('\n').unwrap_or(text.len()));
+            }
+        }
+
+        TokenIterator {
+            lexemes,
+            span: Span::default(),
+            after_dot: false,
+        }
+    }
+
+    /// Get the span of the most recently returned token.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Get the source text being tokenized.
+    pub fn text(&self) -> &'a str {
+        self.lexemes.text()
+    }
+
+    // Scan an identifier starting at byte index `start` and continue lexing
+    // after it.  Unicode letters and digits stand in for `XID_Start` and
+    // `XID_Continue`.
+    fn identifier(&mut self, start: usize) -> &'a str {
+        let text = self.lexemes.text();
+        let len = text[start..]
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(text.len() - start);
+        self.lexemes.seek(start + len);
+        &text[start..start + len]
+    }
+
+    // Scan a number literal starting at byte index `start`.
+    fn number(&mut self, start: usize) -> Result<Token<'a>> {
+        let text = self.lexemes.text();
+        let bytes = text.as_bytes();
+        let radix = match bytes.get(start + 1) {
+            _ if bytes[start] != b'0' => 10,
+            Some(b'x') => 16,
+            Some(b'o') => 8,
+            Some(b'b') => 2,
+            _ => 10,
+        };
+        let mut end = if radix == 10 { start } else { start + 2 };
+        let digit = |ch: u8| {
+            ch == b'_' || (ch as char).is_digit(radix)
+        };
+        while end < bytes.len() && digit(bytes[end]) {
+            end += 1;
+        }
+
+        let mut float = false;
+        if radix == 10 && !self.after_dot && bytes.get(end) == Some(&b'.') {
+            // `1..2` is a range and `1.max(2)` a method call.
+            let next = bytes.get(end + 1).copied().unwrap_or(b' ');
+            if next != b'.' && next != b'_' && !next.is_ascii_alphabetic() {
+                float = true;
+                end += 1;
+                while end < bytes.len() && digit(bytes[end]) {
+                    end += 1;
+                }
+            }
+        }
+        if radix == 10 && (bytes.get(end) == Some(&b'e')
+            || bytes.get(end) == Some(&b'E'))
+        {
+            let mut exp = end + 1;
+            if bytes.get(exp) == Some(&b'+') || bytes.get(exp) == Some(&b'-') {
+                exp += 1;
+            }
+            if bytes.get(exp).is_some_and(|b| b.is_ascii_digit() || *b == b'_')
+            {
+                float = true;
+                end = exp;
+                while end < bytes.len() && digit(bytes[end]) {
+                    end += 1;
+                }
+            }
+        }
+        let digits_end = end;
+        while end < bytes.len()
+            && (bytes[end].is_ascii_alphanumeric() || bytes[end] == b'_')
+        {
+            end += 1;
+        }
+        self.span = Span::new(start, end);
+        self.lexemes.seek(end);
+
+        let digits = text[start..digits_end].replace('_', "");
+        let suffix = &text[digits_end..end];
+        let invalid = |message: &str| {
+            Diagnostic::new(Span::new(start, end), message)
+        };
+        let float_suffix = match suffix {
+            "f32" => Some(FloatType::F32),
+            "f64" => Some(FloatType::F64),
+            _ => None,
+        };
+
+        if float || (float_suffix.is_some() && radix == 10) {
+            if !suffix.is_empty() && float_suffix.is_none() {
+                return Err(invalid("invalid suffix for float literal"));
+            }
+            let value = digits
+                .parse::<f64>()
+                .map_err(|_| invalid("invalid float literal"))?;
+            return Ok(Token::Float(value, float_suffix));
+        }
+
+        let ty = match suffix {
+            "" => None,
+            suffix => match IntType::from_name(suffix) {
+                Some(ty) => Some(ty),
+                None => {
+                    return Err(invalid("invalid suffix for integer literal"))
+                }
+            },
+        };
+        let digits = if radix == 10 { &digits[..] } else { &digits[2..] };
+        if digits.is_empty() {
+            return Err(invalid("no digits in integer literal"));
+        }
+        let value = u128::from_str_radix(digits, radix)
+            .map_err(|_| invalid("integer literal is too large"))?;
+        if ty.is_some_and(|ty| value > ty.max() + ty.is_signed() as u128) {
+            return Err(invalid("integer literal is out of range for its type"));
+        }
+
+        Ok(Token::Int(value, ty))
+    }
+
+    // Decode a string, character or comment.
+    fn text_token(&mut self, string: &'a str, span: Span) -> Result<Token<'a>> {
+        let text = self.lexemes.text();
+        let closed = |close: &str| {
+            span.end - span.start >= string.len() + close.len()
+                && text[..span.end].ends_with(close)
+        };
+
+        if let Some(comment) = string.strip_prefix("//") {
+            // The newline isn't part of the comment.
+            self.span = Span::new(span.start, span.start + string.len());
+            return Ok(if comment.starts_with('/') && !comment.starts_with("//")
+            {
+                Token::OuterDoc(&comment[1..])
+            } else if let Some(doc) = comment.strip_prefix('!') {
+                Token::InnerDoc(doc)
+            } else {
+                Token::Comment(comment)
+            });
+        }
+        if let Some(comment) = string.strip_prefix("/*") {
+            if !closed("*/") {
+                return Err(Diagnostic::new(span, "unterminated block comment"));
+            }
+            return Ok(
+                if comment.starts_with('*') && comment.len() > 1
+                    && !comment.starts_with("**")
+                {
+                    Token::OuterDoc(&comment[1..])
+                } else if let Some(doc) = comment.strip_prefix('!') {
+                    Token::InnerDoc(doc)
+                } else {
+                    Token::Comment(comment)
+                },
+            );
+        }
+
+        let (prefix, rest) = match string.find(|c| "\"'#".contains(c)) {
+            Some(index) => string.split_at(index),
+            None => panic!("Compiler Bug: Invalid text"),
+        };
+        let raw = prefix.ends_with('r');
+        let hashes = if raw { &rest[..rest.find('"').unwrap()] } else { "" };
+        let quote = &rest[hashes.len()..hashes.len() + 1];
+        let contents = &rest[hashes.len() + 1..];
+        if !closed(&format!("{}{}", quote, hashes)) {
+            return Err(Diagnostic::new(span, if quote == "'" {
+                "unterminated character literal"
+            } else {
+                "unterminated string literal"
+            }));
+        }
+
+        let bytes = if raw {
+            contents.as_bytes().to_vec()
+        } else {
+            unescape(contents, span, prefix == "b")?
+        };
+        if prefix.starts_with('b') && !contents.is_ascii() {
+            return Err(Diagnostic::new(
+                span,
+                "non-ASCII character in byte literal",
+            ));
+        }
+
+        Ok(match (prefix, quote) {
+            ("", "'") => {
+                let string = String::from_utf8(bytes).unwrap();
+                let mut chars = string.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(ch), None) => Token::Char(ch),
+                    _ => {
+                        return Err(Diagnostic::new(
+                            span,
+                            "character literal may only contain one codepoint",
+                        ))
+                    }
+                }
+            }
+            ("b", "'") => match bytes[..] {
+                [byte] => Token::Byte(byte),
+                _ => {
+                    return Err(Diagnostic::new(
+                        span,
+                        "byte literal must contain exactly one byte",
+                    ))
+                }
+            },
+            ("" | "r", _) => Token::String(String::from_utf8(bytes).unwrap()),
+            ("b" | "br", _) => Token::ByteString(bytes),
+            _ => {
+                if bytes.contains(&0) {
+                    return Err(Diagnostic::new(
+                        span,
+                        "C string literal can't contain a NUL character",
+                    ));
+                }
+                Token::CString(bytes)
+            }
+        })
+    }
+}
+
+impl<'a> Iterator for TokenIterator<'a> {
+    type Item = Result<Token<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let lexeme = self.lexemes.next()?;
+        let span = self.lexemes.span();
+        self.span = span;
+
+        let token = match lexeme {
+            Lexeme::Word(word) => {
+                let text = self.lexemes.text();
+                let rest = &text[span.end..];
+                if rest.starts_with(|c: char| !c.is_ascii()) {
+                    // A word stops before non-ASCII letters.
+                    let name = self.identifier(span.start);
+                    self.span = Span::new(span.start, span.start + name.len());
+                    Ok(Token::Identifier(name))
+                } else if word == "r" && rest.starts_with('#')
+                    && rest[1..].starts_with(|c: char| {
+                        c.is_alphabetic() || c == '_'
+                    })
+                {
+                    let name = self.identifier(span.end + 1);
+                    self.span = Span::new(span.start, span.end + 1 + name.len());
+                    Ok(Token::Identifier(name))
+                } else if let Some(keyword) = Keyword::new(word) {
+                    Ok(Token::Keyword(keyword))
+                } else {
+                    Ok(Token::Identifier(word))
+                }
+            }
+            Lexeme::Text(text)
+                if text.starts_with(|c: char| !c.is_ascii()) =>
+            {
+                let name = self.identifier(span.start);
+                self.span = Span::new(span.start, span.start + name.len());
+                Ok(Token::Identifier(name))
+            }
+            Lexeme::Text(string) => self.text_token(string, span),
+            Lexeme::Number(_) => self.number(span.start),
+            Lexeme::Operator(text) if text.starts_with('\'') => {
+                let source = self.lexemes.text();
+                let start = if source[span.start + 1..].starts_with("r#") {
+                    span.start + 3
+                } else {
+                    span.start + 1
+                };
+                let name = self.identifier(start);
+                self.span = Span::new(span.start, start + name.len());
+                if source[self.span.end..].starts_with('\'') {
+                    Err(Diagnostic::new(
+                        Span::new(span.start, self.span.end + 1),
+                        "character literal may only contain one codepoint",
+                    ))
+                } else if name.is_empty()
+                    || name.starts_with(|c: char| c.is_ascii_digit())
+                {
+                    Err(Diagnostic::new(self.span, "invalid lifetime"))
+                } else {
+                    Ok(Token::Lifetime(name))
+                }
+            }
+            Lexeme::Operator(text) => {
+                match OPERATORS
+                    .iter()
+                    .find(|(op_text, _)| text.starts_with(op_text))
+                {
+                    Some((op_text, op)) => {
+                        if op_text.len() != text.len() {
+                            self.lexemes.seek(span.start + op_text.len());
+                        }
+                        self.span =
+                            Span::new(span.start, span.start + op_text.len());
+                        Ok(Token::Operator(*op))
+                    }
+                    None => {
+                        let ch = text.chars().next().unwrap();
+                        Err(Diagnostic::new(
+                            Span::new(span.start, span.start + ch.len_utf8()),
+                            format!("unknown start of token `{}`", ch),
+                        ))
+                    }
+                }
+            }
+            Lexeme::Bracket(text) => Ok(Token::Bracket(match text {
+                "(" => Bracket::ParensL,
+                ")" => Bracket::ParensR,
+                "{" => Bracket::BraceL,
+                "}" => Bracket::BraceR,
+                "[" => Bracket::SquareL,
+                "]" => Bracket::SquareR,
+                _ => panic!("COMPILER BUG: Invalid bracket"),
+            })),
+        };
+
+        self.after_dot = token == Ok(Token::Operator(Operator::Dot));
+
+        Some(token)
+    }
+}
+
+/// Decode the escape sequences of a string or character literal, returning
+/// UTF-8 (or raw bytes for byte literals).
+fn unescape(text: &str, span: Span, byte: bool) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut chars = text.chars().peekable();
+    let invalid = |message: &str| Err(Diagnostic::new(span, message));
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            let mut buf = [0; 4];
+            out.extend(ch.encode_utf8(&mut buf).bytes());
+            continue;
+        }
+        let ch = match chars.next() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some(ch @ ('\\' | '\'' | '"')) => ch,
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                let value = match u8::from_str_radix(&digits, 16) {
+                    Ok(value) if digits.len() == 2 => value,
+                    _ => return invalid("invalid `\\x` escape"),
+                };
+                if byte {
+                    out.push(value);
+                    continue;
+                }
+                if value > 0x7F {
+                    return invalid("`\\x` escape out of range (must be at \
+                        most `\\x7F`)");
+                }
+                value as char
+            }
+            Some('u') if !byte => {
+                if chars.next() != Some('{') {
+                    return invalid("`\\u` escape must be followed by `{`");
+                }
+                let digits: String =
+                    chars.by_ref().take_while(|c| *c != '}').collect();
+                let value = u32::from_str_radix(&digits.replace('_', ""), 16)
+                    .ok()
+                    .and_then(std::char::from_u32);
+                match value {
+                    Some(ch) if !digits.is_empty() => ch,
+                    _ => return invalid("invalid unicode escape"),
+                }
+            }
+            // Line continuation skips the newline and leading whitespace.
+            Some('\n') => {
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                continue;
+            }
+            _ => return invalid("unknown character escape"),
+        };
+        let mut buf = [0; 4];
+        out.extend(ch.encode_utf8(&mut buf).bytes());
+    }
+
+    Ok(out)
+}
+
+/// A token, or a group of tokens in brackets (used for macro input and
+/// attribute arguments).
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenTree<'a> {
+    Token(Token<'a>, Span),
+    /// Delimiter (opening bracket), contents, span including brackets
+    Group(Bracket, Vec<TokenTree<'a>>, Span),
+}
+
+impl<'a> TokenTree<'a> {
+    /// Get the span of the token tree.
+    pub fn span(&self) -> Span {
+        match self {
+            TokenTree::Token(_, span) | TokenTree::Group(_, _, span) => *span,
+        }
+    }
+}
+
+/// A path such as `std::vec::Vec<T>` (with `Self`, `self`, `super` and
+/// `crate` as ordinary segment names)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path<'a> {
+    /// Whether the path starts with `::`
+    pub global: bool,
+    pub segments: Vec<PathSegment<'a>>,
+    pub span: Span,
+}
+
+impl std::fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.global {
+            write!(f, "::")?;
+        }
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i != 0 {
+                write!(f, "::")?;
+            }
+            write!(f, "{}", segment.name)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathSegment<'a> {
+    pub name: &'a str,
+    /// Generic arguments (`Fn(A) -> B` is stored as `Fn<(A,), Output = B>`)
+    pub generics: Vec<GenericArg<'a>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenericArg<'a> {
+    Type(Type<'a>),
+    Const(Expr<'a>),
+    /// Associated type binding `Item = T`
+    Binding(&'a str, Type<'a>),
+    /// Associated type bound `Item: Trait`
+    Constraint(&'a str, Vec<Bound<'a>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Type<'a> {
+    pub kind: TypeKind<'a>,
+    pub span: Span,
+}
+
+/// A type, with lifetimes discarded
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeKind<'a> {
+    Path(Path<'a>),
+    /// `<T as Trait>::Name`
+    Qualified(Box<Type<'a>>, Option<Path<'a>>, Vec<PathSegment<'a>>),
+    Ref(bool, Box<Type<'a>>),
+    Ptr(bool, Box<Type<'a>>),
+    Slice(Box<Type<'a>>),
+    Array(Box<Type<'a>>, Expr<'a>),
+    /// Tuple (the unit type when empty)
+    Tuple(Vec<Type<'a>>),
+    /// `!`
+    Never,
+    /// `_`
+    Infer,
+    /// Function pointer: parameters, return type, variadic
+    Fn(Vec<Type<'a>>, Box<Type<'a>>, bool),
+    ImplTrait(Vec<Bound<'a>>),
+    DynTrait(Vec<Bound<'a>>),
+    Macro(MacroCall<'a>),
+}
+
+impl<'a> Type<'a> {
+    /// The unit type `()`.
+    pub fn unit(span: Span) -> Self {
+        Type {
+            kind: TypeKind::Tuple(Vec::new()),
+            span,
+        }
+    }
+}
+
+/// A trait bound (lifetime bounds are discarded)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bound<'a> {
+    /// `?Sized`
+    pub maybe: bool,
+    pub path: Path<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenericParam<'a> {
+    Type {
+        name: &'a str,
+        bounds: Vec<Bound<'a>>,
+        default: Option<Type<'a>>,
+        span: Span,
+    },
+    Const {
+        name: &'a str,
+        ty: Type<'a>,
+        default: Option<Expr<'a>>,
+        span: Span,
+    },
+}
+
+/// `where` clause predicate
+#[derive(Debug, Clone, PartialEq)]
+pub struct WherePredicate<'a> {
+    pub ty: Type<'a>,
+    pub bounds: Vec<Bound<'a>>,
+}
+
+/// Generic parameters (lifetime parameters are discarded)
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Generics<'a> {
+    pub params: Vec<GenericParam<'a>>,
+    pub predicates: Vec<WherePredicate<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Visibility<'a> {
+    Private,
+    Public,
+    /// `pub(crate)`, `pub(super)`, `pub(self)` or `pub(in path)`
+    Restricted(Path<'a>),
+}
+
+/// An attribute; doc comments become `#[doc = "..."]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute<'a> {
+    /// `#![...]` rather than `#[...]`
+    pub inner: bool,
+    pub path: Path<'a>,
+    /// Tokens after the path, such as `(Debug, Clone)` or `= "text"`
+    pub tokens: Vec<TokenTree<'a>>,
+    pub span: Span,
+}
+
+impl Attribute<'_> {
+    /// Whether the path of the attribute is the single identifier `name`.
+    pub fn is(&self, name: &str) -> bool {
+        !self.path.global && self.path.segments.len() == 1
+            && self.path.segments[0].name == name
+    }
+}
+
+/// A literal
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lit {
+    Bool(bool),
+    Char(char),
+    Byte(u8),
+    Str(String),
+    ByteStr(Vec<u8>),
+    CStr(Vec<u8>),
+    Int(u128, Option<IntType>),
+    Float(f64, Option<FloatType>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// `-`
+    Neg,
+    /// `!`
+    Not,
+    /// `*`
+    Deref,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    /// `&&`
+    And,
+    /// `||`
+    Or,
+    BitXor,
+    BitAnd,
+    BitOr,
+    Shl,
+    Shr,
+    Eq,
+    Lt,
+    Le,
+    Ne,
+    Ge,
+    Gt,
+}
+
+impl BinaryOp {
+    /// Get the source text of the operator.
+    pub fn as_str(self) -> &'static str {
+        use BinaryOp::*;
+
+        match self {
+            Add => "+",
+            Sub => "-",
+            Mul => "*",
+            Div => "/",
+            Rem => "%",
+            And => "&&",
+            Or => "||",
+            BitXor => "^",
+            BitAnd => "&",
+            BitOr => "|",
+            Shl => "<<",
+            Shr => ">>",
+            Eq => "==",
+            Lt => "<",
+            Le => "<=",
+            Ne => "!=",
+            Ge => ">=",
+            Gt => ">",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr<'a> {
+    pub kind: ExprKind<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind<'a> {
+    Lit(Lit),
+    /// Path, with turbofish generics on its segments
+    Path(Path<'a>),
+    /// `<T as Trait>::name`
+    Qualified(Box<Type<'a>>, Option<Path<'a>>, Vec<PathSegment<'a>>),
+    /// Tuple (the unit value when empty)
+    Tuple(Vec<Expr<'a>>),
+    Array(Vec<Expr<'a>>),
+    /// `[value; count]`
+    Repeat(Box<Expr<'a>>, Box<Expr<'a>>),
+    /// Struct literal, with the base of `..base`
+    Struct(Path<'a>, Vec<FieldInit<'a>>, Option<Box<Expr<'a>>>),
+    Unary(UnaryOp, Box<Expr<'a>>),
+    /// `&expr` or `&mut expr`
+    Ref(bool, Box<Expr<'a>>),
+    Binary(BinaryOp, Box<Expr<'a>>, Box<Expr<'a>>),
+    Assign(Box<Expr<'a>>, Box<Expr<'a>>),
+    /// `a += b` and similar
+    AssignOp(BinaryOp, Box<Expr<'a>>, Box<Expr<'a>>),
+    Cast(Box<Expr<'a>>, Box<Type<'a>>),
+    Call(Box<Expr<'a>>, Vec<Expr<'a>>),
+    /// Receiver, method name with turbofish generics, arguments
+    MethodCall(Box<Expr<'a>>, PathSegment<'a>, Vec<Expr<'a>>),
+    /// Field access (tuple fields are named `0`, `1`, ...)
+    Field(Box<Expr<'a>>, &'a str),
+    Index(Box<Expr<'a>>, Box<Expr<'a>>),
+    /// `expr?`
+    Try(Box<Expr<'a>>),
+    /// `expr.await`
+    Await(Box<Expr<'a>>),
+    /// Range, inclusive with `..=`
+    Range(Option<Box<Expr<'a>>>, Option<Box<Expr<'a>>>, bool),
+    /// Block, with an optional label
+    Block(Option<&'a str>, Block<'a>),
+    Unsafe(Block<'a>),
+    /// `const { ... }`
+    Const(Block<'a>),
+    /// `async move { ... }`
+    Async(bool, Block<'a>),
+    /// Condition, then block, else branch (a block or another `if`)
+    If(Box<Expr<'a>>, Block<'a>, Option<Box<Expr<'a>>>),
+    /// `let pattern = expr` in the condition of an `if` or `while`
+    Let(Box<Pattern<'a>>, Box<Expr<'a>>),
+    While(Option<&'a str>, Box<Expr<'a>>, Block<'a>),
+    Loop(Option<&'a str>, Block<'a>),
+    For(Option<&'a str>, Box<Pattern<'a>>, Box<Expr<'a>>, Block<'a>),
+    Match(Box<Expr<'a>>, Vec<Arm<'a>>),
+    Closure(Box<Closure<'a>>),
+    /// `break 'label value`
+    Break(Option<&'a str>, Option<Box<Expr<'a>>>),
+    Continue(Option<&'a str>),
+    Return(Option<Box<Expr<'a>>>),
+    Macro(MacroCall<'a>),
+}
+
+impl Expr<'_> {
+    /// Whether the expression ends with a block, so it doesn't need a `;` to
+    /// be a statement.
+    pub fn is_block_like(&self) -> bool {
+        match self.kind {
+            ExprKind::Block(..)
+            | ExprKind::Unsafe(_)
+            | ExprKind::Const(_)
+            | ExprKind::Async(..)
+            | ExprKind::If(..)
+            | ExprKind::While(..)
+            | ExprKind::Loop(..)
+            | ExprKind::For(..)
+            | ExprKind::Match(..) => true,
+            ExprKind::Macro(ref call) => call.delimiter == Bracket::BraceL,
+            _ => false,
+        }
+    }
+}
+
+/// A field of a struct literal (`name` alone is `name: name`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldInit<'a> {
+    pub name: &'a str,
+    pub value: Expr<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arm<'a> {
+    pub attrs: Vec<Attribute<'a>>,
+    pub pattern: Pattern<'a>,
+    pub guard: Option<Expr<'a>>,
+    pub body: Expr<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Closure<'a> {
+    pub is_async: bool,
+    pub is_move: bool,
+    /// Parameters, with optional types
+    pub params: Vec<(Pattern<'a>, Option<Type<'a>>)>,
+    pub ret: Option<Type<'a>>,
+    pub body: Expr<'a>,
+}
+
+/// A block of statements, with an optional final expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block<'a> {
+    pub stmts: Vec<Stmt<'a>>,
+    pub expr: Option<Box<Expr<'a>>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stmt<'a> {
+    pub attrs: Vec<Attribute<'a>>,
+    pub kind: StmtKind<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StmtKind<'a> {
+    Let(Box<Local<'a>>),
+    Item(Box<Item<'a>>),
+    /// Block-like expression without a `;`
+    Expr(Expr<'a>),
+    /// Expression followed by `;`
+    Semi(Expr<'a>),
+}
+
+/// A `let` statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct Local<'a> {
+    pub pattern: Pattern<'a>,
+    pub ty: Option<Type<'a>>,
+    pub init: Option<Expr<'a>>,
+    /// The `else` block of `let ... else`
+    pub diverge: Option<Block<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern<'a> {
+    pub kind: PatternKind<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternKind<'a> {
+    /// `_`
+    Wild,
+    /// `..` in tuple and slice patterns
+    Rest,
+    /// Binding: `ref`, `mut`, name, and subpattern after `@`
+    Ident(bool, bool, &'a str, Option<Box<Pattern<'a>>>),
+    /// Literal, possibly negated, or constant named by a qualified path
+    Lit(Expr<'a>),
+    /// Range with path or literal bounds, inclusive with `..=`
+    Range(Option<Expr<'a>>, Option<Expr<'a>>, bool),
+    /// Path to a constant or unit struct or variant
+    Path(Path<'a>),
+    /// `&pat` or `&mut pat`
+    Ref(bool, Box<Pattern<'a>>),
+    Tuple(Vec<Pattern<'a>>),
+    TupleStruct(Path<'a>, Vec<Pattern<'a>>),
+    /// Struct pattern, with `..` to ignore the other fields
+    Struct(Path<'a>, Vec<FieldPattern<'a>>, bool),
+    Slice(Vec<Pattern<'a>>),
+    /// `a | b`
+    Or(Vec<Pattern<'a>>),
+    Macro(MacroCall<'a>),
+}
+
+/// A field of a struct pattern (`ref mut name` alone binds `name`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldPattern<'a> {
+    pub name: &'a str,
+    pub pattern: Pattern<'a>,
+    pub span: Span,
+}
+
+/// A macro invocation `path!(tokens)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroCall<'a> {
+    pub path: Path<'a>,
+    pub delimiter: Bracket,
+    pub tokens: Vec<TokenTree<'a>>,
+    pub span: Span,
+}
+
+impl<'a> MacroCall<'a> {
+    /// Parse the arguments of a function-like builtin macro such as `vec!` or
+    /// `println!` from the source text of the file containing the call.
+    pub fn args(&self, text: &'a str) -> Result<Vec<Expr<'a>>> {
+        let end = Span::new(self.span.end - 1, self.span.end);
+        Parser::from_tokens(text, &self.tokens, end).macro_args()
+    }
+}
+
+/// The tree of a `use` declaration
+#[derive(Debug, Clone, PartialEq)]
+pub struct UseTree<'a> {
+    pub prefix: Path<'a>,
+    pub kind: UseTreeKind<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UseTreeKind<'a> {
+    /// Import the path, optionally renamed (`as _` renames to `"_"`)
+    Simple(Option<&'a str>),
+    /// `prefix::*`
+    Glob,
+    /// `prefix::{...}`
+    Nested(Vec<UseTree<'a>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelfParam<'a> {
+    /// `self` or `mut self`
+    Value(bool),
+    /// `&self` or `&mut self`
+    Ref(bool),
+    /// `self: Type` or `mut self: Type`
+    Typed(bool, Type<'a>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param<'a> {
+    pub attrs: Vec<Attribute<'a>>,
+    pub pattern: Pattern<'a>,
+    pub ty: Type<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function<'a> {
+    pub name: &'a str,
+    pub generics: Generics<'a>,
+    pub self_param: Option<SelfParam<'a>>,
+    pub params: Vec<Param<'a>>,
+    /// `...` at the end of the parameters of a foreign function
+    pub variadic: bool,
+    pub ret: Option<Type<'a>>,
+    pub body: Option<Block<'a>>,
+    pub is_const: bool,
+    pub is_async: bool,
+    pub is_unsafe: bool,
+    /// ABI string of `extern "C" fn`
+    pub abi: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field<'a> {
+    pub attrs: Vec<Attribute<'a>>,
+    pub vis: Visibility<'a>,
+    /// `None` for tuple struct fields
+    pub name: Option<&'a str>,
+    pub ty: Type<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fields<'a> {
+    Unit,
+    Tuple(Vec<Field<'a>>),
+    Named(Vec<Field<'a>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant<'a> {
+    pub attrs: Vec<Attribute<'a>>,
+    pub name: &'a str,
+    pub fields: Fields<'a>,
+    pub discriminant: Option<Expr<'a>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item<'a> {
+    pub attrs: Vec<Attribute<'a>>,
+    pub vis: Visibility<'a>,
+    pub kind: ItemKind<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemKind<'a> {
+    Use(UseTree<'a>),
+    /// `extern crate name as rename;`
+    ExternCrate(&'a str, Option<&'a str>),
+    /// Module, with `None` items for `mod name;`
+    Mod(&'a str, Option<Vec<Item<'a>>>),
+    Fn(Box<Function<'a>>),
+    Struct(&'a str, Generics<'a>, Fields<'a>),
+    Enum(&'a str, Generics<'a>, Vec<Variant<'a>>),
+    Union(&'a str, Generics<'a>, Vec<Field<'a>>),
+    Trait {
+        is_unsafe: bool,
+        is_auto: bool,
+        name: &'a str,
+        generics: Generics<'a>,
+        supertraits: Vec<Bound<'a>>,
+        items: Vec<Item<'a>>,
+    },
+    Impl {
+        is_unsafe: bool,
+        generics: Generics<'a>,
+        /// `impl !Trait for T`
+        negative: bool,
+        trait_: Option<Path<'a>>,
+        self_ty: Type<'a>,
+        items: Vec<Item<'a>>,
+    },
+    /// Type alias, or associated type with bounds and optional default
+    Type(&'a str, Generics<'a>, Vec<Bound<'a>>, Option<Type<'a>>),
+    /// `const` (name `_` for unnamed constants), with optional value in
+    /// traits
+    Const(&'a str, Type<'a>, Option<Expr<'a>>),
+    /// `static` (mutable), name, type, value (`None` in extern blocks)
+    Static(bool, &'a str, Type<'a>, Option<Expr<'a>>),
+    /// `extern "abi" { ... }`
+    ExternBlock(Option<String>, Vec<Item<'a>>),
+    /// `macro_rules! name { ... }`
+    MacroRules(&'a str, Vec<TokenTree<'a>>),
+    MacroCall(MacroCall<'a>),
+}
+
+/// An iterator over the items of a Rust source file.  Lifetime parameters and
+/// bounds are accepted and discarded.
+pub struct ItemIterator<'a> {
+    parser: Parser<'a>,
+    attributes: Vec<Attribute<'a>>,
+    started: bool,
+    failed: bool,
+}
+
+impl<'a> ItemIterator<'a> {
+    /// Create a new Rust item iterator.
+    pub fn new(text: &'a str) -> Self {
+        ItemIterator {
+            parser: Parser::new(text),
+            attributes: Vec::new(),
+            started: false,
+            failed: false,
+        }
+    }
+
+    /// Create an item iterator over a file of a crate, with its macro calls
+    /// expanded.
+    pub fn expanded(file: &'a SourceFile) -> Self {
+        ItemIterator {
+            parser: Parser::expanded(&file.text, file.len, &file.splices),
+            attributes: Vec::new(),
+            started: false,
+            failed: false,
+        }
+    }
+
+    /// Leave out what is disabled by `#[cfg]` in the configuration `cfg`,
+    /// and expand `#[cfg_attr]`.
+    pub fn configured(self, cfg: &'a Cfg) -> Self {
+        ItemIterator { parser: self.parser.configured(cfg), ..self }
+    }
+
+    /// Get the inner attributes (`#![...]` and `//!`) of the file, which are
+    /// parsed before the first item.
+    pub fn attributes(&self) -> &[Attribute<'a>] {
+        &self.attributes
+    }
+
+    fn item(&mut self) -> Result<Option<Item<'a>>> {
+        if !self.started {
+            self.started = true;
+            self.attributes = self.parser.inner_attributes()?;
+        }
+        match self.parser.item()? {
+            Some(item) => Ok(Some(item)),
+            None => self.parser.expect_eof().map(|_| None),
+        }
+    }
+}
+
+impl<'a> Iterator for ItemIterator<'a> {
+    type Item = Result<Item<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.item() {
+            Ok(item) => item.map(Ok),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
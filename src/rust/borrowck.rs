@@ -0,0 +1,587 @@
+// Rust borrow checking
+//
+//! Borrow checking of function bodies, with regions inferred from the
+//! control flow rather than from written lifetimes, which are never
+//! consulted.
+//!
+//! Each body (and each closure in it) is lowered to a control-flow graph of
+//! assignments between places: variables and temporaries with field, index
+//! and dereference projections.  Three analyses run over the graph:
+//!
+//!  - liveness of locals, which decides where the values holding a loan may
+//!    still be used;
+//!  - the loans each local and each of its fields may hold, as references
+//!    are stored in it or computed from what it holds (a loan is live where
+//!    a local holding it is live, as with non-lexical lifetimes);
+//!  - the places that may be moved out or uninitialized.
+//!
+//! Without lifetimes, what the result of a call holds of its arguments is
+//! found from the body of the function called.  A function without a body
+//! is assumed to return loans held by its arguments when its result type
+//! can hold references, except that a method taking `&self` or `&mut self`
+//! only borrows from its receiver, as with lifetime elision.  `Drop` impls
+//! never extend loans.  These choices err on the side of accepting
+//! programs, so that all programs that work in stable are accepted.
+
+mod build;
+mod check;
+mod dataflow;
+mod summary;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::resolve::{Crate, DefId, DefKind, FileDiagnostic, ModuleId, Res};
+use super::resolve::{FileId, Namespace};
+use super::typeck::{ItemId, Ty, Types};
+use super::{Fields, Function, GenericArg, ItemKind, Type, TypeKind};
+use crate::Span;
+use build::Builder;
+use summary::Flow;
+
+type Local = usize;
+type BlockId = usize;
+
+/// What a dereferenced place is reached through
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Ptr {
+    /// Reference, `true` if mutable
+    Ref(bool),
+    Raw,
+    Box,
+    /// Smart pointer or container dereferenced with `Deref`, like `String`
+    /// to `str`, which owns what it points to
+    Overloaded,
+}
+
+/// A projection of a place
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Elem<'a> {
+    Deref(Ptr),
+    Field(&'a str),
+    /// Field of a tuple, tuple struct or tuple variant, or element of an
+    /// array pattern
+    Tuple(usize),
+    /// Element at an index that isn't known
+    Index,
+}
+
+/// A local with projections
+#[derive(Debug, Clone, PartialEq)]
+struct Place<'a> {
+    local: Local,
+    elems: Vec<Elem<'a>>,
+}
+
+impl<'a> Place<'a> {
+    fn new(local: Local) -> Self {
+        Place { local, elems: Vec::new() }
+    }
+
+    fn project(&self, elem: Elem<'a>) -> Self {
+        let mut place = self.clone();
+        place.elems.push(elem);
+        place
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand<'a> {
+    Copy(Place<'a>),
+    Move(Place<'a>),
+    Const,
+}
+
+impl<'a> Operand<'a> {
+    fn place(&self) -> Option<&Place<'a>> {
+        match *self {
+            Operand::Copy(ref place) | Operand::Move(ref place) => Some(place),
+            Operand::Const => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Rvalue<'a> {
+    Use(Operand<'a>),
+    /// `&place`, or `&mut place` if `true`
+    Ref(bool, Place<'a>),
+    /// Value computed from operands by a call, an operator or an aggregate,
+    /// holding the loans held by the listed places
+    Compute(Vec<Operand<'a>>, Vec<Place<'a>>),
+    /// Struct or tuple of operands, each listed field of which holds the
+    /// loans held by a place
+    Aggregate(Vec<Operand<'a>>, Vec<(Elem<'a>, Place<'a>)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum StatementKind<'a> {
+    Assign(Place<'a>, Rvalue<'a>),
+    /// Read a place without moving it, like a `match` scrutinee or an
+    /// operand of a comparison
+    Read(Place<'a>),
+    /// Modify a place through a mutable reference, like `a += b`
+    Mutate(Place<'a>),
+    /// The second place (and what it mutably borrows) may now hold the
+    /// loans held by the first, like a vector something is pushed to
+    Store(Place<'a>, Place<'a>),
+    /// End of the scope of a local
+    StorageDead(Local),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Statement<'a> {
+    kind: StatementKind<'a>,
+    span: Span,
+}
+
+/// How a statement uses a place
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Access {
+    /// Copy or read
+    Read,
+    Move,
+    /// Borrow, mutably if `true`
+    Borrow(bool),
+    /// Assign, overwriting the place
+    Write,
+    /// Modify through a mutable reference
+    Mutate,
+}
+
+impl<'a> Statement<'a> {
+    /// The places a statement uses, in order.
+    fn accesses(&self) -> Vec<(&Place<'a>, Access)> {
+        let mut accesses = Vec::new();
+        fn operand<'s, 'a>(operand: &'s Operand<'a>)
+            -> Option<(&'s Place<'a>, Access)>
+        {
+            match *operand {
+                Operand::Copy(ref place) => Some((place, Access::Read)),
+                Operand::Move(ref place) => Some((place, Access::Move)),
+                Operand::Const => None,
+            }
+        }
+        match self.kind {
+            StatementKind::Assign(ref dest, ref rvalue) => {
+                match *rvalue {
+                    Rvalue::Use(ref op) => accesses.extend(operand(op)),
+                    Rvalue::Ref(mutable, ref place) => {
+                        accesses.push((place, Access::Borrow(mutable)));
+                    }
+                    Rvalue::Compute(ref ops, _)
+                    | Rvalue::Aggregate(ref ops, _) => {
+                        accesses.extend(ops.iter().filter_map(operand));
+                    }
+                }
+                accesses.push((dest, Access::Write));
+            }
+            StatementKind::Read(ref place) => {
+                accesses.push((place, Access::Read));
+            }
+            StatementKind::Mutate(ref place) => {
+                accesses.push((place, Access::Mutate));
+            }
+            StatementKind::Store(..) | StatementKind::StorageDead(_) => {}
+        }
+        accesses
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Terminator {
+    Goto(BlockId),
+    /// Branch to one of the blocks, on a value read before
+    Switch(Vec<BlockId>),
+    Return,
+    Unreachable,
+}
+
+impl Terminator {
+    fn successors(&self) -> &[BlockId] {
+        match *self {
+            Terminator::Goto(ref target) => std::slice::from_ref(target),
+            Terminator::Switch(ref targets) => targets,
+            Terminator::Return | Terminator::Unreachable => &[],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct BasicBlock<'a> {
+    stmts: Vec<Statement<'a>>,
+    term: Terminator,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LocalKind {
+    /// Where the value of the body goes
+    Return,
+    /// Parameter
+    Arg,
+    /// Variable of an enclosing body captured by a closure
+    Upvar,
+    Var,
+    Temp,
+}
+
+#[derive(Debug, Clone)]
+struct LocalDecl<'a> {
+    /// Name of a variable, `None` for temporaries and the return place
+    name: Option<&'a str>,
+    kind: LocalKind,
+    mutable: bool,
+    ty: Ty<'a>,
+    /// Span of the pattern of a variable, or of the expression of a
+    /// temporary
+    span: Span,
+}
+
+impl LocalDecl<'_> {
+    /// Whether loans held by the local outlive the body, which is the case
+    /// for the values the body gets from its caller and gives back.
+    fn is_free(&self) -> bool {
+        matches!(self.kind,
+            LocalKind::Return | LocalKind::Arg | LocalKind::Upvar)
+    }
+}
+
+/// The control-flow graph of a function, closure or constant body
+#[derive(Debug)]
+struct Body<'a> {
+    /// Locals, starting with the return place
+    locals: Vec<LocalDecl<'a>>,
+    /// Basic blocks, starting with the entry
+    blocks: Vec<BasicBlock<'a>>,
+    file: FileId,
+}
+
+/// Crate-wide state of borrow checking
+struct Checker<'k, 'a> {
+    krate: &'k Crate<'a>,
+    types: &'k Types<'a>,
+    box_def: Option<DefId>,
+    // Whether each struct, enum or union can hold references.
+    holds: RefCell<HashMap<DefId, bool>>,
+    // What calls to each function hold of their arguments, if its body is
+    // known.
+    flows: RefCell<HashMap<ItemId, Option<Vec<Flow>>>>,
+    errors: RefCell<Vec<FileDiagnostic>>,
+}
+
+/// Borrow check the bodies of a crate that type checked.
+pub fn check<'a>(krate: &Crate<'a>, types: &Types<'a>)
+    -> Result<(), Vec<FileDiagnostic>>
+{
+    let cx = Checker::new(krate, types);
+    for (module_id, module) in krate.modules.iter().enumerate() {
+        if krate.is_std(module_id) {
+            continue;
+        }
+        for item in &module.items {
+            let items = match item.kind {
+                ItemKind::Impl { ref items, .. }
+                | ItemKind::Trait { ref items, .. } => items.iter().collect(),
+                _ => vec![item],
+            };
+            for item in items {
+                match item.kind {
+                    ItemKind::Fn(ref function) => {
+                        cx.check_fn(module_id, function);
+                    }
+                    ItemKind::Const(_, _, Some(ref value))
+                    | ItemKind::Static(_, _, _, Some(ref value)) => {
+                        let builder = Builder::new(&cx, module_id);
+                        for body in builder.lower_const(value) {
+                            cx.check_body(&body);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    let mut errors = cx.errors.into_inner();
+    let mut seen = Vec::new();
+    errors.retain(|error| {
+        let key = (error.file, error.diagnostic.span,
+            error.diagnostic.message.clone());
+        let new = !seen.contains(&key);
+        seen.push(key);
+        new
+    });
+    errors.sort_by_key(|error| (error.file, error.diagnostic.span.start));
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+impl<'k, 'a> Checker<'k, 'a> {
+    fn new(krate: &'k Crate<'a>, types: &'k Types<'a>) -> Self {
+        let boxed = krate.modules[krate.std()].binding("boxed", Namespace::Type)
+            .and_then(|binding| match binding.res {
+                Res::Def(def) => match krate.defs[def].kind {
+                    DefKind::Module(module) => Some(module),
+                    _ => None,
+                },
+                _ => None,
+            });
+        let box_def = boxed
+            .and_then(|module| krate.modules[module]
+                .binding("Box", Namespace::Type))
+            .and_then(|binding| match binding.res {
+                Res::Def(def) => Some(def),
+                _ => None,
+            });
+        Checker {
+            krate,
+            types,
+            box_def,
+            holds: RefCell::new(HashMap::new()),
+            flows: RefCell::new(HashMap::new()),
+            errors: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn check_fn(&self, module: ModuleId, function: &Function<'a>) {
+        if let Some(ref block) = function.body {
+            let builder = Builder::new(self, module);
+            for body in builder.lower_fn(function, block) {
+                self.check_body(&body);
+            }
+        }
+    }
+
+    fn error<T: Into<String>>(&self, file: FileId, span: Span, message: T) {
+        self.errors.borrow_mut().push(FileDiagnostic::new(file, span, message));
+    }
+
+    /// What dereferencing a value of a type goes through.
+    fn ptr(&self, ty: &Ty<'a>) -> Ptr {
+        match *ty {
+            Ty::Ref(mutable, _) => Ptr::Ref(mutable),
+            Ty::Ptr(..) => Ptr::Raw,
+            Ty::Adt(def, _) if Some(def) == self.box_def => Ptr::Box,
+            _ => Ptr::Overloaded,
+        }
+    }
+
+    /// Whether a value of a type can hold references, so loans may flow
+    /// into it.
+    fn holds_borrows(&self, ty: &Ty<'a>) -> bool {
+        match *ty {
+            Ty::Bool | Ty::Char | Ty::Str | Ty::Int(_) | Ty::Float(_)
+            | Ty::Never | Ty::Ptr(..) | Ty::Fn(..) | Ty::IntVar(_)
+            | Ty::FloatVar(_) => false,
+            Ty::Tuple(ref tys) => tys.iter().any(|ty| self.holds_borrows(ty)),
+            Ty::Array(ref ty, _) | Ty::Slice(ref ty) => self.holds_borrows(ty),
+            Ty::Adt(def, ref args) => {
+                self.adt_holds_borrows(def)
+                    || args.iter().any(|ty| self.holds_borrows(ty))
+            }
+            Ty::Ref(..) | Ty::Closure(..) | Ty::Dyn(_) | Ty::Opaque(_)
+            | Ty::Param(..) | Ty::Projection(_) | Ty::Var(_) | Ty::Infer
+            | Ty::Error => true,
+        }
+    }
+
+    // Whether a struct, enum or union has fields that can hold references
+    // other than through its generic parameters.
+    fn adt_holds_borrows(&self, def: DefId) -> bool {
+        if let Some(holds) = self.holds.borrow().get(&def) {
+            return *holds;
+        }
+        // Recursive types hold references only if another field does.
+        self.holds.borrow_mut().insert(def, false);
+        let module = self.krate.defs[def].module;
+        let holds = match self.krate.item(def).map(|item| &item.kind) {
+            Some(ItemKind::Struct(_, _, ref fields)) => {
+                self.fields_hold_borrows(module, fields)
+            }
+            Some(ItemKind::Enum(_, _, ref variants)) => variants.iter()
+                .any(|variant| self.fields_hold_borrows(module, &variant.fields)),
+            Some(ItemKind::Union(_, _, ref fields)) => fields.iter()
+                .any(|field| self.type_holds_borrows(module, &field.ty)),
+            _ => false,
+        };
+        self.holds.borrow_mut().insert(def, holds);
+        holds
+    }
+
+    fn fields_hold_borrows(&self, module: ModuleId, fields: &Fields<'a>)
+        -> bool
+    {
+        match *fields {
+            Fields::Unit => false,
+            Fields::Tuple(ref fields) | Fields::Named(ref fields) => {
+                fields.iter()
+                    .any(|field| self.type_holds_borrows(module, &field.ty))
+            }
+        }
+    }
+
+    /// Whether a written type has a reference in it, or a type that holds
+    /// one.  Generic parameters and associated types don't, as their
+    /// references come from elsewhere.
+    fn type_holds_borrows(&self, module: ModuleId, ty: &Type<'a>) -> bool {
+        match ty.kind {
+            TypeKind::Ref(..) | TypeKind::ImplTrait(_) | TypeKind::DynTrait(_) => {
+                true
+            }
+            TypeKind::Path(ref path) => {
+                let args = path.segments.iter()
+                    .flat_map(|segment| &segment.generics)
+                    .any(|arg| match *arg {
+                        GenericArg::Type(ref ty) => {
+                            self.type_holds_borrows(module, ty)
+                        }
+                        _ => false,
+                    });
+                if args {
+                    return true;
+                }
+                let resolved = self.krate.resolve_path(module, path,
+                    Namespace::Type);
+                match resolved {
+                    Ok((Res::Def(def), len)) if len == path.segments.len() => {
+                        match self.krate.item(def).map(|item| &item.kind) {
+                            Some(ItemKind::Type(_, _, _, Some(ref ty))) => {
+                                let module = self.krate.defs[def].module;
+                                self.type_holds_borrows(module, ty)
+                            }
+                            _ => self.adt_holds_borrows(def),
+                        }
+                    }
+                    _ => false,
+                }
+            }
+            TypeKind::Slice(ref ty) | TypeKind::Array(ref ty, _) => {
+                self.type_holds_borrows(module, ty)
+            }
+            TypeKind::Tuple(ref tys) => {
+                tys.iter().any(|ty| self.type_holds_borrows(module, ty))
+            }
+            TypeKind::Qualified(..) | TypeKind::Ptr(..) | TypeKind::Never
+            | TypeKind::Infer | TypeKind::Fn(..) | TypeKind::Macro(_) => false,
+        }
+    }
+
+    /// Whether the written return type of a function may borrow from its
+    /// arguments: whether lifetime elision would give it a lifetime.
+    fn output_borrows(&self, callee: ItemId) -> bool {
+        match self.function(callee) {
+            Some((module, function)) => match function.ret {
+                Some(ref ty) => self.type_holds_borrows(module, ty),
+                None => false,
+            },
+            None => true,
+        }
+    }
+
+    /// The function an item is, and the module it is in.
+    fn function(&self, callee: ItemId) -> Option<(ModuleId, &'k Function<'a>)> {
+        let krate = self.krate;
+        let (module, item) = match callee {
+            ItemId::Def(def) => (krate.defs[def].module, krate.item(def)),
+            ItemId::ImplItem(module, index, i) => {
+                match krate.modules[module].items[index].kind {
+                    ItemKind::Impl { ref items, .. } => (module, items.get(i)),
+                    _ => (module, None),
+                }
+            }
+            ItemId::TraitItem(def, i) => {
+                let items = match krate.item(def).map(|item| &item.kind) {
+                    Some(ItemKind::Trait { ref items, .. }) => items.get(i),
+                    _ => None,
+                };
+                (krate.defs[def].module, items)
+            }
+            ItemId::Impl(..) => return None,
+        };
+        match item?.kind {
+            ItemKind::Fn(ref function) => Some((module, function)),
+            _ => None,
+        }
+    }
+
+    /// Whether a struct or union has a field, named or by index.
+    fn has_field(&self, def: DefId, name: &str) -> bool {
+        match self.krate.item(def).map(|item| &item.kind) {
+            Some(ItemKind::Struct(_, _, Fields::Tuple(ref fields))) => {
+                name.parse::<usize>().is_ok_and(|i| i < fields.len())
+            }
+            Some(ItemKind::Struct(_, _, Fields::Named(ref fields)))
+            | Some(ItemKind::Union(_, _, ref fields)) => {
+                fields.iter().any(|field| field.name == Some(name))
+            }
+            _ => false,
+        }
+    }
+
+    fn is_union(&self, ty: &Ty<'a>) -> bool {
+        match *ty {
+            Ty::Adt(def, _) => matches!(
+                self.krate.item(def).map(|item| &item.kind),
+                Some(ItemKind::Union(..))),
+            _ => false,
+        }
+    }
+
+    fn is_enum(&self, ty: &Ty<'a>) -> bool {
+        match *ty {
+            Ty::Adt(def, _) => matches!(
+                self.krate.item(def).map(|item| &item.kind),
+                Some(ItemKind::Enum(..))),
+            _ => false,
+        }
+    }
+
+    /// The number of fields of a tuple struct, or of the tuple variant of an
+    /// enum with a name.
+    fn variant_len(&self, ty: &Ty<'a>, name: &str) -> Option<usize> {
+        let def = match *ty {
+            Ty::Adt(def, _) => def,
+            _ => return None,
+        };
+        let fields = match self.krate.item(def).map(|item| &item.kind) {
+            Some(ItemKind::Struct(_, _, ref fields)) => fields,
+            Some(ItemKind::Enum(_, _, ref variants)) => {
+                &variants.iter().find(|variant| variant.name == name)?.fields
+            }
+            _ => return None,
+        };
+        match *fields {
+            Fields::Tuple(ref fields) => Some(fields.len()),
+            _ => None,
+        }
+    }
+
+    /// The type of a place of a body with locals.
+    fn place_ty(&self, locals: &[LocalDecl<'a>], place: &Place<'a>)
+        -> Ty<'a>
+    {
+        let mut ty = locals[place.local].ty.clone();
+        for elem in &place.elems {
+            ty = match (elem, ty) {
+                (Elem::Deref(_), Ty::Ref(_, ty))
+                | (Elem::Deref(_), Ty::Ptr(_, ty))
+                | (Elem::Index, Ty::Array(ty, _))
+                | (Elem::Index, Ty::Slice(ty)) => *ty,
+                (Elem::Deref(Ptr::Box), Ty::Adt(_, mut args))
+                    if !args.is_empty() =>
+                {
+                    args.swap_remove(0)
+                }
+                (Elem::Tuple(index), Ty::Tuple(mut tys))
+                    if *index < tys.len() =>
+                {
+                    tys.swap_remove(*index)
+                }
+                _ => Ty::Error,
+            };
+        }
+        ty
+    }
+}
@@ -0,0 +1,1836 @@
+// Rust borrow checking: control-flow graphs
+//
+//! Lowering of function bodies to control-flow graphs.
+//!
+//! Expressions are lowered into a destination place, or to a place or an
+//! operand when their value is used by something else.  Temporaries live
+//! to the end of their statement, except those borrowed by the initializer
+//! of a `let`, which live to the end of the block.  Closures and `async`
+//! blocks are lowered to bodies of their own, and then captured by the
+//! body containing them from the places they use.
+
+use super::summary::Flow;
+use super::{
+    Access, BasicBlock, BlockId, Body, Checker, Elem, Local, LocalDecl,
+    LocalKind, Operand, Place, Ptr, Rvalue, Statement, StatementKind,
+    Terminator,
+};
+use crate::rust::resolve::{FileId, ModuleId, Namespace, Res};
+use crate::rust::typeck::{format_placeholders, FormatArg, Ty};
+use crate::rust::{
+    BinaryOp, Block, Expr, ExprKind, Function, Lit, MacroCall, Pattern,
+    PatternKind, SelfParam, StmtKind, UnaryOp,
+};
+use crate::Span;
+
+/// A loop or labeled block that `break` may leave
+struct Loop<'a> {
+    label: Option<&'a str>,
+    /// Whether this is a labeled block, which plain `break` doesn't leave
+    block: bool,
+    /// Where the values of `break` go
+    dest: Place<'a>,
+    continue_: BlockId,
+    break_: BlockId,
+    /// Number of enclosing scopes outside the loop
+    scopes: usize,
+}
+
+/// How a closure captures a place of the body containing it
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Capture {
+    Shared,
+    Mut,
+    ByValue,
+}
+
+/// The state of lowering a body
+pub(super) struct Builder<'c, 'k, 'a> {
+    cx: &'c Checker<'k, 'a>,
+    module: ModuleId,
+    file: FileId,
+    locals: Vec<LocalDecl<'a>>,
+    blocks: Vec<BasicBlock<'a>>,
+    /// Block statements are added to
+    block: BlockId,
+    exit: BlockId,
+    /// Variables in scope, innermost last
+    names: Vec<(&'a str, Local)>,
+    /// Variables of the enclosing bodies, for closures
+    outer: Vec<(&'a str, LocalDecl<'a>)>,
+    /// Variables of the enclosing bodies the body uses
+    upvars: Vec<(&'a str, Local)>,
+    /// Variables and extended temporaries of each enclosing scope
+    scopes: Vec<Vec<Local>>,
+    /// Temporaries of the statement being lowered
+    temps: Vec<Local>,
+    loops: Vec<Loop<'a>>,
+    // Variables bound by the first alternative of an or-pattern, which the
+    // others bind again.
+    reuse: Option<Vec<(&'a str, Local)>>,
+    // Scope the temporaries borrowed by the expression being lowered live
+    // in, when it is the initializer of a `let`.
+    extend: Option<usize>,
+    /// Bodies of the closures in the body
+    bodies: Vec<Body<'a>>,
+}
+
+impl<'c, 'k, 'a> Builder<'c, 'k, 'a> {
+    pub(super) fn new(cx: &'c Checker<'k, 'a>, module: ModuleId) -> Self {
+        let mut builder = Builder {
+            cx,
+            module,
+            file: cx.krate.modules[module].file,
+            locals: Vec::new(),
+            blocks: Vec::new(),
+            block: 0,
+            exit: 0,
+            names: Vec::new(),
+            outer: Vec::new(),
+            upvars: Vec::new(),
+            scopes: vec![Vec::new()],
+            temps: Vec::new(),
+            loops: Vec::new(),
+            reuse: None,
+            extend: None,
+            bodies: Vec::new(),
+        };
+        builder.new_local(None, LocalKind::Return, true, Ty::Error,
+            Span::default());
+        builder.block = builder.new_block();
+        builder.exit = builder.new_block();
+        builder
+    }
+
+    /// Lower the body of a function, and the closures in it.
+    pub(super) fn lower_fn(mut self, function: &Function<'a>, body: &Block<'a>)
+        -> Vec<Body<'a>>
+    {
+        if let Some(ref param) = function.self_param {
+            let (mutable, ty) = match *param {
+                SelfParam::Value(mutable) => (mutable, Ty::Error),
+                SelfParam::Ref(mutable) => {
+                    (false, Ty::Ref(mutable, Box::new(Ty::Error)))
+                }
+                SelfParam::Typed(mutable, _) => (mutable, Ty::Error),
+            };
+            let local = self.new_local(Some("self"), LocalKind::Arg, mutable,
+                ty, body.span);
+            self.names.push(("self", local));
+        }
+        for param in &function.params {
+            self.lower_param(&param.pattern);
+        }
+        self.lower_block(Place::new(0), body, None);
+        self.finish()
+    }
+
+    /// Lower the value of a constant or static.
+    pub(super) fn lower_const(mut self, value: &Expr<'a>) -> Vec<Body<'a>> {
+        self.expr_into(Place::new(0), value);
+        self.finish()
+    }
+
+    // Bind a parameter: a simple binding is the parameter itself.
+    fn lower_param(&mut self, pattern: &Pattern<'a>) {
+        let ty = self.pattern_ty(pattern);
+        if let PatternKind::Ident(false, mutable, name, None) = pattern.kind {
+            if let Some(ty) = self.binding_ty(pattern) {
+                let local = self.new_local(Some(name), LocalKind::Arg, mutable,
+                    ty, pattern.span);
+                self.names.push((name, local));
+                return;
+            }
+        }
+        let local = self.new_local(None, LocalKind::Arg, false, ty,
+            pattern.span);
+        self.bind_pattern(pattern, Place::new(local), None, false);
+    }
+
+    // End the body at its exit, where its variables go out of scope.
+    fn finish(mut self) -> Vec<Body<'a>> {
+        self.goto(self.exit);
+        self.block = self.exit;
+        let mut dead: Vec<_> = (1..self.locals.len())
+            .filter(|&local| !self.locals[local].is_free())
+            .collect();
+        dead.extend((1..self.locals.len())
+            .filter(|&local| self.locals[local].kind == LocalKind::Arg));
+        for local in dead {
+            self.push(StatementKind::StorageDead(local), Span::default());
+        }
+        self.terminate(Terminator::Return);
+        let mut bodies = std::mem::take(&mut self.bodies);
+        bodies.push(Body {
+            locals: self.locals,
+            blocks: self.blocks,
+            file: self.file,
+        });
+        bodies
+    }
+
+    fn new_local(
+        &mut self,
+        name: Option<&'a str>,
+        kind: LocalKind,
+        mutable: bool,
+        ty: Ty<'a>,
+        span: Span,
+    ) -> Local {
+        self.locals.push(LocalDecl { name, kind, mutable, ty, span });
+        self.locals.len() - 1
+    }
+
+    fn temp(&mut self, ty: Ty<'a>, span: Span) -> Local {
+        let local = self.new_local(None, LocalKind::Temp, true, ty, span);
+        self.temps.push(local);
+        local
+    }
+
+    fn new_block(&mut self) -> BlockId {
+        self.blocks.push(BasicBlock {
+            stmts: Vec::new(),
+            term: Terminator::Unreachable,
+        });
+        self.blocks.len() - 1
+    }
+
+    fn push(&mut self, kind: StatementKind<'a>, span: Span) {
+        self.blocks[self.block].stmts.push(Statement { kind, span });
+    }
+
+    fn assign(&mut self, dest: Place<'a>, rvalue: Rvalue<'a>, span: Span) {
+        // A value that can't hold references holds no loans, even when read
+        // through one.
+        let rvalue = match rvalue {
+            Rvalue::Use(operand) => match operand.place() {
+                Some(place) if !self.cx.holds_borrows(&self.place_ty(place)) => {
+                    Rvalue::Compute(vec![operand], Vec::new())
+                }
+                _ => Rvalue::Use(operand),
+            },
+            rvalue => rvalue,
+        };
+        self.push(StatementKind::Assign(dest, rvalue), span);
+    }
+
+    fn terminate(&mut self, term: Terminator) {
+        self.blocks[self.block].term = term;
+    }
+
+    fn goto(&mut self, target: BlockId) {
+        self.terminate(Terminator::Goto(target));
+    }
+
+    // Branch to new blocks, returning them.
+    fn switch(&mut self, count: usize) -> Vec<BlockId> {
+        let targets: Vec<_> = (0..count).map(|_| self.new_block()).collect();
+        self.terminate(Terminator::Switch(targets.clone()));
+        targets
+    }
+
+    // Continue in a block nothing reaches, after an expression of type `!`.
+    fn diverge(&mut self) {
+        self.terminate(Terminator::Unreachable);
+        self.block = self.new_block();
+    }
+
+    fn ty(&self, expr: &Expr<'a>) -> Ty<'a> {
+        self.cx.types.exprs.get(&(self.file, expr.span)).cloned()
+            .unwrap_or(Ty::Error)
+    }
+
+    fn pattern_ty(&self, pattern: &Pattern<'a>) -> Ty<'a> {
+        self.cx.types.patterns.get(&(self.file, pattern.span)).cloned()
+            .unwrap_or(Ty::Error)
+    }
+
+    fn binding_ty(&self, pattern: &Pattern<'a>) -> Option<Ty<'a>> {
+        self.cx.types.bindings.get(&(self.file, pattern.span)).cloned()
+    }
+
+    fn is_moved(&self, span: Span) -> bool {
+        self.cx.types.moves.contains(&(self.file, span))
+    }
+
+    fn place_ty(&self, place: &Place<'a>) -> Ty<'a> {
+        self.cx.place_ty(&self.locals, place)
+    }
+
+    // Start a scope for variables.
+    fn push_scope(&mut self) -> usize {
+        self.scopes.push(Vec::new());
+        self.names.len()
+    }
+
+    // End a scope, which its variables don't outlive.
+    fn pop_scope(&mut self, names: usize) {
+        let locals = self.scopes.pop().unwrap_or_default();
+        for local in locals.into_iter().rev() {
+            self.push(StatementKind::StorageDead(local), Span::default());
+        }
+        self.names.truncate(names);
+    }
+
+    // End the temporaries of a statement.
+    fn drop_temps(&mut self, mark: usize) {
+        let temps = self.temps.split_off(mark.min(self.temps.len()));
+        for local in temps.into_iter().rev() {
+            self.push(StatementKind::StorageDead(local), Span::default());
+        }
+    }
+
+    // Make a temporary live as long as the variables of a scope.
+    fn extend_temp(&mut self, local: Local, scope: usize) {
+        if let Some(index) = self.temps.iter().rposition(|&temp| temp == local) {
+            self.temps.remove(index);
+            self.scopes[scope].push(local);
+        }
+    }
+
+    /// Find a variable, capturing it from the enclosing bodies of a
+    /// closure.
+    fn lookup(&mut self, name: &'a str) -> Option<Local> {
+        if let Some(&(_, local)) = self.names.iter().rev()
+            .find(|(found, _)| *found == name)
+        {
+            return Some(local);
+        }
+        if let Some(&(_, local)) = self.upvars.iter()
+            .find(|(found, _)| *found == name)
+        {
+            return Some(local);
+        }
+        let decl = self.outer.iter().rev()
+            .find(|(found, _)| *found == name)
+            .map(|(_, decl)| decl.clone())?;
+        let local = self.new_local(Some(name), LocalKind::Upvar, decl.mutable,
+            decl.ty, decl.span);
+        self.upvars.push((name, local));
+        Some(local)
+    }
+
+    // The variable a path expression names.
+    fn local_of(&mut self, expr: &Expr<'a>) -> Option<Local> {
+        let path = match expr.kind {
+            ExprKind::Path(ref path) => path,
+            _ => return None,
+        };
+        if path.global || path.segments.len() != 1
+            || !path.segments[0].generics.is_empty()
+        {
+            return None;
+        }
+        let local = self.lookup(path.segments[0].name)?;
+        if self.locals[local].ty == Ty::Error {
+            self.locals[local].ty = self.ty(expr);
+        }
+        Some(local)
+    }
+
+    fn is_place(&mut self, expr: &Expr<'a>) -> bool {
+        match expr.kind {
+            ExprKind::Path(_) => self.local_of(expr).is_some(),
+            ExprKind::Field(..) | ExprKind::Index(..) => true,
+            ExprKind::Unary(UnaryOp::Deref, _) => true,
+            _ => false,
+        }
+    }
+
+    /// Lower an expression to the place it refers to, or to a temporary
+    /// holding its value.
+    fn as_place(&mut self, expr: &Expr<'a>) -> Place<'a> {
+        match expr.kind {
+            ExprKind::Path(_) => {
+                if let Some(local) = self.local_of(expr) {
+                    self.extend = None;
+                    return Place::new(local);
+                }
+            }
+            ExprKind::Field(ref base, name) => {
+                self.extend = None;
+                let place = self.as_place(base);
+                return self.field(place, self.ty(base), name);
+            }
+            ExprKind::Index(ref base, ref index) => {
+                self.extend = None;
+                let place = self.as_place(base);
+                let index = self.as_operand(index);
+                self.consume(index, expr.span);
+                let (place, _) = self.autoderef(place, self.ty(base),
+                    |ty| matches!(ty, Ty::Array(..) | Ty::Slice(_)));
+                return place.project(Elem::Index);
+            }
+            ExprKind::Unary(UnaryOp::Deref, ref operand) => {
+                self.extend = None;
+                let place = self.as_place(operand);
+                let ptr = self.cx.ptr(&self.ty(operand));
+                return place.project(Elem::Deref(ptr));
+            }
+            _ => {}
+        }
+        let temp = self.temp(self.ty(expr), expr.span);
+        self.expr_into(Place::new(temp), expr);
+        Place::new(temp)
+    }
+
+    /// Lower an expression to an operand, which copies or moves its value.
+    fn as_operand(&mut self, expr: &Expr<'a>) -> Operand<'a> {
+        if let ExprKind::Lit(_) = expr.kind {
+            return Operand::Const;
+        }
+        if let ExprKind::Path(_) | ExprKind::Qualified(..) = expr.kind {
+            if self.local_of(expr).is_none() {
+                return Operand::Const;
+            }
+        }
+        let place = self.as_place(expr);
+        match self.is_moved(expr.span) {
+            true => Operand::Move(place),
+            false => Operand::Copy(place),
+        }
+    }
+
+    /// Lower an argument, which reborrows mutable references instead of
+    /// moving them.
+    fn as_arg(&mut self, expr: &Expr<'a>) -> Operand<'a> {
+        let ty = self.ty(expr);
+        if let Ty::Ref(true, _) = ty {
+            if self.is_place(expr) {
+                let place = self.as_place(expr)
+                    .project(Elem::Deref(Ptr::Ref(true)));
+                let temp = self.temp(ty, expr.span);
+                self.assign(Place::new(temp), Rvalue::Ref(true, place),
+                    expr.span);
+                return Operand::Move(Place::new(temp));
+            }
+        }
+        self.as_operand(expr)
+    }
+
+    // Use the value of an operand for its effects.
+    fn consume(&mut self, operand: Operand<'a>, span: Span) {
+        if let Operand::Move(_) = operand {
+            let temp = self.temp(Ty::Error, span);
+            self.assign(Place::new(temp), Rvalue::Use(operand), span);
+        } else if let Some(place) = operand.place() {
+            self.push(StatementKind::Read(place.clone()), span);
+        }
+    }
+
+    // Read the value of an expression without moving it, like an operand
+    // of a comparison.
+    fn read(&mut self, expr: &Expr<'a>) {
+        let place = self.as_place(expr);
+        self.push(StatementKind::Read(place), expr.span);
+    }
+
+    // Borrow the value of an expression for a moment, as formatting macros
+    // do.
+    fn borrow(&mut self, expr: &Expr<'a>) {
+        let place = self.as_place(expr);
+        let ty = Ty::Ref(false, Box::new(self.ty(expr)));
+        let temp = self.temp(ty, expr.span);
+        self.assign(Place::new(temp), Rvalue::Ref(false, place), expr.span);
+    }
+
+    // Dereference a place of a type until its type satisfies a condition,
+    // returning the place and its type.
+    fn autoderef<F>(&mut self, mut place: Place<'a>, mut ty: Ty<'a>, done: F)
+        -> (Place<'a>, Ty<'a>)
+    where
+        F: Fn(&Ty<'a>) -> bool,
+    {
+        for _ in 0..16 {
+            if done(&ty) || ty == Ty::Error {
+                break;
+            }
+            let (elem, next) = self.deref(&ty);
+            place = place.project(elem);
+            ty = next;
+        }
+        (place, ty)
+    }
+
+    // The projection dereferencing a value of a type, and the type it
+    // points to if it is known.
+    fn deref(&self, ty: &Ty<'a>) -> (Elem<'a>, Ty<'a>) {
+        let ptr = self.cx.ptr(ty);
+        let target = match *ty {
+            Ty::Ref(_, ref inner) | Ty::Ptr(_, ref inner) => (**inner).clone(),
+            Ty::Adt(_, ref args) if ptr == Ptr::Box && !args.is_empty() => {
+                args[0].clone()
+            }
+            _ => Ty::Error,
+        };
+        (Elem::Deref(ptr), target)
+    }
+
+    // Project a place of a type to one of its fields, dereferencing it as
+    // needed.
+    fn field(&mut self, place: Place<'a>, ty: Ty<'a>, name: &'a str)
+        -> Place<'a>
+    {
+        let index = name.parse::<usize>().ok();
+        let cx = self.cx;
+        let (place, _) = self.autoderef(place, ty, |ty| match *ty {
+            Ty::Tuple(ref tys) => index.is_some_and(|index| index < tys.len()),
+            Ty::Adt(def, _) => cx.has_field(def, name),
+            _ => false,
+        });
+        match index {
+            Some(index) => place.project(Elem::Tuple(index)),
+            None => place.project(Elem::Field(name)),
+        }
+    }
+
+    /// Lower an expression into a place.
+    fn expr_into(&mut self, dest: Place<'a>, expr: &Expr<'a>) {
+        let extend = self.extend.take();
+        let span = expr.span;
+        let ty = self.ty(expr);
+        match expr.kind {
+            ExprKind::Lit(_) | ExprKind::Qualified(..) => {
+                self.assign(dest, Rvalue::Use(Operand::Const), span);
+            }
+            ExprKind::Path(_)
+            | ExprKind::Field(..)
+            | ExprKind::Index(..)
+            | ExprKind::Unary(UnaryOp::Deref, _) => {
+                let operand = self.as_operand(expr);
+                self.assign(dest, Rvalue::Use(operand), span);
+            }
+            ExprKind::Tuple(ref exprs) => {
+                let fields = exprs.iter().enumerate()
+                    .map(|(i, expr)| {
+                        self.extend = extend;
+                        (Elem::Tuple(i), self.as_operand(expr))
+                    })
+                    .collect();
+                self.aggregate(dest, fields, span);
+            }
+            ExprKind::Array(ref exprs) => {
+                let operands = exprs.iter()
+                    .map(|expr| {
+                        self.extend = extend;
+                        self.as_operand(expr)
+                    })
+                    .collect();
+                self.compute(dest, operands, &ty, span);
+            }
+            ExprKind::Repeat(ref value, _) => {
+                let operand = self.as_operand(value);
+                self.compute(dest, vec![operand], &ty, span);
+            }
+            ExprKind::Struct(_, ref fields, None) if !self.cx.is_union(&ty) => {
+                let fields = fields.iter()
+                    .map(|field| {
+                        self.extend = extend;
+                        let elem = match field.name.parse::<usize>() {
+                            Ok(index) => Elem::Tuple(index),
+                            Err(_) => Elem::Field(field.name),
+                        };
+                        (elem, self.as_operand(&field.value))
+                    })
+                    .collect();
+                self.aggregate(dest, fields, span);
+            }
+            ExprKind::Struct(_, ref fields, ref base) => {
+                let mut operands: Vec<_> = fields.iter()
+                    .map(|field| {
+                        self.extend = extend;
+                        self.as_operand(&field.value)
+                    })
+                    .collect();
+                // The fields taken from the base are only read.
+                if let Some(ref base) = base {
+                    let place = self.as_place(base);
+                    operands.push(Operand::Copy(place));
+                }
+                self.compute(dest, operands, &ty, span);
+            }
+            ExprKind::Unary(_, ref operand) | ExprKind::Await(ref operand) => {
+                let operand = self.as_operand(operand);
+                self.compute(dest, vec![operand], &ty, span);
+            }
+            ExprKind::Cast(ref operand, _) => {
+                self.extend = extend;
+                let operand = self.as_operand(operand);
+                self.compute(dest, vec![operand], &ty, span);
+            }
+            ExprKind::Ref(mutable, ref operand) => {
+                if !mutable && self.is_promotable(operand) {
+                    self.assign(dest, Rvalue::Use(Operand::Const), span);
+                    return;
+                }
+                let place = self.as_place(operand);
+                if let (Some(scope), true) = (extend, place.elems.is_empty()) {
+                    self.extend_temp(place.local, scope);
+                }
+                self.assign(dest, Rvalue::Ref(mutable, place), span);
+            }
+            ExprKind::Binary(BinaryOp::And, ..)
+            | ExprKind::Binary(BinaryOp::Or, ..) => {
+                let (yes, no) = self.lower_cond(expr);
+                let join = self.new_block();
+                for block in [yes, no] {
+                    self.block = block;
+                    self.assign(dest.clone(), Rvalue::Use(Operand::Const), span);
+                    self.goto(join);
+                }
+                self.block = join;
+            }
+            ExprKind::Binary(op, ref lhs, ref rhs) => match op {
+                BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le
+                | BinaryOp::Gt | BinaryOp::Ge => {
+                    self.read(lhs);
+                    self.read(rhs);
+                    self.assign(dest, Rvalue::Compute(Vec::new(), Vec::new()),
+                        span);
+                }
+                _ => {
+                    let lhs = self.as_operand(lhs);
+                    let rhs = self.as_operand(rhs);
+                    self.compute(dest, vec![lhs, rhs], &ty, span);
+                }
+            },
+            ExprKind::Assign(ref lhs, ref rhs) => {
+                self.lower_assign(lhs, rhs);
+                self.assign(dest, Rvalue::Use(Operand::Const), span);
+            }
+            ExprKind::AssignOp(_, ref lhs, ref rhs) => {
+                let operand = self.as_operand(rhs);
+                let place = self.as_place(lhs);
+                self.consume(operand, rhs.span);
+                self.push(StatementKind::Mutate(place), span);
+                self.assign(dest, Rvalue::Use(Operand::Const), span);
+            }
+            ExprKind::Call(ref callee, ref args) => {
+                let mut flows = Vec::new();
+                match callee.kind {
+                    ExprKind::Path(_) | ExprKind::Qualified(..)
+                        if !self.is_place(callee) => {}
+                    _ => {
+                        let place = self.as_place(callee);
+                        flows.push(place.clone());
+                        self.push(StatementKind::Read(place), callee.span);
+                    }
+                }
+                let operands: Vec<_> = args.iter()
+                    .map(|arg| self.as_arg(arg))
+                    .collect();
+                let summary = self.cx.types.callees
+                    .get(&(self.file, callee.span))
+                    .and_then(|callee| self.cx.flows(*callee))
+                    .filter(|summary| summary.len() == operands.len());
+                self.call(dest, operands, flows, summary.as_deref(), &ty,
+                    span);
+            }
+            ExprKind::MethodCall(ref receiver, _, ref args) => {
+                self.lower_method_call(dest, expr, receiver, args);
+            }
+            ExprKind::Try(ref operand) => {
+                let place = self.as_place(operand);
+                self.push(StatementKind::Read(place.clone()), operand.span);
+                let targets = self.switch(2);
+                self.block = targets[0];
+                let error = Rvalue::Compute(vec![Operand::Move(place.clone())],
+                    Vec::new());
+                self.assign(Place::new(0), error, span);
+                self.goto(self.exit);
+                self.block = targets[1];
+                let value = place.project(Elem::Tuple(0));
+                let value = match self.is_moved(span) {
+                    true => Operand::Move(value),
+                    false => Operand::Copy(value),
+                };
+                self.assign(dest, Rvalue::Use(value), span);
+            }
+            ExprKind::Range(ref start, ref end, _) => {
+                let operands = start.iter().chain(end)
+                    .map(|expr| self.as_operand(expr))
+                    .collect();
+                self.compute(dest, operands, &ty, span);
+            }
+            ExprKind::Block(None, ref block)
+            | ExprKind::Unsafe(ref block)
+            | ExprKind::Const(ref block) => {
+                self.lower_block(dest, block, extend);
+            }
+            ExprKind::Block(Some(label), ref block) => {
+                let break_ = self.new_block();
+                self.loops.push(Loop {
+                    label: Some(label),
+                    block: true,
+                    dest: dest.clone(),
+                    continue_: break_,
+                    break_,
+                    scopes: self.scopes.len(),
+                });
+                self.lower_block(dest, block, extend);
+                self.loops.pop();
+                self.goto(break_);
+                self.block = break_;
+            }
+            ExprKind::Async(is_move, ref block) => {
+                self.lower_closure(dest, &[], is_move, span, |builder| {
+                    builder.lower_block(Place::new(0), block, None);
+                });
+            }
+            ExprKind::Closure(ref closure) => {
+                let params: Vec<_> = closure.params.iter()
+                    .map(|(pattern, _)| pattern)
+                    .collect();
+                self.lower_closure(dest, &params, closure.is_move, span,
+                    |builder| builder.expr_into(Place::new(0), &closure.body));
+            }
+            ExprKind::If(ref cond, ref then, ref otherwise) => {
+                let names = self.push_scope();
+                let (yes, no) = self.lower_cond(cond);
+                let join = self.new_block();
+                self.block = yes;
+                self.lower_block(dest.clone(), then, None);
+                self.goto(join);
+                self.block = no;
+                match otherwise {
+                    Some(otherwise) => self.expr_into(dest, otherwise),
+                    None => self.assign(dest, Rvalue::Use(Operand::Const), span),
+                }
+                self.goto(join);
+                self.block = join;
+                self.pop_scope(names);
+            }
+            ExprKind::Let(..) => {
+                let (yes, no) = self.lower_cond(expr);
+                let join = self.new_block();
+                for block in [yes, no] {
+                    self.block = block;
+                    self.assign(dest.clone(), Rvalue::Use(Operand::Const), span);
+                    self.goto(join);
+                }
+                self.block = join;
+            }
+            ExprKind::While(label, ref cond, ref body) => {
+                let head = self.new_block();
+                self.goto(head);
+                self.block = head;
+                let names = self.push_scope();
+                let (yes, no) = self.lower_cond(cond);
+                let break_ = self.new_block();
+                self.block = no;
+                self.goto(break_);
+                self.block = yes;
+                let scopes = self.scopes.len() - 1;
+                let target = (head, break_);
+                self.lower_loop_body(label, dest.clone(), target, scopes, body);
+                self.scopes.pop();
+                self.names.truncate(names);
+                self.block = break_;
+                self.assign(dest, Rvalue::Use(Operand::Const), span);
+            }
+            ExprKind::Loop(label, ref body) => {
+                let head = self.new_block();
+                self.goto(head);
+                self.block = head;
+                let break_ = self.new_block();
+                let scopes = self.scopes.len();
+                self.lower_loop_body(label, dest, (head, break_), scopes, body);
+                self.block = break_;
+            }
+            ExprKind::For(label, ref pattern, ref iter, ref body) => {
+                let names = self.push_scope();
+                let operand = self.as_operand(iter);
+                let iter_ty = self.ty(iter);
+                let it = self.new_local(None, LocalKind::Temp, true, iter_ty,
+                    iter.span);
+                self.scopes.last_mut().unwrap().push(it);
+                self.compute(Place::new(it), vec![operand], &Ty::Error,
+                    iter.span);
+                let head = self.new_block();
+                self.goto(head);
+                self.block = head;
+                // `next` borrows the iterator mutably.
+                self.push(StatementKind::Mutate(Place::new(it)), iter.span);
+                let item_ty = self.pattern_ty(pattern);
+                let item = self.new_local(None, LocalKind::Temp, true,
+                    item_ty, pattern.span);
+                let next = Rvalue::Compute(Vec::new(), vec![Place::new(it)]);
+                self.assign(Place::new(item), next, iter.span);
+                let targets = self.switch(2);
+                let break_ = targets[1];
+                self.block = targets[0];
+                let inner = self.push_scope();
+                self.scopes.last_mut().unwrap().push(item);
+                self.bind_pattern(pattern, Place::new(item), None, false);
+                let scopes = self.scopes.len() - 1;
+                let target = (head, break_);
+                self.lower_loop_body(label, dest.clone(), target, scopes, body);
+                self.scopes.pop();
+                self.names.truncate(inner);
+                self.block = break_;
+                self.push(StatementKind::StorageDead(item), span);
+                self.assign(dest, Rvalue::Use(Operand::Const), span);
+                self.pop_scope(names);
+            }
+            ExprKind::Match(ref scrutinee, ref arms) => {
+                let place = self.as_place(scrutinee);
+                self.push(StatementKind::Read(place.clone()), scrutinee.span);
+                let entries = self.switch(arms.len());
+                let join = self.new_block();
+                for (i, arm) in arms.iter().enumerate() {
+                    self.block = entries[i];
+                    let names = self.push_scope();
+                    if let Some(ref guard) = arm.guard {
+                        // The guard sees the bindings without moving them.
+                        self.bind_pattern(&arm.pattern, place.clone(), None,
+                            true);
+                        let (yes, no) = self.lower_cond(guard);
+                        self.block = no;
+                        self.exit_scopes(self.scopes.len() - 1);
+                        match entries.get(i + 1) {
+                            Some(&next) => self.goto(next),
+                            None => self.terminate(Terminator::Unreachable),
+                        }
+                        self.block = yes;
+                        self.names.truncate(names);
+                    }
+                    self.bind_pattern(&arm.pattern, place.clone(), None, false);
+                    self.expr_into(dest.clone(), &arm.body);
+                    self.pop_scope(names);
+                    self.goto(join);
+                }
+                self.block = join;
+            }
+            ExprKind::Break(label, ref value) => {
+                let index = match label {
+                    Some(label) => self.loops.iter()
+                        .rposition(|lp| lp.label == Some(label)),
+                    None => self.loops.iter().rposition(|lp| !lp.block),
+                };
+                let index = match index {
+                    Some(index) => index,
+                    None => return self.diverge(),
+                };
+                let loop_dest = self.loops[index].dest.clone();
+                match value {
+                    Some(value) => self.expr_into(loop_dest, value),
+                    None => {
+                        self.assign(loop_dest, Rvalue::Use(Operand::Const), span)
+                    }
+                }
+                let (scopes, target) = (self.loops[index].scopes,
+                    self.loops[index].break_);
+                self.exit_scopes(scopes);
+                self.goto(target);
+                self.diverge();
+            }
+            ExprKind::Continue(label) => {
+                let index = match label {
+                    Some(label) => self.loops.iter()
+                        .rposition(|lp| lp.label == Some(label)),
+                    None => self.loops.iter().rposition(|lp| !lp.block),
+                };
+                if let Some(index) = index {
+                    let (scopes, target) = (self.loops[index].scopes,
+                        self.loops[index].continue_);
+                    self.exit_scopes(scopes);
+                    self.goto(target);
+                }
+                self.diverge();
+            }
+            ExprKind::Return(ref value) => {
+                match value {
+                    Some(value) => self.expr_into(Place::new(0), value),
+                    None => self.assign(Place::new(0),
+                        Rvalue::Use(Operand::Const), span),
+                }
+                self.goto(self.exit);
+                self.diverge();
+            }
+            ExprKind::Macro(ref call) => self.lower_macro(dest, call, &ty),
+        }
+        if ty == Ty::Never {
+            self.diverge();
+        }
+    }
+
+    // Whether the operand of `&` is a constant, whose reference is to a
+    // static value.
+    fn is_promotable(&mut self, expr: &Expr<'a>) -> bool {
+        match expr.kind {
+            ExprKind::Lit(_) | ExprKind::Qualified(..) => true,
+            ExprKind::Path(_) => self.local_of(expr).is_none(),
+            ExprKind::Unary(UnaryOp::Neg, ref operand) => {
+                self.is_promotable(operand)
+            }
+            ExprKind::Tuple(ref exprs) | ExprKind::Array(ref exprs) => {
+                exprs.iter().all(|expr| self.is_promotable(expr))
+            }
+            ExprKind::Field(ref base, _) => self.is_promotable(base),
+            ExprKind::Index(ref base, ref index) => {
+                matches!(self.ty(base), Ty::Array(..))
+                    && self.is_promotable(base) && self.is_promotable(index)
+            }
+            _ => false,
+        }
+    }
+
+    // Assign the value of an expression computed from operands.
+    fn compute(
+        &mut self,
+        dest: Place<'a>,
+        operands: Vec<Operand<'a>>,
+        ty: &Ty<'a>,
+        span: Span,
+    ) {
+        let flows = match self.cx.holds_borrows(ty) {
+            true => self.roots(&operands),
+            false => Vec::new(),
+        };
+        self.assign(dest, Rvalue::Compute(operands, flows), span);
+    }
+
+    // Assign a struct or tuple of operands, each field of which holds the
+    // loans of its own operand.
+    fn aggregate(
+        &mut self,
+        dest: Place<'a>,
+        fields: Vec<(Elem<'a>, Operand<'a>)>,
+        span: Span,
+    ) {
+        let flows = fields.iter()
+            .filter_map(|(elem, operand)| {
+                let place = operand.place()?;
+                let holds = self.cx.holds_borrows(&self.place_ty(place));
+                holds.then(|| (*elem, place.clone()))
+            })
+            .collect();
+        let operands = fields.into_iter().map(|(_, operand)| operand).collect();
+        self.assign(dest, Rvalue::Aggregate(operands, flows), span);
+    }
+
+    // The places of operands that may hold loans.
+    fn roots(&self, operands: &[Operand<'a>]) -> Vec<Place<'a>> {
+        let mut roots = Vec::new();
+        for operand in operands {
+            if let Some(place) = operand.place() {
+                let ty = self.place_ty(place);
+                if self.cx.holds_borrows(&ty) && !roots.contains(place) {
+                    roots.push(place.clone());
+                }
+            }
+        }
+        roots
+    }
+
+    // Assign the result of a call, whose arguments may store the loans of
+    // the others in what they mutably borrow.  The result holds the loans
+    // of the arguments the body of the function lets through, or of all of
+    // them if it isn't known.
+    fn call(
+        &mut self,
+        dest: Place<'a>,
+        operands: Vec<Operand<'a>>,
+        mut flows: Vec<Place<'a>>,
+        summary: Option<&[Flow]>,
+        ty: &Ty<'a>,
+        span: Span,
+    ) {
+        self.store_args(&operands, span);
+        match self.cx.holds_borrows(ty) {
+            true => flows.extend(self.through(&operands, summary)),
+            false => flows.clear(),
+        }
+        self.assign(dest, Rvalue::Compute(operands, flows), span);
+    }
+
+    // The places of the arguments of a call whose loans its result may
+    // hold: what a reference points to, when the result doesn't hold the
+    // reference itself.
+    fn through(&self, operands: &[Operand<'a>], summary: Option<&[Flow]>)
+        -> Vec<Place<'a>>
+    {
+        let summary = match summary {
+            Some(summary) => summary,
+            None => return self.roots(operands),
+        };
+        let operands: Vec<_> = operands.iter().zip(summary)
+            .filter_map(|(operand, flow)| {
+                let place = operand.place()?;
+                match (flow.outer, flow.inner, self.place_ty(place)) {
+                    (true, _, _) => Some(place.clone()),
+                    (false, true, Ty::Ref(mutable, _)) => {
+                        Some(place.project(Elem::Deref(Ptr::Ref(mutable))))
+                    }
+                    (false, true, _) => Some(place.clone()),
+                    (false, false, _) => None,
+                }
+            })
+            .map(Operand::Copy)
+            .collect();
+        self.roots(&operands)
+    }
+
+    fn store_args(&mut self, operands: &[Operand<'a>], span: Span) {
+        let roots = self.roots(operands);
+        for operand in operands {
+            let place = match operand.place() {
+                Some(place) => place,
+                None => continue,
+            };
+            let stores = match self.place_ty(place) {
+                Ty::Ref(true, inner) => self.cx.holds_borrows(&inner),
+                _ => false,
+            };
+            if !stores {
+                continue;
+            }
+            for root in &roots {
+                if root != place {
+                    self.push(StatementKind::Store(root.clone(), place.clone()),
+                        span);
+                }
+            }
+        }
+    }
+
+    fn lower_method_call(
+        &mut self,
+        dest: Place<'a>,
+        expr: &Expr<'a>,
+        receiver: &Expr<'a>,
+        args: &[Expr<'a>],
+    ) {
+        let span = expr.span;
+        let ty = self.ty(expr);
+        let method = match self.cx.types.methods.get(&(self.file, span)) {
+            Some(method) => method.clone(),
+            None => {
+                let mut operands = vec![self.as_operand(receiver)];
+                operands.extend(args.iter().map(|arg| self.as_arg(arg)));
+                return self.call(dest, operands, Vec::new(), None, &ty, span);
+            }
+        };
+        let (receiver, by_ref, autoref, operands) = match method.autoref {
+            Some(false) if self.is_promotable(receiver)
+                && self.derefs(Place::new(0), self.ty(receiver),
+                    method.derefs).0.elems.is_empty() =>
+            {
+                let operands: Vec<_> = args.iter()
+                    .map(|arg| self.as_arg(arg))
+                    .collect();
+                let ref_ty = Ty::Ref(false, Box::new(self.ty(receiver)));
+                let temp = self.temp(ref_ty, receiver.span);
+                self.assign(Place::new(temp), Rvalue::Use(Operand::Const),
+                    span);
+                (Operand::Move(Place::new(temp)), Some(Place::new(temp)), None,
+                    operands)
+            }
+            Some(mutable) => {
+                let place = self.as_place(receiver);
+                let (place, target) = self.derefs(place, self.ty(receiver),
+                    method.derefs);
+                // Two-phase borrow: the arguments are evaluated first.
+                let operands: Vec<_> = args.iter()
+                    .map(|arg| self.as_arg(arg))
+                    .collect();
+                let ref_ty = Ty::Ref(mutable, Box::new(target));
+                let temp = self.temp(ref_ty, receiver.span);
+                let root = place.clone();
+                self.assign(Place::new(temp), Rvalue::Ref(mutable, place),
+                    span);
+                (Operand::Move(Place::new(temp)), Some(root), Some(temp),
+                    operands)
+            }
+            None => {
+                let operand = match method.derefs {
+                    0 => self.as_arg(receiver),
+                    derefs => {
+                        let place = self.as_place(receiver);
+                        let (place, _) = self.derefs(place, self.ty(receiver),
+                            derefs);
+                        Operand::Copy(place)
+                    }
+                };
+                let by_ref = match operand.place() {
+                    Some(place) => match self.place_ty(place) {
+                        Ty::Ref(..) => Some(place.clone()),
+                        _ => None,
+                    },
+                    None => None,
+                };
+                let operands = args.iter().map(|arg| self.as_arg(arg)).collect();
+                (operand, by_ref, None, operands)
+            }
+        };
+        let mut all = vec![receiver];
+        all.extend(operands);
+        let summary = self.cx.flows(method.callee)
+            .filter(|summary| summary.len() == all.len());
+        let root = match by_ref {
+            Some(root) if summary.is_none() => root,
+            _ => {
+                return self.call(dest, all, Vec::new(), summary.as_deref(),
+                    &ty, span);
+            }
+        };
+        // Without the body, a method taking `&self` or `&mut self` is taken
+        // to only borrow from its receiver, as with lifetime elision.
+        self.store_args(&all, span);
+        let mut flows = Vec::new();
+        if self.cx.holds_borrows(&ty) {
+            flows.push(root);
+            if let Some(temp) = autoref {
+                if self.cx.output_borrows(method.callee) {
+                    flows.push(Place::new(temp));
+                }
+            }
+        }
+        self.assign(dest, Rvalue::Compute(all, flows), span);
+    }
+
+    // Dereference a place a number of times, returning the place and its
+    // type.
+    fn derefs(&mut self, mut place: Place<'a>, mut ty: Ty<'a>, count: usize)
+        -> (Place<'a>, Ty<'a>)
+    {
+        for _ in 0..count {
+            // An array is unsized to a slice in place.
+            if let Ty::Array(elem, _) = ty {
+                ty = Ty::Slice(elem);
+                continue;
+            }
+            let (elem, next) = self.deref(&ty);
+            place = place.project(elem);
+            ty = next;
+        }
+        (place, ty)
+    }
+
+    fn lower_assign(&mut self, lhs: &Expr<'a>, rhs: &Expr<'a>) {
+        if let ExprKind::Tuple(ref lhs) = lhs.kind {
+            // Destructuring assignment
+            let place = self.as_place(rhs);
+            for (i, lhs) in lhs.iter().enumerate() {
+                let value = place.project(Elem::Tuple(i));
+                let value = match self.is_moved(lhs.span) {
+                    true => Operand::Move(value),
+                    false => Operand::Copy(value),
+                };
+                if let Some(dest) = self.assign_place(lhs) {
+                    self.assign(dest, Rvalue::Use(value), lhs.span);
+                }
+            }
+            return;
+        }
+        let operand = match self.ty(lhs) {
+            Ty::Ref(true, _) => self.as_arg(rhs),
+            _ => self.as_operand(rhs),
+        };
+        match self.assign_place(lhs) {
+            Some(place) => self.assign(place, Rvalue::Use(operand), lhs.span),
+            None => self.consume(operand, rhs.span),
+        }
+    }
+
+    // The place assigned to by the left side of an assignment, if it isn't
+    // `_`.
+    fn assign_place(&mut self, lhs: &Expr<'a>) -> Option<Place<'a>> {
+        match lhs.kind {
+            ExprKind::Path(ref path) if path.segments.len() == 1
+                && path.segments[0].name == "_" => None,
+            _ => Some(self.as_place(lhs)),
+        }
+    }
+
+    /// Lower a block into a place.
+    fn lower_block(
+        &mut self,
+        dest: Place<'a>,
+        block: &Block<'a>,
+        extend: Option<usize>,
+    ) {
+        let names = self.push_scope();
+        for stmt in &block.stmts {
+            let mark = self.temps.len();
+            match stmt.kind {
+                StmtKind::Let(ref local) => {
+                    let init = match local.init {
+                        Some(ref init) => init,
+                        None => {
+                            self.declare_pattern(&local.pattern);
+                            continue;
+                        }
+                    };
+                    let scope = self.scopes.len() - 1;
+                    let pattern = &local.pattern;
+                    let simple = matches!(pattern.kind,
+                        PatternKind::Ident(false, _, _, None));
+                    let binding = self.binding_ty(pattern);
+                    match (simple, binding, &local.diverge) {
+                        (true, Some(ty), None) => {
+                            let (mutable, name) = match pattern.kind {
+                                PatternKind::Ident(_, mutable, name, _) => {
+                                    (mutable, name)
+                                }
+                                _ => unreachable!(),
+                            };
+                            let var = self.new_local(Some(name), LocalKind::Var,
+                                mutable, ty.clone(), pattern.span);
+                            match (&local.ty, ty) {
+                                (Some(_), Ty::Ref(true, _)) => {
+                                    let operand = self.as_arg(init);
+                                    self.assign(Place::new(var),
+                                        Rvalue::Use(operand), init.span);
+                                }
+                                _ => {
+                                    self.extend = Some(scope);
+                                    self.expr_into(Place::new(var), init);
+                                }
+                            }
+                            self.scopes[scope].push(var);
+                            self.names.push((name, var));
+                        }
+                        _ => {
+                            self.extend = Some(scope);
+                            let place = self.as_place(init);
+                            if place.elems.is_empty() {
+                                self.extend_temp(place.local, scope);
+                            }
+                            if let Some(ref diverge) = local.diverge {
+                                self.push(StatementKind::Read(place.clone()),
+                                    init.span);
+                                let targets = self.switch(2);
+                                self.block = targets[1];
+                                let temp = self.temp(Ty::Never, diverge.span);
+                                self.lower_block(Place::new(temp), diverge,
+                                    None);
+                                self.diverge();
+                                self.block = targets[0];
+                            }
+                            self.bind_pattern(pattern, place, None, false);
+                        }
+                    }
+                }
+                StmtKind::Item(_) => {}
+                StmtKind::Expr(ref expr) | StmtKind::Semi(ref expr) => {
+                    let temp = self.temp(self.ty(expr), expr.span);
+                    self.expr_into(Place::new(temp), expr);
+                }
+            }
+            self.drop_temps(mark);
+        }
+        match block.expr {
+            Some(ref expr) => {
+                self.extend = extend;
+                self.expr_into(dest, expr);
+            }
+            None => {
+                self.assign(dest, Rvalue::Use(Operand::Const), block.span);
+            }
+        }
+        self.pop_scope(names);
+    }
+
+    // Lower the body of a loop jumping to a pair of blocks on `continue`
+    // and `break`, which end the scopes from an index on: those of the
+    // variables of an iteration.
+    fn lower_loop_body(
+        &mut self,
+        label: Option<&'a str>,
+        dest: Place<'a>,
+        (continue_, break_): (BlockId, BlockId),
+        scopes: usize,
+        body: &Block<'a>,
+    ) {
+        self.loops.push(Loop {
+            label,
+            block: false,
+            dest,
+            continue_,
+            break_,
+            scopes,
+        });
+        let temp = self.temp(Ty::unit(), body.span);
+        self.lower_block(Place::new(temp), body, None);
+        let scopes = self.loops.pop().unwrap().scopes;
+        self.exit_scopes(scopes);
+        self.goto(continue_);
+    }
+
+    // End the scopes entered since there were a number of them, as when
+    // jumping out of them.
+    fn exit_scopes(&mut self, scopes: usize) {
+        let locals: Vec<_> = self.scopes[scopes.min(self.scopes.len())..]
+            .iter()
+            .flatten()
+            .copied()
+            .collect();
+        for local in locals.into_iter().rev() {
+            self.push(StatementKind::StorageDead(local), Span::default());
+        }
+    }
+
+    /// Lower a condition, returning the blocks where it is true and
+    /// false.  The bindings of `let` conditions are in scope where it is
+    /// true.
+    fn lower_cond(&mut self, expr: &Expr<'a>) -> (BlockId, BlockId) {
+        match expr.kind {
+            ExprKind::Binary(BinaryOp::And, ref lhs, ref rhs) => {
+                let (yes, no) = self.lower_cond(lhs);
+                self.block = yes;
+                let (yes, other) = self.lower_cond(rhs);
+                let join = self.new_block();
+                for block in [no, other] {
+                    self.block = block;
+                    self.goto(join);
+                }
+                (yes, join)
+            }
+            ExprKind::Binary(BinaryOp::Or, ref lhs, ref rhs) => {
+                let (yes, no) = self.lower_cond(lhs);
+                self.block = no;
+                let (other, no) = self.lower_cond(rhs);
+                let join = self.new_block();
+                for block in [yes, other] {
+                    self.block = block;
+                    self.goto(join);
+                }
+                (join, no)
+            }
+            ExprKind::Unary(UnaryOp::Not, ref operand)
+                if self.ty(operand) == Ty::Bool =>
+            {
+                let (yes, no) = self.lower_cond(operand);
+                (no, yes)
+            }
+            ExprKind::Let(ref pattern, ref value) => {
+                let place = self.as_place(value);
+                if place.elems.is_empty() {
+                    let scope = self.scopes.len() - 1;
+                    self.extend_temp(place.local, scope);
+                }
+                self.push(StatementKind::Read(place.clone()), value.span);
+                let targets = self.switch(2);
+                self.block = targets[0];
+                self.bind_pattern(pattern, place, None, false);
+                (self.block, targets[1])
+            }
+            _ => {
+                let operand = self.as_operand(expr);
+                self.consume(operand, expr.span);
+                let targets = self.switch(2);
+                (targets[0], targets[1])
+            }
+        }
+    }
+
+    // Declare the variables of a `let` without a value.
+    fn declare_pattern(&mut self, pattern: &Pattern<'a>) {
+        match pattern.kind {
+            PatternKind::Ident(is_ref, is_mut, name, ref sub) => {
+                if let Some(ty) = self.binding_ty(pattern) {
+                    self.declare(name, is_mut && !is_ref, ty, pattern.span);
+                }
+                if let Some(ref sub) = sub {
+                    self.declare_pattern(sub);
+                }
+            }
+            PatternKind::Ref(_, ref inner) => self.declare_pattern(inner),
+            PatternKind::Tuple(ref pats)
+            | PatternKind::TupleStruct(_, ref pats)
+            | PatternKind::Slice(ref pats) => {
+                for pat in pats {
+                    self.declare_pattern(pat);
+                }
+            }
+            PatternKind::Struct(_, ref fields, _) => {
+                for field in fields {
+                    self.declare_pattern(&field.pattern);
+                }
+            }
+            PatternKind::Or(ref alternatives) => {
+                if let Some(first) = alternatives.first() {
+                    self.declare_pattern(first);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Declare a variable in the innermost scope, or find the one an earlier
+    // alternative of an or-pattern declared.
+    fn declare(&mut self, name: &'a str, mutable: bool, ty: Ty<'a>, span: Span)
+        -> Local
+    {
+        let reused = self.reuse.as_ref()
+            .and_then(|reuse| reuse.iter().find(|(found, _)| *found == name));
+        if let Some(&(_, local)) = reused {
+            return local;
+        }
+        let local = self.new_local(Some(name), LocalKind::Var, mutable, ty,
+            span);
+        self.scopes.last_mut().unwrap().push(local);
+        self.names.push((name, local));
+        local
+    }
+
+    /// Bind the variables of a pattern to the parts of a place it matches,
+    /// by value or by reference (`Some(true)` if mutable) by default.  The
+    /// bindings of a guard don't move anything.
+    fn bind_pattern(
+        &mut self,
+        pat: &Pattern<'a>,
+        mut place: Place<'a>,
+        mut mode: Option<bool>,
+        guard: bool,
+    ) {
+        let mut ty = self.pattern_ty(pat);
+        let binding = self.binding_ty(pat);
+        let peel = match pat.kind {
+            PatternKind::Wild
+            | PatternKind::Rest
+            | PatternKind::Ref(..)
+            | PatternKind::Or(_)
+            | PatternKind::Macro(_) => false,
+            PatternKind::Ident(..) => binding.is_none(),
+            PatternKind::Lit(ref expr) => !matches!(expr.kind,
+                ExprKind::Lit(Lit::Str(_)) | ExprKind::Lit(Lit::ByteStr(_))),
+            _ => true,
+        };
+        if peel {
+            while let Ty::Ref(mutable, inner) = ty {
+                place = place.project(Elem::Deref(Ptr::Ref(mutable)));
+                mode = Some(mutable && mode != Some(false));
+                ty = *inner;
+            }
+        }
+        let read = |builder: &mut Self, place: &Place<'a>| {
+            builder.push(StatementKind::Read(place.clone()), pat.span);
+        };
+        match pat.kind {
+            PatternKind::Wild | PatternKind::Rest | PatternKind::Macro(_) => {}
+            PatternKind::Ident(is_ref, is_mut, name, ref sub) => {
+                let ty = match binding {
+                    Some(ty) => ty,
+                    // A constant
+                    None => return read(self, &place),
+                };
+                if let Some(ref sub) = sub {
+                    self.bind_pattern(sub, place.clone(), mode, guard);
+                }
+                let by_ref = match (is_ref, mode) {
+                    (true, _) => Some(is_mut),
+                    (false, Some(mutable)) if !is_mut => Some(mutable),
+                    _ => None,
+                };
+                let holds = self.cx.holds_borrows(&ty);
+                let local = self.declare(name, is_mut && !is_ref, ty, pat.span);
+                let operand = match by_ref {
+                    Some(mutable) => {
+                        let rvalue = Rvalue::Ref(mutable, place);
+                        return self.assign(Place::new(local), rvalue, pat.span);
+                    }
+                    None if self.is_moved(pat.span) && !guard => {
+                        Operand::Move(place)
+                    }
+                    None => Operand::Copy(place),
+                };
+                // A value that can't hold references, like `c` of `Some(&c)`,
+                // holds none of the loans of what it is copied out of.
+                let rvalue = match holds {
+                    true => Rvalue::Use(operand),
+                    false => Rvalue::Compute(vec![operand], Vec::new()),
+                };
+                self.assign(Place::new(local), rvalue, pat.span);
+            }
+            PatternKind::Lit(_) | PatternKind::Range(..) | PatternKind::Path(_) => {
+                read(self, &place);
+            }
+            PatternKind::Ref(mutable, ref inner) => {
+                let place = place.project(Elem::Deref(Ptr::Ref(mutable)));
+                self.bind_pattern(inner, place, None, guard);
+            }
+            PatternKind::Tuple(ref pats) => {
+                let len = match ty {
+                    Ty::Tuple(ref tys) => Some(tys.len()),
+                    _ => None,
+                };
+                self.bind_list(pats, &place, len, mode, guard);
+            }
+            PatternKind::TupleStruct(ref path, ref pats) => {
+                if self.cx.is_enum(&ty) {
+                    read(self, &place);
+                }
+                let name = path.segments.last().map_or("", |segment| {
+                    segment.name
+                });
+                let len = self.cx.variant_len(&ty, name);
+                self.bind_list(pats, &place, len, mode, guard);
+            }
+            PatternKind::Struct(_, ref fields, _) => {
+                if self.cx.is_enum(&ty) {
+                    read(self, &place);
+                }
+                for field in fields {
+                    let elem = match field.name.parse::<usize>() {
+                        Ok(index) => Elem::Tuple(index),
+                        Err(_) => Elem::Field(field.name),
+                    };
+                    self.bind_pattern(&field.pattern, place.project(elem), mode,
+                        guard);
+                }
+            }
+            PatternKind::Slice(ref pats) => {
+                if let Ty::Slice(_) = ty {
+                    read(self, &place);
+                }
+                let len = match ty {
+                    Ty::Array(_, len) => len.map(|len| len as usize),
+                    _ => None,
+                };
+                let is_rest = |pat: &Pattern| match pat.kind {
+                    PatternKind::Rest => true,
+                    PatternKind::Ident(_, _, _, Some(ref sub)) => {
+                        sub.kind == PatternKind::Rest
+                    }
+                    _ => false,
+                };
+                let rest = pats.iter().position(is_rest).unwrap_or(pats.len());
+                for (i, pat) in pats.iter().enumerate() {
+                    let elem = match i {
+                        i if i == rest => Elem::Index,
+                        i if i < rest => Elem::Tuple(i),
+                        _ => Elem::Tuple(Self::index_from_end(i, pats.len(),
+                            len)),
+                    };
+                    self.bind_pattern(pat, place.project(elem), mode, guard);
+                }
+            }
+            PatternKind::Or(ref alternatives) => {
+                let start = self.names.len();
+                let saved = self.reuse.take();
+                let targets = self.switch(alternatives.len());
+                let join = self.new_block();
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    self.block = targets[i];
+                    if i > 0 {
+                        self.reuse = Some(self.names[start..].to_vec());
+                    }
+                    self.bind_pattern(alternative, place.clone(), mode, guard);
+                    self.goto(join);
+                }
+                self.reuse = saved;
+                self.block = join;
+            }
+        }
+    }
+
+    // Bind the elements of a tuple or tuple struct pattern, with `..`
+    // standing for the elements not mentioned.
+    fn bind_list(
+        &mut self,
+        pats: &[Pattern<'a>],
+        place: &Place<'a>,
+        len: Option<usize>,
+        mode: Option<bool>,
+        guard: bool,
+    ) {
+        let rest = pats.iter()
+            .position(|pat| pat.kind == PatternKind::Rest)
+            .unwrap_or(pats.len());
+        for (i, pat) in pats.iter().enumerate() {
+            let index = match i > rest {
+                true => Self::index_from_end(i, pats.len(), len),
+                false => i,
+            };
+            self.bind_pattern(pat, place.project(Elem::Tuple(index)), mode,
+                guard);
+        }
+    }
+
+    // The index of an element after `..` in a pattern, counting from the end
+    // of the elements if their number isn't known.
+    fn index_from_end(i: usize, count: usize, len: Option<usize>) -> usize {
+        match len {
+            Some(len) => (len + i).saturating_sub(count),
+            None => usize::MAX - (count - 1 - i),
+        }
+    }
+
+    /// Lower a closure or `async` block into a place: its body is lowered
+    /// on its own, and the places it uses are captured.
+    fn lower_closure<F>(
+        &mut self,
+        dest: Place<'a>,
+        params: &[&Pattern<'a>],
+        is_move: bool,
+        span: Span,
+        lower_body: F,
+    ) where
+        F: FnOnce(&mut Builder<'c, 'k, 'a>),
+    {
+        let mut inner = Builder::new(self.cx, self.module);
+        inner.outer = self.outer.clone();
+        for &(name, local) in &self.names {
+            inner.outer.push((name, self.locals[local].clone()));
+        }
+        for pattern in params {
+            inner.lower_param(pattern);
+        }
+        lower_body(&mut inner);
+        let upvars = inner.upvars.clone();
+        let mut bodies = inner.finish();
+        let captures = match is_move {
+            true => upvars.iter()
+                .map(|&(name, _)| (name, Vec::new(), Capture::ByValue))
+                .collect(),
+            false => captures(bodies.last().unwrap()),
+        };
+        self.bodies.append(&mut bodies);
+
+        let mut operands = Vec::new();
+        for (name, elems, capture) in captures {
+            let local = match self.lookup(name) {
+                Some(local) => local,
+                None => continue,
+            };
+            let place = Place { local, elems };
+            let decl = &self.locals[local];
+            let copy = !self.is_moved(decl.span) && decl.kind != LocalKind::Temp;
+            let mutable = match capture {
+                Capture::ByValue if is_move && copy => {
+                    operands.push(Operand::Copy(place));
+                    continue;
+                }
+                Capture::ByValue => {
+                    operands.push(Operand::Move(place));
+                    continue;
+                }
+                // Assigning to a variable that isn't mutable is reported
+                // in the closure.
+                Capture::Mut => decl.mutable || !place.elems.is_empty(),
+                Capture::Shared => false,
+            };
+            let ty = Ty::Ref(mutable, Box::new(self.place_ty(&place)));
+            let temp = self.temp(ty, span);
+            self.assign(Place::new(temp), Rvalue::Ref(mutable, place), span);
+            operands.push(Operand::Move(Place::new(temp)));
+        }
+        let flows = self.roots(&operands);
+        self.assign(dest, Rvalue::Compute(operands, flows), span);
+    }
+
+    fn lower_macro(&mut self, dest: Place<'a>, call: &MacroCall<'a>, ty: &Ty<'a>) {
+        let span = call.span;
+        let krate = self.cx.krate;
+        let path = match krate.resolve_path(self.module, &call.path,
+            Namespace::Macro)
+        {
+            Ok((Res::External(path), _)) => path,
+            _ => return self.assign(dest, Rvalue::Use(Operand::Const), span),
+        };
+        let args = match call.args(krate.text(self.module)) {
+            Ok(args) => args,
+            Err(_) => return self.assign(dest, Rvalue::Use(Operand::Const), span),
+        };
+        match path.strip_prefix("std::").unwrap_or("") {
+            "print" | "println" | "eprint" | "eprintln" | "format"
+            | "format_args" | "panic" | "unreachable" | "todo"
+            | "unimplemented" => {
+                self.lower_format(&args, span);
+                self.assign(dest, Rvalue::Compute(Vec::new(), Vec::new()), span);
+            }
+            "assert" | "debug_assert" => {
+                if let Some((cond, rest)) = args.split_first() {
+                    let operand = self.as_operand(cond);
+                    self.consume(operand, cond.span);
+                    self.lower_format(rest, span);
+                }
+                self.assign(dest, Rvalue::Use(Operand::Const), span);
+            }
+            "assert_eq" | "assert_ne" | "debug_assert_eq" | "debug_assert_ne" => {
+                for arg in args.iter().take(2) {
+                    self.read(arg);
+                }
+                self.lower_format(args.get(2..).unwrap_or(&[]), span);
+                self.assign(dest, Rvalue::Use(Operand::Const), span);
+            }
+            "vec" => {
+                let operands = match args.first().map(|arg| &arg.kind) {
+                    Some(ExprKind::Repeat(value, _)) if args.len() == 1 => {
+                        vec![self.as_operand(value)]
+                    }
+                    _ => args.iter().map(|arg| self.as_operand(arg)).collect(),
+                };
+                self.compute(dest, operands, ty, span);
+            }
+            "dbg" => match args.len() {
+                1 => {
+                    let operand = self.as_operand(&args[0]);
+                    self.assign(dest, Rvalue::Use(operand), span);
+                }
+                _ => {
+                    let operands = args.iter()
+                        .map(|arg| self.as_operand(arg))
+                        .collect();
+                    self.compute(dest, operands, ty, span);
+                }
+            },
+            "write" | "writeln" => {
+                let (writer, rest) = match args.split_first() {
+                    Some(split) => split,
+                    None => return,
+                };
+                let method = self.cx.types.methods.get(&(self.file, span))
+                    .cloned();
+                let place = self.as_place(writer);
+                self.lower_format(rest, span);
+                let operand = match method {
+                    Some(method) => {
+                        let (place, target) = self.derefs(place,
+                            self.ty(writer), method.derefs);
+                        match method.autoref {
+                            Some(mutable) => {
+                                let ty = Ty::Ref(mutable, Box::new(target));
+                                let temp = self.temp(ty, span);
+                                self.assign(Place::new(temp),
+                                    Rvalue::Ref(mutable, place), span);
+                                Operand::Move(Place::new(temp))
+                            }
+                            None => Operand::Copy(place),
+                        }
+                    }
+                    None => Operand::Copy(place),
+                };
+                self.assign(dest, Rvalue::Compute(vec![operand], Vec::new()),
+                    span);
+            }
+            _ => self.assign(dest, Rvalue::Use(Operand::Const), span),
+        }
+    }
+
+    // Read the arguments of a format string, and the variables it names.
+    fn lower_format(&mut self, args: &[Expr<'a>], span: Span) {
+        let (format, args) = match args.split_first() {
+            Some(split) => split,
+            None => return,
+        };
+        let mut named = Vec::new();
+        for arg in args {
+            match arg.kind {
+                ExprKind::Assign(ref name, ref value) => {
+                    if let ExprKind::Path(ref path) = name.kind {
+                        named.push(path.segments[0].name);
+                    }
+                    self.borrow(value);
+                }
+                _ => self.borrow(arg),
+            }
+        }
+        let string = match format.kind {
+            ExprKind::Lit(Lit::Str(ref string)) => string,
+            _ => return self.read(format),
+        };
+        for (arg, _) in format_placeholders(string).unwrap_or_default() {
+            if let FormatArg::Name(name) = arg {
+                if named.contains(&name) {
+                    continue;
+                }
+                if let Some(local) = self.lookup_str(name) {
+                    let ty = Ty::Ref(false, Box::new(self.place_ty(
+                        &Place::new(local))));
+                    let temp = self.temp(ty, span);
+                    self.assign(Place::new(temp),
+                        Rvalue::Ref(false, Place::new(local)), span);
+                }
+            }
+        }
+    }
+
+    // Find a variable named in a format string, whose name doesn't live as
+    // long as the source.
+    fn lookup_str(&mut self, name: &str) -> Option<Local> {
+        let found = self.names.iter().rev().map(|(found, _)| *found)
+            .chain(self.upvars.iter().map(|(found, _)| *found))
+            .chain(self.outer.iter().rev().map(|(found, _)| *found))
+            .find(|found| *found == name)?;
+        self.lookup(found)
+    }
+}
+
+// The places of the enclosing bodies a closure body uses, and how.
+fn captures<'a>(body: &Body<'a>) -> Vec<(&'a str, Vec<Elem<'a>>, Capture)> {
+    let mut uses = Vec::new();
+    let upvar = |local: Local| match body.locals[local] {
+        LocalDecl { kind: LocalKind::Upvar, name: Some(name), .. } => Some(name),
+        _ => None,
+    };
+    for block in &body.blocks {
+        for stmt in &block.stmts {
+            for (place, access) in stmt.accesses() {
+                let name = match upvar(place.local) {
+                    Some(name) => name,
+                    None => continue,
+                };
+                let mut capture = match access {
+                    Access::Move => Capture::ByValue,
+                    Access::Borrow(true) | Access::Write | Access::Mutate => {
+                        Capture::Mut
+                    }
+                    Access::Read | Access::Borrow(false) => Capture::Shared,
+                };
+                let mut elems = Vec::new();
+                for elem in &place.elems {
+                    match *elem {
+                        Elem::Index
+                        | Elem::Deref(Ptr::Raw)
+                        | Elem::Deref(Ptr::Overloaded) => break,
+                        Elem::Deref(Ptr::Ref(_)) if capture == Capture::ByValue => {
+                            capture = Capture::Shared;
+                        }
+                        _ => {}
+                    }
+                    elems.push(*elem);
+                }
+                uses.push((name, elems, capture));
+            }
+            match stmt.kind {
+                StatementKind::Store(ref from, ref to) => {
+                    if let Some(name) = upvar(from.local) {
+                        uses.push((name, Vec::new(), Capture::Shared));
+                    }
+                    if let Some(name) = upvar(to.local) {
+                        uses.push((name, Vec::new(), Capture::Mut));
+                    }
+                }
+                StatementKind::Assign(_, Rvalue::Compute(_, ref flows)) => {
+                    for place in flows {
+                        if let Some(name) = upvar(place.local) {
+                            uses.push((name, Vec::new(), Capture::Shared));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Merge the uses of a place and the places inside it.
+    uses.sort_by_key(|(_, elems, _)| elems.len());
+    let mut captures: Vec<(&'a str, Vec<Elem<'a>>, Capture)> = Vec::new();
+    for (name, elems, capture) in uses {
+        let prefix = captures.iter_mut().find(|(found, prefix, _)| {
+            *found == name && elems.starts_with(prefix)
+        });
+        match prefix {
+            Some(found) => {
+                if capture > found.2 {
+                    found.2 = capture;
+                }
+            }
+            None => captures.push((name, elems, capture)),
+        }
+    }
+    captures
+}
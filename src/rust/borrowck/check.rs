@@ -0,0 +1,395 @@
+// Rust borrow checking: errors
+//
+//! The checks of each statement of a body against the loans live after
+//! it, the places that may be uninitialized before it, and the mutability
+//! of the places it changes.
+
+use super::dataflow::{
+    forward, liveness, move_path, BitSet, Forward, Holders, Inits, MovePaths,
+};
+use super::{Access, Body, Checker, Elem, LocalKind, Place, Ptr, Statement};
+use super::{StatementKind, Ty};
+
+/// The state of checking a statement
+struct Point<'s, 'b, 'a> {
+    body: &'b Body<'a>,
+    holders: &'s Holders<'a>,
+    paths: &'s MovePaths<'b, 'a>,
+    /// Loans held by the locals live after the statement
+    loans: Vec<usize>,
+    held: &'s BitSet,
+    live: &'s BitSet,
+    inits: &'s Inits,
+}
+
+impl<'k, 'a> Checker<'k, 'a> {
+    pub(super) fn check_body(&self, body: &Body<'a>) {
+        let holders = Holders::new(body);
+        let paths = MovePaths::new(body);
+        let live = liveness(self, body);
+        let held = forward(&holders, body, holders.empty());
+        let inits = forward(&paths, body, paths.entry());
+        for (index, block) in body.blocks.iter().enumerate() {
+            let (mut held, mut inits) = match (&held[index], &inits[index]) {
+                (Some(held), Some(inits)) => (held.clone(), inits.clone()),
+                _ => continue,
+            };
+            for (i, stmt) in block.stmts.iter().enumerate() {
+                let live = &live[index][i];
+                // A local assigned a new value no longer holds the loans of
+                // the old one.
+                let def = match stmt.kind {
+                    StatementKind::Assign(ref dest, _)
+                        if dest.elems.is_empty() => Some(dest.local),
+                    _ => None,
+                };
+                let loans = (0..holders.loans.len())
+                    .filter(|&loan| live.iter().any(|local| {
+                        Some(local) != def
+                            && holders.holds(&held, local, loan)
+                    }))
+                    .collect();
+                let point = Point {
+                    body,
+                    holders: &holders,
+                    paths: &paths,
+                    loans,
+                    held: &held,
+                    live,
+                    inits: &inits,
+                };
+                self.check_stmt(&point, stmt);
+                holders.apply(&mut held, stmt);
+                paths.apply(&mut inits, stmt);
+            }
+        }
+    }
+
+    fn check_stmt(&self, point: &Point<'_, '_, 'a>, stmt: &Statement<'a>) {
+        for (place, access) in stmt.accesses() {
+            let reported = self.check_mutability(point, place, access, stmt)
+                || self.check_move_out(point, place, access, stmt)
+                || self.check_init(point, place, access, stmt);
+            if !reported {
+                self.check_conflicts(point, place, access, stmt);
+            }
+        }
+        if let StatementKind::StorageDead(local) = stmt.kind {
+            self.check_storage_dead(point, local);
+        }
+    }
+
+    // Check that a place changed is mutable.
+    fn check_mutability(
+        &self,
+        point: &Point<'_, '_, 'a>,
+        place: &Place<'a>,
+        access: Access,
+        stmt: &Statement<'a>,
+    ) -> bool {
+        let decl = &point.body.locals[place.local];
+        let behind_shared = place.elems.contains(&Elem::Deref(Ptr::Ref(false)));
+        if (decl.mutable || decl.kind == LocalKind::Temp
+            || decl.kind == LocalKind::Return) && !behind_shared
+        {
+            return false;
+        }
+        let name = decl.name.unwrap_or("_");
+        let shown = self.describe(point.body, place);
+        let message = match access {
+            Access::Write | Access::Mutate if place.elems.is_empty() => {
+                if access == Access::Write {
+                    let path = point.paths.find(place);
+                    let init = path.is_some_and(|path| {
+                        point.inits.init.contains(path)
+                    });
+                    if !init {
+                        return false;
+                    }
+                }
+                match decl.kind {
+                    LocalKind::Arg => {
+                        format!("cannot assign to immutable argument `{}`", name)
+                    }
+                    LocalKind::Upvar => format!(
+                        "cannot assign to `{}`, as it is not declared as \
+                            mutable", name),
+                    _ => format!("cannot assign twice to immutable variable \
+                        `{}`", name),
+                }
+            }
+            Access::Borrow(true) | Access::Write | Access::Mutate => {
+                let through_mut = place.elems.iter().any(|elem| matches!(elem,
+                    Elem::Deref(Ptr::Ref(true)) | Elem::Deref(Ptr::Raw)));
+                let borrow = access == Access::Borrow(true);
+                match (behind_shared, borrow) {
+                    (true, true) => format!("cannot borrow {} as mutable, as \
+                        it is behind a `&` reference", shown),
+                    (true, false) => format!("cannot assign to {}, which is \
+                        behind a `&` reference", shown),
+                    (false, _) if through_mut => return false,
+                    (false, true) if place.elems.is_empty() => format!(
+                        "cannot borrow {} as mutable, as it is not declared \
+                            as mutable", shown),
+                    (false, true) => format!("cannot borrow {} as mutable, as \
+                        `{}` is not declared as mutable", shown, name),
+                    (false, false) => format!("cannot assign to {}, as `{}` \
+                        is not declared as mutable", shown, name),
+                }
+            }
+            _ => return false,
+        };
+        self.error(point.body.file, stmt.span, message);
+        true
+    }
+
+    // Check that a place moved out of isn't behind a pointer or an index.
+    fn check_move_out(
+        &self,
+        point: &Point<'_, '_, 'a>,
+        place: &Place<'a>,
+        access: Access,
+        stmt: &Statement<'a>,
+    ) -> bool {
+        if access != Access::Move {
+            return false;
+        }
+        let path = move_path(place);
+        let elem = match place.elems.get(path.elems.len()) {
+            Some(elem) => *elem,
+            None => return false,
+        };
+        let shown = self.describe(point.body, place);
+        let index = place.elems[path.elems.len()..].iter()
+            .position(|elem| *elem == Elem::Index)
+            .map(|i| path.elems.len() + i);
+        if let Some(index) = index {
+            // Name the type indexed, before any overloaded dereference.
+            let mut base = place.clone();
+            base.elems.truncate(index);
+            if base.elems.last() == Some(&Elem::Deref(Ptr::Overloaded)) {
+                base.elems.pop();
+            }
+            let ty = self.place_ty(&point.body.locals, &base);
+            let message = match ty {
+                Ty::Array(..) => format!("cannot move out of type `{}`, a \
+                    non-copy array", ty.name(self.krate)),
+                Ty::Slice(_) => format!("cannot move out of type `{}`, a \
+                    non-copy slice", ty.name(self.krate)),
+                _ => format!("cannot move out of index of `{}`",
+                    ty.name(self.krate)),
+            };
+            self.error(point.body.file, stmt.span, message);
+            return true;
+        }
+        let message = match elem {
+            Elem::Deref(Ptr::Ref(false)) => format!("cannot move out of {} \
+                which is behind a shared reference", shown),
+            Elem::Deref(Ptr::Ref(true)) => format!("cannot move out of {} \
+                which is behind a mutable reference", shown),
+            Elem::Deref(Ptr::Raw) => format!("cannot move out of {} which is \
+                behind a raw pointer", shown),
+            _ => format!("cannot move out of dereference of {}",
+                self.describe(point.body, &path)),
+        };
+        self.error(point.body.file, stmt.span, message);
+        true
+    }
+
+    // Check that a place used is initialized.
+    fn check_init(
+        &self,
+        point: &Point<'_, '_, 'a>,
+        place: &Place<'a>,
+        access: Access,
+        stmt: &Statement<'a>,
+    ) -> bool {
+        let path = move_path(place);
+        let index = match point.paths.find(&path) {
+            Some(index) => index,
+            None => return false,
+        };
+        let inits = point.inits;
+        let name = point.body.locals[place.local].name.unwrap_or("_");
+        if access == Access::Write && path.elems.len() == place.elems.len() {
+            // Assigning to part of a place needs the rest of it.
+            let prefixes = point.paths.prefixes(index);
+            let uninit = prefixes.iter()
+                .find(|&&i| i != index && inits.uninit.contains(i));
+            let message = match uninit {
+                Some(&i) if inits.moved.contains(i) => format!(
+                    "assign to part of moved value: {}",
+                    self.describe(point.body, &point.paths.paths[i])),
+                Some(_) => format!("partially assigned binding `{}` isn't \
+                    fully initialized", name),
+                None => return false,
+            };
+            self.error(point.body.file, stmt.span, message);
+            return true;
+        }
+        let whole = point.paths.prefixes(index).iter()
+            .find(|&&i| inits.uninit.contains(i))
+            .map(|&i| (i, false));
+        let part = point.paths.children(index).iter()
+            .find(|&&i| inits.uninit.contains(i))
+            .map(|&i| (i, true));
+        let (uninit, partial) = match whole.or(part) {
+            Some(found) => found,
+            None => return false,
+        };
+        let verb = match access {
+            Access::Borrow(_) => "borrow",
+            _ => "use",
+        };
+        let message = if inits.moved.contains(uninit) {
+            match partial {
+                true => format!("{} of partially moved value: {}", verb,
+                    self.describe(point.body, &path)),
+                false => format!("{} of moved value: {}", verb,
+                    self.describe(point.body, &point.paths.paths[uninit])),
+            }
+        } else if partial {
+            format!("used binding `{}` isn't fully initialized", name)
+        } else if inits.init.contains(uninit) {
+            format!("used binding `{}` is possibly-uninitialized", name)
+        } else {
+            format!("used binding `{}` isn't initialized", name)
+        };
+        self.error(point.body.file, stmt.span, message);
+        true
+    }
+
+    // Check that an access doesn't invalidate a live loan.
+    fn check_conflicts(
+        &self,
+        point: &Point<'_, '_, 'a>,
+        place: &Place<'a>,
+        access: Access,
+        stmt: &Statement<'a>,
+    ) {
+        let loans = &point.holders.loans;
+        let conflict = point.loans.iter().map(|&loan| &loans[loan])
+            .find(|loan| {
+                if !overlaps(place, &loan.place) {
+                    return false;
+                }
+                match access {
+                    Access::Read | Access::Borrow(false) => loan.mutable,
+                    Access::Borrow(true) | Access::Move | Access::Mutate => true,
+                    // Overwriting a reference doesn't change what it points
+                    // to.
+                    Access::Write => !loan.place.elems.iter()
+                        .skip(place.elems.len())
+                        .any(|elem| matches!(elem,
+                            Elem::Deref(Ptr::Ref(_)) | Elem::Deref(Ptr::Raw))),
+                }
+            });
+        let loan = match conflict {
+            Some(loan) => loan,
+            None => return,
+        };
+        let shown = self.describe(point.body, place);
+        let message = match access {
+            Access::Borrow(true) if loan.mutable => format!(
+                "cannot borrow {} as mutable more than once at a time", shown),
+            Access::Borrow(true) => format!("cannot borrow {} as mutable \
+                because it is also borrowed as immutable", shown),
+            Access::Borrow(false) => format!("cannot borrow {} as immutable \
+                because it is also borrowed as mutable", shown),
+            Access::Read => {
+                format!("cannot use {} because it was mutably borrowed", shown)
+            }
+            Access::Write | Access::Mutate => {
+                format!("cannot assign to {} because it is borrowed", shown)
+            }
+            Access::Move => {
+                format!("cannot move out of {} because it is borrowed", shown)
+            }
+        };
+        self.error(point.body.file, stmt.span, message);
+    }
+
+    // Check that a local going out of scope isn't borrowed.
+    fn check_storage_dead(&self, point: &Point<'_, '_, 'a>, local: usize) {
+        let loans = &point.holders.loans;
+        let found = point.loans.iter().copied().find(|&loan| {
+            let place = &loans[loan].place;
+            place.local == local && !place.elems.iter().any(|elem| {
+                matches!(elem, Elem::Deref(Ptr::Ref(_)) | Elem::Deref(Ptr::Raw))
+            })
+        });
+        let index = match found {
+            Some(index) => index,
+            None => return,
+        };
+        let loan = &loans[index];
+        let decl = &point.body.locals[local];
+        let returned = point.live.contains(0)
+            && point.holders.holds(point.held, 0, index);
+        let message = match returned {
+            true => {
+                let what = match (decl.kind, decl.name) {
+                    (LocalKind::Arg, Some(name)) => {
+                        format!("function parameter `{}`", name)
+                    }
+                    (LocalKind::Arg, None) => "function parameter".to_string(),
+                    (_, Some(name)) => format!("local variable `{}`", name),
+                    (_, None) => "temporary value".to_string(),
+                };
+                match loan.dest {
+                    0 => format!("cannot return reference to {}", what),
+                    _ => format!("cannot return value referencing {}", what),
+                }
+            }
+            false => match decl.name {
+                Some(name) => format!("`{}` does not live long enough", name),
+                None if decl.kind == LocalKind::Temp => {
+                    "temporary value dropped while borrowed".to_string()
+                }
+                None => "borrowed value does not live long enough".to_string(),
+            },
+        };
+        self.error(point.body.file, loan.span, message);
+    }
+
+    /// Describe a place in a message: its expression in backticks, or a
+    /// temporary value.
+    fn describe(&self, body: &Body<'a>, place: &Place<'a>) -> String {
+        let mut shown = match body.locals[place.local].name {
+            Some(name) => name.to_string(),
+            None => return "temporary value".to_string(),
+        };
+        let mut derefs = 0;
+        for elem in &place.elems {
+            match *elem {
+                Elem::Deref(_) => derefs += 1,
+                Elem::Field(name) => {
+                    derefs = 0;
+                    shown = format!("{}.{}", shown, name);
+                }
+                Elem::Tuple(index) if index < usize::MAX / 2 => {
+                    derefs = 0;
+                    shown = format!("{}.{}", shown, index);
+                }
+                Elem::Tuple(_) => {
+                    derefs = 0;
+                    shown = format!("{}._", shown);
+                }
+                Elem::Index => {
+                    derefs = 0;
+                    shown = format!("{}[_]", shown);
+                }
+            }
+        }
+        format!("`{}{}`", "*".repeat(derefs), shown)
+    }
+}
+
+// Whether two places may refer to the same memory: when one is in the other.
+fn overlaps(a: &Place, b: &Place) -> bool {
+    a.local == b.local && a.elems.iter().zip(&b.elems).all(|pair| match pair {
+        (Elem::Field(a), Elem::Field(b)) => a == b,
+        (Elem::Tuple(a), Elem::Tuple(b)) => a == b,
+        _ => true,
+    })
+}
@@ -0,0 +1,807 @@
+// Rust borrow checking: dataflow
+//
+//! The analyses borrow checking runs over control-flow graphs: the live
+//! locals, the loans each local and field holds, and the places that are
+//! moved out or uninitialized.
+
+use super::{
+    Body, Checker, Elem, Local, LocalKind, Operand, Place, Ptr, Rvalue,
+    Span, Statement, StatementKind, Terminator,
+};
+
+/// A set of small integers
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub(super) fn new(len: usize) -> Self {
+        BitSet { words: vec![0; len.div_ceil(64)] }
+    }
+
+    pub(super) fn contains(&self, i: usize) -> bool {
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub(super) fn insert(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    pub(super) fn remove(&mut self, i: usize) {
+        self.words[i / 64] &= !(1 << (i % 64));
+    }
+
+    /// Add the elements of another set, returning whether any were new.
+    pub(super) fn union(&mut self, other: &BitSet) -> bool {
+        let mut changed = false;
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            let new = *word | other;
+            changed |= new != *word;
+            *word = new;
+        }
+        changed
+    }
+
+    pub(super) fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &word)| {
+            (0..64).filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| i * 64 + bit)
+        })
+    }
+}
+
+/// A forward dataflow analysis
+pub(super) trait Forward<'a> {
+    type State: Clone;
+
+    /// Merge the state of another predecessor of a block into its state,
+    /// returning whether it changed.
+    fn join(&self, state: &mut Self::State, other: &Self::State) -> bool;
+
+    /// Apply the effect of a statement.
+    fn apply(&self, state: &mut Self::State, stmt: &Statement<'a>);
+}
+
+/// Compute the state at the start of each block, `None` for blocks that
+/// can't be reached.
+pub(super) fn forward<'a, A: Forward<'a>>(
+    analysis: &A,
+    body: &Body<'a>,
+    entry: A::State,
+) -> Vec<Option<A::State>> {
+    let mut states: Vec<Option<A::State>> = vec![None; body.blocks.len()];
+    states[0] = Some(entry);
+    let mut work = vec![0];
+    while let Some(block) = work.pop() {
+        let mut state = match states[block] {
+            Some(ref state) => state.clone(),
+            None => continue,
+        };
+        for stmt in &body.blocks[block].stmts {
+            analysis.apply(&mut state, stmt);
+        }
+        for &next in body.blocks[block].term.successors() {
+            let changed = match states[next] {
+                Some(ref mut other) => analysis.join(other, &state),
+                None => {
+                    states[next] = Some(state.clone());
+                    true
+                }
+            };
+            if changed && !work.contains(&next) {
+                work.push(next);
+            }
+        }
+    }
+    states
+}
+
+/// The locals used by a statement, and the local it overwrites.
+fn uses_defs<'a>(body: &Body<'a>, stmt: &Statement<'a>)
+    -> (Vec<Local>, Option<Local>)
+{
+    let mut uses = Vec::new();
+    let mut def = None;
+    match stmt.kind {
+        StatementKind::Assign(ref dest, ref rvalue) => {
+            match *rvalue {
+                Rvalue::Use(ref operand) => uses.extend(root(operand)),
+                Rvalue::Ref(_, ref place) => uses.push(place.local),
+                Rvalue::Compute(ref operands, ref flows) => {
+                    uses.extend(operands.iter().filter_map(root));
+                    uses.extend(flows.iter().map(|place| place.local));
+                }
+                Rvalue::Aggregate(ref operands, ref fields) => {
+                    uses.extend(operands.iter().filter_map(root));
+                    uses.extend(fields.iter().map(|(_, place)| place.local));
+                }
+            }
+            match dest.elems.is_empty() {
+                true => def = Some(dest.local),
+                false => uses.push(dest.local),
+            }
+        }
+        StatementKind::Read(ref place) | StatementKind::Mutate(ref place) => {
+            uses.push(place.local);
+        }
+        StatementKind::Store(ref from, _) => uses.push(from.local),
+        StatementKind::StorageDead(local) => {
+            if !body.locals[local].is_free() {
+                def = Some(local);
+            }
+        }
+    }
+    (uses, def)
+}
+
+fn root(operand: &Operand) -> Option<Local> {
+    operand.place().map(|place| place.local)
+}
+
+/// Compute the locals live after each statement, by block.  A local is
+/// live where the value it has may be used later.
+pub(super) fn liveness<'a>(cx: &Checker<'_, 'a>, body: &Body<'a>)
+    -> Vec<Vec<BitSet>>
+{
+    let count = body.locals.len();
+    // Locals used after the body returns
+    let mut returned = BitSet::new(count);
+    returned.insert(0);
+    for (local, decl) in body.locals.iter().enumerate() {
+        let used = match (decl.kind, &decl.ty) {
+            (LocalKind::Upvar, ty) => cx.holds_borrows(ty),
+            (LocalKind::Arg, super::Ty::Ref(true, inner)) => {
+                cx.holds_borrows(inner)
+            }
+            _ => false,
+        };
+        if used {
+            returned.insert(local);
+        }
+    }
+
+    let mut live_in = vec![BitSet::new(count); body.blocks.len()];
+    let block_out = |live_in: &[BitSet], block: usize| {
+        let term = &body.blocks[block].term;
+        let mut live = match *term {
+            Terminator::Return => returned.clone(),
+            _ => BitSet::new(count),
+        };
+        for &next in term.successors() {
+            live.union(&live_in[next]);
+        }
+        live
+    };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in (0..body.blocks.len()).rev() {
+            let mut live = block_out(&live_in, block);
+            for stmt in body.blocks[block].stmts.iter().rev() {
+                let (uses, def) = uses_defs(body, stmt);
+                if let Some(def) = def {
+                    live.remove(def);
+                }
+                for local in uses {
+                    live.insert(local);
+                }
+            }
+            if live != live_in[block] {
+                live_in[block] = live;
+                changed = true;
+            }
+        }
+    }
+
+    (0..body.blocks.len())
+        .map(|block| {
+            let stmts = &body.blocks[block].stmts;
+            let mut live = block_out(&live_in, block);
+            let mut after = vec![BitSet::new(count); stmts.len()];
+            for (i, stmt) in stmts.iter().enumerate().rev() {
+                after[i] = live.clone();
+                let (uses, def) = uses_defs(body, stmt);
+                if let Some(def) = def {
+                    live.remove(def);
+                }
+                for local in uses {
+                    live.insert(local);
+                }
+            }
+            after
+        })
+        .collect()
+}
+
+/// A borrow of a place
+#[derive(Debug)]
+pub(super) struct Loan<'a> {
+    pub(super) place: Place<'a>,
+    pub(super) mutable: bool,
+    /// Local the reference is assigned to
+    pub(super) dest: Local,
+    pub(super) span: Span,
+}
+
+/// The part of a place loans are held by: up to its first index or
+/// dereference, so that the fields of a local hold loans of their own.
+fn holder_path<'a>(place: &Place<'a>) -> Place<'a> {
+    let len = place.elems.iter()
+        .position(|elem| !matches!(*elem, Elem::Field(_) | Elem::Tuple(_)))
+        .unwrap_or(place.elems.len());
+    Place { local: place.local, elems: place.elems[..len].to_vec() }
+}
+
+// Whether a projection goes through a reference or a raw pointer, whose
+// target isn't part of the place projected.
+fn is_indirect(elem: &Elem) -> bool {
+    matches!(*elem, Elem::Deref(Ptr::Ref(_)) | Elem::Deref(Ptr::Raw))
+}
+
+/// The loans each local and each of its fields may hold: those of the
+/// references it may contain or point through.  Of those, the loans a
+/// value directly is, as a reference, are told apart from the loans of
+/// what it points to, which outlive a copy read through it.
+pub(super) struct Holders<'a> {
+    pub(super) loans: Vec<Loan<'a>>,
+    // Paths holding loans, the paths each one overlaps (those it is in and
+    // those in it), those in it, and the paths of each local.
+    paths: Vec<Place<'a>>,
+    overlapping: Vec<Vec<usize>>,
+    inner: Vec<Vec<usize>>,
+    locals: Vec<Vec<usize>>,
+    // Paths each path mutably borrows, directly or not.
+    aliases: Vec<Vec<usize>>,
+}
+
+/// The loans a place holds, and those of them the place directly is
+#[derive(Default)]
+struct Held {
+    all: Vec<usize>,
+    direct: Vec<usize>,
+}
+
+impl<'a> Holders<'a> {
+    pub(super) fn new(body: &Body<'a>) -> Self {
+        let mut paths: Vec<Place<'a>> = (0..body.locals.len())
+            .map(Place::new)
+            .collect();
+        let mut add = |place: &Place<'a>| {
+            let path = holder_path(place);
+            for len in 1..=path.elems.len() {
+                let prefix = Place {
+                    local: path.local,
+                    elems: path.elems[..len].to_vec(),
+                };
+                if !paths.contains(&prefix) {
+                    paths.push(prefix);
+                }
+            }
+        };
+        for block in &body.blocks {
+            for stmt in &block.stmts {
+                for (place, _) in stmt.accesses() {
+                    add(place);
+                }
+                match stmt.kind {
+                    StatementKind::Assign(_, Rvalue::Compute(_, ref flows)) => {
+                        flows.iter().for_each(&mut add);
+                    }
+                    StatementKind::Assign(ref dest, Rvalue::Aggregate(_,
+                        ref fields)) =>
+                    {
+                        for (elem, place) in fields {
+                            add(&dest.project(*elem));
+                            add(place);
+                        }
+                    }
+                    StatementKind::Store(ref from, ref to) => {
+                        add(from);
+                        add(to);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let is_prefix = |a: &Place<'a>, b: &Place<'a>| {
+            a.local == b.local && b.elems.starts_with(&a.elems)
+        };
+        let overlapping = paths.iter()
+            .map(|path| (0..paths.len())
+                .filter(|&i| is_prefix(&paths[i], path)
+                    || is_prefix(path, &paths[i]))
+                .collect())
+            .collect();
+        let inner = paths.iter()
+            .map(|path| (0..paths.len())
+                .filter(|&i| is_prefix(path, &paths[i]))
+                .collect())
+            .collect();
+        let locals = (0..body.locals.len())
+            .map(|local| (0..paths.len())
+                .filter(|&i| paths[i].local == local)
+                .collect())
+            .collect();
+
+        let find = |place: &Place<'a>| {
+            let path = holder_path(place);
+            paths.iter().position(|found| *found == path).unwrap()
+        };
+        let mut loans = Vec::new();
+        let mut aliases = vec![Vec::new(); paths.len()];
+        for block in &body.blocks {
+            for stmt in &block.stmts {
+                if let StatementKind::Assign(ref dest, Rvalue::Ref(mutable,
+                    ref place)) = stmt.kind
+                {
+                    loans.push(Loan {
+                        place: place.clone(),
+                        mutable,
+                        dest: dest.local,
+                        span: stmt.span,
+                    });
+                    let (dest, place) = (find(dest), find(place));
+                    if mutable && !aliases[dest].contains(&place) {
+                        aliases[dest].push(place);
+                    }
+                }
+            }
+        }
+        // Close the aliases over the paths they mutably borrow.
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for path in 0..aliases.len() {
+                for i in 0..aliases[path].len() {
+                    let alias = aliases[path][i];
+                    for j in 0..aliases[alias].len() {
+                        let next = aliases[alias][j];
+                        if next != path && !aliases[path].contains(&next) {
+                            aliases[path].push(next);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+        Holders { loans, paths, overlapping, inner, locals, aliases }
+    }
+
+    /// Add a loan no statement creates, like those the caller of a body
+    /// holds, returning its index.  The loans are added before any state
+    /// is made.
+    pub(super) fn add_loan(&mut self, loan: Loan<'a>) -> usize {
+        self.loans.push(loan);
+        self.loans.len() - 1
+    }
+
+    /// The loan a statement creates.
+    pub(super) fn loan_of(&self, stmt: &Statement<'a>) -> Option<usize> {
+        match stmt.kind {
+            StatementKind::Assign(ref dest, Rvalue::Ref(mutable, ref place)) => {
+                self.loans.iter().position(|loan| {
+                    loan.span == stmt.span && loan.dest == dest.local
+                        && loan.mutable == mutable && loan.place == *place
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The state where no local holds a loan.
+    pub(super) fn empty(&self) -> BitSet {
+        BitSet::new(self.paths.len() * 2 * self.loans.len())
+    }
+
+    // The index in a state of a loan held by a path, or directly held.
+    fn bit(&self, path: usize, loan: usize, direct: bool) -> usize {
+        (path * 2 + direct as usize) * self.loans.len() + loan
+    }
+
+    /// Whether a local or one of its fields holds a loan.
+    pub(super) fn holds(&self, state: &BitSet, local: Local, loan: usize)
+        -> bool
+    {
+        self.locals[local].iter()
+            .any(|&path| state.contains(self.bit(path, loan, false)))
+    }
+
+    /// Make a local hold a loan, directly if it is the loan of the
+    /// reference the local is.
+    pub(super) fn hold(&self, state: &mut BitSet, local: Local, loan: usize,
+        direct: bool)
+    {
+        state.insert(self.bit(local, loan, false));
+        if direct {
+            state.insert(self.bit(local, loan, true));
+        }
+    }
+
+    fn find(&self, place: &Place<'a>) -> usize {
+        let path = holder_path(place);
+        self.paths.iter().position(|found| *found == path).unwrap()
+    }
+
+    // The loans a path holds itself, directly or not.
+    fn loans_at(&self, state: &BitSet, path: usize, direct: bool)
+        -> Vec<usize>
+    {
+        (0..self.loans.len())
+            .filter(|&loan| state.contains(self.bit(path, loan, direct)))
+            .collect()
+    }
+
+    // The loans a value read from a place holds: those of the place and
+    // of what it is in, except the references it is read through.
+    fn read(&self, state: &BitSet, place: &Place<'a>) -> Held {
+        let path = self.find(place);
+        let mut all = Vec::new();
+        for &overlap in &self.overlapping[path] {
+            for loan in self.loans_at(state, overlap, false) {
+                if !all.contains(&loan) {
+                    all.push(loan);
+                }
+            }
+        }
+        let rest = &place.elems[self.paths[path].elems.len()..];
+        let direct = self.loans_at(state, path, true);
+        match rest.iter().any(is_indirect) {
+            true => {
+                all.retain(|loan| !direct.contains(loan));
+                Held { all, direct: Vec::new() }
+            }
+            false if rest.is_empty() => Held { all, direct },
+            false => Held { all, direct: Vec::new() },
+        }
+    }
+
+    // The loans a reference to a place holds.  Borrowing through a shared
+    // reference found behind another reference needs only what the outer
+    // one points to, so the reference read through isn't held.
+    fn borrow(&self, state: &BitSet, place: &Place<'a>, loan: Option<usize>)
+        -> Held
+    {
+        let path = self.find(place);
+        let rest = &place.elems[self.paths[path].elems.len()..];
+        let first = rest.iter().position(is_indirect);
+        let shared = rest.iter()
+            .rposition(|elem| *elem == Elem::Deref(Ptr::Ref(false)));
+        let mut held = match (first, shared) {
+            (Some(first), Some(shared)) if shared > first => {
+                Held { direct: Vec::new(), ..self.read(state, place) }
+            }
+            _ => {
+                let whole = Place {
+                    local: place.local,
+                    elems: self.paths[path].elems.clone(),
+                };
+                let mut held = self.read(state, &whole);
+                if first.is_none() {
+                    held.direct.clear();
+                }
+                held
+            }
+        };
+        held.all.extend(loan);
+        held.direct.extend(loan);
+        held
+    }
+
+    fn clear(&self, state: &mut BitSet, path: usize) {
+        for &inner in &self.inner[path] {
+            for loan in 0..self.loans.len() {
+                state.remove(self.bit(inner, loan, false));
+                state.remove(self.bit(inner, loan, true));
+            }
+        }
+    }
+
+    fn set(&self, state: &mut BitSet, path: usize, held: &Held) {
+        for &loan in &held.all {
+            state.insert(self.bit(path, loan, false));
+        }
+        for &loan in &held.direct {
+            state.insert(self.bit(path, loan, true));
+        }
+    }
+
+    // Forget a loan, which nothing can use anymore.
+    fn kill(&self, state: &mut BitSet, loan: usize) {
+        for path in 0..self.paths.len() {
+            state.remove(self.bit(path, loan, false));
+            state.remove(self.bit(path, loan, true));
+        }
+    }
+
+    // Add loans to a path, the paths it overlaps, and the paths they
+    // mutably borrow.
+    fn store(&self, state: &mut BitSet, path: usize, loans: &[usize]) {
+        let held = Held { all: loans.to_vec(), direct: Vec::new() };
+        self.set(state, path, &held);
+        for &overlap in &self.overlapping[path] {
+            for &alias in &self.aliases[overlap] {
+                self.set(state, alias, &held);
+            }
+        }
+    }
+
+    // Assign values holding loans to a place, or to fields of it.
+    fn assign(&self, state: &mut BitSet, dest: &Place<'a>,
+        fields: Vec<(Option<Elem<'a>>, Held)>)
+    {
+        let path = self.find(dest);
+        let rest = &dest.elems[self.paths[path].elems.len()..];
+        if !rest.is_empty() {
+            // Part of what the place points to, or an element of it, may
+            // now hold the loans as well.
+            let mut loans = Vec::new();
+            for (_, held) in fields {
+                loans.extend(held.all);
+            }
+            match rest.iter().any(|elem| matches!(elem, Elem::Deref(_))) {
+                true => self.store(state, path, &loans),
+                false => self.set(state, path,
+                    &Held { all: loans, direct: Vec::new() }),
+            }
+            return;
+        }
+        self.clear(state, path);
+        for (elem, held) in fields {
+            let path = match elem {
+                Some(elem) => self.find(&dest.project(elem)),
+                None => path,
+            };
+            self.set(state, path, &held);
+        }
+    }
+}
+
+impl<'a> Forward<'a> for Holders<'a> {
+    type State = BitSet;
+
+    fn join(&self, state: &mut BitSet, other: &BitSet) -> bool {
+        state.union(other)
+    }
+
+    fn apply(&self, state: &mut BitSet, stmt: &Statement<'a>) {
+        match stmt.kind {
+            StatementKind::Assign(ref dest, ref rvalue) => {
+                let mut fields = match *rvalue {
+                    Rvalue::Use(ref operand) => {
+                        let held = match operand.place() {
+                            Some(place) => self.read(state, place),
+                            None => Held::default(),
+                        };
+                        vec![(None, held)]
+                    }
+                    Rvalue::Ref(_, ref place) => {
+                        // A reference to a place also holds what the place
+                        // holds.
+                        vec![(None, self.borrow(state, place,
+                            self.loan_of(stmt)))]
+                    }
+                    Rvalue::Compute(_, ref flows) => {
+                        let mut all = Vec::new();
+                        for place in flows {
+                            all.extend(self.read(state, place).all);
+                        }
+                        vec![(None, Held { all, direct: Vec::new() })]
+                    }
+                    Rvalue::Aggregate(_, ref flows) => flows.iter()
+                        .map(|(elem, place)| {
+                            (Some(*elem), self.read(state, place))
+                        })
+                        .collect(),
+                };
+                // Loans through the old value of a place are unreachable.
+                for (index, loan) in self.loans.iter().enumerate() {
+                    let place = &loan.place;
+                    if place.local == dest.local
+                        && place.elems.len() > dest.elems.len()
+                        && place.elems.starts_with(&dest.elems)
+                        && is_indirect(&place.elems[dest.elems.len()])
+                    {
+                        self.kill(state, index);
+                        for (_, held) in &mut fields {
+                            held.all.retain(|&loan| loan != index);
+                            held.direct.retain(|&loan| loan != index);
+                        }
+                    }
+                }
+                self.assign(state, dest, fields);
+            }
+            StatementKind::Store(ref from, ref to) => {
+                let loans = self.read(state, from).all;
+                self.store(state, self.find(to), &loans);
+            }
+            StatementKind::StorageDead(local) => {
+                for &path in &self.locals[local] {
+                    self.clear(state, path);
+                }
+                for (index, loan) in self.loans.iter().enumerate() {
+                    if loan.place.local == local {
+                        self.kill(state, index);
+                    }
+                }
+            }
+            StatementKind::Read(_) | StatementKind::Mutate(_) => {}
+        }
+    }
+}
+
+/// The state of the places moves are tracked for
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Inits {
+    /// Places that may be uninitialized
+    pub(super) uninit: BitSet,
+    /// Places that may be moved out
+    pub(super) moved: BitSet,
+    /// Places that may be initialized
+    pub(super) init: BitSet,
+}
+
+/// The places moves are tracked for: locals and their fields, and the
+/// contents of their boxes
+pub(super) struct MovePaths<'b, 'a> {
+    body: &'b Body<'a>,
+    pub(super) paths: Vec<Place<'a>>,
+    // Each path, the paths it is in, and those in it.
+    prefixes: Vec<Vec<usize>>,
+    children: Vec<Vec<usize>>,
+}
+
+/// The part of a place moves are tracked for: up to its first index or
+/// dereference of something other than a box.
+pub(super) fn move_path<'a>(place: &Place<'a>) -> Place<'a> {
+    let len = place.elems.iter()
+        .position(|elem| !matches!(*elem,
+            Elem::Field(_) | Elem::Tuple(_) | Elem::Deref(Ptr::Box)))
+        .unwrap_or(place.elems.len());
+    Place { local: place.local, elems: place.elems[..len].to_vec() }
+}
+
+impl<'b, 'a> MovePaths<'b, 'a> {
+    pub(super) fn new(body: &'b Body<'a>) -> Self {
+        let mut paths: Vec<Place<'a>> = (0..body.locals.len())
+            .map(Place::new)
+            .collect();
+        let mut add = |place: &Place<'a>| {
+            let path = move_path(place);
+            for len in 1..=path.elems.len() {
+                let prefix = Place {
+                    local: path.local,
+                    elems: path.elems[..len].to_vec(),
+                };
+                if !paths.contains(&prefix) {
+                    paths.push(prefix);
+                }
+            }
+        };
+        for block in &body.blocks {
+            for stmt in &block.stmts {
+                for (place, _) in stmt.accesses() {
+                    add(place);
+                }
+            }
+        }
+        let is_prefix = |a: &Place<'a>, b: &Place<'a>| {
+            a.local == b.local && b.elems.starts_with(&a.elems)
+        };
+        let prefixes = paths.iter()
+            .map(|path| (0..paths.len())
+                .filter(|&i| is_prefix(&paths[i], path))
+                .collect())
+            .collect();
+        let children = paths.iter()
+            .map(|path| (0..paths.len())
+                .filter(|&i| is_prefix(path, &paths[i]) && paths[i] != *path)
+                .collect())
+            .collect();
+        MovePaths { body, paths, prefixes, children }
+    }
+
+    /// The state at the start of the body, where only the parameters and
+    /// captured variables are initialized.
+    pub(super) fn entry(&self) -> Inits {
+        let mut inits = Inits {
+            uninit: BitSet::new(self.paths.len()),
+            moved: BitSet::new(self.paths.len()),
+            init: BitSet::new(self.paths.len()),
+        };
+        for (i, path) in self.paths.iter().enumerate() {
+            match self.body.locals[path.local].kind {
+                LocalKind::Arg | LocalKind::Upvar => inits.init.insert(i),
+                _ => inits.uninit.insert(i),
+            }
+        }
+        inits
+    }
+
+    /// Find the path of a place.
+    pub(super) fn find(&self, place: &Place<'a>) -> Option<usize> {
+        let path = move_path(place);
+        self.paths.iter().position(|found| *found == path)
+    }
+
+    pub(super) fn prefixes(&self, path: usize) -> &[usize] {
+        &self.prefixes[path]
+    }
+
+    pub(super) fn children(&self, path: usize) -> &[usize] {
+        &self.children[path]
+    }
+
+    fn paths_in(&self, path: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::once(path).chain(self.children[path].iter().copied())
+    }
+
+    fn move_out(&self, state: &mut Inits, place: &Place<'a>) {
+        // Moving out of a reference is an error, which doesn't move.
+        if move_path(place).elems.len() != place.elems.len() {
+            return;
+        }
+        if let Some(path) = self.find(place) {
+            for i in self.paths_in(path).collect::<Vec<_>>() {
+                state.uninit.insert(i);
+                state.moved.insert(i);
+                state.init.remove(i);
+            }
+        }
+    }
+
+    fn initialize(&self, state: &mut Inits, place: &Place<'a>) {
+        if move_path(place).elems.len() != place.elems.len() {
+            return;
+        }
+        if let Some(path) = self.find(place) {
+            for i in self.paths_in(path).collect::<Vec<_>>() {
+                state.uninit.remove(i);
+                state.moved.remove(i);
+                state.init.insert(i);
+            }
+        }
+    }
+}
+
+impl<'a> Forward<'a> for MovePaths<'_, 'a> {
+    type State = Inits;
+
+    fn join(&self, state: &mut Inits, other: &Inits) -> bool {
+        let uninit = state.uninit.union(&other.uninit);
+        let moved = state.moved.union(&other.moved);
+        let init = state.init.union(&other.init);
+        uninit || moved || init
+    }
+
+    fn apply(&self, state: &mut Inits, stmt: &Statement<'a>) {
+        match stmt.kind {
+            StatementKind::Assign(ref dest, ref rvalue) => {
+                let operands = match *rvalue {
+                    Rvalue::Use(ref operand) => std::slice::from_ref(operand),
+                    Rvalue::Compute(ref operands, _)
+                    | Rvalue::Aggregate(ref operands, _) => operands.as_slice(),
+                    Rvalue::Ref(..) => &[],
+                };
+                for operand in operands {
+                    if let Operand::Move(ref place) = *operand {
+                        self.move_out(state, place);
+                    }
+                }
+                self.initialize(state, dest);
+            }
+            StatementKind::StorageDead(local) => {
+                if let Some(path) = self.find(&Place::new(local)) {
+                    for i in self.paths_in(path).collect::<Vec<_>>() {
+                        state.uninit.insert(i);
+                        state.moved.remove(i);
+                        state.init.remove(i);
+                    }
+                }
+            }
+            StatementKind::Read(_)
+            | StatementKind::Mutate(_)
+            | StatementKind::Store(..) => {}
+        }
+    }
+}
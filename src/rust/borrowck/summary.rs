@@ -0,0 +1,115 @@
+// Rust borrow checking: calls
+//
+//! What the result of a call holds of the loans of its arguments, found by
+//! running the analysis of the loans held over the body of the function
+//! called.  The body starts with loans of its own standing for those of
+//! its arguments, and the result is whatever of them reaches the return
+//! place.  An argument that is a reference has two: the loan the reference
+//! is, and the loans of what it points to, so that a function returning
+//! a field of `self` borrows what `self` borrows rather than `self`.
+//!
+//! Functions without a body, like those of the standard library, and
+//! trait methods, whose body depends on the type they are called on, have
+//! no summary.
+
+use super::build::Builder;
+use super::dataflow::{forward, Forward, Holders, Loan};
+use super::{Body, Checker, ItemId, LocalKind, Place, Terminator, Ty};
+use crate::Span;
+
+/// What the result of a call holds of the loans of an argument
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Flow {
+    /// The loan of the reference the argument is, if it is one
+    pub(super) outer: bool,
+    /// The loans of what the argument points to, or holds if it isn't a
+    /// reference
+    pub(super) inner: bool,
+}
+
+impl<'k, 'a> Checker<'k, 'a> {
+    /// What the result of a call to a function holds of each of its
+    /// arguments, starting with `self`, if its body is known.
+    pub(super) fn flows(&self, callee: ItemId) -> Option<Vec<Flow>> {
+        if let Some(flows) = self.flows.borrow().get(&callee) {
+            return flows.clone();
+        }
+        let found = match callee {
+            ItemId::TraitItem(..) => None,
+            _ => self.function(callee),
+        };
+        let (module, function, block) = match found {
+            Some((module, function)) if !self.krate.is_std(module) => {
+                match function.body {
+                    Some(ref block) => (module, function, block),
+                    None => {
+                        self.flows.borrow_mut().insert(callee, None);
+                        return None;
+                    }
+                }
+            }
+            _ => {
+                self.flows.borrow_mut().insert(callee, None);
+                return None;
+            }
+        };
+        // A recursive call holds all the loans of its arguments.
+        let count = function.params.len() + function.self_param.iter().count();
+        let all = Flow { outer: true, inner: true };
+        self.flows.borrow_mut().insert(callee, Some(vec![all; count]));
+        let bodies = Builder::new(self, module).lower_fn(function, block);
+        let flows = returned(bodies.last().unwrap());
+        self.flows.borrow_mut().insert(callee, Some(flows.clone()));
+        Some(flows)
+    }
+}
+
+// What the return place of a function body holds of the loans of each of
+// its arguments.
+fn returned(body: &Body) -> Vec<Flow> {
+    let args: Vec<_> = (0..body.locals.len())
+        .filter(|&local| body.locals[local].kind == LocalKind::Arg)
+        .collect();
+    let mut holders = Holders::new(body);
+    // The loans of the caller are of a place no statement uses.
+    let outside = Place::new(body.locals.len());
+    let mut loans = Vec::new();
+    for &arg in &args {
+        let mut loan = || holders.add_loan(Loan {
+            place: outside.clone(),
+            mutable: false,
+            dest: arg,
+            span: Span::default(),
+        });
+        loans.push((loan(), loan()));
+    }
+    let is_ref = |arg: usize| matches!(body.locals[arg].ty, Ty::Ref(..));
+    let mut entry = holders.empty();
+    for (&arg, &(outer, inner)) in args.iter().zip(&loans) {
+        if is_ref(arg) {
+            holders.hold(&mut entry, arg, outer, true);
+        }
+        holders.hold(&mut entry, arg, inner, false);
+    }
+
+    let states = forward(&holders, body, entry);
+    let mut exit = holders.empty();
+    for (block, state) in body.blocks.iter().zip(states) {
+        if let (Terminator::Return, Some(mut state)) = (&block.term, state) {
+            for stmt in &block.stmts {
+                holders.apply(&mut state, stmt);
+            }
+            exit.union(&state);
+        }
+    }
+    args.iter().zip(loans)
+        .map(|(&arg, (outer, inner))| {
+            let inner = holders.holds(&exit, 0, inner);
+            match is_ref(arg) {
+                true => Flow { outer: holders.holds(&exit, 0, outer), inner },
+                // The loans of a value are all held by what holds it.
+                false => Flow { outer: inner, inner },
+            }
+        })
+        .collect()
+}
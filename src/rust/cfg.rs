@@ -0,0 +1,109 @@
+// Rust conditional compilation
+//
+//! The configuration options tested by `#[cfg(...)]`, `#[cfg_attr(...)]` and
+//! `cfg!(...)`, like the ones Cargo passes to rustc: enabled features are
+//! `feature = "name"`, and a test build sets `test`.
+
+use super::{Bracket, Operator, Result, Token, TokenTree};
+use crate::{Diagnostic, Span};
+
+/// A set of configuration options, each `name` or `name = "value"`.  An
+/// option may be set with several values, like `feature`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cfg {
+    pub options: Vec<(String, Option<String>)>,
+}
+
+impl Default for Cfg {
+    /// A debug build for x86-64 Linux, with no features enabled.
+    fn default() -> Self {
+        let mut cfg = Cfg { options: Vec::new() };
+        cfg.set("debug_assertions", None);
+        cfg.set("panic", Some("unwind"));
+        cfg.set("target_arch", Some("x86_64"));
+        cfg.set("target_endian", Some("little"));
+        cfg.set("target_family", Some("unix"));
+        cfg.set("target_os", Some("linux"));
+        cfg.set("target_pointer_width", Some("64"));
+        cfg.set("unix", None);
+        cfg
+    }
+}
+
+impl Cfg {
+    /// Set an option, keeping any other values it has.
+    pub fn set(&mut self, name: &str, value: Option<&str>) {
+        if !self.is_set(name, value) {
+            self.options.push((name.to_string(), value.map(str::to_string)));
+        }
+    }
+
+    /// Remove all values of an option.
+    pub fn unset(&mut self, name: &str) {
+        self.options.retain(|(found, _)| found != name);
+    }
+
+    /// Enable a feature, as `--features name` does.
+    pub fn feature(&mut self, name: &str) {
+        self.set("feature", Some(name));
+    }
+
+    pub fn is_set(&self, name: &str, value: Option<&str>) -> bool {
+        self.options.iter()
+            .any(|(found, v)| found == name && v.as_deref() == value)
+    }
+
+    /// Evaluate a configuration predicate such as `all(unix, not(test))`.
+    /// `span` is where an empty predicate is reported.
+    pub(super) fn eval(&self, predicate: &[TokenTree], span: Span)
+        -> Result<bool>
+    {
+        let list = |args: &[TokenTree], span: Span| {
+            let mut values = Vec::new();
+            let mut rest = args;
+            while !rest.is_empty() {
+                let end = rest.iter()
+                    .position(|tree| matches!(tree,
+                        TokenTree::Token(Token::Operator(Operator::Comma), _)))
+                    .unwrap_or(rest.len());
+                values.push(self.eval(&rest[..end], span)?);
+                rest = rest.get(end + 1..).unwrap_or_default();
+            }
+            Ok::<_, Diagnostic>(values)
+        };
+        match predicate {
+            [TokenTree::Token(Token::Identifier(name), _)] => {
+                Ok(self.is_set(name, None))
+            }
+            [TokenTree::Token(Token::Identifier(name), _),
+                TokenTree::Token(Token::Operator(Operator::Eq), _),
+                TokenTree::Token(Token::String(value), _)] =>
+            {
+                Ok(self.is_set(name, Some(value)))
+            }
+            [TokenTree::Token(Token::Identifier("all"), _),
+                TokenTree::Group(Bracket::ParensL, args, group)] =>
+            {
+                Ok(list(args, *group)?.into_iter().all(|value| value))
+            }
+            [TokenTree::Token(Token::Identifier("any"), _),
+                TokenTree::Group(Bracket::ParensL, args, group)] =>
+            {
+                Ok(list(args, *group)?.into_iter().any(|value| value))
+            }
+            [TokenTree::Token(Token::Identifier("not"), _),
+                TokenTree::Group(Bracket::ParensL, args, group)] =>
+            {
+                match list(args, *group)?.as_slice() {
+                    [value] => Ok(!value),
+                    _ => Err(Diagnostic::new(*group,
+                        "`not` takes 1 configuration predicate")),
+                }
+            }
+            [first, ..] => Err(Diagnostic::new(first.span(),
+                "invalid configuration predicate")),
+            [] => Err(Diagnostic::new(span,
+                "expected a configuration predicate")),
+        }
+    }
+}
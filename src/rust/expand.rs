@@ -0,0 +1,1470 @@
+// Rust macro expansion
+//
+//! Expansion of `macro_rules!` macros, and of the built-in macros that expand
+//! to a literal (`concat!`, `stringify!`, `line!`, `column!`, `file!`,
+//! `include_str!` and `cfg!`), and of `#[derive]` on structs, enums and
+//! unions.
+//!
+//! Expansion works on source text: the expansion of each macro call is
+//! appended to the text of its file, and the parser reads it in place of the
+//! call (see [`Splice`]).  Other standard library macros such as `println!`,
+//! `format_args!` and `vec!` are checked by the type checker, after the calls
+//! in their arguments are expanded.
+//!
+//! A macro is in scope from its definition to the end of the enclosing block
+//! or module, and `#[macro_use]` on a module keeps its macros in scope after
+//! it.  `#[macro_export]` macros can also be called as `crate::name!` (or
+//! `$crate::name!`) from anywhere.  Local variables declared by a macro body
+//! are renamed in each expansion, so they don't clash with the variables at
+//! the call site.
+//!
+//! Items that `#[cfg]` disables are skipped, and the impls that an item
+//! derives are spliced in right after it, in place of an empty call.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path as FilePath;
+
+use super::cfg::Cfg;
+use super::parser::{Parser, PathStyle};
+use super::resolve::{FileDiagnostic, FileId, SourceFile};
+use super::{Bracket, Keyword, Operator, Result, Token, TokenTree};
+use crate::{Diagnostic, Span};
+
+mod derive;
+
+// Nesting of macro calls in expansions before giving up (lower than rustc's
+// 128, as each level is also a level of nesting for the parser).
+const RECURSION_LIMIT: usize = 64;
+
+// Built-in macros that are expanded to a literal.
+const BUILTINS: &[&str] = &[
+    "cfg", "column", "concat", "file", "include_str", "line", "stringify",
+];
+
+// Standard library macros with a format string, and the number of arguments
+// before it.
+const FORMAT_MACROS: &[(&str, usize)] = &[
+    ("eprint", 0),
+    ("eprintln", 0),
+    ("format", 0),
+    ("format_args", 0),
+    ("print", 0),
+    ("println", 0),
+    ("write", 1),
+    ("writeln", 1),
+];
+
+/// A macro call replaced by its expansion
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Splice {
+    /// The call, with the `;` after it if it expands to items or statements,
+    /// or the empty span at the end of an item with derived impls
+    pub(super) call: Span,
+    /// The expansion, in the text appended to the file
+    pub(super) expansion: Span,
+}
+
+/// Expand the macro calls of the files of a crate, starting from the crate
+/// root, and return the errors.
+pub(super) fn expand<F>(
+    files: &mut [SourceFile],
+    children: &HashMap<(FileId, usize), FileId>,
+    cfg: &Cfg,
+    read: &mut F,
+) -> Vec<FileDiagnostic>
+where
+    F: FnMut(&FilePath) -> io::Result<String>,
+{
+    let mut exported = HashMap::new();
+    for (file, source) in files.iter().enumerate() {
+        if let Ok(trees) = Parser::new(&source.text).token_trees() {
+            exports(file, &trees, &mut exported);
+        }
+    }
+    let mut expander = Expander {
+        visited: vec![false; files.len()],
+        files,
+        children,
+        cfg,
+        read,
+        scope: Vec::new(),
+        exported,
+        errors: Vec::new(),
+        fresh: 0,
+    };
+    for file in 0..expander.files.len() {
+        if !expander.visited[file] {
+            expander.scope.clear();
+            expander.file(file);
+        }
+    }
+    for file in expander.files.iter_mut() {
+        file.splices.sort_by_key(|splice| splice.call.start);
+    }
+    expander.errors
+}
+
+// A `macro_rules!` definition.
+#[derive(Clone)]
+struct MacroDef {
+    name: String,
+    file: FileId,
+    // The group of rules, including its brackets.
+    rules: Span,
+}
+
+// Part of the text of a file, copied so the file can grow while its token
+// trees are in use.  Spans in the region are relative to its start.
+struct Region {
+    file: FileId,
+    base: usize,
+    text: String,
+}
+
+impl Region {
+    fn new(files: &[SourceFile], file: FileId, span: Span) -> Self {
+        let text = files[file].text[span.start..span.end].to_string();
+        Region { file, base: span.start, text }
+    }
+
+    // The span in the file of a span in the region.
+    fn global(&self, span: Span) -> Span {
+        Span::new(span.start + self.base, span.end + self.base)
+    }
+
+    fn error(&self, diagnostic: Diagnostic) -> FileDiagnostic {
+        FileDiagnostic {
+            file: self.file,
+            diagnostic: Diagnostic {
+                span: self.global(diagnostic.span),
+                ..diagnostic
+            },
+        }
+    }
+
+    // Source text of a token, with doc comments written as the tokens of
+    // their `doc` attribute.
+    fn token(&self, token: &Token, span: Span) -> String {
+        let text = &self.text[span.start..span.end];
+        if text.starts_with("//") || text.starts_with("/*") {
+            token.to_string()
+        } else {
+            text.to_string()
+        }
+    }
+
+    // Source text from the first to the last of some token trees.
+    fn trees(&self, trees: &[TokenTree]) -> &str {
+        match (trees.first(), trees.last()) {
+            (Some(first), Some(last)) => {
+                &self.text[first.span().start..last.span().end]
+            }
+            _ => "",
+        }
+    }
+}
+
+// The kind of syntax a macro variable matches.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Fragment {
+    Block,
+    Expr,
+    Ident,
+    Item,
+    Lifetime,
+    Literal,
+    Meta,
+    Pat,
+    PatParam,
+    Path,
+    Stmt,
+    Tt,
+    Ty,
+    Vis,
+}
+
+const FRAGMENTS: &[(&str, Fragment)] = &[
+    ("block", Fragment::Block),
+    ("expr", Fragment::Expr),
+    ("ident", Fragment::Ident),
+    ("item", Fragment::Item),
+    ("lifetime", Fragment::Lifetime),
+    ("literal", Fragment::Literal),
+    ("meta", Fragment::Meta),
+    ("pat", Fragment::Pat),
+    ("pat_param", Fragment::PatParam),
+    ("path", Fragment::Path),
+    ("stmt", Fragment::Stmt),
+    ("tt", Fragment::Tt),
+    ("ty", Fragment::Ty),
+    ("vis", Fragment::Vis),
+];
+
+// How many times a repetition may match.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kleene {
+    /// `*`
+    Many,
+    /// `+`
+    AtLeastOne,
+    /// `?`
+    AtMostOne,
+}
+
+// A part of the input pattern of a rule.
+enum Matcher<'d> {
+    Token(Token<'d>),
+    Group(Bracket, Vec<Matcher<'d>>),
+    /// `$name:fragment`
+    Var(&'d str, Fragment),
+    /// `$( ... ) separator kleene`
+    Repeat(Vec<Matcher<'d>>, Option<Token<'d>>, Kleene),
+}
+
+// A rule of a `macro_rules!` definition.
+struct Rule<'d> {
+    matcher: Vec<Matcher<'d>>,
+    body: Vec<TokenTree<'d>>,
+}
+
+// What a macro variable matched.
+#[derive(Debug, Clone)]
+enum Capture<'c> {
+    One(Fragment, Vec<TokenTree<'c>>),
+    /// One capture for each repetition
+    Many(Vec<Capture<'c>>),
+}
+
+type Captures<'d, 'c> = HashMap<&'d str, Capture<'c>>;
+
+// The macro a call resolves to.
+enum Callee {
+    Rules(MacroDef),
+    Builtin(&'static str),
+    /// A standard library macro left to the type checker
+    Std(String),
+}
+
+struct Expander<'f, F> {
+    files: &'f mut [SourceFile],
+    children: &'f HashMap<(FileId, usize), FileId>,
+    cfg: &'f Cfg,
+    read: &'f mut F,
+    visited: Vec<bool>,
+    // Definitions in textual scope, innermost last.
+    scope: Vec<MacroDef>,
+    // `#[macro_export]` definitions, by name.
+    exported: HashMap<String, MacroDef>,
+    errors: Vec<FileDiagnostic>,
+    // Number of local variables renamed so far.
+    fresh: usize,
+}
+
+impl<F> Expander<'_, F>
+where
+    F: FnMut(&FilePath) -> io::Result<String>,
+{
+    fn file(&mut self, file: FileId) {
+        self.visited[file] = true;
+        let len = self.files[file].len;
+        self.region(file, Span::new(0, len), 0);
+    }
+
+    // Expand the calls in a part of a file.  Parse errors are left for the
+    // parser to report.
+    fn region(&mut self, file: FileId, span: Span, depth: usize) {
+        let region = Region::new(self.files, file, span);
+        if let Ok(trees) = Parser::new(&region.text).token_trees() {
+            self.trees(&region, &trees, depth);
+        }
+    }
+
+    fn trees(&mut self, region: &Region, trees: &[TokenTree], depth: usize) {
+        let mut i = 0;
+        while i < trees.len() {
+            match (&trees[i], trees.get(i + 1), trees.get(i + 2)) {
+                (
+                    TokenTree::Token(Token::Identifier("macro_rules"), _),
+                    Some(TokenTree::Token(Token::Operator(Operator::Not), _)),
+                    Some(TokenTree::Token(Token::Identifier(name), _)),
+                ) => {
+                    if let Some(TokenTree::Group(_, _, span)) = trees.get(i + 3)
+                    {
+                        self.scope.push(MacroDef {
+                            name: name.to_string(),
+                            file: region.file,
+                            rules: region.global(*span),
+                        });
+                    }
+                    i += 4;
+                }
+                (
+                    TokenTree::Token(Token::Keyword(Keyword::Mod), _),
+                    Some(TokenTree::Token(Token::Identifier(_), _)),
+                    Some(TokenTree::Token(Token::Operator(Operator::Semi), _)),
+                ) => {
+                    let start = item_start(trees, i);
+                    let key = (region.file,
+                        region.global(trees[start].span()).start);
+                    if let Some(&child) = self.children.get(&key) {
+                        let len = self.scope.len();
+                        if !self.visited[child] {
+                            self.file(child);
+                        }
+                        if !has_attr(trees, start, "macro_use") {
+                            self.scope.truncate(len);
+                        }
+                    }
+                    i += 3;
+                }
+                (
+                    TokenTree::Token(Token::Operator(Operator::Not), _),
+                    Some(TokenTree::Group(_, args, span)),
+                    _,
+                ) if i > 0 => {
+                    let (start, path) = match call_path(trees, i) {
+                        Some(found) => found,
+                        None => {
+                            i += 1;
+                            continue;
+                        }
+                    };
+                    let call = Span::new(trees[start].span().start, span.end);
+                    let close = Span::new(span.end - 1, span.end);
+                    i += 2;
+                    let expansion = match self.callee(&path) {
+                        Some(Callee::Rules(def)) if depth >= RECURSION_LIMIT => {
+                            Err(region.error(Diagnostic::new(call, format!(
+                                "recursion limit reached while expanding `{}!`",
+                                def.name))))
+                        }
+                        Some(Callee::Rules(def)) => {
+                            self.rules(&def, region, args, close)
+                        }
+                        Some(Callee::Builtin(name)) => {
+                            self.builtin(name, region, call, args, close)
+                                .map_err(|e| region.error(e))
+                        }
+                        Some(Callee::Std(name)) => {
+                            if let Err(e) = format_string(&name, args) {
+                                self.errors.push(region.error(e));
+                            }
+                            self.trees(region, args, depth);
+                            continue;
+                        }
+                        None => {
+                            self.trees(region, args, depth);
+                            continue;
+                        }
+                    };
+                    match expansion {
+                        Ok(expansion) => {
+                            if self.splice(region, trees, call, i, expansion,
+                                depth)
+                            {
+                                i += 1;
+                            }
+                        }
+                        Err(error) => self.errors.push(error),
+                    }
+                }
+                (
+                    TokenTree::Token(Token::Operator(Operator::Pound), _),
+                    Some(TokenTree::Group(Bracket::SquareL, ..)),
+                    _,
+                ) => {
+                    i = self.attributes(region, trees, i, depth);
+                }
+                (TokenTree::Group(bracket, inner, _), _, _) => {
+                    let len = self.scope.len();
+                    self.trees(region, inner, depth);
+                    // Macros in an inline `#[macro_use] mod` stay in scope.
+                    let macro_use = i >= 2
+                        && matches!(trees[i - 2],
+                            TokenTree::Token(Token::Keyword(Keyword::Mod), _))
+                        && has_attr(trees, item_start(trees, i - 2),
+                            "macro_use");
+                    if *bracket == Bracket::BraceL && !macro_use {
+                        self.scope.truncate(len);
+                    }
+                    i += 1;
+                }
+                _ => i += 1,
+            }
+        }
+    }
+
+    // Skip the outer attributes starting at `trees[i]`, or the item they
+    // belong to if `#[cfg]` disables it, and add the impls it derives after
+    // the item.  Return the index of the next tree to expand.
+    fn attributes(
+        &mut self,
+        region: &Region,
+        trees: &[TokenTree],
+        i: usize,
+        depth: usize,
+    ) -> usize {
+        let mut next = i;
+        while let (
+            Some(TokenTree::Token(Token::Operator(Operator::Pound), _)),
+            Some(TokenTree::Group(Bracket::SquareL, ..)),
+        ) = (trees.get(next), trees.get(next + 1))
+        {
+            next += 2;
+        }
+        // Attributes of statements, fields and so on are left to the parser.
+        let end = span(trees).end;
+        let mut parser = Parser::from_tokens(&region.text, &trees[i..],
+            Span::new(end, end)).configured(self.cfg);
+        let item = match parser.any_item() {
+            Ok(Some(item)) => item,
+            _ => return next,
+        };
+        let len = match trees_in(&trees[i..], parser.pos()) {
+            Some(len) => len,
+            None => return next,
+        };
+        match parser.is_enabled(&item.attrs) {
+            Ok(true) => {}
+            Ok(false) => return i + len,
+            Err(_) => return next,
+        }
+        let impls = match derive::derive(&item, &region.text) {
+            Ok(Some(impls)) => impls,
+            Ok(None) => return next,
+            Err(e) => {
+                self.errors.push(region.error(e));
+                return next;
+            }
+        };
+        let end = trees[i + len - 1].span().end;
+        let file = &mut self.files[region.file];
+        // Not right after the item, where the empty call would be inside
+        // its own expansion if the item ends the file
+        file.text.push('\n');
+        let start = file.text.len();
+        file.text.push_str(&impls);
+        let expansion = Span::new(start, file.text.len());
+        file.splices.push(Splice {
+            call: region.global(Span::new(end, end)),
+            expansion,
+        });
+        self.region(region.file, expansion, depth + 1);
+        next
+    }
+
+    // Resolve the path of a macro call.
+    fn callee(&self, path: &[&str]) -> Option<Callee> {
+        match *path {
+            [name] => {
+                if let Some(def) = self.scope.iter().rev()
+                    .find(|def| def.name == name)
+                {
+                    return Some(Callee::Rules(def.clone()));
+                }
+                if let Some(builtin) = BUILTINS.iter().find(|b| **b == name) {
+                    return Some(Callee::Builtin(builtin));
+                }
+                if let Some(def) = self.exported.get(name) {
+                    return Some(Callee::Rules(def.clone()));
+                }
+                Some(Callee::Std(name.to_string()))
+            }
+            ["crate", name] => self.exported.get(name).cloned()
+                .map(Callee::Rules),
+            ["std" | "core" | "alloc", name] => {
+                match BUILTINS.iter().find(|b| **b == name) {
+                    Some(builtin) => Some(Callee::Builtin(builtin)),
+                    None => Some(Callee::Std(name.to_string())),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // Replace the call ending before `trees[next]` with its expansion, and
+    // return whether the `;` at `trees[next]` was replaced with it.
+    fn splice(
+        &mut self,
+        region: &Region,
+        trees: &[TokenTree],
+        mut call: Span,
+        next: usize,
+        mut expansion: String,
+        depth: usize,
+    ) -> bool {
+        let mut semi = false;
+        let mut parser = Parser::new(&expansion);
+        if parser.expr().is_ok() && parser.is_eof() {
+            // Keep the precedence of expressions like `a + b`.
+            let parsed = Parser::new(&expansion).token_trees()
+                .unwrap_or_default();
+            let block_like = match parsed.first() {
+                Some(TokenTree::Group(Bracket::BraceL, ..))
+                | Some(TokenTree::Token(Token::Lifetime(_), _)) => true,
+                Some(TokenTree::Token(Token::Keyword(keyword), _)) => {
+                    matches!(keyword, Keyword::If | Keyword::Match
+                        | Keyword::Loop | Keyword::While | Keyword::For
+                        | Keyword::Unsafe)
+                }
+                _ => false,
+            };
+            if parsed.len() > 1 && !block_like {
+                expansion = format!("({})", expansion);
+            }
+        } else if let Some(TokenTree::Token(Token::Operator(Operator::Semi),
+            span)) = trees.get(next)
+        {
+            // Items and statements end with their own `;` or `}`.
+            let parsed = Parser::new(&expansion).token_trees()
+                .unwrap_or_default();
+            semi = match parsed.last() {
+                None | Some(TokenTree::Group(Bracket::BraceL, ..)) => true,
+                Some(TokenTree::Token(token, _)) => {
+                    *token == Token::Operator(Operator::Semi)
+                }
+                Some(_) => false,
+            };
+            if semi {
+                call.end = span.end;
+            }
+        }
+
+        let file = &mut self.files[region.file];
+        let start = file.text.len();
+        file.text.push_str(&expansion);
+        file.text.push('\n');
+        let expansion = Span::new(start, start + expansion.len());
+        file.splices.push(Splice { call: region.global(call), expansion });
+        self.region(region.file, expansion, depth + 1);
+        semi
+    }
+
+    // Expand a call to a `macro_rules!` macro with the first rule that
+    // matches.
+    fn rules(
+        &mut self,
+        def: &MacroDef,
+        region: &Region,
+        args: &[TokenTree],
+        close: Span,
+    ) -> std::result::Result<String, FileDiagnostic> {
+        let source = Region::new(self.files, def.file, def.rules);
+        let rules = Parser::new(&source.text)
+            .token_trees()
+            .and_then(|trees| match trees.first() {
+                Some(TokenTree::Group(_, inner, _)) => rules(inner),
+                _ => Ok(Vec::new()),
+            })
+            .map_err(|e| source.error(e))?;
+
+        let mut matcher = Match { text: &region.text, furthest: None };
+        for rule in &rules {
+            let mut captures = Captures::new();
+            match matcher.prefix(&rule.matcher, args, close, &mut captures) {
+                Some(len) if len == args.len() => {}
+                Some(len) => {
+                    matcher.fail::<()>(args[len].span());
+                    continue;
+                }
+                None => continue,
+            }
+            let mut renames = HashMap::new();
+            let mut names = Vec::new();
+            bindings(&rule.body, &mut names);
+            for name in names {
+                self.fresh += 1;
+                renames.insert(name, format!("{}__{}", name, self.fresh));
+            }
+            let transcriber = Transcriber {
+                def: &source,
+                call: region,
+                renames,
+            };
+            let mut out = String::new();
+            transcriber.transcribe(&rule.body, &captures, &mut out)
+                .map_err(|e| source.error(e))?;
+            return Ok(out);
+        }
+
+        let diagnostic = match matcher.furthest {
+            None if rules.is_empty() => Diagnostic::new(
+                Span::new(close.start, close.start),
+                format!("macro `{}!` has no rules", def.name)),
+            Some(span) => match token_at(args, span) {
+                Some(token) => Diagnostic::new(span,
+                    format!("no rules expected the token `{}`", token)),
+                None => Diagnostic::new(span,
+                    "unexpected end of macro invocation"),
+            },
+            None => Diagnostic::new(close,
+                "unexpected end of macro invocation"),
+        };
+        Err(region.error(diagnostic))
+    }
+
+    // Expand a built-in macro to a literal.
+    fn builtin(
+        &mut self,
+        name: &str,
+        region: &Region,
+        call: Span,
+        args: &[TokenTree],
+        close: Span,
+    ) -> Result<String> {
+        if matches!(name, "line" | "column" | "file") && !args.is_empty() {
+            return Err(Diagnostic::new(args[0].span(),
+                format!("`{}!` takes no arguments", name)));
+        }
+        let file = &self.files[region.file];
+        Ok(match name {
+            "line" | "column" => {
+                let span = file.origin(region.global(call));
+                let (line, column) = span.line_col(&file.text);
+                let value = if name == "line" { line } else { column };
+                format!("{}u32", value)
+            }
+            "file" => format!("{:?}", file.path.display().to_string()),
+            "stringify" => {
+                let text = region.trees(args).split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!("{:?}", text)
+            }
+            "concat" => {
+                let mut string = String::new();
+                for arg in split(args) {
+                    if !arg.is_empty() {
+                        string.push_str(&self.literal(region, arg)?);
+                    }
+                }
+                format!("{:?}", string)
+            }
+            "include_str" => {
+                let arg = match split(args).as_slice() {
+                    [arg] | [arg, []] if !arg.is_empty() => *arg,
+                    _ => return Err(Diagnostic::new(close,
+                        "`include_str!` takes 1 argument")),
+                };
+                let dir = file.path.parent()
+                    .map(FilePath::to_path_buf)
+                    .unwrap_or_default();
+                let path = dir.join(self.literal(region, arg)?);
+                match (self.read)(&path) {
+                    Ok(text) => format!("{:?}", text),
+                    Err(e) => return Err(Diagnostic::new(span(arg), format!(
+                        "couldn't read `{}`: {}", path.display(), e))),
+                }
+            }
+            "cfg" => match split(args).as_slice() {
+                [predicate] | [predicate, []] if !predicate.is_empty() => {
+                    self.cfg.eval(predicate, close)?.to_string()
+                }
+                _ => return Err(Diagnostic::new(close,
+                    "`cfg!` takes 1 argument")),
+            },
+            _ => unreachable!(),
+        })
+    }
+
+    // The value of a literal, or of a call to a built-in macro, as an
+    // argument of `concat!` or `include_str!`.
+    fn literal(&mut self, region: &Region, arg: &[TokenTree]) -> Result<String>
+    {
+        let expected = || Err(Diagnostic::new(span(arg), "expected a literal"));
+        let (negative, arg) = match arg {
+            [TokenTree::Token(Token::Operator(Operator::Minus), _),
+                rest @ ..] => (true, rest),
+            _ => (false, arg),
+        };
+        match arg {
+            [TokenTree::Token(token, _)] => value(token, negative, span(arg)),
+            [.., TokenTree::Token(Token::Operator(Operator::Not), _),
+                TokenTree::Group(_, args, group)] if !negative =>
+            {
+                let builtin = call_path(arg, arg.len() - 2)
+                    .filter(|(start, _)| *start == 0)
+                    .and_then(|(_, path)| match self.callee(&path) {
+                        Some(Callee::Builtin(name)) => Some(name),
+                        _ => None,
+                    });
+                let name = match builtin {
+                    Some(name) => name,
+                    None => return expected(),
+                };
+                let close = Span::new(group.end - 1, group.end);
+                let text = self.builtin(name, region, span(arg), args, close)?;
+                match Parser::new(&text).token_trees().as_deref() {
+                    Ok([TokenTree::Token(token, _)]) => {
+                        value(token, false, span(arg))
+                    }
+                    _ => expected(),
+                }
+            }
+            _ => expected(),
+        }
+    }
+}
+
+// The value of a literal token as a string, negated if `negative`.
+fn value(token: &Token, negative: bool, span: Span) -> Result<String> {
+    let sign = if negative { "-" } else { "" };
+    Ok(match *token {
+        Token::String(ref string) if !negative => string.clone(),
+        Token::Char(ch) if !negative => ch.to_string(),
+        Token::Keyword(Keyword::True) if !negative => "true".to_string(),
+        Token::Keyword(Keyword::False) if !negative => "false".to_string(),
+        Token::Int(value, _) => format!("{}{}", sign, value),
+        Token::Float(value, _) => format!("{}{}", sign, value),
+        Token::ByteString(_) | Token::Byte(_) | Token::CString(_) => {
+            return Err(Diagnostic::new(span,
+                "cannot concatenate a byte string literal"));
+        }
+        _ => return Err(Diagnostic::new(span, "expected a literal")),
+    })
+}
+
+// The `#[macro_export]` definitions in some token trees.
+fn exports(
+    file: FileId,
+    trees: &[TokenTree],
+    exported: &mut HashMap<String, MacroDef>,
+) {
+    for (i, tree) in trees.iter().enumerate() {
+        match (tree, trees.get(i + 1), trees.get(i + 2), trees.get(i + 3)) {
+            (
+                TokenTree::Token(Token::Identifier("macro_rules"), _),
+                Some(TokenTree::Token(Token::Operator(Operator::Not), _)),
+                Some(TokenTree::Token(Token::Identifier(name), _)),
+                Some(TokenTree::Group(_, _, span)),
+            ) if has_attr(trees, i, "macro_export") => {
+                exported.insert(name.to_string(), MacroDef {
+                    name: name.to_string(),
+                    file,
+                    rules: *span,
+                });
+            }
+            (TokenTree::Group(_, inner, _), ..) => {
+                exports(file, inner, exported);
+            }
+            _ => {}
+        }
+    }
+}
+
+// Index of the first token of the item whose keyword is at `trees[i]`,
+// including its visibility but not its attributes.
+fn item_start(trees: &[TokenTree], i: usize) -> usize {
+    let is_pub = |j: usize| {
+        matches!(trees[j], TokenTree::Token(Token::Keyword(Keyword::Pub), _))
+    };
+    if i >= 1 && is_pub(i - 1) {
+        i - 1
+    } else if i >= 2 && is_pub(i - 2)
+        && matches!(trees[i - 1], TokenTree::Group(Bracket::ParensL, ..))
+    {
+        i - 2
+    } else {
+        i
+    }
+}
+
+// Whether the item starting at `trees[start]` has the attribute `name`.
+fn has_attr(trees: &[TokenTree], start: usize, name: &str) -> bool {
+    let mut i = start;
+    while i >= 2 {
+        match (&trees[i - 2], &trees[i - 1]) {
+            (
+                TokenTree::Token(Token::Operator(Operator::Pound), _),
+                TokenTree::Group(Bracket::SquareL, attr, _),
+            ) => {
+                if let Some(TokenTree::Token(Token::Identifier(found), _)) =
+                    attr.first()
+                {
+                    if *found == name {
+                        return true;
+                    }
+                }
+                i -= 2;
+            }
+            _ => break,
+        }
+    }
+    false
+}
+
+// The path of a macro call whose `!` is at `trees[bang]`, with the index of
+// its first token.
+fn call_path<'t>(trees: &[TokenTree<'t>], bang: usize)
+    -> Option<(usize, Vec<&'t str>)>
+{
+    let segment = |tree: &TokenTree<'t>| match *tree {
+        TokenTree::Token(Token::Identifier(name), _) => Some(name),
+        TokenTree::Token(Token::Keyword(keyword @ Keyword::Crate), _)
+        | TokenTree::Token(Token::Keyword(keyword @ Keyword::SelfValue), _)
+        | TokenTree::Token(Token::Keyword(keyword @ Keyword::Super), _) => {
+            Some(keyword.as_str())
+        }
+        _ => None,
+    };
+    let is_sep = |i: usize| matches!(trees[i],
+        TokenTree::Token(Token::Operator(Operator::PathSep), _));
+
+    let mut start = bang.checked_sub(1)?;
+    let mut path = vec![segment(&trees[start])?];
+    while start >= 2 && is_sep(start - 1) {
+        match segment(&trees[start - 2]) {
+            Some(name) => {
+                path.insert(0, name);
+                start -= 2;
+            }
+            None => break,
+        }
+    }
+    if start >= 1 && is_sep(start - 1) {
+        // `::std::concat!`
+        start -= 1;
+    }
+    if path.len() == 1 && path[0] == "macro_rules" {
+        return None;
+    }
+    Some((start, path))
+}
+
+// Split macro arguments at commas.
+fn split<'s, 't>(args: &'s [TokenTree<'t>]) -> Vec<&'s [TokenTree<'t>]> {
+    if args.is_empty() {
+        return Vec::new();
+    }
+    args.split(|tree| matches!(tree,
+        TokenTree::Token(Token::Operator(Operator::Comma), _)))
+        .collect()
+}
+
+// Check that the format string of a standard library macro is a literal.
+fn format_string(name: &str, args: &[TokenTree]) -> Result<()> {
+    let skip = match FORMAT_MACROS.iter().find(|(found, _)| *found == name) {
+        Some((_, skip)) => *skip,
+        None => return Ok(()),
+    };
+    let format = match split(args).get(skip) {
+        Some(format) if !format.is_empty() => *format,
+        _ => return Ok(()),
+    };
+    match format {
+        [TokenTree::Token(Token::String(_), _)]
+        | [.., TokenTree::Token(Token::Operator(Operator::Not), _),
+            TokenTree::Group(..)] => Ok(()),
+        _ => Err(Diagnostic::new(span(format),
+            "format argument must be a string literal")),
+    }
+}
+
+// The span from the first to the last of some token trees.
+fn span(trees: &[TokenTree]) -> Span {
+    match (trees.first(), trees.last()) {
+        (Some(first), Some(last)) => first.span().to(last.span()),
+        _ => Span::default(),
+    }
+}
+
+// Parse the rules of a `macro_rules!` definition.
+fn rules<'d>(trees: &[TokenTree<'d>]) -> Result<Vec<Rule<'d>>> {
+    let mut rules = Vec::new();
+    let mut iter = trees.iter();
+    while let Some(tree) = iter.next() {
+        let matcher = match tree {
+            TokenTree::Group(_, inner, _) => matchers(inner)?,
+            _ => return Err(Diagnostic::new(tree.span(),
+                "expected a macro matcher in brackets")),
+        };
+        match iter.next() {
+            Some(TokenTree::Token(Token::Operator(Operator::FatArrow), _)) => {}
+            Some(tree) => {
+                return Err(Diagnostic::new(tree.span(), "expected `=>`"));
+            }
+            None => {
+                return Err(Diagnostic::new(tree.span(), "expected `=>`"));
+            }
+        }
+        let body = match iter.next() {
+            Some(TokenTree::Group(_, inner, _)) => inner.clone(),
+            Some(tree) => return Err(Diagnostic::new(tree.span(),
+                "expected a macro body in brackets")),
+            None => return Err(Diagnostic::new(tree.span(),
+                "expected a macro body in brackets")),
+        };
+        rules.push(Rule { matcher, body });
+        match iter.next() {
+            Some(TokenTree::Token(Token::Operator(Operator::Semi), _))
+            | None => {}
+            Some(tree) => {
+                return Err(Diagnostic::new(tree.span(), "expected `;`"));
+            }
+        }
+    }
+    Ok(rules)
+}
+
+// Parse the matchers of a rule.
+fn matchers<'d>(trees: &[TokenTree<'d>]) -> Result<Vec<Matcher<'d>>> {
+    let mut matchers = Vec::new();
+    let mut iter = trees.iter().peekable();
+    while let Some(tree) = iter.next() {
+        let (token, span) = match tree {
+            TokenTree::Group(bracket, inner, _) => {
+                matchers.push(Matcher::Group(*bracket, self::matchers(inner)?));
+                continue;
+            }
+            TokenTree::Token(token, span) => (token, *span),
+        };
+        if *token != Token::Operator(Operator::Dollar) {
+            matchers.push(Matcher::Token(token.clone()));
+            continue;
+        }
+        match iter.next() {
+            Some(TokenTree::Group(Bracket::ParensL, inner, span)) => {
+                let inner = self::matchers(inner)?;
+                let (separator, kleene) = repeat_op(&mut iter, *span)?;
+                matchers.push(Matcher::Repeat(inner, separator, kleene));
+            }
+            Some(tree) => {
+                let name = var_name(tree)
+                    .ok_or_else(|| Diagnostic::new(tree.span(),
+                        "expected identifier or `(` after `$`"))?;
+                match iter.next() {
+                    Some(TokenTree::Token(Token::Operator(Operator::Colon),
+                        _)) => {}
+                    _ => return Err(Diagnostic::new(tree.span(), format!(
+                        "missing fragment specifier for `${}`", name))),
+                }
+                let fragment = match iter.next() {
+                    Some(TokenTree::Token(Token::Identifier(kind), span)) => {
+                        FRAGMENTS.iter()
+                            .find(|(found, _)| found == kind)
+                            .map(|(_, fragment)| *fragment)
+                            .ok_or_else(|| Diagnostic::new(*span, format!(
+                                "invalid fragment specifier `{}`", kind)))?
+                    }
+                    _ => return Err(Diagnostic::new(tree.span(), format!(
+                        "missing fragment specifier for `${}`", name))),
+                };
+                matchers.push(Matcher::Var(name, fragment));
+            }
+            None => {
+                return Err(Diagnostic::new(span,
+                    "expected identifier or `(` after `$`"));
+            }
+        }
+    }
+    Ok(matchers)
+}
+
+// The name of a macro variable after its `$`.
+fn var_name<'d>(tree: &TokenTree<'d>) -> Option<&'d str> {
+    match *tree {
+        TokenTree::Token(Token::Identifier(name), _) => Some(name),
+        TokenTree::Token(Token::Keyword(keyword), _)
+            if keyword != Keyword::Underscore =>
+        {
+            Some(keyword.as_str())
+        }
+        _ => None,
+    }
+}
+
+// Parse the optional separator and the Kleene operator of a repetition.
+fn repeat_op<'s, 'd: 's, I>(
+    iter: &mut std::iter::Peekable<I>,
+    span: Span,
+) -> Result<(Option<Token<'d>>, Kleene)>
+where
+    I: Iterator<Item = &'s TokenTree<'d>>,
+{
+    let kleene = |tree: Option<&TokenTree>| match tree {
+        Some(TokenTree::Token(Token::Operator(Operator::Star), _)) => {
+            Some(Kleene::Many)
+        }
+        Some(TokenTree::Token(Token::Operator(Operator::Plus), _)) => {
+            Some(Kleene::AtLeastOne)
+        }
+        Some(TokenTree::Token(Token::Operator(Operator::Question), _)) => {
+            Some(Kleene::AtMostOne)
+        }
+        _ => None,
+    };
+    let missing = || Diagnostic::new(span,
+        "expected one of: `*`, `+`, or `?` after repetition");
+    if let Some(op) = kleene(iter.peek().copied()) {
+        iter.next();
+        return Ok((None, op));
+    }
+    let separator = match iter.next() {
+        Some(TokenTree::Token(token, _)) => token.clone(),
+        _ => return Err(missing()),
+    };
+    match kleene(iter.next()) {
+        Some(Kleene::AtMostOne) => Err(Diagnostic::new(span,
+            "the `?` macro repetition operator does not take a separator")),
+        Some(op) => Ok((Some(separator), op)),
+        None => Err(missing()),
+    }
+}
+
+// The names of the variables in some matchers, including repetitions.
+fn vars<'d>(matchers: &[Matcher<'d>], names: &mut Vec<&'d str>) {
+    for matcher in matchers {
+        match matcher {
+            Matcher::Var(name, _) if !names.contains(name) => names.push(name),
+            Matcher::Group(_, inner) | Matcher::Repeat(inner, ..) => {
+                vars(inner, names);
+            }
+            _ => {}
+        }
+    }
+}
+
+// The local variables a macro body declares with `let` or `for`.
+fn bindings<'d>(body: &[TokenTree<'d>], names: &mut Vec<&'d str>) {
+    for (i, tree) in body.iter().enumerate() {
+        match tree {
+            TokenTree::Token(Token::Keyword(Keyword::Let), _)
+            | TokenTree::Token(Token::Keyword(Keyword::For), _) => {
+                let name = body[i + 1..].iter()
+                    .find(|tree| !matches!(tree,
+                        TokenTree::Token(Token::Keyword(Keyword::Mut), _)
+                        | TokenTree::Token(Token::Keyword(Keyword::Ref), _)));
+                let after = body[i + 1..].iter()
+                    .skip_while(|tree| !matches!(tree,
+                        TokenTree::Token(Token::Identifier(_), _)))
+                    .nth(1);
+                let pattern = matches!(after,
+                    Some(TokenTree::Group(..))
+                    | Some(TokenTree::Token(Token::Operator(Operator::PathSep),
+                        _)));
+                if let Some(TokenTree::Token(Token::Identifier(name), _)) = name
+                {
+                    if !pattern && !names.contains(name) {
+                        names.push(name);
+                    }
+                }
+            }
+            TokenTree::Group(_, inner, _) => bindings(inner, names),
+            _ => {}
+        }
+    }
+}
+
+// Number of token trees in `trees` that make up their first `tokens` tokens,
+// counting brackets as tokens.
+fn trees_in(trees: &[TokenTree], tokens: usize) -> Option<usize> {
+    fn width(tree: &TokenTree) -> usize {
+        match tree {
+            TokenTree::Token(..) => 1,
+            TokenTree::Group(_, inner, _) => {
+                2 + inner.iter().map(width).sum::<usize>()
+            }
+        }
+    }
+
+    let mut count = 0;
+    for (i, tree) in trees.iter().enumerate() {
+        if count == tokens {
+            return Some(i);
+        }
+        count += width(tree);
+    }
+    (count == tokens).then_some(trees.len())
+}
+
+// The token at `span` in some token trees, for error messages.
+fn token_at(trees: &[TokenTree], span: Span) -> Option<String> {
+    trees.iter().find_map(|tree| match tree {
+        TokenTree::Token(token, found) if *found == span => {
+            Some(token.to_string())
+        }
+        TokenTree::Group(bracket, _, found) if found.start == span.start => {
+            Some(bracket.as_str().to_string())
+        }
+        TokenTree::Group(_, inner, _) => token_at(inner, span),
+        _ => None,
+    })
+}
+
+// Matching of macro arguments against a rule.
+struct Match<'c> {
+    // Text of the region of the call.
+    text: &'c str,
+    // The furthest token that no matcher accepted.
+    furthest: Option<Span>,
+}
+
+impl<'c> Match<'c> {
+    fn fail<T>(&mut self, span: Span) -> Option<T> {
+        if self.furthest.is_none_or(|furthest| span.start > furthest.start) {
+            self.furthest = Some(span);
+        }
+        None
+    }
+
+    // Match a prefix of `input` and return its length.  `end` is the span of
+    // the bracket after the input.
+    fn prefix<'d>(
+        &mut self,
+        matchers: &[Matcher<'d>],
+        input: &[TokenTree<'c>],
+        end: Span,
+        captures: &mut Captures<'d, 'c>,
+    ) -> Option<usize> {
+        let mut pos = 0;
+        let span = |pos: usize| input.get(pos).map_or(end, TokenTree::span);
+        for matcher in matchers {
+            match matcher {
+                Matcher::Token(token) => match input.get(pos) {
+                    Some(TokenTree::Token(found, _)) if found == token => {
+                        pos += 1;
+                    }
+                    _ => return self.fail(span(pos)),
+                },
+                Matcher::Group(bracket, inner) => match input.get(pos) {
+                    Some(TokenTree::Group(found, trees, group))
+                        if found == bracket =>
+                    {
+                        let close = Span::new(group.end - 1, group.end);
+                        let len = self.prefix(inner, trees, close, captures)?;
+                        if len != trees.len() {
+                            return self.fail(trees[len].span());
+                        }
+                        pos += 1;
+                    }
+                    _ => return self.fail(span(pos)),
+                },
+                Matcher::Var(name, fragment) => {
+                    let len = match self.fragment(*fragment, &input[pos..], end)
+                    {
+                        Some(len) => len,
+                        None => return self.fail(span(pos)),
+                    };
+                    let trees = input[pos..pos + len].to_vec();
+                    captures.insert(name, Capture::One(*fragment, trees));
+                    pos += len;
+                }
+                Matcher::Repeat(inner, separator, kleene) => {
+                    let mut repeats = Vec::new();
+                    while *kleene != Kleene::AtMostOne || repeats.is_empty() {
+                        let mut at = pos;
+                        if let (Some(separator), false) =
+                            (separator, repeats.is_empty())
+                        {
+                            match input.get(at) {
+                                Some(TokenTree::Token(found, _))
+                                    if found == separator => at += 1,
+                                _ => {
+                                    self.fail::<()>(span(at));
+                                    break;
+                                }
+                            }
+                        }
+                        let mut repeat = Captures::new();
+                        let len = match self.prefix(inner, &input[at..], end,
+                            &mut repeat)
+                        {
+                            Some(len) => len,
+                            None => break,
+                        };
+                        repeats.push(repeat);
+                        pos = at + len;
+                        if len == 0 {
+                            break;
+                        }
+                    }
+                    if *kleene == Kleene::AtLeastOne && repeats.is_empty() {
+                        return None;
+                    }
+                    let mut names = Vec::new();
+                    vars(inner, &mut names);
+                    for name in names {
+                        let each = repeats.iter_mut()
+                            .filter_map(|repeat| repeat.remove(name))
+                            .collect();
+                        captures.insert(name, Capture::Many(each));
+                    }
+                }
+            }
+        }
+        Some(pos)
+    }
+
+    // Match a fragment at the start of `input` and return its length.
+    fn fragment(&mut self, fragment: Fragment, input: &[TokenTree<'c>],
+        end: Span) -> Option<usize>
+    {
+        let literal = |token: &Token| matches!(token,
+            Token::Char(_) | Token::Byte(_) | Token::String(_)
+            | Token::ByteString(_) | Token::CString(_) | Token::Int(..)
+            | Token::Float(..) | Token::Keyword(Keyword::True)
+            | Token::Keyword(Keyword::False));
+        let first = match input.first() {
+            Some(TokenTree::Token(token, _)) => Some(token),
+            _ => None,
+        };
+        match fragment {
+            Fragment::Tt => return input.first().map(|_| 1),
+            Fragment::Ident => {
+                return match first {
+                    Some(Token::Identifier(_)) => Some(1),
+                    Some(Token::Keyword(keyword)) => {
+                        (*keyword != Keyword::Underscore).then_some(1)
+                    }
+                    _ => None,
+                };
+            }
+            Fragment::Lifetime => {
+                return matches!(first, Some(Token::Lifetime(_))).then_some(1);
+            }
+            Fragment::Literal => {
+                return match input {
+                    [TokenTree::Token(Token::Operator(Operator::Minus), _),
+                        TokenTree::Token(Token::Int(..) | Token::Float(..), _),
+                        ..] => Some(2),
+                    [TokenTree::Token(token, _), ..] if literal(token) => {
+                        Some(1)
+                    }
+                    _ => None,
+                };
+            }
+            _ => {}
+        }
+
+        let mut parser = Parser::from_tokens(self.text, input, end);
+        let parsed = match fragment {
+            Fragment::Block => {
+                matches!(input.first(), Some(TokenTree::Group(Bracket::BraceL,
+                    ..))) && parser.block().is_ok()
+            }
+            Fragment::Expr => parser.expr().is_ok(),
+            Fragment::Item => matches!(parser.item(), Ok(Some(_))),
+            Fragment::Meta => {
+                parser.path(PathStyle::Mod).is_ok() && {
+                    if parser.is_bracket(Bracket::ParensL)
+                        || parser.is_bracket(Bracket::SquareL)
+                        || parser.is_bracket(Bracket::BraceL)
+                    {
+                        parser.token_tree().is_ok()
+                    } else if parser.eat_op(Operator::Eq) {
+                        parser.expr().is_ok()
+                    } else {
+                        true
+                    }
+                }
+            }
+            Fragment::Pat => parser.pattern().is_ok(),
+            Fragment::PatParam => parser.pattern_no_alt().is_ok(),
+            Fragment::Path => parser.path(PathStyle::Type).is_ok(),
+            Fragment::Stmt => parser.stmt().is_ok(),
+            Fragment::Ty => parser.ty().is_ok(),
+            Fragment::Vis => parser.visibility().is_ok(),
+            Fragment::Tt | Fragment::Ident | Fragment::Lifetime
+            | Fragment::Literal => unreachable!(),
+        };
+        if !parsed {
+            return None;
+        }
+        trees_in(input, parser.pos())
+    }
+}
+
+// Writing of the body of a rule with the captured input.
+struct Transcriber<'r, 'd> {
+    def: &'r Region,
+    call: &'r Region,
+    // New names of the local variables the body declares.
+    renames: HashMap<&'d str, String>,
+}
+
+impl<'d> Transcriber<'_, 'd> {
+    fn transcribe(
+        &self,
+        body: &[TokenTree<'d>],
+        captures: &Captures<'d, '_>,
+        out: &mut String,
+    ) -> Result<()> {
+        let mut i = 0;
+        while i < body.len() {
+            let (token, span) = match body[i] {
+                TokenTree::Group(bracket, ref inner, _) => {
+                    let close = match bracket {
+                        Bracket::ParensL => Bracket::ParensR,
+                        Bracket::BraceL => Bracket::BraceR,
+                        _ => Bracket::SquareR,
+                    };
+                    out.push_str(bracket.as_str());
+                    self.transcribe(inner, captures, out)?;
+                    out.push_str(close.as_str());
+                    out.push(' ');
+                    i += 1;
+                    continue;
+                }
+                TokenTree::Token(ref token, span) => (token, span),
+            };
+            i += 1;
+            if *token != Token::Operator(Operator::Dollar) {
+                self.token(body, i - 1, token, span, out);
+                continue;
+            }
+            match body.get(i) {
+                Some(TokenTree::Token(Token::Keyword(Keyword::Crate), _)) => {
+                    out.push_str("crate ");
+                    i += 1;
+                }
+                Some(TokenTree::Group(Bracket::ParensL, inner, group)) => {
+                    let mut rest = body[i + 1..].iter().peekable();
+                    let (separator, _) = repeat_op(&mut rest, *group)?;
+                    i += if separator.is_some() { 3 } else { 2 };
+                    self.repeat(inner, separator, *group, captures, out)?;
+                }
+                Some(tree) => {
+                    let name = var_name(tree).unwrap_or_default();
+                    match captures.get(name) {
+                        Some(Capture::One(fragment, trees)) => {
+                            self.fragment(*fragment, trees, out);
+                            i += 1;
+                        }
+                        Some(Capture::Many(_)) => {
+                            return Err(Diagnostic::new(tree.span(), format!(
+                                "variable `{}` is still repeating at this \
+                                    depth", name)));
+                        }
+                        None => self.token(body, i - 1, token, span, out),
+                    }
+                }
+                None => self.token(body, i - 1, token, span, out),
+            }
+        }
+        Ok(())
+    }
+
+    // Write a repetition once for each capture of the repeating variables
+    // it uses.
+    fn repeat(
+        &self,
+        body: &[TokenTree<'d>],
+        separator: Option<Token<'d>>,
+        span: Span,
+        captures: &Captures<'d, '_>,
+        out: &mut String,
+    ) -> Result<()> {
+        let mut names = Vec::new();
+        used(body, &mut names);
+        names.retain(|name| matches!(captures.get(name),
+            Some(Capture::Many(_))));
+        let mut count = None;
+        for name in &names {
+            let len = match captures.get(name) {
+                Some(Capture::Many(each)) => each.len(),
+                _ => continue,
+            };
+            match count {
+                Some((other, count)) if count != len => {
+                    return Err(Diagnostic::new(span, format!(
+                        "meta-variable `{}` repeats {} times, but `{}` repeats \
+                            {} times", other, count, name, len)));
+                }
+                _ => count = Some((name, len)),
+            }
+        }
+        let count = match count {
+            Some((_, count)) => count,
+            None => return Err(Diagnostic::new(span, "attempted to repeat an \
+                expression containing no syntax variables matched as \
+                repeating at this depth")),
+        };
+        for i in 0..count {
+            if let (Some(separator), true) = (&separator, i != 0) {
+                out.push_str(&separator.to_string());
+                out.push(' ');
+            }
+            let mut each = captures.clone();
+            for name in &names {
+                if let Some(Capture::Many(repeats)) = captures.get(name) {
+                    each.insert(name, repeats[i].clone());
+                }
+            }
+            self.transcribe(body, &each, out)?;
+        }
+        Ok(())
+    }
+
+    // Write the captured input of a variable.
+    fn fragment(&self, fragment: Fragment, trees: &[TokenTree], out: &mut String) {
+        let text = self.call.trees(trees);
+        // Expressions are a single operand wherever they are used.
+        if fragment == Fragment::Expr && trees.len() > 1 {
+            out.push('(');
+            out.push_str(text);
+            out.push(')');
+        } else {
+            out.push_str(text);
+        }
+        out.push(if text.contains("//") { '\n' } else { ' ' });
+    }
+
+    // Write a token of the body, renaming the local variables it declares.
+    fn token(
+        &self,
+        body: &[TokenTree<'d>],
+        i: usize,
+        token: &Token<'d>,
+        span: Span,
+        out: &mut String,
+    ) {
+        let is_op = |tree: Option<&TokenTree>, op: Operator| matches!(tree,
+            Some(TokenTree::Token(Token::Operator(found), _)) if *found == op);
+        let text = self.def.token(token, span);
+        match token {
+            Token::Identifier(name) => {
+                let before = i.checked_sub(1).and_then(|i| body.get(i));
+                let after = body.get(i + 1);
+                let local = !is_op(before, Operator::Dot)
+                    && !is_op(before, Operator::PathSep)
+                    && !is_op(after, Operator::PathSep)
+                    && !is_op(after, Operator::Not);
+                match self.renames.get(name) {
+                    Some(rename) if local => out.push_str(rename),
+                    _ => out.push_str(&text),
+                }
+            }
+            // Implicit format arguments name the renamed variables too.
+            Token::String(_) if !self.renames.is_empty() => {
+                let mut text = text;
+                for (name, rename) in &self.renames {
+                    for close in ["}", ":"] {
+                        text = text.replace(&format!("{{{}{}", name, close),
+                            &format!("{{{}{}", rename, close));
+                    }
+                }
+                out.push_str(&text);
+            }
+            _ => out.push_str(&text),
+        }
+        out.push(' ');
+    }
+}
+
+// The names of the variables a transcriber body uses.
+fn used<'d>(body: &[TokenTree<'d>], names: &mut Vec<&'d str>) {
+    for (i, tree) in body.iter().enumerate() {
+        match tree {
+            TokenTree::Token(Token::Operator(Operator::Dollar), _) => {
+                if let Some(name) = body.get(i + 1).and_then(var_name) {
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+            }
+            TokenTree::Group(_, inner, _) => used(inner, names),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,498 @@
+// Rust built-in derives
+//
+//! The impls written for `#[derive(...)]` of the standard library traits.
+//! Like rustc's, they bound each type parameter of the item by the derived
+//! trait and work field by field.
+
+use super::super::{
+    Attribute, Bound, Bracket, Fields, GenericParam, Generics, Item, ItemKind,
+    Operator, Result, Token, TokenTree, Variant,
+};
+use crate::{Diagnostic, Span};
+
+// Derivable traits, with their paths.
+const TRAITS: &[(&str, &str)] = &[
+    ("Clone", "::core::clone::Clone"),
+    ("Copy", "::core::marker::Copy"),
+    ("Debug", "::core::fmt::Debug"),
+    ("Default", "::core::default::Default"),
+    ("Eq", "::core::cmp::Eq"),
+    ("Hash", "::core::hash::Hash"),
+    ("Ord", "::core::cmp::Ord"),
+    ("PartialEq", "::core::cmp::PartialEq"),
+    ("PartialOrd", "::core::cmp::PartialOrd"),
+];
+
+// The shape of the item a derive is for.
+enum Shape<'i, 'a> {
+    Struct(&'i Fields<'a>),
+    Enum(&'i [Variant<'a>]),
+    Union,
+}
+
+// Writer of the impls for an item, whose spans are in `text`.
+struct Deriver<'i, 'a> {
+    text: &'a str,
+    name: &'a str,
+    generics: &'i Generics<'a>,
+    shape: Shape<'i, 'a>,
+}
+
+/// The impls for the `#[derive]` attributes of an item, or `None` if it has
+/// none.
+pub(super) fn derive(item: &Item, text: &str) -> Result<Option<String>> {
+    let attrs = item.attrs.iter()
+        .filter(|attr| attr.is("derive"))
+        .collect::<Vec<_>>();
+    if attrs.is_empty() {
+        return Ok(None);
+    }
+    let (name, generics, shape) = match item.kind {
+        ItemKind::Struct(name, ref generics, ref fields) => {
+            (name, generics, Shape::Struct(fields))
+        }
+        ItemKind::Enum(name, ref generics, ref variants) => {
+            (name, generics, Shape::Enum(variants))
+        }
+        ItemKind::Union(name, ref generics, _) => {
+            (name, generics, Shape::Union)
+        }
+        _ => return Err(Diagnostic::new(attrs[0].span,
+            "`derive` may only be applied to `struct`s, `enum`s and `union`s")),
+    };
+    let deriver = Deriver { text, name, generics, shape };
+    let mut out = String::new();
+    for attr in attrs {
+        for (name, span) in traits(attr)? {
+            out.push_str(&deriver.derive(name, span)?);
+        }
+    }
+    Ok(Some(out))
+}
+
+// The names of the traits in a `#[derive]` attribute, with their spans.
+fn traits<'a>(attr: &Attribute<'a>) -> Result<Vec<(&'a str, Span)>> {
+    let args = match attr.tokens.as_slice() {
+        [TokenTree::Group(Bracket::ParensL, args, _)] => args,
+        _ => return Err(Diagnostic::new(attr.span,
+            "expected `#[derive(Trait, ...)]`")),
+    };
+    let mut traits = Vec::new();
+    for path in args.split(|tree| matches!(tree,
+        TokenTree::Token(Token::Operator(Operator::Comma), _)))
+    {
+        match path.last() {
+            Some(TokenTree::Token(Token::Identifier(name), _)) => {
+                let span = path[0].span().to(path[path.len() - 1].span());
+                traits.push((*name, span));
+            }
+            Some(tree) => {
+                return Err(Diagnostic::new(tree.span(), "expected a path"));
+            }
+            None => {}
+        }
+    }
+    Ok(traits)
+}
+
+impl Deriver<'_, '_> {
+    fn derive(&self, name: &str, span: Span) -> Result<String> {
+        let path = match TRAITS.iter().find(|(found, _)| *found == name) {
+            Some((_, path)) => *path,
+            None => return Err(Diagnostic::new(span, format!(
+                "cannot find derive macro `{}` in this scope", name))),
+        };
+        if matches!(self.shape, Shape::Union)
+            && !matches!(name, "Clone" | "Copy")
+        {
+            return Err(Diagnostic::new(span, format!(
+                "`{}` cannot be derived for unions", name)));
+        }
+        let body = match name {
+            "Clone" => self.clone(),
+            "Debug" => self.debug(),
+            "Default" => self.default(span)?,
+            "Hash" => self.hash(),
+            "PartialEq" => self.eq(),
+            "PartialOrd" => self.cmp(true),
+            "Ord" => self.cmp(false),
+            _ => String::new(),
+        };
+        Ok(format!("{} {{\n{}}}\n", self.header(path), body))
+    }
+
+    // `impl<T: Trait> Trait for Name<T> where ...`
+    fn header(&self, path: &str) -> String {
+        let mut params = Vec::new();
+        let mut args = Vec::new();
+        for param in &self.generics.params {
+            match *param {
+                GenericParam::Type { name, ref bounds, .. } => {
+                    let mut bounds = bounds.iter()
+                        .map(|bound| self.bound(bound))
+                        .collect::<Vec<_>>();
+                    bounds.push(path.to_string());
+                    params.push(format!("{}: {}", name, bounds.join(" + ")));
+                    args.push(name.to_string());
+                }
+                GenericParam::Const { name, ref ty, .. } => {
+                    params.push(format!("const {}: {}", name,
+                        self.source(ty.span)));
+                    args.push(name.to_string());
+                }
+            }
+        }
+        let predicates = self.generics.predicates.iter()
+            .filter(|predicate| !predicate.bounds.is_empty())
+            .map(|predicate| {
+                let bounds = predicate.bounds.iter()
+                    .map(|bound| self.bound(bound))
+                    .collect::<Vec<_>>();
+                format!("{}: {}", self.source(predicate.ty.span),
+                    bounds.join(" + "))
+            })
+            .collect::<Vec<_>>();
+
+        let mut header = String::from("impl");
+        if !params.is_empty() {
+            header.push_str(&format!("<{}>", params.join(", ")));
+        }
+        header.push_str(&format!(" {} for {}", path, self.name));
+        if !args.is_empty() {
+            header.push_str(&format!("<{}>", args.join(", ")));
+        }
+        if !predicates.is_empty() {
+            header.push_str(&format!(" where {}", predicates.join(", ")));
+        }
+        header
+    }
+
+    fn source(&self, span: Span) -> &str {
+        &self.text[span.start..span.end]
+    }
+
+    fn bound(&self, bound: &Bound) -> String {
+        let maybe = if bound.maybe { "?" } else { "" };
+        format!("{}{}", maybe, self.source(bound.path.span))
+    }
+
+    fn clone(&self) -> String {
+        let clone = |value: &str| {
+            format!("::core::clone::Clone::clone({})", value)
+        };
+        let body = match self.shape {
+            Shape::Struct(fields) => {
+                let values = self.fields(fields, |field| {
+                    clone(&format!("&self.{}", field))
+                });
+                construct(self.name, fields, &values)
+            }
+            Shape::Enum(variants) => self.each_variant(variants, |variant| {
+                let path = format!("{}::{}", self.name, variant.name);
+                let values = bindings("__self", &variant.fields).iter()
+                    .map(|binding| clone(binding))
+                    .collect::<Vec<_>>();
+                construct(&path, &variant.fields, &values)
+            }),
+            Shape::Union => "*self".to_string(),
+        };
+        format!("    fn clone(&self) -> Self {{\n        {}\n    }}\n", body)
+    }
+
+    fn debug(&self) -> String {
+        let debug = |name: &str, fields: &Fields, values: &[String]| {
+            let mut out = match fields {
+                Fields::Unit => return format!("f.write_str({:?})", name),
+                Fields::Tuple(_) => format!("f.debug_tuple({:?})", name),
+                Fields::Named(_) => format!("f.debug_struct({:?})", name),
+            };
+            for (field, value) in field_names(fields).iter().zip(values) {
+                match fields {
+                    Fields::Named(_) => out.push_str(&format!(
+                        ".field({:?}, {})", field, value)),
+                    _ => out.push_str(&format!(".field({})", value)),
+                }
+            }
+            out.push_str(".finish()");
+            out
+        };
+        let body = match self.shape {
+            Shape::Struct(fields) => {
+                let values = self.fields(fields, |field| {
+                    format!("&self.{}", field)
+                });
+                debug(self.name, fields, &values)
+            }
+            Shape::Enum(variants) => self.each_variant(variants, |variant| {
+                let values = bindings("__self", &variant.fields);
+                debug(variant.name, &variant.fields, &values)
+            }),
+            Shape::Union => unreachable!(),
+        };
+        format!("    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) \
+            -> ::core::fmt::Result {{\n        {}\n    }}\n", body)
+    }
+
+    fn default(&self, span: Span) -> Result<String> {
+        let value = "::core::default::Default::default()";
+        let body = match self.shape {
+            Shape::Struct(fields) => {
+                let values = self.fields(fields, |_| value.to_string());
+                construct(self.name, fields, &values)
+            }
+            Shape::Enum(variants) => {
+                let default = variants.iter()
+                    .filter(|variant| variant.attrs.iter()
+                        .any(|attr| attr.is("default")))
+                    .collect::<Vec<_>>();
+                match default.as_slice() {
+                    [variant] if variant.fields == Fields::Unit => {
+                        format!("{}::{}", self.name, variant.name)
+                    }
+                    [variant] => return Err(Diagnostic::new(variant.span,
+                        "the `#[default]` attribute may only be used on unit \
+                            enum variants")),
+                    [] => return Err(Diagnostic::new(span,
+                        "no default declared for enum: add `#[default]` to \
+                            a unit variant")),
+                    [_, second, ..] => return Err(Diagnostic::new(
+                        second.span, "multiple declared defaults")),
+                }
+            }
+            Shape::Union => unreachable!(),
+        };
+        Ok(format!("    fn default() -> Self {{\n        {}\n    }}\n", body))
+    }
+
+    fn hash(&self) -> String {
+        let hash = |value: &str| {
+            format!("::core::hash::Hash::hash({}, state);", value)
+        };
+        let body = match self.shape {
+            Shape::Struct(fields) => {
+                self.fields(fields, |field| hash(&format!("&self.{}", field)))
+                    .join("\n        ")
+            }
+            Shape::Enum(variants) => {
+                let mut tags = self.tags(variants).into_iter();
+                self.each_variant(variants, |variant| {
+                    let mut stmts = Vec::new();
+                    let tag = tags.next().unwrap();
+                    if variants.len() > 1 {
+                        stmts.push(hash(&format!("&{}", tag)));
+                    }
+                    for binding in bindings("__self", &variant.fields) {
+                        stmts.push(hash(&binding));
+                    }
+                    format!("{{ {} }}", stmts.join(" "))
+                })
+            }
+            Shape::Union => unreachable!(),
+        };
+        format!("    fn hash<__H: ::core::hash::Hasher>(&self, state: &mut \
+            __H) {{\n        {}\n    }}\n", body)
+    }
+
+    fn eq(&self) -> String {
+        let all = |pairs: Vec<String>| {
+            if pairs.is_empty() {
+                "true".to_string()
+            } else {
+                pairs.join(" && ")
+            }
+        };
+        let body = match self.shape {
+            Shape::Struct(fields) => {
+                all(self.fields(fields, |field| {
+                    format!("self.{} == other.{}", field, field)
+                }))
+            }
+            Shape::Enum(variants) => {
+                self.each_pair(variants, "false", |variant| {
+                    let this = bindings("__self", &variant.fields);
+                    let other = bindings("__other", &variant.fields);
+                    all(this.iter().zip(&other)
+                        .map(|(a, b)| format!("{} == {}", a, b))
+                        .collect())
+                })
+            }
+            Shape::Union => unreachable!(),
+        };
+        format!("    fn eq(&self, other: &Self) -> bool {{\n        {}\n    \
+            }}\n", body)
+    }
+
+    // `partial_cmp` if `partial`, otherwise `cmp`: the first field or variant
+    // that differs decides.
+    fn cmp(&self, partial: bool) -> String {
+        let (method, equal) = if partial {
+            ("::core::cmp::PartialOrd::partial_cmp",
+                "::core::option::Option::Some(::core::cmp::Ordering::Equal)")
+        } else {
+            ("::core::cmp::Ord::cmp", "::core::cmp::Ordering::Equal")
+        };
+        let chain = |pairs: Vec<(String, String)>| {
+            let mut out = String::from("{ ");
+            for (a, b) in pairs {
+                out.push_str(&format!("match {}({}, {}) {{ {} => {{}} \
+                    ordering => return ordering, }} ", method, a, b, equal));
+            }
+            out.push_str(equal);
+            out.push_str(" }");
+            out
+        };
+        let body = match self.shape {
+            Shape::Struct(fields) => chain(self.fields(fields, |field| {
+                (format!("&self.{}", field), format!("&other.{}", field))
+            })),
+            Shape::Enum(variants) => {
+                // Variants that differ compare by their discriminants.
+                let tags = format!("{}(&__self_tag, &__other_tag)", method);
+                let pairs = self.each_pair(variants, &tags, |variant| {
+                    let this = bindings("__self", &variant.fields);
+                    let other = bindings("__other", &variant.fields);
+                    chain(this.into_iter().zip(other).collect())
+                });
+                if variants.len() > 1 {
+                    let arms = variants.iter().zip(self.tags(variants))
+                        .map(|(variant, tag)| format!("{} => {},",
+                            self.pattern(variant, None), tag))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    format!("let __self_tag = match *self {{ {} }};\n        \
+                        let __other_tag = match *other {{ {} }};\n        {}",
+                        arms, arms, pairs)
+                } else {
+                    pairs
+                }
+            }
+            Shape::Union => unreachable!(),
+        };
+        if partial {
+            format!("    fn partial_cmp(&self, other: &Self) -> \
+                ::core::option::Option<::core::cmp::Ordering> {{\n        \
+                {}\n    }}\n", body)
+        } else {
+            format!("    fn cmp(&self, other: &Self) -> \
+                ::core::cmp::Ordering {{\n        {}\n    }}\n", body)
+        }
+    }
+
+    // A value for each field of a struct, from its name or index.
+    fn fields<T>(&self, fields: &Fields, f: impl FnMut(&str) -> T) -> Vec<T> {
+        field_names(fields).iter().map(|name| name.as_str()).map(f).collect()
+    }
+
+    // `match *self { ... }` with an arm for each variant, which binds its
+    // fields by reference as `__self_0`, `__self_1`, ...
+    fn each_variant(
+        &self,
+        variants: &[Variant],
+        mut f: impl FnMut(&Variant) -> String,
+    ) -> String {
+        let arms = variants.iter()
+            .map(|variant| format!("{} => {},",
+                self.pattern(variant, Some("__self")), f(variant)))
+            .collect::<Vec<_>>();
+        format!("match *self {{ {} }}", arms.join(" "))
+    }
+
+    // `match (self, other) { ... }` with an arm for each variant, where both
+    // are that variant, and `otherwise` when they aren't.
+    fn each_pair(
+        &self,
+        variants: &[Variant],
+        otherwise: &str,
+        mut f: impl FnMut(&Variant) -> String,
+    ) -> String {
+        let mut arms = variants.iter()
+            .map(|variant| format!("(&{}, &{}) => {},",
+                self.pattern(variant, Some("__self")),
+                self.pattern(variant, Some("__other")), f(variant)))
+            .collect::<Vec<_>>();
+        if variants.len() > 1 {
+            arms.push(format!("_ => {},", otherwise));
+        }
+        format!("match (self, other) {{ {} }}", arms.join(" "))
+    }
+
+    // The discriminant of each variant as an `isize`: the one written, or
+    // one more than the previous variant's.
+    fn tags(&self, variants: &[Variant]) -> Vec<String> {
+        let mut last = None;
+        let mut offset = 0;
+        variants.iter()
+            .map(|variant| {
+                if let Some(ref value) = variant.discriminant {
+                    last = Some(self.source(value.span));
+                    offset = 0;
+                }
+                let tag = match last {
+                    None => format!("{}isize", offset),
+                    Some(value) if offset == 0 => {
+                        format!("(({}) as isize)", value)
+                    }
+                    Some(value) => {
+                        format!("(({}) as isize + {})", value, offset)
+                    }
+                };
+                offset += 1;
+                tag
+            })
+            .collect()
+    }
+
+    // A pattern for a variant, binding its fields by reference with the
+    // names from `bindings`, or ignoring them if `prefix` is `None`.
+    fn pattern(&self, variant: &Variant, prefix: Option<&str>) -> String {
+        let path = format!("{}::{}", self.name, variant.name);
+        let prefix = match prefix {
+            Some(prefix) => prefix,
+            None => return match variant.fields {
+                Fields::Unit => path,
+                Fields::Tuple(_) => format!("{}(..)", path),
+                Fields::Named(_) => format!("{} {{ .. }}", path),
+            },
+        };
+        let values = bindings(prefix, &variant.fields).iter()
+            .map(|binding| format!("ref {}", binding))
+            .collect::<Vec<_>>();
+        construct(&path, &variant.fields, &values)
+    }
+}
+
+// The names of fields as written after `self.`.
+fn field_names(fields: &Fields) -> Vec<String> {
+    match fields {
+        Fields::Unit => Vec::new(),
+        Fields::Tuple(fields) => {
+            (0..fields.len()).map(|i| i.to_string()).collect()
+        }
+        Fields::Named(fields) => {
+            fields.iter().filter_map(|field| field.name)
+                .map(str::to_string)
+                .collect()
+        }
+    }
+}
+
+// Names for the fields of a variant bound in a pattern.
+fn bindings(prefix: &str, fields: &Fields) -> Vec<String> {
+    (0..field_names(fields).len())
+        .map(|i| format!("{}_{}", prefix, i))
+        .collect()
+}
+
+// A struct expression or pattern with a value for each field.
+fn construct(path: &str, fields: &Fields, values: &[String]) -> String {
+    match fields {
+        Fields::Unit => path.to_string(),
+        Fields::Tuple(_) => format!("{}({})", path, values.join(", ")),
+        Fields::Named(_) => {
+            let values = field_names(fields).iter().zip(values)
+                .map(|(name, value)| format!("{}: {}", name, value))
+                .collect::<Vec<_>>();
+            format!("{} {{ {} }}", path, values.join(", "))
+        }
+    }
+}
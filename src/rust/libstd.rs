@@ -0,0 +1,7252 @@
+// Rust built-in standard library
+//
+//! The subset of `std` known to the type checker, written as Rust source.
+//!
+//! Functions are declared without bodies, except for the provided methods
+//! of traits which have empty ones; only the signatures, trait impls and
+//! associated types matter for type checking.
+
+pub(super) const SOURCE: &str = r##"
+pub mod marker {
+    pub trait Sized {}
+    pub trait Copy: Clone {}
+    pub trait Send {}
+    pub trait Sync {}
+    pub trait Unpin {}
+    pub struct PhantomData<T: ?Sized>;
+}
+
+pub mod clone {
+    pub trait Clone: Sized {
+        fn clone(&self) -> Self;
+        fn clone_from(&mut self, source: &Self) {}
+    }
+}
+
+pub mod default {
+    pub trait Default: Sized {
+        fn default() -> Self;
+    }
+}
+
+pub mod cmp {
+    pub trait PartialEq<Rhs: ?Sized = Self> {
+        fn eq(&self, other: &Rhs) -> bool;
+        fn ne(&self, other: &Rhs) -> bool {}
+    }
+
+    pub trait Eq: PartialEq {}
+
+    pub enum Ordering {
+        Less,
+        Equal,
+        Greater,
+    }
+
+    impl Ordering {
+        pub fn is_eq(self) -> bool;
+        pub fn is_ne(self) -> bool;
+        pub fn is_lt(self) -> bool;
+        pub fn is_gt(self) -> bool;
+        pub fn is_le(self) -> bool;
+        pub fn is_ge(self) -> bool;
+        pub fn reverse(self) -> Ordering;
+        pub fn then(self, other: Ordering) -> Ordering;
+        pub fn then_with<F: FnOnce() -> Ordering>(self, f: F) -> Ordering;
+    }
+
+    impl Clone for Ordering {}
+    impl Copy for Ordering {}
+    impl PartialEq for Ordering {}
+    impl Eq for Ordering {}
+    impl PartialOrd for Ordering {}
+    impl Ord for Ordering {}
+    impl super::fmt::Debug for Ordering {}
+    impl super::hash::Hash for Ordering {}
+
+    pub trait PartialOrd<Rhs: ?Sized = Self>: PartialEq<Rhs> {
+        fn partial_cmp(&self, other: &Rhs) -> Option<Ordering>;
+        fn lt(&self, other: &Rhs) -> bool {}
+        fn le(&self, other: &Rhs) -> bool {}
+        fn gt(&self, other: &Rhs) -> bool {}
+        fn ge(&self, other: &Rhs) -> bool {}
+    }
+
+    pub trait Ord: Eq + PartialOrd {
+        fn cmp(&self, other: &Self) -> Ordering;
+        fn max(self, other: Self) -> Self where Self: Sized {}
+        fn min(self, other: Self) -> Self where Self: Sized {}
+        fn clamp(self, min: Self, max: Self) -> Self where Self: Sized {}
+    }
+
+    impl<'a, 'b, A: ?Sized + PartialEq<B>, B: ?Sized> PartialEq<&'b B> for &'a A {}
+    impl<'a, 'b, A: ?Sized + PartialEq<B>, B: ?Sized> PartialEq<&'b mut B>
+        for &'a mut A {}
+    impl<'a, A: ?Sized + Eq> Eq for &'a A {}
+    impl<'a, 'b, A: ?Sized + PartialOrd<B>, B: ?Sized> PartialOrd<&'b B>
+        for &'a A {}
+    impl<'a, A: ?Sized + Ord> Ord for &'a A {}
+
+    pub struct Reverse<T>(pub T);
+
+    impl<T: PartialEq> PartialEq for Reverse<T> {}
+    impl<T: Eq> Eq for Reverse<T> {}
+    impl<T: PartialOrd> PartialOrd for Reverse<T> {}
+    impl<T: Ord> Ord for Reverse<T> {}
+    impl<T: Clone> Clone for Reverse<T> {}
+    impl<T: Copy> Copy for Reverse<T> {}
+
+    pub fn max<T: Ord>(v1: T, v2: T) -> T;
+    pub fn min<T: Ord>(v1: T, v2: T) -> T;
+    pub fn max_by_key<T, F: FnMut(&T) -> K, K: Ord>(v1: T, v2: T, f: F) -> T;
+    pub fn min_by_key<T, F: FnMut(&T) -> K, K: Ord>(v1: T, v2: T, f: F) -> T;
+}
+
+pub mod ops {
+    pub trait Add<Rhs = Self> {
+        type Output;
+        fn add(self, rhs: Rhs) -> Self::Output;
+    }
+
+    pub trait Sub<Rhs = Self> {
+        type Output;
+        fn sub(self, rhs: Rhs) -> Self::Output;
+    }
+
+    pub trait Mul<Rhs = Self> {
+        type Output;
+        fn mul(self, rhs: Rhs) -> Self::Output;
+    }
+
+    pub trait Div<Rhs = Self> {
+        type Output;
+        fn div(self, rhs: Rhs) -> Self::Output;
+    }
+
+    pub trait Rem<Rhs = Self> {
+        type Output;
+        fn rem(self, rhs: Rhs) -> Self::Output;
+    }
+
+    pub trait BitAnd<Rhs = Self> {
+        type Output;
+        fn bitand(self, rhs: Rhs) -> Self::Output;
+    }
+
+    pub trait BitOr<Rhs = Self> {
+        type Output;
+        fn bitor(self, rhs: Rhs) -> Self::Output;
+    }
+
+    pub trait BitXor<Rhs = Self> {
+        type Output;
+        fn bitxor(self, rhs: Rhs) -> Self::Output;
+    }
+
+    pub trait Shl<Rhs = Self> {
+        type Output;
+        fn shl(self, rhs: Rhs) -> Self::Output;
+    }
+
+    pub trait Shr<Rhs = Self> {
+        type Output;
+        fn shr(self, rhs: Rhs) -> Self::Output;
+    }
+
+    pub trait Neg {
+        type Output;
+        fn neg(self) -> Self::Output;
+    }
+
+    pub trait Not {
+        type Output;
+        fn not(self) -> Self::Output;
+    }
+
+    pub trait AddAssign<Rhs = Self> {
+        fn add_assign(&mut self, rhs: Rhs);
+    }
+
+    pub trait SubAssign<Rhs = Self> {
+        fn sub_assign(&mut self, rhs: Rhs);
+    }
+
+    pub trait MulAssign<Rhs = Self> {
+        fn mul_assign(&mut self, rhs: Rhs);
+    }
+
+    pub trait DivAssign<Rhs = Self> {
+        fn div_assign(&mut self, rhs: Rhs);
+    }
+
+    pub trait RemAssign<Rhs = Self> {
+        fn rem_assign(&mut self, rhs: Rhs);
+    }
+
+    pub trait BitAndAssign<Rhs = Self> {
+        fn bitand_assign(&mut self, rhs: Rhs);
+    }
+
+    pub trait BitOrAssign<Rhs = Self> {
+        fn bitor_assign(&mut self, rhs: Rhs);
+    }
+
+    pub trait BitXorAssign<Rhs = Self> {
+        fn bitxor_assign(&mut self, rhs: Rhs);
+    }
+
+    pub trait ShlAssign<Rhs = Self> {
+        fn shl_assign(&mut self, rhs: Rhs);
+    }
+
+    pub trait ShrAssign<Rhs = Self> {
+        fn shr_assign(&mut self, rhs: Rhs);
+    }
+
+    pub trait Deref {
+        type Target: ?Sized;
+        fn deref(&self) -> &Self::Target;
+    }
+
+    pub trait DerefMut: Deref {
+        fn deref_mut(&mut self) -> &mut Self::Target;
+    }
+
+    pub trait Index<Idx: ?Sized> {
+        type Output: ?Sized;
+        fn index(&self, index: Idx) -> &Self::Output;
+    }
+
+    pub trait IndexMut<Idx: ?Sized>: Index<Idx> {
+        fn index_mut(&mut self, index: Idx) -> &mut Self::Output;
+    }
+
+    pub trait Drop {
+        fn drop(&mut self);
+    }
+
+    pub trait FnOnce<Args> {
+        type Output;
+        fn call_once(self, args: Args) -> Self::Output;
+    }
+
+    pub trait FnMut<Args>: FnOnce<Args> {
+        fn call_mut(&mut self, args: Args) -> Self::Output;
+    }
+
+    pub trait Fn<Args>: FnMut<Args> {
+        fn call(&self, args: Args) -> Self::Output;
+    }
+
+    pub trait RangeBounds<T: ?Sized> {}
+
+    impl<T> RangeBounds<T> for Range<T> {}
+    impl<T> RangeBounds<T> for RangeFrom<T> {}
+    impl<T> RangeBounds<T> for RangeTo<T> {}
+    impl<T> RangeBounds<T> for RangeInclusive<T> {}
+    impl<T> RangeBounds<T> for RangeToInclusive<T> {}
+    impl<T: ?Sized> RangeBounds<T> for RangeFull {}
+
+    pub struct Range<Idx> {
+        pub start: Idx,
+        pub end: Idx,
+    }
+
+    impl<Idx: PartialOrd<Idx>> Range<Idx> {
+        pub fn contains(&self, item: &Idx) -> bool;
+        pub fn is_empty(&self) -> bool;
+    }
+
+    pub struct RangeFrom<Idx> {
+        pub start: Idx,
+    }
+
+    pub struct RangeTo<Idx> {
+        pub end: Idx,
+    }
+
+    pub struct RangeFull;
+
+    pub struct RangeInclusive<Idx> {
+        start: Idx,
+        end: Idx,
+    }
+
+    impl<Idx> RangeInclusive<Idx> {
+        pub fn new(start: Idx, end: Idx) -> RangeInclusive<Idx>;
+        pub fn start(&self) -> &Idx;
+        pub fn end(&self) -> &Idx;
+    }
+
+    impl<Idx: PartialOrd<Idx>> RangeInclusive<Idx> {
+        pub fn contains(&self, item: &Idx) -> bool;
+        pub fn is_empty(&self) -> bool;
+    }
+
+    pub struct RangeToInclusive<Idx> {
+        pub end: Idx,
+    }
+
+    impl<Idx: Clone> Clone for Range<Idx> {}
+    impl<Idx: Clone> Clone for RangeInclusive<Idx> {}
+    impl<Idx: PartialEq> PartialEq for Range<Idx> {}
+    impl<Idx: PartialEq> PartialEq for RangeInclusive<Idx> {}
+    impl<Idx: super::fmt::Debug> super::fmt::Debug for Range<Idx> {}
+    impl<Idx: super::fmt::Debug> super::fmt::Debug for RangeInclusive<Idx> {}
+}
+
+pub mod option {
+    use super::ops::{Deref, DerefMut};
+
+    pub enum Option<T> {
+        None,
+        Some(T),
+    }
+
+    impl<T> Option<T> {
+        pub fn is_some(&self) -> bool;
+        pub fn is_some_and<F: FnOnce(T) -> bool>(self, f: F) -> bool;
+        pub fn is_none(&self) -> bool;
+        pub fn is_none_or<F: FnOnce(T) -> bool>(self, f: F) -> bool;
+        pub fn as_ref(&self) -> Option<&T>;
+        pub fn as_mut(&mut self) -> Option<&mut T>;
+        pub fn expect(self, msg: &str) -> T;
+        pub fn unwrap(self) -> T;
+        pub fn unwrap_or(self, default: T) -> T;
+        pub fn unwrap_or_else<F: FnOnce() -> T>(self, f: F) -> T;
+        pub fn unwrap_or_default(self) -> T where T: Default;
+        pub unsafe fn unwrap_unchecked(self) -> T;
+        pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Option<U>;
+        pub fn inspect<F: FnOnce(&T)>(self, f: F) -> Option<T>;
+        pub fn map_or<U, F: FnOnce(T) -> U>(self, default: U, f: F) -> U;
+        pub fn map_or_else<U, D: FnOnce() -> U, F: FnOnce(T) -> U>(
+            self,
+            default: D,
+            f: F,
+        ) -> U;
+        pub fn ok_or<E>(self, err: E) -> Result<T, E>;
+        pub fn ok_or_else<E, F: FnOnce() -> E>(self, err: F) -> Result<T, E>;
+        pub fn as_deref(&self) -> Option<&T::Target> where T: Deref;
+        pub fn as_deref_mut(&mut self) -> Option<&mut T::Target>
+            where T: DerefMut;
+        pub fn iter(&self) -> Iter<T>;
+        pub fn iter_mut(&mut self) -> IterMut<T>;
+        pub fn and<U>(self, optb: Option<U>) -> Option<U>;
+        pub fn and_then<U, F: FnOnce(T) -> Option<U>>(self, f: F) -> Option<U>;
+        pub fn filter<P: FnOnce(&T) -> bool>(self, predicate: P) -> Option<T>;
+        pub fn or(self, optb: Option<T>) -> Option<T>;
+        pub fn or_else<F: FnOnce() -> Option<T>>(self, f: F) -> Option<T>;
+        pub fn xor(self, optb: Option<T>) -> Option<T>;
+        pub fn insert(&mut self, value: T) -> &mut T;
+        pub fn get_or_insert(&mut self, value: T) -> &mut T;
+        pub fn get_or_insert_with<F: FnOnce() -> T>(&mut self, f: F) -> &mut T;
+        pub fn take(&mut self) -> Option<T>;
+        pub fn take_if<P: FnOnce(&mut T) -> bool>(&mut self, predicate: P)
+            -> Option<T>;
+        pub fn replace(&mut self, value: T) -> Option<T>;
+        pub fn zip<U>(self, other: Option<U>) -> Option<(T, U)>;
+    }
+
+    impl<'a, T: Clone> Option<&'a T> {
+        pub fn cloned(self) -> Option<T>;
+    }
+
+    impl<'a, T: Copy> Option<&'a T> {
+        pub fn copied(self) -> Option<T>;
+    }
+
+    impl<T> Option<Option<T>> {
+        pub fn flatten(self) -> Option<T>;
+    }
+
+    impl<T, E> Option<Result<T, E>> {
+        pub fn transpose(self) -> Result<Option<T>, E>;
+    }
+
+    impl<T: Clone> Clone for Option<T> {}
+    impl<T: Copy> Copy for Option<T> {}
+    impl<T: PartialEq> PartialEq for Option<T> {}
+    impl<T: Eq> Eq for Option<T> {}
+    impl<T: PartialOrd> PartialOrd for Option<T> {}
+    impl<T: Ord> Ord for Option<T> {}
+    impl<T> Default for Option<T> {}
+    impl<T: super::hash::Hash> super::hash::Hash for Option<T> {}
+    impl<T: super::fmt::Debug> super::fmt::Debug for Option<T> {}
+
+    impl<T> From<T> for Option<T> {}
+
+    pub struct Iter<'a, T> {
+        inner: Option<&'a T>,
+    }
+
+    impl<'a, T> Iterator for Iter<'a, T> {
+        type Item = &'a T;
+    }
+
+    pub struct IterMut<'a, T> {
+        inner: Option<&'a mut T>,
+    }
+
+    impl<'a, T> Iterator for IterMut<'a, T> {
+        type Item = &'a mut T;
+    }
+
+    pub struct IntoIter<T> {
+        inner: Option<T>,
+    }
+
+    impl<T> Iterator for IntoIter<T> {
+        type Item = T;
+    }
+
+    impl<T> IntoIterator for Option<T> {
+        type Item = T;
+        type IntoIter = IntoIter<T>;
+    }
+
+    impl<'a, T> IntoIterator for &'a Option<T> {
+        type Item = &'a T;
+        type IntoIter = Iter<'a, T>;
+    }
+
+    impl<A, V: FromIterator<A>> FromIterator<Option<A>> for Option<V> {}
+}
+
+pub mod result {
+    pub enum Result<T, E> {
+        Ok(T),
+        Err(E),
+    }
+
+    impl<T, E> Result<T, E> {
+        pub fn is_ok(&self) -> bool;
+        pub fn is_ok_and<F: FnOnce(T) -> bool>(self, f: F) -> bool;
+        pub fn is_err(&self) -> bool;
+        pub fn is_err_and<F: FnOnce(E) -> bool>(self, f: F) -> bool;
+        pub fn ok(self) -> Option<T>;
+        pub fn err(self) -> Option<E>;
+        pub fn as_ref(&self) -> Result<&T, &E>;
+        pub fn as_mut(&mut self) -> Result<&mut T, &mut E>;
+        pub fn map<U, F: FnOnce(T) -> U>(self, op: F) -> Result<U, E>;
+        pub fn map_or<U, F: FnOnce(T) -> U>(self, default: U, f: F) -> U;
+        pub fn map_err<F, O: FnOnce(E) -> F>(self, op: O) -> Result<T, F>;
+        pub fn inspect<F: FnOnce(&T)>(self, f: F) -> Result<T, E>;
+        pub fn inspect_err<F: FnOnce(&E)>(self, f: F) -> Result<T, E>;
+        pub fn iter(&self) -> super::option::Iter<T>;
+        pub fn expect(self, msg: &str) -> T;
+        pub fn unwrap(self) -> T;
+        pub fn unwrap_or_default(self) -> T where T: Default;
+        pub fn expect_err(self, msg: &str) -> E;
+        pub fn unwrap_err(self) -> E;
+        pub fn and<U>(self, res: Result<U, E>) -> Result<U, E>;
+        pub fn and_then<U, F: FnOnce(T) -> Result<U, E>>(self, op: F)
+            -> Result<U, E>;
+        pub fn or<F>(self, res: Result<T, F>) -> Result<T, F>;
+        pub fn or_else<F, O: FnOnce(E) -> Result<T, F>>(self, op: O)
+            -> Result<T, F>;
+        pub fn unwrap_or(self, default: T) -> T;
+        pub fn unwrap_or_else<F: FnOnce(E) -> T>(self, op: F) -> T;
+    }
+
+    impl<T, E> Result<Option<T>, E> {
+        pub fn transpose(self) -> Option<Result<T, E>>;
+    }
+
+    impl<T: Clone, E: Clone> Clone for Result<T, E> {}
+    impl<T: Copy, E: Copy> Copy for Result<T, E> {}
+    impl<T: PartialEq, E: PartialEq> PartialEq for Result<T, E> {}
+    impl<T: Eq, E: Eq> Eq for Result<T, E> {}
+    impl<T: PartialOrd, E: PartialOrd> PartialOrd for Result<T, E> {}
+    impl<T: Ord, E: Ord> Ord for Result<T, E> {}
+    impl<T: super::hash::Hash, E: super::hash::Hash> super::hash::Hash
+        for Result<T, E> {}
+    impl<T: super::fmt::Debug, E: super::fmt::Debug> super::fmt::Debug
+        for Result<T, E> {}
+
+    impl<T, E> IntoIterator for Result<T, E> {
+        type Item = T;
+        type IntoIter = super::option::IntoIter<T>;
+    }
+
+    impl<A, E, V: FromIterator<A>> FromIterator<Result<A, E>> for Result<V, E> {}
+}
+
+pub mod boxed {
+    use super::ops::{Deref, DerefMut};
+
+    pub struct Box<T: ?Sized>(*mut T);
+
+    impl<T> Box<T> {
+        pub fn new(x: T) -> Box<T>;
+        pub fn pin(x: T) -> super::pin::Pin<Box<T>>;
+    }
+
+    impl<T: ?Sized> Box<T> {
+        pub fn leak<'a>(b: Box<T>) -> &'a mut T;
+        pub fn into_raw(b: Box<T>) -> *mut T;
+        pub unsafe fn from_raw(raw: *mut T) -> Box<T>;
+    }
+
+    impl<T: ?Sized> Deref for Box<T> {
+        type Target = T;
+    }
+
+    impl<T: ?Sized> DerefMut for Box<T> {}
+    impl<T: Clone> Clone for Box<T> {}
+    impl<T: ?Sized + PartialEq> PartialEq for Box<T> {}
+    impl<T: ?Sized + Eq> Eq for Box<T> {}
+    impl<T: ?Sized + PartialOrd> PartialOrd for Box<T> {}
+    impl<T: ?Sized + Ord> Ord for Box<T> {}
+    impl<T: Default> Default for Box<T> {}
+    impl<T: ?Sized + super::hash::Hash> super::hash::Hash for Box<T> {}
+    impl<T: ?Sized + super::fmt::Debug> super::fmt::Debug for Box<T> {}
+    impl<T: ?Sized + super::fmt::Display> super::fmt::Display for Box<T> {}
+    impl<T> From<T> for Box<T> {}
+    impl<'a> From<&'a str> for Box<str> {}
+    impl<T: ?Sized + AsRef<U>, U: ?Sized> AsRef<U> for Box<T> {}
+
+    impl<I: Iterator + ?Sized> Iterator for Box<I> {
+        type Item = I::Item;
+    }
+
+    impl<Args, F: FnOnce<Args> + ?Sized> FnOnce<Args> for Box<F> {
+        type Output = F::Output;
+    }
+
+    impl<Args, F: FnMut<Args> + ?Sized> FnMut<Args> for Box<F> {}
+    impl<Args, F: Fn<Args> + ?Sized> Fn<Args> for Box<F> {}
+}
+
+pub mod pin {
+    use super::ops::{Deref, DerefMut};
+
+    pub struct Pin<P> {
+        pointer: P,
+    }
+
+    impl<P: Deref> Pin<P> {
+        pub fn new(pointer: P) -> Pin<P>;
+        pub fn as_ref(&self) -> Pin<&P::Target>;
+    }
+
+    impl<P: DerefMut> Pin<P> {
+        pub fn as_mut(&mut self) -> Pin<&mut P::Target>;
+        pub fn set(&mut self, value: P::Target);
+    }
+
+    impl<P: Deref> Deref for Pin<P> {
+        type Target = P::Target;
+    }
+
+    impl<P: DerefMut> DerefMut for Pin<P> {}
+}
+
+pub mod vec {
+    use super::ops::{Deref, DerefMut, Index, IndexMut, Range, RangeFrom,
+        RangeTo, RangeFull, RangeInclusive};
+    use super::slice;
+
+    pub struct Vec<T> {
+        ptr: *mut T,
+        len: usize,
+        cap: usize,
+    }
+
+    impl<T> Vec<T> {
+        pub const fn new() -> Vec<T>;
+        pub fn with_capacity(capacity: usize) -> Vec<T>;
+        pub fn capacity(&self) -> usize;
+        pub fn reserve(&mut self, additional: usize);
+        pub fn shrink_to_fit(&mut self);
+        pub fn into_boxed_slice(self) -> Box<[T]>;
+        pub fn truncate(&mut self, len: usize);
+        pub fn as_slice(&self) -> &[T];
+        pub fn as_mut_slice(&mut self) -> &mut [T];
+        pub fn as_ptr(&self) -> *const T;
+        pub fn as_mut_ptr(&mut self) -> *mut T;
+        pub unsafe fn set_len(&mut self, new_len: usize);
+        pub fn swap_remove(&mut self, index: usize) -> T;
+        pub fn insert(&mut self, index: usize, element: T);
+        pub fn remove(&mut self, index: usize) -> T;
+        pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F);
+        pub fn retain_mut<F: FnMut(&mut T) -> bool>(&mut self, f: F);
+        pub fn dedup_by_key<K: PartialEq, F: FnMut(&mut T) -> K>(
+            &mut self,
+            key: F,
+        );
+        pub fn push(&mut self, value: T);
+        pub fn pop(&mut self) -> Option<T>;
+        pub fn append(&mut self, other: &mut Vec<T>);
+        pub fn drain<R: super::ops::RangeBounds<usize>>(&mut self, range: R)
+            -> Drain<T>;
+        pub fn clear(&mut self);
+        pub fn len(&self) -> usize;
+        pub fn is_empty(&self) -> bool;
+        pub fn split_off(&mut self, at: usize) -> Vec<T>;
+        pub fn resize_with<F: FnMut() -> T>(&mut self, new_len: usize, f: F);
+        pub fn leak<'a>(self) -> &'a mut [T];
+    }
+
+    impl<T: Clone> Vec<T> {
+        pub fn resize(&mut self, new_len: usize, value: T);
+        pub fn extend_from_slice(&mut self, other: &[T]);
+    }
+
+    impl<T: PartialEq> Vec<T> {
+        pub fn dedup(&mut self);
+    }
+
+    impl<T> Deref for Vec<T> {
+        type Target = [T];
+    }
+
+    impl<T> DerefMut for Vec<T> {}
+
+    impl<T> Index<usize> for Vec<T> {
+        type Output = T;
+    }
+
+    impl<T> IndexMut<usize> for Vec<T> {}
+
+    impl<T> Index<Range<usize>> for Vec<T> {
+        type Output = [T];
+    }
+
+    impl<T> Index<RangeFrom<usize>> for Vec<T> {
+        type Output = [T];
+    }
+
+    impl<T> Index<RangeTo<usize>> for Vec<T> {
+        type Output = [T];
+    }
+
+    impl<T> Index<RangeInclusive<usize>> for Vec<T> {
+        type Output = [T];
+    }
+
+    impl<T> Index<RangeFull> for Vec<T> {
+        type Output = [T];
+    }
+
+    impl<T> IndexMut<Range<usize>> for Vec<T> {}
+    impl<T> IndexMut<RangeFrom<usize>> for Vec<T> {}
+    impl<T> IndexMut<RangeTo<usize>> for Vec<T> {}
+    impl<T> IndexMut<RangeInclusive<usize>> for Vec<T> {}
+    impl<T> IndexMut<RangeFull> for Vec<T> {}
+
+    impl<T: Clone> Clone for Vec<T> {}
+    impl<T> Default for Vec<T> {}
+    impl<T: PartialEq<U>, U> PartialEq<Vec<U>> for Vec<T> {}
+    impl<'a, T: PartialEq<U>, U> PartialEq<&'a [U]> for Vec<T> {}
+    impl<T: PartialEq<U>, U> PartialEq<[U]> for Vec<T> {}
+    impl<T: Eq> Eq for Vec<T> {}
+    impl<T: PartialOrd> PartialOrd for Vec<T> {}
+    impl<T: Ord> Ord for Vec<T> {}
+    impl<T: super::hash::Hash> super::hash::Hash for Vec<T> {}
+    impl<T: super::fmt::Debug> super::fmt::Debug for Vec<T> {}
+    impl<T> AsRef<[T]> for Vec<T> {}
+    impl<T> AsMut<[T]> for Vec<T> {}
+    impl<T> AsRef<Vec<T>> for Vec<T> {}
+    impl<T> super::borrow::Borrow<[T]> for Vec<T> {}
+    impl<'a, T: Clone> From<&'a [T]> for Vec<T> {}
+    impl<'a> From<&'a str> for Vec<u8> {}
+    impl From<String> for Vec<u8> {}
+
+    impl<T> FromIterator<T> for Vec<T> {}
+    impl<T> Extend<T> for Vec<T> {}
+    impl<'a, T: Copy + 'a> Extend<&'a T> for Vec<T> {}
+
+    impl<T> IntoIterator for Vec<T> {
+        type Item = T;
+        type IntoIter = IntoIter<T>;
+    }
+
+    impl<'a, T> IntoIterator for &'a Vec<T> {
+        type Item = &'a T;
+        type IntoIter = slice::Iter<'a, T>;
+    }
+
+    impl<'a, T> IntoIterator for &'a mut Vec<T> {
+        type Item = &'a mut T;
+        type IntoIter = slice::IterMut<'a, T>;
+    }
+
+    pub struct IntoIter<T> {
+        vec: Vec<T>,
+    }
+
+    impl<T> Iterator for IntoIter<T> {
+        type Item = T;
+    }
+
+    impl<T> DoubleEndedIterator for IntoIter<T> {}
+    impl<T> ExactSizeIterator for IntoIter<T> {}
+
+    pub struct Drain<'a, T> {
+        vec: &'a mut Vec<T>,
+    }
+
+    impl<'a, T> Iterator for Drain<'a, T> {
+        type Item = T;
+    }
+
+    impl<'a, T> DoubleEndedIterator for Drain<'a, T> {}
+}
+
+pub mod slice {
+    use super::borrow::Borrow;
+    use super::cmp::Ordering;
+    use super::ops::{Index, IndexMut, Range, RangeFrom, RangeTo, RangeFull,
+        RangeInclusive};
+    use super::string::String;
+
+    pub trait Concat<Item: ?Sized> {
+        type Output;
+    }
+
+    impl<S: Borrow<str>> Concat<str> for [S] {
+        type Output = String;
+    }
+
+    impl<T: Clone, V: Borrow<[T]>> Concat<T> for [V] {
+        type Output = Vec<T>;
+    }
+
+    impl<T> [T] {
+        pub const fn len(&self) -> usize;
+        pub const fn is_empty(&self) -> bool;
+        pub fn first(&self) -> Option<&T>;
+        pub fn first_mut(&mut self) -> Option<&mut T>;
+        pub fn last(&self) -> Option<&T>;
+        pub fn last_mut(&mut self) -> Option<&mut T>;
+        pub fn split_first(&self) -> Option<(&T, &[T])>;
+        pub fn split_last(&self) -> Option<(&T, &[T])>;
+        pub fn get(&self, index: usize) -> Option<&T>;
+        pub fn get_mut(&mut self, index: usize) -> Option<&mut T>;
+        pub unsafe fn get_unchecked(&self, index: usize) -> &T;
+        pub fn as_ptr(&self) -> *const T;
+        pub fn as_mut_ptr(&mut self) -> *mut T;
+        pub fn swap(&mut self, a: usize, b: usize);
+        pub fn reverse(&mut self);
+        pub fn iter(&self) -> Iter<T>;
+        pub fn iter_mut(&mut self) -> IterMut<T>;
+        pub fn windows(&self, size: usize) -> Windows<T>;
+        pub fn chunks(&self, chunk_size: usize) -> Chunks<T>;
+        pub fn chunks_mut(&mut self, chunk_size: usize) -> ChunksMut<T>;
+        pub fn split_at(&self, mid: usize) -> (&[T], &[T]);
+        pub fn split_at_mut(&mut self, mid: usize) -> (&mut [T], &mut [T]);
+        pub fn rotate_left(&mut self, mid: usize);
+        pub fn rotate_right(&mut self, k: usize);
+        pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: F);
+        pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, f: F);
+        pub fn sort_unstable_by<F: FnMut(&T, &T) -> Ordering>(
+            &mut self,
+            compare: F,
+        );
+        pub fn sort_unstable_by_key<K: Ord, F: FnMut(&T) -> K>(
+            &mut self,
+            f: F,
+        );
+        pub fn binary_search_by<F: FnMut(&T) -> Ordering>(&self, f: F)
+            -> Result<usize, usize>;
+        pub fn binary_search_by_key<B: Ord, F: FnMut(&T) -> B>(
+            &self,
+            b: &B,
+            f: F,
+        ) -> Result<usize, usize>;
+        pub fn to_vec(&self) -> Vec<T> where T: Clone;
+        pub fn into_vec(self: Box<[T]>) -> Vec<T>;
+        pub fn fill(&mut self, value: T) where T: Clone;
+        pub fn copy_from_slice(&mut self, src: &[T]) where T: Copy;
+        pub fn clone_from_slice(&mut self, src: &[T]) where T: Clone;
+        pub fn contains(&self, x: &T) -> bool where T: PartialEq;
+        pub fn starts_with(&self, needle: &[T]) -> bool where T: PartialEq;
+        pub fn ends_with(&self, needle: &[T]) -> bool where T: PartialEq;
+        pub fn concat<Item: ?Sized>(&self) -> <Self as Concat<Item>>::Output
+            where Self: Concat<Item>;
+        pub fn join<'a>(&self, sep: &'a str) -> String where T: AsRef<str>;
+        pub fn repeat(&self, n: usize) -> Vec<T> where T: Copy;
+    }
+
+    impl<T: Ord> [T] {
+        pub fn sort(&mut self);
+        pub fn sort_unstable(&mut self);
+        pub fn binary_search(&self, x: &T) -> Result<usize, usize>;
+    }
+
+    impl<T> Index<usize> for [T] {
+        type Output = T;
+    }
+
+    impl<T> IndexMut<usize> for [T] {}
+
+    impl<T> Index<Range<usize>> for [T] {
+        type Output = [T];
+    }
+
+    impl<T> Index<RangeFrom<usize>> for [T] {
+        type Output = [T];
+    }
+
+    impl<T> Index<RangeTo<usize>> for [T] {
+        type Output = [T];
+    }
+
+    impl<T> Index<RangeInclusive<usize>> for [T] {
+        type Output = [T];
+    }
+
+    impl<T> Index<RangeFull> for [T] {
+        type Output = [T];
+    }
+
+    impl<T> AsRef<[T]> for [T] {}
+    impl<T> AsMut<[T]> for [T] {}
+
+    impl<T: Clone> super::borrow::ToOwned for [T] {
+        type Owned = Vec<T>;
+    }
+
+    impl<'a, T> IntoIterator for &'a [T] {
+        type Item = &'a T;
+        type IntoIter = Iter<'a, T>;
+    }
+
+    impl<'a, T> IntoIterator for &'a mut [T] {
+        type Item = &'a mut T;
+        type IntoIter = IterMut<'a, T>;
+    }
+
+    impl<'a, T> Default for &'a [T] {}
+
+    pub struct Iter<'a, T> {
+        slice: &'a [T],
+    }
+
+    impl<'a, T> Iterator for Iter<'a, T> {
+        type Item = &'a T;
+    }
+
+    impl<'a, T> DoubleEndedIterator for Iter<'a, T> {}
+    impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+    impl<'a, T> Clone for Iter<'a, T> {}
+
+    impl<'a, T> Iter<'a, T> {
+        pub fn as_slice(&self) -> &'a [T];
+    }
+
+    pub struct IterMut<'a, T> {
+        slice: &'a mut [T],
+    }
+
+    impl<'a, T> Iterator for IterMut<'a, T> {
+        type Item = &'a mut T;
+    }
+
+    impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {}
+    impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+
+    pub struct Windows<'a, T> {
+        slice: &'a [T],
+    }
+
+    impl<'a, T> Iterator for Windows<'a, T> {
+        type Item = &'a [T];
+    }
+
+    pub struct Chunks<'a, T> {
+        slice: &'a [T],
+    }
+
+    impl<'a, T> Iterator for Chunks<'a, T> {
+        type Item = &'a [T];
+    }
+
+    pub struct ChunksMut<'a, T> {
+        slice: &'a mut [T],
+    }
+
+    impl<'a, T> Iterator for ChunksMut<'a, T> {
+        type Item = &'a mut [T];
+    }
+
+    pub fn from_ref<T>(s: &T) -> &[T];
+}
+
+pub mod array {
+    impl<T, const N: usize> [T; N] {
+        pub fn map<U, F: FnMut(T) -> U>(self, f: F) -> [U; N];
+        pub fn as_slice(&self) -> &[T];
+        pub fn as_mut_slice(&mut self) -> &mut [T];
+    }
+
+    impl<T, const N: usize> IntoIterator for [T; N] {
+        type Item = T;
+        type IntoIter = IntoIter<T>;
+    }
+
+    impl<'a, T, const N: usize> IntoIterator for &'a [T; N] {
+        type Item = &'a T;
+        type IntoIter = super::slice::Iter<'a, T>;
+    }
+
+    impl<'a, T, const N: usize> IntoIterator for &'a mut [T; N] {
+        type Item = &'a mut T;
+        type IntoIter = super::slice::IterMut<'a, T>;
+    }
+
+    impl<T, const N: usize> AsRef<[T]> for [T; N] {}
+    impl<T, const N: usize> AsMut<[T]> for [T; N] {}
+
+    pub struct IntoIter<T> {
+        data: *mut T,
+    }
+
+    impl<T> Iterator for IntoIter<T> {
+        type Item = T;
+    }
+
+    impl<T> DoubleEndedIterator for IntoIter<T> {}
+    impl<T> ExactSizeIterator for IntoIter<T> {}
+}
+
+pub mod str {
+    use super::ops::{Index, Range, RangeFrom, RangeTo, RangeFull,
+        RangeInclusive};
+
+    impl str {
+        pub const fn len(&self) -> usize;
+        pub const fn is_empty(&self) -> bool;
+        pub fn is_char_boundary(&self, index: usize) -> bool;
+        pub const fn as_bytes(&self) -> &[u8];
+        pub fn as_ptr(&self) -> *const u8;
+        pub fn get<I>(&self, i: I) -> Option<&str>;
+        pub fn split_at(&self, mid: usize) -> (&str, &str);
+        pub fn chars(&self) -> Chars;
+        pub fn char_indices(&self) -> CharIndices;
+        pub fn bytes(&self) -> Bytes;
+        pub fn split_whitespace(&self) -> SplitWhitespace;
+        pub fn split_ascii_whitespace(&self) -> SplitWhitespace;
+        pub fn lines(&self) -> Lines;
+        pub fn contains<P: Pattern>(&self, pat: P) -> bool;
+        pub fn starts_with<P: Pattern>(&self, pat: P) -> bool;
+        pub fn ends_with<P: Pattern>(&self, pat: P) -> bool;
+        pub fn find<P: Pattern>(&self, pat: P) -> Option<usize>;
+        pub fn rfind<P: Pattern>(&self, pat: P) -> Option<usize>;
+        pub fn split<P: Pattern>(&self, pat: P) -> Split;
+        pub fn rsplit<P: Pattern>(&self, pat: P) -> Split;
+        pub fn split_terminator<P: Pattern>(&self, pat: P) -> Split;
+        pub fn splitn<P: Pattern>(&self, n: usize, pat: P) -> Split;
+        pub fn rsplitn<P: Pattern>(&self, n: usize, pat: P) -> Split;
+        pub fn split_once<P: Pattern>(&self, delimiter: P)
+            -> Option<(&str, &str)>;
+        pub fn rsplit_once<P: Pattern>(&self, delimiter: P)
+            -> Option<(&str, &str)>;
+        pub fn matches<P: Pattern>(&self, pat: P) -> Split;
+        pub fn trim(&self) -> &str;
+        pub fn trim_start(&self) -> &str;
+        pub fn trim_end(&self) -> &str;
+        pub fn trim_matches<P: Pattern>(&self, pat: P) -> &str;
+        pub fn trim_start_matches<P: Pattern>(&self, pat: P) -> &str;
+        pub fn trim_end_matches<P: Pattern>(&self, pat: P) -> &str;
+        pub fn strip_prefix<P: Pattern>(&self, prefix: P) -> Option<&str>;
+        pub fn strip_suffix<P: Pattern>(&self, suffix: P) -> Option<&str>;
+        pub fn parse<F: FromStr>(&self) -> Result<F, F::Err>;
+        pub fn is_ascii(&self) -> bool;
+        pub fn eq_ignore_ascii_case(&self, other: &str) -> bool;
+        pub fn replace<P: Pattern>(&self, from: P, to: &str) -> String;
+        pub fn replacen<P: Pattern>(&self, pat: P, to: &str, count: usize)
+            -> String;
+        pub fn to_lowercase(&self) -> String;
+        pub fn to_uppercase(&self) -> String;
+        pub fn to_ascii_lowercase(&self) -> String;
+        pub fn to_ascii_uppercase(&self) -> String;
+        pub fn repeat(&self, n: usize) -> String;
+        pub fn into_string(self: Box<str>) -> String;
+    }
+
+    pub trait Pattern {}
+
+    impl Pattern for char {}
+    impl<'a> Pattern for &'a str {}
+    impl<'a> Pattern for &'a String {}
+    impl<'a, 'b> Pattern for &'b &'a str {}
+    impl<'a> Pattern for &'a [char] {}
+    impl<const N: usize> Pattern for [char; N] {}
+    impl<F: FnMut(char) -> bool> Pattern for F {}
+
+    impl Index<Range<usize>> for str {
+        type Output = str;
+    }
+
+    impl Index<RangeFrom<usize>> for str {
+        type Output = str;
+    }
+
+    impl Index<RangeTo<usize>> for str {
+        type Output = str;
+    }
+
+    impl Index<RangeInclusive<usize>> for str {
+        type Output = str;
+    }
+
+    impl Index<RangeFull> for str {
+        type Output = str;
+    }
+
+    impl AsRef<str> for str {}
+    impl AsRef<[u8]> for str {}
+
+    impl super::borrow::ToOwned for str {
+        type Owned = String;
+    }
+
+    impl<'a> Default for &'a str {}
+
+    pub trait FromStr: Sized {
+        type Err;
+        fn from_str(s: &str) -> Result<Self, Self::Err>;
+    }
+
+    pub struct ParseBoolError;
+
+    pub struct Utf8Error;
+
+    pub fn from_utf8(v: &[u8]) -> Result<&str, Utf8Error>;
+
+    pub struct Chars<'a> {
+        text: &'a str,
+    }
+
+    impl<'a> Chars<'a> {
+        pub fn as_str(&self) -> &'a str;
+    }
+
+    impl<'a> Iterator for Chars<'a> {
+        type Item = char;
+    }
+
+    impl<'a> DoubleEndedIterator for Chars<'a> {}
+    impl<'a> Clone for Chars<'a> {}
+
+    pub struct CharIndices<'a> {
+        text: &'a str,
+    }
+
+    impl<'a> Iterator for CharIndices<'a> {
+        type Item = (usize, char);
+    }
+
+    impl<'a> DoubleEndedIterator for CharIndices<'a> {}
+
+    pub struct Bytes<'a> {
+        text: &'a str,
+    }
+
+    impl<'a> Iterator for Bytes<'a> {
+        type Item = u8;
+    }
+
+    impl<'a> DoubleEndedIterator for Bytes<'a> {}
+    impl<'a> ExactSizeIterator for Bytes<'a> {}
+
+    pub struct Split<'a> {
+        text: &'a str,
+    }
+
+    impl<'a> Iterator for Split<'a> {
+        type Item = &'a str;
+    }
+
+    impl<'a> DoubleEndedIterator for Split<'a> {}
+
+    pub struct SplitWhitespace<'a> {
+        text: &'a str,
+    }
+
+    impl<'a> Iterator for SplitWhitespace<'a> {
+        type Item = &'a str;
+    }
+
+    impl<'a> DoubleEndedIterator for SplitWhitespace<'a> {}
+
+    pub struct Lines<'a> {
+        text: &'a str,
+    }
+
+    impl<'a> Iterator for Lines<'a> {
+        type Item = &'a str;
+    }
+
+    impl<'a> DoubleEndedIterator for Lines<'a> {}
+}
+
+pub mod string {
+    use super::fmt;
+    use super::ops::{Add, AddAssign, Deref, DerefMut, Index, Range, RangeFrom,
+        RangeTo, RangeFull, RangeInclusive};
+
+    pub struct String {
+        vec: Vec<u8>,
+    }
+
+    impl String {
+        pub const fn new() -> String;
+        pub fn with_capacity(capacity: usize) -> String;
+        pub fn from_utf8(vec: Vec<u8>) -> Result<String, FromUtf8Error>;
+        pub fn from_utf8_lossy(v: &[u8]) -> String;
+        pub fn into_bytes(self) -> Vec<u8>;
+        pub fn as_str(&self) -> &str;
+        pub fn as_mut_str(&mut self) -> &mut str;
+        pub fn push_str(&mut self, string: &str);
+        pub fn capacity(&self) -> usize;
+        pub fn reserve(&mut self, additional: usize);
+        pub fn push(&mut self, ch: char);
+        pub fn as_bytes(&self) -> &[u8];
+        pub fn truncate(&mut self, new_len: usize);
+        pub fn pop(&mut self) -> Option<char>;
+        pub fn remove(&mut self, idx: usize) -> char;
+        pub fn retain<F: FnMut(char) -> bool>(&mut self, f: F);
+        pub fn insert(&mut self, idx: usize, ch: char);
+        pub fn insert_str(&mut self, idx: usize, string: &str);
+        pub fn len(&self) -> usize;
+        pub fn is_empty(&self) -> bool;
+        pub fn split_off(&mut self, at: usize) -> String;
+        pub fn clear(&mut self);
+        pub fn drain<R: super::ops::RangeBounds<usize>>(&mut self, range: R)
+            -> super::str::Chars;
+        pub fn into_boxed_str(self) -> Box<str>;
+    }
+
+    pub struct FromUtf8Error;
+
+    impl Deref for String {
+        type Target = str;
+    }
+
+    impl DerefMut for String {}
+
+    impl Index<Range<usize>> for String {
+        type Output = str;
+    }
+
+    impl Index<RangeFrom<usize>> for String {
+        type Output = str;
+    }
+
+    impl Index<RangeTo<usize>> for String {
+        type Output = str;
+    }
+
+    impl Index<RangeInclusive<usize>> for String {
+        type Output = str;
+    }
+
+    impl Index<RangeFull> for String {
+        type Output = str;
+    }
+
+    impl Clone for String {}
+    impl Default for String {}
+    impl PartialEq for String {}
+    impl PartialEq<str> for String {}
+    impl<'a> PartialEq<&'a str> for String {}
+    impl PartialEq<String> for str {}
+    impl<'a> PartialEq<String> for &'a str {}
+    impl Eq for String {}
+    impl PartialOrd for String {}
+    impl Ord for String {}
+    impl super::hash::Hash for String {}
+    impl fmt::Display for String {}
+    impl fmt::Debug for String {}
+    impl fmt::Write for String {}
+    impl AsRef<str> for String {}
+    impl AsRef<[u8]> for String {}
+    impl AsMut<str> for String {}
+    impl super::borrow::Borrow<str> for String {}
+    impl<'a> From<&'a str> for String {}
+    impl<'a> From<&'a mut str> for String {}
+    impl<'a> From<&'a String> for String {}
+    impl From<char> for String {}
+    impl From<Box<str>> for String {}
+    impl super::str::FromStr for String {
+        type Err = super::convert::Infallible;
+    }
+
+    impl<'a> Add<&'a str> for String {
+        type Output = String;
+    }
+
+    impl<'a> AddAssign<&'a str> for String {}
+
+    impl FromIterator<char> for String {}
+    impl<'a> FromIterator<&'a char> for String {}
+    impl<'a> FromIterator<&'a str> for String {}
+    impl FromIterator<String> for String {}
+    impl Extend<char> for String {}
+    impl<'a> Extend<&'a char> for String {}
+    impl<'a> Extend<&'a str> for String {}
+    impl Extend<String> for String {}
+
+    pub trait ToString {
+        fn to_string(&self) -> String;
+    }
+
+    impl<T: fmt::Display + ?Sized> ToString for T {}
+}
+
+pub mod fmt {
+    pub struct Error;
+
+    pub type Result = super::result::Result<(), Error>;
+
+    pub struct Formatter<'a> {
+        buf: &'a mut dyn Write,
+    }
+
+    impl<'a> Formatter<'a> {
+        pub fn write_str(&mut self, data: &str) -> Result;
+        pub fn write_fmt(&mut self, fmt: Arguments) -> Result;
+        pub fn pad(&mut self, s: &str) -> Result;
+        pub fn alternate(&self) -> bool;
+        pub fn width(&self) -> Option<usize>;
+        pub fn precision(&self) -> Option<usize>;
+        pub fn debug_struct(&mut self, name: &str) -> DebugStruct;
+        pub fn debug_tuple(&mut self, name: &str) -> DebugTuple;
+        pub fn debug_list(&mut self) -> DebugList;
+    }
+
+    pub struct DebugStruct<'a, 'b> {
+        fmt: &'a mut Formatter<'b>,
+    }
+
+    impl<'a, 'b> DebugStruct<'a, 'b> {
+        pub fn field(&mut self, name: &str, value: &dyn Debug)
+            -> &mut DebugStruct<'a, 'b>;
+        pub fn finish(&mut self) -> Result;
+        pub fn finish_non_exhaustive(&mut self) -> Result;
+    }
+
+    pub struct DebugTuple<'a, 'b> {
+        fmt: &'a mut Formatter<'b>,
+    }
+
+    impl<'a, 'b> DebugTuple<'a, 'b> {
+        pub fn field(&mut self, value: &dyn Debug) -> &mut DebugTuple<'a, 'b>;
+        pub fn finish(&mut self) -> Result;
+    }
+
+    pub struct DebugList<'a, 'b> {
+        fmt: &'a mut Formatter<'b>,
+    }
+
+    impl<'a, 'b> DebugList<'a, 'b> {
+        pub fn entry(&mut self, entry: &dyn Debug) -> &mut DebugList<'a, 'b>;
+        pub fn entries<D: Debug, I: IntoIterator<Item = D>>(
+            &mut self,
+            entries: I,
+        ) -> &mut DebugList<'a, 'b>;
+        pub fn finish(&mut self) -> Result;
+    }
+
+    pub struct Arguments<'a> {
+        pieces: &'a [&'static str],
+    }
+
+    impl<'a> Display for Arguments<'a> {}
+    impl<'a> Debug for Arguments<'a> {}
+    impl<'a> Clone for Arguments<'a> {}
+    impl<'a> Copy for Arguments<'a> {}
+
+    pub trait Display {
+        fn fmt(&self, f: &mut Formatter) -> Result;
+    }
+
+    pub trait Debug {
+        fn fmt(&self, f: &mut Formatter) -> Result;
+    }
+
+    pub trait LowerHex {
+        fn fmt(&self, f: &mut Formatter) -> Result;
+    }
+
+    pub trait UpperHex {
+        fn fmt(&self, f: &mut Formatter) -> Result;
+    }
+
+    pub trait Binary {
+        fn fmt(&self, f: &mut Formatter) -> Result;
+    }
+
+    pub trait Write {
+        fn write_str(&mut self, s: &str) -> Result;
+        fn write_char(&mut self, c: char) -> Result {}
+        fn write_fmt(&mut self, args: Arguments) -> Result {}
+    }
+
+    impl<'a, T: Display + ?Sized> Display for &'a T {}
+    impl<'a, T: Display + ?Sized> Display for &'a mut T {}
+    impl<'a, T: Debug + ?Sized> Debug for &'a T {}
+    impl<'a, T: Debug + ?Sized> Debug for &'a mut T {}
+    impl<T: Debug> Debug for [T] {}
+    impl<T: ?Sized> Debug for *const T {}
+    impl<T: ?Sized> Debug for *mut T {}
+    impl Debug for Error {}
+    impl Display for Error {}
+
+    pub fn format(args: Arguments) -> String;
+}
+
+pub mod iter {
+    use super::cmp::Ordering;
+    use super::ops::{Range, RangeFrom, RangeInclusive};
+
+    pub trait Iterator {
+        type Item;
+        fn next(&mut self) -> Option<Self::Item>;
+        fn size_hint(&self) -> (usize, Option<usize>) {}
+        fn count(self) -> usize where Self: Sized {}
+        fn last(self) -> Option<Self::Item> where Self: Sized {}
+        fn nth(&mut self, n: usize) -> Option<Self::Item> {}
+        fn step_by(self, step: usize) -> StepBy<Self> where Self: Sized {}
+        fn chain<U: IntoIterator<Item = Self::Item>>(self, other: U)
+            -> Chain<Self, U::IntoIter> where Self: Sized {}
+        fn zip<U: IntoIterator>(self, other: U) -> Zip<Self, U::IntoIter>
+            where Self: Sized {}
+        fn map<B, F: FnMut(Self::Item) -> B>(self, f: F) -> Map<Self, F>
+            where Self: Sized {}
+        fn for_each<F: FnMut(Self::Item)>(self, f: F) where Self: Sized {}
+        fn filter<P: FnMut(&Self::Item) -> bool>(self, predicate: P)
+            -> Filter<Self, P> where Self: Sized {}
+        fn filter_map<B, F: FnMut(Self::Item) -> Option<B>>(self, f: F)
+            -> FilterMap<Self, F> where Self: Sized {}
+        fn enumerate(self) -> Enumerate<Self> where Self: Sized {}
+        fn peekable(self) -> Peekable<Self> where Self: Sized {}
+        fn skip_while<P: FnMut(&Self::Item) -> bool>(self, predicate: P)
+            -> SkipWhile<Self, P> where Self: Sized {}
+        fn take_while<P: FnMut(&Self::Item) -> bool>(self, predicate: P)
+            -> TakeWhile<Self, P> where Self: Sized {}
+        fn map_while<B, P: FnMut(Self::Item) -> Option<B>>(self, predicate: P)
+            -> MapWhile<Self, P> where Self: Sized {}
+        fn skip(self, n: usize) -> Skip<Self> where Self: Sized {}
+        fn take(self, n: usize) -> Take<Self> where Self: Sized {}
+        fn scan<St, B, F: FnMut(&mut St, Self::Item) -> Option<B>>(
+            self,
+            initial_state: St,
+            f: F,
+        ) -> Scan<Self, St, F> where Self: Sized {}
+        fn flat_map<U: IntoIterator, F: FnMut(Self::Item) -> U>(self, f: F)
+            -> FlatMap<Self, U, F> where Self: Sized {}
+        fn flatten(self) -> Flatten<Self>
+            where Self: Sized, Self::Item: IntoIterator {}
+        fn fuse(self) -> Fuse<Self> where Self: Sized {}
+        fn inspect<F: FnMut(&Self::Item)>(self, f: F) -> Inspect<Self, F>
+            where Self: Sized {}
+        fn by_ref(&mut self) -> &mut Self where Self: Sized {}
+        fn collect<B: FromIterator<Self::Item>>(self) -> B where Self: Sized {}
+        fn partition<B: Default + Extend<Self::Item>, F: FnMut(&Self::Item)
+            -> bool>(self, f: F) -> (B, B) where Self: Sized {}
+        fn fold<B, F: FnMut(B, Self::Item) -> B>(self, init: B, f: F) -> B
+            where Self: Sized {}
+        fn reduce<F: FnMut(Self::Item, Self::Item) -> Self::Item>(self, f: F)
+            -> Option<Self::Item> where Self: Sized {}
+        fn all<F: FnMut(Self::Item) -> bool>(&mut self, f: F) -> bool
+            where Self: Sized {}
+        fn any<F: FnMut(Self::Item) -> bool>(&mut self, f: F) -> bool
+            where Self: Sized {}
+        fn find<P: FnMut(&Self::Item) -> bool>(&mut self, predicate: P)
+            -> Option<Self::Item> where Self: Sized {}
+        fn find_map<B, F: FnMut(Self::Item) -> Option<B>>(&mut self, f: F)
+            -> Option<B> where Self: Sized {}
+        fn position<P: FnMut(Self::Item) -> bool>(&mut self, predicate: P)
+            -> Option<usize> where Self: Sized {}
+        fn rposition<P: FnMut(Self::Item) -> bool>(&mut self, predicate: P)
+            -> Option<usize> where Self: Sized {}
+        fn max(self) -> Option<Self::Item> where Self: Sized, Self::Item: Ord {}
+        fn min(self) -> Option<Self::Item> where Self: Sized, Self::Item: Ord {}
+        fn max_by_key<B: Ord, F: FnMut(&Self::Item) -> B>(self, f: F)
+            -> Option<Self::Item> where Self: Sized {}
+        fn max_by<F: FnMut(&Self::Item, &Self::Item) -> Ordering>(
+            self,
+            compare: F,
+        ) -> Option<Self::Item> where Self: Sized {}
+        fn min_by_key<B: Ord, F: FnMut(&Self::Item) -> B>(self, f: F)
+            -> Option<Self::Item> where Self: Sized {}
+        fn min_by<F: FnMut(&Self::Item, &Self::Item) -> Ordering>(
+            self,
+            compare: F,
+        ) -> Option<Self::Item> where Self: Sized {}
+        fn rev(self) -> Rev<Self> where Self: Sized + DoubleEndedIterator {}
+        fn unzip<A, B, FromA: Default + Extend<A>, FromB: Default + Extend<B>>(
+            self,
+        ) -> (FromA, FromB) where Self: Sized + Iterator<Item = (A, B)> {}
+        fn copied<'a, T: 'a + Copy>(self) -> Copied<Self>
+            where Self: Sized + Iterator<Item = &'a T> {}
+        fn cloned<'a, T: 'a + Clone>(self) -> Cloned<Self>
+            where Self: Sized + Iterator<Item = &'a T> {}
+        fn cycle(self) -> Cycle<Self> where Self: Sized + Clone {}
+        fn sum<S: Sum<Self::Item>>(self) -> S where Self: Sized {}
+        fn product<P: Product<Self::Item>>(self) -> P where Self: Sized {}
+        fn cmp<I: IntoIterator<Item = Self::Item>>(self, other: I) -> Ordering
+            where Self::Item: Ord, Self: Sized {}
+        fn eq<I: IntoIterator>(self, other: I) -> bool
+            where Self::Item: PartialEq<I::Item>, Self: Sized {}
+        fn is_sorted(self) -> bool where Self: Sized, Self::Item: PartialOrd {}
+    }
+
+    pub trait DoubleEndedIterator: Iterator {
+        fn next_back(&mut self) -> Option<Self::Item>;
+        fn nth_back(&mut self, n: usize) -> Option<Self::Item> {}
+        fn rfind<P: FnMut(&Self::Item) -> bool>(&mut self, predicate: P)
+            -> Option<Self::Item> where Self: Sized {}
+    }
+
+    pub trait ExactSizeIterator: Iterator {
+        fn len(&self) -> usize {}
+    }
+
+    pub trait IntoIterator {
+        type Item;
+        type IntoIter: Iterator<Item = Self::Item>;
+        fn into_iter(self) -> Self::IntoIter;
+    }
+
+    impl<I: Iterator> IntoIterator for I {
+        type Item = I::Item;
+        type IntoIter = I;
+    }
+
+    pub trait FromIterator<A>: Sized {
+        fn from_iter<T: IntoIterator<Item = A>>(iter: T) -> Self;
+    }
+
+    pub trait Extend<A> {
+        fn extend<T: IntoIterator<Item = A>>(&mut self, iter: T);
+    }
+
+    pub trait Sum<A = Self>: Sized {
+        fn sum<I: Iterator<Item = A>>(iter: I) -> Self;
+    }
+
+    pub trait Product<A = Self>: Sized {
+        fn product<I: Iterator<Item = A>>(iter: I) -> Self;
+    }
+
+    pub trait Step: Clone + PartialOrd + Sized {}
+
+    impl<I: Iterator + ?Sized> Iterator for &mut I {
+        type Item = I::Item;
+    }
+
+    impl<I: DoubleEndedIterator + ?Sized> DoubleEndedIterator for &mut I {}
+
+    impl<A: Step> Iterator for Range<A> {
+        type Item = A;
+    }
+
+    impl<A: Step> DoubleEndedIterator for Range<A> {}
+    impl<A: Step> ExactSizeIterator for Range<A> {}
+
+    impl<A: Step> Iterator for RangeFrom<A> {
+        type Item = A;
+    }
+
+    impl<A: Step> Iterator for RangeInclusive<A> {
+        type Item = A;
+    }
+
+    impl<A: Step> DoubleEndedIterator for RangeInclusive<A> {}
+
+    pub struct StepBy<I> {
+        iter: I,
+    }
+
+    impl<I: Iterator> Iterator for StepBy<I> {
+        type Item = I::Item;
+    }
+
+    pub struct Chain<A, B> {
+        a: Option<A>,
+        b: Option<B>,
+    }
+
+    impl<A: Iterator, B: Iterator<Item = A::Item>> Iterator for Chain<A, B> {
+        type Item = A::Item;
+    }
+
+    impl<A: DoubleEndedIterator, B: DoubleEndedIterator<Item = A::Item>>
+        DoubleEndedIterator for Chain<A, B> {}
+
+    pub struct Zip<A, B> {
+        a: A,
+        b: B,
+    }
+
+    impl<A: Iterator, B: Iterator> Iterator for Zip<A, B> {
+        type Item = (A::Item, B::Item);
+    }
+
+    impl<A: DoubleEndedIterator, B: DoubleEndedIterator> DoubleEndedIterator
+        for Zip<A, B> {}
+
+    pub fn zip<A: IntoIterator, B: IntoIterator>(a: A, b: B)
+        -> Zip<A::IntoIter, B::IntoIter>;
+
+    pub struct Map<I, F> {
+        iter: I,
+        f: F,
+    }
+
+    impl<B, I: Iterator, F: FnMut(I::Item) -> B> Iterator for Map<I, F> {
+        type Item = B;
+    }
+
+    impl<B, I: DoubleEndedIterator, F: FnMut(I::Item) -> B> DoubleEndedIterator
+        for Map<I, F> {}
+
+    impl<B, I: ExactSizeIterator, F: FnMut(I::Item) -> B> ExactSizeIterator
+        for Map<I, F> {}
+
+    pub struct Filter<I, P> {
+        iter: I,
+        predicate: P,
+    }
+
+    impl<I: Iterator, P: FnMut(&I::Item) -> bool> Iterator for Filter<I, P> {
+        type Item = I::Item;
+    }
+
+    impl<I: DoubleEndedIterator, P: FnMut(&I::Item) -> bool>
+        DoubleEndedIterator for Filter<I, P> {}
+
+    pub struct FilterMap<I, F> {
+        iter: I,
+        f: F,
+    }
+
+    impl<B, I: Iterator, F: FnMut(I::Item) -> Option<B>> Iterator
+        for FilterMap<I, F>
+    {
+        type Item = B;
+    }
+
+    pub struct Enumerate<I> {
+        iter: I,
+        count: usize,
+    }
+
+    impl<I: Iterator> Iterator for Enumerate<I> {
+        type Item = (usize, I::Item);
+    }
+
+    impl<I: DoubleEndedIterator + ExactSizeIterator> DoubleEndedIterator
+        for Enumerate<I> {}
+
+    impl<I: ExactSizeIterator> ExactSizeIterator for Enumerate<I> {}
+
+    pub struct Peekable<I: Iterator> {
+        iter: I,
+        peeked: Option<Option<I::Item>>,
+    }
+
+    impl<I: Iterator> Iterator for Peekable<I> {
+        type Item = I::Item;
+    }
+
+    impl<I: Iterator> Peekable<I> {
+        pub fn peek(&mut self) -> Option<&I::Item>;
+        pub fn peek_mut(&mut self) -> Option<&mut I::Item>;
+        pub fn next_if<F: FnOnce(&I::Item) -> bool>(&mut self, func: F)
+            -> Option<I::Item>;
+        pub fn next_if_eq<T: ?Sized>(&mut self, expected: &T)
+            -> Option<I::Item> where I::Item: PartialEq<T>;
+    }
+
+    pub struct SkipWhile<I, P> {
+        iter: I,
+        predicate: P,
+    }
+
+    impl<I: Iterator, P: FnMut(&I::Item) -> bool> Iterator
+        for SkipWhile<I, P>
+    {
+        type Item = I::Item;
+    }
+
+    pub struct TakeWhile<I, P> {
+        iter: I,
+        predicate: P,
+    }
+
+    impl<I: Iterator, P: FnMut(&I::Item) -> bool> Iterator
+        for TakeWhile<I, P>
+    {
+        type Item = I::Item;
+    }
+
+    pub struct MapWhile<I, P> {
+        iter: I,
+        predicate: P,
+    }
+
+    impl<B, I: Iterator, P: FnMut(I::Item) -> Option<B>> Iterator
+        for MapWhile<I, P>
+    {
+        type Item = B;
+    }
+
+    pub struct Skip<I> {
+        iter: I,
+        n: usize,
+    }
+
+    impl<I: Iterator> Iterator for Skip<I> {
+        type Item = I::Item;
+    }
+
+    impl<I: DoubleEndedIterator + ExactSizeIterator> DoubleEndedIterator
+        for Skip<I> {}
+
+    pub struct Take<I> {
+        iter: I,
+        n: usize,
+    }
+
+    impl<I: Iterator> Iterator for Take<I> {
+        type Item = I::Item;
+    }
+
+    impl<I: DoubleEndedIterator + ExactSizeIterator> DoubleEndedIterator
+        for Take<I> {}
+
+    pub struct Scan<I, St, F> {
+        iter: I,
+        f: F,
+        state: St,
+    }
+
+    impl<B, I: Iterator, St, F: FnMut(&mut St, I::Item) -> Option<B>> Iterator
+        for Scan<I, St, F>
+    {
+        type Item = B;
+    }
+
+    pub struct FlatMap<I, U: IntoIterator, F> {
+        iter: I,
+        f: F,
+        front: Option<U::IntoIter>,
+    }
+
+    impl<I: Iterator, U: IntoIterator, F: FnMut(I::Item) -> U> Iterator
+        for FlatMap<I, U, F>
+    {
+        type Item = U::Item;
+    }
+
+    pub struct Flatten<I: Iterator> {
+        iter: I,
+        front: Option<I::Item>,
+    }
+
+    impl<I: Iterator> Iterator for Flatten<I> where I::Item: IntoIterator {
+        type Item = <I::Item as IntoIterator>::Item;
+    }
+
+    pub struct Fuse<I> {
+        iter: Option<I>,
+    }
+
+    impl<I: Iterator> Iterator for Fuse<I> {
+        type Item = I::Item;
+    }
+
+    pub struct Inspect<I, F> {
+        iter: I,
+        f: F,
+    }
+
+    impl<I: Iterator, F: FnMut(&I::Item)> Iterator for Inspect<I, F> {
+        type Item = I::Item;
+    }
+
+    pub struct Rev<I> {
+        iter: I,
+    }
+
+    impl<I: DoubleEndedIterator> Iterator for Rev<I> {
+        type Item = I::Item;
+    }
+
+    impl<I: DoubleEndedIterator> DoubleEndedIterator for Rev<I> {}
+
+    pub struct Copied<I> {
+        iter: I,
+    }
+
+    impl<'a, I: Iterator<Item = &'a T>, T: 'a + Copy> Iterator for Copied<I> {
+        type Item = T;
+    }
+
+    impl<'a, I: DoubleEndedIterator<Item = &'a T>, T: 'a + Copy>
+        DoubleEndedIterator for Copied<I> {}
+
+    pub struct Cloned<I> {
+        iter: I,
+    }
+
+    impl<'a, I: Iterator<Item = &'a T>, T: 'a + Clone> Iterator for Cloned<I> {
+        type Item = T;
+    }
+
+    impl<'a, I: DoubleEndedIterator<Item = &'a T>, T: 'a + Clone>
+        DoubleEndedIterator for Cloned<I> {}
+
+    pub struct Cycle<I> {
+        orig: I,
+        iter: I,
+    }
+
+    impl<I: Clone + Iterator> Iterator for Cycle<I> {
+        type Item = I::Item;
+    }
+
+    pub struct Empty<T> {
+        marker: super::marker::PhantomData<T>,
+    }
+
+    impl<T> Iterator for Empty<T> {
+        type Item = T;
+    }
+
+    pub fn empty<T>() -> Empty<T>;
+
+    pub struct Once<T> {
+        inner: Option<T>,
+    }
+
+    impl<T> Iterator for Once<T> {
+        type Item = T;
+    }
+
+    pub fn once<T>(value: T) -> Once<T>;
+
+    pub struct Repeat<A> {
+        element: A,
+    }
+
+    impl<A: Clone> Iterator for Repeat<A> {
+        type Item = A;
+    }
+
+    pub fn repeat<T: Clone>(elt: T) -> Repeat<T>;
+
+    pub struct FromFn<F> {
+        f: F,
+    }
+
+    impl<T, F: FnMut() -> Option<T>> Iterator for FromFn<F> {
+        type Item = T;
+    }
+
+    pub fn from_fn<T, F: FnMut() -> Option<T>>(f: F) -> FromFn<F>;
+
+    pub struct Successors<T, F> {
+        next: Option<T>,
+        succ: F,
+    }
+
+    impl<T, F: FnMut(&T) -> Option<T>> Iterator for Successors<T, F> {
+        type Item = T;
+    }
+
+    pub fn successors<T, F: FnMut(&T) -> Option<T>>(first: Option<T>, succ: F)
+        -> Successors<T, F>;
+}
+
+pub mod convert {
+    pub trait From<T>: Sized {
+        fn from(value: T) -> Self;
+    }
+
+    pub trait Into<T>: Sized {
+        fn into(self) -> T;
+    }
+
+    impl<T, U: From<T>> Into<U> for T {}
+    impl<T> From<T> for T {}
+
+    pub trait TryFrom<T>: Sized {
+        type Error;
+        fn try_from(value: T) -> Result<Self, Self::Error>;
+    }
+
+    pub trait TryInto<T>: Sized {
+        type Error;
+        fn try_into(self) -> Result<T, Self::Error>;
+    }
+
+    impl<T, U: TryFrom<T>> TryInto<U> for T {
+        type Error = U::Error;
+    }
+
+    pub trait AsRef<T: ?Sized> {
+        fn as_ref(&self) -> &T;
+    }
+
+    pub trait AsMut<T: ?Sized> {
+        fn as_mut(&mut self) -> &mut T;
+    }
+
+    impl<'a, T: ?Sized + AsRef<U>, U: ?Sized> AsRef<U> for &'a T {}
+    impl<'a, T: ?Sized + AsRef<U>, U: ?Sized> AsRef<U> for &'a mut T {}
+
+    pub enum Infallible {}
+
+    pub fn identity<T>(x: T) -> T;
+}
+
+pub mod borrow {
+    pub trait Borrow<Borrowed: ?Sized> {
+        fn borrow(&self) -> &Borrowed;
+    }
+
+    pub trait BorrowMut<Borrowed: ?Sized>: Borrow<Borrowed> {
+        fn borrow_mut(&mut self) -> &mut Borrowed;
+    }
+
+    impl<T: ?Sized> Borrow<T> for T {}
+    impl<'a, T: ?Sized> Borrow<T> for &'a T {}
+    impl<'a, T: ?Sized> Borrow<T> for &'a mut T {}
+    impl<T: ?Sized> BorrowMut<T> for T {}
+
+    pub trait ToOwned {
+        type Owned;
+        fn to_owned(&self) -> Self::Owned;
+    }
+
+    impl<T: Clone> ToOwned for T {
+        type Owned = T;
+    }
+
+    pub enum Cow<'a, B: ?Sized + ToOwned> {
+        Borrowed(&'a B),
+        Owned(B::Owned),
+    }
+
+    impl<'a, B: ?Sized + ToOwned> Cow<'a, B> {
+        pub fn into_owned(self) -> B::Owned;
+        pub fn to_mut(&mut self) -> &mut B::Owned;
+    }
+
+    impl<'a, B: ?Sized + ToOwned> super::ops::Deref for Cow<'a, B> {
+        type Target = B;
+    }
+}
+
+pub mod hash {
+    pub trait Hash {
+        fn hash<H: Hasher>(&self, state: &mut H);
+    }
+
+    pub trait Hasher {
+        fn finish(&self) -> u64;
+        fn write(&mut self, bytes: &[u8]);
+    }
+
+    pub trait BuildHasher {
+        type Hasher: Hasher;
+        fn build_hasher(&self) -> Self::Hasher;
+    }
+
+    impl<'a, T: ?Sized + Hash> Hash for &'a T {}
+    impl<'a, T: ?Sized + Hash> Hash for &'a mut T {}
+    impl<T: Hash> Hash for [T] {}
+    impl<T: ?Sized> Hash for *const T {}
+    impl<T: ?Sized> Hash for *mut T {}
+}
+
+pub mod collections {
+    pub use self::hash_map::HashMap;
+    pub use self::hash_set::HashSet;
+    pub use self::btree_map::BTreeMap;
+    pub use self::btree_set::BTreeSet;
+    pub use self::vec_deque::VecDeque;
+    pub use self::binary_heap::BinaryHeap;
+
+    pub mod hash_map {
+        use super::super::borrow::Borrow;
+        use super::super::hash::Hash;
+        use super::super::ops::Index;
+
+        pub struct HashMap<K, V> {
+            keys: Vec<K>,
+            values: Vec<V>,
+        }
+
+        impl<K, V> HashMap<K, V> {
+            pub fn new() -> HashMap<K, V>;
+            pub fn with_capacity(capacity: usize) -> HashMap<K, V>;
+            pub fn capacity(&self) -> usize;
+            pub fn keys(&self) -> Keys<K, V>;
+            pub fn into_keys(self) -> IntoKeys<K, V>;
+            pub fn values(&self) -> Values<K, V>;
+            pub fn values_mut(&mut self) -> ValuesMut<K, V>;
+            pub fn into_values(self) -> IntoValues<K, V>;
+            pub fn iter(&self) -> Iter<K, V>;
+            pub fn iter_mut(&mut self) -> IterMut<K, V>;
+            pub fn len(&self) -> usize;
+            pub fn is_empty(&self) -> bool;
+            pub fn drain(&mut self) -> IntoIter<K, V>;
+            pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, f: F);
+            pub fn clear(&mut self);
+        }
+
+        impl<K: Eq + Hash, V> HashMap<K, V> {
+            pub fn reserve(&mut self, additional: usize);
+            pub fn entry(&mut self, key: K) -> Entry<K, V>;
+            pub fn get<Q: ?Sized + Hash + Eq>(&self, k: &Q) -> Option<&V>
+                where K: Borrow<Q>;
+            pub fn get_key_value<Q: ?Sized + Hash + Eq>(&self, k: &Q)
+                -> Option<(&K, &V)> where K: Borrow<Q>;
+            pub fn contains_key<Q: ?Sized + Hash + Eq>(&self, k: &Q) -> bool
+                where K: Borrow<Q>;
+            pub fn get_mut<Q: ?Sized + Hash + Eq>(&mut self, k: &Q)
+                -> Option<&mut V> where K: Borrow<Q>;
+            pub fn insert(&mut self, k: K, v: V) -> Option<V>;
+            pub fn remove<Q: ?Sized + Hash + Eq>(&mut self, k: &Q)
+                -> Option<V> where K: Borrow<Q>;
+            pub fn remove_entry<Q: ?Sized + Hash + Eq>(&mut self, k: &Q)
+                -> Option<(K, V)> where K: Borrow<Q>;
+        }
+
+        impl<'a, K: Eq + Hash + Borrow<Q>, Q: ?Sized + Eq + Hash, V>
+            Index<&'a Q> for HashMap<K, V>
+        {
+            type Output = V;
+        }
+
+        impl<K: Clone, V: Clone> Clone for HashMap<K, V> {}
+        impl<K, V> Default for HashMap<K, V> {}
+        impl<K: Eq + Hash, V: PartialEq> PartialEq for HashMap<K, V> {}
+        impl<K: Eq + Hash, V: Eq> Eq for HashMap<K, V> {}
+        impl<K: super::super::fmt::Debug, V: super::super::fmt::Debug>
+            super::super::fmt::Debug for HashMap<K, V> {}
+        impl<K: Eq + Hash, V> FromIterator<(K, V)> for HashMap<K, V> {}
+        impl<K: Eq + Hash, V> Extend<(K, V)> for HashMap<K, V> {}
+        impl<K: Eq + Hash, V, const N: usize> From<[(K, V); N]>
+            for HashMap<K, V> {}
+
+        impl<K, V> IntoIterator for HashMap<K, V> {
+            type Item = (K, V);
+            type IntoIter = IntoIter<K, V>;
+        }
+
+        impl<'a, K, V> IntoIterator for &'a HashMap<K, V> {
+            type Item = (&'a K, &'a V);
+            type IntoIter = Iter<'a, K, V>;
+        }
+
+        impl<'a, K, V> IntoIterator for &'a mut HashMap<K, V> {
+            type Item = (&'a K, &'a mut V);
+            type IntoIter = IterMut<'a, K, V>;
+        }
+
+        pub struct Entry<'a, K, V> {
+            map: &'a mut HashMap<K, V>,
+            key: K,
+        }
+
+        impl<'a, K, V> Entry<'a, K, V> {
+            pub fn or_insert(self, default: V) -> &'a mut V;
+            pub fn or_insert_with<F: FnOnce() -> V>(self, default: F)
+                -> &'a mut V;
+            pub fn or_insert_with_key<F: FnOnce(&K) -> V>(self, default: F)
+                -> &'a mut V;
+            pub fn key(&self) -> &K;
+            pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Entry<'a, K, V>;
+            pub fn or_default(self) -> &'a mut V where V: Default;
+        }
+
+        pub struct Iter<'a, K, V> {
+            map: &'a HashMap<K, V>,
+        }
+
+        impl<'a, K, V> Iterator for Iter<'a, K, V> {
+            type Item = (&'a K, &'a V);
+        }
+
+        pub struct IterMut<'a, K, V> {
+            map: &'a mut HashMap<K, V>,
+        }
+
+        impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+            type Item = (&'a K, &'a mut V);
+        }
+
+        pub struct IntoIter<K, V> {
+            map: HashMap<K, V>,
+        }
+
+        impl<K, V> Iterator for IntoIter<K, V> {
+            type Item = (K, V);
+        }
+
+        pub struct Keys<'a, K, V> {
+            map: &'a HashMap<K, V>,
+        }
+
+        impl<'a, K, V> Iterator for Keys<'a, K, V> {
+            type Item = &'a K;
+        }
+
+        pub struct IntoKeys<K, V> {
+            map: HashMap<K, V>,
+        }
+
+        impl<K, V> Iterator for IntoKeys<K, V> {
+            type Item = K;
+        }
+
+        pub struct Values<'a, K, V> {
+            map: &'a HashMap<K, V>,
+        }
+
+        impl<'a, K, V> Iterator for Values<'a, K, V> {
+            type Item = &'a V;
+        }
+
+        pub struct ValuesMut<'a, K, V> {
+            map: &'a mut HashMap<K, V>,
+        }
+
+        impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+            type Item = &'a mut V;
+        }
+
+        pub struct IntoValues<K, V> {
+            map: HashMap<K, V>,
+        }
+
+        impl<K, V> Iterator for IntoValues<K, V> {
+            type Item = V;
+        }
+    }
+
+    pub mod hash_set {
+        use super::super::borrow::Borrow;
+        use super::super::hash::Hash;
+
+        pub struct HashSet<T> {
+            items: Vec<T>,
+        }
+
+        impl<T> HashSet<T> {
+            pub fn new() -> HashSet<T>;
+            pub fn with_capacity(capacity: usize) -> HashSet<T>;
+            pub fn iter(&self) -> Iter<T>;
+            pub fn len(&self) -> usize;
+            pub fn is_empty(&self) -> bool;
+            pub fn drain(&mut self) -> IntoIter<T>;
+            pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F);
+            pub fn clear(&mut self);
+        }
+
+        impl<T: Eq + Hash> HashSet<T> {
+            pub fn contains<Q: ?Sized + Hash + Eq>(&self, value: &Q) -> bool
+                where T: Borrow<Q>;
+            pub fn get<Q: ?Sized + Hash + Eq>(&self, value: &Q) -> Option<&T>
+                where T: Borrow<Q>;
+            pub fn insert(&mut self, value: T) -> bool;
+            pub fn remove<Q: ?Sized + Hash + Eq>(&mut self, value: &Q) -> bool
+                where T: Borrow<Q>;
+            pub fn take<Q: ?Sized + Hash + Eq>(&mut self, value: &Q)
+                -> Option<T> where T: Borrow<Q>;
+            pub fn is_subset(&self, other: &HashSet<T>) -> bool;
+            pub fn is_superset(&self, other: &HashSet<T>) -> bool;
+            pub fn is_disjoint(&self, other: &HashSet<T>) -> bool;
+            pub fn union<'a>(&'a self, other: &'a HashSet<T>) -> Iter<'a, T>;
+            pub fn intersection<'a>(&'a self, other: &'a HashSet<T>)
+                -> Iter<'a, T>;
+            pub fn difference<'a>(&'a self, other: &'a HashSet<T>)
+                -> Iter<'a, T>;
+            pub fn symmetric_difference<'a>(&'a self, other: &'a HashSet<T>)
+                -> Iter<'a, T>;
+        }
+
+        impl<T: Clone> Clone for HashSet<T> {}
+        impl<T> Default for HashSet<T> {}
+        impl<T: Eq + Hash> PartialEq for HashSet<T> {}
+        impl<T: Eq + Hash> Eq for HashSet<T> {}
+        impl<T: super::super::fmt::Debug> super::super::fmt::Debug
+            for HashSet<T> {}
+        impl<T: Eq + Hash> FromIterator<T> for HashSet<T> {}
+        impl<T: Eq + Hash> Extend<T> for HashSet<T> {}
+        impl<'a, T: 'a + Eq + Hash + Copy> Extend<&'a T> for HashSet<T> {}
+        impl<T: Eq + Hash, const N: usize> From<[T; N]> for HashSet<T> {}
+
+        impl<T> IntoIterator for HashSet<T> {
+            type Item = T;
+            type IntoIter = IntoIter<T>;
+        }
+
+        impl<'a, T> IntoIterator for &'a HashSet<T> {
+            type Item = &'a T;
+            type IntoIter = Iter<'a, T>;
+        }
+
+        pub struct Iter<'a, T> {
+            set: &'a HashSet<T>,
+        }
+
+        impl<'a, T> Iterator for Iter<'a, T> {
+            type Item = &'a T;
+        }
+
+        pub struct IntoIter<T> {
+            set: HashSet<T>,
+        }
+
+        impl<T> Iterator for IntoIter<T> {
+            type Item = T;
+        }
+    }
+
+    pub mod btree_map {
+        use super::super::borrow::Borrow;
+        use super::super::ops::Index;
+
+        pub struct BTreeMap<K, V> {
+            keys: Vec<K>,
+            values: Vec<V>,
+        }
+
+        impl<K, V> BTreeMap<K, V> {
+            pub const fn new() -> BTreeMap<K, V>;
+            pub fn keys(&self) -> Keys<K, V>;
+            pub fn into_keys(self) -> IntoKeys<K, V>;
+            pub fn values(&self) -> Values<K, V>;
+            pub fn values_mut(&mut self) -> ValuesMut<K, V>;
+            pub fn into_values(self) -> IntoValues<K, V>;
+            pub fn iter(&self) -> Iter<K, V>;
+            pub fn iter_mut(&mut self) -> IterMut<K, V>;
+            pub fn len(&self) -> usize;
+            pub fn is_empty(&self) -> bool;
+            pub fn clear(&mut self);
+        }
+
+        impl<K: Ord, V> BTreeMap<K, V> {
+            pub fn entry(&mut self, key: K) -> Entry<K, V>;
+            pub fn get<Q: ?Sized + Ord>(&self, key: &Q) -> Option<&V>
+                where K: Borrow<Q>;
+            pub fn contains_key<Q: ?Sized + Ord>(&self, key: &Q) -> bool
+                where K: Borrow<Q>;
+            pub fn get_mut<Q: ?Sized + Ord>(&mut self, key: &Q)
+                -> Option<&mut V> where K: Borrow<Q>;
+            pub fn insert(&mut self, key: K, value: V) -> Option<V>;
+            pub fn remove<Q: ?Sized + Ord>(&mut self, key: &Q) -> Option<V>
+                where K: Borrow<Q>;
+            pub fn first_key_value(&self) -> Option<(&K, &V)>;
+            pub fn last_key_value(&self) -> Option<(&K, &V)>;
+            pub fn pop_first(&mut self) -> Option<(K, V)>;
+            pub fn pop_last(&mut self) -> Option<(K, V)>;
+            pub fn range<R>(&self, range: R) -> Iter<K, V>;
+            pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, f: F);
+        }
+
+        impl<'a, K: Ord + Borrow<Q>, Q: ?Sized + Ord, V> Index<&'a Q>
+            for BTreeMap<K, V>
+        {
+            type Output = V;
+        }
+
+        impl<K: Clone, V: Clone> Clone for BTreeMap<K, V> {}
+        impl<K, V> Default for BTreeMap<K, V> {}
+        impl<K: PartialEq, V: PartialEq> PartialEq for BTreeMap<K, V> {}
+        impl<K: Eq, V: Eq> Eq for BTreeMap<K, V> {}
+        impl<K: super::super::fmt::Debug, V: super::super::fmt::Debug>
+            super::super::fmt::Debug for BTreeMap<K, V> {}
+        impl<K: Ord, V> FromIterator<(K, V)> for BTreeMap<K, V> {}
+        impl<K: Ord, V> Extend<(K, V)> for BTreeMap<K, V> {}
+
+        impl<K, V> IntoIterator for BTreeMap<K, V> {
+            type Item = (K, V);
+            type IntoIter = IntoIter<K, V>;
+        }
+
+        impl<'a, K, V> IntoIterator for &'a BTreeMap<K, V> {
+            type Item = (&'a K, &'a V);
+            type IntoIter = Iter<'a, K, V>;
+        }
+
+        impl<'a, K, V> IntoIterator for &'a mut BTreeMap<K, V> {
+            type Item = (&'a K, &'a mut V);
+            type IntoIter = IterMut<'a, K, V>;
+        }
+
+        pub struct Entry<'a, K, V> {
+            map: &'a mut BTreeMap<K, V>,
+            key: K,
+        }
+
+        impl<'a, K: Ord, V> Entry<'a, K, V> {
+            pub fn or_insert(self, default: V) -> &'a mut V;
+            pub fn or_insert_with<F: FnOnce() -> V>(self, default: F)
+                -> &'a mut V;
+            pub fn key(&self) -> &K;
+            pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Entry<'a, K, V>;
+            pub fn or_default(self) -> &'a mut V where V: Default;
+        }
+
+        pub struct Iter<'a, K, V> {
+            map: &'a BTreeMap<K, V>,
+        }
+
+        impl<'a, K, V> Iterator for Iter<'a, K, V> {
+            type Item = (&'a K, &'a V);
+        }
+
+        impl<'a, K, V> DoubleEndedIterator for Iter<'a, K, V> {}
+
+        pub struct IterMut<'a, K, V> {
+            map: &'a mut BTreeMap<K, V>,
+        }
+
+        impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+            type Item = (&'a K, &'a mut V);
+        }
+
+        pub struct IntoIter<K, V> {
+            map: BTreeMap<K, V>,
+        }
+
+        impl<K, V> Iterator for IntoIter<K, V> {
+            type Item = (K, V);
+        }
+
+        pub struct Keys<'a, K, V> {
+            map: &'a BTreeMap<K, V>,
+        }
+
+        impl<'a, K, V> Iterator for Keys<'a, K, V> {
+            type Item = &'a K;
+        }
+
+        impl<'a, K, V> DoubleEndedIterator for Keys<'a, K, V> {}
+
+        pub struct IntoKeys<K, V> {
+            map: BTreeMap<K, V>,
+        }
+
+        impl<K, V> Iterator for IntoKeys<K, V> {
+            type Item = K;
+        }
+
+        pub struct Values<'a, K, V> {
+            map: &'a BTreeMap<K, V>,
+        }
+
+        impl<'a, K, V> Iterator for Values<'a, K, V> {
+            type Item = &'a V;
+        }
+
+        pub struct ValuesMut<'a, K, V> {
+            map: &'a mut BTreeMap<K, V>,
+        }
+
+        impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+            type Item = &'a mut V;
+        }
+
+        pub struct IntoValues<K, V> {
+            map: BTreeMap<K, V>,
+        }
+
+        impl<K, V> Iterator for IntoValues<K, V> {
+            type Item = V;
+        }
+    }
+
+    pub mod btree_set {
+        use super::super::borrow::Borrow;
+
+        pub struct BTreeSet<T> {
+            items: Vec<T>,
+        }
+
+        impl<T> BTreeSet<T> {
+            pub const fn new() -> BTreeSet<T>;
+            pub fn iter(&self) -> Iter<T>;
+            pub fn len(&self) -> usize;
+            pub fn is_empty(&self) -> bool;
+            pub fn clear(&mut self);
+        }
+
+        impl<T: Ord> BTreeSet<T> {
+            pub fn contains<Q: ?Sized + Ord>(&self, value: &Q) -> bool
+                where T: Borrow<Q>;
+            pub fn insert(&mut self, value: T) -> bool;
+            pub fn remove<Q: ?Sized + Ord>(&mut self, value: &Q) -> bool
+                where T: Borrow<Q>;
+            pub fn first(&self) -> Option<&T>;
+            pub fn last(&self) -> Option<&T>;
+            pub fn pop_first(&mut self) -> Option<T>;
+            pub fn pop_last(&mut self) -> Option<T>;
+            pub fn range<R>(&self, range: R) -> Iter<T>;
+            pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F);
+        }
+
+        impl<T: Clone> Clone for BTreeSet<T> {}
+        impl<T> Default for BTreeSet<T> {}
+        impl<T: PartialEq> PartialEq for BTreeSet<T> {}
+        impl<T: Eq> Eq for BTreeSet<T> {}
+        impl<T: super::super::fmt::Debug> super::super::fmt::Debug
+            for BTreeSet<T> {}
+        impl<T: Ord> FromIterator<T> for BTreeSet<T> {}
+        impl<T: Ord> Extend<T> for BTreeSet<T> {}
+
+        impl<T> IntoIterator for BTreeSet<T> {
+            type Item = T;
+            type IntoIter = IntoIter<T>;
+        }
+
+        impl<'a, T> IntoIterator for &'a BTreeSet<T> {
+            type Item = &'a T;
+            type IntoIter = Iter<'a, T>;
+        }
+
+        pub struct Iter<'a, T> {
+            set: &'a BTreeSet<T>,
+        }
+
+        impl<'a, T> Iterator for Iter<'a, T> {
+            type Item = &'a T;
+        }
+
+        impl<'a, T> DoubleEndedIterator for Iter<'a, T> {}
+
+        pub struct IntoIter<T> {
+            set: BTreeSet<T>,
+        }
+
+        impl<T> Iterator for IntoIter<T> {
+            type Item = T;
+        }
+    }
+
+    pub mod vec_deque {
+        use super::super::ops::{Index, IndexMut};
+
+        pub struct VecDeque<T> {
+            buf: Vec<T>,
+        }
+
+        impl<T> VecDeque<T> {
+            pub const fn new() -> VecDeque<T>;
+            pub fn with_capacity(capacity: usize) -> VecDeque<T>;
+            pub fn get(&self, index: usize) -> Option<&T>;
+            pub fn get_mut(&mut self, index: usize) -> Option<&mut T>;
+            pub fn len(&self) -> usize;
+            pub fn is_empty(&self) -> bool;
+            pub fn iter(&self) -> Iter<T>;
+            pub fn iter_mut(&mut self) -> IterMut<T>;
+            pub fn clear(&mut self);
+            pub fn front(&self) -> Option<&T>;
+            pub fn front_mut(&mut self) -> Option<&mut T>;
+            pub fn back(&self) -> Option<&T>;
+            pub fn back_mut(&mut self) -> Option<&mut T>;
+            pub fn pop_front(&mut self) -> Option<T>;
+            pub fn pop_back(&mut self) -> Option<T>;
+            pub fn push_front(&mut self, value: T);
+            pub fn push_back(&mut self, value: T);
+            pub fn insert(&mut self, index: usize, value: T);
+            pub fn remove(&mut self, index: usize) -> Option<T>;
+            pub fn truncate(&mut self, len: usize);
+            pub fn make_contiguous(&mut self) -> &mut [T];
+            pub fn contains(&self, x: &T) -> bool where T: PartialEq;
+        }
+
+        impl<T> Index<usize> for VecDeque<T> {
+            type Output = T;
+        }
+
+        impl<T> IndexMut<usize> for VecDeque<T> {}
+        impl<T: Clone> Clone for VecDeque<T> {}
+        impl<T> Default for VecDeque<T> {}
+        impl<T: PartialEq> PartialEq for VecDeque<T> {}
+        impl<T: Eq> Eq for VecDeque<T> {}
+        impl<T: super::super::fmt::Debug> super::super::fmt::Debug
+            for VecDeque<T> {}
+        impl<T> FromIterator<T> for VecDeque<T> {}
+        impl<T> Extend<T> for VecDeque<T> {}
+        impl<T> From<Vec<T>> for VecDeque<T> {}
+
+        impl<T> IntoIterator for VecDeque<T> {
+            type Item = T;
+            type IntoIter = IntoIter<T>;
+        }
+
+        impl<'a, T> IntoIterator for &'a VecDeque<T> {
+            type Item = &'a T;
+            type IntoIter = Iter<'a, T>;
+        }
+
+        pub struct Iter<'a, T> {
+            deque: &'a VecDeque<T>,
+        }
+
+        impl<'a, T> Iterator for Iter<'a, T> {
+            type Item = &'a T;
+        }
+
+        impl<'a, T> DoubleEndedIterator for Iter<'a, T> {}
+
+        pub struct IterMut<'a, T> {
+            deque: &'a mut VecDeque<T>,
+        }
+
+        impl<'a, T> Iterator for IterMut<'a, T> {
+            type Item = &'a mut T;
+        }
+
+        pub struct IntoIter<T> {
+            deque: VecDeque<T>,
+        }
+
+        impl<T> Iterator for IntoIter<T> {
+            type Item = T;
+        }
+    }
+
+    pub mod binary_heap {
+        pub struct BinaryHeap<T> {
+            data: Vec<T>,
+        }
+
+        impl<T: Ord> BinaryHeap<T> {
+            pub fn new() -> BinaryHeap<T>;
+            pub fn with_capacity(capacity: usize) -> BinaryHeap<T>;
+            pub fn push(&mut self, item: T);
+            pub fn pop(&mut self) -> Option<T>;
+            pub fn into_sorted_vec(self) -> Vec<T>;
+        }
+
+        impl<T> BinaryHeap<T> {
+            pub fn peek(&self) -> Option<&T>;
+            pub fn len(&self) -> usize;
+            pub fn is_empty(&self) -> bool;
+            pub fn iter(&self) -> super::super::slice::Iter<T>;
+            pub fn into_vec(self) -> Vec<T>;
+            pub fn clear(&mut self);
+        }
+
+        impl<T: Clone> Clone for BinaryHeap<T> {}
+        impl<T: Ord> Default for BinaryHeap<T> {}
+        impl<T: super::super::fmt::Debug> super::super::fmt::Debug
+            for BinaryHeap<T> {}
+        impl<T: Ord> FromIterator<T> for BinaryHeap<T> {}
+        impl<T: Ord> Extend<T> for BinaryHeap<T> {}
+        impl<T: Ord> From<Vec<T>> for BinaryHeap<T> {}
+
+        impl<T> IntoIterator for BinaryHeap<T> {
+            type Item = T;
+            type IntoIter = super::super::vec::IntoIter<T>;
+        }
+    }
+}
+
+pub mod rc {
+    use super::ops::Deref;
+
+    pub struct Rc<T: ?Sized> {
+        ptr: *mut T,
+    }
+
+    impl<T> Rc<T> {
+        pub fn new(value: T) -> Rc<T>;
+        pub fn try_unwrap(this: Rc<T>) -> Result<T, Rc<T>>;
+    }
+
+    impl<T: ?Sized> Rc<T> {
+        pub fn strong_count(this: &Rc<T>) -> usize;
+        pub fn ptr_eq(this: &Rc<T>, other: &Rc<T>) -> bool;
+        pub fn get_mut(this: &mut Rc<T>) -> Option<&mut T>;
+        pub fn downgrade(this: &Rc<T>) -> Weak<T>;
+    }
+
+    impl<T: Clone> Rc<T> {
+        pub fn make_mut(this: &mut Rc<T>) -> &mut T;
+    }
+
+    impl<T: ?Sized> Deref for Rc<T> {
+        type Target = T;
+    }
+
+    impl<T: ?Sized> Clone for Rc<T> {}
+    impl<T: ?Sized + PartialEq> PartialEq for Rc<T> {}
+    impl<T: ?Sized + Eq> Eq for Rc<T> {}
+    impl<T: ?Sized + PartialOrd> PartialOrd for Rc<T> {}
+    impl<T: ?Sized + Ord> Ord for Rc<T> {}
+    impl<T: Default> Default for Rc<T> {}
+    impl<T: ?Sized + super::hash::Hash> super::hash::Hash for Rc<T> {}
+    impl<T: ?Sized + super::fmt::Debug> super::fmt::Debug for Rc<T> {}
+    impl<T: ?Sized + super::fmt::Display> super::fmt::Display for Rc<T> {}
+    impl<T> From<T> for Rc<T> {}
+
+    pub struct Weak<T: ?Sized> {
+        ptr: *mut T,
+    }
+
+    impl<T> Weak<T> {
+        pub const fn new() -> Weak<T>;
+    }
+
+    impl<T: ?Sized> Weak<T> {
+        pub fn upgrade(&self) -> Option<Rc<T>>;
+    }
+
+    impl<T: ?Sized> Clone for Weak<T> {}
+}
+
+pub mod cell {
+    use super::ops::{Deref, DerefMut};
+
+    pub struct Cell<T: ?Sized> {
+        value: T,
+    }
+
+    impl<T> Cell<T> {
+        pub const fn new(value: T) -> Cell<T>;
+        pub fn set(&self, val: T);
+        pub fn replace(&self, val: T) -> T;
+        pub fn into_inner(self) -> T;
+        pub fn take(&self) -> T where T: Default;
+    }
+
+    impl<T: Copy> Cell<T> {
+        pub fn get(&self) -> T;
+    }
+
+    impl<T: ?Sized> Cell<T> {
+        pub fn get_mut(&mut self) -> &mut T;
+    }
+
+    impl<T: Copy> Clone for Cell<T> {}
+    impl<T: Default> Default for Cell<T> {}
+
+    pub struct RefCell<T: ?Sized> {
+        value: T,
+    }
+
+    impl<T> RefCell<T> {
+        pub const fn new(value: T) -> RefCell<T>;
+        pub fn into_inner(self) -> T;
+        pub fn replace(&self, t: T) -> T;
+        pub fn take(&self) -> T where T: Default;
+    }
+
+    impl<T: ?Sized> RefCell<T> {
+        pub fn borrow(&self) -> Ref<T>;
+        pub fn borrow_mut(&self) -> RefMut<T>;
+        pub fn get_mut(&mut self) -> &mut T;
+    }
+
+    impl<T: Clone> Clone for RefCell<T> {}
+    impl<T: Default> Default for RefCell<T> {}
+    impl<T: ?Sized + super::fmt::Debug> super::fmt::Debug for RefCell<T> {}
+
+    pub struct Ref<'b, T: ?Sized> {
+        value: &'b T,
+    }
+
+    impl<'b, T: ?Sized> Deref for Ref<'b, T> {
+        type Target = T;
+    }
+
+    pub struct RefMut<'b, T: ?Sized> {
+        value: &'b mut T,
+    }
+
+    impl<'b, T: ?Sized> Deref for RefMut<'b, T> {
+        type Target = T;
+    }
+
+    impl<'b, T: ?Sized> DerefMut for RefMut<'b, T> {}
+
+    pub struct OnceCell<T> {
+        inner: Option<T>,
+    }
+
+    impl<T> OnceCell<T> {
+        pub const fn new() -> OnceCell<T>;
+        pub fn get(&self) -> Option<&T>;
+        pub fn set(&self, value: T) -> Result<(), T>;
+        pub fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> &T;
+    }
+}
+
+pub mod sync {
+    use super::ops::{Deref, DerefMut};
+
+    pub struct Arc<T: ?Sized> {
+        ptr: *mut T,
+    }
+
+    impl<T> Arc<T> {
+        pub fn new(data: T) -> Arc<T>;
+        pub fn try_unwrap(this: Arc<T>) -> Result<T, Arc<T>>;
+    }
+
+    impl<T: ?Sized> Arc<T> {
+        pub fn strong_count(this: &Arc<T>) -> usize;
+        pub fn ptr_eq(this: &Arc<T>, other: &Arc<T>) -> bool;
+        pub fn get_mut(this: &mut Arc<T>) -> Option<&mut T>;
+    }
+
+    impl<T: ?Sized> Deref for Arc<T> {
+        type Target = T;
+    }
+
+    impl<T: ?Sized> Clone for Arc<T> {}
+    impl<T: ?Sized + PartialEq> PartialEq for Arc<T> {}
+    impl<T: Default> Default for Arc<T> {}
+    impl<T: ?Sized + super::fmt::Debug> super::fmt::Debug for Arc<T> {}
+    impl<T: ?Sized + super::fmt::Display> super::fmt::Display for Arc<T> {}
+    impl<T> From<T> for Arc<T> {}
+
+    pub struct PoisonError<T> {
+        guard: T,
+    }
+
+    impl<T> PoisonError<T> {
+        pub fn into_inner(self) -> T;
+    }
+
+    pub type LockResult<Guard> = Result<Guard, PoisonError<Guard>>;
+
+    pub struct Mutex<T: ?Sized> {
+        data: T,
+    }
+
+    impl<T> Mutex<T> {
+        pub const fn new(t: T) -> Mutex<T>;
+        pub fn into_inner(self) -> LockResult<T>;
+    }
+
+    impl<T: ?Sized> Mutex<T> {
+        pub fn lock(&self) -> LockResult<MutexGuard<T>>;
+        pub fn get_mut(&mut self) -> LockResult<&mut T>;
+    }
+
+    impl<T: Default> Default for Mutex<T> {}
+
+    pub struct MutexGuard<'a, T: ?Sized> {
+        lock: &'a Mutex<T>,
+    }
+
+    impl<'a, T: ?Sized> Deref for MutexGuard<'a, T> {
+        type Target = T;
+    }
+
+    impl<'a, T: ?Sized> DerefMut for MutexGuard<'a, T> {}
+
+    pub struct RwLock<T: ?Sized> {
+        data: T,
+    }
+
+    impl<T> RwLock<T> {
+        pub const fn new(t: T) -> RwLock<T>;
+    }
+
+    impl<T: ?Sized> RwLock<T> {
+        pub fn read(&self) -> LockResult<RwLockReadGuard<T>>;
+        pub fn write(&self) -> LockResult<RwLockWriteGuard<T>>;
+    }
+
+    pub struct RwLockReadGuard<'a, T: ?Sized> {
+        lock: &'a RwLock<T>,
+    }
+
+    impl<'a, T: ?Sized> Deref for RwLockReadGuard<'a, T> {
+        type Target = T;
+    }
+
+    pub struct RwLockWriteGuard<'a, T: ?Sized> {
+        lock: &'a RwLock<T>,
+    }
+
+    impl<'a, T: ?Sized> Deref for RwLockWriteGuard<'a, T> {
+        type Target = T;
+    }
+
+    impl<'a, T: ?Sized> DerefMut for RwLockWriteGuard<'a, T> {}
+}
+
+pub mod mem {
+    pub fn swap<T>(x: &mut T, y: &mut T);
+    pub fn replace<T>(dest: &mut T, src: T) -> T;
+    pub fn take<T: Default>(dest: &mut T) -> T;
+    pub fn drop<T>(_x: T);
+    pub fn forget<T>(t: T);
+    pub const fn size_of<T>() -> usize;
+    pub const fn align_of<T>() -> usize;
+    pub fn size_of_val<T: ?Sized>(val: &T) -> usize;
+    pub unsafe fn transmute<Src, Dst>(src: Src) -> Dst;
+    pub unsafe fn zeroed<T>() -> T;
+}
+
+pub mod ptr {
+    pub const fn null<T>() -> *const T;
+    pub const fn null_mut<T>() -> *mut T;
+    pub fn eq<T: ?Sized>(a: *const T, b: *const T) -> bool;
+    pub unsafe fn read<T>(src: *const T) -> T;
+    pub unsafe fn write<T>(dst: *mut T, src: T);
+    pub unsafe fn copy<T>(src: *const T, dst: *mut T, count: usize);
+    pub unsafe fn copy_nonoverlapping<T>(src: *const T, dst: *mut T,
+        count: usize);
+    pub unsafe fn drop_in_place<T: ?Sized>(to_drop: *mut T);
+}
+
+pub mod process {
+    pub fn exit(code: i32) -> !;
+    pub fn abort() -> !;
+}
+
+pub mod panic {
+    pub fn panic_any<M: 'static + Send>(msg: M) -> !;
+}
+
+pub mod num {
+    pub struct ParseIntError;
+    pub struct ParseFloatError;
+    pub struct TryFromIntError;
+
+    impl super::fmt::Debug for ParseIntError {}
+    impl super::fmt::Display for ParseIntError {}
+    impl super::fmt::Debug for ParseFloatError {}
+    impl super::fmt::Display for ParseFloatError {}
+    impl super::fmt::Debug for TryFromIntError {}
+    impl super::fmt::Display for TryFromIntError {}
+    impl PartialEq for ParseIntError {}
+    impl Clone for ParseIntError {}
+}
+
+pub mod env {
+    pub struct Args {
+        inner: Vec<String>,
+    }
+
+    impl Iterator for Args {
+        type Item = String;
+    }
+
+    impl DoubleEndedIterator for Args {}
+    impl ExactSizeIterator for Args {}
+
+    pub fn args() -> Args;
+
+    pub struct VarError;
+
+    pub fn var(key: &str) -> Result<String, VarError>;
+}
+
+pub mod io {
+    pub struct Error;
+
+    impl super::fmt::Debug for Error {}
+    impl super::fmt::Display for Error {}
+
+    pub type Result<T> = super::result::Result<T, Error>;
+
+    pub trait Read {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+        fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize> {}
+        fn read_to_string(&mut self, buf: &mut String) -> Result<usize> {}
+    }
+
+    pub trait Write {
+        fn write(&mut self, buf: &[u8]) -> Result<usize>;
+        fn write_all(&mut self, buf: &[u8]) -> Result<()> {}
+        fn write_fmt(&mut self, fmt: super::fmt::Arguments) -> Result<()> {}
+        fn flush(&mut self) -> Result<()>;
+    }
+
+    pub trait BufRead: Read {
+        fn read_line(&mut self, buf: &mut String) -> Result<usize> {}
+        fn lines(self) -> Lines<Self> where Self: Sized {}
+    }
+
+    pub struct Lines<B> {
+        buf: B,
+    }
+
+    impl<B: BufRead> Iterator for Lines<B> {
+        type Item = Result<String>;
+    }
+
+    pub struct Stdin;
+
+    impl Stdin {
+        pub fn lock(&self) -> StdinLock;
+        pub fn read_line(&self, buf: &mut String) -> Result<usize>;
+        pub fn lines(self) -> Lines<StdinLock<'static>>;
+    }
+
+    impl Read for Stdin {}
+
+    pub struct StdinLock<'a> {
+        inner: &'a Stdin,
+    }
+
+    impl<'a> Read for StdinLock<'a> {}
+    impl<'a> BufRead for StdinLock<'a> {}
+
+    pub fn stdin() -> Stdin;
+
+    pub struct Stdout;
+
+    impl Stdout {
+        pub fn lock(&self) -> StdoutLock;
+    }
+
+    impl Write for Stdout {}
+
+    pub struct StdoutLock<'a> {
+        inner: &'a Stdout,
+    }
+
+    impl<'a> Write for StdoutLock<'a> {}
+
+    pub fn stdout() -> Stdout;
+
+    pub struct Stderr;
+
+    impl Write for Stderr {}
+
+    pub fn stderr() -> Stderr;
+
+    pub struct BufReader<R> {
+        inner: R,
+    }
+
+    impl<R: Read> BufReader<R> {
+        pub fn new(inner: R) -> BufReader<R>;
+    }
+
+    impl<R: Read> Read for BufReader<R> {}
+    impl<R: Read> BufRead for BufReader<R> {}
+
+    pub struct BufWriter<W: Write> {
+        inner: W,
+    }
+
+    impl<W: Write> BufWriter<W> {
+        pub fn new(inner: W) -> BufWriter<W>;
+    }
+
+    impl<W: Write> Write for BufWriter<W> {}
+    impl<'a, W: Write + ?Sized> Write for &'a mut W {}
+    impl Write for Vec<u8> {}
+}
+
+pub mod f32 {
+    pub mod consts {
+        pub const PI: f32 = 3.14159265358979323846264338327950288;
+        pub const TAU: f32 = 6.28318530717958647692528676655900577;
+        pub const E: f32 = 2.71828182845904523536028747135266250;
+        pub const SQRT_2: f32 = 1.41421356237309504880168872420969808;
+        pub const LN_2: f32 = 0.693147180559945309417232121458176568;
+        pub const LN_10: f32 = 2.30258509299404568401799145468436421;
+    }
+}
+
+pub mod f64 {
+    pub mod consts {
+        pub const PI: f64 = 3.14159265358979323846264338327950288;
+        pub const TAU: f64 = 6.28318530717958647692528676655900577;
+        pub const E: f64 = 2.71828182845904523536028747135266250;
+        pub const SQRT_2: f64 = 1.41421356237309504880168872420969808;
+        pub const LN_2: f64 = 0.693147180559945309417232121458176568;
+        pub const LN_10: f64 = 2.30258509299404568401799145468436421;
+    }
+}
+
+pub mod primitive {
+    use super::cmp::Ordering;
+    use super::fmt::{Binary, Debug, Display, LowerHex, UpperHex};
+    use super::hash::Hash;
+    use super::iter::{Product, Step, Sum};
+    use super::num::{ParseFloatError, ParseIntError, TryFromIntError};
+    use super::ops::*;
+    use super::str::{FromStr, ParseBoolError};
+
+    impl i8 {
+        pub const MIN: i8 = -128;
+        pub const MAX: i8 = 127;
+        pub const BITS: u32 = 8;
+        pub const fn count_ones(self) -> u32;
+        pub const fn count_zeros(self) -> u32;
+        pub const fn leading_zeros(self) -> u32;
+        pub const fn trailing_zeros(self) -> u32;
+        pub const fn rotate_left(self, n: u32) -> Self;
+        pub const fn rotate_right(self, n: u32) -> Self;
+        pub const fn swap_bytes(self) -> Self;
+        pub const fn reverse_bits(self) -> Self;
+        pub const fn checked_add(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_sub(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_mul(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_div(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_rem(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_neg(self) -> Option<Self>;
+        pub const fn checked_pow(self, exp: u32) -> Option<Self>;
+        pub const fn saturating_add(self, rhs: Self) -> Self;
+        pub const fn saturating_sub(self, rhs: Self) -> Self;
+        pub const fn saturating_mul(self, rhs: Self) -> Self;
+        pub const fn saturating_pow(self, exp: u32) -> Self;
+        pub const fn wrapping_add(self, rhs: Self) -> Self;
+        pub const fn wrapping_sub(self, rhs: Self) -> Self;
+        pub const fn wrapping_mul(self, rhs: Self) -> Self;
+        pub const fn wrapping_div(self, rhs: Self) -> Self;
+        pub const fn wrapping_rem(self, rhs: Self) -> Self;
+        pub const fn wrapping_neg(self) -> Self;
+        pub const fn wrapping_shl(self, rhs: u32) -> Self;
+        pub const fn wrapping_shr(self, rhs: u32) -> Self;
+        pub const fn wrapping_pow(self, exp: u32) -> Self;
+        pub const fn overflowing_add(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_mul(self, rhs: Self) -> (Self, bool);
+        pub const fn pow(self, exp: u32) -> Self;
+        pub const fn isqrt(self) -> Self;
+        pub const fn ilog2(self) -> u32;
+        pub const fn ilog10(self) -> u32;
+        pub const fn div_euclid(self, rhs: Self) -> Self;
+        pub const fn rem_euclid(self, rhs: Self) -> Self;
+        pub const fn to_be(self) -> Self;
+        pub const fn to_le(self) -> Self;
+        pub fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+        pub const fn min_value() -> Self;
+        pub const fn max_value() -> Self;
+        pub const fn abs(self) -> Self;
+        pub const fn signum(self) -> Self;
+        pub const fn is_positive(self) -> bool;
+        pub const fn is_negative(self) -> bool;
+        pub const fn checked_abs(self) -> Option<Self>;
+        pub const fn wrapping_abs(self) -> Self;
+        pub const fn unsigned_abs(self) -> u8;
+        pub const fn abs_diff(self, other: Self) -> u8;
+        pub const fn to_le_bytes(self) -> [u8; 1];
+        pub const fn to_be_bytes(self) -> [u8; 1];
+        pub const fn to_ne_bytes(self) -> [u8; 1];
+        pub const fn from_le_bytes(bytes: [u8; 1]) -> Self;
+        pub const fn from_be_bytes(bytes: [u8; 1]) -> Self;
+        pub const fn from_ne_bytes(bytes: [u8; 1]) -> Self;
+    }
+
+    impl i16 {
+        pub const MIN: i16 = -32768;
+        pub const MAX: i16 = 32767;
+        pub const BITS: u32 = 16;
+        pub const fn count_ones(self) -> u32;
+        pub const fn count_zeros(self) -> u32;
+        pub const fn leading_zeros(self) -> u32;
+        pub const fn trailing_zeros(self) -> u32;
+        pub const fn rotate_left(self, n: u32) -> Self;
+        pub const fn rotate_right(self, n: u32) -> Self;
+        pub const fn swap_bytes(self) -> Self;
+        pub const fn reverse_bits(self) -> Self;
+        pub const fn checked_add(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_sub(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_mul(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_div(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_rem(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_neg(self) -> Option<Self>;
+        pub const fn checked_pow(self, exp: u32) -> Option<Self>;
+        pub const fn saturating_add(self, rhs: Self) -> Self;
+        pub const fn saturating_sub(self, rhs: Self) -> Self;
+        pub const fn saturating_mul(self, rhs: Self) -> Self;
+        pub const fn saturating_pow(self, exp: u32) -> Self;
+        pub const fn wrapping_add(self, rhs: Self) -> Self;
+        pub const fn wrapping_sub(self, rhs: Self) -> Self;
+        pub const fn wrapping_mul(self, rhs: Self) -> Self;
+        pub const fn wrapping_div(self, rhs: Self) -> Self;
+        pub const fn wrapping_rem(self, rhs: Self) -> Self;
+        pub const fn wrapping_neg(self) -> Self;
+        pub const fn wrapping_shl(self, rhs: u32) -> Self;
+        pub const fn wrapping_shr(self, rhs: u32) -> Self;
+        pub const fn wrapping_pow(self, exp: u32) -> Self;
+        pub const fn overflowing_add(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_mul(self, rhs: Self) -> (Self, bool);
+        pub const fn pow(self, exp: u32) -> Self;
+        pub const fn isqrt(self) -> Self;
+        pub const fn ilog2(self) -> u32;
+        pub const fn ilog10(self) -> u32;
+        pub const fn div_euclid(self, rhs: Self) -> Self;
+        pub const fn rem_euclid(self, rhs: Self) -> Self;
+        pub const fn to_be(self) -> Self;
+        pub const fn to_le(self) -> Self;
+        pub fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+        pub const fn min_value() -> Self;
+        pub const fn max_value() -> Self;
+        pub const fn abs(self) -> Self;
+        pub const fn signum(self) -> Self;
+        pub const fn is_positive(self) -> bool;
+        pub const fn is_negative(self) -> bool;
+        pub const fn checked_abs(self) -> Option<Self>;
+        pub const fn wrapping_abs(self) -> Self;
+        pub const fn unsigned_abs(self) -> u16;
+        pub const fn abs_diff(self, other: Self) -> u16;
+        pub const fn to_le_bytes(self) -> [u8; 2];
+        pub const fn to_be_bytes(self) -> [u8; 2];
+        pub const fn to_ne_bytes(self) -> [u8; 2];
+        pub const fn from_le_bytes(bytes: [u8; 2]) -> Self;
+        pub const fn from_be_bytes(bytes: [u8; 2]) -> Self;
+        pub const fn from_ne_bytes(bytes: [u8; 2]) -> Self;
+    }
+
+    impl i32 {
+        pub const MIN: i32 = -2147483648;
+        pub const MAX: i32 = 2147483647;
+        pub const BITS: u32 = 32;
+        pub const fn count_ones(self) -> u32;
+        pub const fn count_zeros(self) -> u32;
+        pub const fn leading_zeros(self) -> u32;
+        pub const fn trailing_zeros(self) -> u32;
+        pub const fn rotate_left(self, n: u32) -> Self;
+        pub const fn rotate_right(self, n: u32) -> Self;
+        pub const fn swap_bytes(self) -> Self;
+        pub const fn reverse_bits(self) -> Self;
+        pub const fn checked_add(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_sub(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_mul(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_div(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_rem(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_neg(self) -> Option<Self>;
+        pub const fn checked_pow(self, exp: u32) -> Option<Self>;
+        pub const fn saturating_add(self, rhs: Self) -> Self;
+        pub const fn saturating_sub(self, rhs: Self) -> Self;
+        pub const fn saturating_mul(self, rhs: Self) -> Self;
+        pub const fn saturating_pow(self, exp: u32) -> Self;
+        pub const fn wrapping_add(self, rhs: Self) -> Self;
+        pub const fn wrapping_sub(self, rhs: Self) -> Self;
+        pub const fn wrapping_mul(self, rhs: Self) -> Self;
+        pub const fn wrapping_div(self, rhs: Self) -> Self;
+        pub const fn wrapping_rem(self, rhs: Self) -> Self;
+        pub const fn wrapping_neg(self) -> Self;
+        pub const fn wrapping_shl(self, rhs: u32) -> Self;
+        pub const fn wrapping_shr(self, rhs: u32) -> Self;
+        pub const fn wrapping_pow(self, exp: u32) -> Self;
+        pub const fn overflowing_add(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_mul(self, rhs: Self) -> (Self, bool);
+        pub const fn pow(self, exp: u32) -> Self;
+        pub const fn isqrt(self) -> Self;
+        pub const fn ilog2(self) -> u32;
+        pub const fn ilog10(self) -> u32;
+        pub const fn div_euclid(self, rhs: Self) -> Self;
+        pub const fn rem_euclid(self, rhs: Self) -> Self;
+        pub const fn to_be(self) -> Self;
+        pub const fn to_le(self) -> Self;
+        pub fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+        pub const fn min_value() -> Self;
+        pub const fn max_value() -> Self;
+        pub const fn abs(self) -> Self;
+        pub const fn signum(self) -> Self;
+        pub const fn is_positive(self) -> bool;
+        pub const fn is_negative(self) -> bool;
+        pub const fn checked_abs(self) -> Option<Self>;
+        pub const fn wrapping_abs(self) -> Self;
+        pub const fn unsigned_abs(self) -> u32;
+        pub const fn abs_diff(self, other: Self) -> u32;
+        pub const fn to_le_bytes(self) -> [u8; 4];
+        pub const fn to_be_bytes(self) -> [u8; 4];
+        pub const fn to_ne_bytes(self) -> [u8; 4];
+        pub const fn from_le_bytes(bytes: [u8; 4]) -> Self;
+        pub const fn from_be_bytes(bytes: [u8; 4]) -> Self;
+        pub const fn from_ne_bytes(bytes: [u8; 4]) -> Self;
+    }
+
+    impl i64 {
+        pub const MIN: i64 = -9223372036854775807 - 1;
+        pub const MAX: i64 = 9223372036854775807;
+        pub const BITS: u32 = 64;
+        pub const fn count_ones(self) -> u32;
+        pub const fn count_zeros(self) -> u32;
+        pub const fn leading_zeros(self) -> u32;
+        pub const fn trailing_zeros(self) -> u32;
+        pub const fn rotate_left(self, n: u32) -> Self;
+        pub const fn rotate_right(self, n: u32) -> Self;
+        pub const fn swap_bytes(self) -> Self;
+        pub const fn reverse_bits(self) -> Self;
+        pub const fn checked_add(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_sub(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_mul(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_div(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_rem(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_neg(self) -> Option<Self>;
+        pub const fn checked_pow(self, exp: u32) -> Option<Self>;
+        pub const fn saturating_add(self, rhs: Self) -> Self;
+        pub const fn saturating_sub(self, rhs: Self) -> Self;
+        pub const fn saturating_mul(self, rhs: Self) -> Self;
+        pub const fn saturating_pow(self, exp: u32) -> Self;
+        pub const fn wrapping_add(self, rhs: Self) -> Self;
+        pub const fn wrapping_sub(self, rhs: Self) -> Self;
+        pub const fn wrapping_mul(self, rhs: Self) -> Self;
+        pub const fn wrapping_div(self, rhs: Self) -> Self;
+        pub const fn wrapping_rem(self, rhs: Self) -> Self;
+        pub const fn wrapping_neg(self) -> Self;
+        pub const fn wrapping_shl(self, rhs: u32) -> Self;
+        pub const fn wrapping_shr(self, rhs: u32) -> Self;
+        pub const fn wrapping_pow(self, exp: u32) -> Self;
+        pub const fn overflowing_add(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_mul(self, rhs: Self) -> (Self, bool);
+        pub const fn pow(self, exp: u32) -> Self;
+        pub const fn isqrt(self) -> Self;
+        pub const fn ilog2(self) -> u32;
+        pub const fn ilog10(self) -> u32;
+        pub const fn div_euclid(self, rhs: Self) -> Self;
+        pub const fn rem_euclid(self, rhs: Self) -> Self;
+        pub const fn to_be(self) -> Self;
+        pub const fn to_le(self) -> Self;
+        pub fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+        pub const fn min_value() -> Self;
+        pub const fn max_value() -> Self;
+        pub const fn abs(self) -> Self;
+        pub const fn signum(self) -> Self;
+        pub const fn is_positive(self) -> bool;
+        pub const fn is_negative(self) -> bool;
+        pub const fn checked_abs(self) -> Option<Self>;
+        pub const fn wrapping_abs(self) -> Self;
+        pub const fn unsigned_abs(self) -> u64;
+        pub const fn abs_diff(self, other: Self) -> u64;
+        pub const fn to_le_bytes(self) -> [u8; 8];
+        pub const fn to_be_bytes(self) -> [u8; 8];
+        pub const fn to_ne_bytes(self) -> [u8; 8];
+        pub const fn from_le_bytes(bytes: [u8; 8]) -> Self;
+        pub const fn from_be_bytes(bytes: [u8; 8]) -> Self;
+        pub const fn from_ne_bytes(bytes: [u8; 8]) -> Self;
+    }
+
+    impl i128 {
+        pub const MIN: i128 = -170141183460469231731687303715884105727 - 1;
+        pub const MAX: i128 = 170141183460469231731687303715884105727;
+        pub const BITS: u32 = 128;
+        pub const fn count_ones(self) -> u32;
+        pub const fn count_zeros(self) -> u32;
+        pub const fn leading_zeros(self) -> u32;
+        pub const fn trailing_zeros(self) -> u32;
+        pub const fn rotate_left(self, n: u32) -> Self;
+        pub const fn rotate_right(self, n: u32) -> Self;
+        pub const fn swap_bytes(self) -> Self;
+        pub const fn reverse_bits(self) -> Self;
+        pub const fn checked_add(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_sub(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_mul(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_div(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_rem(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_neg(self) -> Option<Self>;
+        pub const fn checked_pow(self, exp: u32) -> Option<Self>;
+        pub const fn saturating_add(self, rhs: Self) -> Self;
+        pub const fn saturating_sub(self, rhs: Self) -> Self;
+        pub const fn saturating_mul(self, rhs: Self) -> Self;
+        pub const fn saturating_pow(self, exp: u32) -> Self;
+        pub const fn wrapping_add(self, rhs: Self) -> Self;
+        pub const fn wrapping_sub(self, rhs: Self) -> Self;
+        pub const fn wrapping_mul(self, rhs: Self) -> Self;
+        pub const fn wrapping_div(self, rhs: Self) -> Self;
+        pub const fn wrapping_rem(self, rhs: Self) -> Self;
+        pub const fn wrapping_neg(self) -> Self;
+        pub const fn wrapping_shl(self, rhs: u32) -> Self;
+        pub const fn wrapping_shr(self, rhs: u32) -> Self;
+        pub const fn wrapping_pow(self, exp: u32) -> Self;
+        pub const fn overflowing_add(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_mul(self, rhs: Self) -> (Self, bool);
+        pub const fn pow(self, exp: u32) -> Self;
+        pub const fn isqrt(self) -> Self;
+        pub const fn ilog2(self) -> u32;
+        pub const fn ilog10(self) -> u32;
+        pub const fn div_euclid(self, rhs: Self) -> Self;
+        pub const fn rem_euclid(self, rhs: Self) -> Self;
+        pub const fn to_be(self) -> Self;
+        pub const fn to_le(self) -> Self;
+        pub fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+        pub const fn min_value() -> Self;
+        pub const fn max_value() -> Self;
+        pub const fn abs(self) -> Self;
+        pub const fn signum(self) -> Self;
+        pub const fn is_positive(self) -> bool;
+        pub const fn is_negative(self) -> bool;
+        pub const fn checked_abs(self) -> Option<Self>;
+        pub const fn wrapping_abs(self) -> Self;
+        pub const fn unsigned_abs(self) -> u128;
+        pub const fn abs_diff(self, other: Self) -> u128;
+        pub const fn to_le_bytes(self) -> [u8; 16];
+        pub const fn to_be_bytes(self) -> [u8; 16];
+        pub const fn to_ne_bytes(self) -> [u8; 16];
+        pub const fn from_le_bytes(bytes: [u8; 16]) -> Self;
+        pub const fn from_be_bytes(bytes: [u8; 16]) -> Self;
+        pub const fn from_ne_bytes(bytes: [u8; 16]) -> Self;
+    }
+
+    impl isize {
+        pub const MIN: isize = -9223372036854775807 - 1;
+        pub const MAX: isize = 9223372036854775807;
+        pub const BITS: u32 = 64;
+        pub const fn count_ones(self) -> u32;
+        pub const fn count_zeros(self) -> u32;
+        pub const fn leading_zeros(self) -> u32;
+        pub const fn trailing_zeros(self) -> u32;
+        pub const fn rotate_left(self, n: u32) -> Self;
+        pub const fn rotate_right(self, n: u32) -> Self;
+        pub const fn swap_bytes(self) -> Self;
+        pub const fn reverse_bits(self) -> Self;
+        pub const fn checked_add(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_sub(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_mul(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_div(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_rem(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_neg(self) -> Option<Self>;
+        pub const fn checked_pow(self, exp: u32) -> Option<Self>;
+        pub const fn saturating_add(self, rhs: Self) -> Self;
+        pub const fn saturating_sub(self, rhs: Self) -> Self;
+        pub const fn saturating_mul(self, rhs: Self) -> Self;
+        pub const fn saturating_pow(self, exp: u32) -> Self;
+        pub const fn wrapping_add(self, rhs: Self) -> Self;
+        pub const fn wrapping_sub(self, rhs: Self) -> Self;
+        pub const fn wrapping_mul(self, rhs: Self) -> Self;
+        pub const fn wrapping_div(self, rhs: Self) -> Self;
+        pub const fn wrapping_rem(self, rhs: Self) -> Self;
+        pub const fn wrapping_neg(self) -> Self;
+        pub const fn wrapping_shl(self, rhs: u32) -> Self;
+        pub const fn wrapping_shr(self, rhs: u32) -> Self;
+        pub const fn wrapping_pow(self, exp: u32) -> Self;
+        pub const fn overflowing_add(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_mul(self, rhs: Self) -> (Self, bool);
+        pub const fn pow(self, exp: u32) -> Self;
+        pub const fn isqrt(self) -> Self;
+        pub const fn ilog2(self) -> u32;
+        pub const fn ilog10(self) -> u32;
+        pub const fn div_euclid(self, rhs: Self) -> Self;
+        pub const fn rem_euclid(self, rhs: Self) -> Self;
+        pub const fn to_be(self) -> Self;
+        pub const fn to_le(self) -> Self;
+        pub fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+        pub const fn min_value() -> Self;
+        pub const fn max_value() -> Self;
+        pub const fn abs(self) -> Self;
+        pub const fn signum(self) -> Self;
+        pub const fn is_positive(self) -> bool;
+        pub const fn is_negative(self) -> bool;
+        pub const fn checked_abs(self) -> Option<Self>;
+        pub const fn wrapping_abs(self) -> Self;
+        pub const fn unsigned_abs(self) -> usize;
+        pub const fn abs_diff(self, other: Self) -> usize;
+        pub const fn to_le_bytes(self) -> [u8; 8];
+        pub const fn to_be_bytes(self) -> [u8; 8];
+        pub const fn to_ne_bytes(self) -> [u8; 8];
+        pub const fn from_le_bytes(bytes: [u8; 8]) -> Self;
+        pub const fn from_be_bytes(bytes: [u8; 8]) -> Self;
+        pub const fn from_ne_bytes(bytes: [u8; 8]) -> Self;
+    }
+
+    impl u8 {
+        pub const MIN: u8 = 0;
+        pub const MAX: u8 = 255;
+        pub const BITS: u32 = 8;
+        pub const fn count_ones(self) -> u32;
+        pub const fn count_zeros(self) -> u32;
+        pub const fn leading_zeros(self) -> u32;
+        pub const fn trailing_zeros(self) -> u32;
+        pub const fn rotate_left(self, n: u32) -> Self;
+        pub const fn rotate_right(self, n: u32) -> Self;
+        pub const fn swap_bytes(self) -> Self;
+        pub const fn reverse_bits(self) -> Self;
+        pub const fn checked_add(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_sub(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_mul(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_div(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_rem(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_neg(self) -> Option<Self>;
+        pub const fn checked_pow(self, exp: u32) -> Option<Self>;
+        pub const fn saturating_add(self, rhs: Self) -> Self;
+        pub const fn saturating_sub(self, rhs: Self) -> Self;
+        pub const fn saturating_mul(self, rhs: Self) -> Self;
+        pub const fn saturating_pow(self, exp: u32) -> Self;
+        pub const fn wrapping_add(self, rhs: Self) -> Self;
+        pub const fn wrapping_sub(self, rhs: Self) -> Self;
+        pub const fn wrapping_mul(self, rhs: Self) -> Self;
+        pub const fn wrapping_div(self, rhs: Self) -> Self;
+        pub const fn wrapping_rem(self, rhs: Self) -> Self;
+        pub const fn wrapping_neg(self) -> Self;
+        pub const fn wrapping_shl(self, rhs: u32) -> Self;
+        pub const fn wrapping_shr(self, rhs: u32) -> Self;
+        pub const fn wrapping_pow(self, exp: u32) -> Self;
+        pub const fn overflowing_add(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_mul(self, rhs: Self) -> (Self, bool);
+        pub const fn pow(self, exp: u32) -> Self;
+        pub const fn isqrt(self) -> Self;
+        pub const fn ilog2(self) -> u32;
+        pub const fn ilog10(self) -> u32;
+        pub const fn div_euclid(self, rhs: Self) -> Self;
+        pub const fn rem_euclid(self, rhs: Self) -> Self;
+        pub const fn to_be(self) -> Self;
+        pub const fn to_le(self) -> Self;
+        pub fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+        pub const fn min_value() -> Self;
+        pub const fn max_value() -> Self;
+        pub const fn is_power_of_two(self) -> bool;
+        pub const fn next_power_of_two(self) -> Self;
+        pub const fn checked_next_power_of_two(self) -> Option<Self>;
+        pub const fn abs_diff(self, other: Self) -> Self;
+        pub const fn div_ceil(self, rhs: Self) -> Self;
+        pub const fn to_le_bytes(self) -> [u8; 1];
+        pub const fn to_be_bytes(self) -> [u8; 1];
+        pub const fn to_ne_bytes(self) -> [u8; 1];
+        pub const fn from_le_bytes(bytes: [u8; 1]) -> Self;
+        pub const fn from_be_bytes(bytes: [u8; 1]) -> Self;
+        pub const fn from_ne_bytes(bytes: [u8; 1]) -> Self;
+        pub const fn is_ascii(&self) -> bool;
+        pub const fn is_ascii_alphabetic(&self) -> bool;
+        pub const fn is_ascii_uppercase(&self) -> bool;
+        pub const fn is_ascii_lowercase(&self) -> bool;
+        pub const fn is_ascii_alphanumeric(&self) -> bool;
+        pub const fn is_ascii_digit(&self) -> bool;
+        pub const fn is_ascii_hexdigit(&self) -> bool;
+        pub const fn is_ascii_punctuation(&self) -> bool;
+        pub const fn is_ascii_whitespace(&self) -> bool;
+        pub const fn is_ascii_control(&self) -> bool;
+        pub const fn to_ascii_uppercase(&self) -> u8;
+        pub const fn to_ascii_lowercase(&self) -> u8;
+        pub const fn eq_ignore_ascii_case(&self, other: &u8) -> bool;
+        pub const fn make_ascii_uppercase(&mut self);
+        pub const fn make_ascii_lowercase(&mut self);
+    }
+
+    impl u16 {
+        pub const MIN: u16 = 0;
+        pub const MAX: u16 = 65535;
+        pub const BITS: u32 = 16;
+        pub const fn count_ones(self) -> u32;
+        pub const fn count_zeros(self) -> u32;
+        pub const fn leading_zeros(self) -> u32;
+        pub const fn trailing_zeros(self) -> u32;
+        pub const fn rotate_left(self, n: u32) -> Self;
+        pub const fn rotate_right(self, n: u32) -> Self;
+        pub const fn swap_bytes(self) -> Self;
+        pub const fn reverse_bits(self) -> Self;
+        pub const fn checked_add(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_sub(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_mul(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_div(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_rem(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_neg(self) -> Option<Self>;
+        pub const fn checked_pow(self, exp: u32) -> Option<Self>;
+        pub const fn saturating_add(self, rhs: Self) -> Self;
+        pub const fn saturating_sub(self, rhs: Self) -> Self;
+        pub const fn saturating_mul(self, rhs: Self) -> Self;
+        pub const fn saturating_pow(self, exp: u32) -> Self;
+        pub const fn wrapping_add(self, rhs: Self) -> Self;
+        pub const fn wrapping_sub(self, rhs: Self) -> Self;
+        pub const fn wrapping_mul(self, rhs: Self) -> Self;
+        pub const fn wrapping_div(self, rhs: Self) -> Self;
+        pub const fn wrapping_rem(self, rhs: Self) -> Self;
+        pub const fn wrapping_neg(self) -> Self;
+        pub const fn wrapping_shl(self, rhs: u32) -> Self;
+        pub const fn wrapping_shr(self, rhs: u32) -> Self;
+        pub const fn wrapping_pow(self, exp: u32) -> Self;
+        pub const fn overflowing_add(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_mul(self, rhs: Self) -> (Self, bool);
+        pub const fn pow(self, exp: u32) -> Self;
+        pub const fn isqrt(self) -> Self;
+        pub const fn ilog2(self) -> u32;
+        pub const fn ilog10(self) -> u32;
+        pub const fn div_euclid(self, rhs: Self) -> Self;
+        pub const fn rem_euclid(self, rhs: Self) -> Self;
+        pub const fn to_be(self) -> Self;
+        pub const fn to_le(self) -> Self;
+        pub fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+        pub const fn min_value() -> Self;
+        pub const fn max_value() -> Self;
+        pub const fn is_power_of_two(self) -> bool;
+        pub const fn next_power_of_two(self) -> Self;
+        pub const fn checked_next_power_of_two(self) -> Option<Self>;
+        pub const fn abs_diff(self, other: Self) -> Self;
+        pub const fn div_ceil(self, rhs: Self) -> Self;
+        pub const fn to_le_bytes(self) -> [u8; 2];
+        pub const fn to_be_bytes(self) -> [u8; 2];
+        pub const fn to_ne_bytes(self) -> [u8; 2];
+        pub const fn from_le_bytes(bytes: [u8; 2]) -> Self;
+        pub const fn from_be_bytes(bytes: [u8; 2]) -> Self;
+        pub const fn from_ne_bytes(bytes: [u8; 2]) -> Self;
+    }
+
+    impl u32 {
+        pub const MIN: u32 = 0;
+        pub const MAX: u32 = 4294967295;
+        pub const BITS: u32 = 32;
+        pub const fn count_ones(self) -> u32;
+        pub const fn count_zeros(self) -> u32;
+        pub const fn leading_zeros(self) -> u32;
+        pub const fn trailing_zeros(self) -> u32;
+        pub const fn rotate_left(self, n: u32) -> Self;
+        pub const fn rotate_right(self, n: u32) -> Self;
+        pub const fn swap_bytes(self) -> Self;
+        pub const fn reverse_bits(self) -> Self;
+        pub const fn checked_add(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_sub(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_mul(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_div(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_rem(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_neg(self) -> Option<Self>;
+        pub const fn checked_pow(self, exp: u32) -> Option<Self>;
+        pub const fn saturating_add(self, rhs: Self) -> Self;
+        pub const fn saturating_sub(self, rhs: Self) -> Self;
+        pub const fn saturating_mul(self, rhs: Self) -> Self;
+        pub const fn saturating_pow(self, exp: u32) -> Self;
+        pub const fn wrapping_add(self, rhs: Self) -> Self;
+        pub const fn wrapping_sub(self, rhs: Self) -> Self;
+        pub const fn wrapping_mul(self, rhs: Self) -> Self;
+        pub const fn wrapping_div(self, rhs: Self) -> Self;
+        pub const fn wrapping_rem(self, rhs: Self) -> Self;
+        pub const fn wrapping_neg(self) -> Self;
+        pub const fn wrapping_shl(self, rhs: u32) -> Self;
+        pub const fn wrapping_shr(self, rhs: u32) -> Self;
+        pub const fn wrapping_pow(self, exp: u32) -> Self;
+        pub const fn overflowing_add(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_mul(self, rhs: Self) -> (Self, bool);
+        pub const fn pow(self, exp: u32) -> Self;
+        pub const fn isqrt(self) -> Self;
+        pub const fn ilog2(self) -> u32;
+        pub const fn ilog10(self) -> u32;
+        pub const fn div_euclid(self, rhs: Self) -> Self;
+        pub const fn rem_euclid(self, rhs: Self) -> Self;
+        pub const fn to_be(self) -> Self;
+        pub const fn to_le(self) -> Self;
+        pub fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+        pub const fn min_value() -> Self;
+        pub const fn max_value() -> Self;
+        pub const fn is_power_of_two(self) -> bool;
+        pub const fn next_power_of_two(self) -> Self;
+        pub const fn checked_next_power_of_two(self) -> Option<Self>;
+        pub const fn abs_diff(self, other: Self) -> Self;
+        pub const fn div_ceil(self, rhs: Self) -> Self;
+        pub const fn to_le_bytes(self) -> [u8; 4];
+        pub const fn to_be_bytes(self) -> [u8; 4];
+        pub const fn to_ne_bytes(self) -> [u8; 4];
+        pub const fn from_le_bytes(bytes: [u8; 4]) -> Self;
+        pub const fn from_be_bytes(bytes: [u8; 4]) -> Self;
+        pub const fn from_ne_bytes(bytes: [u8; 4]) -> Self;
+    }
+
+    impl u64 {
+        pub const MIN: u64 = 0;
+        pub const MAX: u64 = 18446744073709551615;
+        pub const BITS: u32 = 64;
+        pub const fn count_ones(self) -> u32;
+        pub const fn count_zeros(self) -> u32;
+        pub const fn leading_zeros(self) -> u32;
+        pub const fn trailing_zeros(self) -> u32;
+        pub const fn rotate_left(self, n: u32) -> Self;
+        pub const fn rotate_right(self, n: u32) -> Self;
+        pub const fn swap_bytes(self) -> Self;
+        pub const fn reverse_bits(self) -> Self;
+        pub const fn checked_add(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_sub(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_mul(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_div(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_rem(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_neg(self) -> Option<Self>;
+        pub const fn checked_pow(self, exp: u32) -> Option<Self>;
+        pub const fn saturating_add(self, rhs: Self) -> Self;
+        pub const fn saturating_sub(self, rhs: Self) -> Self;
+        pub const fn saturating_mul(self, rhs: Self) -> Self;
+        pub const fn saturating_pow(self, exp: u32) -> Self;
+        pub const fn wrapping_add(self, rhs: Self) -> Self;
+        pub const fn wrapping_sub(self, rhs: Self) -> Self;
+        pub const fn wrapping_mul(self, rhs: Self) -> Self;
+        pub const fn wrapping_div(self, rhs: Self) -> Self;
+        pub const fn wrapping_rem(self, rhs: Self) -> Self;
+        pub const fn wrapping_neg(self) -> Self;
+        pub const fn wrapping_shl(self, rhs: u32) -> Self;
+        pub const fn wrapping_shr(self, rhs: u32) -> Self;
+        pub const fn wrapping_pow(self, exp: u32) -> Self;
+        pub const fn overflowing_add(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_mul(self, rhs: Self) -> (Self, bool);
+        pub const fn pow(self, exp: u32) -> Self;
+        pub const fn isqrt(self) -> Self;
+        pub const fn ilog2(self) -> u32;
+        pub const fn ilog10(self) -> u32;
+        pub const fn div_euclid(self, rhs: Self) -> Self;
+        pub const fn rem_euclid(self, rhs: Self) -> Self;
+        pub const fn to_be(self) -> Self;
+        pub const fn to_le(self) -> Self;
+        pub fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+        pub const fn min_value() -> Self;
+        pub const fn max_value() -> Self;
+        pub const fn is_power_of_two(self) -> bool;
+        pub const fn next_power_of_two(self) -> Self;
+        pub const fn checked_next_power_of_two(self) -> Option<Self>;
+        pub const fn abs_diff(self, other: Self) -> Self;
+        pub const fn div_ceil(self, rhs: Self) -> Self;
+        pub const fn to_le_bytes(self) -> [u8; 8];
+        pub const fn to_be_bytes(self) -> [u8; 8];
+        pub const fn to_ne_bytes(self) -> [u8; 8];
+        pub const fn from_le_bytes(bytes: [u8; 8]) -> Self;
+        pub const fn from_be_bytes(bytes: [u8; 8]) -> Self;
+        pub const fn from_ne_bytes(bytes: [u8; 8]) -> Self;
+    }
+
+    impl u128 {
+        pub const MIN: u128 = 0;
+        pub const MAX: u128 = 340282366920938463463374607431768211455;
+        pub const BITS: u32 = 128;
+        pub const fn count_ones(self) -> u32;
+        pub const fn count_zeros(self) -> u32;
+        pub const fn leading_zeros(self) -> u32;
+        pub const fn trailing_zeros(self) -> u32;
+        pub const fn rotate_left(self, n: u32) -> Self;
+        pub const fn rotate_right(self, n: u32) -> Self;
+        pub const fn swap_bytes(self) -> Self;
+        pub const fn reverse_bits(self) -> Self;
+        pub const fn checked_add(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_sub(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_mul(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_div(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_rem(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_neg(self) -> Option<Self>;
+        pub const fn checked_pow(self, exp: u32) -> Option<Self>;
+        pub const fn saturating_add(self, rhs: Self) -> Self;
+        pub const fn saturating_sub(self, rhs: Self) -> Self;
+        pub const fn saturating_mul(self, rhs: Self) -> Self;
+        pub const fn saturating_pow(self, exp: u32) -> Self;
+        pub const fn wrapping_add(self, rhs: Self) -> Self;
+        pub const fn wrapping_sub(self, rhs: Self) -> Self;
+        pub const fn wrapping_mul(self, rhs: Self) -> Self;
+        pub const fn wrapping_div(self, rhs: Self) -> Self;
+        pub const fn wrapping_rem(self, rhs: Self) -> Self;
+        pub const fn wrapping_neg(self) -> Self;
+        pub const fn wrapping_shl(self, rhs: u32) -> Self;
+        pub const fn wrapping_shr(self, rhs: u32) -> Self;
+        pub const fn wrapping_pow(self, exp: u32) -> Self;
+        pub const fn overflowing_add(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_mul(self, rhs: Self) -> (Self, bool);
+        pub const fn pow(self, exp: u32) -> Self;
+        pub const fn isqrt(self) -> Self;
+        pub const fn ilog2(self) -> u32;
+        pub const fn ilog10(self) -> u32;
+        pub const fn div_euclid(self, rhs: Self) -> Self;
+        pub const fn rem_euclid(self, rhs: Self) -> Self;
+        pub const fn to_be(self) -> Self;
+        pub const fn to_le(self) -> Self;
+        pub fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+        pub const fn min_value() -> Self;
+        pub const fn max_value() -> Self;
+        pub const fn is_power_of_two(self) -> bool;
+        pub const fn next_power_of_two(self) -> Self;
+        pub const fn checked_next_power_of_two(self) -> Option<Self>;
+        pub const fn abs_diff(self, other: Self) -> Self;
+        pub const fn div_ceil(self, rhs: Self) -> Self;
+        pub const fn to_le_bytes(self) -> [u8; 16];
+        pub const fn to_be_bytes(self) -> [u8; 16];
+        pub const fn to_ne_bytes(self) -> [u8; 16];
+        pub const fn from_le_bytes(bytes: [u8; 16]) -> Self;
+        pub const fn from_be_bytes(bytes: [u8; 16]) -> Self;
+        pub const fn from_ne_bytes(bytes: [u8; 16]) -> Self;
+    }
+
+    impl usize {
+        pub const MIN: usize = 0;
+        pub const MAX: usize = 18446744073709551615;
+        pub const BITS: u32 = 64;
+        pub const fn count_ones(self) -> u32;
+        pub const fn count_zeros(self) -> u32;
+        pub const fn leading_zeros(self) -> u32;
+        pub const fn trailing_zeros(self) -> u32;
+        pub const fn rotate_left(self, n: u32) -> Self;
+        pub const fn rotate_right(self, n: u32) -> Self;
+        pub const fn swap_bytes(self) -> Self;
+        pub const fn reverse_bits(self) -> Self;
+        pub const fn checked_add(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_sub(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_mul(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_div(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_rem(self, rhs: Self) -> Option<Self>;
+        pub const fn checked_neg(self) -> Option<Self>;
+        pub const fn checked_pow(self, exp: u32) -> Option<Self>;
+        pub const fn saturating_add(self, rhs: Self) -> Self;
+        pub const fn saturating_sub(self, rhs: Self) -> Self;
+        pub const fn saturating_mul(self, rhs: Self) -> Self;
+        pub const fn saturating_pow(self, exp: u32) -> Self;
+        pub const fn wrapping_add(self, rhs: Self) -> Self;
+        pub const fn wrapping_sub(self, rhs: Self) -> Self;
+        pub const fn wrapping_mul(self, rhs: Self) -> Self;
+        pub const fn wrapping_div(self, rhs: Self) -> Self;
+        pub const fn wrapping_rem(self, rhs: Self) -> Self;
+        pub const fn wrapping_neg(self) -> Self;
+        pub const fn wrapping_shl(self, rhs: u32) -> Self;
+        pub const fn wrapping_shr(self, rhs: u32) -> Self;
+        pub const fn wrapping_pow(self, exp: u32) -> Self;
+        pub const fn overflowing_add(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+        pub const fn overflowing_mul(self, rhs: Self) -> (Self, bool);
+        pub const fn pow(self, exp: u32) -> Self;
+        pub const fn isqrt(self) -> Self;
+        pub const fn ilog2(self) -> u32;
+        pub const fn ilog10(self) -> u32;
+        pub const fn div_euclid(self, rhs: Self) -> Self;
+        pub const fn rem_euclid(self, rhs: Self) -> Self;
+        pub const fn to_be(self) -> Self;
+        pub const fn to_le(self) -> Self;
+        pub fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+        pub const fn min_value() -> Self;
+        pub const fn max_value() -> Self;
+        pub const fn is_power_of_two(self) -> bool;
+        pub const fn next_power_of_two(self) -> Self;
+        pub const fn checked_next_power_of_two(self) -> Option<Self>;
+        pub const fn abs_diff(self, other: Self) -> Self;
+        pub const fn div_ceil(self, rhs: Self) -> Self;
+        pub const fn to_le_bytes(self) -> [u8; 8];
+        pub const fn to_be_bytes(self) -> [u8; 8];
+        pub const fn to_ne_bytes(self) -> [u8; 8];
+        pub const fn from_le_bytes(bytes: [u8; 8]) -> Self;
+        pub const fn from_be_bytes(bytes: [u8; 8]) -> Self;
+        pub const fn from_ne_bytes(bytes: [u8; 8]) -> Self;
+    }
+
+    impl f32 {
+        pub const MIN: f32 = -3.40282347e38;
+        pub const MAX: f32 = 3.40282347e38;
+        pub const MIN_POSITIVE: f32 = 1.17549435e-38;
+        pub const EPSILON: f32 = 1.19209290e-7;
+        pub const INFINITY: f32 = 1.0 / 0.0;
+        pub const NEG_INFINITY: f32 = -1.0 / 0.0;
+        pub const NAN: f32 = 0.0 / 0.0;
+        pub fn floor(self) -> f32;
+        pub fn ceil(self) -> f32;
+        pub fn round(self) -> f32;
+        pub fn trunc(self) -> f32;
+        pub fn fract(self) -> f32;
+        pub fn abs(self) -> f32;
+        pub fn signum(self) -> f32;
+        pub fn sqrt(self) -> f32;
+        pub fn cbrt(self) -> f32;
+        pub fn exp(self) -> f32;
+        pub fn exp2(self) -> f32;
+        pub fn ln(self) -> f32;
+        pub fn log2(self) -> f32;
+        pub fn log10(self) -> f32;
+        pub fn sin(self) -> f32;
+        pub fn cos(self) -> f32;
+        pub fn tan(self) -> f32;
+        pub fn asin(self) -> f32;
+        pub fn acos(self) -> f32;
+        pub fn atan(self) -> f32;
+        pub fn sinh(self) -> f32;
+        pub fn cosh(self) -> f32;
+        pub fn tanh(self) -> f32;
+        pub fn recip(self) -> f32;
+        pub fn to_degrees(self) -> f32;
+        pub fn to_radians(self) -> f32;
+        pub fn powf(self, n: f32) -> f32;
+        pub fn log(self, n: f32) -> f32;
+        pub fn atan2(self, n: f32) -> f32;
+        pub fn hypot(self, n: f32) -> f32;
+        pub fn max(self, n: f32) -> f32;
+        pub fn min(self, n: f32) -> f32;
+        pub fn copysign(self, n: f32) -> f32;
+        pub fn rem_euclid(self, n: f32) -> f32;
+        pub fn div_euclid(self, n: f32) -> f32;
+        pub fn powi(self, n: i32) -> f32;
+        pub fn mul_add(self, a: f32, b: f32) -> f32;
+        pub fn clamp(self, min: f32, max: f32) -> f32;
+        pub fn sin_cos(self) -> (f32, f32);
+        pub fn total_cmp(&self, other: &f32) -> Ordering;
+        pub const fn is_nan(self) -> bool;
+        pub const fn is_infinite(self) -> bool;
+        pub const fn is_finite(self) -> bool;
+        pub const fn is_normal(self) -> bool;
+        pub const fn is_sign_positive(self) -> bool;
+        pub const fn is_sign_negative(self) -> bool;
+        pub const fn to_bits(self) -> u32;
+        pub const fn from_bits(v: u32) -> f32;
+    }
+
+    impl f64 {
+        pub const MIN: f64 = -1.7976931348623157e308;
+        pub const MAX: f64 = 1.7976931348623157e308;
+        pub const MIN_POSITIVE: f64 = 2.2250738585072014e-308;
+        pub const EPSILON: f64 = 2.220446049250313e-16;
+        pub const INFINITY: f64 = 1.0 / 0.0;
+        pub const NEG_INFINITY: f64 = -1.0 / 0.0;
+        pub const NAN: f64 = 0.0 / 0.0;
+        pub fn floor(self) -> f64;
+        pub fn ceil(self) -> f64;
+        pub fn round(self) -> f64;
+        pub fn trunc(self) -> f64;
+        pub fn fract(self) -> f64;
+        pub fn abs(self) -> f64;
+        pub fn signum(self) -> f64;
+        pub fn sqrt(self) -> f64;
+        pub fn cbrt(self) -> f64;
+        pub fn exp(self) -> f64;
+        pub fn exp2(self) -> f64;
+        pub fn ln(self) -> f64;
+        pub fn log2(self) -> f64;
+        pub fn log10(self) -> f64;
+        pub fn sin(self) -> f64;
+        pub fn cos(self) -> f64;
+        pub fn tan(self) -> f64;
+        pub fn asin(self) -> f64;
+        pub fn acos(self) -> f64;
+        pub fn atan(self) -> f64;
+        pub fn sinh(self) -> f64;
+        pub fn cosh(self) -> f64;
+        pub fn tanh(self) -> f64;
+        pub fn recip(self) -> f64;
+        pub fn to_degrees(self) -> f64;
+        pub fn to_radians(self) -> f64;
+        pub fn powf(self, n: f64) -> f64;
+        pub fn log(self, n: f64) -> f64;
+        pub fn atan2(self, n: f64) -> f64;
+        pub fn hypot(self, n: f64) -> f64;
+        pub fn max(self, n: f64) -> f64;
+        pub fn min(self, n: f64) -> f64;
+        pub fn copysign(self, n: f64) -> f64;
+        pub fn rem_euclid(self, n: f64) -> f64;
+        pub fn div_euclid(self, n: f64) -> f64;
+        pub fn powi(self, n: i32) -> f64;
+        pub fn mul_add(self, a: f64, b: f64) -> f64;
+        pub fn clamp(self, min: f64, max: f64) -> f64;
+        pub fn sin_cos(self) -> (f64, f64);
+        pub fn total_cmp(&self, other: &f64) -> Ordering;
+        pub const fn is_nan(self) -> bool;
+        pub const fn is_infinite(self) -> bool;
+        pub const fn is_finite(self) -> bool;
+        pub const fn is_normal(self) -> bool;
+        pub const fn is_sign_positive(self) -> bool;
+        pub const fn is_sign_negative(self) -> bool;
+        pub const fn to_bits(self) -> u64;
+        pub const fn from_bits(v: u64) -> f64;
+    }
+
+    impl char {
+        pub const MAX: char = '\u{10ffff}';
+        pub fn is_alphabetic(self) -> bool;
+        pub fn is_alphanumeric(self) -> bool;
+        pub fn is_numeric(self) -> bool;
+        pub fn is_whitespace(self) -> bool;
+        pub fn is_uppercase(self) -> bool;
+        pub fn is_lowercase(self) -> bool;
+        pub fn is_control(self) -> bool;
+        pub fn is_ascii(&self) -> bool;
+        pub fn is_ascii_alphabetic(&self) -> bool;
+        pub fn is_ascii_uppercase(&self) -> bool;
+        pub fn is_ascii_lowercase(&self) -> bool;
+        pub fn is_ascii_alphanumeric(&self) -> bool;
+        pub fn is_ascii_digit(&self) -> bool;
+        pub fn is_ascii_hexdigit(&self) -> bool;
+        pub fn is_ascii_punctuation(&self) -> bool;
+        pub fn is_ascii_whitespace(&self) -> bool;
+        pub fn is_ascii_graphic(&self) -> bool;
+        pub fn is_ascii_control(&self) -> bool;
+        pub fn is_digit(self, radix: u32) -> bool;
+        pub fn to_digit(self, radix: u32) -> Option<u32>;
+        pub fn to_ascii_uppercase(&self) -> char;
+        pub fn to_ascii_lowercase(&self) -> char;
+        pub fn eq_ignore_ascii_case(&self, other: &char) -> bool;
+        pub fn len_utf8(self) -> usize;
+        pub fn make_ascii_uppercase(&mut self);
+        pub fn make_ascii_lowercase(&mut self);
+        pub fn to_uppercase(self) -> super::str::Chars<'static>;
+        pub fn to_lowercase(self) -> super::str::Chars<'static>;
+        pub fn from_u32(i: u32) -> Option<char>;
+        pub fn from_digit(num: u32, radix: u32) -> Option<char>;
+        pub fn encode_utf8(self, dst: &mut [u8]) -> &mut str;
+    }
+
+    impl bool {
+        pub fn then<T, F: FnOnce() -> T>(self, f: F) -> Option<T>;
+        pub fn then_some<T>(self, t: T) -> Option<T>;
+    }
+
+    impl Clone for i8 {}
+    impl Copy for i8 {}
+    impl Clone for i16 {}
+    impl Copy for i16 {}
+    impl Clone for i32 {}
+    impl Copy for i32 {}
+    impl Clone for i64 {}
+    impl Copy for i64 {}
+    impl Clone for i128 {}
+    impl Copy for i128 {}
+    impl Clone for isize {}
+    impl Copy for isize {}
+    impl Clone for u8 {}
+    impl Copy for u8 {}
+    impl Clone for u16 {}
+    impl Copy for u16 {}
+    impl Clone for u32 {}
+    impl Copy for u32 {}
+    impl Clone for u64 {}
+    impl Copy for u64 {}
+    impl Clone for u128 {}
+    impl Copy for u128 {}
+    impl Clone for usize {}
+    impl Copy for usize {}
+    impl Clone for f32 {}
+    impl Copy for f32 {}
+    impl Clone for f64 {}
+    impl Copy for f64 {}
+    impl Clone for bool {}
+    impl Copy for bool {}
+    impl Clone for char {}
+    impl Copy for char {}
+    impl Clone for () {}
+    impl Copy for () {}
+    impl PartialEq for i8 {}
+    impl PartialOrd for i8 {}
+    impl Eq for i8 {}
+    impl Ord for i8 {}
+    impl Hash for i8 {}
+    impl Debug for i8 {}
+    impl Display for i8 {}
+    impl Default for i8 {}
+    impl PartialEq for i16 {}
+    impl PartialOrd for i16 {}
+    impl Eq for i16 {}
+    impl Ord for i16 {}
+    impl Hash for i16 {}
+    impl Debug for i16 {}
+    impl Display for i16 {}
+    impl Default for i16 {}
+    impl PartialEq for i32 {}
+    impl PartialOrd for i32 {}
+    impl Eq for i32 {}
+    impl Ord for i32 {}
+    impl Hash for i32 {}
+    impl Debug for i32 {}
+    impl Display for i32 {}
+    impl Default for i32 {}
+    impl PartialEq for i64 {}
+    impl PartialOrd for i64 {}
+    impl Eq for i64 {}
+    impl Ord for i64 {}
+    impl Hash for i64 {}
+    impl Debug for i64 {}
+    impl Display for i64 {}
+    impl Default for i64 {}
+    impl PartialEq for i128 {}
+    impl PartialOrd for i128 {}
+    impl Eq for i128 {}
+    impl Ord for i128 {}
+    impl Hash for i128 {}
+    impl Debug for i128 {}
+    impl Display for i128 {}
+    impl Default for i128 {}
+    impl PartialEq for isize {}
+    impl PartialOrd for isize {}
+    impl Eq for isize {}
+    impl Ord for isize {}
+    impl Hash for isize {}
+    impl Debug for isize {}
+    impl Display for isize {}
+    impl Default for isize {}
+    impl PartialEq for u8 {}
+    impl PartialOrd for u8 {}
+    impl Eq for u8 {}
+    impl Ord for u8 {}
+    impl Hash for u8 {}
+    impl Debug for u8 {}
+    impl Display for u8 {}
+    impl Default for u8 {}
+    impl PartialEq for u16 {}
+    impl PartialOrd for u16 {}
+    impl Eq for u16 {}
+    impl Ord for u16 {}
+    impl Hash for u16 {}
+    impl Debug for u16 {}
+    impl Display for u16 {}
+    impl Default for u16 {}
+    impl PartialEq for u32 {}
+    impl PartialOrd for u32 {}
+    impl Eq for u32 {}
+    impl Ord for u32 {}
+    impl Hash for u32 {}
+    impl Debug for u32 {}
+    impl Display for u32 {}
+    impl Default for u32 {}
+    impl PartialEq for u64 {}
+    impl PartialOrd for u64 {}
+    impl Eq for u64 {}
+    impl Ord for u64 {}
+    impl Hash for u64 {}
+    impl Debug for u64 {}
+    impl Display for u64 {}
+    impl Default for u64 {}
+    impl PartialEq for u128 {}
+    impl PartialOrd for u128 {}
+    impl Eq for u128 {}
+    impl Ord for u128 {}
+    impl Hash for u128 {}
+    impl Debug for u128 {}
+    impl Display for u128 {}
+    impl Default for u128 {}
+    impl PartialEq for usize {}
+    impl PartialOrd for usize {}
+    impl Eq for usize {}
+    impl Ord for usize {}
+    impl Hash for usize {}
+    impl Debug for usize {}
+    impl Display for usize {}
+    impl Default for usize {}
+    impl PartialEq for f32 {}
+    impl PartialOrd for f32 {}
+    impl Debug for f32 {}
+    impl Display for f32 {}
+    impl Default for f32 {}
+    impl PartialEq for f64 {}
+    impl PartialOrd for f64 {}
+    impl Debug for f64 {}
+    impl Display for f64 {}
+    impl Default for f64 {}
+    impl PartialEq for bool {}
+    impl PartialOrd for bool {}
+    impl Eq for bool {}
+    impl Ord for bool {}
+    impl Hash for bool {}
+    impl Debug for bool {}
+    impl Display for bool {}
+    impl Default for bool {}
+    impl PartialEq for char {}
+    impl PartialOrd for char {}
+    impl Eq for char {}
+    impl Ord for char {}
+    impl Hash for char {}
+    impl Debug for char {}
+    impl Display for char {}
+    impl Default for char {}
+    impl PartialEq for str {}
+    impl PartialOrd for str {}
+    impl Eq for str {}
+    impl Ord for str {}
+    impl Hash for str {}
+    impl Debug for str {}
+    impl Display for str {}
+    impl PartialEq for () {}
+    impl PartialOrd for () {}
+    impl Eq for () {}
+    impl Ord for () {}
+    impl Hash for () {}
+    impl Debug for () {}
+    impl Default for () {}
+    impl FromStr for i8 {
+        type Err = ParseIntError;
+    }
+    impl FromStr for i16 {
+        type Err = ParseIntError;
+    }
+    impl FromStr for i32 {
+        type Err = ParseIntError;
+    }
+    impl FromStr for i64 {
+        type Err = ParseIntError;
+    }
+    impl FromStr for i128 {
+        type Err = ParseIntError;
+    }
+    impl FromStr for isize {
+        type Err = ParseIntError;
+    }
+    impl FromStr for u8 {
+        type Err = ParseIntError;
+    }
+    impl FromStr for u16 {
+        type Err = ParseIntError;
+    }
+    impl FromStr for u32 {
+        type Err = ParseIntError;
+    }
+    impl FromStr for u64 {
+        type Err = ParseIntError;
+    }
+    impl FromStr for u128 {
+        type Err = ParseIntError;
+    }
+    impl FromStr for usize {
+        type Err = ParseIntError;
+    }
+    impl FromStr for f32 {
+        type Err = ParseFloatError;
+    }
+    impl FromStr for f64 {
+        type Err = ParseFloatError;
+    }
+    impl FromStr for bool {
+        type Err = ParseBoolError;
+    }
+    impl FromStr for char {
+        type Err = super::char::ParseCharError;
+    }
+    impl LowerHex for i8 {}
+    impl UpperHex for i8 {}
+    impl Binary for i8 {}
+    impl LowerHex for i16 {}
+    impl UpperHex for i16 {}
+    impl Binary for i16 {}
+    impl LowerHex for i32 {}
+    impl UpperHex for i32 {}
+    impl Binary for i32 {}
+    impl LowerHex for i64 {}
+    impl UpperHex for i64 {}
+    impl Binary for i64 {}
+    impl LowerHex for i128 {}
+    impl UpperHex for i128 {}
+    impl Binary for i128 {}
+    impl LowerHex for isize {}
+    impl UpperHex for isize {}
+    impl Binary for isize {}
+    impl LowerHex for u8 {}
+    impl UpperHex for u8 {}
+    impl Binary for u8 {}
+    impl LowerHex for u16 {}
+    impl UpperHex for u16 {}
+    impl Binary for u16 {}
+    impl LowerHex for u32 {}
+    impl UpperHex for u32 {}
+    impl Binary for u32 {}
+    impl LowerHex for u64 {}
+    impl UpperHex for u64 {}
+    impl Binary for u64 {}
+    impl LowerHex for u128 {}
+    impl UpperHex for u128 {}
+    impl Binary for u128 {}
+    impl LowerHex for usize {}
+    impl UpperHex for usize {}
+    impl Binary for usize {}
+    impl Sum for i8 {}
+    impl<'a> Sum<&'a i8> for i8 {}
+    impl Product for i8 {}
+    impl<'a> Product<&'a i8> for i8 {}
+    impl Sum for i16 {}
+    impl<'a> Sum<&'a i16> for i16 {}
+    impl Product for i16 {}
+    impl<'a> Product<&'a i16> for i16 {}
+    impl Sum for i32 {}
+    impl<'a> Sum<&'a i32> for i32 {}
+    impl Product for i32 {}
+    impl<'a> Product<&'a i32> for i32 {}
+    impl Sum for i64 {}
+    impl<'a> Sum<&'a i64> for i64 {}
+    impl Product for i64 {}
+    impl<'a> Product<&'a i64> for i64 {}
+    impl Sum for i128 {}
+    impl<'a> Sum<&'a i128> for i128 {}
+    impl Product for i128 {}
+    impl<'a> Product<&'a i128> for i128 {}
+    impl Sum for isize {}
+    impl<'a> Sum<&'a isize> for isize {}
+    impl Product for isize {}
+    impl<'a> Product<&'a isize> for isize {}
+    impl Sum for u8 {}
+    impl<'a> Sum<&'a u8> for u8 {}
+    impl Product for u8 {}
+    impl<'a> Product<&'a u8> for u8 {}
+    impl Sum for u16 {}
+    impl<'a> Sum<&'a u16> for u16 {}
+    impl Product for u16 {}
+    impl<'a> Product<&'a u16> for u16 {}
+    impl Sum for u32 {}
+    impl<'a> Sum<&'a u32> for u32 {}
+    impl Product for u32 {}
+    impl<'a> Product<&'a u32> for u32 {}
+    impl Sum for u64 {}
+    impl<'a> Sum<&'a u64> for u64 {}
+    impl Product for u64 {}
+    impl<'a> Product<&'a u64> for u64 {}
+    impl Sum for u128 {}
+    impl<'a> Sum<&'a u128> for u128 {}
+    impl Product for u128 {}
+    impl<'a> Product<&'a u128> for u128 {}
+    impl Sum for usize {}
+    impl<'a> Sum<&'a usize> for usize {}
+    impl Product for usize {}
+    impl<'a> Product<&'a usize> for usize {}
+    impl Sum for f32 {}
+    impl<'a> Sum<&'a f32> for f32 {}
+    impl Product for f32 {}
+    impl<'a> Product<&'a f32> for f32 {}
+    impl Sum for f64 {}
+    impl<'a> Sum<&'a f64> for f64 {}
+    impl Product for f64 {}
+    impl<'a> Product<&'a f64> for f64 {}
+    impl Step for i8 {}
+    impl Step for i16 {}
+    impl Step for i32 {}
+    impl Step for i64 {}
+    impl Step for i128 {}
+    impl Step for isize {}
+    impl Step for u8 {}
+    impl Step for u16 {}
+    impl Step for u32 {}
+    impl Step for u64 {}
+    impl Step for u128 {}
+    impl Step for usize {}
+    impl Step for char {}
+    impl Add for i8 {
+        type Output = i8;
+    }
+    impl<'a> Add<&'a i8> for i8 {
+        type Output = i8;
+    }
+    impl<'a> Add<i8> for &'a i8 {
+        type Output = i8;
+    }
+    impl<'a, 'b> Add<&'a i8> for &'b i8 {
+        type Output = i8;
+    }
+    impl AddAssign for i8 {}
+    impl<'a> AddAssign<&'a i8> for i8 {}
+    impl Sub for i8 {
+        type Output = i8;
+    }
+    impl<'a> Sub<&'a i8> for i8 {
+        type Output = i8;
+    }
+    impl<'a> Sub<i8> for &'a i8 {
+        type Output = i8;
+    }
+    impl<'a, 'b> Sub<&'a i8> for &'b i8 {
+        type Output = i8;
+    }
+    impl SubAssign for i8 {}
+    impl<'a> SubAssign<&'a i8> for i8 {}
+    impl Mul for i8 {
+        type Output = i8;
+    }
+    impl<'a> Mul<&'a i8> for i8 {
+        type Output = i8;
+    }
+    impl<'a> Mul<i8> for &'a i8 {
+        type Output = i8;
+    }
+    impl<'a, 'b> Mul<&'a i8> for &'b i8 {
+        type Output = i8;
+    }
+    impl MulAssign for i8 {}
+    impl<'a> MulAssign<&'a i8> for i8 {}
+    impl Div for i8 {
+        type Output = i8;
+    }
+    impl<'a> Div<&'a i8> for i8 {
+        type Output = i8;
+    }
+    impl<'a> Div<i8> for &'a i8 {
+        type Output = i8;
+    }
+    impl<'a, 'b> Div<&'a i8> for &'b i8 {
+        type Output = i8;
+    }
+    impl DivAssign for i8 {}
+    impl<'a> DivAssign<&'a i8> for i8 {}
+    impl Rem for i8 {
+        type Output = i8;
+    }
+    impl<'a> Rem<&'a i8> for i8 {
+        type Output = i8;
+    }
+    impl<'a> Rem<i8> for &'a i8 {
+        type Output = i8;
+    }
+    impl<'a, 'b> Rem<&'a i8> for &'b i8 {
+        type Output = i8;
+    }
+    impl RemAssign for i8 {}
+    impl<'a> RemAssign<&'a i8> for i8 {}
+    impl BitAnd for i8 {
+        type Output = i8;
+    }
+    impl<'a> BitAnd<&'a i8> for i8 {
+        type Output = i8;
+    }
+    impl<'a> BitAnd<i8> for &'a i8 {
+        type Output = i8;
+    }
+    impl<'a, 'b> BitAnd<&'a i8> for &'b i8 {
+        type Output = i8;
+    }
+    impl BitAndAssign for i8 {}
+    impl<'a> BitAndAssign<&'a i8> for i8 {}
+    impl BitOr for i8 {
+        type Output = i8;
+    }
+    impl<'a> BitOr<&'a i8> for i8 {
+        type Output = i8;
+    }
+    impl<'a> BitOr<i8> for &'a i8 {
+        type Output = i8;
+    }
+    impl<'a, 'b> BitOr<&'a i8> for &'b i8 {
+        type Output = i8;
+    }
+    impl BitOrAssign for i8 {}
+    impl<'a> BitOrAssign<&'a i8> for i8 {}
+    impl BitXor for i8 {
+        type Output = i8;
+    }
+    impl<'a> BitXor<&'a i8> for i8 {
+        type Output = i8;
+    }
+    impl<'a> BitXor<i8> for &'a i8 {
+        type Output = i8;
+    }
+    impl<'a, 'b> BitXor<&'a i8> for &'b i8 {
+        type Output = i8;
+    }
+    impl BitXorAssign for i8 {}
+    impl<'a> BitXorAssign<&'a i8> for i8 {}
+    impl Shl<i8> for i8 {
+        type Output = i8;
+    }
+    impl ShlAssign<i8> for i8 {}
+    impl Shl<i16> for i8 {
+        type Output = i8;
+    }
+    impl ShlAssign<i16> for i8 {}
+    impl Shl<i32> for i8 {
+        type Output = i8;
+    }
+    impl ShlAssign<i32> for i8 {}
+    impl Shl<i64> for i8 {
+        type Output = i8;
+    }
+    impl ShlAssign<i64> for i8 {}
+    impl Shl<i128> for i8 {
+        type Output = i8;
+    }
+    impl ShlAssign<i128> for i8 {}
+    impl Shl<isize> for i8 {
+        type Output = i8;
+    }
+    impl ShlAssign<isize> for i8 {}
+    impl Shl<u8> for i8 {
+        type Output = i8;
+    }
+    impl ShlAssign<u8> for i8 {}
+    impl Shl<u16> for i8 {
+        type Output = i8;
+    }
+    impl ShlAssign<u16> for i8 {}
+    impl Shl<u32> for i8 {
+        type Output = i8;
+    }
+    impl ShlAssign<u32> for i8 {}
+    impl Shl<u64> for i8 {
+        type Output = i8;
+    }
+    impl ShlAssign<u64> for i8 {}
+    impl Shl<u128> for i8 {
+        type Output = i8;
+    }
+    impl ShlAssign<u128> for i8 {}
+    impl Shl<usize> for i8 {
+        type Output = i8;
+    }
+    impl ShlAssign<usize> for i8 {}
+    impl Shr<i8> for i8 {
+        type Output = i8;
+    }
+    impl ShrAssign<i8> for i8 {}
+    impl Shr<i16> for i8 {
+        type Output = i8;
+    }
+    impl ShrAssign<i16> for i8 {}
+    impl Shr<i32> for i8 {
+        type Output = i8;
+    }
+    impl ShrAssign<i32> for i8 {}
+    impl Shr<i64> for i8 {
+        type Output = i8;
+    }
+    impl ShrAssign<i64> for i8 {}
+    impl Shr<i128> for i8 {
+        type Output = i8;
+    }
+    impl ShrAssign<i128> for i8 {}
+    impl Shr<isize> for i8 {
+        type Output = i8;
+    }
+    impl ShrAssign<isize> for i8 {}
+    impl Shr<u8> for i8 {
+        type Output = i8;
+    }
+    impl ShrAssign<u8> for i8 {}
+    impl Shr<u16> for i8 {
+        type Output = i8;
+    }
+    impl ShrAssign<u16> for i8 {}
+    impl Shr<u32> for i8 {
+        type Output = i8;
+    }
+    impl ShrAssign<u32> for i8 {}
+    impl Shr<u64> for i8 {
+        type Output = i8;
+    }
+    impl ShrAssign<u64> for i8 {}
+    impl Shr<u128> for i8 {
+        type Output = i8;
+    }
+    impl ShrAssign<u128> for i8 {}
+    impl Shr<usize> for i8 {
+        type Output = i8;
+    }
+    impl ShrAssign<usize> for i8 {}
+    impl Not for i8 {
+        type Output = i8;
+    }
+    impl Neg for i8 {
+        type Output = i8;
+    }
+    impl<'a> Neg for &'a i8 {
+        type Output = i8;
+    }
+    impl Add for i16 {
+        type Output = i16;
+    }
+    impl<'a> Add<&'a i16> for i16 {
+        type Output = i16;
+    }
+    impl<'a> Add<i16> for &'a i16 {
+        type Output = i16;
+    }
+    impl<'a, 'b> Add<&'a i16> for &'b i16 {
+        type Output = i16;
+    }
+    impl AddAssign for i16 {}
+    impl<'a> AddAssign<&'a i16> for i16 {}
+    impl Sub for i16 {
+        type Output = i16;
+    }
+    impl<'a> Sub<&'a i16> for i16 {
+        type Output = i16;
+    }
+    impl<'a> Sub<i16> for &'a i16 {
+        type Output = i16;
+    }
+    impl<'a, 'b> Sub<&'a i16> for &'b i16 {
+        type Output = i16;
+    }
+    impl SubAssign for i16 {}
+    impl<'a> SubAssign<&'a i16> for i16 {}
+    impl Mul for i16 {
+        type Output = i16;
+    }
+    impl<'a> Mul<&'a i16> for i16 {
+        type Output = i16;
+    }
+    impl<'a> Mul<i16> for &'a i16 {
+        type Output = i16;
+    }
+    impl<'a, 'b> Mul<&'a i16> for &'b i16 {
+        type Output = i16;
+    }
+    impl MulAssign for i16 {}
+    impl<'a> MulAssign<&'a i16> for i16 {}
+    impl Div for i16 {
+        type Output = i16;
+    }
+    impl<'a> Div<&'a i16> for i16 {
+        type Output = i16;
+    }
+    impl<'a> Div<i16> for &'a i16 {
+        type Output = i16;
+    }
+    impl<'a, 'b> Div<&'a i16> for &'b i16 {
+        type Output = i16;
+    }
+    impl DivAssign for i16 {}
+    impl<'a> DivAssign<&'a i16> for i16 {}
+    impl Rem for i16 {
+        type Output = i16;
+    }
+    impl<'a> Rem<&'a i16> for i16 {
+        type Output = i16;
+    }
+    impl<'a> Rem<i16> for &'a i16 {
+        type Output = i16;
+    }
+    impl<'a, 'b> Rem<&'a i16> for &'b i16 {
+        type Output = i16;
+    }
+    impl RemAssign for i16 {}
+    impl<'a> RemAssign<&'a i16> for i16 {}
+    impl BitAnd for i16 {
+        type Output = i16;
+    }
+    impl<'a> BitAnd<&'a i16> for i16 {
+        type Output = i16;
+    }
+    impl<'a> BitAnd<i16> for &'a i16 {
+        type Output = i16;
+    }
+    impl<'a, 'b> BitAnd<&'a i16> for &'b i16 {
+        type Output = i16;
+    }
+    impl BitAndAssign for i16 {}
+    impl<'a> BitAndAssign<&'a i16> for i16 {}
+    impl BitOr for i16 {
+        type Output = i16;
+    }
+    impl<'a> BitOr<&'a i16> for i16 {
+        type Output = i16;
+    }
+    impl<'a> BitOr<i16> for &'a i16 {
+        type Output = i16;
+    }
+    impl<'a, 'b> BitOr<&'a i16> for &'b i16 {
+        type Output = i16;
+    }
+    impl BitOrAssign for i16 {}
+    impl<'a> BitOrAssign<&'a i16> for i16 {}
+    impl BitXor for i16 {
+        type Output = i16;
+    }
+    impl<'a> BitXor<&'a i16> for i16 {
+        type Output = i16;
+    }
+    impl<'a> BitXor<i16> for &'a i16 {
+        type Output = i16;
+    }
+    impl<'a, 'b> BitXor<&'a i16> for &'b i16 {
+        type Output = i16;
+    }
+    impl BitXorAssign for i16 {}
+    impl<'a> BitXorAssign<&'a i16> for i16 {}
+    impl Shl<i8> for i16 {
+        type Output = i16;
+    }
+    impl ShlAssign<i8> for i16 {}
+    impl Shl<i16> for i16 {
+        type Output = i16;
+    }
+    impl ShlAssign<i16> for i16 {}
+    impl Shl<i32> for i16 {
+        type Output = i16;
+    }
+    impl ShlAssign<i32> for i16 {}
+    impl Shl<i64> for i16 {
+        type Output = i16;
+    }
+    impl ShlAssign<i64> for i16 {}
+    impl Shl<i128> for i16 {
+        type Output = i16;
+    }
+    impl ShlAssign<i128> for i16 {}
+    impl Shl<isize> for i16 {
+        type Output = i16;
+    }
+    impl ShlAssign<isize> for i16 {}
+    impl Shl<u8> for i16 {
+        type Output = i16;
+    }
+    impl ShlAssign<u8> for i16 {}
+    impl Shl<u16> for i16 {
+        type Output = i16;
+    }
+    impl ShlAssign<u16> for i16 {}
+    impl Shl<u32> for i16 {
+        type Output = i16;
+    }
+    impl ShlAssign<u32> for i16 {}
+    impl Shl<u64> for i16 {
+        type Output = i16;
+    }
+    impl ShlAssign<u64> for i16 {}
+    impl Shl<u128> for i16 {
+        type Output = i16;
+    }
+    impl ShlAssign<u128> for i16 {}
+    impl Shl<usize> for i16 {
+        type Output = i16;
+    }
+    impl ShlAssign<usize> for i16 {}
+    impl Shr<i8> for i16 {
+        type Output = i16;
+    }
+    impl ShrAssign<i8> for i16 {}
+    impl Shr<i16> for i16 {
+        type Output = i16;
+    }
+    impl ShrAssign<i16> for i16 {}
+    impl Shr<i32> for i16 {
+        type Output = i16;
+    }
+    impl ShrAssign<i32> for i16 {}
+    impl Shr<i64> for i16 {
+        type Output = i16;
+    }
+    impl ShrAssign<i64> for i16 {}
+    impl Shr<i128> for i16 {
+        type Output = i16;
+    }
+    impl ShrAssign<i128> for i16 {}
+    impl Shr<isize> for i16 {
+        type Output = i16;
+    }
+    impl ShrAssign<isize> for i16 {}
+    impl Shr<u8> for i16 {
+        type Output = i16;
+    }
+    impl ShrAssign<u8> for i16 {}
+    impl Shr<u16> for i16 {
+        type Output = i16;
+    }
+    impl ShrAssign<u16> for i16 {}
+    impl Shr<u32> for i16 {
+        type Output = i16;
+    }
+    impl ShrAssign<u32> for i16 {}
+    impl Shr<u64> for i16 {
+        type Output = i16;
+    }
+    impl ShrAssign<u64> for i16 {}
+    impl Shr<u128> for i16 {
+        type Output = i16;
+    }
+    impl ShrAssign<u128> for i16 {}
+    impl Shr<usize> for i16 {
+        type Output = i16;
+    }
+    impl ShrAssign<usize> for i16 {}
+    impl Not for i16 {
+        type Output = i16;
+    }
+    impl Neg for i16 {
+        type Output = i16;
+    }
+    impl<'a> Neg for &'a i16 {
+        type Output = i16;
+    }
+    impl Add for i32 {
+        type Output = i32;
+    }
+    impl<'a> Add<&'a i32> for i32 {
+        type Output = i32;
+    }
+    impl<'a> Add<i32> for &'a i32 {
+        type Output = i32;
+    }
+    impl<'a, 'b> Add<&'a i32> for &'b i32 {
+        type Output = i32;
+    }
+    impl AddAssign for i32 {}
+    impl<'a> AddAssign<&'a i32> for i32 {}
+    impl Sub for i32 {
+        type Output = i32;
+    }
+    impl<'a> Sub<&'a i32> for i32 {
+        type Output = i32;
+    }
+    impl<'a> Sub<i32> for &'a i32 {
+        type Output = i32;
+    }
+    impl<'a, 'b> Sub<&'a i32> for &'b i32 {
+        type Output = i32;
+    }
+    impl SubAssign for i32 {}
+    impl<'a> SubAssign<&'a i32> for i32 {}
+    impl Mul for i32 {
+        type Output = i32;
+    }
+    impl<'a> Mul<&'a i32> for i32 {
+        type Output = i32;
+    }
+    impl<'a> Mul<i32> for &'a i32 {
+        type Output = i32;
+    }
+    impl<'a, 'b> Mul<&'a i32> for &'b i32 {
+        type Output = i32;
+    }
+    impl MulAssign for i32 {}
+    impl<'a> MulAssign<&'a i32> for i32 {}
+    impl Div for i32 {
+        type Output = i32;
+    }
+    impl<'a> Div<&'a i32> for i32 {
+        type Output = i32;
+    }
+    impl<'a> Div<i32> for &'a i32 {
+        type Output = i32;
+    }
+    impl<'a, 'b> Div<&'a i32> for &'b i32 {
+        type Output = i32;
+    }
+    impl DivAssign for i32 {}
+    impl<'a> DivAssign<&'a i32> for i32 {}
+    impl Rem for i32 {
+        type Output = i32;
+    }
+    impl<'a> Rem<&'a i32> for i32 {
+        type Output = i32;
+    }
+    impl<'a> Rem<i32> for &'a i32 {
+        type Output = i32;
+    }
+    impl<'a, 'b> Rem<&'a i32> for &'b i32 {
+        type Output = i32;
+    }
+    impl RemAssign for i32 {}
+    impl<'a> RemAssign<&'a i32> for i32 {}
+    impl BitAnd for i32 {
+        type Output = i32;
+    }
+    impl<'a> BitAnd<&'a i32> for i32 {
+        type Output = i32;
+    }
+    impl<'a> BitAnd<i32> for &'a i32 {
+        type Output = i32;
+    }
+    impl<'a, 'b> BitAnd<&'a i32> for &'b i32 {
+        type Output = i32;
+    }
+    impl BitAndAssign for i32 {}
+    impl<'a> BitAndAssign<&'a i32> for i32 {}
+    impl BitOr for i32 {
+        type Output = i32;
+    }
+    impl<'a> BitOr<&'a i32> for i32 {
+        type Output = i32;
+    }
+    impl<'a> BitOr<i32> for &'a i32 {
+        type Output = i32;
+    }
+    impl<'a, 'b> BitOr<&'a i32> for &'b i32 {
+        type Output = i32;
+    }
+    impl BitOrAssign for i32 {}
+    impl<'a> BitOrAssign<&'a i32> for i32 {}
+    impl BitXor for i32 {
+        type Output = i32;
+    }
+    impl<'a> BitXor<&'a i32> for i32 {
+        type Output = i32;
+    }
+    impl<'a> BitXor<i32> for &'a i32 {
+        type Output = i32;
+    }
+    impl<'a, 'b> BitXor<&'a i32> for &'b i32 {
+        type Output = i32;
+    }
+    impl BitXorAssign for i32 {}
+    impl<'a> BitXorAssign<&'a i32> for i32 {}
+    impl Shl<i8> for i32 {
+        type Output = i32;
+    }
+    impl ShlAssign<i8> for i32 {}
+    impl Shl<i16> for i32 {
+        type Output = i32;
+    }
+    impl ShlAssign<i16> for i32 {}
+    impl Shl<i32> for i32 {
+        type Output = i32;
+    }
+    impl ShlAssign<i32> for i32 {}
+    impl Shl<i64> for i32 {
+        type Output = i32;
+    }
+    impl ShlAssign<i64> for i32 {}
+    impl Shl<i128> for i32 {
+        type Output = i32;
+    }
+    impl ShlAssign<i128> for i32 {}
+    impl Shl<isize> for i32 {
+        type Output = i32;
+    }
+    impl ShlAssign<isize> for i32 {}
+    impl Shl<u8> for i32 {
+        type Output = i32;
+    }
+    impl ShlAssign<u8> for i32 {}
+    impl Shl<u16> for i32 {
+        type Output = i32;
+    }
+    impl ShlAssign<u16> for i32 {}
+    impl Shl<u32> for i32 {
+        type Output = i32;
+    }
+    impl ShlAssign<u32> for i32 {}
+    impl Shl<u64> for i32 {
+        type Output = i32;
+    }
+    impl ShlAssign<u64> for i32 {}
+    impl Shl<u128> for i32 {
+        type Output = i32;
+    }
+    impl ShlAssign<u128> for i32 {}
+    impl Shl<usize> for i32 {
+        type Output = i32;
+    }
+    impl ShlAssign<usize> for i32 {}
+    impl Shr<i8> for i32 {
+        type Output = i32;
+    }
+    impl ShrAssign<i8> for i32 {}
+    impl Shr<i16> for i32 {
+        type Output = i32;
+    }
+    impl ShrAssign<i16> for i32 {}
+    impl Shr<i32> for i32 {
+        type Output = i32;
+    }
+    impl ShrAssign<i32> for i32 {}
+    impl Shr<i64> for i32 {
+        type Output = i32;
+    }
+    impl ShrAssign<i64> for i32 {}
+    impl Shr<i128> for i32 {
+        type Output = i32;
+    }
+    impl ShrAssign<i128> for i32 {}
+    impl Shr<isize> for i32 {
+        type Output = i32;
+    }
+    impl ShrAssign<isize> for i32 {}
+    impl Shr<u8> for i32 {
+        type Output = i32;
+    }
+    impl ShrAssign<u8> for i32 {}
+    impl Shr<u16> for i32 {
+        type Output = i32;
+    }
+    impl ShrAssign<u16> for i32 {}
+    impl Shr<u32> for i32 {
+        type Output = i32;
+    }
+    impl ShrAssign<u32> for i32 {}
+    impl Shr<u64> for i32 {
+        type Output = i32;
+    }
+    impl ShrAssign<u64> for i32 {}
+    impl Shr<u128> for i32 {
+        type Output = i32;
+    }
+    impl ShrAssign<u128> for i32 {}
+    impl Shr<usize> for i32 {
+        type Output = i32;
+    }
+    impl ShrAssign<usize> for i32 {}
+    impl Not for i32 {
+        type Output = i32;
+    }
+    impl Neg for i32 {
+        type Output = i32;
+    }
+    impl<'a> Neg for &'a i32 {
+        type Output = i32;
+    }
+    impl Add for i64 {
+        type Output = i64;
+    }
+    impl<'a> Add<&'a i64> for i64 {
+        type Output = i64;
+    }
+    impl<'a> Add<i64> for &'a i64 {
+        type Output = i64;
+    }
+    impl<'a, 'b> Add<&'a i64> for &'b i64 {
+        type Output = i64;
+    }
+    impl AddAssign for i64 {}
+    impl<'a> AddAssign<&'a i64> for i64 {}
+    impl Sub for i64 {
+        type Output = i64;
+    }
+    impl<'a> Sub<&'a i64> for i64 {
+        type Output = i64;
+    }
+    impl<'a> Sub<i64> for &'a i64 {
+        type Output = i64;
+    }
+    impl<'a, 'b> Sub<&'a i64> for &'b i64 {
+        type Output = i64;
+    }
+    impl SubAssign for i64 {}
+    impl<'a> SubAssign<&'a i64> for i64 {}
+    impl Mul for i64 {
+        type Output = i64;
+    }
+    impl<'a> Mul<&'a i64> for i64 {
+        type Output = i64;
+    }
+    impl<'a> Mul<i64> for &'a i64 {
+        type Output = i64;
+    }
+    impl<'a, 'b> Mul<&'a i64> for &'b i64 {
+        type Output = i64;
+    }
+    impl MulAssign for i64 {}
+    impl<'a> MulAssign<&'a i64> for i64 {}
+    impl Div for i64 {
+        type Output = i64;
+    }
+    impl<'a> Div<&'a i64> for i64 {
+        type Output = i64;
+    }
+    impl<'a> Div<i64> for &'a i64 {
+        type Output = i64;
+    }
+    impl<'a, 'b> Div<&'a i64> for &'b i64 {
+        type Output = i64;
+    }
+    impl DivAssign for i64 {}
+    impl<'a> DivAssign<&'a i64> for i64 {}
+    impl Rem for i64 {
+        type Output = i64;
+    }
+    impl<'a> Rem<&'a i64> for i64 {
+        type Output = i64;
+    }
+    impl<'a> Rem<i64> for &'a i64 {
+        type Output = i64;
+    }
+    impl<'a, 'b> Rem<&'a i64> for &'b i64 {
+        type Output = i64;
+    }
+    impl RemAssign for i64 {}
+    impl<'a> RemAssign<&'a i64> for i64 {}
+    impl BitAnd for i64 {
+        type Output = i64;
+    }
+    impl<'a> BitAnd<&'a i64> for i64 {
+        type Output = i64;
+    }
+    impl<'a> BitAnd<i64> for &'a i64 {
+        type Output = i64;
+    }
+    impl<'a, 'b> BitAnd<&'a i64> for &'b i64 {
+        type Output = i64;
+    }
+    impl BitAndAssign for i64 {}
+    impl<'a> BitAndAssign<&'a i64> for i64 {}
+    impl BitOr for i64 {
+        type Output = i64;
+    }
+    impl<'a> BitOr<&'a i64> for i64 {
+        type Output = i64;
+    }
+    impl<'a> BitOr<i64> for &'a i64 {
+        type Output = i64;
+    }
+    impl<'a, 'b> BitOr<&'a i64> for &'b i64 {
+        type Output = i64;
+    }
+    impl BitOrAssign for i64 {}
+    impl<'a> BitOrAssign<&'a i64> for i64 {}
+    impl BitXor for i64 {
+        type Output = i64;
+    }
+    impl<'a> BitXor<&'a i64> for i64 {
+        type Output = i64;
+    }
+    impl<'a> BitXor<i64> for &'a i64 {
+        type Output = i64;
+    }
+    impl<'a, 'b> BitXor<&'a i64> for &'b i64 {
+        type Output = i64;
+    }
+    impl BitXorAssign for i64 {}
+    impl<'a> BitXorAssign<&'a i64> for i64 {}
+    impl Shl<i8> for i64 {
+        type Output = i64;
+    }
+    impl ShlAssign<i8> for i64 {}
+    impl Shl<i16> for i64 {
+        type Output = i64;
+    }
+    impl ShlAssign<i16> for i64 {}
+    impl Shl<i32> for i64 {
+        type Output = i64;
+    }
+    impl ShlAssign<i32> for i64 {}
+    impl Shl<i64> for i64 {
+        type Output = i64;
+    }
+    impl ShlAssign<i64> for i64 {}
+    impl Shl<i128> for i64 {
+        type Output = i64;
+    }
+    impl ShlAssign<i128> for i64 {}
+    impl Shl<isize> for i64 {
+        type Output = i64;
+    }
+    impl ShlAssign<isize> for i64 {}
+    impl Shl<u8> for i64 {
+        type Output = i64;
+    }
+    impl ShlAssign<u8> for i64 {}
+    impl Shl<u16> for i64 {
+        type Output = i64;
+    }
+    impl ShlAssign<u16> for i64 {}
+    impl Shl<u32> for i64 {
+        type Output = i64;
+    }
+    impl ShlAssign<u32> for i64 {}
+    impl Shl<u64> for i64 {
+        type Output = i64;
+    }
+    impl ShlAssign<u64> for i64 {}
+    impl Shl<u128> for i64 {
+        type Output = i64;
+    }
+    impl ShlAssign<u128> for i64 {}
+    impl Shl<usize> for i64 {
+        type Output = i64;
+    }
+    impl ShlAssign<usize> for i64 {}
+    impl Shr<i8> for i64 {
+        type Output = i64;
+    }
+    impl ShrAssign<i8> for i64 {}
+    impl Shr<i16> for i64 {
+        type Output = i64;
+    }
+    impl ShrAssign<i16> for i64 {}
+    impl Shr<i32> for i64 {
+        type Output = i64;
+    }
+    impl ShrAssign<i32> for i64 {}
+    impl Shr<i64> for i64 {
+        type Output = i64;
+    }
+    impl ShrAssign<i64> for i64 {}
+    impl Shr<i128> for i64 {
+        type Output = i64;
+    }
+    impl ShrAssign<i128> for i64 {}
+    impl Shr<isize> for i64 {
+        type Output = i64;
+    }
+    impl ShrAssign<isize> for i64 {}
+    impl Shr<u8> for i64 {
+        type Output = i64;
+    }
+    impl ShrAssign<u8> for i64 {}
+    impl Shr<u16> for i64 {
+        type Output = i64;
+    }
+    impl ShrAssign<u16> for i64 {}
+    impl Shr<u32> for i64 {
+        type Output = i64;
+    }
+    impl ShrAssign<u32> for i64 {}
+    impl Shr<u64> for i64 {
+        type Output = i64;
+    }
+    impl ShrAssign<u64> for i64 {}
+    impl Shr<u128> for i64 {
+        type Output = i64;
+    }
+    impl ShrAssign<u128> for i64 {}
+    impl Shr<usize> for i64 {
+        type Output = i64;
+    }
+    impl ShrAssign<usize> for i64 {}
+    impl Not for i64 {
+        type Output = i64;
+    }
+    impl Neg for i64 {
+        type Output = i64;
+    }
+    impl<'a> Neg for &'a i64 {
+        type Output = i64;
+    }
+    impl Add for i128 {
+        type Output = i128;
+    }
+    impl<'a> Add<&'a i128> for i128 {
+        type Output = i128;
+    }
+    impl<'a> Add<i128> for &'a i128 {
+        type Output = i128;
+    }
+    impl<'a, 'b> Add<&'a i128> for &'b i128 {
+        type Output = i128;
+    }
+    impl AddAssign for i128 {}
+    impl<'a> AddAssign<&'a i128> for i128 {}
+    impl Sub for i128 {
+        type Output = i128;
+    }
+    impl<'a> Sub<&'a i128> for i128 {
+        type Output = i128;
+    }
+    impl<'a> Sub<i128> for &'a i128 {
+        type Output = i128;
+    }
+    impl<'a, 'b> Sub<&'a i128> for &'b i128 {
+        type Output = i128;
+    }
+    impl SubAssign for i128 {}
+    impl<'a> SubAssign<&'a i128> for i128 {}
+    impl Mul for i128 {
+        type Output = i128;
+    }
+    impl<'a> Mul<&'a i128> for i128 {
+        type Output = i128;
+    }
+    impl<'a> Mul<i128> for &'a i128 {
+        type Output = i128;
+    }
+    impl<'a, 'b> Mul<&'a i128> for &'b i128 {
+        type Output = i128;
+    }
+    impl MulAssign for i128 {}
+    impl<'a> MulAssign<&'a i128> for i128 {}
+    impl Div for i128 {
+        type Output = i128;
+    }
+    impl<'a> Div<&'a i128> for i128 {
+        type Output = i128;
+    }
+    impl<'a> Div<i128> for &'a i128 {
+        type Output = i128;
+    }
+    impl<'a, 'b> Div<&'a i128> for &'b i128 {
+        type Output = i128;
+    }
+    impl DivAssign for i128 {}
+    impl<'a> DivAssign<&'a i128> for i128 {}
+    impl Rem for i128 {
+        type Output = i128;
+    }
+    impl<'a> Rem<&'a i128> for i128 {
+        type Output = i128;
+    }
+    impl<'a> Rem<i128> for &'a i128 {
+        type Output = i128;
+    }
+    impl<'a, 'b> Rem<&'a i128> for &'b i128 {
+        type Output = i128;
+    }
+    impl RemAssign for i128 {}
+    impl<'a> RemAssign<&'a i128> for i128 {}
+    impl BitAnd for i128 {
+        type Output = i128;
+    }
+    impl<'a> BitAnd<&'a i128> for i128 {
+        type Output = i128;
+    }
+    impl<'a> BitAnd<i128> for &'a i128 {
+        type Output = i128;
+    }
+    impl<'a, 'b> BitAnd<&'a i128> for &'b i128 {
+        type Output = i128;
+    }
+    impl BitAndAssign for i128 {}
+    impl<'a> BitAndAssign<&'a i128> for i128 {}
+    impl BitOr for i128 {
+        type Output = i128;
+    }
+    impl<'a> BitOr<&'a i128> for i128 {
+        type Output = i128;
+    }
+    impl<'a> BitOr<i128> for &'a i128 {
+        type Output = i128;
+    }
+    impl<'a, 'b> BitOr<&'a i128> for &'b i128 {
+        type Output = i128;
+    }
+    impl BitOrAssign for i128 {}
+    impl<'a> BitOrAssign<&'a i128> for i128 {}
+    impl BitXor for i128 {
+        type Output = i128;
+    }
+    impl<'a> BitXor<&'a i128> for i128 {
+        type Output = i128;
+    }
+    impl<'a> BitXor<i128> for &'a i128 {
+        type Output = i128;
+    }
+    impl<'a, 'b> BitXor<&'a i128> for &'b i128 {
+        type Output = i128;
+    }
+    impl BitXorAssign for i128 {}
+    impl<'a> BitXorAssign<&'a i128> for i128 {}
+    impl Shl<i8> for i128 {
+        type Output = i128;
+    }
+    impl ShlAssign<i8> for i128 {}
+    impl Shl<i16> for i128 {
+        type Output = i128;
+    }
+    impl ShlAssign<i16> for i128 {}
+    impl Shl<i32> for i128 {
+        type Output = i128;
+    }
+    impl ShlAssign<i32> for i128 {}
+    impl Shl<i64> for i128 {
+        type Output = i128;
+    }
+    impl ShlAssign<i64> for i128 {}
+    impl Shl<i128> for i128 {
+        type Output = i128;
+    }
+    impl ShlAssign<i128> for i128 {}
+    impl Shl<isize> for i128 {
+        type Output = i128;
+    }
+    impl ShlAssign<isize> for i128 {}
+    impl Shl<u8> for i128 {
+        type Output = i128;
+    }
+    impl ShlAssign<u8> for i128 {}
+    impl Shl<u16> for i128 {
+        type Output = i128;
+    }
+    impl ShlAssign<u16> for i128 {}
+    impl Shl<u32> for i128 {
+        type Output = i128;
+    }
+    impl ShlAssign<u32> for i128 {}
+    impl Shl<u64> for i128 {
+        type Output = i128;
+    }
+    impl ShlAssign<u64> for i128 {}
+    impl Shl<u128> for i128 {
+        type Output = i128;
+    }
+    impl ShlAssign<u128> for i128 {}
+    impl Shl<usize> for i128 {
+        type Output = i128;
+    }
+    impl ShlAssign<usize> for i128 {}
+    impl Shr<i8> for i128 {
+        type Output = i128;
+    }
+    impl ShrAssign<i8> for i128 {}
+    impl Shr<i16> for i128 {
+        type Output = i128;
+    }
+    impl ShrAssign<i16> for i128 {}
+    impl Shr<i32> for i128 {
+        type Output = i128;
+    }
+    impl ShrAssign<i32> for i128 {}
+    impl Shr<i64> for i128 {
+        type Output = i128;
+    }
+    impl ShrAssign<i64> for i128 {}
+    impl Shr<i128> for i128 {
+        type Output = i128;
+    }
+    impl ShrAssign<i128> for i128 {}
+    impl Shr<isize> for i128 {
+        type Output = i128;
+    }
+    impl ShrAssign<isize> for i128 {}
+    impl Shr<u8> for i128 {
+        type Output = i128;
+    }
+    impl ShrAssign<u8> for i128 {}
+    impl Shr<u16> for i128 {
+        type Output = i128;
+    }
+    impl ShrAssign<u16> for i128 {}
+    impl Shr<u32> for i128 {
+        type Output = i128;
+    }
+    impl ShrAssign<u32> for i128 {}
+    impl Shr<u64> for i128 {
+        type Output = i128;
+    }
+    impl ShrAssign<u64> for i128 {}
+    impl Shr<u128> for i128 {
+        type Output = i128;
+    }
+    impl ShrAssign<u128> for i128 {}
+    impl Shr<usize> for i128 {
+        type Output = i128;
+    }
+    impl ShrAssign<usize> for i128 {}
+    impl Not for i128 {
+        type Output = i128;
+    }
+    impl Neg for i128 {
+        type Output = i128;
+    }
+    impl<'a> Neg for &'a i128 {
+        type Output = i128;
+    }
+    impl Add for isize {
+        type Output = isize;
+    }
+    impl<'a> Add<&'a isize> for isize {
+        type Output = isize;
+    }
+    impl<'a> Add<isize> for &'a isize {
+        type Output = isize;
+    }
+    impl<'a, 'b> Add<&'a isize> for &'b isize {
+        type Output = isize;
+    }
+    impl AddAssign for isize {}
+    impl<'a> AddAssign<&'a isize> for isize {}
+    impl Sub for isize {
+        type Output = isize;
+    }
+    impl<'a> Sub<&'a isize> for isize {
+        type Output = isize;
+    }
+    impl<'a> Sub<isize> for &'a isize {
+        type Output = isize;
+    }
+    impl<'a, 'b> Sub<&'a isize> for &'b isize {
+        type Output = isize;
+    }
+    impl SubAssign for isize {}
+    impl<'a> SubAssign<&'a isize> for isize {}
+    impl Mul for isize {
+        type Output = isize;
+    }
+    impl<'a> Mul<&'a isize> for isize {
+        type Output = isize;
+    }
+    impl<'a> Mul<isize> for &'a isize {
+        type Output = isize;
+    }
+    impl<'a, 'b> Mul<&'a isize> for &'b isize {
+        type Output = isize;
+    }
+    impl MulAssign for isize {}
+    impl<'a> MulAssign<&'a isize> for isize {}
+    impl Div for isize {
+        type Output = isize;
+    }
+    impl<'a> Div<&'a isize> for isize {
+        type Output = isize;
+    }
+    impl<'a> Div<isize> for &'a isize {
+        type Output = isize;
+    }
+    impl<'a, 'b> Div<&'a isize> for &'b isize {
+        type Output = isize;
+    }
+    impl DivAssign for isize {}
+    impl<'a> DivAssign<&'a isize> for isize {}
+    impl Rem for isize {
+        type Output = isize;
+    }
+    impl<'a> Rem<&'a isize> for isize {
+        type Output = isize;
+    }
+    impl<'a> Rem<isize> for &'a isize {
+        type Output = isize;
+    }
+    impl<'a, 'b> Rem<&'a isize> for &'b isize {
+        type Output = isize;
+    }
+    impl RemAssign for isize {}
+    impl<'a> RemAssign<&'a isize> for isize {}
+    impl BitAnd for isize {
+        type Output = isize;
+    }
+    impl<'a> BitAnd<&'a isize> for isize {
+        type Output = isize;
+    }
+    impl<'a> BitAnd<isize> for &'a isize {
+        type Output = isize;
+    }
+    impl<'a, 'b> BitAnd<&'a isize> for &'b isize {
+        type Output = isize;
+    }
+    impl BitAndAssign for isize {}
+    impl<'a> BitAndAssign<&'a isize> for isize {}
+    impl BitOr for isize {
+        type Output = isize;
+    }
+    impl<'a> BitOr<&'a isize> for isize {
+        type Output = isize;
+    }
+    impl<'a> BitOr<isize> for &'a isize {
+        type Output = isize;
+    }
+    impl<'a, 'b> BitOr<&'a isize> for &'b isize {
+        type Output = isize;
+    }
+    impl BitOrAssign for isize {}
+    impl<'a> BitOrAssign<&'a isize> for isize {}
+    impl BitXor for isize {
+        type Output = isize;
+    }
+    impl<'a> BitXor<&'a isize> for isize {
+        type Output = isize;
+    }
+    impl<'a> BitXor<isize> for &'a isize {
+        type Output = isize;
+    }
+    impl<'a, 'b> BitXor<&'a isize> for &'b isize {
+        type Output = isize;
+    }
+    impl BitXorAssign for isize {}
+    impl<'a> BitXorAssign<&'a isize> for isize {}
+    impl Shl<i8> for isize {
+        type Output = isize;
+    }
+    impl ShlAssign<i8> for isize {}
+    impl Shl<i16> for isize {
+        type Output = isize;
+    }
+    impl ShlAssign<i16> for isize {}
+    impl Shl<i32> for isize {
+        type Output = isize;
+    }
+    impl ShlAssign<i32> for isize {}
+    impl Shl<i64> for isize {
+        type Output = isize;
+    }
+    impl ShlAssign<i64> for isize {}
+    impl Shl<i128> for isize {
+        type Output = isize;
+    }
+    impl ShlAssign<i128> for isize {}
+    impl Shl<isize> for isize {
+        type Output = isize;
+    }
+    impl ShlAssign<isize> for isize {}
+    impl Shl<u8> for isize {
+        type Output = isize;
+    }
+    impl ShlAssign<u8> for isize {}
+    impl Shl<u16> for isize {
+        type Output = isize;
+    }
+    impl ShlAssign<u16> for isize {}
+    impl Shl<u32> for isize {
+        type Output = isize;
+    }
+    impl ShlAssign<u32> for isize {}
+    impl Shl<u64> for isize {
+        type Output = isize;
+    }
+    impl ShlAssign<u64> for isize {}
+    impl Shl<u128> for isize {
+        type Output = isize;
+    }
+    impl ShlAssign<u128> for isize {}
+    impl Shl<usize> for isize {
+        type Output = isize;
+    }
+    impl ShlAssign<usize> for isize {}
+    impl Shr<i8> for isize {
+        type Output = isize;
+    }
+    impl ShrAssign<i8> for isize {}
+    impl Shr<i16> for isize {
+        type Output = isize;
+    }
+    impl ShrAssign<i16> for isize {}
+    impl Shr<i32> for isize {
+        type Output = isize;
+    }
+    impl ShrAssign<i32> for isize {}
+    impl Shr<i64> for isize {
+        type Output = isize;
+    }
+    impl ShrAssign<i64> for isize {}
+    impl Shr<i128> for isize {
+        type Output = isize;
+    }
+    impl ShrAssign<i128> for isize {}
+    impl Shr<isize> for isize {
+        type Output = isize;
+    }
+    impl ShrAssign<isize> for isize {}
+    impl Shr<u8> for isize {
+        type Output = isize;
+    }
+    impl ShrAssign<u8> for isize {}
+    impl Shr<u16> for isize {
+        type Output = isize;
+    }
+    impl ShrAssign<u16> for isize {}
+    impl Shr<u32> for isize {
+        type Output = isize;
+    }
+    impl ShrAssign<u32> for isize {}
+    impl Shr<u64> for isize {
+        type Output = isize;
+    }
+    impl ShrAssign<u64> for isize {}
+    impl Shr<u128> for isize {
+        type Output = isize;
+    }
+    impl ShrAssign<u128> for isize {}
+    impl Shr<usize> for isize {
+        type Output = isize;
+    }
+    impl ShrAssign<usize> for isize {}
+    impl Not for isize {
+        type Output = isize;
+    }
+    impl Neg for isize {
+        type Output = isize;
+    }
+    impl<'a> Neg for &'a isize {
+        type Output = isize;
+    }
+    impl Add for u8 {
+        type Output = u8;
+    }
+    impl<'a> Add<&'a u8> for u8 {
+        type Output = u8;
+    }
+    impl<'a> Add<u8> for &'a u8 {
+        type Output = u8;
+    }
+    impl<'a, 'b> Add<&'a u8> for &'b u8 {
+        type Output = u8;
+    }
+    impl AddAssign for u8 {}
+    impl<'a> AddAssign<&'a u8> for u8 {}
+    impl Sub for u8 {
+        type Output = u8;
+    }
+    impl<'a> Sub<&'a u8> for u8 {
+        type Output = u8;
+    }
+    impl<'a> Sub<u8> for &'a u8 {
+        type Output = u8;
+    }
+    impl<'a, 'b> Sub<&'a u8> for &'b u8 {
+        type Output = u8;
+    }
+    impl SubAssign for u8 {}
+    impl<'a> SubAssign<&'a u8> for u8 {}
+    impl Mul for u8 {
+        type Output = u8;
+    }
+    impl<'a> Mul<&'a u8> for u8 {
+        type Output = u8;
+    }
+    impl<'a> Mul<u8> for &'a u8 {
+        type Output = u8;
+    }
+    impl<'a, 'b> Mul<&'a u8> for &'b u8 {
+        type Output = u8;
+    }
+    impl MulAssign for u8 {}
+    impl<'a> MulAssign<&'a u8> for u8 {}
+    impl Div for u8 {
+        type Output = u8;
+    }
+    impl<'a> Div<&'a u8> for u8 {
+        type Output = u8;
+    }
+    impl<'a> Div<u8> for &'a u8 {
+        type Output = u8;
+    }
+    impl<'a, 'b> Div<&'a u8> for &'b u8 {
+        type Output = u8;
+    }
+    impl DivAssign for u8 {}
+    impl<'a> DivAssign<&'a u8> for u8 {}
+    impl Rem for u8 {
+        type Output = u8;
+    }
+    impl<'a> Rem<&'a u8> for u8 {
+        type Output = u8;
+    }
+    impl<'a> Rem<u8> for &'a u8 {
+        type Output = u8;
+    }
+    impl<'a, 'b> Rem<&'a u8> for &'b u8 {
+        type Output = u8;
+    }
+    impl RemAssign for u8 {}
+    impl<'a> RemAssign<&'a u8> for u8 {}
+    impl BitAnd for u8 {
+        type Output = u8;
+    }
+    impl<'a> BitAnd<&'a u8> for u8 {
+        type Output = u8;
+    }
+    impl<'a> BitAnd<u8> for &'a u8 {
+        type Output = u8;
+    }
+    impl<'a, 'b> BitAnd<&'a u8> for &'b u8 {
+        type Output = u8;
+    }
+    impl BitAndAssign for u8 {}
+    impl<'a> BitAndAssign<&'a u8> for u8 {}
+    impl BitOr for u8 {
+        type Output = u8;
+    }
+    impl<'a> BitOr<&'a u8> for u8 {
+        type Output = u8;
+    }
+    impl<'a> BitOr<u8> for &'a u8 {
+        type Output = u8;
+    }
+    impl<'a, 'b> BitOr<&'a u8> for &'b u8 {
+        type Output = u8;
+    }
+    impl BitOrAssign for u8 {}
+    impl<'a> BitOrAssign<&'a u8> for u8 {}
+    impl BitXor for u8 {
+        type Output = u8;
+    }
+    impl<'a> BitXor<&'a u8> for u8 {
+        type Output = u8;
+    }
+    impl<'a> BitXor<u8> for &'a u8 {
+        type Output = u8;
+    }
+    impl<'a, 'b> BitXor<&'a u8> for &'b u8 {
+        type Output = u8;
+    }
+    impl BitXorAssign for u8 {}
+    impl<'a> BitXorAssign<&'a u8> for u8 {}
+    impl Shl<i8> for u8 {
+        type Output = u8;
+    }
+    impl ShlAssign<i8> for u8 {}
+    impl Shl<i16> for u8 {
+        type Output = u8;
+    }
+    impl ShlAssign<i16> for u8 {}
+    impl Shl<i32> for u8 {
+        type Output = u8;
+    }
+    impl ShlAssign<i32> for u8 {}
+    impl Shl<i64> for u8 {
+        type Output = u8;
+    }
+    impl ShlAssign<i64> for u8 {}
+    impl Shl<i128> for u8 {
+        type Output = u8;
+    }
+    impl ShlAssign<i128> for u8 {}
+    impl Shl<isize> for u8 {
+        type Output = u8;
+    }
+    impl ShlAssign<isize> for u8 {}
+    impl Shl<u8> for u8 {
+        type Output = u8;
+    }
+    impl ShlAssign<u8> for u8 {}
+    impl Shl<u16> for u8 {
+        type Output = u8;
+    }
+    impl ShlAssign<u16> for u8 {}
+    impl Shl<u32> for u8 {
+        type Output = u8;
+    }
+    impl ShlAssign<u32> for u8 {}
+    impl Shl<u64> for u8 {
+        type Output = u8;
+    }
+    impl ShlAssign<u64> for u8 {}
+    impl Shl<u128> for u8 {
+        type Output = u8;
+    }
+    impl ShlAssign<u128> for u8 {}
+    impl Shl<usize> for u8 {
+        type Output = u8;
+    }
+    impl ShlAssign<usize> for u8 {}
+    impl Shr<i8> for u8 {
+        type Output = u8;
+    }
+    impl ShrAssign<i8> for u8 {}
+    impl Shr<i16> for u8 {
+        type Output = u8;
+    }
+    impl ShrAssign<i16> for u8 {}
+    impl Shr<i32> for u8 {
+        type Output = u8;
+    }
+    impl ShrAssign<i32> for u8 {}
+    impl Shr<i64> for u8 {
+        type Output = u8;
+    }
+    impl ShrAssign<i64> for u8 {}
+    impl Shr<i128> for u8 {
+        type Output = u8;
+    }
+    impl ShrAssign<i128> for u8 {}
+    impl Shr<isize> for u8 {
+        type Output = u8;
+    }
+    impl ShrAssign<isize> for u8 {}
+    impl Shr<u8> for u8 {
+        type Output = u8;
+    }
+    impl ShrAssign<u8> for u8 {}
+    impl Shr<u16> for u8 {
+        type Output = u8;
+    }
+    impl ShrAssign<u16> for u8 {}
+    impl Shr<u32> for u8 {
+        type Output = u8;
+    }
+    impl ShrAssign<u32> for u8 {}
+    impl Shr<u64> for u8 {
+        type Output = u8;
+    }
+    impl ShrAssign<u64> for u8 {}
+    impl Shr<u128> for u8 {
+        type Output = u8;
+    }
+    impl ShrAssign<u128> for u8 {}
+    impl Shr<usize> for u8 {
+        type Output = u8;
+    }
+    impl ShrAssign<usize> for u8 {}
+    impl Not for u8 {
+        type Output = u8;
+    }
+    impl Add for u16 {
+        type Output = u16;
+    }
+    impl<'a> Add<&'a u16> for u16 {
+        type Output = u16;
+    }
+    impl<'a> Add<u16> for &'a u16 {
+        type Output = u16;
+    }
+    impl<'a, 'b> Add<&'a u16> for &'b u16 {
+        type Output = u16;
+    }
+    impl AddAssign for u16 {}
+    impl<'a> AddAssign<&'a u16> for u16 {}
+    impl Sub for u16 {
+        type Output = u16;
+    }
+    impl<'a> Sub<&'a u16> for u16 {
+        type Output = u16;
+    }
+    impl<'a> Sub<u16> for &'a u16 {
+        type Output = u16;
+    }
+    impl<'a, 'b> Sub<&'a u16> for &'b u16 {
+        type Output = u16;
+    }
+    impl SubAssign for u16 {}
+    impl<'a> SubAssign<&'a u16> for u16 {}
+    impl Mul for u16 {
+        type Output = u16;
+    }
+    impl<'a> Mul<&'a u16> for u16 {
+        type Output = u16;
+    }
+    impl<'a> Mul<u16> for &'a u16 {
+        type Output = u16;
+    }
+    impl<'a, 'b> Mul<&'a u16> for &'b u16 {
+        type Output = u16;
+    }
+    impl MulAssign for u16 {}
+    impl<'a> MulAssign<&'a u16> for u16 {}
+    impl Div for u16 {
+        type Output = u16;
+    }
+    impl<'a> Div<&'a u16> for u16 {
+        type Output = u16;
+    }
+    impl<'a> Div<u16> for &'a u16 {
+        type Output = u16;
+    }
+    impl<'a, 'b> Div<&'a u16> for &'b u16 {
+        type Output = u16;
+    }
+    impl DivAssign for u16 {}
+    impl<'a> DivAssign<&'a u16> for u16 {}
+    impl Rem for u16 {
+        type Output = u16;
+    }
+    impl<'a> Rem<&'a u16> for u16 {
+        type Output = u16;
+    }
+    impl<'a> Rem<u16> for &'a u16 {
+        type Output = u16;
+    }
+    impl<'a, 'b> Rem<&'a u16> for &'b u16 {
+        type Output = u16;
+    }
+    impl RemAssign for u16 {}
+    impl<'a> RemAssign<&'a u16> for u16 {}
+    impl BitAnd for u16 {
+        type Output = u16;
+    }
+    impl<'a> BitAnd<&'a u16> for u16 {
+        type Output = u16;
+    }
+    impl<'a> BitAnd<u16> for &'a u16 {
+        type Output = u16;
+    }
+    impl<'a, 'b> BitAnd<&'a u16> for &'b u16 {
+        type Output = u16;
+    }
+    impl BitAndAssign for u16 {}
+    impl<'a> BitAndAssign<&'a u16> for u16 {}
+    impl BitOr for u16 {
+        type Output = u16;
+    }
+    impl<'a> BitOr<&'a u16> for u16 {
+        type Output = u16;
+    }
+    impl<'a> BitOr<u16> for &'a u16 {
+        type Output = u16;
+    }
+    impl<'a, 'b> BitOr<&'a u16> for &'b u16 {
+        type Output = u16;
+    }
+    impl BitOrAssign for u16 {}
+    impl<'a> BitOrAssign<&'a u16> for u16 {}
+    impl BitXor for u16 {
+        type Output = u16;
+    }
+    impl<'a> BitXor<&'a u16> for u16 {
+        type Output = u16;
+    }
+    impl<'a> BitXor<u16> for &'a u16 {
+        type Output = u16;
+    }
+    impl<'a, 'b> BitXor<&'a u16> for &'b u16 {
+        type Output = u16;
+    }
+    impl BitXorAssign for u16 {}
+    impl<'a> BitXorAssign<&'a u16> for u16 {}
+    impl Shl<i8> for u16 {
+        type Output = u16;
+    }
+    impl ShlAssign<i8> for u16 {}
+    impl Shl<i16> for u16 {
+        type Output = u16;
+    }
+    impl ShlAssign<i16> for u16 {}
+    impl Shl<i32> for u16 {
+        type Output = u16;
+    }
+    impl ShlAssign<i32> for u16 {}
+    impl Shl<i64> for u16 {
+        type Output = u16;
+    }
+    impl ShlAssign<i64> for u16 {}
+    impl Shl<i128> for u16 {
+        type Output = u16;
+    }
+    impl ShlAssign<i128> for u16 {}
+    impl Shl<isize> for u16 {
+        type Output = u16;
+    }
+    impl ShlAssign<isize> for u16 {}
+    impl Shl<u8> for u16 {
+        type Output = u16;
+    }
+    impl ShlAssign<u8> for u16 {}
+    impl Shl<u16> for u16 {
+        type Output = u16;
+    }
+    impl ShlAssign<u16> for u16 {}
+    impl Shl<u32> for u16 {
+        type Output = u16;
+    }
+    impl ShlAssign<u32> for u16 {}
+    impl Shl<u64> for u16 {
+        type Output = u16;
+    }
+    impl ShlAssign<u64> for u16 {}
+    impl Shl<u128> for u16 {
+        type Output = u16;
+    }
+    impl ShlAssign<u128> for u16 {}
+    impl Shl<usize> for u16 {
+        type Output = u16;
+    }
+    impl ShlAssign<usize> for u16 {}
+    impl Shr<i8> for u16 {
+        type Output = u16;
+    }
+    impl ShrAssign<i8> for u16 {}
+    impl Shr<i16> for u16 {
+        type Output = u16;
+    }
+    impl ShrAssign<i16> for u16 {}
+    impl Shr<i32> for u16 {
+        type Output = u16;
+    }
+    impl ShrAssign<i32> for u16 {}
+    impl Shr<i64> for u16 {
+        type Output = u16;
+    }
+    impl ShrAssign<i64> for u16 {}
+    impl Shr<i128> for u16 {
+        type Output = u16;
+    }
+    impl ShrAssign<i128> for u16 {}
+    impl Shr<isize> for u16 {
+        type Output = u16;
+    }
+    impl ShrAssign<isize> for u16 {}
+    impl Shr<u8> for u16 {
+        type Output = u16;
+    }
+    impl ShrAssign<u8> for u16 {}
+    impl Shr<u16> for u16 {
+        type Output = u16;
+    }
+    impl ShrAssign<u16> for u16 {}
+    impl Shr<u32> for u16 {
+        type Output = u16;
+    }
+    impl ShrAssign<u32> for u16 {}
+    impl Shr<u64> for u16 {
+        type Output = u16;
+    }
+    impl ShrAssign<u64> for u16 {}
+    impl Shr<u128> for u16 {
+        type Output = u16;
+    }
+    impl ShrAssign<u128> for u16 {}
+    impl Shr<usize> for u16 {
+        type Output = u16;
+    }
+    impl ShrAssign<usize> for u16 {}
+    impl Not for u16 {
+        type Output = u16;
+    }
+    impl Add for u32 {
+        type Output = u32;
+    }
+    impl<'a> Add<&'a u32> for u32 {
+        type Output = u32;
+    }
+    impl<'a> Add<u32> for &'a u32 {
+        type Output = u32;
+    }
+    impl<'a, 'b> Add<&'a u32> for &'b u32 {
+        type Output = u32;
+    }
+    impl AddAssign for u32 {}
+    impl<'a> AddAssign<&'a u32> for u32 {}
+    impl Sub for u32 {
+        type Output = u32;
+    }
+    impl<'a> Sub<&'a u32> for u32 {
+        type Output = u32;
+    }
+    impl<'a> Sub<u32> for &'a u32 {
+        type Output = u32;
+    }
+    impl<'a, 'b> Sub<&'a u32> for &'b u32 {
+        type Output = u32;
+    }
+    impl SubAssign for u32 {}
+    impl<'a> SubAssign<&'a u32> for u32 {}
+    impl Mul for u32 {
+        type Output = u32;
+    }
+    impl<'a> Mul<&'a u32> for u32 {
+        type Output = u32;
+    }
+    impl<'a> Mul<u32> for &'a u32 {
+        type Output = u32;
+    }
+    impl<'a, 'b> Mul<&'a u32> for &'b u32 {
+        type Output = u32;
+    }
+    impl MulAssign for u32 {}
+    impl<'a> MulAssign<&'a u32> for u32 {}
+    impl Div for u32 {
+        type Output = u32;
+    }
+    impl<'a> Div<&'a u32> for u32 {
+        type Output = u32;
+    }
+    impl<'a> Div<u32> for &'a u32 {
+        type Output = u32;
+    }
+    impl<'a, 'b> Div<&'a u32> for &'b u32 {
+        type Output = u32;
+    }
+    impl DivAssign for u32 {}
+    impl<'a> DivAssign<&'a u32> for u32 {}
+    impl Rem for u32 {
+        type Output = u32;
+    }
+    impl<'a> Rem<&'a u32> for u32 {
+        type Output = u32;
+    }
+    impl<'a> Rem<u32> for &'a u32 {
+        type Output = u32;
+    }
+    impl<'a, 'b> Rem<&'a u32> for &'b u32 {
+        type Output = u32;
+    }
+    impl RemAssign for u32 {}
+    impl<'a> RemAssign<&'a u32> for u32 {}
+    impl BitAnd for u32 {
+        type Output = u32;
+    }
+    impl<'a> BitAnd<&'a u32> for u32 {
+        type Output = u32;
+    }
+    impl<'a> BitAnd<u32> for &'a u32 {
+        type Output = u32;
+    }
+    impl<'a, 'b> BitAnd<&'a u32> for &'b u32 {
+        type Output = u32;
+    }
+    impl BitAndAssign for u32 {}
+    impl<'a> BitAndAssign<&'a u32> for u32 {}
+    impl BitOr for u32 {
+        type Output = u32;
+    }
+    impl<'a> BitOr<&'a u32> for u32 {
+        type Output = u32;
+    }
+    impl<'a> BitOr<u32> for &'a u32 {
+        type Output = u32;
+    }
+    impl<'a, 'b> BitOr<&'a u32> for &'b u32 {
+        type Output = u32;
+    }
+    impl BitOrAssign for u32 {}
+    impl<'a> BitOrAssign<&'a u32> for u32 {}
+    impl BitXor for u32 {
+        type Output = u32;
+    }
+    impl<'a> BitXor<&'a u32> for u32 {
+        type Output = u32;
+    }
+    impl<'a> BitXor<u32> for &'a u32 {
+        type Output = u32;
+    }
+    impl<'a, 'b> BitXor<&'a u32> for &'b u32 {
+        type Output = u32;
+    }
+    impl BitXorAssign for u32 {}
+    impl<'a> BitXorAssign<&'a u32> for u32 {}
+    impl Shl<i8> for u32 {
+        type Output = u32;
+    }
+    impl ShlAssign<i8> for u32 {}
+    impl Shl<i16> for u32 {
+        type Output = u32;
+    }
+    impl ShlAssign<i16> for u32 {}
+    impl Shl<i32> for u32 {
+        type Output = u32;
+    }
+    impl ShlAssign<i32> for u32 {}
+    impl Shl<i64> for u32 {
+        type Output = u32;
+    }
+    impl ShlAssign<i64> for u32 {}
+    impl Shl<i128> for u32 {
+        type Output = u32;
+    }
+    impl ShlAssign<i128> for u32 {}
+    impl Shl<isize> for u32 {
+        type Output = u32;
+    }
+    impl ShlAssign<isize> for u32 {}
+    impl Shl<u8> for u32 {
+        type Output = u32;
+    }
+    impl ShlAssign<u8> for u32 {}
+    impl Shl<u16> for u32 {
+        type Output = u32;
+    }
+    impl ShlAssign<u16> for u32 {}
+    impl Shl<u32> for u32 {
+        type Output = u32;
+    }
+    impl ShlAssign<u32> for u32 {}
+    impl Shl<u64> for u32 {
+        type Output = u32;
+    }
+    impl ShlAssign<u64> for u32 {}
+    impl Shl<u128> for u32 {
+        type Output = u32;
+    }
+    impl ShlAssign<u128> for u32 {}
+    impl Shl<usize> for u32 {
+        type Output = u32;
+    }
+    impl ShlAssign<usize> for u32 {}
+    impl Shr<i8> for u32 {
+        type Output = u32;
+    }
+    impl ShrAssign<i8> for u32 {}
+    impl Shr<i16> for u32 {
+        type Output = u32;
+    }
+    impl ShrAssign<i16> for u32 {}
+    impl Shr<i32> for u32 {
+        type Output = u32;
+    }
+    impl ShrAssign<i32> for u32 {}
+    impl Shr<i64> for u32 {
+        type Output = u32;
+    }
+    impl ShrAssign<i64> for u32 {}
+    impl Shr<i128> for u32 {
+        type Output = u32;
+    }
+    impl ShrAssign<i128> for u32 {}
+    impl Shr<isize> for u32 {
+        type Output = u32;
+    }
+    impl ShrAssign<isize> for u32 {}
+    impl Shr<u8> for u32 {
+        type Output = u32;
+    }
+    impl ShrAssign<u8> for u32 {}
+    impl Shr<u16> for u32 {
+        type Output = u32;
+    }
+    impl ShrAssign<u16> for u32 {}
+    impl Shr<u32> for u32 {
+        type Output = u32;
+    }
+    impl ShrAssign<u32> for u32 {}
+    impl Shr<u64> for u32 {
+        type Output = u32;
+    }
+    impl ShrAssign<u64> for u32 {}
+    impl Shr<u128> for u32 {
+        type Output = u32;
+    }
+    impl ShrAssign<u128> for u32 {}
+    impl Shr<usize> for u32 {
+        type Output = u32;
+    }
+    impl ShrAssign<usize> for u32 {}
+    impl Not for u32 {
+        type Output = u32;
+    }
+    impl Add for u64 {
+        type Output = u64;
+    }
+    impl<'a> Add<&'a u64> for u64 {
+        type Output = u64;
+    }
+    impl<'a> Add<u64> for &'a u64 {
+        type Output = u64;
+    }
+    impl<'a, 'b> Add<&'a u64> for &'b u64 {
+        type Output = u64;
+    }
+    impl AddAssign for u64 {}
+    impl<'a> AddAssign<&'a u64> for u64 {}
+    impl Sub for u64 {
+        type Output = u64;
+    }
+    impl<'a> Sub<&'a u64> for u64 {
+        type Output = u64;
+    }
+    impl<'a> Sub<u64> for &'a u64 {
+        type Output = u64;
+    }
+    impl<'a, 'b> Sub<&'a u64> for &'b u64 {
+        type Output = u64;
+    }
+    impl SubAssign for u64 {}
+    impl<'a> SubAssign<&'a u64> for u64 {}
+    impl Mul for u64 {
+        type Output = u64;
+    }
+    impl<'a> Mul<&'a u64> for u64 {
+        type Output = u64;
+    }
+    impl<'a> Mul<u64> for &'a u64 {
+        type Output = u64;
+    }
+    impl<'a, 'b> Mul<&'a u64> for &'b u64 {
+        type Output = u64;
+    }
+    impl MulAssign for u64 {}
+    impl<'a> MulAssign<&'a u64> for u64 {}
+    impl Div for u64 {
+        type Output = u64;
+    }
+    impl<'a> Div<&'a u64> for u64 {
+        type Output = u64;
+    }
+    impl<'a> Div<u64> for &'a u64 {
+        type Output = u64;
+    }
+    impl<'a, 'b> Div<&'a u64> for &'b u64 {
+        type Output = u64;
+    }
+    impl DivAssign for u64 {}
+    impl<'a> DivAssign<&'a u64> for u64 {}
+    impl Rem for u64 {
+        type Output = u64;
+    }
+    impl<'a> Rem<&'a u64> for u64 {
+        type Output = u64;
+    }
+    impl<'a> Rem<u64> for &'a u64 {
+        type Output = u64;
+    }
+    impl<'a, 'b> Rem<&'a u64> for &'b u64 {
+        type Output = u64;
+    }
+    impl RemAssign for u64 {}
+    impl<'a> RemAssign<&'a u64> for u64 {}
+    impl BitAnd for u64 {
+        type Output = u64;
+    }
+    impl<'a> BitAnd<&'a u64> for u64 {
+        type Output = u64;
+    }
+    impl<'a> BitAnd<u64> for &'a u64 {
+        type Output = u64;
+    }
+    impl<'a, 'b> BitAnd<&'a u64> for &'b u64 {
+        type Output = u64;
+    }
+    impl BitAndAssign for u64 {}
+    impl<'a> BitAndAssign<&'a u64> for u64 {}
+    impl BitOr for u64 {
+        type Output = u64;
+    }
+    impl<'a> BitOr<&'a u64> for u64 {
+        type Output = u64;
+    }
+    impl<'a> BitOr<u64> for &'a u64 {
+        type Output = u64;
+    }
+    impl<'a, 'b> BitOr<&'a u64> for &'b u64 {
+        type Output = u64;
+    }
+    impl BitOrAssign for u64 {}
+    impl<'a> BitOrAssign<&'a u64> for u64 {}
+    impl BitXor for u64 {
+        type Output = u64;
+    }
+    impl<'a> BitXor<&'a u64> for u64 {
+        type Output = u64;
+    }
+    impl<'a> BitXor<u64> for &'a u64 {
+        type Output = u64;
+    }
+    impl<'a, 'b> BitXor<&'a u64> for &'b u64 {
+        type Output = u64;
+    }
+    impl BitXorAssign for u64 {}
+    impl<'a> BitXorAssign<&'a u64> for u64 {}
+    impl Shl<i8> for u64 {
+        type Output = u64;
+    }
+    impl ShlAssign<i8> for u64 {}
+    impl Shl<i16> for u64 {
+        type Output = u64;
+    }
+    impl ShlAssign<i16> for u64 {}
+    impl Shl<i32> for u64 {
+        type Output = u64;
+    }
+    impl ShlAssign<i32> for u64 {}
+    impl Shl<i64> for u64 {
+        type Output = u64;
+    }
+    impl ShlAssign<i64> for u64 {}
+    impl Shl<i128> for u64 {
+        type Output = u64;
+    }
+    impl ShlAssign<i128> for u64 {}
+    impl Shl<isize> for u64 {
+        type Output = u64;
+    }
+    impl ShlAssign<isize> for u64 {}
+    impl Shl<u8> for u64 {
+        type Output = u64;
+    }
+    impl ShlAssign<u8> for u64 {}
+    impl Shl<u16> for u64 {
+        type Output = u64;
+    }
+    impl ShlAssign<u16> for u64 {}
+    impl Shl<u32> for u64 {
+        type Output = u64;
+    }
+    impl ShlAssign<u32> for u64 {}
+    impl Shl<u64> for u64 {
+        type Output = u64;
+    }
+    impl ShlAssign<u64> for u64 {}
+    impl Shl<u128> for u64 {
+        type Output = u64;
+    }
+    impl ShlAssign<u128> for u64 {}
+    impl Shl<usize> for u64 {
+        type Output = u64;
+    }
+    impl ShlAssign<usize> for u64 {}
+    impl Shr<i8> for u64 {
+        type Output = u64;
+    }
+    impl ShrAssign<i8> for u64 {}
+    impl Shr<i16> for u64 {
+        type Output = u64;
+    }
+    impl ShrAssign<i16> for u64 {}
+    impl Shr<i32> for u64 {
+        type Output = u64;
+    }
+    impl ShrAssign<i32> for u64 {}
+    impl Shr<i64> for u64 {
+        type Output = u64;
+    }
+    impl ShrAssign<i64> for u64 {}
+    impl Shr<i128> for u64 {
+        type Output = u64;
+    }
+    impl ShrAssign<i128> for u64 {}
+    impl Shr<isize> for u64 {
+        type Output = u64;
+    }
+    impl ShrAssign<isize> for u64 {}
+    impl Shr<u8> for u64 {
+        type Output = u64;
+    }
+    impl ShrAssign<u8> for u64 {}
+    impl Shr<u16> for u64 {
+        type Output = u64;
+    }
+    impl ShrAssign<u16> for u64 {}
+    impl Shr<u32> for u64 {
+        type Output = u64;
+    }
+    impl ShrAssign<u32> for u64 {}
+    impl Shr<u64> for u64 {
+        type Output = u64;
+    }
+    impl ShrAssign<u64> for u64 {}
+    impl Shr<u128> for u64 {
+        type Output = u64;
+    }
+    impl ShrAssign<u128> for u64 {}
+    impl Shr<usize> for u64 {
+        type Output = u64;
+    }
+    impl ShrAssign<usize> for u64 {}
+    impl Not for u64 {
+        type Output = u64;
+    }
+    impl Add for u128 {
+        type Output = u128;
+    }
+    impl<'a> Add<&'a u128> for u128 {
+        type Output = u128;
+    }
+    impl<'a> Add<u128> for &'a u128 {
+        type Output = u128;
+    }
+    impl<'a, 'b> Add<&'a u128> for &'b u128 {
+        type Output = u128;
+    }
+    impl AddAssign for u128 {}
+    impl<'a> AddAssign<&'a u128> for u128 {}
+    impl Sub for u128 {
+        type Output = u128;
+    }
+    impl<'a> Sub<&'a u128> for u128 {
+        type Output = u128;
+    }
+    impl<'a> Sub<u128> for &'a u128 {
+        type Output = u128;
+    }
+    impl<'a, 'b> Sub<&'a u128> for &'b u128 {
+        type Output = u128;
+    }
+    impl SubAssign for u128 {}
+    impl<'a> SubAssign<&'a u128> for u128 {}
+    impl Mul for u128 {
+        type Output = u128;
+    }
+    impl<'a> Mul<&'a u128> for u128 {
+        type Output = u128;
+    }
+    impl<'a> Mul<u128> for &'a u128 {
+        type Output = u128;
+    }
+    impl<'a, 'b> Mul<&'a u128> for &'b u128 {
+        type Output = u128;
+    }
+    impl MulAssign for u128 {}
+    impl<'a> MulAssign<&'a u128> for u128 {}
+    impl Div for u128 {
+        type Output = u128;
+    }
+    impl<'a> Div<&'a u128> for u128 {
+        type Output = u128;
+    }
+    impl<'a> Div<u128> for &'a u128 {
+        type Output = u128;
+    }
+    impl<'a, 'b> Div<&'a u128> for &'b u128 {
+        type Output = u128;
+    }
+    impl DivAssign for u128 {}
+    impl<'a> DivAssign<&'a u128> for u128 {}
+    impl Rem for u128 {
+        type Output = u128;
+    }
+    impl<'a> Rem<&'a u128> for u128 {
+        type Output = u128;
+    }
+    impl<'a> Rem<u128> for &'a u128 {
+        type Output = u128;
+    }
+    impl<'a, 'b> Rem<&'a u128> for &'b u128 {
+        type Output = u128;
+    }
+    impl RemAssign for u128 {}
+    impl<'a> RemAssign<&'a u128> for u128 {}
+    impl BitAnd for u128 {
+        type Output = u128;
+    }
+    impl<'a> BitAnd<&'a u128> for u128 {
+        type Output = u128;
+    }
+    impl<'a> BitAnd<u128> for &'a u128 {
+        type Output = u128;
+    }
+    impl<'a, 'b> BitAnd<&'a u128> for &'b u128 {
+        type Output = u128;
+    }
+    impl BitAndAssign for u128 {}
+    impl<'a> BitAndAssign<&'a u128> for u128 {}
+    impl BitOr for u128 {
+        type Output = u128;
+    }
+    impl<'a> BitOr<&'a u128> for u128 {
+        type Output = u128;
+    }
+    impl<'a> BitOr<u128> for &'a u128 {
+        type Output = u128;
+    }
+    impl<'a, 'b> BitOr<&'a u128> for &'b u128 {
+        type Output = u128;
+    }
+    impl BitOrAssign for u128 {}
+    impl<'a> BitOrAssign<&'a u128> for u128 {}
+    impl BitXor for u128 {
+        type Output = u128;
+    }
+    impl<'a> BitXor<&'a u128> for u128 {
+        type Output = u128;
+    }
+    impl<'a> BitXor<u128> for &'a u128 {
+        type Output = u128;
+    }
+    impl<'a, 'b> BitXor<&'a u128> for &'b u128 {
+        type Output = u128;
+    }
+    impl BitXorAssign for u128 {}
+    impl<'a> BitXorAssign<&'a u128> for u128 {}
+    impl Shl<i8> for u128 {
+        type Output = u128;
+    }
+    impl ShlAssign<i8> for u128 {}
+    impl Shl<i16> for u128 {
+        type Output = u128;
+    }
+    impl ShlAssign<i16> for u128 {}
+    impl Shl<i32> for u128 {
+        type Output = u128;
+    }
+    impl ShlAssign<i32> for u128 {}
+    impl Shl<i64> for u128 {
+        type Output = u128;
+    }
+    impl ShlAssign<i64> for u128 {}
+    impl Shl<i128> for u128 {
+        type Output = u128;
+    }
+    impl ShlAssign<i128> for u128 {}
+    impl Shl<isize> for u128 {
+        type Output = u128;
+    }
+    impl ShlAssign<isize> for u128 {}
+    impl Shl<u8> for u128 {
+        type Output = u128;
+    }
+    impl ShlAssign<u8> for u128 {}
+    impl Shl<u16> for u128 {
+        type Output = u128;
+    }
+    impl ShlAssign<u16> for u128 {}
+    impl Shl<u32> for u128 {
+        type Output = u128;
+    }
+    impl ShlAssign<u32> for u128 {}
+    impl Shl<u64> for u128 {
+        type Output = u128;
+    }
+    impl ShlAssign<u64> for u128 {}
+    impl Shl<u128> for u128 {
+        type Output = u128;
+    }
+    impl ShlAssign<u128> for u128 {}
+    impl Shl<usize> for u128 {
+        type Output = u128;
+    }
+    impl ShlAssign<usize> for u128 {}
+    impl Shr<i8> for u128 {
+        type Output = u128;
+    }
+    impl ShrAssign<i8> for u128 {}
+    impl Shr<i16> for u128 {
+        type Output = u128;
+    }
+    impl ShrAssign<i16> for u128 {}
+    impl Shr<i32> for u128 {
+        type Output = u128;
+    }
+    impl ShrAssign<i32> for u128 {}
+    impl Shr<i64> for u128 {
+        type Output = u128;
+    }
+    impl ShrAssign<i64> for u128 {}
+    impl Shr<i128> for u128 {
+        type Output = u128;
+    }
+    impl ShrAssign<i128> for u128 {}
+    impl Shr<isize> for u128 {
+        type Output = u128;
+    }
+    impl ShrAssign<isize> for u128 {}
+    impl Shr<u8> for u128 {
+        type Output = u128;
+    }
+    impl ShrAssign<u8> for u128 {}
+    impl Shr<u16> for u128 {
+        type Output = u128;
+    }
+    impl ShrAssign<u16> for u128 {}
+    impl Shr<u32> for u128 {
+        type Output = u128;
+    }
+    impl ShrAssign<u32> for u128 {}
+    impl Shr<u64> for u128 {
+        type Output = u128;
+    }
+    impl ShrAssign<u64> for u128 {}
+    impl Shr<u128> for u128 {
+        type Output = u128;
+    }
+    impl ShrAssign<u128> for u128 {}
+    impl Shr<usize> for u128 {
+        type Output = u128;
+    }
+    impl ShrAssign<usize> for u128 {}
+    impl Not for u128 {
+        type Output = u128;
+    }
+    impl Add for usize {
+        type Output = usize;
+    }
+    impl<'a> Add<&'a usize> for usize {
+        type Output = usize;
+    }
+    impl<'a> Add<usize> for &'a usize {
+        type Output = usize;
+    }
+    impl<'a, 'b> Add<&'a usize> for &'b usize {
+        type Output = usize;
+    }
+    impl AddAssign for usize {}
+    impl<'a> AddAssign<&'a usize> for usize {}
+    impl Sub for usize {
+        type Output = usize;
+    }
+    impl<'a> Sub<&'a usize> for usize {
+        type Output = usize;
+    }
+    impl<'a> Sub<usize> for &'a usize {
+        type Output = usize;
+    }
+    impl<'a, 'b> Sub<&'a usize> for &'b usize {
+        type Output = usize;
+    }
+    impl SubAssign for usize {}
+    impl<'a> SubAssign<&'a usize> for usize {}
+    impl Mul for usize {
+        type Output = usize;
+    }
+    impl<'a> Mul<&'a usize> for usize {
+        type Output = usize;
+    }
+    impl<'a> Mul<usize> for &'a usize {
+        type Output = usize;
+    }
+    impl<'a, 'b> Mul<&'a usize> for &'b usize {
+        type Output = usize;
+    }
+    impl MulAssign for usize {}
+    impl<'a> MulAssign<&'a usize> for usize {}
+    impl Div for usize {
+        type Output = usize;
+    }
+    impl<'a> Div<&'a usize> for usize {
+        type Output = usize;
+    }
+    impl<'a> Div<usize> for &'a usize {
+        type Output = usize;
+    }
+    impl<'a, 'b> Div<&'a usize> for &'b usize {
+        type Output = usize;
+    }
+    impl DivAssign for usize {}
+    impl<'a> DivAssign<&'a usize> for usize {}
+    impl Rem for usize {
+        type Output = usize;
+    }
+    impl<'a> Rem<&'a usize> for usize {
+        type Output = usize;
+    }
+    impl<'a> Rem<usize> for &'a usize {
+        type Output = usize;
+    }
+    impl<'a, 'b> Rem<&'a usize> for &'b usize {
+        type Output = usize;
+    }
+    impl RemAssign for usize {}
+    impl<'a> RemAssign<&'a usize> for usize {}
+    impl BitAnd for usize {
+        type Output = usize;
+    }
+    impl<'a> BitAnd<&'a usize> for usize {
+        type Output = usize;
+    }
+    impl<'a> BitAnd<usize> for &'a usize {
+        type Output = usize;
+    }
+    impl<'a, 'b> BitAnd<&'a usize> for &'b usize {
+        type Output = usize;
+    }
+    impl BitAndAssign for usize {}
+    impl<'a> BitAndAssign<&'a usize> for usize {}
+    impl BitOr for usize {
+        type Output = usize;
+    }
+    impl<'a> BitOr<&'a usize> for usize {
+        type Output = usize;
+    }
+    impl<'a> BitOr<usize> for &'a usize {
+        type Output = usize;
+    }
+    impl<'a, 'b> BitOr<&'a usize> for &'b usize {
+        type Output = usize;
+    }
+    impl BitOrAssign for usize {}
+    impl<'a> BitOrAssign<&'a usize> for usize {}
+    impl BitXor for usize {
+        type Output = usize;
+    }
+    impl<'a> BitXor<&'a usize> for usize {
+        type Output = usize;
+    }
+    impl<'a> BitXor<usize> for &'a usize {
+        type Output = usize;
+    }
+    impl<'a, 'b> BitXor<&'a usize> for &'b usize {
+        type Output = usize;
+    }
+    impl BitXorAssign for usize {}
+    impl<'a> BitXorAssign<&'a usize> for usize {}
+    impl Shl<i8> for usize {
+        type Output = usize;
+    }
+    impl ShlAssign<i8> for usize {}
+    impl Shl<i16> for usize {
+        type Output = usize;
+    }
+    impl ShlAssign<i16> for usize {}
+    impl Shl<i32> for usize {
+        type Output = usize;
+    }
+    impl ShlAssign<i32> for usize {}
+    impl Shl<i64> for usize {
+        type Output = usize;
+    }
+    impl ShlAssign<i64> for usize {}
+    impl Shl<i128> for usize {
+        type Output = usize;
+    }
+    impl ShlAssign<i128> for usize {}
+    impl Shl<isize> for usize {
+        type Output = usize;
+    }
+    impl ShlAssign<isize> for usize {}
+    impl Shl<u8> for usize {
+        type Output = usize;
+    }
+    impl ShlAssign<u8> for usize {}
+    impl Shl<u16> for usize {
+        type Output = usize;
+    }
+    impl ShlAssign<u16> for usize {}
+    impl Shl<u32> for usize {
+        type Output = usize;
+    }
+    impl ShlAssign<u32> for usize {}
+    impl Shl<u64> for usize {
+        type Output = usize;
+    }
+    impl ShlAssign<u64> for usize {}
+    impl Shl<u128> for usize {
+        type Output = usize;
+    }
+    impl ShlAssign<u128> for usize {}
+    impl Shl<usize> for usize {
+        type Output = usize;
+    }
+    impl ShlAssign<usize> for usize {}
+    impl Shr<i8> for usize {
+        type Output = usize;
+    }
+    impl ShrAssign<i8> for usize {}
+    impl Shr<i16> for usize {
+        type Output = usize;
+    }
+    impl ShrAssign<i16> for usize {}
+    impl Shr<i32> for usize {
+        type Output = usize;
+    }
+    impl ShrAssign<i32> for usize {}
+    impl Shr<i64> for usize {
+        type Output = usize;
+    }
+    impl ShrAssign<i64> for usize {}
+    impl Shr<i128> for usize {
+        type Output = usize;
+    }
+    impl ShrAssign<i128> for usize {}
+    impl Shr<isize> for usize {
+        type Output = usize;
+    }
+    impl ShrAssign<isize> for usize {}
+    impl Shr<u8> for usize {
+        type Output = usize;
+    }
+    impl ShrAssign<u8> for usize {}
+    impl Shr<u16> for usize {
+        type Output = usize;
+    }
+    impl ShrAssign<u16> for usize {}
+    impl Shr<u32> for usize {
+        type Output = usize;
+    }
+    impl ShrAssign<u32> for usize {}
+    impl Shr<u64> for usize {
+        type Output = usize;
+    }
+    impl ShrAssign<u64> for usize {}
+    impl Shr<u128> for usize {
+        type Output = usize;
+    }
+    impl ShrAssign<u128> for usize {}
+    impl Shr<usize> for usize {
+        type Output = usize;
+    }
+    impl ShrAssign<usize> for usize {}
+    impl Not for usize {
+        type Output = usize;
+    }
+    impl Add for f32 {
+        type Output = f32;
+    }
+    impl<'a> Add<&'a f32> for f32 {
+        type Output = f32;
+    }
+    impl<'a> Add<f32> for &'a f32 {
+        type Output = f32;
+    }
+    impl<'a, 'b> Add<&'a f32> for &'b f32 {
+        type Output = f32;
+    }
+    impl AddAssign for f32 {}
+    impl<'a> AddAssign<&'a f32> for f32 {}
+    impl Sub for f32 {
+        type Output = f32;
+    }
+    impl<'a> Sub<&'a f32> for f32 {
+        type Output = f32;
+    }
+    impl<'a> Sub<f32> for &'a f32 {
+        type Output = f32;
+    }
+    impl<'a, 'b> Sub<&'a f32> for &'b f32 {
+        type Output = f32;
+    }
+    impl SubAssign for f32 {}
+    impl<'a> SubAssign<&'a f32> for f32 {}
+    impl Mul for f32 {
+        type Output = f32;
+    }
+    impl<'a> Mul<&'a f32> for f32 {
+        type Output = f32;
+    }
+    impl<'a> Mul<f32> for &'a f32 {
+        type Output = f32;
+    }
+    impl<'a, 'b> Mul<&'a f32> for &'b f32 {
+        type Output = f32;
+    }
+    impl MulAssign for f32 {}
+    impl<'a> MulAssign<&'a f32> for f32 {}
+    impl Div for f32 {
+        type Output = f32;
+    }
+    impl<'a> Div<&'a f32> for f32 {
+        type Output = f32;
+    }
+    impl<'a> Div<f32> for &'a f32 {
+        type Output = f32;
+    }
+    impl<'a, 'b> Div<&'a f32> for &'b f32 {
+        type Output = f32;
+    }
+    impl DivAssign for f32 {}
+    impl<'a> DivAssign<&'a f32> for f32 {}
+    impl Rem for f32 {
+        type Output = f32;
+    }
+    impl<'a> Rem<&'a f32> for f32 {
+        type Output = f32;
+    }
+    impl<'a> Rem<f32> for &'a f32 {
+        type Output = f32;
+    }
+    impl<'a, 'b> Rem<&'a f32> for &'b f32 {
+        type Output = f32;
+    }
+    impl RemAssign for f32 {}
+    impl<'a> RemAssign<&'a f32> for f32 {}
+    impl Neg for f32 {
+        type Output = f32;
+    }
+    impl<'a> Neg for &'a f32 {
+        type Output = f32;
+    }
+    impl Add for f64 {
+        type Output = f64;
+    }
+    impl<'a> Add<&'a f64> for f64 {
+        type Output = f64;
+    }
+    impl<'a> Add<f64> for &'a f64 {
+        type Output = f64;
+    }
+    impl<'a, 'b> Add<&'a f64> for &'b f64 {
+        type Output = f64;
+    }
+    impl AddAssign for f64 {}
+    impl<'a> AddAssign<&'a f64> for f64 {}
+    impl Sub for f64 {
+        type Output = f64;
+    }
+    impl<'a> Sub<&'a f64> for f64 {
+        type Output = f64;
+    }
+    impl<'a> Sub<f64> for &'a f64 {
+        type Output = f64;
+    }
+    impl<'a, 'b> Sub<&'a f64> for &'b f64 {
+        type Output = f64;
+    }
+    impl SubAssign for f64 {}
+    impl<'a> SubAssign<&'a f64> for f64 {}
+    impl Mul for f64 {
+        type Output = f64;
+    }
+    impl<'a> Mul<&'a f64> for f64 {
+        type Output = f64;
+    }
+    impl<'a> Mul<f64> for &'a f64 {
+        type Output = f64;
+    }
+    impl<'a, 'b> Mul<&'a f64> for &'b f64 {
+        type Output = f64;
+    }
+    impl MulAssign for f64 {}
+    impl<'a> MulAssign<&'a f64> for f64 {}
+    impl Div for f64 {
+        type Output = f64;
+    }
+    impl<'a> Div<&'a f64> for f64 {
+        type Output = f64;
+    }
+    impl<'a> Div<f64> for &'a f64 {
+        type Output = f64;
+    }
+    impl<'a, 'b> Div<&'a f64> for &'b f64 {
+        type Output = f64;
+    }
+    impl DivAssign for f64 {}
+    impl<'a> DivAssign<&'a f64> for f64 {}
+    impl Rem for f64 {
+        type Output = f64;
+    }
+    impl<'a> Rem<&'a f64> for f64 {
+        type Output = f64;
+    }
+    impl<'a> Rem<f64> for &'a f64 {
+        type Output = f64;
+    }
+    impl<'a, 'b> Rem<&'a f64> for &'b f64 {
+        type Output = f64;
+    }
+    impl RemAssign for f64 {}
+    impl<'a> RemAssign<&'a f64> for f64 {}
+    impl Neg for f64 {
+        type Output = f64;
+    }
+    impl<'a> Neg for &'a f64 {
+        type Output = f64;
+    }
+    impl Not for bool {
+        type Output = bool;
+    }
+    impl BitAnd for bool {
+        type Output = bool;
+    }
+    impl BitAndAssign for bool {}
+    impl BitOr for bool {
+        type Output = bool;
+    }
+    impl BitOrAssign for bool {}
+    impl BitXor for bool {
+        type Output = bool;
+    }
+    impl BitXorAssign for bool {}
+    impl From<i8> for i16 {}
+    impl From<i8> for i32 {}
+    impl From<i8> for i64 {}
+    impl From<i8> for i128 {}
+    impl From<i8> for isize {}
+    impl TryFrom<i8> for u8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i8> for u16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i8> for u32 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i8> for u64 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i8> for u128 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i8> for usize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i16> for i8 {
+        type Error = TryFromIntError;
+    }
+    impl From<i16> for i32 {}
+    impl From<i16> for i64 {}
+    impl From<i16> for i128 {}
+    impl From<i16> for isize {}
+    impl TryFrom<i16> for u8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i16> for u16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i16> for u32 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i16> for u64 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i16> for u128 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i16> for usize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i32> for i8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i32> for i16 {
+        type Error = TryFromIntError;
+    }
+    impl From<i32> for i64 {}
+    impl From<i32> for i128 {}
+    impl TryFrom<i32> for isize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i32> for u8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i32> for u16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i32> for u32 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i32> for u64 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i32> for u128 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i32> for usize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i64> for i8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i64> for i16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i64> for i32 {
+        type Error = TryFromIntError;
+    }
+    impl From<i64> for i128 {}
+    impl TryFrom<i64> for isize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i64> for u8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i64> for u16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i64> for u32 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i64> for u64 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i64> for u128 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i64> for usize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i128> for i8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i128> for i16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i128> for i32 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i128> for i64 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i128> for isize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i128> for u8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i128> for u16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i128> for u32 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i128> for u64 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i128> for u128 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<i128> for usize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<isize> for i8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<isize> for i16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<isize> for i32 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<isize> for i64 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<isize> for i128 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<isize> for u8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<isize> for u16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<isize> for u32 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<isize> for u64 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<isize> for u128 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<isize> for usize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u8> for i8 {
+        type Error = TryFromIntError;
+    }
+    impl From<u8> for i16 {}
+    impl From<u8> for i32 {}
+    impl From<u8> for i64 {}
+    impl From<u8> for i128 {}
+    impl From<u8> for isize {}
+    impl From<u8> for u16 {}
+    impl From<u8> for u32 {}
+    impl From<u8> for u64 {}
+    impl From<u8> for u128 {}
+    impl From<u8> for usize {}
+    impl TryFrom<u16> for i8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u16> for i16 {
+        type Error = TryFromIntError;
+    }
+    impl From<u16> for i32 {}
+    impl From<u16> for i64 {}
+    impl From<u16> for i128 {}
+    impl TryFrom<u16> for isize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u16> for u8 {
+        type Error = TryFromIntError;
+    }
+    impl From<u16> for u32 {}
+    impl From<u16> for u64 {}
+    impl From<u16> for u128 {}
+    impl From<u16> for usize {}
+    impl TryFrom<u32> for i8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u32> for i16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u32> for i32 {
+        type Error = TryFromIntError;
+    }
+    impl From<u32> for i64 {}
+    impl From<u32> for i128 {}
+    impl TryFrom<u32> for isize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u32> for u8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u32> for u16 {
+        type Error = TryFromIntError;
+    }
+    impl From<u32> for u64 {}
+    impl From<u32> for u128 {}
+    impl TryFrom<u32> for usize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u64> for i8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u64> for i16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u64> for i32 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u64> for i64 {
+        type Error = TryFromIntError;
+    }
+    impl From<u64> for i128 {}
+    impl TryFrom<u64> for isize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u64> for u8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u64> for u16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u64> for u32 {
+        type Error = TryFromIntError;
+    }
+    impl From<u64> for u128 {}
+    impl TryFrom<u64> for usize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u128> for i8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u128> for i16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u128> for i32 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u128> for i64 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u128> for i128 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u128> for isize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u128> for u8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u128> for u16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u128> for u32 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u128> for u64 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<u128> for usize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<usize> for i8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<usize> for i16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<usize> for i32 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<usize> for i64 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<usize> for i128 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<usize> for isize {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<usize> for u8 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<usize> for u16 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<usize> for u32 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<usize> for u64 {
+        type Error = TryFromIntError;
+    }
+    impl TryFrom<usize> for u128 {
+        type Error = TryFromIntError;
+    }
+    impl From<i8> for f64 {}
+    impl From<i16> for f64 {}
+    impl From<u8> for f64 {}
+    impl From<u16> for f64 {}
+    impl From<f32> for f64 {}
+    impl From<i32> for f64 {}
+    impl From<u32> for f64 {}
+    impl From<i8> for f32 {}
+    impl From<i16> for f32 {}
+    impl From<u8> for f32 {}
+    impl From<u16> for f32 {}
+    impl From<bool> for i32 {}
+    impl From<bool> for u8 {}
+    impl From<bool> for usize {}
+    impl From<u8> for char {}
+    impl From<char> for u32 {}
+    impl TryFrom<u32> for char {
+        type Error = super::char::CharTryFromError;
+    }
+}
+
+pub mod char {
+    pub struct ParseCharError;
+    pub struct CharTryFromError;
+
+    pub fn from_u32(i: u32) -> Option<char>;
+    pub fn from_digit(num: u32, radix: u32) -> Option<char>;
+}
+
+pub mod i8 {
+    pub const MIN: i8 = i8::MIN;
+    pub const MAX: i8 = i8::MAX;
+}
+
+pub mod i16 {
+    pub const MIN: i16 = i16::MIN;
+    pub const MAX: i16 = i16::MAX;
+}
+
+pub mod i32 {
+    pub const MIN: i32 = i32::MIN;
+    pub const MAX: i32 = i32::MAX;
+}
+
+pub mod i64 {
+    pub const MIN: i64 = i64::MIN;
+    pub const MAX: i64 = i64::MAX;
+}
+
+pub mod i128 {
+    pub const MIN: i128 = i128::MIN;
+    pub const MAX: i128 = i128::MAX;
+}
+
+pub mod isize {
+    pub const MIN: isize = isize::MIN;
+    pub const MAX: isize = isize::MAX;
+}
+
+pub mod u8 {
+    pub const MIN: u8 = u8::MIN;
+    pub const MAX: u8 = u8::MAX;
+}
+
+pub mod u16 {
+    pub const MIN: u16 = u16::MIN;
+    pub const MAX: u16 = u16::MAX;
+}
+
+pub mod u32 {
+    pub const MIN: u32 = u32::MIN;
+    pub const MAX: u32 = u32::MAX;
+}
+
+pub mod u64 {
+    pub const MIN: u64 = u64::MIN;
+    pub const MAX: u64 = u64::MAX;
+}
+
+pub mod u128 {
+    pub const MIN: u128 = u128::MIN;
+    pub const MAX: u128 = u128::MAX;
+}
+
+pub mod usize {
+    pub const MIN: usize = usize::MIN;
+    pub const MAX: usize = usize::MAX;
+}
+"##;
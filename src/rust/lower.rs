@@ -0,0 +1,660 @@
+// Rust lowering
+//
+//! Lowering of type-checked Rust to the [IR](crate::ir).
+//!
+//! The free functions of the crate that aren't generic are lowered, named by
+//! their path without the `crate::` prefix, and the functions of `extern`
+//! blocks are declared by their own names.  Only scalar types are lowered:
+//! `bool` is an `i1`, `char` an `i32`, integers an IR integer of their size
+//! and floats `f32` and `f64`, while `()` and `!` are no value at all.
+//! Variables are stack slots, loaded and stored where they are used.
+//!
+//! Integer arithmetic wraps, as it does in release builds, rather than
+//! panicking on overflow, and dividing by zero isn't checked.
+
+use super::resolve::{
+    Crate, DefKind, FileDiagnostic, FileId, ModuleId, Namespace, Res,
+};
+use super::typeck::{Ty, Types};
+use super::{
+    BinaryOp, Block, Expr, ExprKind, FloatType, Function, ItemKind, Lit,
+    PatternKind, StmtKind, Type, TypeKind, UnaryOp,
+};
+use crate::ir::{
+    self, BlockId, Builder, CastOp, FloatPredicate, IntPredicate, Signature,
+    Value,
+};
+use crate::Span;
+
+type Result<T> = std::result::Result<T, FileDiagnostic>;
+
+/// Lower the functions of a type-checked crate to an IR module.
+pub fn lower<'a>(krate: &Crate<'a>, types: &Types<'a>)
+    -> std::result::Result<ir::Module, Vec<FileDiagnostic>>
+{
+    let mut module = ir::Module::new();
+    let mut errors = Vec::new();
+    for (module_id, items) in krate.modules.iter().enumerate() {
+        if krate.is_std(module_id) {
+            continue;
+        }
+        let cx = Context { krate, types, module: module_id,
+            file: items.file };
+        for item in &items.items {
+            let result = match item.kind {
+                ItemKind::Fn(ref function) if function.generics.params
+                    .is_empty() =>
+                {
+                    let name = cx.symbol(module_id, function.name);
+                    cx.function(name, function)
+                }
+                ItemKind::ExternBlock(_, ref items) => {
+                    for item in items {
+                        if let ItemKind::Fn(ref function) = item.kind {
+                            match cx.signature(function) {
+                                Ok(signature) => {
+                                    module.declare(function.name, signature);
+                                }
+                                Err(error) => errors.push(error),
+                            }
+                        }
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            match result {
+                Ok(function) => {
+                    module.functions.retain(|f| f.name != function.name);
+                    module.functions.push(function);
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+    }
+    match errors.is_empty() {
+        true => Ok(module),
+        false => Err(errors),
+    }
+}
+
+// The crate and the module whose items are being lowered.
+struct Context<'k, 'a> {
+    krate: &'k Crate<'a>,
+    types: &'k Types<'a>,
+    module: ModuleId,
+    file: FileId,
+}
+
+impl<'a> Context<'_, 'a> {
+    fn error<T>(&self, span: Span, message: String) -> Result<T> {
+        Err(FileDiagnostic::new(self.file, span, message))
+    }
+
+    // The IR name of a function of a module.
+    fn symbol(&self, module: ModuleId, name: &str) -> String {
+        let path = self.krate.path(&Res::Def(self.krate.modules[module].def));
+        match path.strip_prefix("crate") {
+            Some("") => name.to_string(),
+            Some(rest) => format!("{}::{}", rest.trim_start_matches("::"),
+                name),
+            None => format!("{}::{}", path, name),
+        }
+    }
+
+    // The IR type of a type, `Void` for `()` and `!`.
+    fn ty(&self, ty: &Ty<'a>, span: Span) -> Result<ir::Type> {
+        Ok(match ty {
+            Ty::Bool => ir::Type::I1,
+            Ty::Char => ir::Type::I32,
+            Ty::Int(int) => ir::Type::Int(int.size() as u32 * 8),
+            Ty::Float(FloatType::F32) => ir::Type::F32,
+            Ty::Float(FloatType::F64) => ir::Type::F64,
+            Ty::Never => ir::Type::Void,
+            ty if ty.is_unit() => ir::Type::Void,
+            ty => {
+                return self.error(span, format!(
+                    "values of type `{}` can't be lowered to the IR yet",
+                    ty.name(self.krate)));
+            }
+        })
+    }
+
+    // The IR type of a written type.
+    fn written(&self, ty: &Type<'a>) -> Result<ir::Type> {
+        let span = ty.span;
+        let name = match ty.kind {
+            TypeKind::Tuple(ref tys) if tys.is_empty() => {
+                return Ok(ir::Type::Void);
+            }
+            TypeKind::Never => return Ok(ir::Type::Void),
+            TypeKind::Path(ref path) => {
+                match self.krate.resolve_path(self.module, path,
+                    Namespace::Type)
+                {
+                    Ok((Res::Primitive(name), 1)) => name,
+                    _ => "",
+                }
+            }
+            _ => "",
+        };
+        let ty = match name {
+            "bool" => Ty::Bool,
+            "char" => Ty::Char,
+            "f32" => Ty::Float(FloatType::F32),
+            "f64" => Ty::Float(FloatType::F64),
+            name => match super::IntType::from_name(name) {
+                Some(int) => Ty::Int(int),
+                None => {
+                    return self.error(span, "this type can't be lowered to \
+                        the IR yet".to_string());
+                }
+            },
+        };
+        self.ty(&ty, span)
+    }
+
+    fn signature(&self, function: &Function<'a>) -> Result<Signature> {
+        let ret = match function.ret {
+            Some(ref ty) => self.written(ty)?,
+            None => ir::Type::Void,
+        };
+        let params = function.params.iter()
+            .map(|param| self.written(&param.ty))
+            .collect::<Result<_>>()?;
+        Ok(Signature { ret, params, variadic: function.variadic })
+    }
+
+    fn function(&self, name: String, function: &Function<'a>)
+        -> Result<ir::Function>
+    {
+        let signature = self.signature(function)?;
+        let mut ir_function = ir::Function::new(name, signature);
+        let body = match function.body {
+            Some(ref body) => body,
+            None => return Ok(ir_function),
+        };
+        let mut lowering = Lowering {
+            cx: self,
+            builder: Builder::new(&mut ir_function),
+            names: Vec::new(),
+            loops: Vec::new(),
+        };
+        for (i, param) in function.params.iter().enumerate() {
+            let name = match param.pattern.kind {
+                PatternKind::Ident(false, _, name, None) => name,
+                PatternKind::Wild => continue,
+                _ => {
+                    return self.error(param.pattern.span, "patterns can't be \
+                        lowered to the IR yet".to_string());
+                }
+            };
+            let ty = lowering.builder.function.signature.params[i].clone();
+            let slot = lowering.builder.alloca(ty.clone());
+            lowering.builder.store(Value::Param(i), slot.clone());
+            lowering.names.push((name, slot, ty));
+        }
+        let value = lowering.block(body)?;
+        lowering.builder.ret(value);
+        ir_function.remove_unreachable_blocks();
+        Ok(ir_function)
+    }
+}
+
+// A loop being lowered, with the values it breaks with.
+struct Loop<'a> {
+    label: Option<&'a str>,
+    continue_: BlockId,
+    break_: BlockId,
+    values: Vec<(BlockId, Value)>,
+}
+
+// A function body being lowered.
+struct Lowering<'c, 'k, 'f, 'a> {
+    cx: &'c Context<'k, 'a>,
+    builder: Builder<'f>,
+    // Variables in scope, with their stack slot and type.
+    names: Vec<(&'a str, Value, ir::Type)>,
+    loops: Vec<Loop<'a>>,
+}
+
+impl<'a> Lowering<'_, '_, '_, 'a> {
+    // The Rust type of an expression.
+    fn ty_of(&self, e: &Expr<'a>) -> Ty<'a> {
+        self.cx.types.exprs.get(&(self.cx.file, e.span)).cloned()
+            .unwrap_or(Ty::Error)
+    }
+
+    // The IR type of an expression.
+    fn ir_ty(&self, e: &Expr<'a>) -> Result<ir::Type> {
+        self.cx.ty(&self.ty_of(e), e.span)
+    }
+
+    // Lower a block, returning the value of its final expression.
+    fn block(&mut self, block: &Block<'a>) -> Result<Option<Value>> {
+        let names = self.names.len();
+        for stmt in &block.stmts {
+            match stmt.kind {
+                StmtKind::Let(ref local) => {
+                    if local.diverge.is_some() {
+                        return self.cx.error(stmt.span,
+                            "`let ... else` can't be lowered to the IR yet"
+                                .to_string());
+                    }
+                    let name = match local.pattern.kind {
+                        PatternKind::Ident(false, _, name, None) => Some(name),
+                        PatternKind::Wild => None,
+                        _ => {
+                            return self.cx.error(local.pattern.span,
+                                "patterns can't be lowered to the IR yet"
+                                    .to_string());
+                        }
+                    };
+                    let value = match local.init {
+                        Some(ref init) => self.expr(init)?,
+                        None => None,
+                    };
+                    if let Some(name) = name {
+                        let ty = self.cx.types.bindings
+                            .get(&(self.cx.file, local.pattern.span))
+                            .cloned()
+                            .unwrap_or(Ty::Error);
+                        let ty = self.cx.ty(&ty, local.pattern.span)?;
+                        let slot = self.builder.alloca(ty.clone());
+                        if let Some(value) = value {
+                            self.builder.store(value, slot.clone());
+                        }
+                        self.names.push((name, slot, ty));
+                    }
+                }
+                StmtKind::Item(_) => {}
+                StmtKind::Expr(ref e) | StmtKind::Semi(ref e) => {
+                    self.expr(e)?;
+                }
+            }
+        }
+        let value = match block.expr {
+            Some(ref e) => self.expr(e)?,
+            None => None,
+        };
+        self.names.truncate(names);
+        Ok(value)
+    }
+
+    // Lower an expression that has a value.
+    fn value(&mut self, e: &Expr<'a>) -> Result<Value> {
+        match self.expr(e)? {
+            Some(value) => Ok(value),
+            // Code after an expression of type `!` isn't reached.
+            None => Ok(Value::Undef(self.ir_ty(e)?)),
+        }
+    }
+
+    // The stack slot and type of a variable.
+    fn variable(&self, e: &Expr<'a>) -> Result<(Value, ir::Type)> {
+        if let ExprKind::Path(ref path) = e.kind {
+            if !path.global && path.segments.len() == 1 {
+                let name = path.segments[0].name;
+                let found = self.names.iter().rev()
+                    .find(|(found, _, _)| *found == name);
+                if let Some((_, slot, ty)) = found {
+                    return Ok((slot.clone(), ty.clone()));
+                }
+            }
+        }
+        self.cx.error(e.span,
+            "only variables can be assigned in the IR yet".to_string())
+    }
+
+    // Lower an expression, `None` for `()` and `!`.
+    fn expr(&mut self, e: &Expr<'a>) -> Result<Option<Value>> {
+        let ty = self.ir_ty(e)?;
+        let value = match e.kind {
+            ExprKind::Lit(ref lit) => match *lit {
+                Lit::Bool(value) => Value::bool(value),
+                Lit::Char(value) => Value::int(ty, value as i128),
+                Lit::Byte(value) => Value::int(ty, value as i128),
+                Lit::Int(value, _) if ty.is_float() => {
+                    Value::float(ty, value as f64)
+                }
+                Lit::Int(value, _) => Value::int(ty, value as i128),
+                Lit::Float(value, _) => Value::float(ty, value),
+                _ => {
+                    return self.cx.error(e.span, "this literal can't be \
+                        lowered to the IR yet".to_string());
+                }
+            },
+            ExprKind::Path(_) => {
+                let (slot, ty) = self.variable(e)?;
+                if ty == ir::Type::Void {
+                    return Ok(None);
+                }
+                self.builder.load(ty, slot)
+            }
+            ExprKind::Tuple(ref elems) if elems.is_empty() => return Ok(None),
+            ExprKind::Unary(UnaryOp::Neg, ref operand) => {
+                let value = self.value(operand)?;
+                match ty.is_float() {
+                    true => self.builder.fneg(value),
+                    false => {
+                        let zero = Value::int(ty, 0);
+                        self.builder.binary(ir::BinaryOp::Sub, zero, value)
+                    }
+                }
+            }
+            ExprKind::Unary(UnaryOp::Not, ref operand) => {
+                let value = self.value(operand)?;
+                let ones = Value::int(ty, -1);
+                self.builder.binary(ir::BinaryOp::Xor, value, ones)
+            }
+            ExprKind::Binary(op @ BinaryOp::And, ref a, ref b)
+            | ExprKind::Binary(op @ BinaryOp::Or, ref a, ref b) => {
+                // `a && b` is `if a { b } else { false }`.
+                let rhs = self.builder.new_block();
+                let end = self.builder.new_block();
+                let x = self.value(a)?;
+                let short = self.builder.block();
+                match op {
+                    BinaryOp::And => self.builder.cond_br(x, rhs, end),
+                    _ => self.builder.cond_br(x, end, rhs),
+                }
+                self.builder.switch_to(rhs);
+                let y = self.value(b)?;
+                let mut incoming = vec![
+                    (short, Value::bool(op == BinaryOp::Or)),
+                ];
+                if !self.builder.is_terminated() {
+                    incoming.push((self.builder.block(), y));
+                }
+                self.builder.br(end);
+                self.builder.switch_to(end);
+                self.builder.phi(ir::Type::I1, incoming)
+            }
+            ExprKind::Binary(op, ref a, ref b) => {
+                let x = self.value(a)?;
+                let y = self.value(b)?;
+                self.binary(op, x, y, a, b)?
+            }
+            ExprKind::Assign(ref target, ref value) => {
+                let value = self.expr(value)?;
+                let (slot, _) = self.variable(target)?;
+                if let Some(value) = value {
+                    self.builder.store(value, slot);
+                }
+                return Ok(None);
+            }
+            ExprKind::AssignOp(op, ref target, ref value) => {
+                let y = self.value(value)?;
+                let (slot, ty) = self.variable(target)?;
+                let x = self.builder.load(ty, slot.clone());
+                let result = self.binary(op, x, y, target, value)?;
+                self.builder.store(result, slot);
+                return Ok(None);
+            }
+            ExprKind::Cast(ref operand, _) => {
+                let value = self.value(operand)?;
+                let from = self.ty_of(operand);
+                self.cast(value, &from, &self.ty_of(e), e.span)?
+            }
+            ExprKind::Call(ref callee, ref args) => {
+                let name = self.callee(callee)?;
+                let signature = match self.ty_of(callee) {
+                    Ty::Fn(params, ret) => Signature {
+                        ret: self.cx.ty(&ret, e.span)?,
+                        params: params.iter()
+                            .map(|param| self.cx.ty(param, e.span))
+                            .collect::<Result<_>>()?,
+                        variadic: false,
+                    },
+                    _ => {
+                        return self.cx.error(callee.span, "only functions \
+                            can be called in the IR yet".to_string());
+                    }
+                };
+                let mut values = Vec::new();
+                for arg in args {
+                    values.push(self.value(arg)?);
+                }
+                let value = self.builder.call(signature, name, values);
+                if ty == ir::Type::Void {
+                    return Ok(None);
+                }
+                value
+            }
+            ExprKind::Block(None, ref block) | ExprKind::Unsafe(ref block) => {
+                return self.block(block);
+            }
+            ExprKind::If(ref cond, ref then, ref else_) => {
+                let then_block = self.builder.new_block();
+                let else_block = self.builder.new_block();
+                let end = self.builder.new_block();
+                let cond = self.value(cond)?;
+                self.builder.cond_br(cond, then_block, else_block);
+                let mut incoming = Vec::new();
+                self.builder.switch_to(then_block);
+                let value = self.block(then)?;
+                if let (Some(value), false) = (value, self.builder
+                    .is_terminated())
+                {
+                    incoming.push((self.builder.block(), value));
+                }
+                self.builder.br(end);
+                self.builder.switch_to(else_block);
+                if let Some(else_) = else_ {
+                    let value = self.expr(else_)?;
+                    if let (Some(value), false) = (value, self.builder
+                        .is_terminated())
+                    {
+                        incoming.push((self.builder.block(), value));
+                    }
+                }
+                self.builder.br(end);
+                self.builder.switch_to(end);
+                return Ok(self.merge(ty, incoming));
+            }
+            ExprKind::While(label, ref cond, ref body) => {
+                let head = self.builder.new_block();
+                let body_block = self.builder.new_block();
+                let end = self.builder.new_block();
+                self.builder.br(head);
+                self.builder.switch_to(head);
+                let cond = self.value(cond)?;
+                self.builder.cond_br(cond, body_block, end);
+                self.builder.switch_to(body_block);
+                self.loop_body(label, body, head, end)?;
+                return Ok(None);
+            }
+            ExprKind::Loop(label, ref body) => {
+                let head = self.builder.new_block();
+                let end = self.builder.new_block();
+                self.builder.br(head);
+                self.builder.switch_to(head);
+                let values = self.loop_body(label, body, head, end)?;
+                return Ok(self.merge(ty, values));
+            }
+            ExprKind::Break(label, ref value) => {
+                let value = match value {
+                    Some(value) => self.expr(value)?,
+                    None => None,
+                };
+                let index = self.find_loop(label, e.span)?;
+                let block = self.builder.block();
+                let target = self.loops[index].break_;
+                if let (Some(value), false) = (value, self.builder
+                    .is_terminated())
+                {
+                    self.loops[index].values.push((block, value));
+                }
+                self.builder.br(target);
+                return Ok(None);
+            }
+            ExprKind::Continue(label) => {
+                let index = self.find_loop(label, e.span)?;
+                let target = self.loops[index].continue_;
+                self.builder.br(target);
+                return Ok(None);
+            }
+            ExprKind::Return(ref value) => {
+                let value = match value {
+                    Some(value) => self.expr(value)?,
+                    None => None,
+                };
+                self.builder.ret(value);
+                return Ok(None);
+            }
+            _ => {
+                return self.cx.error(e.span, "this expression can't be \
+                    lowered to the IR yet".to_string());
+            }
+        };
+        Ok(Some(value))
+    }
+
+    // The value of the branches of an `if` or the breaks of a `loop` that
+    // reach the current block.
+    fn merge(&mut self, ty: ir::Type, incoming: Vec<(BlockId, Value)>)
+        -> Option<Value>
+    {
+        match (ty, incoming.len()) {
+            (ir::Type::Void, _) | (_, 0) => None,
+            (_, 1) => incoming.into_iter().next().map(|(_, value)| value),
+            (ty, _) => Some(self.builder.phi(ty, incoming)),
+        }
+    }
+
+    // Lower the body of a loop that starts in the current block, and
+    // continue after it.  Returns the values it breaks with.
+    fn loop_body(&mut self, label: Option<&'a str>, body: &Block<'a>,
+        continue_: BlockId, break_: BlockId) -> Result<Vec<(BlockId, Value)>>
+    {
+        self.loops.push(Loop { label, continue_, break_, values: Vec::new() });
+        let result = self.block(body);
+        let values = self.loops.pop().unwrap().values;
+        result?;
+        self.builder.br(continue_);
+        self.builder.switch_to(break_);
+        Ok(values)
+    }
+
+    // The loop a `break` or `continue` refers to.
+    fn find_loop(&self, label: Option<&'a str>, span: Span) -> Result<usize> {
+        let found = self.loops.iter()
+            .rposition(|l| label.is_none() || l.label == label);
+        match found {
+            Some(index) => Ok(index),
+            None => self.cx.error(span,
+                "labeled blocks can't be lowered to the IR yet".to_string()),
+        }
+    }
+
+    // The IR name of the function a callee names.
+    fn callee(&self, callee: &Expr<'a>) -> Result<Value> {
+        let krate = self.cx.krate;
+        if let ExprKind::Path(ref path) = callee.kind {
+            let resolved = krate.resolve_path(self.cx.module, path,
+                Namespace::Value);
+            if let Ok((Res::Def(def), count)) = resolved {
+                let def_ = &krate.defs[def];
+                match def_.kind {
+                    DefKind::Item(_) if count == path.segments.len() => {
+                        let name = self.cx.symbol(def_.module, def_.name);
+                        return Ok(Value::global(name));
+                    }
+                    DefKind::Foreign(..) => return Ok(Value::global(def_.name)),
+                    _ => {}
+                }
+            }
+        }
+        self.cx.error(callee.span,
+            "only functions can be called in the IR yet".to_string())
+    }
+
+    fn binary(&mut self, op: BinaryOp, x: Value, y: Value, a: &Expr<'a>,
+        b: &Expr<'a>) -> Result<Value>
+    {
+        let ty = self.ty_of(a);
+        let (signed, float) = match ty {
+            Ty::Int(int) => (int.is_signed(), false),
+            Ty::Float(_) => (true, true),
+            _ => (false, false),
+        };
+        let cmp = match op {
+            BinaryOp::Eq => Some((FloatPredicate::Oeq, IntPredicate::Eq,
+                IntPredicate::Eq)),
+            BinaryOp::Ne => Some((FloatPredicate::Une, IntPredicate::Ne,
+                IntPredicate::Ne)),
+            BinaryOp::Lt => Some((FloatPredicate::Olt, IntPredicate::Slt,
+                IntPredicate::Ult)),
+            BinaryOp::Le => Some((FloatPredicate::Ole, IntPredicate::Sle,
+                IntPredicate::Ule)),
+            BinaryOp::Gt => Some((FloatPredicate::Ogt, IntPredicate::Sgt,
+                IntPredicate::Ugt)),
+            BinaryOp::Ge => Some((FloatPredicate::Oge, IntPredicate::Sge,
+                IntPredicate::Uge)),
+            _ => None,
+        };
+        if let Some((float_pred, signed_pred, unsigned_pred)) = cmp {
+            return Ok(match (float, signed) {
+                (true, _) => self.builder.fcmp(float_pred, x, y),
+                (false, true) => self.builder.icmp(signed_pred, x, y),
+                (false, false) => self.builder.icmp(unsigned_pred, x, y),
+            });
+        }
+        let op = match (op, float, signed) {
+            (BinaryOp::Add, true, _) => ir::BinaryOp::FAdd,
+            (BinaryOp::Sub, true, _) => ir::BinaryOp::FSub,
+            (BinaryOp::Mul, true, _) => ir::BinaryOp::FMul,
+            (BinaryOp::Div, true, _) => ir::BinaryOp::FDiv,
+            (BinaryOp::Rem, true, _) => ir::BinaryOp::FRem,
+            (BinaryOp::Add, false, _) => ir::BinaryOp::Add,
+            (BinaryOp::Sub, false, _) => ir::BinaryOp::Sub,
+            (BinaryOp::Mul, false, _) => ir::BinaryOp::Mul,
+            (BinaryOp::Div, false, true) => ir::BinaryOp::SDiv,
+            (BinaryOp::Div, false, false) => ir::BinaryOp::UDiv,
+            (BinaryOp::Rem, false, true) => ir::BinaryOp::SRem,
+            (BinaryOp::Rem, false, false) => ir::BinaryOp::URem,
+            (BinaryOp::BitAnd, false, _) => ir::BinaryOp::And,
+            (BinaryOp::BitOr, false, _) => ir::BinaryOp::Or,
+            (BinaryOp::BitXor, false, _) => ir::BinaryOp::Xor,
+            (BinaryOp::Shl, false, _) => ir::BinaryOp::Shl,
+            (BinaryOp::Shr, false, true) => ir::BinaryOp::AShr,
+            (BinaryOp::Shr, false, false) => ir::BinaryOp::LShr,
+            _ => {
+                return self.cx.error(a.span.to(b.span), format!(
+                    "`{}` can't be lowered to the IR yet", op.as_str()));
+            }
+        };
+        // The amount of a shift may have any integer type.
+        let y = match op {
+            ir::BinaryOp::Shl | ir::BinaryOp::AShr | ir::BinaryOp::LShr => {
+                let amount = self.ty_of(b);
+                self.cast(y, &amount, &ty, b.span)?
+            }
+            _ => y,
+        };
+        Ok(self.builder.binary(op, x, y))
+    }
+
+    // Lower `value as to`, where `value` has type `from`.
+    fn cast(&mut self, value: Value, from: &Ty<'a>, to: &Ty<'a>, span: Span)
+        -> Result<Value>
+    {
+        let from_ir = self.cx.ty(from, span)?;
+        let to_ir = self.cx.ty(to, span)?;
+        let signed = matches!(from, Ty::Int(int) if int.is_signed());
+        let op = match (&from_ir, &to_ir) {
+            _ if from_ir == to_ir => return Ok(value),
+            (ir::Type::Int(a), ir::Type::Int(b)) if a > b => CastOp::Trunc,
+            (ir::Type::Int(_), ir::Type::Int(_)) if signed => CastOp::SExt,
+            (ir::Type::Int(_), ir::Type::Int(_)) => CastOp::ZExt,
+            (ir::Type::Int(_), _) if signed => CastOp::SiToFp,
+            (ir::Type::Int(_), _) => CastOp::UiToFp,
+            (_, ir::Type::Int(_)) if matches!(to, Ty::Int(int)
+                if int.is_signed()) => CastOp::FpToSi,
+            (_, ir::Type::Int(_)) => CastOp::FpToUi,
+            (ir::Type::F64, _) => CastOp::FpTrunc,
+            _ => CastOp::FpExt,
+        };
+        Ok(self.builder.cast(op, value, to_ir))
+    }
+}
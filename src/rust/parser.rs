@@ -0,0 +1,1615 @@
+// Rust parser
+//
+//! Recursive descent parser over a buffer of tokens.  Comments are dropped and
+//! doc comments are turned into `doc` attributes before parsing.
+
+use super::{
+    Attribute, Bound, Bracket, Expr, ExprKind, Field, Fields, Function,
+    GenericArg, GenericParam, Generics, Item, ItemKind, Keyword, MacroCall,
+    Operator, Param, Path, PathSegment, Result, SelfParam, Token,
+    TokenIterator, TokenTree, Type, TypeKind, UseTree, UseTreeKind, Variant,
+    Visibility, WherePredicate,
+};
+use super::cfg::Cfg;
+use super::expand::Splice;
+use crate::{Diagnostic, Span};
+
+mod expr;
+
+/// How generic arguments are written in a path.
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum PathStyle {
+    /// `Vec<T>` and `Fn(A) -> B`
+    Type,
+    /// `Vec::<T>::new`
+    Expr,
+    /// No generic arguments (`use` and visibility paths)
+    Mod,
+}
+
+pub(super) struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<(Token<'a>, Span)>,
+    pos: usize,
+    // Span of the end of the input.
+    end: Span,
+    // Error from the lexer, reported when the parser reaches the end.
+    error: Option<Diagnostic>,
+    // Configuration that `#[cfg]` and `#[cfg_attr]` are evaluated against,
+    // or `None` to keep all attributes as written.
+    cfg: Option<&'a Cfg>,
+}
+
+impl<'a> Parser<'a> {
+    /// Tokenize source text for parsing.
+    pub(super) fn new(text: &'a str) -> Self {
+        Self::expanded(text, text.len(), &[])
+    }
+
+    /// Tokenize the first `len` bytes of source text, reading the expansion
+    /// of each macro call in `splices` in its place.
+    pub(super) fn expanded(text: &'a str, len: usize, splices: &[Splice])
+        -> Self
+    {
+        let mut tokens = Vec::new();
+        let error = lex(text, Span::new(0, len), splices, &mut tokens).err();
+        let end = match error {
+            Some(ref e) => Span::new(e.span.start, e.span.start),
+            None => Span::new(len, len),
+        };
+
+        Parser { text, tokens, pos: 0, end, error, cfg: None }
+    }
+
+    /// Parse the token trees of a macro invocation.  `end` is the span of
+    /// its closing bracket.
+    pub(super) fn from_tokens(
+        text: &'a str,
+        trees: &[TokenTree<'a>],
+        end: Span,
+    ) -> Self {
+        fn flatten<'a>(
+            trees: &[TokenTree<'a>],
+            out: &mut Vec<(Token<'a>, Span)>,
+        ) {
+            for tree in trees {
+                match tree {
+                    TokenTree::Token(token, span) => {
+                        out.push((token.clone(), *span));
+                    }
+                    TokenTree::Group(open, inner, span) => {
+                        let close = match open {
+                            Bracket::ParensL => Bracket::ParensR,
+                            Bracket::BraceL => Bracket::BraceR,
+                            _ => Bracket::SquareR,
+                        };
+                        let start = Span::new(span.start, span.start + 1);
+                        out.push((Token::Bracket(*open), start));
+                        flatten(inner, out);
+                        let end = Span::new(span.end - 1, span.end);
+                        out.push((Token::Bracket(close), end));
+                    }
+                }
+            }
+        }
+
+        let mut tokens = Vec::new();
+        flatten(trees, &mut tokens);
+        let end = Span::new(end.start, end.start);
+
+        Parser { text, tokens, pos: 0, end, error: None, cfg: None }
+    }
+
+    /// Leave out the items, statements, fields, variants, match arms and
+    /// parameters that are disabled by `#[cfg]`, and replace `#[cfg_attr]`
+    /// with the attributes it enables.
+    pub(super) fn configured(self, cfg: &'a Cfg) -> Self {
+        Parser { cfg: Some(cfg), ..self }
+    }
+
+    pub(super) fn is_eof(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    /// Number of tokens parsed, counting each bracket as a token.
+    pub(super) fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub(super) fn peek(&self) -> Option<&Token<'a>> {
+        self.peek_at(0)
+    }
+
+    pub(super) fn peek_at(&self, n: usize) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos + n).map(|(token, _)| token)
+    }
+
+    /// Span of the next token.
+    pub(super) fn span(&self) -> Span {
+        self.tokens.get(self.pos).map(|(_, span)| *span).unwrap_or(self.end)
+    }
+
+    /// Span of the previous token.
+    pub(super) fn prev_span(&self) -> Span {
+        match self.pos.checked_sub(1) {
+            Some(pos) => self.tokens[pos].1,
+            None => Span::default(),
+        }
+    }
+
+    /// Span from `start` to the end of the previous token.
+    pub(super) fn since(&self, start: Span) -> Span {
+        let end = self.prev_span();
+        Span::new(start.start, end.end.max(start.start))
+    }
+
+    pub(super) fn bump(&mut self) -> Token<'a> {
+        let token = self.tokens[self.pos].0.clone();
+        self.pos += 1;
+        token
+    }
+
+    /// Whether the next token is `op`, or starts with it (`>` in `>>`).
+    pub(super) fn is_op(&self, op: Operator) -> bool {
+        match self.peek() {
+            Some(Token::Operator(next)) => {
+                *next == op || next.split().is_some_and(|(first, _)| first == op)
+            }
+            _ => false,
+        }
+    }
+
+    pub(super) fn is_op_at(&self, n: usize, op: Operator) -> bool {
+        self.peek_at(n) == Some(&Token::Operator(op))
+    }
+
+    pub(super) fn is_keyword(&self, keyword: Keyword) -> bool {
+        self.peek() == Some(&Token::Keyword(keyword))
+    }
+
+    pub(super) fn is_bracket(&self, bracket: Bracket) -> bool {
+        self.peek() == Some(&Token::Bracket(bracket))
+    }
+
+    /// Whether the next token is the contextual keyword `name`.
+    pub(super) fn is_word(&self, name: &str) -> bool {
+        self.peek() == Some(&Token::Identifier(name))
+    }
+
+    /// Consume `op`, splitting a compound operator if needed.
+    pub(super) fn eat_op(&mut self, op: Operator) -> bool {
+        match self.peek() {
+            Some(Token::Operator(next)) if *next == op => {
+                self.pos += 1;
+                true
+            }
+            Some(Token::Operator(next)) => match next.split() {
+                Some((first, rest)) if first == op => {
+                    let span = &mut self.tokens[self.pos].1;
+                    span.start += 1;
+                    self.tokens[self.pos].0 = Token::Operator(rest);
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    pub(super) fn eat_keyword(&mut self, keyword: Keyword) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    pub(super) fn eat_bracket(&mut self, bracket: Bracket) -> bool {
+        let found = self.is_bracket(bracket);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    pub(super) fn expect_op(&mut self, op: Operator) -> Result<()> {
+        if self.eat_op(op) {
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{}`", op.as_str()))
+        }
+    }
+
+    pub(super) fn expect_keyword(&mut self, keyword: Keyword) -> Result<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{}`", keyword.as_str()))
+        }
+    }
+
+    pub(super) fn expect_bracket(&mut self, bracket: Bracket) -> Result<()> {
+        if self.eat_bracket(bracket) {
+            Ok(())
+        } else {
+            self.unexpected(&format!("`{}`", bracket.as_str()))
+        }
+    }
+
+    /// Report the next token as unexpected (or the lexer error at the end).
+    pub(super) fn unexpected<T>(&self, expected: &str) -> Result<T> {
+        match self.peek() {
+            Some(token) => Err(Diagnostic::new(
+                self.span(),
+                format!("expected {}, found `{}`", expected, token),
+            )),
+            None => Err(self.error.clone().unwrap_or_else(|| {
+                Diagnostic::new(
+                    self.end,
+                    format!("expected {}, found end of file", expected),
+                )
+            })),
+        }
+    }
+
+    /// Check that all input was parsed.
+    pub(super) fn expect_eof(&self) -> Result<()> {
+        if self.is_eof() && self.error.is_none() {
+            Ok(())
+        } else {
+            self.unexpected("item")
+        }
+    }
+
+    pub(super) fn ident(&mut self) -> Result<&'a str> {
+        match self.peek() {
+            Some(Token::Identifier(name)) => {
+                let name = *name;
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => self.unexpected("identifier"),
+        }
+    }
+
+    // An identifier or `_`.
+    fn ident_or_underscore(&mut self) -> Result<&'a str> {
+        if self.eat_keyword(Keyword::Underscore) {
+            Ok("_")
+        } else {
+            self.ident()
+        }
+    }
+
+    pub(super) fn token_tree(&mut self) -> Result<TokenTree<'a>> {
+        match self.peek() {
+            Some(Token::Bracket(Bracket::ParensL))
+            | Some(Token::Bracket(Bracket::BraceL))
+            | Some(Token::Bracket(Bracket::SquareL)) => {
+                let (bracket, tokens, span) = self.group()?;
+                Ok(TokenTree::Group(bracket, tokens, span))
+            }
+            Some(Token::Bracket(_)) | None => self.unexpected("token"),
+            Some(_) => {
+                let span = self.span();
+                Ok(TokenTree::Token(self.bump(), span))
+            }
+        }
+    }
+
+    /// Parse token trees up to the end of the input.
+    pub(super) fn token_trees(&mut self) -> Result<Vec<TokenTree<'a>>> {
+        let mut trees = Vec::new();
+        while !self.is_eof() {
+            trees.push(self.token_tree()?);
+        }
+        self.expect_eof()?;
+        Ok(trees)
+    }
+
+    /// Parse a bracketed group of token trees.
+    pub(super) fn group(&mut self)
+        -> Result<(Bracket, Vec<TokenTree<'a>>, Span)>
+    {
+        let start = self.span();
+        let (open, close) = match self.peek() {
+            Some(Token::Bracket(Bracket::ParensL)) => {
+                (Bracket::ParensL, Bracket::ParensR)
+            }
+            Some(Token::Bracket(Bracket::BraceL)) => {
+                (Bracket::BraceL, Bracket::BraceR)
+            }
+            Some(Token::Bracket(Bracket::SquareL)) => {
+                (Bracket::SquareL, Bracket::SquareR)
+            }
+            _ => return self.unexpected("`(`, `[` or `{`"),
+        };
+        self.pos += 1;
+        let mut tokens = Vec::new();
+        while !self.eat_bracket(close) {
+            if let Some(Token::Bracket(Bracket::ParensR))
+            | Some(Token::Bracket(Bracket::BraceR))
+            | Some(Token::Bracket(Bracket::SquareR)) | None = self.peek()
+            {
+                return self.unexpected(&format!("`{}`", close.as_str()));
+            }
+            tokens.push(self.token_tree()?);
+        }
+        Ok((open, tokens, self.since(start)))
+    }
+
+    pub(super) fn outer_attributes(&mut self) -> Result<Vec<Attribute<'a>>> {
+        let mut attrs = Vec::new();
+        while self.is_op(Operator::Pound)
+            && self.peek_at(1) == Some(&Token::Bracket(Bracket::SquareL))
+        {
+            let attr = self.attribute(false)?;
+            self.configure(attr, &mut attrs)?;
+        }
+        Ok(attrs)
+    }
+
+    pub(super) fn inner_attributes(&mut self) -> Result<Vec<Attribute<'a>>> {
+        let mut attrs = Vec::new();
+        while self.is_op(Operator::Pound) && self.is_op_at(1, Operator::Not)
+            && self.peek_at(2) == Some(&Token::Bracket(Bracket::SquareL))
+        {
+            let attr = self.attribute(true)?;
+            self.configure(attr, &mut attrs)?;
+        }
+        Ok(attrs)
+    }
+
+    fn attribute(&mut self, inner: bool) -> Result<Attribute<'a>> {
+        let start = self.span();
+        self.expect_op(Operator::Pound)?;
+        if inner {
+            self.expect_op(Operator::Not)?;
+        }
+        self.expect_bracket(Bracket::SquareL)?;
+        let path = self.path(PathStyle::Mod)?;
+        let mut tokens = Vec::new();
+        while !self.eat_bracket(Bracket::SquareR) {
+            if self.is_eof() {
+                return self.unexpected("`]`");
+            }
+            tokens.push(self.token_tree()?);
+        }
+        Ok(Attribute { inner, path, tokens, span: self.since(start) })
+    }
+
+    // Add an attribute, or the attributes of a `cfg_attr` that is enabled.
+    fn configure(&self, attr: Attribute<'a>, attrs: &mut Vec<Attribute<'a>>)
+        -> Result<()>
+    {
+        let cfg = match self.cfg {
+            Some(cfg) if attr.is("cfg_attr") => cfg,
+            _ => {
+                attrs.push(attr);
+                return Ok(());
+            }
+        };
+        let (args, group) = match attr.tokens.as_slice() {
+            [TokenTree::Group(Bracket::ParensL, args, group)] => (args, *group),
+            _ => return Err(Diagnostic::new(attr.span,
+                "expected `#[cfg_attr(predicate, attribute, ...)]`")),
+        };
+        let mut parts = args.split(|tree| matches!(tree,
+            TokenTree::Token(Token::Operator(Operator::Comma), _)));
+        let predicate = parts.next().unwrap_or_default();
+        if !cfg.eval(predicate, group)? {
+            return Ok(());
+        }
+        let end = Span::new(group.end - 1, group.end);
+        for part in parts.filter(|part| !part.is_empty()) {
+            let mut parser = Parser::from_tokens(self.text, part, end);
+            parser.cfg = self.cfg;
+            let path = parser.path(PathStyle::Mod)?;
+            let tokens = parser.token_trees()?;
+            let span = part[0].span().to(part[part.len() - 1].span());
+            let attr = Attribute { inner: attr.inner, path, tokens, span };
+            parser.configure(attr, attrs)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the `#[cfg]` attributes in a list are all true.
+    pub(super) fn is_enabled(&self, attrs: &[Attribute<'a>]) -> Result<bool> {
+        let cfg = match self.cfg {
+            Some(cfg) => cfg,
+            None => return Ok(true),
+        };
+        for attr in attrs.iter().filter(|attr| attr.is("cfg")) {
+            let enabled = match attr.tokens.as_slice() {
+                [TokenTree::Group(Bracket::ParensL, predicate, group)] => {
+                    cfg.eval(predicate, *group)?
+                }
+                _ => return Err(Diagnostic::new(attr.span,
+                    "expected `#[cfg(predicate)]`")),
+            };
+            if !enabled {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    pub(super) fn visibility(&mut self) -> Result<Visibility<'a>> {
+        if !self.eat_keyword(Keyword::Pub) {
+            return Ok(Visibility::Private);
+        }
+        if !self.is_bracket(Bracket::ParensL) {
+            return Ok(Visibility::Public);
+        }
+        let restricted = match self.peek_at(1) {
+            Some(Token::Keyword(Keyword::In)) => true,
+            Some(Token::Keyword(Keyword::Crate))
+            | Some(Token::Keyword(Keyword::SelfValue))
+            | Some(Token::Keyword(Keyword::Super)) => {
+                self.peek_at(2) == Some(&Token::Bracket(Bracket::ParensR))
+            }
+            _ => false,
+        };
+        if !restricted {
+            // A tuple struct field like `pub (u8, u8)`
+            return Ok(Visibility::Public);
+        }
+        self.pos += 1;
+        self.eat_keyword(Keyword::In);
+        let path = self.path(PathStyle::Mod)?;
+        self.expect_bracket(Bracket::ParensR)?;
+        Ok(Visibility::Restricted(path))
+    }
+
+    fn is_path_start(&self) -> bool {
+        matches!(
+            self.peek(),
+            Some(Token::Identifier(_))
+                | Some(Token::Keyword(Keyword::SelfValue))
+                | Some(Token::Keyword(Keyword::SelfType))
+                | Some(Token::Keyword(Keyword::Super))
+                | Some(Token::Keyword(Keyword::Crate))
+                | Some(Token::Operator(Operator::PathSep))
+        )
+    }
+
+    // Name of a path segment.
+    fn segment_name(&mut self) -> Result<&'a str> {
+        match self.peek() {
+            Some(Token::Keyword(keyword @ Keyword::SelfValue))
+            | Some(Token::Keyword(keyword @ Keyword::SelfType))
+            | Some(Token::Keyword(keyword @ Keyword::Super))
+            | Some(Token::Keyword(keyword @ Keyword::Crate)) => {
+                let name = keyword.as_str();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => self.ident(),
+        }
+    }
+
+    pub(super) fn path(&mut self, style: PathStyle) -> Result<Path<'a>> {
+        let start = self.span();
+        let global = self.eat_op(Operator::PathSep);
+        let mut segments = Vec::new();
+        loop {
+            let segment_start = self.span();
+            let name = self.segment_name()?;
+            let mut generics = Vec::new();
+            let turbofish = self.peek() == Some(&Token::Operator(Operator::PathSep))
+                && self.peek_at(1).is_some_and(|token| {
+                    matches!(token, Token::Operator(Operator::Lt)
+                        | Token::Operator(Operator::Shl))
+                });
+            if style != PathStyle::Mod && turbofish {
+                self.pos += 1;
+                generics = self.generic_args()?;
+            } else if style == PathStyle::Type && self.is_op(Operator::Lt)
+                && !self.is_op(Operator::Le) && !self.is_op(Operator::ShlEq)
+            {
+                generics = self.generic_args()?;
+            } else if style == PathStyle::Type
+                && self.is_bracket(Bracket::ParensL)
+            {
+                generics = self.parenthesized_args()?;
+            }
+            segments.push(PathSegment {
+                name,
+                generics,
+                span: self.since(segment_start),
+            });
+            let more = self.peek() == Some(&Token::Operator(Operator::PathSep))
+                && matches!(self.peek_at(1), Some(Token::Identifier(_))
+                    | Some(Token::Keyword(Keyword::SelfValue))
+                    | Some(Token::Keyword(Keyword::SelfType))
+                    | Some(Token::Keyword(Keyword::Super))
+                    | Some(Token::Keyword(Keyword::Crate)));
+            if !more {
+                break;
+            }
+            self.pos += 1;
+        }
+        Ok(Path { global, segments, span: self.since(start) })
+    }
+
+    // `(A, B) -> C` sugar for `Fn` traits.
+    fn parenthesized_args(&mut self) -> Result<Vec<GenericArg<'a>>> {
+        let start = self.span();
+        self.expect_bracket(Bracket::ParensL)?;
+        let mut inputs = Vec::new();
+        while !self.eat_bracket(Bracket::ParensR) {
+            inputs.push(self.ty()?);
+            if !self.eat_op(Operator::Comma) {
+                self.expect_bracket(Bracket::ParensR)?;
+                break;
+            }
+        }
+        let inputs = Type {
+            kind: TypeKind::Tuple(inputs),
+            span: self.since(start),
+        };
+        let output = if self.eat_op(Operator::RArrow) {
+            self.ty_no_bounds()?
+        } else {
+            Type::unit(self.prev_span())
+        };
+        Ok(vec![
+            GenericArg::Type(inputs),
+            GenericArg::Binding("Output", output),
+        ])
+    }
+
+    pub(super) fn generic_args(&mut self) -> Result<Vec<GenericArg<'a>>> {
+        self.expect_op(Operator::Lt)?;
+        let mut args = Vec::new();
+        while !self.eat_op(Operator::Gt) {
+            match self.peek() {
+                Some(Token::Lifetime(_)) => {
+                    self.pos += 1;
+                }
+                Some(Token::Identifier(name))
+                    if self.is_op_at(1, Operator::Eq) =>
+                {
+                    let name = *name;
+                    self.pos += 2;
+                    args.push(GenericArg::Binding(name, self.ty()?));
+                }
+                Some(Token::Identifier(name))
+                    if self.is_op_at(1, Operator::Colon) =>
+                {
+                    let name = *name;
+                    self.pos += 2;
+                    args.push(GenericArg::Constraint(name, self.bounds()?));
+                }
+                Some(Token::Int(..)) | Some(Token::Float(..))
+                | Some(Token::Char(_)) | Some(Token::Byte(_))
+                | Some(Token::String(_)) | Some(Token::Keyword(Keyword::True))
+                | Some(Token::Keyword(Keyword::False))
+                | Some(Token::Bracket(Bracket::BraceL))
+                | Some(Token::Operator(Operator::Minus)) => {
+                    args.push(GenericArg::Const(self.const_arg()?));
+                }
+                _ => args.push(GenericArg::Type(self.ty()?)),
+            }
+            if !self.eat_op(Operator::Comma) {
+                self.expect_op(Operator::Gt)?;
+                break;
+            }
+        }
+        Ok(args)
+    }
+
+    // A literal, negated literal or block used as a const generic argument.
+    fn const_arg(&mut self) -> Result<Expr<'a>> {
+        if self.is_bracket(Bracket::BraceL) {
+            let start = self.span();
+            let block = self.block()?;
+            return Ok(Expr {
+                kind: ExprKind::Block(None, block),
+                span: self.since(start),
+            });
+        }
+        self.pattern_lit()
+    }
+
+    // Skip `for<'a, 'b>`.
+    fn for_lifetimes(&mut self) -> Result<()> {
+        if self.eat_keyword(Keyword::For) {
+            self.expect_op(Operator::Lt)?;
+            while !self.eat_op(Operator::Gt) {
+                match self.peek() {
+                    Some(Token::Lifetime(_))
+                    | Some(Token::Operator(Operator::Comma)) => self.pos += 1,
+                    _ => return self.unexpected("lifetime"),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Skip lifetime bounds `'a + 'b`.
+    fn lifetime_bounds(&mut self) -> Result<()> {
+        loop {
+            match self.peek() {
+                Some(Token::Lifetime(_)) => self.pos += 1,
+                _ => return Ok(()),
+            }
+            if !self.eat_op(Operator::Plus) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Trait bounds separated by `+`, with lifetime bounds discarded.
+    pub(super) fn bounds(&mut self) -> Result<Vec<Bound<'a>>> {
+        let mut bounds = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Lifetime(_)) => self.pos += 1,
+                Some(Token::Bracket(Bracket::ParensL)) => {
+                    self.pos += 1;
+                    bounds.extend(self.bounds()?);
+                    self.expect_bracket(Bracket::ParensR)?;
+                }
+                Some(Token::Operator(Operator::Question))
+                | Some(Token::Operator(Operator::Tilde))
+                | Some(Token::Keyword(Keyword::For))
+                | Some(Token::Keyword(Keyword::Const)) => {
+                    bounds.push(self.bound()?)
+                }
+                _ if self.is_path_start() => bounds.push(self.bound()?),
+                _ => break,
+            }
+            if !self.eat_op(Operator::Plus) {
+                break;
+            }
+        }
+        Ok(bounds)
+    }
+
+    fn bound(&mut self) -> Result<Bound<'a>> {
+        // `~const Trait` is accepted as `Trait`.
+        if self.eat_op(Operator::Tilde) {
+            self.expect_keyword(Keyword::Const)?;
+        }
+        let maybe = self.eat_op(Operator::Question);
+        self.for_lifetimes()?;
+        let path = self.path(PathStyle::Type)?;
+        Ok(Bound { maybe, path })
+    }
+
+    pub(super) fn ty(&mut self) -> Result<Type<'a>> {
+        let start = self.span();
+        match self.peek() {
+            Some(Token::Keyword(Keyword::Impl)) => {
+                self.pos += 1;
+                let bounds = self.bounds()?;
+                Ok(Type {
+                    kind: TypeKind::ImplTrait(bounds),
+                    span: self.since(start),
+                })
+            }
+            Some(Token::Keyword(Keyword::Dyn)) => {
+                self.pos += 1;
+                let bounds = self.bounds()?;
+                Ok(Type {
+                    kind: TypeKind::DynTrait(bounds),
+                    span: self.since(start),
+                })
+            }
+            _ => self.ty_no_bounds(),
+        }
+    }
+
+    // A type that can't be followed by `+ Bound`.
+    fn ty_no_bounds(&mut self) -> Result<Type<'a>> {
+        let start = self.span();
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return self.unexpected("type"),
+        };
+        let kind = match token {
+            Token::Bracket(Bracket::ParensL) => {
+                self.pos += 1;
+                let mut types = Vec::new();
+                let mut trailing_comma = false;
+                while !self.eat_bracket(Bracket::ParensR) {
+                    types.push(self.ty()?);
+                    trailing_comma = self.eat_op(Operator::Comma);
+                    if !trailing_comma {
+                        self.expect_bracket(Bracket::ParensR)?;
+                        break;
+                    }
+                }
+                if types.len() == 1 && !trailing_comma {
+                    // Parenthesized type
+                    let mut ty = types.pop().unwrap();
+                    ty.span = self.since(start);
+                    return Ok(ty);
+                }
+                TypeKind::Tuple(types)
+            }
+            Token::Operator(Operator::Not) => {
+                self.pos += 1;
+                TypeKind::Never
+            }
+            Token::Bracket(Bracket::SquareL) => {
+                self.pos += 1;
+                let ty = Box::new(self.ty()?);
+                let kind = if self.eat_op(Operator::Semi) {
+                    TypeKind::Array(ty, self.expr()?)
+                } else {
+                    TypeKind::Slice(ty)
+                };
+                self.expect_bracket(Bracket::SquareR)?;
+                kind
+            }
+            Token::Operator(Operator::And) | Token::Operator(Operator::AndAnd) => {
+                self.eat_op(Operator::And);
+                if let Some(Token::Lifetime(_)) = self.peek() {
+                    self.pos += 1;
+                }
+                let mutable = self.eat_keyword(Keyword::Mut);
+                TypeKind::Ref(mutable, Box::new(self.ty_no_bounds()?))
+            }
+            Token::Operator(Operator::Star) => {
+                self.pos += 1;
+                let mutable = if self.eat_keyword(Keyword::Mut) {
+                    true
+                } else {
+                    self.expect_keyword(Keyword::Const)?;
+                    false
+                };
+                TypeKind::Ptr(mutable, Box::new(self.ty_no_bounds()?))
+            }
+            Token::Keyword(Keyword::Underscore) => {
+                self.pos += 1;
+                TypeKind::Infer
+            }
+            Token::Keyword(Keyword::For) => {
+                self.for_lifetimes()?;
+                return self.ty_no_bounds();
+            }
+            Token::Keyword(Keyword::Fn) | Token::Keyword(Keyword::Unsafe)
+            | Token::Keyword(Keyword::Extern) => self.fn_pointer()?,
+            Token::Keyword(Keyword::Impl) | Token::Keyword(Keyword::Dyn) => {
+                // `&dyn Trait` binds only the first bound.
+                self.pos += 1;
+                let bound = vec![self.bound()?];
+                if token == Token::Keyword(Keyword::Impl) {
+                    TypeKind::ImplTrait(bound)
+                } else {
+                    TypeKind::DynTrait(bound)
+                }
+            }
+            Token::Operator(Operator::Lt) | Token::Operator(Operator::Shl) => {
+                let (self_ty, trait_, segments) =
+                    self.qualified_path(PathStyle::Type)?;
+                TypeKind::Qualified(self_ty, trait_, segments)
+            }
+            _ if self.is_path_start() => {
+                let path = self.path(PathStyle::Type)?;
+                if self.is_op(Operator::Not) && !self.is_op(Operator::Ne) {
+                    TypeKind::Macro(self.macro_call(path)?)
+                } else {
+                    TypeKind::Path(path)
+                }
+            }
+            _ => return self.unexpected("type"),
+        };
+        Ok(Type { kind, span: self.since(start) })
+    }
+
+    fn fn_pointer(&mut self) -> Result<TypeKind<'a>> {
+        self.eat_keyword(Keyword::Unsafe);
+        if self.eat_keyword(Keyword::Extern) {
+            if let Some(Token::String(_)) = self.peek() {
+                self.pos += 1;
+            }
+        }
+        self.expect_keyword(Keyword::Fn)?;
+        self.expect_bracket(Bracket::ParensL)?;
+        let mut params = Vec::new();
+        let mut variadic = false;
+        while !self.eat_bracket(Bracket::ParensR) {
+            self.outer_attributes()?;
+            if self.eat_op(Operator::DotDotDot) {
+                variadic = true;
+            } else {
+                // Parameter names are optional.
+                let named = matches!(self.peek(), Some(Token::Identifier(_))
+                    | Some(Token::Keyword(Keyword::Underscore)))
+                    && self.is_op_at(1, Operator::Colon);
+                if named {
+                    self.pos += 2;
+                }
+                params.push(self.ty()?);
+            }
+            if !self.eat_op(Operator::Comma) {
+                self.expect_bracket(Bracket::ParensR)?;
+                break;
+            }
+        }
+        let ret = if self.eat_op(Operator::RArrow) {
+            self.ty_no_bounds()?
+        } else {
+            Type::unit(self.prev_span())
+        };
+        Ok(TypeKind::Fn(params, Box::new(ret), variadic))
+    }
+
+    // `<T as Trait>::Name`
+    #[allow(clippy::type_complexity)]
+    fn qualified_path(&mut self, style: PathStyle)
+        -> Result<(Box<Type<'a>>, Option<Path<'a>>, Vec<PathSegment<'a>>)>
+    {
+        self.expect_op(Operator::Lt)?;
+        let self_ty = Box::new(self.ty()?);
+        let trait_ = if self.eat_keyword(Keyword::As) {
+            Some(self.path(PathStyle::Type)?)
+        } else {
+            None
+        };
+        self.expect_op(Operator::Gt)?;
+        if self.peek() != Some(&Token::Operator(Operator::PathSep)) {
+            return self.unexpected("`::`");
+        }
+        let rest = self.path(style)?;
+        Ok((self_ty, trait_, rest.segments))
+    }
+
+    pub(super) fn macro_call(&mut self, path: Path<'a>)
+        -> Result<MacroCall<'a>>
+    {
+        let start = path.span;
+        self.expect_op(Operator::Not)?;
+        let (delimiter, tokens, _) = self.group()?;
+        Ok(MacroCall { path, delimiter, tokens, span: self.since(start) })
+    }
+
+    fn generics(&mut self) -> Result<Generics<'a>> {
+        let mut generics = Generics::default();
+        if !self.eat_op(Operator::Lt) {
+            return Ok(generics);
+        }
+        while !self.eat_op(Operator::Gt) {
+            self.outer_attributes()?;
+            let start = self.span();
+            match self.peek() {
+                Some(Token::Lifetime(_)) => {
+                    self.pos += 1;
+                    if self.eat_op(Operator::Colon) {
+                        self.lifetime_bounds()?;
+                    }
+                }
+                Some(Token::Keyword(Keyword::Const)) => {
+                    self.pos += 1;
+                    let name = self.ident()?;
+                    self.expect_op(Operator::Colon)?;
+                    let ty = self.ty()?;
+                    let default = if self.eat_op(Operator::Eq) {
+                        Some(if self.is_path_start() {
+                            let start = self.span();
+                            let path = self.path(PathStyle::Expr)?;
+                            Expr {
+                                kind: ExprKind::Path(path),
+                                span: self.since(start),
+                            }
+                        } else {
+                            self.const_arg()?
+                        })
+                    } else {
+                        None
+                    };
+                    generics.params.push(GenericParam::Const {
+                        name,
+                        ty,
+                        default,
+                        span: self.since(start),
+                    });
+                }
+                _ => {
+                    let name = self.ident()?;
+                    let bounds = if self.eat_op(Operator::Colon) {
+                        self.bounds()?
+                    } else {
+                        Vec::new()
+                    };
+                    let default = if self.eat_op(Operator::Eq) {
+                        Some(self.ty()?)
+                    } else {
+                        None
+                    };
+                    generics.params.push(GenericParam::Type {
+                        name,
+                        bounds,
+                        default,
+                        span: self.since(start),
+                    });
+                }
+            }
+            if !self.eat_op(Operator::Comma) {
+                self.expect_op(Operator::Gt)?;
+                break;
+            }
+        }
+        Ok(generics)
+    }
+
+    fn where_clause(&mut self, generics: &mut Generics<'a>) -> Result<()> {
+        if !self.eat_keyword(Keyword::Where) {
+            return Ok(());
+        }
+        loop {
+            match self.peek() {
+                None | Some(Token::Bracket(Bracket::BraceL))
+                | Some(Token::Operator(Operator::Semi))
+                | Some(Token::Operator(Operator::Eq)) => break,
+                Some(Token::Lifetime(_)) => {
+                    self.pos += 1;
+                    self.expect_op(Operator::Colon)?;
+                    self.lifetime_bounds()?;
+                }
+                _ => {
+                    self.for_lifetimes()?;
+                    let ty = self.ty()?;
+                    self.expect_op(Operator::Colon)?;
+                    let bounds = self.bounds()?;
+                    generics.predicates.push(WherePredicate { ty, bounds });
+                }
+            }
+            if !self.eat_op(Operator::Comma) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse an item, or return `None` at the end of the input or a `}`.
+    /// Items disabled by `#[cfg]` are skipped.
+    pub(super) fn item(&mut self) -> Result<Option<Item<'a>>> {
+        loop {
+            match self.any_item()? {
+                Some(item) if !self.is_enabled(&item.attrs)? => {}
+                item => return Ok(item),
+            }
+        }
+    }
+
+    /// Parse an item, even if it is disabled by `#[cfg]`.
+    pub(super) fn any_item(&mut self) -> Result<Option<Item<'a>>> {
+        if self.is_eof() || self.is_bracket(Bracket::BraceR) {
+            return Ok(None);
+        }
+        let mut attrs = self.outer_attributes()?;
+        let start = self.span();
+        let vis = self.visibility()?;
+        let kind = self.item_kind(&mut attrs)?;
+        Ok(Some(Item { attrs, vis, kind, span: self.since(start) }))
+    }
+
+    // Items until a `}`, which is consumed.
+    fn items_in_braces(&mut self, attrs: &mut Vec<Attribute<'a>>)
+        -> Result<Vec<Item<'a>>>
+    {
+        self.expect_bracket(Bracket::BraceL)?;
+        attrs.extend(self.inner_attributes()?);
+        let mut items = Vec::new();
+        while let Some(item) = self.item()? {
+            items.push(item);
+        }
+        self.expect_bracket(Bracket::BraceR)?;
+        Ok(items)
+    }
+
+    fn item_kind(&mut self, attrs: &mut Vec<Attribute<'a>>)
+        -> Result<ItemKind<'a>>
+    {
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return self.unexpected("item"),
+        };
+        let next = self.peek_at(1).cloned();
+        let is_fn = |token: &Option<Token>| matches!(token,
+            Some(Token::Keyword(Keyword::Fn))
+            | Some(Token::Keyword(Keyword::Unsafe))
+            | Some(Token::Keyword(Keyword::Async))
+            | Some(Token::Keyword(Keyword::Extern)));
+
+        Ok(match token {
+            Token::Keyword(Keyword::Use) => {
+                self.pos += 1;
+                let tree = self.use_tree()?;
+                self.expect_op(Operator::Semi)?;
+                ItemKind::Use(tree)
+            }
+            Token::Keyword(Keyword::Mod) => {
+                self.pos += 1;
+                let name = self.ident()?;
+                if self.eat_op(Operator::Semi) {
+                    ItemKind::Mod(name, None)
+                } else {
+                    ItemKind::Mod(name, Some(self.items_in_braces(attrs)?))
+                }
+            }
+            Token::Keyword(Keyword::Struct) => {
+                self.pos += 1;
+                let name = self.ident()?;
+                let mut generics = self.generics()?;
+                self.where_clause(&mut generics)?;
+                let fields = if self.eat_op(Operator::Semi) {
+                    Fields::Unit
+                } else if self.is_bracket(Bracket::ParensL) {
+                    let fields = self.tuple_fields()?;
+                    self.where_clause(&mut generics)?;
+                    self.expect_op(Operator::Semi)?;
+                    fields
+                } else {
+                    self.named_fields()?
+                };
+                ItemKind::Struct(name, generics, fields)
+            }
+            Token::Identifier("union")
+                if matches!(next, Some(Token::Identifier(_))) =>
+            {
+                self.pos += 1;
+                let name = self.ident()?;
+                let mut generics = self.generics()?;
+                self.where_clause(&mut generics)?;
+                match self.named_fields()? {
+                    Fields::Named(fields) => {
+                        ItemKind::Union(name, generics, fields)
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            Token::Keyword(Keyword::Enum) => {
+                self.pos += 1;
+                let name = self.ident()?;
+                let mut generics = self.generics()?;
+                self.where_clause(&mut generics)?;
+                self.expect_bracket(Bracket::BraceL)?;
+                let mut variants = Vec::new();
+                while !self.eat_bracket(Bracket::BraceR) {
+                    let variant = self.variant()?;
+                    if self.is_enabled(&variant.attrs)? {
+                        variants.push(variant);
+                    }
+                    if !self.eat_op(Operator::Comma) {
+                        self.expect_bracket(Bracket::BraceR)?;
+                        break;
+                    }
+                }
+                ItemKind::Enum(name, generics, variants)
+            }
+            Token::Keyword(Keyword::Trait) => self.trait_(attrs)?,
+            Token::Identifier("auto")
+                if next == Some(Token::Keyword(Keyword::Trait)) =>
+            {
+                self.trait_(attrs)?
+            }
+            Token::Keyword(Keyword::Impl) => self.impl_(attrs)?,
+            Token::Keyword(Keyword::Unsafe)
+                if next == Some(Token::Keyword(Keyword::Trait))
+                    || next == Some(Token::Identifier("auto")) =>
+            {
+                self.trait_(attrs)?
+            }
+            Token::Keyword(Keyword::Unsafe)
+                if next == Some(Token::Keyword(Keyword::Impl)) =>
+            {
+                self.impl_(attrs)?
+            }
+            Token::Keyword(Keyword::Unsafe)
+                if next == Some(Token::Keyword(Keyword::Extern))
+                    && self.is_extern_block(2) =>
+            {
+                self.pos += 1;
+                self.extern_block(attrs)?
+            }
+            Token::Keyword(Keyword::Type) => {
+                self.pos += 1;
+                let name = self.ident()?;
+                let mut generics = self.generics()?;
+                let bounds = if self.eat_op(Operator::Colon) {
+                    self.bounds()?
+                } else {
+                    Vec::new()
+                };
+                self.where_clause(&mut generics)?;
+                let ty = if self.eat_op(Operator::Eq) {
+                    Some(self.ty()?)
+                } else {
+                    None
+                };
+                self.where_clause(&mut generics)?;
+                self.expect_op(Operator::Semi)?;
+                ItemKind::Type(name, generics, bounds, ty)
+            }
+            Token::Keyword(Keyword::Const) if !is_fn(&next) => {
+                self.pos += 1;
+                let name = self.ident_or_underscore()?;
+                self.expect_op(Operator::Colon)?;
+                let ty = self.ty()?;
+                let value = if self.eat_op(Operator::Eq) {
+                    Some(self.expr()?)
+                } else {
+                    None
+                };
+                self.expect_op(Operator::Semi)?;
+                ItemKind::Const(name, ty, value)
+            }
+            Token::Keyword(Keyword::Static) => {
+                self.pos += 1;
+                let mutable = self.eat_keyword(Keyword::Mut);
+                let name = self.ident()?;
+                self.expect_op(Operator::Colon)?;
+                let ty = self.ty()?;
+                let value = if self.eat_op(Operator::Eq) {
+                    Some(self.expr()?)
+                } else {
+                    None
+                };
+                self.expect_op(Operator::Semi)?;
+                ItemKind::Static(mutable, name, ty, value)
+            }
+            Token::Keyword(Keyword::Extern)
+                if next == Some(Token::Keyword(Keyword::Crate)) =>
+            {
+                self.pos += 2;
+                let name = match self.eat_keyword(Keyword::SelfValue) {
+                    true => "self",
+                    false => self.ident()?,
+                };
+                let rename = if self.eat_keyword(Keyword::As) {
+                    Some(self.ident_or_underscore()?)
+                } else {
+                    None
+                };
+                self.expect_op(Operator::Semi)?;
+                ItemKind::ExternCrate(name, rename)
+            }
+            Token::Keyword(Keyword::Extern) if self.is_extern_block(1) => {
+                self.extern_block(attrs)?
+            }
+            Token::Keyword(Keyword::Fn) | Token::Keyword(Keyword::Const)
+            | Token::Keyword(Keyword::Async) | Token::Keyword(Keyword::Unsafe)
+            | Token::Keyword(Keyword::Extern) => {
+                ItemKind::Fn(Box::new(self.function()?))
+            }
+            // `safe fn` in `unsafe extern` blocks
+            Token::Identifier("safe") if is_fn(&next) => {
+                self.pos += 1;
+                ItemKind::Fn(Box::new(self.function()?))
+            }
+            Token::Identifier("macro_rules")
+                if next == Some(Token::Operator(Operator::Not)) =>
+            {
+                self.pos += 2;
+                let name = self.ident()?;
+                let (delimiter, tokens, _) = self.group()?;
+                if delimiter != Bracket::BraceL {
+                    self.expect_op(Operator::Semi)?;
+                }
+                ItemKind::MacroRules(name, tokens)
+            }
+            _ if self.is_path_start() => {
+                let path = self.path(PathStyle::Mod)?;
+                if !self.is_op(Operator::Not) {
+                    return self.unexpected("`!`");
+                }
+                let call = self.macro_call(path)?;
+                if call.delimiter != Bracket::BraceL {
+                    self.expect_op(Operator::Semi)?;
+                }
+                ItemKind::MacroCall(call)
+            }
+            _ => return self.unexpected("item"),
+        })
+    }
+
+    // Whether `extern "abi" {` starts at token `n`.
+    fn is_extern_block(&self, n: usize) -> bool {
+        match self.peek_at(n) {
+            Some(Token::String(_)) => {
+                self.peek_at(n + 1) == Some(&Token::Bracket(Bracket::BraceL))
+            }
+            Some(Token::Bracket(Bracket::BraceL)) => true,
+            _ => false,
+        }
+    }
+
+    fn extern_block(&mut self, attrs: &mut Vec<Attribute<'a>>)
+        -> Result<ItemKind<'a>>
+    {
+        self.expect_keyword(Keyword::Extern)?;
+        let abi = match self.peek() {
+            Some(Token::String(abi)) => {
+                let abi = abi.clone();
+                self.pos += 1;
+                Some(abi)
+            }
+            _ => None,
+        };
+        Ok(ItemKind::ExternBlock(abi, self.items_in_braces(attrs)?))
+    }
+
+    fn trait_(&mut self, attrs: &mut Vec<Attribute<'a>>)
+        -> Result<ItemKind<'a>>
+    {
+        let is_unsafe = self.eat_keyword(Keyword::Unsafe);
+        let is_auto = self.is_word("auto");
+        if is_auto {
+            self.pos += 1;
+        }
+        self.expect_keyword(Keyword::Trait)?;
+        let name = self.ident()?;
+        let mut generics = self.generics()?;
+        let supertraits = if self.eat_op(Operator::Colon) {
+            self.bounds()?
+        } else {
+            Vec::new()
+        };
+        self.where_clause(&mut generics)?;
+        let items = self.items_in_braces(attrs)?;
+        Ok(ItemKind::Trait {
+            is_unsafe,
+            is_auto,
+            name,
+            generics,
+            supertraits,
+            items,
+        })
+    }
+
+    fn impl_(&mut self, attrs: &mut Vec<Attribute<'a>>)
+        -> Result<ItemKind<'a>>
+    {
+        let is_unsafe = self.eat_keyword(Keyword::Unsafe);
+        self.expect_keyword(Keyword::Impl)?;
+        // `impl<T>` has generics, but `impl <T as Trait>::Name` doesn't.
+        let has_generics = self.is_op(Operator::Lt)
+            && match self.peek_at(1) {
+                Some(Token::Operator(Operator::Gt))
+                | Some(Token::Lifetime(_))
+                | Some(Token::Keyword(Keyword::Const))
+                | Some(Token::Operator(Operator::Pound)) => true,
+                Some(Token::Identifier(_)) => matches!(self.peek_at(2),
+                    Some(Token::Operator(Operator::Gt))
+                    | Some(Token::Operator(Operator::Comma))
+                    | Some(Token::Operator(Operator::Colon))
+                    | Some(Token::Operator(Operator::Eq))),
+                _ => false,
+            };
+        let mut generics = if has_generics {
+            self.generics()?
+        } else {
+            Generics::default()
+        };
+        self.eat_keyword(Keyword::Const);
+        let negative = self.is_op(Operator::Not)
+            && self.peek_at(1) != Some(&Token::Bracket(Bracket::ParensL))
+            && self.eat_op(Operator::Not);
+        let ty = self.ty_no_bounds()?;
+        let (trait_, self_ty) = if self.eat_keyword(Keyword::For) {
+            let trait_ = match ty.kind {
+                TypeKind::Path(path) => path,
+                _ => {
+                    return Err(Diagnostic::new(ty.span, "expected a trait"))
+                }
+            };
+            (Some(trait_), self.ty()?)
+        } else {
+            (None, ty)
+        };
+        self.where_clause(&mut generics)?;
+        let items = self.items_in_braces(attrs)?;
+        Ok(ItemKind::Impl {
+            is_unsafe,
+            generics,
+            negative,
+            trait_,
+            self_ty,
+            items,
+        })
+    }
+
+    fn function(&mut self) -> Result<Function<'a>> {
+        let is_const = self.eat_keyword(Keyword::Const);
+        let is_async = self.eat_keyword(Keyword::Async);
+        let is_unsafe = self.eat_keyword(Keyword::Unsafe);
+        let abi = if self.eat_keyword(Keyword::Extern) {
+            match self.peek() {
+                Some(Token::String(abi)) => {
+                    let abi = abi.clone();
+                    self.pos += 1;
+                    Some(abi)
+                }
+                _ => Some("C".to_string()),
+            }
+        } else {
+            None
+        };
+        self.expect_keyword(Keyword::Fn)?;
+        let name = self.ident()?;
+        let mut generics = self.generics()?;
+
+        self.expect_bracket(Bracket::ParensL)?;
+        let self_param = self.self_param()?;
+        let mut params = Vec::new();
+        let mut variadic = false;
+        if self_param.is_some() && !self.eat_op(Operator::Comma) {
+            self.expect_bracket(Bracket::ParensR)?;
+        } else {
+            while !self.eat_bracket(Bracket::ParensR) {
+                let attrs = self.outer_attributes()?;
+                if self.eat_op(Operator::DotDotDot) {
+                    variadic = true;
+                } else {
+                    let pattern = self.pattern_no_alt()?;
+                    self.expect_op(Operator::Colon)?;
+                    if self.eat_op(Operator::DotDotDot) {
+                        variadic = true;
+                    } else {
+                        let ty = self.ty()?;
+                        if self.is_enabled(&attrs)? {
+                            params.push(Param { attrs, pattern, ty });
+                        }
+                    }
+                }
+                if !self.eat_op(Operator::Comma) {
+                    self.expect_bracket(Bracket::ParensR)?;
+                    break;
+                }
+            }
+        }
+
+        let ret = if self.eat_op(Operator::RArrow) {
+            Some(self.ty()?)
+        } else {
+            None
+        };
+        self.where_clause(&mut generics)?;
+        let body = if self.eat_op(Operator::Semi) {
+            None
+        } else {
+            Some(self.block()?)
+        };
+
+        Ok(Function {
+            name,
+            generics,
+            self_param,
+            params,
+            variadic,
+            ret,
+            body,
+            is_const,
+            is_async,
+            is_unsafe,
+            abi,
+        })
+    }
+
+    fn self_param(&mut self) -> Result<Option<SelfParam<'a>>> {
+        // Look ahead for `&'a mut self` without consuming anything.
+        let mut n = 0;
+        let by_ref = self.is_op_at(0, Operator::And);
+        if by_ref {
+            n += 1;
+            if let Some(Token::Lifetime(_)) = self.peek_at(n) {
+                n += 1;
+            }
+        }
+        let mutable = self.peek_at(n) == Some(&Token::Keyword(Keyword::Mut));
+        if mutable {
+            n += 1;
+        }
+        if self.peek_at(n) != Some(&Token::Keyword(Keyword::SelfValue))
+            || self.is_op_at(n + 1, Operator::PathSep)
+        {
+            return Ok(None);
+        }
+        self.pos += n + 1;
+        Ok(Some(if by_ref {
+            SelfParam::Ref(mutable)
+        } else if self.eat_op(Operator::Colon) {
+            SelfParam::Typed(mutable, self.ty()?)
+        } else {
+            SelfParam::Value(mutable)
+        }))
+    }
+
+    fn tuple_fields(&mut self) -> Result<Fields<'a>> {
+        self.expect_bracket(Bracket::ParensL)?;
+        let mut fields = Vec::new();
+        while !self.eat_bracket(Bracket::ParensR) {
+            let attrs = self.outer_attributes()?;
+            let start = self.span();
+            let vis = self.visibility()?;
+            let ty = self.ty()?;
+            let enabled = self.is_enabled(&attrs)?;
+            let field = Field {
+                attrs,
+                vis,
+                name: None,
+                ty,
+                span: self.since(start),
+            };
+            if enabled {
+                fields.push(field);
+            }
+            if !self.eat_op(Operator::Comma) {
+                self.expect_bracket(Bracket::ParensR)?;
+                break;
+            }
+        }
+        Ok(Fields::Tuple(fields))
+    }
+
+    fn named_fields(&mut self) -> Result<Fields<'a>> {
+        self.expect_bracket(Bracket::BraceL)?;
+        let mut fields = Vec::new();
+        while !self.eat_bracket(Bracket::BraceR) {
+            let attrs = self.outer_attributes()?;
+            let start = self.span();
+            let vis = self.visibility()?;
+            let name = self.ident()?;
+            self.expect_op(Operator::Colon)?;
+            let ty = self.ty()?;
+            let enabled = self.is_enabled(&attrs)?;
+            let field = Field {
+                attrs,
+                vis,
+                name: Some(name),
+                ty,
+                span: self.since(start),
+            };
+            if enabled {
+                fields.push(field);
+            }
+            if !self.eat_op(Operator::Comma) {
+                self.expect_bracket(Bracket::BraceR)?;
+                break;
+            }
+        }
+        Ok(Fields::Named(fields))
+    }
+
+    fn variant(&mut self) -> Result<Variant<'a>> {
+        let attrs = self.outer_attributes()?;
+        let start = self.span();
+        self.visibility()?;
+        let name = self.ident()?;
+        let fields = if self.is_bracket(Bracket::ParensL) {
+            self.tuple_fields()?
+        } else if self.is_bracket(Bracket::BraceL) {
+            self.named_fields()?
+        } else {
+            Fields::Unit
+        };
+        let discriminant = if self.eat_op(Operator::Eq) {
+            Some(self.expr()?)
+        } else {
+            None
+        };
+        Ok(Variant {
+            attrs,
+            name,
+            fields,
+            discriminant,
+            span: self.since(start),
+        })
+    }
+
+    fn use_tree(&mut self) -> Result<UseTree<'a>> {
+        let start = self.span();
+        let global = self.eat_op(Operator::PathSep);
+        let mut segments = Vec::new();
+        let kind = loop {
+            if self.is_bracket(Bracket::BraceL) {
+                self.pos += 1;
+                let mut trees = Vec::new();
+                while !self.eat_bracket(Bracket::BraceR) {
+                    trees.push(self.use_tree()?);
+                    if !self.eat_op(Operator::Comma) {
+                        self.expect_bracket(Bracket::BraceR)?;
+                        break;
+                    }
+                }
+                break UseTreeKind::Nested(trees);
+            }
+            if self.eat_op(Operator::Star) {
+                break UseTreeKind::Glob;
+            }
+            let segment_start = self.span();
+            let name = self.segment_name()?;
+            segments.push(PathSegment {
+                name,
+                generics: Vec::new(),
+                span: self.since(segment_start),
+            });
+            if !self.eat_op(Operator::PathSep) {
+                let rename = if self.eat_keyword(Keyword::As) {
+                    Some(self.ident_or_underscore()?)
+                } else {
+                    None
+                };
+                break UseTreeKind::Simple(rename);
+            }
+        };
+        let prefix = Path {
+            global,
+            span: match segments.last() {
+                Some(segment) => Span::new(start.start, segment.span.end),
+                None => Span::new(start.start, start.start),
+            },
+            segments,
+        };
+        Ok(UseTree { prefix, kind })
+    }
+}
+
+// Tokenize `text[range]` into `tokens`, reading the expansion of each macro
+// call in `splices` (sorted by position) in its place.
+fn lex<'a>(
+    text: &'a str,
+    range: Span,
+    splices: &[Splice],
+    tokens: &mut Vec<(Token<'a>, Span)>,
+) -> Result<()> {
+    let offset = |span: Span| {
+        Span::new(span.start + range.start, span.end + range.start)
+    };
+    let mut iter = TokenIterator::new(&text[range.start..range.end]);
+    let mut inside = splices
+        .iter()
+        .filter(|splice| {
+            range.start <= splice.call.start && splice.call.end <= range.end
+        })
+        .peekable();
+    // Tokens before `skip` are part of a call that was replaced.
+    let mut skip = range.start;
+
+    loop {
+        let token = iter.next();
+        let span = match token {
+            Some(_) => offset(iter.span()),
+            None => Span::new(range.end, range.end),
+        };
+        while let Some(splice) =
+            inside.next_if(|splice| splice.call.start <= span.start)
+        {
+            // Calls in the arguments of a replaced call are gone with it.
+            if splice.call.start >= skip {
+                lex(text, splice.expansion, splices, tokens)?;
+                skip = splice.call.end;
+            }
+        }
+        let (inner, doc) = match token {
+            None => return Ok(()),
+            Some(_) if span.start < skip => continue,
+            Some(Ok(Token::Comment(_))) => continue,
+            Some(Ok(Token::OuterDoc(doc))) => (false, doc),
+            Some(Ok(Token::InnerDoc(doc))) => (true, doc),
+            Some(Ok(token)) => {
+                tokens.push((token, span));
+                continue;
+            }
+            Some(Err(mut e)) => {
+                e.span = offset(e.span);
+                return Err(e);
+            }
+        };
+        // `/// text` is `#[doc = " text"]`
+        tokens.push((Token::Operator(Operator::Pound), span));
+        if inner {
+            tokens.push((Token::Operator(Operator::Not), span));
+        }
+        tokens.push((Token::Bracket(Bracket::SquareL), span));
+        tokens.push((Token::Identifier("doc"), span));
+        tokens.push((Token::Operator(Operator::Eq), span));
+        tokens.push((Token::String(doc.to_string()), span));
+        tokens.push((Token::Bracket(Bracket::SquareR), span));
+    }
+}
@@ -0,0 +1,1045 @@
+// Rust expression parser
+//
+//! Expressions, statements and patterns.  Binary operators are parsed by
+//! precedence climbing, and struct literals are disallowed where a `{` would
+//! start a block (the conditions of `if`, `while`, `match` and `for`).
+
+use super::{Parser, PathStyle};
+use crate::rust::{
+    Arm, BinaryOp, Block, Bracket, Closure, Expr, ExprKind, FieldInit,
+    FieldPattern, Item, Keyword, Lit, Local, Operator, Path, PathSegment,
+    Pattern, PatternKind, Result, Stmt, StmtKind, Token, UnaryOp,
+};
+use crate::Span;
+
+// Binary operator and precedence (higher binds tighter).
+fn binary_op(op: Operator) -> Option<(BinaryOp, u8)> {
+    Some(match op {
+        Operator::OrOr => (BinaryOp::Or, 1),
+        Operator::AndAnd => (BinaryOp::And, 2),
+        Operator::EqEq => (BinaryOp::Eq, 3),
+        Operator::Ne => (BinaryOp::Ne, 3),
+        Operator::Lt => (BinaryOp::Lt, 3),
+        Operator::Le => (BinaryOp::Le, 3),
+        Operator::Gt => (BinaryOp::Gt, 3),
+        Operator::Ge => (BinaryOp::Ge, 3),
+        Operator::Or => (BinaryOp::BitOr, 4),
+        Operator::Caret => (BinaryOp::BitXor, 5),
+        Operator::And => (BinaryOp::BitAnd, 6),
+        Operator::Shl => (BinaryOp::Shl, 7),
+        Operator::Shr => (BinaryOp::Shr, 7),
+        Operator::Plus => (BinaryOp::Add, 8),
+        Operator::Minus => (BinaryOp::Sub, 8),
+        Operator::Star => (BinaryOp::Mul, 9),
+        Operator::Slash => (BinaryOp::Div, 9),
+        Operator::Percent => (BinaryOp::Rem, 9),
+        _ => return None,
+    })
+}
+
+// Operator of a compound assignment.
+fn assign_op(op: Operator) -> Option<BinaryOp> {
+    Some(match op {
+        Operator::PlusEq => BinaryOp::Add,
+        Operator::MinusEq => BinaryOp::Sub,
+        Operator::StarEq => BinaryOp::Mul,
+        Operator::SlashEq => BinaryOp::Div,
+        Operator::PercentEq => BinaryOp::Rem,
+        Operator::CaretEq => BinaryOp::BitXor,
+        Operator::AndEq => BinaryOp::BitAnd,
+        Operator::OrEq => BinaryOp::BitOr,
+        Operator::ShlEq => BinaryOp::Shl,
+        Operator::ShrEq => BinaryOp::Shr,
+        _ => return None,
+    })
+}
+
+// An expression from the start of `start` to the end of `end`.
+fn join<'a>(start: Span, end: Span, kind: ExprKind<'a>) -> Expr<'a> {
+    Expr { kind, span: Span::new(start.start, end.end) }
+}
+
+impl<'a> Parser<'a> {
+    // Whether the next token is exactly `op` (not the start of a longer one).
+    fn at_op(&self, op: Operator) -> bool {
+        self.is_op_at(0, op)
+    }
+
+    // The literal at the next token, if any.
+    fn lit(&self) -> Option<Lit> {
+        Some(match self.peek()? {
+            Token::Keyword(Keyword::True) => Lit::Bool(true),
+            Token::Keyword(Keyword::False) => Lit::Bool(false),
+            Token::Char(ch) => Lit::Char(*ch),
+            Token::Byte(byte) => Lit::Byte(*byte),
+            Token::String(string) => Lit::Str(string.clone()),
+            Token::ByteString(bytes) => Lit::ByteStr(bytes.clone()),
+            Token::CString(bytes) => Lit::CStr(bytes.clone()),
+            Token::Int(value, ty) => Lit::Int(*value, *ty),
+            Token::Float(value, ty) => Lit::Float(*value, *ty),
+            _ => return None,
+        })
+    }
+
+    // A field name, which is an identifier or a tuple index.
+    fn field_name(&mut self) -> Result<&'a str> {
+        match self.peek() {
+            Some(Token::Int(_, None)) => {
+                let span = self.span();
+                self.pos += 1;
+                Ok(&self.text[span.start..span.end])
+            }
+            _ => self.ident(),
+        }
+    }
+
+    // Whether the next token can start an expression.
+    fn can_begin_expr(&self) -> bool {
+        match self.peek() {
+            None => false,
+            Some(Token::Operator(op)) => matches!(op,
+                Operator::Minus | Operator::Not | Operator::Star
+                | Operator::And | Operator::AndAnd | Operator::Or
+                | Operator::OrOr | Operator::Lt | Operator::Shl
+                | Operator::DotDot | Operator::DotDotEq | Operator::PathSep),
+            Some(Token::Bracket(bracket)) => matches!(bracket,
+                Bracket::ParensL | Bracket::SquareL | Bracket::BraceL),
+            Some(Token::Keyword(keyword)) => !matches!(keyword,
+                Keyword::As | Keyword::Else | Keyword::In | Keyword::Where
+                | Keyword::Mut | Keyword::Ref | Keyword::Underscore),
+            Some(_) => true,
+        }
+    }
+
+    /// Whether an item (rather than a statement) starts at the next token.
+    fn is_item_start(&self) -> bool {
+        let next = self.peek_at(1);
+        match self.peek() {
+            Some(Token::Keyword(keyword)) => match keyword {
+                Keyword::Fn | Keyword::Struct | Keyword::Enum | Keyword::Use
+                | Keyword::Static | Keyword::Impl | Keyword::Trait
+                | Keyword::Mod | Keyword::Type | Keyword::Extern
+                | Keyword::Pub => true,
+                Keyword::Const => !matches!(next,
+                    Some(Token::Bracket(Bracket::BraceL))
+                    | Some(Token::Keyword(Keyword::Move))
+                    | Some(Token::Operator(Operator::Or))
+                    | Some(Token::Operator(Operator::OrOr))),
+                Keyword::Unsafe => matches!(next,
+                    Some(Token::Keyword(Keyword::Fn))
+                    | Some(Token::Keyword(Keyword::Impl))
+                    | Some(Token::Keyword(Keyword::Trait))
+                    | Some(Token::Keyword(Keyword::Extern))
+                    | Some(Token::Identifier("auto"))),
+                Keyword::Async => matches!(next,
+                    Some(Token::Keyword(Keyword::Fn))
+                    | Some(Token::Keyword(Keyword::Unsafe))),
+                _ => false,
+            },
+            Some(Token::Identifier("union")) | Some(Token::Identifier("auto")) => {
+                matches!(next, Some(Token::Identifier(_))
+                    | Some(Token::Keyword(Keyword::Trait)))
+            }
+            Some(Token::Identifier("macro_rules")) => {
+                next == Some(&Token::Operator(Operator::Not))
+                    && matches!(self.peek_at(2), Some(Token::Identifier(_)))
+            }
+            _ => false,
+        }
+    }
+
+    /// Parse an expression.
+    pub(in crate::rust) fn expr(&mut self) -> Result<Expr<'a>> {
+        self.expr_with(false)
+    }
+
+    /// Comma separated macro arguments up to the end of the input, or a
+    /// single `Repeat` expression for `value; count`.
+    pub(in crate::rust) fn macro_args(&mut self) -> Result<Vec<Expr<'a>>> {
+        let mut exprs = Vec::new();
+        while !self.is_eof() {
+            let start = self.span();
+            let expr = self.expr()?;
+            if exprs.is_empty() && self.eat_op(Operator::Semi) {
+                let count = self.expr()?;
+                self.expect_eof()?;
+                let kind = ExprKind::Repeat(Box::new(expr), Box::new(count));
+                return Ok(vec![Expr { kind, span: self.since(start) }]);
+            }
+            exprs.push(expr);
+            if !self.eat_op(Operator::Comma) {
+                self.expect_eof()?;
+            }
+        }
+        Ok(exprs)
+    }
+
+    // An expression; `no_struct` disallows struct literals outside brackets.
+    fn expr_with(&mut self, no_struct: bool) -> Result<Expr<'a>> {
+        let start = self.span();
+        if self.at_op(Operator::DotDot) || self.at_op(Operator::DotDotEq) {
+            let inclusive = self.at_op(Operator::DotDotEq);
+            self.pos += 1;
+            let end = if self.can_begin_range_end(no_struct) {
+                Some(Box::new(self.expr_binary(1, no_struct)?))
+            } else {
+                None
+            };
+            return Ok(Expr {
+                kind: ExprKind::Range(None, end, inclusive),
+                span: self.since(start),
+            });
+        }
+        let lhs = self.expr_binary(1, no_struct)?;
+        self.expr_rest(lhs, no_struct)
+    }
+
+    fn can_begin_range_end(&self, no_struct: bool) -> bool {
+        self.can_begin_expr()
+            && !(no_struct && self.is_bracket(Bracket::BraceL))
+    }
+
+    // Ranges and assignments after a left-hand side.
+    fn expr_rest(&mut self, lhs: Expr<'a>, no_struct: bool)
+        -> Result<Expr<'a>>
+    {
+        if self.at_op(Operator::DotDot) || self.at_op(Operator::DotDotEq) {
+            let inclusive = self.at_op(Operator::DotDotEq);
+            self.pos += 1;
+            let end = if self.can_begin_range_end(no_struct) {
+                Some(Box::new(self.expr_binary(1, no_struct)?))
+            } else {
+                None
+            };
+            let span = self.prev_span();
+            return Ok(join(lhs.span, span, ExprKind::Range(
+                Some(Box::new(lhs)), end, inclusive)));
+        }
+        if self.at_op(Operator::Eq) {
+            self.pos += 1;
+            let rhs = self.expr_with(no_struct)?;
+            let span = rhs.span;
+            return Ok(join(lhs.span, span, ExprKind::Assign(
+                Box::new(lhs), Box::new(rhs))));
+        }
+        let op = match self.peek() {
+            Some(Token::Operator(op)) => assign_op(*op),
+            _ => None,
+        };
+        if let Some(op) = op {
+            self.pos += 1;
+            let rhs = self.expr_with(no_struct)?;
+            let span = rhs.span;
+            return Ok(join(lhs.span, span, ExprKind::AssignOp(op,
+                Box::new(lhs), Box::new(rhs))));
+        }
+        Ok(lhs)
+    }
+
+    // Binary operators binding at least as tight as `min`.
+    fn expr_binary(&mut self, min: u8, no_struct: bool) -> Result<Expr<'a>> {
+        let lhs = self.expr_unary(no_struct)?;
+        let lhs = self.cast_rest(lhs)?;
+        self.binary_rest(lhs, min, no_struct)
+    }
+
+    fn binary_rest(&mut self, mut lhs: Expr<'a>, min: u8, no_struct: bool)
+        -> Result<Expr<'a>>
+    {
+        while let Some(Token::Operator(op)) = self.peek() {
+            let (op, prec) = match binary_op(*op) {
+                Some((op, prec)) if prec >= min => (op, prec),
+                _ => break,
+            };
+            self.pos += 1;
+            let rhs = self.expr_binary(prec + 1, no_struct)?;
+            let span = rhs.span;
+            lhs = join(lhs.span, span, ExprKind::Binary(op,
+                Box::new(lhs), Box::new(rhs)));
+        }
+        Ok(lhs)
+    }
+
+    fn cast_rest(&mut self, mut lhs: Expr<'a>) -> Result<Expr<'a>> {
+        while self.eat_keyword(Keyword::As) {
+            let ty = self.ty_no_bounds()?;
+            let span = ty.span;
+            lhs = join(lhs.span, span, ExprKind::Cast(Box::new(lhs), Box::new(ty)));
+        }
+        Ok(lhs)
+    }
+
+    fn expr_unary(&mut self, no_struct: bool) -> Result<Expr<'a>> {
+        let start = self.span();
+        let kind = if self.at_op(Operator::Minus) {
+            self.pos += 1;
+            ExprKind::Unary(UnaryOp::Neg, Box::new(self.expr_unary(no_struct)?))
+        } else if self.at_op(Operator::Not) {
+            self.pos += 1;
+            ExprKind::Unary(UnaryOp::Not, Box::new(self.expr_unary(no_struct)?))
+        } else if self.at_op(Operator::Star) {
+            self.pos += 1;
+            let operand = self.expr_unary(no_struct)?;
+            ExprKind::Unary(UnaryOp::Deref, Box::new(operand))
+        } else if self.at_op(Operator::And) || self.at_op(Operator::AndAnd) {
+            // `&&x` is `& &x`
+            self.eat_op(Operator::And);
+            let mutable = self.eat_keyword(Keyword::Mut);
+            ExprKind::Ref(mutable, Box::new(self.expr_unary(no_struct)?))
+        } else {
+            let primary = self.expr_primary(no_struct)?;
+            return self.postfix_rest(primary);
+        };
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    // Method calls, field accesses, calls, indexing, `?` and `.await`.
+    fn postfix_rest(&mut self, mut lhs: Expr<'a>) -> Result<Expr<'a>> {
+        loop {
+            let start = lhs.span;
+            let kind = if self.at_op(Operator::Question) {
+                self.pos += 1;
+                ExprKind::Try(Box::new(lhs))
+            } else if self.at_op(Operator::Dot) {
+                self.pos += 1;
+                if self.eat_keyword(Keyword::Await) {
+                    ExprKind::Await(Box::new(lhs))
+                } else {
+                    let segment_start = self.span();
+                    let name = self.field_name()?;
+                    let generics = if self.at_op(Operator::PathSep) {
+                        self.pos += 1;
+                        self.generic_args()?
+                    } else {
+                        Vec::new()
+                    };
+                    if !generics.is_empty()
+                        || self.is_bracket(Bracket::ParensL)
+                    {
+                        let method = PathSegment {
+                            name,
+                            generics,
+                            span: self.since(segment_start),
+                        };
+                        let args = self.call_args()?;
+                        ExprKind::MethodCall(Box::new(lhs), method, args)
+                    } else {
+                        ExprKind::Field(Box::new(lhs), name)
+                    }
+                }
+            } else if self.is_bracket(Bracket::ParensL) {
+                let args = self.call_args()?;
+                ExprKind::Call(Box::new(lhs), args)
+            } else if self.eat_bracket(Bracket::SquareL) {
+                let index = self.expr()?;
+                self.expect_bracket(Bracket::SquareR)?;
+                ExprKind::Index(Box::new(lhs), Box::new(index))
+            } else {
+                return Ok(lhs);
+            };
+            lhs = join(start, self.prev_span(), kind);
+        }
+    }
+
+    // Parenthesized, comma separated arguments.
+    fn call_args(&mut self) -> Result<Vec<Expr<'a>>> {
+        self.expect_bracket(Bracket::ParensL)?;
+        self.expr_list(Bracket::ParensR).map(|(args, _)| args)
+    }
+
+    // Expressions separated by commas up to `close`, and whether there was a
+    // trailing comma.
+    fn expr_list(&mut self, close: Bracket) -> Result<(Vec<Expr<'a>>, bool)> {
+        let mut exprs = Vec::new();
+        let mut trailing_comma = false;
+        while !self.eat_bracket(close) {
+            self.outer_attributes()?;
+            exprs.push(self.expr()?);
+            trailing_comma = self.eat_op(Operator::Comma);
+            if !trailing_comma {
+                self.expect_bracket(close)?;
+                break;
+            }
+        }
+        Ok((exprs, trailing_comma))
+    }
+
+    fn expr_primary(&mut self, no_struct: bool) -> Result<Expr<'a>> {
+        let start = self.span();
+        if let Some(lit) = self.lit() {
+            self.pos += 1;
+            return Ok(Expr { kind: ExprKind::Lit(lit), span: start });
+        }
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return self.unexpected("expression"),
+        };
+        let next = self.peek_at(1).cloned();
+        let kind = match token {
+            Token::Bracket(Bracket::ParensL) => {
+                self.pos += 1;
+                let (mut exprs, trailing_comma) =
+                    self.expr_list(Bracket::ParensR)?;
+                if exprs.len() == 1 && !trailing_comma {
+                    // Parenthesized expression
+                    let mut expr = exprs.pop().unwrap();
+                    expr.span = self.since(start);
+                    return Ok(expr);
+                }
+                ExprKind::Tuple(exprs)
+            }
+            Token::Bracket(Bracket::SquareL) => {
+                self.pos += 1;
+                if self.eat_bracket(Bracket::SquareR) {
+                    ExprKind::Array(Vec::new())
+                } else {
+                    let first = self.expr()?;
+                    if self.eat_op(Operator::Semi) {
+                        let count = self.expr()?;
+                        self.expect_bracket(Bracket::SquareR)?;
+                        ExprKind::Repeat(Box::new(first), Box::new(count))
+                    } else if self.eat_op(Operator::Comma) {
+                        let (mut exprs, _) =
+                            self.expr_list(Bracket::SquareR)?;
+                        exprs.insert(0, first);
+                        ExprKind::Array(exprs)
+                    } else {
+                        self.expect_bracket(Bracket::SquareR)?;
+                        ExprKind::Array(vec![first])
+                    }
+                }
+            }
+            Token::Bracket(Bracket::BraceL) => {
+                ExprKind::Block(None, self.block()?)
+            }
+            Token::Lifetime(label)
+                if next == Some(Token::Operator(Operator::Colon)) =>
+            {
+                self.pos += 2;
+                self.labeled(Some(label))?
+            }
+            Token::Keyword(Keyword::If) => self.if_expr()?,
+            Token::Keyword(Keyword::Match) => {
+                self.pos += 1;
+                let scrutinee = self.expr_with(true)?;
+                self.expect_bracket(Bracket::BraceL)?;
+                self.inner_attributes()?;
+                let mut arms = Vec::new();
+                while !self.eat_bracket(Bracket::BraceR) {
+                    let arm = self.arm()?;
+                    let needs_comma = !arm.body.is_block_like();
+                    if self.is_enabled(&arm.attrs)? {
+                        arms.push(arm);
+                    }
+                    if !self.eat_op(Operator::Comma) && needs_comma {
+                        self.expect_bracket(Bracket::BraceR)?;
+                        break;
+                    }
+                }
+                ExprKind::Match(Box::new(scrutinee), arms)
+            }
+            Token::Keyword(Keyword::Loop) | Token::Keyword(Keyword::While)
+            | Token::Keyword(Keyword::For) => self.labeled(None)?,
+            Token::Keyword(Keyword::Unsafe) => {
+                self.pos += 1;
+                ExprKind::Unsafe(self.block()?)
+            }
+            Token::Keyword(Keyword::Const)
+                if next == Some(Token::Bracket(Bracket::BraceL)) =>
+            {
+                self.pos += 1;
+                ExprKind::Const(self.block()?)
+            }
+            Token::Keyword(Keyword::Async)
+                if next == Some(Token::Bracket(Bracket::BraceL))
+                    || next == Some(Token::Keyword(Keyword::Move))
+                        && self.peek_at(2)
+                            == Some(&Token::Bracket(Bracket::BraceL)) =>
+            {
+                self.pos += 1;
+                let is_move = self.eat_keyword(Keyword::Move);
+                ExprKind::Async(is_move, self.block()?)
+            }
+            Token::Keyword(Keyword::Async) | Token::Keyword(Keyword::Move)
+            | Token::Operator(Operator::Or)
+            | Token::Operator(Operator::OrOr) => self.closure(no_struct)?,
+            Token::Keyword(Keyword::Return) => {
+                self.pos += 1;
+                let value = if self.can_begin_expr() {
+                    Some(Box::new(self.expr_with(no_struct)?))
+                } else {
+                    None
+                };
+                ExprKind::Return(value)
+            }
+            Token::Keyword(Keyword::Break) => {
+                self.pos += 1;
+                let label = self.label();
+                let value = if self.can_begin_range_end(no_struct) {
+                    Some(Box::new(self.expr_with(no_struct)?))
+                } else {
+                    None
+                };
+                ExprKind::Break(label, value)
+            }
+            Token::Keyword(Keyword::Continue) => {
+                self.pos += 1;
+                ExprKind::Continue(self.label())
+            }
+            Token::Keyword(Keyword::Let) => {
+                self.pos += 1;
+                let pattern = self.pattern()?;
+                self.expect_op(Operator::Eq)?;
+                // `&&` chains further conditions.
+                let value = self.expr_binary(3, no_struct)?;
+                ExprKind::Let(Box::new(pattern), Box::new(value))
+            }
+            Token::Operator(Operator::Lt) | Token::Operator(Operator::Shl) => {
+                let (self_ty, trait_, segments) =
+                    self.qualified_path(PathStyle::Expr)?;
+                ExprKind::Qualified(self_ty, trait_, segments)
+            }
+            _ if self.is_path_start() => {
+                let path = self.path(PathStyle::Expr)?;
+                if self.at_op(Operator::Not) && matches!(self.peek_at(1),
+                    Some(Token::Bracket(Bracket::ParensL))
+                    | Some(Token::Bracket(Bracket::SquareL))
+                    | Some(Token::Bracket(Bracket::BraceL)))
+                {
+                    ExprKind::Macro(self.macro_call(path)?)
+                } else if !no_struct && self.is_bracket(Bracket::BraceL) {
+                    self.struct_literal(path)?
+                } else {
+                    ExprKind::Path(path)
+                }
+            }
+            _ => return self.unexpected("expression"),
+        };
+        Ok(Expr { kind, span: self.since(start) })
+    }
+
+    // Optional label after `break` or `continue`.
+    fn label(&mut self) -> Option<&'a str> {
+        match self.peek() {
+            Some(Token::Lifetime(label)) => {
+                let label = *label;
+                self.pos += 1;
+                Some(label)
+            }
+            _ => None,
+        }
+    }
+
+    // A loop or block after an optional label.
+    fn labeled(&mut self, label: Option<&'a str>) -> Result<ExprKind<'a>> {
+        Ok(if self.eat_keyword(Keyword::Loop) {
+            ExprKind::Loop(label, self.block()?)
+        } else if self.eat_keyword(Keyword::While) {
+            let condition = self.expr_with(true)?;
+            ExprKind::While(label, Box::new(condition), self.block()?)
+        } else if self.eat_keyword(Keyword::For) {
+            let pattern = self.pattern()?;
+            self.expect_keyword(Keyword::In)?;
+            let iter = self.expr_with(true)?;
+            let body = self.block()?;
+            ExprKind::For(label, Box::new(pattern), Box::new(iter), body)
+        } else if self.is_bracket(Bracket::BraceL) {
+            ExprKind::Block(label, self.block()?)
+        } else {
+            return self.unexpected("loop or block");
+        })
+    }
+
+    fn if_expr(&mut self) -> Result<ExprKind<'a>> {
+        self.expect_keyword(Keyword::If)?;
+        let condition = self.expr_with(true)?;
+        let then = self.block()?;
+        let otherwise = if self.eat_keyword(Keyword::Else) {
+            let start = self.span();
+            let kind = if self.is_keyword(Keyword::If) {
+                self.if_expr()?
+            } else {
+                ExprKind::Block(None, self.block()?)
+            };
+            Some(Box::new(Expr { kind, span: self.since(start) }))
+        } else {
+            None
+        };
+        Ok(ExprKind::If(Box::new(condition), then, otherwise))
+    }
+
+    fn arm(&mut self) -> Result<Arm<'a>> {
+        let attrs = self.outer_attributes()?;
+        let start = self.span();
+        let pattern = self.pattern()?;
+        let guard = if self.eat_keyword(Keyword::If) {
+            Some(self.expr()?)
+        } else {
+            None
+        };
+        self.expect_op(Operator::FatArrow)?;
+        let body = self.expr_stmt()?;
+        Ok(Arm { attrs, pattern, guard, body, span: self.since(start) })
+    }
+
+    fn closure(&mut self, no_struct: bool) -> Result<ExprKind<'a>> {
+        let is_async = self.eat_keyword(Keyword::Async);
+        let is_move = self.eat_keyword(Keyword::Move);
+        let mut params = Vec::new();
+        if !self.eat_op(Operator::OrOr) {
+            self.expect_op(Operator::Or)?;
+            while !self.eat_op(Operator::Or) {
+                self.outer_attributes()?;
+                let pattern = self.pattern_no_alt()?;
+                let ty = if self.at_op(Operator::Colon) {
+                    self.pos += 1;
+                    Some(self.ty()?)
+                } else {
+                    None
+                };
+                params.push((pattern, ty));
+                if !self.eat_op(Operator::Comma) {
+                    self.expect_op(Operator::Or)?;
+                    break;
+                }
+            }
+        }
+        let (ret, body) = if self.eat_op(Operator::RArrow) {
+            // The body must be a block when the return type is given.
+            let ret = self.ty_no_bounds()?;
+            let start = self.span();
+            let block = self.block()?;
+            let body = Expr {
+                kind: ExprKind::Block(None, block),
+                span: self.since(start),
+            };
+            (Some(ret), body)
+        } else {
+            (None, self.expr_with(no_struct)?)
+        };
+        Ok(ExprKind::Closure(Box::new(Closure {
+            is_async,
+            is_move,
+            params,
+            ret,
+            body,
+        })))
+    }
+
+    fn struct_literal(&mut self, path: Path<'a>) -> Result<ExprKind<'a>> {
+        self.expect_bracket(Bracket::BraceL)?;
+        let mut fields = Vec::new();
+        let mut base = None;
+        while !self.eat_bracket(Bracket::BraceR) {
+            self.outer_attributes()?;
+            if self.at_op(Operator::DotDot) {
+                self.pos += 1;
+                if !self.is_bracket(Bracket::BraceR) {
+                    base = Some(Box::new(self.expr()?));
+                }
+                self.expect_bracket(Bracket::BraceR)?;
+                break;
+            }
+            let start = self.span();
+            let name = self.field_name()?;
+            let value = if self.at_op(Operator::Colon) {
+                self.pos += 1;
+                self.expr()?
+            } else {
+                // Shorthand `name` for `name: name`
+                let segment = PathSegment {
+                    name,
+                    generics: Vec::new(),
+                    span: start,
+                };
+                Expr {
+                    kind: ExprKind::Path(Path {
+                        global: false,
+                        segments: vec![segment],
+                        span: start,
+                    }),
+                    span: start,
+                }
+            };
+            fields.push(FieldInit { name, value, span: self.since(start) });
+            if !self.eat_op(Operator::Comma) {
+                self.expect_bracket(Bracket::BraceR)?;
+                break;
+            }
+        }
+        Ok(ExprKind::Struct(path, fields, base))
+    }
+
+    // An expression in statement position or a match arm, where a block-like
+    // expression ends the statement unless followed by `.` or `?`.
+    fn expr_stmt(&mut self) -> Result<Expr<'a>> {
+        let block_like = match self.peek() {
+            Some(Token::Bracket(Bracket::BraceL))
+            | Some(Token::Keyword(Keyword::If))
+            | Some(Token::Keyword(Keyword::Match))
+            | Some(Token::Keyword(Keyword::Loop))
+            | Some(Token::Keyword(Keyword::While))
+            | Some(Token::Keyword(Keyword::For)) => true,
+            Some(Token::Keyword(Keyword::Unsafe)) => {
+                self.peek_at(1) == Some(&Token::Bracket(Bracket::BraceL))
+            }
+            Some(Token::Lifetime(_)) => self.is_op_at(1, Operator::Colon),
+            _ => false,
+        };
+        if !block_like {
+            return self.expr();
+        }
+        let expr = self.expr_primary(false)?;
+        if !self.at_op(Operator::Dot) && !self.at_op(Operator::Question) {
+            return Ok(expr);
+        }
+        let expr = self.postfix_rest(expr)?;
+        let expr = self.cast_rest(expr)?;
+        let expr = self.binary_rest(expr, 1, false)?;
+        self.expr_rest(expr, false)
+    }
+
+    /// Parse a block of statements.
+    pub(in crate::rust) fn block(&mut self) -> Result<Block<'a>> {
+        let start = self.span();
+        self.expect_bracket(Bracket::BraceL)?;
+        self.inner_attributes()?;
+        let mut stmts = Vec::new();
+        let mut expr = None;
+        while !self.eat_bracket(Bracket::BraceR) {
+            if self.eat_op(Operator::Semi) {
+                continue;
+            }
+            let mut attrs = self.outer_attributes()?;
+            let stmt_start = self.span();
+            let kind = if self.eat_keyword(Keyword::Let) {
+                let local = self.local()?;
+                self.expect_op(Operator::Semi)?;
+                StmtKind::Let(Box::new(local))
+            } else if self.is_item_start() {
+                let vis = self.visibility()?;
+                let kind = self.item_kind(&mut attrs)?;
+                let item = Item {
+                    attrs: std::mem::take(&mut attrs),
+                    vis,
+                    kind,
+                    span: self.since(stmt_start),
+                };
+                StmtKind::Item(Box::new(item))
+            } else {
+                let e = self.expr_stmt()?;
+                if self.eat_op(Operator::Semi) {
+                    StmtKind::Semi(e)
+                } else if self.eat_bracket(Bracket::BraceR) {
+                    if self.is_enabled(&attrs)? {
+                        expr = Some(Box::new(e));
+                    }
+                    break;
+                } else if e.is_block_like() {
+                    StmtKind::Expr(e)
+                } else {
+                    return self.unexpected("`;` or `}`");
+                }
+            };
+            if let StmtKind::Item(ref item) = kind {
+                if !self.is_enabled(&item.attrs)? {
+                    continue;
+                }
+            }
+            if self.is_enabled(&attrs)? {
+                stmts.push(Stmt { attrs, kind, span: self.since(stmt_start) });
+            }
+        }
+        Ok(Block { stmts, expr, span: self.since(start) })
+    }
+
+    // A `let` statement after the `let`.
+    fn local(&mut self) -> Result<Local<'a>> {
+        let pattern = self.pattern()?;
+        let ty = if self.at_op(Operator::Colon) {
+            self.pos += 1;
+            Some(self.ty()?)
+        } else {
+            None
+        };
+        let init = if self.at_op(Operator::Eq) {
+            self.pos += 1;
+            Some(self.expr()?)
+        } else {
+            None
+        };
+        let diverge = if init.is_some() && self.eat_keyword(Keyword::Else) {
+            Some(self.block()?)
+        } else {
+            None
+        };
+        Ok(Local { pattern, ty, init, diverge })
+    }
+
+    /// Parse a statement without its `;`, as matched by a `stmt` macro
+    /// fragment.
+    pub(in crate::rust) fn stmt(&mut self) -> Result<()> {
+        let mut attrs = self.outer_attributes()?;
+        if self.eat_keyword(Keyword::Let) {
+            self.local()?;
+        } else if self.is_item_start() {
+            self.visibility()?;
+            self.item_kind(&mut attrs)?;
+        } else {
+            self.expr_stmt()?;
+        }
+        Ok(())
+    }
+
+    /// Parse a pattern, including or-patterns.
+    pub(in crate::rust) fn pattern(&mut self) -> Result<Pattern<'a>> {
+        let start = self.span();
+        if self.at_op(Operator::Or) {
+            self.pos += 1;
+        }
+        let first = self.pattern_no_alt()?;
+        if !self.at_op(Operator::Or) {
+            return Ok(first);
+        }
+        let mut patterns = vec![first];
+        while self.at_op(Operator::Or) {
+            self.pos += 1;
+            patterns.push(self.pattern_no_alt()?);
+        }
+        Ok(Pattern {
+            kind: PatternKind::Or(patterns),
+            span: self.since(start),
+        })
+    }
+
+    /// Parse a pattern without top-level `|`, as in closure and function
+    /// parameters.
+    pub(in crate::rust) fn pattern_no_alt(&mut self) -> Result<Pattern<'a>> {
+        let start = self.span();
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return self.unexpected("pattern"),
+        };
+        let next = self.peek_at(1).cloned();
+        let kind = match token {
+            Token::Keyword(Keyword::Underscore) => {
+                self.pos += 1;
+                PatternKind::Wild
+            }
+            Token::Operator(Operator::DotDot) => {
+                self.pos += 1;
+                PatternKind::Rest
+            }
+            Token::Operator(Operator::DotDotEq) => {
+                self.pos += 1;
+                PatternKind::Range(None, Some(self.range_end()?), true)
+            }
+            Token::Operator(Operator::And) | Token::Operator(Operator::AndAnd) => {
+                self.eat_op(Operator::And);
+                let mutable = self.eat_keyword(Keyword::Mut);
+                PatternKind::Ref(mutable, Box::new(self.pattern_no_alt()?))
+            }
+            Token::Bracket(Bracket::ParensL) => {
+                self.pos += 1;
+                let (mut patterns, trailing_comma) =
+                    self.pattern_list(Bracket::ParensR)?;
+                if patterns.len() == 1 && !trailing_comma {
+                    // Parenthesized pattern
+                    let mut pattern = patterns.pop().unwrap();
+                    pattern.span = self.since(start);
+                    return Ok(pattern);
+                }
+                PatternKind::Tuple(patterns)
+            }
+            Token::Bracket(Bracket::SquareL) => {
+                self.pos += 1;
+                PatternKind::Slice(self.pattern_list(Bracket::SquareR)?.0)
+            }
+            Token::Keyword(Keyword::Ref) | Token::Keyword(Keyword::Mut) => {
+                let by_ref = self.eat_keyword(Keyword::Ref);
+                let mutable = self.eat_keyword(Keyword::Mut);
+                let name = self.ident()?;
+                PatternKind::Ident(by_ref, mutable, name, self.subpattern()?)
+            }
+            Token::Identifier(name) if !matches!(next,
+                Some(Token::Operator(Operator::PathSep))
+                | Some(Token::Operator(Operator::Not))
+                | Some(Token::Operator(Operator::DotDot))
+                | Some(Token::Operator(Operator::DotDotEq))
+                | Some(Token::Operator(Operator::DotDotDot))
+                | Some(Token::Bracket(Bracket::ParensL))
+                | Some(Token::Bracket(Bracket::BraceL))) =>
+            {
+                self.pos += 1;
+                PatternKind::Ident(false, false, name, self.subpattern()?)
+            }
+            Token::Operator(Operator::Minus) => {
+                let lit = self.pattern_lit()?;
+                self.range_rest(lit)?
+            }
+            _ if self.lit().is_some() => {
+                let lit = self.pattern_lit()?;
+                self.range_rest(lit)?
+            }
+            Token::Operator(Operator::Lt) | Token::Operator(Operator::Shl) => {
+                let bound = self.qualified_constant()?;
+                self.range_rest(bound)?
+            }
+            _ if self.is_path_start() => {
+                let path = self.path(PathStyle::Expr)?;
+                if self.eat_bracket(Bracket::ParensL) {
+                    let patterns = self.pattern_list(Bracket::ParensR)?.0;
+                    PatternKind::TupleStruct(path, patterns)
+                } else if self.is_bracket(Bracket::BraceL) {
+                    self.struct_pattern(path)?
+                } else if self.at_op(Operator::Not) {
+                    PatternKind::Macro(self.macro_call(path)?)
+                } else if self.at_op(Operator::DotDot)
+                    || self.at_op(Operator::DotDotEq)
+                    || self.at_op(Operator::DotDotDot)
+                {
+                    let span = path.span;
+                    let bound = Expr { kind: ExprKind::Path(path), span };
+                    self.range_rest(bound)?
+                } else {
+                    PatternKind::Path(path)
+                }
+            }
+            _ => return self.unexpected("pattern"),
+        };
+        Ok(Pattern { kind, span: self.since(start) })
+    }
+
+    // Patterns separated by commas up to `close`, and whether there was a
+    // trailing comma.
+    fn pattern_list(&mut self, close: Bracket)
+        -> Result<(Vec<Pattern<'a>>, bool)>
+    {
+        let mut patterns = Vec::new();
+        let mut trailing_comma = false;
+        while !self.eat_bracket(close) {
+            patterns.push(self.pattern()?);
+            trailing_comma = self.eat_op(Operator::Comma);
+            if !trailing_comma {
+                self.expect_bracket(close)?;
+                break;
+            }
+        }
+        Ok((patterns, trailing_comma))
+    }
+
+    // `@ pattern` after a binding.
+    fn subpattern(&mut self) -> Result<Option<Box<Pattern<'a>>>> {
+        if self.at_op(Operator::At) {
+            self.pos += 1;
+            Ok(Some(Box::new(self.pattern_no_alt()?)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// A literal, possibly negated.
+    pub(super) fn pattern_lit(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let negative = self.at_op(Operator::Minus);
+        if negative {
+            self.pos += 1;
+        }
+        let lit = match self.lit() {
+            Some(lit) => lit,
+            None => return self.unexpected("literal"),
+        };
+        let lit_span = self.span();
+        self.pos += 1;
+        let lit = Expr { kind: ExprKind::Lit(lit), span: lit_span };
+        Ok(if negative {
+            Expr {
+                kind: ExprKind::Unary(UnaryOp::Neg, Box::new(lit)),
+                span: self.since(start),
+            }
+        } else {
+            lit
+        })
+    }
+
+    // A constant named by a qualified path, like `<T as Trait>::MAX`.
+    fn qualified_constant(&mut self) -> Result<Expr<'a>> {
+        let start = self.span();
+        let (self_ty, trait_, segments) =
+            self.qualified_path(PathStyle::Expr)?;
+        Ok(Expr {
+            kind: ExprKind::Qualified(self_ty, trait_, segments),
+            span: self.since(start),
+        })
+    }
+
+    // The upper bound of a range pattern.
+    fn range_end(&mut self) -> Result<Expr<'a>> {
+        if self.is_op(Operator::Lt) {
+            self.qualified_constant()
+        } else if self.is_path_start() {
+            let start = self.span();
+            let path = self.path(PathStyle::Expr)?;
+            Ok(Expr { kind: ExprKind::Path(path), span: self.since(start) })
+        } else {
+            self.pattern_lit()
+        }
+    }
+
+    // A literal pattern, or a range pattern starting with `start`.
+    fn range_rest(&mut self, start: Expr<'a>) -> Result<PatternKind<'a>> {
+        if self.at_op(Operator::DotDotEq) || self.at_op(Operator::DotDotDot) {
+            self.pos += 1;
+            Ok(PatternKind::Range(Some(start), Some(self.range_end()?), true))
+        } else if self.at_op(Operator::DotDot) {
+            self.pos += 1;
+            let end = if self.is_path_start() || self.at_op(Operator::Minus)
+                || self.is_op(Operator::Lt) || self.lit().is_some()
+            {
+                Some(self.range_end()?)
+            } else {
+                None
+            };
+            Ok(PatternKind::Range(Some(start), end, false))
+        } else {
+            Ok(PatternKind::Lit(start))
+        }
+    }
+
+    fn struct_pattern(&mut self, path: Path<'a>) -> Result<PatternKind<'a>> {
+        self.expect_bracket(Bracket::BraceL)?;
+        let mut fields = Vec::new();
+        let mut rest = false;
+        while !self.eat_bracket(Bracket::BraceR) {
+            self.outer_attributes()?;
+            if self.at_op(Operator::DotDot) {
+                self.pos += 1;
+                rest = true;
+                self.expect_bracket(Bracket::BraceR)?;
+                break;
+            }
+            let start = self.span();
+            let explicit = matches!(self.peek(),
+                Some(Token::Identifier(_)) | Some(Token::Int(..)))
+                && self.is_op_at(1, Operator::Colon);
+            let (name, pattern) = if explicit {
+                let name = self.field_name()?;
+                self.pos += 1;
+                (name, self.pattern()?)
+            } else {
+                // Shorthand `ref mut name` for `name: ref mut name`
+                let by_ref = self.eat_keyword(Keyword::Ref);
+                let mutable = self.eat_keyword(Keyword::Mut);
+                let name = self.ident()?;
+                let kind = PatternKind::Ident(by_ref, mutable, name, None);
+                (name, Pattern { kind, span: self.since(start) })
+            };
+            fields.push(FieldPattern { name, pattern, span: self.since(start) });
+            if !self.eat_op(Operator::Comma) {
+                self.expect_bracket(Bracket::BraceR)?;
+                break;
+            }
+        }
+        Ok(PatternKind::Struct(path, fields, rest))
+    }
+}
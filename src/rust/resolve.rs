@@ -0,0 +1,1451 @@
+// Rust name resolution
+//
+//! Loading of a crate's module tree from `foo.rs` and `foo/mod.rs` files, and
+//! resolution of paths to definitions.
+//!
+//! Imports are resolved to a fixed point, so they may appear in any order and
+//! depend on each other through globs and re-exports.  The standard library
+//! (`std`, `core` and `alloc`) is a built-in module tree with the signatures
+//! the type checker knows about; other paths into it, and paths into other
+//! crates, aren't checked and resolve to [`Res::External`].
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path as FilePath, PathBuf};
+
+use super::cfg::Cfg;
+use super::expand::{self, Splice};
+use super::{
+    libstd, Attribute, Fields, Item, ItemIterator, ItemKind, Path, Token,
+    TokenTree, UseTree, UseTreeKind, Visibility,
+};
+use crate::{Diagnostic, Span};
+
+/// Index of a file in [`Sources`]
+pub type FileId = usize;
+/// Index of a module in [`Crate::modules`]
+pub type ModuleId = usize;
+/// Index of a definition in [`Crate::defs`]
+pub type DefId = usize;
+
+/// File of the built-in standard library
+pub const STD_FILE: FileId = usize::MAX;
+
+/// A diagnostic in one of the files of a crate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDiagnostic {
+    pub file: FileId,
+    pub diagnostic: Diagnostic,
+}
+
+impl FileDiagnostic {
+    pub(super) fn new<T: Into<String>>(file: FileId, span: Span, message: T)
+        -> Self
+    {
+        FileDiagnostic { file, diagnostic: Diagnostic::new(span, message) }
+    }
+}
+
+/// A source file, with the expansions of its macro calls appended to its
+/// text.
+#[derive(Debug)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub text: String,
+    // Length of the text as written.
+    pub(super) len: usize,
+    // Macro calls and their expansions, sorted by position.
+    pub(super) splices: Vec<Splice>,
+}
+
+impl SourceFile {
+    fn new(path: PathBuf, text: String) -> Self {
+        SourceFile { len: text.len(), path, text, splices: Vec::new() }
+    }
+
+    /// The span of the code a span was expanded from: the outermost macro
+    /// call for spans in macro expansions.
+    pub fn origin(&self, mut span: Span) -> Span {
+        while span.start >= self.len {
+            match self.splices.iter().find(|splice| {
+                splice.expansion.start <= span.start
+                    && span.start < splice.expansion.end
+            }) {
+                Some(splice) => span = splice.call,
+                None => break,
+            }
+        }
+        span
+    }
+
+    /// Each macro call of the file, including those in expansions, and the
+    /// text it expands to, in order of the calls.
+    pub fn expansions(&self) -> impl Iterator<Item = (Span, &str)> {
+        self.splices.iter().map(move |splice| {
+            let expansion = splice.expansion;
+            (splice.call, &self.text[expansion.start..expansion.end])
+        })
+    }
+}
+
+/// The source files of a crate, starting with the crate root.
+#[derive(Debug)]
+pub struct Sources {
+    files: Vec<SourceFile>,
+    // File of each `mod name;`, by file and start of the item.
+    children: HashMap<(FileId, usize), FileId>,
+    std: SourceFile,
+    cfg: Cfg,
+    // Errors from macro expansion.
+    errors: Vec<FileDiagnostic>,
+}
+
+impl Default for Sources {
+    fn default() -> Self {
+        Sources {
+            files: Vec::new(),
+            children: HashMap::new(),
+            std: SourceFile::new(PathBuf::from("<std>"),
+                libstd::SOURCE.to_string()),
+            cfg: Cfg::default(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+// A `mod name;` declaration and where its file may be.
+struct ModDecl<'a> {
+    name: &'a str,
+    span: Span,
+    // Possible files, with the directory of their child modules.
+    candidates: Vec<(PathBuf, PathBuf)>,
+}
+
+impl Sources {
+    /// Load the crate rooted at `root` (such as `src/lib.rs`) from the file
+    /// system, with the default configuration.
+    pub fn load<P: AsRef<FilePath>>(root: P)
+        -> Result<Sources, FileDiagnostic>
+    {
+        Self::load_with(root, Cfg::default(),
+            |path| std::fs::read_to_string(path))
+    }
+
+    /// Load the crate rooted at `root` with the configuration `cfg`, with
+    /// `read` to get the contents of a file.  Modules disabled by `#[cfg]`
+    /// aren't loaded.
+    pub fn load_with<P, F>(root: P, cfg: Cfg, mut read: F)
+        -> Result<Sources, FileDiagnostic>
+    where
+        P: AsRef<FilePath>,
+        F: FnMut(&FilePath) -> io::Result<String>,
+    {
+        let root = root.as_ref().to_path_buf();
+        let mut sources = Sources { cfg, ..Sources::default() };
+        let text = read(&root);
+        let text = text.map_err(|e| {
+            FileDiagnostic::new(0, Span::default(),
+                format!("couldn't read `{}`: {}", root.display(), e))
+        })?;
+        sources.files.push(SourceFile::new(root, text));
+
+        let root = &sources.files[0].path;
+        let dir = root.parent().map(FilePath::to_path_buf).unwrap_or_default();
+        let mut queue = vec![(0, dir)];
+        while let Some((file, dir)) = queue.pop() {
+            let mut decls = Vec::new();
+            let mut iter = ItemIterator::new(&sources.files[file].text)
+                .configured(&sources.cfg);
+            let mut items = Vec::new();
+            for item in &mut iter {
+                items.push(item.map_err(|diagnostic| {
+                    FileDiagnostic { file, diagnostic }
+                })?);
+            }
+            let file_dir = sources.files[file].path.parent()
+                .map(FilePath::to_path_buf)
+                .unwrap_or_default();
+            mod_decls(&items, &dir, &file_dir, &mut decls);
+
+            let mut found = Vec::new();
+            for decl in decls {
+                let mut texts = Vec::new();
+                for (path, child_dir) in decl.candidates.iter() {
+                    if let Ok(text) = read(path) {
+                        texts.push((path, child_dir, text));
+                    }
+                }
+                if texts.len() > 1 {
+                    return Err(FileDiagnostic::new(file, decl.span, format!(
+                        "file for module `{}` found at both `{}` and `{}`",
+                        decl.name,
+                        texts[0].0.display(),
+                        texts[1].0.display(),
+                    )));
+                }
+                let (path, child_dir, text) = match texts.pop() {
+                    Some(found) => found,
+                    None => {
+                        return Err(FileDiagnostic::new(file, decl.span,
+                            format!("file not found for module `{}` (expected \
+                                `{}`)", decl.name,
+                                decl.candidates[0].0.display())));
+                    }
+                };
+                found.push((decl.span, path.clone(), child_dir.clone(), text));
+            }
+            for (span, path, child_dir, text) in found {
+                let id = sources.files.len();
+                sources.files.push(SourceFile::new(path, text));
+                sources.children.insert((file, span.start), id);
+                queue.push((id, child_dir));
+            }
+        }
+        sources.expand(read);
+        Ok(sources)
+    }
+
+    /// Create the sources of a crate with only a root file, with the default
+    /// configuration.
+    pub fn single<P: Into<PathBuf>>(path: P, text: String) -> Sources {
+        Self::single_with(path, text, Cfg::default())
+    }
+
+    /// Create the sources of a crate with only a root file, with the
+    /// configuration `cfg`.
+    pub fn single_with<P: Into<PathBuf>>(path: P, text: String, cfg: Cfg)
+        -> Sources
+    {
+        let mut sources = Sources {
+            files: vec![SourceFile::new(path.into(), text)],
+            cfg,
+            ..Sources::default()
+        };
+        sources.expand(|_| Err(io::ErrorKind::NotFound.into()));
+        sources
+    }
+
+    // Expand the macro calls of all files.
+    fn expand<F>(&mut self, mut read: F)
+    where
+        F: FnMut(&FilePath) -> io::Result<String>,
+    {
+        self.errors = expand::expand(&mut self.files, &self.children,
+            &self.cfg, &mut read);
+    }
+
+    /// The configuration `#[cfg]` is evaluated against.
+    pub fn cfg(&self) -> &Cfg {
+        &self.cfg
+    }
+
+    /// The files of the crate, without the standard library.
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    pub fn file(&self, file: FileId) -> &SourceFile {
+        match file {
+            STD_FILE => &self.std,
+            file => &self.files[file],
+        }
+    }
+
+    /// Render a diagnostic with the offending source line underlined.
+    pub fn render(&self, error: &FileDiagnostic) -> String {
+        let file = self.file(error.file);
+        let diagnostic = Diagnostic {
+            span: file.origin(error.diagnostic.span),
+            ..error.diagnostic.clone()
+        };
+        diagnostic.render(&file.path.display().to_string(),
+            &file.text[..file.len])
+    }
+}
+
+// The value of a `#[path = "..."]` attribute.
+fn path_attribute<'b>(attrs: &'b [Attribute]) -> Option<&'b str> {
+    attrs.iter().find_map(|attr| {
+        if !attr.is("path") {
+            return None;
+        }
+        match attr.tokens.as_slice() {
+            [TokenTree::Token(Token::Operator(super::Operator::Eq), _),
+                TokenTree::Token(Token::String(path), _)] => Some(path.as_str()),
+            _ => None,
+        }
+    })
+}
+
+// Find `mod name;` declarations, with `dir` the directory of child modules and
+// `base` the directory `#[path]` attributes are relative to.
+fn mod_decls<'a>(
+    items: &[Item<'a>],
+    dir: &FilePath,
+    base: &FilePath,
+    decls: &mut Vec<ModDecl<'a>>,
+) {
+    for item in items {
+        match item.kind {
+            ItemKind::Mod(name, None) => {
+                let candidates = match path_attribute(&item.attrs) {
+                    Some(path) => {
+                        let path = base.join(path);
+                        let dir = path.parent()
+                            .map(FilePath::to_path_buf)
+                            .unwrap_or_default();
+                        vec![(path, dir)]
+                    }
+                    None => vec![
+                        (dir.join(format!("{}.rs", name)), dir.join(name)),
+                        (dir.join(name).join("mod.rs"), dir.join(name)),
+                    ],
+                };
+                decls.push(ModDecl { name, span: item.span, candidates });
+            }
+            ItemKind::Mod(name, Some(ref items)) => {
+                let dir = match path_attribute(&item.attrs) {
+                    Some(path) => base.join(path),
+                    None => dir.join(name),
+                };
+                mod_decls(items, &dir, &dir, decls);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The three namespaces of Rust names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Namespace {
+    /// Modules, types and traits
+    Type,
+    /// Functions, constants, statics and constructors
+    Value,
+    Macro,
+}
+
+const NAMESPACES: [Namespace; 3] =
+    [Namespace::Type, Namespace::Value, Namespace::Macro];
+
+/// What a path resolves to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Res {
+    /// A definition in the crate
+    Def(DefId),
+    /// A primitive type such as `u32`
+    Primitive(&'static str),
+    /// An item in another crate, by path (such as `std::vec::Vec`)
+    External(String),
+}
+
+const PRIMITIVES: &[&str] = &[
+    "bool", "char", "str", "f32", "f64", "i8", "i16", "i32", "i64", "i128",
+    "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+];
+
+// Crates that are always available.
+const EXTERN_PRELUDE: &[&str] = &["std", "core", "alloc"];
+
+// Names of the standard library prelude.
+const PRELUDE: &[(&str, Namespace, &str)] = &[
+    ("Option", Namespace::Type, "std::option::Option"),
+    ("Some", Namespace::Value, "std::option::Option::Some"),
+    ("None", Namespace::Value, "std::option::Option::None"),
+    ("Result", Namespace::Type, "std::result::Result"),
+    ("Ok", Namespace::Value, "std::result::Result::Ok"),
+    ("Err", Namespace::Value, "std::result::Result::Err"),
+    ("Vec", Namespace::Type, "std::vec::Vec"),
+    ("String", Namespace::Type, "std::string::String"),
+    ("Box", Namespace::Type, "std::boxed::Box"),
+    ("ToString", Namespace::Type, "std::string::ToString"),
+    ("ToOwned", Namespace::Type, "std::borrow::ToOwned"),
+    ("Clone", Namespace::Type, "std::clone::Clone"),
+    ("Copy", Namespace::Type, "std::marker::Copy"),
+    ("Send", Namespace::Type, "std::marker::Send"),
+    ("Sync", Namespace::Type, "std::marker::Sync"),
+    ("Sized", Namespace::Type, "std::marker::Sized"),
+    ("Unpin", Namespace::Type, "std::marker::Unpin"),
+    ("Drop", Namespace::Type, "std::ops::Drop"),
+    ("Fn", Namespace::Type, "std::ops::Fn"),
+    ("FnMut", Namespace::Type, "std::ops::FnMut"),
+    ("FnOnce", Namespace::Type, "std::ops::FnOnce"),
+    ("Default", Namespace::Type, "std::default::Default"),
+    ("Iterator", Namespace::Type, "std::iter::Iterator"),
+    ("IntoIterator", Namespace::Type, "std::iter::IntoIterator"),
+    ("DoubleEndedIterator", Namespace::Type, "std::iter::DoubleEndedIterator"),
+    ("ExactSizeIterator", Namespace::Type, "std::iter::ExactSizeIterator"),
+    ("Extend", Namespace::Type, "std::iter::Extend"),
+    ("AsRef", Namespace::Type, "std::convert::AsRef"),
+    ("AsMut", Namespace::Type, "std::convert::AsMut"),
+    ("Into", Namespace::Type, "std::convert::Into"),
+    ("From", Namespace::Type, "std::convert::From"),
+    ("PartialEq", Namespace::Type, "std::cmp::PartialEq"),
+    ("Eq", Namespace::Type, "std::cmp::Eq"),
+    ("PartialOrd", Namespace::Type, "std::cmp::PartialOrd"),
+    ("Ord", Namespace::Type, "std::cmp::Ord"),
+    ("Eq", Namespace::Type, "std::cmp::Eq"),
+    ("PartialEq", Namespace::Type, "std::cmp::PartialEq"),
+    ("Ord", Namespace::Type, "std::cmp::Ord"),
+    ("PartialOrd", Namespace::Type, "std::cmp::PartialOrd"),
+    ("Iterator", Namespace::Type, "std::iter::Iterator"),
+    ("IntoIterator", Namespace::Type, "std::iter::IntoIterator"),
+    ("DoubleEndedIterator", Namespace::Type, "std::iter::DoubleEndedIterator"),
+    ("ExactSizeIterator", Namespace::Type, "std::iter::ExactSizeIterator"),
+    ("Extend", Namespace::Type, "std::iter::Extend"),
+    ("FromIterator", Namespace::Type, "std::iter::FromIterator"),
+    ("AsRef", Namespace::Type, "std::convert::AsRef"),
+    ("AsMut", Namespace::Type, "std::convert::AsMut"),
+    ("Into", Namespace::Type, "std::convert::Into"),
+    ("From", Namespace::Type, "std::convert::From"),
+    ("TryFrom", Namespace::Type, "std::convert::TryFrom"),
+    ("TryInto", Namespace::Type, "std::convert::TryInto"),
+    ("drop", Namespace::Value, "std::mem::drop"),
+    ("assert", Namespace::Macro, "std::assert"),
+    ("assert_eq", Namespace::Macro, "std::assert_eq"),
+    ("assert_ne", Namespace::Macro, "std::assert_ne"),
+    ("cfg", Namespace::Macro, "std::cfg"),
+    ("column", Namespace::Macro, "std::column"),
+    ("compile_error", Namespace::Macro, "std::compile_error"),
+    ("concat", Namespace::Macro, "std::concat"),
+    ("dbg", Namespace::Macro, "std::dbg"),
+    ("debug_assert", Namespace::Macro, "std::debug_assert"),
+    ("debug_assert_eq", Namespace::Macro, "std::debug_assert_eq"),
+    ("debug_assert_ne", Namespace::Macro, "std::debug_assert_ne"),
+    ("env", Namespace::Macro, "std::env"),
+    ("eprint", Namespace::Macro, "std::eprint"),
+    ("eprintln", Namespace::Macro, "std::eprintln"),
+    ("file", Namespace::Macro, "std::file"),
+    ("format", Namespace::Macro, "std::format"),
+    ("format_args", Namespace::Macro, "std::format_args"),
+    ("include", Namespace::Macro, "std::include"),
+    ("include_bytes", Namespace::Macro, "std::include_bytes"),
+    ("include_str", Namespace::Macro, "std::include_str"),
+    ("line", Namespace::Macro, "std::line"),
+    ("matches", Namespace::Macro, "std::matches"),
+    ("module_path", Namespace::Macro, "std::module_path"),
+    ("option_env", Namespace::Macro, "std::option_env"),
+    ("panic", Namespace::Macro, "std::panic"),
+    ("print", Namespace::Macro, "std::print"),
+    ("println", Namespace::Macro, "std::println"),
+    ("stringify", Namespace::Macro, "std::stringify"),
+    ("todo", Namespace::Macro, "std::todo"),
+    ("unimplemented", Namespace::Macro, "std::unimplemented"),
+    ("unreachable", Namespace::Macro, "std::unreachable"),
+    ("vec", Namespace::Macro, "std::vec"),
+    ("write", Namespace::Macro, "std::write"),
+    ("writeln", Namespace::Macro, "std::writeln"),
+];
+
+/// Where a name is visible
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vis {
+    Public,
+    /// Visible in a module and its descendants
+    Module(ModuleId),
+}
+
+/// A name bound in a module
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binding {
+    pub res: Res,
+    pub vis: Vis,
+    /// Whether the name comes from a glob import, so it can be shadowed
+    pub glob: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DefKind {
+    Module(ModuleId),
+    /// Item of the module, by index into [`Module::items`]
+    Item(usize),
+    /// Function or static of an `extern` block: index of the block in
+    /// [`Module::items`], and index in the block
+    Foreign(usize, usize),
+    /// Variant of an enum definition, by index
+    Variant(DefId, usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Def<'a> {
+    pub name: &'a str,
+    pub kind: DefKind,
+    /// The module containing the definition
+    pub module: ModuleId,
+    pub vis: Vis,
+    pub span: Span,
+}
+
+#[derive(Debug)]
+pub struct Module<'a> {
+    /// Name of the module (`crate` for the root)
+    pub name: &'a str,
+    pub parent: Option<ModuleId>,
+    /// Definition of the module
+    pub def: DefId,
+    /// File the module is in
+    pub file: FileId,
+    /// Attributes of the module, including inner attributes
+    pub attrs: Vec<Attribute<'a>>,
+    /// Items of the module; the items of child modules are moved into their
+    /// own [`Module`].
+    pub items: Vec<Item<'a>>,
+    bindings: HashMap<(Namespace, &'a str), Binding>,
+    // Paths of glob imports from other crates.
+    external_globs: Vec<String>,
+}
+
+impl<'a> Module<'a> {
+    /// Get the binding of a name declared or imported in the module.
+    pub fn binding(&self, name: &'a str, ns: Namespace) -> Option<&Binding> {
+        self.bindings.get(&(ns, name))
+    }
+
+    /// Iterate over the names declared or imported in the module.
+    pub fn bindings(&self)
+        -> impl Iterator<Item = (&'a str, Namespace, &Binding)> + '_
+    {
+        self.bindings.iter().map(|((ns, name), binding)| (*name, *ns, binding))
+    }
+}
+
+#[derive(Clone)]
+enum ImportKind<'a> {
+    /// Import as name; `None` for `as _`
+    Simple(Option<&'a str>),
+    /// `use path::{self}` imports only the module
+    SelfImport(&'a str),
+    Glob,
+}
+
+#[derive(Clone)]
+struct Import<'a> {
+    module: ModuleId,
+    global: bool,
+    path: Vec<(&'a str, Span)>,
+    kind: ImportKind<'a>,
+    vis: Vis,
+    span: Span,
+}
+
+/// A crate with its module tree and resolved imports
+#[derive(Debug)]
+pub struct Crate<'a> {
+    /// Modules, starting with the crate root
+    pub modules: Vec<Module<'a>>,
+    pub defs: Vec<Def<'a>>,
+    sources: &'a Sources,
+    // Variants by enum and name.
+    variants: HashMap<(DefId, &'a str), DefId>,
+    // `extern crate` renames in the crate root.
+    extern_crates: HashMap<&'a str, &'a str>,
+    // Root module of the standard library.
+    std: ModuleId,
+    // Whether unknown names in the standard library resolve, which waits
+    // until the known imports are resolved.
+    unknown_std: bool,
+    errors: Vec<FileDiagnostic>,
+}
+
+impl<'a> Crate<'a> {
+    /// Parse all files of a crate, build the module tree and resolve
+    /// imports.
+    pub fn new(sources: &'a Sources) -> Result<Crate<'a>, Vec<FileDiagnostic>> {
+        let mut krate = Crate {
+            modules: Vec::new(),
+            defs: Vec::new(),
+            sources,
+            variants: HashMap::new(),
+            extern_crates: HashMap::new(),
+            std: 0,
+            unknown_std: false,
+            errors: sources.errors.clone(),
+        };
+        let mut imports = Vec::new();
+        for (name, file) in [("crate", 0), ("std", STD_FILE)] {
+            let (attrs, items) = match parse(sources, file) {
+                Ok(parsed) => parsed,
+                Err(e) => return Err(vec![e]),
+            };
+            krate.std = krate.add_module(sources, name, None, Vis::Public,
+                Span::default(), file, attrs, items, &mut imports);
+        }
+        // `extern crate std;` was bound before the standard library existed.
+        let std = Res::Def(krate.modules[krate.std].def);
+        for module in krate.modules.iter_mut() {
+            for binding in module.bindings.values_mut() {
+                if matches!(binding.res, Res::External(ref krate)
+                    if EXTERN_PRELUDE.contains(&krate.as_str()))
+                {
+                    binding.res = std.clone();
+                }
+            }
+        }
+        krate.resolve_imports(imports);
+        if krate.errors.is_empty() {
+            Ok(krate)
+        } else {
+            Err(std::mem::take(&mut krate.errors))
+        }
+    }
+
+    /// The root module
+    pub fn root(&self) -> &Module<'a> {
+        &self.modules[0]
+    }
+
+    /// The source text of the file a module is in
+    pub fn text(&self, module: ModuleId) -> &'a str {
+        &self.sources.file(self.modules[module].file).text
+    }
+
+    /// The root module of the standard library
+    pub fn std(&self) -> ModuleId {
+        self.std
+    }
+
+    /// Whether a module is part of the standard library.
+    pub fn is_std(&self, module: ModuleId) -> bool {
+        self.modules[module].file == STD_FILE
+    }
+
+    /// Get the item of a definition, if it is one.
+    pub fn item(&self, def: DefId) -> Option<&Item<'a>> {
+        let def = &self.defs[def];
+        let items = &self.modules[def.module].items;
+        match def.kind {
+            DefKind::Item(index) => Some(&items[index]),
+            DefKind::Foreign(block, index) => match items[block].kind {
+                ItemKind::ExternBlock(_, ref items) => Some(&items[index]),
+                _ => None,
+            },
+            DefKind::Module(_) | DefKind::Variant(..) => None,
+        }
+    }
+
+    /// The traits whose methods may be called in a module: those declared or
+    /// imported in it, and those of the prelude.
+    pub fn traits_in_scope(&self, module: ModuleId) -> Vec<DefId> {
+        let is_trait = |res: &Res| match *res {
+            Res::Def(def) => matches!(self.item(def).map(|item| &item.kind),
+                Some(ItemKind::Trait { .. })),
+            _ => false,
+        };
+        let mut traits = Vec::new();
+        let bindings = self.modules[module].bindings()
+            .filter(|(_, ns, _)| *ns == Namespace::Type)
+            .map(|(_, _, binding)| binding.res.clone());
+        let prelude = PRELUDE.iter()
+            .filter(|(_, ns, _)| *ns == Namespace::Type)
+            .filter_map(|(name, ns, _)| self.lookup(module, name, *ns));
+        for res in bindings.chain(prelude) {
+            if let Res::Def(def) = res {
+                if is_trait(&res) && !traits.contains(&def) {
+                    traits.push(def);
+                }
+            }
+        }
+        traits
+    }
+
+    /// Get the full path of a resolution, like `crate::a::Foo`.
+    pub fn path(&self, res: &Res) -> String {
+        match *res {
+            Res::Def(def) => {
+                let def = &self.defs[def];
+                let mut path = def.name.to_string();
+                let mut module = match def.kind {
+                    DefKind::Module(module) => self.modules[module].parent,
+                    DefKind::Variant(parent, _) => {
+                        return format!("{}::{}", self.path(&Res::Def(parent)),
+                            path)
+                    }
+                    _ => Some(def.module),
+                };
+                while let Some(parent) = module {
+                    path = format!("{}::{}", self.modules[parent].name, path);
+                    module = self.modules[parent].parent;
+                }
+                path
+            }
+            Res::Primitive(name) => name.to_string(),
+            Res::External(ref path) => path.clone(),
+        }
+    }
+
+    /// Whether something visible as `vis` can be named from `module`.
+    pub fn is_visible(&self, vis: Vis, module: ModuleId) -> bool {
+        match vis {
+            Vis::Public => true,
+            Vis::Module(ancestor) => {
+                let mut module = Some(module);
+                while let Some(current) = module {
+                    if current == ancestor {
+                        return true;
+                    }
+                    module = self.modules[current].parent;
+                }
+                false
+            }
+        }
+    }
+
+    /// Look up a name in the scope of a module: its items and imports, then
+    /// extern crates, the standard library prelude and primitive types.
+    pub fn lookup(&self, module: ModuleId, name: &'a str, ns: Namespace)
+        -> Option<Res>
+    {
+        let module = &self.modules[module];
+        if let Some(binding) = module.binding(name, ns) {
+            return Some(binding.res.clone());
+        }
+        if ns == Namespace::Type {
+            if let Some(krate) = self.extern_crate(name) {
+                return Some(krate);
+            }
+        }
+        if let Some((_, _, path)) = PRELUDE.iter()
+            .find(|(prelude, prelude_ns, _)| *prelude == name && *prelude_ns == ns)
+        {
+            return Some(self.std_path(path, ns));
+        }
+        if ns == Namespace::Type {
+            if let Some(primitive) = PRIMITIVES.iter().find(|p| **p == name) {
+                return Some(Res::Primitive(primitive));
+            }
+        }
+        module.external_globs.first()
+            .map(|glob| Res::External(format!("{}::{}", glob, name)))
+    }
+
+    fn extern_crate(&self, name: &str) -> Option<Res> {
+        if let Some(krate) = self.extern_crates.get(name) {
+            return Some(match *krate {
+                "self" => Res::Def(self.modules[0].def),
+                krate if EXTERN_PRELUDE.contains(&krate) => {
+                    Res::Def(self.modules[self.std].def)
+                }
+                krate => Res::External(krate.to_string()),
+            });
+        }
+        EXTERN_PRELUDE.iter()
+            .find(|krate| **krate == name)
+            .map(|_| Res::Def(self.modules[self.std].def))
+    }
+
+    // Resolve a path of the standard library like `std::option::Option`.
+    fn std_path(&self, path: &'static str, ns: Namespace) -> Res {
+        let mut res = Res::Def(self.modules[self.std].def);
+        let segments: Vec<&'static str> = path.split("::").skip(1).collect();
+        for (index, name) in segments.iter().enumerate() {
+            let ns = match index + 1 == segments.len() {
+                true => ns,
+                false => Namespace::Type,
+            };
+            res = match self.member(&res, name, ns, self.std) {
+                Ok(Some(member)) => member,
+                _ => return Res::External(path.to_string()),
+            };
+        }
+        res
+    }
+
+    /// Resolve a path in the scope of a module.  Returns the resolution and
+    /// the number of segments it covers; the rest name associated items
+    /// (like `new` in `Vec::new`), which are resolved during type checking.
+    pub fn resolve_path(&self, module: ModuleId, path: &Path<'a>, ns: Namespace)
+        -> Result<(Res, usize), FileDiagnostic>
+    {
+        let segments: Vec<_> = path.segments.iter()
+            .map(|segment| (segment.name, segment.span))
+            .collect();
+        self.resolve_segments(module, path.global, &segments, ns)
+            .map_err(|diagnostic| FileDiagnostic {
+                file: self.modules[module].file,
+                diagnostic,
+            })
+    }
+
+    /// Resolve a path whose first segment is already resolved to `first`,
+    /// such as a name imported inside a function body.
+    pub fn resolve_path_from(
+        &self,
+        module: ModuleId,
+        first: Res,
+        path: &Path<'a>,
+        ns: Namespace,
+    ) -> Result<(Res, usize), FileDiagnostic> {
+        let segments: Vec<_> = path.segments.iter()
+            .map(|segment| (segment.name, segment.span))
+            .collect();
+        self.resolve_rest(module, first, &segments, ns)
+            .map_err(|diagnostic| FileDiagnostic {
+                file: self.modules[module].file,
+                diagnostic,
+            })
+    }
+
+    fn resolve_segments(
+        &self,
+        module: ModuleId,
+        global: bool,
+        segments: &[(&'a str, Span)],
+        ns: Namespace,
+    ) -> Result<(Res, usize), Diagnostic> {
+        let namespace = match segments.len() {
+            1 => ns,
+            _ => Namespace::Type,
+        };
+        let (first, span) = segments[0];
+        let res = if global {
+            match self.extern_crate(first) {
+                Some(krate) => krate,
+                None => return Err(Diagnostic::new(span,
+                    format!("could not find crate `{}`", first))),
+            }
+        } else {
+            match first {
+                "crate" => Res::Def(self.modules[0].def),
+                "self" => Res::Def(self.modules[module].def),
+                "super" => match self.modules[module].parent {
+                    Some(parent) => Res::Def(self.modules[parent].def),
+                    None => return Err(Diagnostic::new(span,
+                        "there are too many leading `super` keywords")),
+                },
+                name => match self.lookup(module, name, namespace) {
+                    Some(res) => res,
+                    None => return Err(Diagnostic::new(span, format!(
+                        "cannot find `{}` in this scope", name))),
+                },
+            }
+        };
+        self.resolve_rest(module, res, segments, ns)
+    }
+
+    // Resolve the segments after the first one.
+    fn resolve_rest(
+        &self,
+        module: ModuleId,
+        mut res: Res,
+        segments: &[(&'a str, Span)],
+        ns: Namespace,
+    ) -> Result<(Res, usize), Diagnostic> {
+        let namespace = |index: usize| match index + 1 == segments.len() {
+            true => ns,
+            false => Namespace::Type,
+        };
+        for (index, &(name, span)) in segments.iter().enumerate().skip(1) {
+            if name == "super" {
+                res = match res {
+                    Res::Def(def) => match self.defs[def].kind {
+                        DefKind::Module(current) => {
+                            match self.modules[current].parent {
+                                Some(parent) => Res::Def(self.modules[parent].def),
+                                None => return Err(Diagnostic::new(span,
+                                    "there are too many leading `super` \
+                                        keywords")),
+                            }
+                        }
+                        _ => return Err(Diagnostic::new(span,
+                            "`super` must follow a module")),
+                    },
+                    _ => return Err(Diagnostic::new(span,
+                        "`super` must follow a module")),
+                };
+                continue;
+            }
+            match self.member(&res, name, namespace(index), module) {
+                Ok(Some(member)) => res = member,
+                Ok(None) => {
+                    if let Some(unknown) = self.unknown_std(&res, name) {
+                        res = unknown;
+                        continue;
+                    }
+                    let scope = &segments[..index];
+                    let scope = scope.iter().map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join("::");
+                    // Names in an enum that aren't variants are left to the
+                    // type checker, as associated items.
+                    let is_module = matches!(res, Res::Def(def)
+                        if matches!(self.defs[def].kind, DefKind::Module(_)));
+                    if is_module {
+                        return Err(Diagnostic::new(span, format!(
+                            "cannot find `{}` in `{}`", name, scope)));
+                    }
+                    return Ok((res, index));
+                }
+                Err(()) => return Err(Diagnostic::new(span,
+                    format!("`{}` is private", name))),
+            }
+        }
+        Ok((res, segments.len()))
+    }
+
+    // Look up `name` in a module or enum, or return `Err` if it's not visible
+    // from `from`.
+    fn member(&self, scope: &Res, name: &'a str, ns: Namespace, from: ModuleId)
+        -> Result<Option<Res>, ()>
+    {
+        match *scope {
+            Res::Def(def) => match self.defs[def].kind {
+                DefKind::Module(module) => {
+                    let module = &self.modules[module];
+                    match module.binding(name, ns) {
+                        Some(binding) if self.is_visible(binding.vis, from) => {
+                            Ok(Some(binding.res.clone()))
+                        }
+                        Some(_) => Err(()),
+                        None => Ok(module.external_globs.first().map(|glob| {
+                            Res::External(format!("{}::{}", glob, name))
+                        })),
+                    }
+                }
+                DefKind::Item(_) if ns != Namespace::Macro => {
+                    Ok(self.variants.get(&(def, name)).map(|v| Res::Def(*v)))
+                }
+                _ => Ok(None),
+            },
+            Res::External(ref path) => {
+                Ok(Some(Res::External(format!("{}::{}", path, name))))
+            }
+            Res::Primitive(_) => Ok(None),
+        }
+    }
+
+    // Only part of the standard library is known, so other names in its
+    // modules are external.
+    fn unknown_std(&self, scope: &Res, name: &str) -> Option<Res> {
+        if !self.unknown_std {
+            return None;
+        }
+        match *scope {
+            Res::Def(def) => match self.defs[def].kind {
+                DefKind::Module(module) if self.is_std(module) => {
+                    Some(Res::External(format!("{}::{}", self.path(scope), name)))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn add_def(&mut self, def: Def<'a>) -> DefId {
+        self.defs.push(def);
+        self.defs.len() - 1
+    }
+
+    // Bind a name in a module, returning whether anything changed.
+    fn bind(
+        &mut self,
+        module: ModuleId,
+        name: &'a str,
+        ns: Namespace,
+        binding: Binding,
+    ) -> bool {
+        let file = self.modules[module].file;
+        let bindings = &mut self.modules[module].bindings;
+        match bindings.get(&(ns, name)) {
+            None => {
+                bindings.insert((ns, name), binding);
+                true
+            }
+            Some(old) if old.glob && !binding.glob => {
+                let changed = old.res != binding.res;
+                bindings.insert((ns, name), binding);
+                changed
+            }
+            Some(old) if !old.glob && !binding.glob && old.res != binding.res => {
+                // Once, though imports are bound again until nothing changes
+                // and a unit struct is in both namespaces
+                let error = FileDiagnostic::new(file, binding.span,
+                    format!("the name `{}` is defined multiple times", name));
+                if !self.errors.contains(&error) {
+                    self.errors.push(error);
+                }
+                false
+            }
+            Some(_) => false,
+        }
+    }
+
+    // Define an item of a module in the given namespaces.
+    fn define(
+        &mut self,
+        module: ModuleId,
+        def: Def<'a>,
+        namespaces: &[Namespace],
+    ) -> DefId {
+        let (name, vis, span) = (def.name, def.vis, def.span);
+        let id = self.add_def(def);
+        let errors = self.errors.len();
+        for ns in namespaces {
+            let binding = Binding { res: Res::Def(id), vis, glob: false, span };
+            self.bind(module, name, *ns, binding);
+            // Report a name defined twice only once.
+            if self.errors.len() != errors {
+                break;
+            }
+        }
+        id
+    }
+
+    /// Get the visibility of a declaration in a module.
+    pub fn vis(&self, module: ModuleId, vis: &Visibility) -> Vis {
+        match vis {
+            Visibility::Public => Vis::Public,
+            Visibility::Private => Vis::Module(module),
+            // `pub(in path)` is treated as `pub(crate)`.
+            Visibility::Restricted(path) => match path.segments[0].name {
+                "self" => Vis::Module(module),
+                "super" => Vis::Module(self.modules[module].parent.unwrap_or(0)),
+                _ => Vis::Module(0),
+            },
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn add_module(
+        &mut self,
+        sources: &'a Sources,
+        name: &'a str,
+        parent: Option<ModuleId>,
+        vis: Vis,
+        span: Span,
+        file: FileId,
+        attrs: Vec<Attribute<'a>>,
+        mut items: Vec<Item<'a>>,
+        imports: &mut Vec<Import<'a>>,
+    ) -> ModuleId {
+        let id = self.modules.len();
+        let def = self.add_def(Def {
+            name,
+            kind: DefKind::Module(id),
+            module: parent.unwrap_or(id),
+            vis,
+            span,
+        });
+        self.modules.push(Module {
+            name,
+            parent,
+            def,
+            file,
+            attrs,
+            items: Vec::new(),
+            bindings: HashMap::new(),
+            external_globs: Vec::new(),
+        });
+        if let Some(parent) = parent {
+            let binding = Binding { res: Res::Def(def), vis, glob: false, span };
+            self.bind(parent, name, Namespace::Type, binding);
+        }
+
+        for (index, item) in items.iter_mut().enumerate() {
+            let vis = self.vis(id, &item.vis);
+            let span = item.span;
+            let new_def = |name, kind| Def { name, kind, module: id, vis, span };
+            let item_def = |name| new_def(name, DefKind::Item(index));
+            match item.kind {
+                ItemKind::Mod(name, ref mut contents) => {
+                    let (file, attrs, items) = match contents.take() {
+                        Some(items) => (file, item.attrs.clone(), items),
+                        None => {
+                            let child = sources.children.get(&(file, span.start));
+                            let child = match child {
+                                Some(child) => *child,
+                                None => {
+                                    self.errors.push(FileDiagnostic::new(file,
+                                        span, format!("file not found for \
+                                            module `{}`", name)));
+                                    continue;
+                                }
+                            };
+                            match parse(sources, child) {
+                                Ok((mut attrs, items)) => {
+                                    attrs.splice(0..0, item.attrs.clone());
+                                    (child, attrs, items)
+                                }
+                                Err(e) => {
+                                    self.errors.push(e);
+                                    continue;
+                                }
+                            }
+                        }
+                    };
+                    self.add_module(sources, name, Some(id), vis, span, file,
+                        attrs, items, imports);
+                }
+                ItemKind::Use(ref tree) => {
+                    flatten_use(tree, id, false, Vec::new(), vis, imports);
+                }
+                ItemKind::ExternCrate(krate, rename) => {
+                    let name = rename.unwrap_or(krate);
+                    if id == 0 {
+                        self.extern_crates.insert(name, krate);
+                    }
+                    let res = match krate {
+                        "self" => Res::Def(self.modules[0].def),
+                        krate => Res::External(krate.to_string()),
+                    };
+                    if name != "_" {
+                        let binding = Binding { res, vis, glob: false, span };
+                        self.bind(id, name, Namespace::Type, binding);
+                    }
+                }
+                ItemKind::Fn(ref function) => {
+                    self.define(id, item_def(function.name), &[Namespace::Value]);
+                }
+                ItemKind::Const(name, ..) | ItemKind::Static(_, name, ..) => {
+                    if name != "_" {
+                        self.define(id, item_def(name), &[Namespace::Value]);
+                    }
+                }
+                ItemKind::Struct(name, _, ref fields) => {
+                    let namespaces: &[Namespace] = match fields {
+                        Fields::Named(_) => &[Namespace::Type],
+                        _ => &[Namespace::Type, Namespace::Value],
+                    };
+                    self.define(id, item_def(name), namespaces);
+                }
+                ItemKind::Enum(name, _, ref variants) => {
+                    let enum_def = self.define(id, item_def(name),
+                        &[Namespace::Type]);
+                    for (i, variant) in variants.iter().enumerate() {
+                        let variant = self.add_def(Def {
+                            name: variant.name,
+                            kind: DefKind::Variant(enum_def, i),
+                            module: id,
+                            vis,
+                            span: variant.span,
+                        });
+                        let name = self.defs[variant].name;
+                        self.variants.insert((enum_def, name), variant);
+                    }
+                }
+                ItemKind::Union(name, ..)
+                | ItemKind::Trait { name, .. }
+                | ItemKind::Type(name, ..) => {
+                    self.define(id, item_def(name), &[Namespace::Type]);
+                }
+                ItemKind::MacroRules(name, _) => {
+                    let exported = item.attrs.iter()
+                        .any(|attr| attr.is("macro_export"));
+                    let def = self.define(id, item_def(name),
+                        &[Namespace::Macro]);
+                    if exported && id != 0 {
+                        let binding = Binding {
+                            res: Res::Def(def),
+                            vis: Vis::Public,
+                            glob: false,
+                            span,
+                        };
+                        self.bind(0, name, Namespace::Macro, binding);
+                    }
+                }
+                ItemKind::ExternBlock(_, ref foreign) => {
+                    for (i, foreign) in foreign.iter().enumerate() {
+                        let name = match foreign.kind {
+                            ItemKind::Fn(ref function) => function.name,
+                            ItemKind::Static(_, name, ..) => name,
+                            _ => continue,
+                        };
+                        let def = Def {
+                            name,
+                            kind: DefKind::Foreign(index, i),
+                            module: id,
+                            vis: self.vis(id, &foreign.vis),
+                            span: foreign.span,
+                        };
+                        self.define(id, def, &[Namespace::Value]);
+                    }
+                }
+                ItemKind::Impl { .. } | ItemKind::MacroCall(_) => {}
+            }
+        }
+        self.modules[id].items = items;
+        id
+    }
+
+    fn resolve_imports(&mut self, imports: Vec<Import<'a>>) {
+        let mut pending: Vec<_> = imports.into_iter().map(|i| (i, None)).collect();
+        loop {
+            let mut progress = false;
+            let mut index = 0;
+            while index < pending.len() {
+                let import = pending[index].0.clone();
+                match self.resolve_import(&import) {
+                    Ok(changed) => {
+                        progress |= changed;
+                        if let ImportKind::Glob = import.kind {
+                            index += 1;
+                        } else {
+                            pending.swap_remove(index);
+                        }
+                    }
+                    Err(e) => {
+                        pending[index].1 = Some(e);
+                        index += 1;
+                    }
+                }
+            }
+            if !progress {
+                if self.unknown_std {
+                    break;
+                }
+                self.unknown_std = true;
+            }
+        }
+        for (import, error) in pending {
+            if let Some(error) = error {
+                let path = import.path.iter().map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join("::");
+                let message = format!("unresolved import `{}{}`: {}",
+                    if import.global { "::" } else { "" },
+                    path, error.message);
+                let file = self.modules[import.module].file;
+                self.errors.push(FileDiagnostic::new(file, error.span, message));
+            }
+        }
+    }
+
+    // Try to resolve an import, returning whether any names changed.
+    fn resolve_import(&mut self, import: &Import<'a>) -> Result<bool, Diagnostic> {
+        let module = import.module;
+        let (name, name_span) = *import.path.last().unwrap();
+        let binding = |res| Binding {
+            res,
+            vis: import.vis,
+            glob: false,
+            span: import.span,
+        };
+
+        if let ImportKind::Glob = import.kind {
+            let (res, _) = self.resolve_full(import, Namespace::Type)?;
+            return Ok(self.glob_import(import, res));
+        }
+
+        // The last segment may name something in any namespace.
+        let prefix = &import.path[..import.path.len() - 1];
+        let found: Vec<(Namespace, Res)> = if prefix.is_empty() && !import.global
+        {
+            let mut found = Vec::new();
+            for ns in NAMESPACES.iter() {
+                if matches!(import.kind, ImportKind::SelfImport(_))
+                    && *ns != Namespace::Type
+                {
+                    continue;
+                }
+                let res = self.resolve_segments(module, false, &import.path, *ns);
+                if let Ok((res, count)) = res {
+                    if count == import.path.len()
+                        && !self.is_import_of(module, name, *ns, import.span)
+                    {
+                        found.push((*ns, res));
+                    }
+                }
+            }
+            found
+        } else if prefix.is_empty() {
+            match self.extern_crate(name) {
+                Some(res) => vec![(Namespace::Type, res)],
+                None => Vec::new(),
+            }
+        } else {
+            let (scope, _) = self.resolve_full_segments(import, prefix)?;
+            let mut found = Vec::new();
+            for ns in NAMESPACES.iter() {
+                if matches!(import.kind, ImportKind::SelfImport(_))
+                    && *ns != Namespace::Type
+                {
+                    continue;
+                }
+                match self.member(&scope, name, *ns, module) {
+                    Ok(Some(res)) => {
+                        // Items of other crates exist in every namespace.
+                        let external = matches!(res, Res::External(_));
+                        found.push((*ns, res));
+                        if external {
+                            break;
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(()) => {
+                        return Err(Diagnostic::new(name_span,
+                            format!("`{}` is private", name)));
+                    }
+                }
+            }
+            if found.is_empty() {
+                found.extend(self.unknown_std(&scope, name)
+                    .map(|res| (Namespace::Type, res)));
+            }
+            found
+        };
+        if found.is_empty() {
+            let scope = prefix.iter().map(|(name, _)| *name)
+                .collect::<Vec<_>>()
+                .join("::");
+            return Err(Diagnostic::new(name_span, match scope.is_empty() {
+                true => format!("no `{}` in scope", name),
+                false => format!("no `{}` in `{}`", name, scope),
+            }));
+        }
+
+        let name = match import.kind {
+            ImportKind::Simple(Some(rename)) | ImportKind::SelfImport(rename) => {
+                rename
+            }
+            // `as _` only brings traits into scope for method calls.
+            _ => return Ok(true),
+        };
+        let mut changed = false;
+        for (ns, res) in found {
+            if let Res::External(_) = res {
+                for ns in NAMESPACES.iter() {
+                    changed |= self.bind(module, name, *ns, binding(res.clone()));
+                }
+            } else {
+                changed |= self.bind(module, name, ns, binding(res));
+            }
+        }
+        Ok(changed)
+    }
+
+    // Whether a binding of `name` in `module` came from the import at `span`,
+    // so `use foo;` doesn't resolve to itself.
+    fn is_import_of(&self, module: ModuleId, name: &'a str, ns: Namespace, span: Span)
+        -> bool
+    {
+        self.modules[module].binding(name, ns)
+            .is_some_and(|binding| binding.span == span)
+    }
+
+    fn resolve_full(&self, import: &Import<'a>, ns: Namespace)
+        -> Result<(Res, usize), Diagnostic>
+    {
+        let res = self.resolve_segments(import.module, import.global,
+            &import.path, ns)?;
+        if res.1 != import.path.len() {
+            let (name, span) = import.path[res.1];
+            return Err(Diagnostic::new(span,
+                format!("`{}` is not a module or enum", name)));
+        }
+        Ok(res)
+    }
+
+    fn resolve_full_segments(&self, import: &Import<'a>, path: &[(&'a str, Span)])
+        -> Result<(Res, usize), Diagnostic>
+    {
+        let prefix = Import {
+            path: path.to_vec(),
+            ..import.clone()
+        };
+        self.resolve_full(&prefix, Namespace::Type)
+    }
+
+    // Import all visible names of a module or enum.
+    fn glob_import(&mut self, import: &Import<'a>, scope: Res) -> bool {
+        let module = import.module;
+        let glob = |res| Binding {
+            res,
+            vis: import.vis,
+            glob: true,
+            span: import.span,
+        };
+        let names: Vec<(&'a str, Namespace, Res)> = match scope {
+            Res::Def(def) => match self.defs[def].kind {
+                DefKind::Module(target) => self.modules[target].bindings.iter()
+                    .filter(|(_, binding)| self.is_visible(binding.vis, module))
+                    .map(|((ns, name), binding)| (*name, *ns, binding.res.clone()))
+                    .collect(),
+                DefKind::Item(_) => self.variants.iter()
+                    .filter(|((parent, _), _)| *parent == def)
+                    .flat_map(|((_, name), variant)| vec![
+                        (*name, Namespace::Type, Res::Def(*variant)),
+                        (*name, Namespace::Value, Res::Def(*variant)),
+                    ])
+                    .collect(),
+                _ => Vec::new(),
+            },
+            Res::External(path) => {
+                let globs = &mut self.modules[module].external_globs;
+                if globs.contains(&path) {
+                    return false;
+                }
+                globs.push(path);
+                return true;
+            }
+            Res::Primitive(_) => Vec::new(),
+        };
+        let mut changed = false;
+        for (name, ns, res) in names {
+            changed |= self.bind(module, name, ns, glob(res));
+        }
+        changed
+    }
+}
+
+// Parse the items and inner attributes of a file.
+fn parse<'a>(sources: &'a Sources, file: FileId)
+    -> Result<(Vec<Attribute<'a>>, Vec<Item<'a>>), FileDiagnostic>
+{
+    let mut iter = ItemIterator::expanded(sources.file(file))
+        .configured(&sources.cfg);
+    let mut items = Vec::new();
+    for item in &mut iter {
+        items.push(item.map_err(|diagnostic| {
+            FileDiagnostic { file, diagnostic }
+        })?);
+    }
+    Ok((iter.attributes().to_vec(), items))
+}
+
+// Turn a use tree into a list of imports.
+fn flatten_use<'a>(
+    tree: &UseTree<'a>,
+    module: ModuleId,
+    global: bool,
+    mut path: Vec<(&'a str, Span)>,
+    vis: Vis,
+    imports: &mut Vec<Import<'a>>,
+) {
+    let global = global || tree.prefix.global;
+    path.extend(tree.prefix.segments.iter().map(|s| (s.name, s.span)));
+    let span = tree.prefix.span;
+    match tree.kind {
+        UseTreeKind::Simple(rename) => {
+            let kind = match path.last() {
+                // `use a::{self}` imports the module `a`
+                Some(&("self", _)) if path.len() > 1 => {
+                    path.pop();
+                    let name = path.last().unwrap().0;
+                    ImportKind::SelfImport(rename.unwrap_or(name))
+                }
+                Some(&(name, _)) => match rename {
+                    Some("_") => ImportKind::Simple(None),
+                    rename => ImportKind::Simple(Some(rename.unwrap_or(name))),
+                },
+                None => return,
+            };
+            imports.push(Import { module, global, path, kind, vis, span });
+        }
+        UseTreeKind::Glob if path.is_empty() => {}
+        UseTreeKind::Glob => {
+            imports.push(Import {
+                module,
+                global,
+                path,
+                kind: ImportKind::Glob,
+                vis,
+                span,
+            });
+        }
+        UseTreeKind::Nested(ref trees) => {
+            for tree in trees {
+                flatten_use(tree, module, global, path.clone(), vis, imports);
+            }
+        }
+    }
+}
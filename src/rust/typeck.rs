@@ -0,0 +1,2089 @@
+// Rust type checking
+//
+//! Type inference and trait resolution.
+//!
+//! Function bodies are checked one at a time, Hindley–Milner style: the types
+//! of expressions start out as inference variables that are unified as the
+//! body is walked, and integer and float literals whose type isn't otherwise
+//! decided fall back to `i32` and `f64`.  Trait bounds become obligations,
+//! solved against the `where` clauses in scope and the impls of the crate and
+//! the built-in standard library once enough is known about their types.
+//! Item signatures are lowered on demand and memoized, so items may refer to
+//! each other in any order.
+
+mod expr;
+mod infer;
+mod method;
+mod pat;
+mod path;
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use super::resolve::{
+    Crate, DefId, DefKind, FileDiagnostic, FileId, ModuleId, Namespace, Res,
+    Vis,
+};
+use super::{
+    BinaryOp, Bound, Expr, ExprKind, Fields, FloatType, Function, GenericArg,
+    GenericParam, Generics, IntType, Item, ItemKind, Lit, Path, PathSegment,
+    SelfParam, Type, TypeKind,
+};
+use crate::Span;
+pub(in crate::rust) use self::expr::{
+    placeholders as format_placeholders, FormatArg,
+};
+
+/// A type, with lifetimes erased
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ty<'a> {
+    Bool,
+    Char,
+    Str,
+    Int(IntType),
+    Float(FloatType),
+    /// `!`
+    Never,
+    /// Tuple (the unit type when empty)
+    Tuple(Vec<Ty<'a>>),
+    /// Array, with its length if it is known
+    Array(Box<Ty<'a>>, Option<u64>),
+    Slice(Box<Ty<'a>>),
+    /// Reference, `true` if mutable
+    Ref(bool, Box<Ty<'a>>),
+    /// Raw pointer, `true` if mutable
+    Ptr(bool, Box<Ty<'a>>),
+    /// Struct, enum or union with its generic arguments
+    Adt(DefId, Vec<Ty<'a>>),
+    /// Function pointer or function item: parameters and return type
+    Fn(Vec<Ty<'a>>, Box<Ty<'a>>),
+    /// Closure, numbered within its function, with its signature
+    Closure(usize, Vec<Ty<'a>>, Box<Ty<'a>>),
+    Dyn(Vec<TraitBound<'a>>),
+    /// `impl Trait` returned from a function
+    Opaque(Vec<TraitBound<'a>>),
+    /// Generic parameter, by index into the parameters of the item (starting
+    /// with those of its impl or trait), and name
+    Param(usize, &'a str),
+    /// Associated type that isn't known, like `T::Item`
+    Projection(Box<Projection<'a>>),
+    /// Inference variable
+    Var(usize),
+    /// Inference variable of an integer literal
+    IntVar(usize),
+    /// Inference variable of a float literal
+    FloatVar(usize),
+    /// `_`, replaced by an inference variable in function bodies
+    Infer,
+    /// Type of something with an error, or of something in an unknown part
+    /// of the standard library
+    Error,
+}
+
+/// A trait with its generic arguments, without the `Self` type
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraitRef<'a> {
+    pub def: DefId,
+    pub args: Vec<Ty<'a>>,
+}
+
+/// A trait bound of a `dyn` or `impl` type, with associated type bindings
+/// like `Item = u8`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraitBound<'a> {
+    pub trait_ref: TraitRef<'a>,
+    pub bindings: Vec<(&'a str, Ty<'a>)>,
+}
+
+/// An associated type of a trait implementation, `<T as Trait>::Name`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projection<'a> {
+    pub self_ty: Ty<'a>,
+    pub trait_ref: TraitRef<'a>,
+    pub name: &'a str,
+}
+
+/// A `where` clause
+#[derive(Debug, Clone, PartialEq)]
+enum Pred<'a> {
+    /// `T: Trait`
+    Trait(Ty<'a>, TraitRef<'a>),
+    /// `<T as Trait>::Name == U`
+    Eq(Projection<'a>, Ty<'a>),
+}
+
+impl<'a> Ty<'a> {
+    /// The unit type `()`.
+    pub fn unit() -> Self {
+        Ty::Tuple(Vec::new())
+    }
+
+    /// Whether this is the unit type.
+    pub fn is_unit(&self) -> bool {
+        matches!(self, Ty::Tuple(tys) if tys.is_empty())
+    }
+
+    /// Rebuild the type, replacing the types for which `f` returns a
+    /// replacement (outermost first).
+    fn fold(&self, f: &mut dyn FnMut(&Ty<'a>) -> Option<Ty<'a>>) -> Ty<'a> {
+        if let Some(ty) = f(self) {
+            return ty;
+        }
+        let fold_all = |tys: &[Ty<'a>], f: &mut dyn FnMut(&Ty<'a>) -> _| {
+            tys.iter().map(|ty| ty.fold(f)).collect()
+        };
+        match self {
+            Ty::Tuple(tys) => Ty::Tuple(fold_all(tys, f)),
+            Ty::Array(ty, len) => Ty::Array(Box::new(ty.fold(f)), *len),
+            Ty::Slice(ty) => Ty::Slice(Box::new(ty.fold(f))),
+            Ty::Ref(mutable, ty) => Ty::Ref(*mutable, Box::new(ty.fold(f))),
+            Ty::Ptr(mutable, ty) => Ty::Ptr(*mutable, Box::new(ty.fold(f))),
+            Ty::Adt(def, args) => Ty::Adt(*def, fold_all(args, f)),
+            Ty::Fn(inputs, output) => {
+                Ty::Fn(fold_all(inputs, f), Box::new(output.fold(f)))
+            }
+            Ty::Closure(id, inputs, output) => {
+                Ty::Closure(*id, fold_all(inputs, f), Box::new(output.fold(f)))
+            }
+            Ty::Dyn(bounds) => {
+                Ty::Dyn(bounds.iter().map(|bound| bound.fold(f)).collect())
+            }
+            Ty::Opaque(bounds) => {
+                Ty::Opaque(bounds.iter().map(|bound| bound.fold(f)).collect())
+            }
+            Ty::Projection(projection) => {
+                Ty::Projection(Box::new(projection.fold(f)))
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Whether `f` is true for the type or any type in it.
+    fn any(&self, f: &mut dyn FnMut(&Ty<'a>) -> bool) -> bool {
+        if f(self) {
+            return true;
+        }
+        let bound_any = |bound: &TraitBound<'a>,
+                         f: &mut dyn FnMut(&Ty<'a>) -> bool|
+        {
+            bound.trait_ref.args.iter().any(|ty| ty.any(f))
+                || bound.bindings.iter().any(|(_, ty)| ty.any(f))
+        };
+        match self {
+            Ty::Tuple(tys) | Ty::Adt(_, tys) => tys.iter().any(|ty| ty.any(f)),
+            Ty::Array(ty, _) | Ty::Slice(ty) | Ty::Ref(_, ty)
+            | Ty::Ptr(_, ty) => ty.any(f),
+            Ty::Fn(inputs, output) | Ty::Closure(_, inputs, output) => {
+                inputs.iter().any(|ty| ty.any(f)) || output.any(f)
+            }
+            Ty::Dyn(bounds) | Ty::Opaque(bounds) => {
+                bounds.iter().any(|bound| bound_any(bound, f))
+            }
+            Ty::Projection(projection) => {
+                projection.self_ty.any(f)
+                    || projection.trait_ref.args.iter().any(|ty| ty.any(f))
+            }
+            _ => false,
+        }
+    }
+
+    /// Substitute generic arguments for generic parameters.
+    fn subst(&self, args: &[Ty<'a>]) -> Ty<'a> {
+        self.fold(&mut |ty| match *ty {
+            Ty::Param(index, _) => args.get(index).cloned(),
+            _ => None,
+        })
+    }
+
+    /// Whether the type contains inference variables.
+    fn has_vars(&self) -> bool {
+        self.any(&mut |ty| {
+            matches!(ty, Ty::Var(_) | Ty::IntVar(_) | Ty::FloatVar(_))
+        })
+    }
+
+    /// Get the name of the type as it would be written in Rust.
+    pub fn name(&self, krate: &Crate<'a>) -> String {
+        let mut out = String::new();
+        write_ty(&mut out, self, krate);
+        out
+    }
+}
+
+impl<'a> TraitRef<'a> {
+    fn fold(&self, f: &mut dyn FnMut(&Ty<'a>) -> Option<Ty<'a>>) -> Self {
+        TraitRef {
+            def: self.def,
+            args: self.args.iter().map(|ty| ty.fold(f)).collect(),
+        }
+    }
+
+    fn subst(&self, args: &[Ty<'a>]) -> Self {
+        self.fold(&mut |ty| match *ty {
+            Ty::Param(index, _) => args.get(index).cloned(),
+            _ => None,
+        })
+    }
+}
+
+impl<'a> TraitBound<'a> {
+    fn fold(&self, f: &mut dyn FnMut(&Ty<'a>) -> Option<Ty<'a>>) -> Self {
+        TraitBound {
+            trait_ref: self.trait_ref.fold(f),
+            bindings: self.bindings.iter()
+                .map(|(name, ty)| (*name, ty.fold(f)))
+                .collect(),
+        }
+    }
+}
+
+impl<'a> Projection<'a> {
+    fn fold(&self, f: &mut dyn FnMut(&Ty<'a>) -> Option<Ty<'a>>) -> Self {
+        Projection {
+            self_ty: self.self_ty.fold(f),
+            trait_ref: self.trait_ref.fold(f),
+            name: self.name,
+        }
+    }
+}
+
+impl<'a> Pred<'a> {
+    fn fold(&self, f: &mut dyn FnMut(&Ty<'a>) -> Option<Ty<'a>>) -> Self {
+        match self {
+            Pred::Trait(ty, trait_ref) => {
+                Pred::Trait(ty.fold(f), trait_ref.fold(f))
+            }
+            Pred::Eq(projection, ty) => Pred::Eq(projection.fold(f), ty.fold(f)),
+        }
+    }
+
+    fn subst(&self, args: &[Ty<'a>]) -> Self {
+        self.fold(&mut |ty| match *ty {
+            Ty::Param(index, _) => args.get(index).cloned(),
+            _ => None,
+        })
+    }
+}
+
+fn write_list<'a>(out: &mut String, tys: &[Ty<'a>], krate: &Crate<'a>) {
+    for (i, ty) in tys.iter().enumerate() {
+        if i != 0 {
+            out.push_str(", ");
+        }
+        write_ty(out, ty, krate);
+    }
+}
+
+fn write_trait<'a>(
+    out: &mut String,
+    trait_ref: &TraitRef<'a>,
+    bindings: &[(&'a str, Ty<'a>)],
+    krate: &Crate<'a>,
+) {
+    out.push_str(krate.defs[trait_ref.def].name);
+    if trait_ref.args.is_empty() && bindings.is_empty() {
+        return;
+    }
+    out.push('<');
+    write_list(out, &trait_ref.args, krate);
+    for (i, (name, ty)) in bindings.iter().enumerate() {
+        if i != 0 || !trait_ref.args.is_empty() {
+            out.push_str(", ");
+        }
+        out.push_str(name);
+        out.push_str(" = ");
+        write_ty(out, ty, krate);
+    }
+    out.push('>');
+}
+
+fn write_bounds<'a>(
+    out: &mut String,
+    bounds: &[TraitBound<'a>],
+    krate: &Crate<'a>,
+) {
+    for (i, bound) in bounds.iter().enumerate() {
+        if i != 0 {
+            out.push_str(" + ");
+        }
+        write_trait(out, &bound.trait_ref, &bound.bindings, krate);
+    }
+}
+
+fn write_ty<'a>(out: &mut String, ty: &Ty<'a>, krate: &Crate<'a>) {
+    match ty {
+        Ty::Bool => out.push_str("bool"),
+        Ty::Char => out.push_str("char"),
+        Ty::Str => out.push_str("str"),
+        Ty::Int(int) => out.push_str(int.as_str()),
+        Ty::Float(float) => out.push_str(float.as_str()),
+        Ty::Never => out.push('!'),
+        Ty::Tuple(tys) => {
+            out.push('(');
+            write_list(out, tys, krate);
+            if tys.len() == 1 {
+                out.push(',');
+            }
+            out.push(')');
+        }
+        Ty::Array(ty, len) => {
+            out.push('[');
+            write_ty(out, ty, krate);
+            match len {
+                Some(len) => out.push_str(&format!("; {}]", len)),
+                None => out.push_str("; _]"),
+            }
+        }
+        Ty::Slice(ty) => {
+            out.push('[');
+            write_ty(out, ty, krate);
+            out.push(']');
+        }
+        Ty::Ref(mutable, ty) => {
+            out.push_str(if *mutable { "&mut " } else { "&" });
+            write_ty(out, ty, krate);
+        }
+        Ty::Ptr(mutable, ty) => {
+            out.push_str(if *mutable { "*mut " } else { "*const " });
+            write_ty(out, ty, krate);
+        }
+        Ty::Adt(def, args) => {
+            out.push_str(krate.defs[*def].name);
+            if !args.is_empty() {
+                out.push('<');
+                write_list(out, args, krate);
+                out.push('>');
+            }
+        }
+        Ty::Fn(inputs, output) => {
+            out.push_str("fn(");
+            write_list(out, inputs, krate);
+            out.push(')');
+            if !output.is_unit() {
+                out.push_str(" -> ");
+                write_ty(out, output, krate);
+            }
+        }
+        Ty::Closure(..) => out.push_str("{closure}"),
+        Ty::Dyn(bounds) => {
+            out.push_str("dyn ");
+            write_bounds(out, bounds, krate);
+        }
+        Ty::Opaque(bounds) => {
+            out.push_str("impl ");
+            write_bounds(out, bounds, krate);
+        }
+        Ty::Param(_, name) => out.push_str(name),
+        Ty::Projection(projection) => {
+            out.push('<');
+            write_ty(out, &projection.self_ty, krate);
+            out.push_str(" as ");
+            write_trait(out, &projection.trait_ref, &[], krate);
+            out.push_str(">::");
+            out.push_str(projection.name);
+        }
+        Ty::Var(_) | Ty::Infer => out.push('_'),
+        Ty::IntVar(_) => out.push_str("{integer}"),
+        Ty::FloatVar(_) => out.push_str("{float}"),
+        Ty::Error => out.push_str("{unknown}"),
+    }
+}
+
+/// An item with generics, a type or a signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ItemId {
+    /// Item with a name in a module
+    Def(DefId),
+    /// Impl, by module and index into the module's items
+    Impl(ModuleId, usize),
+    /// Item of an impl, by index into the impl's items
+    ImplItem(ModuleId, usize, usize),
+    /// Item of a trait, by index into the trait's items
+    TraitItem(DefId, usize),
+}
+
+/// The method called by a method call expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Method<'a> {
+    /// The function in an impl or trait
+    pub callee: ItemId,
+    /// Generic arguments of the function, starting with those of its impl or
+    /// trait
+    pub args: Vec<Ty<'a>>,
+    /// Number of times the receiver is dereferenced
+    pub derefs: usize,
+    /// Reference taken of the dereferenced receiver, `Some(true)` if
+    /// mutable
+    pub autoref: Option<bool>,
+}
+
+/// Types of the expressions and variables of a crate, by file and span
+#[derive(Debug, Default)]
+pub struct Types<'a> {
+    pub exprs: HashMap<(FileId, Span), Ty<'a>>,
+    /// Type of each variable, by the span of its pattern
+    pub bindings: HashMap<(FileId, Span), Ty<'a>>,
+    /// Type of the value each pattern matches, before it sees through
+    /// references
+    pub patterns: HashMap<(FileId, Span), Ty<'a>>,
+    /// Method of each method call expression
+    pub methods: HashMap<(FileId, Span), Method<'a>>,
+    /// Function each path expression names, like `f` or `Vec::new`
+    pub callees: HashMap<(FileId, Span), ItemId>,
+    /// Expressions and variables whose type isn't `Copy`, so using them by
+    /// value moves them
+    pub moves: HashSet<(FileId, Span)>,
+}
+
+/// Type check a crate.
+pub fn check<'a>(krate: &Crate<'a>) -> Result<Types<'a>, Vec<FileDiagnostic>> {
+    let cx = Context::new(krate);
+    let mut types = Types::default();
+
+    for (module_id, module) in krate.modules.iter().enumerate() {
+        if krate.is_std(module_id) {
+            continue;
+        }
+        for (index, item) in module.items.iter().enumerate() {
+            let def = cx.item_defs.get(&(module_id, index)).copied();
+            match (def, &item.kind) {
+                (Some(def), ItemKind::Fn(ref function)) => {
+                    cx.check_fn(ItemId::Def(def), function, &mut types);
+                }
+                (Some(def), ItemKind::Const(_, _, Some(ref value)))
+                | (Some(def), ItemKind::Static(_, _, _, Some(ref value))) => {
+                    cx.check_const(ItemId::Def(def), value, &mut types);
+                }
+                (Some(def), ItemKind::Struct(..))
+                | (Some(def), ItemKind::Enum(..))
+                | (Some(def), ItemKind::Union(..)) => {
+                    cx.adt(def);
+                    cx.predicates_of(ItemId::Def(def));
+                }
+                (Some(def), ItemKind::Type(..)) => {
+                    cx.alias_ty(def);
+                }
+                (Some(def), ItemKind::Trait { ref items, .. }) => {
+                    cx.predicates_of(ItemId::Def(def));
+                    for (i, item) in items.iter().enumerate() {
+                        let id = ItemId::TraitItem(def, i);
+                        match item.kind {
+                            ItemKind::Fn(ref function) => {
+                                cx.check_fn(id, function, &mut types);
+                            }
+                            ItemKind::Const(_, _, Some(ref value)) => {
+                                cx.check_const(id, value, &mut types);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                (_, ItemKind::Impl { ref items, .. }) => {
+                    cx.check_impl(module_id, index);
+                    for (i, item) in items.iter().enumerate() {
+                        let id = ItemId::ImplItem(module_id, index, i);
+                        match item.kind {
+                            ItemKind::Fn(ref function) => {
+                                cx.check_fn(id, function, &mut types);
+                            }
+                            ItemKind::Const(_, _, Some(ref value)) => {
+                                cx.check_const(id, value, &mut types);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                (_, ItemKind::ExternBlock(_, ref items)) => {
+                    for (i, item) in items.iter().enumerate() {
+                        let def = krate.defs.iter().position(|def| {
+                            def.module == module_id
+                                && def.kind == DefKind::Foreign(index, i)
+                        });
+                        match (def, &item.kind) {
+                            (Some(def), ItemKind::Fn(_)) => {
+                                cx.fn_sig(ItemId::Def(def));
+                            }
+                            (Some(def), ItemKind::Static(..)) => {
+                                cx.const_ty(ItemId::Def(def));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut errors = cx.errors.into_inner();
+    let mut seen = Vec::new();
+    errors.retain(|error| {
+        let key = (error.file, error.diagnostic.span,
+            error.diagnostic.message.clone());
+        let new = !seen.contains(&key);
+        seen.push(key);
+        new
+    });
+    errors.sort_by_key(|error| (error.file, error.diagnostic.span.start));
+    match errors.is_empty() {
+        true => Ok(types),
+        false => Err(errors),
+    }
+}
+
+/// Generic parameters of an item, starting with those of its impl or trait
+#[derive(Debug, Clone, Default)]
+struct GenericsDef<'a> {
+    names: Vec<&'a str>,
+    /// Whether each parameter is a const parameter
+    consts: Vec<bool>,
+    /// Number of parameters of the impl or trait
+    parent: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AdtKind {
+    Struct,
+    Enum,
+    Union,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CtorKind {
+    Unit,
+    Tuple,
+    Named,
+}
+
+#[derive(Debug)]
+struct FieldDef<'a> {
+    /// `None` for tuple fields
+    name: Option<&'a str>,
+    ty: Ty<'a>,
+    vis: Vis,
+}
+
+#[derive(Debug)]
+struct VariantDef<'a> {
+    name: &'a str,
+    ctor: CtorKind,
+    fields: Vec<FieldDef<'a>>,
+}
+
+impl<'a> VariantDef<'a> {
+    // Find a field by name, or by index for tuple fields.
+    fn field(&self, name: &str) -> Option<usize> {
+        match self.ctor {
+            CtorKind::Named => {
+                self.fields.iter().position(|field| field.name == Some(name))
+            }
+            _ => name.parse().ok().filter(|index| *index < self.fields.len()),
+        }
+    }
+}
+
+/// A struct, enum or union with its generic parameters as [`Ty::Param`]
+#[derive(Debug)]
+struct AdtDef<'a> {
+    kind: AdtKind,
+    variants: Vec<VariantDef<'a>>,
+}
+
+/// A function signature, with `self` as the first input
+#[derive(Debug, Clone)]
+struct FnSig<'a> {
+    inputs: Vec<Ty<'a>>,
+    output: Ty<'a>,
+    has_self: bool,
+    variadic: bool,
+}
+
+/// What a trait impl is for
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ImplOf {
+    Inherent,
+    Trait(DefId),
+    /// A trait outside the known part of the standard library
+    Unknown,
+}
+
+struct ImplInfo<'k, 'a> {
+    module: ModuleId,
+    index: usize,
+    item: &'k Item<'a>,
+    of: ImplOf,
+}
+
+#[derive(Debug)]
+struct ImplHeader<'a> {
+    self_ty: Ty<'a>,
+    trait_ref: Option<TraitRef<'a>>,
+    negative: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AssocKind {
+    Fn,
+    Type,
+    Const,
+}
+
+// The kind and name of an item of a trait or impl.
+fn assoc_kind<'a>(item: &Item<'a>) -> Option<(AssocKind, &'a str)> {
+    match item.kind {
+        ItemKind::Fn(ref function) => Some((AssocKind::Fn, function.name)),
+        ItemKind::Type(name, ..) => Some((AssocKind::Type, name)),
+        ItemKind::Const(name, ..) => Some((AssocKind::Const, name)),
+        _ => None,
+    }
+}
+
+// The outermost type constructor of a type, which impls are looked up by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Head {
+    Bool,
+    Char,
+    Str,
+    Int(IntType),
+    Float(FloatType),
+    Never,
+    Tuple(usize),
+    Array,
+    Slice,
+    Ref(bool),
+    Ptr(bool),
+    Adt(DefId),
+    Fn,
+    Closure,
+    Dyn(DefId),
+}
+
+fn head(ty: &Ty) -> Option<Head> {
+    Some(match *ty {
+        Ty::Bool => Head::Bool,
+        Ty::Char => Head::Char,
+        Ty::Str => Head::Str,
+        Ty::Int(int) => Head::Int(int),
+        Ty::Float(float) => Head::Float(float),
+        Ty::Never => Head::Never,
+        Ty::Tuple(ref tys) => Head::Tuple(tys.len()),
+        Ty::Array(..) => Head::Array,
+        Ty::Slice(_) => Head::Slice,
+        Ty::Ref(mutable, _) => Head::Ref(mutable),
+        Ty::Ptr(mutable, _) => Head::Ptr(mutable),
+        Ty::Adt(def, _) => Head::Adt(def),
+        Ty::Fn(..) => Head::Fn,
+        Ty::Closure(..) => Head::Closure,
+        Ty::Dyn(ref bounds) => Head::Dyn(bounds.first()?.trait_ref.def),
+        _ => return None,
+    })
+}
+
+// Whether an impl for a type with head `impl_head` could apply to `ty`.
+fn may_apply(impl_head: Option<Head>, ty: &Ty) -> bool {
+    match (impl_head, ty) {
+        (None, _) => true,
+        (_, Ty::Var(_)) | (_, Ty::Error) | (_, Ty::Infer) => true,
+        (Some(Head::Int(_)), Ty::IntVar(_)) => true,
+        (Some(Head::Float(_)), Ty::FloatVar(_)) => true,
+        (Some(impl_head), ty) => head(ty) == Some(impl_head),
+    }
+}
+
+/// What `impl Trait` means where a type is lowered
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ImplTraitMode {
+    /// An anonymous generic parameter
+    Param,
+    /// An opaque return type
+    Opaque,
+    Forbidden,
+}
+
+/// The names in scope when lowering types
+#[derive(Clone)]
+struct Scope<'k, 'a> {
+    module: ModuleId,
+    /// Generic parameters, with the bounds declared on them
+    params: Vec<(&'a str, Vec<&'k Bound<'a>>)>,
+    self_ty: Option<Ty<'a>>,
+    trait_: Option<DefId>,
+    /// Index of the impl the scope is in
+    impl_: Option<usize>,
+    /// Names imported by `use` in the function body being checked
+    uses: Vec<(&'a str, Namespace, Res)>,
+    /// Whether types may be left for inference
+    in_body: bool,
+    impl_trait: Cell<ImplTraitMode>,
+    /// Index of the next anonymous `impl Trait` parameter
+    apit: Cell<usize>,
+}
+
+impl<'k, 'a> Scope<'k, 'a> {
+    fn new(module: ModuleId) -> Self {
+        Scope {
+            module,
+            params: Vec::new(),
+            self_ty: None,
+            trait_: None,
+            impl_: None,
+            uses: Vec::new(),
+            in_body: false,
+            impl_trait: Cell::new(ImplTraitMode::Forbidden),
+            apit: Cell::new(0),
+        }
+    }
+
+    fn param(&self, name: &str) -> Option<usize> {
+        self.params.iter().rposition(|(param, _)| *param == name)
+    }
+
+    // Add the generic parameters of an item with their bounds.
+    fn add_params(&mut self, generics: &'k Generics<'a>) {
+        for param in &generics.params {
+            match *param {
+                GenericParam::Type { name, ref bounds, .. } => {
+                    self.params.push((name, bounds.iter().collect()));
+                }
+                GenericParam::Const { name, .. } => {
+                    self.params.push((name, Vec::new()));
+                }
+            }
+        }
+        for predicate in &generics.predicates {
+            if let Some(name) = param_name(&predicate.ty) {
+                if let Some(index) = self.param(name) {
+                    self.params[index].1.extend(predicate.bounds.iter());
+                }
+            }
+        }
+    }
+}
+
+// The name of a type that is a single identifier, which may be a generic
+// parameter.
+fn param_name<'a>(ty: &Type<'a>) -> Option<&'a str> {
+    match ty.kind {
+        TypeKind::Path(ref path)
+            if !path.global
+                && path.segments.len() == 1
+                && path.segments[0].generics.is_empty() =>
+        {
+            Some(path.segments[0].name)
+        }
+        _ => None,
+    }
+}
+
+// The generics declared by an item.
+fn generics_ast<'k, 'a>(item: &'k Item<'a>) -> Option<&'k Generics<'a>> {
+    match item.kind {
+        ItemKind::Fn(ref function) => Some(&function.generics),
+        ItemKind::Struct(_, ref generics, _)
+        | ItemKind::Enum(_, ref generics, _)
+        | ItemKind::Union(_, ref generics, _)
+        | ItemKind::Type(_, ref generics, ..)
+        | ItemKind::Trait { ref generics, .. }
+        | ItemKind::Impl { ref generics, .. } => Some(generics),
+        _ => None,
+    }
+}
+
+// The bounds of `impl Trait` parameter types of a function, which are
+// anonymous generic parameters after the declared ones.
+fn impl_trait_params<'k, 'a>(function: &'k Function<'a>)
+    -> Vec<&'k [Bound<'a>]>
+{
+    fn walk<'k, 'a>(ty: &'k Type<'a>, out: &mut Vec<&'k [Bound<'a>]>) {
+        match ty.kind {
+            TypeKind::ImplTrait(ref bounds) => out.push(bounds),
+            TypeKind::Ref(_, ref ty)
+            | TypeKind::Ptr(_, ref ty)
+            | TypeKind::Slice(ref ty)
+            | TypeKind::Array(ref ty, _) => walk(ty, out),
+            TypeKind::Tuple(ref tys) => {
+                for ty in tys {
+                    walk(ty, out);
+                }
+            }
+            TypeKind::Path(ref path) => {
+                for segment in &path.segments {
+                    for arg in &segment.generics {
+                        if let GenericArg::Type(ref ty) = *arg {
+                            walk(ty, out);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    for param in &function.params {
+        walk(&param.ty, &mut out);
+    }
+    out
+}
+
+fn primitive<'a>(name: &str) -> Ty<'a> {
+    match name {
+        "bool" => Ty::Bool,
+        "char" => Ty::Char,
+        "str" => Ty::Str,
+        "f32" => Ty::Float(FloatType::F32),
+        "f64" => Ty::Float(FloatType::F64),
+        name => IntType::from_name(name).map(Ty::Int).unwrap_or(Ty::Error),
+    }
+}
+
+#[derive(Default)]
+struct Memo<'a> {
+    generics: HashMap<ItemId, Rc<GenericsDef<'a>>>,
+    predicates: HashMap<ItemId, Rc<Vec<Pred<'a>>>>,
+    defaults: HashMap<ItemId, Rc<Vec<Option<Ty<'a>>>>>,
+    supertraits: HashMap<DefId, Rc<Vec<TraitBound<'a>>>>,
+    adts: HashMap<DefId, Rc<AdtDef<'a>>>,
+    sigs: HashMap<ItemId, Rc<FnSig<'a>>>,
+    impl_headers: HashMap<usize, Rc<ImplHeader<'a>>>,
+    /// Types of aliases, constants and associated types; `None` while
+    /// being lowered
+    types: HashMap<ItemId, Option<Ty<'a>>>,
+    lang: HashMap<&'static str, Option<DefId>>,
+}
+
+/// Signatures and impls of a crate, lowered on demand
+struct Context<'k, 'a> {
+    krate: &'k Crate<'a>,
+    impls: Vec<ImplInfo<'k, 'a>>,
+    /// Impls by module and index
+    impl_ids: HashMap<(ModuleId, usize), usize>,
+    trait_impls: HashMap<DefId, Vec<usize>>,
+    inherent_impls: Vec<usize>,
+    /// Definitions of module items by module and index
+    item_defs: HashMap<(ModuleId, usize), DefId>,
+    memo: RefCell<Memo<'a>>,
+    errors: RefCell<Vec<FileDiagnostic>>,
+}
+
+impl<'k, 'a> Context<'k, 'a> {
+    fn new(krate: &'k Crate<'a>) -> Self {
+        let mut cx = Context {
+            krate,
+            impls: Vec::new(),
+            impl_ids: HashMap::new(),
+            trait_impls: HashMap::new(),
+            inherent_impls: Vec::new(),
+            item_defs: HashMap::new(),
+            memo: RefCell::new(Memo::default()),
+            errors: RefCell::new(Vec::new()),
+        };
+        for (def_id, def) in krate.defs.iter().enumerate() {
+            if let DefKind::Item(index) = def.kind {
+                cx.item_defs.insert((def.module, index), def_id);
+            }
+        }
+
+        for (module_id, module) in krate.modules.iter().enumerate() {
+            for (index, item) in module.items.iter().enumerate() {
+                let trait_ = match item.kind {
+                    ItemKind::Impl { ref trait_, .. } => trait_,
+                    _ => continue,
+                };
+                let of = match trait_ {
+                    None => ImplOf::Inherent,
+                    Some(path) => cx.resolve_trait(module_id, path),
+                };
+                let id = cx.impls.len();
+                match of {
+                    ImplOf::Inherent => cx.inherent_impls.push(id),
+                    ImplOf::Trait(def) => {
+                        cx.trait_impls.entry(def).or_default().push(id);
+                    }
+                    ImplOf::Unknown => {}
+                }
+                cx.impl_ids.insert((module_id, index), id);
+                cx.impls.push(ImplInfo {
+                    module: module_id,
+                    index,
+                    item,
+                    of,
+                });
+            }
+        }
+        cx
+    }
+
+    fn error<T: Into<String>>(&self, module: ModuleId, span: Span, message: T) {
+        let file = self.krate.modules[module].file;
+        self.errors.borrow_mut().push(FileDiagnostic::new(file, span, message));
+    }
+
+    // Resolve the trait of an impl.
+    fn resolve_trait(&self, module: ModuleId, path: &Path<'a>) -> ImplOf {
+        match self.krate.resolve_path(module, path, Namespace::Type) {
+            Ok((Res::Def(def), len)) if len == path.segments.len()
+                && self.is_trait(def) => ImplOf::Trait(def),
+            Ok((Res::External(_), _)) => ImplOf::Unknown,
+            Ok(_) => {
+                self.error(module, path.span,
+                    format!("expected trait, found `{}`", path));
+                ImplOf::Unknown
+            }
+            Err(error) => {
+                self.errors.borrow_mut().push(error);
+                ImplOf::Unknown
+            }
+        }
+    }
+
+    fn is_trait(&self, def: DefId) -> bool {
+        matches!(self.krate.item(def).map(|item| &item.kind),
+            Some(ItemKind::Trait { .. }))
+    }
+
+    fn is_auto_trait(&self, def: DefId) -> bool {
+        matches!(self.krate.item(def).map(|item| &item.kind),
+            Some(ItemKind::Trait { is_auto: true, .. }))
+    }
+
+    /// The trait or type of the standard library at a path like
+    /// `ops::Add`.
+    fn lang(&self, path: &'static str) -> Option<DefId> {
+        if let Some(def) = self.memo.borrow().lang.get(path) {
+            return *def;
+        }
+        let mut module = self.krate.std();
+        let mut def = None;
+        for name in path.split("::") {
+            let binding = self.krate.modules[module]
+                .binding(name, Namespace::Type);
+            def = match binding.map(|binding| &binding.res) {
+                Some(Res::Def(found)) => Some(*found),
+                _ => None,
+            };
+            if let Some(DefKind::Module(inner)) =
+                def.map(|def| &self.krate.defs[def].kind)
+            {
+                module = *inner;
+            }
+        }
+        self.memo.borrow_mut().lang.insert(path, def);
+        def
+    }
+
+    fn is_lang(&self, def: DefId, path: &'static str) -> bool {
+        self.lang(path) == Some(def)
+    }
+
+    // Get the AST of an item and the module it is in.
+    fn ast(&self, id: ItemId) -> (&'k Item<'a>, ModuleId) {
+        let krate = self.krate;
+        match id {
+            ItemId::Def(def) => {
+                (krate.item(def).expect("not an item"), krate.defs[def].module)
+            }
+            ItemId::Impl(module, index) => {
+                (&krate.modules[module].items[index], module)
+            }
+            ItemId::ImplItem(module, index, i) => {
+                match krate.modules[module].items[index].kind {
+                    ItemKind::Impl { ref items, .. } => (&items[i], module),
+                    _ => unreachable!(),
+                }
+            }
+            ItemId::TraitItem(def, i) => match krate.item(def).map(|i| &i.kind) {
+                Some(ItemKind::Trait { items, .. }) => {
+                    (&items[i], krate.defs[def].module)
+                }
+                _ => unreachable!(),
+            },
+        }
+    }
+
+    fn parent(&self, id: ItemId) -> Option<ItemId> {
+        match id {
+            ItemId::ImplItem(module, index, _) => {
+                Some(ItemId::Impl(module, index))
+            }
+            ItemId::TraitItem(def, _) => Some(ItemId::Def(def)),
+            _ => None,
+        }
+    }
+
+    fn impl_id(&self, id: ItemId) -> Option<usize> {
+        match id {
+            ItemId::Impl(module, index) | ItemId::ImplItem(module, index, _) => {
+                self.impl_ids.get(&(module, index)).copied()
+            }
+            _ => None,
+        }
+    }
+
+    fn generics_of(&self, id: ItemId) -> Rc<GenericsDef<'a>> {
+        if let Some(generics) = self.memo.borrow().generics.get(&id) {
+            return generics.clone();
+        }
+        let mut generics = match self.parent(id) {
+            Some(parent) => (*self.generics_of(parent)).clone(),
+            None => GenericsDef::default(),
+        };
+        generics.parent = generics.names.len();
+        let (item, _) = self.ast(id);
+        if let ItemKind::Trait { .. } = item.kind {
+            generics.names.push("Self");
+            generics.consts.push(false);
+        }
+        if let Some(ast) = generics_ast(item) {
+            for param in &ast.params {
+                let (name, is_const) = match *param {
+                    GenericParam::Type { name, .. } => (name, false),
+                    GenericParam::Const { name, .. } => (name, true),
+                };
+                generics.names.push(name);
+                generics.consts.push(is_const);
+            }
+        }
+        if let ItemKind::Fn(ref function) = item.kind {
+            for _ in impl_trait_params(function) {
+                generics.names.push("impl Trait");
+                generics.consts.push(false);
+            }
+        }
+        let generics = Rc::new(generics);
+        self.memo.borrow_mut().generics.insert(id, generics.clone());
+        generics
+    }
+
+    // The scope of an item, without the `Self` type of impls.
+    fn bare_scope(&self, id: ItemId) -> Scope<'k, 'a> {
+        let (item, module) = self.ast(id);
+        let mut scope = match self.parent(id) {
+            Some(parent) => self.bare_scope(parent),
+            None => Scope::new(module),
+        };
+        match (id, &item.kind) {
+            (ItemId::Def(def), ItemKind::Trait { .. }) => {
+                scope.params.push(("Self", Vec::new()));
+                scope.self_ty = Some(Ty::Param(0, "Self"));
+                scope.trait_ = Some(def);
+            }
+            (ItemId::Impl(..), _) => scope.impl_ = self.impl_id(id),
+            _ => {}
+        }
+        if let Some(generics) = generics_ast(item) {
+            scope.add_params(generics);
+        }
+        if let ItemKind::Fn(ref function) = item.kind {
+            for bounds in impl_trait_params(function) {
+                scope.params.push(("impl Trait", bounds.iter().collect()));
+            }
+        }
+        if let (ItemId::Def(def), ItemKind::Struct(..))
+        | (ItemId::Def(def), ItemKind::Enum(..))
+        | (ItemId::Def(def), ItemKind::Union(..)) = (id, &item.kind)
+        {
+            let params = scope.params.iter().enumerate()
+                .map(|(index, (name, _))| Ty::Param(index, name))
+                .collect();
+            scope.self_ty = Some(Ty::Adt(def, params));
+        }
+        scope
+    }
+
+    /// The scope for lowering the types of an item.
+    fn scope_of(&self, id: ItemId) -> Scope<'k, 'a> {
+        let mut scope = self.bare_scope(id);
+        if let Some(impl_id) = scope.impl_ {
+            scope.self_ty = Some(self.impl_header(impl_id).self_ty.clone());
+        }
+        scope
+    }
+
+    // The generic parameters of a scope as types.
+    fn identity(&self, generics: &GenericsDef<'a>) -> Vec<Ty<'a>> {
+        generics.names.iter().enumerate()
+            .map(|(index, name)| Ty::Param(index, name))
+            .collect()
+    }
+
+    fn predicates_of(&self, id: ItemId) -> Rc<Vec<Pred<'a>>> {
+        if let Some(predicates) = self.memo.borrow().predicates.get(&id) {
+            return predicates.clone();
+        }
+        let mut predicates = match self.parent(id) {
+            Some(parent) => (*self.predicates_of(parent)).clone(),
+            None => Vec::new(),
+        };
+        let generics = self.generics_of(id);
+        let scope = self.scope_of(id);
+        let (item, _) = self.ast(id);
+        let mut first = generics.parent;
+        let is_trait = matches!(item.kind, ItemKind::Trait { .. });
+        if let (ItemId::Def(def), true) = (id, is_trait) {
+            let self_ty = Ty::Param(0, "Self");
+            let args = self.identity(&generics)[1..].to_vec();
+            predicates.push(Pred::Trait(self_ty.clone(), TraitRef { def, args }));
+            for bound in self.supertraits_of(def).iter() {
+                self.bound_predicates(&self_ty, bound, &mut predicates);
+            }
+            first += 1;
+        }
+        if let Some(ast) = generics_ast(item) {
+            for (i, param) in ast.params.iter().enumerate() {
+                if let GenericParam::Type { name, ref bounds, .. } = *param {
+                    let ty = Ty::Param(first + i, name);
+                    for bound in bounds {
+                        self.lower_bound_predicates(&scope, &ty, bound,
+                            &mut predicates);
+                    }
+                }
+            }
+            for predicate in &ast.predicates {
+                if is_trait && param_name(&predicate.ty) == Some("Self") {
+                    continue;
+                }
+                let ty = self.lower(&scope, &predicate.ty);
+                for bound in &predicate.bounds {
+                    self.lower_bound_predicates(&scope, &ty, bound,
+                        &mut predicates);
+                }
+            }
+        }
+        if let ItemKind::Fn(ref function) = item.kind {
+            let params = impl_trait_params(function);
+            let first = generics.names.len() - params.len();
+            for (i, bounds) in params.into_iter().enumerate() {
+                let ty = Ty::Param(first + i, "impl Trait");
+                for bound in bounds {
+                    self.lower_bound_predicates(&scope, &ty, bound,
+                        &mut predicates);
+                }
+            }
+        }
+        let predicates = Rc::new(predicates);
+        self.memo.borrow_mut().predicates.insert(id, predicates.clone());
+        predicates
+    }
+
+    // The predicates of a lowered trait bound on `ty`.
+    fn bound_predicates(
+        &self,
+        ty: &Ty<'a>,
+        bound: &TraitBound<'a>,
+        predicates: &mut Vec<Pred<'a>>,
+    ) {
+        predicates.push(Pred::Trait(ty.clone(), bound.trait_ref.clone()));
+        for (name, bound_ty) in &bound.bindings {
+            let trait_ref = self.assoc_owner(ty, &bound.trait_ref, name)
+                .unwrap_or_else(|| bound.trait_ref.clone());
+            let projection = Projection { self_ty: ty.clone(), trait_ref, name };
+            predicates.push(Pred::Eq(projection, bound_ty.clone()));
+        }
+    }
+
+    // Lower a bound on `ty` to predicates, including bounds on associated
+    // types like `Iterator<Item: Debug>`.
+    fn lower_bound_predicates(
+        &self,
+        scope: &Scope<'k, 'a>,
+        ty: &Ty<'a>,
+        bound: &'k Bound<'a>,
+        predicates: &mut Vec<Pred<'a>>,
+    ) {
+        let lowered = match self.lower_bound(scope, ty, bound) {
+            Some(lowered) => lowered,
+            None => return,
+        };
+        self.bound_predicates(ty, &lowered, predicates);
+        let segment = bound.path.segments.last().unwrap();
+        for arg in &segment.generics {
+            if let GenericArg::Constraint(name, ref bounds) = *arg {
+                let trait_ref = self.assoc_owner(ty, &lowered.trait_ref, name);
+                if let Some(trait_ref) = trait_ref {
+                    let projection = Ty::Projection(Box::new(Projection {
+                        self_ty: ty.clone(),
+                        trait_ref,
+                        name,
+                    }));
+                    for bound in bounds {
+                        self.lower_bound_predicates(scope, &projection, bound,
+                            predicates);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Defaults of the generic parameters of a type or trait.
+    fn defaults_of(&self, id: ItemId) -> Rc<Vec<Option<Ty<'a>>>> {
+        if let Some(defaults) = self.memo.borrow().defaults.get(&id) {
+            return defaults.clone();
+        }
+        let generics = self.generics_of(id);
+        let mut defaults = vec![None; generics.names.len()];
+        let (item, _) = self.ast(id);
+        if let Some(ast) = generics_ast(item) {
+            let scope = self.scope_of(id);
+            let is_trait = matches!(item.kind, ItemKind::Trait { .. });
+            let first = generics.parent + is_trait as usize;
+            for (i, param) in ast.params.iter().enumerate() {
+                if let GenericParam::Type { default: Some(ref ty), .. } = *param {
+                    defaults[first + i] = Some(self.lower(&scope, ty));
+                }
+            }
+        }
+        let defaults = Rc::new(defaults);
+        self.memo.borrow_mut().defaults.insert(id, defaults.clone());
+        defaults
+    }
+
+    /// The supertraits of a trait, with `Self` as parameter 0.
+    fn supertraits_of(&self, def: DefId) -> Rc<Vec<TraitBound<'a>>> {
+        if let Some(bounds) = self.memo.borrow().supertraits.get(&def) {
+            return bounds.clone();
+        }
+        // Supertraits that refer to the trait itself see no supertraits.
+        self.memo.borrow_mut().supertraits.insert(def, Rc::new(Vec::new()));
+        let (item, _) = self.ast(ItemId::Def(def));
+        let (supertraits, generics) = match item.kind {
+            ItemKind::Trait { ref supertraits, ref generics, .. } => {
+                (supertraits, generics)
+            }
+            _ => unreachable!(),
+        };
+        let scope = self.scope_of(ItemId::Def(def));
+        let self_ty = Ty::Param(0, "Self");
+        let mut bounds = Vec::new();
+        let where_self = generics.predicates.iter()
+            .filter(|predicate| param_name(&predicate.ty) == Some("Self"))
+            .flat_map(|predicate| predicate.bounds.iter());
+        for bound in supertraits.iter().chain(where_self) {
+            if let Some(bound) = self.lower_bound(&scope, &self_ty, bound) {
+                bounds.push(bound);
+            }
+        }
+        let bounds = Rc::new(bounds);
+        self.memo.borrow_mut().supertraits.insert(def, bounds.clone());
+        bounds
+    }
+
+    /// Find the trait declaring the associated type `name`: the trait of
+    /// `trait_ref` or one of its supertraits.
+    fn assoc_owner(
+        &self,
+        self_ty: &Ty<'a>,
+        trait_ref: &TraitRef<'a>,
+        name: &str,
+    ) -> Option<TraitRef<'a>> {
+        let mut stack = vec![trait_ref.clone()];
+        let mut seen = Vec::new();
+        while let Some(trait_ref) = stack.pop() {
+            if seen.contains(&trait_ref.def) {
+                continue;
+            }
+            seen.push(trait_ref.def);
+            if self.trait_item(trait_ref.def, AssocKind::Type, name).is_some() {
+                return Some(trait_ref);
+            }
+            let args = self.trait_args(self_ty, &trait_ref);
+            for bound in self.supertraits_of(trait_ref.def).iter() {
+                stack.push(bound.trait_ref.subst(&args));
+            }
+        }
+        None
+    }
+
+    // The generic arguments of a trait including `Self`.
+    fn trait_args(&self, self_ty: &Ty<'a>, trait_ref: &TraitRef<'a>)
+        -> Vec<Ty<'a>>
+    {
+        let mut args = vec![self_ty.clone()];
+        args.extend(trait_ref.args.iter().cloned());
+        args
+    }
+
+    /// Find an item of a trait by kind and name.
+    fn trait_item(&self, def: DefId, kind: AssocKind, name: &str)
+        -> Option<usize>
+    {
+        match self.krate.item(def).map(|item| &item.kind) {
+            Some(ItemKind::Trait { items, .. }) => items.iter()
+                .position(|item| assoc_kind(item) == Some((kind, name))),
+            _ => None,
+        }
+    }
+
+    /// Find an item of an impl by kind and name.
+    fn impl_item(&self, impl_id: usize, kind: AssocKind, name: &str)
+        -> Option<usize>
+    {
+        match self.impls[impl_id].item.kind {
+            ItemKind::Impl { ref items, .. } => items.iter()
+                .position(|item| assoc_kind(item) == Some((kind, name))),
+            _ => None,
+        }
+    }
+
+    fn impl_header(&self, impl_id: usize) -> Rc<ImplHeader<'a>> {
+        if let Some(header) = self.memo.borrow().impl_headers.get(&impl_id) {
+            return header.clone();
+        }
+        let info = &self.impls[impl_id];
+        let mut scope = self.bare_scope(ItemId::Impl(info.module, info.index));
+        let (trait_, self_ty, negative) = match info.item.kind {
+            ItemKind::Impl { ref trait_, ref self_ty, negative, .. } => {
+                (trait_, self_ty, negative)
+            }
+            _ => unreachable!(),
+        };
+        let self_ty = self.lower(&scope, self_ty);
+        scope.self_ty = Some(self_ty.clone());
+        let trait_ref = match (info.of, trait_) {
+            (ImplOf::Trait(def), Some(path)) => {
+                let segment = path.segments.last().unwrap();
+                let args = self.lower_args(&scope, ItemId::Def(def), segment,
+                    Some(self_ty.clone()));
+                Some(TraitRef { def, args: args[1..].to_vec() })
+            }
+            _ => None,
+        };
+        let header = Rc::new(ImplHeader { self_ty, trait_ref, negative });
+        self.memo.borrow_mut().impl_headers.insert(impl_id, header.clone());
+        header
+    }
+
+    /// The type of an associated type of an impl.
+    fn impl_assoc_ty(&self, impl_id: usize, name: &str) -> Option<Ty<'a>> {
+        let index = self.impl_item(impl_id, AssocKind::Type, name)?;
+        let info = &self.impls[impl_id];
+        Some(self.item_ty(ItemId::ImplItem(info.module, info.index, index)))
+    }
+
+    /// The default of an associated type of a trait, with the generics of
+    /// the trait.
+    fn assoc_default(&self, def: DefId, name: &str) -> Option<Ty<'a>> {
+        let index = self.trait_item(def, AssocKind::Type, name)?;
+        let (item, _) = self.ast(ItemId::TraitItem(def, index));
+        match item.kind {
+            ItemKind::Type(_, _, _, Some(_)) => {
+                Some(self.item_ty(ItemId::TraitItem(def, index)))
+            }
+            _ => None,
+        }
+    }
+
+    /// The bounds declared on an associated type of a trait, with the
+    /// generics of the trait.
+    fn assoc_bounds(&self, def: DefId, name: &str) -> Vec<TraitBound<'a>> {
+        let index = match self.trait_item(def, AssocKind::Type, name) {
+            Some(index) => index,
+            None => return Vec::new(),
+        };
+        let id = ItemId::TraitItem(def, index);
+        let (item, _) = self.ast(id);
+        let (name, bounds) = match item.kind {
+            ItemKind::Type(name, _, ref bounds, _) => (name, bounds),
+            _ => unreachable!(),
+        };
+        let scope = self.scope_of(id);
+        let generics = self.generics_of(ItemId::Def(def));
+        let self_ty = Ty::Projection(Box::new(Projection {
+            self_ty: Ty::Param(0, "Self"),
+            trait_ref: TraitRef {
+                def,
+                args: self.identity(&generics)[1..].to_vec(),
+            },
+            name,
+        }));
+        bounds.iter()
+            .filter_map(|bound| self.lower_bound(&scope, &self_ty, bound))
+            .collect()
+    }
+
+    /// The type of a type alias, associated type, constant or static.
+    fn item_ty(&self, id: ItemId) -> Ty<'a> {
+        match self.memo.borrow().types.get(&id) {
+            Some(Some(ty)) => return ty.clone(),
+            Some(None) => {
+                let (item, module) = self.ast(id);
+                self.error(module, item.span,
+                    "cycle detected when expanding type alias");
+                return Ty::Error;
+            }
+            None => {}
+        }
+        self.memo.borrow_mut().types.insert(id, None);
+        let (item, _) = self.ast(id);
+        let scope = self.scope_of(id);
+        let ty = match item.kind {
+            ItemKind::Type(_, _, _, Some(ref ty))
+            | ItemKind::Const(_, ref ty, _)
+            | ItemKind::Static(_, _, ref ty, _) => self.lower(&scope, ty),
+            _ => Ty::Error,
+        };
+        self.memo.borrow_mut().types.insert(id, Some(ty.clone()));
+        ty
+    }
+
+    /// The target of a type alias.
+    fn alias_ty(&self, def: DefId) -> Ty<'a> {
+        self.item_ty(ItemId::Def(def))
+    }
+
+    /// The type of a constant or static.
+    fn const_ty(&self, id: ItemId) -> Ty<'a> {
+        self.item_ty(id)
+    }
+
+    fn adt(&self, def: DefId) -> Rc<AdtDef<'a>> {
+        if let Some(adt) = self.memo.borrow().adts.get(&def) {
+            return adt.clone();
+        }
+        let (item, module) = self.ast(ItemId::Def(def));
+        let scope = self.scope_of(ItemId::Def(def));
+        let lower_fields = |fields: &'k Fields<'a>, public: bool| {
+            let (ctor, fields) = match fields {
+                Fields::Unit => (CtorKind::Unit, &[][..]),
+                Fields::Tuple(fields) => (CtorKind::Tuple, &fields[..]),
+                Fields::Named(fields) => (CtorKind::Named, &fields[..]),
+            };
+            let fields = fields.iter().map(|field| FieldDef {
+                name: field.name,
+                ty: self.lower(&scope, &field.ty),
+                vis: match public {
+                    true => Vis::Public,
+                    false => self.krate.vis(module, &field.vis),
+                },
+            });
+            (ctor, fields.collect())
+        };
+        let name = self.krate.defs[def].name;
+        let (kind, variants) = match item.kind {
+            ItemKind::Struct(_, _, ref fields) => {
+                let (ctor, fields) = lower_fields(fields, false);
+                (AdtKind::Struct, vec![VariantDef { name, ctor, fields }])
+            }
+            ItemKind::Union(_, _, ref fields) => {
+                let fields = fields.iter().map(|field| FieldDef {
+                    name: field.name,
+                    ty: self.lower(&scope, &field.ty),
+                    vis: self.krate.vis(module, &field.vis),
+                });
+                let variant = VariantDef {
+                    name,
+                    ctor: CtorKind::Named,
+                    fields: fields.collect(),
+                };
+                (AdtKind::Union, vec![variant])
+            }
+            ItemKind::Enum(_, _, ref variants) => {
+                let variants = variants.iter().map(|variant| {
+                    let (ctor, fields) = lower_fields(&variant.fields, true);
+                    VariantDef { name: variant.name, ctor, fields }
+                });
+                (AdtKind::Enum, variants.collect())
+            }
+            _ => unreachable!(),
+        };
+        let adt = Rc::new(AdtDef { kind, variants });
+        self.memo.borrow_mut().adts.insert(def, adt.clone());
+        adt
+    }
+
+    fn fn_sig(&self, id: ItemId) -> Rc<FnSig<'a>> {
+        if let Some(sig) = self.memo.borrow().sigs.get(&id) {
+            return sig.clone();
+        }
+        let (item, module) = self.ast(id);
+        let function = match item.kind {
+            ItemKind::Fn(ref function) => function,
+            _ => unreachable!(),
+        };
+        let scope = self.scope_of(id);
+        let mut inputs = Vec::new();
+        if let Some(ref self_param) = function.self_param {
+            let self_ty = scope.self_ty.clone().unwrap_or_else(|| {
+                self.error(module, item.span,
+                    "`self` parameter is only allowed in associated functions");
+                Ty::Error
+            });
+            inputs.push(match self_param {
+                SelfParam::Value(_) => self_ty,
+                SelfParam::Ref(mutable) => Ty::Ref(*mutable, Box::new(self_ty)),
+                SelfParam::Typed(_, ty) => self.lower(&scope, ty),
+            });
+        }
+        let generics = self.generics_of(id);
+        let impl_traits = impl_trait_params(function).len();
+        scope.apit.set(generics.names.len() - impl_traits);
+        scope.impl_trait.set(ImplTraitMode::Param);
+        for param in &function.params {
+            inputs.push(self.lower(&scope, &param.ty));
+        }
+        scope.impl_trait.set(ImplTraitMode::Opaque);
+        let output = match function.ret {
+            Some(ref ty) => self.lower(&scope, ty),
+            None => Ty::unit(),
+        };
+        let sig = Rc::new(FnSig {
+            inputs,
+            output,
+            has_self: function.self_param.is_some(),
+            variadic: function.variadic,
+        });
+        self.memo.borrow_mut().sigs.insert(id, sig.clone());
+        sig
+    }
+
+    // Resolve a path in a scope, reporting errors.
+    fn resolve(&self, scope: &Scope<'k, 'a>, path: &Path<'a>, ns: Namespace)
+        -> Option<(Res, usize)>
+    {
+        let first = path.segments[0].name;
+        let first_ns = match path.segments.len() {
+            1 => ns,
+            _ => Namespace::Type,
+        };
+        let imported = match path.global {
+            true => None,
+            false => scope.uses.iter().rev()
+                .find(|(name, use_ns, _)| *name == first && *use_ns == first_ns)
+                .map(|(_, _, res)| res.clone()),
+        };
+        let result = match imported {
+            Some(res) => {
+                self.krate.resolve_path_from(scope.module, res, path, ns)
+            }
+            None => self.krate.resolve_path(scope.module, path, ns),
+        };
+        match result {
+            Ok(resolved) => Some(resolved),
+            Err(error) => {
+                self.errors.borrow_mut().push(error);
+                None
+            }
+        }
+    }
+
+    /// Lower a type in a scope.
+    fn lower(&self, scope: &Scope<'k, 'a>, ty: &Type<'a>) -> Ty<'a> {
+        match ty.kind {
+            TypeKind::Path(ref path) => self.lower_path(scope, path),
+            TypeKind::Qualified(ref self_ty, ref trait_path, ref rest) => {
+                let self_ty = self.lower(scope, self_ty);
+                self.lower_qualified(scope, self_ty, trait_path.as_ref(), rest,
+                    ty.span)
+            }
+            TypeKind::Ref(mutable, ref ty) => {
+                Ty::Ref(mutable, Box::new(self.lower(scope, ty)))
+            }
+            TypeKind::Ptr(mutable, ref ty) => {
+                Ty::Ptr(mutable, Box::new(self.lower(scope, ty)))
+            }
+            TypeKind::Slice(ref ty) => Ty::Slice(Box::new(self.lower(scope, ty))),
+            TypeKind::Array(ref ty, ref len) => {
+                let len = self.const_usize(scope.module, len, 0);
+                Ty::Array(Box::new(self.lower(scope, ty)), len)
+            }
+            TypeKind::Tuple(ref tys) => {
+                Ty::Tuple(tys.iter().map(|ty| self.lower(scope, ty)).collect())
+            }
+            TypeKind::Never => Ty::Never,
+            TypeKind::Infer if scope.in_body => Ty::Infer,
+            TypeKind::Infer => {
+                self.error(scope.module, ty.span, "the placeholder `_` is not \
+                    allowed within types on item signatures");
+                Ty::Error
+            }
+            TypeKind::Fn(ref inputs, ref output, _) => {
+                let mode = scope.impl_trait.replace(ImplTraitMode::Forbidden);
+                let inputs = inputs.iter().map(|ty| self.lower(scope, ty));
+                let ty = Ty::Fn(inputs.collect(),
+                    Box::new(self.lower(scope, output)));
+                scope.impl_trait.set(mode);
+                ty
+            }
+            TypeKind::ImplTrait(ref bounds) => match scope.impl_trait.get() {
+                ImplTraitMode::Param => {
+                    let index = scope.apit.get();
+                    scope.apit.set(index + 1);
+                    Ty::Param(index, "impl Trait")
+                }
+                ImplTraitMode::Opaque => {
+                    let mode = scope.impl_trait.replace(ImplTraitMode::Forbidden);
+                    let bounds = self.lower_bounds(scope, bounds);
+                    scope.impl_trait.set(mode);
+                    Ty::Opaque(bounds)
+                }
+                ImplTraitMode::Forbidden => {
+                    self.error(scope.module, ty.span, "`impl Trait` is only \
+                        allowed in function parameters and return types");
+                    Ty::Error
+                }
+            },
+            TypeKind::DynTrait(ref bounds) => {
+                Ty::Dyn(self.lower_bounds(scope, bounds))
+            }
+            TypeKind::Macro(_) => {
+                self.error(scope.module, ty.span,
+                    "macros in type position are not supported");
+                Ty::Error
+            }
+        }
+    }
+
+    // Lower the bounds of a `dyn` or `impl` type, whose `Self` type is left
+    // as an error.
+    fn lower_bounds(&self, scope: &Scope<'k, 'a>, bounds: &[Bound<'a>])
+        -> Vec<TraitBound<'a>>
+    {
+        bounds.iter()
+            .filter_map(|bound| self.lower_bound(scope, &Ty::Error, bound))
+            .collect()
+    }
+
+    fn lower_path(&self, scope: &Scope<'k, 'a>, path: &Path<'a>) -> Ty<'a> {
+        let segments = &path.segments;
+        let first = &segments[0];
+        if !path.global && segments.len() <= 2 {
+            let base = match first.name {
+                "Self" => match scope.self_ty {
+                    Some(ref self_ty) => Some(self_ty.clone()),
+                    None => {
+                        self.error(scope.module, first.span, "`Self` is only \
+                            available in impls, traits, and type definitions");
+                        return Ty::Error;
+                    }
+                },
+                name => scope.param(name)
+                    .map(|index| Ty::Param(index, scope.params[index].0)),
+            };
+            if let Some(base) = base {
+                return match segments.get(1) {
+                    Some(segment) => self.assoc_ty(scope, base, segment),
+                    None => base,
+                };
+            }
+        }
+        let (res, len) = match self.resolve(scope, path, Namespace::Type) {
+            Some(resolved) => resolved,
+            None => return Ty::Error,
+        };
+        let ty = self.res_ty(scope, &res, &segments[len - 1]);
+        match &segments[len..] {
+            [] => ty,
+            [segment] if ty != Ty::Error => self.assoc_ty(scope, ty, segment),
+            [..] => {
+                if ty != Ty::Error {
+                    self.error(scope.module, path.span,
+                        "ambiguous associated type");
+                }
+                Ty::Error
+            }
+        }
+    }
+
+    // The type named by a resolved path, with the generic arguments on its
+    // last segment.
+    fn res_ty(
+        &self,
+        scope: &Scope<'k, 'a>,
+        res: &Res,
+        segment: &PathSegment<'a>,
+    ) -> Ty<'a> {
+        let def = match *res {
+            Res::Def(def) => def,
+            Res::Primitive(name) => {
+                if !segment.generics.is_empty() {
+                    self.error(scope.module, segment.span, format!(
+                        "type arguments are not allowed on builtin type `{}`",
+                        name));
+                }
+                return primitive(name);
+            }
+            Res::External(_) => return Ty::Error,
+        };
+        let item = self.krate.item(def).map(|item| &item.kind);
+        match item {
+            Some(ItemKind::Struct(..))
+            | Some(ItemKind::Enum(..))
+            | Some(ItemKind::Union(..)) => {
+                Ty::Adt(def, self.lower_args(scope, ItemId::Def(def), segment,
+                    None))
+            }
+            Some(ItemKind::Type(..)) => {
+                let args = self.lower_args(scope, ItemId::Def(def), segment,
+                    None);
+                self.alias_ty(def).subst(&args)
+            }
+            // A bare trait is a trait object, as in the 2018 edition.
+            Some(ItemKind::Trait { .. }) => {
+                let bound = self.lower_trait(scope, &Ty::Error, def, segment);
+                Ty::Dyn(vec![bound])
+            }
+            _ => {
+                let what = match self.krate.defs[def].kind {
+                    DefKind::Module(_) => "module",
+                    DefKind::Variant(..) => "variant",
+                    _ => "value",
+                };
+                self.error(scope.module, segment.span, format!(
+                    "expected type, found {} `{}`", what, self.krate.path(res)));
+                Ty::Error
+            }
+        }
+    }
+
+    /// Lower the generic arguments of a path segment naming a type or trait,
+    /// filling in defaults.  `self_ty` is the first argument of traits.
+    fn lower_args(
+        &self,
+        scope: &Scope<'k, 'a>,
+        owner: ItemId,
+        segment: &PathSegment<'a>,
+        self_ty: Option<Ty<'a>>,
+    ) -> Vec<Ty<'a>> {
+        let generics = self.generics_of(owner);
+        let defaults = self.defaults_of(owner);
+        let mut given = segment.generics.iter().filter(|arg| {
+            matches!(arg, GenericArg::Type(_) | GenericArg::Const(_))
+        });
+        let count = given.clone().count();
+        let mut args: Vec<_> = self_ty.into_iter().collect();
+        let expected = generics.names.len() - args.len();
+        let required = (args.len()..generics.names.len())
+            .filter(|index| defaults[*index].is_none())
+            .count();
+        let infer = count == 0 && scope.in_body;
+        if count > expected || (count < required && !infer) {
+            let message = match count {
+                0 => format!("missing generics for `{}`", segment.name),
+                _ => format!(
+                    "`{}` takes {} generic arguments but {} were supplied",
+                    segment.name, expected, count),
+            };
+            self.error(scope.module, segment.span, message);
+        }
+        for index in args.len()..generics.names.len() {
+            let arg = given.next();
+            let ty = match arg {
+                _ if generics.consts[index] => Ty::Error,
+                Some(GenericArg::Type(ty)) => self.lower(scope, ty),
+                Some(_) => Ty::Error,
+                None => match defaults[index] {
+                    Some(ref ty) => ty.subst(&args),
+                    None if infer => Ty::Infer,
+                    None => Ty::Error,
+                },
+            };
+            args.push(ty);
+        }
+        args
+    }
+
+    /// Lower a bound on `self_ty`; `?Sized` and traits outside the known
+    /// part of the standard library are skipped.
+    fn lower_bound(
+        &self,
+        scope: &Scope<'k, 'a>,
+        self_ty: &Ty<'a>,
+        bound: &Bound<'a>,
+    ) -> Option<TraitBound<'a>> {
+        if bound.maybe {
+            return None;
+        }
+        let path = &bound.path;
+        let def = match self.resolve(scope, path, Namespace::Type)? {
+            (Res::Def(def), len) if len == path.segments.len()
+                && self.is_trait(def) => def,
+            (Res::External(_), _) => return None,
+            _ => {
+                self.error(scope.module, path.span,
+                    format!("expected trait, found `{}`", path));
+                return None;
+            }
+        };
+        let segment = path.segments.last().unwrap();
+        Some(self.lower_trait(scope, self_ty, def, segment))
+    }
+
+    // Lower the arguments and associated type bindings of a trait.
+    fn lower_trait(
+        &self,
+        scope: &Scope<'k, 'a>,
+        self_ty: &Ty<'a>,
+        def: DefId,
+        segment: &PathSegment<'a>,
+    ) -> TraitBound<'a> {
+        let args = self.lower_args(scope, ItemId::Def(def), segment,
+            Some(self_ty.clone()));
+        let trait_ref = TraitRef { def, args: args[1..].to_vec() };
+        let mut bindings = Vec::new();
+        for arg in &segment.generics {
+            if let GenericArg::Binding(name, ref ty) = *arg {
+                if self.assoc_owner(self_ty, &trait_ref, name).is_none() {
+                    self.error(scope.module, ty.span, format!(
+                        "associated type `{}` not found for `{}`", name,
+                        segment.name));
+                }
+                bindings.push((name, self.lower(scope, ty)));
+            }
+        }
+        TraitBound { trait_ref, bindings }
+    }
+
+    // Lower `<T as Trait>::Name` or `<T>::Name`.
+    fn lower_qualified(
+        &self,
+        scope: &Scope<'k, 'a>,
+        self_ty: Ty<'a>,
+        trait_path: Option<&Path<'a>>,
+        rest: &[PathSegment<'a>],
+        span: Span,
+    ) -> Ty<'a> {
+        let segment = match rest {
+            [segment] => segment,
+            _ => {
+                self.error(scope.module, span, "ambiguous associated type");
+                return Ty::Error;
+            }
+        };
+        let path = match trait_path {
+            Some(path) => path,
+            None => return self.assoc_ty(scope, self_ty, segment),
+        };
+        let bound = Bound { maybe: false, path: path.clone() };
+        let bound = match self.lower_bound(scope, &self_ty, &bound) {
+            Some(bound) => bound,
+            None => return Ty::Error,
+        };
+        match self.assoc_owner(&self_ty, &bound.trait_ref, segment.name) {
+            Some(trait_ref) => Ty::Projection(Box::new(Projection {
+                self_ty,
+                trait_ref,
+                name: segment.name,
+            })),
+            None => {
+                self.error(scope.module, segment.span, format!(
+                    "cannot find associated type `{}` in trait `{}`",
+                    segment.name, path));
+                Ty::Error
+            }
+        }
+    }
+
+    /// Lower `T::Name` to the associated type of a trait bounding `T`, or of
+    /// the trait or impl the scope is in.
+    fn assoc_ty(
+        &self,
+        scope: &Scope<'k, 'a>,
+        base: Ty<'a>,
+        segment: &PathSegment<'a>,
+    ) -> Ty<'a> {
+        let name = segment.name;
+        let projection = |trait_ref| {
+            Ty::Projection(Box::new(Projection {
+                self_ty: base.clone(),
+                trait_ref,
+                name,
+            }))
+        };
+        if let Ty::Param(index, _) = base {
+            if let (Some(def), 0) = (scope.trait_, index) {
+                let generics = self.generics_of(ItemId::Def(def));
+                let args = self.identity(&generics)[1..].to_vec();
+                let trait_ref = TraitRef { def, args };
+                if let Some(owner) = self.assoc_owner(&base, &trait_ref, name) {
+                    return projection(owner);
+                }
+            }
+            for bound in &scope.params[index].1 {
+                if let Some(bound) = self.lower_bound(scope, &base, bound) {
+                    let owner = self.assoc_owner(&base, &bound.trait_ref, name);
+                    if let Some(owner) = owner {
+                        return projection(owner);
+                    }
+                }
+            }
+            self.error(scope.module, segment.span, format!(
+                "associated type `{}` not found for `{}`", name,
+                base.name(self.krate)));
+            return Ty::Error;
+        }
+        let is_self = scope.self_ty.as_ref() == Some(&base);
+        if let (Some(impl_id), true) = (scope.impl_, is_self) {
+            if let Some(ty) = self.impl_assoc_ty(impl_id, name) {
+                return ty;
+            }
+            let header = self.impl_header(impl_id);
+            if let Some(ref trait_ref) = header.trait_ref {
+                if let Some(owner) = self.assoc_owner(&base, trait_ref, name) {
+                    return projection(owner);
+                }
+            }
+        }
+        if base != Ty::Error {
+            self.error(scope.module, segment.span, format!(
+                "ambiguous associated type `{}` of `{}`", name,
+                base.name(self.krate)));
+        }
+        Ty::Error
+    }
+
+    /// Evaluate a constant expression of type `usize`, like an array length,
+    /// if it is simple enough.
+    fn const_usize(&self, module: ModuleId, expr: &Expr<'a>, depth: usize)
+        -> Option<u64>
+    {
+        if depth > 32 {
+            return None;
+        }
+        match expr.kind {
+            ExprKind::Lit(Lit::Int(value, _)) => u64::try_from(value).ok(),
+            ExprKind::Cast(ref expr, _) => self.const_usize(module, expr, depth),
+            ExprKind::Block(_, ref block) if block.stmts.is_empty() => {
+                self.const_usize(module, block.expr.as_ref()?, depth)
+            }
+            ExprKind::Binary(op, ref lhs, ref rhs) => {
+                let lhs = self.const_usize(module, lhs, depth + 1)?;
+                let rhs = self.const_usize(module, rhs, depth + 1)?;
+                match op {
+                    BinaryOp::Add => lhs.checked_add(rhs),
+                    BinaryOp::Sub => lhs.checked_sub(rhs),
+                    BinaryOp::Mul => lhs.checked_mul(rhs),
+                    BinaryOp::Div => lhs.checked_div(rhs),
+                    BinaryOp::Rem => lhs.checked_rem(rhs),
+                    BinaryOp::Shl => lhs.checked_shl(rhs as u32),
+                    BinaryOp::Shr => lhs.checked_shr(rhs as u32),
+                    BinaryOp::BitAnd => Some(lhs & rhs),
+                    BinaryOp::BitOr => Some(lhs | rhs),
+                    BinaryOp::BitXor => Some(lhs ^ rhs),
+                    _ => None,
+                }
+            }
+            ExprKind::Path(ref path) => {
+                let resolved = self.krate.resolve_path(module, path,
+                    Namespace::Value);
+                let def = match resolved {
+                    Ok((Res::Def(def), len)) if len == path.segments.len() => def,
+                    _ => return None,
+                };
+                match self.krate.item(def)?.kind {
+                    ItemKind::Const(_, _, Some(ref value)) => {
+                        let module = self.krate.defs[def].module;
+                        self.const_usize(module, value, depth + 1)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // Check that a trait impl has the items of its trait.
+    fn check_impl(&self, module: ModuleId, index: usize) {
+        let impl_id = self.impl_ids[&(module, index)];
+        let header = self.impl_header(impl_id);
+        self.predicates_of(ItemId::Impl(module, index));
+        let info = &self.impls[impl_id];
+        let (items, trait_path) = match info.item.kind {
+            ItemKind::Impl { ref items, trait_: Some(ref path), .. } => {
+                (items, path)
+            }
+            _ => return,
+        };
+        let def = match info.of {
+            ImplOf::Trait(def) => def,
+            _ => return,
+        };
+        let trait_items = match self.krate.item(def).map(|item| &item.kind) {
+            Some(ItemKind::Trait { items, .. }) => items,
+            _ => return,
+        };
+        let trait_name = self.krate.defs[def].name;
+        for item in items {
+            let (kind, name) = match assoc_kind(item) {
+                Some(assoc) => assoc,
+                None => continue,
+            };
+            let what = match kind {
+                AssocKind::Fn => "method",
+                AssocKind::Type => "type",
+                AssocKind::Const => "const",
+            };
+            let found = match self.trait_item(def, kind, name) {
+                Some(found) => &trait_items[found],
+                None => {
+                    self.error(module, item.span, format!(
+                        "{} `{}` is not a member of trait `{}`", what, name,
+                        trait_name));
+                    continue;
+                }
+            };
+            if let (ItemKind::Fn(ref function), ItemKind::Fn(ref declared)) =
+                (&item.kind, &found.kind)
+            {
+                let has_self = function.self_param.is_some();
+                if has_self != declared.self_param.is_some() {
+                    let message = match has_self {
+                        true => format!("method `{}` has a `self` declaration \
+                            in the impl, but not in the trait", name),
+                        false => format!("method `{}` has a `self` \
+                            declaration in the trait, but not in the impl",
+                            name),
+                    };
+                    self.error(module, item.span, message);
+                } else if function.params.len() != declared.params.len() {
+                    self.error(module, item.span, format!(
+                        "method `{}` has {} parameters but the declaration in \
+                            trait `{}` has {}", name, function.params.len(),
+                        trait_name, declared.params.len()));
+                }
+            }
+        }
+        if header.negative {
+            return;
+        }
+        let missing: Vec<_> = trait_items.iter()
+            .filter(|item| match item.kind {
+                ItemKind::Fn(ref function) => function.body.is_none(),
+                ItemKind::Type(_, _, _, ref default) => default.is_none(),
+                ItemKind::Const(_, _, ref value) => value.is_none(),
+                _ => false,
+            })
+            .filter_map(assoc_kind)
+            .filter(|(kind, name)| self.impl_item(impl_id, *kind, name).is_none())
+            .map(|(_, name)| format!("`{}`", name))
+            .collect();
+        if !missing.is_empty() {
+            self.error(module, trait_path.span, format!(
+                "not all trait items implemented, missing: {}",
+                missing.join(", ")));
+        }
+        // The type must implement the supertraits, given the impl's `where`
+        // clauses.
+        let trait_ref = match header.trait_ref {
+            Some(ref trait_ref) => trait_ref,
+            None => return,
+        };
+        let mut infcx = infer::InferCtxt::new(self, module);
+        infcx.env = Rc::new(infcx.elaborate(
+            &self.predicates_of(ItemId::Impl(module, index))));
+        let args = self.trait_args(&header.self_ty, trait_ref);
+        let mut preds = Vec::new();
+        for bound in self.supertraits_of(def).iter() {
+            let bound = bound.fold(&mut |ty| match *ty {
+                Ty::Param(index, _) => args.get(index).cloned(),
+                _ => None,
+            });
+            self.bound_predicates(&header.self_ty, &bound, &mut preds);
+        }
+        for pred in preds {
+            infcx.register(pred, trait_path.span, infer::Cause::Misc);
+        }
+        infcx.select_pending();
+    }
+}
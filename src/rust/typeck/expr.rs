@@ -0,0 +1,2020 @@
+// Rust expression type checking
+//
+//! Type checking of function bodies: statements, expressions, coercions and
+//! the builtin macros of the standard library.
+//!
+//! Expressions are checked in order, with the expected type passed down
+//! where it is known so closures can get their signatures from it.  The
+//! arguments of a call are checked before the closures among them, so that
+//! the parameter types of the closures are known by then.
+
+use std::rc::Rc;
+
+use super::infer::{Cause, InferCtxt};
+use super::{
+    AdtKind, Context, CtorKind, ItemId, Method, Pred, Projection, Scope,
+    TraitRef, Ty, Types,
+};
+use crate::rust::resolve::{FileId, Namespace, Res};
+use crate::rust::{
+    BinaryOp, Block, Closure, Expr, ExprKind, FieldInit, Function,
+    IntType, ItemKind, Lit, MacroCall, Path, StmtKind, UnaryOp, UseTree,
+    UseTreeKind,
+};
+use crate::Span;
+
+/// A loop or labeled block that `break` may leave
+struct LoopCx<'a> {
+    label: Option<&'a str>,
+    /// Type of the values of `break`, `None` for `while` and `for` loops
+    ty: Option<Ty<'a>>,
+    /// Whether this is a labeled block, which plain `break` doesn't leave
+    block: bool,
+    broken: bool,
+}
+
+/// The state of checking a function body
+pub(super) struct FnCtxt<'c, 'k, 'a> {
+    pub(super) infcx: InferCtxt<'c, 'k, 'a>,
+    pub(super) cx: &'c Context<'k, 'a>,
+    pub(super) scope: Scope<'k, 'a>,
+    file: FileId,
+    /// Variables in scope, innermost last
+    pub(super) locals: Vec<(&'a str, Ty<'a>)>,
+    /// Return type of the function or closure being checked
+    ret_ty: Option<Ty<'a>>,
+    loops: Vec<LoopCx<'a>>,
+    closures: usize,
+    exprs: Vec<(Span, Ty<'a>)>,
+    pub(super) bindings: Vec<(Span, Ty<'a>)>,
+    pub(super) patterns: Vec<(Span, Ty<'a>)>,
+    methods: Vec<(Span, Method<'a>)>,
+    pub(super) callees: Vec<(Span, ItemId)>,
+}
+
+impl<'k, 'a> Context<'k, 'a> {
+    /// Check the body of a function.
+    pub(super) fn check_fn(
+        &self,
+        id: ItemId,
+        function: &'k Function<'a>,
+        types: &mut Types<'a>,
+    ) {
+        let sig = self.fn_sig(id);
+        let body = match function.body {
+            Some(ref body) => body,
+            None => return,
+        };
+        let mut fcx = FnCtxt::new(self, id);
+        let mut inputs = sig.inputs.iter();
+        if sig.has_self {
+            let ty = fcx.infcx.normalize(inputs.next().unwrap(), body.span);
+            fcx.locals.push(("self", ty));
+        }
+        for (param, ty) in function.params.iter().zip(inputs) {
+            let ty = fcx.infcx.normalize(ty, param.ty.span);
+            fcx.check_pat(&param.pattern, &ty);
+        }
+        let span = function.ret.as_ref().map_or(body.span, |ty| ty.span);
+        let output = fcx.infcx.normalize(&sig.output, span);
+        let output = fcx.instantiate_opaque(&output, span);
+        fcx.ret_ty = Some(output.clone());
+        let ty = fcx.check_block(body, Some(&output));
+        fcx.coerce_or_error(body.span, &ty, &output);
+        fcx.finish(types);
+    }
+
+    /// Check the value of a constant or static.
+    pub(super) fn check_const(
+        &self,
+        id: ItemId,
+        value: &'k Expr<'a>,
+        types: &mut Types<'a>,
+    ) {
+        let ty = self.const_ty(id);
+        let mut fcx = FnCtxt::new(self, id);
+        let ty = fcx.infcx.normalize(&ty, value.span);
+        fcx.check_expr_coerce(value, &ty);
+        fcx.finish(types);
+    }
+}
+
+impl<'c, 'k, 'a> FnCtxt<'c, 'k, 'a> {
+    fn new(cx: &'c Context<'k, 'a>, id: ItemId) -> Self {
+        let (_, module) = cx.ast(id);
+        let mut scope = cx.scope_of(id);
+        scope.in_body = true;
+        let mut infcx = InferCtxt::new(cx, module);
+        infcx.env = Rc::new(infcx.elaborate(&cx.predicates_of(id)));
+        FnCtxt {
+            infcx,
+            cx,
+            scope,
+            file: cx.krate.modules[module].file,
+            locals: Vec::new(),
+            ret_ty: None,
+            loops: Vec::new(),
+            closures: 0,
+            exprs: Vec::new(),
+            bindings: Vec::new(),
+            patterns: Vec::new(),
+            methods: Vec::new(),
+            callees: Vec::new(),
+        }
+    }
+
+    // Solve what is left and record the types of the body.
+    fn finish(mut self, types: &mut Types<'a>) {
+        self.infcx.select_pending();
+        self.infcx.fallback();
+        self.infcx.select_pending();
+        let infcx = &self.infcx;
+        let unknown = |ty: &Ty<'a>| {
+            infcx.resolve(ty).fold(&mut |ty| match ty {
+                Ty::Var(_) => Some(Ty::Error),
+                _ => None,
+            })
+        };
+        let mut reported = false;
+        for (span, ty) in &self.bindings {
+            if infcx.resolve(ty).has_vars() && !reported {
+                self.cx.error(self.scope.module, *span,
+                    "type annotations needed");
+                reported = true;
+            }
+            types.bindings.insert((self.file, *span), unknown(ty));
+        }
+        for (span, ty) in &self.exprs {
+            types.exprs.insert((self.file, *span), unknown(ty));
+        }
+        for (span, ty) in &self.patterns {
+            types.patterns.insert((self.file, *span), unknown(ty));
+        }
+        let values: Vec<_> = self.bindings.iter().chain(&self.exprs)
+            .map(|(span, ty)| (*span, unknown(ty)))
+            .collect();
+        for (span, method) in self.methods {
+            let args = method.args.iter().map(unknown).collect();
+            types.methods.insert((self.file, span), Method { args, ..method });
+        }
+        for (span, callee) in self.callees {
+            types.callees.insert((self.file, span), callee);
+        }
+        // Values whose type is known not to be `Copy` are moved.
+        let copy = match self.cx.lang("marker::Copy") {
+            Some(def) => TraitRef { def, args: Vec::new() },
+            None => return,
+        };
+        for (span, ty) in values {
+            let is_copy = self.infcx.probe(|infcx| {
+                infcx.evaluate(&ty, &copy, 0)
+            });
+            if is_copy == Some(false) {
+                types.moves.insert((self.file, span));
+            }
+        }
+    }
+
+    pub(super) fn error<T: Into<String>>(&self, span: Span, message: T) {
+        self.cx.error(self.scope.module, span, message);
+    }
+
+    /// Get the name of a type with what is known about its variables.
+    pub(super) fn ty_name(&self, ty: &Ty<'a>) -> String {
+        self.infcx.resolve(ty).name(self.cx.krate)
+    }
+
+    pub(super) fn mismatch(
+        &self,
+        span: Span,
+        expected: &Ty<'a>,
+        found: &Ty<'a>,
+    ) {
+        let expected = self.infcx.resolve(expected);
+        let found = self.infcx.resolve(found);
+        let is_error = |ty: &Ty<'a>| ty.any(&mut |ty| *ty == Ty::Error);
+        if is_error(&expected) || is_error(&found) {
+            return;
+        }
+        self.error(span, format!("mismatched types: expected `{}`, found `{}`",
+            expected.name(self.cx.krate), found.name(self.cx.krate)));
+    }
+
+    /// Make two types equal, or report that they aren't.
+    pub(super) fn unify_or_error(
+        &mut self,
+        span: Span,
+        expected: &Ty<'a>,
+        found: &Ty<'a>,
+    ) {
+        if !self.unify_exact(expected, found) {
+            self.mismatch(span, expected, found);
+        }
+    }
+
+    // Make two types equal, leaving them unchanged if they can't be.
+    pub(super) fn unify_exact(&mut self, a: &Ty<'a>, b: &Ty<'a>) -> bool {
+        self.infcx.commit_if(|infcx| infcx.unify(a, b).then_some(())).is_some()
+    }
+
+    // Replace the `impl Trait` types in the return type of the function being
+    // checked by variables, which must satisfy their bounds.
+    fn instantiate_opaque(&mut self, ty: &Ty<'a>, span: Span) -> Ty<'a> {
+        let infcx = &mut self.infcx;
+        let mut opaque = Vec::new();
+        let ty = ty.fold(&mut |ty| match ty {
+            Ty::Opaque(bounds) => {
+                let var = infcx.fresh();
+                opaque.push((var.clone(), bounds.clone()));
+                Some(var)
+            }
+            _ => None,
+        });
+        let mut preds = Vec::new();
+        for (var, bounds) in opaque {
+            for bound in &bounds {
+                self.cx.bound_predicates(&var, bound, &mut preds);
+            }
+        }
+        for pred in preds {
+            self.infcx.register(pred, span, Cause::Misc);
+        }
+        ty
+    }
+
+    /// Lower a type written in the body.
+    pub(super) fn lower(&mut self, ty: &crate::rust::Type<'a>) -> Ty<'a> {
+        let lowered = self.cx.lower(&self.scope, ty);
+        let lowered = self.infcx.replace_infer(&lowered);
+        self.infcx.normalize(&lowered, ty.span)
+    }
+
+    /// The standard library type at a path like `string::String` with the
+    /// given arguments.
+    pub(super) fn lang_ty(&self, path: &'static str, args: Vec<Ty<'a>>)
+        -> Ty<'a>
+    {
+        match self.cx.lang(path) {
+            Some(def) => Ty::Adt(def, args),
+            None => Ty::Error,
+        }
+    }
+
+    // Try to coerce a value of type `from` to type `to`: a `!` to anything,
+    // `&mut T` to `&T`, dereferencing references, unsizing arrays to slices
+    // and values to trait objects behind a pointer, and closures to function
+    // pointers.
+    pub(super) fn try_coerce(&mut self, from: &Ty<'a>, to: &Ty<'a>, span: Span)
+        -> bool
+    {
+        let from = self.infcx.shallow(from);
+        let to = self.infcx.shallow(to);
+        if from == Ty::Never {
+            return true;
+        }
+        match (&from, &to) {
+            (Ty::Ref(from_mut, a), Ty::Ref(to_mut, b))
+                if *from_mut || !*to_mut =>
+            {
+                if self.unify_exact(a, b) {
+                    return true;
+                }
+                let steps = self.autoderef(a, span);
+                for step in steps.iter().skip(1) {
+                    if self.unify_exact(step, b) {
+                        return true;
+                    }
+                }
+                return self.try_unsize(a, b, span);
+            }
+            (Ty::Ref(from_mut, a), Ty::Ptr(to_mut, b))
+            | (Ty::Ptr(from_mut, a), Ty::Ptr(to_mut, b))
+                if *from_mut || !*to_mut =>
+            {
+                return self.unify_exact(a, b) || self.try_unsize(a, b, span);
+            }
+            (Ty::Adt(a_def, a), Ty::Adt(b_def, b))
+                if a_def == b_def && self.cx.is_lang(*a_def, "boxed::Box") =>
+            {
+                return self.unify_exact(&a[0], &b[0])
+                    || self.try_unsize(&a[0], &b[0], span);
+            }
+            (Ty::Closure(_, inputs, output), Ty::Fn(..)) => {
+                let ty = Ty::Fn(inputs.clone(), output.clone());
+                return self.unify_exact(&ty, &to);
+            }
+            _ => {}
+        }
+        self.unify_exact(&from, &to)
+    }
+
+    // Coerce the target of a pointer to an unsized type.
+    fn try_unsize(&mut self, from: &Ty<'a>, to: &Ty<'a>, span: Span) -> bool {
+        let from = self.infcx.shallow(from);
+        let to = self.infcx.shallow(to);
+        match (&from, &to) {
+            (Ty::Array(a, _), Ty::Slice(b)) => self.unify_exact(a, b),
+            (Ty::Var(_), _) | (Ty::Dyn(_), Ty::Dyn(_)) => false,
+            (_, Ty::Dyn(bounds)) => {
+                let mut preds = Vec::new();
+                for bound in bounds {
+                    self.cx.bound_predicates(&from, bound, &mut preds);
+                }
+                for pred in preds {
+                    self.infcx.register(pred, span, Cause::Misc);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Coerce a value to a type, or report that it can't be.
+    pub(super) fn coerce_or_error(
+        &mut self,
+        span: Span,
+        from: &Ty<'a>,
+        to: &Ty<'a>,
+    ) {
+        if !self.try_coerce(from, to, span) {
+            self.mismatch(span, to, from);
+        }
+    }
+
+    // Add the type of a branch to the common type of the branches of an `if`
+    // or `match`, which starts as `None` (like `!`).
+    fn coerce_branch(
+        &mut self,
+        common: &mut Option<Ty<'a>>,
+        ty: Ty<'a>,
+        span: Span,
+    ) {
+        match common {
+            None => {
+                if self.infcx.shallow(&ty) != Ty::Never {
+                    *common = Some(ty);
+                }
+            }
+            Some(prev) => {
+                let prev = prev.clone();
+                if self.try_coerce(&ty, &prev, span) {
+                    return;
+                }
+                if self.try_coerce(&prev, &ty, span) {
+                    *common = Some(ty);
+                    return;
+                }
+                self.mismatch(span, &prev, &ty);
+            }
+        }
+    }
+
+    /// Check an expression, with the type it is expected to have if known.
+    pub(super) fn check_expr(
+        &mut self,
+        expr: &Expr<'a>,
+        expected: Option<&Ty<'a>>,
+    ) -> Ty<'a> {
+        let ty = self.check_expr_kind(expr, expected);
+        self.exprs.push((expr.span, ty.clone()));
+        ty
+    }
+
+    /// Check an expression and coerce it to a type.
+    pub(super) fn check_expr_coerce(&mut self, expr: &Expr<'a>, ty: &Ty<'a>)
+        -> Ty<'a>
+    {
+        let found = self.check_expr(expr, Some(ty));
+        self.coerce_or_error(expr.span, &found, ty);
+        ty.clone()
+    }
+
+    fn check_expr_kind(&mut self, expr: &Expr<'a>, expected: Option<&Ty<'a>>)
+        -> Ty<'a>
+    {
+        let span = expr.span;
+        match expr.kind {
+            ExprKind::Lit(ref lit) => self.check_lit(lit),
+            ExprKind::Path(ref path) => self.check_path(path),
+            ExprKind::Qualified(ref ty, ref trait_path, ref rest) => {
+                self.check_qualified(ty, trait_path.as_ref(), rest, span)
+            }
+            ExprKind::Tuple(ref exprs) => {
+                let expected = match expected.map(|ty| self.infcx.shallow(ty)) {
+                    Some(Ty::Tuple(tys)) if tys.len() == exprs.len() => tys,
+                    _ => Vec::new(),
+                };
+                let tys = exprs.iter().enumerate()
+                    .map(|(i, expr)| self.check_expr(expr, expected.get(i)))
+                    .collect();
+                Ty::Tuple(tys)
+            }
+            ExprKind::Array(ref exprs) => {
+                let elem = self.expected_elem(expected);
+                let elem = self.check_elems(exprs.iter(), elem);
+                Ty::Array(Box::new(elem), Some(exprs.len() as u64))
+            }
+            ExprKind::Repeat(ref value, ref count) => {
+                let elem = self.expected_elem(expected)
+                    .unwrap_or_else(|| self.infcx.fresh());
+                self.check_expr_coerce(value, &elem);
+                self.check_expr_coerce(count, &Ty::Int(IntType::Usize));
+                let len = self.cx.const_usize(self.scope.module, count, 0);
+                Ty::Array(Box::new(elem), len)
+            }
+            ExprKind::Struct(ref path, ref fields, ref base) => {
+                self.check_struct(path, fields, base.as_deref(), span)
+            }
+            ExprKind::Unary(op, ref operand) => {
+                self.check_unary(op, operand, span)
+            }
+            ExprKind::Ref(mutable, ref operand) => {
+                let hint = match expected.map(|ty| self.infcx.shallow(ty)) {
+                    Some(Ty::Ref(_, inner)) => match *inner {
+                        Ty::Slice(_) | Ty::Str | Ty::Dyn(_) => None,
+                        inner => Some(inner),
+                    },
+                    _ => None,
+                };
+                let ty = self.check_expr(operand, hint.as_ref());
+                Ty::Ref(mutable, Box::new(ty))
+            }
+            ExprKind::Binary(op, ref lhs, ref rhs) => {
+                self.check_binary(op, lhs, rhs, span)
+            }
+            ExprKind::Assign(ref lhs, ref rhs) => {
+                let ty = self.check_expr(lhs, None);
+                self.check_expr_coerce(rhs, &ty);
+                Ty::unit()
+            }
+            ExprKind::AssignOp(op, ref lhs, ref rhs) => {
+                self.check_assign_op(op, lhs, rhs, span)
+            }
+            ExprKind::Cast(ref operand, ref ty) => {
+                self.check_cast(operand, ty, span)
+            }
+            ExprKind::Call(ref callee, ref args) => {
+                self.check_call(callee, args, expected, span)
+            }
+            ExprKind::MethodCall(ref receiver, ref segment, ref args) => {
+                let ty = self.check_expr(receiver, None);
+                self.check_method_call(&ty, receiver.span, segment, args,
+                    expected, span)
+            }
+            ExprKind::Field(ref base, name) => self.check_field(base, name, span),
+            ExprKind::Index(ref base, ref index) => {
+                self.check_index(base, index, span)
+            }
+            ExprKind::Try(ref operand) => self.check_try(operand, span),
+            ExprKind::Await(ref operand) => self.check_expr(operand, expected),
+            ExprKind::Range(ref start, ref end, inclusive) => {
+                self.check_range(start.as_deref(), end.as_deref(), inclusive)
+            }
+            ExprKind::Block(None, ref block)
+            | ExprKind::Unsafe(ref block)
+            | ExprKind::Const(ref block) => self.check_block(block, expected),
+            ExprKind::Block(Some(label), ref block) => {
+                let ty = self.infcx.fresh();
+                self.loops.push(LoopCx {
+                    label: Some(label),
+                    ty: Some(ty.clone()),
+                    block: true,
+                    broken: false,
+                });
+                let value = self.check_block(block, expected);
+                let lp = self.loops.pop().unwrap();
+                match lp.broken {
+                    true => {
+                        self.coerce_or_error(block.span, &value, &ty);
+                        ty
+                    }
+                    false => value,
+                }
+            }
+            ExprKind::Async(_, ref block) => {
+                let ret = self.infcx.fresh();
+                let ret = self.ret_ty.replace(ret);
+                let loops = std::mem::take(&mut self.loops);
+                let ty = self.check_block(block, None);
+                self.ret_ty = ret;
+                self.loops = loops;
+                ty
+            }
+            ExprKind::If(ref cond, ref then, ref otherwise) => {
+                self.check_if(cond, then, otherwise.as_deref(), expected)
+            }
+            ExprKind::Let(ref pattern, ref value) => {
+                let ty = self.check_expr(value, None);
+                self.check_pat(pattern, &ty);
+                Ty::Bool
+            }
+            ExprKind::While(label, ref cond, ref body) => {
+                let locals = self.locals.len();
+                self.check_expr_coerce(cond, &Ty::Bool);
+                self.check_loop_body(label, None, body);
+                self.locals.truncate(locals);
+                Ty::unit()
+            }
+            ExprKind::Loop(label, ref body) => {
+                let ty = self.infcx.fresh();
+                match self.check_loop_body(label, Some(ty.clone()), body) {
+                    true => ty,
+                    false => Ty::Never,
+                }
+            }
+            ExprKind::For(label, ref pattern, ref iter, ref body) => {
+                let ty = self.check_expr(iter, None);
+                let item = self.iterator_item(&ty, iter.span);
+                let locals = self.locals.len();
+                self.check_pat(pattern, &item);
+                self.check_loop_body(label, None, body);
+                self.locals.truncate(locals);
+                Ty::unit()
+            }
+            ExprKind::Match(ref scrutinee, ref arms) => {
+                let ty = self.check_expr(scrutinee, None);
+                let mut common = None;
+                for arm in arms {
+                    let locals = self.locals.len();
+                    self.check_pat(&arm.pattern, &ty);
+                    if let Some(ref guard) = arm.guard {
+                        self.check_expr_coerce(guard, &Ty::Bool);
+                    }
+                    match expected {
+                        Some(expected) => {
+                            self.check_expr_coerce(&arm.body, expected);
+                        }
+                        None => {
+                            let ty = self.check_expr(&arm.body, None);
+                            self.coerce_branch(&mut common, ty, arm.body.span);
+                        }
+                    }
+                    self.locals.truncate(locals);
+                }
+                match expected {
+                    Some(expected) if !arms.is_empty() => expected.clone(),
+                    _ => common.unwrap_or(Ty::Never),
+                }
+            }
+            ExprKind::Closure(ref closure) => {
+                self.check_closure(closure, expected)
+            }
+            ExprKind::Break(label, ref value) => {
+                self.check_break(label, value.as_deref(), span)
+            }
+            ExprKind::Continue(label) => {
+                if self.find_loop(label, span).is_some() {
+                    if let Some(index) = self.find_loop(label, span) {
+                        if self.loops[index].block {
+                            self.error(span,
+                                "`continue` pointing to a labeled block");
+                        }
+                    }
+                }
+                Ty::Never
+            }
+            ExprKind::Return(ref value) => {
+                let ret = match self.ret_ty.clone() {
+                    Some(ret) => ret,
+                    None => {
+                        self.error(span,
+                            "return statement outside of function body");
+                        Ty::Error
+                    }
+                };
+                match value {
+                    Some(value) => {
+                        self.check_expr_coerce(value, &ret);
+                    }
+                    None => self.coerce_or_error(span, &Ty::unit(), &ret),
+                }
+                Ty::Never
+            }
+            ExprKind::Macro(ref call) => self.check_macro(call, expected),
+        }
+    }
+
+    fn check_lit(&mut self, lit: &Lit) -> Ty<'a> {
+        match *lit {
+            Lit::Bool(_) => Ty::Bool,
+            Lit::Char(_) => Ty::Char,
+            Lit::Byte(_) => Ty::Int(IntType::U8),
+            Lit::Str(_) => Ty::Ref(false, Box::new(Ty::Str)),
+            Lit::ByteStr(ref bytes) => {
+                let array = Ty::Array(Box::new(Ty::Int(IntType::U8)),
+                    Some(bytes.len() as u64));
+                Ty::Ref(false, Box::new(array))
+            }
+            Lit::CStr(_) => Ty::Error,
+            Lit::Int(_, Some(int)) => Ty::Int(int),
+            Lit::Int(_, None) => self.infcx.fresh_int(),
+            Lit::Float(_, Some(float)) => Ty::Float(float),
+            Lit::Float(_, None) => self.infcx.fresh_float(),
+        }
+    }
+
+    // The element type of an expected array or slice type.
+    fn expected_elem(&self, expected: Option<&Ty<'a>>) -> Option<Ty<'a>> {
+        match expected.map(|ty| self.infcx.shallow(ty)) {
+            Some(Ty::Array(elem, _)) | Some(Ty::Slice(elem)) => Some(*elem),
+            _ => None,
+        }
+    }
+
+    // Check the elements of an array, returning their common type.
+    fn check_elems<'e>(
+        &mut self,
+        exprs: impl Iterator<Item = &'e Expr<'a>>,
+        elem: Option<Ty<'a>>,
+    ) -> Ty<'a>
+    where
+        'a: 'e,
+    {
+        let mut common = elem.clone();
+        for expr in exprs {
+            match elem {
+                Some(ref elem) => {
+                    self.check_expr_coerce(expr, elem);
+                }
+                None => {
+                    let ty = self.check_expr(expr, common.as_ref());
+                    self.coerce_branch(&mut common, ty, expr.span);
+                }
+            }
+        }
+        common.unwrap_or_else(|| self.infcx.fresh())
+    }
+
+    /// Check a block, with the type its value is expected to have.
+    pub(super) fn check_block(
+        &mut self,
+        block: &Block<'a>,
+        expected: Option<&Ty<'a>>,
+    ) -> Ty<'a> {
+        let locals = self.locals.len();
+        let uses = self.scope.uses.len();
+        let mut diverges = false;
+        for stmt in &block.stmts {
+            let ty = match stmt.kind {
+                StmtKind::Let(ref local) => {
+                    let ty = match local.ty {
+                        Some(ref ty) => self.lower(ty),
+                        None => self.infcx.fresh(),
+                    };
+                    let init = match local.init {
+                        Some(ref init) => match local.ty {
+                            Some(_) => self.check_expr_coerce(init, &ty),
+                            None => {
+                                let found = self.check_expr(init, None);
+                                self.unify_or_error(init.span, &ty, &found);
+                                found
+                            }
+                        },
+                        None => Ty::unit(),
+                    };
+                    if let Some(ref diverge) = local.diverge {
+                        let ty = self.check_block(diverge, None);
+                        if self.infcx.shallow(&ty) != Ty::Never {
+                            self.error(diverge.span, "`else` clause of \
+                                `let...else` does not diverge");
+                        }
+                    }
+                    self.check_pat(&local.pattern, &ty);
+                    init
+                }
+                StmtKind::Item(ref item) => {
+                    match item.kind {
+                        ItemKind::Use(ref tree) => {
+                            self.add_use(tree, Vec::new(), false);
+                        }
+                        _ => self.error(item.span,
+                            "items inside function bodies are not supported"),
+                    }
+                    Ty::unit()
+                }
+                StmtKind::Expr(ref expr) => {
+                    let ty = self.check_expr(expr, Some(&Ty::unit()));
+                    if !self.try_coerce(&ty, &Ty::unit(), expr.span) {
+                        self.mismatch(expr.span, &Ty::unit(), &ty);
+                    }
+                    ty
+                }
+                StmtKind::Semi(ref expr) => self.check_expr(expr, None),
+            };
+            if self.infcx.shallow(&ty) == Ty::Never {
+                diverges = true;
+            }
+        }
+        let ty = match block.expr {
+            Some(ref expr) => match expected {
+                Some(expected) => self.check_expr_coerce(expr, expected),
+                None => self.check_expr(expr, None),
+            },
+            None if diverges => Ty::Never,
+            None => Ty::unit(),
+        };
+        self.locals.truncate(locals);
+        self.scope.uses.truncate(uses);
+        ty
+    }
+
+    // Add the names imported by a `use` in a function body.
+    fn add_use(
+        &mut self,
+        tree: &UseTree<'a>,
+        mut prefix: Vec<crate::rust::PathSegment<'a>>,
+        global: bool,
+    ) {
+        let global = global || tree.prefix.global;
+        prefix.extend(tree.prefix.segments.iter().cloned());
+        let path = Path { global, segments: prefix, span: tree.prefix.span };
+        let krate = self.cx.krate;
+        let module = self.scope.module;
+        match tree.kind {
+            UseTreeKind::Simple(rename) => {
+                let mut path = path;
+                if path.segments.len() > 1
+                    && path.segments.last().unwrap().name == "self"
+                {
+                    path.segments.pop();
+                }
+                let name = match rename {
+                    Some(rename) => rename,
+                    None => path.segments.last().unwrap().name,
+                };
+                let mut error = None;
+                let mut found = false;
+                for ns in [Namespace::Type, Namespace::Value, Namespace::Macro] {
+                    match krate.resolve_path(module, &path, ns) {
+                        Ok((res, len)) if len == path.segments.len() => {
+                            self.scope.uses.push((name, ns, res));
+                            found = true;
+                        }
+                        Ok(_) => {}
+                        Err(e) => error = error.or(Some(e)),
+                    }
+                }
+                match (found, error) {
+                    (false, Some(error)) => {
+                        self.cx.errors.borrow_mut().push(error)
+                    }
+                    (false, None) => self.error(path.span,
+                        format!("unresolved import `{}`", path)),
+                    _ => {}
+                }
+            }
+            UseTreeKind::Glob => {
+                let resolved = krate.resolve_path(module, &path, Namespace::Type);
+                let def = match resolved {
+                    Ok((Res::Def(def), len)) if len == path.segments.len() => def,
+                    Ok(_) => return,
+                    Err(error) => {
+                        self.cx.errors.borrow_mut().push(error);
+                        return;
+                    }
+                };
+                match krate.defs[def].kind {
+                    crate::rust::resolve::DefKind::Module(inner) => {
+                        let bindings = krate.modules[inner].bindings()
+                            .filter(|(_, _, binding)| {
+                                krate.is_visible(binding.vis, module)
+                            })
+                            .map(|(name, ns, binding)| {
+                                (name, ns, binding.res.clone())
+                            });
+                        self.scope.uses.extend(bindings.collect::<Vec<_>>());
+                    }
+                    _ => {
+                        let variants = krate.defs.iter().enumerate()
+                            .filter(|(_, variant)| matches!(variant.kind,
+                                crate::rust::resolve::DefKind::Variant(e, _)
+                                    if e == def));
+                        for (id, variant) in variants {
+                            for ns in [Namespace::Type, Namespace::Value] {
+                                let res = Res::Def(id);
+                                self.scope.uses.push((variant.name, ns, res));
+                            }
+                        }
+                    }
+                }
+            }
+            UseTreeKind::Nested(ref trees) => {
+                for tree in trees {
+                    self.add_use(tree, path.segments.clone(), global);
+                }
+            }
+        }
+    }
+
+    // Check the body of a loop, returning whether it is left by `break`.
+    fn check_loop_body(
+        &mut self,
+        label: Option<&'a str>,
+        ty: Option<Ty<'a>>,
+        body: &Block<'a>,
+    ) -> bool {
+        self.loops.push(LoopCx { label, ty, block: false, broken: false });
+        let ty = self.check_block(body, Some(&Ty::unit()));
+        self.coerce_or_error(body.span, &ty, &Ty::unit());
+        self.loops.pop().unwrap().broken
+    }
+
+    // Find the loop or block a `break` or `continue` refers to.
+    fn find_loop(&self, label: Option<&'a str>, span: Span) -> Option<usize> {
+        let index = match label {
+            Some(label) => {
+                self.loops.iter().rposition(|lp| lp.label == Some(label))
+            }
+            None => self.loops.iter().rposition(|lp| !lp.block),
+        };
+        if index.is_none() {
+            match label {
+                Some(label) => self.error(span,
+                    format!("use of undeclared label `'{}`", label)),
+                None => self.error(span, "`break` or `continue` outside of a \
+                    loop"),
+            }
+        }
+        index
+    }
+
+    fn check_break(
+        &mut self,
+        label: Option<&'a str>,
+        value: Option<&Expr<'a>>,
+        span: Span,
+    ) -> Ty<'a> {
+        let index = match self.find_loop(label, span) {
+            Some(index) => index,
+            None => {
+                if let Some(value) = value {
+                    self.check_expr(value, None);
+                }
+                return Ty::Never;
+            }
+        };
+        match (value, self.loops[index].ty.clone()) {
+            (Some(value), Some(ty)) => {
+                self.check_expr_coerce(value, &ty);
+            }
+            (Some(value), None) => {
+                self.check_expr(value, None);
+                self.error(span, "`break` with value from a `while` or `for` \
+                    loop");
+            }
+            (None, Some(ty)) => self.coerce_or_error(span, &Ty::unit(), &ty),
+            (None, None) => {}
+        }
+        self.loops[index].broken = true;
+        Ty::Never
+    }
+
+    fn check_if(
+        &mut self,
+        cond: &Expr<'a>,
+        then: &Block<'a>,
+        otherwise: Option<&Expr<'a>>,
+        expected: Option<&Ty<'a>>,
+    ) -> Ty<'a> {
+        let locals = self.locals.len();
+        self.check_expr_coerce(cond, &Ty::Bool);
+        let otherwise = match otherwise {
+            Some(otherwise) => otherwise,
+            None => {
+                let ty = self.check_block(then, Some(&Ty::unit()));
+                self.coerce_or_error(then.span, &ty, &Ty::unit());
+                self.locals.truncate(locals);
+                return Ty::unit();
+            }
+        };
+        if let Some(expected) = expected {
+            let ty = self.check_block(then, Some(expected));
+            self.coerce_or_error(then.span, &ty, expected);
+            self.locals.truncate(locals);
+            self.check_expr_coerce(otherwise, expected);
+            return expected.clone();
+        }
+        let mut common = None;
+        let ty = self.check_block(then, None);
+        self.coerce_branch(&mut common, ty, then.span);
+        self.locals.truncate(locals);
+        let ty = self.check_expr(otherwise, common.as_ref());
+        self.coerce_branch(&mut common, ty, otherwise.span);
+        common.unwrap_or(Ty::Never)
+    }
+
+    // The type of the items of an iterable value.
+    fn iterator_item(&mut self, ty: &Ty<'a>, span: Span) -> Ty<'a> {
+        let def = match self.cx.lang("iter::IntoIterator") {
+            Some(def) => def,
+            None => return Ty::Error,
+        };
+        let trait_ref = TraitRef { def, args: Vec::new() };
+        self.infcx.require(ty, trait_ref.clone(), span, Cause::Iterator);
+        let projection = Projection {
+            self_ty: ty.clone(),
+            trait_ref,
+            name: "Item",
+        };
+        self.infcx.normalize(&Ty::Projection(Box::new(projection)), span)
+    }
+
+    fn check_closure(
+        &mut self,
+        closure: &Closure<'a>,
+        expected: Option<&Ty<'a>>,
+    ) -> Ty<'a> {
+        let (expected_inputs, expected_output) = self.closure_sig(expected);
+        let locals = self.locals.len();
+        let mut inputs = Vec::new();
+        for (i, (pattern, ty)) in closure.params.iter().enumerate() {
+            let expected = expected_inputs.get(i);
+            let ty = match (ty, expected) {
+                (Some(ty), expected) => {
+                    let ty = self.lower(ty);
+                    if let Some(expected) = expected {
+                        self.unify_or_error(pattern.span, expected, &ty);
+                    }
+                    ty
+                }
+                (None, Some(expected)) => expected.clone(),
+                (None, None) => self.infcx.fresh(),
+            };
+            self.check_pat(pattern, &ty);
+            inputs.push(ty);
+        }
+        let output = match (&closure.ret, expected_output) {
+            (Some(ty), _) => self.lower(ty),
+            (None, Some(output)) => output,
+            (None, None) => self.infcx.fresh(),
+        };
+        let id = self.closures;
+        self.closures += 1;
+        let ret = self.ret_ty.replace(output.clone());
+        let loops = std::mem::take(&mut self.loops);
+        self.check_expr_coerce(&closure.body, &output);
+        self.ret_ty = ret;
+        self.loops = loops;
+        self.locals.truncate(locals);
+        Ty::Closure(id, inputs, Box::new(output))
+    }
+
+    // The parameter and return types of a closure from the type it is
+    // expected to have: a function pointer, or a variable with pending
+    // function trait obligations.
+    fn closure_sig(&self, expected: Option<&Ty<'a>>)
+        -> (Vec<Ty<'a>>, Option<Ty<'a>>)
+    {
+        let cx = self.cx;
+        let is_fn_trait = |def| {
+            ["ops::Fn", "ops::FnMut", "ops::FnOnce"].iter()
+                .any(|path| cx.is_lang(def, path))
+        };
+        let var = match expected.map(|ty| self.infcx.shallow(ty)) {
+            Some(Ty::Fn(inputs, output)) => return (inputs, Some(*output)),
+            Some(Ty::Dyn(bounds)) => {
+                let bound = bounds.iter()
+                    .find(|bound| is_fn_trait(bound.trait_ref.def));
+                return match bound {
+                    Some(bound) => {
+                        let inputs = match bound.trait_ref.args.first() {
+                            Some(Ty::Tuple(inputs)) => inputs.clone(),
+                            _ => Vec::new(),
+                        };
+                        let output = bound.bindings.iter()
+                            .find(|(name, _)| *name == "Output")
+                            .map(|(_, ty)| ty.clone());
+                        (inputs, output)
+                    }
+                    None => (Vec::new(), None),
+                };
+            }
+            Some(Ty::Var(var)) => var,
+            _ => return (Vec::new(), None),
+        };
+        let mut inputs = Vec::new();
+        let mut output = None;
+        for obligation in &self.infcx.pending {
+            let (self_ty, trait_ref) = match obligation.pred {
+                Pred::Trait(ref ty, ref trait_ref) => (ty, trait_ref),
+                Pred::Eq(ref projection, ref ty) => {
+                    if projection.name == "Output" {
+                        let self_ty = self.infcx.shallow(&projection.self_ty);
+                        if self_ty == Ty::Var(var)
+                            && is_fn_trait(projection.trait_ref.def)
+                        {
+                            output = Some(ty.clone());
+                        }
+                    }
+                    (&projection.self_ty, &projection.trait_ref)
+                }
+            };
+            if self.infcx.shallow(self_ty) != Ty::Var(var)
+                || !is_fn_trait(trait_ref.def)
+            {
+                continue;
+            }
+            if let Some(Ty::Tuple(args)) = trait_ref.args.first()
+                .map(|ty| self.infcx.shallow(ty))
+            {
+                inputs = args;
+            }
+        }
+        (inputs, output)
+    }
+
+    fn check_unary(&mut self, op: UnaryOp, operand: &Expr<'a>, span: Span)
+        -> Ty<'a>
+    {
+        let ty = self.check_expr(operand, None);
+        let shallow = self.infcx.shallow(&ty);
+        let (path, symbol) = match op {
+            UnaryOp::Neg => match shallow {
+                Ty::Int(int) if int.is_signed() => return ty,
+                Ty::IntVar(_) | Ty::Float(_) | Ty::FloatVar(_) => return ty,
+                _ => ("ops::Neg", "-"),
+            },
+            UnaryOp::Not => match shallow {
+                Ty::Bool | Ty::Int(_) | Ty::IntVar(_) => return ty,
+                _ => ("ops::Not", "!"),
+            },
+            UnaryOp::Deref => match shallow {
+                Ty::Ref(_, inner) | Ty::Ptr(_, inner) => return *inner,
+                Ty::Adt(def, args) if self.cx.is_lang(def, "boxed::Box") => {
+                    return args[0].clone();
+                }
+                _ => ("ops::Deref", "*"),
+            },
+        };
+        let def = match self.cx.lang(path) {
+            Some(def) => def,
+            None => return Ty::Error,
+        };
+        let trait_ref = TraitRef { def, args: Vec::new() };
+        self.infcx.require(&ty, trait_ref.clone(), span, Cause::Unary(symbol));
+        let name = match op {
+            UnaryOp::Deref => "Target",
+            _ => "Output",
+        };
+        let projection = Projection { self_ty: ty, trait_ref, name };
+        self.infcx.normalize(&Ty::Projection(Box::new(projection)), span)
+    }
+
+    fn check_binary(
+        &mut self,
+        op: BinaryOp,
+        lhs: &Expr<'a>,
+        rhs: &Expr<'a>,
+        span: Span,
+    ) -> Ty<'a> {
+        if let BinaryOp::And | BinaryOp::Or = op {
+            self.check_expr_coerce(lhs, &Ty::Bool);
+            self.check_expr_coerce(rhs, &Ty::Bool);
+            return Ty::Bool;
+        }
+        let is_cmp = matches!(op, BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt
+            | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge);
+        let path = match op {
+            BinaryOp::Add => "ops::Add",
+            BinaryOp::Sub => "ops::Sub",
+            BinaryOp::Mul => "ops::Mul",
+            BinaryOp::Div => "ops::Div",
+            BinaryOp::Rem => "ops::Rem",
+            BinaryOp::BitAnd => "ops::BitAnd",
+            BinaryOp::BitOr => "ops::BitOr",
+            BinaryOp::BitXor => "ops::BitXor",
+            BinaryOp::Shl => "ops::Shl",
+            BinaryOp::Shr => "ops::Shr",
+            BinaryOp::Eq | BinaryOp::Ne => "cmp::PartialEq",
+            _ => "cmp::PartialOrd",
+        };
+        let lhs_ty = self.check_expr(lhs, None);
+        let trait_ref = match self.check_rhs(op, &lhs_ty, rhs, path, span) {
+            Some(trait_ref) => trait_ref,
+            None if is_cmp => return Ty::Bool,
+            None => return lhs_ty,
+        };
+        if is_cmp {
+            return Ty::Bool;
+        }
+        let projection = Projection {
+            self_ty: lhs_ty,
+            trait_ref,
+            name: "Output",
+        };
+        self.infcx.normalize(&Ty::Projection(Box::new(projection)), span)
+    }
+
+    // Check the right operand of a binary operator, returning the trait the
+    // operator is implemented with unless it is built in.  The trait is
+    // required before the operand is checked, so that the operand can be
+    // coerced to the type the impl takes, like `&str` for `String + &s`.
+    fn check_rhs(
+        &mut self,
+        op: BinaryOp,
+        lhs_ty: &Ty<'a>,
+        rhs: &Expr<'a>,
+        path: &'static str,
+        span: Span,
+    ) -> Option<TraitRef<'a>> {
+        let scalar = matches!(self.infcx.shallow(lhs_ty), Ty::Int(_)
+            | Ty::IntVar(_) | Ty::Float(_) | Ty::FloatVar(_) | Ty::Bool
+            | Ty::Char | Ty::Ptr(..) | Ty::Var(_) | Ty::Never | Ty::Error);
+        let def = self.cx.lang(path)?;
+        if !scalar {
+            let rhs_ty = self.infcx.fresh();
+            let trait_ref = TraitRef { def, args: vec![rhs_ty.clone()] };
+            self.infcx.require(lhs_ty, trait_ref.clone(), span,
+                Cause::Binary(op));
+            self.infcx.select_pending();
+            self.check_expr_coerce(rhs, &rhs_ty);
+            return Some(trait_ref);
+        }
+        let rhs_ty = self.check_expr(rhs, Some(lhs_ty));
+        if self.is_builtin_binary(op, lhs_ty, &rhs_ty) {
+            if !matches!(op, BinaryOp::Shl | BinaryOp::Shr) {
+                self.unify_or_error(rhs.span, lhs_ty, &rhs_ty);
+            }
+            return None;
+        }
+        let trait_ref = TraitRef { def, args: vec![rhs_ty] };
+        self.infcx.require(lhs_ty, trait_ref.clone(), span, Cause::Binary(op));
+        Some(trait_ref)
+    }
+
+    // Whether a binary operator is built in for its operand types.
+    fn is_builtin_binary(
+        &self,
+        op: BinaryOp,
+        lhs: &Ty<'a>,
+        rhs: &Ty<'a>,
+    ) -> bool {
+        let lhs = self.infcx.shallow(lhs);
+        let rhs = self.infcx.shallow(rhs);
+        let int = |ty: &Ty| matches!(ty, Ty::Int(_) | Ty::IntVar(_));
+        let float = |ty: &Ty| matches!(ty, Ty::Float(_) | Ty::FloatVar(_));
+        let both = |f: &dyn Fn(&Ty) -> bool| f(&lhs) && f(&rhs);
+        match op {
+            BinaryOp::Add
+            | BinaryOp::Sub
+            | BinaryOp::Mul
+            | BinaryOp::Div
+            | BinaryOp::Rem => both(&int) || both(&float),
+            BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor => {
+                both(&int) || both(&|ty| *ty == Ty::Bool)
+            }
+            BinaryOp::Shl | BinaryOp::Shr => both(&int),
+            _ => {
+                both(&int)
+                    || both(&float)
+                    || both(&|ty| matches!(ty, Ty::Bool | Ty::Char | Ty::Ptr(..)))
+            }
+        }
+    }
+
+    fn check_assign_op(
+        &mut self,
+        op: BinaryOp,
+        lhs: &Expr<'a>,
+        rhs: &Expr<'a>,
+        span: Span,
+    ) -> Ty<'a> {
+        let path = match op {
+            BinaryOp::Add => "ops::AddAssign",
+            BinaryOp::Sub => "ops::SubAssign",
+            BinaryOp::Mul => "ops::MulAssign",
+            BinaryOp::Div => "ops::DivAssign",
+            BinaryOp::Rem => "ops::RemAssign",
+            BinaryOp::BitAnd => "ops::BitAndAssign",
+            BinaryOp::BitOr => "ops::BitOrAssign",
+            BinaryOp::BitXor => "ops::BitXorAssign",
+            BinaryOp::Shl => "ops::ShlAssign",
+            _ => "ops::ShrAssign",
+        };
+        let lhs_ty = self.check_expr(lhs, None);
+        self.check_rhs(op, &lhs_ty, rhs, path, span);
+        Ty::unit()
+    }
+
+    fn check_cast(
+        &mut self,
+        operand: &Expr<'a>,
+        ty: &crate::rust::Type<'a>,
+        span: Span,
+    ) -> Ty<'a> {
+        let target = self.lower(ty);
+        let found = self.check_expr(operand, None);
+        let from = self.infcx.resolve(&found);
+        let to = self.infcx.resolve(&target);
+        let numeric = |ty: &Ty| matches!(ty, Ty::Int(_) | Ty::IntVar(_)
+            | Ty::Float(_) | Ty::FloatVar(_));
+        let int = |ty: &Ty| matches!(ty, Ty::Int(_) | Ty::IntVar(_));
+        let fieldless_enum = match from {
+            Ty::Adt(def, _) => {
+                let adt = self.cx.adt(def);
+                adt.kind == AdtKind::Enum
+                    && adt.variants.iter().all(|v| v.fields.is_empty())
+            }
+            _ => false,
+        };
+        let valid = match (&from, &to) {
+            (Ty::Var(_), _) | (_, Ty::Var(_)) => true,
+            (Ty::Error, _) | (_, Ty::Error) | (Ty::Never, _) => true,
+            (from, to) if numeric(from) && numeric(to) => true,
+            (Ty::Bool, to) | (Ty::Char, to) if int(to) => true,
+            (Ty::Int(IntType::U8), Ty::Char) | (Ty::IntVar(_), Ty::Char) => true,
+            (Ty::Ptr(..), Ty::Ptr(..)) | (Ty::Ref(..), Ty::Ptr(..)) => true,
+            (Ty::Fn(..), Ty::Ptr(..)) => true,
+            (Ty::Ptr(..), to) | (Ty::Fn(..), to) if int(to) => true,
+            (from, Ty::Ptr(..)) if int(from) => true,
+            (_, to) if fieldless_enum && int(to) => true,
+            _ => false,
+        };
+        if !valid && !self.try_coerce(&found, &target, span) {
+            self.error(span, format!("non-primitive cast: `{}` as `{}`",
+                self.ty_name(&found), self.ty_name(&target)));
+        }
+        target
+    }
+
+    fn check_call(
+        &mut self,
+        callee: &Expr<'a>,
+        args: &[Expr<'a>],
+        expected: Option<&Ty<'a>>,
+        span: Span,
+    ) -> Ty<'a> {
+        let ty = self.check_expr(callee, None);
+        for step in self.autoderef(&ty, callee.span) {
+            match step {
+                Ty::Fn(mut inputs, output)
+                | Ty::Closure(_, mut inputs, output) => {
+                    // The extra arguments of C variadic functions are only
+                    // checked on their own.
+                    if self.is_variadic(callee) {
+                        while inputs.len() < args.len() {
+                            inputs.push(self.infcx.fresh());
+                        }
+                    }
+                    self.check_args(&inputs, &output, expected, args, span);
+                    return *output;
+                }
+                Ty::Error => {
+                    self.check_exprs(args);
+                    return Ty::Error;
+                }
+                Ty::Var(_) => break,
+                Ty::Param(..) | Ty::Dyn(_) | Ty::Opaque(_)
+                | Ty::Projection(_) => {
+                    let def = match self.cx.lang("ops::FnOnce") {
+                        Some(def) => def,
+                        None => break,
+                    };
+                    let inputs: Vec<_> = args.iter()
+                        .map(|_| self.infcx.fresh())
+                        .collect();
+                    let trait_ref = TraitRef {
+                        def,
+                        args: vec![Ty::Tuple(inputs.clone())],
+                    };
+                    if !self.infcx.may_implement(&step, &trait_ref) {
+                        continue;
+                    }
+                    self.infcx.require(&step, trait_ref.clone(), span,
+                        Cause::Misc);
+                    self.infcx.select_pending();
+                    let projection = Projection {
+                        self_ty: step,
+                        trait_ref,
+                        name: "Output",
+                    };
+                    let output = self.infcx.normalize(
+                        &Ty::Projection(Box::new(projection)), span);
+                    self.check_args(&inputs, &output, expected, args, span);
+                    return output;
+                }
+                _ => {}
+            }
+        }
+        self.error(callee.span,
+            format!("expected function, found `{}`", self.ty_name(&ty)));
+        self.check_exprs(args);
+        Ty::Error
+    }
+
+    // Whether a callee is a path to a C variadic function.
+    fn is_variadic(&self, callee: &Expr<'a>) -> bool {
+        let path = match callee.kind {
+            ExprKind::Path(ref path) => path,
+            _ => return false,
+        };
+        let krate = self.cx.krate;
+        match krate.resolve_path(self.scope.module, path, Namespace::Value) {
+            Ok((Res::Def(def), len)) if len == path.segments.len() => {
+                matches!(krate.item(def).map(|item| &item.kind),
+                    Some(ItemKind::Fn(_)))
+                    && self.cx.fn_sig(ItemId::Def(def)).variadic
+            }
+            _ => false,
+        }
+    }
+
+    /// Check the arguments of a call, closures last.  The type the call is
+    /// expected to have gives the types expected of the arguments, which
+    /// they are coerced to if they can be.
+    pub(super) fn check_args(
+        &mut self,
+        inputs: &[Ty<'a>],
+        output: &Ty<'a>,
+        expected: Option<&Ty<'a>>,
+        args: &[Expr<'a>],
+        span: Span,
+    ) {
+        if inputs.len() != args.len() {
+            let s = if inputs.len() == 1 { "" } else { "s" };
+            let supplied = match args.len() {
+                1 => "argument was",
+                _ => "arguments were",
+            };
+            self.error(span, format!(
+                "this function takes {} argument{} but {} {} supplied",
+                inputs.len(), s, args.len(), supplied));
+        }
+        let hints = match expected {
+            Some(expected) => self.infcx.probe(|infcx| {
+                match infcx.unify(output, expected) {
+                    true => inputs.iter().map(|ty| infcx.resolve(ty)).collect(),
+                    false => Vec::new(),
+                }
+            }),
+            None => Vec::new(),
+        };
+        for closures in [false, true] {
+            for (i, arg) in args.iter().enumerate() {
+                if matches!(arg.kind, ExprKind::Closure(_)) != closures {
+                    continue;
+                }
+                let input = match inputs.get(i) {
+                    Some(input) => input,
+                    None => {
+                        self.check_expr(arg, None);
+                        continue;
+                    }
+                };
+                let hint = hints.get(i).map(|ty| self.infcx.shallow(ty));
+                let target = match hint {
+                    Some(Ty::Var(_)) | Some(Ty::Dyn(_)) | Some(Ty::Slice(_))
+                    | Some(Ty::Str) | None => input.clone(),
+                    Some(hint) => hint,
+                };
+                let found = self.check_expr(arg, hints.get(i).or(Some(input)));
+                self.coerce_or_error(arg.span, &found, &target);
+                if target != *input {
+                    self.unify_or_error(arg.span, input, &target);
+                }
+            }
+            if !closures {
+                self.infcx.select_pending();
+            }
+        }
+    }
+
+    /// Check the arguments of a call to something that isn't known to be
+    /// callable.
+    pub(super) fn check_exprs(&mut self, args: &[Expr<'a>]) {
+        for arg in args {
+            self.check_expr(arg, None);
+        }
+    }
+
+    fn check_method_call(
+        &mut self,
+        receiver: &Ty<'a>,
+        receiver_span: Span,
+        segment: &crate::rust::PathSegment<'a>,
+        args: &[Expr<'a>],
+        expected: Option<&Ty<'a>>,
+        span: Span,
+    ) -> Ty<'a> {
+        let pick = self.lookup_method(receiver, segment.name, receiver_span);
+        let pick = match pick {
+            Some(pick) => pick,
+            None => {
+                self.check_exprs(args);
+                return Ty::Error;
+            }
+        };
+        let (inputs, output, method) = self.confirm_method(&pick, segment, span);
+        self.methods.push((span, method));
+        self.check_args(&inputs, &output, expected, args, span);
+        output
+    }
+
+    fn check_field(&mut self, base: &Expr<'a>, name: &'a str, span: Span)
+        -> Ty<'a>
+    {
+        let ty = self.check_expr(base, None);
+        if let Ty::Var(_) = self.infcx.shallow(&ty) {
+            self.infcx.select_pending();
+        }
+        for (derefs, step) in self.autoderef(&ty, span).into_iter().enumerate() {
+            match step {
+                Ty::Tuple(ref tys) => {
+                    if let Some(ty) = name.parse::<usize>().ok()
+                        .and_then(|index| tys.get(index))
+                    {
+                        return ty.clone();
+                    }
+                }
+                Ty::Adt(def, ref args) => {
+                    let adt = self.cx.adt(def);
+                    if adt.kind == AdtKind::Enum {
+                        continue;
+                    }
+                    let variant = &adt.variants[0];
+                    if let Some(index) = variant.field(name) {
+                        let field = &variant.fields[index];
+                        let module = self.scope.module;
+                        if !self.cx.krate.is_visible(field.vis, module) {
+                            self.error(span, format!(
+                                "field `{}` of struct `{}` is private", name,
+                                variant.name));
+                        }
+                        let ty = field.ty.subst(args);
+                        return self.infcx.normalize(&ty, span);
+                    }
+                }
+                Ty::Var(_) if derefs == 0 => {
+                    self.error(base.span, "type annotations needed");
+                    return Ty::Error;
+                }
+                Ty::Error => return Ty::Error,
+                _ => {}
+            }
+        }
+        self.error(span, format!("no field `{}` on type `{}`", name,
+            self.ty_name(&ty)));
+        Ty::Error
+    }
+
+    fn check_index(&mut self, base: &Expr<'a>, index: &Expr<'a>, span: Span)
+        -> Ty<'a>
+    {
+        let ty = self.check_expr(base, None);
+        let index_ty = self.check_expr(index, None);
+        let def = self.cx.lang("ops::Index");
+        for step in self.autoderef(&ty, span) {
+            let index = self.infcx.shallow(&index_ty);
+            match step {
+                Ty::Array(ref elem, _) | Ty::Slice(ref elem)
+                    if matches!(index, Ty::Int(IntType::Usize) | Ty::IntVar(_)) =>
+                {
+                    self.infcx.unify(&index, &Ty::Int(IntType::Usize));
+                    return (**elem).clone();
+                }
+                Ty::Var(_) => break,
+                Ty::Error => return Ty::Error,
+                _ => {}
+            }
+            let def = match def {
+                Some(def) => def,
+                None => return Ty::Error,
+            };
+            let trait_ref = TraitRef { def, args: vec![index_ty.clone()] };
+            if self.infcx.may_implement(&step, &trait_ref) {
+                self.infcx.require(&step, trait_ref.clone(), span, Cause::Index);
+                let projection = Projection {
+                    self_ty: step,
+                    trait_ref,
+                    name: "Output",
+                };
+                return self.infcx.normalize(&Ty::Projection(Box::new(projection)),
+                    span);
+            }
+        }
+        let index = self.infcx.resolve(&index_ty);
+        if index != Ty::Error {
+            self.error(span, format!("the type `{}` cannot be indexed by `{}`",
+                self.ty_name(&ty), index.name(self.cx.krate)));
+        }
+        Ty::Error
+    }
+
+    fn check_try(&mut self, operand: &Expr<'a>, span: Span) -> Ty<'a> {
+        let ty = self.check_expr(operand, None);
+        if let Ty::Var(_) = self.infcx.shallow(&ty) {
+            self.infcx.select_pending();
+        }
+        let ret = self.ret_ty.clone()
+            .map(|ret| self.infcx.shallow(&ret))
+            .unwrap_or(Ty::Error);
+        let cx = self.cx;
+        match self.infcx.shallow(&ty) {
+            Ty::Adt(def, args) if cx.is_lang(def, "option::Option") => {
+                match ret {
+                    Ty::Adt(ret, _) if ret == def => {}
+                    Ty::Var(_) => {
+                        let option = Ty::Adt(def, vec![self.infcx.fresh()]);
+                        self.infcx.unify(&ret, &option);
+                    }
+                    Ty::Error | Ty::Opaque(_) => {}
+                    _ => self.error(span, "the `?` operator can only be used \
+                        on `Option`s in a function that returns `Option`"),
+                }
+                args[0].clone()
+            }
+            Ty::Adt(def, args) if cx.is_lang(def, "result::Result") => {
+                match ret {
+                    Ty::Adt(ret, ref ret_args) if ret == def => {
+                        if let Some(from) = cx.lang("convert::From") {
+                            let trait_ref = TraitRef {
+                                def: from,
+                                args: vec![args[1].clone()],
+                            };
+                            self.infcx.require(&ret_args[1], trait_ref, span,
+                                Cause::Misc);
+                        }
+                    }
+                    Ty::Var(_) => {
+                        let result = Ty::Adt(def,
+                            vec![self.infcx.fresh(), args[1].clone()]);
+                        self.infcx.unify(&ret, &result);
+                    }
+                    Ty::Error | Ty::Opaque(_) => {}
+                    _ => self.error(span, "the `?` operator can only be used \
+                        on `Result`s in a function that returns `Result`"),
+                }
+                args[0].clone()
+            }
+            Ty::Error => Ty::Error,
+            Ty::Var(_) => {
+                self.error(operand.span, "type annotations needed");
+                Ty::Error
+            }
+            _ => {
+                self.error(span, format!("the `?` operator can only be applied \
+                    to values that implement `Try`, not `{}`",
+                    self.ty_name(&ty)));
+                Ty::Error
+            }
+        }
+    }
+
+    fn check_range(
+        &mut self,
+        start: Option<&Expr<'a>>,
+        end: Option<&Expr<'a>>,
+        inclusive: bool,
+    ) -> Ty<'a> {
+        let path = match (start, end, inclusive) {
+            (Some(_), Some(_), false) => "ops::Range",
+            (Some(_), Some(_), true) => "ops::RangeInclusive",
+            (Some(_), None, _) => "ops::RangeFrom",
+            (None, Some(_), false) => "ops::RangeTo",
+            (None, Some(_), true) => "ops::RangeToInclusive",
+            (None, None, _) => return self.lang_ty("ops::RangeFull", Vec::new()),
+        };
+        let idx = self.infcx.fresh();
+        for expr in start.into_iter().chain(end) {
+            let ty = self.check_expr(expr, Some(&idx));
+            self.unify_or_error(expr.span, &idx, &ty);
+        }
+        self.lang_ty(path, vec![idx])
+    }
+
+    fn check_struct(
+        &mut self,
+        path: &Path<'a>,
+        fields: &[FieldInit<'a>],
+        base: Option<&Expr<'a>>,
+        span: Span,
+    ) -> Ty<'a> {
+        let variant = self.resolve_variant(path, Namespace::Type);
+        let (def, index, args) = match variant {
+            Some(variant) => variant,
+            None => {
+                for field in fields {
+                    self.check_expr(&field.value, None);
+                }
+                if let Some(base) = base {
+                    self.check_expr(base, None);
+                }
+                return Ty::Error;
+            }
+        };
+        let adt = self.cx.adt(def);
+        let variant = &adt.variants[index];
+        let mut seen = Vec::new();
+        for field in fields {
+            let index = match variant.field(field.name) {
+                Some(index) => index,
+                None => {
+                    self.error(field.span, format!(
+                        "`{}` has no field named `{}`", variant.name,
+                        field.name));
+                    self.check_expr(&field.value, None);
+                    continue;
+                }
+            };
+            if seen.contains(&index) {
+                self.error(field.span, format!(
+                    "field `{}` specified more than once", field.name));
+            }
+            seen.push(index);
+            let def = &variant.fields[index];
+            if !self.cx.krate.is_visible(def.vis, self.scope.module) {
+                self.error(field.span, format!(
+                    "field `{}` of struct `{}` is private", field.name,
+                    variant.name));
+            }
+            let ty = self.infcx.normalize(&def.ty.subst(&args), field.span);
+            self.check_expr_coerce(&field.value, &ty);
+        }
+        let ty = Ty::Adt(def, args);
+        match base {
+            Some(base) => {
+                self.check_expr_coerce(base, &ty);
+            }
+            None if adt.kind != AdtKind::Union => {
+                let missing: Vec<_> = variant.fields.iter().enumerate()
+                    .filter(|(index, _)| !seen.contains(index))
+                    .map(|(index, field)| match field.name {
+                        Some(name) => format!("`{}`", name),
+                        None => format!("`{}`", index),
+                    })
+                    .collect();
+                match missing.len() {
+                    0 => {}
+                    1 => self.error(span, format!(
+                        "missing field {} in initializer of `{}`", missing[0],
+                        variant.name)),
+                    _ => self.error(span, format!(
+                        "missing fields {} in initializer of `{}`",
+                        missing.join(", "), variant.name)),
+                }
+            }
+            None => {
+                if fields.len() != 1 {
+                    self.error(span, "union expressions should have exactly \
+                        one field");
+                }
+            }
+        }
+        ty
+    }
+
+    /// The type of a constructor of a struct or enum variant: a function
+    /// for tuple constructors.
+    pub(super) fn ctor_ty(
+        &mut self,
+        def: crate::rust::resolve::DefId,
+        index: usize,
+        args: Vec<Ty<'a>>,
+        span: Span,
+    ) -> Ty<'a> {
+        let adt = self.cx.adt(def);
+        let variant = &adt.variants[index];
+        let ty = Ty::Adt(def, args.clone());
+        match variant.ctor {
+            CtorKind::Unit => ty,
+            CtorKind::Tuple => {
+                let module = self.scope.module;
+                let private = variant.fields.iter()
+                    .any(|field| !self.cx.krate.is_visible(field.vis, module));
+                if private {
+                    self.error(span, format!("cannot initialize a tuple \
+                        struct which contains private fields: `{}`",
+                        variant.name));
+                }
+                let inputs = variant.fields.iter()
+                    .map(|field| {
+                        self.infcx.normalize(&field.ty.subst(&args), span)
+                    })
+                    .collect();
+                Ty::Fn(inputs, Box::new(ty))
+            }
+            CtorKind::Named => {
+                self.error(span, format!(
+                    "expected value, found struct variant `{}`", variant.name));
+                Ty::Error
+            }
+        }
+    }
+
+    fn check_macro(&mut self, call: &MacroCall<'a>, expected: Option<&Ty<'a>>)
+        -> Ty<'a>
+    {
+        let path = match self.cx.resolve(&self.scope, &call.path,
+            Namespace::Macro)
+        {
+            Some((Res::External(path), _)) => path,
+            // Calls to macros defined in the crate are expanded before
+            // parsing, so this is one whose expansion failed.
+            _ => return Ty::Error,
+        };
+        let name = match path.strip_prefix("std::") {
+            Some(name) => name,
+            None => return Ty::Error,
+        };
+        let str_ref = Ty::Ref(false, Box::new(Ty::Str));
+        let ty = match name {
+            "stringify" | "concat" | "file" | "module_path" | "env"
+            | "include_str" => return str_ref,
+            "line" | "column" => return Ty::Int(IntType::U32),
+            "option_env" => return self.lang_ty("option::Option", vec![str_ref]),
+            "include_bytes" => {
+                let bytes = Ty::Array(Box::new(Ty::Int(IntType::U8)), None);
+                return Ty::Ref(false, Box::new(bytes));
+            }
+            "cfg" | "matches" => return Ty::Bool,
+            "print" | "println" | "eprint" | "eprintln" | "assert"
+            | "debug_assert" | "assert_eq" | "assert_ne" | "debug_assert_eq"
+            | "debug_assert_ne" => Ty::unit(),
+            "format" => self.lang_ty("string::String", Vec::new()),
+            "format_args" => self.lang_ty("fmt::Arguments", Vec::new()),
+            "panic" | "unreachable" | "todo" | "unimplemented"
+            | "compile_error" => Ty::Never,
+            "vec" | "dbg" | "write" | "writeln" => Ty::Error,
+            _ => return Ty::Error,
+        };
+        let text = self.cx.krate.text(self.scope.module);
+        let args = match call.args(text) {
+            Ok(args) => args,
+            Err(diagnostic) => {
+                self.cx.errors.borrow_mut().push(
+                    crate::rust::resolve::FileDiagnostic {
+                        file: self.file,
+                        diagnostic,
+                    });
+                return ty;
+            }
+        };
+        match name {
+            "print" | "println" | "eprint" | "eprintln" | "format"
+            | "format_args" | "panic" | "unreachable" | "todo"
+            | "unimplemented" => {
+                self.check_format(&args, call.span);
+                ty
+            }
+            "assert" | "debug_assert" => {
+                match args.split_first() {
+                    Some((cond, rest)) => {
+                        self.check_expr_coerce(cond, &Ty::Bool);
+                        self.check_format(rest, call.span);
+                    }
+                    None => self.error(call.span,
+                        "macro requires a boolean expression as an argument"),
+                }
+                ty
+            }
+            "assert_eq" | "assert_ne" | "debug_assert_eq" | "debug_assert_ne" => {
+                if args.len() < 2 {
+                    self.error(call.span, "macro requires two arguments");
+                    return ty;
+                }
+                let lhs = self.check_expr(&args[0], None);
+                let rhs = self.check_expr(&args[1], Some(&lhs));
+                if let Some(def) = self.cx.lang("cmp::PartialEq") {
+                    let trait_ref = TraitRef { def, args: vec![rhs.clone()] };
+                    self.infcx.require(&lhs, trait_ref, call.span,
+                        Cause::Binary(BinaryOp::Eq));
+                }
+                self.require_fmt(&lhs, "fmt::Debug", args[0].span);
+                self.require_fmt(&rhs, "fmt::Debug", args[1].span);
+                self.check_format(&args[2..], call.span);
+                ty
+            }
+            "compile_error" => {
+                match args.first().map(|arg| &arg.kind) {
+                    Some(ExprKind::Lit(Lit::Str(message))) => {
+                        self.error(call.span, message.clone());
+                    }
+                    _ => self.error(call.span,
+                        "argument must be a string literal"),
+                }
+                ty
+            }
+            "vec" => {
+                let elem = match expected.map(|ty| self.infcx.shallow(ty)) {
+                    Some(Ty::Adt(def, args))
+                        if self.cx.is_lang(def, "vec::Vec") =>
+                    {
+                        Some(args[0].clone())
+                    }
+                    _ => None,
+                };
+                let elem = match args.first().map(|arg| &arg.kind) {
+                    Some(ExprKind::Repeat(value, count)) if args.len() == 1 => {
+                        let elem = elem.unwrap_or_else(|| self.infcx.fresh());
+                        self.check_expr_coerce(value, &elem);
+                        self.check_expr_coerce(count, &Ty::Int(IntType::Usize));
+                        elem
+                    }
+                    _ => self.check_elems(args.iter(), elem),
+                };
+                self.lang_ty("vec::Vec", vec![elem])
+            }
+            "dbg" => {
+                let tys: Vec<_> = args.iter()
+                    .map(|arg| {
+                        let ty = self.check_expr(arg, None);
+                        self.require_fmt(&ty, "fmt::Debug", arg.span);
+                        ty
+                    })
+                    .collect();
+                match tys.len() {
+                    1 => tys.into_iter().next().unwrap(),
+                    _ => Ty::Tuple(tys),
+                }
+            }
+            _ => {
+                // `write!` and `writeln!` call `write_fmt` on their first
+                // argument.
+                let (writer, rest) = match args.split_first() {
+                    Some(split) => split,
+                    None => {
+                        self.error(call.span, "macro requires a destination");
+                        return Ty::Error;
+                    }
+                };
+                let writer_ty = self.check_expr(writer, None);
+                self.check_format(rest, call.span);
+                let pick = self.lookup_method(&writer_ty, "write_fmt",
+                    writer.span);
+                match pick {
+                    Some(pick) => {
+                        let segment = crate::rust::PathSegment {
+                            name: "write_fmt",
+                            generics: Vec::new(),
+                            span: call.span,
+                        };
+                        self.confirm_method(&pick, &segment, call.span).1
+                    }
+                    None => Ty::Error,
+                }
+            }
+        }
+    }
+
+    fn require_fmt(&mut self, ty: &Ty<'a>, path: &'static str, span: Span) {
+        if let Some(def) = self.cx.lang(path) {
+            let trait_ref = TraitRef { def, args: Vec::new() };
+            self.infcx.require(ty, trait_ref, span, Cause::Misc);
+        }
+    }
+
+    // Check a format string and its arguments, requiring the traits its
+    // placeholders format the arguments with.
+    fn check_format(&mut self, args: &[Expr<'a>], span: Span) {
+        let (format, args) = match args.split_first() {
+            Some(split) => split,
+            None => return,
+        };
+        let string = match format.kind {
+            ExprKind::Lit(Lit::Str(ref string)) => string,
+            _ => {
+                self.check_expr(format, None);
+                for arg in args {
+                    self.check_expr(arg, None);
+                }
+                return;
+            }
+        };
+        let mut positional = Vec::new();
+        let mut named = Vec::new();
+        for arg in args {
+            match arg.kind {
+                ExprKind::Assign(ref name, ref value) => {
+                    let name = match name.kind {
+                        ExprKind::Path(ref path) if path.segments.len() == 1 => {
+                            path.segments[0].name
+                        }
+                        _ => {
+                            self.error(name.span, "invalid named argument");
+                            continue;
+                        }
+                    };
+                    let ty = self.check_expr(value, None);
+                    named.push((name, ty, value.span));
+                }
+                _ => {
+                    let ty = self.check_expr(arg, None);
+                    positional.push((ty, arg.span));
+                }
+            }
+        }
+        let placeholders = match placeholders(string) {
+            Some(placeholders) => placeholders,
+            None => {
+                self.error(format.span, "invalid format string");
+                return;
+            }
+        };
+        let mut next = 0;
+        let mut used = vec![false; positional.len()];
+        for (arg, path) in placeholders {
+            let (ty, arg_span) = match arg {
+                FormatArg::Next | FormatArg::Index(_) => {
+                    let index = match arg {
+                        FormatArg::Index(index) => index,
+                        _ => {
+                            next += 1;
+                            next - 1
+                        }
+                    };
+                    match positional.get(index) {
+                        Some((ty, span)) => {
+                            used[index] = true;
+                            (ty.clone(), *span)
+                        }
+                        None => {
+                            let s = if positional.len() == 1 { "" } else { "s" };
+                            self.error(format.span, format!(
+                                "invalid reference to positional argument {} \
+                                    (there {} {} argument{})", index,
+                                if positional.len() == 1 { "is" } else { "are" },
+                                positional.len(), s));
+                            continue;
+                        }
+                    }
+                }
+                FormatArg::Name(name) => {
+                    let found = named.iter()
+                        .find(|(named, _, _)| *named == name)
+                        .map(|(_, ty, span)| (ty.clone(), *span));
+                    let local = self.locals.iter().rev()
+                        .find(|(local, _)| *local == name)
+                        .map(|(_, ty)| (ty.clone(), format.span));
+                    match found.or(local) {
+                        Some(found) => found,
+                        None => {
+                            self.error(format.span, format!(
+                                "cannot find value `{}` in this scope", name));
+                            continue;
+                        }
+                    }
+                }
+            };
+            self.require_fmt(&ty, path, arg_span);
+        }
+        for (index, used) in used.into_iter().enumerate() {
+            if !used {
+                self.error(positional[index].1, "argument never used");
+            }
+        }
+        let _ = span;
+    }
+}
+
+/// The argument a placeholder of a format string refers to
+pub(in crate::rust) enum FormatArg<'s> {
+    Next,
+    Index(usize),
+    Name(&'s str),
+}
+
+// Parse the placeholders of a format string, with the trait each formats its
+// argument with, or return `None` if the string is invalid.
+pub(in crate::rust) fn placeholders(string: &str)
+    -> Option<Vec<(FormatArg<'_>, &'static str)>>
+{
+    let mut out = Vec::new();
+    let mut chars = string.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '{' if chars.peek().map(|(_, c)| *c) == Some('{') => {
+                chars.next();
+            }
+            '}' if chars.peek().map(|(_, c)| *c) == Some('}') => {
+                chars.next();
+            }
+            '}' => return None,
+            '{' => {
+                let end = chars.find(|(_, c)| *c == '}')?.0;
+                let inner = &string[start + 1..end];
+                let (arg, spec) = match inner.find(':') {
+                    Some(colon) => (&inner[..colon], &inner[colon + 1..]),
+                    None => (inner, ""),
+                };
+                let arg = arg.trim();
+                let arg = if arg.is_empty() {
+                    FormatArg::Next
+                } else if let Ok(index) = arg.parse() {
+                    FormatArg::Index(index)
+                } else {
+                    FormatArg::Name(arg)
+                };
+                let path = match spec.chars().last() {
+                    Some('?') => "fmt::Debug",
+                    Some('x') => "fmt::LowerHex",
+                    Some('X') => "fmt::UpperHex",
+                    Some('b') => "fmt::Binary",
+                    _ => "fmt::Display",
+                };
+                out.push((arg, path));
+            }
+            _ => {}
+        }
+    }
+    Some(out)
+}
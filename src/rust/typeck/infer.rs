@@ -0,0 +1,996 @@
+// Rust type inference
+//
+//! Inference variables, unification and trait selection.
+//!
+//! Obligations that can't be decided yet, because the types they are about
+//! still have inference variables, stay pending until more is known.
+//! Selection looks for built-in impls first, then `where` clauses, then the
+//! bounds of `dyn`, `impl Trait` and associated types, then impls.  When
+//! exactly one candidate applies it is chosen, even if that decides some
+//! inference variables.
+
+use std::rc::Rc;
+
+use super::{
+    head, may_apply, Context, GenericsDef, ItemId, Pred, Projection,
+    TraitBound, TraitRef, Ty,
+};
+use crate::rust::resolve::ModuleId;
+use crate::rust::BinaryOp;
+use crate::Span;
+
+// Nesting of impl selection and normalization before giving up.
+const MAX_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum VarKind {
+    General,
+    Int,
+    Float,
+}
+
+/// Why an obligation must hold, for error messages
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Cause {
+    Misc,
+    Binary(BinaryOp),
+    Unary(&'static str),
+    Index,
+    Iterator,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Obligation<'a> {
+    pub(super) pred: Pred<'a>,
+    pub(super) span: Span,
+    cause: Cause,
+    depth: usize,
+}
+
+pub(super) enum Selection<'a> {
+    /// Holds if the nested predicates hold
+    Yes(Vec<Pred<'a>>),
+    No,
+    /// Not known until more is known about the types
+    Maybe,
+}
+
+enum ImplSelection<'a> {
+    /// The impl, its generic arguments and its predicates
+    Unique(usize, Vec<Ty<'a>>, Vec<Pred<'a>>),
+    Ambiguous,
+    None,
+}
+
+/// The result of looking up an associated type
+pub(super) enum Projected<'a> {
+    Ty(Ty<'a>),
+    /// An associated type of a generic parameter or opaque type, which
+    /// stays a projection
+    Rigid,
+    Ambiguous,
+    /// The trait isn't implemented
+    NoImpl,
+}
+
+pub(super) struct Snapshot {
+    vars: usize,
+    undo: usize,
+    pending: usize,
+}
+
+pub(super) struct InferCtxt<'c, 'k, 'a> {
+    pub(super) cx: &'c Context<'k, 'a>,
+    pub(super) module: ModuleId,
+    values: Vec<Option<Ty<'a>>>,
+    kinds: Vec<VarKind>,
+    // Variables bound since the outermost snapshot.
+    undo: Vec<usize>,
+    snapshots: usize,
+    /// `where` clauses in scope, with supertraits
+    pub(super) env: Rc<Vec<Pred<'a>>>,
+    pub(super) pending: Vec<Obligation<'a>>,
+}
+
+impl<'c, 'k, 'a> InferCtxt<'c, 'k, 'a> {
+    pub(super) fn new(cx: &'c Context<'k, 'a>, module: ModuleId) -> Self {
+        InferCtxt {
+            cx,
+            module,
+            values: Vec::new(),
+            kinds: Vec::new(),
+            undo: Vec::new(),
+            snapshots: 0,
+            env: Rc::new(Vec::new()),
+            pending: Vec::new(),
+        }
+    }
+
+    fn new_var(&mut self, kind: VarKind) -> usize {
+        self.values.push(None);
+        self.kinds.push(kind);
+        self.values.len() - 1
+    }
+
+    pub(super) fn fresh(&mut self) -> Ty<'a> {
+        Ty::Var(self.new_var(VarKind::General))
+    }
+
+    pub(super) fn fresh_int(&mut self) -> Ty<'a> {
+        Ty::IntVar(self.new_var(VarKind::Int))
+    }
+
+    pub(super) fn fresh_float(&mut self) -> Ty<'a> {
+        Ty::FloatVar(self.new_var(VarKind::Float))
+    }
+
+    /// Fresh variables for generic parameters (const parameters aren't
+    /// checked).
+    pub(super) fn fresh_args(&mut self, generics: &GenericsDef<'a>)
+        -> Vec<Ty<'a>>
+    {
+        generics.consts.iter()
+            .map(|is_const| match is_const {
+                true => Ty::Error,
+                false => self.fresh(),
+            })
+            .collect()
+    }
+
+    /// Replace `_` in a type with fresh variables.
+    pub(super) fn replace_infer(&mut self, ty: &Ty<'a>) -> Ty<'a> {
+        ty.fold(&mut |ty| match ty {
+            Ty::Infer => Some(self.fresh()),
+            _ => None,
+        })
+    }
+
+    /// Follow the bindings of a variable at the outside of a type.
+    pub(super) fn shallow(&self, ty: &Ty<'a>) -> Ty<'a> {
+        let mut ty = ty;
+        loop {
+            match *ty {
+                Ty::Var(var) | Ty::IntVar(var) | Ty::FloatVar(var) => {
+                    match self.values[var] {
+                        Some(ref value) => ty = value,
+                        None => return ty.clone(),
+                    }
+                }
+                _ => return ty.clone(),
+            }
+        }
+    }
+
+    /// Replace all bound variables in a type.
+    pub(super) fn resolve(&self, ty: &Ty<'a>) -> Ty<'a> {
+        ty.fold(&mut |ty| match *ty {
+            Ty::Var(var) | Ty::IntVar(var) | Ty::FloatVar(var) => {
+                self.values[var].as_ref().map(|value| self.resolve(value))
+            }
+            _ => None,
+        })
+    }
+
+    pub(super) fn resolve_trait(&self, trait_ref: &TraitRef<'a>)
+        -> TraitRef<'a>
+    {
+        TraitRef {
+            def: trait_ref.def,
+            args: trait_ref.args.iter().map(|ty| self.resolve(ty)).collect(),
+        }
+    }
+
+    fn resolve_projection(&self, projection: &Projection<'a>)
+        -> Projection<'a>
+    {
+        Projection {
+            self_ty: self.resolve(&projection.self_ty),
+            trait_ref: self.resolve_trait(&projection.trait_ref),
+            name: projection.name,
+        }
+    }
+
+    pub(super) fn snapshot(&mut self) -> Snapshot {
+        self.snapshots += 1;
+        Snapshot {
+            vars: self.values.len(),
+            undo: self.undo.len(),
+            pending: self.pending.len(),
+        }
+    }
+
+    pub(super) fn rollback(&mut self, snapshot: Snapshot) {
+        let undo: Vec<_> = self.undo.drain(snapshot.undo..).collect();
+        for var in undo.into_iter().rev() {
+            self.values[var] = None;
+        }
+        self.values.truncate(snapshot.vars);
+        self.kinds.truncate(snapshot.vars);
+        self.pending.truncate(snapshot.pending);
+        self.snapshots -= 1;
+        if self.snapshots == 0 {
+            self.undo.clear();
+        }
+    }
+
+    pub(super) fn commit(&mut self, _snapshot: Snapshot) {
+        self.snapshots -= 1;
+        if self.snapshots == 0 {
+            self.undo.clear();
+        }
+    }
+
+    /// Run `f` and undo what it did.
+    pub(super) fn probe<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        let snapshot = self.snapshot();
+        let result = f(self);
+        self.rollback(snapshot);
+        result
+    }
+
+    /// Run `f`, and undo what it did unless it succeeds.
+    pub(super) fn commit_if<R>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Option<R>,
+    ) -> Option<R> {
+        let snapshot = self.snapshot();
+        let result = f(self);
+        match result {
+            Some(_) => self.commit(snapshot),
+            None => self.rollback(snapshot),
+        }
+        result
+    }
+
+    fn bind(&mut self, var: usize, ty: &Ty<'a>) -> bool {
+        if self.resolve(ty).any(&mut |ty| match *ty {
+            Ty::Var(other) | Ty::IntVar(other) | Ty::FloatVar(other) => {
+                other == var
+            }
+            _ => false,
+        }) {
+            return false;
+        }
+        self.values[var] = Some(ty.clone());
+        if self.snapshots > 0 {
+            self.undo.push(var);
+        }
+        true
+    }
+
+    /// Make two types equal.
+    pub(super) fn unify(&mut self, a: &Ty<'a>, b: &Ty<'a>) -> bool {
+        let a = self.shallow(a);
+        let b = self.shallow(b);
+        match (&a, &b) {
+            (Ty::Var(x), Ty::Var(y)) if x == y => true,
+            (Ty::Var(x), _) => self.bind(*x, &b),
+            (_, Ty::Var(y)) => self.bind(*y, &a),
+            (Ty::IntVar(x), Ty::IntVar(y))
+            | (Ty::FloatVar(x), Ty::FloatVar(y)) => {
+                x == y || self.bind(*x, &b)
+            }
+            (Ty::IntVar(x), Ty::Int(_)) | (Ty::FloatVar(x), Ty::Float(_)) => {
+                self.bind(*x, &b)
+            }
+            (Ty::Int(_), Ty::IntVar(y)) | (Ty::Float(_), Ty::FloatVar(y)) => {
+                self.bind(*y, &a)
+            }
+            (Ty::Error, _) | (_, Ty::Error) => true,
+            (Ty::Tuple(x), Ty::Tuple(y)) => self.unify_all(x, y),
+            (Ty::Array(x, n), Ty::Array(y, m)) => {
+                (n.is_none() || m.is_none() || n == m) && self.unify(x, y)
+            }
+            (Ty::Slice(x), Ty::Slice(y)) => self.unify(x, y),
+            (Ty::Ref(m, x), Ty::Ref(n, y)) | (Ty::Ptr(m, x), Ty::Ptr(n, y)) => {
+                m == n && self.unify(x, y)
+            }
+            (Ty::Adt(d, x), Ty::Adt(e, y)) => d == e && self.unify_all(x, y),
+            (Ty::Fn(xs, x), Ty::Fn(ys, y)) => {
+                self.unify_all(xs, ys) && self.unify(x, y)
+            }
+            (Ty::Closure(c, xs, x), Ty::Closure(d, ys, y)) => {
+                c == d && self.unify_all(xs, ys) && self.unify(x, y)
+            }
+            (Ty::Dyn(x), Ty::Dyn(y)) | (Ty::Opaque(x), Ty::Opaque(y)) => {
+                self.unify_bounds(x, y)
+            }
+            (Ty::Param(x, _), Ty::Param(y, _)) => x == y,
+            (Ty::Projection(p), Ty::Projection(q)) => {
+                p.name == q.name
+                    && p.trait_ref.def == q.trait_ref.def
+                    && self.unify(&p.self_ty, &q.self_ty)
+                    && self.unify_all(&p.trait_ref.args, &q.trait_ref.args)
+            }
+            _ => a == b,
+        }
+    }
+
+    pub(super) fn unify_all(&mut self, a: &[Ty<'a>], b: &[Ty<'a>]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| self.unify(a, b))
+    }
+
+    fn unify_bounds(&mut self, a: &[TraitBound<'a>], b: &[TraitBound<'a>])
+        -> bool
+    {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| {
+            a.trait_ref.def == b.trait_ref.def
+                && a.bindings.len() == b.bindings.len()
+                && self.unify_all(&a.trait_ref.args, &b.trait_ref.args)
+                && a.bindings.iter().zip(&b.bindings).all(|(x, y)| {
+                    x.0 == y.0 && self.unify(&x.1, &y.1)
+                })
+        })
+    }
+
+    /// Bind integer and float literal variables that are still unknown to
+    /// `i32` and `f64`.
+    pub(super) fn fallback(&mut self) {
+        for var in 0..self.values.len() {
+            if self.values[var].is_some() {
+                continue;
+            }
+            self.values[var] = match self.kinds[var] {
+                VarKind::Int => Some(Ty::Int(crate::rust::IntType::I32)),
+                VarKind::Float => Some(Ty::Float(crate::rust::FloatType::F64)),
+                VarKind::General => None,
+            };
+        }
+    }
+
+    /// Add an obligation, normalizing its associated types.
+    pub(super) fn register(
+        &mut self,
+        pred: Pred<'a>,
+        span: Span,
+        cause: Cause,
+    ) {
+        self.register_nested(pred, span, cause, 0);
+    }
+
+    fn register_nested(
+        &mut self,
+        pred: Pred<'a>,
+        span: Span,
+        cause: Cause,
+        depth: usize,
+    ) {
+        let pred = match pred {
+            Pred::Trait(ty, trait_ref) => {
+                let ty = self.normalize(&ty, span);
+                let args = trait_ref.args.iter()
+                    .map(|ty| self.normalize(ty, span))
+                    .collect();
+                Pred::Trait(ty, TraitRef { def: trait_ref.def, args })
+            }
+            Pred::Eq(projection, ty) => {
+                let self_ty = self.normalize(&projection.self_ty, span);
+                let args = projection.trait_ref.args.iter()
+                    .map(|ty| self.normalize(ty, span))
+                    .collect();
+                let projection = Projection {
+                    self_ty,
+                    trait_ref: TraitRef { def: projection.trait_ref.def, args },
+                    name: projection.name,
+                };
+                Pred::Eq(projection, self.normalize(&ty, span))
+            }
+        };
+        self.pending.push(Obligation { pred, span, cause, depth });
+    }
+
+    /// Add an obligation that a type implements a trait.
+    pub(super) fn require(
+        &mut self,
+        ty: &Ty<'a>,
+        trait_ref: TraitRef<'a>,
+        span: Span,
+        cause: Cause,
+    ) {
+        self.register(Pred::Trait(ty.clone(), trait_ref), span, cause);
+    }
+
+    /// Replace the associated types in a type by what they are, or by
+    /// variables to be decided by obligations.
+    pub(super) fn normalize(&mut self, ty: &Ty<'a>, span: Span) -> Ty<'a> {
+        self.normalize_depth(ty, span, 0)
+    }
+
+    fn normalize_depth(&mut self, ty: &Ty<'a>, span: Span, depth: usize)
+        -> Ty<'a>
+    {
+        ty.fold(&mut |ty| match *ty {
+            Ty::Projection(ref projection) => {
+                Some(self.normalize_projection(projection, span, depth))
+            }
+            _ => None,
+        })
+    }
+
+    fn normalize_projection(
+        &mut self,
+        projection: &Projection<'a>,
+        span: Span,
+        depth: usize,
+    ) -> Ty<'a> {
+        if depth > MAX_DEPTH {
+            return Ty::Error;
+        }
+        let projection = Projection {
+            self_ty: self.normalize_depth(&projection.self_ty, span, depth + 1),
+            trait_ref: TraitRef {
+                def: projection.trait_ref.def,
+                args: projection.trait_ref.args.iter()
+                    .map(|ty| self.normalize_depth(ty, span, depth + 1))
+                    .collect(),
+            },
+            name: projection.name,
+        };
+        match self.project(&projection, span, 0) {
+            Projected::Ty(ty) => self.normalize_depth(&ty, span, depth + 1),
+            Projected::Rigid => {
+                Ty::Projection(Box::new(self.resolve_projection(&projection)))
+            }
+            Projected::Ambiguous => {
+                let var = self.fresh();
+                self.pending.push(Obligation {
+                    pred: Pred::Eq(projection, var.clone()),
+                    span,
+                    cause: Cause::Misc,
+                    depth: 0,
+                });
+                var
+            }
+            // The trait is reported as not implemented where it is
+            // required.
+            Projected::NoImpl => Ty::Error,
+        }
+    }
+
+    /// Solve the pending obligations as far as possible, reporting those
+    /// that don't hold.
+    pub(super) fn select_pending(&mut self) {
+        loop {
+            let mut progress = false;
+            for obligation in std::mem::take(&mut self.pending) {
+                let span = obligation.span;
+                let depth = obligation.depth + 1;
+                match obligation.pred {
+                    Pred::Trait(ref ty, ref trait_ref) => {
+                        match self.select(ty, trait_ref, 0) {
+                            Selection::Yes(nested) => {
+                                progress = true;
+                                if depth > MAX_DEPTH * 4 {
+                                    continue;
+                                }
+                                for pred in nested {
+                                    self.register_nested(pred, span,
+                                        obligation.cause, depth);
+                                }
+                            }
+                            Selection::No => {
+                                progress = true;
+                                self.report(&obligation);
+                            }
+                            Selection::Maybe => self.pending.push(obligation),
+                        }
+                    }
+                    Pred::Eq(ref projection, ref ty) => {
+                        let value = match self.project(projection, span, 0) {
+                            Projected::Ty(value) => self.normalize(&value, span),
+                            Projected::Rigid => Ty::Projection(Box::new(
+                                self.resolve_projection(projection))),
+                            Projected::Ambiguous => {
+                                self.pending.push(obligation);
+                                continue;
+                            }
+                            Projected::NoImpl => {
+                                progress = true;
+                                let unsatisfied = Obligation {
+                                    pred: Pred::Trait(projection.self_ty.clone(),
+                                        projection.trait_ref.clone()),
+                                    ..obligation
+                                };
+                                self.report(&unsatisfied);
+                                continue;
+                            }
+                        };
+                        progress = true;
+                        if !self.unify(&value, ty) {
+                            let projection = self.resolve_projection(projection);
+                            let message = format!("type mismatch resolving \
+                                `{} == {}`",
+                                Ty::Projection(Box::new(projection))
+                                    .name(self.cx.krate),
+                                self.resolve(ty).name(self.cx.krate));
+                            self.cx.error(self.module, span, message);
+                        }
+                    }
+                }
+            }
+            if !progress {
+                break;
+            }
+        }
+    }
+
+    fn report(&self, obligation: &Obligation<'a>) {
+        let (ty, trait_ref) = match obligation.pred {
+            Pred::Trait(ref ty, ref trait_ref) => {
+                (self.resolve(ty), self.resolve_trait(trait_ref))
+            }
+            Pred::Eq(..) => return,
+        };
+        if ty.any(&mut |ty| *ty == Ty::Error)
+            || trait_ref.args.iter().any(|ty| ty.any(&mut |ty| *ty == Ty::Error))
+        {
+            return;
+        }
+        let krate = self.cx.krate;
+        let name = ty.name(krate);
+        let message = match obligation.cause {
+            Cause::Binary(op) => format!(
+                "binary operation `{}` cannot be applied to type `{}`",
+                op.as_str(), name),
+            Cause::Unary(op) => format!(
+                "cannot apply unary operator `{}` to type `{}`", op, name),
+            Cause::Index => format!("the type `{}` cannot be indexed by `{}`",
+                name, trait_ref.args[0].name(krate)),
+            Cause::Iterator => format!("`{}` is not an iterator", name),
+            Cause::Misc => {
+                let mut bound = String::new();
+                super::write_trait(&mut bound, &trait_ref, &[], krate);
+                format!("the trait bound `{}: {}` is not satisfied", name,
+                    bound)
+            }
+        };
+        self.cx.error(self.module, obligation.span, message);
+    }
+
+    /// Add the supertraits of trait predicates, and the bindings of their
+    /// associated types.
+    pub(super) fn elaborate(&self, preds: &[Pred<'a>]) -> Vec<Pred<'a>> {
+        let mut out: Vec<Pred<'a>> = Vec::new();
+        for pred in preds {
+            if !out.contains(pred) {
+                out.push(pred.clone());
+            }
+        }
+        let mut index = 0;
+        while index < out.len() && out.len() < 256 {
+            if let Pred::Trait(ref ty, ref trait_ref) = out[index].clone() {
+                let args = self.cx.trait_args(ty, trait_ref);
+                let mut new = Vec::new();
+                for bound in self.cx.supertraits_of(trait_ref.def).iter() {
+                    let bound = bound.fold(&mut |ty| match *ty {
+                        Ty::Param(index, _) => args.get(index).cloned(),
+                        _ => None,
+                    });
+                    self.cx.bound_predicates(ty, &bound, &mut new);
+                }
+                for pred in new {
+                    if !out.contains(&pred) {
+                        out.push(pred);
+                    }
+                }
+            }
+            index += 1;
+        }
+        out
+    }
+
+    /// The predicates that hold for `dyn`, `impl Trait` and associated
+    /// types by their bounds.
+    pub(super) fn bounds_of(&self, ty: &Ty<'a>) -> Vec<Pred<'a>> {
+        let mut preds = Vec::new();
+        match *ty {
+            Ty::Dyn(ref bounds) | Ty::Opaque(ref bounds) => {
+                for bound in bounds {
+                    self.cx.bound_predicates(ty, bound, &mut preds);
+                }
+            }
+            Ty::Projection(ref projection) => {
+                let args = self.cx.trait_args(&projection.self_ty,
+                    &projection.trait_ref);
+                let bounds = self.cx.assoc_bounds(projection.trait_ref.def,
+                    projection.name);
+                for bound in bounds {
+                    let bound = bound.fold(&mut |ty| match *ty {
+                        Ty::Param(index, _) => args.get(index).cloned(),
+                        _ => None,
+                    });
+                    self.cx.bound_predicates(ty, &bound, &mut preds);
+                }
+            }
+            _ => return preds,
+        }
+        self.elaborate(&preds)
+    }
+
+    /// Decide whether a type implements a trait.
+    pub(super) fn select(
+        &mut self,
+        ty: &Ty<'a>,
+        trait_ref: &TraitRef<'a>,
+        depth: usize,
+    ) -> Selection<'a> {
+        if depth > MAX_DEPTH {
+            return Selection::Maybe;
+        }
+        let ty = self.resolve(ty);
+        let trait_ref = self.resolve_trait(trait_ref);
+        if ty == Ty::Error || trait_ref.args.contains(&Ty::Error) {
+            return Selection::Yes(Vec::new());
+        }
+        if let Some(selection) = self.builtin(&ty, &trait_ref) {
+            return selection;
+        }
+        if let Ty::Var(_) = ty {
+            return Selection::Maybe;
+        }
+        if self.select_where(&ty, &trait_ref) {
+            return Selection::Yes(Vec::new());
+        }
+        match self.select_impl(&ty, &trait_ref, depth) {
+            ImplSelection::Unique(_, _, nested) => Selection::Yes(nested),
+            ImplSelection::Ambiguous => Selection::Maybe,
+            ImplSelection::None if self.cx.is_auto_trait(trait_ref.def) => {
+                Selection::Yes(Vec::new())
+            }
+            ImplSelection::None => Selection::No,
+        }
+    }
+
+    /// Whether a type implements a trait, including the predicates of the
+    /// impl that applies; `None` if that isn't known yet.
+    pub(super) fn evaluate(
+        &mut self,
+        ty: &Ty<'a>,
+        trait_ref: &TraitRef<'a>,
+        depth: usize,
+    ) -> Option<bool> {
+        match self.select(ty, trait_ref, depth) {
+            Selection::Yes(nested) => {
+                for pred in nested {
+                    if let Pred::Trait(ref ty, ref trait_ref) = pred {
+                        if !self.evaluate(ty, trait_ref, depth + 1)? {
+                            return Some(false);
+                        }
+                    }
+                }
+                Some(true)
+            }
+            Selection::No => Some(false),
+            Selection::Maybe => None,
+        }
+    }
+
+    /// Whether a type might implement a trait, without deciding anything.
+    pub(super) fn may_implement(
+        &mut self,
+        ty: &Ty<'a>,
+        trait_ref: &TraitRef<'a>,
+    ) -> bool {
+        self.probe(|infer| {
+            !matches!(infer.select(ty, trait_ref, 0), Selection::No)
+        })
+    }
+
+    // Select a `where` clause or a bound of the type itself.
+    fn select_where(&mut self, ty: &Ty<'a>, trait_ref: &TraitRef<'a>) -> bool {
+        let env = self.env.clone();
+        let bounds = self.bounds_of(ty);
+        for pred in env.iter().chain(&bounds) {
+            if let Pred::Trait(ref bound_ty, ref bound) = *pred {
+                if bound.def != trait_ref.def {
+                    continue;
+                }
+                let matched = self.commit_if(|infer| {
+                    let matched = infer.unify(bound_ty, ty)
+                        && infer.unify_all(&bound.args, &trait_ref.args);
+                    matched.then_some(())
+                });
+                if matched.is_some() {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    // Find the only impl of a trait for a type.
+    fn select_impl(
+        &mut self,
+        ty: &Ty<'a>,
+        trait_ref: &TraitRef<'a>,
+        depth: usize,
+    ) -> ImplSelection<'a> {
+        let cx = self.cx;
+        let impls = match cx.trait_impls.get(&trait_ref.def) {
+            Some(impls) => impls,
+            None => return ImplSelection::None,
+        };
+        let mut found = None;
+        for &impl_id in impls {
+            let header = cx.impl_header(impl_id);
+            if header.negative || !may_apply(head(&header.self_ty), ty) {
+                continue;
+            }
+            let matched = self.probe(|infer| {
+                infer.match_impl(impl_id, ty, trait_ref, Some(depth)).is_some()
+            });
+            if matched {
+                if found.is_some() {
+                    return ImplSelection::Ambiguous;
+                }
+                found = Some(impl_id);
+            }
+        }
+        match found {
+            Some(impl_id) => {
+                let (args, nested) = self.match_impl(impl_id, ty, trait_ref, None)
+                    .expect("impl no longer matches");
+                ImplSelection::Unique(impl_id, args, nested)
+            }
+            None => ImplSelection::None,
+        }
+    }
+
+    /// Unify the header of an impl with a type and trait, returning its
+    /// generic arguments and predicates.  With `evaluate`, also check that
+    /// none of the predicates is known not to hold.
+    pub(super) fn match_impl(
+        &mut self,
+        impl_id: usize,
+        ty: &Ty<'a>,
+        trait_ref: &TraitRef<'a>,
+        evaluate: Option<usize>,
+    ) -> Option<(Vec<Ty<'a>>, Vec<Pred<'a>>)> {
+        let cx = self.cx;
+        let info = &cx.impls[impl_id];
+        let id = ItemId::Impl(info.module, info.index);
+        let args = self.fresh_args(&cx.generics_of(id));
+        let header = cx.impl_header(impl_id);
+        if !self.unify(&header.self_ty.subst(&args), ty) {
+            return None;
+        }
+        if let Some(ref impl_trait) = header.trait_ref {
+            let impl_trait = impl_trait.subst(&args);
+            if !self.unify_all(&impl_trait.args, &trait_ref.args) {
+                return None;
+            }
+        }
+        let preds: Vec<_> = cx.predicates_of(id).iter()
+            .map(|pred| pred.subst(&args))
+            .collect();
+        if let Some(depth) = evaluate {
+            for pred in &preds {
+                if let Pred::Trait(ref ty, ref trait_ref) = *pred {
+                    let mut is_projection =
+                        |ty: &Ty<'a>| matches!(ty, Ty::Projection(_));
+                    if ty.any(&mut is_projection)
+                        || trait_ref.args.iter()
+                            .any(|arg| arg.any(&mut is_projection))
+                    {
+                        continue;
+                    }
+                    if let Selection::No = self.select(ty, trait_ref, depth + 1) {
+                        return None;
+                    }
+                }
+            }
+        }
+        Some((args, preds))
+    }
+
+    // Impls built into the compiler: `Sized`, `Copy` and `Clone` for
+    // primitives, comparison and formatting of tuples, arrays and slices,
+    // and the function traits for closures and functions.
+    fn builtin(&mut self, ty: &Ty<'a>, trait_ref: &TraitRef<'a>)
+        -> Option<Selection<'a>>
+    {
+        let cx = self.cx;
+        let def = trait_ref.def;
+        let yes = |nested| Some(Selection::Yes(nested));
+        let each = |tys: &[Ty<'a>], args: &[Ty<'a>]| {
+            tys.iter()
+                .map(|ty| Pred::Trait(ty.clone(), TraitRef {
+                    def,
+                    args: args.to_vec(),
+                }))
+                .collect()
+        };
+        if cx.is_lang(def, "marker::Sized") {
+            return match ty {
+                Ty::Str | Ty::Slice(_) | Ty::Dyn(_) => Some(Selection::No),
+                _ => yes(Vec::new()),
+            };
+        }
+        if cx.is_lang(def, "marker::Send")
+            || cx.is_lang(def, "marker::Sync")
+            || cx.is_lang(def, "marker::Unpin")
+        {
+            return yes(Vec::new());
+        }
+        if cx.is_lang(def, "marker::Copy") || cx.is_lang(def, "clone::Clone") {
+            return match ty {
+                Ty::Bool | Ty::Char | Ty::Int(_) | Ty::Float(_) | Ty::Never
+                | Ty::Ptr(..) | Ty::Fn(..) | Ty::IntVar(_) | Ty::FloatVar(_)
+                | Ty::Ref(false, _) | Ty::Closure(..) => yes(Vec::new()),
+                Ty::Ref(true, _) | Ty::Str | Ty::Slice(_) | Ty::Dyn(_) => {
+                    Some(Selection::No)
+                }
+                Ty::Tuple(tys) => yes(each(tys, &[])),
+                Ty::Array(elem, _) => yes(each(&[(**elem).clone()], &[])),
+                _ => None,
+            };
+        }
+        let structural = ["cmp::Eq", "cmp::Ord", "hash::Hash", "fmt::Debug",
+            "default::Default"];
+        let with_rhs = ["cmp::PartialEq", "cmp::PartialOrd"];
+        if structural.iter().any(|path| cx.is_lang(def, path)) {
+            let is_default = cx.is_lang(def, "default::Default");
+            return match ty {
+                Ty::Tuple(tys) => yes(each(tys, &[])),
+                Ty::Array(elem, len) if !is_default || *len == Some(0) => {
+                    yes(each(&[(**elem).clone()], &[]))
+                }
+                Ty::Slice(elem) if !is_default => {
+                    yes(each(&[(**elem).clone()], &[]))
+                }
+                Ty::Fn(..) | Ty::Ptr(..) | Ty::Never if !is_default => {
+                    yes(Vec::new())
+                }
+                _ => None,
+            };
+        }
+        if with_rhs.iter().any(|path| cx.is_lang(def, path)) {
+            let rhs = self.shallow(&trait_ref.args[0]);
+            return match (ty, &rhs) {
+                (Ty::Tuple(tys), _) => {
+                    let others: Vec<_> = tys.iter()
+                        .map(|_| self.fresh())
+                        .collect();
+                    match self.unify(&rhs, &Ty::Tuple(others.clone())) {
+                        true => yes(tys.iter().zip(others)
+                            .map(|(ty, other)| Pred::Trait(ty.clone(),
+                                TraitRef { def, args: vec![other] }))
+                            .collect()),
+                        false => Some(Selection::No),
+                    }
+                }
+                (Ty::Array(elem, _), Ty::Array(other, _))
+                | (Ty::Array(elem, _), Ty::Slice(other))
+                | (Ty::Slice(elem), Ty::Array(other, _))
+                | (Ty::Slice(elem), Ty::Slice(other)) => {
+                    yes(vec![Pred::Trait((**elem).clone(), TraitRef {
+                        def,
+                        args: vec![(**other).clone()],
+                    })])
+                }
+                (Ty::Array(..), Ty::Var(_)) | (Ty::Slice(_), Ty::Var(_)) => {
+                    Some(Selection::Maybe)
+                }
+                (Ty::Array(..), _) | (Ty::Slice(_), _) => Some(Selection::No),
+                (Ty::Fn(..), _) | (Ty::Ptr(..), _) | (Ty::Never, _) => {
+                    match self.unify(&rhs, ty) {
+                        true => yes(Vec::new()),
+                        false => Some(Selection::No),
+                    }
+                }
+                _ => None,
+            };
+        }
+        let fn_traits = ["ops::Fn", "ops::FnMut", "ops::FnOnce"];
+        if fn_traits.iter().any(|path| cx.is_lang(def, path)) {
+            return match ty {
+                Ty::Fn(inputs, _) | Ty::Closure(_, inputs, _) => {
+                    let args = Ty::Tuple(inputs.clone());
+                    match self.unify(&trait_ref.args[0], &args) {
+                        true => yes(Vec::new()),
+                        false => Some(Selection::No),
+                    }
+                }
+                Ty::Ref(_, inner) => yes(vec![Pred::Trait((**inner).clone(),
+                    trait_ref.clone())]),
+                Ty::Adt(adt, args) if cx.is_lang(*adt, "boxed::Box") => {
+                    yes(vec![Pred::Trait(args[0].clone(), trait_ref.clone())])
+                }
+                _ => None,
+            };
+        }
+        None
+    }
+
+    /// Look up the associated type of a trait implementation.
+    pub(super) fn project(
+        &mut self,
+        projection: &Projection<'a>,
+        span: Span,
+        depth: usize,
+    ) -> Projected<'a> {
+        if depth > MAX_DEPTH {
+            return Projected::Ambiguous;
+        }
+        let cx = self.cx;
+        let projection = self.resolve_projection(projection);
+        let self_ty = &projection.self_ty;
+        let trait_ref = &projection.trait_ref;
+        let name = projection.name;
+        if *self_ty == Ty::Error || trait_ref.args.contains(&Ty::Error) {
+            return Projected::Ty(Ty::Error);
+        }
+        if cx.is_lang(trait_ref.def, "ops::FnOnce") && name == "Output" {
+            match self_ty {
+                Ty::Fn(inputs, output) | Ty::Closure(_, inputs, output) => {
+                    let args = Ty::Tuple(inputs.clone());
+                    return match self.unify(&trait_ref.args[0], &args) {
+                        true => Projected::Ty((**output).clone()),
+                        false => Projected::NoImpl,
+                    };
+                }
+                Ty::Ref(_, inner) => {
+                    let inner = Projection {
+                        self_ty: (**inner).clone(),
+                        ..projection.clone()
+                    };
+                    return self.project(&inner, span, depth + 1);
+                }
+                Ty::Adt(def, args) if cx.is_lang(*def, "boxed::Box") => {
+                    let inner = Projection {
+                        self_ty: args[0].clone(),
+                        ..projection.clone()
+                    };
+                    return self.project(&inner, span, depth + 1);
+                }
+                _ => {}
+            }
+        }
+        if let Ty::Var(_) = self_ty {
+            return Projected::Ambiguous;
+        }
+
+        // `where` clauses and bounds binding the associated type
+        let env = self.env.clone();
+        let bounds = self.bounds_of(self_ty);
+        for pred in env.iter().chain(&bounds) {
+            if let Pred::Eq(ref bound, ref value) = *pred {
+                if bound.name != name || bound.trait_ref.def != trait_ref.def {
+                    continue;
+                }
+                let matched = self.commit_if(|infer| {
+                    let matched = infer.unify(&bound.self_ty, self_ty)
+                        && infer.unify_all(&bound.trait_ref.args,
+                            &trait_ref.args);
+                    matched.then_some(())
+                });
+                if matched.is_some() {
+                    return Projected::Ty(value.clone());
+                }
+            }
+        }
+        let rigid = matches!(self_ty, Ty::Param(..) | Ty::Projection(_)
+            | Ty::Dyn(_) | Ty::Opaque(_));
+        if rigid && self.select_where(self_ty, trait_ref) {
+            return Projected::Rigid;
+        }
+
+        match self.select_impl(self_ty, trait_ref, depth) {
+            ImplSelection::Unique(impl_id, args, nested) => {
+                for pred in nested {
+                    self.register_nested(pred, span, Cause::Misc, depth + 1);
+                }
+                let ty = match cx.impl_assoc_ty(impl_id, name) {
+                    Some(ty) => ty.subst(&args),
+                    None => match cx.assoc_default(trait_ref.def, name) {
+                        Some(ty) => ty.subst(&cx.trait_args(self_ty, trait_ref)),
+                        None => Ty::Error,
+                    },
+                };
+                Projected::Ty(ty)
+            }
+            ImplSelection::Ambiguous => Projected::Ambiguous,
+            ImplSelection::None => Projected::NoImpl,
+        }
+    }
+}
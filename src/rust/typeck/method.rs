@@ -0,0 +1,450 @@
+// Rust method lookup
+//
+//! Method calls and associated items.
+//!
+//! The receiver of a method call is dereferenced step by step, through
+//! references, boxes and `Deref` impls.  At each step the method is looked
+//! up on the type itself, then on a reference to it, then on a mutable
+//! reference, with inherent methods before those of traits.  Trait methods
+//! are only found for traits in scope or in the bounds of the type.
+
+use super::expr::FnCtxt;
+use super::infer::{Cause, Selection};
+use super::{
+    head, may_apply, AssocKind, ItemId, Method, Pred, Projection, TraitRef,
+    Ty,
+};
+use crate::rust::resolve::{DefId, Namespace, Res};
+use crate::rust::PathSegment;
+use crate::Span;
+
+/// Maximum number of dereferences of a method receiver
+const MAX_DEREFS: usize = 8;
+
+/// The function a method call or associated item path was resolved to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PickItem {
+    /// Item of an impl, by impl and index into its items
+    Inherent(usize, usize),
+    /// Item of a trait, by index into its items
+    Trait(DefId, usize),
+}
+
+/// A method found for a receiver
+pub(super) struct Pick<'a> {
+    item: PickItem,
+    /// The dereferenced receiver type
+    step: Ty<'a>,
+    derefs: usize,
+    autoref: Option<bool>,
+}
+
+impl<'c, 'k, 'a> FnCtxt<'c, 'k, 'a> {
+    /// The types a value can be dereferenced to, starting with its own.
+    pub(super) fn autoderef(&mut self, ty: &Ty<'a>, span: Span) -> Vec<Ty<'a>> {
+        let deref = self.cx.lang("ops::Deref");
+        let mut steps = Vec::new();
+        let mut ty = self.infcx.shallow(ty);
+        while steps.len() <= MAX_DEREFS {
+            steps.push(ty.clone());
+            let next = match ty {
+                Ty::Ref(_, ref inner) => (**inner).clone(),
+                Ty::Adt(def, ref args) if self.cx.is_lang(def, "boxed::Box") => {
+                    args[0].clone()
+                }
+                Ty::Var(_) | Ty::IntVar(_) | Ty::FloatVar(_) | Ty::Error => break,
+                _ => {
+                    let def = match deref {
+                        Some(def) => def,
+                        None => break,
+                    };
+                    let trait_ref = TraitRef { def, args: Vec::new() };
+                    if !self.infcx.may_implement(&ty, &trait_ref) {
+                        break;
+                    }
+                    let projection = Projection {
+                        self_ty: ty.clone(),
+                        trait_ref,
+                        name: "Target",
+                    };
+                    let target = Ty::Projection(Box::new(projection));
+                    self.infcx.normalize(&target, span)
+                }
+            };
+            ty = self.infcx.shallow(&next);
+        }
+        if let Some(Ty::Array(elem, _)) = steps.last().cloned() {
+            steps.push(Ty::Slice(elem));
+        }
+        steps
+    }
+
+    // The traits whose items may be used on a type: those in scope and those
+    // bounding the type.
+    fn candidate_traits(&self, ty: &Ty<'a>) -> Vec<DefId> {
+        let cx = self.cx;
+        let mut traits = cx.krate.traits_in_scope(self.scope.module);
+        let imported = self.scope.uses.iter()
+            .filter_map(|(_, ns, res)| match (ns, res) {
+                (Namespace::Type, Res::Def(def)) if cx.is_trait(*def) => {
+                    Some(*def)
+                }
+                _ => None,
+            });
+        let bounds = self.infcx.bounds_of(ty);
+        let bounds = self.infcx.env.iter().chain(&bounds)
+            .filter_map(|pred| match *pred {
+                Pred::Trait(_, ref trait_ref) => Some(trait_ref.def),
+                Pred::Eq(..) => None,
+            });
+        for def in imported.collect::<Vec<_>>().into_iter().chain(bounds) {
+            if !traits.contains(&def) {
+                traits.push(def);
+            }
+        }
+        traits
+    }
+
+    /// Find the method called on a receiver.
+    pub(super) fn lookup_method(
+        &mut self,
+        receiver: &Ty<'a>,
+        name: &str,
+        span: Span,
+    ) -> Option<Pick<'a>> {
+        if let Ty::Var(_) = self.infcx.shallow(receiver) {
+            self.infcx.select_pending();
+        }
+        match self.infcx.shallow(receiver) {
+            Ty::Error => return None,
+            Ty::Var(_) => {
+                self.error(span, "type annotations needed");
+                return None;
+            }
+            _ => {}
+        }
+        // Only trait methods can be called on a literal of unknown type.
+        let numeric = matches!(self.infcx.shallow(receiver),
+            Ty::IntVar(_) | Ty::FloatVar(_));
+        let cx = self.cx;
+        for (derefs, step) in self.autoderef(receiver, span).into_iter()
+            .enumerate()
+        {
+            let traits = self.candidate_traits(&step);
+            for autoref in [None, Some(false), Some(true)] {
+                let adjusted = match autoref {
+                    Some(mutable) => Ty::Ref(mutable, Box::new(step.clone())),
+                    None => step.clone(),
+                };
+                let pick = |item| Pick {
+                    item,
+                    step: step.clone(),
+                    derefs,
+                    autoref,
+                };
+                for &impl_id in &cx.inherent_impls {
+                    let header = cx.impl_header(impl_id);
+                    if numeric || !may_apply(head(&header.self_ty), &step) {
+                        continue;
+                    }
+                    let index = match cx.impl_item(impl_id, AssocKind::Fn, name) {
+                        Some(index) => index,
+                        None => continue,
+                    };
+                    let info = &cx.impls[impl_id];
+                    let id = ItemId::ImplItem(info.module, info.index, index);
+                    let sig = cx.fn_sig(id);
+                    if !sig.has_self {
+                        continue;
+                    }
+                    let generics = cx.generics_of(id);
+                    let matched = self.infcx.probe(|infcx| {
+                        let args = infcx.fresh_args(&generics);
+                        infcx.unify(&header.self_ty.subst(&args), &step)
+                            && infcx.unify(&sig.inputs[0].subst(&args), &adjusted)
+                    });
+                    if matched {
+                        let (item, _) = cx.ast(id);
+                        let vis = cx.krate.vis(info.module, &item.vis);
+                        if !cx.krate.is_visible(vis, self.scope.module) {
+                            self.error(span,
+                                format!("method `{}` is private", name));
+                        }
+                        return Some(pick(PickItem::Inherent(impl_id, index)));
+                    }
+                }
+                let mut found = Vec::new();
+                for &def in &traits {
+                    let index = match cx.trait_item(def, AssocKind::Fn, name) {
+                        Some(index) => index,
+                        None => continue,
+                    };
+                    let id = ItemId::TraitItem(def, index);
+                    let sig = cx.fn_sig(id);
+                    if !sig.has_self {
+                        continue;
+                    }
+                    let generics = cx.generics_of(id);
+                    let matched = self.infcx.probe(|infcx| {
+                        let args = infcx.fresh_args(&generics);
+                        if !infcx.unify(&sig.inputs[0].subst(&args), &adjusted) {
+                            return false;
+                        }
+                        let trait_ref = TraitRef {
+                            def,
+                            args: args[1..generics.parent].to_vec(),
+                        };
+                        !matches!(infcx.select(&args[0], &trait_ref, 0),
+                            Selection::No)
+                    });
+                    if matched {
+                        found.push(PickItem::Trait(def, index));
+                    }
+                }
+                match found.len() {
+                    0 => {}
+                    1 => return Some(pick(found[0])),
+                    _ => {
+                        self.error(span, format!(
+                            "multiple applicable items in scope: `{}`", name));
+                        return Some(pick(found[0]));
+                    }
+                }
+            }
+        }
+        let message = match numeric {
+            true => format!(
+                "can't call method `{}` on ambiguous numeric type `{}`", name,
+                self.ty_name(receiver)),
+            false => format!(
+                "no method named `{}` found for `{}` in the current scope",
+                name, self.ty_name(receiver)),
+        };
+        self.error(span, message);
+        None
+    }
+
+    /// Instantiate the method a call resolved to, returning the types of
+    /// its arguments after `self` and of its result.
+    pub(super) fn confirm_method(
+        &mut self,
+        pick: &Pick<'a>,
+        segment: &PathSegment<'a>,
+        span: Span,
+    ) -> (Vec<Ty<'a>>, Ty<'a>, Method<'a>) {
+        let cx = self.cx;
+        let id = match pick.item {
+            PickItem::Inherent(impl_id, index) => {
+                let info = &cx.impls[impl_id];
+                ItemId::ImplItem(info.module, info.index, index)
+            }
+            PickItem::Trait(def, index) => ItemId::TraitItem(def, index),
+        };
+        let generics = cx.generics_of(id);
+        let mut args = self.infcx.fresh_args(&generics);
+        self.apply_generics(segment, &mut args, &generics, generics.parent);
+        if let PickItem::Inherent(impl_id, _) = pick.item {
+            let self_ty = cx.impl_header(impl_id).self_ty.subst(&args);
+            self.infcx.unify(&self_ty, &pick.step);
+        }
+        let adjusted = match pick.autoref {
+            Some(mutable) => Ty::Ref(mutable, Box::new(pick.step.clone())),
+            None => pick.step.clone(),
+        };
+        let (mut inputs, output) = self.instantiate_fn(id, &args, span);
+        let receiver = inputs.remove(0);
+        self.infcx.unify(&receiver, &adjusted);
+        let method = Method {
+            callee: id,
+            args,
+            derefs: pick.derefs,
+            autoref: pick.autoref,
+        };
+        (inputs, output, method)
+    }
+
+    /// Instantiate the signature of a function with generic arguments,
+    /// registering its predicates.
+    pub(super) fn instantiate_fn(
+        &mut self,
+        id: ItemId,
+        args: &[Ty<'a>],
+        span: Span,
+    ) -> (Vec<Ty<'a>>, Ty<'a>) {
+        for pred in self.cx.predicates_of(id).iter() {
+            self.infcx.register(pred.subst(args), span, Cause::Misc);
+        }
+        let sig = self.cx.fn_sig(id);
+        let inputs = sig.inputs.iter()
+            .map(|ty| self.infcx.normalize(&ty.subst(args), span))
+            .collect();
+        let output = self.infcx.normalize(&sig.output.subst(args), span);
+        (inputs, output)
+    }
+
+    /// The value of an associated item or variant of a type, as in
+    /// `Vec::new` or `Option::<T>::None`.
+    pub(super) fn assoc_value(
+        &mut self,
+        base: &Ty<'a>,
+        segment: &PathSegment<'a>,
+        span: Span,
+    ) -> Ty<'a> {
+        let cx = self.cx;
+        let name = segment.name;
+        let base = self.infcx.shallow(base);
+        if let Ty::Adt(def, ref args) = base {
+            let adt = cx.adt(def);
+            let variant = adt.variants.iter()
+                .position(|variant| variant.name == name);
+            if let (super::AdtKind::Enum, Some(index)) = (adt.kind, variant) {
+                return self.ctor_ty(def, index, args.clone(), span);
+            }
+        }
+        if base == Ty::Error {
+            return Ty::Error;
+        }
+        for &impl_id in &cx.inherent_impls {
+            let header = cx.impl_header(impl_id);
+            if !may_apply(head(&header.self_ty), &base) {
+                continue;
+            }
+            let kinds = [AssocKind::Fn, AssocKind::Const];
+            let found = kinds.iter()
+                .find_map(|kind| {
+                    Some((*kind, cx.impl_item(impl_id, *kind, name)?))
+                });
+            let (kind, index) = match found {
+                Some(found) => found,
+                None => continue,
+            };
+            let info = &cx.impls[impl_id];
+            let id = ItemId::ImplItem(info.module, info.index, index);
+            let generics = cx.generics_of(id);
+            let matched = self.infcx.commit_if(|infcx| {
+                let args = infcx.fresh_args(&generics);
+                infcx.unify(&header.self_ty.subst(&args), &base)
+                    .then_some(args)
+            });
+            let mut args = match matched {
+                Some(args) => args,
+                None => continue,
+            };
+            let (item, _) = cx.ast(id);
+            let vis = cx.krate.vis(info.module, &item.vis);
+            if !cx.krate.is_visible(vis, self.scope.module) {
+                self.error(segment.span, format!(
+                    "associated item `{}` is private", name));
+            }
+            return self.item_value(id, kind, &mut args, segment, span);
+        }
+        let mut found = Vec::new();
+        for def in self.candidate_traits(&base) {
+            let kinds = [AssocKind::Fn, AssocKind::Const];
+            let item = kinds.iter()
+                .find_map(|kind| Some((*kind, cx.trait_item(def, *kind, name)?)));
+            let (kind, index) = match item {
+                Some(item) => item,
+                None => continue,
+            };
+            let generics = cx.generics_of(ItemId::Def(def));
+            let matched = self.infcx.probe(|infcx| {
+                let mut args = infcx.fresh_args(&generics);
+                args[0] = base.clone();
+                let trait_ref = TraitRef { def, args: args[1..].to_vec() };
+                !matches!(infcx.select(&base, &trait_ref, 0), Selection::No)
+            });
+            if matched {
+                found.push((def, kind, index));
+            }
+        }
+        let (def, kind, index) = match found.len() {
+            0 => {
+                self.error(segment.span, format!(
+                    "no function or associated item named `{}` found for `{}` \
+                        in the current scope", name, self.ty_name(&base)));
+                return Ty::Error;
+            }
+            1 => found[0],
+            _ => {
+                self.error(segment.span, format!(
+                    "multiple applicable items in scope: `{}`", name));
+                found[0]
+            }
+        };
+        let id = ItemId::TraitItem(def, index);
+        let generics = cx.generics_of(id);
+        let mut args = self.infcx.fresh_args(&generics);
+        args[0] = base;
+        self.item_value(id, kind, &mut args, segment, span)
+    }
+
+    /// The value of an item of a trait, with the arguments of the trait,
+    /// as in `Default::default` or `<T as Iterator>::next`.
+    pub(super) fn trait_value(
+        &mut self,
+        def: DefId,
+        trait_args: Option<Vec<Ty<'a>>>,
+        trait_segment: &PathSegment<'a>,
+        segment: &PathSegment<'a>,
+        span: Span,
+    ) -> Ty<'a> {
+        let cx = self.cx;
+        let kinds = [AssocKind::Fn, AssocKind::Const];
+        let item = kinds.iter()
+            .find_map(|kind| {
+                Some((*kind, cx.trait_item(def, *kind, segment.name)?))
+            });
+        let (kind, index) = match item {
+            Some(item) => item,
+            None => {
+                self.error(segment.span, format!(
+                    "cannot find method or associated constant `{}` in trait \
+                        `{}`", segment.name, cx.krate.defs[def].name));
+                return Ty::Error;
+            }
+        };
+        let id = ItemId::TraitItem(def, index);
+        let generics = cx.generics_of(id);
+        let mut args = self.infcx.fresh_args(&generics);
+        match trait_args {
+            Some(trait_args) => {
+                args[..trait_args.len()].clone_from_slice(&trait_args);
+            }
+            None => {
+                let trait_generics = cx.generics_of(ItemId::Def(def));
+                self.apply_generics(trait_segment, &mut args, &trait_generics,
+                    1);
+            }
+        }
+        self.item_value(id, kind, &mut args, segment, span)
+    }
+
+    // The value of a function or constant of an impl or trait with the
+    // arguments of the impl or trait.
+    fn item_value(
+        &mut self,
+        id: ItemId,
+        kind: AssocKind,
+        args: &mut Vec<Ty<'a>>,
+        segment: &PathSegment<'a>,
+        span: Span,
+    ) -> Ty<'a> {
+        let generics = self.cx.generics_of(id);
+        self.apply_generics(segment, args, &generics, generics.parent);
+        match kind {
+            AssocKind::Fn => {
+                self.callees.push((span, id));
+                let (inputs, output) = self.instantiate_fn(id, args, span);
+                Ty::Fn(inputs, Box::new(output))
+            }
+            _ => {
+                for pred in self.cx.predicates_of(id).iter() {
+                    self.infcx.register(pred.subst(args), span, Cause::Misc);
+                }
+                let ty = self.cx.const_ty(id).subst(args);
+                self.infcx.normalize(&ty, span)
+            }
+        }
+    }
+}
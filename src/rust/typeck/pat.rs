@@ -0,0 +1,458 @@
+// Rust pattern type checking
+//
+//! Type checking of patterns against the type of the value they match.
+//!
+//! Patterns other than bindings and `&` patterns see through references to
+//! the value they match, and the bindings inside them then bind references
+//! (the default binding modes of RFC 2005).
+
+use super::expr::FnCtxt;
+use super::{AdtKind, CtorKind, Ty};
+use crate::rust::resolve::{DefId, DefKind, Namespace, Res};
+use crate::rust::{
+    ExprKind, ItemKind, Lit, Path, PathSegment, Pattern, PatternKind,
+};
+
+impl<'c, 'k, 'a> FnCtxt<'c, 'k, 'a> {
+    /// Check a pattern against the type of the value it matches, adding
+    /// its bindings to the variables in scope.
+    pub(super) fn check_pat(&mut self, pat: &Pattern<'a>, expected: &Ty<'a>) {
+        self.check_pat_mode(pat, expected, None);
+    }
+
+    // Check a pattern with the default binding mode: by value, or by
+    // reference (`Some(true)` if mutable).
+    fn check_pat_mode(
+        &mut self,
+        pat: &Pattern<'a>,
+        expected: &Ty<'a>,
+        mut mode: Option<bool>,
+    ) {
+        self.patterns.push((pat.span, expected.clone()));
+        let const_path = match pat.kind {
+            PatternKind::Ident(false, false, name, None) => {
+                self.const_path(name, pat)
+            }
+            _ => None,
+        };
+        let peel = match pat.kind {
+            PatternKind::Wild
+            | PatternKind::Rest
+            | PatternKind::Ref(..)
+            | PatternKind::Or(_)
+            | PatternKind::Macro(_) => false,
+            PatternKind::Ident(..) => const_path.is_some(),
+            PatternKind::Lit(ref expr) => !matches!(expr.kind,
+                ExprKind::Lit(Lit::Str(_)) | ExprKind::Lit(Lit::ByteStr(_))),
+            _ => true,
+        };
+        let mut expected = self.infcx.shallow(expected);
+        if peel {
+            while let Ty::Ref(mutable, inner) = expected {
+                mode = Some(mutable && mode != Some(false));
+                expected = self.infcx.shallow(&inner);
+            }
+        }
+        if let Some(ref path) = const_path {
+            return self.check_path_pat(path, &expected, pat);
+        }
+        match pat.kind {
+            PatternKind::Wild | PatternKind::Rest => {}
+            PatternKind::Ident(is_ref, is_mut, name, ref sub) => {
+                let ty = match (is_ref, mode) {
+                    (true, _) => Ty::Ref(is_mut, Box::new(expected.clone())),
+                    (false, Some(mutable)) if !is_mut => {
+                        Ty::Ref(mutable, Box::new(expected.clone()))
+                    }
+                    _ => expected.clone(),
+                };
+                if let Some(ref sub) = sub {
+                    self.check_pat_mode(sub, &expected, mode);
+                }
+                self.bindings.push((pat.span, ty.clone()));
+                self.locals.push((name, ty));
+            }
+            PatternKind::Lit(ref expr) => {
+                let ty = self.check_expr(expr, Some(&expected));
+                self.unify_or_error(pat.span, &expected, &ty);
+            }
+            PatternKind::Range(ref start, ref end, _) => {
+                for expr in start.iter().chain(end) {
+                    let ty = self.check_expr(expr, Some(&expected));
+                    self.unify_or_error(expr.span, &expected, &ty);
+                }
+            }
+            PatternKind::Path(ref path) => {
+                self.check_path_pat(path, &expected, pat);
+            }
+            PatternKind::Ref(mutable, ref inner) => {
+                let inner_ty = match expected {
+                    Ty::Ref(found, ref inner) if found == mutable => {
+                        (**inner).clone()
+                    }
+                    Ty::Error => Ty::Error,
+                    _ => {
+                        let inner = self.infcx.fresh();
+                        let ty = Ty::Ref(mutable, Box::new(inner.clone()));
+                        self.unify_or_error(pat.span, &expected, &ty);
+                        inner
+                    }
+                };
+                self.check_pat_mode(inner, &inner_ty, None);
+            }
+            PatternKind::Tuple(ref pats) => {
+                let has_rest = pats.iter()
+                    .any(|pat| pat.kind == PatternKind::Rest);
+                let tys = match expected {
+                    Ty::Tuple(ref tys) => tys.clone(),
+                    Ty::Error => Vec::new(),
+                    _ if has_rest => {
+                        if let Ty::Var(_) = expected {
+                            self.error(pat.span, "type annotations needed");
+                        } else {
+                            self.mismatch_tuple(pat, &expected);
+                        }
+                        Vec::new()
+                    }
+                    _ => {
+                        let tys: Vec<_> = pats.iter()
+                            .map(|_| self.infcx.fresh())
+                            .collect();
+                        let ty = Ty::Tuple(tys.clone());
+                        self.unify_or_error(pat.span, &expected, &ty);
+                        tys
+                    }
+                };
+                let arity = pats.len() - has_rest as usize;
+                let valid = match has_rest {
+                    true => arity <= tys.len(),
+                    false => arity == tys.len(),
+                };
+                if !valid && expected != Ty::Error {
+                    self.error(pat.span, format!(
+                        "mismatched types: expected a tuple with {} elements, \
+                            found one with {} elements", tys.len(), arity));
+                }
+                self.check_pat_list(pats, &tys, valid, mode);
+            }
+            PatternKind::TupleStruct(ref path, ref pats) => {
+                let variant = self.resolve_variant(path, Namespace::Value);
+                let (def, index, args) = match variant {
+                    Some(variant) => variant,
+                    None => return self.check_pat_list(pats, &[], false, mode),
+                };
+                let ty = Ty::Adt(def, args.clone());
+                self.unify_or_error(pat.span, &expected, &ty);
+                let adt = self.cx.adt(def);
+                let variant = &adt.variants[index];
+                if variant.ctor != CtorKind::Tuple {
+                    self.error(pat.span, format!(
+                        "expected tuple struct or tuple variant, found `{}`",
+                        path));
+                    return self.check_pat_list(pats, &[], false, mode);
+                }
+                let tys: Vec<_> = variant.fields.iter()
+                    .map(|field| {
+                        self.infcx.normalize(&field.ty.subst(&args), pat.span)
+                    })
+                    .collect();
+                let has_rest = pats.iter()
+                    .any(|pat| pat.kind == PatternKind::Rest);
+                let arity = pats.len() - has_rest as usize;
+                let valid = match has_rest {
+                    true => arity <= tys.len(),
+                    false => arity == tys.len(),
+                };
+                if !valid {
+                    let s = |n| if n == 1 { "" } else { "s" };
+                    self.error(pat.span, format!(
+                        "this pattern has {} field{}, but the corresponding \
+                            tuple variant has {} field{}", arity, s(arity),
+                        tys.len(), s(tys.len())));
+                }
+                self.check_pat_list(pats, &tys, valid, mode);
+            }
+            PatternKind::Struct(ref path, ref fields, rest) => {
+                let variant = self.resolve_variant(path, Namespace::Type);
+                let (def, index, args) = match variant {
+                    Some(variant) => variant,
+                    None => {
+                        for field in fields {
+                            self.check_pat_mode(&field.pattern, &Ty::Error,
+                                mode);
+                        }
+                        return;
+                    }
+                };
+                let ty = Ty::Adt(def, args.clone());
+                self.unify_or_error(pat.span, &expected, &ty);
+                let adt = self.cx.adt(def);
+                let variant = &adt.variants[index];
+                let mut seen = Vec::new();
+                for field in fields {
+                    let ty = match variant.field(field.name) {
+                        Some(index) => {
+                            if seen.contains(&index) {
+                                self.error(field.span, format!(
+                                    "field `{}` bound multiple times in the \
+                                        pattern", field.name));
+                            }
+                            seen.push(index);
+                            let ty = variant.fields[index].ty.subst(&args);
+                            self.infcx.normalize(&ty, field.span)
+                        }
+                        None => {
+                            self.error(field.span, format!(
+                                "`{}` does not have a field named `{}`",
+                                variant.name, field.name));
+                            Ty::Error
+                        }
+                    };
+                    self.check_pat_mode(&field.pattern, &ty, mode);
+                }
+                let missing: Vec<_> = variant.fields.iter().enumerate()
+                    .filter(|(index, _)| !seen.contains(index))
+                    .map(|(index, field)| match field.name {
+                        Some(name) => format!("`{}`", name),
+                        None => format!("`{}`", index),
+                    })
+                    .collect();
+                if !rest && adt.kind != AdtKind::Union && !missing.is_empty() {
+                    let s = if missing.len() == 1 { "" } else { "s" };
+                    self.error(pat.span, format!(
+                        "pattern does not mention field{} {}", s,
+                        missing.join(", ")));
+                }
+            }
+            PatternKind::Slice(ref pats) => {
+                let (elem, len) = match expected {
+                    Ty::Array(ref elem, len) => ((**elem).clone(), len),
+                    Ty::Slice(ref elem) => ((**elem).clone(), None),
+                    Ty::Error => (Ty::Error, None),
+                    _ => {
+                        self.error(pat.span, format!(
+                            "expected an array or slice, found `{}`",
+                            self.ty_name(&expected)));
+                        (Ty::Error, None)
+                    }
+                };
+                let is_rest = |pat: &Pattern| match pat.kind {
+                    PatternKind::Rest => true,
+                    PatternKind::Ident(_, _, _, Some(ref sub)) => {
+                        sub.kind == PatternKind::Rest
+                    }
+                    _ => false,
+                };
+                let has_rest = pats.iter().any(is_rest);
+                let arity = pats.len() - has_rest as usize;
+                if let (Some(len), false) = (len, has_rest) {
+                    if len != arity as u64 {
+                        self.error(pat.span, format!(
+                            "pattern requires {} elements but array has {}",
+                            arity, len));
+                    }
+                }
+                for pat in pats {
+                    if !is_rest(pat) {
+                        self.check_pat_mode(pat, &elem, mode);
+                        continue;
+                    }
+                    let rest = match (&expected, len) {
+                        (Ty::Array(..), Some(len)) => Ty::Array(
+                            Box::new(elem.clone()),
+                            len.checked_sub(arity as u64)),
+                        (Ty::Array(..), None) => {
+                            Ty::Array(Box::new(elem.clone()), None)
+                        }
+                        _ => Ty::Slice(Box::new(elem.clone())),
+                    };
+                    self.check_pat_mode(pat, &rest, mode);
+                }
+            }
+            PatternKind::Or(ref alternatives) => {
+                let start = self.locals.len();
+                let mut first = Vec::new();
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    self.check_pat_mode(alternative, &expected, mode);
+                    if i == 0 {
+                        first = self.locals[start..].to_vec();
+                        continue;
+                    }
+                    let bound = self.locals.split_off(start + first.len());
+                    for (name, ty) in bound {
+                        match first.iter().find(|(first, _)| *first == name) {
+                            Some((_, first)) => {
+                                let first = first.clone();
+                                self.unify_or_error(alternative.span, &first,
+                                    &ty);
+                            }
+                            None => self.error(alternative.span, format!(
+                                "variable `{}` is not bound in all patterns",
+                                name)),
+                        }
+                    }
+                }
+            }
+            PatternKind::Macro(_) => {
+                self.error(pat.span,
+                    "macros in pattern position are not supported");
+            }
+        }
+    }
+
+    fn mismatch_tuple(&self, pat: &Pattern<'a>, expected: &Ty<'a>) {
+        self.error(pat.span, format!(
+            "mismatched types: expected `{}`, found tuple",
+            self.ty_name(expected)));
+    }
+
+    // Check the elements of a tuple or tuple struct pattern, with `..`
+    // standing for the elements not mentioned.
+    fn check_pat_list(
+        &mut self,
+        pats: &[Pattern<'a>],
+        tys: &[Ty<'a>],
+        valid: bool,
+        mode: Option<bool>,
+    ) {
+        let rest = pats.iter()
+            .position(|pat| pat.kind == PatternKind::Rest)
+            .unwrap_or(pats.len());
+        for (i, pat) in pats.iter().enumerate() {
+            let index = match i > rest {
+                true => (tys.len() + i).checked_sub(pats.len()),
+                false => Some(i),
+            };
+            let ty = match index.and_then(|index| tys.get(index)) {
+                Some(ty) if valid => ty.clone(),
+                _ => Ty::Error,
+            };
+            self.check_pat_mode(pat, &ty, mode);
+        }
+    }
+
+    // Check a pattern naming a constant or unit struct or variant.
+    fn check_path_pat(
+        &mut self,
+        path: &Path<'a>,
+        expected: &Ty<'a>,
+        pat: &Pattern<'a>,
+    ) {
+        let ty = self.check_path(path);
+        if let Ty::Fn(..) = ty {
+            self.error(pat.span, format!("expected unit struct, unit variant \
+                or constant, found tuple variant `{}`", path));
+            return;
+        }
+        self.unify_or_error(pat.span, expected, &ty);
+    }
+
+    // A binding pattern that names a constant or a unit struct or variant
+    // instead, as a path.
+    fn const_path(&self, name: &'a str, pat: &Pattern<'a>) -> Option<Path<'a>> {
+        let cx = self.cx;
+        let imported = self.scope.uses.iter().rev()
+            .find(|(used, ns, _)| *used == name && *ns == Namespace::Value)
+            .map(|(_, _, res)| res.clone());
+        let res = imported
+            .or_else(|| {
+                cx.krate.lookup(self.scope.module, name, Namespace::Value)
+            });
+        let def = match res {
+            Some(Res::Def(def)) => def,
+            _ => return None,
+        };
+        let is_const = match cx.krate.defs[def].kind {
+            DefKind::Variant(enum_def, index) => {
+                cx.adt(enum_def).variants[index].ctor == CtorKind::Unit
+            }
+            DefKind::Item(_) => match cx.krate.item(def).map(|item| &item.kind) {
+                Some(ItemKind::Const(..)) => true,
+                Some(ItemKind::Struct(..)) => {
+                    cx.adt(def).variants[0].ctor == CtorKind::Unit
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+        let segment = PathSegment { name, generics: Vec::new(), span: pat.span };
+        is_const.then(|| Path {
+            global: false,
+            segments: vec![segment],
+            span: pat.span,
+        })
+    }
+
+    /// Resolve the path of a struct pattern or literal, or of a tuple
+    /// struct pattern, to a struct or variant and the generic arguments of
+    /// its type.
+    pub(super) fn resolve_variant(&mut self, path: &Path<'a>, ns: Namespace)
+        -> Option<(DefId, usize, Vec<Ty<'a>>)>
+    {
+        let cx = self.cx;
+        let segments = &path.segments;
+        if !path.global && segments[0].name == "Self" {
+            let self_ty = self.scope.self_ty.clone()
+                .map(|ty| self.infcx.shallow(&ty));
+            match (self_ty, segments.len()) {
+                (Some(Ty::Adt(def, args)), 1)
+                    if cx.adt(def).kind != AdtKind::Enum =>
+                {
+                    return Some((def, 0, args));
+                }
+                (Some(Ty::Adt(def, args)), 2) => {
+                    let index = cx.adt(def).variants.iter()
+                        .position(|variant| variant.name == segments[1].name);
+                    let kind = cx.adt(def).kind;
+                    if let (AdtKind::Enum, Some(index)) = (kind, index) {
+                        return Some((def, index, args));
+                    }
+                }
+                _ => {}
+            }
+            self.error(path.span, format!(
+                "expected struct or variant, found `{}`", path));
+            return None;
+        }
+        let (res, len) = cx.resolve(&self.scope, path, ns)?;
+        let def = match res {
+            Res::Def(def) if len == segments.len() => def,
+            Res::External(_) => return None,
+            _ => {
+                self.error(path.span, format!(
+                    "expected struct or variant, found `{}`", path));
+                return None;
+            }
+        };
+        match cx.krate.defs[def].kind {
+            DefKind::Variant(enum_def, index) => {
+                let args = self.variant_args(enum_def, path);
+                return Some((enum_def, index, args));
+            }
+            DefKind::Item(_) => {}
+            _ => {
+                self.error(path.span, format!(
+                    "expected struct or variant, found `{}`", path));
+                return None;
+            }
+        }
+        let segment = segments.last().unwrap();
+        let ty = match cx.krate.item(def).map(|item| &item.kind) {
+            Some(ItemKind::Struct(..))
+            | Some(ItemKind::Union(..))
+            | Some(ItemKind::Type(..)) => cx.res_ty(&self.scope, &res, segment),
+            _ => Ty::Error,
+        };
+        let ty = self.infcx.replace_infer(&ty);
+        match self.infcx.normalize(&ty, path.span) {
+            Ty::Adt(def, args) if cx.adt(def).kind != AdtKind::Enum => {
+                Some((def, 0, args))
+            }
+            _ => {
+                self.error(path.span, format!(
+                    "expected struct or variant, found `{}`", path));
+                None
+            }
+        }
+    }
+}
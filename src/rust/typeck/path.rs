@@ -0,0 +1,218 @@
+// Rust value paths
+//
+//! Type checking of paths in expressions: variables, functions, constants,
+//! constructors and associated items.
+//!
+//! A path is resolved as far as the resolver can, and what is left is
+//! looked up as an associated item of the type or trait it resolved to.
+
+use super::expr::FnCtxt;
+use super::{AdtKind, GenericsDef, ItemId, Ty};
+use crate::rust::resolve::{DefId, DefKind, Namespace, Res};
+use crate::rust::{Bound, GenericArg, ItemKind, Path, PathSegment, Type};
+use crate::Span;
+
+impl<'c, 'k, 'a> FnCtxt<'c, 'k, 'a> {
+    /// The type of a path in an expression.
+    pub(super) fn check_path(&mut self, path: &Path<'a>) -> Ty<'a> {
+        let segments = &path.segments;
+        let first = &segments[0];
+        if !path.global && segments.len() == 1 {
+            let local = self.locals.iter().rev()
+                .find(|(name, _)| *name == first.name);
+            if let Some((_, ty)) = local {
+                return ty.clone();
+            }
+        }
+        if !path.global && segments.len() <= 2 {
+            let base = match first.name {
+                "Self" => self.scope.self_ty.clone(),
+                name => self.scope.param(name)
+                    .map(|index| Ty::Param(index, self.scope.params[index].0)),
+            };
+            match (base, segments.get(1)) {
+                (Some(base), Some(segment)) => {
+                    return self.assoc_value(&base, segment, path.span);
+                }
+                (Some(Ty::Adt(def, args)), None)
+                    if self.cx.adt(def).kind == AdtKind::Struct =>
+                {
+                    return self.ctor_ty(def, 0, args, path.span);
+                }
+                (Some(_), None) => {
+                    self.error(path.span, format!(
+                        "expected value, found type `{}`", path));
+                    return Ty::Error;
+                }
+                (None, _) => {}
+            }
+        }
+        let (res, len) = match self.cx.resolve(&self.scope, path,
+            Namespace::Value)
+        {
+            Some(resolved) => resolved,
+            None => return Ty::Error,
+        };
+        if len == segments.len() {
+            return self.res_value(&res, path);
+        }
+        let rest = &segments[len..];
+        if rest.len() > 1 {
+            self.error(path.span, "ambiguous associated item");
+            return Ty::Error;
+        }
+        if let Res::Def(def) = res {
+            if self.cx.is_trait(def) {
+                return self.trait_value(def, None, &segments[len - 1],
+                    &rest[0], path.span);
+            }
+        }
+        let ty = self.cx.res_ty(&self.scope, &res, &segments[len - 1]);
+        let ty = self.infcx.replace_infer(&ty);
+        let ty = self.infcx.normalize(&ty, path.span);
+        self.assoc_value(&ty, &rest[0], path.span)
+    }
+
+    /// The type of `<T as Trait>::name` or `<T>::name` in an expression.
+    pub(super) fn check_qualified(
+        &mut self,
+        ty: &Type<'a>,
+        trait_path: Option<&Path<'a>>,
+        rest: &[PathSegment<'a>],
+        span: Span,
+    ) -> Ty<'a> {
+        let self_ty = self.lower(ty);
+        let segment = match rest {
+            [segment] => segment,
+            _ => {
+                self.error(span, "ambiguous associated item");
+                return Ty::Error;
+            }
+        };
+        let path = match trait_path {
+            Some(path) => path,
+            None => return self.assoc_value(&self_ty, segment, span),
+        };
+        let bound = Bound { maybe: false, path: path.clone() };
+        let bound = match self.cx.lower_bound(&self.scope, &self_ty, &bound) {
+            Some(bound) => bound,
+            None => return Ty::Error,
+        };
+        let trait_ref = self.infcx.resolve_trait(&bound.trait_ref);
+        let trait_ref = super::TraitRef {
+            def: trait_ref.def,
+            args: trait_ref.args.iter()
+                .map(|ty| self.infcx.replace_infer(ty))
+                .collect(),
+        };
+        let mut args = vec![self_ty];
+        args.extend(trait_ref.args);
+        let trait_segment = path.segments.last().unwrap();
+        self.trait_value(trait_ref.def, Some(args), trait_segment, segment,
+            span)
+    }
+
+    // The value a path resolved to in full.
+    fn res_value(&mut self, res: &Res, path: &Path<'a>) -> Ty<'a> {
+        let cx = self.cx;
+        let segment = path.segments.last().unwrap();
+        let def = match *res {
+            Res::Def(def) => def,
+            Res::Primitive(name) => {
+                self.error(path.span, format!(
+                    "expected value, found builtin type `{}`", name));
+                return Ty::Error;
+            }
+            Res::External(_) => return Ty::Error,
+        };
+        match cx.krate.defs[def].kind {
+            DefKind::Variant(enum_def, index) => {
+                let args = self.variant_args(enum_def, path);
+                return self.ctor_ty(enum_def, index, args, path.span);
+            }
+            DefKind::Module(_) => {
+                self.error(path.span, format!(
+                    "expected value, found module `{}`", path));
+                return Ty::Error;
+            }
+            DefKind::Item(_) | DefKind::Foreign(..) => {}
+        }
+        let id = ItemId::Def(def);
+        match cx.krate.item(def).map(|item| &item.kind) {
+            Some(ItemKind::Fn(_)) => {
+                let generics = cx.generics_of(id);
+                let mut args = self.infcx.fresh_args(&generics);
+                self.apply_generics(segment, &mut args, &generics, 0);
+                self.callees.push((path.span, id));
+                let (inputs, output) = self.instantiate_fn(id, &args,
+                    path.span);
+                Ty::Fn(inputs, Box::new(output))
+            }
+            Some(ItemKind::Const(..)) | Some(ItemKind::Static(..)) => {
+                let ty = cx.const_ty(id);
+                self.infcx.normalize(&ty, path.span)
+            }
+            Some(ItemKind::Struct(..)) => {
+                let generics = cx.generics_of(id);
+                let mut args = self.infcx.fresh_args(&generics);
+                self.apply_generics(segment, &mut args, &generics, 0);
+                self.ctor_ty(def, 0, args, path.span)
+            }
+            _ => {
+                self.error(path.span, format!(
+                    "expected value, found `{}`", path));
+                Ty::Error
+            }
+        }
+    }
+
+    /// The generic arguments of an enum from a path to one of its variants,
+    /// given on the enum as in `Option::<T>::None` or on the variant.
+    pub(super) fn variant_args(&mut self, def: DefId, path: &Path<'a>)
+        -> Vec<Ty<'a>>
+    {
+        let generics = self.cx.generics_of(ItemId::Def(def));
+        let mut args = self.infcx.fresh_args(&generics);
+        let segments = &path.segments;
+        let segment = match segments.len() {
+            1 => &segments[0],
+            len if segments[len - 2].generics.is_empty() => &segments[len - 1],
+            len => &segments[len - 2],
+        };
+        self.apply_generics(segment, &mut args, &generics, 0);
+        args
+    }
+
+    /// Replace the arguments of the generic parameters from `first` on by
+    /// those given on a path segment, if any.
+    pub(super) fn apply_generics(
+        &mut self,
+        segment: &PathSegment<'a>,
+        args: &mut [Ty<'a>],
+        generics: &GenericsDef<'a>,
+        first: usize,
+    ) {
+        let given: Vec<_> = segment.generics.iter()
+            .filter(|arg| {
+                matches!(arg, GenericArg::Type(_) | GenericArg::Const(_))
+            })
+            .collect();
+        if given.is_empty() {
+            return;
+        }
+        let params: Vec<_> = (first..generics.names.len())
+            .filter(|index| generics.names[*index] != "impl Trait")
+            .collect();
+        if given.len() != params.len() {
+            self.error(segment.span, format!(
+                "`{}` takes {} generic arguments but {} were supplied",
+                segment.name, params.len(), given.len()));
+            return;
+        }
+        for (index, arg) in params.into_iter().zip(given) {
+            if let (GenericArg::Type(ty), false) = (arg, generics.consts[index]) {
+                args[index] = self.lower(ty);
+            }
+        }
+    }
+}
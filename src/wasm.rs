@@ -0,0 +1,502 @@
+// WebAssembly
+//
+//! Generates WebAssembly modules from IR modules, and encodes them as
+//! binary `.wasm` files or prints them as `.wat` text.  Binary modules
+//! decode back to a [`Module`], which can be validated.
+//!
+//! # Memory
+//!
+//! Pointers are 32-bit addresses in the module's one linear memory,
+//! exported as `memory`.  They keep the eight bytes the IR gives them in
+//! memory, the address in the low four, so aggregates are laid out as the
+//! front ends expect.  Global variables are placed from address 1024 on,
+//! with their initial values in data segments, followed by the constant
+//! aggregates functions use, then a 64 KiB stack, which grows down from the
+//! address in the mutable global `__stack_pointer`.  The end of the stack
+//! is exported as `__heap_base`, where memory free for the embedder
+//! starts.  Each global variable that isn't local (with a `.` in its name)
+//! is exported too, as a global holding its address.
+//!
+//! A function with `alloca`s or aggregate values has a frame on the stack,
+//! which its prologue allocates and each return frees.  Every aggregate
+//! value gets a slot of the frame, only written where it is defined, and
+//! is the address of that slot; aggregates are passed as the address of
+//! their value, and returned to an address passed before the arguments.
+//! The extra arguments of a variadic function are laid out in the frame of
+//! the caller as a struct of their types, and passed as its address after
+//! the others.  A `va_list` keeps the address of the next one at offset 8,
+//! where `va_start` puts that of the buffer and each `va_arg` aligns it and
+//! moves it past the argument it reads.
+//!
+//! # Functions
+//!
+//! Functions declared but not defined are imported from the module `env`,
+//! when they are used, and functions defined are exported unless they are
+//! local.  Functions whose address is taken are put in the table, from
+//! index 1, and their addresses are those indices, so a null pointer is
+//! never one; calls through pointers are `call_indirect`s.
+//!
+//! Integers up to 32 bits and pointers are `i32`s, `i64`s are `i64`s, and
+//! `i128` isn't supported.  An integer narrower than 32 bits is kept sign
+//! extended from its width, but for `i1`, which is 0 or 1, as the IR
+//! wraps constants.  Values used once, right where they are computed, are
+//! left on the operand stack; the others are kept in locals.
+//!
+//! The control flow of each function is rebuilt from its blocks with the
+//! algorithm of Norman Ramsey's "Beyond Relooper" (see [`stackify`]), or,
+//! when the control-flow graph isn't reducible, with a loop dispatching on
+//! the block to run next.
+
+mod binary;
+mod lower;
+mod stackify;
+mod text;
+mod validate;
+
+use crate::ir;
+use crate::Diagnostic;
+
+pub use self::binary::decode;
+pub use self::validate::validate;
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+/// Translate an IR module to a WebAssembly module.
+pub fn translate(module: &ir::Module) -> Result<Module> {
+    lower::lower(module)
+}
+
+/// The type of a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValType {
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl ValType {
+    /// Get the name of the type in the text format.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ValType::I32 => "i32",
+            ValType::I64 => "i64",
+            ValType::F32 => "f32",
+            ValType::F64 => "f64",
+        }
+    }
+}
+
+use self::ValType::{F32, F64, I32, I64};
+
+/// The type of a function
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct FuncType {
+    pub params: Vec<ValType>,
+    pub results: Vec<ValType>,
+}
+
+/// The alignment, as a power of two, and constant offset of a memory access
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MemArg {
+    pub align: u32,
+    pub offset: u32,
+}
+
+/// An instruction.  Blocks, loops and `if`s take and leave no values.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Instr {
+    Unreachable,
+    Nop,
+    Block,
+    Loop,
+    If,
+    Else,
+    End,
+    Br(u32),
+    BrIf(u32),
+    /// Labels of each index, and the default label
+    BrTable(Vec<u32>, u32),
+    Return,
+    Call(u32),
+    /// Call through the table, of a function of a type
+    CallIndirect(u32),
+    Drop,
+    Select,
+    LocalGet(u32),
+    LocalSet(u32),
+    LocalTee(u32),
+    GlobalGet(u32),
+    GlobalSet(u32),
+    /// Load or store of an opcode from `0x28` to `0x3E`
+    Memory(u8, MemArg),
+    I32Const(i32),
+    I64Const(i64),
+    /// `f32` of its bits
+    F32Const(u32),
+    /// `f64` of its bits
+    F64Const(u64),
+    /// Numeric instruction of an opcode from `0x45` to `0xC4`
+    Numeric(u8),
+    /// Saturating conversion of a float to an integer, of a subopcode of
+    /// `0xFC` from 0 to 7
+    TruncSat(u8),
+    MemoryCopy,
+    MemoryFill,
+}
+
+// The numeric instructions, from opcode `0x45` on: their names and the
+// types of their operands and result.
+const NUMERIC: &[(&str, &[ValType], ValType)] = &[
+    ("i32.eqz", &[I32], I32),
+    ("i32.eq", &[I32, I32], I32),
+    ("i32.ne", &[I32, I32], I32),
+    ("i32.lt_s", &[I32, I32], I32),
+    ("i32.lt_u", &[I32, I32], I32),
+    ("i32.gt_s", &[I32, I32], I32),
+    ("i32.gt_u", &[I32, I32], I32),
+    ("i32.le_s", &[I32, I32], I32),
+    ("i32.le_u", &[I32, I32], I32),
+    ("i32.ge_s", &[I32, I32], I32),
+    ("i32.ge_u", &[I32, I32], I32),
+    ("i64.eqz", &[I64], I32),
+    ("i64.eq", &[I64, I64], I32),
+    ("i64.ne", &[I64, I64], I32),
+    ("i64.lt_s", &[I64, I64], I32),
+    ("i64.lt_u", &[I64, I64], I32),
+    ("i64.gt_s", &[I64, I64], I32),
+    ("i64.gt_u", &[I64, I64], I32),
+    ("i64.le_s", &[I64, I64], I32),
+    ("i64.le_u", &[I64, I64], I32),
+    ("i64.ge_s", &[I64, I64], I32),
+    ("i64.ge_u", &[I64, I64], I32),
+    ("f32.eq", &[F32, F32], I32),
+    ("f32.ne", &[F32, F32], I32),
+    ("f32.lt", &[F32, F32], I32),
+    ("f32.gt", &[F32, F32], I32),
+    ("f32.le", &[F32, F32], I32),
+    ("f32.ge", &[F32, F32], I32),
+    ("f64.eq", &[F64, F64], I32),
+    ("f64.ne", &[F64, F64], I32),
+    ("f64.lt", &[F64, F64], I32),
+    ("f64.gt", &[F64, F64], I32),
+    ("f64.le", &[F64, F64], I32),
+    ("f64.ge", &[F64, F64], I32),
+    ("i32.clz", &[I32], I32),
+    ("i32.ctz", &[I32], I32),
+    ("i32.popcnt", &[I32], I32),
+    ("i32.add", &[I32, I32], I32),
+    ("i32.sub", &[I32, I32], I32),
+    ("i32.mul", &[I32, I32], I32),
+    ("i32.div_s", &[I32, I32], I32),
+    ("i32.div_u", &[I32, I32], I32),
+    ("i32.rem_s", &[I32, I32], I32),
+    ("i32.rem_u", &[I32, I32], I32),
+    ("i32.and", &[I32, I32], I32),
+    ("i32.or", &[I32, I32], I32),
+    ("i32.xor", &[I32, I32], I32),
+    ("i32.shl", &[I32, I32], I32),
+    ("i32.shr_s", &[I32, I32], I32),
+    ("i32.shr_u", &[I32, I32], I32),
+    ("i32.rotl", &[I32, I32], I32),
+    ("i32.rotr", &[I32, I32], I32),
+    ("i64.clz", &[I64], I64),
+    ("i64.ctz", &[I64], I64),
+    ("i64.popcnt", &[I64], I64),
+    ("i64.add", &[I64, I64], I64),
+    ("i64.sub", &[I64, I64], I64),
+    ("i64.mul", &[I64, I64], I64),
+    ("i64.div_s", &[I64, I64], I64),
+    ("i64.div_u", &[I64, I64], I64),
+    ("i64.rem_s", &[I64, I64], I64),
+    ("i64.rem_u", &[I64, I64], I64),
+    ("i64.and", &[I64, I64], I64),
+    ("i64.or", &[I64, I64], I64),
+    ("i64.xor", &[I64, I64], I64),
+    ("i64.shl", &[I64, I64], I64),
+    ("i64.shr_s", &[I64, I64], I64),
+    ("i64.shr_u", &[I64, I64], I64),
+    ("i64.rotl", &[I64, I64], I64),
+    ("i64.rotr", &[I64, I64], I64),
+    ("f32.abs", &[F32], F32),
+    ("f32.neg", &[F32], F32),
+    ("f32.ceil", &[F32], F32),
+    ("f32.floor", &[F32], F32),
+    ("f32.trunc", &[F32], F32),
+    ("f32.nearest", &[F32], F32),
+    ("f32.sqrt", &[F32], F32),
+    ("f32.add", &[F32, F32], F32),
+    ("f32.sub", &[F32, F32], F32),
+    ("f32.mul", &[F32, F32], F32),
+    ("f32.div", &[F32, F32], F32),
+    ("f32.min", &[F32, F32], F32),
+    ("f32.max", &[F32, F32], F32),
+    ("f32.copysign", &[F32, F32], F32),
+    ("f64.abs", &[F64], F64),
+    ("f64.neg", &[F64], F64),
+    ("f64.ceil", &[F64], F64),
+    ("f64.floor", &[F64], F64),
+    ("f64.trunc", &[F64], F64),
+    ("f64.nearest", &[F64], F64),
+    ("f64.sqrt", &[F64], F64),
+    ("f64.add", &[F64, F64], F64),
+    ("f64.sub", &[F64, F64], F64),
+    ("f64.mul", &[F64, F64], F64),
+    ("f64.div", &[F64, F64], F64),
+    ("f64.min", &[F64, F64], F64),
+    ("f64.max", &[F64, F64], F64),
+    ("f64.copysign", &[F64, F64], F64),
+    ("i32.wrap_i64", &[I64], I32),
+    ("i32.trunc_f32_s", &[F32], I32),
+    ("i32.trunc_f32_u", &[F32], I32),
+    ("i32.trunc_f64_s", &[F64], I32),
+    ("i32.trunc_f64_u", &[F64], I32),
+    ("i64.extend_i32_s", &[I32], I64),
+    ("i64.extend_i32_u", &[I32], I64),
+    ("i64.trunc_f32_s", &[F32], I64),
+    ("i64.trunc_f32_u", &[F32], I64),
+    ("i64.trunc_f64_s", &[F64], I64),
+    ("i64.trunc_f64_u", &[F64], I64),
+    ("f32.convert_i32_s", &[I32], F32),
+    ("f32.convert_i32_u", &[I32], F32),
+    ("f32.convert_i64_s", &[I64], F32),
+    ("f32.convert_i64_u", &[I64], F32),
+    ("f32.demote_f64", &[F64], F32),
+    ("f64.convert_i32_s", &[I32], F64),
+    ("f64.convert_i32_u", &[I32], F64),
+    ("f64.convert_i64_s", &[I64], F64),
+    ("f64.convert_i64_u", &[I64], F64),
+    ("f64.promote_f32", &[F32], F64),
+    ("i32.reinterpret_f32", &[F32], I32),
+    ("i64.reinterpret_f64", &[F64], I64),
+    ("f32.reinterpret_i32", &[I32], F32),
+    ("f64.reinterpret_i64", &[I64], F64),
+    ("i32.extend8_s", &[I32], I32),
+    ("i32.extend16_s", &[I32], I32),
+    ("i64.extend8_s", &[I64], I64),
+    ("i64.extend16_s", &[I64], I64),
+    ("i64.extend32_s", &[I64], I64),
+];
+
+// The loads and stores, from opcode `0x28` on: their names, the type of
+// the value and the natural alignment.
+const MEMORY: &[(&str, ValType, u32)] = &[
+    ("i32.load", I32, 2),
+    ("i64.load", I64, 3),
+    ("f32.load", F32, 2),
+    ("f64.load", F64, 3),
+    ("i32.load8_s", I32, 0),
+    ("i32.load8_u", I32, 0),
+    ("i32.load16_s", I32, 1),
+    ("i32.load16_u", I32, 1),
+    ("i64.load8_s", I64, 0),
+    ("i64.load8_u", I64, 0),
+    ("i64.load16_s", I64, 1),
+    ("i64.load16_u", I64, 1),
+    ("i64.load32_s", I64, 2),
+    ("i64.load32_u", I64, 2),
+    ("i32.store", I32, 2),
+    ("i64.store", I64, 3),
+    ("f32.store", F32, 2),
+    ("f64.store", F64, 3),
+    ("i32.store8", I32, 0),
+    ("i32.store16", I32, 1),
+    ("i64.store8", I64, 0),
+    ("i64.store16", I64, 1),
+    ("i64.store32", I64, 2),
+];
+
+// The first opcode of a store.
+const STORE: u8 = 0x36;
+
+// The saturating conversions, by subopcode: their names and the types of
+// their operand and result.
+const TRUNC_SAT: &[(&str, ValType, ValType)] = &[
+    ("i32.trunc_sat_f32_s", F32, I32),
+    ("i32.trunc_sat_f32_u", F32, I32),
+    ("i32.trunc_sat_f64_s", F64, I32),
+    ("i32.trunc_sat_f64_u", F64, I32),
+    ("i64.trunc_sat_f32_s", F32, I64),
+    ("i64.trunc_sat_f32_u", F32, I64),
+    ("i64.trunc_sat_f64_s", F64, I64),
+    ("i64.trunc_sat_f64_u", F64, I64),
+];
+
+impl Instr {
+    /// The numeric instruction of a name, like `i32.add`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no such instruction.
+    pub fn numeric(name: &str) -> Instr {
+        let index = NUMERIC.iter().position(|(op, ..)| *op == name)
+            .unwrap_or_else(|| panic!("no numeric instruction `{}`", name));
+        Instr::Numeric(0x45 + index as u8)
+    }
+
+    /// The load or store of a name, like `i32.load8_s`, at a constant
+    /// offset from its address, which is naturally aligned.
+    ///
+    /// # Panics
+    ///
+    /// If there is no such instruction.
+    pub fn memory(name: &str, offset: u32) -> Instr {
+        let index = MEMORY.iter().position(|(op, ..)| *op == name)
+            .unwrap_or_else(|| panic!("no load or store `{}`", name));
+        let align = MEMORY[index].2;
+        Instr::Memory(0x28 + index as u8, MemArg { align, offset })
+    }
+
+    /// The saturating conversion of a name, like `i32.trunc_sat_f64_s`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no such instruction.
+    pub fn trunc_sat(name: &str) -> Instr {
+        let index = TRUNC_SAT.iter().position(|(op, ..)| *op == name)
+            .unwrap_or_else(|| panic!("no conversion `{}`", name));
+        Instr::TruncSat(index as u8)
+    }
+
+    /// Get the name of the instruction in the text format.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Instr::Unreachable => "unreachable",
+            Instr::Nop => "nop",
+            Instr::Block => "block",
+            Instr::Loop => "loop",
+            Instr::If => "if",
+            Instr::Else => "else",
+            Instr::End => "end",
+            Instr::Br(_) => "br",
+            Instr::BrIf(_) => "br_if",
+            Instr::BrTable(..) => "br_table",
+            Instr::Return => "return",
+            Instr::Call(_) => "call",
+            Instr::CallIndirect(_) => "call_indirect",
+            Instr::Drop => "drop",
+            Instr::Select => "select",
+            Instr::LocalGet(_) => "local.get",
+            Instr::LocalSet(_) => "local.set",
+            Instr::LocalTee(_) => "local.tee",
+            Instr::GlobalGet(_) => "global.get",
+            Instr::GlobalSet(_) => "global.set",
+            Instr::Memory(opcode, _) => MEMORY[usize::from(opcode - 0x28)].0,
+            Instr::I32Const(_) => "i32.const",
+            Instr::I64Const(_) => "i64.const",
+            Instr::F32Const(_) => "f32.const",
+            Instr::F64Const(_) => "f64.const",
+            Instr::Numeric(opcode) => NUMERIC[usize::from(opcode - 0x45)].0,
+            Instr::TruncSat(op) => TRUNC_SAT[usize::from(op)].0,
+            Instr::MemoryCopy => "memory.copy",
+            Instr::MemoryFill => "memory.fill",
+        }
+    }
+}
+
+/// A function imported from the embedder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Import {
+    pub module: String,
+    pub name: String,
+    /// Index of its type
+    pub ty: u32,
+}
+
+/// A function defined by a module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    /// The name in the `name` section, or empty
+    pub name: String,
+    /// Index of its type
+    pub ty: u32,
+    /// The types of the locals after the parameters
+    pub locals: Vec<ValType>,
+    /// The instructions, without the final `end`
+    pub body: Vec<Instr>,
+}
+
+/// A global variable of a module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Global {
+    /// The name in the `name` section, or empty
+    pub name: String,
+    pub ty: ValType,
+    pub mutable: bool,
+    /// The constant initial value
+    pub init: Instr,
+}
+
+/// What an export refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportKind {
+    Func,
+    Table,
+    Memory,
+    Global,
+}
+
+/// A function, table, memory or global made visible to the embedder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Export {
+    pub name: String,
+    pub kind: ExportKind,
+    pub index: u32,
+}
+
+/// Functions put in the table from an index when the module is
+/// instantiated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Element {
+    pub offset: u32,
+    pub functions: Vec<u32>,
+}
+
+/// Bytes copied into memory at an address when the module is instantiated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Data {
+    pub offset: u32,
+    pub bytes: Vec<u8>,
+}
+
+/// A WebAssembly module.  Functions are numbered imports first.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Module {
+    pub types: Vec<FuncType>,
+    pub imports: Vec<Import>,
+    pub functions: Vec<Function>,
+    /// The size of the table of functions, if there is one
+    pub table: Option<u32>,
+    /// The initial size of the memory in 64 KiB pages, if there is one
+    pub memory: Option<u32>,
+    pub globals: Vec<Global>,
+    pub exports: Vec<Export>,
+    pub elements: Vec<Element>,
+    pub data: Vec<Data>,
+}
+
+impl Module {
+    /// Encode the module in the binary format.
+    pub fn encode(&self) -> Vec<u8> {
+        binary::encode(self)
+    }
+
+    /// The type of a function, imported or not.
+    pub fn function_type(&self, index: u32) -> Option<&FuncType> {
+        let imports = self.imports.len();
+        let ty = match (index as usize).checked_sub(imports) {
+            None => self.imports[index as usize].ty,
+            Some(index) => self.functions.get(index)?.ty,
+        };
+        self.types.get(ty as usize)
+    }
+
+    /// The name of a function, imported or not.
+    pub fn function_name(&self, index: u32) -> Option<&str> {
+        let imports = self.imports.len();
+        match (index as usize).checked_sub(imports) {
+            None => Some(&self.imports[index as usize].name),
+            Some(index) => Some(&self.functions.get(index)?.name),
+        }
+    }
+}
@@ -0,0 +1,784 @@
+// Binary format
+//
+//! Encodes modules in the binary format of WebAssembly, and decodes them.
+//!
+//! Besides the standard sections, the custom `name` section names the
+//! functions and globals.  Only what [`encode`] writes is understood by
+//! [`decode`]: tables of a fixed size, memories without a maximum, and
+//! active element and data segments at constant offsets.
+
+use std::convert::{TryFrom, TryInto};
+
+use super::{
+    Data, Element, Export, ExportKind, FuncType, Function, Global, Import,
+    Instr, MemArg, Module, Result, ValType, MEMORY, NUMERIC, TRUNC_SAT,
+};
+use crate::{Diagnostic, Span};
+
+const MAGIC: &[u8] = b"\0asm";
+const VERSION: u32 = 1;
+
+// Section ids
+const CUSTOM: u8 = 0;
+const TYPE: u8 = 1;
+const IMPORT: u8 = 2;
+const FUNCTION: u8 = 3;
+const TABLE: u8 = 4;
+const MEMORY_SECTION: u8 = 5;
+const GLOBAL: u8 = 6;
+const EXPORT: u8 = 7;
+const ELEMENT: u8 = 9;
+const CODE: u8 = 10;
+const DATA: u8 = 11;
+
+// Subsections of the `name` section
+const FUNCTION_NAMES: u8 = 1;
+const GLOBAL_NAMES: u8 = 7;
+
+const FUNCREF: u8 = 0x70;
+const FUNC_TYPE: u8 = 0x60;
+const EMPTY_BLOCK: u8 = 0x40;
+const PREFIX: u8 = 0xFC;
+const MEMORY_COPY: u8 = 10;
+const MEMORY_FILL: u8 = 11;
+
+fn val_type_code(ty: ValType) -> u8 {
+    match ty {
+        ValType::I32 => 0x7F,
+        ValType::I64 => 0x7E,
+        ValType::F32 => 0x7D,
+        ValType::F64 => 0x7C,
+    }
+}
+
+fn export_kind_code(kind: ExportKind) -> u8 {
+    match kind {
+        ExportKind::Func => 0,
+        ExportKind::Table => 1,
+        ExportKind::Memory => 2,
+        ExportKind::Global => 3,
+    }
+}
+
+// Append an unsigned LEB128 number.
+fn put_u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+// Append a signed LEB128 number.
+fn put_i64(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0)
+            || (value == -1 && byte & 0x40 != 0);
+        if done {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn put_len(out: &mut Vec<u8>, len: usize) {
+    put_u32(out, u32::try_from(len).unwrap());
+}
+
+fn put_name(out: &mut Vec<u8>, name: &str) {
+    put_len(out, name.len());
+    out.extend(name.as_bytes());
+}
+
+// Append a section, if it has anything in it.
+fn put_section(out: &mut Vec<u8>, id: u8, count: usize,
+    contents: impl FnOnce(&mut Vec<u8>))
+{
+    if count == 0 {
+        return;
+    }
+    let mut bytes = Vec::new();
+    if id != CUSTOM {
+        put_len(&mut bytes, count);
+    }
+    contents(&mut bytes);
+    out.push(id);
+    put_len(out, bytes.len());
+    out.extend(bytes);
+}
+
+// Append a constant expression.
+fn put_const(out: &mut Vec<u8>, init: &Instr) {
+    put_instr(out, init);
+    put_instr(out, &Instr::End);
+}
+
+fn put_instr(out: &mut Vec<u8>, instr: &Instr) {
+    match *instr {
+        Instr::Unreachable => out.push(0x00),
+        Instr::Nop => out.push(0x01),
+        Instr::Block => out.extend([0x02, EMPTY_BLOCK]),
+        Instr::Loop => out.extend([0x03, EMPTY_BLOCK]),
+        Instr::If => out.extend([0x04, EMPTY_BLOCK]),
+        Instr::Else => out.push(0x05),
+        Instr::End => out.push(0x0B),
+        Instr::Br(label) => {
+            out.push(0x0C);
+            put_u32(out, label);
+        }
+        Instr::BrIf(label) => {
+            out.push(0x0D);
+            put_u32(out, label);
+        }
+        Instr::BrTable(ref labels, default) => {
+            out.push(0x0E);
+            put_len(out, labels.len());
+            for &label in labels {
+                put_u32(out, label);
+            }
+            put_u32(out, default);
+        }
+        Instr::Return => out.push(0x0F),
+        Instr::Call(function) => {
+            out.push(0x10);
+            put_u32(out, function);
+        }
+        Instr::CallIndirect(ty) => {
+            out.push(0x11);
+            put_u32(out, ty);
+            out.push(0);
+        }
+        Instr::Drop => out.push(0x1A),
+        Instr::Select => out.push(0x1B),
+        Instr::LocalGet(local) => {
+            out.push(0x20);
+            put_u32(out, local);
+        }
+        Instr::LocalSet(local) => {
+            out.push(0x21);
+            put_u32(out, local);
+        }
+        Instr::LocalTee(local) => {
+            out.push(0x22);
+            put_u32(out, local);
+        }
+        Instr::GlobalGet(global) => {
+            out.push(0x23);
+            put_u32(out, global);
+        }
+        Instr::GlobalSet(global) => {
+            out.push(0x24);
+            put_u32(out, global);
+        }
+        Instr::Memory(opcode, MemArg { align, offset }) => {
+            out.push(opcode);
+            put_u32(out, align);
+            put_u32(out, offset);
+        }
+        Instr::I32Const(value) => {
+            out.push(0x41);
+            put_i64(out, i64::from(value));
+        }
+        Instr::I64Const(value) => {
+            out.push(0x42);
+            put_i64(out, value);
+        }
+        Instr::F32Const(bits) => {
+            out.push(0x43);
+            out.extend(bits.to_le_bytes());
+        }
+        Instr::F64Const(bits) => {
+            out.push(0x44);
+            out.extend(bits.to_le_bytes());
+        }
+        Instr::Numeric(opcode) => out.push(opcode),
+        Instr::TruncSat(op) => out.extend([PREFIX, op]),
+        Instr::MemoryCopy => out.extend([PREFIX, MEMORY_COPY, 0, 0]),
+        Instr::MemoryFill => out.extend([PREFIX, MEMORY_FILL, 0]),
+    }
+}
+
+/// Encode a module.
+pub(super) fn encode(module: &Module) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    out.extend(VERSION.to_le_bytes());
+    put_section(&mut out, TYPE, module.types.len(), |out| {
+        for ty in &module.types {
+            out.push(FUNC_TYPE);
+            for types in [&ty.params, &ty.results] {
+                put_len(out, types.len());
+                out.extend(types.iter().map(|&ty| val_type_code(ty)));
+            }
+        }
+    });
+    put_section(&mut out, IMPORT, module.imports.len(), |out| {
+        for import in &module.imports {
+            put_name(out, &import.module);
+            put_name(out, &import.name);
+            out.push(export_kind_code(ExportKind::Func));
+            put_u32(out, import.ty);
+        }
+    });
+    put_section(&mut out, FUNCTION, module.functions.len(), |out| {
+        for function in &module.functions {
+            put_u32(out, function.ty);
+        }
+    });
+    put_section(&mut out, TABLE, module.table.iter().len(), |out| {
+        let size = module.table.unwrap();
+        out.extend([FUNCREF, 1]);
+        put_u32(out, size);
+        put_u32(out, size);
+    });
+    put_section(&mut out, MEMORY_SECTION, module.memory.iter().len(), |out| {
+        out.push(0);
+        put_u32(out, module.memory.unwrap());
+    });
+    put_section(&mut out, GLOBAL, module.globals.len(), |out| {
+        for global in &module.globals {
+            out.push(val_type_code(global.ty));
+            out.push(global.mutable as u8);
+            put_const(out, &global.init);
+        }
+    });
+    put_section(&mut out, EXPORT, module.exports.len(), |out| {
+        for export in &module.exports {
+            put_name(out, &export.name);
+            out.push(export_kind_code(export.kind));
+            put_u32(out, export.index);
+        }
+    });
+    put_section(&mut out, ELEMENT, module.elements.len(), |out| {
+        for element in &module.elements {
+            out.push(0);
+            put_const(out, &Instr::I32Const(element.offset as i32));
+            put_len(out, element.functions.len());
+            for &function in &element.functions {
+                put_u32(out, function);
+            }
+        }
+    });
+    put_section(&mut out, CODE, module.functions.len(), |out| {
+        for function in &module.functions {
+            let mut code = Vec::new();
+            // Runs of locals of the same type.
+            let mut runs: Vec<(u32, ValType)> = Vec::new();
+            for &ty in &function.locals {
+                match runs.last_mut() {
+                    Some((count, last)) if *last == ty => *count += 1,
+                    _ => runs.push((1, ty)),
+                }
+            }
+            put_len(&mut code, runs.len());
+            for (count, ty) in runs {
+                put_u32(&mut code, count);
+                code.push(val_type_code(ty));
+            }
+            for instr in &function.body {
+                put_instr(&mut code, instr);
+            }
+            put_instr(&mut code, &Instr::End);
+            put_len(out, code.len());
+            out.extend(code);
+        }
+    });
+    put_section(&mut out, DATA, module.data.len(), |out| {
+        for data in &module.data {
+            out.push(0);
+            put_const(out, &Instr::I32Const(data.offset as i32));
+            put_len(out, data.bytes.len());
+            out.extend(&data.bytes);
+        }
+    });
+
+    let functions = module.imports.iter().map(|import| &import.name)
+        .chain(module.functions.iter().map(|function| &function.name));
+    let globals = module.globals.iter().map(|global| &global.name);
+    let names = [
+        (FUNCTION_NAMES, functions.collect::<Vec<_>>()),
+        (GLOBAL_NAMES, globals.collect()),
+    ];
+    let named = names.iter().any(|(_, names)| {
+        names.iter().any(|name| !name.is_empty())
+    });
+    put_section(&mut out, CUSTOM, named as usize, |out| {
+        put_name(out, "name");
+        for (id, names) in &names {
+            let mut map = Vec::new();
+            let named: Vec<_> = names.iter().enumerate()
+                .filter(|(_, name)| !name.is_empty())
+                .collect();
+            put_len(&mut map, named.len());
+            for (index, name) in named {
+                put_len(&mut map, index);
+                put_name(&mut map, name);
+            }
+            out.push(*id);
+            put_len(out, map.len());
+            out.extend(map);
+        }
+    });
+    out
+}
+
+// A reader of the bytes of a module.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, message: &str) -> Diagnostic {
+        let at = self.at.min(self.bytes.len());
+        Diagnostic::new(Span::new(at, at + 1), message.to_string())
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        let byte = *self.bytes.get(self.at)
+            .ok_or_else(|| self.error("unexpected end"))?;
+        self.at += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.bytes.len() - self.at {
+            return Err(self.error("unexpected end"));
+        }
+        let bytes = &self.bytes[self.at..self.at + len];
+        self.at += len;
+        Ok(bytes)
+    }
+
+    fn expect(&mut self, byte: u8, what: &str) -> Result<()> {
+        match self.byte()? {
+            b if b == byte => Ok(()),
+            _ => {
+                self.at -= 1;
+                Err(self.error(&format!("expected {}", what)))
+            }
+        }
+    }
+
+    // A signed or unsigned LEB128 number of at most `bits` bits.
+    fn leb(&mut self, bits: u32, signed: bool) -> Result<i64> {
+        let start = self.at;
+        let mut value: i64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift >= bits {
+                self.at = start;
+                return Err(self.error("number too large"));
+            }
+            value |= i64::from(byte & 0x7F) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if signed && shift < 64 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                break;
+            }
+        }
+        let fits = match (signed, bits) {
+            (_, 64) => true,
+            (true, _) => value >> (bits - 1) == 0 || value >> (bits - 1) == -1,
+            (false, _) => value >> bits == 0,
+        };
+        if !fits {
+            self.at = start;
+            return Err(self.error("number too large"));
+        }
+        Ok(value)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(self.leb(32, false)? as u32)
+    }
+
+    fn len(&mut self) -> Result<usize> {
+        let len = self.u32()? as usize;
+        match len > self.bytes.len() - self.at {
+            true => Err(self.error("length past the end")),
+            false => Ok(len),
+        }
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let len = self.len()?;
+        let start = self.at;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| {
+            self.at = start;
+            self.error("name isn't UTF-8")
+        })
+    }
+
+    fn val_type(&mut self) -> Result<ValType> {
+        match self.byte()? {
+            0x7F => Ok(ValType::I32),
+            0x7E => Ok(ValType::I64),
+            0x7D => Ok(ValType::F32),
+            0x7C => Ok(ValType::F64),
+            _ => {
+                self.at -= 1;
+                Err(self.error("expected a value type"))
+            }
+        }
+    }
+
+    // A vector of items.
+    fn vec<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T>)
+        -> Result<Vec<T>>
+    {
+        let len = self.len()?;
+        (0..len).map(|_| item(self)).collect()
+    }
+
+    fn instr(&mut self) -> Result<Instr> {
+        let start = self.at;
+        let instr = match self.byte()? {
+            0x00 => Instr::Unreachable,
+            0x01 => Instr::Nop,
+            opcode @ 0x02..=0x04 => {
+                self.expect(EMPTY_BLOCK, "a block without values")?;
+                match opcode {
+                    0x02 => Instr::Block,
+                    0x03 => Instr::Loop,
+                    _ => Instr::If,
+                }
+            }
+            0x05 => Instr::Else,
+            0x0B => Instr::End,
+            0x0C => Instr::Br(self.u32()?),
+            0x0D => Instr::BrIf(self.u32()?),
+            0x0E => {
+                let labels = self.vec(Self::u32)?;
+                Instr::BrTable(labels, self.u32()?)
+            }
+            0x0F => Instr::Return,
+            0x10 => Instr::Call(self.u32()?),
+            0x11 => {
+                let ty = self.u32()?;
+                self.expect(0, "table 0")?;
+                Instr::CallIndirect(ty)
+            }
+            0x1A => Instr::Drop,
+            0x1B => Instr::Select,
+            0x20 => Instr::LocalGet(self.u32()?),
+            0x21 => Instr::LocalSet(self.u32()?),
+            0x22 => Instr::LocalTee(self.u32()?),
+            0x23 => Instr::GlobalGet(self.u32()?),
+            0x24 => Instr::GlobalSet(self.u32()?),
+            opcode if is_in(opcode, 0x28, MEMORY.len()) => {
+                let align = self.u32()?;
+                let offset = self.u32()?;
+                Instr::Memory(opcode, MemArg { align, offset })
+            }
+            0x41 => Instr::I32Const(self.leb(32, true)? as i32),
+            0x42 => Instr::I64Const(self.leb(64, true)?),
+            0x43 => {
+                let bytes = self.take(4)?;
+                Instr::F32Const(u32::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            0x44 => {
+                let bytes = self.take(8)?;
+                Instr::F64Const(u64::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            opcode if is_in(opcode, 0x45, NUMERIC.len()) => {
+                Instr::Numeric(opcode)
+            }
+            PREFIX => match self.u32()? {
+                op if (op as usize) < TRUNC_SAT.len() => {
+                    Instr::TruncSat(op as u8)
+                }
+                op if op == u32::from(MEMORY_COPY) => {
+                    self.expect(0, "memory 0")?;
+                    self.expect(0, "memory 0")?;
+                    Instr::MemoryCopy
+                }
+                op if op == u32::from(MEMORY_FILL) => {
+                    self.expect(0, "memory 0")?;
+                    Instr::MemoryFill
+                }
+                _ => {
+                    self.at = start;
+                    return Err(self.error("unknown instruction"));
+                }
+            },
+            _ => {
+                self.at = start;
+                return Err(self.error("unknown instruction"));
+            }
+        };
+        Ok(instr)
+    }
+
+    // A constant expression: one instruction, then `end`.
+    fn const_expr(&mut self) -> Result<Instr> {
+        let start = self.at;
+        let instr = self.instr()?;
+        match instr {
+            Instr::I32Const(_)
+            | Instr::I64Const(_)
+            | Instr::F32Const(_)
+            | Instr::F64Const(_) => {}
+            _ => {
+                self.at = start;
+                return Err(self.error("expected a constant"));
+            }
+        }
+        self.expect(0x0B, "`end`")?;
+        Ok(instr)
+    }
+
+    // An `i32` offset of a segment.
+    fn offset(&mut self) -> Result<u32> {
+        let start = self.at;
+        match self.const_expr()? {
+            Instr::I32Const(offset) => Ok(offset as u32),
+            _ => {
+                self.at = start;
+                Err(self.error("expected an `i32` offset"))
+            }
+        }
+    }
+}
+
+/// Decode a module.  The spans of errors are byte offsets in the module.
+pub fn decode(bytes: &[u8]) -> Result<Module> {
+    let mut reader = Reader { bytes, at: 0 };
+    if bytes.len() < 8 || &bytes[..4] != MAGIC {
+        return Err(Diagnostic::new(Span::new(0, bytes.len().min(4)),
+            "not a WebAssembly module".to_string()));
+    }
+    if bytes[4..8] != VERSION.to_le_bytes() {
+        return Err(Diagnostic::new(Span::new(4, 8),
+            "unknown version".to_string()));
+    }
+    reader.at = 8;
+
+    let mut module = Module::default();
+    let mut types = Vec::new();
+    let mut last = 0;
+    while reader.at < bytes.len() {
+        let start = reader.at;
+        let id = reader.byte()?;
+        let len = reader.len()?;
+        let end = reader.at + len;
+        if id != CUSTOM {
+            if id <= last || id > DATA || id == 8 {
+                reader.at = start;
+                return Err(reader.error("unexpected section"));
+            }
+            last = id;
+        }
+        let mut section = Reader { bytes: &bytes[..end], at: reader.at };
+        let r = &mut section;
+        match id {
+            CUSTOM => {
+                if r.name()? == "name" {
+                    names(r, &mut module)?;
+                }
+                r.at = end;
+            }
+            TYPE => {
+                module.types = r.vec(|r| {
+                    r.expect(FUNC_TYPE, "a function type")?;
+                    let params = r.vec(Reader::val_type)?;
+                    let results = r.vec(Reader::val_type)?;
+                    Ok(FuncType { params, results })
+                })?;
+            }
+            IMPORT => {
+                module.imports = r.vec(|r| {
+                    let module = r.name()?;
+                    let name = r.name()?;
+                    r.expect(0, "a function import")?;
+                    let ty = r.u32()?;
+                    Ok(Import { module, name, ty })
+                })?;
+            }
+            FUNCTION => types = r.vec(Reader::u32)?,
+            TABLE => {
+                let tables = r.vec(|r| {
+                    r.expect(FUNCREF, "`funcref`")?;
+                    r.expect(1, "a table of a fixed size")?;
+                    let min = r.u32()?;
+                    let start = r.at;
+                    if r.u32()? != min {
+                        r.at = start;
+                        return Err(r.error("expected a table of a fixed size"));
+                    }
+                    Ok(min)
+                })?;
+                if tables.len() > 1 {
+                    return Err(r.error("more than one table"));
+                }
+                module.table = tables.first().copied();
+            }
+            MEMORY_SECTION => {
+                let memories = r.vec(|r| {
+                    r.expect(0, "a memory without a maximum")?;
+                    r.u32()
+                })?;
+                if memories.len() > 1 {
+                    return Err(r.error("more than one memory"));
+                }
+                module.memory = memories.first().copied();
+            }
+            GLOBAL => {
+                module.globals = r.vec(|r| {
+                    let ty = r.val_type()?;
+                    let mutable = match r.byte()? {
+                        0 => false,
+                        1 => true,
+                        _ => {
+                            r.at -= 1;
+                            return Err(r.error("expected a mutability"));
+                        }
+                    };
+                    let init = r.const_expr()?;
+                    Ok(Global { name: String::new(), ty, mutable, init })
+                })?;
+            }
+            EXPORT => {
+                module.exports = r.vec(|r| {
+                    let name = r.name()?;
+                    let kind = match r.byte()? {
+                        0 => ExportKind::Func,
+                        1 => ExportKind::Table,
+                        2 => ExportKind::Memory,
+                        3 => ExportKind::Global,
+                        _ => {
+                            r.at -= 1;
+                            return Err(r.error("unknown export kind"));
+                        }
+                    };
+                    let index = r.u32()?;
+                    Ok(Export { name, kind, index })
+                })?;
+            }
+            ELEMENT => {
+                module.elements = r.vec(|r| {
+                    r.expect(0, "an active element segment")?;
+                    let offset = r.offset()?;
+                    let functions = r.vec(Reader::u32)?;
+                    Ok(Element { offset, functions })
+                })?;
+            }
+            CODE => {
+                let count = r.len()?;
+                if count != types.len() {
+                    return Err(r.error("as many bodies as functions needed"));
+                }
+                for &ty in &types {
+                    let len = r.len()?;
+                    let end = r.at + len;
+                    let mut locals = Vec::new();
+                    let runs = r.vec(|r| Ok((r.u32()?, r.val_type()?)))?;
+                    for (count, ty) in runs {
+                        let count = count as usize;
+                        if locals.len() + count > 50_000 {
+                            return Err(r.error("too many locals"));
+                        }
+                        locals.extend(std::iter::repeat_n(ty, count));
+                    }
+                    let mut body = Vec::new();
+                    let mut depth = 0;
+                    loop {
+                        if r.at >= end {
+                            return Err(r.error("body without `end`"));
+                        }
+                        let instr = r.instr()?;
+                        match instr {
+                            Instr::Block | Instr::Loop | Instr::If => {
+                                depth += 1;
+                            }
+                            Instr::End if depth == 0 => break,
+                            Instr::End => depth -= 1,
+                            _ => {}
+                        }
+                        body.push(instr);
+                    }
+                    if r.at != end {
+                        return Err(r.error("body ends before its size"));
+                    }
+                    module.functions.push(Function {
+                        name: String::new(),
+                        ty,
+                        locals,
+                        body,
+                    });
+                }
+            }
+            DATA => {
+                module.data = r.vec(|r| {
+                    r.expect(0, "an active data segment")?;
+                    let offset = r.offset()?;
+                    let len = r.len()?;
+                    let bytes = r.take(len)?.to_vec();
+                    Ok(Data { offset, bytes })
+                })?;
+            }
+            _ => unreachable!(),
+        }
+        if section.at != end {
+            return Err(section.error("section ends before its size"));
+        }
+        reader.at = end;
+    }
+    if module.functions.len() != types.len() {
+        return Err(reader.error("functions without bodies"));
+    }
+    Ok(module)
+}
+
+// Name the functions and globals of a module from its `name` section.
+fn names(r: &mut Reader, module: &mut Module) -> Result<()> {
+    while r.at < r.bytes.len() {
+        let id = r.byte()?;
+        let len = r.len()?;
+        let end = r.at + len;
+        let map = match id {
+            FUNCTION_NAMES | GLOBAL_NAMES => r.vec(|r| {
+                Ok((r.u32()? as usize, r.name()?))
+            })?,
+            _ => {
+                r.at = end;
+                continue;
+            }
+        };
+        for (index, name) in map {
+            let imports = module.imports.len();
+            let slot = match id {
+                FUNCTION_NAMES if index < imports => None,
+                FUNCTION_NAMES => module.functions.get_mut(index - imports)
+                    .map(|function| &mut function.name),
+                _ => module.globals.get_mut(index)
+                    .map(|global| &mut global.name),
+            };
+            match slot {
+                Some(slot) => *slot = name,
+                None if id == FUNCTION_NAMES && index < imports => {}
+                None => return Err(r.error("name of nothing")),
+            }
+        }
+        if r.at != end {
+            return Err(r.error("subsection ends before its size"));
+        }
+    }
+    Ok(())
+}
+
+// Whether an opcode is one of a table starting at an opcode.
+fn is_in(opcode: u8, first: u8, len: usize) -> bool {
+    (usize::from(first)..usize::from(first) + len)
+        .contains(&usize::from(opcode))
+}
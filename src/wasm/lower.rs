@@ -0,0 +1,1382 @@
+// Lowering
+//
+//! Translates IR modules to WebAssembly modules: numbers the functions and
+//! their types, lays out the global variables in memory, and translates
+//! each function body, leaving the control flow to [`super::stackify`].
+
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
+use super::stackify::{self, Emitter};
+use super::{
+    Data, Element, Export, ExportKind, FuncType, Function, Global, Import,
+    Instr, Module, Result, ValType,
+};
+use crate::ir::{
+    self, BinaryOp, BlockId, CastOp, FloatPredicate, InstId, InstKind,
+    IntPredicate, Signature, Type, Value,
+};
+use crate::{Diagnostic, Span};
+
+// Where global variables start, so no null pointer points to one
+const DATA_START: u32 = 1024;
+const STACK_SIZE: u32 = 64 * 1024;
+const PAGE: u32 = 64 * 1024;
+// The global with the address of the top of the stack
+const STACK_POINTER: u32 = 0;
+// The offset in a `va_list` of the address of the next extra argument
+const VA_BUFFER: u32 = 8;
+
+fn unsupported(function: &str, what: &str) -> Diagnostic {
+    Diagnostic::new(Span::default(), format!("in `{}`: {} isn't supported \
+        by the WebAssembly back end", ir::symbol(function), what))
+}
+
+fn is_local(name: &str) -> bool {
+    name.contains('.')
+}
+
+fn align_to(offset: u32, align: u64) -> u32 {
+    let align = align as u32;
+    offset.div_ceil(align) * align
+}
+
+// The type a scalar is kept in; aggregates are addresses.
+fn val_type(ty: &Type) -> ValType {
+    match ty {
+        Type::Int(64) => ValType::I64,
+        Type::F32 => ValType::F32,
+        Type::F64 => ValType::F64,
+        _ => ValType::I32,
+    }
+}
+
+fn signature_type(signature: &Signature) -> FuncType {
+    let mut params = Vec::new();
+    if signature.ret.is_aggregate() {
+        params.push(ValType::I32);
+    }
+    params.extend(signature.params.iter().map(val_type));
+    if signature.variadic {
+        params.push(ValType::I32);
+    }
+    let results = match signature.ret {
+        Type::Void => Vec::new(),
+        ref ty if ty.is_aggregate() => Vec::new(),
+        ref ty => vec![val_type(ty)],
+    };
+    FuncType { params, results }
+}
+
+// The functions of the C library `frem` calls, for `f32` and `f64`.
+fn fmod(ty: &Type) -> (&'static str, Signature) {
+    let name = match ty {
+        Type::F32 => "fmodf",
+        _ => "fmod",
+    };
+    (name, Signature::new(ty.clone(), vec![ty.clone(); 2]))
+}
+
+/// Translate a module.
+pub(super) fn lower(module: &ir::Module) -> Result<Module> {
+    let mut lowering = Lowering::new(module)?;
+    for function in &module.functions {
+        if !function.is_declaration() {
+            let function = lowering.function(function)?;
+            lowering.wasm.functions.push(function);
+        }
+    }
+    Ok(lowering.finish())
+}
+
+// The translation of a module.
+struct Lowering<'m> {
+    module: &'m ir::Module,
+    wasm: Module,
+    // The index of each function used or defined
+    functions: HashMap<String, u32>,
+    // The table index of each function whose address is taken
+    table: HashMap<String, u32>,
+    // The address of each global variable defined
+    addresses: HashMap<String, u32>,
+    // The memory from `DATA_START` on, as it starts
+    memory: Vec<u8>,
+    // The address and size of each global variable and constant
+    items: Vec<(u32, u32)>,
+    // The address of each constant aggregate functions use
+    constants: HashMap<Value, u32>,
+}
+
+// The functions a module calls and takes the address of.
+#[derive(Default)]
+struct References {
+    used: Vec<String>,
+    taken: Vec<String>,
+}
+
+impl References {
+    fn visit(&mut self, module: &ir::Module, within: &str, value: &Value)
+        -> Result<()>
+    {
+        match value {
+            Value::Global(name) if module.function(name).is_some() => {
+                if !self.taken.contains(name) {
+                    self.taken.push(name.clone());
+                }
+                self.call(name);
+            }
+            Value::Global(name) => {
+                let defined = module.global(name)
+                    .is_some_and(|global| global.init.is_some());
+                if !defined {
+                    let what = format!("`{}`, a global variable defined \
+                        elsewhere,", ir::symbol(name));
+                    return Err(unsupported(within, &what));
+                }
+            }
+            Value::Aggregate(_, members) => {
+                for member in members {
+                    self.visit(module, within, member)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn call(&mut self, name: &str) {
+        if !self.used.iter().any(|used| used == name) {
+            self.used.push(name.to_string());
+        }
+    }
+}
+
+impl<'m> Lowering<'m> {
+    // Number the functions and lay out the global variables.
+    fn new(module: &'m ir::Module) -> Result<Self> {
+        let mut references = References::default();
+        let mut libm = Vec::new();
+        for function in &module.functions {
+            for &inst in function.blocks.iter().flat_map(|b| &b.insts) {
+                let kind = &function.insts[inst].kind;
+                let operands = match kind {
+                    InstKind::Call(_, Value::Global(callee), args)
+                        if module.function(callee).is_some() =>
+                    {
+                        references.call(callee);
+                        args.iter().collect()
+                    }
+                    InstKind::Binary(BinaryOp::FRem, ..) => {
+                        let (name, signature) =
+                            fmod(&function.insts[inst].ty);
+                        references.call(name);
+                        if !libm.iter().any(|&(used, _)| used == name) {
+                            libm.push((name, signature));
+                        }
+                        kind.operands()
+                    }
+                    _ => kind.operands(),
+                };
+                for operand in operands {
+                    references.visit(module, &function.name, operand)?;
+                }
+            }
+            for block in &function.blocks {
+                if let Some(operand) = block.term.operand() {
+                    references.visit(module, &function.name, operand)?;
+                }
+            }
+        }
+        for global in &module.globals {
+            if let Some(ref init) = global.init {
+                references.visit(module, &global.name, init)?;
+            }
+        }
+
+        let mut lowering = Lowering {
+            module,
+            wasm: Module::default(),
+            functions: HashMap::new(),
+            table: HashMap::new(),
+            addresses: HashMap::new(),
+            memory: Vec::new(),
+            items: Vec::new(),
+            constants: HashMap::new(),
+        };
+        let used = |name: &str| references.used.iter().any(|used| used == name);
+        let mut imports: Vec<_> = module.functions.iter()
+            .filter(|f| f.is_declaration() && used(&f.name))
+            .map(|function| (function.name.as_str(), &function.signature))
+            .collect();
+        for (name, signature) in &libm {
+            if module.function(name).is_none() {
+                imports.push((*name, signature));
+            }
+        }
+        for (name, signature) in imports {
+            let index = lowering.wasm.imports.len() as u32;
+            let ty = lowering.type_index(signature_type(signature));
+            lowering.wasm.imports.push(Import {
+                module: "env".to_string(),
+                name: name.to_string(),
+                ty,
+            });
+            lowering.functions.insert(name.to_string(), index);
+        }
+        let defined = module.functions.iter()
+            .filter(|function| !function.is_declaration());
+        for (index, function) in defined.enumerate() {
+            let index = (lowering.wasm.imports.len() + index) as u32;
+            lowering.functions.insert(function.name.clone(), index);
+        }
+        if !references.taken.is_empty() {
+            let mut functions = Vec::new();
+            for (index, name) in references.taken.into_iter().enumerate() {
+                functions.push(lowering.functions[&name]);
+                lowering.table.insert(name, index as u32 + 1);
+            }
+            lowering.wasm.table = Some(functions.len() as u32 + 1);
+            lowering.wasm.elements.push(Element { offset: 1, functions });
+        }
+
+        // Every address is needed before any initial value is written.
+        let defined: Vec<_> = module.globals.iter()
+            .filter_map(|global| Some((global, global.init.as_ref()?)))
+            .collect();
+        let mut addresses = Vec::new();
+        for &(global, _) in &defined {
+            let address = lowering.allocate(&global.ty);
+            lowering.addresses.insert(global.name.clone(), address);
+            addresses.push(address);
+        }
+        for (&(global, init), address) in defined.iter().zip(addresses) {
+            lowering.put(address, &global.ty, init);
+        }
+        Ok(lowering)
+    }
+
+    fn type_index(&mut self, ty: FuncType) -> u32 {
+        let types = &mut self.wasm.types;
+        match types.iter().position(|other| *other == ty) {
+            Some(index) => index as u32,
+            None => {
+                types.push(ty);
+                types.len() as u32 - 1
+            }
+        }
+    }
+
+    // Room in memory for a value of a type.
+    fn allocate(&mut self, ty: &Type) -> u32 {
+        let start = align_to(self.memory.len() as u32, ty.align());
+        let size = ty.size() as u32;
+        self.memory.resize((start + size) as usize, 0);
+        self.items.push((DATA_START + start, size));
+        DATA_START + start
+    }
+
+    // Write the initial value of memory at an address.
+    fn put(&mut self, address: u32, ty: &Type, value: &Value) {
+        let at = (address - DATA_START) as usize;
+        match value {
+            Value::Int(_, value) => {
+                let len = ty.size() as usize;
+                let bytes = value.to_le_bytes();
+                self.memory[at..at + len].copy_from_slice(&bytes[..len]);
+            }
+            Value::Float(Type::F32, bits) => {
+                let value = f64::from_bits(*bits) as f32;
+                self.memory[at..at + 4].copy_from_slice(&value.to_le_bytes());
+            }
+            Value::Float(_, bits) => {
+                self.memory[at..at + 8].copy_from_slice(&bits.to_le_bytes());
+            }
+            Value::Aggregate(_, members) => {
+                for (index, member) in members.iter().enumerate() {
+                    let member_ty = ty.member(index as u32).unwrap();
+                    let offset = ty.offset(index) as u32;
+                    self.put(address + offset, member_ty, member);
+                }
+            }
+            Value::Bytes(bytes) => {
+                self.memory[at..at + bytes.len()].copy_from_slice(bytes);
+            }
+            Value::Global(name) => {
+                let address = self.address(name);
+                self.memory[at..at + 4].copy_from_slice(&address.to_le_bytes());
+            }
+            _ => {}
+        }
+    }
+
+    // The address of a global variable, or the table index of a function.
+    fn address(&self, name: &str) -> u32 {
+        match self.table.get(name) {
+            Some(&index) => index,
+            None => self.addresses[name],
+        }
+    }
+
+    // The address of a constant aggregate in memory.
+    fn constant(&mut self, ty: &Type, value: &Value) -> u32 {
+        if let Some(&address) = self.constants.get(value) {
+            return address;
+        }
+        let address = self.allocate(ty);
+        self.put(address, ty, value);
+        self.constants.insert(value.clone(), address);
+        address
+    }
+
+    fn function(&mut self, function: &ir::Function) -> Result<Function> {
+        check(self.module, function)?;
+        let mut function = function.clone();
+        stackify::split_switch_edges(&mut function);
+        let ty = signature_type(&function.signature);
+        let mut translator = Translator::new(self, &function, &ty);
+        stackify::stackify(&function, &mut translator);
+
+        let mut body = Vec::new();
+        if translator.frame > 0 {
+            body.extend([
+                Instr::GlobalGet(STACK_POINTER),
+                Instr::I32Const(translator.frame as i32),
+                Instr::numeric("i32.sub"),
+                Instr::LocalTee(translator.fp),
+                Instr::GlobalSet(STACK_POINTER),
+            ]);
+        }
+        body.append(&mut translator.body);
+        stackify::simplify(&mut body);
+        if !ty.results.is_empty() && body.last() == Some(&Instr::End) {
+            body.push(Instr::Unreachable);
+        }
+        let locals = translator.locals;
+        Ok(Function {
+            name: function.name.clone(),
+            ty: self.type_index(ty),
+            locals,
+            body,
+        })
+    }
+
+    // Add the stack, the exports and the data segments.
+    fn finish(mut self) -> Module {
+        let data_end = DATA_START + self.memory.len() as u32;
+        let stack_top = align_to(data_end, 16) + STACK_SIZE;
+        self.wasm.memory = Some(stack_top.div_ceil(PAGE));
+        self.wasm.globals.push(Global {
+            name: "__stack_pointer".to_string(),
+            ty: ValType::I32,
+            mutable: true,
+            init: Instr::I32Const(stack_top as i32),
+        });
+        self.wasm.globals.push(Global {
+            name: "__heap_base".to_string(),
+            ty: ValType::I32,
+            mutable: false,
+            init: Instr::I32Const(stack_top as i32),
+        });
+
+        let mut exports = vec![
+            Export {
+                name: "memory".to_string(),
+                kind: ExportKind::Memory,
+                index: 0,
+            },
+            Export {
+                name: "__heap_base".to_string(),
+                kind: ExportKind::Global,
+                index: 1,
+            },
+        ];
+        for function in &self.module.functions {
+            if !function.is_declaration() && !is_local(&function.name) {
+                exports.push(Export {
+                    name: function.name.clone(),
+                    kind: ExportKind::Func,
+                    index: self.functions[&function.name],
+                });
+            }
+        }
+        for global in &self.module.globals {
+            if global.init.is_none() || is_local(&global.name) {
+                continue;
+            }
+            exports.push(Export {
+                name: global.name.clone(),
+                kind: ExportKind::Global,
+                index: self.wasm.globals.len() as u32,
+            });
+            self.wasm.globals.push(Global {
+                name: global.name.clone(),
+                ty: ValType::I32,
+                mutable: false,
+                init: Instr::I32Const(self.addresses[&global.name] as i32),
+            });
+        }
+        // The memory and `__heap_base` take precedence over symbols of the
+        // same names.
+        let mut names = HashSet::new();
+        exports.retain(|export| names.insert(export.name.clone()));
+        self.wasm.exports = exports;
+
+        for &(address, size) in &self.items {
+            let start = (address - DATA_START) as usize;
+            let bytes = &self.memory[start..start + size as usize];
+            if bytes.iter().any(|&byte| byte != 0) {
+                self.wasm.data.push(Data {
+                    offset: address,
+                    bytes: bytes.to_vec(),
+                });
+            }
+        }
+        self.wasm
+    }
+}
+
+// Reject what can't be translated.
+fn check(module: &ir::Module, function: &ir::Function) -> Result<()> {
+    let wide = |ty: &Type| *ty == Type::Int(128);
+    let signature = &function.signature;
+    let mut types: Vec<Type> = signature.params.iter().cloned()
+        .chain(std::iter::once(signature.ret.clone()))
+        .chain(function.insts.iter().map(|inst| inst.ty.clone()))
+        .collect();
+    for block in &function.blocks {
+        for &inst in &block.insts {
+            let kind = &function.insts[inst].kind;
+            types.extend(kind.operands().into_iter()
+                .map(|operand| function.value_type(operand)));
+            match kind {
+                InstKind::Call(signature, callee, _) => {
+                    types.extend(signature.params.iter().cloned());
+                    types.push(signature.ret.clone());
+                    let callee = match callee {
+                        Value::Global(name) => module.function(name),
+                        _ => None,
+                    };
+                    let matches = callee.is_none_or(|callee| {
+                        signature_type(&callee.signature)
+                            == signature_type(signature)
+                    });
+                    if !matches {
+                        let what = format!("calling `{}` with another \
+                            signature", ir::symbol(&callee.unwrap().name));
+                        return Err(unsupported(&function.name, &what));
+                    }
+                }
+                InstKind::Cast(CastOp::Bitcast, value) => {
+                    let from = function.value_type(value);
+                    let to = &function.insts[inst].ty;
+                    if from.is_aggregate() || to.is_aggregate() {
+                        return Err(unsupported(&function.name,
+                            "`bitcast` of aggregates"));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    if types.iter().any(wide) {
+        return Err(unsupported(&function.name, "`i128`"));
+    }
+    Ok(())
+}
+
+// Where a value is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Repr {
+    // Nowhere, as it isn't used
+    None,
+    Local(u32),
+    // On the operand stack, computed where it is used
+    Inline,
+    // In a slot of the frame, at an offset; the value is its address, or
+    // the address of the aggregate stored there
+    Frame(u32),
+}
+
+// The translation of a function body.
+struct Translator<'a, 'm> {
+    lowering: &'a mut Lowering<'m>,
+    function: &'a ir::Function,
+    body: Vec<Instr>,
+    // The number of parameters, and the types of the locals after them
+    params: u32,
+    locals: Vec<ValType>,
+    values: Vec<Repr>,
+    // The size of the frame, and the local with its address
+    frame: u32,
+    fp: u32,
+    // Whether aggregates are returned to an address passed first
+    sret: bool,
+    // The offset of the buffer of the extra arguments of each call to a
+    // variadic function
+    buffers: HashMap<InstId, u32>,
+    // The offset of a slot for each aggregate `phi` that other `phi`s of
+    // its block may read on an edge
+    temporaries: HashMap<InstId, u32>,
+}
+
+impl<'a, 'm> Translator<'a, 'm> {
+    fn new(lowering: &'a mut Lowering<'m>, function: &'a ir::Function,
+        ty: &FuncType) -> Self
+    {
+        let mut translator = Translator {
+            lowering,
+            function,
+            body: Vec::new(),
+            params: ty.params.len() as u32,
+            locals: Vec::new(),
+            values: vec![Repr::None; function.insts.len()],
+            frame: 0,
+            fp: 0,
+            sret: function.signature.ret.is_aggregate(),
+            buffers: HashMap::new(),
+            temporaries: HashMap::new(),
+        };
+        translator.analyze();
+        if translator.frame > 0 {
+            translator.frame = align_to(translator.frame, 16);
+            translator.fp = translator.local(ValType::I32);
+        }
+        translator
+    }
+
+    // Decide where each value is kept.
+    fn analyze(&mut self) {
+        let function = self.function;
+        let mut uses = vec![0; function.insts.len()];
+        for block in &function.blocks {
+            let operands = block.insts.iter()
+                .flat_map(|&inst| function.insts[inst].kind.operands());
+            for operand in operands.chain(block.term.operand()) {
+                if let Value::Inst(inst) = *operand {
+                    uses[inst] += 1;
+                }
+            }
+        }
+
+        let mut inline = vec![false; function.insts.len()];
+        for block in &function.blocks {
+            let insts = &block.insts;
+            let positions: HashMap<InstId, usize> = insts.iter().enumerate()
+                .map(|(at, &inst)| (inst, at))
+                .collect();
+            let position = |value: &Value| match *value {
+                Value::Inst(inst) => positions.get(&inst).copied(),
+                _ => None,
+            };
+            // The position of the user in the block of each value, if it
+            // can take its operands from the stack
+            let mut users = vec![None; insts.len()];
+            for (at, &inst) in insts.iter().enumerate() {
+                let kind = &function.insts[inst].kind;
+                let stacked = !matches!(kind, InstKind::Phi(_)
+                    | InstKind::Fcmp(FloatPredicate::One, ..)
+                    | InstKind::Fcmp(FloatPredicate::Ueq, ..));
+                if !stacked {
+                    continue;
+                }
+                for operand in kind.operands() {
+                    if let Some(def) = position(operand).filter(|&d| d < at) {
+                        users[def] = Some(at);
+                    }
+                }
+            }
+            if !matches!(block.term, ir::Terminator::Switch(..)) {
+                if let Some(def) = block.term.operand().and_then(position) {
+                    users[def] = Some(insts.len());
+                }
+            }
+            // Where the code of each instruction ends up, working back so
+            // the users are placed first
+            let mut placed: Vec<_> = (0..=insts.len()).collect();
+            for at in (0..insts.len()).rev() {
+                let inst = insts[at];
+                let to = match users[at] {
+                    Some(user) if uses[inst] == 1 && self.stackable(inst) => {
+                        placed[user]
+                    }
+                    _ => continue,
+                };
+                let past = &insts[at + 1..to];
+                if !self.effectful(inst)
+                    || !past.iter().any(|&other| self.effectful(other))
+                {
+                    inline[inst] = true;
+                    placed[at] = to;
+                }
+            }
+        }
+
+        for block in &function.blocks {
+            let mut phis = Vec::new();
+            for &inst in &block.insts {
+                self.values[inst] = match inline[inst] {
+                    true => Repr::Inline,
+                    false => self.repr(inst, uses[inst]),
+                };
+                let kind = &function.insts[inst].kind;
+                match kind {
+                    InstKind::Phi(_) if self.values[inst] != Repr::Inline
+                        && function.insts[inst].ty.is_aggregate() =>
+                    {
+                        phis.push(inst);
+                    }
+                    InstKind::Call(signature, _, args)
+                        if args.len() > signature.params.len() =>
+                    {
+                        let layout = varargs(function, signature, args);
+                        let buffer = self.slot(&layout);
+                        self.buffers.insert(inst, buffer);
+                    }
+                    _ => {}
+                }
+            }
+            if phis.len() > 1 {
+                for phi in phis {
+                    let slot = self.slot(&function.insts[phi].ty);
+                    self.temporaries.insert(phi, slot);
+                }
+            }
+        }
+    }
+
+    // Whether an instruction's value can be left on the operand stack.
+    fn stackable(&self, inst: InstId) -> bool {
+        let inst = &self.function.insts[inst];
+        inst.ty != Type::Void && !inst.ty.is_aggregate()
+            && !matches!(inst.kind, InstKind::Phi(_) | InstKind::Alloca(_))
+    }
+
+    // Whether moving an instruction past another it is moved past could
+    // change what either does.
+    fn effectful(&self, inst: InstId) -> bool {
+        let inst = &self.function.insts[inst];
+        inst.ty.is_aggregate() || match inst.kind {
+            InstKind::Load(_)
+            | InstKind::Store(..)
+            | InstKind::Call(..)
+            | InstKind::ExtractValue(..)
+            | InstKind::InsertValue(..)
+            | InstKind::VaStart(_)
+            | InstKind::VaArg(_) => true,
+            InstKind::Binary(op, ..) => matches!(op, BinaryOp::SDiv
+                | BinaryOp::UDiv | BinaryOp::SRem | BinaryOp::URem),
+            _ => false,
+        }
+    }
+
+    fn repr(&mut self, inst: InstId, uses: u32) -> Repr {
+        let inst = &self.function.insts[inst];
+        match inst.kind {
+            InstKind::Alloca(ref ty) => Repr::Frame(self.slot(ty)),
+            _ if inst.ty.is_aggregate() => Repr::Frame(self.slot(&inst.ty)),
+            _ if inst.ty == Type::Void || uses == 0 => Repr::None,
+            _ => Repr::Local(self.local(val_type(&inst.ty))),
+        }
+    }
+
+    // A slot of the frame for a value of a type.
+    fn slot(&mut self, ty: &Type) -> u32 {
+        let offset = align_to(self.frame, ty.align());
+        self.frame = offset + ty.size() as u32;
+        offset
+    }
+
+    fn push(&mut self, instr: Instr) {
+        self.body.push(instr);
+    }
+
+    fn numeric(&mut self, name: &str) {
+        self.push(Instr::numeric(name));
+    }
+
+    // Push the address of a slot of the frame.
+    fn frame_address(&mut self, offset: u32) {
+        self.push(Instr::LocalGet(self.fp));
+        if offset != 0 {
+            self.push(Instr::I32Const(offset as i32));
+            self.numeric("i32.add");
+        }
+    }
+
+    // Copy bytes from the address on top of the stack to the one below.
+    fn copy(&mut self, size: u64) {
+        self.push(Instr::I32Const(size as i32));
+        self.push(Instr::MemoryCopy);
+    }
+
+    fn value(&mut self, value: &Value) {
+        let function = self.function;
+        let instr = match *value {
+            Value::Inst(inst) => match self.values[inst] {
+                Repr::Local(local) => Instr::LocalGet(local),
+                Repr::Inline => return self.compute(inst),
+                Repr::Frame(offset) => return self.frame_address(offset),
+                Repr::None => unreachable!("value of an unused instruction"),
+            },
+            Value::Param(index) => {
+                Instr::LocalGet(index as u32 + u32::from(self.sret))
+            }
+            Value::Int(Type::Int(64), value) => Instr::I64Const(value as i64),
+            Value::Int(_, value) => Instr::I32Const(value as i32),
+            Value::Float(Type::F32, bits) => {
+                Instr::F32Const((f64::from_bits(bits) as f32).to_bits())
+            }
+            Value::Float(_, bits) => Instr::F64Const(bits),
+            Value::Null => Instr::I32Const(0),
+            Value::Global(ref name) => {
+                Instr::I32Const(self.lowering.address(name) as i32)
+            }
+            Value::Undef(ref ty) | Value::Zero(ref ty)
+                if !ty.is_aggregate() =>
+            {
+                match val_type(ty) {
+                    ValType::I32 => Instr::I32Const(0),
+                    ValType::I64 => Instr::I64Const(0),
+                    ValType::F32 => Instr::F32Const(0),
+                    ValType::F64 => Instr::F64Const(0),
+                }
+            }
+            _ => {
+                let ty = function.value_type(value);
+                let address = self.lowering.constant(&ty, value);
+                Instr::I32Const(address as i32)
+            }
+        };
+        self.push(instr);
+    }
+
+    // Push the base of an address, and return the constant offset to add.
+    fn address(&mut self, value: &Value) -> u32 {
+        let function = self.function;
+        if let Value::Inst(inst) = *value {
+            match (self.values[inst], &function.insts[inst].kind) {
+                (Repr::Frame(offset), _) => {
+                    self.push(Instr::LocalGet(self.fp));
+                    return offset;
+                }
+                (Repr::Inline, InstKind::PtrAdd(base, Value::Int(_, offset)))
+                    =>
+                {
+                    if let Ok(offset) = u32::try_from(*offset) {
+                        let inner = self.address(base);
+                        return match inner.checked_add(offset) {
+                            Some(sum) => sum,
+                            None => {
+                                self.push(Instr::I32Const(inner as i32));
+                                self.numeric("i32.add");
+                                offset
+                            }
+                        };
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.value(value);
+        0
+    }
+
+    // Push the value of an instruction computing a scalar.
+    fn compute(&mut self, inst: InstId) {
+        let function = self.function;
+        let ty = &function.insts[inst].ty;
+        match function.insts[inst].kind {
+            InstKind::Binary(op, ref a, ref b) => self.binary(op, ty, a, b),
+            InstKind::FNeg(ref a) => {
+                self.value(a);
+                self.numeric(&format!("{}.neg", val_type(ty).as_str()));
+            }
+            InstKind::Icmp(predicate, ref a, ref b) => {
+                self.icmp(predicate, a, b);
+            }
+            InstKind::Fcmp(predicate, ref a, ref b) => {
+                self.fcmp(predicate, a, b);
+            }
+            InstKind::Cast(op, ref value) => self.cast(op, value, ty),
+            InstKind::Select(ref condition, ref a, ref b) => {
+                self.value(a);
+                self.value(b);
+                self.value(condition);
+                self.push(Instr::Select);
+            }
+            InstKind::Load(ref address) => {
+                let offset = self.address(address);
+                self.push(Instr::memory(load(ty), offset));
+            }
+            InstKind::PtrAdd(ref address, ref offset) => {
+                self.value(address);
+                match *offset {
+                    Value::Int(_, 0) => return,
+                    Value::Int(_, offset) => {
+                        self.push(Instr::I32Const(offset as i32));
+                    }
+                    _ => {
+                        self.value(offset);
+                        let ty = function.value_type(offset);
+                        if val_type(&ty) == ValType::I64 {
+                            self.numeric("i32.wrap_i64");
+                        }
+                    }
+                }
+                self.numeric("i32.add");
+            }
+            InstKind::Call(..) => self.call(inst),
+            InstKind::VaArg(ref va_list) => {
+                self.next_arg(va_list, ty);
+                self.push(Instr::memory(load(ty), 0));
+            }
+            InstKind::ExtractValue(ref aggregate, index) => {
+                let aggregate_ty = function.value_type(aggregate);
+                let member = aggregate_ty.offset(index as usize) as u32;
+                let offset = self.address(aggregate);
+                match offset.checked_add(member) {
+                    Some(offset) => self.push(Instr::memory(load(ty), offset)),
+                    None => {
+                        self.push(Instr::I32Const(offset as i32));
+                        self.numeric("i32.add");
+                        self.push(Instr::memory(load(ty), member));
+                    }
+                }
+            }
+            _ => unreachable!("no scalar value"),
+        }
+    }
+
+    fn binary(&mut self, op: BinaryOp, ty: &Type, a: &Value, b: &Value) {
+        let prefix = val_type(ty).as_str();
+        if op.is_float() {
+            self.value(a);
+            self.value(b);
+            let name = match op {
+                BinaryOp::FAdd => "add",
+                BinaryOp::FSub => "sub",
+                BinaryOp::FMul => "mul",
+                BinaryOp::FDiv => "div",
+                _ => {
+                    let (name, _) = fmod(ty);
+                    let index = self.lowering.functions[name];
+                    return self.push(Instr::Call(index));
+                }
+            };
+            return self.numeric(&format!("{}.{}", prefix, name));
+        }
+
+        let bits = match *ty {
+            Type::Int(bits) => bits,
+            _ => 32,
+        };
+        // The name, whether the operands are zero extended and whether the
+        // result is sign extended
+        let (name, unsigned, normalize) = match op {
+            BinaryOp::Add => ("add", 0, true),
+            BinaryOp::Sub => ("sub", 0, true),
+            BinaryOp::Mul => ("mul", 0, true),
+            BinaryOp::SDiv => ("div_s", 0, true),
+            BinaryOp::UDiv => ("div_u", 2, true),
+            BinaryOp::SRem => ("rem_s", 0, true),
+            BinaryOp::URem => ("rem_u", 2, true),
+            BinaryOp::Shl => ("shl", 0, true),
+            BinaryOp::LShr => ("shr_u", 1, true),
+            BinaryOp::AShr => ("shr_s", 0, false),
+            BinaryOp::And => ("and", 0, false),
+            BinaryOp::Or => ("or", 0, false),
+            BinaryOp::Xor => ("xor", 0, false),
+            _ => unreachable!(),
+        };
+        self.value(a);
+        if unsigned > 0 {
+            self.zero_extend(bits);
+        }
+        self.value(b);
+        if unsigned > 1 {
+            self.zero_extend(bits);
+        }
+        self.numeric(&format!("{}.{}", prefix, name));
+        if normalize {
+            self.normalize(bits);
+        }
+    }
+
+    // Sign extend the narrow integer on top of the stack from its width.
+    fn normalize(&mut self, bits: u32) {
+        match bits {
+            1 => {
+                self.push(Instr::I32Const(1));
+                self.numeric("i32.and");
+            }
+            8 => self.numeric("i32.extend8_s"),
+            16 => self.numeric("i32.extend16_s"),
+            _ => {}
+        }
+    }
+
+    // Zero extend the narrow integer on top of the stack from its width.
+    fn zero_extend(&mut self, bits: u32) {
+        match bits {
+            8 | 16 => {
+                self.push(Instr::I32Const((1 << bits) - 1));
+                self.numeric("i32.and");
+            }
+            _ => {}
+        }
+    }
+
+    fn icmp(&mut self, predicate: IntPredicate, a: &Value, b: &Value) {
+        let ty = self.function.value_type(a);
+        let bits = match ty {
+            Type::Int(bits) => bits,
+            _ => 32,
+        };
+        let name = match predicate {
+            IntPredicate::Eq => "eq",
+            IntPredicate::Ne => "ne",
+            IntPredicate::Slt => "lt_s",
+            IntPredicate::Sle => "le_s",
+            IntPredicate::Sgt => "gt_s",
+            IntPredicate::Sge => "ge_s",
+            IntPredicate::Ult => "lt_u",
+            IntPredicate::Ule => "le_u",
+            IntPredicate::Ugt => "gt_u",
+            IntPredicate::Uge => "ge_u",
+        };
+        let unsigned = name.ends_with("_u");
+        self.value(a);
+        if unsigned {
+            self.zero_extend(bits);
+        }
+        self.value(b);
+        if unsigned {
+            self.zero_extend(bits);
+        }
+        self.numeric(&format!("{}.{}", val_type(&ty).as_str(), name));
+    }
+
+    fn fcmp(&mut self, predicate: FloatPredicate, a: &Value, b: &Value) {
+        let prefix = val_type(&self.function.value_type(a)).as_str();
+        let name = match predicate {
+            FloatPredicate::Oeq => "eq",
+            FloatPredicate::Olt => "lt",
+            FloatPredicate::Ole => "le",
+            FloatPredicate::Ogt => "gt",
+            FloatPredicate::Oge => "ge",
+            FloatPredicate::Une => "ne",
+            // Ordered and unequal is less or greater, and unordered or
+            // equal is the opposite.
+            FloatPredicate::One | FloatPredicate::Ueq => {
+                for name in ["lt", "gt"] {
+                    self.value(a);
+                    self.value(b);
+                    self.numeric(&format!("{}.{}", prefix, name));
+                }
+                self.numeric("i32.or");
+                if predicate == FloatPredicate::Ueq {
+                    self.numeric("i32.eqz");
+                }
+                return;
+            }
+        };
+        self.value(a);
+        self.value(b);
+        self.numeric(&format!("{}.{}", prefix, name));
+    }
+
+    fn cast(&mut self, op: CastOp, value: &Value, to: &Type) {
+        let from = self.function.value_type(value);
+        let bits = |ty: &Type| match *ty {
+            Type::Int(bits) => bits,
+            _ => 32,
+        };
+        let (from_bits, to_bits) = (bits(&from), bits(to));
+        let from_ty = val_type(&from);
+        let to_ty = val_type(to);
+        self.value(value);
+        match op {
+            CastOp::Trunc => {
+                if from_ty == ValType::I64 && to_ty == ValType::I32 {
+                    self.numeric("i32.wrap_i64");
+                }
+                self.normalize(to_bits);
+            }
+            CastOp::ZExt => {
+                self.zero_extend(from_bits);
+                if from_ty == ValType::I32 && to_ty == ValType::I64 {
+                    self.numeric("i64.extend_i32_u");
+                }
+            }
+            CastOp::SExt => {
+                // `i1` is 0 or 1, so it is negated to be sign extended.
+                if from_bits == 1 {
+                    self.push(Instr::I32Const(-1));
+                    self.numeric("i32.mul");
+                }
+                if from_ty == ValType::I32 && to_ty == ValType::I64 {
+                    self.numeric("i64.extend_i32_s");
+                }
+            }
+            CastOp::FpTrunc => self.numeric("f32.demote_f64"),
+            CastOp::FpExt => self.numeric("f64.promote_f32"),
+            CastOp::FpToSi | CastOp::FpToUi => {
+                let sign = match op {
+                    CastOp::FpToSi => "s",
+                    _ => "u",
+                };
+                self.push(Instr::trunc_sat(&format!("{}.trunc_sat_{}_{}",
+                    to_ty.as_str(), from_ty.as_str(), sign)));
+                self.normalize(to_bits);
+            }
+            CastOp::SiToFp => {
+                self.numeric(&format!("{}.convert_{}_s", to_ty.as_str(),
+                    from_ty.as_str()));
+            }
+            CastOp::UiToFp => {
+                self.zero_extend(from_bits);
+                self.numeric(&format!("{}.convert_{}_u", to_ty.as_str(),
+                    from_ty.as_str()));
+            }
+            CastOp::PtrToInt | CastOp::IntToPtr | CastOp::Bitcast => {
+                match (from_ty, to_ty) {
+                    (ValType::I32, ValType::I64) => {
+                        self.zero_extend(from_bits);
+                        self.numeric("i64.extend_i32_u");
+                    }
+                    (ValType::I64, ValType::I32) => {
+                        self.numeric("i32.wrap_i64");
+                        self.normalize(to_bits);
+                    }
+                    (ValType::I32, ValType::I32) => {
+                        if op == CastOp::IntToPtr {
+                            self.zero_extend(from_bits);
+                        } else {
+                            self.normalize(to_bits);
+                        }
+                    }
+                    (from_ty, to_ty) if from_ty != to_ty => {
+                        self.numeric(&format!("{}.reinterpret_{}",
+                            to_ty.as_str(), from_ty.as_str()));
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // Call a function, pushing its result unless it is an aggregate, which
+    // goes to the instruction's slot.
+    fn call(&mut self, inst: InstId) {
+        let function = self.function;
+        let (signature, callee, args) = match function.insts[inst].kind {
+            InstKind::Call(ref signature, ref callee, ref args) => {
+                (signature, callee, args)
+            }
+            _ => unreachable!(),
+        };
+        if let Repr::Frame(offset) = self.values[inst] {
+            self.frame_address(offset);
+        }
+        let (fixed, rest) = args.split_at(signature.params.len());
+        for arg in fixed {
+            self.value(arg);
+        }
+        if signature.variadic {
+            match self.buffers.get(&inst) {
+                Some(&buffer) => {
+                    let layout = varargs(function, signature, args);
+                    for (index, arg) in rest.iter().enumerate() {
+                        let ty = layout.member(index as u32).unwrap();
+                        let at = buffer + layout.offset(index) as u32;
+                        self.store_at(at, ty, arg);
+                    }
+                    self.frame_address(buffer);
+                }
+                None => self.push(Instr::I32Const(0)),
+            }
+        }
+        match callee {
+            Value::Global(name) if self.lowering.functions.contains_key(name)
+                =>
+            {
+                let index = self.lowering.functions[name];
+                self.push(Instr::Call(index));
+            }
+            _ => {
+                self.value(callee);
+                let ty = self.lowering.type_index(signature_type(signature));
+                self.push(Instr::CallIndirect(ty));
+            }
+        }
+    }
+
+    // Push the address of the next extra argument of a type in the buffer a
+    // `va_list` points to, and move it past the argument.
+    fn next_arg(&mut self, va_list: &Value, ty: &Type) {
+        let list = self.local(ValType::I32);
+        let arg = self.local(ValType::I32);
+        self.value(va_list);
+        self.push(Instr::LocalTee(list));
+        self.push(Instr::LocalGet(list));
+        self.push(Instr::memory("i32.load", VA_BUFFER));
+        let align = ty.align() as i32;
+        if align > 1 {
+            self.push(Instr::I32Const(align - 1));
+            self.numeric("i32.add");
+            self.push(Instr::I32Const(-align));
+            self.numeric("i32.and");
+        }
+        self.push(Instr::LocalTee(arg));
+        self.push(Instr::I32Const(ty.size() as i32));
+        self.numeric("i32.add");
+        self.push(Instr::memory("i32.store", VA_BUFFER));
+        self.push(Instr::LocalGet(arg));
+    }
+
+    // Store a value in a slot of the frame.
+    fn store_at(&mut self, offset: u32, ty: &Type, value: &Value) {
+        if ty.is_aggregate() {
+            self.frame_address(offset);
+            self.value(value);
+            self.copy(ty.size());
+        } else {
+            self.push(Instr::LocalGet(self.fp));
+            self.value(value);
+            self.push(Instr::memory(store(ty), offset));
+        }
+    }
+
+    // Translate an instruction whose value isn't left on the stack.
+    fn statement(&mut self, inst: InstId) {
+        let function = self.function;
+        let ty = &function.insts[inst].ty;
+        match (self.values[inst], &function.insts[inst].kind) {
+            (Repr::Inline, _)
+            | (_, InstKind::Phi(_))
+            | (_, InstKind::Alloca(_)) => {}
+            (_, InstKind::Store(value, address)) => {
+                let ty = function.value_type(value);
+                let offset = self.address(address);
+                if ty.is_aggregate() {
+                    if offset != 0 {
+                        self.push(Instr::I32Const(offset as i32));
+                        self.numeric("i32.add");
+                    }
+                    self.value(value);
+                    self.copy(ty.size());
+                } else {
+                    self.value(value);
+                    self.push(Instr::memory(store(&ty), offset));
+                }
+            }
+            (Repr::Local(local), _) => {
+                self.compute(inst);
+                self.push(Instr::LocalSet(local));
+            }
+            (Repr::Frame(offset), kind) => self.aggregate(inst, offset, kind),
+            (Repr::None, InstKind::Call(..)) => {
+                self.call(inst);
+                if *ty != Type::Void {
+                    self.push(Instr::Drop);
+                }
+            }
+            (_, InstKind::VaStart(va_list)) => {
+                let offset = self.address(va_list);
+                self.push(Instr::LocalGet(self.params - 1));
+                self.push(Instr::memory("i32.store", offset + VA_BUFFER));
+            }
+            (Repr::None, InstKind::VaArg(va_list)) => {
+                self.next_arg(va_list, ty);
+                self.push(Instr::Drop);
+            }
+            (Repr::None, _) => {}
+        }
+    }
+
+    // Write the value of an aggregate instruction to its slot.
+    fn aggregate(&mut self, inst: InstId, offset: u32, kind: &InstKind) {
+        let function = self.function;
+        let ty = &function.insts[inst].ty;
+        match *kind {
+            InstKind::Call(..) => self.call(inst),
+            InstKind::VaArg(ref va_list) => {
+                self.frame_address(offset);
+                self.next_arg(va_list, ty);
+                self.copy(ty.size());
+            }
+            InstKind::Load(ref address) => {
+                self.frame_address(offset);
+                self.value(address);
+                self.copy(ty.size());
+            }
+            InstKind::Select(ref condition, ref a, ref b) => {
+                self.frame_address(offset);
+                self.value(a);
+                self.value(b);
+                self.value(condition);
+                self.push(Instr::Select);
+                self.copy(ty.size());
+            }
+            InstKind::ExtractValue(ref aggregate, index) => {
+                let aggregate_ty = function.value_type(aggregate);
+                let member = aggregate_ty.offset(index as usize) as u32;
+                self.frame_address(offset);
+                self.value(aggregate);
+                if member != 0 {
+                    self.push(Instr::I32Const(member as i32));
+                    self.numeric("i32.add");
+                }
+                self.copy(ty.size());
+            }
+            InstKind::InsertValue(ref aggregate, ref value, index) => {
+                if !matches!(aggregate, Value::Undef(_)) {
+                    self.frame_address(offset);
+                    self.value(aggregate);
+                    self.copy(ty.size());
+                }
+                let member_ty = ty.member(index).unwrap();
+                let at = offset + ty.offset(index as usize) as u32;
+                self.store_at(at, member_ty, value);
+            }
+            _ => {}
+        }
+    }
+
+    // The epilogue: free the frame.
+    fn epilogue(&mut self) {
+        if self.frame > 0 {
+            self.push(Instr::LocalGet(self.fp));
+            self.push(Instr::I32Const(self.frame as i32));
+            self.numeric("i32.add");
+            self.push(Instr::GlobalSet(STACK_POINTER));
+        }
+    }
+}
+
+impl<'a, 'm> Emitter for Translator<'a, 'm> {
+    fn instr(&mut self, instr: Instr) {
+        self.push(instr);
+    }
+
+    fn block(&mut self, block: BlockId) {
+        let function = self.function;
+        for &inst in &function.blocks[block].insts {
+            self.statement(inst);
+        }
+    }
+
+    fn operand(&mut self, value: &Value) {
+        self.value(value);
+    }
+
+    fn edge(&mut self, from: BlockId, to: BlockId) {
+        let function = self.function;
+        let mut phis = Vec::new();
+        for &inst in &function.blocks[to].insts {
+            let incoming = match function.insts[inst].kind {
+                InstKind::Phi(ref incoming) => incoming,
+                _ => break,
+            };
+            let value = incoming.iter().find(|&&(pred, _)| pred == from)
+                .map(|(_, value)| value);
+            if let Some(value) = value {
+                if *value != Value::Inst(inst) {
+                    phis.push((inst, value));
+                }
+            }
+        }
+
+        // The scalars are all read before any is written, with the
+        // aggregates copied in between, through temporary slots if they
+        // may read each other.
+        let mut locals = Vec::new();
+        let mut aggregates = Vec::new();
+        for &(phi, value) in &phis {
+            match self.values[phi] {
+                Repr::Local(local) => {
+                    self.value(value);
+                    locals.push(local);
+                }
+                Repr::Frame(offset) => aggregates.push((phi, offset, value)),
+                _ => {}
+            }
+        }
+        let overlap = aggregates.len() > 1
+            && aggregates.iter().any(|&(_, _, value)| {
+                aggregates.iter().any(|&(phi, ..)| *value == Value::Inst(phi))
+            });
+        for &(phi, offset, value) in &aggregates {
+            let size = function.insts[phi].ty.size();
+            let offset = match overlap {
+                true => self.temporaries[&phi],
+                false => offset,
+            };
+            self.frame_address(offset);
+            self.value(value);
+            self.copy(size);
+        }
+        if overlap {
+            for &(phi, offset, _) in &aggregates {
+                let size = function.insts[phi].ty.size();
+                self.frame_address(offset);
+                self.frame_address(self.temporaries[&phi]);
+                self.copy(size);
+            }
+        }
+        for local in locals.into_iter().rev() {
+            self.push(Instr::LocalSet(local));
+        }
+    }
+
+    fn ret(&mut self, value: Option<&Value>) {
+        match value {
+            Some(value) if self.sret => {
+                let size = self.function.value_type(value).size();
+                self.push(Instr::LocalGet(0));
+                self.value(value);
+                self.copy(size);
+            }
+            Some(value) => self.value(value),
+            None => {}
+        }
+        self.epilogue();
+        self.push(Instr::Return);
+    }
+
+    fn local(&mut self, ty: ValType) -> u32 {
+        self.locals.push(ty);
+        self.params + self.locals.len() as u32 - 1
+    }
+}
+
+// The layout of the extra arguments of a call to a variadic function.
+fn varargs(function: &ir::Function, signature: &Signature, args: &[Value])
+    -> Type
+{
+    let extra = &args[signature.params.len().min(args.len())..];
+    Type::Struct(extra.iter().map(|arg| function.value_type(arg)).collect())
+}
+
+fn load(ty: &Type) -> &'static str {
+    match ty {
+        Type::Int(1) => "i32.load8_u",
+        Type::Int(8) => "i32.load8_s",
+        Type::Int(16) => "i32.load16_s",
+        Type::Int(64) => "i64.load",
+        Type::F32 => "f32.load",
+        Type::F64 => "f64.load",
+        _ => "i32.load",
+    }
+}
+
+fn store(ty: &Type) -> &'static str {
+    match ty {
+        Type::Int(1) | Type::Int(8) => "i32.store8",
+        Type::Int(16) => "i32.store16",
+        Type::Int(64) => "i64.store",
+        Type::F32 => "f32.store",
+        Type::F64 => "f64.store",
+        _ => "i32.store",
+    }
+}
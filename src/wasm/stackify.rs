@@ -0,0 +1,496 @@
+// Control flow
+//
+//! Rebuilds structured control flow from the blocks of a function, with
+//! the algorithm of Norman Ramsey's "Beyond Relooper: Recursive Translation
+//! of Unstructured Control Flow to Structured Control Flow".
+//!
+//! The code of a block is placed where its immediate dominator's code
+//! ends, so it follows the code of every block branching to it.  A block
+//! branched to from one place is inlined there; a block that is a merge
+//! point, with more than one edge coming in from blocks earlier in reverse
+//! postorder, follows a `block` that branches to it break out of, and a
+//! loop header starts a `loop` that branches back to it continue.  Blocks
+//! nest in reverse postorder so each branch's label is in scope.  The
+//! targets of a `switch` are all treated as merge points, so `br_table`
+//! can reach them.
+//!
+//! This needs every loop to have a single entry: when a back edge goes to a
+//! block that doesn't dominate its source, the control-flow graph isn't
+//! reducible and the blocks are instead put in a loop that dispatches on a
+//! local holding the index of the block to run next.
+
+use super::{Instr, ValType};
+use crate::ir::dominators::Dominators;
+use crate::ir::{BlockId, Function, InstKind, Terminator, Type, Value};
+
+/// What the rebuilding of control flow needs from the translation of the
+/// rest of a function
+pub(super) trait Emitter {
+    fn instr(&mut self, instr: Instr);
+
+    /// Translate the instructions of a block but its terminator.
+    fn block(&mut self, block: BlockId);
+
+    /// Push the value of the operand of a terminator.
+    fn operand(&mut self, value: &Value);
+
+    /// Give the `phi`s of `to` their values on the edge from `from`.
+    fn edge(&mut self, from: BlockId, to: BlockId);
+
+    /// Return from the function.
+    fn ret(&mut self, value: Option<&Value>);
+
+    /// Add a local of a type.
+    fn local(&mut self, ty: ValType) -> u32;
+}
+
+// Switches with at most this many values per case use `br_table`.
+const DENSITY: i128 = 3;
+
+/// Split the edges from `switch`es to blocks with `phi`s, which
+/// `br_table` can't give values on.
+pub(super) fn split_switch_edges(function: &mut Function) {
+    for block in 0..function.blocks.len() {
+        if !matches!(function.blocks[block].term, Terminator::Switch(..)) {
+            continue;
+        }
+        let mut split: Vec<(BlockId, BlockId)> = Vec::new();
+        for succ in function.blocks[block].term.successors() {
+            let has_phis = function.blocks[succ].insts.first()
+                .is_some_and(|&inst| {
+                    matches!(function.insts[inst].kind, InstKind::Phi(_))
+                });
+            if !has_phis || split.iter().any(|&(to, _)| to == succ) {
+                continue;
+            }
+            let new = function.add_block();
+            function.blocks[new].term = Terminator::Br(succ);
+            for &inst in &function.blocks[succ].insts {
+                match function.insts[inst].kind {
+                    InstKind::Phi(ref mut incoming) => {
+                        for (pred, _) in incoming.iter_mut() {
+                            if *pred == block {
+                                *pred = new;
+                            }
+                        }
+                    }
+                    _ => break,
+                }
+            }
+            split.push((succ, new));
+        }
+        for succ in function.blocks[block].term.successors_mut() {
+            if let Some(&(_, new)) = split.iter().find(|(to, _)| to == succ) {
+                *succ = new;
+            }
+        }
+    }
+}
+
+// What a label branches to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Label {
+    // The end of an `if`, which nothing branches to
+    If,
+    // The start of the loop of a header
+    Loop(BlockId),
+    // The end of the `block` a block follows
+    Follow(BlockId),
+    // The start of the dispatch loop
+    Dispatch,
+}
+
+struct Stackifier<'a, E> {
+    function: &'a Function,
+    emitter: &'a mut E,
+    // Index of each block in reverse postorder
+    order: Vec<usize>,
+    // Dominator tree children
+    children: Vec<Vec<BlockId>>,
+    merge: Vec<bool>,
+    header: Vec<bool>,
+    // The labels in scope, innermost last
+    labels: Vec<Label>,
+    // The local of the next block in the dispatch loop, and the index of
+    // each block in it
+    dispatch: Option<(u32, Vec<usize>)>,
+}
+
+/// Translate the control flow of a function with a body.
+pub(super) fn stackify<E: Emitter>(function: &Function, emitter: &mut E) {
+    let dominators = Dominators::new(function);
+    let count = function.blocks.len();
+    let mut order = vec![usize::MAX; count];
+    for (index, &block) in dominators.reverse_postorder().iter().enumerate() {
+        order[block] = index;
+    }
+    let mut forward = vec![0; count];
+    let mut header = vec![false; count];
+    let mut reducible = true;
+    for &block in dominators.reverse_postorder() {
+        for succ in function.blocks[block].term.successors() {
+            if order[succ] > order[block] {
+                forward[succ] += 1;
+            } else {
+                header[succ] = true;
+                reducible &= dominators.dominates(succ, block);
+            }
+        }
+    }
+
+    let mut stackifier = Stackifier {
+        function,
+        emitter,
+        order,
+        children: dominators.children(),
+        merge: forward.iter().map(|&edges| edges > 1).collect(),
+        header,
+        labels: Vec::new(),
+        dispatch: None,
+    };
+    match reducible {
+        true => stackifier.tree(0),
+        false => stackifier.dispatch(dominators.reverse_postorder()),
+    }
+}
+
+impl<'a, E: Emitter> Stackifier<'a, E> {
+    // The code of a block and those it immediately dominates.
+    fn tree(&mut self, block: BlockId) {
+        let switch = match self.function.blocks[block].term {
+            Terminator::Switch(..) => {
+                self.function.blocks[block].term.successors()
+            }
+            _ => Vec::new(),
+        };
+        let mut follows: Vec<_> = self.children[block].iter().copied()
+            .filter(|&child| self.merge[child] || switch.contains(&child))
+            .collect();
+        follows.sort_by_key(|&child| std::cmp::Reverse(self.order[child]));
+        if self.header[block] {
+            self.emitter.instr(Instr::Loop);
+            self.labels.push(Label::Loop(block));
+            self.within(block, &follows);
+            self.labels.pop();
+            self.emitter.instr(Instr::End);
+        } else {
+            self.within(block, &follows);
+        }
+    }
+
+    // The code of a block inside the `block`s its followers follow.
+    fn within(&mut self, block: BlockId, follows: &[BlockId]) {
+        match follows.split_first() {
+            Some((&follow, rest)) => {
+                self.emitter.instr(Instr::Block);
+                self.labels.push(Label::Follow(follow));
+                self.within(block, rest);
+                self.labels.pop();
+                self.emitter.instr(Instr::End);
+                self.tree(follow);
+            }
+            None => {
+                self.emitter.block(block);
+                self.terminator(block);
+            }
+        }
+    }
+
+    fn terminator(&mut self, block: BlockId) {
+        match self.function.blocks[block].term {
+            Terminator::Br(target) => self.branch(block, target),
+            Terminator::CondBr(ref cond, then, else_) => {
+                self.emitter.operand(cond);
+                self.emitter.instr(Instr::If);
+                self.labels.push(Label::If);
+                self.branch(block, then);
+                self.emitter.instr(Instr::Else);
+                self.branch(block, else_);
+                self.labels.pop();
+                self.emitter.instr(Instr::End);
+            }
+            Terminator::Switch(ref value, default, ref cases) => {
+                self.switch(block, value, default, cases);
+            }
+            Terminator::Ret(ref value) => self.emitter.ret(value.as_ref()),
+            Terminator::Unreachable => self.emitter.instr(Instr::Unreachable),
+        }
+    }
+
+    fn branch(&mut self, from: BlockId, to: BlockId) {
+        self.emitter.edge(from, to);
+        if let Some((local, ref indices)) = self.dispatch {
+            let index = indices[to] as i32;
+            self.emitter.instr(Instr::I32Const(index));
+            self.emitter.instr(Instr::LocalSet(local));
+            let depth = self.depth(Label::Dispatch);
+            self.emitter.instr(Instr::Br(depth));
+        } else if self.order[to] <= self.order[from] {
+            let depth = self.depth(Label::Loop(to));
+            self.emitter.instr(Instr::Br(depth));
+        } else if self.merge[to] {
+            let depth = self.depth(Label::Follow(to));
+            self.emitter.instr(Instr::Br(depth));
+        } else {
+            self.tree(to);
+        }
+    }
+
+    // The depth of a label in scope.
+    fn depth(&self, label: Label) -> u32 {
+        let depth = self.labels.iter().rev().position(|&l| l == label);
+        depth.unwrap() as u32
+    }
+
+    // The depth of the label a `switch` branches to a block by.
+    fn target(&self, from: BlockId, to: BlockId) -> u32 {
+        match self.dispatch {
+            Some(_) => unreachable!(),
+            None if self.order[to] <= self.order[from] => {
+                self.depth(Label::Loop(to))
+            }
+            None => self.depth(Label::Follow(to)),
+        }
+    }
+
+    fn switch(&mut self, block: BlockId, value: &Value, default: BlockId,
+        cases: &[(i128, BlockId)])
+    {
+        // In the dispatch loop, each case sets the next block.
+        if self.dispatch.is_some() {
+            for &(case, target) in cases {
+                self.compare(value, case);
+                self.emitter.instr(Instr::If);
+                self.labels.push(Label::If);
+                self.branch(block, target);
+                self.labels.pop();
+                self.emitter.instr(Instr::End);
+            }
+            self.branch(block, default);
+            return;
+        }
+
+        let min = cases.iter().map(|&(case, _)| case).min();
+        let max = cases.iter().map(|&(case, _)| case).max();
+        let range = match (min, max) {
+            (Some(min), Some(max)) => max - min + 1,
+            _ => 0,
+        };
+        let dense = range > 0 && range <= DENSITY * cases.len() as i128
+            && range <= 1 << 16;
+        if !dense {
+            for &(case, target) in cases {
+                self.compare(value, case);
+                let depth = self.target(block, target);
+                self.emitter.instr(Instr::BrIf(depth));
+            }
+            let depth = self.target(block, default);
+            self.emitter.instr(Instr::Br(depth));
+            return;
+        }
+
+        // The index of the value from the lowest case, with values out of
+        // range going to the default.
+        let min = min.unwrap();
+        let default_depth = self.target(block, default);
+        self.emitter.operand(value);
+        if self.function.value_type(value) == Type::I64 {
+            let local = self.emitter.local(ValType::I64);
+            self.emitter.instr(Instr::I64Const(min as i64));
+            self.emitter.instr(Instr::numeric("i64.sub"));
+            self.emitter.instr(Instr::LocalTee(local));
+            self.emitter.instr(Instr::I64Const(range as i64));
+            self.emitter.instr(Instr::numeric("i64.ge_u"));
+            self.emitter.instr(Instr::BrIf(default_depth));
+            self.emitter.instr(Instr::LocalGet(local));
+            self.emitter.instr(Instr::numeric("i32.wrap_i64"));
+        } else if min != 0 {
+            self.emitter.instr(Instr::I32Const(min as i32));
+            self.emitter.instr(Instr::numeric("i32.sub"));
+        }
+        let labels = (min..min + range)
+            .map(|value| {
+                let target = cases.iter().find(|&&(case, _)| case == value)
+                    .map_or(default, |&(_, target)| target);
+                self.target(block, target)
+            })
+            .collect();
+        self.emitter.instr(Instr::BrTable(labels, default_depth));
+    }
+
+    // Push whether the operand of a `switch` is a case.
+    fn compare(&mut self, value: &Value, case: i128) {
+        self.emitter.operand(value);
+        match self.function.value_type(value) {
+            Type::I64 => {
+                self.emitter.instr(Instr::I64Const(case as i64));
+                self.emitter.instr(Instr::numeric("i64.eq"));
+            }
+            _ => {
+                self.emitter.instr(Instr::I32Const(case as i32));
+                self.emitter.instr(Instr::numeric("i32.eq"));
+            }
+        }
+    }
+
+    // Put the blocks in a loop dispatching on the index of the next one.
+    fn dispatch(&mut self, order: &[BlockId]) {
+        let mut indices = vec![0; self.function.blocks.len()];
+        for (index, &block) in order.iter().enumerate() {
+            indices[block] = index;
+        }
+        let local = self.emitter.local(ValType::I32);
+        self.dispatch = Some((local, indices));
+        self.emitter.instr(Instr::Loop);
+        self.labels.push(Label::Dispatch);
+        for &block in order.iter().rev() {
+            self.emitter.instr(Instr::Block);
+            self.labels.push(Label::Follow(block));
+        }
+        self.emitter.instr(Instr::LocalGet(local));
+        let labels: Vec<_> = (0..order.len() as u32).collect();
+        let last = labels.len() as u32 - 1;
+        self.emitter.instr(Instr::BrTable(labels, last));
+        for &block in order {
+            self.labels.pop();
+            self.emitter.instr(Instr::End);
+            self.emitter.block(block);
+            self.terminator(block);
+        }
+        self.labels.pop();
+        self.emitter.instr(Instr::End);
+    }
+}
+
+/// Remove the branches to where control gets without them, at the ends
+/// of the constructs they end, and a final `return`.
+pub(super) fn simplify(body: &mut Vec<Instr>) {
+    // The `end` of the `if` of each `else`
+    let mut ends = vec![0; body.len()];
+    let mut elses = Vec::new();
+    for (index, instr) in body.iter().enumerate() {
+        match instr {
+            Instr::Block | Instr::Loop | Instr::If => elses.push(None),
+            Instr::Else => *elses.last_mut().unwrap() = Some(index),
+            Instr::End => {
+                if let Some(Some(else_)) = elses.pop() {
+                    ends[else_] = index;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut open = Vec::new();
+    let mut out = Vec::with_capacity(body.len());
+    for (index, instr) in body.iter().enumerate() {
+        match *instr {
+            Instr::Block | Instr::Loop | Instr::If => open.push(instr.clone()),
+            Instr::End => {
+                open.pop();
+            }
+            Instr::Br(label)
+                if falls_through(body, &ends, &open, index, label as usize) =>
+            {
+                continue;
+            }
+            _ => {}
+        }
+        out.push(instr.clone());
+    }
+    if out.last() == Some(&Instr::Return) {
+        out.pop();
+    }
+    *body = unwrap_blocks(out);
+}
+
+// Remove the `block`s nothing branches out of, renumbering the labels of
+// the branches out of other constructs around them.
+fn unwrap_blocks(body: Vec<Instr>) -> Vec<Instr> {
+    let labels = |instr: &Instr| match *instr {
+        Instr::Br(label) | Instr::BrIf(label) => vec![label],
+        Instr::BrTable(ref labels, default) => {
+            labels.iter().copied().chain([default]).collect()
+        }
+        _ => Vec::new(),
+    };
+    let mut open = Vec::new();
+    let mut targeted = vec![false; body.len()];
+    let mut removed = vec![false; body.len()];
+    for (index, instr) in body.iter().enumerate() {
+        match instr {
+            Instr::Block | Instr::Loop | Instr::If => open.push(index),
+            Instr::End => {
+                let start = open.pop().unwrap();
+                if body[start] == Instr::Block && !targeted[start] {
+                    removed[start] = true;
+                    removed[index] = true;
+                }
+            }
+            _ => {
+                for label in labels(instr) {
+                    let depth = open.len().checked_sub(label as usize + 1);
+                    if let Some(depth) = depth {
+                        targeted[open[depth]] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut out = Vec::with_capacity(body.len());
+    for (index, mut instr) in body.into_iter().enumerate() {
+        match instr {
+            Instr::Block | Instr::Loop | Instr::If => open.push(index),
+            Instr::End => {
+                open.pop();
+            }
+            _ => {}
+        }
+        if removed[index] {
+            continue;
+        }
+        // Count the constructs kept between the branch and its target.
+        let renumber = |label: &mut u32| {
+            let between = match open.len().checked_sub(*label as usize + 1) {
+                Some(depth) => &open[depth + 1..],
+                None => &open[..],
+            };
+            *label -= between.iter().filter(|&&i| removed[i]).count() as u32;
+        };
+        match instr {
+            Instr::Br(ref mut label) | Instr::BrIf(ref mut label) => {
+                renumber(label);
+            }
+            Instr::BrTable(ref mut labels, ref mut default) => {
+                labels.iter_mut().for_each(renumber);
+                renumber(default);
+            }
+            _ => {}
+        }
+        out.push(instr);
+    }
+    out
+}
+
+// Whether the branch at an index only leaves the constructs around it up
+// to the one of its label, which isn't a loop.
+fn falls_through(body: &[Instr], ends: &[usize], open: &[Instr],
+    index: usize, label: usize) -> bool
+{
+    let mut at = index + 1;
+    for depth in 0..=label {
+        let construct = open.len().checked_sub(depth + 1).map(|i| &open[i]);
+        match body.get(at) {
+            Some(Instr::End) => {}
+            Some(Instr::Else) => at = ends[at],
+            None => return construct.is_none() && depth == label,
+            _ => return false,
+        }
+        if depth == label {
+            return construct != Some(&Instr::Loop);
+        }
+        at += 1;
+    }
+    false
+}
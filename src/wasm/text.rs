@@ -0,0 +1,244 @@
+// Text format
+//
+//! Prints modules in the text format of WebAssembly, laid out like
+//! `wasm2wat` does: sections in the order of the binary format, one
+//! instruction per line, indented by the blocks they are in.  Functions
+//! and globals are referred to by their names when they have usable ones,
+//! and by their indices otherwise.
+
+use std::fmt::{self, Write};
+
+use super::{ExportKind, FuncType, Instr, Module, ValType, MEMORY};
+
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut lines = Vec::new();
+        for (index, ty) in self.types.iter().enumerate() {
+            lines.push(format!("(type (;{};) (func{}))", index,
+                signature(ty)));
+        }
+        for (index, import) in self.imports.iter().enumerate() {
+            lines.push(format!("(import {:?} {:?} (func {}(type {})))",
+                import.module, import.name, id(&import.name, index),
+                import.ty));
+        }
+        let imports = self.imports.len();
+        for (index, function) in self.functions.iter().enumerate() {
+            let mut text = format!("(func {}(type {})",
+                id(&function.name, imports + index), function.ty);
+            if let Some(ty) = self.types.get(function.ty as usize) {
+                text.push_str(&signature(ty));
+            }
+            if !function.locals.is_empty() {
+                let _ = write!(text, "\n    (local{})",
+                    types(&function.locals));
+            }
+            let mut depth = 1;
+            for instr in &function.body {
+                if let Instr::Else | Instr::End = instr {
+                    depth -= 1;
+                }
+                let _ = write!(text, "\n{:width$}{}", "",
+                    self.instr(instr), width = 2 + 2 * depth);
+                if let Instr::Block | Instr::Loop | Instr::If | Instr::Else =
+                    instr
+                {
+                    depth += 1;
+                }
+            }
+            text.push(')');
+            lines.push(text);
+        }
+        if let Some(size) = self.table {
+            lines.push(format!("(table (;0;) {} {} funcref)", size, size));
+        }
+        if let Some(pages) = self.memory {
+            lines.push(format!("(memory (;0;) {})", pages));
+        }
+        for (index, global) in self.globals.iter().enumerate() {
+            let ty = match global.mutable {
+                true => format!("(mut {})", global.ty.as_str()),
+                false => global.ty.as_str().to_string(),
+            };
+            lines.push(format!("(global {}{} ({}))", id(&global.name, index),
+                ty, self.instr(&global.init)));
+        }
+        for export in &self.exports {
+            let (kind, reference) = match export.kind {
+                ExportKind::Func => ("func", self.function(export.index)),
+                ExportKind::Table => ("table", export.index.to_string()),
+                ExportKind::Memory => ("memory", export.index.to_string()),
+                ExportKind::Global => ("global", self.global(export.index)),
+            };
+            lines.push(format!("(export {:?} ({} {}))", export.name, kind,
+                reference));
+        }
+        for (index, element) in self.elements.iter().enumerate() {
+            let mut text = format!("(elem (;{};) (i32.const {}) func", index,
+                element.offset as i32);
+            for &function in &element.functions {
+                let _ = write!(text, " {}", self.function(function));
+            }
+            text.push(')');
+            lines.push(text);
+        }
+        for (index, data) in self.data.iter().enumerate() {
+            lines.push(format!("(data (;{};) (i32.const {}) \"{}\")", index,
+                data.offset as i32, escape(&data.bytes)));
+        }
+
+        write!(f, "(module")?;
+        for line in lines {
+            write!(f, "\n  {}", line)?;
+        }
+        writeln!(f, ")")
+    }
+}
+
+impl Module {
+    // A reference to a function.
+    fn function(&self, index: u32) -> String {
+        match self.function_name(index) {
+            Some(name) if is_id(name) => format!("${}", name),
+            _ => index.to_string(),
+        }
+    }
+
+    // A reference to a global.
+    fn global(&self, index: u32) -> String {
+        match self.globals.get(index as usize) {
+            Some(global) if is_id(&global.name) => format!("${}", global.name),
+            _ => index.to_string(),
+        }
+    }
+
+    fn instr(&self, instr: &Instr) -> String {
+        let name = instr.name();
+        match *instr {
+            Instr::Br(label) | Instr::BrIf(label) => {
+                format!("{} {}", name, label)
+            }
+            Instr::BrTable(ref labels, default) => {
+                let mut text = name.to_string();
+                for label in labels.iter().chain([&default]) {
+                    let _ = write!(text, " {}", label);
+                }
+                text
+            }
+            Instr::Call(function) => {
+                format!("{} {}", name, self.function(function))
+            }
+            Instr::CallIndirect(ty) => format!("{} (type {})", name, ty),
+            Instr::LocalGet(local)
+            | Instr::LocalSet(local)
+            | Instr::LocalTee(local) => format!("{} {}", name, local),
+            Instr::GlobalGet(global) | Instr::GlobalSet(global) => {
+                format!("{} {}", name, self.global(global))
+            }
+            Instr::Memory(opcode, memarg) => {
+                let mut text = name.to_string();
+                if memarg.offset != 0 {
+                    let _ = write!(text, " offset={}", memarg.offset);
+                }
+                let natural = MEMORY[usize::from(opcode - 0x28)].2;
+                if memarg.align != natural {
+                    let _ = write!(text, " align={}",
+                        1u64 << memarg.align.min(63));
+                }
+                text
+            }
+            Instr::I32Const(value) => format!("{} {}", name, value),
+            Instr::I64Const(value) => format!("{} {}", name, value),
+            Instr::F32Const(bits) => {
+                let value = f32::from_bits(bits);
+                let text = match value.is_nan() {
+                    true => nan(bits & 0x8000_0000 != 0,
+                        u64::from(bits & 0x7F_FFFF), 0x40_0000),
+                    false => float(value.is_infinite(),
+                        value.is_sign_negative(), format!("{:?}", value)),
+                };
+                format!("{} {}", name, text)
+            }
+            Instr::F64Const(bits) => {
+                let value = f64::from_bits(bits);
+                let text = match value.is_nan() {
+                    true => nan(bits >> 63 != 0, bits & 0xF_FFFF_FFFF_FFFF,
+                        0x8_0000_0000_0000),
+                    false => float(value.is_infinite(),
+                        value.is_sign_negative(), format!("{:?}", value)),
+                };
+                format!("{} {}", name, text)
+            }
+            _ => name.to_string(),
+        }
+    }
+}
+
+// The parameters and results of a function type.
+fn signature(ty: &FuncType) -> String {
+    let mut text = String::new();
+    if !ty.params.is_empty() {
+        let _ = write!(text, " (param{})", types(&ty.params));
+    }
+    if !ty.results.is_empty() {
+        let _ = write!(text, " (result{})", types(&ty.results));
+    }
+    text
+}
+
+fn types(types: &[ValType]) -> String {
+    types.iter().map(|ty| format!(" {}", ty.as_str())).collect()
+}
+
+// The name of something defined, followed by a space, or a comment with
+// its index if it has no usable name.
+fn id(name: &str, index: usize) -> String {
+    match is_id(name) {
+        true => format!("${} ", name),
+        false => format!("(;{};) ", index),
+    }
+}
+
+// Whether a name can be written as an identifier.
+fn is_id(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| {
+        c.is_ascii_alphanumeric() || "!#$%&'*+-./:<=>?@\\^_`|~".contains(c)
+    })
+}
+
+// A float that isn't NaN.
+fn float(infinite: bool, negative: bool, text: String) -> String {
+    match (infinite, negative) {
+        (true, true) => "-inf".to_string(),
+        (true, false) => "inf".to_string(),
+        _ => text,
+    }
+}
+
+// A NaN of a sign and payload.
+fn nan(negative: bool, payload: u64, canonical: u64) -> String {
+    let sign = match negative {
+        true => "-",
+        false => "",
+    };
+    match payload == canonical {
+        true => format!("{}nan", sign),
+        false => format!("{}nan:0x{:x}", sign, payload),
+    }
+}
+
+// Bytes as the contents of a string.
+fn escape(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for &byte in bytes {
+        match byte {
+            0x20..=0x7E if byte != b'"' && byte != b'\\' => {
+                text.push(byte as char);
+            }
+            _ => {
+                let _ = write!(text, "\\{:02x}", byte);
+            }
+        }
+    }
+    text
+}
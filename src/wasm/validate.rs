@@ -0,0 +1,393 @@
+// Validation
+//
+//! Checks that modules are valid, as the WebAssembly specification
+//! defines: every index refers to something of the right kind, and every
+//! function body type checks, with the algorithm of the appendix of the
+//! specification, tracking the types on the operand stack and the blocks
+//! around each instruction.  Element and data segments must also fit in
+//! the table and memory, which would otherwise fail instantiation.
+
+use std::collections::HashSet;
+
+use super::{
+    ExportKind, FuncType, Instr, Module, Result, ValType, MEMORY, NUMERIC,
+    STORE, TRUNC_SAT,
+};
+use crate::{Diagnostic, Span};
+
+// 4 GiB of 64 KiB pages
+const MAX_PAGES: u32 = 65536;
+const PAGE: u64 = 65536;
+
+fn error(message: String) -> Diagnostic {
+    Diagnostic::new(Span::default(), message)
+}
+
+/// Validate a module.
+pub fn validate(module: &Module) -> Result<()> {
+    let ty = |ty: u32, what: &str| {
+        module.types.get(ty as usize)
+            .ok_or_else(|| error(format!("{} has no type {}", what, ty)))
+    };
+    for import in &module.imports {
+        ty(import.ty, &format!("import `{}`", import.name))?;
+    }
+    for (index, function) in module.functions.iter().enumerate() {
+        ty(function.ty, &format!("function {}", index))?;
+    }
+    if module.memory.is_some_and(|pages| pages > MAX_PAGES) {
+        return Err(error("memory larger than 4 GiB".to_string()));
+    }
+    for (index, global) in module.globals.iter().enumerate() {
+        let init = match global.init {
+            Instr::I32Const(_) => ValType::I32,
+            Instr::I64Const(_) => ValType::I64,
+            Instr::F32Const(_) => ValType::F32,
+            Instr::F64Const(_) => ValType::F64,
+            _ => {
+                return Err(error(format!("global {} isn't initialized to a \
+                    constant", index)));
+            }
+        };
+        if init != global.ty {
+            return Err(error(format!("global {} is initialized to a value \
+                of another type", index)));
+        }
+    }
+
+    let functions = (module.imports.len() + module.functions.len()) as u32;
+    let mut names = HashSet::new();
+    for export in &module.exports {
+        if !names.insert(&export.name) {
+            return Err(error(format!("`{}` is exported twice", export.name)));
+        }
+        let exists = match export.kind {
+            ExportKind::Func => export.index < functions,
+            ExportKind::Table => module.table.is_some() && export.index == 0,
+            ExportKind::Memory => module.memory.is_some() && export.index == 0,
+            ExportKind::Global => {
+                (export.index as usize) < module.globals.len()
+            }
+        };
+        if !exists {
+            return Err(error(format!("`{}` exports nothing", export.name)));
+        }
+    }
+    for element in &module.elements {
+        let size = module.table
+            .ok_or_else(|| error("element segment without a table".into()))?;
+        let end = u64::from(element.offset) + element.functions.len() as u64;
+        if end > u64::from(size) {
+            return Err(error("element segment outside the table".to_string()));
+        }
+        if element.functions.iter().any(|&function| function >= functions) {
+            return Err(error("element segment of no function".to_string()));
+        }
+    }
+    for data in &module.data {
+        let pages = module.memory
+            .ok_or_else(|| error("data segment without a memory".into()))?;
+        let end = u64::from(data.offset) + data.bytes.len() as u64;
+        if end > u64::from(pages) * PAGE {
+            return Err(error("data segment outside the memory".to_string()));
+        }
+    }
+
+    for (index, function) in module.functions.iter().enumerate() {
+        let index = module.imports.len() + index;
+        let ty = &module.types[function.ty as usize];
+        let mut locals = ty.params.clone();
+        locals.extend(&function.locals);
+        let mut checker = Checker {
+            module,
+            locals,
+            values: Vec::new(),
+            controls: vec![Control {
+                kind: Kind::Function,
+                height: 0,
+                unreachable: false,
+            }],
+            results: &ty.results,
+        };
+        let end = std::iter::once(&Instr::End);
+        for (at, instr) in function.body.iter().chain(end).enumerate() {
+            checker.instr(instr).map_err(|message| {
+                let name = match function.name.is_empty() {
+                    true => index.to_string(),
+                    false => format!("`{}`", function.name),
+                };
+                error(format!("in function {}, instruction {}: {}", name, at,
+                    message))
+            })?;
+        }
+        if !checker.controls.is_empty() {
+            return Err(error(format!("function {} has unclosed blocks",
+                index)));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Block,
+    Loop,
+    If,
+    Else,
+    Function,
+}
+
+// A block being checked.
+struct Control {
+    kind: Kind,
+    // The height of the operand stack when it started
+    height: usize,
+    // Whether the rest of the block can't be reached, so any operands can
+    // be popped
+    unreachable: bool,
+}
+
+// The type checker of a function body.
+struct Checker<'a> {
+    module: &'a Module,
+    locals: Vec<ValType>,
+    // The operand stack, with `None` for values of any type
+    values: Vec<Option<ValType>>,
+    controls: Vec<Control>,
+    // The results of the function
+    results: &'a [ValType],
+}
+
+type Check<T> = std::result::Result<T, String>;
+
+impl<'a> Checker<'a> {
+    fn push(&mut self, ty: ValType) {
+        self.values.push(Some(ty));
+    }
+
+    fn pop(&mut self) -> Check<Option<ValType>> {
+        let control = self.controls.last().unwrap();
+        if self.values.len() == control.height {
+            return match control.unreachable {
+                true => Ok(None),
+                false => Err("operand stack underflow".to_string()),
+            };
+        }
+        Ok(self.values.pop().unwrap())
+    }
+
+    fn pop_type(&mut self, expected: ValType) -> Check<()> {
+        match self.pop()? {
+            Some(ty) if ty != expected => Err(format!("expected `{}`, found \
+                `{}`", expected.as_str(), ty.as_str())),
+            _ => Ok(()),
+        }
+    }
+
+    fn pop_types(&mut self, types: &[ValType]) -> Check<()> {
+        for &ty in types.iter().rev() {
+            self.pop_type(ty)?;
+        }
+        Ok(())
+    }
+
+    // The rest of the block can't be reached.
+    fn unreachable(&mut self) {
+        let control = self.controls.last_mut().unwrap();
+        self.values.truncate(control.height);
+        control.unreachable = true;
+    }
+
+    // The types a branch to a label passes.
+    fn label(&self, label: u32) -> Check<&'a [ValType]> {
+        let index = self.controls.len().checked_sub(label as usize + 1)
+            .ok_or_else(|| format!("no label {}", label))?;
+        Ok(match self.controls[index].kind {
+            Kind::Function => self.results,
+            _ => &[],
+        })
+    }
+
+    fn memory(&self) -> Check<()> {
+        match self.module.memory {
+            Some(_) => Ok(()),
+            None => Err("no memory".to_string()),
+        }
+    }
+
+    fn call(&mut self, ty: &FuncType) -> Check<()> {
+        self.pop_types(&ty.params)?;
+        for &result in &ty.results {
+            self.push(result);
+        }
+        Ok(())
+    }
+
+    fn local(&self, local: u32) -> Check<ValType> {
+        self.locals.get(local as usize).copied()
+            .ok_or_else(|| format!("no local {}", local))
+    }
+
+    fn instr(&mut self, instr: &Instr) -> Check<()> {
+        if self.controls.is_empty() {
+            return Err("instruction after the end of the function"
+                .to_string());
+        }
+        match *instr {
+            Instr::Unreachable => self.unreachable(),
+            Instr::Nop => {}
+            Instr::Block | Instr::Loop | Instr::If => {
+                if *instr == Instr::If {
+                    self.pop_type(ValType::I32)?;
+                }
+                let kind = match instr {
+                    Instr::Block => Kind::Block,
+                    Instr::Loop => Kind::Loop,
+                    _ => Kind::If,
+                };
+                self.controls.push(Control {
+                    kind,
+                    height: self.values.len(),
+                    unreachable: false,
+                });
+            }
+            Instr::Else | Instr::End => {
+                let results = match self.controls.last().unwrap().kind {
+                    Kind::Function => self.results,
+                    _ => &[],
+                };
+                self.pop_types(results)?;
+                let control = self.controls.pop().unwrap();
+                if self.values.len() != control.height {
+                    return Err("values left at the end of a block"
+                        .to_string());
+                }
+                if *instr == Instr::Else {
+                    if control.kind != Kind::If {
+                        return Err("`else` outside an `if`".to_string());
+                    }
+                    self.controls.push(Control {
+                        kind: Kind::Else,
+                        height: self.values.len(),
+                        unreachable: false,
+                    });
+                }
+            }
+            Instr::Br(label) => {
+                let types = self.label(label)?;
+                self.pop_types(types)?;
+                self.unreachable();
+            }
+            Instr::BrIf(label) => {
+                self.pop_type(ValType::I32)?;
+                let types = self.label(label)?;
+                self.pop_types(types)?;
+                for &ty in types {
+                    self.push(ty);
+                }
+            }
+            Instr::BrTable(ref labels, default) => {
+                self.pop_type(ValType::I32)?;
+                let types = self.label(default)?;
+                for &label in labels {
+                    if self.label(label)?.len() != types.len() {
+                        return Err("`br_table` to labels of different types"
+                            .to_string());
+                    }
+                }
+                self.pop_types(types)?;
+                self.unreachable();
+            }
+            Instr::Return => {
+                self.pop_types(self.results)?;
+                self.unreachable();
+            }
+            Instr::Call(function) => {
+                let ty = self.module.function_type(function)
+                    .ok_or_else(|| format!("no function {}", function))?;
+                self.call(ty)?;
+            }
+            Instr::CallIndirect(ty) => {
+                if self.module.table.is_none() {
+                    return Err("no table".to_string());
+                }
+                let ty = self.module.types.get(ty as usize)
+                    .ok_or_else(|| format!("no type {}", ty))?;
+                self.pop_type(ValType::I32)?;
+                self.call(ty)?;
+            }
+            Instr::Drop => {
+                self.pop()?;
+            }
+            Instr::Select => {
+                self.pop_type(ValType::I32)?;
+                let a = self.pop()?;
+                let b = self.pop()?;
+                match (a, b) {
+                    (Some(a), Some(b)) if a != b => {
+                        return Err("`select` of values of different types"
+                            .to_string());
+                    }
+                    _ => self.values.push(a.or(b)),
+                }
+            }
+            Instr::LocalGet(local) => {
+                let ty = self.local(local)?;
+                self.push(ty);
+            }
+            Instr::LocalSet(local) => {
+                let ty = self.local(local)?;
+                self.pop_type(ty)?;
+            }
+            Instr::LocalTee(local) => {
+                let ty = self.local(local)?;
+                self.pop_type(ty)?;
+                self.push(ty);
+            }
+            Instr::GlobalGet(global) | Instr::GlobalSet(global) => {
+                let global_ty = self.module.globals.get(global as usize)
+                    .ok_or_else(|| format!("no global {}", global))?;
+                match *instr {
+                    Instr::GlobalGet(_) => self.push(global_ty.ty),
+                    _ if !global_ty.mutable => {
+                        return Err(format!("global {} is immutable", global));
+                    }
+                    _ => self.pop_type(global_ty.ty)?,
+                }
+            }
+            Instr::Memory(opcode, memarg) => {
+                self.memory()?;
+                let (_, ty, natural) = MEMORY[usize::from(opcode - 0x28)];
+                if memarg.align > natural {
+                    return Err("alignment larger than natural".to_string());
+                }
+                if opcode >= STORE {
+                    self.pop_type(ty)?;
+                    self.pop_type(ValType::I32)?;
+                } else {
+                    self.pop_type(ValType::I32)?;
+                    self.push(ty);
+                }
+            }
+            Instr::I32Const(_) => self.push(ValType::I32),
+            Instr::I64Const(_) => self.push(ValType::I64),
+            Instr::F32Const(_) => self.push(ValType::F32),
+            Instr::F64Const(_) => self.push(ValType::F64),
+            Instr::Numeric(opcode) => {
+                let (_, params, result) = NUMERIC[usize::from(opcode - 0x45)];
+                self.pop_types(params)?;
+                self.push(result);
+            }
+            Instr::TruncSat(op) => {
+                let (_, from, to) = TRUNC_SAT[usize::from(op)];
+                self.pop_type(from)?;
+                self.push(to);
+            }
+            Instr::MemoryCopy | Instr::MemoryFill => {
+                self.memory()?;
+                self.pop_types(&[ValType::I32; 3])?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,499 @@
+// x86-64
+//
+//! Generates x86-64 machine code from IR modules, as ELF64 relocatable
+//! objects for the system linker, or as GNU assembly.
+//!
+//! Each function is translated in four steps:
+//!
+//! 1. Instruction selection turns the IR into machine instructions on
+//!    virtual registers, with the values of `phi`s copied on the edges into
+//!    their blocks and calls following the System V calling convention.
+//! 2. Linear-scan register allocation assigns a register to each virtual
+//!    register, or a stack slot when there are too few.
+//! 3. The stack frame is laid out: the stack slots, the callee-saved
+//!    registers used, and the arguments of calls passed on the stack.
+//! 4. The instructions are encoded, with relocations for the symbols they
+//!    refer to, or printed.
+//!
+//! Integers up to 64 bits, pointers and floats are supported, and
+//! aggregates, which are kept in stack slots and passed and returned as
+//! the ABI says, as are the `va_list`s of variadic functions.  Only the low
+//! bits of an integer narrower than 32 bits are meaningful in a register,
+//! and an `i1` is 0 or 1 in its low byte.
+//!
+//! Every symbol is global but those with a `.` in their name, which the
+//! front ends only use for what they make up, like string literals, so
+//! objects can be linked together.  Functions go in `.text`, constants in
+//! `.rodata` unless they hold addresses, other initialized variables in
+//! `.data`, and those all zero in `.bss`.  Code refers to symbols defined
+//! in the module relative to the instruction pointer, and to the others
+//! through the global offset table, so it can be linked into position
+//! independent executables.
+
+mod abi;
+mod asm;
+mod elf;
+mod encode;
+mod frame;
+mod isel;
+mod regalloc;
+
+use crate::ir::{BlockId, Global, Module, Type, Value};
+use crate::{Diagnostic, Span};
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+/// Translate a module to the bytes of an ELF64 relocatable object.
+pub fn object(module: &Module) -> Result<Vec<u8>> {
+    let functions = compile(module)?;
+    Ok(elf::write(module, &functions))
+}
+
+/// Translate a module to GNU assembly, in Intel syntax.
+pub fn assembly(module: &Module) -> Result<String> {
+    let functions = compile(module)?;
+    Ok(asm::print(module, &functions))
+}
+
+// The machine code of each function with a body.
+fn compile(module: &Module) -> Result<Vec<MachineFunction>> {
+    let mut functions = Vec::new();
+    for function in &module.functions {
+        if function.is_declaration() {
+            continue;
+        }
+        let mut machine = isel::select(module, function)?;
+        regalloc::allocate(&mut machine);
+        frame::lay_out(&mut machine);
+        remove_fallthroughs(&mut machine);
+        functions.push(machine);
+    }
+    Ok(functions)
+}
+
+// Remove the jumps to the block right after theirs.
+fn remove_fallthroughs(function: &mut MachineFunction) {
+    for (block, insns) in function.blocks.iter_mut().enumerate() {
+        let next = block + 1;
+        let len = insns.len();
+        if len >= 2 {
+            if let (Insn::Jcc(cond, then), Insn::Jmp(else_)) =
+                (&insns[len - 2], &insns[len - 1])
+            {
+                if *then == next {
+                    let (cond, else_) = (cond.invert(), *else_);
+                    insns.truncate(len - 2);
+                    insns.extend([Insn::Jcc(cond, else_), Insn::Jmp(next)]);
+                }
+            }
+        }
+        if insns.last() == Some(&Insn::Jmp(next)) {
+            insns.pop();
+        }
+    }
+}
+
+// The initial values of the global variables defined by a module, and the
+// sections they go in.
+fn data(module: &Module) -> Vec<(&Global, Data, Section)> {
+    let globals = module.globals.iter().filter_map(|global| {
+        let data = Data::new(&global.ty, global.init.as_ref()?);
+        let section = Section::of(global.constant, &data);
+        Some((global, data, section))
+    });
+    globals.collect()
+}
+
+// Whether a symbol is only visible in the object.
+fn is_local(name: &str) -> bool {
+    name.contains('.')
+}
+
+// Whether a symbol is defined by the module.
+fn is_defined(module: &Module, name: &str) -> bool {
+    match module.function(name) {
+        Some(function) => !function.is_declaration(),
+        None => module.global(name).is_some_and(|global| global.init.is_some()),
+    }
+}
+
+// The error for what can't be translated.
+fn unsupported(function: &str, what: &str) -> Diagnostic {
+    Diagnostic::new(Span::default(), format!("in `{}`: {} isn't supported \
+        by the x86-64 back end", crate::ir::symbol(function), what))
+}
+
+/// A general-purpose register, numbered as in the encoding
+pub(crate) mod gp {
+    pub const RAX: u8 = 0;
+    pub const RCX: u8 = 1;
+    pub const RDX: u8 = 2;
+    pub const RBX: u8 = 3;
+    pub const RSP: u8 = 4;
+    pub const RBP: u8 = 5;
+    pub const RSI: u8 = 6;
+    pub const RDI: u8 = 7;
+    pub const R8: u8 = 8;
+    pub const R9: u8 = 9;
+    pub const R10: u8 = 10;
+    pub const R11: u8 = 11;
+    pub const R12: u8 = 12;
+    pub const R13: u8 = 13;
+    pub const R14: u8 = 14;
+    pub const R15: u8 = 15;
+}
+
+// The registers a call may change.
+const CALLER_SAVED: &[u8] = &[
+    gp::RAX, gp::RCX, gp::RDX, gp::RSI, gp::RDI, gp::R8, gp::R9, gp::R10,
+    gp::R11,
+];
+// The registers a function must restore, but for `rbp`.
+const CALLEE_SAVED: &[u8] = &[gp::RBX, gp::R12, gp::R13, gp::R14, gp::R15];
+
+// A register: physical, or virtual before register allocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Reg {
+    Gp(u8),
+    Xmm(u8),
+    Virt(usize),
+}
+
+// Whether a virtual register holds an integer or a float.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Gp,
+    Xmm,
+}
+
+// The size of an operand.  For floats, `D` is an `f32` and `Q` an `f64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Size {
+    B,
+    W,
+    D,
+    Q,
+}
+
+impl Size {
+    // The size of a value of a scalar type in a register.
+    fn of(ty: &Type) -> Size {
+        match ty {
+            Type::Int(1) | Type::Int(8) => Size::B,
+            Type::Int(16) => Size::W,
+            Type::Int(32) | Type::F32 => Size::D,
+            _ => Size::Q,
+        }
+    }
+
+    fn len(self) -> u64 {
+        match self {
+            Size::B => 1,
+            Size::W => 2,
+            Size::D => 4,
+            Size::Q => 8,
+        }
+    }
+}
+
+// A condition of the flags, numbered as in the encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cond {
+    B = 2,
+    Ae = 3,
+    E = 4,
+    Ne = 5,
+    Be = 6,
+    A = 7,
+    S = 8,
+    Ns = 9,
+    P = 10,
+    Np = 11,
+    L = 12,
+    Ge = 13,
+    Le = 14,
+    G = 15,
+}
+
+impl Cond {
+    // The condition that holds when this one doesn't.
+    fn invert(self) -> Cond {
+        match self {
+            Cond::B => Cond::Ae,
+            Cond::Ae => Cond::B,
+            Cond::E => Cond::Ne,
+            Cond::Ne => Cond::E,
+            Cond::Be => Cond::A,
+            Cond::A => Cond::Be,
+            Cond::S => Cond::Ns,
+            Cond::Ns => Cond::S,
+            Cond::P => Cond::Np,
+            Cond::Np => Cond::P,
+            Cond::L => Cond::Ge,
+            Cond::Ge => Cond::L,
+            Cond::Le => Cond::G,
+            Cond::G => Cond::Le,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Cond::B => "b",
+            Cond::Ae => "ae",
+            Cond::E => "e",
+            Cond::Ne => "ne",
+            Cond::Be => "be",
+            Cond::A => "a",
+            Cond::S => "s",
+            Cond::Ns => "ns",
+            Cond::P => "p",
+            Cond::Np => "np",
+            Cond::L => "l",
+            Cond::Ge => "ge",
+            Cond::Le => "le",
+            Cond::G => "g",
+        }
+    }
+}
+
+// Where an address is relative to.
+#[derive(Debug, Clone, PartialEq)]
+enum Base {
+    Reg(Reg),
+    // A stack slot of the function
+    Slot(usize),
+    // The arguments passed on the stack to the function
+    Incoming,
+    // The arguments passed on the stack to the functions it calls
+    Outgoing,
+    // A symbol, relative to the instruction pointer
+    Symbol(String),
+    // The entry of a symbol in the global offset table
+    Got(String),
+}
+
+// An address.
+#[derive(Debug, Clone, PartialEq)]
+struct Mem {
+    base: Base,
+    disp: i32,
+}
+
+impl Mem {
+    fn new(base: Base) -> Mem {
+        Mem { base, disp: 0 }
+    }
+
+    fn reg(reg: Reg) -> Mem {
+        Mem::new(Base::Reg(reg))
+    }
+
+    // The address `offset` bytes further.
+    fn offset(&self, offset: i64) -> Mem {
+        Mem { base: self.base.clone(), disp: self.disp + offset as i32 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Reg(Reg),
+    Mem(Mem),
+    // An immediate, which fits in 32 bits sign-extended
+    Imm(i64),
+}
+
+// A two-operand arithmetic or logic instruction, numbered as its
+// encoding's `/digit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AluOp {
+    Add = 0,
+    Or = 1,
+    And = 4,
+    Sub = 5,
+    Xor = 6,
+    Cmp = 7,
+}
+
+// A shift, numbered as its encoding's `/digit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShiftOp {
+    Shl = 4,
+    Shr = 5,
+    Sar = 7,
+}
+
+// An arithmetic instruction on floats, numbered as its opcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FloatOp {
+    Add = 0x58,
+    Mul = 0x59,
+    Sub = 0x5C,
+    Div = 0x5E,
+}
+
+// A function a call goes to.
+#[derive(Debug, Clone, PartialEq)]
+enum Callee {
+    Symbol(String),
+    Reg(Reg),
+}
+
+// A machine instruction.  Operands are in Intel order, destination first.
+#[derive(Debug, Clone, PartialEq)]
+enum Insn {
+    // Copy, at most one operand in memory
+    Mov(Size, Operand, Operand),
+    // Load of a 64-bit immediate
+    MovAbs(Reg, i64),
+    // Zero or sign extension from the first size to the second
+    MovZx(Size, Size, Reg, Operand),
+    MovSx(Size, Size, Reg, Operand),
+    Lea(Reg, Mem),
+    Alu(AluOp, Size, Operand, Operand),
+    Test(Size, Reg, Reg),
+    Imul(Size, Reg, Operand),
+    // Shift by an immediate, or by `cl`
+    Shift(ShiftOp, Size, Reg, Option<u8>),
+    Neg(Size, Reg),
+    // Sign extension of `eax` or `rax` into `edx` or `rdx`
+    SignExtend(Size),
+    // Division of `rdx:rax`, signed or not
+    Div(bool, Size, Reg),
+    Setcc(Cond, Reg),
+    Cmov(Cond, Size, Reg, Reg),
+    // Copy of a float, at most one operand in memory
+    MovF(Size, Operand, Operand),
+    // Copy of a whole vector register
+    MovAps(Reg, Reg),
+    FloatOp(FloatOp, Size, Reg, Operand),
+    // Comparison of floats, setting the flags as an unsigned comparison
+    // would, and the parity flag if they are unordered
+    Ucomi(Size, Reg, Reg),
+    Xorps(Reg, Reg),
+    // Integer of a size to a float of a size
+    IntToFloat(Size, Size, Reg, Reg),
+    // Float of a size to an integer of a size, rounding towards zero
+    FloatToInt(Size, Size, Reg, Reg),
+    // Float to the other size of float
+    FloatToFloat(Size, Reg, Reg),
+    // Copy of the bits of a register of the other class, of a size
+    MovGpXmm(Size, Reg, Reg),
+    // Copy of `rcx` bytes from `rsi` to `rdi`
+    RepMovsb,
+    // Fill of `rcx` bytes at `rdi` with `al`
+    RepStosb,
+    Jmp(BlockId),
+    Jcc(Cond, BlockId),
+    // Call, using the registers arguments are passed in
+    Call(Callee, Vec<Reg>),
+    // Return from the function, using the registers of the value returned
+    Return(Vec<Reg>),
+    Push(Reg),
+    Pop(Reg),
+    Ret,
+    Ud2,
+}
+
+// A stack slot: its size and alignment in bytes.
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    size: u64,
+    align: u64,
+}
+
+// A function in machine instructions.
+#[derive(Debug, Clone)]
+struct MachineFunction {
+    name: String,
+    blocks: Vec<Vec<Insn>>,
+    // The class of each virtual register.
+    vregs: Vec<Class>,
+    slots: Vec<Slot>,
+    // The bytes of stack the arguments of calls need.
+    outgoing: u64,
+}
+
+impl MachineFunction {
+    fn vreg(&mut self, class: Class) -> Reg {
+        self.vregs.push(class);
+        Reg::Virt(self.vregs.len() - 1)
+    }
+
+    fn slot(&mut self, size: u64, align: u64) -> usize {
+        self.slots.push(Slot { size, align });
+        self.slots.len() - 1
+    }
+}
+
+// The bytes of a global's initial value, and the symbols whose addresses
+// go in them: their offsets, names and addends.
+struct Data {
+    bytes: Vec<u8>,
+    relocations: Vec<(u64, String, i64)>,
+}
+
+impl Data {
+    fn new(ty: &Type, value: &Value) -> Data {
+        let mut data = Data {
+            bytes: vec![0; ty.size() as usize],
+            relocations: Vec::new(),
+        };
+        data.put(0, ty, value);
+        data
+    }
+
+    fn put(&mut self, offset: u64, ty: &Type, value: &Value) {
+        let at = offset as usize;
+        match value {
+            Value::Int(_, value) => {
+                let len = ty.size() as usize;
+                let bytes = value.to_le_bytes();
+                self.bytes[at..at + len].copy_from_slice(&bytes[..len]);
+            }
+            Value::Float(Type::F32, bits) => {
+                let value = f64::from_bits(*bits) as f32;
+                self.bytes[at..at + 4].copy_from_slice(&value.to_le_bytes());
+            }
+            Value::Float(_, bits) => {
+                self.bytes[at..at + 8].copy_from_slice(&bits.to_le_bytes());
+            }
+            Value::Aggregate(_, members) => {
+                for (index, member) in members.iter().enumerate() {
+                    let member_ty = ty.member(index as u32).unwrap();
+                    self.put(offset + ty.offset(index), member_ty, member);
+                }
+            }
+            Value::Bytes(bytes) => {
+                self.bytes[at..at + bytes.len()].copy_from_slice(bytes);
+            }
+            Value::Global(name) => {
+                self.relocations.push((offset, name.clone(), 0));
+            }
+            _ => {}
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.relocations.is_empty() && self.bytes.iter().all(|&b| b == 0)
+    }
+}
+
+// The section a global goes in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Section {
+    Data,
+    Rodata,
+    Bss,
+}
+
+impl Section {
+    fn of(constant: bool, data: &Data) -> Section {
+        if constant && data.relocations.is_empty() {
+            Section::Rodata
+        } else if !constant && data.is_zero() {
+            Section::Bss
+        } else {
+            Section::Data
+        }
+    }
+}
@@ -0,0 +1,155 @@
+// System V calling convention
+//
+//! Where the arguments of a call and the value it returns are passed.
+//!
+//! Each argument is split into eightbytes, classified as integers, which
+//! go in `rdi`, `rsi`, `rdx`, `rcx`, `r8` and `r9`, or floats, which go in
+//! `xmm0` to `xmm7`.  An eightbyte of an aggregate is an integer if any of
+//! what it holds is.  Aggregates bigger than 16 bytes, and arguments that
+//! don't fit in the registers left, are passed on the stack, each at a
+//! multiple of 8 bytes.  Values are returned in `rax` and `rdx`, or `xmm0`
+//! and `xmm1`, or, if they would be passed on the stack, in memory the
+//! caller passes the address of in `rdi`, which is returned in `rax`.
+
+use super::{gp, Reg};
+use crate::ir::Type;
+
+pub(super) const INT_ARGS: &[u8] =
+    &[gp::RDI, gp::RSI, gp::RDX, gp::RCX, gp::R8, gp::R9];
+pub(super) const FLOAT_ARGS: u8 = 8;
+const INT_RETURNS: &[u8] = &[gp::RAX, gp::RDX];
+
+/// The class of an eightbyte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Class {
+    Int,
+    Float,
+}
+
+/// Where a value is passed.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Location {
+    /// In a register for each eightbyte, in order.
+    Regs(Vec<Reg>),
+    /// On the stack, at an offset from the first argument there.
+    Stack(u64),
+}
+
+/// Where the arguments of a call and its value are.
+#[derive(Debug, Clone)]
+pub(super) struct Call {
+    pub args: Vec<Location>,
+    /// `None` if the value is returned in memory, or if there is none.
+    pub ret: Option<Vec<Reg>>,
+    /// Whether the address of the memory for the value is passed.
+    pub sret: bool,
+    /// The bytes of stack the arguments take.
+    pub stack: u64,
+    /// The number of integer registers used, and of float registers.
+    pub ints: u8,
+    pub floats: u8,
+}
+
+/// Place the arguments and the value of a call.
+pub(super) fn call(params: &[Type], ret: &Type) -> Call {
+    let (ret, sret) = match classify(ret) {
+        _ if *ret == Type::Void => (None, false),
+        Some(classes) => {
+            let (mut ints, mut floats) = (0, 0);
+            let regs = classes.iter().map(|class| match class {
+                Class::Int => {
+                    ints += 1;
+                    Reg::Gp(INT_RETURNS[ints - 1])
+                }
+                Class::Float => {
+                    floats += 1;
+                    Reg::Xmm(floats - 1)
+                }
+            });
+            (Some(regs.collect()), false)
+        }
+        None => (None, true),
+    };
+
+    let mut ints = sret as usize;
+    let mut floats = 0;
+    let mut stack = 0;
+    let mut args = Vec::new();
+    for param in params {
+        let classes = classify(param).filter(|classes| {
+            let int = classes.iter().filter(|&&c| c == Class::Int).count();
+            let float = classes.len() - int;
+            ints + int <= INT_ARGS.len()
+                && floats + float <= FLOAT_ARGS as usize
+        });
+        match classes {
+            Some(classes) => {
+                let regs = classes.iter().map(|class| match class {
+                    Class::Int => {
+                        ints += 1;
+                        Reg::Gp(INT_ARGS[ints - 1])
+                    }
+                    Class::Float => {
+                        floats += 1;
+                        Reg::Xmm(floats as u8 - 1)
+                    }
+                });
+                args.push(Location::Regs(regs.collect()));
+            }
+            None => {
+                stack = align(stack, param.align().max(8));
+                args.push(Location::Stack(stack));
+                stack += align(param.size(), 8);
+            }
+        }
+    }
+    Call { args, ret, sret, stack, ints: ints as u8, floats: floats as u8 }
+}
+
+/// The classes of the eightbytes of a value, or `None` if it is passed in
+/// memory.
+pub(super) fn classify(ty: &Type) -> Option<Vec<Class>> {
+    let size = ty.size();
+    if size > 16 {
+        return None;
+    }
+    let mut classes = vec![None; size.div_ceil(8) as usize];
+    leaves(ty, 0, &mut |offset, leaf| {
+        let class = match leaf.is_float() {
+            true => Class::Float,
+            false => Class::Int,
+        };
+        let first = (offset / 8) as usize;
+        let last = ((offset + leaf.size().max(1) - 1) / 8) as usize;
+        for eightbyte in &mut classes[first..=last] {
+            if *eightbyte != Some(Class::Int) {
+                *eightbyte = Some(class);
+            }
+        }
+    });
+    Some(classes.into_iter()
+        .map(|class| class.unwrap_or(Class::Float))
+        .collect())
+}
+
+// Call `f` with the offset and type of each scalar in a value.
+fn leaves(ty: &Type, offset: u64, f: &mut dyn FnMut(u64, &Type)) {
+    match ty {
+        Type::Array(member, len) => {
+            for index in 0..*len {
+                leaves(member, offset + member.size() * index, f);
+            }
+        }
+        Type::Struct(members) => {
+            for (index, member) in members.iter().enumerate() {
+                leaves(member, offset + ty.offset(index), f);
+            }
+        }
+        Type::Void => {}
+        _ => f(offset, ty),
+    }
+}
+
+fn align(offset: u64, align: u64) -> u64 {
+    offset.div_ceil(align) * align
+}
@@ -0,0 +1,314 @@
+// Assembly printer
+//
+//! Prints machine code as GNU assembly in Intel syntax, which assembles to
+//! the same bytes [`encode`](super::encode) gives.
+
+use std::fmt::Write;
+
+use super::{
+    data, is_local, AluOp, Base, Callee, FloatOp, Insn, MachineFunction,
+    Mem, Operand, Reg, Section, ShiftOp, Size,
+};
+use crate::ir::Module;
+
+/// Print the functions and global variables of a module.
+pub(super) fn print(module: &Module, functions: &[MachineFunction])
+    -> String
+{
+    let mut out = String::new();
+    out.push_str("\t.intel_syntax noprefix\n");
+    if !functions.is_empty() {
+        out.push_str("\t.text\n");
+    }
+    for (index, function) in functions.iter().enumerate() {
+        let name = symbol(&function.name);
+        if !is_local(&function.name) {
+            writeln!(out, "\t.globl\t{}", name).unwrap();
+        }
+        writeln!(out, "\t.type\t{}, @function", name).unwrap();
+        writeln!(out, "{}:", name).unwrap();
+        for (block, insns) in function.blocks.iter().enumerate() {
+            if block != 0 {
+                writeln!(out, "{}:", label(index, block)).unwrap();
+            }
+            for insn in insns {
+                writeln!(out, "\t{}", self::insn(index, insn)).unwrap();
+            }
+        }
+        writeln!(out, "\t.size\t{}, .-{}", name, name).unwrap();
+    }
+
+    let mut current = None;
+    for (global, data, section) in data(module) {
+        if current != Some(section) {
+            out.push_str(match section {
+                Section::Data => "\t.data\n",
+                Section::Rodata => "\t.section\t.rodata\n",
+                Section::Bss => "\t.bss\n",
+            });
+            current = Some(section);
+        }
+        let name = symbol(&global.name);
+        if !is_local(&global.name) {
+            writeln!(out, "\t.globl\t{}", name).unwrap();
+        }
+        writeln!(out, "\t.type\t{}, @object", name).unwrap();
+        writeln!(out, "\t.balign\t{}", global.ty.align()).unwrap();
+        writeln!(out, "{}:", name).unwrap();
+        if section == Section::Bss {
+            writeln!(out, "\t.zero\t{}", data.bytes.len()).unwrap();
+        } else {
+            let mut at = 0;
+            for (offset, symbol, addend) in &data.relocations {
+                bytes(&mut out, &data.bytes[at..*offset as usize]);
+                writeln!(out, "\t.quad\t{}{}", self::symbol(symbol),
+                    addend_str(*addend)).unwrap();
+                at = *offset as usize + 8;
+            }
+            bytes(&mut out, &data.bytes[at..]);
+        }
+        writeln!(out, "\t.size\t{}, {}", name, data.bytes.len()).unwrap();
+    }
+    out.push_str("\t.section\t.note.GNU-stack,\"\",@progbits\n");
+    out
+}
+
+// Print bytes as `.byte` directives.
+fn bytes(out: &mut String, bytes: &[u8]) {
+    for chunk in bytes.chunks(16) {
+        let chunk: Vec<_> = chunk.iter().map(u8::to_string).collect();
+        writeln!(out, "\t.byte\t{}", chunk.join(", ")).unwrap();
+    }
+}
+
+// A symbol's name, quoted unless the assembler would take it as it is.
+fn symbol(name: &str) -> String {
+    let plain = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'
+        || c == '.' || c == '$');
+    match plain && !name.starts_with(|c: char| c.is_ascii_digit()) {
+        true => name.to_string(),
+        false => format!("{:?}", name),
+    }
+}
+
+fn addend_str(addend: i64) -> String {
+    match addend {
+        0 => String::new(),
+        addend => format!("{:+}", addend),
+    }
+}
+
+fn label(function: usize, block: usize) -> String {
+    format!(".L{}_{}", function, block)
+}
+
+fn reg(reg: Reg, size: Size) -> String {
+    const NAMES: [&str; 8] = ["ax", "cx", "dx", "bx", "sp", "bp", "si", "di"];
+    match reg {
+        Reg::Gp(num @ 8..=15) => {
+            let suffix = match size {
+                Size::B => "b",
+                Size::W => "w",
+                Size::D => "d",
+                Size::Q => "",
+            };
+            format!("r{}{}", num, suffix)
+        }
+        Reg::Gp(num) => {
+            let name = NAMES[num as usize];
+            match size {
+                Size::B if num < 4 => format!("{}l", &name[..1]),
+                Size::B => format!("{}l", name),
+                Size::W => name.to_string(),
+                Size::D => format!("e{}", name),
+                Size::Q => format!("r{}", name),
+            }
+        }
+        Reg::Xmm(num) => format!("xmm{}", num),
+        Reg::Virt(num) => format!("%{}", num),
+    }
+}
+
+fn ptr(size: Size) -> &'static str {
+    match size {
+        Size::B => "byte ptr ",
+        Size::W => "word ptr ",
+        Size::D => "dword ptr ",
+        Size::Q => "qword ptr ",
+    }
+}
+
+fn mem(mem: &Mem) -> String {
+    let disp = |disp: i32| match disp {
+        0 => String::new(),
+        disp if disp < 0 => format!(" - {}", -i64::from(disp)),
+        disp => format!(" + {}", disp),
+    };
+    match mem.base {
+        Base::Reg(base) => format!("[{}{}]", reg(base, Size::Q), disp(mem.disp)),
+        Base::Symbol(ref name) => {
+            format!("[rip + {}{}]", symbol(name), addend_str(mem.disp.into()))
+        }
+        Base::Got(ref name) => format!("[rip + {}@GOTPCREL]", symbol(name)),
+        Base::Slot(slot) => format!("[slot{}{}]", slot, disp(mem.disp)),
+        Base::Incoming => format!("[incoming{}]", disp(mem.disp)),
+        Base::Outgoing => format!("[outgoing{}]", disp(mem.disp)),
+    }
+}
+
+// An operand of a size, with the size spelled out for memory.
+fn operand(operand: &Operand, size: Size) -> String {
+    match operand {
+        Operand::Reg(r) => reg(*r, size),
+        Operand::Mem(m) => format!("{}{}", ptr(size), mem(m)),
+        Operand::Imm(imm) => imm.to_string(),
+    }
+}
+
+// A float operand of a size.
+fn float(operand: &Operand, size: Size) -> String {
+    match operand {
+        Operand::Reg(r) => reg(*r, size),
+        operand => self::operand(operand, size),
+    }
+}
+
+// The suffix of a scalar SSE instruction on floats of a size.
+fn float_suffix(size: Size) -> &'static str {
+    match size {
+        Size::D => "ss",
+        _ => "sd",
+    }
+}
+
+fn insn(function: usize, insn: &Insn) -> String {
+    match *insn {
+        Insn::Mov(size, ref dst, ref src) => {
+            format!("mov {}, {}", operand(dst, size), operand(src, size))
+        }
+        Insn::MovAbs(dst, value) => {
+            format!("movabs {}, {}", reg(dst, Size::Q), value)
+        }
+        Insn::MovZx(from, to, dst, ref src) => {
+            format!("movzx {}, {}", reg(dst, to), operand(src, from))
+        }
+        Insn::MovSx(from, to, dst, ref src) => {
+            let name = match from {
+                Size::D => "movsxd",
+                _ => "movsx",
+            };
+            format!("{} {}, {}", name, reg(dst, to), operand(src, from))
+        }
+        Insn::Lea(dst, ref src) => {
+            format!("lea {}, {}", reg(dst, Size::Q), mem(src))
+        }
+        Insn::Alu(op, size, ref dst, ref src) => {
+            let name = match op {
+                AluOp::Add => "add",
+                AluOp::Or => "or",
+                AluOp::And => "and",
+                AluOp::Sub => "sub",
+                AluOp::Xor => "xor",
+                AluOp::Cmp => "cmp",
+            };
+            format!("{} {}, {}", name, operand(dst, size), operand(src, size))
+        }
+        Insn::Test(size, a, b) => {
+            format!("test {}, {}", reg(a, size), reg(b, size))
+        }
+        Insn::Imul(size, dst, ref src) => {
+            format!("imul {}, {}", reg(dst, size), operand(src, size))
+        }
+        Insn::Shift(op, size, dst, amount) => {
+            let name = match op {
+                ShiftOp::Shl => "shl",
+                ShiftOp::Shr => "shr",
+                ShiftOp::Sar => "sar",
+            };
+            let amount = match amount {
+                Some(amount) => amount.to_string(),
+                None => "cl".to_string(),
+            };
+            format!("{} {}, {}", name, reg(dst, size), amount)
+        }
+        Insn::Neg(size, dst) => format!("neg {}", reg(dst, size)),
+        Insn::SignExtend(Size::Q) => "cqo".to_string(),
+        Insn::SignExtend(_) => "cdq".to_string(),
+        Insn::Div(signed, size, divisor) => {
+            let name = match signed {
+                true => "idiv",
+                false => "div",
+            };
+            format!("{} {}", name, reg(divisor, size))
+        }
+        Insn::Setcc(cond, dst) => {
+            format!("set{} {}", cond.as_str(), reg(dst, Size::B))
+        }
+        Insn::Cmov(cond, size, dst, src) => {
+            format!("cmov{} {}, {}", cond.as_str(), reg(dst, size),
+                reg(src, size))
+        }
+        Insn::MovF(size, ref dst, ref src) => {
+            format!("mov{} {}, {}", float_suffix(size), float(dst, size),
+                float(src, size))
+        }
+        Insn::MovAps(dst, src) => {
+            format!("movaps {}, {}", reg(dst, Size::Q), reg(src, Size::Q))
+        }
+        Insn::FloatOp(op, size, dst, ref src) => {
+            let name = match op {
+                FloatOp::Add => "add",
+                FloatOp::Mul => "mul",
+                FloatOp::Sub => "sub",
+                FloatOp::Div => "div",
+            };
+            format!("{}{} {}, {}", name, float_suffix(size), reg(dst, size),
+                float(src, size))
+        }
+        Insn::Ucomi(size, a, b) => {
+            format!("ucomi{} {}, {}", float_suffix(size), reg(a, size),
+                reg(b, size))
+        }
+        Insn::Xorps(dst, src) => {
+            format!("xorps {}, {}", reg(dst, Size::Q), reg(src, Size::Q))
+        }
+        Insn::IntToFloat(int, size, dst, src) => {
+            format!("cvtsi2{} {}, {}", float_suffix(size),
+                reg(dst, size), reg(src, int))
+        }
+        Insn::FloatToInt(size, int, dst, src) => {
+            format!("cvtt{}2si {}, {}", float_suffix(size),
+                reg(dst, int), reg(src, size))
+        }
+        Insn::FloatToFloat(Size::D, dst, src) => {
+            format!("cvtsd2ss {}, {}", reg(dst, Size::D), reg(src, Size::Q))
+        }
+        Insn::FloatToFloat(_, dst, src) => {
+            format!("cvtss2sd {}, {}", reg(dst, Size::Q), reg(src, Size::D))
+        }
+        Insn::MovGpXmm(size, dst, src) => {
+            let name = match size {
+                Size::Q => "movq",
+                _ => "movd",
+            };
+            format!("{} {}, {}", name, reg(dst, size), reg(src, size))
+        }
+        Insn::RepMovsb => "rep movsb".to_string(),
+        Insn::RepStosb => "rep stosb".to_string(),
+        Insn::Jmp(target) => format!("jmp {}", label(function, target)),
+        Insn::Jcc(cond, target) => {
+            format!("j{} {}", cond.as_str(), label(function, target))
+        }
+        Insn::Call(Callee::Symbol(ref name), _) => {
+            format!("call {}@PLT", symbol(name))
+        }
+        Insn::Call(Callee::Reg(callee), _) => {
+            format!("call {}", reg(callee, Size::Q))
+        }
+        Insn::Return(_) => "# return".to_string(),
+        Insn::Push(src) => format!("push {}", reg(src, Size::Q)),
+        Insn::Pop(dst) => format!("pop {}", reg(dst, Size::Q)),
+        Insn::Ret => "ret".to_string(),
+        Insn::Ud2 => "ud2".to_string(),
+    }
+}
@@ -0,0 +1,322 @@
+// ELF objects
+//
+//! Writes ELF64 relocatable objects for x86-64 Linux.
+//!
+//! The object has a fixed set of sections, empty or not: `.text`, `.data`,
+//! `.bss` and `.rodata`, the relocations of the two with addresses in them,
+//! the symbol table with its strings, and an empty `.note.GNU-stack` so the
+//! stack isn't made executable.  Every symbol defined or referred to is in
+//! the table, local ones first, and relocations refer to them by index.
+
+use std::collections::HashMap;
+
+use super::encode::{self, Relocation, RelocationKind};
+use super::{data, is_local, MachineFunction, Section};
+use crate::ir::Module;
+
+// Section types
+const SHT_PROGBITS: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHT_RELA: u32 = 4;
+const SHT_NOBITS: u32 = 8;
+
+// Section flags
+const SHF_WRITE: u64 = 1;
+const SHF_ALLOC: u64 = 2;
+const SHF_EXECINSTR: u64 = 4;
+const SHF_INFO_LINK: u64 = 0x40;
+
+// Symbol bindings and types
+const STB_LOCAL: u8 = 0;
+const STB_GLOBAL: u8 = 1;
+const STT_NOTYPE: u8 = 0;
+const STT_OBJECT: u8 = 1;
+const STT_FUNC: u8 = 2;
+
+// The indices of the sections.
+const TEXT: u16 = 1;
+const DATA: u16 = 3;
+const BSS: u16 = 5;
+const RODATA: u16 = 6;
+const SYMTAB: u16 = 8;
+const STRTAB: u16 = 9;
+const SECTIONS: u16 = 11;
+
+fn relocation_type(kind: RelocationKind) -> u64 {
+    match kind {
+        RelocationKind::Abs64 => 1,
+        RelocationKind::Pc32 => 2,
+        RelocationKind::Plt32 => 4,
+        RelocationKind::GotPcRel => 9,
+    }
+}
+
+// A symbol defined by the object.
+struct Symbol {
+    name: String,
+    section: u16,
+    value: u64,
+    size: u64,
+    ty: u8,
+}
+
+// The contents of a section being built.
+#[derive(Default)]
+struct Contents {
+    bytes: Vec<u8>,
+    // The size of `.bss`, which has no bytes
+    size: u64,
+    align: u64,
+    relocations: Vec<Relocation>,
+}
+
+impl Contents {
+    // Start something aligned to `align` bytes, returning its offset.
+    fn start(&mut self, align: u64, bss: bool) -> u64 {
+        self.align = self.align.max(align);
+        if bss {
+            self.size = self.size.div_ceil(align) * align;
+            return self.size;
+        }
+        let offset = self.bytes.len().div_ceil(align as usize) * align as usize;
+        self.bytes.resize(offset, 0);
+        offset as u64
+    }
+}
+
+/// Write an object with the code of the functions of a module and its
+/// global variables.
+pub(super) fn write(module: &Module, functions: &[MachineFunction])
+    -> Vec<u8>
+{
+    let mut symbols = Vec::new();
+    let mut text = Contents { align: 16, ..Contents::default() };
+    for function in functions {
+        let code = encode::encode(function);
+        let offset = text.start(1, false);
+        symbols.push(Symbol {
+            name: function.name.clone(),
+            section: TEXT,
+            value: offset,
+            size: code.bytes.len() as u64,
+            ty: STT_FUNC,
+        });
+        text.bytes.extend(code.bytes);
+        text.relocations.extend(code.relocations.into_iter()
+            .map(|relocation| Relocation {
+                offset: offset + relocation.offset,
+                ..relocation
+            }));
+    }
+
+    let mut sections: HashMap<Section, Contents> = HashMap::new();
+    for (global, data, section) in data(module) {
+        let contents = sections.entry(section).or_default();
+        let bss = section == Section::Bss;
+        let offset = contents.start(global.ty.align(), bss);
+        let size = data.bytes.len() as u64;
+        match bss {
+            true => contents.size += size,
+            false => contents.bytes.extend(&data.bytes),
+        }
+        contents.relocations.extend(data.relocations.into_iter()
+            .map(|(at, symbol, addend)| Relocation {
+                offset: offset + at,
+                symbol,
+                kind: RelocationKind::Abs64,
+                addend,
+            }));
+        symbols.push(Symbol {
+            name: global.name.clone(),
+            section: match section {
+                Section::Data => DATA,
+                Section::Rodata => RODATA,
+                Section::Bss => BSS,
+            },
+            value: offset,
+            size,
+            ty: STT_OBJECT,
+        });
+    }
+    let mut take = |section| sections.remove(&section).unwrap_or_default();
+    let data = take(Section::Data);
+    let rodata = take(Section::Rodata);
+    let bss = take(Section::Bss);
+
+    // The symbol table: local symbols, then global ones, then those
+    // referred to but not defined.
+    let mut strtab = vec![0];
+    let mut symtab = vec![0; 24];
+    let mut indices = HashMap::new();
+    let (locals, globals): (Vec<_>, Vec<_>) = symbols.into_iter()
+        .partition(|symbol| is_local(&symbol.name));
+    let locals_len = locals.len() + 1;
+    for (symbol, bind) in locals.into_iter().map(|s| (s, STB_LOCAL))
+        .chain(globals.into_iter().map(|s| (s, STB_GLOBAL)))
+    {
+        indices.insert(symbol.name.clone(), indices.len() + 1);
+        put_symbol(&mut symtab, &mut strtab, &symbol.name,
+            bind << 4 | symbol.ty, symbol.section, symbol.value, symbol.size);
+    }
+    let referred = text.relocations.iter().chain(&data.relocations);
+    for relocation in referred {
+        if !indices.contains_key(&relocation.symbol) {
+            indices.insert(relocation.symbol.clone(), indices.len() + 1);
+            put_symbol(&mut symtab, &mut strtab, &relocation.symbol,
+                STB_GLOBAL << 4 | STT_NOTYPE, 0, 0, 0);
+        }
+    }
+
+    let rela = |relocations: &[Relocation]| {
+        let mut bytes = Vec::with_capacity(24 * relocations.len());
+        for relocation in relocations {
+            let symbol = indices[&relocation.symbol] as u64;
+            bytes.extend(relocation.offset.to_le_bytes());
+            let info = symbol << 32 | relocation_type(relocation.kind);
+            bytes.extend(info.to_le_bytes());
+            bytes.extend(relocation.addend.to_le_bytes());
+        }
+        bytes
+    };
+    let rela_text = rela(&text.relocations);
+    let rela_data = rela(&data.relocations);
+
+    let symtab_info = locals_len as u32;
+    let text_header = Header::new(".text", SHT_PROGBITS,
+        SHF_ALLOC | SHF_EXECINSTR, &text);
+    let data_header = Header::new(".data", SHT_PROGBITS,
+        SHF_ALLOC | SHF_WRITE, &data);
+    let mut bss_header = Header::new(".bss", SHT_NOBITS,
+        SHF_ALLOC | SHF_WRITE, &bss);
+    bss_header.size = bss.size;
+    let headers = [
+        text_header,
+        Header::rela(".rela.text", &rela_text, TEXT),
+        data_header,
+        Header::rela(".rela.data", &rela_data, DATA),
+        bss_header,
+        Header::new(".rodata", SHT_PROGBITS, SHF_ALLOC, &rodata),
+        Header::table(".note.GNU-stack", SHT_PROGBITS, &[]),
+        Header {
+            link: u32::from(STRTAB),
+            info: symtab_info,
+            align: 8,
+            entsize: 24,
+            ..Header::table(".symtab", SHT_SYMTAB, &symtab)
+        },
+        Header::table(".strtab", SHT_STRTAB, &strtab),
+    ];
+    let mut shstrtab = vec![0];
+    let mut names = Vec::new();
+    for header in headers.iter().map(|header| header.name)
+        .chain([".shstrtab"])
+    {
+        names.push(shstrtab.len() as u32);
+        shstrtab.extend(header.as_bytes());
+        shstrtab.push(0);
+    }
+    let shstrtab_header = Header::table(".shstrtab", SHT_STRTAB, &shstrtab);
+
+    // The header, the contents of the sections, then their headers.
+    let mut out = vec![0; 64];
+    let mut table = vec![0; 64];
+    for (header, name) in headers.iter().chain([&shstrtab_header]).zip(names) {
+        let align = header.align as usize;
+        out.resize(out.len().div_ceil(align) * align, 0);
+        let offset = out.len() as u64;
+        out.extend(header.bytes);
+        table.extend(name.to_le_bytes());
+        table.extend(header.ty.to_le_bytes());
+        table.extend(header.flags.to_le_bytes());
+        table.extend(0u64.to_le_bytes());
+        table.extend(offset.to_le_bytes());
+        table.extend(header.size.to_le_bytes());
+        table.extend(header.link.to_le_bytes());
+        table.extend(header.info.to_le_bytes());
+        table.extend(header.align.to_le_bytes());
+        table.extend(header.entsize.to_le_bytes());
+    }
+    out.resize(out.len().div_ceil(8) * 8, 0);
+    let headers_offset = out.len() as u64;
+    out.extend(table);
+
+    let header = &mut out[..64];
+    header[..4].copy_from_slice(b"\x7fELF");
+    // 64-bit, little-endian, version 1, System V
+    header[4..8].copy_from_slice(&[2, 1, 1, 0]);
+    header[16..18].copy_from_slice(&1u16.to_le_bytes());
+    header[18..20].copy_from_slice(&62u16.to_le_bytes());
+    header[20..24].copy_from_slice(&1u32.to_le_bytes());
+    header[40..48].copy_from_slice(&headers_offset.to_le_bytes());
+    header[52..54].copy_from_slice(&64u16.to_le_bytes());
+    header[58..60].copy_from_slice(&64u16.to_le_bytes());
+    header[60..62].copy_from_slice(&SECTIONS.to_le_bytes());
+    header[62..64].copy_from_slice(&(SECTIONS - 1).to_le_bytes());
+    out
+}
+
+// The header of a section, but for its name and offset.
+#[derive(Clone, Copy)]
+struct Header<'a> {
+    name: &'a str,
+    ty: u32,
+    flags: u64,
+    bytes: &'a [u8],
+    size: u64,
+    link: u32,
+    info: u32,
+    align: u64,
+    entsize: u64,
+}
+
+impl<'a> Header<'a> {
+    fn new(name: &'a str, ty: u32, flags: u64, contents: &'a Contents)
+        -> Self
+    {
+        Header {
+            flags,
+            align: contents.align.max(1),
+            ..Header::table(name, ty, &contents.bytes)
+        }
+    }
+
+    // A section that isn't loaded.
+    fn table(name: &'a str, ty: u32, bytes: &'a [u8]) -> Self {
+        Header {
+            name,
+            ty,
+            flags: 0,
+            bytes,
+            size: bytes.len() as u64,
+            link: 0,
+            info: 0,
+            align: 1,
+            entsize: 0,
+        }
+    }
+
+    // The relocations of a section.
+    fn rela(name: &'a str, bytes: &'a [u8], section: u16) -> Self {
+        Header {
+            flags: SHF_INFO_LINK,
+            link: u32::from(SYMTAB),
+            info: u32::from(section),
+            align: 8,
+            entsize: 24,
+            ..Header::table(name, SHT_RELA, bytes)
+        }
+    }
+}
+
+fn put_symbol(symtab: &mut Vec<u8>, strtab: &mut Vec<u8>, name: &str,
+    info: u8, section: u16, value: u64, size: u64)
+{
+    symtab.extend((strtab.len() as u32).to_le_bytes());
+    strtab.extend(name.as_bytes());
+    strtab.push(0);
+    symtab.extend([info, 0]);
+    symtab.extend(section.to_le_bytes());
+    symtab.extend(value.to_le_bytes());
+    symtab.extend(size.to_le_bytes());
+}
@@ -0,0 +1,537 @@
+// Instruction encoding
+//
+//! Encodes machine instructions to bytes, with the relocations for the
+//! symbols they refer to.
+//!
+//! Where an instruction has several encodings this picks the one the GNU
+//! assembler does, so the code is the same whether assembled or not.
+//! Branches are short when their target is near enough, found by starting
+//! with every branch short and making those too far long until none are.
+
+use std::convert::TryFrom;
+
+use super::{
+    gp, AluOp, Base, Callee, Cond, Insn, MachineFunction, Mem, Operand, Reg,
+    Size,
+};
+
+/// The kind of a relocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum RelocationKind {
+    /// The 64-bit address of a symbol
+    Abs64,
+    /// The 32-bit offset to a symbol
+    Pc32,
+    /// The 32-bit offset to a function, or its entry in the procedure
+    /// linkage table
+    Plt32,
+    /// The 32-bit offset to a symbol's entry in the global offset table
+    GotPcRel,
+}
+
+/// A place in code or data where the address of a symbol goes.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Relocation {
+    pub offset: u64,
+    pub symbol: String,
+    pub kind: RelocationKind,
+    pub addend: i64,
+}
+
+/// The machine code of a function.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Code {
+    pub bytes: Vec<u8>,
+    pub relocations: Vec<Relocation>,
+}
+
+// A run of instructions, or a branch whose size isn't known yet.
+enum Piece {
+    Code(Code),
+    Branch(Option<Cond>, usize),
+}
+
+/// Encode a function after register allocation.
+pub(super) fn encode(function: &MachineFunction) -> Code {
+    let mut blocks = Vec::with_capacity(function.blocks.len());
+    for insns in &function.blocks {
+        let mut pieces = Vec::new();
+        let mut code = Code::default();
+        for insn in insns {
+            match *insn {
+                Insn::Jmp(target) => {
+                    pieces.push(Piece::Code(std::mem::take(&mut code)));
+                    pieces.push(Piece::Branch(None, target));
+                }
+                Insn::Jcc(cond, target) => {
+                    pieces.push(Piece::Code(std::mem::take(&mut code)));
+                    pieces.push(Piece::Branch(Some(cond), target));
+                }
+                ref insn => code.insn(insn),
+            }
+        }
+        pieces.push(Piece::Code(code));
+        blocks.push(pieces);
+    }
+
+    let branch_len = |cond: Option<Cond>, long: bool| match (cond, long) {
+        (_, false) => 2,
+        (None, true) => 5,
+        (Some(_), true) => 6,
+    };
+    let branches = blocks.iter().flatten()
+        .filter(|piece| matches!(piece, Piece::Branch(..)))
+        .count();
+    let mut long = vec![false; branches];
+    let starts = loop {
+        // The offset of each block, then each branch's end.
+        let mut starts = Vec::with_capacity(blocks.len());
+        let mut ends = Vec::with_capacity(branches);
+        let mut offset = 0;
+        for pieces in &blocks {
+            starts.push(offset);
+            for piece in pieces {
+                offset += match *piece {
+                    Piece::Code(ref code) => code.bytes.len() as i64,
+                    Piece::Branch(cond, _) => {
+                        let len = branch_len(cond, long[ends.len()]);
+                        ends.push(offset + len);
+                        len
+                    }
+                };
+            }
+        }
+        let mut changed = false;
+        let targets = blocks.iter().flatten().filter_map(|piece| match *piece {
+            Piece::Branch(_, target) => Some(target),
+            Piece::Code(_) => None,
+        });
+        for (index, target) in targets.enumerate() {
+            let disp = starts[target] - ends[index];
+            if !long[index] && i8::try_from(disp).is_err() {
+                long[index] = true;
+                changed = true;
+            }
+        }
+        if !changed {
+            break starts;
+        }
+    };
+
+    let mut code = Code::default();
+    let mut index = 0;
+    for pieces in blocks {
+        for piece in pieces {
+            match piece {
+                Piece::Code(piece) => {
+                    let base = code.bytes.len() as u64;
+                    code.bytes.extend(piece.bytes);
+                    code.relocations.extend(piece.relocations.into_iter()
+                        .map(|relocation| Relocation {
+                            offset: base + relocation.offset,
+                            ..relocation
+                        }));
+                }
+                Piece::Branch(cond, target) => {
+                    let len = branch_len(cond, long[index]);
+                    let end = code.bytes.len() as i64 + len;
+                    let disp = starts[target] - end;
+                    match (cond, long[index]) {
+                        (None, false) => code.bytes.push(0xEB),
+                        (None, true) => code.bytes.push(0xE9),
+                        (Some(cond), false) => code.bytes.push(0x70 | cond as u8),
+                        (Some(cond), true) => {
+                            code.bytes.extend([0x0F, 0x80 | cond as u8]);
+                        }
+                    }
+                    match long[index] {
+                        true => code.bytes.extend((disp as i32).to_le_bytes()),
+                        false => code.bytes.push(disp as i8 as u8),
+                    }
+                    index += 1;
+                }
+            }
+        }
+    }
+    code
+}
+
+fn num(reg: Reg) -> u8 {
+    match reg {
+        Reg::Gp(num) | Reg::Xmm(num) => num,
+        Reg::Virt(_) => unreachable!("virtual register after allocation"),
+    }
+}
+
+// Whether a byte register needs a REX prefix: `spl`, `bpl`, `sil` and
+// `dil` rather than `ah`, `ch`, `dh` and `bh`.
+fn is_rex_byte(reg: Reg) -> bool {
+    matches!(reg, Reg::Gp(4..=7))
+}
+
+// The operand in the ModRM byte's r/m field.
+#[derive(Clone, Copy)]
+enum Rm<'a> {
+    Reg(Reg),
+    Mem(&'a Mem),
+}
+
+// What goes in the ModRM byte's reg field.
+#[derive(Clone, Copy)]
+enum Field {
+    Reg(Reg),
+    // An opcode extension, or the number of a register that isn't a byte
+    Ext(u8),
+}
+
+// An immediate of a size.
+fn imm(size: Size, value: i64) -> Vec<u8> {
+    value.to_le_bytes()[..size.len().min(4) as usize].to_vec()
+}
+
+impl Code {
+    // Encode an instruction with a ModRM byte.  `byte` is whether its
+    // registers are bytes.
+    #[allow(clippy::too_many_arguments)]
+    fn modrm(&mut self, prefix: Option<u8>, w: bool, opcode: &[u8],
+        field: Field, rm: Rm, byte: bool, imm: &[u8])
+    {
+        self.bytes.extend(prefix);
+        let (base, force) = match rm {
+            Rm::Reg(rm) => (num(rm), byte && is_rex_byte(rm)),
+            Rm::Mem(mem) => match mem.base {
+                Base::Reg(base) => (num(base), false),
+                _ => (0, false),
+            },
+        };
+        let (reg, force) = match field {
+            Field::Reg(reg) => (num(reg), force || byte && is_rex_byte(reg)),
+            Field::Ext(ext) => (ext, force),
+        };
+        let rex = 0x40 | (w as u8) << 3 | (reg >> 3) << 2 | base >> 3;
+        if rex != 0x40 || force {
+            self.bytes.push(rex);
+        }
+        self.bytes.extend(opcode);
+        let reg = (reg & 7) << 3;
+        match rm {
+            Rm::Reg(rm) => self.bytes.push(0xC0 | reg | num(rm) & 7),
+            Rm::Mem(mem) => match mem.base {
+                Base::Reg(base) => {
+                    let base = num(base) & 7;
+                    let disp = mem.disp;
+                    let mode = match i8::try_from(disp) {
+                        _ if disp == 0 && base != gp::RBP => 0x00,
+                        Ok(_) => 0x40,
+                        Err(_) => 0x80,
+                    };
+                    self.bytes.push(mode | reg | base);
+                    if base == gp::RSP {
+                        self.bytes.push(0x24);
+                    }
+                    match mode {
+                        0x40 => self.bytes.push(disp as u8),
+                        0x80 => self.bytes.extend(disp.to_le_bytes()),
+                        _ => {}
+                    }
+                }
+                Base::Symbol(ref symbol) | Base::Got(ref symbol) => {
+                    self.bytes.push(reg | 0x05);
+                    let kind = match mem.base {
+                        Base::Got(_) => RelocationKind::GotPcRel,
+                        _ => RelocationKind::Pc32,
+                    };
+                    self.relocations.push(Relocation {
+                        offset: self.bytes.len() as u64,
+                        symbol: symbol.clone(),
+                        kind,
+                        addend: i64::from(mem.disp) - 4 - imm.len() as i64,
+                    });
+                    self.bytes.extend([0; 4]);
+                }
+                _ => unreachable!("stack slot after frame layout"),
+            },
+        }
+        self.bytes.extend(imm);
+    }
+
+    // Encode an instruction with the register in its opcode.
+    fn plus_reg(&mut self, w: bool, opcode: u8, reg: u8, byte: bool) {
+        let rex = 0x40 | (w as u8) << 3 | reg >> 3;
+        if rex != 0x40 || byte && (4..8).contains(&reg) {
+            self.bytes.push(rex);
+        }
+        self.bytes.push(opcode + (reg & 7));
+    }
+
+    fn insn(&mut self, insn: &Insn) {
+        match *insn {
+            Insn::Mov(size, ref dst, ref src) => self.mov(size, dst, src),
+            Insn::MovAbs(reg, value) => {
+                self.plus_reg(true, 0xB8, num(reg), false);
+                self.bytes.extend(value.to_le_bytes());
+            }
+            Insn::MovZx(from, to, dst, ref src) => {
+                let opcode = match from {
+                    Size::B => [0x0F, 0xB6],
+                    _ => [0x0F, 0xB7],
+                };
+                self.modrm(None, to == Size::Q, &opcode, Field::Ext(num(dst)), rm(src),
+                    from == Size::B, &[]);
+            }
+            Insn::MovSx(from, to, dst, ref src) => {
+                let opcode: &[u8] = match from {
+                    Size::B => &[0x0F, 0xBE],
+                    Size::W => &[0x0F, 0xBF],
+                    _ => &[0x63],
+                };
+                self.modrm(None, to == Size::Q, opcode, Field::Ext(num(dst)), rm(src),
+                    from == Size::B, &[]);
+            }
+            Insn::Lea(reg, ref mem) => {
+                self.modrm(None, true, &[0x8D], Field::Reg(reg), Rm::Mem(mem), false,
+                    &[]);
+            }
+            Insn::Alu(op, size, ref dst, ref src) => self.alu(op, size, dst, src),
+            Insn::Test(size, a, b) => {
+                let opcode = match size {
+                    Size::B => 0x84,
+                    _ => 0x85,
+                };
+                self.sized(size, &[opcode], Field::Reg(b), Rm::Reg(a), &[]);
+            }
+            Insn::Imul(size, dst, ref src) => {
+                self.sized(size, &[0x0F, 0xAF], Field::Reg(dst), rm(src), &[]);
+            }
+            Insn::Shift(op, size, reg, amount) => {
+                let byte = size == Size::B;
+                let (opcode, imm) = match amount {
+                    None => (0xD3, None),
+                    Some(1) => (0xD1, None),
+                    Some(amount) => (0xC1, Some(amount)),
+                };
+                let opcode = opcode - byte as u8;
+                let imm: Vec<u8> = imm.into_iter().collect();
+                self.sized(size, &[opcode], Field::Ext(op as u8), Rm::Reg(reg),
+                    &imm);
+            }
+            Insn::Neg(size, reg) => {
+                let opcode = match size {
+                    Size::B => 0xF6,
+                    _ => 0xF7,
+                };
+                self.sized(size, &[opcode], Field::Ext(3), Rm::Reg(reg), &[]);
+            }
+            Insn::SignExtend(size) => {
+                if size == Size::Q {
+                    self.bytes.push(0x48);
+                }
+                self.bytes.push(0x99);
+            }
+            Insn::Div(signed, size, reg) => {
+                let opcode = match size {
+                    Size::B => 0xF6,
+                    _ => 0xF7,
+                };
+                let digit = match signed {
+                    true => 7,
+                    false => 6,
+                };
+                self.sized(size, &[opcode], Field::Ext(digit), Rm::Reg(reg), &[]);
+            }
+            Insn::Setcc(cond, reg) => {
+                self.modrm(None, false, &[0x0F, 0x90 | cond as u8], Field::Ext(0),
+                    Rm::Reg(reg), true, &[]);
+            }
+            Insn::Cmov(cond, size, dst, src) => {
+                self.sized(size, &[0x0F, 0x40 | cond as u8], Field::Reg(dst),
+                    Rm::Reg(src), &[]);
+            }
+            Insn::MovF(size, ref dst, ref src) => {
+                let prefix = float_prefix(size);
+                match (dst, src) {
+                    (Operand::Reg(dst), src) => {
+                        self.modrm(Some(prefix), false, &[0x0F, 0x10],
+                            Field::Reg(*dst), rm(src), false, &[]);
+                    }
+                    (dst, Operand::Reg(src)) => {
+                        self.modrm(Some(prefix), false, &[0x0F, 0x11],
+                            Field::Reg(*src), rm(dst), false, &[]);
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            Insn::MovAps(dst, src) => {
+                self.modrm(None, false, &[0x0F, 0x28], Field::Reg(dst), Rm::Reg(src),
+                    false, &[]);
+            }
+            Insn::FloatOp(op, size, dst, ref src) => {
+                self.modrm(Some(float_prefix(size)), false, &[0x0F, op as u8],
+                    Field::Reg(dst), rm(src), false, &[]);
+            }
+            Insn::Ucomi(size, a, b) => {
+                let prefix = match size {
+                    Size::Q => Some(0x66),
+                    _ => None,
+                };
+                self.modrm(prefix, false, &[0x0F, 0x2E], Field::Reg(a), Rm::Reg(b),
+                    false, &[]);
+            }
+            Insn::Xorps(dst, src) => {
+                self.modrm(None, false, &[0x0F, 0x57], Field::Reg(dst), Rm::Reg(src),
+                    false, &[]);
+            }
+            Insn::IntToFloat(int, float, dst, src) => {
+                self.modrm(Some(float_prefix(float)), int == Size::Q,
+                    &[0x0F, 0x2A], Field::Reg(dst), Rm::Reg(src), false, &[]);
+            }
+            Insn::FloatToInt(float, int, dst, src) => {
+                self.modrm(Some(float_prefix(float)), int == Size::Q,
+                    &[0x0F, 0x2C], Field::Reg(dst), Rm::Reg(src), false, &[]);
+            }
+            Insn::FloatToFloat(to, dst, src) => {
+                let from = match to {
+                    Size::D => Size::Q,
+                    _ => Size::D,
+                };
+                self.modrm(Some(float_prefix(from)), false, &[0x0F, 0x5A],
+                    Field::Reg(dst), Rm::Reg(src), false, &[]);
+            }
+            Insn::MovGpXmm(size, dst, src) => {
+                let (opcode, xmm, gp) = match dst {
+                    Reg::Xmm(_) => (0x6E, dst, src),
+                    _ => (0x7E, src, dst),
+                };
+                self.modrm(Some(0x66), size == Size::Q, &[0x0F, opcode],
+                    Field::Reg(xmm), Rm::Reg(gp), false, &[]);
+            }
+            Insn::RepMovsb => self.bytes.extend([0xF3, 0xA4]),
+            Insn::RepStosb => self.bytes.extend([0xF3, 0xAA]),
+            Insn::Call(Callee::Symbol(ref symbol), _) => {
+                self.bytes.push(0xE8);
+                self.relocations.push(Relocation {
+                    offset: self.bytes.len() as u64,
+                    symbol: symbol.clone(),
+                    kind: RelocationKind::Plt32,
+                    addend: -4,
+                });
+                self.bytes.extend([0; 4]);
+            }
+            Insn::Call(Callee::Reg(reg), _) => {
+                self.modrm(None, false, &[0xFF], Field::Ext(2), Rm::Reg(reg),
+                    false, &[]);
+            }
+            Insn::Push(reg) => self.plus_reg(false, 0x50, num(reg), false),
+            Insn::Pop(reg) => self.plus_reg(false, 0x58, num(reg), false),
+            Insn::Ret => self.bytes.push(0xC3),
+            Insn::Ud2 => self.bytes.extend([0x0F, 0x0B]),
+            Insn::Jmp(_) | Insn::Jcc(..) | Insn::Return(_) => {
+                unreachable!("{:?} isn't encoded on its own", insn)
+            }
+        }
+    }
+
+    // Encode an integer instruction whose operand size is given by a
+    // prefix or REX.W.
+    fn sized(&mut self, size: Size, opcode: &[u8], field: Field, rm: Rm,
+        imm: &[u8])
+    {
+        let prefix = match size {
+            Size::W => Some(0x66),
+            _ => None,
+        };
+        self.modrm(prefix, size == Size::Q, opcode, field, rm,
+            size == Size::B, imm);
+    }
+
+    fn mov(&mut self, size: Size, dst: &Operand, src: &Operand) {
+        let byte = size == Size::B;
+        match (dst, src) {
+            (Operand::Reg(dst), &Operand::Imm(value)) if size != Size::Q => {
+                if size == Size::W {
+                    self.bytes.push(0x66);
+                }
+                let opcode = match byte {
+                    true => 0xB0,
+                    false => 0xB8,
+                };
+                self.plus_reg(false, opcode, num(*dst), byte);
+                self.bytes.extend(imm(size, value));
+            }
+            (dst, &Operand::Imm(value)) => {
+                let opcode = 0xC7 - byte as u8;
+                self.sized(size, &[opcode], Field::Ext(0), rm(dst),
+                    &imm(size, value));
+            }
+            (dst, Operand::Reg(src)) => {
+                self.sized(size, &[0x89 - byte as u8], Field::Reg(*src), rm(dst),
+                    &[]);
+            }
+            (Operand::Reg(dst), src) => {
+                self.sized(size, &[0x8B - byte as u8], Field::Reg(*dst), rm(src),
+                    &[]);
+            }
+            _ => unreachable!("move between memory"),
+        }
+    }
+
+    fn alu(&mut self, op: AluOp, size: Size, dst: &Operand, src: &Operand) {
+        let base = (op as u8) << 3;
+        let byte = size == Size::B;
+        match (dst, src) {
+            (&Operand::Reg(Reg::Gp(gp::RAX)), &Operand::Imm(value))
+                if byte || i8::try_from(value).is_err() =>
+            {
+                if size == Size::W {
+                    self.bytes.push(0x66);
+                }
+                if size == Size::Q {
+                    self.bytes.push(0x48);
+                }
+                self.bytes.push(base + 5 - byte as u8);
+                self.bytes.extend(imm(size, value));
+            }
+            (dst, &Operand::Imm(value)) if !byte => {
+                match i8::try_from(value) {
+                    Ok(value) => {
+                        self.sized(size, &[0x83], Field::Ext(op as u8), rm(dst),
+                            &[value as u8]);
+                    }
+                    Err(_) => {
+                        self.sized(size, &[0x81], Field::Ext(op as u8), rm(dst),
+                            &imm(size, value));
+                    }
+                }
+            }
+            (dst, &Operand::Imm(value)) => {
+                self.sized(size, &[0x80], Field::Ext(op as u8), rm(dst),
+                    &[value as u8]);
+            }
+            (dst, Operand::Reg(src)) => {
+                self.sized(size, &[base + 1 - byte as u8], Field::Reg(*src), rm(dst),
+                    &[]);
+            }
+            (Operand::Reg(dst), src) => {
+                self.sized(size, &[base + 3 - byte as u8], Field::Reg(*dst), rm(src),
+                    &[]);
+            }
+            _ => unreachable!("operation between memory"),
+        }
+    }
+}
+
+fn rm(operand: &Operand) -> Rm<'_> {
+    match operand {
+        Operand::Reg(reg) => Rm::Reg(*reg),
+        Operand::Mem(mem) => Rm::Mem(mem),
+        Operand::Imm(_) => unreachable!("immediate as r/m operand"),
+    }
+}
+
+// The prefix of scalar SSE instructions on floats of a size.
+fn float_prefix(size: Size) -> u8 {
+    match size {
+        Size::D => 0xF3,
+        _ => 0xF2,
+    }
+}
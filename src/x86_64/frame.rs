@@ -0,0 +1,105 @@
+// Stack frames
+//
+//! Lays out the stack frame of a function, and adds its prologue and
+//! epilogues.
+//!
+//! ```text
+//!         arguments passed on the stack     rbp + 16
+//!         return address
+//! rbp --> saved rbp
+//!         saved callee-saved registers
+//!         stack slots
+//!         arguments of calls on the stack   rsp + 0
+//! ```
+//!
+//! `rsp` stays a multiple of 16 once the prologue is done, as calls need.
+
+use super::regalloc;
+use super::{gp, AluOp, Base, Insn, MachineFunction, Mem, Operand, Reg, Size};
+
+/// Give the stack slots of a function their addresses, and replace its
+/// returns with epilogues.
+pub(super) fn lay_out(function: &mut MachineFunction) {
+    let rbp = Reg::Gp(gp::RBP);
+    let rsp = Reg::Gp(gp::RSP);
+    let saved = regalloc::callee_saved(function);
+
+    // Offsets below `rbp`.
+    let mut size = 8 * saved.len() as u64;
+    let mut offsets = Vec::with_capacity(function.slots.len());
+    for slot in &function.slots {
+        size = align(size + slot.size, slot.align);
+        offsets.push(size);
+    }
+    size = align(size + function.outgoing, 16);
+
+    for block in &mut function.blocks {
+        let insns = std::mem::take(block);
+        for mut insn in insns {
+            for mem in mems_mut(&mut insn) {
+                let (base, disp) = match mem.base {
+                    Base::Slot(slot) => (rbp, -(offsets[slot] as i32)),
+                    Base::Incoming => (rbp, 16),
+                    Base::Outgoing => (rsp, 0),
+                    _ => continue,
+                };
+                *mem = Mem { base: Base::Reg(base), disp: mem.disp + disp };
+            }
+            if let Insn::Return(_) = insn {
+                match saved.len() {
+                    0 => block.push(Insn::Mov(Size::Q, Operand::Reg(rsp),
+                        Operand::Reg(rbp))),
+                    len => block.push(Insn::Lea(rsp, Mem {
+                        base: Base::Reg(rbp),
+                        disp: -8 * len as i32,
+                    })),
+                }
+                for &reg in saved.iter().rev() {
+                    block.push(Insn::Pop(Reg::Gp(reg)));
+                }
+                block.push(Insn::Pop(rbp));
+                block.push(Insn::Ret);
+                continue;
+            }
+            block.push(insn);
+        }
+    }
+
+    let mut prologue = vec![
+        Insn::Push(rbp),
+        Insn::Mov(Size::Q, Operand::Reg(rbp), Operand::Reg(rsp)),
+    ];
+    prologue.extend(saved.iter().map(|&reg| Insn::Push(Reg::Gp(reg))));
+    let rest = size - 8 * saved.len() as u64;
+    if rest != 0 {
+        prologue.push(Insn::Alu(AluOp::Sub, Size::Q, Operand::Reg(rsp),
+            Operand::Imm(rest as i64)));
+    }
+    prologue.append(&mut function.blocks[0]);
+    function.blocks[0] = prologue;
+}
+
+// Every address an instruction refers to.
+fn mems_mut(insn: &mut Insn) -> Vec<&mut Mem> {
+    fn operand(operand: &mut Operand) -> Option<&mut Mem> {
+        match operand {
+            Operand::Mem(mem) => Some(mem),
+            _ => None,
+        }
+    }
+    match insn {
+        Insn::Mov(_, a, b) | Insn::MovF(_, a, b) | Insn::Alu(_, _, a, b) => {
+            operand(a).into_iter().chain(operand(b)).collect()
+        }
+        Insn::MovZx(_, _, _, src)
+        | Insn::MovSx(_, _, _, src)
+        | Insn::Imul(_, _, src)
+        | Insn::FloatOp(_, _, _, src) => operand(src).into_iter().collect(),
+        Insn::Lea(_, mem) => vec![mem],
+        _ => Vec::new(),
+    }
+}
+
+fn align(offset: u64, align: u64) -> u64 {
+    offset.div_ceil(align) * align
+}
@@ -0,0 +1,1516 @@
+// Instruction selection
+//
+//! Translates an IR function to machine instructions on virtual registers.
+//!
+//! Every scalar value of an instruction or parameter gets a virtual
+//! register, and every aggregate value a stack slot, which is only written
+//! where it is defined.  Stack slots are also the addresses of `alloca`s,
+//! which, like the addresses of symbols and a constant offset from either,
+//! are folded into the instructions using them rather than computed.
+//!
+//! `phi`s are replaced by copies at the end of the blocks branching to
+//! theirs, so the edges from blocks branching elsewhere too are first
+//! split.  The copies go through temporaries when a `phi` is copied to
+//! another, as the values must all be read before any is written.
+//!
+//! An `icmp` only used by the `condbr` right after it sets the flags the
+//! branch tests, rather than an `i1`.
+//!
+//! A variadic function with a `va_start` stores the argument registers in
+//! a register save area on entry, integers then floats, 16 bytes each.  A
+//! `va_list` holds the offsets there of the next integer and float
+//! registers, the address of the next argument on the stack and that of
+//! the area, as in the System V ABI, and `va_arg` picks where to read from
+//! with conditional moves rather than branches.
+
+use std::convert::TryFrom;
+
+use super::abi::{self, Location};
+use super::{
+    gp, is_defined, unsupported, AluOp, Base, Callee, Class, Cond, FloatOp,
+    Insn, MachineFunction, Mem, Operand, Reg, Result, ShiftOp, Size,
+};
+use crate::ir::{
+    BinaryOp, BlockId, CastOp, FloatPredicate, Function, InstId, InstKind,
+    IntPredicate, Module, Terminator, Type, Value,
+};
+
+// Copies of at most this many bytes are done with moves rather than a
+// `rep movsb`.
+const INLINE_COPY: u64 = 64;
+// Where the float registers start in the register save area of a variadic
+// function, after the integer ones, and its size: 16 bytes for each.
+const FLOAT_AREA: i64 = 8 * abi::INT_ARGS.len() as i64;
+const SAVE_AREA: i64 = FLOAT_AREA + 16 * abi::FLOAT_ARGS as i64;
+
+// How a value is in the machine code.
+#[derive(Debug, Clone, PartialEq)]
+enum Lowered {
+    // A scalar in a register
+    Reg(Reg),
+    // A pointer known without computing it
+    Addr(Mem),
+    // The address of a symbol defined elsewhere, in the global offset table
+    Got(String),
+    // An aggregate in memory
+    Memory(Mem),
+    None,
+}
+
+/// Translate a function with a body.
+pub(super) fn select(module: &Module, function: &Function)
+    -> Result<MachineFunction>
+{
+    check(function)?;
+    let function = &split_edges(function);
+    let mut machine = MachineFunction {
+        name: function.name.clone(),
+        blocks: vec![Vec::new(); function.blocks.len()],
+        vregs: Vec::new(),
+        slots: Vec::new(),
+        outgoing: 0,
+    };
+
+    // The representation of every instruction's value.
+    let mut values = Vec::with_capacity(function.insts.len());
+    for inst in &function.insts {
+        let lowered = match inst.kind {
+            InstKind::Alloca(ref ty) => {
+                let slot = machine.slot(ty.size(), ty.align());
+                Lowered::Addr(Mem::new(Base::Slot(slot)))
+            }
+            _ if inst.ty == Type::Void => Lowered::None,
+            _ if inst.ty.is_aggregate() => value_slot(&mut machine, &inst.ty),
+            _ => Lowered::Reg(machine.vreg(class(&inst.ty))),
+        };
+        values.push(lowered);
+    }
+
+    let mut uses = vec![0; function.insts.len()];
+    let mut used_params = vec![false; function.signature.params.len()];
+    for block in &function.blocks {
+        let operands = block.insts.iter()
+            .flat_map(|&inst| function.insts[inst].kind.operands())
+            .chain(block.term.operand());
+        for operand in operands {
+            match *operand {
+                Value::Inst(inst) => uses[inst] += 1,
+                Value::Param(index) => used_params[index] = true,
+                _ => {}
+            }
+        }
+    }
+
+    let mut selector = Selector {
+        module,
+        function,
+        machine,
+        block: 0,
+        values,
+        params: Vec::new(),
+        sret: None,
+        save_area: None,
+        uses,
+    };
+    selector.params(&used_params);
+    for block in 0..function.blocks.len() {
+        selector.block = block;
+        for &inst in &function.blocks[block].insts {
+            selector.inst(inst);
+        }
+        selector.terminator();
+    }
+    Ok(selector.machine)
+}
+
+// Reject what can't be translated.
+fn check(function: &Function) -> Result<()> {
+    let wide = |ty: &Type| *ty == Type::Int(128);
+    let params = function.signature.params.iter();
+    let insts = function.insts.iter().map(|inst| &inst.ty);
+    if wide(&function.signature.ret) || params.chain(insts).any(wide) {
+        return Err(unsupported(&function.name, "`i128`"));
+    }
+    Ok(())
+}
+
+// The function with a new block on each edge to a block with `phi`s from a
+// block branching elsewhere too.
+fn split_edges(function: &Function) -> Function {
+    let mut function = function.clone();
+    for block in 0..function.blocks.len() {
+        let mut succs = function.blocks[block].term.successors();
+        if succs.len() < 2 {
+            continue;
+        }
+        succs.sort_unstable();
+        succs.dedup();
+        for succ in succs {
+            let has_phis = function.blocks[succ].insts.first()
+                .is_some_and(|&inst| {
+                    matches!(function.insts[inst].kind, InstKind::Phi(_))
+                });
+            if !has_phis {
+                continue;
+            }
+            let edge = function.add_block();
+            function.blocks[edge].term = Terminator::Br(succ);
+            for target in function.blocks[block].term.successors_mut() {
+                if *target == succ {
+                    *target = edge;
+                }
+            }
+            for &inst in &function.blocks[succ].insts {
+                if let InstKind::Phi(ref mut incoming) =
+                    function.insts[inst].kind
+                {
+                    for (pred, _) in incoming.iter_mut() {
+                        if *pred == block {
+                            *pred = edge;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    function
+}
+
+fn class(ty: &Type) -> Class {
+    match ty.is_float() {
+        true => Class::Xmm,
+        false => Class::Gp,
+    }
+}
+
+// A stack slot for an aggregate value, big enough to be read and written
+// an eightbyte at a time.
+fn value_slot(machine: &mut MachineFunction, ty: &Type) -> Lowered {
+    let slot = machine.slot(ty.size().div_ceil(8) * 8, ty.align().max(8));
+    Lowered::Memory(Mem::new(Base::Slot(slot)))
+}
+
+// Whether an integer fits in an immediate of an instruction of a size.
+fn fits(size: Size, value: i128) -> bool {
+    size != Size::Q || i32::try_from(value).is_ok()
+}
+
+// The size integer instructions on values of a size are done at.
+fn wide(size: Size) -> Size {
+    size.max(Size::D)
+}
+
+fn cond(predicate: IntPredicate) -> Cond {
+    match predicate {
+        IntPredicate::Eq => Cond::E,
+        IntPredicate::Ne => Cond::Ne,
+        IntPredicate::Slt => Cond::L,
+        IntPredicate::Sle => Cond::Le,
+        IntPredicate::Sgt => Cond::G,
+        IntPredicate::Sge => Cond::Ge,
+        IntPredicate::Ult => Cond::B,
+        IntPredicate::Ule => Cond::Be,
+        IntPredicate::Ugt => Cond::A,
+        IntPredicate::Uge => Cond::Ae,
+    }
+}
+
+// The predicate with the operands swapped.
+fn swapped(predicate: IntPredicate) -> IntPredicate {
+    match predicate {
+        IntPredicate::Slt => IntPredicate::Sgt,
+        IntPredicate::Sle => IntPredicate::Sge,
+        IntPredicate::Sgt => IntPredicate::Slt,
+        IntPredicate::Sge => IntPredicate::Sle,
+        IntPredicate::Ult => IntPredicate::Ugt,
+        IntPredicate::Ule => IntPredicate::Uge,
+        IntPredicate::Ugt => IntPredicate::Ult,
+        IntPredicate::Uge => IntPredicate::Ule,
+        predicate => predicate,
+    }
+}
+
+struct Selector<'a> {
+    module: &'a Module,
+    function: &'a Function,
+    machine: MachineFunction,
+    block: BlockId,
+    values: Vec<Lowered>,
+    params: Vec<Lowered>,
+    // The address of the memory to return the value in.
+    sret: Option<Reg>,
+    // The register save area, in a variadic function with a `va_start`.
+    save_area: Option<Mem>,
+    uses: Vec<usize>,
+}
+
+impl Selector<'_> {
+    fn emit(&mut self, insn: Insn) {
+        self.machine.blocks[self.block].push(insn);
+    }
+
+    fn gp(&mut self) -> Reg {
+        self.machine.vreg(Class::Gp)
+    }
+
+    fn xmm(&mut self) -> Reg {
+        self.machine.vreg(Class::Xmm)
+    }
+
+    fn ty(&self, value: &Value) -> Type {
+        self.function.value_type(value)
+    }
+
+    // Copy the parameters used out of where they are passed, and the
+    // argument registers to the register save area if `va_start` reads the
+    // arguments after them.
+    fn params(&mut self, used: &[bool]) {
+        let signature = &self.function.signature;
+        let va_start = self.function.insts.iter()
+            .any(|inst| matches!(inst.kind, InstKind::VaStart(_)));
+        if signature.variadic && va_start {
+            let slot = self.machine.slot(SAVE_AREA as u64, 16);
+            let area = Mem::new(Base::Slot(slot));
+            for (index, &reg) in abi::INT_ARGS.iter().enumerate() {
+                self.store_reg(area.offset(8 * index as i64), Reg::Gp(reg));
+            }
+            for index in 0..abi::FLOAT_ARGS {
+                let offset = FLOAT_AREA + 16 * i64::from(index);
+                self.store_reg(area.offset(offset), Reg::Xmm(index));
+            }
+            self.save_area = Some(area);
+        }
+        let call = abi::call(&signature.params, &signature.ret);
+        if call.sret {
+            let sret = self.gp();
+            self.emit(Insn::Mov(Size::Q, Operand::Reg(sret),
+                Operand::Reg(Reg::Gp(gp::RDI))));
+            self.sret = Some(sret);
+        }
+        let params = signature.params.iter().zip(call.args).zip(used);
+        for ((ty, location), &used) in params {
+            let lowered = match location {
+                _ if !used => Lowered::None,
+                Location::Regs(regs) if ty.is_aggregate() => {
+                    let value = value_slot(&mut self.machine, ty);
+                    if let Lowered::Memory(ref mem) = value {
+                        for (index, reg) in regs.into_iter().enumerate() {
+                            let mem = mem.offset(8 * index as i64);
+                            self.store_reg(mem, reg);
+                        }
+                    }
+                    value
+                }
+                Location::Regs(regs) => {
+                    let reg = self.machine.vreg(class(ty));
+                    self.copy(reg, regs[0]);
+                    Lowered::Reg(reg)
+                }
+                Location::Stack(offset) => {
+                    let mem = Mem { base: Base::Incoming, disp: offset as i32 };
+                    match ty.is_aggregate() {
+                        true => Lowered::Memory(mem),
+                        false => {
+                            let reg = self.machine.vreg(class(ty));
+                            self.load(reg, ty, mem);
+                            Lowered::Reg(reg)
+                        }
+                    }
+                }
+            };
+            self.params.push(lowered);
+        }
+    }
+
+    // Copy a register to another of the same class.
+    fn copy(&mut self, dst: Reg, src: Reg) {
+        match dst {
+            Reg::Xmm(_) => self.emit(Insn::MovAps(dst, src)),
+            Reg::Virt(v) if self.machine.vregs[v] == Class::Xmm => {
+                self.emit(Insn::MovAps(dst, src))
+            }
+            _ => self.emit(Insn::Mov(Size::Q, Operand::Reg(dst),
+                Operand::Reg(src))),
+        }
+    }
+
+    // Store a whole register.
+    fn store_reg(&mut self, mem: Mem, reg: Reg) {
+        match reg {
+            Reg::Xmm(_) => self.emit(Insn::MovF(Size::Q, Operand::Mem(mem),
+                Operand::Reg(reg))),
+            _ => self.emit(Insn::Mov(Size::Q, Operand::Mem(mem),
+                Operand::Reg(reg))),
+        }
+    }
+
+    // Load a scalar of a type to a register.
+    fn load(&mut self, dst: Reg, ty: &Type, mem: Mem) {
+        let size = Size::of(ty);
+        let insn = match ty.is_float() {
+            true => Insn::MovF(size, Operand::Reg(dst), Operand::Mem(mem)),
+            false if size < Size::D => {
+                Insn::MovZx(size, Size::D, dst, Operand::Mem(mem))
+            }
+            false => Insn::Mov(size, Operand::Reg(dst), Operand::Mem(mem)),
+        };
+        self.emit(insn);
+    }
+
+    // How a value is in the machine code.
+    fn lowered(&self, value: &Value) -> Lowered {
+        match *value {
+            Value::Inst(inst) => match self.function.insts[inst].kind {
+                InstKind::PtrAdd(ref base, Value::Int(_, offset)) => {
+                    match (self.lowered(base), i32::try_from(offset)) {
+                        (Lowered::Addr(mem), Ok(offset)) => {
+                            Lowered::Addr(mem.offset(i64::from(offset)))
+                        }
+                        _ => self.values[inst].clone(),
+                    }
+                }
+                _ => self.values[inst].clone(),
+            },
+            Value::Param(index) => self.params[index].clone(),
+            Value::Global(ref name) if is_defined(self.module, name) => {
+                Lowered::Addr(Mem::new(Base::Symbol(name.clone())))
+            }
+            Value::Global(ref name) => Lowered::Got(name.clone()),
+            _ => Lowered::None,
+        }
+    }
+
+    // An integer or pointer value as an operand of an instruction of a
+    // size: a register, or an immediate.
+    fn operand(&mut self, value: &Value, size: Size) -> Operand {
+        match *value {
+            Value::Int(_, int) if fits(size, int) => {
+                return Operand::Imm(int as i64);
+            }
+            Value::Int(_, int) => {
+                let reg = self.gp();
+                self.emit(Insn::MovAbs(reg, int as i64));
+                return Operand::Reg(reg);
+            }
+            Value::Null | Value::Undef(_) | Value::Zero(_) => {
+                return Operand::Imm(0);
+            }
+            _ => {}
+        }
+        match self.lowered(value) {
+            Lowered::Reg(reg) => Operand::Reg(reg),
+            Lowered::Addr(mem) => {
+                let reg = self.gp();
+                self.emit(Insn::Lea(reg, mem));
+                Operand::Reg(reg)
+            }
+            Lowered::Got(name) => {
+                let reg = self.gp();
+                self.emit(Insn::Mov(Size::Q, Operand::Reg(reg),
+                    Operand::Mem(Mem::new(Base::Got(name)))));
+                Operand::Reg(reg)
+            }
+            Lowered::Memory(_) | Lowered::None => unreachable!(),
+        }
+    }
+
+    // An integer or pointer value in a register.
+    fn reg(&mut self, value: &Value, size: Size) -> Reg {
+        match self.operand(value, size) {
+            Operand::Reg(reg) => reg,
+            imm => {
+                let reg = self.gp();
+                self.emit(Insn::Mov(wide(size), Operand::Reg(reg), imm));
+                reg
+            }
+        }
+    }
+
+    // A float value in a register.
+    fn float(&mut self, value: &Value, size: Size) -> Reg {
+        let bits = match *value {
+            Value::Float(_, bits) if size == Size::D => {
+                i64::from((f64::from_bits(bits) as f32).to_bits() as i32)
+            }
+            Value::Float(_, bits) => bits as i64,
+            Value::Inst(_) | Value::Param(_) => {
+                match self.lowered(value) {
+                    Lowered::Reg(reg) => return reg,
+                    _ => unreachable!(),
+                }
+            }
+            _ => 0,
+        };
+        let int = self.reg(&Value::Int(Type::I64, i128::from(bits)), size);
+        let reg = self.xmm();
+        self.emit(Insn::MovGpXmm(size, reg, int));
+        reg
+    }
+
+    // A pointer value as an address.
+    fn address(&mut self, value: &Value) -> Mem {
+        match self.lowered(value) {
+            Lowered::Addr(mem) => mem,
+            _ => Mem::reg(self.reg(value, Size::Q)),
+        }
+    }
+
+    // The memory an aggregate value is in.
+    fn memory(&mut self, value: &Value) -> Mem {
+        match self.lowered(value) {
+            Lowered::Memory(mem) => mem,
+            _ => {
+                let ty = self.ty(value);
+                let mem = match value_slot(&mut self.machine, &ty) {
+                    Lowered::Memory(mem) => mem,
+                    _ => unreachable!(),
+                };
+                self.store_const(mem.clone(), &ty, value);
+                mem
+            }
+        }
+    }
+
+    // The register an instruction's value goes in.
+    fn dst(&self, inst: InstId) -> Reg {
+        match self.values[inst] {
+            Lowered::Reg(reg) => reg,
+            _ => unreachable!(),
+        }
+    }
+
+    // The memory an instruction's aggregate value goes in.
+    fn dst_memory(&self, inst: InstId) -> Mem {
+        match self.values[inst] {
+            Lowered::Memory(ref mem) => mem.clone(),
+            _ => unreachable!(),
+        }
+    }
+
+    // Whether an `icmp` only sets the flags for the branch after it.
+    fn is_fused(&self, inst: InstId) -> bool {
+        let block = &self.function.blocks[self.block];
+        self.uses[inst] == 1
+            && block.insts.last() == Some(&inst)
+            && matches!(block.term, Terminator::CondBr(Value::Inst(cond), ..)
+                if cond == inst)
+            && matches!(self.function.insts[inst].kind, InstKind::Icmp(..))
+    }
+
+    fn inst(&mut self, inst: InstId) {
+        let ty = self.function.insts[inst].ty.clone();
+        match self.function.insts[inst].kind {
+            InstKind::Binary(op, ref a, ref b) if op.is_float() => {
+                self.float_binary(inst, op, a, b, &ty);
+            }
+            InstKind::Binary(op, ref a, ref b) => {
+                self.binary(inst, op, a, b, &ty);
+            }
+            InstKind::FNeg(ref a) => {
+                let size = Size::of(&ty);
+                let sign = match size {
+                    Size::D => i128::from(i32::MIN),
+                    _ => i128::from(i64::MIN),
+                };
+                let int = self.reg(&Value::Int(Type::I64, sign), size);
+                let mask = self.xmm();
+                self.emit(Insn::MovGpXmm(size, mask, int));
+                let a = self.float(a, size);
+                let dst = self.dst(inst);
+                self.emit(Insn::MovAps(dst, a));
+                self.emit(Insn::Xorps(dst, mask));
+            }
+            InstKind::Icmp(predicate, ref a, ref b) => {
+                if !self.is_fused(inst) {
+                    let cond = self.compare(predicate, a, b);
+                    let dst = self.dst(inst);
+                    self.emit(Insn::Setcc(cond, dst));
+                }
+            }
+            InstKind::Fcmp(predicate, ref a, ref b) => {
+                let dst = self.dst(inst);
+                self.float_compare(dst, predicate, a, b);
+            }
+            InstKind::Cast(op, ref value) => self.cast(inst, op, value, &ty),
+            InstKind::Select(ref cond, ref then, ref else_) => {
+                self.select(inst, cond, then, else_, &ty);
+            }
+            InstKind::Alloca(_) | InstKind::Phi(_) => {}
+            InstKind::Load(ref address) => {
+                let mem = self.address(address);
+                match self.values[inst].clone() {
+                    Lowered::Memory(dst) => {
+                        self.copy_memory(dst, mem, ty.size());
+                    }
+                    Lowered::Reg(dst) => self.load(dst, &ty, mem),
+                    _ => unreachable!(),
+                }
+            }
+            InstKind::Store(ref value, ref address) => {
+                let mem = self.address(address);
+                let ty = self.ty(value);
+                self.store(mem, &ty, value);
+            }
+            InstKind::PtrAdd(ref base, ref offset) => {
+                if let Lowered::Addr(_) = self.lowered(&Value::Inst(inst)) {
+                    return;
+                }
+                let mem = self.address(base);
+                let dst = self.dst(inst);
+                match *offset {
+                    Value::Int(_, offset) if fits(Size::Q, offset) => {
+                        self.emit(Insn::Lea(dst, mem.offset(offset as i64)));
+                    }
+                    _ => {
+                        self.emit(Insn::Lea(dst, mem));
+                        let offset = self.operand(offset, Size::Q);
+                        self.emit(Insn::Alu(AluOp::Add, Size::Q,
+                            Operand::Reg(dst), offset));
+                    }
+                }
+            }
+            InstKind::Call(ref signature, ref callee, ref args) => {
+                let mut types = signature.params.clone();
+                types.extend(args[types.len()..].iter().map(|a| self.ty(a)));
+                let args: Vec<_> = types.into_iter().zip(args.iter().cloned())
+                    .collect();
+                let callee = match *callee {
+                    Value::Global(ref name) => Callee::Symbol(name.clone()),
+                    ref callee => Callee::Reg(self.reg(callee, Size::Q)),
+                };
+                let result = self.values[inst].clone();
+                self.call(callee, &args, signature.variadic, &ty, result);
+            }
+            InstKind::ExtractValue(ref aggregate, index) => {
+                let aggregate_ty = self.ty(aggregate);
+                let offset = aggregate_ty.offset(index as usize);
+                let mem = self.memory(aggregate).offset(offset as i64);
+                match self.values[inst].clone() {
+                    Lowered::Memory(dst) => {
+                        self.copy_memory(dst, mem, ty.size());
+                    }
+                    Lowered::Reg(dst) => self.load(dst, &ty, mem),
+                    _ => unreachable!(),
+                }
+            }
+            InstKind::InsertValue(ref aggregate, ref value, index) => {
+                let dst = self.dst_memory(inst);
+                match *aggregate {
+                    Value::Inst(_) | Value::Param(_) => {
+                        let mem = self.memory(aggregate);
+                        self.copy_memory(dst.clone(), mem, ty.size());
+                    }
+                    ref value => self.store_const(dst.clone(), &ty, value),
+                }
+                let offset = ty.offset(index as usize);
+                let member_ty = self.ty(value);
+                self.store(dst.offset(offset as i64), &member_ty, value);
+            }
+            InstKind::VaStart(ref va_list) => self.va_start(va_list),
+            InstKind::VaArg(ref va_list) => self.va_arg(inst, va_list, &ty),
+        }
+    }
+
+    // Set up a `va_list` for the arguments after the parameters: the
+    // offsets in the register save area of the registers the first of them
+    // would be passed in, the address of the first passed on the stack, and
+    // the address of the area.
+    fn va_start(&mut self, va_list: &Value) {
+        let signature = &self.function.signature;
+        let call = abi::call(&signature.params, &signature.ret);
+        let list = self.address(va_list);
+        let ints = 8 * i64::from(call.ints);
+        let floats = FLOAT_AREA + 16 * i64::from(call.floats);
+        self.emit(Insn::Mov(Size::D, Operand::Mem(list.clone()),
+            Operand::Imm(ints)));
+        self.emit(Insn::Mov(Size::D, Operand::Mem(list.offset(4)),
+            Operand::Imm(floats)));
+        let stack = self.gp();
+        self.emit(Insn::Lea(stack, Mem {
+            base: Base::Incoming,
+            disp: call.stack as i32,
+        }));
+        self.emit(Insn::Mov(Size::Q, Operand::Mem(list.offset(8)),
+            Operand::Reg(stack)));
+        let area = self.gp();
+        self.emit(Insn::Lea(area, self.save_area.clone().unwrap()));
+        self.emit(Insn::Mov(Size::Q, Operand::Mem(list.offset(16)),
+            Operand::Reg(area)));
+    }
+
+    // Take the next argument of a type from a `va_list`: from the register
+    // save area if the registers it would be passed in are left, or else
+    // from the stack, choosing between the two with conditional moves.
+    fn va_arg(&mut self, inst: InstId, va_list: &Value, ty: &Type) {
+        let list = self.address(va_list);
+        let stack = self.gp();
+        self.emit(Insn::Mov(Size::Q, Operand::Reg(stack),
+            Operand::Mem(list.offset(8))));
+        let next = self.gp();
+        self.emit(Insn::Lea(next, Mem {
+            base: Base::Reg(stack),
+            disp: (ty.size().div_ceil(8) * 8) as i32,
+        }));
+        let classes = match abi::classify(ty) {
+            Some(classes) => classes,
+            None => {
+                self.emit(Insn::Mov(Size::Q, Operand::Mem(list.offset(8)),
+                    Operand::Reg(next)));
+                let dst = self.dst_memory(inst);
+                return self.copy_memory(dst, Mem::reg(stack), ty.size());
+            }
+        };
+
+        // The address of each eightbyte in the register save area and on
+        // the stack, and the offsets past the registers it takes.
+        let offsets = [self.gp(), self.gp()];
+        let area = self.gp();
+        self.emit(Insn::Mov(Size::D, Operand::Reg(offsets[0]),
+            Operand::Mem(list.clone())));
+        self.emit(Insn::Mov(Size::D, Operand::Reg(offsets[1]),
+            Operand::Mem(list.offset(4))));
+        self.emit(Insn::Mov(Size::Q, Operand::Reg(area),
+            Operand::Mem(list.offset(16))));
+        let mut taken = [0, 0];
+        let mut addresses = Vec::new();
+        for (index, class) in classes.iter().enumerate() {
+            let (kind, len) = match class {
+                abi::Class::Int => (0, 8),
+                abi::Class::Float => (1, 16),
+            };
+            let saved = self.gp();
+            self.emit(Insn::Mov(Size::Q, Operand::Reg(saved),
+                Operand::Reg(offsets[kind])));
+            self.emit(Insn::Alu(AluOp::Add, Size::Q, Operand::Reg(saved),
+                Operand::Reg(area)));
+            if taken[kind] != 0 {
+                self.emit(Insn::Alu(AluOp::Add, Size::Q, Operand::Reg(saved),
+                    Operand::Imm(taken[kind])));
+            }
+            taken[kind] += len;
+            let passed = self.gp();
+            self.emit(Insn::Lea(passed, Mem {
+                base: Base::Reg(stack),
+                disp: 8 * index as i32,
+            }));
+            addresses.push((passed, saved));
+        }
+        let mut past = Vec::new();
+        for kind in 0..2 {
+            if taken[kind] != 0 {
+                let reg = self.gp();
+                self.emit(Insn::Lea(reg, Mem {
+                    base: Base::Reg(offsets[kind]),
+                    disp: taken[kind] as i32,
+                }));
+                past.push((kind, reg));
+            }
+        }
+
+        // Whether every register is left, in the flags.
+        let ends = [FLOAT_AREA, SAVE_AREA];
+        let mut left = Vec::new();
+        for &(kind, _) in &past {
+            self.emit(Insn::Alu(AluOp::Cmp, Size::D,
+                Operand::Reg(offsets[kind]),
+                Operand::Imm(ends[kind] - taken[kind])));
+            if past.len() > 1 {
+                let reg = self.gp();
+                self.emit(Insn::Setcc(Cond::Be, reg));
+                left.push(reg);
+            }
+        }
+        let cond = match *left {
+            [a, b] => {
+                self.emit(Insn::Alu(AluOp::And, Size::B, Operand::Reg(a),
+                    Operand::Reg(b)));
+                Cond::Ne
+            }
+            _ => Cond::Be,
+        };
+        for &(passed, saved) in &addresses {
+            self.emit(Insn::Cmov(cond, Size::Q, passed, saved));
+        }
+        for &(kind, reg) in &past {
+            self.emit(Insn::Cmov(cond.invert(), Size::D, reg,
+                offsets[kind]));
+            self.emit(Insn::Mov(Size::D,
+                Operand::Mem(list.offset(4 * kind as i64)),
+                Operand::Reg(reg)));
+        }
+        self.emit(Insn::Cmov(cond, Size::Q, next, stack));
+        self.emit(Insn::Mov(Size::Q, Operand::Mem(list.offset(8)),
+            Operand::Reg(next)));
+
+        match self.values[inst].clone() {
+            Lowered::Memory(dst) => {
+                for (index, &(address, _)) in addresses.iter().enumerate() {
+                    let offset = 8 * index as u64;
+                    let len = (ty.size() - offset).min(8);
+                    self.copy_memory(dst.offset(offset as i64),
+                        Mem::reg(address), len);
+                }
+            }
+            Lowered::Reg(dst) => self.load(dst, ty, Mem::reg(addresses[0].0)),
+            _ => unreachable!(),
+        }
+    }
+
+    fn binary(&mut self, inst: InstId, op: BinaryOp, a: &Value, b: &Value,
+        ty: &Type)
+    {
+        let size = Size::of(ty);
+        let wide = wide(size);
+        let dst = self.dst(inst);
+        match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::And | BinaryOp::Or
+            | BinaryOp::Xor | BinaryOp::Mul =>
+            {
+                let a = self.operand(a, size);
+                let b = match op {
+                    BinaryOp::Mul => Operand::Reg(self.reg(b, size)),
+                    _ => self.operand(b, size),
+                };
+                self.emit(Insn::Mov(wide, Operand::Reg(dst), a));
+                let alu = match op {
+                    BinaryOp::Add => AluOp::Add,
+                    BinaryOp::Sub => AluOp::Sub,
+                    BinaryOp::And => AluOp::And,
+                    BinaryOp::Or => AluOp::Or,
+                    BinaryOp::Xor => AluOp::Xor,
+                    _ => {
+                        self.emit(Insn::Imul(wide, dst, b));
+                        return self.normalize(dst, ty);
+                    }
+                };
+                self.emit(Insn::Alu(alu, wide, Operand::Reg(dst), b));
+                if !matches!(op, BinaryOp::And | BinaryOp::Or | BinaryOp::Xor) {
+                    self.normalize(dst, ty);
+                }
+            }
+            BinaryOp::Shl | BinaryOp::LShr | BinaryOp::AShr => {
+                let (shift, signed) = match op {
+                    BinaryOp::Shl => (ShiftOp::Shl, None),
+                    BinaryOp::LShr => (ShiftOp::Shr, Some(false)),
+                    _ => (ShiftOp::Sar, Some(true)),
+                };
+                match signed {
+                    Some(signed) => self.extend(dst, a, ty, signed),
+                    None => {
+                        let a = self.operand(a, size);
+                        self.emit(Insn::Mov(wide, Operand::Reg(dst), a));
+                    }
+                }
+                match *b {
+                    Value::Int(_, amount) => {
+                        let bits = wide.len() as i128 * 8;
+                        let amount = amount.rem_euclid(bits) as u8;
+                        self.emit(Insn::Shift(shift, wide, dst, Some(amount)));
+                    }
+                    _ => {
+                        let amount = self.operand(b, size);
+                        self.emit(Insn::Mov(Size::D,
+                            Operand::Reg(Reg::Gp(gp::RCX)), amount));
+                        self.emit(Insn::Shift(shift, wide, dst, None));
+                    }
+                }
+                self.normalize(dst, ty);
+            }
+            BinaryOp::SDiv | BinaryOp::SRem | BinaryOp::UDiv
+            | BinaryOp::URem =>
+            {
+                let signed = matches!(op, BinaryOp::SDiv | BinaryOp::SRem);
+                let rax = Reg::Gp(gp::RAX);
+                let rdx = Reg::Gp(gp::RDX);
+                let divisor = self.gp();
+                self.extend(divisor, b, ty, signed);
+                let dividend = self.gp();
+                self.extend(dividend, a, ty, signed);
+                self.emit(Insn::Mov(wide, Operand::Reg(rax),
+                    Operand::Reg(dividend)));
+                match signed {
+                    true => self.emit(Insn::SignExtend(wide)),
+                    false => self.emit(Insn::Mov(Size::D, Operand::Reg(rdx),
+                        Operand::Imm(0))),
+                }
+                self.emit(Insn::Div(signed, wide, divisor));
+                let result = match op {
+                    BinaryOp::SDiv | BinaryOp::UDiv => rax,
+                    _ => rdx,
+                };
+                self.emit(Insn::Mov(wide, Operand::Reg(dst),
+                    Operand::Reg(result)));
+                self.normalize(dst, ty);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // Make an `i1` in a register 0 or 1 again after arithmetic.
+    fn normalize(&mut self, reg: Reg, ty: &Type) {
+        if *ty == Type::I1 {
+            self.emit(Insn::Alu(AluOp::And, Size::D, Operand::Reg(reg),
+                Operand::Imm(1)));
+        }
+    }
+
+    // Extend an integer of a type to at least 32 bits in a register, with
+    // zeros or its sign.
+    fn extend(&mut self, dst: Reg, value: &Value, ty: &Type, signed: bool) {
+        let size = Size::of(ty);
+        let src = self.operand(value, size);
+        if let Operand::Imm(imm) = src {
+            let imm = match *ty {
+                Type::Int(1) if signed => -(imm & 1),
+                Type::Int(bits) if bits < 64 && signed => {
+                    crate::ir::wrap(bits, i128::from(imm)) as i64
+                }
+                Type::Int(bits) if bits < 64 => imm & ((1 << bits) - 1),
+                _ => imm,
+            };
+            let size = match fits(Size::Q, i128::from(imm)) {
+                true => wide(size),
+                false => Size::Q,
+            };
+            return match fits(size, i128::from(imm)) {
+                true => self.emit(Insn::Mov(size, Operand::Reg(dst),
+                    Operand::Imm(imm))),
+                false => self.emit(Insn::MovAbs(dst, imm)),
+            };
+        }
+        match size {
+            Size::B | Size::W if *ty == Type::I1 || !signed => {
+                self.emit(Insn::MovZx(size, Size::D, dst, src));
+                if *ty == Type::I1 && signed {
+                    self.emit(Insn::Neg(Size::D, dst));
+                }
+            }
+            Size::B | Size::W => self.emit(Insn::MovSx(size, Size::D, dst, src)),
+            _ => self.emit(Insn::Mov(size, Operand::Reg(dst), src)),
+        }
+    }
+
+    fn float_binary(&mut self, inst: InstId, op: BinaryOp, a: &Value,
+        b: &Value, ty: &Type)
+    {
+        let size = Size::of(ty);
+        let op = match op {
+            BinaryOp::FAdd => FloatOp::Add,
+            BinaryOp::FSub => FloatOp::Sub,
+            BinaryOp::FMul => FloatOp::Mul,
+            BinaryOp::FDiv => FloatOp::Div,
+            _ => {
+                let name = match size {
+                    Size::D => "fmodf",
+                    _ => "fmod",
+                };
+                let args = [(ty.clone(), a.clone()), (ty.clone(), b.clone())];
+                let result = self.values[inst].clone();
+                let callee = Callee::Symbol(name.to_string());
+                return self.call(callee, &args, false, ty, result);
+            }
+        };
+        let a = self.float(a, size);
+        let b = self.float(b, size);
+        let dst = self.dst(inst);
+        self.emit(Insn::MovAps(dst, a));
+        self.emit(Insn::FloatOp(op, size, dst, Operand::Reg(b)));
+    }
+
+    // Compare integers or pointers, returning the condition of the flags
+    // that is the predicate.
+    fn compare(&mut self, predicate: IntPredicate, a: &Value, b: &Value)
+        -> Cond
+    {
+        let ty = self.ty(a);
+        let size = Size::of(&ty);
+        let (mut predicate, a, b) = match a.is_const() && !b.is_const() {
+            true => (swapped(predicate), b, a),
+            false => (predicate, a, b),
+        };
+        // The `i1` 1 is -1 when signed.
+        if ty == Type::I1 {
+            predicate = match predicate {
+                IntPredicate::Slt => IntPredicate::Ugt,
+                IntPredicate::Sle => IntPredicate::Uge,
+                IntPredicate::Sgt => IntPredicate::Ult,
+                IntPredicate::Sge => IntPredicate::Ule,
+                predicate => predicate,
+            };
+        }
+        let a = self.reg(a, size);
+        let b = self.operand(b, size);
+        self.emit(Insn::Alu(AluOp::Cmp, size, Operand::Reg(a), b));
+        cond(predicate)
+    }
+
+    fn float_compare(&mut self, dst: Reg, predicate: FloatPredicate,
+        a: &Value, b: &Value)
+    {
+        let size = Size::of(&self.ty(a));
+        let a = self.float(a, size);
+        let b = self.float(b, size);
+        let (a, b, cond) = match predicate {
+            FloatPredicate::Oeq | FloatPredicate::Ueq => (a, b, Cond::E),
+            FloatPredicate::One | FloatPredicate::Une => (a, b, Cond::Ne),
+            FloatPredicate::Ogt => (a, b, Cond::A),
+            FloatPredicate::Oge => (a, b, Cond::Ae),
+            FloatPredicate::Olt => (b, a, Cond::A),
+            FloatPredicate::Ole => (b, a, Cond::Ae),
+        };
+        self.emit(Insn::Ucomi(size, a, b));
+        self.emit(Insn::Setcc(cond, dst));
+        // Unordered operands set the zero flag, as if equal.
+        let (parity, op) = match predicate {
+            FloatPredicate::Oeq => (Cond::Np, AluOp::And),
+            FloatPredicate::Une => (Cond::P, AluOp::Or),
+            _ => return,
+        };
+        let other = self.gp();
+        self.emit(Insn::Setcc(parity, other));
+        self.emit(Insn::Alu(op, Size::B, Operand::Reg(dst),
+            Operand::Reg(other)));
+    }
+
+    fn cast(&mut self, inst: InstId, op: CastOp, value: &Value, ty: &Type) {
+        let from = self.ty(value);
+        let from_size = Size::of(&from);
+        let size = Size::of(ty);
+        if ty.is_aggregate() {
+            let mem = self.memory(value);
+            let dst = self.dst_memory(inst);
+            return self.copy_memory(dst, mem, ty.size());
+        }
+        let dst = self.dst(inst);
+        match op {
+            CastOp::Trunc | CastOp::PtrToInt | CastOp::IntToPtr
+                if from_size >= size =>
+            {
+                let src = self.operand(value, from_size);
+                self.emit(Insn::Mov(wide(size), Operand::Reg(dst), src));
+                self.normalize(dst, ty);
+            }
+            CastOp::ZExt | CastOp::IntToPtr => {
+                self.extend(dst, value, &from, false);
+            }
+            CastOp::SExt => {
+                self.extend(dst, value, &from, true);
+                if from_size < Size::Q && size == Size::Q {
+                    self.emit(Insn::MovSx(Size::D.max(from_size), Size::Q, dst,
+                        Operand::Reg(dst)));
+                }
+            }
+            CastOp::FpTrunc | CastOp::FpExt => {
+                let src = self.float(value, from_size);
+                self.emit(Insn::FloatToFloat(size, dst, src));
+            }
+            CastOp::FpToSi | CastOp::FpToUi if size < Size::Q
+                || op == CastOp::FpToSi =>
+            {
+                // Unsigned 32-bit integers are the low half of the 64-bit
+                // signed ones.
+                let int_size = match (op, size) {
+                    (CastOp::FpToUi, Size::D) | (_, Size::Q) => Size::Q,
+                    _ => Size::D,
+                };
+                let src = self.float(value, from_size);
+                self.emit(Insn::FloatToInt(from_size, int_size, dst, src));
+                self.normalize(dst, ty);
+            }
+            CastOp::FpToUi => {
+                // Past 2^63, convert less 2^63 and set the top bit back.
+                let src = self.float(value, from_size);
+                let limit = Value::float(from.clone(), 9_223_372_036_854_775_808.0);
+                let limit = self.float(&limit, from_size);
+                let high = self.xmm();
+                self.emit(Insn::MovAps(high, src));
+                self.emit(Insn::FloatOp(FloatOp::Sub, from_size, high,
+                    Operand::Reg(limit)));
+                let high_int = self.gp();
+                self.emit(Insn::FloatToInt(from_size, Size::Q, high_int, high));
+                let top = self.reg(&Value::Int(Type::I64,
+                    i128::from(i64::MIN)), Size::Q);
+                self.emit(Insn::Alu(AluOp::Xor, Size::Q,
+                    Operand::Reg(high_int), Operand::Reg(top)));
+                self.emit(Insn::FloatToInt(from_size, Size::Q, dst, src));
+                self.emit(Insn::Ucomi(from_size, src, limit));
+                self.emit(Insn::Cmov(Cond::Ae, Size::Q, dst, high_int));
+            }
+            CastOp::SiToFp => {
+                let int = self.gp();
+                self.extend(int, value, &from, true);
+                let int_size = from_size.max(Size::D);
+                self.emit(Insn::IntToFloat(int_size, size, dst, int));
+            }
+            CastOp::UiToFp if from_size < Size::Q => {
+                let int = self.gp();
+                self.extend(int, value, &from, false);
+                self.emit(Insn::IntToFloat(Size::Q, size, dst, int));
+            }
+            CastOp::UiToFp => {
+                // Past 2^63, halve keeping the lowest bit for rounding,
+                // convert and double.
+                let int = self.reg(value, Size::Q);
+                let half = self.gp();
+                self.emit(Insn::Mov(Size::Q, Operand::Reg(half),
+                    Operand::Reg(int)));
+                self.emit(Insn::Shift(ShiftOp::Shr, Size::Q, half, Some(1)));
+                let low = self.gp();
+                self.emit(Insn::Mov(Size::D, Operand::Reg(low),
+                    Operand::Reg(int)));
+                self.emit(Insn::Alu(AluOp::And, Size::D, Operand::Reg(low),
+                    Operand::Imm(1)));
+                self.emit(Insn::Alu(AluOp::Or, Size::Q, Operand::Reg(half),
+                    Operand::Reg(low)));
+                let input = self.gp();
+                self.emit(Insn::Mov(Size::Q, Operand::Reg(input),
+                    Operand::Reg(int)));
+                self.emit(Insn::Test(Size::Q, int, int));
+                self.emit(Insn::Cmov(Cond::S, Size::Q, input, half));
+                let converted = self.xmm();
+                self.emit(Insn::IntToFloat(Size::Q, size, converted, input));
+                let doubled = self.xmm();
+                self.emit(Insn::MovAps(doubled, converted));
+                self.emit(Insn::FloatOp(FloatOp::Add, size, doubled,
+                    Operand::Reg(converted)));
+                let bits = self.gp();
+                self.emit(Insn::MovGpXmm(size, bits, converted));
+                let doubled_bits = self.gp();
+                self.emit(Insn::MovGpXmm(size, doubled_bits, doubled));
+                self.emit(Insn::Test(Size::Q, int, int));
+                self.emit(Insn::Cmov(Cond::S, Size::Q, bits, doubled_bits));
+                self.emit(Insn::MovGpXmm(size, dst, bits));
+            }
+            CastOp::Bitcast if from.is_float() != ty.is_float() => {
+                let src = match from.is_float() {
+                    true => self.float(value, from_size),
+                    false => self.reg(value, from_size),
+                };
+                self.emit(Insn::MovGpXmm(size, dst, src));
+            }
+            CastOp::Bitcast if ty.is_float() => {
+                let src = self.float(value, from_size);
+                self.emit(Insn::MovAps(dst, src));
+            }
+            _ => {
+                let src = self.operand(value, from_size);
+                self.emit(Insn::Mov(wide(size), Operand::Reg(dst), src));
+            }
+        }
+    }
+
+    fn select(&mut self, inst: InstId, cond: &Value, then: &Value,
+        else_: &Value, ty: &Type)
+    {
+        if let Value::Int(_, cond) = *cond {
+            let value = match cond {
+                0 => else_,
+                _ => then,
+            };
+            return self.copy_value(inst, value, ty);
+        }
+        let size = Size::of(ty);
+        let cond = self.reg(cond, Size::B);
+        if ty.is_aggregate() {
+            let then = self.memory(then);
+            let else_ = self.memory(else_);
+            let (address, other) = (self.gp(), self.gp());
+            self.emit(Insn::Lea(address, else_));
+            self.emit(Insn::Lea(other, then));
+            self.emit(Insn::Test(Size::B, cond, cond));
+            self.emit(Insn::Cmov(Cond::Ne, Size::Q, address, other));
+            let dst = self.dst_memory(inst);
+            return self.copy_memory(dst, Mem::reg(address), ty.size());
+        }
+        let dst = self.dst(inst);
+        if ty.is_float() {
+            let then = self.float(then, size);
+            let else_ = self.float(else_, size);
+            let (bits, other) = (self.gp(), self.gp());
+            self.emit(Insn::MovGpXmm(size, bits, else_));
+            self.emit(Insn::MovGpXmm(size, other, then));
+            self.emit(Insn::Test(Size::B, cond, cond));
+            self.emit(Insn::Cmov(Cond::Ne, Size::Q, bits, other));
+            self.emit(Insn::MovGpXmm(size, dst, bits));
+        } else {
+            let then = self.reg(then, size);
+            let else_ = self.operand(else_, size);
+            self.emit(Insn::Mov(wide(size), Operand::Reg(dst), else_));
+            self.emit(Insn::Test(Size::B, cond, cond));
+            self.emit(Insn::Cmov(Cond::Ne, wide(size), dst, then));
+        }
+    }
+
+    // Copy a value to where an instruction's value goes.
+    fn copy_value(&mut self, inst: InstId, value: &Value, ty: &Type) {
+        match self.values[inst].clone() {
+            Lowered::Memory(dst) => {
+                let mem = self.memory(value);
+                self.copy_memory(dst, mem, ty.size());
+            }
+            Lowered::Reg(dst) => self.copy_to(dst, value, ty),
+            _ => unreachable!(),
+        }
+    }
+
+    // Copy a scalar value to a register.
+    fn copy_to(&mut self, dst: Reg, value: &Value, ty: &Type) {
+        let size = Size::of(ty);
+        match ty.is_float() {
+            true => {
+                let src = self.float(value, size);
+                self.emit(Insn::MovAps(dst, src));
+            }
+            false => match self.operand(value, size) {
+                Operand::Imm(imm) if fits(Size::Q, imm.into()) || size != Size::Q => {
+                    self.emit(Insn::Mov(wide(size), Operand::Reg(dst),
+                        Operand::Imm(imm)));
+                }
+                src => self.emit(Insn::Mov(wide(size), Operand::Reg(dst), src)),
+            },
+        }
+    }
+
+    // Store a value of a type to memory.
+    fn store(&mut self, mem: Mem, ty: &Type, value: &Value) {
+        let size = Size::of(ty);
+        if value.is_const() {
+            return self.store_const(mem, ty, value);
+        }
+        match self.lowered(value) {
+            Lowered::Memory(src) => self.copy_memory(mem, src, ty.size()),
+            _ if ty.is_float() => {
+                let src = self.float(value, size);
+                self.emit(Insn::MovF(size, Operand::Mem(mem),
+                    Operand::Reg(src)));
+            }
+            _ => {
+                let src = self.operand(value, size);
+                self.emit(Insn::Mov(size, Operand::Mem(mem), src));
+            }
+        }
+    }
+
+    // Store a constant of a type to memory.
+    fn store_const(&mut self, mem: Mem, ty: &Type, value: &Value) {
+        match *value {
+            Value::Undef(_) => {}
+            Value::Zero(_) => self.fill_zero(mem, ty.size()),
+            Value::Aggregate(_, ref members) => {
+                for (index, member) in members.iter().enumerate() {
+                    let member_ty = ty.member(index as u32).unwrap();
+                    let offset = ty.offset(index) as i64;
+                    self.store_const(mem.offset(offset), member_ty, member);
+                }
+            }
+            Value::Bytes(ref bytes) => {
+                let mut offset = 0;
+                for size in [Size::Q, Size::D, Size::W, Size::B] {
+                    let len = size.len() as usize;
+                    while bytes.len() - offset >= len {
+                        let mut chunk = [0; 8];
+                        chunk[..len].copy_from_slice(&bytes[offset..][..len]);
+                        let int = i64::from_le_bytes(chunk);
+                        let int = Value::Int(Type::I64, i128::from(int));
+                        let src = self.operand(&int, size);
+                        self.emit(Insn::Mov(size, Operand::Mem(
+                            mem.offset(offset as i64)), src));
+                        offset += len;
+                    }
+                }
+            }
+            Value::Float(_, bits) => {
+                let size = Size::of(ty);
+                let int = match size {
+                    Size::D => {
+                        i128::from((f64::from_bits(bits) as f32).to_bits() as i32)
+                    }
+                    _ => i128::from(bits as i64),
+                };
+                let src = self.operand(&Value::Int(Type::I64, int), size);
+                self.emit(Insn::Mov(size, Operand::Mem(mem), src));
+            }
+            ref value => {
+                let size = Size::of(ty);
+                let src = self.operand(value, size);
+                self.emit(Insn::Mov(size, Operand::Mem(mem), src));
+            }
+        }
+    }
+
+    // Set bytes of memory to zero.
+    fn fill_zero(&mut self, mem: Mem, len: u64) {
+        if len > INLINE_COPY {
+            self.emit(Insn::Lea(Reg::Gp(gp::RDI), mem));
+            self.emit(Insn::Mov(Size::Q, Operand::Reg(Reg::Gp(gp::RCX)),
+                Operand::Imm(len as i64)));
+            self.emit(Insn::Mov(Size::D, Operand::Reg(Reg::Gp(gp::RAX)),
+                Operand::Imm(0)));
+            return self.emit(Insn::RepStosb);
+        }
+        for (offset, size) in chunks(len) {
+            self.emit(Insn::Mov(size, Operand::Mem(mem.offset(offset)),
+                Operand::Imm(0)));
+        }
+    }
+
+    // Copy bytes of memory.
+    fn copy_memory(&mut self, dst: Mem, src: Mem, len: u64) {
+        if len > INLINE_COPY {
+            self.emit(Insn::Lea(Reg::Gp(gp::RSI), src));
+            self.emit(Insn::Lea(Reg::Gp(gp::RDI), dst));
+            self.emit(Insn::Mov(Size::Q, Operand::Reg(Reg::Gp(gp::RCX)),
+                Operand::Imm(len as i64)));
+            return self.emit(Insn::RepMovsb);
+        }
+        for (offset, size) in chunks(len) {
+            let reg = self.gp();
+            self.emit(Insn::Mov(size, Operand::Reg(reg),
+                Operand::Mem(src.offset(offset))));
+            self.emit(Insn::Mov(size, Operand::Mem(dst.offset(offset)),
+                Operand::Reg(reg)));
+        }
+    }
+
+    // Call a function with arguments of types, putting its value where
+    // `result` says.
+    fn call(&mut self, callee: Callee, args: &[(Type, Value)],
+        variadic: bool, ret: &Type, result: Lowered)
+    {
+        let types: Vec<_> = args.iter().map(|(ty, _)| ty.clone()).collect();
+        let call = abi::call(&types, ret);
+        self.machine.outgoing = self.machine.outgoing.max(call.stack);
+
+        // Arguments on the stack go first, as copying them may use the
+        // registers of the others.
+        let mut loads = Vec::new();
+        for ((ty, value), location) in args.iter().zip(&call.args) {
+            match *location {
+                Location::Stack(offset) => {
+                    let mem = Mem { base: Base::Outgoing, disp: offset as i32 };
+                    match ty.is_aggregate() {
+                        true => {
+                            let src = self.memory(value);
+                            self.copy_memory(mem, src, ty.size());
+                        }
+                        false => self.store(mem, ty, value),
+                    }
+                }
+                Location::Regs(ref regs) if ty.is_aggregate() => {
+                    let mem = self.memory(value);
+                    for (index, &reg) in regs.iter().enumerate() {
+                        let mem = mem.offset(8 * index as i64);
+                        loads.push(match reg {
+                            Reg::Xmm(_) => Insn::MovF(Size::Q,
+                                Operand::Reg(reg), Operand::Mem(mem)),
+                            _ => Insn::Mov(Size::Q, Operand::Reg(reg),
+                                Operand::Mem(mem)),
+                        });
+                    }
+                }
+                Location::Regs(ref regs) if ty.is_float() => {
+                    let src = self.float(value, Size::of(ty));
+                    loads.push(Insn::MovAps(regs[0], src));
+                }
+                Location::Regs(ref regs) => {
+                    let size = Size::of(ty);
+                    let insn = match self.lowered(value) {
+                        Lowered::Addr(mem) => Insn::Lea(regs[0], mem),
+                        _ => {
+                            let src = self.operand(value, size);
+                            Insn::Mov(wide(size), Operand::Reg(regs[0]), src)
+                        }
+                    };
+                    loads.push(insn);
+                }
+            }
+        }
+        let mut uses: Vec<Reg> = call.args.iter()
+            .flat_map(|location| match location {
+                Location::Regs(regs) => regs.clone(),
+                Location::Stack(_) => Vec::new(),
+            })
+            .collect();
+        if call.sret {
+            let mem = match result {
+                Lowered::Memory(ref mem) => mem.clone(),
+                _ => unreachable!(),
+            };
+            loads.push(Insn::Lea(Reg::Gp(gp::RDI), mem));
+            uses.push(Reg::Gp(gp::RDI));
+        }
+        if variadic {
+            loads.push(Insn::Mov(Size::D, Operand::Reg(Reg::Gp(gp::RAX)),
+                Operand::Imm(i64::from(call.floats))));
+            uses.push(Reg::Gp(gp::RAX));
+        }
+        for insn in loads {
+            self.emit(insn);
+        }
+        self.emit(Insn::Call(callee, uses));
+
+        match (result, call.ret) {
+            (Lowered::Memory(mem), Some(regs)) => {
+                for (index, reg) in regs.into_iter().enumerate() {
+                    self.store_reg(mem.offset(8 * index as i64), reg);
+                }
+            }
+            (Lowered::Reg(dst), Some(regs)) => self.copy(dst, regs[0]),
+            _ => {}
+        }
+    }
+
+    // Copy the values of the `phi`s of a block for the edge from the
+    // current block.
+    fn phi_copies(&mut self, succ: BlockId) {
+        let mut copies = Vec::new();
+        for &inst in &self.function.blocks[succ].insts {
+            match self.function.insts[inst].kind {
+                InstKind::Phi(ref incoming) => {
+                    let value = incoming.iter()
+                        .find(|(pred, _)| *pred == self.block)
+                        .map(|(_, value)| value.clone())
+                        .unwrap();
+                    copies.push((inst, value));
+                }
+                _ => break,
+            }
+        }
+        // A `phi` read by another's copy must keep its value until then.
+        let overlap = copies.len() > 1 && copies.iter().any(|(_, value)| {
+            copies.iter().any(|(phi, _)| *value == Value::Inst(*phi))
+        });
+        let mut temporaries = Vec::new();
+        for (phi, value) in copies {
+            let ty = self.function.insts[phi].ty.clone();
+            if !overlap {
+                self.copy_value(phi, &value, &ty);
+                continue;
+            }
+            let temporary = match ty.is_aggregate() {
+                true => value_slot(&mut self.machine, &ty),
+                false => Lowered::Reg(self.machine.vreg(class(&ty))),
+            };
+            match temporary {
+                Lowered::Memory(ref dst) => {
+                    let mem = self.memory(&value);
+                    self.copy_memory(dst.clone(), mem, ty.size());
+                }
+                Lowered::Reg(dst) => self.copy_to(dst, &value, &ty),
+                _ => unreachable!(),
+            }
+            temporaries.push((phi, temporary, ty));
+        }
+        for (phi, temporary, ty) in temporaries {
+            match (self.values[phi].clone(), temporary) {
+                (Lowered::Memory(dst), Lowered::Memory(src)) => {
+                    self.copy_memory(dst, src, ty.size());
+                }
+                (Lowered::Reg(dst), Lowered::Reg(src)) => self.copy(dst, src),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    fn terminator(&mut self) {
+        match self.function.blocks[self.block].term.clone() {
+            Terminator::Br(target) => {
+                self.phi_copies(target);
+                self.emit(Insn::Jmp(target));
+            }
+            Terminator::CondBr(Value::Int(_, cond), then, else_) => {
+                let target = match cond {
+                    0 => else_,
+                    _ => then,
+                };
+                self.emit(Insn::Jmp(target));
+            }
+            Terminator::CondBr(Value::Inst(inst), then, else_)
+                if self.is_fused(inst) =>
+            {
+                let (predicate, a, b) = match self.function.insts[inst].kind {
+                    InstKind::Icmp(predicate, ref a, ref b) => {
+                        (predicate, a.clone(), b.clone())
+                    }
+                    _ => unreachable!(),
+                };
+                let cond = self.compare(predicate, &a, &b);
+                self.emit(Insn::Jcc(cond, then));
+                self.emit(Insn::Jmp(else_));
+            }
+            Terminator::CondBr(cond, then, else_) => {
+                let cond = self.reg(&cond, Size::B);
+                self.emit(Insn::Test(Size::B, cond, cond));
+                self.emit(Insn::Jcc(Cond::Ne, then));
+                self.emit(Insn::Jmp(else_));
+            }
+            Terminator::Switch(_, default, ref cases) if cases.is_empty() => {
+                self.phi_copies(default);
+                self.emit(Insn::Jmp(default));
+            }
+            Terminator::Switch(value, default, cases) => {
+                let size = Size::of(&self.ty(&value));
+                let reg = self.reg(&value, size);
+                for (case, target) in cases {
+                    let case = self.operand(&Value::Int(Type::I64, case), size);
+                    self.emit(Insn::Alu(AluOp::Cmp, size, Operand::Reg(reg),
+                        case));
+                    self.emit(Insn::Jcc(Cond::E, target));
+                }
+                self.emit(Insn::Jmp(default));
+            }
+            Terminator::Ret(value) => self.ret(value),
+            Terminator::Unreachable => self.emit(Insn::Ud2),
+        }
+    }
+
+    fn ret(&mut self, value: Option<Value>) {
+        let signature = &self.function.signature;
+        let call = abi::call(&[], &signature.ret);
+        let ty = signature.ret.clone();
+        let mut uses = Vec::new();
+        match (value, call.ret) {
+            (Some(value), Some(regs)) if ty.is_aggregate() => {
+                let mem = self.memory(&value);
+                for (index, &reg) in regs.iter().enumerate() {
+                    let mem = mem.offset(8 * index as i64);
+                    self.emit(match reg {
+                        Reg::Xmm(_) => Insn::MovF(Size::Q, Operand::Reg(reg),
+                            Operand::Mem(mem)),
+                        _ => Insn::Mov(Size::Q, Operand::Reg(reg),
+                            Operand::Mem(mem)),
+                    });
+                }
+                uses = regs;
+            }
+            (Some(value), Some(regs)) => {
+                self.copy_to(regs[0], &value, &ty);
+                uses = regs;
+            }
+            (Some(value), None) if call.sret => {
+                let sret = self.sret.unwrap();
+                let mem = self.memory(&value);
+                self.copy_memory(Mem::reg(sret), mem, ty.size());
+                let rax = Reg::Gp(gp::RAX);
+                self.emit(Insn::Mov(Size::Q, Operand::Reg(rax),
+                    Operand::Reg(sret)));
+                uses.push(rax);
+            }
+            _ => {}
+        }
+        self.emit(Insn::Return(uses));
+    }
+}
+
+// The offsets and sizes of the moves copying bytes.
+fn chunks(len: u64) -> Vec<(i64, Size)> {
+    let mut chunks = Vec::new();
+    let mut offset = 0;
+    for size in [Size::Q, Size::D, Size::W, Size::B] {
+        while len - offset >= size.len() {
+            chunks.push((offset as i64, size));
+            offset += size.len();
+        }
+    }
+    chunks
+}
@@ -0,0 +1,543 @@
+// Register allocation
+//
+//! Assigns physical registers to virtual ones by linear scan.
+//!
+//! The instructions are numbered in block order, a virtual register being
+//! read at twice an instruction's number and written one after, and each
+//! is live over the span from the first to the last position it is live
+//! at, as found from the liveness of the blocks.  The physical registers
+//! instruction selection uses, for calls, returns and instructions with
+//! fixed operands, are only live within a block, and a virtual register
+//! doesn't get one while it is.
+//!
+//! Spans are given registers in the order they start, caller-saved ones
+//! first.  When none is free, the span ending last is spilled to a stack
+//! slot, and `r10`, `r11`, `xmm14` and `xmm15` are used to load and store
+//! its value around each instruction using it.
+
+use std::collections::HashMap;
+
+use super::{
+    gp, Base, Callee, Class, Insn, MachineFunction, Mem, Operand, Reg, Size,
+    CALLEE_SAVED,
+};
+
+// The registers spans are given, in the order they are tried.
+const GP: &[u8] = &[
+    gp::RAX, gp::RCX, gp::RDX, gp::RSI, gp::RDI, gp::R8, gp::R9, gp::RBX,
+    gp::R12, gp::R13, gp::R14, gp::R15,
+];
+const XMM: u8 = 14;
+// The registers spilled values are loaded into.
+const GP_SCRATCH: &[u8] = &[gp::R11, gp::R10];
+const XMM_SCRATCH: &[u8] = &[15, 14];
+
+/// The registers an instruction reads and writes.
+pub(super) fn operands(insn: &Insn) -> (Vec<Reg>, Vec<Reg>) {
+    let mut uses = Vec::new();
+    let mut defs = Vec::new();
+    let mem = |uses: &mut Vec<Reg>, mem: &Mem| {
+        if let Base::Reg(reg) = mem.base {
+            uses.push(reg);
+        }
+    };
+    let operand = |uses: &mut Vec<Reg>, operand: &Operand| match operand {
+        Operand::Reg(reg) => uses.push(*reg),
+        Operand::Mem(m) => mem(uses, m),
+        Operand::Imm(_) => {}
+    };
+    let rax = Reg::Gp(gp::RAX);
+    let rcx = Reg::Gp(gp::RCX);
+    let rdx = Reg::Gp(gp::RDX);
+    let rsi = Reg::Gp(gp::RSI);
+    let rdi = Reg::Gp(gp::RDI);
+    match insn {
+        Insn::Mov(_, dst, src) | Insn::MovF(_, dst, src) => {
+            operand(&mut uses, src);
+            match dst {
+                Operand::Reg(reg) => defs.push(*reg),
+                dst => operand(&mut uses, dst),
+            }
+        }
+        Insn::MovAbs(reg, _) => defs.push(*reg),
+        Insn::MovZx(_, _, reg, src) | Insn::MovSx(_, _, reg, src) => {
+            operand(&mut uses, src);
+            defs.push(*reg);
+        }
+        Insn::Lea(reg, m) => {
+            mem(&mut uses, m);
+            defs.push(*reg);
+        }
+        Insn::Alu(op, _, dst, src) => {
+            operand(&mut uses, src);
+            operand(&mut uses, dst);
+            if let (Operand::Reg(reg), false) = (dst, *op == super::AluOp::Cmp)
+            {
+                defs.push(*reg);
+            }
+        }
+        Insn::Test(_, a, b) | Insn::Ucomi(_, a, b) => {
+            uses.extend([*a, *b]);
+        }
+        Insn::Imul(_, reg, src) | Insn::FloatOp(_, _, reg, src) => {
+            operand(&mut uses, src);
+            uses.push(*reg);
+            defs.push(*reg);
+        }
+        Insn::Shift(_, _, reg, amount) => {
+            uses.push(*reg);
+            if amount.is_none() {
+                uses.push(rcx);
+            }
+            defs.push(*reg);
+        }
+        Insn::Neg(_, reg) => {
+            uses.push(*reg);
+            defs.push(*reg);
+        }
+        Insn::SignExtend(_) => {
+            uses.push(rax);
+            defs.push(rdx);
+        }
+        Insn::Div(_, _, reg) => {
+            uses.extend([*reg, rax, rdx]);
+            defs.extend([rax, rdx]);
+        }
+        Insn::Setcc(_, reg) => defs.push(*reg),
+        Insn::Cmov(_, _, dst, src) | Insn::Xorps(dst, src) => {
+            uses.extend([*dst, *src]);
+            defs.push(*dst);
+        }
+        Insn::MovAps(dst, src)
+        | Insn::IntToFloat(_, _, dst, src)
+        | Insn::FloatToInt(_, _, dst, src)
+        | Insn::FloatToFloat(_, dst, src)
+        | Insn::MovGpXmm(_, dst, src) => {
+            uses.push(*src);
+            defs.push(*dst);
+        }
+        Insn::RepMovsb => {
+            uses.extend([rdi, rsi, rcx]);
+            defs.extend([rdi, rsi, rcx]);
+        }
+        Insn::RepStosb => {
+            uses.extend([rdi, rcx, rax]);
+            defs.extend([rdi, rcx]);
+        }
+        Insn::Call(callee, args) => {
+            if let Callee::Reg(reg) = callee {
+                uses.push(*reg);
+            }
+            uses.extend(args);
+            defs.extend(super::CALLER_SAVED.iter().map(|&reg| Reg::Gp(reg)));
+            defs.extend((0..16).map(Reg::Xmm));
+        }
+        Insn::Return(regs) => uses.extend(regs),
+        Insn::Push(reg) => uses.push(*reg),
+        Insn::Pop(reg) => defs.push(*reg),
+        Insn::Jmp(_) | Insn::Jcc(..) | Insn::Ret | Insn::Ud2 => {}
+    }
+    (uses, defs)
+}
+
+// Every register an instruction refers to.
+fn regs_mut(insn: &mut Insn) -> Vec<&mut Reg> {
+    fn mem(mem: &mut Mem) -> Option<&mut Reg> {
+        match mem.base {
+            Base::Reg(ref mut reg) => Some(reg),
+            _ => None,
+        }
+    }
+    fn operand(operand: &mut Operand) -> Option<&mut Reg> {
+        match operand {
+            Operand::Reg(reg) => Some(reg),
+            Operand::Mem(m) => mem(m),
+            Operand::Imm(_) => None,
+        }
+    }
+    match insn {
+        Insn::Mov(_, a, b) | Insn::MovF(_, a, b) | Insn::Alu(_, _, a, b) => {
+            operand(a).into_iter().chain(operand(b)).collect()
+        }
+        Insn::MovZx(_, _, reg, src)
+        | Insn::MovSx(_, _, reg, src)
+        | Insn::Imul(_, reg, src)
+        | Insn::FloatOp(_, _, reg, src) => {
+            let mut regs = vec![reg];
+            regs.extend(operand(src));
+            regs
+        }
+        Insn::Lea(reg, m) => {
+            let mut regs = vec![reg];
+            regs.extend(mem(m));
+            regs
+        }
+        Insn::Test(_, a, b)
+        | Insn::Ucomi(_, a, b)
+        | Insn::Cmov(_, _, a, b)
+        | Insn::Xorps(a, b)
+        | Insn::MovAps(a, b)
+        | Insn::IntToFloat(_, _, a, b)
+        | Insn::FloatToInt(_, _, a, b)
+        | Insn::FloatToFloat(_, a, b)
+        | Insn::MovGpXmm(_, a, b) => vec![a, b],
+        Insn::MovAbs(reg, _)
+        | Insn::Shift(_, _, reg, _)
+        | Insn::Neg(_, reg)
+        | Insn::Div(_, _, reg)
+        | Insn::Setcc(_, reg)
+        | Insn::Push(reg)
+        | Insn::Pop(reg) => vec![reg],
+        Insn::Call(Callee::Reg(reg), _) => vec![reg],
+        _ => Vec::new(),
+    }
+}
+
+// Where a virtual register ended up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Assignment {
+    Reg(Reg),
+    Spilled(usize),
+}
+
+// The span of positions a virtual register is live over.
+#[derive(Debug, Clone, Copy)]
+struct Interval {
+    vreg: usize,
+    start: usize,
+    end: usize,
+}
+
+// The spans each physical register is live or written in.
+type Fixed = HashMap<Reg, Vec<(usize, usize)>>;
+
+/// Replace the virtual registers of a function with physical ones.
+pub(super) fn allocate(function: &mut MachineFunction) {
+    let (intervals, fixed) = live_ranges(function);
+    let assignments = scan(function, intervals, &fixed);
+    rewrite(function, &assignments);
+}
+
+// The span of each virtual register, and the spans each physical register
+// is live or written in.
+fn live_ranges(function: &MachineFunction)
+    -> (Vec<Interval>, Fixed)
+{
+    let blocks = &function.blocks;
+    let mut starts = Vec::with_capacity(blocks.len());
+    let mut position = 0;
+    for block in blocks {
+        starts.push(position);
+        position += 2 * block.len();
+    }
+
+    // Virtual registers read before written, and written, in each block.
+    let count = function.vregs.len();
+    let mut gen = vec![vec![false; count]; blocks.len()];
+    let mut kill = vec![vec![false; count]; blocks.len()];
+    for (block, insns) in blocks.iter().enumerate() {
+        for insn in insns {
+            let (uses, defs) = operands(insn);
+            for reg in uses {
+                if let Reg::Virt(v) = reg {
+                    if !kill[block][v] {
+                        gen[block][v] = true;
+                    }
+                }
+            }
+            for reg in defs {
+                if let Reg::Virt(v) = reg {
+                    kill[block][v] = true;
+                }
+            }
+        }
+    }
+    let succs: Vec<Vec<usize>> = blocks.iter()
+        .map(|insns| {
+            insns.iter()
+                .filter_map(|insn| match *insn {
+                    Insn::Jmp(target) | Insn::Jcc(_, target) => Some(target),
+                    _ => None,
+                })
+                .collect()
+        })
+        .collect();
+    let mut live_in = gen.clone();
+    let mut live_out = vec![vec![false; count]; blocks.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in (0..blocks.len()).rev() {
+            for &succ in &succs[block] {
+                for v in 0..count {
+                    if live_in[succ][v] && !live_out[block][v] {
+                        live_out[block][v] = true;
+                        if !kill[block][v] && !live_in[block][v] {
+                            live_in[block][v] = true;
+                        }
+                        changed = true;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut spans: Vec<Option<(usize, usize)>> = vec![None; count];
+    let mut extend = |v: usize, position: usize| {
+        let span = spans[v].get_or_insert((position, position));
+        span.0 = span.0.min(position);
+        span.1 = span.1.max(position);
+    };
+    let mut fixed: Fixed = HashMap::new();
+    for (block, insns) in blocks.iter().enumerate() {
+        let start = starts[block];
+        let end = start + 2 * insns.len();
+        for v in 0..count {
+            if live_in[block][v] {
+                extend(v, start);
+            }
+            if live_out[block][v] {
+                extend(v, end.saturating_sub(1));
+            }
+        }
+        // Physical registers, from their last read back to the write.
+        let mut live: HashMap<Reg, usize> = HashMap::new();
+        for (index, insn) in insns.iter().enumerate().rev() {
+            let position = start + 2 * index;
+            let (uses, defs) = operands(insn);
+            for reg in defs {
+                match reg {
+                    Reg::Virt(v) => extend(v, position + 1),
+                    reg => {
+                        let last = live.remove(&reg).unwrap_or(position + 1);
+                        fixed.entry(reg).or_default().push((position + 1, last));
+                    }
+                }
+            }
+            for reg in uses {
+                match reg {
+                    Reg::Virt(v) => extend(v, position),
+                    reg => {
+                        live.entry(reg).or_insert(position);
+                    }
+                }
+            }
+        }
+        for (reg, last) in live {
+            fixed.entry(reg).or_default().push((start, last));
+        }
+    }
+
+    let mut intervals: Vec<_> = spans.into_iter().enumerate()
+        .filter_map(|(vreg, span)| {
+            span.map(|(start, end)| Interval { vreg, start, end })
+        })
+        .collect();
+    intervals.sort_by_key(|interval| (interval.start, interval.vreg));
+    (intervals, fixed)
+}
+
+// Give each span a register, or a stack slot.
+fn scan(function: &mut MachineFunction, intervals: Vec<Interval>,
+    fixed: &Fixed) -> Vec<Option<Assignment>>
+{
+    let is_free = |reg: Reg, interval: &Interval| {
+        fixed.get(&reg).is_none_or(|ranges| {
+            ranges.iter().all(|&(start, end)| {
+                end < interval.start || start > interval.end
+            })
+        })
+    };
+    let mut assignments = vec![None; function.vregs.len()];
+    let mut active: Vec<(Interval, Reg)> = Vec::new();
+    for interval in intervals {
+        active.retain(|(other, _)| other.end >= interval.start);
+        let class = function.vregs[interval.vreg];
+        let candidates: Vec<Reg> = match class {
+            Class::Gp => GP.iter().map(|&reg| Reg::Gp(reg)).collect(),
+            Class::Xmm => (0..XMM).map(Reg::Xmm).collect(),
+        };
+        let free = candidates.into_iter().find(|&reg| {
+            is_free(reg, &interval)
+                && active.iter().all(|&(_, other)| other != reg)
+        });
+        if let Some(reg) = free {
+            assignments[interval.vreg] = Some(Assignment::Reg(reg));
+            active.push((interval, reg));
+            continue;
+        }
+        // Spill whichever ends last, of this span and those whose register
+        // it could have.
+        let victim = active.iter().enumerate()
+            .filter(|(_, (other, reg))| {
+                function.vregs[other.vreg] == class && is_free(*reg, &interval)
+            })
+            .max_by_key(|(_, (other, _))| other.end)
+            .map(|(index, &(other, reg))| (index, other, reg));
+        match victim {
+            Some((index, other, reg)) if other.end > interval.end => {
+                let slot = function.slot(8, 8);
+                assignments[other.vreg] = Some(Assignment::Spilled(slot));
+                active[index] = (interval, reg);
+                assignments[interval.vreg] = Some(Assignment::Reg(reg));
+            }
+            _ => {
+                let slot = function.slot(8, 8);
+                assignments[interval.vreg] = Some(Assignment::Spilled(slot));
+            }
+        }
+    }
+    assignments
+}
+
+// Replace virtual registers with their assignments, loading and storing
+// those spilled.
+fn rewrite(function: &mut MachineFunction,
+    assignments: &[Option<Assignment>])
+{
+    let assignment = |reg: Reg| match reg {
+        Reg::Virt(v) => assignments[v],
+        reg => Some(Assignment::Reg(reg)),
+    };
+    let vregs = function.vregs.clone();
+    for block in &mut function.blocks {
+        let insns = std::mem::take(block);
+        for mut insn in insns {
+            if let Some(insn) = spilled_mov(&insn, &assignment) {
+                block.push(insn);
+                continue;
+            }
+            let (uses, defs) = operands(&insn);
+            let mut scratch: Vec<(Reg, usize, Reg)> = Vec::new();
+            for reg in regs_mut(&mut insn) {
+                let v = match *reg {
+                    Reg::Virt(v) => v,
+                    _ => continue,
+                };
+                match assignments[v] {
+                    Some(Assignment::Reg(phys)) => *reg = phys,
+                    Some(Assignment::Spilled(slot)) => {
+                        let known = scratch.iter()
+                            .find(|&&(virt, _, _)| virt == Reg::Virt(v));
+                        let phys = match known {
+                            Some(&(_, _, phys)) => phys,
+                            None => {
+                                let class = vregs[v];
+                                let taken = scratch.iter()
+                                    .filter(|&&(virt, _, _)| {
+                                        virt_class(&vregs, virt) == class
+                                    })
+                                    .count();
+                                let phys = match class {
+                                    Class::Gp => Reg::Gp(GP_SCRATCH[taken]),
+                                    Class::Xmm => Reg::Xmm(XMM_SCRATCH[taken]),
+                                };
+                                scratch.push((Reg::Virt(v), slot, phys));
+                                phys
+                            }
+                        };
+                        *reg = phys;
+                    }
+                    // Never written or read
+                    None => {}
+                }
+            }
+            for &(virt, slot, phys) in &scratch {
+                if uses.contains(&virt) {
+                    block.push(load(phys, slot));
+                }
+            }
+            match insn {
+                // A copy to itself, but for a 32-bit one clearing the top
+                Insn::Mov(Size::Q, Operand::Reg(dst), Operand::Reg(src))
+                | Insn::MovAps(dst, src)
+                    if dst == src && scratch.is_empty() => {}
+                insn => block.push(insn),
+            }
+            for &(virt, slot, phys) in &scratch {
+                if defs.contains(&virt) {
+                    block.push(store(slot, phys));
+                }
+            }
+        }
+    }
+}
+
+fn virt_class(vregs: &[Class], reg: Reg) -> Class {
+    match reg {
+        Reg::Virt(v) => vregs[v],
+        Reg::Xmm(_) => Class::Xmm,
+        Reg::Gp(_) => Class::Gp,
+    }
+}
+
+fn slot_mem(slot: usize) -> Mem {
+    Mem::new(Base::Slot(slot))
+}
+
+fn load(reg: Reg, slot: usize) -> Insn {
+    match reg {
+        Reg::Xmm(_) => {
+            Insn::MovF(Size::Q, Operand::Reg(reg), Operand::Mem(slot_mem(slot)))
+        }
+        _ => Insn::Mov(Size::Q, Operand::Reg(reg), Operand::Mem(slot_mem(slot))),
+    }
+}
+
+fn store(slot: usize, reg: Reg) -> Insn {
+    match reg {
+        Reg::Xmm(_) => {
+            Insn::MovF(Size::Q, Operand::Mem(slot_mem(slot)), Operand::Reg(reg))
+        }
+        _ => Insn::Mov(Size::Q, Operand::Mem(slot_mem(slot)), Operand::Reg(reg)),
+    }
+}
+
+// A move to or from a spilled register done with its slot, rather than
+// through a scratch register.
+fn spilled_mov(insn: &Insn, assignment: &dyn Fn(Reg) -> Option<Assignment>)
+    -> Option<Insn>
+{
+    let (size, dst, src) = match *insn {
+        Insn::Mov(size, Operand::Reg(dst), ref src) => (size, dst, src),
+        _ => return None,
+    };
+    match (assignment(dst)?, src) {
+        // Only a whole register written to its slot is all it would hold.
+        (Assignment::Spilled(slot), &Operand::Reg(src)) if size == Size::Q => {
+            match assignment(src)? {
+                Assignment::Reg(src) => Some(Insn::Mov(size,
+                    Operand::Mem(slot_mem(slot)), Operand::Reg(src))),
+                Assignment::Spilled(_) => None,
+            }
+        }
+        (Assignment::Spilled(slot), &Operand::Imm(imm)) if size == Size::Q => {
+            Some(Insn::Mov(size, Operand::Mem(slot_mem(slot)),
+                Operand::Imm(imm)))
+        }
+        (Assignment::Reg(dst), &Operand::Reg(src)) => {
+            match assignment(src)? {
+                Assignment::Spilled(slot) => Some(Insn::Mov(size,
+                    Operand::Reg(dst), Operand::Mem(slot_mem(slot)))),
+                Assignment::Reg(_) => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The callee-saved registers a function writes.
+pub(super) fn callee_saved(function: &MachineFunction) -> Vec<u8> {
+    let mut saved = Vec::new();
+    for insn in function.blocks.iter().flatten() {
+        for reg in operands(insn).1 {
+            if let Reg::Gp(reg) = reg {
+                if CALLEE_SAVED.contains(&reg) && !saved.contains(&reg) {
+                    saved.push(reg);
+                }
+            }
+        }
+    }
+    saved.sort_unstable();
+    saved
+}
@@ -0,0 +1,174 @@
+// Aratar front end tests
+
+#![cfg(feature = "aratar")]
+
+use compiler::aratar::{parse_module, Op, Token, TokenIterator};
+
+// The tokens of `text`, displayed, with errors as `error: message`.
+fn tokens(text: &str) -> Vec<String> {
+    TokenIterator::new(text)
+        .map(|token| match token {
+            Ok(token) => token.to_string(),
+            Err(error) => format!("error: {}", error.message),
+        })
+        .collect()
+}
+
+#[test]
+fn definitions() {
+    let text = "def main:\n    let pos: $Vec4\n\n    out color: view * pos\n";
+    assert_eq!(tokens(text), [
+        "def", "main", ":", "newline",
+        "indent", "let", "pos", ":", "$Vec4", "newline",
+        "out", "color", ":", "view", "*", "pos", "newline",
+        "dedent",
+    ]);
+    assert_eq!(tokens("def f:\n  var x: $Int1; out y: x"), [
+        "def", "f", ":", "newline",
+        "indent", "var", "x", ":", "$Int1", ";", "out", "y", ":", "x",
+        "newline", "dedent",
+    ]);
+}
+
+#[test]
+fn comments() {
+    let text = "#a# let a: $Vec2 # b #; let c: a\n  # only a comment\nd\n";
+    assert_eq!(tokens(text), [
+        "comment", "let", "a", ":", "$Vec2", "comment", ";", "let", "c",
+        ":", "a", "newline",
+        "comment",
+        "d", "newline",
+    ]);
+    let comments: Vec<_> = TokenIterator::new("x # one # y #two\n")
+        .filter_map(|token| match token {
+            Ok(Token::Comment(text)) => Some(text),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(comments, [" one ", "two"]);
+}
+
+#[test]
+fn indentation() {
+    let text = "def a:\n    def b:\n        let x: $Vec1\n    let y: $Vec1\nz\n";
+    assert_eq!(tokens(text), [
+        "def", "a", ":", "newline",
+        "indent", "def", "b", ":", "newline",
+        "indent", "let", "x", ":", "$Vec1", "newline",
+        "dedent", "let", "y", ":", "$Vec1", "newline",
+        "dedent", "z", "newline",
+    ]);
+}
+
+#[test]
+fn bad_indentation() {
+    let errors = |text| {
+        tokens(text).into_iter()
+            .filter(|token| token.starts_with("error: "))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(errors("def a:\n    b\n  c\n"),
+        ["error: unindent does not match any outer indentation level"]);
+    assert_eq!(errors("def a:\n\tb\n"),
+        ["error: tabs can't be used for indentation"]);
+}
+
+#[test]
+fn numbers_and_operators() {
+    assert_eq!(tokens("a: (1 + 2.5) / b.c % -3\n"), [
+        "a", ":", "(", "1", "+", "2.5", ")", "/", "b", ".", "c", "%", "-",
+        "3", "newline",
+    ]);
+    assert_eq!(tokens("x: (a +\n    b)\n"), [
+        "x", ":", "(", "a", "+", "b", ")", "newline",
+    ]);
+    assert_eq!(tokens("4x")[0], "error: invalid number literal");
+    assert_eq!(tokens("99999999999999999999")[0],
+        "error: integer literal is too large");
+}
+
+#[test]
+fn errors() {
+    assert_eq!(tokens("a $ b")[1],
+        "error: expected the name of a type after `$`");
+    assert_eq!(tokens("a = b")[1], "error: unexpected character `=`");
+    assert_eq!(tokens("[a]")[0], "error: unexpected character `[`");
+    assert_eq!(tokens("a)")[1], "error: unmatched ')'");
+    assert_eq!(tokens("(a"), ["(", "a", "error: '(' was never closed"]);
+    assert_eq!(tokens("é")[0], "error: invalid character 'é' (U+00E9)");
+}
+
+// The program formatted back to source, or its error as `error: message`.
+fn parse(text: &str) -> String {
+    match parse_module(text) {
+        Ok(module) => module.to_string(),
+        Err(error) => format!("error: {}", error.message),
+    }
+}
+
+#[test]
+fn bindings() {
+    assert_eq!(parse("def main:\n    let pos: $Vec4; var n: $Int1\n        \
+        view: $View\n    out color: -view pos * 2 + n % (a - 1.5)\n"),
+        "def main:\n    let pos: $Vec4\n    var n: $Int1\n    var view: \
+        $View\n    out color: (((-(view pos)) * 2) + (n % (a - 1.5)))\n");
+    assert_eq!(parse("out a: b # c #\n  d: e f g\n"),
+        "out a: b\nout d: ((e f) g)\n");
+}
+
+#[test]
+fn syntax_errors() {
+    assert_eq!(parse("let a $Vec4\n"), "error: expected `:`, found `$Vec4`");
+    assert_eq!(parse("let a: b c: d\n"),
+        "error: expected `;` or newline, found `:`");
+    assert_eq!(parse("out a: $Vec4\n"),
+        "error: an input can only be the value of `let` or `var`");
+    assert_eq!(parse("let a: b * $Vec4\n"),
+        "error: an input must be the whole value of `let` or `var`");
+    assert_eq!(parse("def f:\nlet a: $Vec1\n"),
+        "error: expected an indented block after `def`");
+    assert_eq!(parse("let a: b\n    def f:\n"),
+        "error: expected name, found `def`");
+    assert_eq!(parse("x: y\n"),
+        "error: expected `def`, `let`, `var` or `out`, found `x`");
+    assert_eq!(parse("out a: b.c\n"),
+        "error: function operators aren't supported yet");
+    assert_eq!(parse("out a: (b\n"), "error: '(' was never closed");
+}
+
+#[test]
+fn ops() {
+    let module = parse_module("def f:\n    let pos: $Vec4\n    var view: \
+        $View\n    let moved: view pos\n    out position: view * (pos + \
+        moved)\n    out color: pos\n").unwrap();
+    let text = |text: &str| text.to_string();
+    assert_eq!(module.to_ops().unwrap(), [
+        Op::Function { name: text("f") },
+        Op::InLet { name: text("pos"), def: text("Vec4") },
+        Op::InVar { name: text("view"), def: text("View") },
+        Op::Call {
+            name: text("moved"),
+            var_a: text("view"),
+            operator: text(""),
+            var_b: text("pos"),
+        },
+        Op::Call {
+            name: text("%1"),
+            var_a: text("pos"),
+            operator: text("+"),
+            var_b: text("moved"),
+        },
+        Op::Call {
+            name: text(""),
+            var_a: text("view"),
+            operator: text("*"),
+            var_b: text("%1"),
+        },
+        Op::Out { name: text("position"), def: text(""), from: text("") },
+        Op::Out { name: text("color"), def: text(""), from: text("pos") },
+    ]);
+    let error = |text| parse_module(text).unwrap().to_ops().unwrap_err();
+    assert_eq!(error("let a: b * 2\n").message, "a number has no operation");
+    assert_eq!(error("let a: b\n").message,
+        "a copy of a variable has no operation");
+}
@@ -0,0 +1,76 @@
+// Borrow checker corpus
+//
+// Every sample in `tests/borrowck/pass` is accepted by stable Rust, and must
+// be accepted.  Every sample in `tests/borrowck/fail` is rejected by stable
+// Rust, and must be rejected with the errors its `// error: ` comments list
+// on the lines they are reported on.
+
+#![cfg(feature = "rust")]
+
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use compiler::rust::resolve::{Crate, Sources};
+use compiler::rust::{borrowck, typeck};
+
+// The samples in a directory of the corpus, sorted.
+fn samples(dir: &str) -> Vec<PathBuf> {
+    common::samples(&format!("tests/borrowck/{}", dir), |ext| ext == "rs")
+}
+
+// Borrow check a sample, returning the line and message of each error.
+fn check(path: &Path) -> Vec<(usize, String)> {
+    let text = fs::read_to_string(path).unwrap();
+    let sources = Sources::single(path, text.clone());
+    let render = |errors: Vec<_>| {
+        errors.iter().map(|error| sources.render(error)).collect::<String>()
+    };
+    let krate = Crate::new(&sources).unwrap_or_else(|errors| {
+        panic!("{}: doesn't resolve\n{}", path.display(), render(errors))
+    });
+    let types = typeck::check(&krate).unwrap_or_else(|errors| {
+        panic!("{}: doesn't type check\n{}", path.display(), render(errors))
+    });
+    match borrowck::check(&krate, &types) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors
+            .iter()
+            .map(|error| {
+                let span = sources.file(error.file)
+                    .origin(error.diagnostic.span);
+                let line = text[..span.start].matches('\n').count() + 1;
+                (line, error.diagnostic.message.clone())
+            })
+            .collect(),
+    }
+}
+
+// The errors listed by the comments of a sample.
+fn expected(path: &Path) -> Vec<(usize, String)> {
+    let text = fs::read_to_string(path).unwrap();
+    text.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let (_, message) = line.split_once("// error: ")?;
+            Some((i + 1, message.to_string()))
+        })
+        .collect()
+}
+
+#[test]
+fn accepts() {
+    for path in samples("pass") {
+        assert_eq!(check(&path), Vec::new(), "{}", path.display());
+    }
+}
+
+#[test]
+fn rejects() {
+    for path in samples("fail") {
+        let expected = expected(&path);
+        assert!(!expected.is_empty(), "{}: no errors listed", path.display());
+        assert_eq!(check(&path), expected, "{}", path.display());
+    }
+}
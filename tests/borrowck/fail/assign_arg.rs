@@ -0,0 +1,8 @@
+fn reset(x: i32) -> i32 {
+    x = 0; // error: cannot assign to immutable argument `x`
+    x
+}
+
+fn main() {
+    reset(1);
+}
@@ -0,0 +1,6 @@
+fn main() {
+    let x = 1;
+    println!("{}", x);
+    x = 2; // error: cannot assign twice to immutable variable `x`
+    println!("{}", x);
+}
@@ -0,0 +1,4 @@
+fn main() {
+    let v = vec![1];
+    v.push(2); // error: cannot borrow `v` as mutable, as it is not declared as mutable
+}
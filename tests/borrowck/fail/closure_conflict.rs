@@ -0,0 +1,6 @@
+fn main() {
+    let mut count = 0;
+    let mut increment = || count += 1;
+    println!("{}", count); // error: cannot borrow `count` as immutable because it is also borrowed as mutable
+    increment();
+}
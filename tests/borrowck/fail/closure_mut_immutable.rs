@@ -0,0 +1,5 @@
+fn main() {
+    let count = 0;
+    let mut increment = || count += 1; // error: cannot assign to `count`, as it is not declared as mutable
+    increment();
+}
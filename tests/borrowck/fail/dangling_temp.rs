@@ -0,0 +1,5 @@
+fn main() {
+    let r: &String;
+    r = &String::from("a"); // error: temporary value dropped while borrowed
+    println!("{}", r);
+}
@@ -0,0 +1,13 @@
+// Deriving `Clone` without `Copy` leaves a type moved by assignment.
+
+#[derive(Debug, Clone)]
+struct Buffer {
+    data: Vec<u8>,
+}
+
+fn main() {
+    let a = Buffer { data: vec![1, 2, 3] };
+    let b = a.clone();
+    let c = a;
+    println!("{:?} {:?} {:?}", a, b, c); // error: borrow of moved value: `a`
+}
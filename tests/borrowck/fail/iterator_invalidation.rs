@@ -0,0 +1,8 @@
+fn main() {
+    let mut v = vec![1, 2, 3];
+    for x in &v {
+        if *x == 2 {
+            v.push(4); // error: cannot borrow `v` as mutable because it is also borrowed as immutable
+        }
+    }
+}
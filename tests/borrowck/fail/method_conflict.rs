@@ -0,0 +1,20 @@
+struct Stack {
+    items: Vec<i32>,
+}
+
+impl Stack {
+    fn top(&self) -> &i32 {
+        &self.items[0]
+    }
+
+    fn push(&mut self, item: i32) {
+        self.items.push(item);
+    }
+}
+
+fn main() {
+    let mut stack = Stack { items: vec![1] };
+    let top = stack.top();
+    stack.push(2); // error: cannot borrow `stack` as mutable because it is also borrowed as immutable
+    println!("{}", top);
+}
@@ -0,0 +1,5 @@
+fn main() {
+    let v = vec![String::from("a")];
+    let s = v[0]; // error: cannot move out of index of `Vec<String>`
+    println!("{}", s);
+}
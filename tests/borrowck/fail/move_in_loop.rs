@@ -0,0 +1,8 @@
+fn consume(_s: String) {}
+
+fn main() {
+    let s = String::from("a");
+    for _ in 0..2 {
+        consume(s); // error: use of moved value: `s`
+    }
+}
@@ -0,0 +1,11 @@
+struct Holder {
+    name: String,
+}
+
+fn take(holder: &Holder) -> String {
+    holder.name // error: cannot move out of `holder.name` which is behind a shared reference
+}
+
+fn main() {
+    take(&Holder { name: String::new() });
+}
@@ -0,0 +1,8 @@
+fn consume(_s: String) {}
+
+fn main() {
+    let s = String::from("a");
+    let r = &s;
+    consume(s); // error: cannot move out of `s` because it is borrowed
+    println!("{}", r);
+}
@@ -0,0 +1,8 @@
+fn main() {
+    let opt = Some(String::from("a"));
+    match opt {
+        Some(s) => println!("{}", s),
+        None => {}
+    }
+    println!("{:?}", opt); // error: borrow of partially moved value: `opt`
+}
@@ -0,0 +1,6 @@
+fn main() {
+    let s = String::from("a");
+    let f = move || s.len();
+    f();
+    println!("{}", s); // error: borrow of moved value: `s`
+}
@@ -0,0 +1,6 @@
+fn main() {
+    let mut v = vec![1, 2, 3];
+    let first = &v[0];
+    v.push(4); // error: cannot borrow `v` as mutable because it is also borrowed as immutable
+    println!("{}", first);
+}
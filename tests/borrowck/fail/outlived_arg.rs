@@ -0,0 +1,18 @@
+struct Picker;
+
+impl Picker {
+    // The result borrows from `x`, not from `self`.
+    fn pick<'b>(&self, x: &'b String) -> &'b str {
+        x.as_str()
+    }
+}
+
+fn main() {
+    let p = Picker;
+    let r;
+    {
+        let s = String::from("short");
+        r = p.pick(&s); // error: `s` does not live long enough
+    }
+    println!("{}", r);
+}
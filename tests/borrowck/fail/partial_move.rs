@@ -0,0 +1,5 @@
+fn main() {
+    let pair = (String::from("a"), String::from("b"));
+    let first = pair.0;
+    println!("{} {:?}", first, pair); // error: borrow of partially moved value: `pair`
+}
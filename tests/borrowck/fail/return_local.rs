@@ -0,0 +1,8 @@
+fn dangle() -> &'static String {
+    let s = String::from("a");
+    &s // error: cannot return reference to local variable `s`
+}
+
+fn main() {
+    dangle();
+}
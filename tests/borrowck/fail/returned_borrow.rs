@@ -0,0 +1,11 @@
+fn first(v: &mut Vec<i32>) -> &mut i32 {
+    &mut v[0]
+}
+
+fn main() {
+    let mut v = vec![1, 2];
+    let a = first(&mut v);
+    let b = first(&mut v); // error: cannot borrow `v` as mutable more than once at a time
+    *a += 1;
+    *b += 1;
+}
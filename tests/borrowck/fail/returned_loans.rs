@@ -0,0 +1,68 @@
+// The body of a function decides what the result of a call borrows.
+
+struct Owner {
+    s: String,
+}
+
+impl Owner {
+    fn get(&self) -> &str {
+        &self.s
+    }
+
+    fn name(&self) -> &'static str {
+        "owner"
+    }
+}
+
+struct Parsed<'a> {
+    s: &'a str,
+}
+
+impl<'a> Parsed<'a> {
+    fn new(s: &'a str) -> Self {
+        Parsed { s }
+    }
+
+    fn get(&self) -> &'a str {
+        self.s
+    }
+}
+
+fn id<T>(x: T) -> T {
+    x
+}
+
+fn longest<'a>(a: &'a str, b: &'a str) -> &'a str {
+    if a.len() > b.len() { a } else { b }
+}
+
+fn main() {
+    let o = Owner { s: String::from("x") };
+    let g = o.get();
+    drop(o); // error: cannot move out of `o` because it is borrowed
+    println!("{}", g);
+
+    let o = Owner { s: String::from("x") };
+    let n = o.name();
+    drop(o);
+    println!("{}", n);
+
+    let text = String::from("t");
+    let p = Parsed::new(&text);
+    let g = p.get();
+    drop(text); // error: cannot move out of `text` because it is borrowed
+    println!("{}", g);
+
+    let text = String::from("t");
+    let r = id(&text);
+    drop(text); // error: cannot move out of `text` because it is borrowed
+    println!("{}", r);
+
+    let a = String::from("a");
+    let r;
+    {
+        let b = String::from("b");
+        r = longest(&a, &b); // error: `b` does not live long enough
+    }
+    println!("{}", r);
+}
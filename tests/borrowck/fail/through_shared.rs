@@ -0,0 +1,7 @@
+fn bump(x: &i32) {
+    *x += 1; // error: cannot assign to `*x`, which is behind a `&` reference
+}
+
+fn main() {
+    bump(&1);
+}
@@ -0,0 +1,7 @@
+fn main() {
+    let mut v = vec![1];
+    let a = &mut v;
+    let b = &mut v; // error: cannot borrow `v` as mutable more than once at a time
+    a.push(1);
+    b.push(2);
+}
@@ -0,0 +1,8 @@
+fn main() {
+    let x: i32;
+    let flag = true;
+    if flag {
+        x = 1;
+    }
+    println!("{}", x); // error: used binding `x` is possibly-uninitialized
+}
@@ -0,0 +1,7 @@
+fn consume(_s: String) {}
+
+fn main() {
+    let s = String::from("a");
+    consume(s);
+    println!("{}", s); // error: borrow of moved value: `s`
+}
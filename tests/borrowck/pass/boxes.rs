@@ -0,0 +1,34 @@
+// Boxes own their contents, which can be moved out of and into.
+
+enum Tree {
+    Leaf(i32),
+    Node(Box<Tree>, Box<Tree>),
+}
+
+fn sum(tree: &Tree) -> i32 {
+    match tree {
+        Tree::Leaf(value) => *value,
+        Tree::Node(left, right) => sum(left) + sum(right),
+    }
+}
+
+fn mirror(tree: Tree) -> Tree {
+    match tree {
+        Tree::Leaf(value) => Tree::Leaf(value),
+        Tree::Node(left, right) => Tree::Node(
+            Box::new(mirror(*right)), Box::new(mirror(*left))),
+    }
+}
+
+fn main() {
+    let boxed = Box::new(String::from("a"));
+    let inner = *boxed;
+    println!("{}", inner);
+    let mut b = Box::new(5);
+    *b += 1;
+    let r = &mut *b;
+    *r += 1;
+    let tree = Tree::Node(Box::new(Tree::Leaf(1)), Box::new(Tree::Leaf(2)));
+    let tree = mirror(tree);
+    println!("{} {}", sum(&tree), b);
+}
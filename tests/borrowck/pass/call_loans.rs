@@ -0,0 +1,50 @@
+// A call holds the loans its function's body returns of its arguments: a
+// reference read out of `self` borrows what `self` borrows, not `self`.
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn advance(&mut self) {
+        self.pos += 1;
+    }
+}
+
+struct Holder<'a> {
+    inner: &'a Parser<'a>,
+}
+
+impl<'a> Holder<'a> {
+    fn parser(&self) -> &'a Parser<'a> {
+        self.inner
+    }
+}
+
+fn pick<'a>(first: &'a str, _second: &str) -> &'a str {
+    first
+}
+
+fn main() {
+    let text = String::from("abc");
+    let mut p = Parser { text: &text, pos: 0 };
+    let r = p.rest();
+    p.advance();
+    println!("{} {}", r, p.rest());
+
+    let q = Parser { text: &text, pos: 1 };
+    let mut h = Holder { inner: &q };
+    let parser = h.parser();
+    h = Holder { inner: &q };
+    println!("{} {}", parser.rest(), h.inner.pos);
+
+    let mut other = String::from("x");
+    let s = pick(&text, &other);
+    other.push('y');
+    println!("{} {}", s, other);
+}
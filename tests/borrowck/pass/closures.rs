@@ -0,0 +1,33 @@
+// Closures borrow what they capture until their last call.
+
+fn apply<F: FnMut()>(mut f: F) {
+    f();
+    f();
+}
+
+fn main() {
+    let mut count = 0;
+    let mut increment = || count += 1;
+    increment();
+    increment();
+    println!("{}", count);
+
+    let names = vec![String::from("a")];
+    let has = |name: &str| names.iter().any(|n| n == name);
+    let found = has("a") && !has("b");
+    println!("{} {}", found, names.len());
+
+    let mut total = 0;
+    apply(|| total += 2);
+    println!("{}", total);
+
+    let owned = String::from("moved");
+    let print = move || println!("{}", owned);
+    print();
+    print();
+
+    let mut values = vec![3, 1, 2];
+    values.sort_by(|a, b| a.cmp(b));
+    let max = values.iter().copied().fold(0, |max, v| if v > max { v } else { max });
+    println!("{}", max);
+}
@@ -0,0 +1,39 @@
+// Collections of references and entry-style APIs.
+
+use std::collections::HashMap;
+
+fn count(words: &[&str]) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for word in words {
+        *counts.entry(word.to_string()).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn longest<'a>(words: &[&'a str]) -> Vec<&'a str> {
+    let mut out = Vec::new();
+    let mut best = 0;
+    for &word in words {
+        if word.len() > best {
+            best = word.len();
+            out.clear();
+        }
+        if word.len() == best {
+            out.push(word);
+        }
+    }
+    out
+}
+
+fn main() {
+    let text = String::from("a bb a ccc");
+    let words: Vec<&str> = text.split(' ').collect();
+    let counts = count(&words);
+    let best = longest(&words);
+    println!("{} {}", counts.len(), best.len());
+    let mut grid = vec![vec![0; 3]; 3];
+    let n = grid.len();
+    grid[0][n - 1] = 1;
+    let (top, bottom) = grid.split_at_mut(1);
+    top[0][0] = bottom[0][0];
+}
@@ -0,0 +1,42 @@
+// Labeled breaks, early returns and shadowing.
+
+fn find(grid: &[Vec<i32>], target: i32) -> Option<(usize, usize)> {
+    let mut found = None;
+    'outer: for (i, row) in grid.iter().enumerate() {
+        for (j, &cell) in row.iter().enumerate() {
+            if cell == target {
+                found = Some((i, j));
+                break 'outer;
+            }
+        }
+    }
+    found
+}
+
+fn first_even(values: &mut Vec<i32>) -> Option<&mut i32> {
+    for value in values.iter_mut() {
+        if *value % 2 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn main() {
+    let grid = vec![vec![1, 2], vec![3, 4]];
+    let position = find(&grid, 4);
+    let text = String::from("5");
+    let text = text.len();
+    let text = text + 1;
+    let mut values = vec![1, 2, 3];
+    if let Some(even) = first_even(&mut values) {
+        *even = 0;
+    }
+    let result = loop {
+        if values.len() > 2 {
+            break values.len();
+        }
+        values.push(1);
+    };
+    println!("{:?} {} {}", position, text, result);
+}
@@ -0,0 +1,121 @@
+// Code that relies on `#[derive]` impls and `#[cfg]`, with the default
+// configuration: no features, not a test build.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Wrapper<T>(T, usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+enum Shape {
+    Circle { radius: f64 },
+    Rect(f64, f64),
+    Empty,
+}
+
+#[derive(Debug, Default)]
+enum Mode {
+    #[default]
+    Fast,
+    #[allow(dead_code)]
+    Slow,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Config {
+    name: String,
+    #[cfg(feature = "verbose")]
+    verbose: NotAType,
+    level: u8,
+}
+
+#[cfg_attr(not(test), derive(Clone, Copy))]
+struct Token(u32);
+
+#[cfg(test)]
+fn missing() -> NotAType {
+    undefined()
+}
+
+#[cfg(feature = "verbose")]
+mod verbose;
+
+#[cfg(not(feature = "verbose"))]
+fn describe(config: &Config) -> String {
+    format!("{:?}", config)
+}
+
+fn copies(p: Point) -> (Point, Point) {
+    let q = p;
+    (p, q)
+}
+
+fn moves_and_clones(w: Wrapper<String>) -> (Wrapper<String>, bool) {
+    let c = w.clone();
+    let same = c == w;
+    (w, same)
+}
+
+fn show<T: Debug>(value: &T) -> String {
+    format!("{:?}", value)
+}
+
+fn main() {
+    let p = Point { x: 1, y: 2 };
+    let (a, b) = copies(p);
+    assert!(a == b && a <= p);
+    let mut counts: HashMap<Point, usize> = HashMap::new();
+    *counts.entry(p).or_insert(0) += 1;
+    let origin = Point::default();
+    let _ = p.cmp(&origin);
+
+    let (w, same) = moves_and_clones(Wrapper(String::from("w"), 1));
+    assert!(same);
+    let _ = show(&w);
+
+    let d = Direction::North;
+    let e = d;
+    let _ = d < e && Direction::West > Direction::East;
+    let _ = Direction::South.max(d);
+
+    let s = Shape::Rect(1.0, 2.0);
+    let t = s.clone();
+    let _ = s.partial_cmp(&t);
+    let _ = Shape::Circle { radius: 1.0 } == Shape::Empty;
+
+    let _ = show(&Mode::default());
+
+    let config = Config::default();
+    let _ = (describe(&config), config.name.len(), config.level);
+
+    let token = Token(3);
+    let other = token;
+    let _ = (token.0, other.0);
+
+    #[cfg(test)]
+    undefined();
+    let x = 1;
+    #[cfg(feature = "verbose")]
+    let x = "shadowed";
+    let _ = x + 1;
+    let _ = match x {
+        #[cfg(test)]
+        0 => undefined(),
+        _ => 2,
+    };
+}
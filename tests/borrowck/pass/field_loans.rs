@@ -0,0 +1,38 @@
+// Each field of a struct or tuple holds the loans of its own value.
+
+struct Pair<'a, 'b> {
+    a: &'a String,
+    b: &'b String,
+}
+
+fn struct_fields() {
+    let s1 = String::from("one");
+    let s2 = String::from("two");
+    let p = Pair { a: &s1, b: &s2 };
+    let x = p.a;
+    drop(s2);
+    println!("{}", x);
+}
+
+fn tuple_fields() {
+    let mut s1 = String::from("one");
+    let s2 = String::from("two");
+    let t = (&s1, &s2);
+    let y = t.1;
+    s1.push('x');
+    println!("{}", y);
+}
+
+fn destructured() {
+    let s1 = String::from("one");
+    let mut s2 = String::from("two");
+    let Pair { a, .. } = Pair { a: &s1, b: &s2 };
+    s2.push('x');
+    println!("{} {}", a, s2);
+}
+
+fn main() {
+    struct_fields();
+    tuple_fields();
+    destructured();
+}
@@ -0,0 +1,38 @@
+// Borrows of disjoint fields don't conflict.
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+struct Pair {
+    left: Vec<i32>,
+    right: Vec<i32>,
+}
+
+fn swap_halves(pair: &mut Pair) {
+    let left = &mut pair.left;
+    let right = &mut pair.right;
+    std::mem::swap(left, right);
+    left.push(1);
+    right.push(2);
+}
+
+fn tuple_fields() -> i32 {
+    let mut t = (1, 2);
+    let a = &mut t.0;
+    let b = &mut t.1;
+    *a += *b;
+    *b += 1;
+    t.0 + t.1
+}
+
+fn main() {
+    let mut p = Point { x: 1, y: 2 };
+    let x = &mut p.x;
+    let y = &p.y;
+    *x += *y;
+    let mut pair = Pair { left: vec![1], right: vec![2] };
+    swap_halves(&mut pair);
+    tuple_fields();
+}
@@ -0,0 +1,84 @@
+// Common idioms that only non-lexical lifetimes accept.
+
+use std::collections::HashMap;
+
+enum State {
+    Idle,
+    Running(u32),
+}
+
+struct Machine {
+    state: State,
+    log: Vec<String>,
+    head: Option<Box<Link>>,
+}
+
+struct Link {
+    value: u32,
+    next: Option<Box<Link>>,
+}
+
+impl Machine {
+    fn step(&mut self) {
+        match self.state {
+            State::Idle => self.state = State::Running(0),
+            State::Running(n) if n > 2 => self.state = State::Idle,
+            State::Running(ref mut n) => *n += 1,
+        }
+        let entry = format!("{}", self.log.len());
+        self.log.push(entry);
+    }
+
+    fn pop(&mut self) -> Option<u32> {
+        match self.head.take() {
+            Some(link) => {
+                self.head = link.next;
+                Some(link.value)
+            }
+            None => None,
+        }
+    }
+
+    fn drain_log(&mut self) -> Vec<String> {
+        std::mem::replace(&mut self.log, Vec::new())
+    }
+}
+
+fn lookup_or_insert(map: &mut HashMap<u32, String>, key: u32) -> usize {
+    if let Some(value) = map.get(&key) {
+        return value.len();
+    }
+    map.insert(key, String::from("new"));
+    0
+}
+
+fn prefix_sums(v: &mut Vec<i32>) {
+    for i in 1..v.len() {
+        v[i] = v[i - 1] + v[i];
+    }
+    let last = v[v.len() - 1];
+    v.truncate(v.len() - 1);
+    v.push(last);
+}
+
+fn main() {
+    let mut machine = Machine {
+        state: State::Idle,
+        log: Vec::new(),
+        head: Some(Box::new(Link { value: 1, next: None })),
+    };
+    for _ in 0..5 {
+        machine.step();
+    }
+    let popped = machine.pop();
+    let log = machine.drain_log();
+    let mut map = HashMap::new();
+    lookup_or_insert(&mut map, 1);
+    let mut v = vec![1, 2, 3];
+    prefix_sums(&mut v);
+    for x in v.iter_mut() {
+        *x = *x * 2;
+    }
+    let evens = v.iter().filter(|x| **x % 2 == 0).count();
+    println!("{:?} {} {} {}", popped, log.len(), map.len(), evens);
+}
@@ -0,0 +1,97 @@
+// A small tokenizer and evaluator mixing most of the above.
+
+use std::collections::HashMap;
+
+enum Token {
+    Num(i64),
+    Ident(String),
+    Op(char),
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(text: &'a str) -> Self {
+        Lexer { chars: text.chars().peekable() }
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        while let Some(&c) = self.chars.peek() {
+            if c == ' ' {
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        let c = self.chars.next()?;
+        if c.is_ascii_digit() {
+            let mut n = c.to_digit(10).unwrap() as i64;
+            while let Some(d) = self.chars.peek().and_then(|c| c.to_digit(10)) {
+                n = n * 10 + d as i64;
+                self.chars.next();
+            }
+            Some(Token::Num(n))
+        } else if c.is_alphabetic() {
+            let mut name = c.to_string();
+            while let Some(&c) = self.chars.peek() {
+                if !c.is_alphanumeric() {
+                    break;
+                }
+                name.push(c);
+                self.chars.next();
+            }
+            Some(Token::Ident(name))
+        } else {
+            Some(Token::Op(c))
+        }
+    }
+}
+
+struct Env {
+    vars: HashMap<String, i64>,
+    history: Vec<i64>,
+}
+
+impl Env {
+    fn lookup(&self, name: &str) -> i64 {
+        self.vars.get(name).copied().unwrap_or(0)
+    }
+
+    fn eval(&mut self, text: &str) -> i64 {
+        let mut lexer = Lexer::new(text);
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.next_token() {
+            tokens.push(token);
+        }
+        let mut total = 0;
+        let mut sign = 1;
+        let mut target = None;
+        for (i, token) in tokens.iter().enumerate() {
+            match token {
+                Token::Num(n) => total += sign * n,
+                Token::Ident(name) if i == 0 => target = Some(name.clone()),
+                Token::Ident(name) => total += sign * self.lookup(name),
+                Token::Op('-') => sign = -1,
+                Token::Op(_) => sign = 1,
+            }
+        }
+        if let Some(name) = target {
+            let slot = self.vars.entry(name).or_insert(0);
+            *slot = total;
+        }
+        self.history.push(total);
+        let last = self.history.last_mut().unwrap();
+        *last += 0;
+        total
+    }
+}
+
+fn main() {
+    let mut env = Env { vars: HashMap::new(), history: Vec::new() };
+    let lines = ["x = 1 + 2", "y = x - 1", "x + y"];
+    let results: Vec<i64> = lines.iter().map(|line| env.eval(line)).collect();
+    let sum: i64 = env.history.iter().sum();
+    println!("{:?} {}", results, sum);
+}
@@ -0,0 +1,50 @@
+// Mutable references updated across iterations.
+
+struct Node {
+    value: i32,
+    next: Option<Box<Node>>,
+}
+
+fn push_back(list: &mut Option<Box<Node>>, value: i32) {
+    let mut cur = list;
+    while let Some(node) = cur {
+        cur = &mut node.next;
+    }
+    *cur = Some(Box::new(Node { value, next: None }));
+}
+
+fn sum(list: &Option<Box<Node>>) -> i32 {
+    let mut total = 0;
+    let mut cur = list;
+    while let Some(ref node) = *cur {
+        total += node.value;
+        cur = &node.next;
+    }
+    total
+}
+
+fn increment_all(values: &mut [i32]) {
+    for value in values.iter_mut() {
+        *value += 1;
+    }
+    let mut i = 0;
+    while i < values.len() {
+        values[i] *= 2;
+        i += 1;
+    }
+}
+
+fn main() {
+    let mut list = None;
+    push_back(&mut list, 1);
+    push_back(&mut list, 2);
+    let mut values = [1, 2, 3];
+    increment_all(&mut values);
+    let mut stack = vec![1];
+    while let Some(top) = stack.pop() {
+        if top < 3 {
+            stack.push(top + 1);
+        }
+    }
+    println!("{}", sum(&list));
+}
@@ -0,0 +1,75 @@
+// Code that uses `macro_rules!` and built-in macros, checked after expansion.
+
+macro_rules! square {
+    ($x:expr) => {
+        $x * $x
+    };
+}
+
+macro_rules! swap {
+    ($a:expr, $b:expr) => {{
+        let tmp = $a;
+        $a = $b;
+        $b = tmp;
+    }};
+}
+
+macro_rules! sum {
+    () => { 0 };
+    ($first:expr $(, $rest:expr)* $(,)?) => { $first + sum!($($rest),*) };
+}
+
+macro_rules! point {
+    ($name:ident { $($field:ident: $ty:ty),* }) => {
+        struct $name {
+            $($field: $ty),*
+        }
+
+        impl $name {
+            fn total(&self) -> i64 {
+                0 $(+ self.$field as i64)*
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! first {
+    ($v:expr) => {
+        $crate::first_of(&$v)
+    };
+}
+
+point!(Point { x: i32, y: i32 });
+
+fn first_of(v: &Vec<String>) -> Option<&String> {
+    v.first()
+}
+
+fn hygiene() -> i32 {
+    let tmp = 1;
+    let mut a = 2;
+    let mut b = 3;
+    swap!(a, b);
+    tmp + a + b
+}
+
+fn builtins() -> usize {
+    let name: &str = concat!("macro", 's', 1, true);
+    let text = stringify!(a + b);
+    let here: u32 = line!();
+    let path = file!();
+    let unix = cfg!(all(unix, not(windows)));
+    name.len() + text.len() + here as usize + path.len() + unix as usize
+}
+
+fn main() {
+    let n = square!(1 + 2);
+    let total = sum!(1, 2, 3,);
+    let p = Point { x: 1, y: n };
+    let q = &p;
+    let words = vec![String::from("a")];
+    let head = first!(words);
+    println!("{} {} {} {:?} {}", total, q.total(), p.x, head, hygiene());
+    println!(concat!("{}", "!"), builtins());
+}
@@ -0,0 +1,50 @@
+// Matching borrows or moves out of the scrutinee as its patterns need.
+
+enum Shape {
+    Circle(f64),
+    Rect { w: f64, h: f64 },
+    Named(String),
+}
+
+fn area(shape: &Shape) -> f64 {
+    match shape {
+        Shape::Circle(r) => 3.14 * r * r,
+        Shape::Rect { w, h } => w * h,
+        Shape::Named(_) => 0.0,
+    }
+}
+
+fn rename(shape: &mut Shape) {
+    match shape {
+        Shape::Named(name) => name.push('!'),
+        Shape::Circle(ref mut r) if *r > 1.0 => *r = 1.0,
+        _ => {}
+    }
+}
+
+fn into_name(shape: Shape) -> String {
+    match shape {
+        Shape::Named(name) => name,
+        other => format!("{}", area(&other)),
+    }
+}
+
+fn first_word(words: &[String]) -> &str {
+    match words.first() {
+        Some(word) => word,
+        None => "",
+    }
+}
+
+fn main() {
+    let mut shape = Shape::Named(String::from("s"));
+    rename(&mut shape);
+    let name = into_name(shape);
+    let words = vec![name];
+    println!("{}", first_word(&words));
+    let opt = Some(String::from("x"));
+    if let Some(ref s) = opt {
+        println!("{}", s);
+    }
+    println!("{:?}", opt);
+}
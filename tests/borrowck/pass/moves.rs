@@ -0,0 +1,55 @@
+// Moved variables may be assigned again, and moves in one branch don't
+// affect the other.
+
+fn consume(_s: String) {}
+
+fn reassign_after_move() -> String {
+    let mut s = String::from("a");
+    consume(s);
+    s = String::from("b");
+    s
+}
+
+fn branch_moves(flag: bool) {
+    let s = String::from("c");
+    if flag {
+        consume(s);
+    } else {
+        println!("{}", s);
+    }
+}
+
+fn move_in_loop() {
+    let mut items = Vec::new();
+    for i in 0..3 {
+        let s = format!("{}", i);
+        items.push(s);
+    }
+    let mut last = String::new();
+    for item in items {
+        last = item;
+    }
+    consume(last);
+}
+
+fn partial_move() -> usize {
+    let pair = (String::from("x"), String::from("y"));
+    let first = pair.0;
+    let second = pair.1;
+    first.len() + second.len()
+}
+
+fn copy_types() -> i32 {
+    let a = 1;
+    let b = a;
+    let c = a;
+    b + c
+}
+
+fn main() {
+    reassign_after_move();
+    branch_moves(true);
+    move_in_loop();
+    partial_move();
+    copy_types();
+}
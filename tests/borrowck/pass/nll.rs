@@ -0,0 +1,44 @@
+// Loans end at their last use rather than at the end of their scope.
+
+fn first_then_push(v: &mut Vec<i32>) -> i32 {
+    let first = &v[0];
+    let value = *first;
+    v.push(value);
+    value
+}
+
+fn reborrow_after_use() -> i32 {
+    let mut x = 5;
+    let r = &mut x;
+    *r += 1;
+    let s = &x;
+    *s
+}
+
+fn conditional_use(flag: bool) -> i32 {
+    let mut data = vec![1, 2, 3];
+    let r = &data;
+    if flag {
+        return r.len() as i32;
+    }
+    data.push(4);
+    data.len() as i32
+}
+
+fn overwrite_reference() -> i32 {
+    let mut a = 1;
+    let mut b = 2;
+    let mut r = &mut a;
+    *r += 1;
+    r = &mut b;
+    *r += 1;
+    a + b
+}
+
+fn main() {
+    let mut v = vec![1];
+    first_then_push(&mut v);
+    reborrow_after_use();
+    conditional_use(true);
+    overwrite_reference();
+}
@@ -0,0 +1,39 @@
+// Option and Result adapters over borrowed contents.
+
+struct Cache {
+    value: Option<String>,
+}
+
+impl Cache {
+    fn get_or_fill(&mut self) -> &String {
+        if self.value.is_none() {
+            self.value = Some(String::from("filled"));
+        }
+        self.value.as_ref().unwrap()
+    }
+
+    fn take(&mut self) -> Option<String> {
+        self.value.take()
+    }
+}
+
+fn parse(text: &str) -> Result<i32, String> {
+    let n: i32 = text.trim().parse().map_err(|_| String::from("bad"))?;
+    Ok(n * 2)
+}
+
+fn main() {
+    let mut cache = Cache { value: None };
+    let len = cache.get_or_fill().len();
+    let taken = cache.take();
+    let again = cache.get_or_fill().clone();
+    println!("{} {:?} {}", len, taken, again);
+    let mut maybe = Some(3);
+    if let Some(x) = maybe.as_mut() {
+        *x += 1;
+    }
+    let value = maybe.unwrap_or(0);
+    let names = vec![String::from("a"), String::from("b")];
+    let first = names.first().map(|s| s.as_str()).unwrap_or("");
+    println!("{} {} {:?}", value, first, parse(" 4 "));
+}
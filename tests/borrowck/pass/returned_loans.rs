@@ -0,0 +1,57 @@
+// The loans a call returns are those its function's body returns.
+
+struct Parsed<'a> {
+    s: &'a str,
+}
+
+impl<'a> Parsed<'a> {
+    // The result borrows what `self` borrows, not `self`.
+    fn get(&self) -> &'a str {
+        self.s
+    }
+}
+
+struct Lexer<'a> {
+    text: &'a str,
+}
+
+impl<'a> Lexer<'a> {
+    fn next_word(&mut self) -> &'a str {
+        let text = self.text.trim_start();
+        let end = text.find(' ').unwrap_or(text.len());
+        self.text = &text[end..];
+        &text[..end]
+    }
+}
+
+fn first<'a, 'b>(a: &'a str, _b: &'b mut String) -> &'a str {
+    a
+}
+
+fn main() {
+    let text = String::from("a b");
+    let p = Parsed { s: &text };
+    let g = p.get();
+    drop(p);
+    println!("{}", g);
+
+    let mut lx = Lexer { text: &text };
+    let a = lx.next_word();
+    let b = lx.next_word();
+    println!("{} {}", a, b);
+
+    let mut it = text.chars().peekable();
+    let mut count = 0;
+    while let Some(&c) = it.peek() {
+        it.next();
+        if c != ' ' {
+            count += 1;
+        }
+    }
+    println!("{}", count);
+
+    let mut out = String::new();
+    let word = first(&text, &mut out);
+    out.push_str(word);
+    println!("{}", out);
+}
@@ -0,0 +1,40 @@
+// References returned from functions borrow from their arguments.
+
+struct Store {
+    items: Vec<String>,
+}
+
+impl Store {
+    fn get(&self, index: usize) -> &String {
+        &self.items[index]
+    }
+
+    fn get_mut(&mut self, index: usize) -> &mut String {
+        &mut self.items[index]
+    }
+
+    fn longest<'a>(&'a self, other: &'a str) -> &'a str {
+        let first = self.items[0].as_str();
+        if first.len() > other.len() { first } else { other }
+    }
+}
+
+fn pick<'a>(a: &'a str, b: &'a str, first: bool) -> &'a str {
+    if first { a } else { b }
+}
+
+fn main() {
+    let mut store = Store { items: vec![String::from("a"), String::from("bc")] };
+    let len = store.get(0).len();
+    store.get_mut(1).push_str("d");
+    let other = String::from("xyz");
+    let longest = store.longest(&other);
+    println!("{} {}", len, longest);
+    let a = String::from("a");
+    let chosen;
+    {
+        let b = "static";
+        chosen = pick(&a, b, false);
+    }
+    println!("{}", chosen);
+}
@@ -0,0 +1,21 @@
+// Constants and statics, and references to constant expressions.
+
+const LIMITS: [i32; 3] = [1, 2, 3];
+static GREETING: &str = "hi";
+
+fn limit() -> &'static i32 {
+    &LIMITS[1]
+}
+
+fn zero() -> &'static i32 {
+    &0
+}
+
+fn pair() -> &'static (i32, i32) {
+    &(1, -2)
+}
+
+fn main() {
+    let max = [3, 1, 2].iter().max().unwrap();
+    println!("{} {} {} {:?} {}", GREETING, limit(), zero(), pair(), max);
+}
@@ -0,0 +1,28 @@
+// Slices of strings borrow the string they are taken from.
+
+fn first_word(text: &str) -> &str {
+    match text.find(' ') {
+        Some(end) => &text[..end],
+        None => text,
+    }
+}
+
+fn words(text: &str) -> Vec<&str> {
+    text.split_whitespace().collect()
+}
+
+fn main() {
+    let mut text = String::from("hello world");
+    let word = first_word(&text).len();
+    text.push_str("!");
+    let all = words(&text);
+    let joined = all.join("-");
+    text.clear();
+    let mut out = String::new();
+    for c in joined.chars().rev() {
+        out.push(c);
+    }
+    let s: &'static str = "static";
+    let t = s;
+    println!("{} {} {} {}", word, out, s, t);
+}
@@ -0,0 +1,49 @@
+// Generic functions and trait methods borrow through their parameters.
+
+trait Named {
+    fn name(&self) -> &str;
+    fn rename(&mut self, name: &str);
+}
+
+struct Dog {
+    name: String,
+}
+
+impl Named for Dog {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn rename(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+}
+
+fn longest_name<T: Named>(items: &[T]) -> &str {
+    let mut best = "";
+    for item in items {
+        if item.name().len() > best.len() {
+            best = item.name();
+        }
+    }
+    best
+}
+
+fn rename_all<T: Named>(items: &mut [T]) {
+    for item in items.iter_mut() {
+        let upper = item.name().to_uppercase();
+        item.rename(&upper);
+    }
+}
+
+fn describe(item: &dyn Named) -> String {
+    format!("<{}>", item.name())
+}
+
+fn main() {
+    let mut dogs = vec![Dog { name: String::from("rex") }];
+    rename_all(&mut dogs);
+    let best = longest_name(&dogs).to_string();
+    dogs.push(Dog { name: best });
+    println!("{}", describe(&dogs[1]));
+}
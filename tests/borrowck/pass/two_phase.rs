@@ -0,0 +1,25 @@
+// Two-phase borrows: arguments may read the receiver of a `&mut self`
+// method before it is borrowed mutably.
+
+struct Counter {
+    counts: Vec<usize>,
+}
+
+impl Counter {
+    fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    fn add(&mut self, count: usize) {
+        self.counts.push(count);
+    }
+}
+
+fn main() {
+    let mut v: Vec<usize> = Vec::new();
+    v.push(v.len());
+    v.push(v.len() + v[0]);
+    let mut counter = Counter { counts: Vec::new() };
+    counter.add(counter.total() + 1);
+    counter.counts.push(counter.counts.len());
+}
@@ -0,0 +1,29 @@
+// Variables may be declared first and initialized on every path later.
+
+fn classify(n: i32) -> &'static str {
+    let kind;
+    if n < 0 {
+        kind = "negative";
+    } else if n == 0 {
+        kind = "zero";
+    } else {
+        kind = "positive";
+    }
+    kind
+}
+
+fn parse(flag: bool) -> String {
+    let value: String;
+    match flag {
+        true => value = String::from("yes"),
+        false => return String::new(),
+    }
+    value
+}
+
+fn main() {
+    let total;
+    let count = 3;
+    total = count * 2;
+    println!("{} {} {}", classify(total), parse(true), total);
+}
@@ -0,0 +1,218 @@
+// C interpreter tests
+
+#![cfg(feature = "c")]
+
+use compiler::c::interpreter::{self, Interpreter, Value};
+use compiler::c::ItemIterator;
+
+// `malloc` and `free`, for want of `#include`.
+const STDLIB: &str = "void *malloc(unsigned long); void free(void *);\n";
+
+// The message of the error running `text`, and the text it points at.
+fn error(text: &str) -> (String, &str) {
+    let error = interpreter::run(text).unwrap_err();
+    (error.message, &text[error.span.start..error.span.end])
+}
+
+// The same, with the limits of an interpreter changed first.
+fn limited(text: &str, limit: impl FnOnce(&mut Interpreter))
+    -> (String, &str)
+{
+    let items: Vec<_> = ItemIterator::new(text)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let mut interpreter = Interpreter::new(&items).unwrap();
+    limit(&mut interpreter);
+    let error = interpreter.run_main().unwrap_err();
+    (error.message, &text[error.span.start..error.span.end])
+}
+
+#[test]
+fn output() {
+    let text = r#"
+int printf(const char *, ...);
+int main(void) {
+    int total = 0;
+    for (int i = 1; i <= 10; i++)
+        total += i;
+    printf("%d %s\n", total, "done");
+    return 3;
+}
+"#;
+    let (code, output) = interpreter::run(text).unwrap();
+    assert_eq!(code, 3);
+    assert_eq!(output, b"55 done\n");
+}
+
+#[test]
+fn signed_overflow() {
+    assert_eq!(error("int main(void) { int x = 2147483647; return x + 1; }"),
+        ("undefined behavior: signed integer overflow in `int`".into(),
+            "x + 1"));
+    let text = "int main(void) { long long x = 9223372036854775807ll; \
+        return x * 2 > 0; }";
+    assert_eq!(error(text).0,
+        "undefined behavior: signed integer overflow in `long long`");
+    let text = "int main(void) { double d = 1e20; \
+        unsigned long n = d; return n > 0; }";
+    assert_eq!(error(text).0, "undefined behavior: floating point value \
+        100000000000000000000 doesn't fit in `unsigned long`");
+    // Unsigned arithmetic wraps.
+    let text = "int main(void) { unsigned x = 4294967295u; return x + 1; }";
+    assert_eq!(interpreter::run(text).unwrap().0, 0);
+}
+
+#[test]
+fn out_of_bounds() {
+    let text = "int main(void) { int a[4] = {0}; int i = 4; return a[i]; }";
+    assert_eq!(error(text),
+        ("undefined behavior: out-of-bounds access of 4 bytes at offset 16 \
+            of a 16-byte allocation".into(), "a[i]"));
+    let text = "int main(void) { int a[2]; int *p = a + 3; return 0; }";
+    assert!(error(text).0
+        .starts_with("undefined behavior: pointer arithmetic out of bounds"));
+    // One past the end may be formed, but not read.
+    let text = "int main(void) { int a[2]; int *p = a + 2; return p - a; }";
+    assert_eq!(interpreter::run(text).unwrap().0, 2);
+}
+
+#[test]
+fn use_after_free() {
+    let text = format!("{}{}", STDLIB, "int main(void) { \
+        int *p = malloc(4); *p = 1; free(p); return *p; }");
+    assert_eq!(error(&text),
+        ("undefined behavior: use after free".into(), "*p"));
+    let text = format!("{}{}", STDLIB, "int main(void) { \
+        int *p = malloc(4); free(p); free(p); return 0; }");
+    assert_eq!(error(&text).0, "undefined behavior: double free");
+}
+
+#[test]
+fn dangling_locals() {
+    let text = "int *g(void) { int x = 1; return &x; } \
+        int main(void) { return *g(); }";
+    assert!(error(text).0.starts_with(
+        "undefined behavior: use of a local variable after its lifetime"));
+    assert_eq!(error("int main(void) { int *p = 0; return *p; }"),
+        ("undefined behavior: null pointer dereference".into(), "*p"));
+}
+
+#[test]
+fn uninitialized() {
+    assert_eq!(error("int main(void) { int x; return x; }"),
+        ("undefined behavior: read of uninitialized memory".into(), "x"));
+}
+
+#[test]
+fn varargs() {
+    let text = r#"
+int printf(const char *, ...);
+double mean(int n, ...) {
+    __builtin_va_list ap, aq;
+    __builtin_va_start(ap, n);
+    __builtin_va_copy(aq, ap);
+    double total = 0;
+    for (int i = 0; i < n; i++) total += __builtin_va_arg(ap, double);
+    __builtin_va_end(ap);
+    printf("%d\n", (int)__builtin_va_arg(aq, double));
+    return total / n;
+}
+int main(void) { return mean(2, 4.5, 7.5f); }
+"#;
+    let (code, output) = interpreter::run(text).unwrap();
+    assert_eq!(code, 6);
+    assert_eq!(output, b"4\n");
+    let text = "int first(int n, ...) { __builtin_va_list ap; \
+        __builtin_va_start(ap, n); return __builtin_va_arg(ap, int); } \
+        int main(void) { return first(0); }";
+    assert_eq!(error(text), ("undefined behavior: `va_arg` of type `int` \
+        after the last argument".into(), "__builtin_va_arg(ap, int)"));
+}
+
+#[test]
+fn division_by_zero() {
+    assert_eq!(error("int main(void) { int a = 1, b = 0; return a / b; }"),
+        ("undefined behavior: division by zero".into(), "a / b"));
+}
+
+#[test]
+fn shifts() {
+    assert_eq!(error("int main(void) { int a = 1, b = 32; return a << b; }"),
+        ("undefined behavior: shift by 32 bits in `int`".into(),
+            "a << b"));
+    assert_eq!(error("int main(void) { int a = -1; return a << 1; }"),
+        ("undefined behavior: left shift of a negative value".into(),
+            "a << 1"));
+}
+
+#[test]
+fn pointer_comparison() {
+    assert_eq!(error("int main(void) { int a, b; a = b = 0; return &a < &b; }"),
+        ("undefined behavior: comparing pointers to different objects".into(),
+            "&a < &b"));
+    // Equality is defined for any two pointers.
+    let text = "int main(void) { int a, b; return &a == &b; }";
+    assert_eq!(interpreter::run(text).unwrap().0, 0);
+}
+
+#[test]
+fn string_literals() {
+    let text = "int main(void) { char *s = \"hi\"; s[0] = 'x'; return 0; }";
+    assert!(error(text).0.contains("modifying a string literal"));
+}
+
+#[test]
+fn step_limit() {
+    // A loop without a condition is charged to its function.
+    assert_eq!(error("int main(void) { for (;;) {} }"),
+        ("evaluation step limit exceeded".into(), "main"));
+    let text = "int main(void) { int i = 0; while (i < 100) i++; return i; }";
+    assert_eq!(limited(text, |interpreter| interpreter.set_step_limit(50)),
+        ("evaluation step limit exceeded".into(), "i < 100"));
+}
+
+#[test]
+fn depth_limit() {
+    let text = "int f(int n) { return f(n + 1); } \
+        int main(void) { return f(0); }";
+    let expected = ("stack overflow (call depth limit exceeded)".into(),
+        "f(n + 1)");
+    assert_eq!(error(text), expected);
+    assert_eq!(limited(text, |interpreter| interpreter.set_depth_limit(2000)),
+        expected);
+    let text = "int f(int n) { return n ? f(n - 1) + 1 : 0; } \
+        int main(void) { return f(10); }";
+    assert_eq!(limited(text, |interpreter| interpreter.set_depth_limit(5)),
+        ("stack overflow (call depth limit exceeded)".into(), "f(n - 1)"));
+}
+
+#[test]
+fn constants() {
+    let text = "\
+struct point { int x, y; };
+constexpr int size = 4 * 8 - 1;
+constexpr long mask = (1L << 40) | size;
+int table[3] = { size, size / 2, -size };
+struct point origin = { .y = 2 };
+int square(int n) { return n * n; }
+";
+    let items: Vec<_> = ItemIterator::new(text)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let mut interpreter = Interpreter::new(&items).unwrap();
+    assert_eq!(interpreter.global("size").unwrap(), Value::Int(31));
+    assert_eq!(interpreter.global("mask").unwrap(), Value::Int((1 << 40) | 31));
+    assert_eq!(interpreter.global("table").unwrap().to_string(),
+        "[1f, 00, 00, 00, 0f, 00, 00, 00, e1, ff, ff, ff]");
+    assert_eq!(interpreter.global("origin").unwrap().to_string(),
+        "[00, 00, 00, 00, 02, 00, 00, 00]");
+    assert_eq!(interpreter.call("square", vec![Value::Int(12)]).unwrap(),
+        Value::Int(144));
+    assert_eq!(interpreter.global("nothing").unwrap_err().message,
+        "no global named `nothing`");
+    // Initializers that aren't constant
+    let error = ItemIterator::new("int n; constexpr int m = n;")
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err();
+    assert_eq!(error.message, "constexpr initializer is not constant");
+}
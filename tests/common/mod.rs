@@ -0,0 +1,68 @@
+// Shared test helpers
+//
+// Reading the samples of the corpus and what they're expected to produce,
+// compiling them, and running the tools that check the output.  Each test
+// uses only some of them.
+
+#![allow(dead_code)]
+
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use compiler::ir::{self, Module};
+
+/// The files of a directory of the corpus with an extension picked by
+/// `filter`, sorted.
+pub fn samples(dir: &str, filter: impl Fn(&OsStr) -> bool) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(&filter))
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// The contents of the file next to a sample with extension `ext`.
+pub fn expected(path: &Path, ext: &str) -> String {
+    fs::read_to_string(path.with_extension(ext)).unwrap_or_else(|_| {
+        panic!("{}: no `.{}` file", path.display(), ext)
+    })
+}
+
+/// The IR module of a sample.
+pub fn parse(path: &Path) -> Module {
+    let text = fs::read_to_string(path).unwrap();
+    ir::parse(&text).unwrap_or_else(|error| {
+        panic!("{}", error.render(&path.display().to_string(), &text))
+    })
+}
+
+/// The module a C program lowers to, after the pipeline of a level.
+#[cfg(feature = "c")]
+pub fn compile_c(path: &Path, level: u32) -> Module {
+    use compiler::c::{lower, ItemIterator};
+    use compiler::ir::passes::PassManager;
+    let text = fs::read_to_string(path).unwrap();
+    let items: Result<Vec<_>, _> = ItemIterator::new(&text).collect();
+    let mut module = items.and_then(|items| lower::lower(&items))
+        .unwrap_or_else(|error| {
+            panic!("{}", error.render(&path.display().to_string(), &text))
+        });
+    PassManager::for_level(level).run(&mut module).unwrap();
+    module
+}
+
+/// A file in the temporary directory, unique to the test and its process.
+pub fn temp(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{}-{}-{}", env!("CARGO_CRATE_NAME"),
+        std::process::id(), name))
+}
+
+/// Whether a command can be run.
+pub fn installed(program: &str) -> bool {
+    Command::new(program).arg("--version").output().is_ok()
+}
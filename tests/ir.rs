@@ -0,0 +1,326 @@
+// IR tests
+//
+// Every sample `tests/ir/*.c`, `*.rs`, `*.py` and `*.aratar` is lowered by
+// its front end to an IR module, which must verify, print as the sample's
+// `.ir` file and parse back from it to a module printing the same, and
+// still verify after the pipeline of every optimization level.  Every
+// module `tests/ir/<pass>/*.ir` must print as its `.out` file after the
+// pass, and every module `tests/ir/O<level>/*.ir` after the pipeline of
+// the level.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use compiler::ir::passes::{Pass, PassManager};
+use compiler::ir::{self, Module};
+
+use common::{expected, samples};
+
+// The module a sample lowers to, or `None` if its front end is disabled.
+fn lower(path: &Path) -> Option<Module> {
+    match path.extension().unwrap().to_str().unwrap() {
+        #[cfg(feature = "c")]
+        "c" => {
+            use compiler::c::{lower, ItemIterator};
+            let text = fs::read_to_string(path).unwrap();
+            let items: Result<Vec<_>, _> = ItemIterator::new(&text).collect();
+            let module = items.and_then(|items| lower::lower(&items));
+            Some(module.unwrap_or_else(|error| {
+                panic!("{}: {}", path.display(), error.render("sample", &text))
+            }))
+        }
+        #[cfg(feature = "rust")]
+        "rs" => {
+            use compiler::rust::resolve::{Crate, Sources};
+            use compiler::rust::{lower, typeck};
+            let text = fs::read_to_string(path).unwrap();
+            let sources = Sources::single(path, text);
+            let krate = Crate::new(&sources).unwrap();
+            let types = typeck::check(&krate).unwrap();
+            Some(lower::lower(&krate, &types).unwrap_or_else(|errors| {
+                panic!("{}", sources.render(&errors[0]))
+            }))
+        }
+        #[cfg(feature = "python")]
+        "py" => {
+            use compiler::python::{lower, parse_module};
+            let text = fs::read_to_string(path).unwrap();
+            let module = parse_module(&text)
+                .and_then(|module| lower::lower(&module));
+            Some(module.unwrap_or_else(|error| {
+                panic!("{}: {}", path.display(), error.render("sample", &text))
+            }))
+        }
+        #[cfg(feature = "shader")]
+        "aratar" => {
+            use compiler::aratar::yote::{compile_shader, lower};
+            let text = fs::read_to_string(path).unwrap();
+            let module = compile_shader(&text)
+                .and_then(|bytecode| lower::lower(&bytecode));
+            Some(module.unwrap_or_else(|error| {
+                panic!("{}: {}", path.display(), error.render("sample", &text))
+            }))
+        }
+        _ => None,
+    }
+}
+
+// The message of the error verifying the module `text` parses to.
+fn verify_error(text: &str) -> String {
+    let module = ir::parse(text).unwrap_or_else(|error| {
+        panic!("{}", error.render("sample", text))
+    });
+    ir::verify(&module).unwrap_err().message
+}
+
+#[test]
+fn lowering() {
+    for path in samples("tests/ir", |ext| ext != "ir") {
+        let module = match lower(&path) {
+            Some(module) => module,
+            None => continue,
+        };
+        ir::verify(&module).unwrap_or_else(|error| {
+            panic!("{}: {}", path.display(), error.message)
+        });
+        let text = module.to_string();
+        assert_eq!(text, expected(&path, "ir"), "{}", path.display());
+        let parsed = ir::parse(&text).unwrap_or_else(|error| {
+            panic!("{}: {}", path.display(), error.render("ir", &text))
+        });
+        assert_eq!(parsed.to_string(), text, "{}", path.display());
+        for level in 1..=3 {
+            let mut module = module.clone();
+            let mut manager = PassManager::for_level(level);
+            manager.verify_each = true;
+            manager.run(&mut module).unwrap_or_else(|error| {
+                panic!("{}: -O{}: {}", path.display(), level, error.message)
+            });
+        }
+    }
+}
+
+// Variadic functions can be called and defined, and read their extra
+// arguments with `<stdarg.h>`.
+#[cfg(feature = "c")]
+#[test]
+fn variadic() {
+    use compiler::c::{lower, ItemIterator};
+    let lower = |text: &str| {
+        ItemIterator::new(text).collect::<Result<Vec<_>, _>>()
+            .and_then(|items| lower::lower(&items))
+    };
+    let module = lower("int count(int n, ...) { return n; }
+        int main(void) { float f = 1.5f; return count(2, f, 'x'); }")
+        .unwrap();
+    let text = module.to_string();
+    assert!(text.contains("define i32 @count(i32 %0, ...) {"), "{}", text);
+    assert!(text.contains("call i32 (i32, ...) @count(i32 2, f64 %"),
+        "{}", text);
+    let module = lower("typedef __builtin_va_list va_list;
+        long last(int n, ...) {
+            va_list ap, aq;
+            __builtin_va_start(ap, n);
+            __builtin_va_copy(aq, ap);
+            long x = 0;
+            while (n--) x = __builtin_va_arg(aq, long);
+            __builtin_va_end(aq);
+            __builtin_va_end(ap);
+            return x;
+        }").unwrap();
+    ir::verify(&module).unwrap();
+    let text = module.to_string();
+    assert!(text.contains("alloca [1 x {i32, i32, ptr, ptr}]"), "{}", text);
+    assert!(text.contains("va_start %"), "{}", text);
+    assert!(text.contains("va_arg i64, %"), "{}", text);
+
+    let error = |text: &str| {
+        let error = lower(text).unwrap_err();
+        (error.message, text[error.span.start..error.span.end].to_string())
+    };
+    assert_eq!(error("void f(int n) { __builtin_va_list ap; \
+        __builtin_va_start(ap, n); }"), (
+        "`va_start` used in a function with fixed arguments".to_string(),
+        "__builtin_va_start".to_string()));
+    assert_eq!(error("void f(int n, ...) { __builtin_va_list ap; \
+        float x = __builtin_va_arg(ap, float); }"), (
+        "arguments of type `float` are promoted to `double` when passed \
+        through `...`".to_string(),
+        "float".to_string()));
+    assert_eq!(error("void f(int n, ...) { __builtin_va_end(n); }"), (
+        "expected a `va_list`, found `int`".to_string(),
+        "n".to_string()));
+}
+
+#[test]
+fn passes() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/ir");
+    let mut dirs: Vec<_> = fs::read_dir(&root)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    for dir in dirs {
+        // A directory is named after a pass, or a level like `O2`.
+        let name = dir.file_name().unwrap().to_str().unwrap();
+        let mut manager = match Pass::named(name) {
+            Some(pass) => {
+                let mut manager = PassManager::new();
+                manager.add(pass);
+                manager
+            }
+            None => PassManager::for_level(name[1..].parse().unwrap()),
+        };
+        manager.verify_each = true;
+        for path in samples(&format!("tests/ir/{}", name), |ext| ext == "ir") {
+            let text = fs::read_to_string(&path).unwrap();
+            let mut module = ir::parse(&text).unwrap_or_else(|error| {
+                panic!("{}: {}", path.display(), error.render("sample", &text))
+            });
+            manager.run(&mut module).unwrap_or_else(|error| {
+                panic!("{}: {}", path.display(), error.message)
+            });
+            assert_eq!(module.to_string(), expected(&path, "out"),
+                "{}", path.display());
+        }
+    }
+}
+
+#[test]
+fn text() {
+    // Names, comments and forward references are normalized.
+    let text = "\
+@message = constant [6 x i8] c\"hello\\00\"   ; a string
+@\"math::one\" = global {i32, ptr} {i32 1, ptr null}
+
+declare i32 @puts(ptr)
+declare i32 @printf(ptr, ...)
+
+define i32 @main(i32 %argc) {
+start:
+  %r = call i32 @puts(ptr @message)
+  %big = icmp sgt i32 %argc, 1
+  condbr %big, more, done
+more:
+  %n = add i32 %argc, %r
+  br done
+done:
+  %x = phi i32 [0, start], [%n, more]
+  ret i32 %x
+}
+";
+    let module = ir::parse(text).unwrap();
+    ir::verify(&module).unwrap();
+    assert_eq!(module.to_string(), "\
+@message = constant [6 x i8] c\"hello\\00\"
+@\"math::one\" = global {i32, ptr} {i32 1, ptr null}
+
+declare i32 @puts(ptr)
+
+declare i32 @printf(ptr, ...)
+
+define i32 @main(i32 %0) {
+b0:
+  %1 = call i32 @puts(ptr @message)
+  %2 = icmp sgt i32 %0, 1
+  condbr %2, b1, b2
+b1:
+  %3 = add i32 %0, %1
+  br b2
+b2:
+  %4 = phi i32 [0, b0], [%3, b1]
+  ret i32 %4
+}
+");
+}
+
+#[test]
+fn parse_errors() {
+    let error = |text: &str| ir::parse(text).unwrap_err().message;
+    assert_eq!(error("define i32 @f() {\nb0:\n  ret i32 %x\n}\n"),
+        "`%x` isn't defined");
+    assert_eq!(error("@x = global i7 0\n"), "expected a type, found `i7`");
+}
+
+#[test]
+fn verify_errors() {
+    assert_eq!(verify_error("\
+define i32 @f(i1 %c) {
+b0:
+  condbr %c, b1, b2
+b1:
+  %x = add i32 1, 2
+  br b2
+b2:
+  ret i32 %x
+}
+"), "in `@f`: `%1` doesn't dominate its use in b2");
+    assert_eq!(verify_error("\
+define i32 @f(i64 %a) {
+b0:
+  %x = add i32 %a, 1
+  ret i32 %x
+}
+"), "in `@f`: `%1` in b0: `%0` is a `i64`, not a `i32`");
+    assert_eq!(verify_error("\
+define void @f(i32 %a) {
+b0:
+  br b1
+b1:
+  %x = phi i32 [%a, b0], [%a, b0]
+  ret void
+}
+"), "in `@f`: `%1` has two values for b0");
+    assert_eq!(verify_error("\
+declare i32 @g(i32)
+
+define i32 @f() {
+b0:
+  %x = call i32 @g()
+  ret i32 %x
+}
+"), "in `@f`: `%0` in b0: calls `@g` as `i32 ()`, but it is \
+        `i32 (i32)`");
+    assert_eq!(verify_error("\
+define void @f(ptr %ap) {
+b0:
+  va_start %ap
+  ret void
+}
+"), "in `@f`: instruction 0 in b0: `va_start` in a function that \
+        isn't variadic");
+}
+
+#[test]
+fn command() {
+    use std::process::Command;
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_compiler"))
+            .args(args)
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        (output.status.success(), stdout, stderr)
+    };
+    let sample = "tests/ir/O2/calls.ir";
+    let (ok, out, _) = run(&["-O2", "--verify-each", sample]);
+    assert!(ok);
+    assert_eq!(out, expected(Path::new(sample), "out"));
+    // Without inlining, `@f` keeps its call.
+    let (ok, out, _) = run(&["-O2", "-fno-inline", sample]);
+    assert!(ok);
+    assert!(out.contains("call i32 @square(i32 3)"), "{}", out);
+    // A pass on its own, on top of `-O0`.
+    let (ok, out, _) = run(&["-finline", sample]);
+    assert!(ok);
+    assert!(!out.contains("call"), "{}", out);
+    let (ok, _, err) = run(&["-fvectorize", sample]);
+    assert!(!ok);
+    assert!(err.starts_with("no pass `vectorize`"), "{}", err);
+}
@@ -0,0 +1,23 @@
+// Modules, loops with values, short-circuiting and extern functions.
+extern "C" { fn putchar(c: i32) -> i32; }
+
+mod math {
+    pub fn gcd(mut a: u64, mut b: u64) -> u64 {
+        while b != 0 { let t = b; b = a % b; a = t; }
+        a
+    }
+}
+
+fn collatz(mut n: u64) -> u32 {
+    let mut steps = 0;
+    loop {
+        if n == 1 { break steps; }
+        n = if n % 2 == 0 { n / 2 } else { 3 * n + 1 };
+        steps += 1;
+    }
+}
+
+fn main() {
+    let x = math::gcd(12, 18) as i32;
+    if x > 2 && collatz(27) < 200 { unsafe { putchar('y' as i32); } }
+}
@@ -0,0 +1,26 @@
+/* Loops, pointers, structs and globals. */
+struct point { int x; long y; };
+int count = 3;
+int table[4] = {1, 2, 3};
+struct point origin = {1, 2};
+
+unsigned sum(unsigned *p, int n) {
+    unsigned total = 0;
+    for (int i = 0; i < n; i++) {
+        if (p[i] == 0) continue;
+        total += p[i] >> 1;
+    }
+    return total;
+}
+
+long dist(struct point *p) { return p->x * p->x + p->y; }
+
+int main(void) {
+    unsigned a[3] = {1, 2, 3};
+    struct point q = origin;
+    double d = count / 2.0;
+    int *e = &table[1];
+    while (count > 0) { count--; if (count == 1) break; }
+    do { d -= 1; } while (d > 0);
+    return sum(a, 3) + dist(&q) + (int)d + (e - table);
+}
@@ -0,0 +1,49 @@
+/* Switches, goto, short-circuiting, struct copies, varargs and strings. */
+struct pair { int a; double b; };
+int printf(const char *fmt, ...);
+const char *names[] = {"zero", "one", "zero"};
+
+struct pair make(int a) { struct pair p = {a, 2.5}; return p; }
+
+int classify(int x) {
+    int r = 0;
+    switch (x) {
+    case 1: r = 10;
+    case 2: r += 2; break;
+    case -1: { r = -1; break; }
+    default:
+        for (int i = 0; i < x; i++) { if (i == 3) continue; r++; }
+    case 7: switch (r) { case 0: return 99; } r = 7;
+    }
+    return r;
+}
+
+int search(int *p, int n, int k) {
+    int i = 0;
+again:
+    if (i >= n) goto fail;
+    if (p[i] == k && k != 0 || !p[i]) return i;
+    i++;
+    goto again;
+fail:
+    return -1;
+}
+
+double total(int n, ...) {
+    __builtin_va_list ap;
+    __builtin_va_start(ap, n);
+    double sum = 0;
+    while (n--) sum += __builtin_va_arg(ap, double);
+    __builtin_va_end(ap);
+    return sum;
+}
+
+int main(void) {
+    struct pair q = make(3), r;
+    r = q;
+    double x = total(2, make(4).b, 0.5f);
+    int flag = q.a > 2 && x < 5.0;
+    int pick = flag ? q.a : (int)r.b;
+    printf("%s %d\n", names[flag], pick);
+    return classify(pick) || search(0, 0, 1);
+}
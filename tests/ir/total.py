@@ -0,0 +1,18 @@
+"""Typed functions over ints, floats and bools."""
+def fdiv(a: float, b: int) -> float:
+    return a // b + a % 2.0 + a ** 2
+
+def total(n: int) -> int:
+    s = 0
+    for i in range(1, n, 2):
+        if i > 5 and i < 9 or i == 1:
+            continue
+        s += i // 2
+    while s > 100:
+        s -= 1
+        if 0 < s < 50:
+            break
+    return s if s else 1
+
+def check(x: float) -> bool:
+    return not x and bool(int(x))
@@ -0,0 +1,684 @@
+// Python front end tests
+
+#![cfg(feature = "python")]
+
+use compiler::python::compile::{compile, Program};
+use compiler::python::{parse_module, Token, TokenIterator};
+
+// The tokens of `text`, displayed, with errors as `error: message`.
+fn tokens(text: &str) -> Vec<String> {
+    TokenIterator::new(text)
+        .map(|token| match token {
+            Ok(token) => token.to_string(),
+            Err(error) => format!("error: {}", error.message),
+        })
+        .collect()
+}
+
+#[test]
+fn indentation() {
+    let text = "if x:\n    y = 1\n\n    # comment\n  \n    if y:\n        z\nw\n";
+    assert_eq!(tokens(text), [
+        "if", "x", ":", "newline",
+        "indent", "y", "=", "1", "newline",
+        "comment",
+        "if", "y", ":", "newline",
+        "indent", "z", "newline",
+        "dedent", "dedent", "w", "newline",
+    ]);
+    assert_eq!(tokens("if x:\n\tif y:\n\t    z\n").len(), 14);
+}
+
+#[test]
+fn dedent_at_end() {
+    assert_eq!(tokens("def f():\n  pass"), [
+        "def", "f", "(", ")", ":", "newline",
+        "indent", "pass", "newline", "dedent",
+    ]);
+}
+
+#[test]
+fn bad_indentation() {
+    let errors = |text| {
+        tokens(text).into_iter()
+            .filter(|token| token.starts_with("error: "))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(errors("if x:\n    a\n  b\n"),
+        ["error: unindent does not match any outer indentation level"]);
+    assert_eq!(errors("if x:\n        a\n\tb\n"),
+        ["error: inconsistent use of tabs and spaces in indentation"]);
+    assert_eq!(errors("if x:\n\ta\n        b\n"),
+        ["error: inconsistent use of tabs and spaces in indentation"]);
+}
+
+#[test]
+fn line_joining() {
+    let text = "x = [1,\n  2] + \\\n    3\nfoo(a,\n\n b)\n";
+    assert_eq!(tokens(text), [
+        "x", "=", "[", "1", ",", "2", "]", "+", "3", "newline",
+        "foo", "(", "a", ",", "b", ")", "newline",
+    ]);
+    assert_eq!(tokens("(1]\n")[2],
+        "error: closing parenthesis ']' does not match opening parenthesis '('");
+    assert_eq!(tokens("x = (1,\n"), ["x", "=", "(", "1", ",",
+        "error: '(' was never closed"]);
+    assert_eq!(tokens("1 \\ 2\n")[1],
+        "error: unexpected character after line continuation character");
+}
+
+#[test]
+fn strings() {
+    let strings = |text| {
+        TokenIterator::new(text)
+            .map(Result::unwrap)
+            .filter(|token| *token != Token::Newline)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(strings(r#"'a\n' r'a\n' u"\x41\u00e9" b'\xff\0' Rb"\n""#), [
+        Token::String("a\n".to_string()),
+        Token::String("a\\n".to_string()),
+        Token::String("Aé".to_string()),
+        Token::Bytes(vec![0xff, 0]),
+        Token::Bytes(b"\\n".to_vec()),
+    ]);
+    assert_eq!(strings("'''a\n'b'\n''' \"\"\"\\\nc\"\"\""), [
+        Token::String("a\n'b'\n".to_string()),
+        Token::String("c".to_string()),
+    ]);
+    assert_eq!(tokens("'abc\nx"),
+        ["error: unterminated string literal", "x", "newline"]);
+    assert_eq!(tokens("b'é'")[0],
+        "error: bytes can only contain ASCII literal characters");
+}
+
+#[test]
+fn fstrings() {
+    assert_eq!(tokens("f'a{b!r:>{w}}c{{d}}' + 1"), [
+        "f'", "a", "{", "b", "!", "r", ":", ">", "{", "w", "}", "}", "c{d}",
+        "end of f-string", "+", "1", "newline",
+    ]);
+    // PEP 701: the same quotes may be reused inside replacement fields.
+    assert_eq!(tokens("f\"{f\"{x}\"}\""), [
+        "f\"", "{", "f\"", "{", "x", "}", "end of f-string", "}",
+        "end of f-string", "newline",
+    ]);
+    assert_eq!(tokens("f'{x:=5}{(y:=5)}'"), [
+        "f'", "{", "x", ":", "=5", "}", "{", "(", "y", ":=", "5", ")", "}",
+        "end of f-string", "newline",
+    ]);
+    assert_eq!(tokens("f'{\n  x\n}'"), [
+        "f'", "{", "x", "}", "end of f-string", "newline",
+    ]);
+    assert_eq!(tokens("f'a}'")[1], "error: f-string: single '}' is not allowed");
+    assert_eq!(tokens("f'abc")[1], "error: unterminated f-string literal");
+}
+
+#[test]
+fn numbers() {
+    let numbers = |text| {
+        TokenIterator::new(text)
+            .map(Result::unwrap)
+            .filter(|token| *token != Token::Newline)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(numbers("1_000 0x_ff 0o17 0b1010 0 00 1.5 1. .5 1e3 2.5E-1_0"), [
+        Token::Int(1000),
+        Token::Int(255),
+        Token::Int(15),
+        Token::Int(10),
+        Token::Int(0),
+        Token::Int(0),
+        Token::Float(1.5),
+        Token::Float(1.0),
+        Token::Float(0.5),
+        Token::Float(1000.0),
+        Token::Float(2.5e-10),
+    ]);
+    assert_eq!(numbers("3j 1.5J 1_0e1j"), [
+        Token::Imaginary(3.0),
+        Token::Imaginary(1.5),
+        Token::Imaginary(100.0),
+    ]);
+    assert_eq!(tokens("1if x else 2")[..2], ["1", "if"]);
+    assert_eq!(tokens("012")[0], "error: leading zeros in decimal integer \
+        literals are not permitted; use an 0o prefix for octal integers");
+    assert_eq!(tokens("1abc")[0], "error: invalid decimal literal");
+    assert_eq!(tokens("1__0")[0], "error: invalid decimal literal");
+    assert_eq!(tokens("x.y")[..3], ["x", ".", "y"]);
+}
+
+#[test]
+fn names() {
+    assert_eq!(tokens("match café: 变量 = None"), [
+        "match", "café", ":", "变量", "=", "None", "newline",
+    ]);
+    assert_eq!(tokens("a ? b")[1], "error: invalid character '?' (U+003F)");
+}
+
+// The `ast.dump` of a module's body, or its error as `error: message`.
+fn parse(text: &str) -> String {
+    match parse_module(text) {
+        Ok(module) => {
+            let dump = module.dump();
+            dump["Module(body=[".len()..dump.len() - "], type_ignores=[])".len()]
+                .to_string()
+        }
+        Err(error) => format!("error: {}", error.message),
+    }
+}
+
+#[test]
+fn assignments() {
+    assert_eq!(parse("x: int = 1\n(y): str\n"),
+        "AnnAssign(target=Name(id='x', ctx=Store()), annotation=Name(id='int', \
+        ctx=Load()), value=Constant(value=1), simple=1), AnnAssign(target=\
+        Name(id='y', ctx=Store()), annotation=Name(id='str', ctx=Load()), \
+        simple=0)");
+    assert_eq!(parse("a, *b = c = d\nx += 1\ndel a[0], b.c\n"),
+        "Assign(targets=[Tuple(elts=[Name(id='a', ctx=Store()), Starred(value=\
+        Name(id='b', ctx=Store()), ctx=Store())], ctx=Store()), Name(id='c', \
+        ctx=Store())], value=Name(id='d', ctx=Load())), AugAssign(target=\
+        Name(id='x', ctx=Store()), op=Add(), value=Constant(value=1)), \
+        Delete(targets=[Subscript(value=Name(id='a', ctx=Load()), slice=\
+        Constant(value=0), ctx=Del()), Attribute(value=Name(id='b', ctx=\
+        Load()), attr='c', ctx=Del())])");
+    assert_eq!(parse("*a, *b = c\n"),
+        "error: multiple starred expressions in assignment");
+    assert_eq!(parse("f() = 1\n"), "error: cannot assign to function call");
+}
+
+#[test]
+fn expressions() {
+    assert_eq!(parse("f = lambda x, /, y=1, *, z: (yield)\n"),
+        "Assign(targets=[Name(id='f', ctx=Store())], value=Lambda(args=\
+        arguments(posonlyargs=[arg(arg='x')], args=[arg(arg='y')], kwonlyargs=\
+        [arg(arg='z')], kw_defaults=[None], defaults=[Constant(value=1)]), \
+        body=Yield()))");
+    assert_eq!(parse("x = [i for i in y if i]\n"),
+        "Assign(targets=[Name(id='x', ctx=Store())], value=ListComp(elt=\
+        Name(id='i', ctx=Load()), generators=[comprehension(target=Name(id=\
+        'i', ctx=Store()), iter=Name(id='y', ctx=Load()), ifs=[Name(id='i', \
+        ctx=Load())], is_async=0)]))");
+    assert_eq!(parse("-a ** b * c if not d < e <= f else g\n"),
+        "Expr(value=IfExp(test=UnaryOp(op=Not(), operand=Compare(left=\
+        Name(id='d', ctx=Load()), ops=[Lt(), LtE()], comparators=[Name(id='e', \
+        ctx=Load()), Name(id='f', ctx=Load())])), body=BinOp(left=UnaryOp(op=\
+        USub(), operand=BinOp(left=Name(id='a', ctx=Load()), op=Pow(), right=\
+        Name(id='b', ctx=Load()))), op=Mult(), right=Name(id='c', ctx=Load())), \
+        orelse=Name(id='g', ctx=Load())))");
+    assert_eq!(parse("f(**k, *a)\n"), "error: iterable argument unpacking \
+        follows keyword argument unpacking");
+    assert_eq!(parse("x = 'a' b'b'\n"),
+        "error: cannot mix bytes and nonbytes literals");
+}
+
+#[test]
+fn joined_strings() {
+    assert_eq!(parse("f'{a!r:>{w}} {b=}'\n"),
+        "Expr(value=JoinedStr(values=[FormattedValue(value=Name(id='a', ctx=\
+        Load()), conversion=114, format_spec=JoinedStr(values=[Constant(value=\
+        '>'), FormattedValue(value=Name(id='w', ctx=Load()), conversion=-1)])), \
+        Constant(value=' b='), FormattedValue(value=Name(id='b', ctx=Load()), \
+        conversion=114)]))");
+    assert_eq!(parse("'a' f'b{c}' 'd'\n"),
+        "Expr(value=JoinedStr(values=[Constant(value='ab'), FormattedValue(\
+        value=Name(id='c', ctx=Load()), conversion=-1), Constant(value='d')]))");
+}
+
+#[test]
+fn definitions() {
+    assert_eq!(parse("@d\nasync def f(a, *args, b=2, **kw) -> T:\n    \
+        await g()\n"),
+        "AsyncFunctionDef(name='f', args=arguments(posonlyargs=[], args=[arg(\
+        arg='a')], vararg=arg(arg='args'), kwonlyargs=[arg(arg='b')], \
+        kw_defaults=[Constant(value=2)], kwarg=arg(arg='kw'), defaults=[]), \
+        body=[Expr(value=Await(value=Call(func=Name(id='g', ctx=Load()), \
+        args=[], keywords=[])))], decorator_list=[Name(id='d', ctx=Load())], \
+        returns=Name(id='T', ctx=Load()), type_params=[])");
+    assert_eq!(parse("type X[T: int, *Ts, **P] = list[T]\n\
+        class C[T](B, metaclass=M): pass\n"),
+        "TypeAlias(name=Name(id='X', ctx=Store()), type_params=[TypeVar(name=\
+        'T', bound=Name(id='int', ctx=Load())), TypeVarTuple(name='Ts'), \
+        ParamSpec(name='P')], value=Subscript(value=Name(id='list', ctx=\
+        Load()), slice=Name(id='T', ctx=Load()), ctx=Load())), ClassDef(name=\
+        'C', bases=[Name(id='B', ctx=Load())], keywords=[keyword(arg=\
+        'metaclass', value=Name(id='M', ctx=Load()))], body=[Pass()], \
+        decorator_list=[], type_params=[TypeVar(name='T')])");
+    assert_eq!(parse("def f(a=1, b):\n    pass\n"), "error: parameter without \
+        a default follows parameter with a default");
+    assert_eq!(parse("def f(*): pass\n"),
+        "error: named arguments must follow bare *");
+}
+
+#[test]
+fn compound_statements() {
+    assert_eq!(parse("match p:\n    case [1, *rest] if rest:\n        pass\n    \
+        case {'k': v, **kw}:\n        pass\n    \
+        case Point(x=0) | None as q:\n        pass\n"),
+        "Match(subject=Name(id='p', ctx=Load()), cases=[match_case(pattern=\
+        MatchSequence(patterns=[MatchValue(value=Constant(value=1)), \
+        MatchStar(name='rest')]), guard=Name(id='rest', ctx=Load()), body=[\
+        Pass()]), match_case(pattern=MatchMapping(keys=[Constant(value='k')], \
+        patterns=[MatchAs(name='v')], rest='kw'), body=[Pass()]), match_case(\
+        pattern=MatchAs(pattern=MatchOr(patterns=[MatchClass(cls=Name(id=\
+        'Point', ctx=Load()), patterns=[], kwd_attrs=['x'], kwd_patterns=[\
+        MatchValue(value=Constant(value=0))]), MatchSingleton(value=None)]), \
+        name='q'), body=[Pass()])])");
+    // `match` is only a keyword at the start of a match statement.
+    assert_eq!(parse("match = match(x)\n"),
+        "Assign(targets=[Name(id='match', ctx=Store())], value=Call(func=\
+        Name(id='match', ctx=Load()), args=[Name(id='x', ctx=Load())], \
+        keywords=[]))");
+    assert_eq!(parse("if x:\npass\n"),
+        "error: expected an indented block after 'if' statement on line 1");
+    assert_eq!(parse("try:\n    pass\nexcept* E:\n    pass\n\
+        except F:\n    pass\n"),
+        "error: cannot have both 'except' and 'except*' on the same 'try'");
+}
+
+// A module compiled, or its error as `error: message`.
+fn compiled(text: &str) -> Result<Program, String> {
+    let module = parse_module(text).unwrap();
+    compile(&module).map_err(|error| format!("error: {}", error.message))
+}
+
+// What a compiled module prints, ending with the exception it raises as
+// `Kind: message`.
+fn run(text: &str) -> String {
+    match compiled(text) {
+        Ok(program) => match program.run() {
+            Ok(output) => output,
+            Err((exception, output)) => format!(
+                "{}{}: {}\n",
+                output,
+                exception.kind,
+                exception.message,
+            ),
+        },
+        Err(error) => error,
+    }
+}
+
+#[test]
+fn monomorphization() {
+    let program = compiled("def fib(n):\n    if n < 2:\n        return n\n    \
+        return fib(n - 1) + fib(n - 2)\n\
+        def add(a, b):\n    return a + b\n\
+        print(fib(20), add(1, 2), add('a', 'b'))\n").unwrap();
+    assert_eq!(program.signatures(), [
+        "fib(n: int) -> int",
+        "add(a: int, b: int) -> int",
+        "add(a: str, b: str) -> str",
+    ]);
+    assert_eq!(program.run().unwrap(), "6765 3 ab\n");
+    // Values of different types join to `object`.
+    let program = compiled("def f(flag, x: float):\n    if flag:\n        \
+        return x\n    return 'no'\nprint(f(True, 1), f(False, 2.5))\n\
+        xs = [1, 'a']\nxs.append(None)\nprint(xs)\n").unwrap();
+    assert_eq!(program.signatures(), ["f(flag: bool, x: float) -> object"]);
+    assert_eq!(program.run().unwrap(), "1.0 no\n[1, 'a', None]\n");
+}
+
+#[test]
+fn compiled_statements() {
+    assert_eq!(run("count = 0\ndef bump():\n    global count\n    \
+        count += 1\n    return count\nbump()\nprint(bump(), count)\n\
+        total = 0\nfor x in [1, 2, 3]:\n    if x == 2:\n        continue\n    \
+        total += x * x\nelse:\n    print('sum', total)\n\
+        i = 0\nwhile True:\n    i += 1\n    if i > 3:\n        break\n\
+        a, (b, *c) = 1, (2, 3, 4)\nprint(i, a, b, c)\n\
+        d = {'a': 1}\nd['b'] = 2\ndel d['a']\n\
+        for k, v in d.items():\n    print(k, v)\n"),
+        "2 2\nsum 10\n4 1 2 [3, 4]\nb 2\n");
+    assert_eq!(run("def g(x, y=10, *, z=5):\n    return x + y + z\n\
+        print(g(1), g(1, 2), g(1, z=0), g(y=1, x=2))\n\
+        print(list(map(lambda x: x * 2, [1, 2])))\n"),
+        "16 8 11 8\n[2, 4]\n");
+}
+
+#[test]
+fn compiled_expressions() {
+    assert_eq!(run("print(1 / 2, 7 // 2, -7 // 2, 7 % -3, 2 ** 10, 2 ** -1)\n\
+        print(1 < 2 < 3, 'a' in 'abc', 2 not in [1], 3 == 3.0, not [])\n\
+        s = 'hello world'\nprint(s.upper(), s.split(), s[1:4], s[::-1])\n\
+        print(f\"{7:>4}|{3.14159:.2f}|{'x'!r}\")\n"),
+        "0.5 3 -4 -2 1024 0.5\nTrue True True True True\n\
+        HELLO WORLD ['hello', 'world'] ell dlrow olleh\n   7|3.14|'x'\n");
+    assert_eq!(run("print([x * x for x in range(6) if x % 2 == 0])\n\
+        print({k: v for k, v in zip('ab', range(2))})\n\
+        print([[i * j for j in range(3)] for i in range(2)])\n"),
+        "[0, 4, 16]\n{'a': 0, 'b': 1}\n[[0, 0, 0], [0, 1, 2]]\n");
+}
+
+#[test]
+fn compiled_builtins() {
+    assert_eq!(run("words = 'a bb ccc'.split()\n\
+        print(max(words, key=len), sorted(words, key=lambda w: -len(w)))\n\
+        print(sum([1.5, 2]), min(4, 2, 8), abs(-3), round(2.5), \
+        round(3.14159, 2))\n\
+        print(divmod(7, 2), list(enumerate('ab', 1)), isinstance(1, int))\n\
+        print(int('12') + 1, float(3), str(4) + '!', repr('q'), len({1: 2}))\n\
+        print(*[1, 2], sep=', ', end='.\\n')\n"),
+        "ccc ['ccc', 'bb', 'a']\n3.5 2 3 2 3.14\n\
+        (3, 1) [(1, 'a'), (2, 'b')] True\n13 3.0 4! 'q' 1\n1, 2.\n");
+    assert_eq!(run("print(pow(2, 10), pow(2, -1), pow(2.0, 3), pow(3, 4, 5))\n\
+        print(pow(2, 100, 10 ** 9 + 7), pow(-3, 3, 5), pow(3, -1, 7))\n\
+        f = pow\nprint(f(3, 2, 4))\nprint(pow(2, -1, 4))\n"),
+        "1024 0.5 8.0 1\n976371285 3 5\n1\n\
+        ValueError: base is not invertible for the given modulus\n");
+    assert_eq!(run("print(pow(2.0, 3, 5))\n"),
+        "TypeError: pow() 3rd argument not allowed unless all arguments are \
+        integers\n");
+}
+
+#[test]
+fn percent_formatting() {
+    assert_eq!(run("x = 42\n\
+        print('%d|%5d|%-5d|%05d|%+d|%.3d' % (x, x, x, -x, x, 7))\n\
+        print('%x %#X %o %c%c %i' % (255, 255, 8, 65, 'z', 2.9))\n\
+        print('%s and %r, %5.1f%% %e %g' % ('a', 'b', 3.14159, 12345.678, \
+        0.0001))\n\
+        print('%(name)s is %(age)d' % {'name': 'Al', 'age': 30})\n\
+        print('%*d|%-*s|' % (4, 7, 3, 'x'), '%s' % [1, 2], '%a' % 'é')\n\
+        s = 'n=%d'\nprint((s % 3).upper())\n"),
+        "42|   42|42   |-0042|+42|007\nff 0XFF 10 Az 2\n\
+        a and 'b',   3.1% 1.234568e+04 0.0001\nAl is 30\n   7|x  | \
+        [1, 2] '\\xe9'\nN=3\n");
+    assert_eq!(run("print('%d' % 'a')\n"),
+        "TypeError: %d format: a real number is required, not str\n");
+    assert_eq!(run("print('%x' % 1.5)\n"),
+        "TypeError: %x format: an integer is required, not float\n");
+    assert_eq!(run("print('%d %d' % (1,))\n"),
+        "TypeError: not enough arguments for format string\n");
+    assert_eq!(run("print('%d' % (1, 2))\n"),
+        "TypeError: not all arguments converted during string formatting\n");
+    assert_eq!(run("print('%q' % 1)\n"),
+        "ValueError: unsupported format character 'q' (0x71) at index 1\n");
+}
+
+#[test]
+fn closures() {
+    assert_eq!(run("def counter():\n    n = 0\n    def bump():\n        \
+        nonlocal n\n        n += 1\n        return n\n    return bump\n\
+        c = counter()\nc()\nprint(c(), c(), counter()())\n\
+        def adder(k):\n    return lambda x: x + k\n\
+        print(adder(2)(3), adder('a')('b'))\n"),
+        "2 3 1\n5 ba\n");
+    // Cells are shared, and passed on by the functions in between.
+    assert_eq!(run("def outer():\n    x = 1\n    def mid():\n        \
+        def inner():\n            return x * 10\n        return inner()\n    \
+        x = 5\n    return mid()\nprint(outer())\n\
+        def late():\n    fs = []\n    for i in range(3):\n        \
+        fs.append(lambda: i)\n    return [g() for g in fs]\nprint(late())\n\
+        def gen(n: int):\n    def scale(x):\n        return x * n\n    \
+        for i in range(3):\n        yield scale(i)\nprint(list(gen(3)))\n"),
+        "50\n[2, 2, 2]\n[0, 3, 6]\n");
+    let program = compiled("def adder(k: int):\n    return lambda x: x + k\n\
+        print(adder(1)(2))\n").unwrap();
+    assert_eq!(program.signatures(), [
+        "adder(k: int) -> object",
+        "<lambda>(x: object) -> object",
+    ]);
+    assert_eq!(run("def f():\n    def g():\n        return y\n    \
+        r = g()\n    y = 1\nf()\n"),
+        "NameError: cannot access free variable 'y' where it is not \
+        associated with a value in enclosing scope\n");
+    assert_eq!(run("def f():\n    nonlocal q\nf()\n"),
+        "error: no binding for nonlocal 'q' found");
+}
+
+#[test]
+fn classes() {
+    assert_eq!(run("class Counter:\n    '''Counts.'''\n    made = 0\n    \
+        def __init__(self, start=0):\n        self.n = start\n        \
+        Counter.made += 1\n    def bump(self, by=1):\n        \
+        self.n += by\n        return self\n\
+        a = Counter()\nb = Counter(5)\nprint(a.bump().bump(by=2).n, b.n)\n\
+        print(Counter.made, a.made, Counter.bump(b).n)\n\
+        bump = a.bump\nbump()\nprint(a.n, a.__class__ is Counter, a == b)\n\
+        del a.n\nprint(a.n)\n"),
+        "3 5\n2 2 6\n4 True False\n\
+        AttributeError: 'Counter' object has no attribute 'n'\n");
+    // Methods are looked up through the bases, and `super()` starts after
+    // the method's class.
+    assert_eq!(run("class Shape:\n    def __init__(self, name):\n        \
+        self.name = name\n    def describe(self):\n        \
+        return self.name + ' with area ' + str(self.area())\n\
+        class Square(Shape):\n    def __init__(self, side):\n        \
+        super().__init__('square')\n        self.side = side\n    \
+        def area(self):\n        return self.side ** 2\n    \
+        def describe(self):\n        return super().describe() + '!'\n\
+        s = Square(3)\nprint(s.describe())\n\
+        print(isinstance(s, Shape), isinstance(Shape('x'), Square), \
+        isinstance(s, (int, Square)))\n\
+        print(Shape('x').area())\n"),
+        "square with area 9!\nTrue False True\n\
+        AttributeError: 'Shape' object has no attribute 'area'\n");
+    // Methods see the variables of the function the class is in.
+    assert_eq!(run("def make(k):\n    class Adder:\n        \
+        def add(self, x):\n            return x + k\n    return Adder()\n\
+        print(make(10).add(1))\n"),
+        "11\n");
+    assert_eq!(run("class A:\n    pass\nA(1)\n"),
+        "TypeError: A() takes no arguments\n");
+    assert_eq!(run("class A:\n    def __init__(self):\n        \
+        return 1\nA()\n"),
+        "TypeError: __init__() should return None, not 'int'\n");
+    assert_eq!(run("class A:\n    def __str__(self):\n        return 'a'\n"),
+        "error: special methods other than `__init__` can't be compiled yet");
+    assert_eq!(run("class E(Exception):\n    pass\n"),
+        "error: subclasses of built-in classes can't be compiled yet");
+}
+
+#[test]
+fn compiled_errors() {
+    assert_eq!(run("x: int = 'a'\n"),
+        "error: mismatched types: expected `int`, found `str`");
+    assert_eq!(run("def f(x: int):\n    return x\nf('a')\n"),
+        "error: mismatched types: expected `int`, found `str`");
+    assert_eq!(run("def f(a, b):\n    return a\nprint('start')\nf(1)\n"),
+        "start\nTypeError: f() missing 1 required positional argument: 'b'\n");
+    assert_eq!(run("def f():\n    print(y)\n    y = 1\nf()\n"),
+        "UnboundLocalError: cannot access local variable 'y' where it is not \
+        associated with a value\n");
+    assert_eq!(run("print(undefined)\n"),
+        "NameError: name 'undefined' is not defined\n");
+    assert_eq!(run("print(1 // 0)\n"),
+        "ZeroDivisionError: integer division or modulo by zero\n");
+    assert_eq!(run("print(1 << -1)\n"),
+        "ValueError: negative shift count\n");
+    assert_eq!(run("def f(x: int):\n    return x\nxs = [1, 'a']\nf(xs[1])\n"),
+        "TypeError: int object expected; got str\n");
+    // Every level of Python calls takes several native frames.
+    let deep = std::thread::Builder::new()
+        .stack_size(1 << 28)
+        .spawn(|| run("def r(n):\n    return r(n + 1)\nr(0)\n"))
+        .unwrap();
+    assert_eq!(deep.join().unwrap(),
+        "RecursionError: maximum recursion depth exceeded\n");
+}
+
+#[test]
+fn big_ints() {
+    assert_eq!(run("x = 2 ** 100\nprint(x, -x // 7, x % 13, x >> 3)\n\
+        print(hex(x), format(x, ','), int('ff', 16), int('0b101', 0))\n\
+        print(int('123456789012345678901234567890') * 3, 10 ** 20 / 10 ** 18)\n\
+        print(hash(1), hash(1.0), hash(2 ** 61), hash((1, 2)), hash(-1))\n"),
+        "1267650600228229401496703205376 -181092942889747057356671886483 3 \
+        158456325028528675187087900672\n\
+        0x10000000000000000000000000 \
+        1,267,650,600,228,229,401,496,703,205,376 255 5\n\
+        370370367037037036703703703670 100.0\n\
+        1 1 1 -3550055125485641917 -2\n");
+    assert_eq!(run("print(2 ** 64 > 1.5, 3 ** 50 == 717897987691852588770249)\n"),
+        "True True\n");
+    assert_eq!(run("print(int('12_3'), int('1__2'))\n"),
+        "ValueError: invalid literal for int() with base 10: '1__2'\n");
+}
+
+#[test]
+fn dicts_and_sets() {
+    assert_eq!(run("d = {1: 'a', 'b': 2}\nd[1.0] = 'c'\nd[True] = 'd'\n\
+        del d['b']\nd['b'] = 3\nprint(d)\nprint(d.popitem(), d)\n"),
+        "{1: 'd', 'b': 3}\n('b', 3) {1: 'd'}\n");
+    assert_eq!(run("s = {3, 1, 2}\ns.add(4)\ns.discard(1)\n\
+        print(sorted(s), 2 in s, len(s), sorted(s | {9}), sorted(s & {2, 3}))\n\
+        print(sorted(s - {2}), sorted(s ^ {2, 7}), {1, 2} <= {1, 2, 3})\n\
+        print(sorted({x % 3 for x in range(10)}), set(), set([1, 1]))\n"),
+        "[2, 3, 4] True 3 [2, 3, 4, 9] [2, 3]\n[3, 4] [3, 4, 7] True\n\
+        [0, 1, 2] set() {1}\n");
+    assert_eq!(run("s = set()\ns.remove(1)\n"), "KeyError: 1\n");
+    assert_eq!(run("print({[1]})\n"),
+        "TypeError: unhashable type: 'list'\n");
+}
+
+#[test]
+fn tuples_and_iterators() {
+    assert_eq!(run("t = (1, 2, 2, 3)\n\
+        print(t.count(2), t.index(3), t + (4,), t[1:])\n\
+        it = iter([1, 2])\nprint(next(it), next(it), next(it, 'done'))\n"),
+        "2 3 (1, 2, 2, 3, 4) (2, 2, 3)\n1 2 done\n");
+    assert_eq!(run("next(iter(()))\n"), "StopIteration: \n");
+}
+
+#[test]
+fn exceptions() {
+    assert_eq!(run("def f(n):\n    if n == 0:\n        raise ValueError('boom')\n\
+        \x20   return f(n - 1)\n\
+        try:\n    f(2)\nexcept (KeyError, ValueError) as e:\n\
+        \x20   print('caught', e, repr(e), isinstance(e, Exception))\n\
+        finally:\n    print('finally')\n"),
+        "caught boom ValueError('boom') True\nfinally\n");
+    assert_eq!(run("def g():\n    try:\n        return 1\n    finally:\n\
+        \x20       print('cleanup')\nprint(g())\n\
+        try:\n    try:\n        {}['k']\n    except KeyError:\n\
+        \x20       print('inner')\n        raise\n\
+        except LookupError as e:\n    print('outer', repr(e))\n\
+        try:\n    pass\nexcept Exception:\n    print('no')\n\
+        else:\n    print('else')\n"),
+        "cleanup\n1\ninner\nouter KeyError('k')\nelse\n");
+    assert_eq!(run("try:\n    1 / 0\nexcept TypeError:\n    pass\n"),
+        "ZeroDivisionError: division by zero\n");
+    assert_eq!(run("raise\n"),
+        "RuntimeError: No active exception to reraise\n");
+}
+
+#[test]
+fn tracebacks() {
+    let source = "def inner(x):\n    return [1][x]\n\n\
+        def outer():\n    return inner(1)\n\nouter()\n";
+    let (exception, _) = compiled(source).unwrap().run().unwrap_err();
+    assert_eq!(exception.traceback(source, "main.py"),
+        "Traceback (most recent call last):\n  \
+        File \"main.py\", line 7, in <module>\n    outer()\n  \
+        File \"main.py\", line 5, in outer\n    return inner(1)\n  \
+        File \"main.py\", line 2, in inner\n    return [1][x]\n\
+        IndexError: list index out of range\n");
+}
+
+#[test]
+fn cycle_collection() {
+    use compiler::python::runtime::{gc, set_attribute, Class, Value};
+    use std::rc::Rc;
+
+    let list = Value::list(Vec::new());
+    let weak = match &list {
+        Value::List(items) => {
+            items.borrow_mut().push(list.clone());
+            Rc::downgrade(items)
+        }
+        _ => unreachable!(),
+    };
+    let alive = Value::list(vec![Value::int(1)]);
+    drop(list);
+    assert!(weak.upgrade().is_some());
+    assert!(gc::collect() >= 1);
+    assert!(weak.upgrade().is_none());
+    assert_eq!(alive.to_string(), "[1]");
+    // An instance holding itself in an attribute
+    let object = Value::object(Rc::new(Class::new("A", None)));
+    set_attribute(&object, "me", object.clone()).unwrap();
+    let weak = match &object {
+        Value::Object(object) => Rc::downgrade(object),
+        _ => unreachable!(),
+    };
+    drop(object);
+    assert!(gc::collect() >= 1);
+    assert!(weak.upgrade().is_none());
+}
+
+#[test]
+fn generators() {
+    assert_eq!(run("def count(n):\n    i = 0\n    while i < n:\n        \
+        got = yield i\n        if got is not None:\n            \
+        print('got', got)\n        i += 1\n    return 'done'\n\
+        g = count(3)\nprint(next(g), g.send('x'), next(g))\n\
+        try:\n    next(g)\nexcept StopIteration as e:\n    print('stop', e)\n\
+        print(list(count(4)), sum(count(5)), 2 in count(3))\n\
+        for x in count(2):\n    print(x)\n"),
+        "got x\n0 1 2\nstop done\n[0, 1, 2, 3] 10 True\n0\n1\n");
+    assert_eq!(run("def guarded():\n    try:\n        yield 1\n        \
+        yield 2\n    except ValueError as e:\n        print('caught', e)\n        \
+        yield 3\n    finally:\n        print('cleanup')\n\
+        g = guarded()\nprint(next(g), g.throw(ValueError('bad')))\n\
+        print(list(g))\ng = guarded()\nnext(g)\ng.close()\nprint(list(g))\n"),
+        "caught bad\n1 3\ncleanup\n[]\ncleanup\n[]\n");
+    // The value a generator returns reaches the `StopIteration`.
+    assert_eq!(run("def echo():\n    x = yield 1\n    return [x, 2]\n\
+        def empty():\n    if False:\n        yield 0\n\
+        g = echo()\nnext(g)\ntry:\n    g.send('a')\n\
+        except StopIteration as e:\n    print(e.value, e.args)\n\
+        try:\n    next(empty())\nexcept StopIteration as e:\n    \
+        print(e.value, e.args)\n\
+        try:\n    next(g)\nexcept StopIteration as e:\n    print(e.value)\n\
+        try:\n    raise KeyError('k', 2)\nexcept KeyError as e:\n    \
+        print(e.args)\n    print(e.value)\n"),
+        "['a', 2] (['a', 2],)\nNone ()\nNone\n('k', 2)\n\
+        AttributeError: 'KeyError' object has no attribute 'value'\n");
+    assert_eq!(run("def bad():\n    yield 1\n    raise StopIteration\n\
+        list(bad())\n"),
+        "RuntimeError: generator raised StopIteration\n");
+    assert_eq!(run("def f():\n    print((yield))\nf()\n"),
+        "error: `yield` can only be compiled as a statement, an assignment's \
+        value or a returned value");
+    assert_eq!(run("yield 1\n"), "error: 'yield' outside function");
+}
+
+#[test]
+fn delegation() {
+    assert_eq!(run("def walk(tree):\n    if isinstance(tree, list):\n        \
+        for child in tree:\n            yield from walk(child)\n    \
+        else:\n        yield tree\n\
+        print(list(walk([1, [2, [3, 4]], [[5]], 6])))\n\
+        def inner():\n    x = yield 1\n    print('inner got', x)\n    \
+        yield 2\n    return 10\n\
+        def outer():\n    r = yield from inner()\n    print('returned', r)\n    \
+        yield from [7, 8]\n\
+        o = outer()\nprint(next(o), o.send('hi'), list(o))\n"),
+        "[1, 2, 3, 4, 5, 6]\ninner got hi\nreturned 10\n1 2 [7, 8]\n");
+}
+
+#[test]
+fn coroutines() {
+    assert_eq!(run("import asyncio\n\
+        async def work(name, delay):\n    print('start', name)\n    \
+        await asyncio.sleep(delay)\n    print('end', name)\n    \
+        return name.upper()\n\
+        async def main():\n    a = asyncio.create_task(work('a', 2))\n    \
+        b = asyncio.create_task(work('b', 1))\n    print('made')\n    \
+        r = await a\n    print(r)\n    r = await b\n    print(r)\n    \
+        return await asyncio.gather(work('x', 2), work('y', 1))\n\
+        print(asyncio.run(main()))\n"),
+        "made\nstart a\nstart b\nend b\nend a\nA\nB\nstart x\nstart y\n\
+        end y\nend x\n['X', 'Y']\n");
+    assert_eq!(run("import asyncio\nasync def add(a, b):\n    return a + b\n\
+        async def reuse():\n    c = add(1, 2)\n    r = await c\n    \
+        print(r)\n    await c\nasyncio.run(reuse())\n"),
+        "3\nRuntimeError: cannot reuse already awaited coroutine\n");
+    assert_eq!(run("import asyncio\nasync def f():\n    await 5\n\
+        asyncio.run(f())\n"),
+        "TypeError: object int can't be used in 'await' expression\n");
+    assert_eq!(run("def f():\n    await g()\nf()\n"),
+        "error: 'await' outside async function");
+}
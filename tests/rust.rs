@@ -0,0 +1,771 @@
+// Rust front end tests
+
+#![cfg(feature = "rust")]
+
+use std::io;
+use std::path::Path as FilePath;
+
+use compiler::rust::cfg::Cfg;
+use compiler::rust::resolve::{Crate, FileDiagnostic, Namespace, Sources};
+use compiler::rust::{
+    typeck, Item, ItemIterator, ItemKind, Token, TokenIterator,
+};
+use compiler::Span;
+
+// The tokens of `text`, displayed, with errors as `error: message`.
+fn tokens(text: &str) -> Vec<String> {
+    TokenIterator::new(text)
+        .map(|token| match token {
+            Ok(token) => token.to_string(),
+            Err(error) => format!("error: {}", error.message),
+        })
+        .collect()
+}
+
+#[test]
+fn unicode_identifiers() {
+    assert_eq!(tokens("let ü = größe + x\u{e9}1;"),
+        ["let", "ü", "=", "größe", "+", "xé1", ";"]);
+    assert_eq!(tokens("'été: loop {} r#straße 'ä'"),
+        ["'été", ":", "loop", "{", "}", "straße", "'ä'"]);
+    let mut iter = TokenIterator::new("a + größe");
+    iter.nth(1);
+    assert_eq!(iter.next(), Some(Ok(Token::Identifier("größe"))));
+    assert_eq!(iter.span(), Span::new(4, 11));
+}
+
+#[test]
+fn unknown_characters() {
+    let mut iter = TokenIterator::new("a € b");
+    iter.next();
+    let error = iter.next().unwrap().unwrap_err();
+    assert_eq!(error.message, "unknown start of token `€`");
+    assert_eq!(error.span, Span::new(2, 5));
+    assert_eq!(iter.next(), Some(Ok(Token::Identifier("b"))));
+    assert_eq!(tokens("x🦀"), ["x", "error: unknown start of token `🦀`"]);
+}
+
+// Parse `text`, checking that each item parses the same from the text of its
+// span alone, with the rest blanked out.
+fn round_trip(text: &str) -> Vec<Item<'_>> {
+    let items = ItemIterator::new(text).collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|error| panic!("{}", error.render("main.rs", text)));
+    for item in &items {
+        let masked: String = text.char_indices()
+            .map(|(i, c)| {
+                let inside = (item.span.start..item.span.end).contains(&i);
+                match c {
+                    _ if inside => c.to_string(),
+                    '\n' => "\n".to_string(),
+                    _ => " ".repeat(c.len_utf8()),
+                }
+            })
+            .collect();
+        let reparsed = ItemIterator::new(&masked)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|error| panic!("{}", error.render("", &masked)));
+        assert_eq!(reparsed, std::slice::from_ref(item), "{}", masked.trim());
+    }
+    items
+}
+
+// The message of the first error parsing `text`, and the text it points at.
+fn parse_error(text: &str) -> (String, &str) {
+    let error = ItemIterator::new(text).find_map(Result::err).unwrap();
+    (error.message, &text[error.span.start..error.span.end])
+}
+
+#[test]
+fn items() {
+    let text = "\
+//! Crate docs
+#![allow(dead_code)]
+use std::collections::{HashMap, hash_map::Entry as E};
+use self::shapes::*;
+extern crate alloc as a;
+pub mod shapes {
+    pub(crate) struct Point<T = i32> { pub x: T, y: T }
+    pub struct Unit;
+    pub struct Pair<'a, T: ?Sized>(&'a T, pub u8) where T: 'a;
+    #[derive(Debug)]
+    pub enum Shape { Dot, Circle { radius: f64 }, Poly(Vec<Point>) = 3 }
+    union Bits { int: u32, float: f32 }
+}
+mod external;
+pub trait Area: Clone + 'static {
+    type Unit: Default;
+    const SIDES: usize = 0;
+    fn area(&self) -> f64;
+    fn scaled(self, by: f64) -> Self where Self: Sized { self }
+}
+impl<'a, T> Area for shapes::Pair<'a, T> where T: Clone {
+    type Unit = ();
+    fn area(&self) -> f64 { 0.0 }
+}
+impl<T> !Send for Wrapper<T> {}
+pub type Map<V> = HashMap<&'static str, V>;
+const LIMIT: u32 = 1 << 10;
+static mut COUNT: usize = 0;
+extern \"C\" {
+    fn abs(x: i32) -> i32;
+    static errno: i32;
+}
+pub(in crate::shapes) const unsafe extern \"C\" fn raw() {}
+async fn fetch<'a, F: for<'b> Fn(&'b str) -> &'b str>(f: F) -> impl Sized {}
+macro_rules! square { ($x:expr) => { $x * $x }; }
+";
+    let items = round_trip(text);
+    assert_eq!(items.len(), 15);
+    let spans: Vec<_> = items.iter()
+        .map(|item| text[item.span.start..item.span.end].lines().next())
+        .collect();
+    assert_eq!(spans[..5], [
+        Some("use std::collections::{HashMap, hash_map::Entry as E};"),
+        Some("use self::shapes::*;"),
+        Some("extern crate alloc as a;"),
+        Some("pub mod shapes {"),
+        Some("mod external;"),
+    ]);
+    match items[3].kind {
+        ItemKind::Mod(_, Some(ref inner)) => assert_eq!(inner.len(), 5),
+        ref kind => panic!("{:?}", kind),
+    }
+}
+
+#[test]
+fn item_errors() {
+    assert_eq!(parse_error("fn f( { }"),
+        ("expected pattern, found `{`".into(), "{"));
+    assert_eq!(parse_error("struct S { a: u8 b: u8 }"),
+        ("expected `}`, found `b`".into(), "b"));
+    assert_eq!(parse_error("enum E { A = }"),
+        ("expected expression, found `}`".into(), "}"));
+    assert_eq!(parse_error("use a::{b, ;"),
+        ("expected identifier, found `;`".into(), ";"));
+    assert_eq!(parse_error("pub(crate) fn f() -> { }"),
+        ("expected type, found `{`".into(), "{"));
+    // The item after an error isn't parsed.
+    let mut iter = ItemIterator::new("fn f( {} fn g() {}");
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+    // At the end of the file, errors point at the end.
+    let error = ItemIterator::new("mod m").find_map(Result::err).unwrap();
+    assert_eq!((error.message.as_str(), error.span),
+        ("expected `{`, found end of file", Span::new(5, 5)));
+}
+
+// The text of each statement of the body of the function `text`, and of its
+// final expression.
+fn statements(text: &str) -> Vec<&str> {
+    let items = round_trip(text);
+    let body = match items[0].kind {
+        ItemKind::Fn(ref function) => function.body.as_ref().unwrap(),
+        ref kind => panic!("{:?}", kind),
+    };
+    body.stmts.iter().map(|stmt| stmt.span)
+        .chain(body.expr.iter().map(|expr| expr.span))
+        .map(|span| &text[span.start..span.end])
+        .collect()
+}
+
+#[test]
+fn expressions() {
+    let text = "fn main() {
+    let total = { let x = 1; x * 2 } + 3;
+    let Some(first) = items.first() else { return };
+    if let Some(x) = y && x > 0 { go(x)?; }
+    let close = move |a: u8, b| -> u8 { a + b };
+    'outer: loop { while i < n { break 'outer i; } }
+    let parts = text.split::<char>(',').map(str::trim).collect::<Vec<_>>();
+    let r = (..=5, 1.., a..b, ..);
+    fetch(url).await?.json()
+}";
+    assert_eq!(statements(text), [
+        "let total = { let x = 1; x * 2 } + 3;",
+        "let Some(first) = items.first() else { return };",
+        "if let Some(x) = y && x > 0 { go(x)?; }",
+        "let close = move |a: u8, b| -> u8 { a + b };",
+        "'outer: loop { while i < n { break 'outer i; } }",
+        "let parts = text.split::<char>(',').map(str::trim)\
+            .collect::<Vec<_>>();",
+        "let r = (..=5, 1.., a..b, ..);",
+        "fetch(url).await?.json()",
+    ]);
+    // A struct literal can't be the condition of an `if`.
+    let text = "fn main() { if x == S {} else {} }";
+    assert_eq!(statements(text), ["if x == S {} else {}"]);
+    assert_eq!(parse_error("fn f() { if S {} == x {} }"),
+        ("expected expression, found `==`".into(), "=="));
+}
+
+#[test]
+fn patterns() {
+    let text = "fn main() {
+    match value {
+        0 | 1 => {}
+        -5..=-1 | 10.. => {}
+        'a'..='z' | Self::MIN..=0 => {}
+        <u8 as Bounded>::MAX | <u8>::MIN..<u8 as Bounded>::MAX => {}
+        (a, .., ref mut b) => {}
+        [first, rest @ .., last] => {}
+        Point { x: 0, ref y, .. } | Point { x, y } => {}
+        Some(n @ 1..=9) if n > 2 => {}
+        &mut [_, _] | &(_, _) => {}
+        path::Unit | Wrapper(_) => {}
+    }
+}";
+    let items = round_trip(text);
+    assert_eq!(items.len(), 1);
+    assert_eq!(parse_error("fn f() { match x { 1 => } }"),
+        ("expected expression, found `}`".into(), "}"));
+    assert_eq!(parse_error("fn f() { let (a, b = x; }"),
+        ("expected `)`, found `=`".into(), "="));
+    // Constants named by qualified paths are type checked as expressions.
+    let text = "trait Tr { const C: u32; }
+        impl Tr for u32 { const C: u32 = 3; }
+        fn f(x: u8) -> u8 {
+            match x { <u32 as Tr>::C => 1, 0..<u8>::MAX => 2, _ => 3 }
+        }";
+    assert_eq!(types(text, &[]).unwrap_err(), [(
+        "mismatched types: expected `u8`, found `u32`".to_string(),
+        "<u32 as Tr>::C".to_string(),
+    )]);
+}
+
+// Errors, as messages and the text they point at
+type Errors = Vec<(String, String)>;
+
+// Load the crate of `files`, rooted at the first, configured by `cfg`.
+fn load(files: &[(&str, &str)], cfg: Cfg) -> Result<Sources, Errors> {
+    let sources = Sources::load_with(files[0].0, cfg, |path| {
+        files.iter().find(|(name, _)| FilePath::new(name) == path)
+            .map(|(_, text)| text.to_string())
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    });
+    // The errors loading these crates are in the root file, as written.
+    sources.map_err(|error| {
+        let span = error.diagnostic.span;
+        let at = &files[0].1[span.start..span.end];
+        vec![(error.diagnostic.message, at.to_string())]
+    })
+}
+
+// The messages of `errors` in `sources`, and the text they point at, or the
+// macro call they come from.
+fn messages(sources: &Sources, errors: Vec<FileDiagnostic>) -> Errors {
+    errors.into_iter()
+        .map(|error| {
+            let file = sources.file(error.file);
+            let span = file.origin(error.diagnostic.span);
+            (error.diagnostic.message,
+                file.text[span.start..span.end].to_string())
+        })
+        .collect()
+}
+
+// Load and resolve the crate of `files`, rooted at the first.  Each of
+// `names` is looked up in the root module, types first, and given with the
+// path of what it resolves to.
+fn resolve(files: &[(&str, &str)], names: &[&str])
+    -> Result<Vec<String>, Errors>
+{
+    let sources = load(files, Cfg::default())?;
+    let errors = |errors| messages(&sources, errors);
+    let krate = Crate::new(&sources).map_err(errors)?;
+    typeck::check(&krate).map_err(errors)?;
+    Ok(names.iter()
+        .map(|name| {
+            let res = krate.lookup(0, name, Namespace::Type)
+                .or_else(|| krate.lookup(0, name, Namespace::Value))
+                .unwrap_or_else(|| panic!("no `{}`", name));
+            krate.path(&res)
+        })
+        .collect())
+}
+
+#[test]
+fn modules() {
+    let files = [
+        ("src/lib.rs", "mod a; mod b;
+            pub use a::*;
+            use b::Thing;
+            pub use self::a::inner::Deep as D;"),
+        ("src/a.rs", "pub struct Thing; pub fn f() {} pub mod inner;"),
+        ("src/a/inner.rs", "pub struct Deep;
+            pub fn g() -> super::super::b::Thing { crate::b::Thing }"),
+        ("src/b/mod.rs", "pub struct Thing;"),
+    ];
+    // The import of `Thing` shadows the glob import.
+    assert_eq!(resolve(&files, &["Thing", "f", "D", "inner", "Option", "u8"])
+        .unwrap(), [
+        "crate::b::Thing", "crate::a::f", "crate::a::inner::Deep",
+        "crate::a::inner", "std::option::Option", "u8",
+    ]);
+    assert_eq!(resolve(&[("src/lib.rs", "mod gone;")], &[]).unwrap_err(), [(
+        "file not found for module `gone` (expected `src/gone.rs`)".into(),
+        "mod gone;".into(),
+    )]);
+    let files = [
+        ("src/main.rs", "mod twice;"),
+        ("src/twice.rs", ""),
+        ("src/twice/mod.rs", ""),
+    ];
+    assert_eq!(resolve(&files, &[]).unwrap_err()[0].1, "mod twice;");
+}
+
+#[test]
+fn shadowing() {
+    // Items shadow the prelude.
+    let text = "struct Vec; mod m { pub struct A; } use m::A as B;";
+    assert_eq!(resolve(&[("src/lib.rs", text)], &["Vec", "B"]).unwrap(),
+        ["crate::Vec", "crate::m::A"]);
+    // Variables shadow earlier ones, to the end of their block.
+    let text = "fn main() {
+        let x = 1u8;
+        let x = \"s\";
+        let y = x;
+        { let x = 2.0; let w = x; }
+        let z = x;
+    }";
+    assert_eq!(types(text, &["y", "w", "z"]).unwrap(),
+        ["&str", "f64", "&str"]);
+    // Imports of a block shadow the names of the module.
+    let text = "fn f() -> u8 { 1 }
+        mod m { pub fn f() -> char { 'c' } }
+        fn main() { let a = f(); { use m::f; let b = f(); } }";
+    assert_eq!(types(text, &["a", "b"]).unwrap(), ["u8", "char"]);
+}
+
+#[test]
+fn unresolved() {
+    let error = |text: &str| resolve(&[("src/lib.rs", text)], &[]).unwrap_err();
+    let expected = |message: &str, at: &str| {
+        vec![(message.to_string(), at.to_string())]
+    };
+    assert_eq!(error("use nowhere::Thing;"), expected(
+        "unresolved import `nowhere::Thing`: cannot find `nowhere` in this \
+            scope", "nowhere"));
+    assert_eq!(error("use self::m::Nope; mod m {}"), expected(
+        "unresolved import `self::m::Nope`: no `Nope` in `self::m`", "Nope"));
+    assert_eq!(error("mod m { fn private() {} } fn main() { m::private(); }"),
+        expected("`private` is private", "private"));
+    assert_eq!(error("mod m {} fn main() { crate::m::g(); }"),
+        expected("cannot find `g` in `crate::m`", "g"));
+    assert_eq!(error("mod m {} fn main() { let s: m::T = 1; }"),
+        expected("cannot find `T` in `m`", "T"));
+    assert_eq!(error("mod a { pub struct X; } use a::X; struct X;"),
+        expected("the name `X` is defined multiple times", "a::X"));
+}
+
+// The span of the first occurrence of `word` in `text` that isn't part of a
+// longer identifier.
+fn find(text: &str, word: &str) -> Span {
+    let ident = |c: Option<char>| {
+        c.is_some_and(|c| c.is_alphanumeric() || c == '_')
+    };
+    let start = text.match_indices(word)
+        .map(|(start, _)| start)
+        .find(|&start| {
+            !ident(text[..start].chars().next_back())
+                && !ident(text[start + word.len()..].chars().next())
+        })
+        .unwrap_or_else(|| panic!("no `{}`", word));
+    Span::new(start, start + word.len())
+}
+
+// Type check `text` as the root of a crate.  Each of `exprs` is the text of
+// an expression or variable pattern, found by `find`, and is given with its
+// type.
+fn types(text: &str, exprs: &[&str]) -> Result<Vec<String>, Errors> {
+    types_with(text, Cfg::default(), exprs)
+}
+
+// Type check `text` as the root of a crate configured by `cfg`, like
+// `types`.
+fn types_with(text: &str, cfg: Cfg, exprs: &[&str])
+    -> Result<Vec<String>, Errors>
+{
+    let sources = Sources::single_with("main.rs", text.to_string(), cfg);
+    let errors = |errors| messages(&sources, errors);
+    let krate = Crate::new(&sources).map_err(errors)?;
+    let types = typeck::check(&krate).map_err(errors)?;
+    Ok(exprs.iter()
+        .map(|expr| {
+            let key = (0, find(text, expr));
+            let ty = types.exprs.get(&key).or_else(|| types.bindings.get(&key))
+                .unwrap_or_else(|| panic!("no type for `{}`", expr));
+            ty.name(&krate)
+        })
+        .collect())
+}
+
+// The number of times the receiver of a method call is dereferenced, and the
+// reference taken of it.
+fn receiver(text: &str, call: &str) -> (usize, Option<bool>) {
+    let sources = Sources::single("main.rs", text.to_string());
+    let krate = Crate::new(&sources).unwrap();
+    let types = typeck::check(&krate).unwrap();
+    let method = &types.methods[&(0, find(text, call))];
+    (method.derefs, method.autoref)
+}
+
+#[test]
+fn inference() {
+    let text = "fn main() {
+        let a = 1;
+        let b = 2.0;
+        let c = a + 1i64;
+        let mut items = Vec::new();
+        items.push(b);
+        let pair = (a, 'x');
+        let last = items.pop();
+    }";
+    assert_eq!(types(text, &["a", "b", "c", "mut items", "pair", "last"])
+        .unwrap(),
+        ["i64", "f64", "i64", "Vec<f64>", "(i64, char)", "Option<f64>"]);
+    // Literals without other constraints fall back to `i32` and `f64`.
+    let text = "fn main() { let n = 7; let x = 0.5; let s = [n; 3]; }";
+    assert_eq!(types(text, &["n", "x", "s"]).unwrap(),
+        ["i32", "f64", "[i32; 3]"]);
+    // Generic functions are instantiated at each call.
+    let text = "fn twice<T: Clone>(x: T) -> (T, T) { (x.clone(), x) }
+        fn main() { let p = twice(\"hi\"); let q = twice(3u8); }";
+    assert_eq!(types(text, &["p", "q"]).unwrap(),
+        ["(&str, &str)", "(u8, u8)"]);
+    // Closures take their types from how they are used.
+    let text = "fn main() {
+        let o = Some(3).map(|x| x * 2).unwrap_or(0);
+        let s: String = \"abc\".chars().rev().collect();
+    }";
+    assert_eq!(types(text, &["o", "s", "\"abc\".chars().rev()"]).unwrap(),
+        ["i32", "String", "Rev<Chars>"]);
+}
+
+#[test]
+fn methods() {
+    // Methods of libstd through auto-ref and auto-deref
+    let text = "use std::collections::HashMap;
+        fn main() {
+            let s = String::from(\"hi\");
+            let b = &s;
+            let mut m = HashMap::new();
+            m.insert(\"k\", 1.5);
+            let upper = s.to_uppercase();
+            let len = b.len();
+            let found = m.get(\"k\");
+        }";
+    assert_eq!(types(text, &["upper", "len", "found"]).unwrap(),
+        ["String", "usize", "Option<&f64>"]);
+    // `String` derefs to `str` for `to_uppercase`, then is borrowed.
+    assert_eq!(receiver(text, "s.to_uppercase()"), (1, Some(false)));
+    assert_eq!(receiver(text, "b.len()"), (1, Some(false)));
+    assert_eq!(receiver(text, "m.insert(\"k\", 1.5)"), (0, Some(true)));
+    // Methods of traits, with associated types
+    let text = "trait Shape { type Out; fn area(&self) -> Self::Out; }
+        struct Square(f32);
+        impl Shape for Square {
+            type Out = f32;
+            fn area(&self) -> f32 { self.0 * self.0 }
+        }
+        fn main() { let s = Square(2.0); let r = &&s; r.area(); s.area(); }";
+    assert_eq!(types(text, &["r.area()"]).unwrap(), ["f32"]);
+    assert_eq!(receiver(text, "r.area()"), (1, None));
+    assert_eq!(receiver(text, "s.area()"), (0, Some(false)));
+}
+
+#[test]
+fn coercions() {
+    let text = "fn main() {
+        let a = [1, 2, 3];
+        let s: &[i32] = &a;
+        let r: &str = &String::new();
+        let m = &mut 5;
+        let i: &i32 = m;
+        let b: Box<dyn std::fmt::Debug> = Box::new(1);
+    }";
+    assert_eq!(types(text, &["&a", "s", "r", "i"]).unwrap(),
+        ["&[i32; 3]", "&[i32]", "&str", "&i32"]);
+}
+
+#[test]
+fn type_errors() {
+    let error = |text: &str| {
+        let errors = types(text, &[]).unwrap_err();
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        errors[0].clone()
+    };
+    let expected = |message: &str, at: &str| {
+        (message.to_string(), at.to_string())
+    };
+    assert_eq!(error("fn main() { let x: u8 = 1; let y: i32 = x; }"),
+        expected("mismatched types: expected `i32`, found `u8`", "x"));
+    assert_eq!(error("fn main() { let x = 1 + \"a\"; }"),
+        expected("binary operation `+` cannot be applied to type \
+            `{integer}`", "1 + \"a\""));
+    assert_eq!(error("fn main() { let s = String::new(); s.frobnicate(); }"),
+        expected("no method named `frobnicate` found for `String` in the \
+            current scope", "s"));
+    assert_eq!(error("struct P { x: i32 } fn main() { P { x: 1 }.y; }"),
+        expected("no field `y` on type `P`", "P { x: 1 }.y"));
+    assert_eq!(error("fn f(a: i32) -> i32 { a } fn main() { f(1, 2); }"),
+        expected("this function takes 1 argument but 2 arguments were \
+            supplied", "f(1, 2)"));
+    assert_eq!(error("fn main() { let v = Vec::new(); }"),
+        expected("type annotations needed", "v"));
+    assert_eq!(error("fn main() { let x = undefined; }"),
+        expected("cannot find `undefined` in this scope", "undefined"));
+}
+
+// Expand the macros of the crate of `files`, rooted at the first.  Each
+// macro call of the root file, then each call in their expansions, is given
+// with what it expands to, as their tokens separated by spaces.
+fn expand(files: &[(&str, &str)]) -> Result<Vec<(String, String)>, Errors> {
+    let sources = load(files, Cfg::default())?;
+    Crate::new(&sources).map_err(|errors| messages(&sources, errors))?;
+    let root = &sources.files()[0];
+    Ok(root.expansions()
+        .map(|(call, expansion)| {
+            let call = &root.text[call.start..call.end];
+            (tokens(call).join(" "), tokens(expansion).join(" "))
+        })
+        .collect())
+}
+
+// The expansion of each macro call in the crate root `text`.
+fn expansions(text: &str) -> Vec<(String, String)> {
+    expand(&[("src/main.rs", text)]).unwrap()
+}
+
+#[test]
+fn macro_rules() {
+    let text = "macro_rules! twice {
+    ($($x:expr),* $(,)?) => { [$($x * 2),*] };
+}
+macro_rules! sum {
+    () => { 0 };
+    ($x:expr $(, $rest:expr)*) => { $x + sum!($($rest),*) };
+}
+macro_rules! swap {
+    ($a:ident, $b:ident) => { let t = $a; $a = $b; $b = t; };
+}
+fn main() {
+    let a = twice!(1, 2 + 3,);
+    let s = sum!(1, 2);
+    let t = 0;
+    let (mut x, mut y) = (1, 2);
+    swap!(x, y);
+}";
+    // Expressions are parenthesized, and variables of the body renamed.
+    assert_eq!(expansions(text), [
+        ("twice ! ( 1 , 2 + 3 , )", "[ 1 * 2 , ( 2 + 3 ) * 2 ]"),
+        ("sum ! ( 1 , 2 )", "( 1 + sum ! ( 2 ) )"),
+        ("swap ! ( x , y ) ;", "let t__1 = x ; x = y ; y = t__1 ;"),
+        ("sum ! ( 2 )", "( 2 + sum ! ( ) )"),
+        ("sum ! ( )", "0"),
+    ].map(|(call, expansion)| (call.to_string(), expansion.to_string())));
+}
+
+#[test]
+fn fragments() {
+    let text = "macro_rules! parts {
+    ($p:pat, $t:path, $l:literal, $b:block, $i:item, $lt:lifetime, $v:vis) => {
+        $i
+        fn get<$lt>(x: &$lt u8) -> $t { match *x { $p => $l, _ => $b } }
+        $v struct S;
+    };
+}
+parts!(1 | 2, std::string::String, 7u8, { 0 }, fn k() {}, 'a, pub(crate));";
+    assert_eq!(expansions(text)[0].1,
+        "fn k ( ) { } fn get < 'a > ( x : & 'a u8 ) -> std :: string :: \
+        String { match * x { 1 | 2 => 7u8 , _ => { 0 } } } pub ( crate ) \
+        struct S ;");
+}
+
+#[test]
+fn builtin_macros() {
+    let files = [
+        ("src/main.rs", "fn main() {
+    let s = stringify!(a + b * (c));
+    let c = concat!(\"x\", 1, 2.5, true, 'c', -3);
+    let l = line!();
+    let col = column!();
+    let f = file!();
+    let words = include_str!(\"data/words.txt\");
+    let unix = cfg!(all(unix, not(windows)));
+}"),
+        ("src/data/words.txt", "one\ntwo \"2\"\n"),
+    ];
+    let expanded: Vec<_> = expand(&files).unwrap().into_iter()
+        .map(|(_, expansion)| expansion)
+        .collect();
+    assert_eq!(expanded, [
+        "\"a + b * (c)\"", "\"x12.5truec-3\"", "4u32", "15u32",
+        "\"src/main.rs\"", "\"one\\ntwo \\\"2\\\"\\n\"", "true",
+    ]);
+}
+
+#[test]
+fn macro_errors() {
+    let error = |text: &str| expand(&[("src/lib.rs", text)]).unwrap_err();
+    let expected = |message: &str, at: &str| {
+        vec![(message.to_string(), at.to_string())]
+    };
+    let two = "macro_rules! two { ($a:expr, $b:expr) => { $a + $b }; }";
+    assert_eq!(error(&format!("{} fn f() {{ two!(1; 2); }}", two)),
+        expected("no rules expected the token `;`", ";"));
+    assert_eq!(error(&format!("{} fn f() {{ two!(1,); }}", two)),
+        expected("unexpected end of macro invocation", ")"));
+    assert_eq!(error("macro_rules! r { () => { r!() }; } fn f() { r!(); }"),
+        expected("recursion limit reached while expanding `r!`", "r!()"));
+    assert_eq!(error("fn f() { line!(1); }"),
+        expected("`line!` takes no arguments", "1"));
+    assert_eq!(error("fn f() { include_str!(\"gone.txt\"); }"),
+        expected("couldn't read `src/gone.txt`: entity not found",
+            "\"gone.txt\""));
+}
+
+#[test]
+fn configurations() {
+    let text = "#[cfg(feature = \"verbose\")] type Log = String;
+#[cfg(not(feature = \"verbose\"))] type Log = ();
+#[cfg(test)] type Build = u8;
+#[cfg(not(test))] type Build = u16;
+#[cfg(all(unix, target_os = \"linux\"))] type Os = i32;
+#[cfg(windows)] type Os = i64;
+#[cfg_attr(feature = \"verbose\", derive(PartialEq))]
+struct S;
+fn main() {
+    let log: Option<Log> = None;
+    let build: Option<Build> = None;
+    let os: Option<Os> = None;
+    let set = (cfg!(feature = \"verbose\"), cfg!(test), cfg!(windows));
+}";
+    let run = |cfg: Cfg| {
+        let sources = Sources::single_with("main.rs", text.to_string(),
+            cfg.clone());
+        let root = &sources.files()[0];
+        let set = root.expansions()
+            .filter(|(call, _)| root.text[call.start..].starts_with("cfg!"))
+            .map(|(_, expansion)| expansion.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let types = types_with(text, cfg, &["log", "build", "os"]).unwrap();
+        (types.join(" "), set)
+    };
+    let default = Cfg::default();
+    assert_eq!(run(default.clone()), (
+        "Option<()> Option<u16> Option<i32>".to_string(),
+        "false false false".to_string(),
+    ));
+
+    let mut verbose = default.clone();
+    verbose.feature("verbose");
+    let (types, set) = run(verbose.clone());
+    assert_eq!((types.as_str(), set.as_str()),
+        ("Option<String> Option<u16> Option<i32>", "true false false"));
+    // Only the `verbose` build derives the `PartialEq` that `==` needs.
+    let compare = format!("{} fn same() -> bool {{ S == S }}", text);
+    assert_eq!(types_with(&compare, verbose, &["S == S"]).unwrap(), ["bool"]);
+    assert_eq!(types_with(&compare, default.clone(), &[]).unwrap_err(), [(
+        "binary operation `==` cannot be applied to type `S`".to_string(),
+        "S == S".to_string(),
+    )]);
+
+    let mut test = default.clone();
+    test.set("test", None);
+    assert_eq!(run(test).0, "Option<()> Option<u8> Option<i32>");
+
+    let mut windows = default;
+    for name in ["target_os", "target_family", "unix"] {
+        windows.unset(name);
+    }
+    windows.set("target_os", Some("windows"));
+    windows.set("target_family", Some("windows"));
+    windows.set("windows", None);
+    assert_eq!(run(windows), (
+        "Option<()> Option<u16> Option<i64>".to_string(),
+        "false false true".to_string(),
+    ));
+}
+
+#[test]
+fn derives() {
+    let text = "use std::collections::HashMap;
+#[derive(PartialEq, PartialOrd)]
+struct Meters(f64);
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Point { x: i32, y: u8 }
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Level { High = 2, Low = 1, Lower, Unknown(u8) }
+fn main() {
+    let shorter = Meters(1.0) < Meters(2.0);
+    let order = Meters(1.0).partial_cmp(&Meters(f64::NAN));
+    let p = Point { x: 1, y: 2 };
+    let max = p.max(Point { x: 0, y: 3 });
+    let seen = {
+        let mut seen = HashMap::new();
+        seen.insert(p, Level::High);
+        seen
+    };
+    let level = Level::Low.cmp(&Level::Unknown(0));
+}
+#[derive(Debug, Default)]
+struct Last(u8);";
+    assert_eq!(types(text, &["shorter", "order", "max", "seen", "level"])
+        .unwrap(), [
+            "bool", "Option<Ordering>", "Point", "HashMap<Point, Level>",
+            "Ordering",
+        ]);
+
+    // Fields compare in order, and variants by their discriminants, which
+    // are also what's hashed.
+    let text = "#[derive(PartialEq, PartialOrd, Hash)]
+struct P { x: i32, y: u8 }
+#[derive(PartialOrd, Hash)]
+enum E { A = 2, B, C(u8) }";
+    let expanded = expansions(text);
+    let compare = |a: &str, b: &str| {
+        format!("match :: core :: cmp :: PartialOrd :: partial_cmp ( {} , \
+            {} ) {{ :: core :: option :: Option :: Some ( :: core :: cmp :: \
+            Ordering :: Equal ) => {{ }} ordering => return ordering , }}",
+            a, b)
+    };
+    let point = &expanded[0].1;
+    assert!(point.contains(&format!("{{ {} {} :: core",
+        compare("& self . x", "& other . x"),
+        compare("& self . y", "& other . y"))));
+    assert!(point.contains("hash ( & self . x , state ) ; :: core :: hash \
+        :: Hash :: hash ( & self . y , state ) ;"));
+    let e = &expanded[1].1;
+    let tags = "E :: A => ( ( 2 ) as isize ) , E :: B => ( ( 2 ) as isize \
+        + 1 ) , E :: C ( .. ) => ( ( 2 ) as isize + 2 ) ,";
+    assert!(e.contains(&format!("let __self_tag = match * self {{ {} }}",
+        tags)));
+    assert!(e.contains("_ => :: core :: cmp :: PartialOrd :: partial_cmp ( \
+        & __self_tag , & __other_tag ) ,"));
+    assert!(e.contains("E :: B => { :: core :: hash :: Hash :: hash ( & ( \
+        ( 2 ) as isize + 1 ) , state ) ; }"));
+
+    let error = |text: &str| types(text, &[]).unwrap_err();
+    let expected = |messages: &[&str]| {
+        messages.iter()
+            .map(|message| (message.to_string(), String::new()))
+            .collect::<Vec<_>>()
+    };
+    // Errors in derived impls point at the end of the item they're for.
+    assert_eq!(error("#[derive(PartialEq, Eq, PartialOrd, Ord)] \
+            struct F(f64);"),
+        expected(&["the trait bound `f64: Ord` is not satisfied",
+            "the trait bound `f64: Eq` is not satisfied"]));
+    assert_eq!(error("#[derive(Ord)] struct K(u8);"),
+        expected(&["the trait bound `K: Eq` is not satisfied",
+            "the trait bound `K: PartialOrd<K>` is not satisfied"]));
+    assert_eq!(error("#[derive(Hash)] struct K(Vec<f64>);"),
+        expected(&["the trait bound `Vec<f64>: Hash` is not satisfied"]));
+    assert_eq!(error("use std::collections::HashMap;
+        #[derive(PartialEq, Eq)] struct K(u8);
+        fn f() { HashMap::new().insert(K(1), 2); }"), [(
+            "the trait bound `K: Hash` is not satisfied".to_string(),
+            "HashMap::new().insert(K(1), 2)".to_string(),
+        )]);
+}
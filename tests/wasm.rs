@@ -0,0 +1,254 @@
+// WebAssembly tests
+//
+// Every module `tests/wasm/*.ir` must print as its `.wat` file.  Every IR
+// sample of the repository and every C program, at `-O0` and `-O2`, must
+// translate to a module that validates and decodes back from its binary
+// encoding unchanged.  Where Node.js is installed, every C program in
+// `tests/wasm` and `tests/x86_64`, run by `tests/wasm/run.js`, must print
+// its `.out` file.
+
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use compiler::ir::{self, Module};
+use compiler::wasm::{self, ExportKind, FuncType, Instr, ValType};
+
+use common::{expected, parse, temp};
+#[cfg(feature = "c")]
+use common::{compile_c, installed};
+
+// The files of a directory of the corpus with extension `ext`, sorted.
+fn samples(dir: &str, ext: &str) -> Vec<PathBuf> {
+    common::samples(dir, |found| found == ext)
+}
+
+// The C programs to run, with the ones of the x86-64 tests.
+#[cfg(feature = "c")]
+fn programs() -> Vec<PathBuf> {
+    let mut paths = samples("tests/wasm", "c");
+    paths.extend(samples("tests/x86_64", "c"));
+    paths
+}
+
+fn translate(name: &str, module: &Module) -> wasm::Module {
+    wasm::translate(module).unwrap_or_else(|error| {
+        panic!("{}: {}", name, error.message)
+    })
+}
+
+#[test]
+fn text() {
+    for path in samples("tests/wasm", "ir") {
+        let module = translate(&path.display().to_string(), &parse(&path));
+        assert_eq!(module.to_string(), expected(&path, "wat"), "{}",
+            path.display());
+    }
+}
+
+#[test]
+fn binary() {
+    let mut modules = Vec::new();
+    for dir in ["tests/wasm", "tests/x86_64", "tests/ir"] {
+        for path in samples(dir, "ir") {
+            modules.push((path.display().to_string(), parse(&path)));
+        }
+    }
+    // `@external` is defined elsewhere.
+    modules.retain(|(name, _)| !name.ends_with("x86_64/globals.ir"));
+    #[cfg(feature = "c")]
+    for path in programs() {
+        for level in [0, 2] {
+            let name = format!("{} -O{}", path.display(), level);
+            modules.push((name, compile_c(&path, level)));
+        }
+    }
+    for (name, module) in &modules {
+        let module = translate(name, module);
+        wasm::validate(&module).unwrap_or_else(|error| {
+            panic!("{}: {}", name, error.message)
+        });
+        let bytes = module.encode();
+        let decoded = wasm::decode(&bytes).unwrap_or_else(|error| {
+            panic!("{}: {} at byte {}", name, error.message, error.span.start)
+        });
+        assert!(decoded == module, "{}: decodes differently", name);
+    }
+}
+
+#[test]
+fn layout() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/wasm/memory.ir");
+    let module = translate("memory.ir", &parse(&path));
+    // `@twice` is in the table, at index 1, and in `@handlers`.
+    assert_eq!(module.table, Some(2));
+    assert_eq!(module.elements[0].offset, 1);
+    let handlers = module.data.iter().find(|data| data.offset == 1040)
+        .unwrap();
+    assert_eq!(handlers.bytes[..8], [1, 0, 0, 0, 0, 0, 0, 0]);
+    // Exported globals hold the addresses of the variables.
+    let address = |name: &str| {
+        let export = module.exports.iter().find(|e| e.name == name).unwrap();
+        assert_eq!(export.kind, ExportKind::Global);
+        module.globals[export.index as usize].init.clone()
+    };
+    assert_eq!(address("count"), Instr::I32Const(1032));
+    assert_eq!(address("origin"), Instr::I32Const(1056));
+    assert!(module.exports.iter().all(|export| export.name != "fmt.0"));
+    // The stack starts at 64 KiB past the end of the data, aligned.
+    assert_eq!(module.globals[0].name, "__stack_pointer");
+    assert_eq!(address("__heap_base"), Instr::I32Const(1088 + 65536));
+    assert_eq!(module.memory, Some(2));
+    // `@printf` takes the address of its extra arguments last, and the
+    // aggregate `@pick` returns is written to an address passed first.
+    let ty = |index| module.function_type(index).unwrap().clone();
+    assert_eq!(ty(0), FuncType {
+        params: vec![ValType::I32, ValType::I32],
+        results: vec![ValType::I32],
+    });
+    assert_eq!(ty(4), FuncType {
+        params: vec![ValType::I32, ValType::I32, ValType::I32],
+        results: vec![],
+    });
+}
+
+#[test]
+fn decoder() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/wasm/control.ir");
+    let bytes = translate("control.ir", &parse(&path)).encode();
+    let error = |bytes: &[u8]| wasm::decode(bytes).unwrap_err().message;
+    assert_eq!(error(b"\0elf\x01\0\0\0"), "not a WebAssembly module");
+    assert_eq!(error(b"\0asm\x02\0\0\0"), "unknown version");
+    assert_eq!(error(&bytes[..bytes.len() - 3]), "length past the end");
+    // A second type section
+    let mut twice = bytes[..8].to_vec();
+    twice.extend([1, 1, 0, 1, 1, 0]);
+    assert_eq!(error(&twice), "unexpected section");
+}
+
+#[test]
+fn validator() {
+    let module = |body: Vec<Instr>| wasm::Module {
+        types: vec![FuncType {
+            params: vec![ValType::I32],
+            results: vec![ValType::I32],
+        }],
+        functions: vec![wasm::Function {
+            name: "f".to_string(),
+            ty: 0,
+            locals: vec![ValType::F64],
+            body,
+        }],
+        memory: Some(1),
+        ..wasm::Module::default()
+    };
+    let error = |body: Vec<Instr>| {
+        wasm::validate(&module(body)).unwrap_err().message
+    };
+    assert!(wasm::validate(&module(vec![Instr::LocalGet(0)])).is_ok());
+    assert_eq!(error(vec![Instr::LocalGet(1)]),
+        "in function `f`, instruction 1: expected `i32`, found `f64`");
+    assert_eq!(error(vec![
+        Instr::LocalGet(0),
+        Instr::I64Const(1),
+        Instr::numeric("i32.add"),
+    ]), "in function `f`, instruction 2: expected `i32`, found `i64`");
+    assert_eq!(error(vec![Instr::Block, Instr::LocalGet(0), Instr::End]),
+        "in function `f`, instruction 2: values left at the end of a block");
+    assert_eq!(error(vec![Instr::Br(1)]),
+        "in function `f`, instruction 0: no label 1");
+    assert_eq!(error(vec![Instr::Call(1)]),
+        "in function `f`, instruction 0: no function 1");
+    // Anything may be popped after an unconditional branch.
+    assert!(wasm::validate(&module(vec![
+        Instr::Unreachable,
+        Instr::numeric("f64.neg"),
+        Instr::Drop,
+    ])).is_ok());
+}
+
+#[cfg(feature = "c")]
+#[test]
+fn run() {
+    if !installed("node") {
+        eprintln!("skipped: no Node.js to run modules with");
+        return;
+    }
+    let runner = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/wasm/run.js");
+    for path in programs() {
+        for level in [0, 2] {
+            let name = path.file_stem().unwrap().to_str().unwrap();
+            let binary = temp(&format!("{}-O{}.wasm", name, level));
+            let module = translate(name, &compile_c(&path, level));
+            fs::write(&binary, module.encode()).unwrap();
+            let output = Command::new("node").arg(&runner).arg(&binary)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{} -O{}: {}\n{}",
+                path.display(), level, output.status,
+                String::from_utf8_lossy(&output.stderr));
+            assert_eq!(String::from_utf8(output.stdout).unwrap(),
+                expected(&path, "out"), "{} -O{}", path.display(), level);
+            fs::remove_file(binary).unwrap();
+        }
+    }
+}
+
+#[test]
+fn unsupported() {
+    let error = |text: &str| {
+        wasm::translate(&ir::parse(text).unwrap()).unwrap_err().message
+    };
+    assert_eq!(error("\
+define i128 @wide(i128 %x) {
+entry:
+  ret i128 %x
+}
+"), "in `@wide`: `i128` isn't supported by the WebAssembly back end");
+    assert_eq!(error("\
+@errno = external global i32
+
+define i32 @get() {
+entry:
+  %e = load i32, @errno
+  ret i32 %e
+}
+"), "in `@get`: `@errno`, a global variable defined elsewhere, isn't \
+        supported by the WebAssembly back end");
+}
+
+#[test]
+fn command() {
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_compiler"))
+            .args(args)
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        (output.status.success(), output.stdout, stderr)
+    };
+    let sample = "tests/wasm/control.ir";
+    let (ok, out, _) = run(&["--emit", "wat", sample]);
+    assert!(ok);
+    assert_eq!(String::from_utf8(out).unwrap(),
+        expected(Path::new(sample), "wat"));
+    let binary = temp("command.wasm");
+    let (ok, _, _) = run(&["-O2", "--emit", "wasm", "-o",
+        binary.to_str().unwrap(), sample]);
+    assert!(ok);
+    wasm::validate(&wasm::decode(&fs::read(&binary).unwrap()).unwrap())
+        .unwrap();
+    fs::remove_file(binary).unwrap();
+    let (ok, _, err) = run(&["--emit", "wasm", sample]);
+    assert!(!ok);
+    assert_eq!(err, "`--emit wasm` needs `-o`\n");
+    let (ok, _, err) = run(&["--emit", "exe", sample]);
+    assert!(!ok);
+    assert_eq!(err, "`--emit` needs `ir`, `asm`, `obj`, `wasm` or `wat`\n");
+}
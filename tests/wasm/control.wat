@@ -0,0 +1,184 @@
+(module
+  (type (;0;) (func (param i32 i32) (result i32)))
+  (type (;1;) (func (param i32) (result i32)))
+  (type (;2;) (func (param i64) (result i64)))
+  (type (;3;) (func (param i64) (result i32)))
+  (func $gcd (type 0) (param i32 i32) (result i32)
+    (local i32 i32 i32)
+    local.get 0
+    local.get 1
+    local.set 3
+    local.set 2
+    loop
+      local.get 3
+      i32.const 0
+      i32.eq
+      if
+        local.get 2
+        return
+      else
+        local.get 2
+        local.get 3
+        i32.rem_s
+        local.set 4
+        local.get 3
+        local.get 4
+        local.set 3
+        local.set 2
+        br 1
+      end
+    end
+    unreachable)
+  (func $dense (type 1) (param i32) (result i32)
+    (local i32)
+    block
+      block
+        block
+          block
+            block
+              local.get 0
+              i32.const 1
+              i32.sub
+              br_table 3 1 2 0 3 0
+            end
+            i32.const 0
+            local.set 1
+            br 3
+          end
+          i32.const 20
+          local.set 1
+          br 2
+        end
+        i32.const 30
+        return
+      end
+      i32.const 10
+      local.set 1
+    end
+    local.get 1)
+  (func $sparse (type 2) (param i64) (result i64)
+    block
+      block
+        local.get 0
+        i64.const 100
+        i64.eq
+        br_if 1
+        local.get 0
+        i64.const -7
+        i64.eq
+        br_if 0
+        local.get 0
+        i64.const 8
+        i64.eq
+        br_if 1
+        i64.const 0
+        return
+      end
+      i64.const 2
+      return
+    end
+    i64.const 1)
+  (func $wide (type 3) (param i64) (result i32)
+    (local i64)
+    block
+      block
+        block
+          local.get 0
+          i64.const 4
+          i64.sub
+          local.tee 1
+          i64.const 3
+          i64.ge_u
+          br_if 0
+          local.get 1
+          i32.wrap_i64
+          br_table 2 1 2 0
+        end
+        i32.const 0
+        return
+      end
+      i32.const 1
+      return
+    end
+    i32.const -1)
+  (func $tangle (type 0) (param i32 i32) (result i32)
+    (local i32 i32 i32 i32 i32 i32)
+    loop
+      block
+        block
+          block
+            block
+              local.get 7
+              br_table 0 1 2 3 3
+            end
+            local.get 0
+            if
+              local.get 1
+              local.set 2
+              i32.const 2
+              local.set 7
+              br 4
+            else
+              local.get 1
+              local.set 4
+              i32.const 1
+              local.set 7
+              br 4
+            end
+          end
+          local.get 4
+          i32.const 2
+          i32.sub
+          local.set 5
+          local.get 5
+          i32.const 0
+          i32.le_s
+          if
+            local.get 5
+            local.set 6
+            i32.const 3
+            local.set 7
+            br 3
+          else
+            local.get 5
+            local.set 2
+            i32.const 2
+            local.set 7
+            br 3
+          end
+        end
+        local.get 2
+        i32.const 1
+        i32.sub
+        local.set 3
+        local.get 3
+        i32.const 0
+        i32.le_s
+        if
+          local.get 3
+          local.set 6
+          i32.const 3
+          local.set 7
+          br 2
+        else
+          local.get 3
+          local.set 4
+          i32.const 1
+          local.set 7
+          br 2
+        end
+      end
+      local.get 6
+      return
+    end
+    unreachable)
+  (memory (;0;) 2)
+  (global $__stack_pointer (mut i32) (i32.const 66560))
+  (global $__heap_base i32 (i32.const 66560))
+  (export "memory" (memory 0))
+  (export "__heap_base" (global $__heap_base))
+  (export "gcd" (func $gcd))
+  (export "dense" (func $dense))
+  (export "sparse" (func $sparse))
+  (export "wide" (func $wide))
+  (export "tangle" (func $tangle)))
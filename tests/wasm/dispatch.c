@@ -0,0 +1,50 @@
+/* Calls through a table of function pointers, a switch lowered to a
+   `br_table`, and a loop with two entries that only the dispatch loop
+   can translate. */
+int printf(const char *fmt, ...);
+
+int add(int a, int b) { return a + b; }
+int sub(int a, int b) { return a - b; }
+int mul(int a, int b) { return a * b; }
+
+int (*ops[3])(int, int) = {add, sub, mul};
+
+const char *name(int op) {
+    switch (op) {
+    case 0: return "add";
+    case 1: return "sub";
+    case 2: return "mul";
+    case 3:
+    case 4: return "none";
+    default: return "?";
+    }
+}
+
+int tangle(int start, int n) {
+    int steps = 0;
+    if (start) goto left;
+right:
+    n -= 2;
+    steps++;
+    if (n <= 0) return steps * 100 + n;
+left:
+    n -= 1;
+    steps++;
+    if (n <= 0) return steps * 100 + n;
+    goto right;
+}
+
+int main(void) {
+    for (int i = 0; i < 5; i++) {
+        int r = i < 3 ? ops[i](7, 3) : 0;
+        printf("%s %d\n", name(i), r);
+    }
+    printf("%d %d\n", tangle(0, 10), tangle(1, 10));
+    unsigned char c = 250;
+    c += 10;
+    short s = 32767;
+    s++;
+    long big = 1L << 40;
+    printf("%u %d %ld %lu\n", c, s, big / 3, (unsigned long)-1 / 2);
+    return 0;
+}
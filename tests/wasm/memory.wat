@@ -0,0 +1,142 @@
+(module
+  (type (;0;) (func (param i32 i32) (result i32)))
+  (type (;1;) (func (param f64 f64) (result f64)))
+  (type (;2;) (func (param i32) (result i32)))
+  (type (;3;) (func (param i32 i32 i32)))
+  (type (;4;) (func (param i32) (result f64)))
+  (import "env" "printf" (func $printf (type 0)))
+  (import "env" "fmod" (func $fmod (type 1)))
+  (func $twice (type 2) (param i32) (result i32)
+    local.get 0
+    i32.const 1
+    i32.shl)
+  (func $dispatch (type 2) (param i32) (result i32)
+    (local i32 i32)
+    local.get 0
+    i32.const 1040
+    i32.load
+    call_indirect (type 2)
+    local.set 1
+    local.get 1
+    i32.const 1032
+    i32.load
+    i32.add
+    local.set 2
+    i32.const 1032
+    local.get 2
+    i32.store
+    local.get 2)
+  (func $pick (type 3) (param i32 i32 i32)
+    (local i32)
+    global.get $__stack_pointer
+    i32.const 32
+    i32.sub
+    local.tee 3
+    global.set $__stack_pointer
+    local.get 1
+    if
+      local.get 3
+      local.get 2
+      i32.const 16
+      memory.copy
+      local.get 3
+      i32.const 7
+      i32.store
+      local.get 3
+      i32.const 16
+      i32.add
+      local.get 3
+      i32.const 16
+      memory.copy
+    else
+      local.get 3
+      i32.const 16
+      i32.add
+      i32.const 1072
+      i32.const 16
+      memory.copy
+    end
+    local.get 0
+    local.get 3
+    i32.const 16
+    i32.add
+    i32.const 16
+    memory.copy
+    local.get 3
+    i32.const 32
+    i32.add
+    global.set $__stack_pointer)
+  (func $report (type 4) (param i32) (result f64)
+    (local i32 f64 i32)
+    global.get $__stack_pointer
+    i32.const 64
+    i32.sub
+    local.tee 3
+    global.set $__stack_pointer
+    local.get 3
+    local.get 0
+    i32.store8
+    local.get 3
+    i32.const 8
+    i32.add
+    i32.const 1056
+    i32.const 16
+    memory.copy
+    local.get 3
+    i32.const 24
+    i32.add
+    i32.const 1
+    local.get 3
+    i32.const 8
+    i32.add
+    call $pick
+    local.get 3
+    i32.load offset=24
+    local.set 1
+    local.get 3
+    f64.load offset=32
+    f64.const 0.25
+    call $fmod
+    local.set 2
+    i32.const 1024
+    local.get 3
+    local.get 1
+    local.get 3
+    i32.load8_s
+    i32.add
+    i32.store offset=40
+    local.get 3
+    local.get 2
+    f64.store offset=48
+    local.get 3
+    i32.const 40
+    i32.add
+    call $printf
+    drop
+    local.get 2
+    local.get 3
+    i32.const 64
+    i32.add
+    global.set $__stack_pointer)
+  (table (;0;) 2 2 funcref)
+  (memory (;0;) 2)
+  (global $__stack_pointer (mut i32) (i32.const 66624))
+  (global $__heap_base i32 (i32.const 66624))
+  (global $count i32 (i32.const 1032))
+  (global $handlers i32 (i32.const 1040))
+  (global $origin i32 (i32.const 1056))
+  (export "memory" (memory 0))
+  (export "__heap_base" (global $__heap_base))
+  (export "twice" (func $twice))
+  (export "dispatch" (func $dispatch))
+  (export "pick" (func $pick))
+  (export "report" (func $report))
+  (export "count" (global $count))
+  (export "handlers" (global $handlers))
+  (export "origin" (global $origin))
+  (elem (;0;) (i32.const 1) func $twice)
+  (data (;0;) (i32.const 1024) "%d %f\0a\00")
+  (data (;1;) (i32.const 1032) "\03\00\00\00")
+  (data (;2;) (i32.const 1040) "\01\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00")
+  (data (;3;) (i32.const 1056) "\01\00\00\00\00\00\00\00\00\00\00\00\00\00\e0?")
+  (data (;4;) (i32.const 1072) "\01\00\00\00\00\00\00\00\00\00\00\00\00\00\e0?"))
@@ -0,0 +1,102 @@
+// Runs a WebAssembly module compiled from C, with the few C library
+// functions the test programs use, and exits with what `main` returns.
+//
+// Extra arguments of variadic functions are passed as the address of a
+// struct of their types, so `printf` walks it as its format says, and
+// `vprintf` walks the one whose address a `va_list` keeps at offset 8.
+
+const fs = require("fs");
+
+const bytes = fs.readFileSync(process.argv[2]);
+let memory;
+let output = "";
+
+function view() {
+    return new DataView(memory.buffer);
+}
+
+function string(address) {
+    const bytes = new Uint8Array(memory.buffer);
+    let end = address;
+    while (bytes[end] !== 0) {
+        end++;
+    }
+    return Buffer.from(bytes.subarray(address, end)).toString("latin1");
+}
+
+function printf(format, args) {
+    const text = string(format);
+    let at = args;
+    const next = (size) => {
+        at = Math.ceil(at / size) * size;
+        const address = at;
+        at += size;
+        return address;
+    };
+    let out = "";
+    for (let i = 0; i < text.length; i++) {
+        if (text[i] !== "%") {
+            out += text[i];
+            continue;
+        }
+        const spec = /^%(-?)(\d*)(?:\.(\d+))?(l*)([dicuxsf%])/
+            .exec(text.slice(i));
+        i += spec[0].length - 1;
+        const [, left, width, precision, long, conversion] = spec;
+        let value;
+        switch (conversion) {
+            case "%":
+                value = "%";
+                break;
+            case "d":
+            case "i":
+                value = long
+                    ? view().getBigInt64(next(8), true).toString()
+                    : view().getInt32(next(4), true).toString();
+                break;
+            case "u":
+            case "x":
+                value = long
+                    ? view().getBigUint64(next(8), true)
+                    : view().getUint32(next(4), true);
+                value = value.toString(conversion === "x" ? 16 : 10);
+                break;
+            case "c":
+                value = String.fromCharCode(view().getInt32(next(4), true) & 255);
+                break;
+            case "s":
+                value = string(view().getUint32(next(8), true));
+                break;
+            case "f":
+                value = view().getFloat64(next(8), true)
+                    .toFixed(precision === undefined ? 6 : Number(precision));
+                break;
+        }
+        const pad = " ".repeat(Math.max(0, Number(width || 0) - value.length));
+        out += left ? value + pad : pad + value;
+    }
+    output += out;
+    return out.length;
+}
+
+const env = {
+    printf,
+    vprintf: (format, ap) => printf(format, view().getUint32(ap + 8, true)),
+    puts: (s) => {
+        output += string(s) + "\n";
+        return 0;
+    },
+    putchar: (c) => {
+        output += String.fromCharCode(c & 255);
+        return c;
+    },
+    fmod: (a, b) => a % b,
+    fmodf: (a, b) => Math.fround(a % b),
+};
+
+WebAssembly.instantiate(bytes, { env }).then(({ instance }) => {
+    memory = instance.exports.memory;
+    const status = instance.exports.main();
+    process.stdout.write(Buffer.from(output, "latin1"));
+    process.exit(status & 255);
+});
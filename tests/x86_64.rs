@@ -0,0 +1,278 @@
+// x86-64 tests
+//
+// Every module `tests/x86_64/*.ir` must print as its `.s` file in
+// assembly.  Every program `tests/x86_64/*.c`, compiled at `-O0` and
+// `-O2` and linked with the system's C compiler, must print its `.out`
+// file.  Where the GNU assembler is installed, the assembly of every
+// sample must assemble to the same code and data as the object written
+// for it.
+
+mod common;
+
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use compiler::ir;
+use compiler::x86_64;
+
+use common::{expected, installed, parse, samples, temp};
+#[cfg(feature = "c")]
+use common::compile_c;
+
+// An ELF64 relocatable object, read back.
+struct Elf {
+    // The name, type, flags, contents, link and info of each section
+    sections: Vec<(String, u32, u64, Vec<u8>, u32, u32)>,
+}
+
+impl Elf {
+    fn read(bytes: &[u8]) -> Elf {
+        let u16_at = |at: usize| {
+            u16::from_le_bytes(bytes[at..at + 2].try_into().unwrap())
+        };
+        let u32_at = |at: usize| {
+            u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+        };
+        let u64_at = |at: usize| {
+            u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+        };
+        assert_eq!(&bytes[..4], b"\x7fELF");
+        assert_eq!(&bytes[4..6], &[2, 1], "not 64-bit little-endian");
+        assert_eq!(u16_at(16), 1, "not relocatable");
+        assert_eq!(u16_at(18), 62, "not x86-64");
+        let table = u64_at(40) as usize;
+        let count = u16_at(60) as usize;
+        let names = u16_at(62) as usize;
+        let header = |index: usize| table + 64 * index;
+        let contents = |index: usize| {
+            let at = header(index);
+            let (offset, size) = (u64_at(at + 24), u64_at(at + 32));
+            match u32_at(at + 4) {
+                // `SHT_NOBITS`
+                8 => Vec::new(),
+                _ => bytes[offset as usize..][..size as usize].to_vec(),
+            }
+        };
+        let strings = contents(names);
+        let sections = (0..count)
+            .map(|index| {
+                let at = header(index);
+                let name = string(&strings, u32_at(at) as usize);
+                (name, u32_at(at + 4), u64_at(at + 8), contents(index),
+                    u32_at(at + 40), u32_at(at + 44))
+            })
+            .collect();
+        Elf { sections }
+    }
+
+    fn section(&self, name: &str) -> &(String, u32, u64, Vec<u8>, u32, u32) {
+        self.sections.iter().find(|section| section.0 == name)
+            .unwrap_or_else(|| panic!("no section `{}`", name))
+    }
+
+    // The bytes of a section, empty if there's no such section.
+    fn bytes(&self, name: &str) -> &[u8] {
+        self.sections.iter().find(|section| section.0 == name)
+            .map_or(&[], |section| &section.3)
+    }
+
+    // The name, binding, type and section index of each symbol.
+    fn symbols(&self) -> Vec<(String, u8, u8, u16)> {
+        let symtab = self.section(".symtab");
+        let strtab = &self.sections[symtab.4 as usize].3;
+        symtab.3.chunks(24).skip(1)
+            .map(|entry| {
+                let name = u32::from_le_bytes(entry[..4].try_into().unwrap());
+                let section = u16::from_le_bytes([entry[6], entry[7]]);
+                (string(strtab, name as usize), entry[4] >> 4, entry[4] & 15,
+                    section)
+            })
+            .collect()
+    }
+
+    // The offset, symbol, type and addend of each relocation of a section.
+    fn relocations(&self, section: &str) -> Vec<(u64, String, u32, i64)> {
+        let symbols = self.symbols();
+        self.section(&format!(".rela{}", section)).3.chunks(24)
+            .map(|entry| {
+                let word = |at: usize| {
+                    u64::from_le_bytes(entry[at..at + 8].try_into().unwrap())
+                };
+                let info = word(8);
+                let symbol = symbols[(info >> 32) as usize - 1].0.clone();
+                (word(0), symbol, info as u32, word(16) as i64)
+            })
+            .collect()
+    }
+}
+
+fn string(table: &[u8], at: usize) -> String {
+    let len = table[at..].iter().position(|&b| b == 0).unwrap();
+    String::from_utf8(table[at..at + len].to_vec()).unwrap()
+}
+
+#[test]
+fn assembly() {
+    for path in samples("tests/x86_64", |ext| ext == "ir") {
+        let module = parse(&path);
+        let text = x86_64::assembly(&module).unwrap_or_else(|error| {
+            panic!("{}: {}", path.display(), error.message)
+        });
+        assert_eq!(text, expected(&path, "s"), "{}", path.display());
+    }
+}
+
+#[test]
+fn objects() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/x86_64/globals.ir");
+    let elf = Elf::read(&x86_64::object(&parse(&path)).unwrap());
+    let names: Vec<_> = elf.sections.iter().map(|s| s.0.as_str()).collect();
+    assert_eq!(names, [
+        "", ".text", ".rela.text", ".data", ".rela.data", ".bss", ".rodata",
+        ".note.GNU-stack", ".symtab", ".strtab", ".shstrtab",
+    ]);
+    // `@limit` is 100 in `.rodata`, `@counter` in `.bss`, and `@table`
+    // holds the addresses of both.
+    assert_eq!(elf.section(".rodata").3, b"\x64\0\0\0hi\0");
+    assert_eq!(elf.section(".data").3, [0; 16]);
+    let index = |name: &str| {
+        elf.sections.iter().position(|s| s.0 == name).unwrap() as u16
+    };
+    // Local symbols come first; `@external` is undefined.
+    assert_eq!(elf.symbols(), [
+        ("name.0".to_string(), 0, 1, index(".rodata")),
+        ("bump".to_string(), 1, 2, index(".text")),
+        ("limit".to_string(), 1, 1, index(".rodata")),
+        ("counter".to_string(), 1, 1, index(".bss")),
+        ("table".to_string(), 1, 1, index(".data")),
+        ("errno_copy".to_string(), 1, 1, index(".bss")),
+        ("external".to_string(), 1, 0, 0),
+    ]);
+    // `R_X86_64_64`
+    assert_eq!(elf.relocations(".data"), [
+        (0, "limit".to_string(), 1, 0),
+        (8, "counter".to_string(), 1, 0),
+    ]);
+    // `R_X86_64_PC32` to symbols in the object, `R_X86_64_GOTPCREL` to
+    // the entry of the one that isn't.
+    let kinds: Vec<_> = elf.relocations(".text").into_iter()
+        .map(|(_, symbol, kind, addend)| (symbol, kind, addend))
+        .collect();
+    assert_eq!(kinds, [
+        ("counter".to_string(), 2, -4),
+        ("counter".to_string(), 2, -4),
+        ("table".to_string(), 2, 4),
+        ("external".to_string(), 9, -4),
+        ("limit".to_string(), 2, -4),
+        ("errno_copy".to_string(), 2, -4),
+    ]);
+}
+
+#[test]
+fn assembler() {
+    if !installed("as") {
+        eprintln!("skipped: no assembler");
+        return;
+    }
+    #[cfg_attr(not(feature = "c"), allow(unused_mut))]
+    let mut modules: Vec<_> = samples("tests/x86_64", |ext| ext == "ir")
+        .into_iter()
+        .map(|path| (path.display().to_string(), parse(&path)))
+        .collect();
+    #[cfg(feature = "c")]
+    for path in samples("tests/x86_64", |ext| ext == "c") {
+        for level in [0, 2] {
+            let name = format!("{} -O{}", path.display(), level);
+            modules.push((name, compile_c(&path, level)));
+        }
+    }
+    for (index, (name, module)) in modules.iter().enumerate() {
+        let source = temp(&format!("{}.s", index));
+        let object = temp(&format!("{}.o", index));
+        fs::write(&source, x86_64::assembly(module).unwrap()).unwrap();
+        let status = Command::new("as").arg(&source).arg("-o").arg(&object)
+            .status()
+            .unwrap();
+        assert!(status.success(), "{}: doesn't assemble", name);
+        let assembled = Elf::read(&fs::read(&object).unwrap());
+        let written = Elf::read(&x86_64::object(module).unwrap());
+        for section in [".text", ".data", ".rodata"] {
+            assert!(written.bytes(section) == assembled.bytes(section),
+                "{}: `{}` differs", name, section);
+        }
+        fs::remove_file(source).unwrap();
+        fs::remove_file(object).unwrap();
+    }
+}
+
+#[cfg(feature = "c")]
+#[test]
+fn programs() {
+    if !installed("cc") {
+        eprintln!("skipped: no C compiler to link with");
+        return;
+    }
+    for path in samples("tests/x86_64", |ext| ext == "c") {
+        for level in [0, 2] {
+            let name = path.file_stem().unwrap().to_str().unwrap();
+            let object = temp(&format!("{}-O{}.o", name, level));
+            let program = temp(&format!("{}-O{}", name, level));
+            let module = compile_c(&path, level);
+            fs::write(&object, x86_64::object(&module).unwrap()).unwrap();
+            let status = Command::new("cc").arg(&object).arg("-o")
+                .arg(&program)
+                .status()
+                .unwrap();
+            assert!(status.success(), "{}: doesn't link", path.display());
+            let output = Command::new(&program).output().unwrap();
+            assert!(output.status.success(), "{} -O{}: {}", path.display(),
+                level, output.status);
+            assert_eq!(String::from_utf8(output.stdout).unwrap(),
+                expected(&path, "out"), "{} -O{}", path.display(), level);
+            fs::remove_file(object).unwrap();
+            fs::remove_file(program).unwrap();
+        }
+    }
+}
+
+#[test]
+fn unsupported() {
+    let module = ir::parse("\
+define i128 @wide(i128 %x) {
+entry:
+  ret i128 %x
+}
+").unwrap();
+    assert_eq!(x86_64::object(&module).unwrap_err().message,
+        "in `@wide`: `i128` isn't supported by the x86-64 back end");
+}
+
+#[test]
+fn command() {
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_compiler"))
+            .args(args)
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        (output.status.success(), output.stdout, stderr)
+    };
+    let sample = "tests/x86_64/phis.ir";
+    let (ok, out, _) = run(&["--emit", "asm", sample]);
+    assert!(ok);
+    assert_eq!(String::from_utf8(out).unwrap(),
+        expected(Path::new(sample), "s"));
+    let object = temp("command.o");
+    let (ok, _, _) = run(&["-O2", "--emit", "obj", "-o",
+        object.to_str().unwrap(), sample]);
+    assert!(ok);
+    Elf::read(&fs::read(&object).unwrap());
+    fs::remove_file(object).unwrap();
+    let (ok, _, err) = run(&["--emit", "obj", sample]);
+    assert!(!ok);
+    assert_eq!(err, "`--emit obj` needs `-o`\n");
+}
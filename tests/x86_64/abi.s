@@ -0,0 +1,72 @@
+	.intel_syntax noprefix
+	.text
+	.globl	pair
+	.type	pair, @function
+pair:
+	push rbp
+	mov rbp, rsp
+	sub rsp, 32
+	mov rax, rdi
+	mov dword ptr [rbp - 16], eax
+	mov rax, qword ptr [rbp - 16]
+	mov qword ptr [rbp - 32], rax
+	mov rax, qword ptr [rbp - 8]
+	mov qword ptr [rbp - 24], rax
+	movsd qword ptr [rbp - 24], xmm0
+	mov rax, qword ptr [rbp - 32]
+	movsd xmm0, qword ptr [rbp - 24]
+	mov rsp, rbp
+	pop rbp
+	ret
+	.size	pair, .-pair
+	.globl	last
+	.type	last, @function
+last:
+	push rbp
+	mov rbp, rsp
+	mov rax, qword ptr [rbp + 16]
+	cvttsd2si rcx, xmm0
+	add rax, rcx
+	mov rsp, rbp
+	pop rbp
+	ret
+	.size	last, .-last
+	.globl	use
+	.type	use, @function
+use:
+	push rbp
+	mov rbp, rsp
+	push rbx
+	sub rsp, 40
+	mov rsi, 1
+	lea rdi, [rbp - 32]
+	call triple@PLT
+	mov rax, qword ptr [rbp - 16]
+	mov qword ptr [rsp], rax
+	movabs rax, 4609434218613702656
+	movq xmm0, rax
+	mov rdi, 1
+	mov rsi, 2
+	mov rdx, 3
+	mov rcx, 4
+	mov r8, 5
+	mov r9, 6
+	call last@PLT
+	mov rbx, rax
+	lea rdi, [rip + fmt.0]
+	mov rsi, rbx
+	mov eax, 0
+	call printf@PLT
+	mov rax, rbx
+	lea rsp, [rbp - 8]
+	pop rbx
+	pop rbp
+	ret
+	.size	use, .-use
+	.section	.rodata
+	.type	fmt.0, @object
+	.balign	1
+fmt.0:
+	.byte	37, 108, 100, 10, 0
+	.size	fmt.0, 5
+	.section	.note.GNU-stack,"",@progbits
@@ -0,0 +1,36 @@
+/* Integer arithmetic of every width, signed and unsigned. */
+int printf(const char *fmt, ...);
+
+long mix(long a, long b) {
+    return a * b - a / b + a % b + (a << 3) - (b >> 2) + (a ^ b) + (a | b) + (a & b);
+}
+
+unsigned long umix(unsigned long a, unsigned long b) {
+    return a / b + a % b + (a >> 60) + (b << 7);
+}
+
+int narrow(signed char c, short s, unsigned char u, unsigned short w) {
+    signed char d = c * 3;
+    short t = s / 7;
+    unsigned char v = u + 200;
+    unsigned short x = w >> 3;
+    return d + t + v + x + c / u + (s >> 2);
+}
+
+int shifts(int x, int n) {
+    return (x << n) + (x >> n) + ((unsigned)x >> n);
+}
+
+int main(void) {
+    printf("%ld\n", mix(123456789, -97));
+    printf("%ld\n", mix(-5, 3));
+    printf("%lu\n", umix(18446744073709551000UL, 13));
+    printf("%d\n", narrow(-100, -3000, 250, 65000));
+    printf("%d\n", shifts(-12345, 5));
+    printf("%d %d\n", 7 / -2, -7 % 3);
+    long big = 0x123456789abcdefL;
+    printf("%ld %ld\n", big + 1, big * 16);
+    unsigned u = 4000000000u;
+    printf("%u %u\n", u / 3, u % 1000);
+    return 0;
+}
@@ -0,0 +1,51 @@
+/* Loops, switches, pointers, globals and function pointers. */
+int printf(const char *fmt, ...);
+int puts(const char *s);
+
+int counter;
+int squares[8];
+const char *words[] = {"alpha", "beta", "gamma"};
+int *cursor;
+
+static int twice(int x) { return 2 * x; }
+static int thrice(int x) { return 3 * x; }
+
+int apply(int (*f)(int), int x) { return f(x); }
+
+const char *name(int n) {
+    switch (n) {
+    case 0: return "zero";
+    case 1: case 2: return "small";
+    case 100: return "hundred";
+    default: return n < 0 ? "negative" : "large";
+    }
+}
+
+int collatz(long n) {
+    int steps = 0;
+    while (n != 1) {
+        n = n % 2 ? 3 * n + 1 : n / 2;
+        steps++;
+    }
+    return steps;
+}
+
+int main(void) {
+    cursor = &squares[2];
+    for (int i = 0; i < 8; i++) squares[i] = i * i;
+    *cursor += 100;
+    printf("%d %d\n", squares[2], squares[7]);
+    for (int i = -1; i < 4; i++) puts(name(i));
+    puts(name(100));
+    printf("%d %d\n", apply(twice, 21), apply(thrice, 5));
+    printf("%d\n", collatz(27));
+    int (*fs[2])(int) = {twice, thrice};
+    for (int i = 0; i < 2; i++) counter += fs[i](10);
+    printf("%d %s\n", counter, words[counter % 3]);
+    char buf[16];
+    char *p = buf;
+    for (const char *s = "hello"; *s; s++) *p++ = *s - 32;
+    *p = 0;
+    puts(buf);
+    return counter == 50 ? 0 : 1;
+}
@@ -0,0 +1,28 @@
+/* Float arithmetic, comparisons and conversions. */
+int printf(const char *fmt, ...);
+
+double poly(double x) { return 3.0 * x * x - 2.0 * x + 1.0 / x; }
+float half(float f) { return f / 2.0f - -f; }
+
+int compare(double a, double b) {
+    return (a < b) + 2 * (a <= b) + 4 * (a > b) + 8 * (a >= b) + 16 * (a == b) + 32 * (a != b);
+}
+
+int main(void) {
+    printf("%.6f\n", poly(1.5));
+    printf("%.6f\n", (double)half(3.25f));
+    printf("%d %d %d\n", compare(1.0, 2.0), compare(2.0, 2.0), compare(3.0, 2.0));
+    double nan = 0.0 / 0.0;
+    printf("%d\n", compare(nan, 1.0));
+    double d = -7.9;
+    printf("%d %ld %u\n", (int)d, (long)(d * 1e10), (unsigned)(-d));
+    unsigned long big = 18446744073709551615UL;
+    printf("%.1f\n", (double)big);
+    printf("%lu\n", (unsigned long)1.8e19);
+    printf("%.3f %.3f\n", (double)(float)(1.0 / 3.0), (double)-5);
+    unsigned char c = 200;
+    printf("%.1f %.1f\n", (double)c, (float)(signed char)c);
+    float f = 1e20f;
+    printf("%.0f\n", (double)(f * f > 1e38f));
+    return 0;
+}
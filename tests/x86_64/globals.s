@@ -0,0 +1,59 @@
+	.intel_syntax noprefix
+	.text
+	.globl	bump
+	.type	bump, @function
+bump:
+	push rbp
+	mov rbp, rsp
+	mov rax, rdi
+	mov rcx, qword ptr [rip + counter]
+	add rcx, rax
+	mov qword ptr [rip + counter], rcx
+	mov rax, qword ptr [rip + table+8]
+	mov rax, qword ptr [rip + external@GOTPCREL]
+	mov eax, dword ptr [rax]
+	mov edx, dword ptr [rip + limit]
+	mov eax, eax
+	add eax, edx
+	mov dword ptr [rip + errno_copy], eax
+	mov rax, rcx
+	mov rsp, rbp
+	pop rbp
+	ret
+	.size	bump, .-bump
+	.section	.rodata
+	.globl	limit
+	.type	limit, @object
+	.balign	4
+limit:
+	.byte	100, 0, 0, 0
+	.size	limit, 4
+	.bss
+	.globl	counter
+	.type	counter, @object
+	.balign	8
+counter:
+	.zero	8
+	.size	counter, 8
+	.data
+	.globl	table
+	.type	table, @object
+	.balign	8
+table:
+	.quad	limit
+	.quad	counter
+	.size	table, 16
+	.section	.rodata
+	.type	name.0, @object
+	.balign	1
+name.0:
+	.byte	104, 105, 0
+	.size	name.0, 3
+	.bss
+	.globl	errno_copy
+	.type	errno_copy, @object
+	.balign	4
+errno_copy:
+	.zero	4
+	.size	errno_copy, 4
+	.section	.note.GNU-stack,"",@progbits
@@ -0,0 +1,43 @@
+/* Values swapped around loops, selects and narrow values. */
+int printf(const char *fmt, ...);
+
+long fib(int n) {
+    long a = 0, b = 1;
+    for (int i = 0; i < n; i++) { long t = a; a = b; b = t + b; }
+    return a;
+}
+
+int rotate(int n) {
+    int x = 1, y = 2, z = 3;
+    while (n-- > 0) { int t = x; x = y; y = z; z = t; }
+    return x * 100 + y * 10 + z;
+}
+
+int pick(int a, int b, double x, double y) {
+    int m = a < b ? a : b;
+    double f = x > y ? x : y;
+    _Bool both = a && b;
+    return m + (int)f + both;
+}
+
+unsigned char bytes(unsigned char *p, int n) {
+    unsigned char acc = 0, hi = 0, lo = 255;
+    for (int i = 0; i < n; i++) {
+        acc ^= p[i];
+        hi = p[i] > hi ? p[i] : hi;
+        lo = p[i] < lo ? p[i] : lo;
+    }
+    return acc + hi - lo;
+}
+
+int main(void) {
+    printf("%ld %ld\n", fib(10), fib(90));
+    printf("%d %d %d\n", rotate(0), rotate(1), rotate(5));
+    printf("%d %d\n", pick(3, 4, 1.5, 2.5), pick(9, 0, -1.0, -2.0));
+    unsigned char data[6] = {17, 200, 3, 99, 255, 1};
+    printf("%d\n", bytes(data, 6));
+    signed char sc = -128;
+    short sh = -32768;
+    printf("%d %d %d\n", sc / -1 == 128, sh % 7, (unsigned short)sh >> 15);
+    return 0;
+}
@@ -0,0 +1,27 @@
+/* Large copies and fills of memory, and arrays of bytes. */
+int printf(const char *fmt, ...);
+
+struct big { long v[12]; char tag[20]; };
+
+struct big make(int n) {
+    struct big b = {0};
+    for (int i = 0; i < 12; i++) b.v[i] = n * i;
+    b.tag[0] = 'a' + n;
+    return b;
+}
+
+int main(void) {
+    char text[100] = "some text that is long enough";
+    char small[7] = "abcdef";
+    struct big a = make(2), b;
+    b = a;
+    b.v[11] = -1;
+    long t = 0;
+    for (int i = 0; i < 12; i++) t += a.v[i] + b.v[i];
+    printf("%s %s %ld %s %d\n", text, small, t, b.tag, text[99]);
+    struct big arr[3];
+    arr[1] = make(5);
+    arr[2] = arr[1];
+    printf("%ld %c\n", arr[2].v[11], arr[2].tag[0]);
+    return 0;
+}
@@ -0,0 +1,65 @@
+	.intel_syntax noprefix
+	.text
+	.globl	fib
+	.type	fib, @function
+fib:
+	push rbp
+	mov rbp, rsp
+	mov rax, rdi
+	mov rcx, 0
+	mov rdx, 1
+	mov rsi, 0
+.L0_1:
+	cmp rsi, rax
+	jge .L0_3
+.L0_2:
+	mov rdi, rcx
+	add rdi, rdx
+	mov r8, rsi
+	add r8, 1
+	mov r9, rdx
+	mov rcx, r9
+	mov rdx, rdi
+	mov rsi, r8
+	jmp .L0_1
+.L0_3:
+	mov rax, rcx
+	mov rsp, rbp
+	pop rbp
+	ret
+	.size	fib, .-fib
+	.globl	swap
+	.type	swap, @function
+swap:
+	push rbp
+	mov rbp, rsp
+	mov rax, rdi
+	mov rcx, rsi
+	mov ecx, ecx
+	mov edx, edx
+	mov esi, eax
+.L1_1:
+	mov edi, esi
+	sub edi, 1
+	cmp edi, 0
+	jg .L1_3
+.L1_2:
+	mov eax, 10
+	mov r8d, ecx
+	imul r8d, eax
+	mov eax, r8d
+	add eax, edx
+	mov eax, eax
+	mov rsp, rbp
+	pop rbp
+	ret
+.L1_3:
+	mov eax, edx
+	mov r8d, ecx
+	mov edi, edi
+	mov rcx, rax
+	mov rdx, r8
+	mov rsi, rdi
+	jmp .L1_1
+	.size	swap, .-swap
+	.section	.note.GNU-stack,"",@progbits
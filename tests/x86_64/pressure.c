@@ -0,0 +1,20 @@
+/* More live values than registers, across calls. */
+int printf(const char *fmt, ...);
+
+long id(long x) { return x; }
+
+long pressure(long a, long b) {
+    long c = a + 1, d = b + 2, e = a * b, f = a - b, g = a ^ 5, h = b | 8;
+    long i = c * d, j = e + f, k = g - h, l = a * 3, m = b * 5, n = c + e;
+    long o = id(a) + d, p = id(b) + f, q = i + j + k;
+    double x = (double)a / 3, y = (double)b * 1.5, z = x + y, w = x * y;
+    double v = id(7) + z, u = w - v;
+    return c + d + e + f + g + h + i + j + k + l + m + n + o + p + q
+        + (long)(x + y + z + w + v + u);
+}
+
+int main(void) {
+    printf("%ld\n", pressure(10, 20));
+    printf("%ld\n", pressure(-7, 1000));
+    return 0;
+}
@@ -0,0 +1,45 @@
+/* Structs passed and returned by value, in registers and in memory. */
+int printf(const char *fmt, ...);
+
+struct small { int a; int b; };
+struct mixed { double x; long n; };
+struct floats { float a, b, c; };
+struct big { long v[10]; };
+
+struct small swap(struct small s) { struct small t = {s.b, s.a}; return t; }
+struct mixed scale(struct mixed m, double k) { m.x *= k; m.n *= 2; return m; }
+struct floats sum3(struct floats f) { f.a += f.b + f.c; return f; }
+struct big fill(long start) {
+    struct big b;
+    for (int i = 0; i < 10; i++) b.v[i] = start + i;
+    return b;
+}
+long total(struct big b) {
+    long t = 0;
+    for (int i = 0; i < 10; i++) t += b.v[i];
+    return t;
+}
+
+long many(long a, long b, long c, long d, long e, long f, long g, long h,
+          double x, struct small s, struct big bb) {
+    return a + b + c + d + e + f + g * h + (long)x + s.a * s.b + bb.v[9];
+}
+
+int main(void) {
+    struct small s = {1, 2};
+    s = swap(s);
+    printf("%d %d\n", s.a, s.b);
+    struct mixed m = {1.5, 7};
+    m = scale(m, 4.0);
+    printf("%.2f %ld\n", m.x, m.n);
+    struct floats f = {1.0f, 2.0f, 3.5f};
+    f = sum3(f);
+    printf("%.2f %.2f\n", (double)f.a, (double)f.c);
+    struct big b = fill(100);
+    printf("%ld\n", total(b));
+    printf("%ld\n", many(1, 2, 3, 4, 5, 6, 7, 8, 9.5, s, b));
+    struct big copy = b;
+    copy.v[0] = 0;
+    printf("%ld %ld\n", total(copy), b.v[0]);
+    return 0;
+}
@@ -0,0 +1,93 @@
+/* Variadic functions reading their extra arguments with <stdarg.h>. */
+typedef __builtin_va_list va_list;
+int printf(const char *fmt, ...);
+int vprintf(const char *fmt, va_list ap);
+
+struct mixed { double x; long n; };
+struct big { long v[4]; };
+struct odd { long n; int k; };
+struct floats { float a, b, c; };
+
+long sum(int n, ...) {
+    va_list ap;
+    __builtin_va_start(ap, n);
+    long total = 0;
+    for (int i = 0; i < n; i++) total += __builtin_va_arg(ap, long);
+    __builtin_va_end(ap);
+    return total;
+}
+
+double mean(int n, ...) {
+    va_list ap;
+    __builtin_va_start(ap, n);
+    double total = 0;
+    for (int i = 0; i < n; i++) {
+        if (i % 2) total += __builtin_va_arg(ap, int);
+        else total += __builtin_va_arg(ap, double);
+    }
+    __builtin_va_end(ap);
+    return total / n;
+}
+
+long structs(int n, ...) {
+    va_list ap;
+    __builtin_va_start(ap, n);
+    long skipped = 0;
+    for (int i = 0; i < n; i++) skipped += __builtin_va_arg(ap, long);
+    struct mixed m = __builtin_va_arg(ap, struct mixed);
+    struct big b = __builtin_va_arg(ap, struct big);
+    struct mixed k = __builtin_va_arg(ap, struct mixed);
+    __builtin_va_end(ap);
+    return skipped + (long)(m.x * k.x) + m.n + k.n + b.v[0] + b.v[3];
+}
+
+double odds(int n, ...) {
+    va_list ap;
+    __builtin_va_start(ap, n);
+    struct odd o = __builtin_va_arg(ap, struct odd);
+    struct floats f = __builtin_va_arg(ap, struct floats);
+    __builtin_va_end(ap);
+    return n + o.n + o.k + f.a + f.b * f.c;
+}
+
+long second(va_list ap) {
+    __builtin_va_arg(ap, long);
+    return __builtin_va_arg(ap, long);
+}
+
+long twice(int n, ...) {
+    va_list ap, aq;
+    __builtin_va_start(ap, n);
+    __builtin_va_copy(aq, ap);
+    long a = second(ap);
+    long b = __builtin_va_arg(aq, long);
+    __builtin_va_end(aq);
+    __builtin_va_end(ap);
+    return a * 100 + b;
+}
+
+void print(const char *fmt, ...) {
+    va_list ap;
+    __builtin_va_start(ap, fmt);
+    vprintf(fmt, ap);
+    __builtin_va_end(ap);
+}
+
+int main(void) {
+    printf("%ld\n", sum(3, 1L, 2L, 3L));
+    printf("%ld\n", sum(10, 1L, 2L, 3L, 4L, 5L, 6L, 7L, 8L, 9L, 10L));
+    printf("%.3f\n", mean(4, 1.5, 2, 2.5, 4));
+    printf("%.3f\n", mean(20, 1.0, 2, 3.0, 4, 5.0, 6, 7.0, 8, 9.0, 10,
+        11.0, 12, 13.0, 14, 15.0, 16, 17.0, 18, 19.0, 20));
+    struct mixed m = {2.5, 3}, k = {4.0, 5};
+    struct big b = {{10, 20, 30, 40}};
+    printf("%ld\n", structs(0, m, b, k));
+    printf("%ld\n", structs(4, 1L, 2L, 3L, 4L, m, b, k));
+    printf("%ld\n", structs(5, 1L, 2L, 3L, 4L, 5L, m, b, k));
+    struct odd o = {100, 20};
+    struct floats f = {0.5f, 1.5f, 3.0f};
+    printf("%.2f\n", odds(1, o, f));
+    printf("%ld\n", twice(0, 7L, 9L));
+    print("%s %d %.1f %c\n", "mixed", 42, 0.5f, 'z');
+    return 0;
+}
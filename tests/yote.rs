@@ -0,0 +1,232 @@
+// Yote shader tests
+//
+// Every sample `tests/yote/*.aratar` is compiled to Yote bytecode, which
+// must disassemble to the sample's `.dis` file and translate to its
+// `.es100.vert`, `.es100.frag`, `.es300.vert` and `.es300.frag` shaders,
+// its `.wgsl` module and its `.spv` SPIR-V module, which must disassemble
+// to its `.spvasm` file.  Where `spirv-val`, `naga` and `glslangValidator`
+// are installed, they must accept every sample's `.spv` module, `.wgsl`
+// module and GLSL shaders respectively.
+
+#![cfg(feature = "shader")]
+
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use compiler::aratar::yote::glsl::{self, Version};
+use compiler::aratar::yote::{
+    compile_shader, disassemble, spirv, wgsl, Instruction, Operation, Output,
+    Primitive, Program,
+};
+
+use common::{expected, installed};
+
+// The samples of the corpus, sorted.
+fn samples() -> Vec<PathBuf> {
+    common::samples("tests/yote", |ext| ext == "aratar")
+}
+
+// The bytecode of a sample.
+fn bytecode(path: &Path) -> Vec<u8> {
+    let text = fs::read_to_string(path).unwrap();
+    compile_shader(&text).unwrap_or_else(|error| {
+        panic!("{}: {}", path.display(), error.render("sample", &text))
+    })
+}
+
+// Run a validator with `args` on the file next to each sample with
+// extension `ext`, which it must accept.
+fn validate(program: &str, args: &[&str], ext: &str) {
+    for path in samples() {
+        let file = path.with_extension(ext);
+        let output = Command::new(program).args(args).arg(&file).output()
+            .unwrap();
+        assert!(output.status.success(), "{}: rejected by {}:\n{}{}",
+            file.display(), program,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr));
+    }
+}
+
+#[test]
+fn disassembly() {
+    for path in samples() {
+        let bytecode = bytecode(&path);
+        assert_eq!(disassemble(&bytecode).unwrap(), expected(&path, "dis"),
+            "{}", path.display());
+        let program = Program::decode(&bytecode).unwrap();
+        assert_eq!(program.encode(), bytecode);
+    }
+}
+
+#[test]
+fn glsl() {
+    for path in samples() {
+        let bytecode = bytecode(&path);
+        for &(version, ext) in &[(Version::Es100, "es100"),
+            (Version::Es300, "es300")]
+        {
+            let shaders = glsl::translate(&bytecode, version).unwrap();
+            assert_eq!(shaders.vertex,
+                expected(&path, &format!("{}.vert", ext)),
+                "{}", path.display());
+            assert_eq!(shaders.fragment,
+                expected(&path, &format!("{}.frag", ext)),
+                "{}", path.display());
+        }
+    }
+}
+
+#[test]
+fn wgsl() {
+    for path in samples() {
+        let module = wgsl::translate(&bytecode(&path)).unwrap();
+        assert_eq!(module, expected(&path, "wgsl"), "{}", path.display());
+    }
+}
+
+#[test]
+fn spirv() {
+    for path in samples() {
+        let words = spirv::translate(&bytecode(&path)).unwrap();
+        let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes())
+            .collect();
+        assert!(bytes == fs::read(path.with_extension("spv")).unwrap(),
+            "{}", path.display());
+        assert_eq!(spirv::disassemble(&words).unwrap(),
+            expected(&path, "spvasm"), "{}", path.display());
+    }
+
+    let error = |words: &[u32]| spirv::disassemble(words).unwrap_err();
+    assert_eq!(error(&[0x0203_0723, 0x0001_0000, 0, 1, 0]).message,
+        "not a SPIR-V module");
+    let header = [spirv::MAGIC, 0x0001_0000, 0, 1, 0];
+    assert_eq!(error(&[&header[..], &[2 << 16 | 17]].concat()).message,
+        "instruction ends past the end of the module");
+    assert_eq!(error(&[&header[..], &[1 << 16 | 1000]].concat()).message,
+        "unknown opcode 1000");
+    assert_eq!(error(&[&header[..], &[1 << 16 | 62]].concat()).message,
+        "malformed `OpStore`");
+}
+
+#[test]
+fn spirv_validator() {
+    if !installed("spirv-val") {
+        eprintln!("skipped: no spirv-val");
+        return;
+    }
+    validate("spirv-val", &["--target-env", "vulkan1.0"], "spv");
+}
+
+#[test]
+fn wgsl_validator() {
+    if !installed("naga") {
+        eprintln!("skipped: no naga");
+        return;
+    }
+    validate("naga", &[], "wgsl");
+}
+
+#[test]
+fn glsl_validator() {
+    if !installed("glslangValidator") {
+        eprintln!("skipped: no glslangValidator");
+        return;
+    }
+    for ext in ["es100.vert", "es100.frag", "es300.vert", "es300.frag"] {
+        validate("glslangValidator", &[], ext);
+    }
+}
+
+#[test]
+fn bindings() {
+    let bytecode = compile_shader("let a: $Vec4; var b: $Vec2; let c: $Bmap\n\
+        let d: $View\n").unwrap();
+    let program = Program::decode(&bytecode).unwrap();
+    assert_eq!(program.bindings(), [Some(0), None, Some(1), Some(3)]);
+}
+
+#[test]
+fn dependencies() {
+    let bytecode = compile_shader("let a: $Vec4; let b: $Vec1; var c: $Vec3\n\
+        out position: a * b; out color: c * b\n").unwrap();
+    let program = Program::decode(&bytecode).unwrap();
+    assert_eq!(program.dependencies(Output::Position),
+        [true, true, false, true, false]);
+    assert_eq!(program.dependencies(Output::Color),
+        [false, true, true, false, true]);
+    let error = glsl::translate(&bytecode[..bytecode.len() - 2],
+        Version::Es100).unwrap_err();
+    assert_eq!(error.message, "the program doesn't output a color");
+}
+
+#[test]
+fn bytecode_format() {
+    let bytecode = compile_shader("let view: $View\nvar pos: $Vec4\n\
+        out position: view pos\n").unwrap();
+    assert_eq!(bytecode, [
+        b'Y', b'o', b't', b'e', 1,
+        5,
+        0x40 + 4,
+        0x80 + 4, 3, 0, 1,
+        0xFE, 2,
+    ]);
+    let program = Program::decode(&bytecode).unwrap();
+    assert_eq!(program.instructions[2],
+        Instruction::Call(Primitive::Vec4, Operation::Mul, 0, 1));
+    assert_eq!(program.types,
+        [Primitive::View, Primitive::Vec4, Primitive::Vec4]);
+}
+
+#[test]
+fn compile_errors() {
+    let error = |text| compile_shader(text).unwrap_err().message;
+    assert_eq!(error("let a: $Vec5\n"), "unknown type `Vec5`");
+    assert_eq!(error("var a: $Bmap\n"),
+        "a `Bmap` can't be given for each vertex");
+    assert_eq!(error("let a: $Vec3; out position: a b\n"),
+        "no variable named `b`");
+    assert_eq!(error("let a: $Vec3; let b: $Vec4; let c: a + b\n"),
+        "`+` can't be used on `Vec3` and `Vec4`");
+    assert_eq!(error("let a: $Vec3; let b: a a\n"),
+        "`Vec3` can't be applied, only `View` and `Bmap` can");
+    assert_eq!(error("let a: $View; let b: $Vec3; let c: a b\n"),
+        "`View` can't be applied to `Vec3`");
+    assert_eq!(error("let a: $Vec1; let b: $Int1; let c: a * b\n"),
+        "`*` can't be used on `Vec1` and `Int1`");
+    assert_eq!(error("let a: $Vec2; out color: a\n"),
+        "the color must be a `Vec3` or `Vec4`, not `Vec2`");
+    assert_eq!(error("let a: $Vec3; out color: a; out color: a\n"),
+        "the color is output twice");
+    assert_eq!(error("let a: $Vec3; out normal: a\n"),
+        "the outputs of a shader are `position` and `color`");
+    assert_eq!(error("let a: $Vec1; let a: $Vec1\n"), "`a` is defined twice");
+    assert_eq!(error("def main:\n    let a: $Vec1\n"),
+        "a shader can't have definitions");
+}
+
+#[test]
+fn decode_errors() {
+    let error = |bytecode: &[u8]| {
+        let error = Program::decode(bytecode).unwrap_err();
+        (error.span.start, error.message)
+    };
+    assert_eq!(error(b"Yeet\x01"), (0, "not Yote bytecode".to_string()));
+    assert_eq!(error(b"Yote\x02"),
+        (4, "unsupported Yote version 2".to_string()));
+    assert_eq!(error(b"Yote\x01\x01\x08"),
+        (6, "unknown instruction 0x08".to_string()));
+    assert_eq!(error(b"Yote\x01\x01\x81\x01\x00"),
+        (6, "bytecode ends in the middle of an instruction".to_string()));
+    assert_eq!(error(b"Yote\x01\x01\x81\x01\x00\x01"),
+        (9, "variable 1 isn't defined yet".to_string()));
+    assert_eq!(error(b"Yote\x01\x01\x81\x07\x00\x00"),
+        (7, "unknown operation 7".to_string()));
+    assert_eq!(error(b"Yote\x01\x01\x02\x81\x01\x00\x01"),
+        (7, "`add` of `Vec1` and `Vec2` isn't `Vec1`".to_string()));
+    assert_eq!(error(b"Yote\x01\x04\xFF\x00\xFF\x00"),
+        (8, "the color is output twice".to_string()));
+}
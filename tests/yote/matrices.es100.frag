@@ -0,0 +1,10 @@
+#version 100
+precision mediump float;
+
+uniform float u1;
+uniform vec4 u2;
+
+void main() {
+    vec4 t11 = u1 * u2;
+    gl_FragColor = t11;
+}
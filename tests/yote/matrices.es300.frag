@@ -0,0 +1,11 @@
+#version 300 es
+precision mediump float;
+
+uniform float u1;
+uniform vec4 u2;
+out vec4 color;
+
+void main() {
+    vec4 t11 = u1 * u2;
+    color = t11;
+}
@@ -0,0 +1,17 @@
+#version 300 es
+
+uniform mat4 u0;
+uniform mediump float u1;
+uniform mediump vec4 u2;
+
+void main() {
+    mat4 t3 = u0 + u0;
+    mat4 t4 = t3 - u1;
+    mat4 t5 = t4 / u1;
+    mat4 t6 = u1 - t5;
+    mat4 t7 = t6 * u0;
+    mat4 t8 = t7 * u1;
+    vec4 t9 = t8 * u2;
+    vec4 t10 = mod(t9, u1);
+    gl_Position = t10;
+}
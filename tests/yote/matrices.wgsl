@@ -0,0 +1,28 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> u0: mat4x4<f32>;
+@group(0) @binding(1) var<uniform> u1: f32;
+@group(0) @binding(2) var<uniform> u2: vec4<f32>;
+
+@vertex
+fn vs_main() -> VertexOutput {
+    let t3 = u0 + u0;
+    let t4 = mat4x4<f32>(t3[0] - u1, t3[1] - u1, t3[2] - u1, t3[3] - u1);
+    let t5 = mat4x4<f32>(t4[0] / u1, t4[1] / u1, t4[2] / u1, t4[3] / u1);
+    let t6 = mat4x4<f32>(u1 - t5[0], u1 - t5[1], u1 - t5[2], u1 - t5[3]);
+    let t7 = t6 * u0;
+    let t8 = t7 * u1;
+    let t9 = t8 * u2;
+    let t10 = t9 - u1 * floor(t9 / u1);
+    var output: VertexOutput;
+    output.position = t10;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let t11 = u1 * u2;
+    return t11;
+}
@@ -0,0 +1,11 @@
+#version 100
+precision mediump float;
+
+uniform float u2;
+varying vec3 v5;
+
+void main() {
+    vec3 t11 = v5 * u2;
+    vec3 t12 = t11 + v5;
+    gl_FragColor = vec4(t12, 1.0);
+}
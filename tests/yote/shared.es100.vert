@@ -0,0 +1,19 @@
+#version 100
+
+uniform mat4 u0;
+uniform sampler2D u1;
+uniform mediump float u2;
+attribute vec4 a3;
+attribute vec2 a4;
+attribute vec3 a5;
+attribute float a6;
+varying vec3 v5;
+
+void main() {
+    vec4 t7 = texture2DLod(u1, a4, 0.0);
+    vec4 t8 = t7 * u2;
+    vec4 t9 = a3 + t8;
+    vec4 t10 = u0 * t9;
+    gl_Position = t10;
+    v5 = a5;
+}
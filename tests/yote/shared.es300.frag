@@ -0,0 +1,12 @@
+#version 300 es
+precision mediump float;
+
+uniform float u2;
+in vec3 v5;
+out vec4 color;
+
+void main() {
+    vec3 t11 = v5 * u2;
+    vec3 t12 = t11 + v5;
+    color = vec4(t12, 1.0);
+}
@@ -0,0 +1,19 @@
+#version 300 es
+
+uniform mat4 u0;
+uniform sampler2D u1;
+uniform mediump float u2;
+layout(location = 0) in vec4 a3;
+layout(location = 1) in vec2 a4;
+layout(location = 2) in vec3 a5;
+layout(location = 3) in int a6;
+out vec3 v5;
+
+void main() {
+    vec4 t7 = textureLod(u1, a4, 0.0);
+    vec4 t8 = t7 * u2;
+    vec4 t9 = a3 + t8;
+    vec4 t10 = u0 * t9;
+    gl_Position = t10;
+    v5 = a5;
+}
@@ -0,0 +1,33 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) v5: vec3<f32>,
+}
+
+@group(0) @binding(0) var<uniform> u0: mat4x4<f32>;
+@group(0) @binding(1) var u1: texture_2d<f32>;
+@group(0) @binding(2) var s1: sampler;
+@group(0) @binding(3) var<uniform> u2: f32;
+
+@vertex
+fn vs_main(
+    @location(0) a3: vec4<f32>,
+    @location(1) a4: vec2<f32>,
+    @location(2) a5: vec3<f32>,
+    @location(3) a6: i32,
+) -> VertexOutput {
+    let t7 = textureSampleLevel(u1, s1, a4, 0.0);
+    let t8 = t7 * u2;
+    let t9 = a3 + t8;
+    let t10 = u0 * t9;
+    var output: VertexOutput;
+    output.position = t10;
+    output.v5 = a5;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let t11 = input.v5 * u2;
+    let t12 = t11 + input.v5;
+    return vec4<f32>(t12, 1.0);
+}
@@ -0,0 +1,17 @@
+#version 100
+precision mediump float;
+
+uniform sampler2D u1;
+uniform vec4 u2;
+uniform float u3;
+varying vec2 v5;
+
+void main() {
+    vec4 t7 = texture2D(u1, v5);
+    vec4 t8 = t7 * u2;
+    vec4 t9 = u2 / u3;
+    vec4 t10 = t8 + t9;
+    vec4 t11 = u2 - u3;
+    vec4 t12 = mod(t10, t11);
+    gl_FragColor = t12;
+}
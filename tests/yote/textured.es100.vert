@@ -0,0 +1,12 @@
+#version 100
+
+uniform mat4 u0;
+attribute vec4 a4;
+attribute vec2 a5;
+varying vec2 v5;
+
+void main() {
+    vec4 t6 = u0 * a4;
+    gl_Position = t6;
+    v5 = a5;
+}
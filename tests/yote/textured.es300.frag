@@ -0,0 +1,18 @@
+#version 300 es
+precision mediump float;
+
+uniform sampler2D u1;
+uniform vec4 u2;
+uniform float u3;
+in vec2 v5;
+out vec4 color;
+
+void main() {
+    vec4 t7 = texture(u1, v5);
+    vec4 t8 = t7 * u2;
+    vec4 t9 = u2 / u3;
+    vec4 t10 = t8 + t9;
+    vec4 t11 = u2 - u3;
+    vec4 t12 = mod(t10, t11);
+    color = t12;
+}
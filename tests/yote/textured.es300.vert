@@ -0,0 +1,12 @@
+#version 300 es
+
+uniform mat4 u0;
+layout(location = 0) in vec4 a4;
+layout(location = 1) in vec2 a5;
+out vec2 v5;
+
+void main() {
+    vec4 t6 = u0 * a4;
+    gl_Position = t6;
+    v5 = a5;
+}
@@ -0,0 +1,33 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) v5: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> u0: mat4x4<f32>;
+@group(0) @binding(1) var u1: texture_2d<f32>;
+@group(0) @binding(2) var s1: sampler;
+@group(0) @binding(3) var<uniform> u2: vec4<f32>;
+@group(0) @binding(4) var<uniform> u3: f32;
+
+@vertex
+fn vs_main(
+    @location(0) a4: vec4<f32>,
+    @location(1) a5: vec2<f32>,
+) -> VertexOutput {
+    let t6 = u0 * a4;
+    var output: VertexOutput;
+    output.position = t6;
+    output.v5 = a5;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let t7 = textureSample(u1, s1, input.v5);
+    let t8 = t7 * u2;
+    let t9 = u2 / u3;
+    let t10 = t8 + t9;
+    let t11 = u2 - u3;
+    let t12 = t10 - t11 * floor(t10 / t11);
+    return t12;
+}
@@ -0,0 +1,12 @@
+#version 100
+
+uniform mat4 u0;
+attribute vec4 a1;
+attribute vec4 a2;
+varying vec4 v2;
+
+void main() {
+    vec4 t3 = u0 * a1;
+    gl_Position = t3;
+    v2 = a2;
+}
@@ -0,0 +1,9 @@
+#version 300 es
+precision mediump float;
+
+in vec4 v2;
+out vec4 color;
+
+void main() {
+    color = v2;
+}
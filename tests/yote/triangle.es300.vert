@@ -0,0 +1,12 @@
+#version 300 es
+
+uniform mat4 u0;
+layout(location = 0) in vec4 a1;
+layout(location = 1) in vec4 a2;
+out vec4 v2;
+
+void main() {
+    vec4 t3 = u0 * a1;
+    gl_Position = t3;
+    v2 = a2;
+}
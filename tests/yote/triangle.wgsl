@@ -0,0 +1,23 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) v2: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> u0: mat4x4<f32>;
+
+@vertex
+fn vs_main(
+    @location(0) a1: vec4<f32>,
+    @location(1) a2: vec4<f32>,
+) -> VertexOutput {
+    let t3 = u0 * a1;
+    var output: VertexOutput;
+    output.position = t3;
+    output.v2 = a2;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    return input.v2;
+}
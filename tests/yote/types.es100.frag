@@ -0,0 +1,8 @@
+#version 100
+precision mediump float;
+
+uniform vec3 u2;
+
+void main() {
+    gl_FragColor = vec4(u2, 1.0);
+}
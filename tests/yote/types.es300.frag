@@ -0,0 +1,9 @@
+#version 300 es
+precision mediump float;
+
+uniform vec3 u2;
+out vec4 color;
+
+void main() {
+    color = vec4(u2, 1.0);
+}
@@ -0,0 +1,11 @@
+#version 300 es
+
+uniform float u0;
+uniform vec4 u3;
+uniform mat4 u4;
+
+void main() {
+    mat4 t7 = u4 * u0;
+    vec4 t10 = t7 * u3;
+    gl_Position = t10;
+}
@@ -0,0 +1,26 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> u0: f32;
+@group(0) @binding(1) var<uniform> u1: vec2<f32>;
+@group(0) @binding(2) var<uniform> u2: vec3<f32>;
+@group(0) @binding(3) var<uniform> u3: vec4<f32>;
+@group(0) @binding(4) var<uniform> u4: mat4x4<f32>;
+@group(0) @binding(5) var<uniform> u5: i32;
+@group(0) @binding(6) var u6: texture_2d<f32>;
+@group(0) @binding(7) var s6: sampler;
+
+@vertex
+fn vs_main() -> VertexOutput {
+    let t7 = u4 * u0;
+    let t10 = t7 * u3;
+    var output: VertexOutput;
+    output.position = t10;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(u2, 1.0);
+}